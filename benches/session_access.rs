@@ -0,0 +1,72 @@
+//! Session Access Benchmarks
+//!
+//! Compares cloning a large session via `get_or_create` against the in-place
+//! `with_session` / `recent_messages` accessors and the partial copy of
+//! `get_or_create_from` that agent turns use.
+//!
+//! Run with: cargo bench --bench session_access
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+use tokio::runtime::Runtime;
+use zeptoclaw::session::{Message, SessionManager};
+
+const MESSAGE_COUNT: usize = 10_000;
+
+fn seeded_manager(rt: &Runtime) -> SessionManager {
+    let manager = SessionManager::new_memory();
+    rt.block_on(async {
+        let mut session = manager.get_or_create("bench").await.unwrap();
+        for i in 0..MESSAGE_COUNT {
+            let text = format!("message {} with some representative chat content", i);
+            if i % 2 == 0 {
+                session.add_message(Message::user(&text));
+            } else {
+                session.add_message(Message::assistant(&text));
+            }
+        }
+        manager.save(&session).await.unwrap();
+    });
+    manager
+}
+
+fn benchmark_session_access(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let manager = seeded_manager(&rt);
+
+    let mut group = c.benchmark_group("session_access_10k");
+
+    group.bench_function("get_or_create_clone", |b| {
+        b.to_async(&rt).iter(|| async {
+            let session = manager.get_or_create("bench").await.unwrap();
+            black_box(session.messages.len());
+        });
+    });
+
+    group.bench_function("message_count", |b| {
+        b.to_async(&rt).iter(|| async {
+            black_box(manager.message_count("bench").await.unwrap());
+        });
+    });
+
+    group.bench_function("recent_messages_20", |b| {
+        b.to_async(&rt).iter(|| async {
+            black_box(manager.recent_messages("bench", 20).await.unwrap());
+        });
+    });
+
+    group.bench_function("get_or_create_from_tail_20", |b| {
+        b.to_async(&rt).iter(|| async {
+            let (session, start) = manager
+                .get_or_create_from("bench", |s| s.messages.len() - 20)
+                .await
+                .unwrap();
+            black_box((session.messages.len(), start));
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_session_access);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+;; Example WASM plugin: echoes its JSON arguments back to the model.
+;;
+;; Build with `wat2wasm echo.wat -o echo.wasm` (or any toolchain that
+;; targets wasm32) and copy echo.wasm into a plugin directory such as
+;; ~/.zeptoclaw/plugins/. Requires the `wasm-plugins` feature.
+;;
+;; Strings are returned as an i64 packing (pointer << 32) | length.
+(module
+  (memory (export "memory") 1)
+
+  ;; Static strings.
+  (data (i32.const 0) "wasm_echo")
+  (data (i32.const 16) "Echo the arguments back as JSON")
+  (data (i32.const 64) "{\"type\":\"object\",\"properties\":{\"message\":{\"type\":\"string\",\"description\":\"Text to echo\"}},\"required\":[\"message\"]}")
+
+  ;; Bump allocator for host-written arguments; each call gets a fresh
+  ;; instance, so nothing is ever freed.
+  (global $heap (mut i32) (i32.const 1024))
+
+  (func (export "name") (result i64)
+    i64.const 9)
+
+  (func (export "description") (result i64)
+    i64.const 68719476767)
+
+  (func (export "parameters") (result i64)
+    i64.const 274877907056)
+
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+    ;; Grow memory until the new heap end fits.
+    (block $done
+      (loop $grow
+        (br_if $done
+          (i32.le_u (global.get $heap)
+                    (i32.mul (memory.size) (i32.const 65536))))
+        (if (i32.eq (memory.grow (i32.const 1)) (i32.const -1))
+          (then unreachable))
+        (br $grow)))
+    (local.get $ptr))
+
+  ;; The arguments are already JSON, so returning them unchanged hands the
+  ;; model back e.g. {"message":"hi"}.
+  (func (export "execute") (param $ptr i32) (param $len i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+      (i64.extend_i32_u (local.get $len))))
+)
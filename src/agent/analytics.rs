@@ -0,0 +1,184 @@
+//! Conversation analytics — per-turn engagement events.
+//!
+//! When a sink is attached with [`AgentLoop::with_conversation_analytics`],
+//! the agent emits one [`AnalyticsEvent`] after every completed turn. Sinks
+//! are called synchronously on the agent task, so implementations should be
+//! cheap (append to a file, push onto a channel) and must not panic.
+//!
+//! [`AgentLoop::with_conversation_analytics`]: crate::agent::AgentLoop::with_conversation_analytics
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::error::Result;
+use crate::session::{Role, Session};
+
+/// Engagement metrics for a single completed conversation turn.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalyticsEvent {
+    /// Session the turn belongs to.
+    pub session_key: String,
+    /// 1-based turn number within the session.
+    pub turn_number: u64,
+    /// Wall-clock time from receiving the message to the final reply.
+    pub response_time_ms: u64,
+    /// Length of the user's message, as received, in characters.
+    pub user_message_length: usize,
+    /// Length of the final assistant reply in characters.
+    pub assistant_message_length: usize,
+    /// Names of the tools called during the turn, in call order.
+    pub tool_calls: Vec<String>,
+    /// Seconds between session creation and the end of this turn.
+    pub session_age_secs: u64,
+    /// When the turn completed.
+    pub timestamp: DateTime<Utc>,
+}
+
+impl AnalyticsEvent {
+    /// Build the event for the turn that just ended in `session`: a user
+    /// message of `user_message_length` characters answered with a reply of
+    /// `assistant_message_length` after `response_time`.
+    ///
+    /// The turn number is the session's turn count; the tool calls are the
+    /// ones after the last user message.
+    pub fn for_turn(
+        session: &Session,
+        user_message_length: usize,
+        assistant_message_length: usize,
+        response_time: Duration,
+    ) -> Self {
+        let turn_start = session
+            .messages
+            .iter()
+            .rposition(|m| m.role == Role::User)
+            .unwrap_or(0);
+        let tool_calls = session.messages[turn_start..]
+            .iter()
+            .filter_map(|m| m.tool_calls.as_ref())
+            .flatten()
+            .map(|tc| tc.name.clone())
+            .collect();
+
+        let now = Utc::now();
+        Self {
+            session_key: session.key.clone(),
+            turn_number: session.turns,
+            response_time_ms: response_time.as_millis() as u64,
+            user_message_length,
+            assistant_message_length,
+            tool_calls,
+            session_age_secs: (now - session.created_at).num_seconds().max(0) as u64,
+            timestamp: now,
+        }
+    }
+}
+
+/// Destination for conversation analytics events.
+pub trait AnalyticsSink: Send + Sync {
+    /// Record one completed turn.
+    fn record(&self, event: AnalyticsEvent);
+}
+
+/// Sink that appends each event as one JSON object per line.
+pub struct JsonLinesFileSink {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl JsonLinesFileSink {
+    /// Open (or create) `path` for appending. Parent directories are created.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Path of the output file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AnalyticsSink for JsonLinesFileSink {
+    fn record(&self, event: AnalyticsEvent) {
+        let mut line = match serde_json::to_string(&event) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!(error = %e, "Failed to serialize analytics event");
+                return;
+            }
+        };
+        line.push('\n');
+        let Ok(mut file) = self.file.lock() else {
+            warn!("Analytics file lock poisoned; dropping event");
+            return;
+        };
+        if let Err(e) = file.write_all(line.as_bytes()) {
+            warn!(path = %self.path.display(), error = %e, "Failed to write analytics event");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{Message, ToolCall};
+    use tempfile::TempDir;
+
+    fn two_turn_session() -> Session {
+        let mut session = Session::new("telegram:42");
+        session.add_message(Message::user("hi"));
+        session.add_message(Message::assistant("hello"));
+        session.add_message(Message::user("what's the weather?"));
+        session.add_message(Message::assistant_with_tools(
+            "",
+            vec![ToolCall::new("c1", "web_search", "{}")],
+        ));
+        session.add_message(Message::tool_result("c1", "sunny"));
+        session.add_message(Message::assistant("Sunny ☀"));
+        session.turns = 2;
+        session
+    }
+
+    #[test]
+    fn test_event_for_turn_covers_last_turn() {
+        let event =
+            AnalyticsEvent::for_turn(&two_turn_session(), 19, 7, Duration::from_millis(1500));
+        assert_eq!(event.session_key, "telegram:42");
+        assert_eq!(event.turn_number, 2);
+        assert_eq!(event.response_time_ms, 1500);
+        assert_eq!(event.user_message_length, 19);
+        assert_eq!(event.assistant_message_length, 7);
+        assert_eq!(event.tool_calls, vec!["web_search".to_string()]);
+    }
+
+    #[test]
+    fn test_json_lines_sink_appends() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested").join("analytics.jsonl");
+        let sink = JsonLinesFileSink::new(&path).unwrap();
+
+        let event = AnalyticsEvent::for_turn(&two_turn_session(), 19, 7, Duration::ZERO);
+        sink.record(event.clone());
+        sink.record(event.clone());
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let parsed: AnalyticsEvent = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(parsed, event);
+    }
+}
@@ -0,0 +1,279 @@
+//! Approval policies for gated tool calls.
+//!
+//! When the [`ApprovalGate`](crate::tools::approval::ApprovalGate) flags a
+//! tool call and no inline handler is installed (the CLI installs one), the
+//! agent loop asks its [`ApprovalPolicy`]. [`ChannelApprovalPolicy`] posts the
+//! question to the chat the request came from and waits for the next message
+//! in that session from the sender who started the turn; anything but an
+//! affirmative reply, or no reply before the timeout, denies the call.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tracing::info;
+
+use crate::bus::{InboundMessage, MessageBus, OutboundMessage};
+use crate::tools::approval::{ApprovalRequest, ApprovalResponse};
+
+/// Longest argument preview shown in a channel approval prompt, in chars.
+const PROMPT_ARGS_MAX_CHARS: usize = 300;
+
+/// Decides whether a gated tool call may run.
+#[async_trait]
+pub trait ApprovalPolicy: Send + Sync {
+    /// Ask for approval of `request`. `origin` is the message that started
+    /// the turn, so the question can go back to the same chat.
+    async fn request_approval(
+        &self,
+        request: ApprovalRequest,
+        origin: &InboundMessage,
+    ) -> ApprovalResponse;
+}
+
+/// Asks in the originating chat over the [`MessageBus`] and waits for a reply.
+pub struct ChannelApprovalPolicy {
+    bus: Arc<MessageBus>,
+    timeout: Duration,
+}
+
+impl ChannelApprovalPolicy {
+    /// Create a policy that waits up to `timeout` for each reply.
+    pub fn new(bus: Arc<MessageBus>, timeout: Duration) -> Self {
+        Self { bus, timeout }
+    }
+}
+
+#[async_trait]
+impl ApprovalPolicy for ChannelApprovalPolicy {
+    async fn request_approval(
+        &self,
+        request: ApprovalRequest,
+        origin: &InboundMessage,
+    ) -> ApprovalResponse {
+        // Register before sending so a quick reply cannot slip past.
+        let reply = self
+            .bus
+            .wait_for_reply_from(&origin.session_key, &origin.sender_id);
+
+        let mut prompt = OutboundMessage::new(
+            &origin.channel,
+            &origin.chat_id,
+            &format_prompt(&request.tool_name, &request.arguments),
+        );
+        if let Some(tid) = origin.metadata.get("telegram_thread_id") {
+            prompt
+                .metadata
+                .insert("telegram_thread_id".to_string(), tid.clone());
+        }
+        if self.bus.publish_outbound(prompt).await.is_err() {
+            self.bus.cancel_reply(&origin.session_key);
+            return ApprovalResponse::Denied("The approval prompt could not be sent.".into());
+        }
+
+        match tokio::time::timeout(self.timeout, reply).await {
+            Ok(Ok(answer)) if is_affirmative(&answer.content) => {
+                info!(tool = %request.tool_name, session = %origin.session_key, "Tool call approved in channel");
+                ApprovalResponse::Approved
+            }
+            Ok(Ok(answer)) => {
+                info!(tool = %request.tool_name, session = %origin.session_key, "Tool call denied in channel");
+                ApprovalResponse::Denied(format!("The user replied: \"{}\"", answer.content.trim()))
+            }
+            // Replaced by a newer request for this session.
+            Ok(Err(_)) => ApprovalResponse::TimedOut,
+            Err(_) => {
+                self.bus.cancel_reply(&origin.session_key);
+                info!(tool = %request.tool_name, session = %origin.session_key, "Tool approval timed out");
+                ApprovalResponse::TimedOut
+            }
+        }
+    }
+}
+
+/// The question sent to the chat, e.g. "About to run: `rm -rf build/` — approve?".
+fn format_prompt(tool_name: &str, args: &Value) -> String {
+    if let Some(command) = args
+        .get("command")
+        .and_then(Value::as_str)
+        .filter(|_| tool_name == "shell")
+    {
+        return format!("About to run: `{}` — approve? (yes/no)", command);
+    }
+    let args = args.to_string();
+    let preview: String = args.chars().take(PROMPT_ARGS_MAX_CHARS).collect();
+    let ellipsis = if preview.len() < args.len() {
+        "…"
+    } else {
+        ""
+    };
+    format!(
+        "About to call `{}` with {}{} — approve? (yes/no)",
+        tool_name, preview, ellipsis
+    )
+}
+
+/// Whether a chat reply approves the call.
+fn is_affirmative(reply: &str) -> bool {
+    let reply = reply.trim().trim_end_matches(['.', '!']).to_lowercase();
+    matches!(
+        reply.as_str(),
+        "y" | "yes"
+            | "ok"
+            | "okay"
+            | "approve"
+            | "approved"
+            | "go"
+            | "go ahead"
+            | "proceed"
+            | "sure"
+            | "👍"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(tool: &str, args: Value) -> ApprovalRequest {
+        ApprovalRequest::new(tool.to_string(), args, 0)
+    }
+
+    #[test]
+    fn test_format_prompt() {
+        assert_eq!(
+            format_prompt("shell", &json!({"command": "rm -rf build/"})),
+            "About to run: `rm -rf build/` — approve? (yes/no)"
+        );
+        assert_eq!(
+            format_prompt("write_file", &json!({"path": "a.txt"})),
+            "About to call `write_file` with {\"path\":\"a.txt\"} — approve? (yes/no)"
+        );
+        let long = format_prompt("write_file", &json!({"content": "x".repeat(1000)}));
+        assert!(long.contains("…"));
+        assert!(long.len() < 400);
+    }
+
+    #[test]
+    fn test_is_affirmative() {
+        for reply in ["yes", " Yes. ", "y", "OK", "go ahead!", "approve"] {
+            assert!(is_affirmative(reply), "{reply}");
+        }
+        for reply in ["no", "yes but not now", "", "stop", "nope"] {
+            assert!(!is_affirmative(reply), "{reply}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_channel_policy_approves_on_reply_in_same_session() {
+        let bus = Arc::new(MessageBus::new());
+        let policy = ChannelApprovalPolicy::new(Arc::clone(&bus), Duration::from_secs(5));
+        let origin = InboundMessage::new("telegram", "user1", "chat1", "clean up");
+
+        let replier = {
+            let bus = Arc::clone(&bus);
+            tokio::spawn(async move {
+                let prompt = bus.consume_outbound().await.unwrap();
+                assert_eq!(prompt.chat_id, "chat1");
+                assert!(prompt.content.starts_with("About to run: `rm -rf build/`"));
+                // Another session's message must not answer the prompt.
+                bus.publish_inbound(InboundMessage::new("telegram", "u2", "chat2", "yes"))
+                    .await
+                    .unwrap();
+                bus.publish_inbound(InboundMessage::new("telegram", "user1", "chat1", "yes"))
+                    .await
+                    .unwrap();
+            })
+        };
+
+        let response = policy
+            .request_approval(
+                request("shell", json!({"command": "rm -rf build/"})),
+                &origin,
+            )
+            .await;
+        replier.await.unwrap();
+
+        assert_eq!(response, ApprovalResponse::Approved);
+        assert_eq!(bus.consume_inbound().await.unwrap().chat_id, "chat2");
+    }
+
+    #[tokio::test]
+    async fn test_channel_policy_denies_on_other_reply() {
+        let bus = Arc::new(MessageBus::new());
+        let policy = ChannelApprovalPolicy::new(Arc::clone(&bus), Duration::from_secs(5));
+        let origin = InboundMessage::new("slack", "user1", "C1", "deploy");
+
+        let replier = {
+            let bus = Arc::clone(&bus);
+            tokio::spawn(async move {
+                bus.consume_outbound().await.unwrap();
+                bus.publish_inbound(InboundMessage::new("slack", "user1", "C1", "no, wait"))
+                    .await
+                    .unwrap();
+            })
+        };
+
+        let response = policy
+            .request_approval(request("write_file", json!({"path": "x"})), &origin)
+            .await;
+        replier.await.unwrap();
+
+        assert_eq!(
+            response,
+            ApprovalResponse::Denied("The user replied: \"no, wait\"".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_channel_policy_ignores_other_senders_in_the_chat() {
+        let bus = Arc::new(MessageBus::new());
+        let policy = ChannelApprovalPolicy::new(Arc::clone(&bus), Duration::from_secs(5));
+        let origin = InboundMessage::new("slack", "user1", "C1", "deploy");
+
+        let replier = {
+            let bus = Arc::clone(&bus);
+            tokio::spawn(async move {
+                bus.consume_outbound().await.unwrap();
+                bus.publish_inbound(InboundMessage::new("slack", "user2", "C1", "yes"))
+                    .await
+                    .unwrap();
+                bus.publish_inbound(InboundMessage::new("slack", "user1", "C1", "no"))
+                    .await
+                    .unwrap();
+            })
+        };
+
+        let response = policy
+            .request_approval(request("write_file", json!({"path": "x"})), &origin)
+            .await;
+        replier.await.unwrap();
+
+        assert_eq!(
+            response,
+            ApprovalResponse::Denied("The user replied: \"no\"".into())
+        );
+        // The other sender's "yes" is an ordinary message.
+        assert_eq!(bus.consume_inbound().await.unwrap().sender_id, "user2");
+    }
+
+    #[tokio::test]
+    async fn test_channel_policy_times_out_and_releases_session() {
+        let bus = Arc::new(MessageBus::new());
+        let policy = ChannelApprovalPolicy::new(Arc::clone(&bus), Duration::from_millis(20));
+        let origin = InboundMessage::new("telegram", "user1", "chat1", "clean up");
+
+        let response = policy
+            .request_approval(request("shell", json!({"command": "ls"})), &origin)
+            .await;
+        assert_eq!(response, ApprovalResponse::TimedOut);
+
+        // A late reply is an ordinary message again.
+        bus.publish_inbound(InboundMessage::new("telegram", "user1", "chat1", "yes"))
+            .await
+            .unwrap();
+        assert_eq!(bus.consume_inbound().await.unwrap().content, "yes");
+    }
+}
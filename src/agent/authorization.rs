@@ -0,0 +1,179 @@
+//! Who may talk to the agent, and what they may do.
+//!
+//! A channel with an entry in `agents.authorization.channels` only answers
+//! the senders it lists, by user ID or chat ID, or everyone when it has a
+//! `default_role`. Anyone else gets `rejection_message` and nothing else:
+//! no session, no model call. Channels without an entry, and heartbeats,
+//! are not restricted.
+//!
+//! A sender's role limits the tools offered to the model and the chat
+//! commands they can run. Commands marked admin-only (see
+//! [`Command::admin_only`](crate::agent::Command::admin_only)), such as
+//! those that change the model, agent or tools, are for admins only,
+//! whatever the role's command list says; so is `/reset` on another
+//! conversation.
+
+use crate::bus::InboundMessage;
+use crate::config::{AuthorizationConfig, RolePermissions, UserRole};
+
+/// Commands a read-only sender may run unless configured otherwise.
+const READONLY_COMMANDS: &[&str] = &["/help", "/history", "/whoami", "/plan", "/stats"];
+
+/// What the sender of a message may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Access {
+    /// The channel has no rules: everything.
+    Unrestricted,
+    /// The sender's role on the channel.
+    Role(UserRole),
+    /// The sender may not talk to the agent.
+    Denied,
+}
+
+impl Access {
+    /// What `msg`'s sender may do under `config`.
+    pub(crate) fn of(config: &AuthorizationConfig, msg: &InboundMessage) -> Self {
+        let Some(channel) = config.channels.get(&msg.channel) else {
+            return Self::Unrestricted;
+        };
+        if msg.is_heartbeat() {
+            return Self::Unrestricted;
+        }
+        channel
+            .users
+            .get(&msg.sender_id)
+            .or_else(|| channel.chats.get(&msg.chat_id))
+            .copied()
+            .or(channel.default_role)
+            .map_or(Self::Denied, Self::Role)
+    }
+
+    /// Whether the sender may run admin commands and act on other
+    /// conversations.
+    pub(crate) fn is_admin(self) -> bool {
+        matches!(self, Self::Unrestricted | Self::Role(UserRole::Admin))
+    }
+
+    /// The tools the sender may use; `None` for all.
+    pub(crate) fn tools(self, config: &AuthorizationConfig) -> Option<Vec<String>> {
+        match self {
+            Self::Role(role) => permissions(config, role).tools,
+            Self::Unrestricted | Self::Denied => None,
+        }
+    }
+
+    /// The reply refusing `command` (e.g. `/model`), or `None` if the sender
+    /// may run it. `admin_only` commands are refused to every other role.
+    pub(crate) fn refuse_command(
+        self,
+        config: &AuthorizationConfig,
+        command: &str,
+        admin_only: bool,
+    ) -> Option<String> {
+        let Self::Role(role) = self else {
+            return None;
+        };
+        if role == UserRole::Admin {
+            return None;
+        }
+        if admin_only {
+            return Some(format!("Only admins can use {}.", command));
+        }
+        match permissions(config, role).commands {
+            Some(allowed) if !allowed.iter().any(|c| c == command) => {
+                Some(format!("You can't use {} here.", command))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// `role`'s permissions: the configured ones, else the defaults.
+fn permissions(config: &AuthorizationConfig, role: UserRole) -> RolePermissions {
+    if let Some(permissions) = config.roles.get(&role) {
+        return permissions.clone();
+    }
+    match role {
+        UserRole::Admin | UserRole::User => RolePermissions::default(),
+        UserRole::Readonly => RolePermissions {
+            tools: Some(Vec::new()),
+            commands: Some(READONLY_COMMANDS.iter().map(|c| c.to_string()).collect()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ChannelAuthorization;
+    use std::collections::HashMap;
+
+    fn config() -> AuthorizationConfig {
+        AuthorizationConfig {
+            channels: HashMap::from([(
+                "telegram".to_string(),
+                ChannelAuthorization {
+                    users: HashMap::from([
+                        ("alice".to_string(), UserRole::Admin),
+                        ("bob".to_string(), UserRole::User),
+                    ]),
+                    chats: HashMap::from([("lobby".to_string(), UserRole::Readonly)]),
+                    default_role: None,
+                },
+            )]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_access_by_user_then_chat() {
+        let config = config();
+        let access = |sender: &str, chat: &str| {
+            Access::of(
+                &config,
+                &InboundMessage::new("telegram", sender, chat, "hi"),
+            )
+        };
+        assert_eq!(access("alice", "lobby"), Access::Role(UserRole::Admin));
+        assert_eq!(access("carol", "lobby"), Access::Role(UserRole::Readonly));
+        assert_eq!(access("carol", "dm"), Access::Denied);
+        assert_eq!(
+            Access::of(&config, &InboundMessage::new("slack", "carol", "dm", "hi")),
+            Access::Unrestricted
+        );
+    }
+
+    #[test]
+    fn test_roles_limit_commands_and_tools() {
+        let mut config = config();
+        let user = Access::Role(UserRole::User);
+        let readonly = Access::Role(UserRole::Readonly);
+        assert_eq!(
+            user.refuse_command(&config, "/model", true).as_deref(),
+            Some("Only admins can use /model.")
+        );
+        assert_eq!(user.refuse_command(&config, "/pin", false), None);
+        assert_eq!(readonly.refuse_command(&config, "/history", false), None);
+        assert!(readonly.refuse_command(&config, "/pin", false).is_some());
+        assert_eq!(
+            Access::Role(UserRole::Admin).refuse_command(&config, "/model", true),
+            None
+        );
+        assert_eq!(user.tools(&config), None);
+        assert_eq!(readonly.tools(&config), Some(Vec::new()));
+
+        config.roles.insert(
+            UserRole::Readonly,
+            RolePermissions {
+                tools: Some(vec!["web_search".to_string()]),
+                commands: None,
+            },
+        );
+        assert_eq!(readonly.refuse_command(&config, "/pin", false), None);
+        assert!(readonly.refuse_command(&config, "/tools", true).is_some());
+        assert_eq!(
+            readonly.tools(&config),
+            Some(vec!["web_search".to_string()])
+        );
+    }
+}
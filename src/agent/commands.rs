@@ -0,0 +1,389 @@
+//! Chat commands registered on an agent.
+//!
+//! A message whose first word names a registered command (`/whoami`) is
+//! answered by the command's handler without calling the model. Every agent
+//! starts with the session commands the loop answers itself (`/help`,
+//! `/model`, `/pin`, ...) and four with handlers here: `/reset`, `/history`,
+//! `/export` and `/whoami`. [`AgentLoop::register_command`] adds more, or
+//! replaces any of them, and `/help` lists whatever is registered.
+//!
+//! A command marked [`Command::admin_only`] is refused to senders whose
+//! `agents.authorization` role is not admin.
+//!
+//! A message that looks like a command but names none goes to the model
+//! like any other, unless `agents.defaults.strict_commands` is on.
+//!
+//! [`AgentLoop::register_command`]: crate::agent::AgentLoop::register_command
+
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use tracing::info;
+
+use crate::bus::{InboundMessage, MessageBus};
+use crate::error::{Result, ZeptoError};
+use crate::session::{Role, SessionManager};
+use crate::utils::string::preview;
+
+/// Messages `/history` shows by default, and at most.
+const HISTORY_DEFAULT_MESSAGES: usize = 10;
+const HISTORY_MAX_MESSAGES: usize = 50;
+
+/// Characters of each message `/history` shows.
+const HISTORY_PREVIEW_CHARS: usize = 200;
+
+/// What a command handler gets to work with.
+pub struct CommandContext {
+    /// The message that invoked the command.
+    pub msg: InboundMessage,
+    /// The words after the command name.
+    pub args: Vec<String>,
+    /// The agent profile answering in this conversation, if any.
+    pub agent_profile: Option<String>,
+    /// Whether the sender may act on other conversations: an admin, or
+    /// anyone on a channel without `agents.authorization` rules.
+    pub is_admin: bool,
+    /// Sessions, for commands that read or change the conversation.
+    pub session_manager: Arc<SessionManager>,
+    /// The message bus, for commands that send more than their reply.
+    pub bus: Arc<MessageBus>,
+    usage: String,
+}
+
+impl CommandContext {
+    /// The session the command was sent in.
+    pub fn session_key(&self) -> &str {
+        &self.msg.session_key
+    }
+
+    /// A "Usage: ..." reply for arguments the command can't use.
+    pub fn usage(&self) -> String {
+        format!("Usage: {}", self.usage)
+    }
+}
+
+type Handler = Arc<dyn Fn(CommandContext) -> BoxFuture<'static, Result<String>> + Send + Sync>;
+
+/// A chat command: a name, how `/help` describes it and the handler whose
+/// result is the reply.
+#[derive(Clone)]
+pub struct Command {
+    name: String,
+    args: String,
+    max_args: usize,
+    description: String,
+    admin_only: bool,
+    /// `None` for a session command the agent loop answers itself.
+    handler: Option<Handler>,
+}
+
+impl std::fmt::Debug for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Command")
+            .field("name", &self.name)
+            .field("args", &self.args)
+            .field("max_args", &self.max_args)
+            .field("description", &self.description)
+            .field("admin_only", &self.admin_only)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Command {
+    /// A command taking no arguments. `name` may be given with or without
+    /// the leading `/`.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use zeptoclaw::agent::Command;
+    ///
+    /// let ping = Command::new("ping", "Check the agent is up", |_ctx| async {
+    ///     Ok("pong".to_string())
+    /// });
+    /// ```
+    pub fn new<F, Fut>(name: &str, description: &str, handler: F) -> Self
+    where
+        F: Fn(CommandContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        Self {
+            name: name.trim_start_matches('/').to_string(),
+            args: String::new(),
+            max_args: 0,
+            description: description.to_string(),
+            admin_only: false,
+            handler: Some(Arc::new(move |ctx| Box::pin(handler(ctx)))),
+        }
+    }
+
+    /// A session command the agent loop parses and answers itself, shown in
+    /// `/help` with `args`.
+    pub(crate) fn session(name: &str, args: &str, description: &str) -> Self {
+        Self {
+            name: name.trim_start_matches('/').to_string(),
+            args: args.to_string(),
+            max_args: 0,
+            description: description.to_string(),
+            admin_only: false,
+            handler: None,
+        }
+    }
+
+    /// Take up to `max` arguments, shown in `/help` as `usage` (e.g.
+    /// `[n]`). More than `max` get the usage line back without running the
+    /// handler.
+    pub fn with_args(mut self, usage: &str, max: usize) -> Self {
+        self.args = usage.to_string();
+        self.max_args = max;
+        self
+    }
+
+    /// Refuse the command to senders whose role is not admin.
+    pub fn admin_only(mut self) -> Self {
+        self.admin_only = true;
+        self
+    }
+
+    /// Whether only admins may run the command.
+    pub fn is_admin_only(&self) -> bool {
+        self.admin_only
+    }
+
+    /// Whether the agent loop answers the command itself.
+    pub(crate) fn is_session(&self) -> bool {
+        self.handler.is_none()
+    }
+
+    /// The command's name, without the leading `/`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The command's arguments as `/help` shows them; empty for none.
+    pub fn args(&self) -> &str {
+        &self.args
+    }
+
+    /// What the command does, as `/help` shows it.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// `/name [args]`.
+    pub(crate) fn usage(&self) -> String {
+        if self.args.is_empty() {
+            format!("/{}", self.name)
+        } else {
+            format!("/{} {}", self.name, self.args)
+        }
+    }
+
+    /// The command's line in `/help`.
+    pub(crate) fn help_line(&self) -> String {
+        format!("- {}: {}", self.usage(), self.description)
+    }
+
+    /// Run the command for `msg`, with `args` already split off.
+    pub(crate) async fn run(
+        &self,
+        msg: &InboundMessage,
+        args: Vec<String>,
+        agent_profile: Option<String>,
+        is_admin: bool,
+        session_manager: Arc<SessionManager>,
+        bus: Arc<MessageBus>,
+    ) -> Result<String> {
+        let ctx = CommandContext {
+            msg: msg.clone(),
+            args,
+            agent_profile,
+            is_admin,
+            session_manager,
+            bus,
+            usage: self.usage(),
+        };
+        let Some(handler) = &self.handler else {
+            return Err(ZeptoError::Config(format!(
+                "/{} is answered by the agent loop",
+                self.name
+            )));
+        };
+        if ctx.args.len() > self.max_args {
+            return Ok(ctx.usage());
+        }
+        handler(ctx).await
+    }
+}
+
+/// The command name and arguments of `text` if it looks like a chat command:
+/// a first word of `/` and a name of letters, digits, `-` or `_`.
+pub(crate) fn parse(text: &str) -> Option<(&str, Vec<String>)> {
+    let mut parts = text.split_whitespace();
+    let name = parts.next()?.strip_prefix('/')?;
+    let looks_like_name = name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    looks_like_name.then(|| (name, parts.map(str::to_string).collect()))
+}
+
+/// The commands every agent starts with.
+pub(crate) fn builtin() -> Vec<Command> {
+    vec![
+        Command::new(
+            "reset",
+            "Clear this conversation (or, for admins, another), keeping its settings and tags",
+            reset,
+        )
+        .with_args("[session]", 1),
+        Command::new("history", "Show the last messages", history).with_args("[n]", 1),
+        Command::new("export", "Send this conversation as Markdown", export),
+        Command::new(
+            "whoami",
+            "Show this conversation's session and agent",
+            whoami,
+        ),
+    ]
+}
+
+/// `/reset [session]`: drop the transcript, summary and plan of this
+/// conversation or, for admins, another. Pins, tags, notes, settings and
+/// usage stay.
+async fn reset(ctx: CommandContext) -> Result<String> {
+    let target = match ctx.args.first() {
+        Some(key) if key != ctx.session_key() => {
+            if !ctx.is_admin {
+                return Ok("Only admins can reset other conversations.".to_string());
+            }
+            let exists = ctx.session_manager.with_session(key, |_| ()).await?;
+            if exists.is_none() {
+                return Ok(format!("No conversation {}.", key));
+            }
+            Some(key.as_str())
+        }
+        _ => None,
+    };
+    let key = target.unwrap_or(ctx.session_key());
+    let cleared = ctx
+        .session_manager
+        .with_session_mut(key, |session| {
+            let cleared = session.messages.len();
+            session.messages.clear();
+            session.summary = None;
+            session.plan.clear();
+            cleared
+        })
+        .await?;
+    info!(session = %key, by = %ctx.msg.sender_id, cleared, "Conversation reset");
+    Ok(match target {
+        Some(key) => format!("Conversation {} cleared ({} messages).", key, cleared),
+        None => format!("Conversation cleared ({} messages).", cleared),
+    })
+}
+
+/// `/history [n]`: the last `n` user and assistant messages.
+async fn history(ctx: CommandContext) -> Result<String> {
+    let n = match ctx.args.first() {
+        None => HISTORY_DEFAULT_MESSAGES,
+        Some(arg) => match arg.parse::<usize>() {
+            Ok(n) if n > 0 => n.min(HISTORY_MAX_MESSAGES),
+            _ => return Ok(ctx.usage()),
+        },
+    };
+    let lines = ctx
+        .session_manager
+        .with_session(ctx.session_key(), |session| {
+            let mut lines: Vec<String> = session
+                .messages
+                .iter()
+                .filter(|m| matches!(m.role, Role::User | Role::Assistant))
+                .filter(|m| !m.content.trim().is_empty())
+                .rev()
+                .take(n)
+                .map(|m| {
+                    let text = preview(m.content.trim(), HISTORY_PREVIEW_CHARS);
+                    format!("[{}] {}", m.role, text)
+                })
+                .collect();
+            lines.reverse();
+            lines
+        })
+        .await?
+        .unwrap_or_default();
+    if lines.is_empty() {
+        return Ok("No messages yet.".to_string());
+    }
+    Ok(lines.join("\n"))
+}
+
+/// `/export`: the conversation as a Markdown transcript.
+async fn export(ctx: CommandContext) -> Result<String> {
+    let empty = ctx
+        .session_manager
+        .with_session(ctx.session_key(), |session| session.messages.is_empty())
+        .await?
+        .unwrap_or(true);
+    if empty {
+        return Ok("Nothing to export yet.".to_string());
+    }
+    ctx.session_manager.export_markdown(ctx.session_key()).await
+}
+
+/// `/whoami`: the session key and the agent profile answering.
+async fn whoami(ctx: CommandContext) -> Result<String> {
+    Ok(format!(
+        "Session: {}\nAgent: {}",
+        ctx.session_key(),
+        ctx.agent_profile.as_deref().unwrap_or("default")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_only_accepts_command_shaped_words() {
+        assert_eq!(
+            parse("/history 5"),
+            Some(("history", vec!["5".to_string()]))
+        );
+        assert_eq!(parse("  /whoami"), Some(("whoami", vec![])));
+        assert_eq!(parse("/usr/bin is missing"), None);
+        assert_eq!(parse("/ 5"), None);
+        assert_eq!(parse("what does /reset do?"), None);
+    }
+
+    #[tokio::test]
+    async fn test_run_checks_the_argument_count() {
+        let echo = Command::new("/echo", "Say it back", |ctx| async move {
+            Ok(ctx.args.join(" "))
+        })
+        .with_args("<word>", 1);
+        assert_eq!(echo.name(), "echo");
+        assert_eq!(echo.help_line(), "- /echo <word>: Say it back");
+
+        let msg = InboundMessage::new("cli", "user", "chat", "/echo");
+        let manager = Arc::new(SessionManager::new_memory());
+        let bus = Arc::new(MessageBus::new());
+        let args = |words: &[&str]| words.iter().map(|w| w.to_string()).collect();
+        let reply = echo
+            .run(
+                &msg,
+                args(&["hi"]),
+                None,
+                true,
+                manager.clone(),
+                bus.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(reply, "hi");
+        let reply = echo
+            .run(&msg, args(&["hi", "there"]), None, true, manager, bus)
+            .await
+            .unwrap();
+        assert_eq!(reply, "Usage: /echo <word>");
+    }
+}
@@ -4,9 +4,25 @@
 //! and message history for LLM conversations. It also provides `RuntimeContext`
 //! for injecting environment-awareness into the agent's system prompt.
 
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 use chrono::Local;
+use tracing::warn;
+
+use crate::agent::context_monitor::ContextMonitor;
+use crate::agent::retrieval::{self, Retriever};
+use crate::agent::workspace_brief::WorkspaceBrief;
+use crate::session::{ContentPart, MemoryNote, Message, PlanItem, Role};
+use crate::tools::output::truncate_middle;
+
+/// Tool results are never truncated below this many bytes.
+const MIN_TRUNCATED_RESULT_BYTES: usize = 1024;
 
-use crate::session::Message;
+/// How long a retriever may take before the turn goes on without it.
+const RETRIEVAL_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Format a timestamp envelope for a user message.
 ///
@@ -40,7 +56,8 @@ You have an ask_clarification tool. When facing ambiguity, use it instead of gue
 - Multiple valid approaches to choose from
 - Destructive or irreversible actions that need confirmation
 - Ambiguous requirements that could be interpreted different ways
-Do not over-use it for trivial decisions you can make yourself."#;
+Do not over-use it for trivial decisions you can make yourself.
+When you need the answer to carry on with the current task, use ask_user instead: it waits for the reply and returns it to you in the same turn."#;
 
 /// System prompt suffix for first-run persona guidance.
 // Wired in by the persona override extraction task (common.rs); suppress
@@ -242,6 +259,235 @@ impl RuntimeContext {
     }
 }
 
+/// Render session memory notes as a system prompt section.
+///
+/// Returns `None` when there are no notes.
+pub fn format_session_notes(notes: &[MemoryNote]) -> Option<String> {
+    if notes.is_empty() {
+        return None;
+    }
+    let mut section =
+        String::from("## Conversation Notes\n\nDurable facts recorded with the `memory` tool:\n");
+    for note in notes {
+        section.push_str("\n- ");
+        section.push_str(&note.text);
+    }
+    Some(section)
+}
+
+/// Render the session's plan as a system prompt section, with `[x]` for
+/// done items and `[ ]` for the rest.
+///
+/// Returns `None` when there is no plan.
+pub fn format_session_plan(plan: &[PlanItem]) -> Option<String> {
+    if plan.is_empty() {
+        return None;
+    }
+    let done = plan.iter().filter(|item| item.done).count();
+    Some(format!(
+        "## Current Plan\n\n{} of {} steps done. Update it with the `todo` tool as you work.\n\n{}",
+        done,
+        plan.len(),
+        crate::tools::todo::format_plan(plan)
+    ))
+}
+
+/// Token budget for the messages of one LLM request.
+///
+/// Derived from the model's context window: the response's `max_tokens`
+/// are reserved, and at most `headroom_ratio` of the window is used so
+/// tool definitions still fit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContextBudget {
+    /// The model's context window in tokens.
+    pub context_window: usize,
+    /// Tokens kept free for the response.
+    pub response_reserve: usize,
+    /// Fraction of the window the messages may use.
+    pub headroom_ratio: f64,
+    /// Multiplier applied to token estimates (>1.0 = conservative).
+    pub safety_margin: f64,
+}
+
+impl ContextBudget {
+    /// A budget for a `context_window`-token model, keeping
+    /// `response_reserve` tokens for the response.
+    pub fn new(context_window: usize, response_reserve: usize) -> Self {
+        Self {
+            context_window,
+            response_reserve,
+            headroom_ratio: 1.0,
+            safety_margin: 1.2,
+        }
+    }
+
+    /// Use at most `ratio` of the window for messages.
+    pub fn with_headroom_ratio(mut self, ratio: f64) -> Self {
+        self.headroom_ratio = ratio;
+        self
+    }
+
+    /// Multiply token estimates by `margin`.
+    pub fn with_safety_margin(mut self, margin: f64) -> Self {
+        self.safety_margin = margin;
+        self
+    }
+
+    /// Tokens available for the messages.
+    pub fn input_tokens(&self) -> usize {
+        let by_ratio = (self.context_window as f64 * self.headroom_ratio) as usize;
+        by_ratio.min(self.context_window.saturating_sub(self.response_reserve))
+    }
+
+    fn estimate(&self, messages: &[Message]) -> usize {
+        ContextMonitor::estimate_tokens_with_margin(messages, self.safety_margin)
+    }
+}
+
+/// What fitting a request into its [`ContextBudget`] left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContextReport {
+    /// Tokens available for the messages.
+    pub budget_tokens: usize,
+    /// Estimated tokens of the messages sent.
+    pub estimated_tokens: usize,
+    /// Oldest turns left out of the request.
+    pub dropped_turns: usize,
+    /// Messages in those turns.
+    pub dropped_messages: usize,
+    /// Tool results shortened middle-out.
+    pub truncated_results: usize,
+}
+
+impl ContextReport {
+    /// Whether anything was truncated or left out.
+    pub fn is_trimmed(&self) -> bool {
+        self.dropped_turns > 0 || self.truncated_results > 0
+    }
+
+    /// Whether the messages fit the budget. Only the system prompt and the
+    /// latest turn are always kept, so they alone may exceed it.
+    pub fn fits(&self) -> bool {
+        self.estimated_tokens <= self.budget_tokens
+    }
+}
+
+impl fmt::Display for ContextReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "~{}/{} tokens, {} turn(s) ({} messages) dropped, {} tool result(s) truncated",
+            self.estimated_tokens,
+            self.budget_tokens,
+            self.dropped_turns,
+            self.dropped_messages,
+            self.truncated_results
+        )
+    }
+}
+
+/// Fit `messages` (system prompt first, then history) into `budget`.
+///
+/// The system prompt and the latest turn are always kept. While the
+/// messages are over budget, the largest tool result is shortened
+/// middle-out, just enough to fit, down to [`MIN_TRUNCATED_RESULT_BYTES`];
+/// only then are the oldest turns dropped. A turn runs from a user message
+/// up to the next one, so tool calls and their results are kept or dropped
+/// together.
+pub fn fit_to_budget(
+    mut messages: Vec<Message>,
+    budget: &ContextBudget,
+) -> (Vec<Message>, ContextReport) {
+    let mut report = ContextReport {
+        budget_tokens: budget.input_tokens(),
+        estimated_tokens: budget.estimate(&messages),
+        ..Default::default()
+    };
+
+    let mut truncated = HashSet::new();
+    while report.estimated_tokens > report.budget_tokens {
+        let largest = messages
+            .iter()
+            .enumerate()
+            .filter(|(i, m)| {
+                m.is_tool_result()
+                    && m.content.len() > MIN_TRUNCATED_RESULT_BYTES
+                    && !truncated.contains(i)
+            })
+            .max_by_key(|(_, m)| m.content.len())
+            .map(|(i, _)| i);
+        let Some(i) = largest else {
+            break;
+        };
+        let message = &mut messages[i];
+        let tokens = budget.estimate(std::slice::from_ref(message)).max(1);
+        let bytes_per_token = message.content.len() as f64 / tokens as f64;
+        let over = report.estimated_tokens - report.budget_tokens;
+        // The truncation marker adds a little; ask for a bit more than needed.
+        let shed = (over as f64 * bytes_per_token) as usize + 128;
+        let keep = message
+            .content
+            .len()
+            .saturating_sub(shed)
+            .max(MIN_TRUNCATED_RESULT_BYTES);
+        message.content = truncate_middle(&message.content, keep);
+        if !message.content_parts.is_empty() {
+            message.content_parts = vec![ContentPart::Text {
+                text: message.content.clone(),
+            }];
+        }
+        truncated.insert(i);
+        report.truncated_results += 1;
+        report.estimated_tokens = budget.estimate(&messages);
+    }
+
+    let first = usize::from(messages.first().is_some_and(|m| m.role == Role::System));
+    while report.estimated_tokens > report.budget_tokens {
+        // The oldest turn ends where the next user message starts; the
+        // latest turn is never dropped.
+        let Some(end) = messages
+            .iter()
+            .enumerate()
+            .skip(first + 1)
+            .find(|(_, m)| m.role == Role::User)
+            .map(|(i, _)| i)
+        else {
+            break;
+        };
+        messages.drain(first..end);
+        report.dropped_turns += 1;
+        report.dropped_messages += end - first;
+        report.estimated_tokens = budget.estimate(&messages);
+    }
+
+    (messages, report)
+}
+
+/// Index of the first `history` message [`fit_to_budget`] could keep.
+///
+/// Walking back from the newest message, once the messages seen are over
+/// `budget` even with every tool result shortened away, every turn before
+/// the current one would be dropped, so the index returned is the start of
+/// the turn the walk stopped in. It is 0 when the whole history may fit.
+pub fn history_start(history: &[Message], budget: &ContextBudget) -> usize {
+    let budget_tokens = budget.input_tokens();
+    let mut tokens = 0;
+    for (i, message) in history.iter().enumerate().rev() {
+        let shortenable =
+            message.is_tool_result() && message.content.len() > MIN_TRUNCATED_RESULT_BYTES;
+        if !shortenable {
+            tokens += budget.estimate(std::slice::from_ref(message));
+        }
+        if tokens > budget_tokens {
+            return history[..=i]
+                .iter()
+                .rposition(|m| m.role == Role::User)
+                .unwrap_or(0);
+        }
+    }
+    0
+}
+
 /// Builder for constructing conversation context for LLM calls.
 ///
 /// The `ContextBuilder` helps construct the full message list including
@@ -270,6 +516,17 @@ pub struct ContextBuilder {
     runtime_context: Option<RuntimeContext>,
     /// Optional memory context to append to system prompt
     memory_context: Option<String>,
+    /// Optional workspace brief, rendered on demand
+    workspace_brief: Option<WorkspaceBrief>,
+    /// Optional retriever of document excerpts for the latest user message
+    retriever: Option<Arc<dyn Retriever>>,
+    /// Excerpts retrieved per turn at most
+    retrieval_top_k: usize,
+    /// Estimated token budget of the excerpts
+    retrieval_max_tokens: usize,
+    /// The last query and its excerpts, so the iterations of one turn
+    /// retrieve once
+    last_retrieval: Mutex<Option<(String, Option<String>)>>,
 }
 
 impl ContextBuilder {
@@ -290,6 +547,11 @@ impl ContextBuilder {
             skills_prompt: None,
             runtime_context: None,
             memory_context: None,
+            workspace_brief: None,
+            retriever: None,
+            retrieval_top_k: 4,
+            retrieval_max_tokens: 1500,
+            last_retrieval: Mutex::new(None),
         }
     }
 
@@ -412,6 +674,34 @@ impl ContextBuilder {
         self
     }
 
+    /// Attach a workspace brief.
+    ///
+    /// The brief is not part of [`build_system_message`](Self::build_system_message);
+    /// the agent loop adds it per turn when the brief is enabled for the
+    /// session, via [`workspace_brief`](Self::workspace_brief).
+    pub fn with_workspace_brief(mut self, brief: WorkspaceBrief) -> Self {
+        self.workspace_brief = Some(brief);
+        self
+    }
+
+    /// Attach a retriever of document excerpts.
+    ///
+    /// Like the workspace brief, the excerpts are not part of
+    /// [`build_system_message`](Self::build_system_message); the agent loop
+    /// adds them per turn via [`retrieve`](Self::retrieve).
+    pub fn with_retriever(mut self, retriever: Arc<dyn Retriever>) -> Self {
+        self.retriever = Some(retriever);
+        self
+    }
+
+    /// Set how many excerpts are retrieved per turn (default 4) and the
+    /// estimated tokens they may use together (default 1500).
+    pub fn with_retrieval_limits(mut self, top_k: usize, max_tokens: usize) -> Self {
+        self.retrieval_top_k = top_k;
+        self.retrieval_max_tokens = max_tokens;
+        self
+    }
+
     /// Append a suffix to the system prompt.
     ///
     /// Used for injecting additional instructions like first-run persona prompts.
@@ -445,6 +735,8 @@ impl ContextBuilder {
             content.push_str("\n\n## Available Skills\n\n");
             content.push_str(skills);
         }
+        // Everything after this point may change from turn to turn.
+        let stable_prefix_len = content.len();
         if let Some(ref ctx) = self.runtime_context {
             if let Some(rendered) = ctx.render() {
                 content.push_str("\n\n");
@@ -455,14 +747,22 @@ impl ContextBuilder {
             content.push_str("\n\n");
             content.push_str(mem);
         }
-        Message::system(&content)
+        let mut message = Message::system(&content);
+        message.stable_prefix_len = Some(stable_prefix_len);
+        message
     }
 
-    /// Build system message with an optional memory context override.
+    /// Build system message with an optional memory context override and the
+    /// session's memory notes and plan.
     ///
     /// When `memory_override` is `Some`, it replaces the stored
     /// `memory_context`. `Some("")` suppresses memory injection.
-    fn build_system_message_with_memory_override(&self, memory_override: Option<&str>) -> Message {
+    fn build_system_message_with_memory_override(
+        &self,
+        memory_override: Option<&str>,
+        notes: &[MemoryNote],
+        plan: &[PlanItem],
+    ) -> Message {
         let mut content = String::new();
         if let Some(ref soul) = self.soul_prompt {
             content.push_str(soul);
@@ -473,6 +773,8 @@ impl ContextBuilder {
             content.push_str("\n\n## Available Skills\n\n");
             content.push_str(skills);
         }
+        // Everything after this point may change from turn to turn.
+        let stable_prefix_len = content.len();
         if let Some(ref ctx) = self.runtime_context {
             if let Some(rendered) = ctx.render() {
                 content.push_str("\n\n");
@@ -489,8 +791,18 @@ impl ContextBuilder {
             content.push_str("\n\n");
             content.push_str(memory);
         }
+        if let Some(notes) = format_session_notes(notes) {
+            content.push_str("\n\n");
+            content.push_str(&notes);
+        }
+        if let Some(plan) = format_session_plan(plan) {
+            content.push_str("\n\n");
+            content.push_str(&plan);
+        }
 
-        Message::system(&content)
+        let mut message = Message::system(&content);
+        message.stable_prefix_len = Some(stable_prefix_len);
+        message
     }
 
     /// Build the full message list for an LLM call.
@@ -551,7 +863,35 @@ impl ContextBuilder {
         user_input: &str,
         memory_override: Option<&str>,
     ) -> Vec<Message> {
-        let mut messages = vec![self.build_system_message_with_memory_override(memory_override)];
+        self.build_messages_with_notes(history, user_input, memory_override, &[])
+    }
+
+    /// Build the full message list for a session turn.
+    ///
+    /// Works like `build_messages_with_memory_override` and additionally
+    /// injects the session's memory notes into the system prompt.
+    pub fn build_messages_with_notes(
+        &self,
+        history: &[Message],
+        user_input: &str,
+        memory_override: Option<&str>,
+        notes: &[MemoryNote],
+    ) -> Vec<Message> {
+        self.build_messages_with_plan(history, user_input, memory_override, notes, &[])
+    }
+
+    /// Build the full message list for a session turn, injecting both the
+    /// session's memory notes and its current plan into the system prompt.
+    pub fn build_messages_with_plan(
+        &self,
+        history: &[Message],
+        user_input: &str,
+        memory_override: Option<&str>,
+        notes: &[MemoryNote],
+        plan: &[PlanItem],
+    ) -> Vec<Message> {
+        let mut messages =
+            vec![self.build_system_message_with_memory_override(memory_override, notes, plan)];
         messages.extend(history.iter().cloned());
         if !user_input.is_empty() {
             let content = if let Some(ref ctx) = self.runtime_context {
@@ -569,6 +909,41 @@ impl ContextBuilder {
         messages
     }
 
+    /// Build the full message list for a session turn like
+    /// [`build_messages_with_plan`](Self::build_messages_with_plan), then
+    /// fit it into `budget` with [`fit_to_budget`].
+    ///
+    /// Turns before [`history_start`] are counted as dropped without being
+    /// copied.
+    pub fn build_messages_within_budget(
+        &self,
+        history: &[Message],
+        user_input: &str,
+        memory_override: Option<&str>,
+        notes: &[MemoryNote],
+        plan: &[PlanItem],
+        budget: &ContextBudget,
+    ) -> (Vec<Message>, ContextReport) {
+        let start = history_start(history, budget);
+        let messages = self.build_messages_with_plan(
+            &history[start..],
+            user_input,
+            memory_override,
+            notes,
+            plan,
+        );
+        let (messages, mut report) = fit_to_budget(messages, budget);
+        if start > 0 {
+            // Turns start at each user message after the first message.
+            report.dropped_turns += history[1..=start]
+                .iter()
+                .filter(|m| m.role == Role::User)
+                .count();
+            report.dropped_messages += start;
+        }
+        (messages, report)
+    }
+
     /// Get the current system prompt.
     pub fn system_prompt(&self) -> &str {
         &self.system_prompt
@@ -583,6 +958,65 @@ impl ContextBuilder {
     pub fn has_skills(&self) -> bool {
         self.skills_prompt.is_some()
     }
+
+    /// Check if a workspace brief is attached.
+    pub fn has_workspace_brief(&self) -> bool {
+        self.workspace_brief.is_some()
+    }
+
+    /// The workspace brief, if one is attached and the workspace is not
+    /// empty. Cached until the workspace changes.
+    pub fn workspace_brief(&self) -> Option<String> {
+        self.workspace_brief.as_ref()?.render()
+    }
+
+    /// Check if a retriever is attached.
+    pub fn has_retriever(&self) -> bool {
+        self.retriever.is_some()
+    }
+
+    /// The `## Reference Excerpts` section for `query` (the latest user
+    /// message), or `None` without a retriever, when it finds nothing, or
+    /// when it fails or takes too long. The last query's section is kept,
+    /// so asking again in the same turn doesn't retrieve again.
+    pub async fn retrieve(&self, query: &str) -> Option<String> {
+        let retriever = self.retriever.as_ref()?;
+        if query.trim().is_empty() || self.retrieval_top_k == 0 {
+            return None;
+        }
+        if let Some((last, section)) = self
+            .last_retrieval
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+        {
+            if last == query {
+                return section.clone();
+            }
+        }
+
+        let section = match tokio::time::timeout(
+            RETRIEVAL_TIMEOUT,
+            retriever.retrieve(query, self.retrieval_top_k),
+        )
+        .await
+        {
+            Ok(Ok(chunks)) => retrieval::format_excerpts(&chunks, self.retrieval_max_tokens),
+            Ok(Err(e)) => {
+                warn!(error = %e, "Retrieval failed; continuing without excerpts");
+                None
+            }
+            Err(_) => {
+                warn!("Retrieval timed out; continuing without excerpts");
+                None
+            }
+        };
+        *self
+            .last_retrieval
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some((query.to_string(), section.clone()));
+        section
+    }
 }
 
 impl Default for ContextBuilder {
@@ -594,7 +1028,6 @@ impl Default for ContextBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::session::Role;
 
     #[test]
     fn test_context_builder_new() {
@@ -1152,4 +1585,244 @@ mod tests {
         assert!(FIRST_RUN_PERSONA_PROMPT.contains("concise"));
         assert!(FIRST_RUN_PERSONA_PROMPT.contains("persona_pref"));
     }
+
+    #[test]
+    fn test_build_messages_with_notes_injects_section() {
+        let mut session = crate::session::Session::new("test");
+        session.add_note("prefers metric units", "agent", 10);
+        let builder = ContextBuilder::new().with_memory_context("## Memory\n- x".to_string());
+
+        let messages = builder.build_messages_with_notes(&[], "hi", None, &session.notes);
+        let system = &messages[0].content;
+        assert!(system.contains("## Memory"));
+        assert!(system.contains("## Conversation Notes"));
+        assert!(system.contains("- prefers metric units"));
+
+        let messages = builder.build_messages_with_memory_override(&[], "hi", None);
+        assert!(!messages[0].content.contains("Conversation Notes"));
+    }
+
+    #[test]
+    fn test_build_messages_with_plan_renders_markers() {
+        let mut session = crate::session::Session::new("test");
+        session.set_plan(&["fetch data", "write report"]);
+        session.check_plan_item(1);
+        let builder = ContextBuilder::new();
+
+        let messages = builder.build_messages_with_plan(&[], "hi", None, &[], &session.plan);
+        let system = &messages[0].content;
+        assert!(system.contains("## Current Plan\n\n1 of 2 steps done."));
+        assert!(system.contains("1. [x] fetch data\n2. [ ] write report"));
+
+        let messages = builder.build_messages_with_notes(&[], "hi", None, &[]);
+        assert!(!messages[0].content.contains("Current Plan"));
+    }
+
+    #[test]
+    fn test_system_message_marks_stable_prefix() {
+        let builder = ContextBuilder::new()
+            .with_system_prompt("Be brief.")
+            .with_skills("- /test: Test skill")
+            .with_runtime_context(RuntimeContext::new().with_timezone("UTC"));
+        let mut session = crate::session::Session::new("test");
+        session.set_plan(&["fetch data"]);
+
+        let messages = builder.build_messages_with_plan(&[], "hi", None, &[], &session.plan);
+        let system = &messages[0];
+        let stable = &system.content[..system.stable_prefix_len.unwrap()];
+        assert!(stable.starts_with("Be brief."));
+        assert!(stable.ends_with("- /test: Test skill"));
+        let changing = &system.content[stable.len()..];
+        assert!(changing.contains("Current time"));
+        assert!(changing.contains("## Current Plan"));
+    }
+
+    fn budget(tokens: usize) -> ContextBudget {
+        ContextBudget::new(tokens, 0).with_safety_margin(1.0)
+    }
+
+    fn tool_turn(id: &str, result: &str) -> Vec<Message> {
+        vec![
+            Message::user(&format!("look up {}", id)),
+            Message::assistant_with_tools(
+                "",
+                vec![crate::session::ToolCall::new(id, "lookup", "{}")],
+            ),
+            Message::tool_result(id, result),
+            Message::assistant(&format!("found {}", id)),
+        ]
+    }
+
+    #[test]
+    fn test_context_budget_input_tokens() {
+        let budget = ContextBudget::new(100_000, 8_000);
+        assert_eq!(budget.input_tokens(), 92_000);
+        assert_eq!(budget.with_headroom_ratio(0.75).input_tokens(), 75_000);
+        assert_eq!(ContextBudget::new(1_000, 4_000).input_tokens(), 0);
+    }
+
+    #[test]
+    fn test_fit_to_budget_keeps_messages_that_fit() {
+        let history = tool_turn("call_1", "small result");
+        let (messages, report) = ContextBuilder::new().build_messages_within_budget(
+            &history,
+            "thanks",
+            None,
+            &[],
+            &[],
+            &budget(100_000),
+        );
+        assert_eq!(messages.len(), 6);
+        assert!(!report.is_trimmed());
+        assert!(report.fits());
+        assert!(report.estimated_tokens > 0);
+    }
+
+    #[test]
+    fn test_fit_to_budget_truncates_huge_tool_result_before_dropping_turns() {
+        let mut history = tool_turn("call_1", &format!("START{}END", "x".repeat(40_000)));
+        history.extend(tool_turn("call_2", "small result"));
+        let (messages, report) = ContextBuilder::new().build_messages_within_budget(
+            &history,
+            "",
+            None,
+            &[],
+            &[],
+            &budget(5_000),
+        );
+
+        assert_eq!(report.dropped_turns, 0);
+        assert_eq!(report.truncated_results, 1);
+        assert!(report.fits(), "{}", report);
+        assert_eq!(messages.len(), 9);
+        let result = &messages[3];
+        assert!(result.content.starts_with("START"));
+        assert!(result.content.ends_with("END"));
+        assert!(result.content.contains("bytes truncated"));
+        assert_eq!(messages[7].content, "small result");
+    }
+
+    #[test]
+    fn test_fit_to_budget_drops_oldest_turns_with_their_tool_results() {
+        let mut history = Vec::new();
+        for i in 0..10 {
+            history.extend(tool_turn(&format!("call_{}", i), &"y".repeat(800)));
+        }
+        let budget = budget(2_000);
+        let (messages, report) = fit_to_budget(
+            ContextBuilder::new().build_messages_with_plan(&history, "", None, &[], &[]),
+            &budget,
+        );
+
+        assert!(report.fits(), "{}", report);
+        assert!(report.dropped_turns > 0);
+        assert_eq!(report.truncated_results, 0);
+        assert_eq!(report.dropped_messages, report.dropped_turns * 4);
+        assert_eq!(messages[0].role, Role::System);
+        assert_eq!(messages[1].role, Role::User);
+        assert_eq!(messages.last().unwrap().content, "found call_9");
+        // Every remaining tool result still has its tool call.
+        let calls: HashSet<&str> = messages
+            .iter()
+            .flat_map(|m| m.tool_calls.iter().flatten())
+            .map(|c| c.id.as_str())
+            .collect();
+        assert!(messages
+            .iter()
+            .filter_map(|m| m.tool_call_id.as_deref())
+            .all(|id| calls.contains(id)));
+    }
+
+    #[test]
+    fn test_fit_to_budget_always_keeps_system_prompt_and_latest_turn() {
+        let history = vec![
+            Message::user("old question"),
+            Message::assistant("old answer"),
+            Message::user(&"z".repeat(4_000)),
+        ];
+        let (messages, report) = fit_to_budget(
+            ContextBuilder::new().build_messages(&history, ""),
+            &budget(100),
+        );
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, Role::System);
+        assert_eq!(report.dropped_turns, 1);
+        assert!(!report.fits());
+    }
+
+    #[test]
+    fn test_history_start_skips_only_turns_that_cannot_fit() {
+        let filler = "x".repeat(400);
+        let mut history = Vec::new();
+        for i in 0..20 {
+            history.push(Message::user(&format!("question {} {}", i, filler)));
+            history.push(Message::assistant(&filler));
+        }
+        assert_eq!(history_start(&history, &budget(100_000)), 0);
+        let budget = budget(1_000);
+        let start = history_start(&history, &budget);
+        assert!(start > 0);
+        assert_eq!(history[start].role, Role::User);
+
+        // Skipping them changes neither the request nor the report.
+        let builder = ContextBuilder::new();
+        let expected = fit_to_budget(
+            builder.build_messages_with_plan(&history, "", None, &[], &[]),
+            &budget,
+        );
+        let (messages, report) =
+            builder.build_messages_within_budget(&history, "", None, &[], &[], &budget);
+        assert_eq!(report, expected.1);
+        let contents = |m: &[Message]| m.iter().map(|m| m.content.clone()).collect::<Vec<_>>();
+        assert_eq!(contents(&messages[1..]), contents(&expected.0[1..]));
+
+        // Long tool results may be shortened, so they don't count.
+        let tool_heavy = [
+            tool_turn("call_1", &"t".repeat(40_000)),
+            tool_turn("call_2", &"t".repeat(40_000)),
+        ]
+        .concat();
+        assert_eq!(history_start(&tool_heavy, &budget), 0);
+    }
+
+    /// Fails every other call, counting them.
+    #[derive(Default)]
+    struct FlakyRetriever {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Retriever for FlakyRetriever {
+        async fn retrieve(
+            &self,
+            query: &str,
+            _k: usize,
+        ) -> crate::error::Result<Vec<retrieval::RetrievedChunk>> {
+            let n = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if n % 2 == 1 {
+                return Err(crate::error::ZeptoError::Tool("index offline".into()));
+            }
+            Ok(vec![retrieval::RetrievedChunk {
+                source: "notes.md:1-1".to_string(),
+                text: format!("About {}", query),
+                score: 1.0,
+            }])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_once_per_query_and_degrade_on_errors() {
+        assert!(ContextBuilder::new().retrieve("anything").await.is_none());
+
+        let retriever = Arc::new(FlakyRetriever::default());
+        let builder = ContextBuilder::new().with_retriever(retriever.clone());
+        let section = builder.retrieve("backups").await.unwrap();
+        assert!(section.contains("[notes.md:1-1]\nAbout backups"));
+        assert_eq!(builder.retrieve("backups").await, Some(section));
+        assert_eq!(retriever.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        assert!(builder.retrieve("restores").await.is_none());
+        assert!(builder.retrieve("").await.is_none());
+        assert_eq!(retriever.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
 }
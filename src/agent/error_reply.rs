@@ -0,0 +1,244 @@
+//! What the user is told when a turn fails.
+//!
+//! Raw errors often carry API responses, serde messages or file paths, none
+//! of which belong in a chat. A failed turn is answered instead with a short
+//! reply for its [`FailureKind`] (customizable under `agents.error_replies`)
+//! and a reference ID. The full error is logged with the same ID, so a user
+//! quoting it can be matched to the log line.
+
+use crate::config::ErrorRepliesConfig;
+use crate::error::{ProviderError, ZeptoError};
+use crate::providers::classify_error_message;
+
+/// Kind of failure, as far as the user is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FailureKind {
+    /// The provider refused the API key, account or model.
+    ProviderAuth,
+    /// The provider is rate limiting, or a usage limit was reached.
+    RateLimit,
+    /// The provider is overloaded, failing or not responding.
+    ProviderUnavailable,
+    /// The conversation no longer fits the model's context window.
+    ContextTooLong,
+    /// A tool or MCP server failed.
+    ToolFailure,
+    /// Anything else.
+    Internal,
+}
+
+impl FailureKind {
+    /// Classify `err`.
+    pub(crate) fn of(err: &ZeptoError) -> Self {
+        match err {
+            ZeptoError::ProviderTyped(pe) => Self::of_provider(pe),
+            ZeptoError::Provider(msg) => Self::of_provider(&classify_error_message(msg)),
+            ZeptoError::Unauthorized(_) => Self::ProviderAuth,
+            ZeptoError::QuotaExceeded(_) | ZeptoError::QuotaRejected(_) => Self::RateLimit,
+            ZeptoError::Http(_) => Self::ProviderUnavailable,
+            ZeptoError::Tool(_) | ZeptoError::Mcp(_) => Self::ToolFailure,
+            _ => Self::Internal,
+        }
+    }
+
+    fn of_provider(err: &ProviderError) -> Self {
+        match err {
+            ProviderError::Auth(_)
+            | ProviderError::Billing(_)
+            | ProviderError::ModelNotFound(_) => Self::ProviderAuth,
+            ProviderError::RateLimit(_) => Self::RateLimit,
+            ProviderError::ServerError(_)
+            | ProviderError::Overloaded(_)
+            | ProviderError::Timeout(_) => Self::ProviderUnavailable,
+            ProviderError::ContextOverflow(_) => Self::ContextTooLong,
+            ProviderError::InvalidRequest(_)
+            | ProviderError::Format(_)
+            | ProviderError::Unknown(_) => Self::Internal,
+        }
+    }
+
+    /// The kind's name in logs and in `agents.error_replies`.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::ProviderAuth => "provider_auth",
+            Self::RateLimit => "rate_limit",
+            Self::ProviderUnavailable => "provider_unavailable",
+            Self::ContextTooLong => "context_too_long",
+            Self::ToolFailure => "tool_failure",
+            Self::Internal => "internal",
+        }
+    }
+
+    fn template(self, templates: &ErrorRepliesConfig) -> &str {
+        let custom = match self {
+            Self::ProviderAuth => &templates.provider_auth,
+            Self::RateLimit => &templates.rate_limit,
+            Self::ProviderUnavailable => &templates.provider_unavailable,
+            Self::ContextTooLong => &templates.context_too_long,
+            Self::ToolFailure => &templates.tool_failure,
+            Self::Internal => &templates.internal,
+        };
+        custom.as_deref().unwrap_or(match self {
+            Self::ProviderAuth => {
+                "The AI provider rejected my credentials or model, so I can't answer right now. \
+                 Please tell the bot's administrator (ref {ref})."
+            }
+            Self::RateLimit => {
+                "The AI provider is rate limiting requests or a usage limit was reached. \
+                 Please try again later (ref {ref})."
+            }
+            Self::ProviderUnavailable => {
+                "The AI provider is overloaded or not responding right now. \
+                 Please try again in a few minutes (ref {ref})."
+            }
+            Self::ContextTooLong => {
+                "This conversation is too long for the model. \
+                 Try /new to start a fresh one (ref {ref})."
+            }
+            Self::ToolFailure => {
+                "One of my tools failed, so I couldn't finish. \
+                 Please try again or rephrase the request (ref {ref})."
+            }
+            Self::Internal => "Something went wrong on my side. Please try again (ref {ref}).",
+        })
+    }
+}
+
+/// The reply to a failed turn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ErrorReply {
+    /// How the failure was classified.
+    pub kind: FailureKind,
+    /// ID tying the reply to the logged error.
+    pub reference: String,
+    /// Text for the chat.
+    pub text: String,
+}
+
+impl ErrorReply {
+    /// The reply to a turn that failed with `err`, with a new reference ID.
+    pub(crate) fn new(err: &ZeptoError, templates: &ErrorRepliesConfig) -> Self {
+        let reference = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+        Self::with_reference(err, templates, reference)
+    }
+
+    fn with_reference(err: &ZeptoError, templates: &ErrorRepliesConfig, reference: String) -> Self {
+        let kind = FailureKind::of(err);
+        let text = kind.template(templates).replace("{ref}", &reference);
+        Self {
+            kind,
+            reference,
+            text,
+        }
+    }
+}
+
+/// `err` followed by each of its sources, for the log.
+pub(crate) fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut chain = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        chain.push_str(": ");
+        chain.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::SessionOp;
+
+    fn reply(err: ZeptoError) -> ErrorReply {
+        ErrorReply::with_reference(&err, &ErrorRepliesConfig::default(), "ab12cd34".to_string())
+    }
+
+    #[test]
+    fn test_each_kind_gets_its_reply_without_error_details() {
+        let cases = [
+            (
+                ZeptoError::from(ProviderError::Auth("invalid x-api-key sk-ant-123".into())),
+                FailureKind::ProviderAuth,
+                "credentials",
+            ),
+            (
+                ZeptoError::Provider("HTTP 429: Too Many Requests".into()),
+                FailureKind::RateLimit,
+                "rate limiting",
+            ),
+            (
+                ZeptoError::QuotaRejected("monthly budget of $5.00 spent".into()),
+                FailureKind::RateLimit,
+                "usage limit",
+            ),
+            (
+                ZeptoError::Provider("HTTP 529: overloaded".into()),
+                FailureKind::ProviderUnavailable,
+                "overloaded",
+            ),
+            (
+                ZeptoError::from(ProviderError::ContextOverflow("prompt is too long".into())),
+                FailureKind::ContextTooLong,
+                "/new",
+            ),
+            (
+                ZeptoError::Tool("failed to read /home/alice/secret.txt".into()),
+                FailureKind::ToolFailure,
+                "tools failed",
+            ),
+            (
+                ZeptoError::SessionIo {
+                    op: SessionOp::Save,
+                    key: Some("telegram:1".into()),
+                    path: "/var/lib/zeptoclaw/sessions/telegram_1.json".into(),
+                    source: "disk full".into(),
+                },
+                FailureKind::Internal,
+                "went wrong",
+            ),
+        ];
+        for (err, kind, wording) in cases {
+            let raw = err.to_string();
+            let reply = reply(err);
+            assert_eq!(reply.kind, kind, "{raw}");
+            assert!(reply.text.contains(wording), "{}", reply.text);
+            assert!(reply.text.ends_with("(ref ab12cd34)."), "{}", reply.text);
+            for detail in ["sk-ant", "429", "529", "$5", "alice", "/var/lib", "disk"] {
+                assert!(!reply.text.contains(detail), "{}", reply.text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_templates_override_replies() {
+        let templates = ErrorRepliesConfig {
+            tool_failure: Some("Tool trouble, sorry! Quote {ref} to support.".to_string()),
+            ..Default::default()
+        };
+        let err = ZeptoError::Mcp("server crashed".into());
+        let reply = ErrorReply::with_reference(&err, &templates, "0042beef".to_string());
+        assert_eq!(
+            reply.text,
+            "Tool trouble, sorry! Quote 0042beef to support."
+        );
+
+        let other = ErrorReply::new(&ZeptoError::BusClosed, &templates);
+        assert_eq!(other.kind, FailureKind::Internal);
+        assert_eq!(other.reference.len(), 8);
+        assert!(other.text.contains(&other.reference));
+    }
+
+    #[test]
+    fn test_error_chain_includes_sources() {
+        let err = ZeptoError::SessionIo {
+            op: SessionOp::Load,
+            key: None,
+            path: "/tmp/s.json".into(),
+            source: Box::new(std::io::Error::other("permission denied")),
+        };
+        let chain = error_chain(&err);
+        assert!(chain.starts_with("Session load failed"));
+        assert!(chain.ends_with(": permission denied"));
+    }
+}
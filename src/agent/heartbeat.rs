@@ -0,0 +1,189 @@
+//! Heartbeats: turns the agent runs on a schedule, without a user message.
+//!
+//! Each entry of `agents.heartbeats` names a session key or a namespace and
+//! holds a standing prompt ("check the watched folder", "anything I should
+//! do?"). When an entry comes due, the agent loop runs its prompt as a turn
+//! in each target session, tools included, unless that session has a turn
+//! under way. A reply of [`HEARTBEAT_OK`] means there is nothing to report:
+//! it is not sent, so quiet checks don't spam the chat.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::bus::message::HEARTBEAT_KEY;
+use crate::bus::InboundMessage;
+use crate::config::HeartbeatSchedule;
+use crate::heartbeat::HEARTBEAT_OK;
+
+/// Shortest interval between two heartbeats of an entry.
+pub(crate) const MIN_INTERVAL_SECS: u64 = 30;
+
+/// How often the agent loop checks for due heartbeats.
+pub(crate) const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Sender ID of heartbeat prompts.
+const HEARTBEAT_SENDER: &str = "heartbeat";
+
+/// When each `agents.heartbeats` entry is due next.
+#[derive(Debug, Default)]
+pub(crate) struct HeartbeatScheduler {
+    next_due: HashMap<String, Instant>,
+}
+
+impl HeartbeatScheduler {
+    /// The entries of `schedules` due at `now`, sorted. An entry is first
+    /// due one interval after it was first seen, then one interval after
+    /// each time it came due.
+    pub(crate) fn due<'a>(
+        &mut self,
+        schedules: &'a HashMap<String, HeartbeatSchedule>,
+        now: Instant,
+    ) -> Vec<&'a str> {
+        let mut due = Vec::new();
+        for (target, schedule) in schedules {
+            if schedule.interval_secs == 0 || schedule.prompt.trim().is_empty() {
+                continue;
+            }
+            let interval = Duration::from_secs(schedule.interval_secs.max(MIN_INTERVAL_SECS));
+            let next = self
+                .next_due
+                .entry(target.clone())
+                .or_insert_with(|| now + interval);
+            if now >= *next {
+                *next = now + interval;
+                due.push(target.as_str());
+            }
+        }
+        due.sort_unstable();
+        due
+    }
+}
+
+/// The sessions an entry of `schedules` runs in: the session it names, or
+/// the `sessions` in the namespace it names that have no entry of their own.
+pub(crate) fn targets(
+    target: &str,
+    schedules: &HashMap<String, HeartbeatSchedule>,
+    sessions: &[String],
+) -> Vec<String> {
+    if target.contains(':') {
+        return vec![target.to_string()];
+    }
+    sessions
+        .iter()
+        .filter(|key| key.split_once(':').is_some_and(|(ns, _)| ns == target))
+        .filter(|key| !schedules.contains_key(key.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// The heartbeat prompt for `session_key`, or `None` if the key names no
+/// channel and chat.
+pub(crate) fn heartbeat_message(session_key: &str, prompt: &str) -> Option<InboundMessage> {
+    let (channel, chat_id) = session_key.split_once(':')?;
+    let content = format!(
+        "{}\n\nIf nothing needs the user's attention, reply with just: {}",
+        prompt.trim(),
+        HEARTBEAT_OK
+    );
+    let mut msg = InboundMessage::new(channel, HEARTBEAT_SENDER, chat_id, &content)
+        .with_metadata(HEARTBEAT_KEY, "true");
+    msg.session_key = session_key.to_string();
+    Some(msg)
+}
+
+/// Whether a heartbeat's `reply` says there is nothing to report: it is
+/// [`HEARTBEAT_OK`], perhaps with punctuation or formatting around it.
+pub(crate) fn is_nothing_to_report(reply: &str) -> bool {
+    let reply = reply.trim_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation());
+    reply == HEARTBEAT_OK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedules() -> HashMap<String, HeartbeatSchedule> {
+        HashMap::from([
+            (
+                "telegram".to_string(),
+                HeartbeatSchedule {
+                    interval_secs: 600,
+                    prompt: "Anything I should do?".to_string(),
+                },
+            ),
+            (
+                "telegram:42".to_string(),
+                HeartbeatSchedule {
+                    interval_secs: 60,
+                    prompt: "Check the inbox folder.".to_string(),
+                },
+            ),
+            (
+                "slack".to_string(),
+                HeartbeatSchedule {
+                    interval_secs: 0,
+                    prompt: "Off.".to_string(),
+                },
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_entries_come_due_once_per_interval() {
+        let schedules = schedules();
+        let mut scheduler = HeartbeatScheduler::default();
+        let start = Instant::now();
+
+        assert!(scheduler.due(&schedules, start).is_empty());
+        assert!(scheduler
+            .due(&schedules, start + Duration::from_secs(59))
+            .is_empty());
+        assert_eq!(
+            scheduler.due(&schedules, start + Duration::from_secs(60)),
+            ["telegram:42"]
+        );
+        assert!(scheduler
+            .due(&schedules, start + Duration::from_secs(90))
+            .is_empty());
+        assert_eq!(
+            scheduler.due(&schedules, start + Duration::from_secs(600)),
+            ["telegram", "telegram:42"]
+        );
+    }
+
+    #[test]
+    fn test_namespace_targets_skip_sessions_with_own_entry() {
+        let schedules = schedules();
+        let sessions = [
+            "telegram:1".to_string(),
+            "telegram:42".to_string(),
+            "telegramx:2".to_string(),
+            "slack:3".to_string(),
+        ];
+        assert_eq!(targets("telegram", &schedules, &sessions), ["telegram:1"]);
+        assert_eq!(
+            targets("telegram:42", &schedules, &sessions),
+            ["telegram:42"]
+        );
+    }
+
+    #[test]
+    fn test_heartbeat_message_and_quiet_replies() {
+        let msg = heartbeat_message("telegram:42", "Check the inbox folder.").unwrap();
+        assert_eq!(
+            (msg.channel.as_str(), msg.chat_id.as_str()),
+            ("telegram", "42")
+        );
+        assert_eq!(msg.session_key, "telegram:42");
+        assert!(msg.is_heartbeat());
+        assert!(msg.content.starts_with("Check the inbox folder."));
+        assert!(msg.content.ends_with(HEARTBEAT_OK));
+        assert!(heartbeat_message("nokey", "x").is_none());
+
+        assert!(is_nothing_to_report("HEARTBEAT_OK"));
+        assert!(is_nothing_to_report("  **HEARTBEAT_OK**.\n"));
+        assert!(!is_nothing_to_report("Two new files arrived. HEARTBEAT_OK"));
+        assert!(!is_nothing_to_report(""));
+    }
+}
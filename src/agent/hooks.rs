@@ -0,0 +1,364 @@
+//! Agent hooks — code that runs before and after every turn.
+//!
+//! Hooks registered with [`AgentLoop::register_hook`] see each inbound
+//! message before the agent does and each reply before it is sent. They run
+//! in registration order:
+//!
+//! - `before_turn` may rewrite the message (strip a bot mention), answer it
+//!   without running the agent ([`HookDecision::Skip`]) or drop it
+//!   ([`HookDecision::Reject`]). The first hook that doesn't continue ends the
+//!   chain.
+//! - `after_turn` may rewrite the reply (append a signature, translate).
+//!
+//! A hook that panics fails only the turn it was running for, with
+//! [`ZeptoError::Hook`].
+//!
+//! Two hooks ship with ZeptoClaw: [`AllowlistHook`] and [`RegexFilterHook`].
+//!
+//! [`AgentLoop::register_hook`]: crate::agent::AgentLoop::register_hook
+
+use std::collections::HashSet;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::FutureExt;
+use regex::Regex;
+use tracing::{debug, error};
+
+use crate::bus::InboundMessage;
+use crate::error::{Result, ZeptoError};
+
+/// What a [`AgentHook::before_turn`] hook wants done with a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookDecision {
+    /// Run the turn with the (possibly rewritten) message.
+    Continue,
+    /// Don't run the turn; answer with this reply instead.
+    Skip(String),
+    /// Drop the message without running the turn or replying. The reason is
+    /// logged and returned as [`ZeptoError::Rejected`] to direct callers.
+    Reject(String),
+}
+
+/// A reply on its way out, as seen by [`AgentHook::after_turn`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboundResponse {
+    /// Channel the reply goes to.
+    pub channel: String,
+    /// Chat the reply goes to.
+    pub chat_id: String,
+    /// Sender of the message being answered.
+    pub sender_id: String,
+    /// Session the turn belongs to.
+    pub session_key: String,
+    /// The reply text.
+    pub content: String,
+}
+
+impl OutboundResponse {
+    /// The reply `content` to `msg`.
+    pub fn reply_to(msg: &InboundMessage, content: String) -> Self {
+        Self {
+            channel: msg.channel.clone(),
+            chat_id: msg.chat_id.clone(),
+            sender_id: msg.sender_id.clone(),
+            session_key: msg.session_key.clone(),
+            content,
+        }
+    }
+}
+
+/// Code run before and after every turn of an [`AgentLoop`].
+///
+/// Both methods default to doing nothing, so a hook implements only the
+/// side it cares about.
+///
+/// [`AgentLoop`]: crate::agent::AgentLoop
+#[async_trait]
+pub trait AgentHook: Send + Sync {
+    /// Name used in logs and errors.
+    fn name(&self) -> &str;
+
+    /// Inspect or rewrite `msg` before the agent sees it.
+    async fn before_turn(&self, _msg: &mut InboundMessage) -> HookDecision {
+        HookDecision::Continue
+    }
+
+    /// Inspect or rewrite the reply to a turn that ran.
+    async fn after_turn(&self, _response: &mut OutboundResponse) {}
+}
+
+/// Run `hooks`' `before_turn` in order, stopping at the first decision other
+/// than [`HookDecision::Continue`].
+pub(crate) async fn run_before_turn(
+    hooks: &[Arc<dyn AgentHook>],
+    msg: &mut InboundMessage,
+) -> Result<HookDecision> {
+    for hook in hooks {
+        let decision = AssertUnwindSafe(hook.before_turn(msg))
+            .catch_unwind()
+            .await
+            .map_err(|_| panicked(hook.as_ref(), "before_turn"))?;
+        if decision != HookDecision::Continue {
+            debug!(hook = hook.name(), ?decision, "Hook ended the turn early");
+            return Ok(decision);
+        }
+    }
+    Ok(HookDecision::Continue)
+}
+
+/// Run `hooks`' `after_turn` in order.
+pub(crate) async fn run_after_turn(
+    hooks: &[Arc<dyn AgentHook>],
+    response: &mut OutboundResponse,
+) -> Result<()> {
+    for hook in hooks {
+        AssertUnwindSafe(hook.after_turn(response))
+            .catch_unwind()
+            .await
+            .map_err(|_| panicked(hook.as_ref(), "after_turn"))?;
+    }
+    Ok(())
+}
+
+fn panicked(hook: &dyn AgentHook, phase: &str) -> ZeptoError {
+    error!(hook = hook.name(), phase, "Agent hook panicked");
+    ZeptoError::Hook(format!("hook '{}' panicked in {}", hook.name(), phase))
+}
+
+// ---------------------------------------------------------------------------
+// Built-in hooks
+// ---------------------------------------------------------------------------
+
+/// Lets only listed senders through.
+///
+/// An entry is either a sender ID (`"12345"`) or a sender on one channel
+/// (`"telegram:12345"`). Other senders are rejected, or answered with a
+/// fixed reply when one is set.
+///
+/// # Example
+/// ```
+/// use zeptoclaw::agent::hooks::AllowlistHook;
+///
+/// let hook = AllowlistHook::new(["telegram:12345", "admin"])
+///     .with_reply("Sorry, this bot is private.");
+/// ```
+#[derive(Debug, Clone)]
+pub struct AllowlistHook {
+    allowed: HashSet<String>,
+    reply: Option<String>,
+}
+
+impl AllowlistHook {
+    /// Allow the senders in `allowed`.
+    pub fn new<I, S>(allowed: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            allowed: allowed.into_iter().map(Into::into).collect(),
+            reply: None,
+        }
+    }
+
+    /// Answer other senders with `reply` instead of ignoring them.
+    pub fn with_reply(mut self, reply: &str) -> Self {
+        self.reply = Some(reply.to_string());
+        self
+    }
+
+    fn allows(&self, msg: &InboundMessage) -> bool {
+        self.allowed.contains(&msg.sender_id)
+            || self
+                .allowed
+                .contains(&format!("{}:{}", msg.channel, msg.sender_id))
+    }
+}
+
+#[async_trait]
+impl AgentHook for AllowlistHook {
+    fn name(&self) -> &str {
+        "allowlist"
+    }
+
+    async fn before_turn(&self, msg: &mut InboundMessage) -> HookDecision {
+        if self.allows(msg) {
+            return HookDecision::Continue;
+        }
+        match &self.reply {
+            Some(reply) => HookDecision::Skip(reply.clone()),
+            None => HookDecision::Reject(format!(
+                "sender '{}' on {} is not allowed",
+                msg.sender_id, msg.channel
+            )),
+        }
+    }
+}
+
+/// Replaces text matching any of a set of patterns, in both the incoming
+/// message and the reply.
+///
+/// Useful as a profanity filter (`"(?i)\\bdarn\\b"` → `"***"`) or to strip
+/// bot mentions (`"@my_bot\\s*"` → `""`).
+///
+/// # Example
+/// ```
+/// use zeptoclaw::agent::hooks::RegexFilterHook;
+///
+/// let hook = RegexFilterHook::new(&["(?i)\\bdarn\\b"]).unwrap();
+/// let strip_mentions = RegexFilterHook::new(&["@my_bot\\s*"])
+///     .unwrap()
+///     .with_replacement("")
+///     .incoming_only();
+/// ```
+#[derive(Debug, Clone)]
+pub struct RegexFilterHook {
+    patterns: Vec<Regex>,
+    replacement: String,
+    filter_replies: bool,
+}
+
+impl RegexFilterHook {
+    /// Replace matches of any of `patterns` with `***`.
+    pub fn new(patterns: &[&str]) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|p| {
+                Regex::new(p).map_err(|e| {
+                    ZeptoError::Config(format!("Invalid filter pattern '{}': {}", p, e))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            patterns,
+            replacement: "***".to_string(),
+            filter_replies: true,
+        })
+    }
+
+    /// Replace matches with `replacement` instead of `***`.
+    pub fn with_replacement(mut self, replacement: &str) -> Self {
+        self.replacement = replacement.to_string();
+        self
+    }
+
+    /// Leave replies alone and filter only incoming messages.
+    pub fn incoming_only(mut self) -> Self {
+        self.filter_replies = false;
+        self
+    }
+
+    fn filter(&self, text: &mut String) {
+        for pattern in &self.patterns {
+            if pattern.is_match(text) {
+                *text = pattern
+                    .replace_all(text, self.replacement.as_str())
+                    .into_owned();
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl AgentHook for RegexFilterHook {
+    fn name(&self) -> &str {
+        "regex_filter"
+    }
+
+    async fn before_turn(&self, msg: &mut InboundMessage) -> HookDecision {
+        self.filter(&mut msg.content);
+        HookDecision::Continue
+    }
+
+    async fn after_turn(&self, response: &mut OutboundResponse) {
+        if self.filter_replies {
+            self.filter(&mut response.content);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct PanickingHook;
+
+    #[async_trait]
+    impl AgentHook for PanickingHook {
+        fn name(&self) -> &str {
+            "panicking"
+        }
+
+        async fn before_turn(&self, _msg: &mut InboundMessage) -> HookDecision {
+            panic!("boom");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_allowlist_rejects_or_answers_unknown_senders() {
+        let hook = AllowlistHook::new(["telegram:alice", "bob"]);
+        let mut alice = InboundMessage::new("telegram", "alice", "chat1", "hi");
+        let mut alice_elsewhere = InboundMessage::new("discord", "alice", "chat1", "hi");
+        let mut bob = InboundMessage::new("slack", "bob", "chat1", "hi");
+        assert_eq!(hook.before_turn(&mut alice).await, HookDecision::Continue);
+        assert_eq!(hook.before_turn(&mut bob).await, HookDecision::Continue);
+        assert!(matches!(
+            hook.before_turn(&mut alice_elsewhere).await,
+            HookDecision::Reject(_)
+        ));
+
+        let hook = hook.with_reply("Private bot.");
+        assert_eq!(
+            hook.before_turn(&mut alice_elsewhere).await,
+            HookDecision::Skip("Private bot.".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_regex_filter_rewrites_both_directions() {
+        let hook = RegexFilterHook::new(&["(?i)\\bdarn\\b", "heck"]).unwrap();
+        let mut msg = InboundMessage::new("cli", "user", "chat1", "Darn it, what the heck");
+        assert_eq!(hook.before_turn(&mut msg).await, HookDecision::Continue);
+        assert_eq!(msg.content, "*** it, what the ***");
+
+        let mut reply = OutboundResponse::reply_to(&msg, "darn right".to_string());
+        hook.after_turn(&mut reply).await;
+        assert_eq!(reply.content, "*** right");
+
+        let mentions = RegexFilterHook::new(&["@bot\\s*"])
+            .unwrap()
+            .with_replacement("")
+            .incoming_only();
+        let mut reply = OutboundResponse::reply_to(&msg, "ask @bot".to_string());
+        mentions.after_turn(&mut reply).await;
+        assert_eq!(reply.content, "ask @bot");
+
+        assert!(RegexFilterHook::new(&["("]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hooks_run_in_order_and_panics_fail_the_turn() {
+        let chain: Vec<Arc<dyn AgentHook>> = vec![
+            Arc::new(
+                RegexFilterHook::new(&["@bot\\s*"])
+                    .unwrap()
+                    .with_replacement(""),
+            ),
+            Arc::new(AllowlistHook::new(["alice"])),
+            Arc::new(PanickingHook),
+        ];
+
+        // The allowlist stops the chain before the panicking hook runs.
+        let mut msg = InboundMessage::new("cli", "mallory", "chat1", "@bot hi");
+        let decision = run_before_turn(&chain, &mut msg).await.unwrap();
+        assert!(matches!(decision, HookDecision::Reject(_)));
+        assert_eq!(msg.content, "hi");
+
+        let mut msg = InboundMessage::new("cli", "alice", "chat1", "@bot hi");
+        let err = run_before_turn(&chain, &mut msg).await.unwrap_err();
+        assert!(matches!(err, ZeptoError::Hook(_)), "{err}");
+        assert!(err.to_string().contains("panicking"));
+    }
+}
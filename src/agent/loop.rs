@@ -3,33 +3,66 @@
 //! This module provides the core agent loop that processes messages,
 //! calls LLM providers, and executes tools.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-use futures::FutureExt;
+use futures::stream::FuturesUnordered;
+use futures::{FutureExt, StreamExt};
 use tokio::sync::{watch, Mutex, RwLock};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, info_span, warn, Instrument};
 
 use crate::agent::context_monitor::{CompactionUrgency, ContextMonitor, PreflightAction};
 use crate::agent::loop_guard::{truncate_utf8, LoopGuard, LoopGuardAction, ToolCallSig};
+use crate::bus::message::MODEL_OVERRIDE_KEY;
 use crate::bus::{InboundMessage, MessageBus, OutboundMessage};
 use crate::cache::ResponseCache;
-use crate::config::Config;
+use crate::config::{
+    AgentConfig, AgentDefaults, AgentProfile, Config, GenerationSettings, MessageQueueMode,
+    OnboardingMode,
+};
 use crate::error::{ProviderError, Result, ZeptoError};
 use crate::health::UsageMetrics;
-use crate::providers::{ChatOptions, LLMProvider, LLMToolCall};
+use crate::providers::structured::validate_json_response;
+use crate::providers::{
+    ChatOptions, LLMProvider, LLMResponse, LLMToolCall, QuotaCheckResult, QuotaConfig, QuotaPeriod,
+    QuotaStore, ToolDefinition, Usage,
+};
 use crate::safety::SafetyLayer;
-use crate::session::{Message, Role, SessionManager, ToolCall};
+use crate::security::Sandbox;
+use crate::session::{CommandRecord, Message, Role, SessionManager, ToolCall, UsageCost};
 use crate::tools::approval::{ApprovalGate, ApprovalRequest, ApprovalResponse};
-use crate::tools::{Tool, ToolCategory, ToolContext, ToolRegistry};
+use crate::tools::rate_limit::{Quota, ToolRateLimiter};
+use crate::tools::{
+    ArtifactStore, ProcessRegistry, Tool, ToolContext, ToolMiddleware, ToolProgress, ToolRegistry,
+    ToolUsage,
+};
+use crate::utils::cost::{estimate_cost, estimate_cost_with_cache, BudgetAction, ModelPricing};
 use crate::utils::metrics::MetricsCollector;
 
+use super::analytics::{AnalyticsEvent, AnalyticsSink};
+use super::approval::{ApprovalPolicy, ChannelApprovalPolicy};
+use super::authorization::Access;
 use super::budget::TokenBudget;
-use super::context::ContextBuilder;
+use super::commands::{self, Command};
+use super::context::{history_start, ContextBudget, ContextBuilder};
+use super::error_reply::{error_chain, ErrorReply};
+use super::heartbeat::{self, HeartbeatScheduler};
+use super::hooks::{run_after_turn, run_before_turn, AgentHook, HookDecision, OutboundResponse};
+use super::onboarding;
+use super::progress::{FeedbackSink, TurnProgress};
+use super::reply_stream::{self, ReplyStream};
+use super::response;
+use super::retrieval::DirectoryRetriever;
+use super::scheduler::{Admission, TurnScheduler, TurnSlot};
+use super::settings::{self, Setting};
+use super::tool_access::ToolAccess;
 use super::tool_call_limit::ToolCallLimitTracker;
+use super::trace::{self, ActiveTrace, TraceRecorder, TurnTrace};
+use super::workspace_brief::WorkspaceBrief;
 
 /// System prompt sent during the memory flush turn, instructing the LLM to
 /// persist important facts and deduplicate existing long-term memory entries.
@@ -49,6 +82,14 @@ const MAX_IMAGE_SIZE: usize = 20 * 1024 * 1024; // 20MB
 
 const INTERACTIVE_CLI_METADATA_KEY: &str = "interactive_cli";
 const TRUSTED_LOCAL_SESSION_METADATA_KEY: &str = "trusted_local_session";
+/// How often streamed tool output is relayed to the user.
+const TOOL_PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+/// Most recent streamed output shown in each progress message.
+const TOOL_PROGRESS_MAX_BYTES: usize = 1500;
+/// Directory under the sessions directory holding the monthly spend counter.
+const SPEND_DIR: &str = "spend";
+/// Key of the all-conversations total in the spend counter.
+const SPEND_KEY: &str = "total";
 
 type ApprovalFuture = Pin<Box<dyn Future<Output = ApprovalResponse> + Send>>;
 type ApprovalHandler = Arc<dyn Fn(ApprovalRequest) -> ApprovalFuture + Send + Sync>;
@@ -69,25 +110,111 @@ fn is_trusted_local_session(msg: &InboundMessage) -> bool {
             .is_none_or(|value| value != "true")
 }
 
+/// Relay output streamed by a running tool to the user as intermediate
+/// messages.
+///
+/// Output is batched and sent at most every [`TOOL_PROGRESS_INTERVAL`],
+/// showing the latest [`TOOL_PROGRESS_MAX_BYTES`]. A tool that finishes
+/// within the first interval sends nothing, and output still buffered at the
+/// end is dropped since the tool's result carries it. Abort the returned
+/// handle once the tool is done.
+fn spawn_progress_relay(
+    bus: Arc<MessageBus>,
+    ctx: &ToolContext,
+    inbound_meta: &HashMap<String, String>,
+    tool_name: &str,
+) -> (ToolProgress, tokio::task::JoinHandle<()>) {
+    let mut template = OutboundMessage::new(
+        ctx.channel.as_deref().unwrap_or(""),
+        ctx.chat_id.as_deref().unwrap_or(""),
+        "",
+    );
+    for key in ["telegram_thread_id", "telegram_message_id"] {
+        if let Some(value) = inbound_meta.get(key) {
+            template.metadata.insert(key.to_string(), value.clone());
+        }
+    }
+    template
+        .metadata
+        .insert("keep_typing".to_string(), "true".to_string());
+    let tool_name = tool_name.to_string();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    let progress = ToolProgress::new(move |chunk| {
+        let _ = tx.send(chunk.to_string());
+    });
+    let relay = tokio::spawn(async move {
+        let mut buffer = String::new();
+        let start = tokio::time::Instant::now() + TOOL_PROGRESS_INTERVAL;
+        let mut ticker = tokio::time::interval_at(start, TOOL_PROGRESS_INTERVAL);
+        loop {
+            tokio::select! {
+                chunk = rx.recv() => match chunk {
+                    Some(chunk) => {
+                        buffer.push_str(&chunk);
+                        if buffer.len() > 2 * TOOL_PROGRESS_MAX_BYTES {
+                            buffer = output_tail(&buffer, TOOL_PROGRESS_MAX_BYTES).to_string();
+                        }
+                    }
+                    None => break,
+                },
+                _ = ticker.tick() => {
+                    if buffer.is_empty() {
+                        continue;
+                    }
+                    let mut outbound = template.clone();
+                    outbound.content = format!(
+                        "`{}` is still running:\n```\n{}\n```",
+                        tool_name,
+                        output_tail(&buffer, TOOL_PROGRESS_MAX_BYTES).trim_end()
+                    );
+                    buffer.clear();
+                    let _ = bus.publish_outbound(outbound).await;
+                }
+            }
+        }
+    });
+    (progress, relay)
+}
+
+/// The last `max_bytes` of `text`, starting on a char boundary.
+fn output_tail(text: &str, max_bytes: usize) -> &str {
+    let mut start = text.len().saturating_sub(max_bytes);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    &text[start..]
+}
+
 async fn resolve_tool_approval(
     gate: &ApprovalGate,
     approval_handler: Option<&ApprovalHandler>,
+    approval_policy: Option<&Arc<dyn ApprovalPolicy>>,
+    origin: &InboundMessage,
     tool_name: &str,
     args: &serde_json::Value,
 ) -> Option<String> {
-    if !gate.requires_approval(tool_name) {
+    if !gate.requires_approval_for(tool_name, args) {
         return None;
     }
 
-    if let Some(handler) = approval_handler {
-        match handler(gate.create_request(tool_name, args)).await {
+    let request = gate.create_request(tool_name, args);
+    let response = match (approval_handler, approval_policy) {
+        (Some(handler), _) => Some(handler(request).await),
+        (None, Some(policy)) => Some(policy.request_approval(request, origin).await),
+        (None, None) => None,
+    };
+    if let Some(response) = response {
+        match response {
             ApprovalResponse::Approved => None,
             ApprovalResponse::Denied(reason) => Some(format!(
-                "Tool '{}' was denied by user approval. {}",
+                "Tool '{}' was denied by user approval. {} \
+                 Do not retry the same call; ask the user how to proceed or take another approach.",
                 tool_name, reason
             )),
             ApprovalResponse::TimedOut => Some(format!(
-                "Tool '{}' approval timed out and was not executed.",
+                "Tool '{}' approval timed out and was not executed. \
+                 Treat this as a denial; do not retry the same call without asking the user.",
                 tool_name
             )),
         }
@@ -100,27 +227,55 @@ async fn resolve_tool_approval(
     }
 }
 
-/// Returns `true` if any tool in the batch may cause ordering-sensitive side effects
-/// (filesystem writes, shell commands) and the batch should be executed sequentially
-/// rather than in parallel.
+/// For each call in the batch, whether its tool may run alongside the others
+/// ([`Tool::parallel_safe`]).
 ///
-/// Unknown tools (not found in the registry) default to `true` (fail-safe: serialize).
-async fn needs_sequential_execution(
+/// Unknown tools (not found in the registry) count as unsafe (fail-safe: serialize).
+async fn parallel_safety(
     tools: &Arc<RwLock<ToolRegistry>>,
     tool_calls: &[LLMToolCall],
-) -> bool {
+) -> Vec<bool> {
     let guard = tools.read().await;
-    tool_calls.iter().any(|tc| {
-        guard
-            .get(&tc.name)
-            .map(|t| {
-                matches!(
-                    t.category(),
-                    ToolCategory::FilesystemWrite | ToolCategory::Shell
-                )
-            })
-            .unwrap_or(true) // unknown tool → serialize to be safe
-    })
+    tool_calls
+        .iter()
+        .map(|tc| guard.get(&tc.name).is_some_and(|t| t.parallel_safe()))
+        .collect()
+}
+
+/// Run one assistant message's tool calls concurrently, at most `limit` at a
+/// time (0 = no limit), and time each call from when it starts.
+///
+/// Calls that are not parallel-safe also take a shared lock, so they run one
+/// after another in their original order while safe calls proceed. Results
+/// are returned in input order so `tool_call_id` pairing is preserved.
+async fn run_tool_batch<F: Future>(
+    calls: Vec<F>,
+    parallel_safe: &[bool],
+    limit: usize,
+) -> Vec<(F::Output, std::time::Duration)> {
+    let permits = if limit == 0 {
+        calls.len().max(1)
+    } else {
+        limit
+    };
+    let slots = Arc::new(tokio::sync::Semaphore::new(permits));
+    let serial = Arc::new(Mutex::new(()));
+    let guarded = calls.into_iter().zip(parallel_safe).map(|(call, &safe)| {
+        let slots = Arc::clone(&slots);
+        let serial = Arc::clone(&serial);
+        async move {
+            // Take the serial lock before a slot so a waiting unsafe call
+            // does not hold a slot that a safe call could use.
+            let _serial = if safe {
+                None
+            } else {
+                Some(serial.lock().await)
+            };
+            let _slot = slots.acquire().await.expect("tool slots never closed");
+            timed(call).await
+        }
+    });
+    futures::future::join_all(guarded).await
 }
 
 /// Check the loop guard for repeated tool-call patterns.
@@ -228,6 +383,954 @@ fn check_loop_guard_outcomes(
     false
 }
 
+/// Commands `/audit` shows by default, and at most.
+const AUDIT_DEFAULT_ENTRIES: usize = 10;
+const AUDIT_MAX_ENTRIES: usize = 100;
+
+/// The session commands, which the loop parses with
+/// [`parse_session_command`] and answers itself. A command missing here is
+/// not answered, so `/help` always lists every command.
+fn session_commands() -> Vec<Command> {
+    vec![
+        Command::session("/help", "", "Show these commands"),
+        Command::session("/tools", "", "List the tools available here").admin_only(),
+        Command::session("/stop", "", "Stop the reply being worked on"),
+        Command::session(
+            "/agent",
+            "[name|auto]",
+            "Show or switch the agent answering",
+        )
+        .admin_only(),
+        Command::session("/model", "[name|list|reset]", "Show or switch the model").admin_only(),
+        Command::session("/temp", "[value|reset]", "Set the sampling temperature").admin_only(),
+        Command::session(
+            "/think",
+            "[budget|off|reset]",
+            "Set the extended thinking budget",
+        )
+        .admin_only(),
+        Command::session(
+            "/settings",
+            "[name value|reset]",
+            "Show or change the model and sampling settings",
+        )
+        .admin_only(),
+        Command::session("/plan", "", "Show the agent's plan"),
+        Command::session("/stats", "", "Show tool use and cost so far"),
+        Command::session("/audit", "[n]", "Show the last shell commands run").admin_only(),
+        Command::session(
+            "/artifacts",
+            "[clear]",
+            "List or delete the files tools saved",
+        ),
+        Command::session("/pin", "", "Keep this conversation during cleanup"),
+        Command::session(
+            "/unpin",
+            "",
+            "Stop keeping this conversation during cleanup",
+        ),
+        Command::session(
+            "/tag",
+            "[tags...]",
+            "Tag this conversation, or list its tags",
+        ),
+        Command::session("/untag", "<tags...>", "Remove tags"),
+        Command::session(
+            "/dryrun",
+            "[on|off]",
+            "Describe tool calls instead of running them",
+        )
+        .admin_only(),
+        Command::session(
+            "/brief",
+            "[on|off]",
+            "Add a map of the workspace to the prompt",
+        )
+        .admin_only(),
+        Command::session("/trace", "[on|off]", "Record each turn for debugging").admin_only(),
+    ]
+}
+
+/// The chat commands every agent starts with: the session commands, then
+/// [`commands::builtin`].
+fn default_commands() -> Vec<Command> {
+    session_commands()
+        .into_iter()
+        .chain(commands::builtin())
+        .collect()
+}
+
+/// One line per chat command, as `/help` and the welcome list them.
+fn format_command_list(commands: &[Command]) -> String {
+    commands
+        .iter()
+        .map(Command::help_line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Session management command typed into a chat.
+#[derive(Debug, PartialEq, Eq)]
+enum SessionCommand {
+    /// `/help` — list the chat commands.
+    Help,
+    /// `/pin` (true) or `/unpin` (false).
+    Pin(bool),
+    /// `/tag [tags...]` — add tags, or list them when none are given.
+    Tag(Vec<String>),
+    /// `/untag <tags...>` — remove tags.
+    Untag(Vec<String>),
+    /// `/tools` — list the tools available in this conversation.
+    Tools,
+    /// `/artifacts` (false) lists this session's artifacts; `/artifacts clear`
+    /// (true) deletes them.
+    Artifacts(bool),
+    /// `/plan` — show the agent's plan for this conversation.
+    Plan,
+    /// `/stats` — show tool usage in this conversation.
+    Stats,
+    /// `/audit [n]` — show the last `n` shell commands run in this
+    /// conversation.
+    Audit(usize),
+    /// `/dryrun on|off` turns dry-run mode on or off for this conversation;
+    /// `/dryrun` (None) shows whether it is on.
+    DryRun(Option<bool>),
+    /// `/brief on|off` turns the workspace brief on or off for this
+    /// conversation; `/brief` (None) shows whether it is on.
+    Brief(Option<bool>),
+    /// `/trace on|off` turns turn tracing on or off for this conversation;
+    /// `/trace` (None) shows whether it is on and where the last trace is.
+    Trace(Option<bool>),
+    /// `/stop` with no turn running (a running turn is stopped by the bus
+    /// before the message is queued).
+    Stop,
+    /// `/agent <name>` switches this conversation to an agent profile
+    /// (`/agent auto` goes back to routing); `/agent` (None) shows the active
+    /// profile.
+    Agent(Option<String>),
+    /// `/settings` (or `/temp`, `/think`) — show the model and sampling
+    /// settings in effect.
+    Settings,
+    /// `/model` (or `/model list`) — show the model in effect and the models
+    /// to switch to.
+    Models,
+    /// `/model <name>`, `/temp <value>`, `/think <budget|off>` or
+    /// `/settings <name> <value>` sets a setting for this conversation; the
+    /// value `reset` (None) clears it.
+    Set(Setting, Option<String>),
+    /// `/settings reset` — clear all of this conversation's settings.
+    ResetSettings,
+}
+
+/// Parse a session management chat command.
+///
+/// Returns `None` for anything that is not exactly one of the commands
+/// (including words that merely start with `/pin`, `/tag` or `/tools`).
+fn parse_session_command(text: &str) -> Option<SessionCommand> {
+    let mut parts = text.split_whitespace();
+    let command = parts.next()?;
+    let args: Vec<String> = parts.map(str::to_string).collect();
+    match command {
+        "/help" if args.is_empty() => Some(SessionCommand::Help),
+        "/pin" if args.is_empty() => Some(SessionCommand::Pin(true)),
+        "/unpin" if args.is_empty() => Some(SessionCommand::Pin(false)),
+        "/tag" => Some(SessionCommand::Tag(args)),
+        "/untag" => Some(SessionCommand::Untag(args)),
+        "/tools" if args.is_empty() => Some(SessionCommand::Tools),
+        "/artifacts" if args.is_empty() => Some(SessionCommand::Artifacts(false)),
+        "/artifacts" if args == ["clear"] => Some(SessionCommand::Artifacts(true)),
+        "/plan" if args.is_empty() => Some(SessionCommand::Plan),
+        "/stats" if args.is_empty() => Some(SessionCommand::Stats),
+        "/audit" if args.is_empty() => Some(SessionCommand::Audit(AUDIT_DEFAULT_ENTRIES)),
+        "/audit" if args.len() == 1 => match args[0].parse::<usize>() {
+            Ok(n) if n > 0 => Some(SessionCommand::Audit(n.min(AUDIT_MAX_ENTRIES))),
+            _ => None,
+        },
+        "/dryrun" if args.is_empty() => Some(SessionCommand::DryRun(None)),
+        "/dryrun" if args == ["on"] => Some(SessionCommand::DryRun(Some(true))),
+        "/dryrun" if args == ["off"] => Some(SessionCommand::DryRun(Some(false))),
+        "/brief" if args.is_empty() => Some(SessionCommand::Brief(None)),
+        "/brief" if args == ["on"] => Some(SessionCommand::Brief(Some(true))),
+        "/brief" if args == ["off"] => Some(SessionCommand::Brief(Some(false))),
+        "/trace" if args.is_empty() => Some(SessionCommand::Trace(None)),
+        "/trace" if args == ["on"] => Some(SessionCommand::Trace(Some(true))),
+        "/trace" if args == ["off"] => Some(SessionCommand::Trace(Some(false))),
+        "/stop" if args.is_empty() => Some(SessionCommand::Stop),
+        "/agent" if args.is_empty() => Some(SessionCommand::Agent(None)),
+        "/agent" if args.len() == 1 => Some(SessionCommand::Agent(args.into_iter().next())),
+        "/settings" | "/temp" | "/think" if args.is_empty() => Some(SessionCommand::Settings),
+        "/settings" if args == ["reset"] => Some(SessionCommand::ResetSettings),
+        "/settings" if args.len() == 2 => {
+            Setting::parse(&args[0]).map(|s| SessionCommand::Set(s, setting_value(&args[1])))
+        }
+        "/model" if args.is_empty() || args == ["list"] => Some(SessionCommand::Models),
+        "/model" if args.len() == 1 => {
+            Some(SessionCommand::Set(Setting::Model, setting_value(&args[0])))
+        }
+        "/temp" if args.len() == 1 => Some(SessionCommand::Set(
+            Setting::Temperature,
+            setting_value(&args[0]),
+        )),
+        "/think" if args.len() == 1 => Some(SessionCommand::Set(
+            Setting::ThinkingBudget,
+            setting_value(&args[0]),
+        )),
+        _ => None,
+    }
+}
+
+/// A setting command's value; `reset` clears the setting.
+fn setting_value(arg: &str) -> Option<String> {
+    (arg != "reset").then(|| arg.to_string())
+}
+
+/// The agent profile answering in `session_key`'s conversation: the one
+/// `chosen` with `/agent`, else the route for the key's namespace (the part
+/// before the first `:`), else `agents.default_profile`. Names without a
+/// configured profile are passed over.
+fn route_agent_profile<'a>(
+    agents: &'a AgentConfig,
+    session_key: &str,
+    chosen: Option<&str>,
+) -> Option<(&'a str, &'a AgentProfile)> {
+    let namespace = session_key.split(':').next().unwrap_or(session_key);
+    [
+        chosen,
+        agents.routes.get(namespace).map(String::as_str),
+        agents.default_profile.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(|name| agents.profiles.get_key_value(name))
+    .map(|(name, profile)| (name.as_str(), profile))
+}
+
+/// `msg` naming `model` as its `model_override`, so the provider serving
+/// that model answers it, or `None` when the message already names it or
+/// names none and `model` is the default.
+fn with_model_override(
+    msg: &InboundMessage,
+    model: Option<&str>,
+    default_model: &str,
+) -> Option<InboundMessage> {
+    let model = model?;
+    let current = msg
+        .metadata
+        .get(MODEL_OVERRIDE_KEY)
+        .map(String::as_str)
+        .filter(|m| !m.is_empty());
+    if current.unwrap_or(default_model) == model {
+        return None;
+    }
+    Some(msg.clone().with_metadata(MODEL_OVERRIDE_KEY, model))
+}
+
+/// Tokens and estimated cost of one provider call to `model`.
+fn usage_cost(
+    model: &str,
+    usage: Option<&Usage>,
+    pricing: &HashMap<String, ModelPricing>,
+) -> UsageCost {
+    let Some(usage) = usage else {
+        return UsageCost::default();
+    };
+    let uncached_cost =
+        estimate_cost(model, usage.prompt_tokens, usage.completion_tokens, pricing).unwrap_or(0.0);
+    let cost_usd = estimate_cost_with_cache(
+        model,
+        usage.prompt_tokens,
+        usage.completion_tokens,
+        usage.cache_read_tokens,
+        usage.cache_write_tokens,
+        pricing,
+    )
+    .unwrap_or(0.0);
+    // Thinking is billed as output.
+    let thinking_cost_usd = estimate_cost(model, 0, usage.thinking_tokens, pricing).unwrap_or(0.0);
+    UsageCost {
+        input_tokens: usage.prompt_tokens as u64,
+        output_tokens: usage.completion_tokens as u64,
+        cost_usd,
+        cache_read_tokens: usage.cache_read_tokens as u64,
+        cache_write_tokens: usage.cache_write_tokens as u64,
+        cache_savings_usd: uncached_cost - cost_usd,
+        thinking_tokens: usage.thinking_tokens as u64,
+        thinking_cost_usd,
+    }
+}
+
+/// Attach a turn's usage to its final `reply` and add it to the session's
+/// and the month's totals.
+fn record_turn_usage(
+    spend: &QuotaStore,
+    session: &mut crate::session::Session,
+    reply: &mut Message,
+    usage: UsageCost,
+) {
+    if usage.is_empty() {
+        return;
+    }
+    reply.usage = Some(usage);
+    session.record_usage(&usage);
+    spend.record(
+        SPEND_KEY,
+        &QuotaPeriod::Monthly,
+        usage.cost_usd,
+        usage.input_tokens + usage.output_tokens,
+    );
+}
+
+/// Send `sink` the analytics event of the turn that just ended in
+/// `session`: a message of `request_length` characters answered with `reply`.
+fn record_turn_analytics(
+    sink: &dyn AnalyticsSink,
+    session: &crate::session::Session,
+    request_length: usize,
+    reply: &str,
+    response_time: std::time::Duration,
+) {
+    sink.record(AnalyticsEvent::for_turn(
+        session,
+        request_length,
+        reply.chars().count(),
+        response_time,
+    ));
+}
+
+/// The line `cost.show_cost` appends to a reply.
+fn cost_note(turn: &UsageCost, session_total: f64) -> String {
+    format!(
+        "\n\n(cost: ${:.4} this turn, ${:.4} in this conversation)",
+        turn.cost_usd, session_total
+    )
+}
+
+/// Run `fut` and report how long it took.
+async fn timed<F: Future>(fut: F) -> (F::Output, std::time::Duration) {
+    let started = std::time::Instant::now();
+    let output = fut.await;
+    (output, started.elapsed())
+}
+
+/// Timeout for one call to `name`: its `tool_timeouts` override, or the
+/// default.
+fn tool_call_timeout(
+    default: std::time::Duration,
+    overrides: &HashMap<String, u64>,
+    name: &str,
+) -> std::time::Duration {
+    overrides
+        .get(name)
+        .map(|secs| std::time::Duration::from_secs((*secs).max(1)))
+        .unwrap_or(default)
+}
+
+/// Shorten `timeout` to what is left of the turn's tool budget.
+///
+/// Returns the timeout to use and whether the budget shortened it, or `None`
+/// when the budget is already spent and the call should not start.
+fn limit_to_turn_budget(
+    timeout: std::time::Duration,
+    deadline: Option<std::time::Instant>,
+) -> Option<(std::time::Duration, bool)> {
+    let Some(deadline) = deadline else {
+        return Some((timeout, false));
+    };
+    let left = deadline.saturating_duration_since(std::time::Instant::now());
+    if left.is_zero() {
+        None
+    } else if left < timeout {
+        Some((left, true))
+    } else {
+        Some((timeout, false))
+    }
+}
+
+/// Tool result for a call skipped because the turn's tool budget is spent.
+fn turn_budget_spent_message(name: &str, budget_secs: u64) -> String {
+    format!(
+        "Tool '{}' was not run: the {}s tool time budget for this turn is used up. \
+         Answer with the information you already have.",
+        name, budget_secs
+    )
+}
+
+/// What the tool calls of one assistant message share, built once per batch
+/// by both turn paths and passed to [`execute_tool_call`] for each call.
+struct ToolBatch<'a> {
+    tools: &'a Arc<RwLock<ToolRegistry>>,
+    ctx: ToolContext,
+    /// The inbound message: origin of approval requests and source of the
+    /// routing metadata copied onto `for_user` replies.
+    msg: &'a InboundMessage,
+    bus: &'a Arc<MessageBus>,
+    defaults: &'a AgentDefaults,
+    turn_started: std::time::Instant,
+    access: &'a ToolAccess,
+    expired: &'a AtomicBool,
+    feedback: &'a FeedbackSink,
+    usage_metrics: Option<&'a UsageMetrics>,
+    metrics: &'a MetricsCollector,
+    approval_gate: &'a ApprovalGate,
+    approval_handler: Option<ApprovalHandler>,
+    approval_policy: Option<Arc<dyn ApprovalPolicy>>,
+    hooks: crate::hooks::HookEngine,
+    safety: Option<&'a SafetyLayer>,
+    taint: Option<&'a std::sync::RwLock<crate::safety::taint::TaintEngine>>,
+    #[cfg(feature = "panel")]
+    event_bus: Option<&'a crate::api::events::EventBus>,
+    agent_mode: crate::security::AgentMode,
+    trusted_local_session: bool,
+    /// Size limit for each sanitized result.
+    result_budget: usize,
+}
+
+impl ToolBatch<'_> {
+    /// Timeout for one call to `name`, before the turn's tool budget.
+    fn call_timeout(&self, name: &str) -> std::time::Duration {
+        let secs = if self.defaults.tool_timeout_secs > 0 {
+            self.defaults.tool_timeout_secs
+        } else {
+            self.defaults.agent_timeout_secs
+        };
+        tool_call_timeout(
+            std::time::Duration::from_secs(secs.max(1)),
+            &self.defaults.tool_timeouts,
+            name,
+        )
+    }
+
+    /// When the turn's tool budget runs out, if it has one.
+    fn turn_deadline(&self) -> Option<std::time::Instant> {
+        let secs = self.defaults.turn_tool_budget_secs;
+        (secs > 0).then(|| self.turn_started + std::time::Duration::from_secs(secs))
+    }
+}
+
+/// Run one tool call of `batch` through the access, hook, agent mode,
+/// approval and tool budget checks, then execute it within its timeout.
+///
+/// Returns the call id, the sanitized result and whether the tool asked to
+/// pause for user input.
+async fn execute_tool_call(
+    batch: &ToolBatch<'_>,
+    tool_call: &LLMToolCall,
+    repeat: Option<u32>,
+) -> (String, String, bool) {
+    let name = tool_call.name.as_str();
+    let id = tool_call.id.clone();
+    let raw_args = &tool_call.arguments;
+    let ctx = &batch.ctx;
+    let args: serde_json::Value = match serde_json::from_str(raw_args) {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::warn!(tool = %name, error = %e, "Invalid JSON in tool arguments");
+            serde_json::json!({"_parse_error": format!("Invalid arguments JSON: {}", e)})
+        }
+    };
+
+    if ctx.is_cancelled() {
+        return (
+            id,
+            turn_stopped_message(name, batch.expired.load(Ordering::SeqCst)),
+            false,
+        );
+    }
+
+    if let Some(count) = repeat {
+        warn!(tool = %name, count, "Repeated identical tool call answered without running it");
+        batch.metrics.record_repeated_call(name);
+        return (id, repeated_call_message(name, count), false);
+    }
+
+    if !batch.access.allows(name) {
+        info!(tool = %name, "Tool not available in this context");
+        return (
+            id,
+            format!("Tool '{}' is not available in this context", name),
+            false,
+        );
+    }
+
+    // Check hooks before executing
+    let channel_name = ctx.channel.as_deref().unwrap_or("cli");
+    let chat_id = ctx.chat_id.as_deref().unwrap_or(channel_name);
+    if let crate::hooks::HookResult::Block(msg) =
+        batch.hooks.before_tool(name, &args, channel_name, chat_id)
+    {
+        return (
+            id,
+            format!("Tool '{}' blocked by hook: {}", name, msg),
+            false,
+        );
+    }
+
+    // Agent mode enforcement (before approval gate).
+    // RequiresApproval: blocks the tool unless ApprovalGate is
+    // already configured to gate this tool name. In practice, this
+    // means Assistant mode blocks Shell/Hardware/Destructive tools
+    // unless the operator has explicitly listed them in
+    // `approval.require_approval_for`. This is "fail-closed" by design.
+    {
+        let agent_mode = batch.agent_mode;
+        let mode_policy = crate::security::ModePolicy::new(agent_mode);
+        let tools_guard = batch.tools.read().await;
+        if let Some(tool) = tools_guard.get(name) {
+            let tool_category = tool.category();
+            match mode_policy.check(tool_category) {
+                crate::security::CategoryPermission::Blocked => {
+                    info!(tool = %name, mode = %agent_mode, category = ?tool_category, "Tool blocked by agent mode");
+                    return (
+                        id,
+                        format!(
+                            "Tool '{}' is blocked in {} mode (category: {})",
+                            name, agent_mode, tool_category
+                        ),
+                        false,
+                    );
+                }
+                crate::security::CategoryPermission::RequiresApproval => {
+                    if batch.trusted_local_session {
+                        info!(tool = %name, mode = %agent_mode, category = ?tool_category, "Trusted local session bypassed approval-gated tool");
+                    } else if !batch.approval_gate.requires_approval(name) {
+                        info!(tool = %name, mode = %agent_mode, category = ?tool_category, "Tool requires approval per agent mode");
+                        return (
+                            id,
+                            format!(
+                                "Tool '{}' requires approval in {} mode (category: {}). Not executed.",
+                                name, agent_mode, tool_category
+                            ),
+                            false,
+                        );
+                    }
+                    // Fall through to approval gate — it will prompt for approval
+                }
+                crate::security::CategoryPermission::Allowed => {}
+            }
+        }
+    }
+
+    // Check approval gate before executing
+    if !batch.trusted_local_session {
+        if let Some(message) = resolve_tool_approval(
+            batch.approval_gate,
+            batch.approval_handler.as_ref(),
+            batch.approval_policy.as_ref(),
+            batch.msg,
+            name,
+            &args,
+        )
+        .await
+        {
+            info!(tool = %name, "Tool requires approval, blocking execution");
+            return (id, message, false);
+        }
+    }
+
+    // Stop starting tools once the turn's tool budget is spent,
+    // and cut a call short when the budget ends first.
+    let turn_budget_secs = batch.defaults.turn_tool_budget_secs;
+    let Some((call_timeout, cut_by_budget)) =
+        limit_to_turn_budget(batch.call_timeout(name), batch.turn_deadline())
+    else {
+        info!(tool = %name, budget_secs = turn_budget_secs, "Turn tool budget spent, skipping tool call");
+        return (id, turn_budget_spent_message(name, turn_budget_secs), false);
+    };
+
+    // Send tool starting feedback
+    batch.feedback.send(ToolFeedback {
+        tool_name: name.to_string(),
+        phase: ToolFeedbackPhase::Starting,
+        args_json: Some(raw_args.clone()),
+    });
+    #[cfg(feature = "panel")]
+    if let Some(bus) = batch.event_bus {
+        bus.send(crate::api::events::PanelEvent::ToolStarted {
+            tool: name.to_string(),
+        });
+    }
+    let (progress, progress_relay) =
+        spawn_progress_relay(Arc::clone(batch.bus), ctx, &batch.msg.metadata, name);
+    let exec_ctx = ctx.clone().with_progress(progress);
+    let tool_start = std::time::Instant::now();
+    let execution = std::panic::AssertUnwindSafe(async {
+        let tools_guard = batch.tools.read().await;
+        crate::kernel::execute_tool(
+            &tools_guard,
+            name,
+            args,
+            &exec_ctx,
+            batch.safety,
+            batch.metrics,
+            batch.taint,
+        )
+        .await
+    })
+    .catch_unwind();
+    let outcome = tokio::select! {
+        biased;
+        _ = ctx.cancellation.cancelled() => None,
+        outcome = tokio::time::timeout(call_timeout, execution) => Some(outcome),
+    };
+    let (result, success, tool_output) = match outcome {
+        None | Some(Ok(Ok(Err(ZeptoError::Cancelled(_))))) => {
+            info!(tool = %name, "Tool cancelled because the turn was stopped");
+            (
+                turn_stopped_message(name, batch.expired.load(Ordering::SeqCst)),
+                false,
+                None,
+            )
+        }
+        Some(Ok(Ok(Ok(output)))) => {
+            let success = !output.is_error;
+            let for_llm = output.for_llm.clone();
+            (for_llm, success, Some(output))
+        }
+        Some(Ok(Ok(Err(e)))) => (format!("Error: {}", e), false, None),
+        Some(Ok(Err(_panic))) => {
+            error!(tool = %name, "Tool panicked during execution");
+            (
+                format!("Error: Tool '{}' panicked during execution", name),
+                false,
+                None,
+            )
+        }
+        Some(Err(_)) => {
+            error!(tool = %name, timeout_secs = call_timeout.as_secs_f64(), cut_by_budget, "Tool execution timed out");
+            let message = if cut_by_budget {
+                format!(
+                    "Error: Tool '{}' was cancelled after {:.1}s because the {}s tool time budget for this turn ran out",
+                    name,
+                    call_timeout.as_secs_f64(),
+                    turn_budget_secs
+                )
+            } else {
+                format!(
+                    "Error: Tool '{}' timed out after {}s",
+                    name,
+                    call_timeout.as_secs()
+                )
+            };
+            (message, false, None)
+        }
+    };
+
+    progress_relay.abort();
+    let pause = tool_output.as_ref().is_some_and(|o| o.pause_for_input);
+    let elapsed = tool_start.elapsed();
+    let latency_ms = elapsed.as_millis() as u64;
+    // Send to user if tool opted in
+    if let Some(user_msg) = tool_output.as_ref().and_then(|o| o.for_user.as_ref()) {
+        let mut outbound = crate::bus::OutboundMessage::new(
+            ctx.channel.as_deref().unwrap_or(""),
+            ctx.chat_id.as_deref().unwrap_or(""),
+            user_msg,
+        );
+        // Propagate routing metadata (e.g. telegram_thread_id, telegram_message_id)
+        for key in ["telegram_thread_id", "telegram_message_id"] {
+            if let Some(value) = batch.msg.metadata.get(key) {
+                outbound.metadata.insert(key.to_string(), value.clone());
+            }
+        }
+        // Keep typing indicator alive — agent is still working
+        outbound
+            .metadata
+            .insert("keep_typing".to_string(), "true".to_string());
+        let _ = batch.bus.publish_outbound(outbound).await;
+    }
+    if success {
+        debug!(tool = %name, latency_ms = latency_ms, "Tool executed successfully");
+        batch
+            .hooks
+            .after_tool(name, &result, elapsed, channel_name, chat_id);
+        batch.feedback.send(ToolFeedback {
+            tool_name: name.to_string(),
+            phase: ToolFeedbackPhase::Done {
+                elapsed_ms: latency_ms,
+            },
+            args_json: Some(raw_args.clone()),
+        });
+        #[cfg(feature = "panel")]
+        if let Some(bus) = batch.event_bus {
+            bus.send(crate::api::events::PanelEvent::ToolDone {
+                tool: name.to_string(),
+                duration_ms: latency_ms,
+            });
+        }
+    } else {
+        error!(tool = %name, latency_ms = latency_ms, error = %result, "Tool execution failed");
+        batch.hooks.on_error(name, &result, channel_name, chat_id);
+        if let Some(metrics) = batch.usage_metrics {
+            metrics.record_error();
+        }
+        batch.feedback.send(ToolFeedback {
+            tool_name: name.to_string(),
+            phase: ToolFeedbackPhase::Failed {
+                elapsed_ms: latency_ms,
+                error: result.clone(),
+            },
+            args_json: Some(raw_args.clone()),
+        });
+        #[cfg(feature = "panel")]
+        if let Some(bus) = batch.event_bus {
+            bus.send(crate::api::events::PanelEvent::ToolFailed {
+                tool: name.to_string(),
+                error: result.clone(),
+            });
+        }
+    }
+
+    // Sanitize the result with dynamic budget
+    let sanitized = crate::utils::sanitize::sanitize_tool_result(&result, batch.result_budget);
+
+    (id, sanitized, pause)
+}
+
+/// Instruction added to the session when a turn uses up its tool
+/// iterations, before the final call without tools.
+fn tool_iteration_limit_prompt(limit: u32) -> String {
+    format!(
+        "[Tool limit] You have used all {} tool iterations for this request. \
+         Do not call any more tools. Summarize what you found so far and give \
+         the best answer you can with it, saying what is still unfinished.",
+        limit
+    )
+}
+
+/// Correction sent back once when a reply misses the requested JSON format.
+fn format_correction_prompt(problem: &str) -> String {
+    format!(
+        "[Format] Your previous reply is not valid for the requested format: {}. \
+         Reply again with only the corrected JSON, no other text.",
+        problem
+    )
+}
+
+/// Note appended to the reply of a turn stopped by the tool iteration limit.
+fn tool_iteration_limit_note(limit: u32) -> String {
+    format!(
+        "\n\n(Stopped after reaching the limit of {} tool iterations.)",
+        limit
+    )
+}
+
+/// Final reply of a turn the user stopped.
+const TURN_STOPPED_REPLY: &str = "Stopped.";
+
+/// Appended to the text a turn produced before its time limit cut it off.
+const TIME_LIMIT_NOTE: &str = "(stopped early due to time limit)";
+
+/// Tool result for a call cut short or skipped because the turn was stopped,
+/// by the user or, when `expired`, by its time limit.
+fn turn_stopped_message(name: &str, expired: bool) -> String {
+    let cause = if expired {
+        "this turn ran out of time"
+    } else {
+        "the user stopped this turn"
+    };
+    format!("Error: Tool '{}' was cancelled because {}.", name, cause)
+}
+
+/// Final reply of a turn cut off by its time `limit`: the text it produced
+/// so far followed by [`TIME_LIMIT_NOTE`], or an apology if there is none.
+fn time_limit_reply(produced: &[String], limit: std::time::Duration) -> String {
+    let text: Vec<&str> = produced
+        .iter()
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .collect();
+    if text.is_empty() {
+        format!(
+            "Sorry, I couldn't finish this within the {}s time limit. \
+             Try a simpler request, or split it into smaller steps.",
+            limit.as_secs()
+        )
+    } else {
+        format!("{}\n\n{}", text.join("\n\n"), TIME_LIMIT_NOTE)
+    }
+}
+
+/// Tool result for a call that repeats an identical earlier call of the
+/// turn, given instead of running it again.
+fn repeated_call_message(name: &str, count: u32) -> String {
+    format!(
+        "Error: Tool '{}' was not run: this is call number {} with exactly the same \
+         arguments in this turn, and it would return the same result. Change your \
+         approach or answer with what you have.",
+        name, count
+    )
+}
+
+/// Run a provider call for a turn, dropping it (and so aborting the request)
+/// when the turn is stopped. A stopped call answers [`TURN_STOPPED_REPLY`]
+/// without tool calls, which ends the turn.
+async fn unless_stopped(
+    token: &CancellationToken,
+    call: impl Future<Output = Result<LLMResponse>>,
+) -> Result<LLMResponse> {
+    tokio::select! {
+        biased;
+        _ = token.cancelled() => Ok(LLMResponse::text(TURN_STOPPED_REPLY)),
+        result = call => result,
+    }
+}
+
+/// A turn registered with the bus so a "stop" message can cancel it;
+/// unregistered on drop. A turn with a time limit is also cancelled when
+/// the limit is reached.
+struct ActiveTurn<'a> {
+    bus: &'a MessageBus,
+    session_key: &'a str,
+    token: CancellationToken,
+    /// The turn's time limit, if it has one.
+    limit: Option<std::time::Duration>,
+    /// Set when the time limit, not the user, cancelled the turn.
+    expired: Arc<AtomicBool>,
+    /// Cancels the turn when its time limit is reached.
+    timer: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl<'a> ActiveTurn<'a> {
+    fn begin(
+        bus: &'a MessageBus,
+        session_key: &'a str,
+        limit: Option<std::time::Duration>,
+    ) -> Self {
+        let token = bus.begin_turn(session_key);
+        let expired = Arc::new(AtomicBool::new(false));
+        let timer = limit.map(|limit| {
+            let token = token.clone();
+            let expired = Arc::clone(&expired);
+            tokio::spawn(async move {
+                tokio::select! {
+                    _ = token.cancelled() => {}
+                    _ = tokio::time::sleep(limit) => {
+                        expired.store(true, Ordering::SeqCst);
+                        token.cancel();
+                    }
+                }
+            })
+        });
+        Self {
+            bus,
+            session_key,
+            token,
+            limit,
+            expired,
+            timer,
+        }
+    }
+
+    /// Whether the turn's time limit cancelled it.
+    fn expired(&self) -> bool {
+        self.expired.load(Ordering::SeqCst)
+    }
+
+    /// Settle a cancelled turn: answer tool calls left without a result and,
+    /// if the time limit cut the reply off, replace the stopped reply with
+    /// `produced` and the `unfinished` streamed text (see [`time_limit_reply`]).
+    fn close(
+        &self,
+        session: &mut crate::session::Session,
+        response: &mut LLMResponse,
+        mut produced: Vec<String>,
+        unfinished: Option<&str>,
+    ) {
+        if !self.token.is_cancelled() {
+            return;
+        }
+        let expired = self.expired();
+        session.answer_pending_tool_calls(|call| turn_stopped_message(&call.name, expired));
+        // A call the limit cut off leaves the stopped reply in place of its
+        // own; one that finished just before the limit is kept.
+        if expired && response.content == TURN_STOPPED_REPLY {
+            let limit = self.limit.unwrap_or_default();
+            warn!(
+                session = %self.session_key,
+                limit_secs = limit.as_secs(),
+                "Turn stopped at its time limit"
+            );
+            produced.extend(unfinished.map(str::to_string));
+            response.content = time_limit_reply(&produced, limit);
+        }
+    }
+}
+
+impl Drop for ActiveTurn<'_> {
+    fn drop(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            timer.abort();
+        }
+        self.bus.end_turn(self.session_key);
+    }
+}
+
+/// Time limit for a turn in `channel`: its entry in
+/// `agents.defaults.turn_timeouts`, else `turn_timeout_secs`. 0 = none.
+fn turn_time_limit(defaults: &AgentDefaults, channel: &str) -> Option<std::time::Duration> {
+    let secs = defaults
+        .turn_timeouts
+        .get(channel)
+        .copied()
+        .unwrap_or(defaults.turn_timeout_secs);
+    (secs > 0).then(|| std::time::Duration::from_secs(secs))
+}
+
+/// Queue mode for messages arriving in `channel` during a turn: its entry in
+/// `agents.defaults.message_queue_modes`, else `message_queue_mode`.
+fn queue_mode(defaults: &AgentDefaults, channel: &str) -> MessageQueueMode {
+    defaults
+        .message_queue_modes
+        .get(channel)
+        .cloned()
+        .unwrap_or_else(|| defaults.message_queue_mode.clone())
+}
+
+/// Render the `/tools` reply: active tools, then any disabled here.
+fn format_tool_availability(
+    active: &[&str],
+    disabled: &[&str],
+    quotas: &[(&str, Quota)],
+) -> String {
+    let mut out = if active.is_empty() {
+        "No tools are available in this conversation.".to_string()
+    } else {
+        format!("Active tools ({}): {}", active.len(), active.join(", "))
+    };
+    if !disabled.is_empty() {
+        out.push_str(&format!("\nDisabled here: {}", disabled.join(", ")));
+    }
+    if !quotas.is_empty() {
+        let quotas: Vec<String> = quotas
+            .iter()
+            .map(|(name, q)| format!("{} {} of {} left", name, q.remaining, q.limit))
+            .collect();
+        out.push_str(&format!("\nRate limits: {}", quotas.join(", ")));
+    }
+    out
+}
+
+/// Render a session's tags for a chat reply.
+fn format_tags(tags: &std::collections::BTreeSet<String>) -> String {
+    let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+    format!("Tags: {}", tags.join(", "))
+}
+
+/// Render the configured agent profiles and the `active` one for a chat
+/// reply.
+fn format_agent_profiles(agents: &AgentConfig, active: Option<&str>) -> String {
+    if agents.profiles.is_empty() {
+        return "No agent profiles are configured.".to_string();
+    }
+    let mut names: Vec<&str> = agents.profiles.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    format!(
+        "Agent: {}\nAvailable: {}\nUse /agent <name> to switch, or /agent auto to follow routing.",
+        active.unwrap_or("none"),
+        names.join(", ")
+    )
+}
+
 /// Propagate channel-specific routing metadata (e.g. `telegram_thread_id`)
 /// from an inbound message to an outbound message so that the response is
 /// delivered to the correct forum topic / thread.
@@ -244,6 +1347,25 @@ fn propagate_routing_metadata(outbound: &mut OutboundMessage, inbound: &InboundM
     }
 }
 
+/// The tracing span for one inbound message's turn.
+fn request_span(msg: &InboundMessage) -> tracing::Span {
+    let tenant_id = msg
+        .metadata
+        .get("tenant_id")
+        .filter(|v| !v.is_empty())
+        .map(String::as_str)
+        .unwrap_or(&msg.chat_id);
+    info_span!(
+        "request",
+        request_id = %uuid::Uuid::new_v4(),
+        tenant_id = %tenant_id,
+        chat_id = %msg.chat_id,
+        session_id = %msg.session_key,
+        channel = %msg.channel,
+        sender = %msg.sender_id,
+    )
+}
+
 /// Sync trimmed tool-result messages from the resolved (preflight-mutated) buffer
 /// back into the session so that the trims persist across iterations and saves.
 /// Matches on `tool_call_id` since resolved messages include a system-prompt prefix.
@@ -280,8 +1402,10 @@ fn sync_trimmed_tool_results(session_messages: &mut [Message], resolved_messages
 /// invalid images are skipped rather than aborting.
 ///
 /// When a `MediaStore` is provided the raw bytes are written to disk first and
-/// the resulting relative path is stored as `ImageSource::FilePath`; otherwise
-/// (or on a store-write error) the image is inlined as `ImageSource::Base64`.
+/// the resulting relative path is stored as `ImageSource::FilePath` and noted
+/// in the message text so tools such as `analyze_image` can refer to it;
+/// otherwise (or on a store-write error) the image is inlined as
+/// `ImageSource::Base64`.
 async fn inbound_to_message(
     msg: &InboundMessage,
     media_store: Option<&crate::session::media::MediaStore>,
@@ -353,6 +1477,7 @@ async fn inbound_to_message(
     }
 
     let mut image_parts: Vec<ContentPart> = Vec::new();
+    let mut stored_paths: Vec<String> = Vec::new();
     for attachment in image_media {
         let data = attachment.data.as_ref().unwrap();
         let mime = attachment.mime_type.as_deref().unwrap_or("image/jpeg");
@@ -364,7 +1489,10 @@ async fn inbound_to_message(
 
         let source = if let Some(store) = media_store {
             match store.save(data, mime).await {
-                Ok(path) => ImageSource::FilePath { path },
+                Ok(path) => {
+                    stored_paths.push(path.clone());
+                    ImageSource::FilePath { path }
+                }
                 Err(_) => ImageSource::Base64 {
                     data: base64::engine::general_purpose::STANDARD.encode(data),
                 },
@@ -381,6 +1509,10 @@ async fn inbound_to_message(
         });
     }
 
+    for path in stored_paths {
+        content.push_str(&format!("\n\n[Image attachment: {}]", path));
+    }
+
     if image_parts.is_empty() {
         crate::session::Message::user(&content)
     } else {
@@ -535,6 +1667,9 @@ pub struct AgentLoop {
     metrics_collector: Arc<MetricsCollector>,
     /// Shutdown signal sender
     shutdown_tx: watch::Sender<bool>,
+    /// `true` while `start()` is not running, so `shutdown()` can wait for it
+    /// to return.
+    stopped_tx: watch::Sender<bool>,
     /// Per-session locks to serialize concurrent messages for the same session
     session_locks: Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>>,
     /// Pending messages for sessions with active runs (for queue modes).
@@ -551,6 +1686,9 @@ pub struct AgentLoop {
     approval_gate: Arc<ApprovalGate>,
     /// Optional handler used by interactive frontends to resolve approval prompts inline.
     approval_handler: Arc<RwLock<Option<ApprovalHandler>>>,
+    /// Policy asked when no inline handler is installed, e.g. a prompt in the
+    /// originating chat (`approval.ask_in_channel`).
+    approval_policy: Arc<RwLock<Option<Arc<dyn ApprovalPolicy>>>>,
     /// Agent mode for category-based tool enforcement.
     agent_mode: crate::security::AgentMode,
     /// Optional safety layer for tool output sanitization.
@@ -561,6 +1699,8 @@ pub struct AgentLoop {
     tool_feedback_tx: Arc<RwLock<Option<tokio::sync::mpsc::UnboundedSender<ToolFeedback>>>>,
     /// Optional LLM response cache (SHA-256 keyed, TTL + LRU).
     cache: Option<Arc<std::sync::Mutex<ResponseCache>>>,
+    /// Sessions whose latest reply came from `cache`, until it is sent.
+    cached_replies: std::sync::Mutex<HashSet<String>>,
     /// Optional pairing manager for device token validation.
     /// Present only when `config.pairing.enabled` is true.
     pairing: Option<Arc<std::sync::Mutex<crate::security::PairingManager>>>,
@@ -573,9 +1713,63 @@ pub struct AgentLoop {
     event_bus: Option<crate::api::events::EventBus>,
     /// MCP clients to shut down when the agent stops (prevents zombie child processes).
     mcp_clients: Arc<tokio::sync::RwLock<Vec<Arc<crate::tools::mcp::client::McpClient>>>>,
+    /// Background processes started through the `process` tool.
+    processes: Arc<ProcessRegistry>,
+    /// Optional sink receiving one analytics event per completed turn.
+    analytics: Option<Arc<dyn AnalyticsSink>>,
+    /// Session-scoped artifacts tools use to pass large data by reference.
+    artifacts: Arc<ArtifactStore>,
+    /// Spend of all conversations in the current month, for
+    /// `cost.monthly_budget_usd`.
+    spend: Arc<QuotaStore>,
+    /// Hooks run before and after every turn, in registration order.
+    hooks: Arc<RwLock<Vec<Arc<dyn AgentHook>>>>,
+    /// Chat commands answered without the model: the built-in ones, then
+    /// those from `register_command`.
+    commands: Arc<RwLock<Vec<Command>>>,
+    /// Traces of turns in sessions with tracing on.
+    traces: TraceRecorder,
+    /// Slots for turns under `agents.defaults.concurrency`.
+    turns: Arc<TurnScheduler>,
 }
 
 impl AgentLoop {
+    /// Load the monthly spend counter kept next to the session files, or
+    /// an in-memory one when sessions aren't persisted.
+    fn build_spend_store(session_manager: &SessionManager) -> QuotaStore {
+        match session_manager.sessions_dir() {
+            Some(dir) => QuotaStore::load_from_dir(dir.join(SPEND_DIR)),
+            None => QuotaStore::in_memory(),
+        }
+    }
+
+    /// Attach the configured workspace's brief to `builder`, unless it has
+    /// one already or there is no workspace.
+    fn attach_workspace_brief(config: &Config, builder: ContextBuilder) -> ContextBuilder {
+        if builder.has_workspace_brief() {
+            return builder;
+        }
+        match WorkspaceBrief::from_config(config) {
+            Some(brief) => builder.with_workspace_brief(brief),
+            None => builder,
+        }
+    }
+
+    /// Attach a retriever over `agents.defaults.retrieval.dir` to `builder`,
+    /// unless it has one already or no directory is configured.
+    fn attach_retriever(config: &Config, builder: ContextBuilder) -> ContextBuilder {
+        if builder.has_retriever() {
+            return builder;
+        }
+        let retrieval = &config.agents.defaults.retrieval;
+        match DirectoryRetriever::from_config(retrieval) {
+            Some(retriever) => builder
+                .with_retriever(Arc::new(retriever))
+                .with_retrieval_limits(retrieval.top_k, retrieval.max_tokens),
+            None => builder,
+        }
+    }
+
     /// Build an optional cache from config.
     fn build_cache(config: &Config) -> Option<Arc<std::sync::Mutex<ResponseCache>>> {
         if config.cache.enabled {
@@ -627,9 +1821,15 @@ impl AgentLoop {
     /// ```
     pub fn new(config: Config, session_manager: SessionManager, bus: Arc<MessageBus>) -> Self {
         let (shutdown_tx, _) = watch::channel(false);
+        let processes = Arc::new(ProcessRegistry::new(
+            config.tools.process.max_processes,
+            config.tools.process.log_tail_bytes,
+        ));
         let token_budget = Arc::new(TokenBudget::new(config.agents.defaults.token_budget));
         let tool_call_limit = ToolCallLimitTracker::new(config.agents.defaults.max_tool_calls);
         let approval_gate = Arc::new(ApprovalGate::new(config.approval.clone()));
+        let approval_policy = Self::build_approval_policy(&config, &bus);
+        Self::install_sender_check(&config, &bus);
         let agent_mode = config.agent_mode.resolve();
         let safety_layer = if config.safety.enabled {
             Some(Arc::new(SafetyLayer::new(config.safety.clone())))
@@ -644,18 +1844,28 @@ impl AgentLoop {
         let cache = Self::build_cache(&config);
         let pairing = Self::build_pairing(&config);
         let streaming_default = config.agents.defaults.streaming;
+        let tools = ToolRegistry::new()
+            .with_arg_validation(config.tools.argument_validation)
+            .with_rate_limiter(ToolRateLimiter::from_config(&config.tools.rate_limits));
+        let artifacts = Arc::new(ArtifactStore::new(config.workspace_path()));
+        let spend = Arc::new(Self::build_spend_store(&session_manager));
+        let traces = TraceRecorder::new(&config.agents.defaults.trace);
+        let turns = TurnScheduler::new(config.agents.defaults.concurrency.clone());
+        let context_builder = Self::attach_workspace_brief(&config, ContextBuilder::new());
+        let context_builder = Self::attach_retriever(&config, context_builder);
         Self {
             config,
             session_manager: Arc::new(session_manager),
             bus,
             provider: Arc::new(RwLock::new(None)),
             provider_registry: Arc::new(RwLock::new(HashMap::new())),
-            tools: Arc::new(RwLock::new(ToolRegistry::new())),
+            tools: Arc::new(RwLock::new(tools)),
             running: AtomicBool::new(false),
-            context_builder: ContextBuilder::new(),
+            context_builder,
             usage_metrics: Arc::new(RwLock::new(None)),
             metrics_collector: Arc::new(MetricsCollector::new()),
             shutdown_tx,
+            stopped_tx: watch::channel(true).0,
             session_locks: Arc::new(Mutex::new(HashMap::new())),
             pending_messages: Arc::new(Mutex::new(HashMap::new())),
             streaming: AtomicBool::new(streaming_default),
@@ -664,17 +1874,27 @@ impl AgentLoop {
             tool_call_limit,
             approval_gate,
             approval_handler: Arc::new(RwLock::new(None)),
+            approval_policy: Arc::new(RwLock::new(approval_policy)),
             agent_mode,
             safety_layer,
             context_monitor,
             tool_feedback_tx: Arc::new(RwLock::new(None)),
             cache,
+            cached_replies: std::sync::Mutex::new(HashSet::new()),
             pairing,
             ltm: None,
             taint: None,
             #[cfg(feature = "panel")]
             event_bus: None,
             mcp_clients: Arc::new(tokio::sync::RwLock::new(Vec::new())),
+            processes,
+            analytics: None,
+            artifacts,
+            spend,
+            hooks: Arc::new(RwLock::new(Vec::new())),
+            commands: Arc::new(RwLock::new(default_commands())),
+            traces,
+            turns,
         }
     }
 
@@ -692,9 +1912,15 @@ impl AgentLoop {
         context_builder: ContextBuilder,
     ) -> Self {
         let (shutdown_tx, _) = watch::channel(false);
+        let processes = Arc::new(ProcessRegistry::new(
+            config.tools.process.max_processes,
+            config.tools.process.log_tail_bytes,
+        ));
         let token_budget = Arc::new(TokenBudget::new(config.agents.defaults.token_budget));
         let tool_call_limit = ToolCallLimitTracker::new(config.agents.defaults.max_tool_calls);
         let approval_gate = Arc::new(ApprovalGate::new(config.approval.clone()));
+        let approval_policy = Self::build_approval_policy(&config, &bus);
+        Self::install_sender_check(&config, &bus);
         let agent_mode = config.agent_mode.resolve();
         let safety_layer = if config.safety.enabled {
             Some(Arc::new(SafetyLayer::new(config.safety.clone())))
@@ -709,18 +1935,28 @@ impl AgentLoop {
         let cache = Self::build_cache(&config);
         let pairing = Self::build_pairing(&config);
         let streaming_default = config.agents.defaults.streaming;
+        let tools = ToolRegistry::new()
+            .with_arg_validation(config.tools.argument_validation)
+            .with_rate_limiter(ToolRateLimiter::from_config(&config.tools.rate_limits));
+        let artifacts = Arc::new(ArtifactStore::new(config.workspace_path()));
+        let spend = Arc::new(Self::build_spend_store(&session_manager));
+        let traces = TraceRecorder::new(&config.agents.defaults.trace);
+        let turns = TurnScheduler::new(config.agents.defaults.concurrency.clone());
+        let context_builder = Self::attach_workspace_brief(&config, context_builder);
+        let context_builder = Self::attach_retriever(&config, context_builder);
         Self {
             config,
             session_manager: Arc::new(session_manager),
             bus,
             provider: Arc::new(RwLock::new(None)),
             provider_registry: Arc::new(RwLock::new(HashMap::new())),
-            tools: Arc::new(RwLock::new(ToolRegistry::new())),
+            tools: Arc::new(RwLock::new(tools)),
             running: AtomicBool::new(false),
             context_builder,
             usage_metrics: Arc::new(RwLock::new(None)),
             metrics_collector: Arc::new(MetricsCollector::new()),
             shutdown_tx,
+            stopped_tx: watch::channel(true).0,
             session_locks: Arc::new(Mutex::new(HashMap::new())),
             pending_messages: Arc::new(Mutex::new(HashMap::new())),
             streaming: AtomicBool::new(streaming_default),
@@ -729,28 +1965,184 @@ impl AgentLoop {
             tool_call_limit,
             approval_gate,
             approval_handler: Arc::new(RwLock::new(None)),
+            approval_policy: Arc::new(RwLock::new(approval_policy)),
             agent_mode,
             safety_layer,
             context_monitor,
             tool_feedback_tx: Arc::new(RwLock::new(None)),
             cache,
+            cached_replies: std::sync::Mutex::new(HashSet::new()),
             pairing,
             ltm: None,
             taint: None,
             #[cfg(feature = "panel")]
             event_bus: None,
             mcp_clients: Arc::new(tokio::sync::RwLock::new(Vec::new())),
+            processes,
+            analytics: None,
+            artifacts,
+            spend,
+            hooks: Arc::new(RwLock::new(Vec::new())),
+            commands: Arc::new(RwLock::new(default_commands())),
+            traces,
+            turns,
         }
     }
 
-    async fn build_memory_override(&self, user_input: &str) -> Option<String> {
-        let ltm = self.ltm.as_ref()?;
-        let guard = ltm.lock().await;
-        let memory = crate::memory::build_memory_injection(
-            &guard,
-            user_input,
-            crate::memory::MEMORY_INJECTION_BUDGET,
-        );
+    /// Attach a conversation analytics sink.
+    ///
+    /// The sink receives an [`AnalyticsEvent`] after every completed turn
+    /// (cached, tool-using, and streamed replies alike). Session commands
+    /// such as `/pin` are not counted as turns.
+    pub fn with_conversation_analytics(
+        mut self,
+        sink: Box<dyn AnalyticsSink + Send + Sync>,
+    ) -> Self {
+        let sink: Arc<dyn AnalyticsSink + Send + Sync> = Arc::from(sink);
+        self.analytics = Some(sink);
+        self
+    }
+
+    /// Refuse the turn, or log a warning, once `cost.monthly_budget_usd` is
+    /// spent.
+    fn check_monthly_budget(&self) -> Result<()> {
+        let Some(budget) = self.config.cost.monthly_budget_usd else {
+            return Ok(());
+        };
+        let quota = QuotaConfig {
+            max_cost_usd: Some(budget),
+            ..Default::default()
+        };
+        if self.spend.check(SPEND_KEY, &quota) != QuotaCheckResult::Exceeded {
+            return Ok(());
+        }
+        let spent = self.monthly_spend_usd();
+        match self.config.cost.budget_action {
+            BudgetAction::Warn => {
+                warn!(
+                    budget_usd = budget,
+                    spent_usd = spent,
+                    "Monthly budget exceeded"
+                );
+                Ok(())
+            }
+            BudgetAction::Refuse => Err(ZeptoError::QuotaRejected(format!(
+                "monthly budget of ${:.2} spent (${:.2} this month)",
+                budget, spent
+            ))),
+        }
+    }
+
+    /// Estimated spend in USD of all conversations this calendar month.
+    pub fn monthly_spend_usd(&self) -> f64 {
+        let month = QuotaStore::current_period_key(&QuotaPeriod::Monthly);
+        self.spend
+            .snapshot()
+            .get(SPEND_KEY)
+            .filter(|usage| usage.period_key == month)
+            .map_or(0.0, |usage| usage.cost_usd)
+    }
+
+    /// Tokens and estimated cost of one provider call to `model`, priced
+    /// with `cost.custom_pricing` and the built-in table.
+    fn call_usage(&self, model: &str, usage: Option<&Usage>) -> UsageCost {
+        usage_cost(model, usage, &self.config.cost.custom_pricing)
+    }
+
+    /// Emit an analytics event for the turn that just completed in
+    /// `session`, answering `msg` with `reply`.
+    fn record_turn(
+        &self,
+        session: &crate::session::Session,
+        msg: &InboundMessage,
+        reply: &str,
+        started: std::time::Instant,
+    ) {
+        if let Some(ref sink) = self.analytics {
+            record_turn_analytics(
+                sink.as_ref(),
+                session,
+                msg.content.chars().count(),
+                reply,
+                started.elapsed(),
+            );
+        }
+    }
+
+    /// Load session `key` for a turn answered with `model`.
+    ///
+    /// With compaction and `max_messages` off nothing shortens the history,
+    /// so it grows without bound. The turns that can no longer fit the
+    /// model's context budget (see [`history_start`]) are then left in
+    /// place rather than copied; the index returned is where the copy
+    /// starts, for [`SessionManager::save_from`].
+    async fn load_turn_session(
+        &self,
+        key: &str,
+        model: &str,
+    ) -> Result<(crate::session::Session, usize)> {
+        let options = self.session_manager.options_for(key).await?;
+        let unbounded = self.context_monitor.is_none() && options.max_messages.is_none();
+        let budget = self.context_budget_for(model);
+        self.session_manager
+            .get_or_create_from(key, |session| {
+                if unbounded {
+                    history_start(&session.messages, &budget)
+                } else {
+                    0
+                }
+            })
+            .await
+    }
+
+    /// Trim the history to the session's `max_messages` option, resolved
+    /// through [`SessionManager::options_for`].
+    async fn trim_session(&self, session: &mut crate::session::Session) -> Result<()> {
+        let options = self.session_manager.options_for(&session.key).await?;
+        if let Some(max) = options.max_messages {
+            let removed = session.trim(max, options.trim_strategy);
+            if removed > 0 {
+                debug!(session = %session.key, removed, "Trimmed session history");
+            }
+        }
+        Ok(())
+    }
+
+    /// Tool iterations allowed per turn in a session: its
+    /// `max_tool_iterations` option, else `agents.defaults.max_tool_iterations`.
+    async fn max_tool_iterations_for(&self, session_key: &str) -> Result<u32> {
+        let options = self.session_manager.options_for(session_key).await?;
+        Ok(options
+            .max_tool_iterations
+            .unwrap_or(self.config.agents.defaults.max_tool_iterations))
+    }
+
+    /// Pick up memory notes and the plan the `memory` and `todo` tools wrote
+    /// to the stored session during this turn, so the turn's final save does
+    /// not overwrite them.
+    async fn refresh_session_notes(&self, session: &mut crate::session::Session) {
+        match self
+            .session_manager
+            .with_session(&session.key, |s| (s.notes.clone(), s.plan.clone()))
+            .await
+        {
+            Ok(Some((notes, plan))) => {
+                session.notes = notes;
+                session.plan = plan;
+            }
+            Ok(None) => {}
+            Err(e) => warn!(error = %e, "Failed to refresh session notes"),
+        }
+    }
+
+    async fn build_memory_override(&self, user_input: &str) -> Option<String> {
+        let ltm = self.ltm.as_ref()?;
+        let guard = ltm.lock().await;
+        let memory = crate::memory::build_memory_injection(
+            &guard,
+            user_input,
+            crate::memory::MEMORY_INJECTION_BUDGET,
+        );
         if memory.is_empty() {
             None
         } else {
@@ -875,6 +2267,25 @@ impl AgentLoop {
         Arc::clone(&self.metrics_collector)
     }
 
+    /// Usage of the agent's tools: call and error counts, p50/p95 durations
+    /// and output bytes, across all sessions or for one session.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// let usage = agent.tool_metrics().await;
+    /// let shell = usage.snapshot().get("shell").copied();
+    /// let in_chat = usage.session("telegram:123");
+    /// ```
+    pub async fn tool_metrics(&self) -> Arc<ToolUsage> {
+        self.tools.read().await.usage()
+    }
+
+    /// The last `limit` shell and background-process commands run in
+    /// `session_key`, oldest first, from the session manager's command log.
+    pub fn command_history(&self, session_key: &str, limit: usize) -> Result<Vec<CommandRecord>> {
+        self.session_manager.recent_commands(session_key, limit)
+    }
+
     /// Register a tool with the agent.
     ///
     /// # Arguments
@@ -891,6 +2302,60 @@ impl AgentLoop {
         tools.register(tool);
     }
 
+    /// Register a hook run before and after every turn, after the hooks
+    /// registered so far.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use zeptoclaw::agent::hooks::AllowlistHook;
+    ///
+    /// agent.register_hook(Box::new(AllowlistHook::new(["telegram:12345"]))).await;
+    /// ```
+    pub async fn register_hook(&self, hook: Box<dyn AgentHook>) {
+        self.hooks.write().await.push(Arc::from(hook));
+    }
+
+    /// Register a chat command, replacing a registered one of the same name,
+    /// built-in session commands (`/help`, `/model`, ...) included (see
+    /// [`commands`]).
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use zeptoclaw::agent::Command;
+    ///
+    /// agent
+    ///     .register_command(Command::new("ping", "Check the agent is up", |_ctx| async {
+    ///         Ok("pong".to_string())
+    ///     }))
+    ///     .await;
+    /// ```
+    pub async fn register_command(&self, command: Command) {
+        let mut commands = self.commands.write().await;
+        match commands.iter_mut().find(|c| c.name() == command.name()) {
+            Some(existing) => *existing = command,
+            None => commands.push(command),
+        }
+    }
+
+    /// Wrap every tool execution in `middleware`.
+    ///
+    /// Middlewares run in the order they were added: the first is the
+    /// outermost, seeing each call first and its result last. See
+    /// [`crate::tools::middleware`].
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use std::sync::Arc;
+    /// use zeptoclaw::tools::{ResultSizeLimitMiddleware, TracingMiddleware};
+    ///
+    /// agent.add_tool_middleware(Arc::new(TracingMiddleware::default())).await;
+    /// agent.add_tool_middleware(Arc::new(ResultSizeLimitMiddleware::new(16 * 1024))).await;
+    /// ```
+    pub async fn add_tool_middleware(&self, middleware: Arc<dyn ToolMiddleware>) {
+        let mut tools = self.tools.write().await;
+        tools.add_middleware(middleware);
+    }
+
     /// Install an approval handler used to resolve approval requests inline.
     pub async fn set_approval_handler<F, Fut>(&self, handler: F)
     where
@@ -902,6 +2367,36 @@ impl AgentLoop {
         *slot = Some(wrapped);
     }
 
+    /// Install the policy asked about gated tool calls when no inline
+    /// approval handler is set.
+    pub async fn set_approval_policy(&self, policy: Arc<dyn ApprovalPolicy>) {
+        let mut slot = self.approval_policy.write().await;
+        *slot = Some(policy);
+    }
+
+    fn build_approval_policy(
+        config: &Config,
+        bus: &Arc<MessageBus>,
+    ) -> Option<Arc<dyn ApprovalPolicy>> {
+        let approval = &config.approval;
+        (approval.enabled && approval.ask_in_channel).then(|| {
+            Arc::new(ChannelApprovalPolicy::new(
+                Arc::clone(bus),
+                std::time::Duration::from_secs(approval.reply_timeout_secs.max(1)),
+            )) as Arc<dyn ApprovalPolicy>
+        })
+    }
+
+    /// Keep senders `agents.authorization` turns away from stopping turns or
+    /// answering prompts on `bus`; their messages reach the loop instead.
+    fn install_sender_check(config: &Config, bus: &MessageBus) {
+        let authorization = config.agents.authorization.clone();
+        if authorization.channels.is_empty() {
+            return;
+        }
+        bus.set_sender_check(move |msg| Access::of(&authorization, msg) != Access::Denied);
+    }
+
     /// Merge all tools from a kernel ToolRegistry and register MCP clients.
     ///
     /// Used by `create_agent_with_template()` to transfer pre-assembled kernel
@@ -969,11 +2464,110 @@ impl AgentLoop {
     /// - No provider is configured
     /// - The LLM call fails
     /// - Session management fails
+    /// - A hook rejects the message ([`ZeptoError::Rejected`]) or panics
+    ///   ([`ZeptoError::Hook`])
+    /// - The session already saw the channel's message ID
+    ///   ([`ZeptoError::Duplicate`])
     pub async fn process_message(&self, msg: &InboundMessage) -> Result<String> {
+        self.run_turn(msg, None, None).await
+    }
+
+    /// [`Self::process_message`], streaming partial replies to `stream` and
+    /// reporting what the turn is doing to `progress`.
+    async fn run_turn(
+        &self,
+        msg: &InboundMessage,
+        stream: Option<&mut ReplyStream>,
+        progress: Option<&TurnProgress>,
+    ) -> Result<String> {
+        if let Some(reply) = self.turn_away(msg)? {
+            return Ok(reply);
+        }
+        let hooks = self.hooks.read().await.clone();
+        if hooks.is_empty() {
+            return self.run_agent_turn(msg, stream, progress).await;
+        }
+        let mut msg = msg.clone();
+        match run_before_turn(&hooks, &mut msg).await? {
+            HookDecision::Continue => {}
+            HookDecision::Skip(reply) => return Ok(reply),
+            HookDecision::Reject(reason) => return Err(ZeptoError::Rejected(reason)),
+        }
+        let reply = self.run_agent_turn(&msg, stream, progress).await?;
+        let mut response = OutboundResponse::reply_to(&msg, reply);
+        run_after_turn(&hooks, &mut response).await?;
+        Ok(response.content)
+    }
+
+    /// The turn itself, after the `before_turn` hooks.
+    async fn run_agent_turn(
+        &self,
+        msg: &InboundMessage,
+        stream: Option<&mut ReplyStream>,
+        progress: Option<&TurnProgress>,
+    ) -> Result<String> {
         // Acquire a per-session lock to serialize concurrent messages for the
         // same session key. Different sessions can still proceed concurrently.
         let session_lock = self.session_lock_for(&msg.session_key).await;
         let _session_guard = session_lock.lock().await;
+        // A mark left by a reply that wasn't sent through the bus must not
+        // carry over to this one.
+        self.take_cached_reply(&msg.session_key);
+
+        // A message the channel delivered again is not answered twice.
+        self.check_redelivery(msg).await?;
+
+        // Chat commands are handled locally without an LLM call.
+        if let Some(reply) = self.command_reply(msg).await? {
+            return Ok(reply);
+        }
+
+        match self.welcome_for(msg).await? {
+            Some((welcome, OnboardingMode::Instead)) => Ok(welcome),
+            Some((welcome, OnboardingMode::Before)) => {
+                let reply = self.answer_turn(msg, stream, progress).await?;
+                Ok(onboarding::before(&welcome, &reply))
+            }
+            None => self.answer_turn(msg, stream, progress).await,
+        }
+    }
+
+    /// The model's answer to `msg`, under the session lock.
+    async fn answer_turn(
+        &self,
+        msg: &InboundMessage,
+        mut stream: Option<&mut ReplyStream>,
+        progress: Option<&TurnProgress>,
+    ) -> Result<String> {
+        // The agent profile and settings answering this turn. They are
+        // resolved under the session lock, so an `/agent` or `/model` switch
+        // lands between turns and never separates a tool call from its result.
+        let profile = self.agent_profile_for(msg).await?;
+        let settings = self
+            .generation_settings_for(msg, profile.map(|(_, p)| p))
+            .await?;
+        let switched = with_model_override(
+            msg,
+            settings.model.as_deref(),
+            &self.config.agents.defaults.model,
+        );
+        let msg = switched.as_ref().unwrap_or(msg);
+        // Tools disabled for this channel/session are neither offered nor run.
+        let tool_access = self.tool_access_for(msg, profile.map(|(_, p)| p));
+        // Programmatic callers can ask for a JSON reply.
+        let response_format = msg.response_format();
+        // A "stop" message, a disconnect or the turn's time limit cancels
+        // this turn's tool calls.
+        let turn = ActiveTurn::begin(
+            &self.bus,
+            &msg.session_key,
+            turn_time_limit(&self.config.agents.defaults, &msg.channel),
+        );
+        let feedback = FeedbackSink::new(self.tool_feedback_tx.read().await.clone(), progress);
+        self.check_monthly_budget()?;
+        let mut turn_usage = UsageCost::default();
+
+        let turn_started = std::time::Instant::now();
 
         // Reset per-run counters so limits apply to each process_message call
         // independently, not across the lifetime of the AgentLoop struct.
@@ -982,7 +2576,11 @@ impl AgentLoop {
 
         // Resolve the inbound message content first (inlines text attachments) so the
         // injection scanner sees the fully-expanded prompt, not just msg.content.
-        let user_message = inbound_to_message(msg, None).await;
+        let media_store = self
+            .session_manager
+            .sessions_dir()
+            .map(|dir| crate::session::media::MediaStore::new(dir.to_path_buf()));
+        let user_message = inbound_to_message(msg, media_store.as_ref()).await;
         let resolved_user_prompt = user_message.content.clone();
 
         // Tiered inbound injection scanning: block untrusted channels, warn others.
@@ -1045,10 +2643,19 @@ impl AgentLoop {
         let metrics_collector = Arc::clone(&self.metrics_collector);
 
         // Get or create session
-        let mut session = self.session_manager.get_or_create(&msg.session_key).await?;
+        let model_string = self.resolve_model_for_message(msg);
+        let (mut session, history_from) = self
+            .load_turn_session(&msg.session_key, &model_string)
+            .await?;
 
         // Add the user message BEFORE compaction so compaction sees the full context.
         session.add_message(user_message);
+        session.turns += 1;
+        self.trim_session(&mut session).await?;
+
+        // Record the turn when tracing is on for this session.
+        let turn_trace = self.begin_trace(&session);
+        let provider = trace::traced(provider, turn_trace.as_ref());
 
         // Apply three-tier context overflow recovery if needed
         if let Some(ref monitor) = self.context_monitor {
@@ -1085,14 +2692,11 @@ impl AgentLoop {
         // entry here.
         let memory_override = self.build_memory_override(&resolved_user_prompt).await;
         let mut messages = self
-            .build_resolved_messages(&session, memory_override.as_deref())
+            .build_resolved_messages(&session, memory_override.as_deref(), &model_string)
             .await;
 
         // Get tool definitions (short-lived read lock)
-        let tool_definitions = {
-            let tools = self.tools.read().await;
-            tools.definitions_with_options(self.config.agents.defaults.compact_tools)
-        };
+        let tool_definitions = self.tool_definitions_for(&tool_access).await;
 
         // Pre-flight context guard: trim oversized tool results and check budget
         if let Some(ref monitor) = self.context_monitor {
@@ -1117,18 +2721,24 @@ impl AgentLoop {
                         );
                     session.messages = recovered;
                     messages = self
-                        .build_resolved_messages(&session, memory_override.as_deref())
+                        .build_resolved_messages(
+                            &session,
+                            memory_override.as_deref(),
+                            &model_string,
+                        )
                         .await;
                 }
             }
         }
 
         // Build chat options
-        let options = ChatOptions::new()
-            .with_max_tokens(self.config.agents.defaults.max_tokens)
-            .with_temperature(self.config.agents.defaults.temperature);
+        let mut options = settings::chat_options(&settings);
+        if let Some(format) = response_format.clone() {
+            options = options.with_output_format(format);
+        } else {
+            options = self.with_stop_sequences(options, msg);
+        }
 
-        let model_string = self.resolve_model_for_message(msg);
         let model = Some(model_string.as_str());
 
         // Check token budget before first LLM call
@@ -1139,20 +2749,22 @@ impl AgentLoop {
             )));
         }
 
-        // Build cache key from (model, system_prompt, user_prompt) for the
-        // initial LLM call only. Tool follow-up calls are never cached.
-        let cache_key = self.cache.as_ref().map(|_| {
-            let system_prompt = messages
-                .first()
-                .filter(|m| m.role == Role::System)
-                .map(|m| m.content.as_str())
-                .unwrap_or("");
-            ResponseCache::cache_key(
-                self.config.agents.defaults.model.as_str(),
-                system_prompt,
-                &resolved_user_prompt,
-            )
-        });
+        // Build cache key from the whole request (model, messages, tool
+        // schemas, chat options) for the initial LLM call only. Tool
+        // follow-up calls are never cached, and neither are replies in a
+        // requested format or to senders that asked for a fresh answer.
+        let cache_key = self
+            .cache
+            .as_ref()
+            .filter(|_| response_format.is_none() && !msg.no_cache())
+            .map(|_| {
+                ResponseCache::conversation_key(
+                    &model_string,
+                    &messages,
+                    &tool_definitions,
+                    &options,
+                )
+            });
 
         // Check response cache before calling the provider.
         // The MutexGuard must be dropped before any .await to remain Send.
@@ -1163,34 +2775,46 @@ impl AgentLoop {
         };
         if let Some(cached_response) = cached_hit {
             debug!("Cache hit for initial prompt");
+            metrics_collector.record_cache_hit();
             // User message was already added to session before build_messages.
-            session.add_message(Message::assistant(&cached_response));
-            self.session_manager.save(&session).await?;
+            let mut reply_msg = Message::assistant(&cached_response);
+            reply_msg.agent = profile.map(|(name, _)| name.to_string());
+            reply_msg.settings = Some(settings);
+            session.add_message(reply_msg);
+            self.session_manager
+                .save_from(&session, history_from)
+                .await?;
+            self.record_turn(&session, msg, &cached_response, turn_started);
+            if let Ok(mut cached) = self.cached_replies.lock() {
+                cached.insert(session.key.clone());
+            }
             return Ok(cached_response);
         }
 
         // Send thinking feedback
-        if let Some(tx) = self.tool_feedback_tx.read().await.as_ref() {
-            let _ = tx.send(ToolFeedback {
-                tool_name: String::new(),
-                phase: ToolFeedbackPhase::Thinking,
-                args_json: None,
-            });
-        }
+        feedback.send(ToolFeedback {
+            tool_name: String::new(),
+            phase: ToolFeedbackPhase::Thinking,
+            args_json: None,
+        });
 
         // Call LLM with overflow retry -- provider lock is NOT held during this await
         let mut response = {
             let max_retries = self.config.compaction.overflow_retries;
             let mut last_messages = messages;
             let mut last_tool_defs = tool_definitions;
-            let mut result = provider
-                .chat(
+            let mut result = unless_stopped(
+                &turn.token,
+                reply_stream::chat(
+                    provider.as_ref(),
+                    stream.as_deref_mut(),
                     last_messages.clone(),
                     last_tool_defs.clone(),
                     model,
                     options.clone(),
-                )
-                .await;
+                ),
+            )
+            .await;
 
             let mut attempt = 0u32;
             while let Err(ref e) = result {
@@ -1218,33 +2842,32 @@ impl AgentLoop {
                 );
                 session.messages = recovered;
                 last_messages = self
-                    .build_resolved_messages(&session, memory_override.as_deref())
+                    .build_resolved_messages(&session, memory_override.as_deref(), &model_string)
                     .await;
-                last_tool_defs = {
-                    let tools = self.tools.read().await;
-                    tools.definitions_with_options(self.config.agents.defaults.compact_tools)
-                };
-                result = provider
-                    .chat(
+                last_tool_defs = self.tool_definitions_for(&tool_access).await;
+                result = unless_stopped(
+                    &turn.token,
+                    reply_stream::chat(
+                        provider.as_ref(),
+                        stream.as_deref_mut(),
                         last_messages.clone(),
                         last_tool_defs.clone(),
                         model,
                         options.clone(),
-                    )
-                    .await;
+                    ),
+                )
+                .await;
                 attempt += 1;
             }
             result?
         };
 
         // Send thinking done feedback
-        if let Some(tx) = self.tool_feedback_tx.read().await.as_ref() {
-            let _ = tx.send(ToolFeedback {
-                tool_name: String::new(),
-                phase: ToolFeedbackPhase::ThinkingDone,
-                args_json: None,
-            });
-        }
+        feedback.send(ToolFeedback {
+            tool_name: String::new(),
+            phase: ToolFeedbackPhase::ThinkingDone,
+            args_json: None,
+        });
 
         if let (Some(metrics), Some(usage)) = (usage_metrics.as_ref(), response.usage.as_ref()) {
             metrics.record_tokens(usage.prompt_tokens as u64, usage.completion_tokens as u64);
@@ -1255,10 +2878,11 @@ impl AgentLoop {
             self.token_budget
                 .record(usage.prompt_tokens as u64, usage.completion_tokens as u64);
         }
+        turn_usage.add(&self.call_usage(&model_string, response.usage.as_ref()));
 
         // Cache the response if it has no tool calls (pure text reply).
         // Responses with tool calls depend on tool execution and are not cacheable.
-        if !response.has_tool_calls() {
+        if !response.has_tool_calls() && !turn.token.is_cancelled() {
             if let (Some(ref cache_mutex), Some(key)) = (&self.cache, cache_key) {
                 let token_count = response
                     .usage
@@ -1275,9 +2899,12 @@ impl AgentLoop {
         // User message was already added to session before build_messages above.
 
         // Tool loop
-        let max_iterations = self.config.agents.defaults.max_tool_iterations;
+        let max_iterations = self.max_tool_iterations_for(&session.key).await?;
         let mut iteration = 0;
         let mut chain_tracker = crate::safety::chain_alert::ChainTracker::new();
+        // Text the model wrote alongside its tool calls, kept for the reply
+        // of a turn its time limit cuts off.
+        let mut produced = Vec::new();
         let mut loop_guard = if self.config.agents.defaults.loop_guard.enabled {
             Some(LoopGuard::new(
                 self.config.agents.defaults.loop_guard.clone(),
@@ -1333,302 +2960,116 @@ impl AgentLoop {
                     })
                     .collect(),
             );
+            // Kept for the provider: some APIs require it back with the tool
+            // results in the next call.
+            assistant_msg.thinking = response.thinking.clone();
             session.add_message(assistant_msg);
+            produced.push(response.content.clone());
 
             // Execute tool calls in parallel
             let workspace = self.config.workspace_path();
             let workspace_str = workspace.to_string_lossy();
             let tool_ctx = ToolContext::new()
                 .with_channel(&msg.channel, &msg.chat_id)
-                .with_workspace(&workspace_str)
-                .with_batch(msg.metadata.get("is_batch").is_some_and(|v| v == "true"));
+                .with_sandbox(self.sandbox(&workspace_str))
+                .with_batch(msg.metadata.get("is_batch").is_some_and(|v| v == "true"))
+                .with_processes(Arc::clone(&self.processes))
+                .with_artifacts(Arc::clone(&self.artifacts), &msg.session_key)
+                .with_command_log(self.session_manager.command_log(), &msg.session_key)
+                .with_dry_run(self.dry_run_forced() || session.dry_run.unwrap_or(false))
+                .with_admin(Access::of(&self.config.agents.authorization, msg).is_admin())
+                .with_cancellation(turn.token.clone());
 
-            let approval_gate = Arc::clone(&self.approval_gate);
             let approval_handler = self.approval_handler.read().await.clone();
-            let safety_layer = self.safety_layer.clone();
-            let taint_engine = self.taint.clone();
-            let hook_engine = Arc::new(
-                crate::hooks::HookEngine::new(self.config.hooks.clone())
-                    .with_bus(Arc::clone(&self.bus)),
-            );
+            let approval_policy = self.approval_policy.read().await.clone();
+            let trusted_local_session = is_trusted_local_session(msg);
+
+            // Approval prompts are answered one at a time, so batches that
+            // need approval run in order; otherwise only tools that are not
+            // parallel-safe are serialized.
+            let run_sequential = !trusted_local_session
+                && (approval_handler.is_some() || approval_policy.is_some())
+                && response
+                    .tool_calls
+                    .iter()
+                    .any(|tool_call| self.approval_gate.requires_approval(&tool_call.name));
+            let parallel_safe = parallel_safety(&self.tools, &response.tool_calls).await;
 
             // Compute dynamic tool result budget based on remaining context space
             let current_tokens = ContextMonitor::estimate_tokens_with_margin(
                 &session.messages,
                 self.config.compaction.safety_margin,
             );
-            let context_limit = self.config.compaction.context_limit;
-            let max_result_bytes = self.config.agents.defaults.max_tool_result_bytes;
             let result_budget = crate::utils::sanitize::compute_tool_result_budget_with_share(
-                context_limit,
+                self.config.compaction.context_limit,
                 current_tokens,
                 response.tool_calls.len(),
-                max_result_bytes,
+                self.config.agents.defaults.max_tool_result_bytes,
                 self.config.compaction.single_tool_result_share,
             );
 
-            let tool_feedback_tx = self.tool_feedback_tx.clone();
-            #[cfg(feature = "panel")]
-            let event_bus_clone = self.event_bus.clone();
-            let is_dry_run = self.dry_run.load(Ordering::SeqCst);
-            let current_agent_mode = self.agent_mode;
-            let trusted_local_session = is_trusted_local_session(msg);
-
-            let run_sequential = (!trusted_local_session
-                && approval_handler.is_some()
-                && response
-                    .tool_calls
-                    .iter()
-                    .any(|tool_call| approval_gate.requires_approval(&tool_call.name)))
-                || needs_sequential_execution(&self.tools, &response.tool_calls).await;
-            let tool_timeout_secs = if self.config.agents.defaults.tool_timeout_secs > 0 {
-                self.config.agents.defaults.tool_timeout_secs
-            } else {
-                self.config.agents.defaults.agent_timeout_secs
+            let batch = ToolBatch {
+                tools: &self.tools,
+                ctx: tool_ctx,
+                msg,
+                bus: &self.bus,
+                defaults: &self.config.agents.defaults,
+                turn_started,
+                access: &tool_access,
+                expired: &turn.expired,
+                feedback: &feedback,
+                usage_metrics: usage_metrics.as_deref(),
+                metrics: &metrics_collector,
+                approval_gate: &self.approval_gate,
+                approval_handler,
+                approval_policy,
+                hooks: crate::hooks::HookEngine::new(self.config.hooks.clone())
+                    .with_bus(Arc::clone(&self.bus)),
+                safety: self.safety_layer.as_deref(),
+                taint: self.taint.as_deref(),
+                #[cfg(feature = "panel")]
+                event_bus: self.event_bus.as_ref(),
+                agent_mode: self.agent_mode,
+                trusted_local_session,
+                result_budget,
             };
-            let tool_timeout = std::time::Duration::from_secs(tool_timeout_secs.max(1));
 
-            // Clone inbound metadata for routing propagation in tool `for_user` messages.
-            let inbound_metadata = msg.metadata.clone();
-
-            let tool_futures: Vec<_> = response
+            // Identical calls the model keeps asking for are answered, not run.
+            let repeats: Vec<Option<u32>> = response
                 .tool_calls
                 .iter()
                 .map(|tool_call| {
-                    let tools = Arc::clone(&self.tools);
-                    let ctx = tool_ctx.clone();
-                    let name = tool_call.name.clone();
-                    let id = tool_call.id.clone();
-                    let raw_args = tool_call.arguments.clone();
-                    let usage_metrics = usage_metrics.clone();
-                    let metrics_collector = Arc::clone(&metrics_collector);
-                    let gate = Arc::clone(&approval_gate);
-                    let approval_handler = approval_handler.clone();
-                    let hooks = Arc::clone(&hook_engine);
-                    let safety = safety_layer.clone();
-                    let taint = taint_engine.clone();
-                    let budget = result_budget;
-                    let tool_feedback_tx = tool_feedback_tx.clone();
-                    #[cfg(feature = "panel")]
-                    let event_bus = event_bus_clone.clone();
-                    let dry_run = is_dry_run;
-                    let agent_mode = current_agent_mode;
-                    let bus_for_tools = Arc::clone(&self.bus);
-                    let inbound_meta = inbound_metadata.clone();
-
-                    async move {
-                        let args: serde_json::Value = match serde_json::from_str(&raw_args) {
-                            Ok(v) => v,
-                            Err(e) => {
-                                tracing::warn!(tool = %name, error = %e, "Invalid JSON in tool arguments");
-                                serde_json::json!({"_parse_error": format!("Invalid arguments JSON: {}", e)})
-                            }
-                        };
-
-                        // Check hooks before executing
-                        let channel_name = ctx.channel.as_deref().unwrap_or("cli");
-                        let chat_id = ctx.chat_id.as_deref().unwrap_or(channel_name);
-                        if let crate::hooks::HookResult::Block(msg) =
-                            hooks.before_tool(&name, &args, channel_name, chat_id)
-                        {
-                            return (id, format!("Tool '{}' blocked by hook: {}", name, msg), false);
-                        }
-
-                        // Agent mode enforcement (before approval gate).
-                        // RequiresApproval: blocks the tool unless ApprovalGate is
-                        // already configured to gate this tool name. In practice, this
-                        // means Assistant mode blocks Shell/Hardware/Destructive tools
-                        // unless the operator has explicitly listed them in
-                        // `approval.require_approval_for`. This is "fail-closed" by design.
-                        {
-                            let mode_policy = crate::security::ModePolicy::new(agent_mode);
-                            let tools_guard = tools.read().await;
-                            if let Some(tool) = tools_guard.get(&name) {
-                                let tool_category = tool.category();
-                                match mode_policy.check(tool_category) {
-                                    crate::security::CategoryPermission::Blocked => {
-                                        info!(tool = %name, mode = %agent_mode, category = ?tool_category, "Tool blocked by agent mode");
-                                        return (id, format!(
-                                            "Tool '{}' is blocked in {} mode (category: {})",
-                                            name, agent_mode, tool_category
-                                        ), false);
-                                    }
-                                    crate::security::CategoryPermission::RequiresApproval => {
-                                        if trusted_local_session {
-                                            info!(tool = %name, mode = %agent_mode, category = ?tool_category, "Trusted local session bypassed approval-gated tool");
-                                        } else if !gate.requires_approval(&name) {
-                                            info!(tool = %name, mode = %agent_mode, category = ?tool_category, "Tool requires approval per agent mode");
-                                            return (id, format!(
-                                                "Tool '{}' requires approval in {} mode (category: {}). Not executed.",
-                                                name, agent_mode, tool_category
-                                            ), false);
-                                        }
-                                        // Fall through to approval gate — it will prompt for approval
-                                    }
-                                    crate::security::CategoryPermission::Allowed => {}
-                                }
-                            }
-                        }
-
-                        // Check approval gate before executing
-                        if !trusted_local_session {
-                            if let Some(message) = resolve_tool_approval(
-                                &gate,
-                                approval_handler.as_ref(),
-                                &name,
-                                &args,
-                            )
-                            .await
-                            {
-                                info!(tool = %name, "Tool requires approval, blocking execution");
-                                return (id, message, false);
-                            }
-                        }
-
-                        // Dry-run mode: describe what would happen without executing
-                        if dry_run {
-                            return (id, Self::dry_run_result(&name, &args, &raw_args, budget), false);
-                        }
-
-                        // Send tool starting feedback
-                        if let Some(tx) = tool_feedback_tx.read().await.as_ref() {
-                            let _ = tx.send(ToolFeedback {
-                                tool_name: name.clone(),
-                                phase: ToolFeedbackPhase::Starting,
-                                args_json: Some(raw_args.clone()),
-                            });
-                        }
-                        #[cfg(feature = "panel")]
-                        if let Some(bus) = &event_bus {
-                            bus.send(crate::api::events::PanelEvent::ToolStarted {
-                                tool: name.clone(),
-                            });
-                        }
-                        let tool_start = std::time::Instant::now();
-                        let execution = std::panic::AssertUnwindSafe(async {
-                            let tools_guard = tools.read().await;
-                            crate::kernel::execute_tool(
-                                &tools_guard,
-                                &name,
-                                args,
-                                &ctx,
-                                safety.as_ref().map(|s| s.as_ref()),
-                                &metrics_collector,
-                                taint.as_ref().map(|t| t.as_ref()),
-                            )
-                            .await
+                    loop_guard.as_mut().and_then(|guard| {
+                        guard.check_repeat(ToolCallSig {
+                            name: &tool_call.name,
+                            arguments: &tool_call.arguments,
                         })
-                        .catch_unwind();
-                        let (result, success, tool_output) = match tokio::time::timeout(tool_timeout, execution).await {
-                            Ok(Ok(Ok(output))) => {
-                                let success = !output.is_error;
-                                let for_llm = output.for_llm.clone();
-                                (for_llm, success, Some(output))
-                            }
-                            Ok(Ok(Err(e))) => {
-                                (format!("Error: {}", e), false, None)
-                            }
-                            Ok(Err(_panic)) => {
-                                error!(tool = %name, "Tool panicked during execution");
-                                (format!("Error: Tool '{}' panicked during execution", name), false, None)
-                            }
-                            Err(_) => {
-                                error!(tool = %name, timeout_secs = tool_timeout.as_secs(), "Tool execution timed out");
-                                (format!("Error: Tool '{}' timed out after {}s", name, tool_timeout.as_secs()), false, None)
-                            }
-                        };
-
-                        let pause = tool_output.as_ref().is_some_and(|o| o.pause_for_input);
-                        let elapsed = tool_start.elapsed();
-                        let latency_ms = elapsed.as_millis() as u64;
-                        // Send to user if tool opted in
-                        if let Some(ref output) = tool_output {
-                            if let Some(ref user_msg) = output.for_user {
-                                let mut outbound = crate::bus::OutboundMessage::new(
-                                    ctx.channel.as_deref().unwrap_or(""),
-                                    ctx.chat_id.as_deref().unwrap_or(""),
-                                    user_msg,
-                                );
-                                // Propagate routing metadata (e.g. telegram_thread_id, telegram_message_id)
-                                if let Some(tid) = inbound_meta.get("telegram_thread_id") {
-                                    outbound
-                                        .metadata
-                                        .insert("telegram_thread_id".to_string(), tid.clone());
-                                }
-                                if let Some(mid) = inbound_meta.get("telegram_message_id") {
-                                    outbound
-                                        .metadata
-                                        .insert("telegram_message_id".to_string(), mid.clone());
-                                }
-                                // Keep typing indicator alive — agent is still working
-                                outbound
-                                    .metadata
-                                    .insert("keep_typing".to_string(), "true".to_string());
-                                let _ = bus_for_tools.publish_outbound(outbound).await;
-                            }
-                        }
-                        if success {
-                            debug!(tool = %name, latency_ms = latency_ms, "Tool executed successfully");
-                            hooks.after_tool(&name, &result, elapsed, channel_name, chat_id);
-                            if let Some(tx) = tool_feedback_tx.read().await.as_ref() {
-                                let _ = tx.send(ToolFeedback {
-                                    tool_name: name.clone(),
-                                    phase: ToolFeedbackPhase::Done { elapsed_ms: latency_ms },
-                                    args_json: Some(raw_args.clone()),
-                                });
-                            }
-                            #[cfg(feature = "panel")]
-                            if let Some(bus) = &event_bus {
-                                bus.send(crate::api::events::PanelEvent::ToolDone {
-                                    tool: name.clone(),
-                                    duration_ms: latency_ms,
-                                });
-                            }
-                        } else {
-                            error!(tool = %name, latency_ms = latency_ms, error = %result, "Tool execution failed");
-                            hooks.on_error(&name, &result, channel_name, chat_id);
-                            if let Some(metrics) = usage_metrics.as_ref() {
-                                metrics.record_error();
-                            }
-                            if let Some(tx) = tool_feedback_tx.read().await.as_ref() {
-                                let _ = tx.send(ToolFeedback {
-                                    tool_name: name.clone(),
-                                    phase: ToolFeedbackPhase::Failed {
-                                        elapsed_ms: latency_ms,
-                                        error: result.clone(),
-                                    },
-                                    args_json: Some(raw_args.clone()),
-                                });
-                            }
-                            #[cfg(feature = "panel")]
-                            if let Some(bus) = &event_bus {
-                                bus.send(crate::api::events::PanelEvent::ToolFailed {
-                                    tool: name.clone(),
-                                    error: result.clone(),
-                                });
-                            }
-                        }
-
-                        // Sanitize the result with dynamic budget
-                        let sanitized = crate::utils::sanitize::sanitize_tool_result(
-                            &result,
-                            budget,
-                        );
-
-                        (id, sanitized, pause)
-                    }
+                    })
                 })
                 .collect();
 
-            let results = if run_sequential {
+            let tool_futures: Vec<_> = response
+                .tool_calls
+                .iter()
+                .zip(repeats)
+                .map(|(tool_call, repeat)| execute_tool_call(&batch, tool_call, repeat))
+                .collect();
+
+            let timed_results = if run_sequential {
                 let mut out = Vec::with_capacity(tool_futures.len());
                 for fut in tool_futures {
-                    out.push(fut.await);
+                    out.push(timed(fut).await);
                 }
                 out
             } else {
-                futures::future::join_all(tool_futures).await
+                run_tool_batch(
+                    tool_futures,
+                    &parallel_safe,
+                    self.config.agents.defaults.max_parallel_tools,
+                )
+                .await
             };
+            let (results, elapsed): (Vec<_>, Vec<_>) = timed_results.into_iter().unzip();
 
             // Record tool names for chain alerting
             let tool_names: Vec<String> = response
@@ -1639,9 +3080,23 @@ impl AgentLoop {
             chain_tracker.record(&tool_names);
 
             let results: Vec<(String, String, bool)> = results;
+            if let Some(turn_trace) = &turn_trace {
+                turn_trace.record_tools(&response.tool_calls, &results, &elapsed);
+            }
             let should_pause = results.iter().any(|(_, _, pause)| *pause);
-            for (id, result, _) in &results {
-                session.add_message(Message::tool_result(id, result));
+            for ((id, result, _), elapsed) in results.iter().zip(elapsed) {
+                let message = Message::tool_result(id, result).with_tool_elapsed(elapsed);
+                if self.config.session.dedup_tool_results {
+                    session.add_message_deduped(message);
+                } else {
+                    session.add_message(message);
+                }
+            }
+            if tool_names
+                .iter()
+                .any(|name| name == "memory" || name == "todo")
+            {
+                self.refresh_session_notes(&mut session).await;
             }
 
             // In-loop compaction: check if tool results pushed context over threshold
@@ -1666,10 +3121,20 @@ impl AgentLoop {
                 }
             }
 
+            if turn.token.is_cancelled() {
+                info!(session = %msg.session_key, time_limit = turn.expired(), "Turn stopped");
+                response.content = TURN_STOPPED_REPLY.to_string();
+                break;
+            }
+
             if should_pause {
                 break;
             }
 
+            // In steer mode, messages sent meanwhile join the turn here, so
+            // the model sees them before its next step.
+            self.steer(msg, &mut session).await;
+
             // Increment tool call counter after execution.
             self.tool_call_limit
                 .increment(response.tool_calls.len() as u32);
@@ -1689,85 +3154,20 @@ impl AgentLoop {
                         "Tool call limit reached. Token budget exceeded.".to_string();
                     break;
                 }
-                let mut messages = self
-                    .build_resolved_messages(&session, memory_override.as_deref())
-                    .await;
-                // Pre-flight guard for synthesis call
-                if let Some(ref monitor) = self.context_monitor {
-                    if let PreflightAction::NeedsCompaction =
-                        monitor.preflight_check(&mut messages, &[])
-                    {
-                        let ctx_limit = self.config.compaction.context_limit;
-                        let cap = self.config.agents.defaults.max_tool_result_bytes;
-                        let (recovered, _) =
-                            crate::agent::compaction::try_recover_context_with_urgency(
-                                session.messages,
-                                ctx_limit,
-                                CompactionUrgency::Emergency,
-                                5,
-                                cap,
-                                self.config.compaction.safety_margin,
-                            );
-                        session.messages = recovered;
-                        messages = self
-                            .build_resolved_messages(&session, memory_override.as_deref())
-                            .await;
-                    }
-                }
-                response = {
-                    let max_retries = self.config.compaction.overflow_retries;
-                    let mut last_messages = messages;
-                    let mut result = provider
-                        .chat(last_messages.clone(), vec![], model, options.clone())
-                        .await;
-                    let mut attempt = 0u32;
-                    while let Err(ref e) = result {
-                        if !Self::is_context_overflow(e) || attempt >= max_retries {
-                            break;
-                        }
-                        if self.context_monitor.is_none() {
-                            break; // compaction disabled
-                        }
-                        warn!(
-                            attempt = attempt + 1,
-                            max = max_retries,
-                            "Context overflow in synthesis call, compacting and retrying"
-                        );
-                        let urgency = Self::overflow_retry_urgency(attempt);
-                        let ctx_limit = self.config.compaction.context_limit;
-                        let cap = self.config.agents.defaults.max_tool_result_bytes;
-                        let (recovered, _) =
-                            crate::agent::compaction::try_recover_context_with_urgency(
-                                session.messages,
-                                ctx_limit,
-                                urgency,
-                                5,
-                                cap,
-                                self.config.compaction.safety_margin,
-                            );
-                        session.messages = recovered;
-                        last_messages = self
-                            .build_resolved_messages(&session, memory_override.as_deref())
-                            .await;
-                        result = provider
-                            .chat(last_messages.clone(), vec![], model, options.clone())
-                            .await;
-                        attempt += 1;
-                    }
-                    result?
-                };
-                if let (Some(metrics), Some(usage)) =
-                    (usage_metrics.as_ref(), response.usage.as_ref())
-                {
-                    metrics
-                        .record_tokens(usage.prompt_tokens as u64, usage.completion_tokens as u64);
-                }
-                if let Some(usage) = response.usage.as_ref() {
-                    metrics_collector
-                        .record_tokens(usage.prompt_tokens as u64, usage.completion_tokens as u64);
-                    self.token_budget
-                        .record(usage.prompt_tokens as u64, usage.completion_tokens as u64);
-                }
+                response = unless_stopped(
+                    &turn.token,
+                    self.synthesize_without_tools(
+                        &mut session,
+                        memory_override.as_deref(),
+                        provider.as_ref(),
+                        stream.as_deref_mut(),
+                        model,
+                        &options,
+                        usage_metrics.as_ref(),
+                    ),
+                )
+                .await?;
+                turn_usage.add(&self.call_usage(&model_string, response.usage.as_ref()));
                 break;
             }
 
@@ -1796,10 +3196,7 @@ impl AgentLoop {
             }
 
             // Get fresh tool definitions for the next LLM call
-            let tool_definitions = {
-                let tools = self.tools.read().await;
-                tools.definitions_with_options(self.config.agents.defaults.compact_tools)
-            };
+            let tool_definitions = self.tool_definitions_for(&tool_access).await;
 
             // Check token budget before next LLM call
             if self.token_budget.is_exceeded() {
@@ -1809,7 +3206,7 @@ impl AgentLoop {
 
             // Call LLM again with tool results -- provider lock NOT held
             let mut messages = self
-                .build_resolved_messages(&session, memory_override.as_deref())
+                .build_resolved_messages(&session, memory_override.as_deref(), &model_string)
                 .await;
 
             // Pre-flight context guard (tool loop)
@@ -1835,33 +3232,39 @@ impl AgentLoop {
                             );
                         session.messages = recovered;
                         messages = self
-                            .build_resolved_messages(&session, memory_override.as_deref())
+                            .build_resolved_messages(
+                                &session,
+                                memory_override.as_deref(),
+                                &model_string,
+                            )
                             .await;
                     }
                 }
             }
 
             // Send thinking feedback for tool-loop LLM call
-            if let Some(tx) = self.tool_feedback_tx.read().await.as_ref() {
-                let _ = tx.send(ToolFeedback {
-                    tool_name: String::new(),
-                    phase: ToolFeedbackPhase::Thinking,
-                    args_json: None,
-                });
-            }
+            feedback.send(ToolFeedback {
+                tool_name: String::new(),
+                phase: ToolFeedbackPhase::Thinking,
+                args_json: None,
+            });
 
             response = {
                 let max_retries = self.config.compaction.overflow_retries;
                 let mut last_messages = messages;
                 let mut last_tool_defs = tool_definitions;
-                let mut result = provider
-                    .chat(
+                let mut result = unless_stopped(
+                    &turn.token,
+                    reply_stream::chat(
+                        provider.as_ref(),
+                        stream.as_deref_mut(),
                         last_messages.clone(),
                         last_tool_defs.clone(),
                         model,
                         options.clone(),
-                    )
-                    .await;
+                    ),
+                )
+                .await;
                 let mut attempt = 0u32;
                 while let Err(ref e) = result {
                     if !Self::is_context_overflow(e) || attempt >= max_retries {
@@ -1888,33 +3291,36 @@ impl AgentLoop {
                     );
                     session.messages = recovered;
                     last_messages = self
-                        .build_resolved_messages(&session, memory_override.as_deref())
+                        .build_resolved_messages(
+                            &session,
+                            memory_override.as_deref(),
+                            &model_string,
+                        )
                         .await;
-                    last_tool_defs = {
-                        let tools = self.tools.read().await;
-                        tools.definitions_with_options(self.config.agents.defaults.compact_tools)
-                    };
-                    result = provider
-                        .chat(
+                    last_tool_defs = self.tool_definitions_for(&tool_access).await;
+                    result = unless_stopped(
+                        &turn.token,
+                        reply_stream::chat(
+                            provider.as_ref(),
+                            stream.as_deref_mut(),
                             last_messages.clone(),
                             last_tool_defs.clone(),
                             model,
                             options.clone(),
-                        )
-                        .await;
+                        ),
+                    )
+                    .await;
                     attempt += 1;
                 }
                 result?
             };
 
             // Send thinking done feedback
-            if let Some(tx) = self.tool_feedback_tx.read().await.as_ref() {
-                let _ = tx.send(ToolFeedback {
-                    tool_name: String::new(),
-                    phase: ToolFeedbackPhase::ThinkingDone,
-                    args_json: None,
-                });
-            }
+            feedback.send(ToolFeedback {
+                tool_name: String::new(),
+                phase: ToolFeedbackPhase::ThinkingDone,
+                args_json: None,
+            });
 
             if let (Some(metrics), Some(usage)) = (usage_metrics.as_ref(), response.usage.as_ref())
             {
@@ -1926,29 +3332,116 @@ impl AgentLoop {
                 self.token_budget
                     .record(usage.prompt_tokens as u64, usage.completion_tokens as u64);
             }
+            turn_usage.add(&self.call_usage(&model_string, response.usage.as_ref()));
         }
 
-        if iteration >= max_iterations && response.has_tool_calls() {
+        // Out of tool iterations with more tools requested: have the model
+        // answer from what it has instead of returning the tool-call stub.
+        let hit_iteration_limit =
+            iteration >= max_iterations && response.has_tool_calls() && !turn.token.is_cancelled();
+        let mut limit_note = None;
+        if hit_iteration_limit {
             info!(
                 iterations = iteration,
-                "Tool loop reached maximum iterations, returning partial response"
+                "Tool loop reached maximum iterations, making final synthesis call"
             );
+            if self.token_budget.is_exceeded() {
+                info!(budget = %self.token_budget.summary(), "Token budget exceeded, skipping synthesis call");
+            } else {
+                session.add_message(Message::system(&tool_iteration_limit_prompt(
+                    max_iterations,
+                )));
+                response = unless_stopped(
+                    &turn.token,
+                    self.synthesize_without_tools(
+                        &mut session,
+                        memory_override.as_deref(),
+                        provider.as_ref(),
+                        stream.as_deref_mut(),
+                        model,
+                        &options,
+                        usage_metrics.as_ref(),
+                    ),
+                )
+                .await?;
+                turn_usage.add(&self.call_usage(&model_string, response.usage.as_ref()));
+            }
+            limit_note = Some(tool_iteration_limit_note(max_iterations));
+        }
+        session
+            .tool_iterations
+            .record_turn(iteration, hit_iteration_limit);
+
+        // A reply in a requested format must parse and match its schema. One
+        // that doesn't goes back to the model once with what is wrong.
+        if let Some(format) = response_format.as_ref() {
+            if let Err(problem) = validate_json_response(&response.content, format) {
+                if !turn.token.is_cancelled() {
+                    warn!(error = %problem, "Reply does not match the requested format, retrying");
+                    session.add_message(Message::assistant(&response.content));
+                    session.add_message(Message::user(&format_correction_prompt(&problem)));
+                    response = unless_stopped(
+                        &turn.token,
+                        self.synthesize_without_tools(
+                            &mut session,
+                            memory_override.as_deref(),
+                            provider.as_ref(),
+                            None,
+                            model,
+                            &options,
+                            usage_metrics.as_ref(),
+                        ),
+                    )
+                    .await?;
+                    turn_usage.add(&self.call_usage(&model_string, response.usage.as_ref()));
+                }
+            }
+            if !turn.token.is_cancelled() {
+                let value = validate_json_response(&response.content, format).map_err(|e| {
+                    ZeptoError::InvalidOutput(format!("reply still invalid after a retry: {}", e))
+                })?;
+                response.content = value.to_string();
+            }
         }
 
+        turn.close(
+            &mut session,
+            &mut response,
+            produced,
+            stream.as_deref().and_then(ReplyStream::unfinished_text),
+        );
+
         // Signal that tools are done and response is ready
-        if let Some(tx) = self.tool_feedback_tx.read().await.as_ref() {
-            let _ = tx.send(ToolFeedback {
-                tool_name: String::new(),
-                phase: ToolFeedbackPhase::ResponseReady,
-                args_json: None,
-            });
-        }
+        feedback.send(ToolFeedback {
+            tool_name: String::new(),
+            phase: ToolFeedbackPhase::ResponseReady,
+            args_json: None,
+        });
 
         // Add final assistant response
-        session.add_message(Message::assistant(&response.content));
-        self.session_manager.save(&session).await?;
-
-        Ok(response.content)
+        let mut reply_msg = Message::assistant(&response.content);
+        reply_msg.cancelled = turn.token.is_cancelled();
+        reply_msg.agent = profile.map(|(name, _)| name.to_string());
+        reply_msg.settings = Some(settings);
+        reply_msg.thinking = std::mem::take(&mut response.thinking);
+        record_turn_usage(&self.spend, &mut session, &mut reply_msg, turn_usage);
+        session.add_message(reply_msg);
+        self.session_manager
+            .save_from(&session, history_from)
+            .await?;
+        self.record_turn(&session, msg, &response.content, turn_started);
+
+        let mut reply = response.content;
+        // Notes would break a reply that must be JSON.
+        if response_format.is_none() {
+            if let Some(note) = limit_note {
+                reply.push_str(&note);
+            }
+            if self.config.cost.show_cost {
+                reply.push_str(&cost_note(&turn_usage, session.total_cost()));
+            }
+        }
+        Ok(reply)
     }
 
     /// Process a message with streaming output for the final LLM response.
@@ -1957,16 +3450,142 @@ impl AgentLoop {
     /// token-by-token through the returned receiver. Tool loop iterations are
     /// still non-streaming. The assembled final response is returned via
     /// `StreamEvent::Done`.
+    ///
+    /// Hooks run as for `process_message()`, except that `after_turn` can
+    /// only rewrite the `Done` content: the deltas have already been sent.
     pub async fn process_message_streaming(
         &self,
         msg: &InboundMessage,
     ) -> Result<tokio::sync::mpsc::Receiver<crate::providers::StreamEvent>> {
         use crate::providers::StreamEvent;
 
+        if let Some(reply) = self.turn_away(msg)? {
+            let (tx, rx) = tokio::sync::mpsc::channel(1);
+            let _ = tx
+                .send(StreamEvent::Done {
+                    content: reply,
+                    usage: None,
+                })
+                .await;
+            return Ok(rx);
+        }
+        let hooks = self.hooks.read().await.clone();
+        if hooks.is_empty() {
+            return self.stream_agent_turn(msg).await;
+        }
+        let mut msg = msg.clone();
+        match run_before_turn(&hooks, &mut msg).await? {
+            HookDecision::Continue => {}
+            HookDecision::Skip(reply) => {
+                let (tx, rx) = tokio::sync::mpsc::channel(1);
+                let _ = tx
+                    .send(StreamEvent::Done {
+                        content: reply,
+                        usage: None,
+                    })
+                    .await;
+                return Ok(rx);
+            }
+            HookDecision::Reject(reason) => return Err(ZeptoError::Rejected(reason)),
+        }
+
+        let mut events = self.stream_agent_turn(&msg).await?;
+        let (tx, rx) = tokio::sync::mpsc::channel(32);
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                let event = match event {
+                    StreamEvent::Done { content, usage } => {
+                        let mut response = OutboundResponse::reply_to(&msg, content);
+                        match run_after_turn(&hooks, &mut response).await {
+                            Ok(()) => StreamEvent::Done {
+                                content: response.content,
+                                usage,
+                            },
+                            Err(e) => StreamEvent::Error(e),
+                        }
+                    }
+                    other => other,
+                };
+                if tx.send(event).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(rx)
+    }
+
+    /// The streamed turn itself, after the `before_turn` hooks.
+    async fn stream_agent_turn(
+        &self,
+        msg: &InboundMessage,
+    ) -> Result<tokio::sync::mpsc::Receiver<crate::providers::StreamEvent>> {
+        use crate::providers::StreamEvent;
+
         // Acquire per-session lock
         let session_lock = self.session_lock_for(&msg.session_key).await;
         let _session_guard = session_lock.lock().await;
 
+        self.check_redelivery(msg).await?;
+
+        if let Some(reply) = self.command_reply(msg).await? {
+            let (tx, rx) = tokio::sync::mpsc::channel(1);
+            let _ = tx
+                .send(StreamEvent::Done {
+                    content: reply,
+                    usage: None,
+                })
+                .await;
+            return Ok(rx);
+        }
+
+        match self.welcome_for(msg).await? {
+            Some((welcome, OnboardingMode::Instead)) => {
+                let (tx, rx) = tokio::sync::mpsc::channel(1);
+                let _ = tx
+                    .send(StreamEvent::Done {
+                        content: welcome,
+                        usage: None,
+                    })
+                    .await;
+                Ok(rx)
+            }
+            Some((welcome, OnboardingMode::Before)) => Ok(onboarding::before_stream(
+                welcome,
+                self.stream_answer_turn(msg).await?,
+            )),
+            None => self.stream_answer_turn(msg).await,
+        }
+    }
+
+    /// The model's streamed answer to `msg`, under the session lock.
+    async fn stream_answer_turn(
+        &self,
+        msg: &InboundMessage,
+    ) -> Result<tokio::sync::mpsc::Receiver<crate::providers::StreamEvent>> {
+        use crate::providers::StreamEvent;
+
+        let profile = self.agent_profile_for(msg).await?;
+        let settings = self
+            .generation_settings_for(msg, profile.map(|(_, p)| p))
+            .await?;
+        let switched = with_model_override(
+            msg,
+            settings.model.as_deref(),
+            &self.config.agents.defaults.model,
+        );
+        let msg = switched.as_ref().unwrap_or(msg);
+        let tool_access = self.tool_access_for(msg, profile.map(|(_, p)| p));
+        let turn = ActiveTurn::begin(
+            &self.bus,
+            &msg.session_key,
+            turn_time_limit(&self.config.agents.defaults, &msg.channel),
+        );
+        let feedback = FeedbackSink::new(self.tool_feedback_tx.read().await.clone(), None);
+        self.check_monthly_budget()?;
+        let mut turn_usage = UsageCost::default();
+
+        let turn_started = std::time::Instant::now();
+
         // Reset per-run counters so limits apply to each process_message call
         // independently, not across the lifetime of the AgentLoop struct.
         self.tool_call_limit.reset();
@@ -1974,7 +3593,11 @@ impl AgentLoop {
 
         // Resolve the inbound message content first (inlines text attachments) so the
         // injection scanner sees the fully-expanded prompt, not just msg.content.
-        let user_message = inbound_to_message(msg, None).await;
+        let media_store = self
+            .session_manager
+            .sessions_dir()
+            .map(|dir| crate::session::media::MediaStore::new(dir.to_path_buf()));
+        let user_message = inbound_to_message(msg, media_store.as_ref()).await;
         let resolved_user_prompt = user_message.content.clone();
 
         // Tiered inbound injection scanning (streaming path).
@@ -2034,10 +3657,19 @@ impl AgentLoop {
         };
         let metrics_collector = Arc::clone(&self.metrics_collector);
 
-        let mut session = self.session_manager.get_or_create(&msg.session_key).await?;
+        let model_string = self.resolve_model_for_message(msg);
+        let (mut session, history_from) = self
+            .load_turn_session(&msg.session_key, &model_string)
+            .await?;
 
         // Add the user message BEFORE compaction so compaction sees the full context.
         session.add_message(user_message);
+        session.turns += 1;
+        self.trim_session(&mut session).await?;
+
+        // Record the turn when tracing is on for this session.
+        let turn_trace = self.begin_trace(&session);
+        let provider = trace::traced(provider, turn_trace.as_ref());
 
         // Apply three-tier context overflow recovery if needed (streaming)
         if let Some(ref monitor) = self.context_monitor {
@@ -2070,13 +3702,10 @@ impl AgentLoop {
         // Pass an empty user_input: the current user message is already in session.
         let memory_override = self.build_memory_override(&resolved_user_prompt).await;
         let mut messages = self
-            .build_resolved_messages(&session, memory_override.as_deref())
+            .build_resolved_messages(&session, memory_override.as_deref(), &model_string)
             .await;
 
-        let tool_definitions = {
-            let tools = self.tools.read().await;
-            tools.definitions_with_options(self.config.agents.defaults.compact_tools)
-        };
+        let tool_definitions = self.tool_definitions_for(&tool_access).await;
 
         // Pre-flight context guard (streaming)
         if let Some(ref monitor) = self.context_monitor {
@@ -2101,16 +3730,20 @@ impl AgentLoop {
                         );
                     session.messages = recovered;
                     messages = self
-                        .build_resolved_messages(&session, memory_override.as_deref())
+                        .build_resolved_messages(
+                            &session,
+                            memory_override.as_deref(),
+                            &model_string,
+                        )
                         .await;
                 }
             }
         }
 
-        let options = ChatOptions::new()
-            .with_max_tokens(self.config.agents.defaults.max_tokens)
-            .with_temperature(self.config.agents.defaults.temperature);
-        let model_string = self.resolve_model_for_message(msg);
+        // Streamed turns skip the response cache: the cache keeps only the
+        // final text, while callers here consume thinking and text deltas as
+        // they arrive, and a hit would have nothing to stream.
+        let options = self.with_stop_sequences(settings::chat_options(&settings), msg);
         let model = Some(model_string.as_str());
 
         // Check token budget before first LLM call
@@ -2121,27 +3754,27 @@ impl AgentLoop {
             )));
         }
 
-        if let Some(tx) = self.tool_feedback_tx.read().await.as_ref() {
-            let _ = tx.send(ToolFeedback {
-                tool_name: String::new(),
-                phase: ToolFeedbackPhase::Thinking,
-                args_json: None,
-            });
-        }
+        feedback.send(ToolFeedback {
+            tool_name: String::new(),
+            phase: ToolFeedbackPhase::Thinking,
+            args_json: None,
+        });
 
         // First call: non-streaming to see if there are tool calls, with overflow retry
         let mut response = {
             let max_retries = self.config.compaction.overflow_retries;
             let mut last_messages = messages;
             let mut last_tool_defs = tool_definitions;
-            let mut result = provider
-                .chat(
+            let mut result = unless_stopped(
+                &turn.token,
+                provider.chat(
                     last_messages.clone(),
                     last_tool_defs.clone(),
                     model,
                     options.clone(),
-                )
-                .await;
+                ),
+            )
+            .await;
 
             let mut attempt = 0u32;
             while let Err(ref e) = result {
@@ -2169,31 +3802,28 @@ impl AgentLoop {
                 );
                 session.messages = recovered;
                 last_messages = self
-                    .build_resolved_messages(&session, memory_override.as_deref())
+                    .build_resolved_messages(&session, memory_override.as_deref(), &model_string)
                     .await;
-                last_tool_defs = {
-                    let tools = self.tools.read().await;
-                    tools.definitions_with_options(self.config.agents.defaults.compact_tools)
-                };
-                result = provider
-                    .chat(
+                last_tool_defs = self.tool_definitions_for(&tool_access).await;
+                result = unless_stopped(
+                    &turn.token,
+                    provider.chat(
                         last_messages.clone(),
                         last_tool_defs.clone(),
                         model,
                         options.clone(),
-                    )
-                    .await;
+                    ),
+                )
+                .await;
                 attempt += 1;
             }
             result?
         };
-        if let Some(tx) = self.tool_feedback_tx.read().await.as_ref() {
-            let _ = tx.send(ToolFeedback {
-                tool_name: String::new(),
-                phase: ToolFeedbackPhase::ThinkingDone,
-                args_json: None,
-            });
-        }
+        feedback.send(ToolFeedback {
+            tool_name: String::new(),
+            phase: ToolFeedbackPhase::ThinkingDone,
+            args_json: None,
+        });
         if let (Some(metrics), Some(usage)) = (usage_metrics.as_ref(), response.usage.as_ref()) {
             metrics.record_tokens(usage.prompt_tokens as u64, usage.completion_tokens as u64);
         }
@@ -2203,14 +3833,18 @@ impl AgentLoop {
             self.token_budget
                 .record(usage.prompt_tokens as u64, usage.completion_tokens as u64);
         }
+        turn_usage.add(&self.call_usage(&model_string, response.usage.as_ref()));
 
         // User message was already added to session before build_messages above.
 
         // Tool loop (non-streaming)
-        let max_iterations = self.config.agents.defaults.max_tool_iterations;
+        let max_iterations = self.max_tool_iterations_for(&session.key).await?;
         let mut iteration = 0;
         let mut tool_limit_hit = false;
         let mut chain_tracker = crate::safety::chain_alert::ChainTracker::new();
+        // Text the model wrote alongside its tool calls, kept for the reply
+        // of a turn its time limit cuts off.
+        let mut produced = Vec::new();
         let mut loop_guard = if self.config.agents.defaults.loop_guard.enabled {
             Some(LoopGuard::new(
                 self.config.agents.defaults.loop_guard.clone(),
@@ -2263,290 +3897,115 @@ impl AgentLoop {
                     })
                     .collect(),
             );
+            // Kept for the provider: some APIs require it back with the tool
+            // results in the next call.
+            assistant_msg.thinking = response.thinking.clone();
             session.add_message(assistant_msg);
+            produced.push(response.content.clone());
 
             let workspace = self.config.workspace_path();
             let workspace_str = workspace.to_string_lossy();
             let tool_ctx = ToolContext::new()
                 .with_channel(&msg.channel, &msg.chat_id)
-                .with_workspace(&workspace_str)
-                .with_batch(msg.metadata.get("is_batch").is_some_and(|v| v == "true"));
+                .with_sandbox(self.sandbox(&workspace_str))
+                .with_batch(msg.metadata.get("is_batch").is_some_and(|v| v == "true"))
+                .with_processes(Arc::clone(&self.processes))
+                .with_artifacts(Arc::clone(&self.artifacts), &msg.session_key)
+                .with_command_log(self.session_manager.command_log(), &msg.session_key)
+                .with_dry_run(self.dry_run_forced() || session.dry_run.unwrap_or(false))
+                .with_admin(Access::of(&self.config.agents.authorization, msg).is_admin())
+                .with_cancellation(turn.token.clone());
 
-            let approval_gate = Arc::clone(&self.approval_gate);
             let approval_handler = self.approval_handler.read().await.clone();
-            let safety_layer_stream = self.safety_layer.clone();
-            let taint_engine_stream = self.taint.clone();
-            let hook_engine = Arc::new(
-                crate::hooks::HookEngine::new(self.config.hooks.clone())
-                    .with_bus(Arc::clone(&self.bus)),
-            );
+            let approval_policy = self.approval_policy.read().await.clone();
+            let trusted_local_session = is_trusted_local_session(msg);
+
+            // Approval prompts are answered one at a time, so batches that
+            // need approval run in order; otherwise only tools that are not
+            // parallel-safe are serialized.
+            let run_sequential = !trusted_local_session
+                && (approval_handler.is_some() || approval_policy.is_some())
+                && response
+                    .tool_calls
+                    .iter()
+                    .any(|tool_call| self.approval_gate.requires_approval(&tool_call.name));
+            let parallel_safe = parallel_safety(&self.tools, &response.tool_calls).await;
 
             // Compute dynamic tool result budget based on remaining context space
-            let current_tokens_stream = ContextMonitor::estimate_tokens_with_margin(
+            let current_tokens = ContextMonitor::estimate_tokens_with_margin(
                 &session.messages,
                 self.config.compaction.safety_margin,
             );
-            let context_limit_stream = self.config.compaction.context_limit;
-            let max_result_bytes_stream = self.config.agents.defaults.max_tool_result_bytes;
-            let result_budget_stream =
-                crate::utils::sanitize::compute_tool_result_budget_with_share(
-                    context_limit_stream,
-                    current_tokens_stream,
-                    response.tool_calls.len(),
-                    max_result_bytes_stream,
-                    self.config.compaction.single_tool_result_share,
-                );
-
-            let tool_feedback_tx = self.tool_feedback_tx.clone();
-            #[cfg(feature = "panel")]
-            let event_bus_clone_stream = self.event_bus.clone();
-            let is_dry_run_stream = self.dry_run.load(Ordering::SeqCst);
-            let current_agent_mode_stream = self.agent_mode;
-            let trusted_local_session = is_trusted_local_session(msg);
+            let result_budget = crate::utils::sanitize::compute_tool_result_budget_with_share(
+                self.config.compaction.context_limit,
+                current_tokens,
+                response.tool_calls.len(),
+                self.config.agents.defaults.max_tool_result_bytes,
+                self.config.compaction.single_tool_result_share,
+            );
 
-            let run_sequential = (!trusted_local_session
-                && approval_handler.is_some()
-                && response
-                    .tool_calls
-                    .iter()
-                    .any(|tool_call| approval_gate.requires_approval(&tool_call.name)))
-                || needs_sequential_execution(&self.tools, &response.tool_calls).await;
-            let tool_timeout_secs = if self.config.agents.defaults.tool_timeout_secs > 0 {
-                self.config.agents.defaults.tool_timeout_secs
-            } else {
-                self.config.agents.defaults.agent_timeout_secs
+            let batch = ToolBatch {
+                tools: &self.tools,
+                ctx: tool_ctx,
+                msg,
+                bus: &self.bus,
+                defaults: &self.config.agents.defaults,
+                turn_started,
+                access: &tool_access,
+                expired: &turn.expired,
+                feedback: &feedback,
+                usage_metrics: usage_metrics.as_deref(),
+                metrics: &metrics_collector,
+                approval_gate: &self.approval_gate,
+                approval_handler,
+                approval_policy,
+                hooks: crate::hooks::HookEngine::new(self.config.hooks.clone())
+                    .with_bus(Arc::clone(&self.bus)),
+                safety: self.safety_layer.as_deref(),
+                taint: self.taint.as_deref(),
+                #[cfg(feature = "panel")]
+                event_bus: self.event_bus.as_ref(),
+                agent_mode: self.agent_mode,
+                trusted_local_session,
+                result_budget,
             };
-            let tool_timeout = std::time::Duration::from_secs(tool_timeout_secs.max(1));
-
-            // Clone inbound metadata for routing propagation in tool `for_user` messages.
-            let inbound_metadata_stream = msg.metadata.clone();
 
-            let tool_futures: Vec<_> = response
+            // Identical calls the model keeps asking for are answered, not run.
+            let repeats: Vec<Option<u32>> = response
                 .tool_calls
                 .iter()
                 .map(|tool_call| {
-                    let tools = Arc::clone(&self.tools);
-                    let ctx = tool_ctx.clone();
-                    let name = tool_call.name.clone();
-                    let id = tool_call.id.clone();
-                    let raw_args = tool_call.arguments.clone();
-                    let usage_metrics = usage_metrics.clone();
-                    let metrics_collector = Arc::clone(&metrics_collector);
-                    let gate = Arc::clone(&approval_gate);
-                    let approval_handler = approval_handler.clone();
-                    let hooks = Arc::clone(&hook_engine);
-                    let safety = safety_layer_stream.clone();
-                    let taint = taint_engine_stream.clone();
-                    let budget = result_budget_stream;
-                    let tool_feedback_tx = tool_feedback_tx.clone();
-                    #[cfg(feature = "panel")]
-                    let event_bus = event_bus_clone_stream.clone();
-                    let dry_run = is_dry_run_stream;
-                    let agent_mode = current_agent_mode_stream;
-                    let bus_for_tools = Arc::clone(&self.bus);
-                    let inbound_meta = inbound_metadata_stream.clone();
-
-                    async move {
-                        let args: serde_json::Value = match serde_json::from_str(&raw_args) {
-                            Ok(v) => v,
-                            Err(e) => {
-                                tracing::warn!(tool = %name, error = %e, "Invalid JSON in tool arguments");
-                                serde_json::json!({"_parse_error": format!("Invalid arguments JSON: {}", e)})
-                            }
-                        };
-
-                        let channel_name = ctx.channel.as_deref().unwrap_or("cli");
-                        let chat_id = ctx.chat_id.as_deref().unwrap_or(channel_name);
-                        if let crate::hooks::HookResult::Block(msg) =
-                            hooks.before_tool(&name, &args, channel_name, chat_id)
-                        {
-                            return (id, format!("Tool '{}' blocked by hook: {}", name, msg), false);
-                        }
-
-                        // Agent mode enforcement — same fail-closed logic as non-streaming path.
-                        {
-                            let mode_policy = crate::security::ModePolicy::new(agent_mode);
-                            let tools_guard = tools.read().await;
-                            if let Some(tool) = tools_guard.get(&name) {
-                                let tool_category = tool.category();
-                                match mode_policy.check(tool_category) {
-                                    crate::security::CategoryPermission::Blocked => {
-                                        info!(tool = %name, mode = %agent_mode, category = ?tool_category, "Tool blocked by agent mode");
-                                        return (id, format!(
-                                            "Tool '{}' is blocked in {} mode (category: {})",
-                                            name, agent_mode, tool_category
-                                        ), false);
-                                    }
-                                    crate::security::CategoryPermission::RequiresApproval => {
-                                        if trusted_local_session {
-                                            info!(tool = %name, mode = %agent_mode, category = ?tool_category, "Trusted local session bypassed approval-gated tool");
-                                        } else if !gate.requires_approval(&name) {
-                                            info!(tool = %name, mode = %agent_mode, category = ?tool_category, "Tool requires approval per agent mode");
-                                            return (id, format!(
-                                                "Tool '{}' requires approval in {} mode (category: {}). Not executed.",
-                                                name, agent_mode, tool_category
-                                            ), false);
-                                        }
-                                    }
-                                    crate::security::CategoryPermission::Allowed => {}
-                                }
-                            }
-                        }
-
-                        // Check approval gate before executing
-                        if !trusted_local_session {
-                            if let Some(message) = resolve_tool_approval(
-                                &gate,
-                                approval_handler.as_ref(),
-                                &name,
-                                &args,
-                            )
-                            .await
-                            {
-                                info!(tool = %name, "Tool requires approval, blocking execution");
-                                return (id, message, false);
-                            }
-                        }
-
-                        // Dry-run mode: describe what would happen without executing
-                        if dry_run {
-                            return (id, Self::dry_run_result(&name, &args, &raw_args, budget), false);
-                        }
-
-                        // Send tool starting feedback
-                        if let Some(tx) = tool_feedback_tx.read().await.as_ref() {
-                            let _ = tx.send(ToolFeedback {
-                                tool_name: name.clone(),
-                                phase: ToolFeedbackPhase::Starting,
-                                args_json: Some(raw_args.clone()),
-                            });
-                        }
-                        #[cfg(feature = "panel")]
-                        if let Some(bus) = &event_bus {
-                            bus.send(crate::api::events::PanelEvent::ToolStarted {
-                                tool: name.clone(),
-                            });
-                        }
-                        let tool_start = std::time::Instant::now();
-                        let execution = std::panic::AssertUnwindSafe(async {
-                            let tools_guard = tools.read().await;
-                            crate::kernel::execute_tool(
-                                &tools_guard,
-                                &name,
-                                args,
-                                &ctx,
-                                safety.as_ref().map(|s| s.as_ref()),
-                                &metrics_collector,
-                                taint.as_ref().map(|t| t.as_ref()),
-                            )
-                            .await
+                    loop_guard.as_mut().and_then(|guard| {
+                        guard.check_repeat(ToolCallSig {
+                            name: &tool_call.name,
+                            arguments: &tool_call.arguments,
                         })
-                        .catch_unwind();
-                        let (result, success, tool_output) = match tokio::time::timeout(tool_timeout, execution).await {
-                            Ok(Ok(Ok(output))) => {
-                                let success = !output.is_error;
-                                let for_llm = output.for_llm.clone();
-                                (for_llm, success, Some(output))
-                            }
-                            Ok(Ok(Err(e))) => (format!("Error: {}", e), false, None),
-                            Ok(Err(_panic)) => {
-                                error!(tool = %name, "Tool panicked during execution");
-                                (format!("Error: Tool '{}' panicked during execution", name), false, None)
-                            }
-                            Err(_) => {
-                                error!(tool = %name, timeout_secs = tool_timeout.as_secs(), "Tool execution timed out");
-                                (format!("Error: Tool '{}' timed out after {}s", name, tool_timeout.as_secs()), false, None)
-                            }
-                        };
-                        let pause = tool_output.as_ref().is_some_and(|o| o.pause_for_input);
-                        let elapsed = tool_start.elapsed();
-                        let latency_ms = elapsed.as_millis() as u64;
-                        if let Some(output) = tool_output {
-                            // Send to user if tool opted in
-                            if let Some(ref user_msg) = output.for_user {
-                                let mut outbound = crate::bus::OutboundMessage::new(
-                                    ctx.channel.as_deref().unwrap_or(""),
-                                    ctx.chat_id.as_deref().unwrap_or(""),
-                                    user_msg,
-                                );
-                                // Propagate routing metadata (e.g. telegram_thread_id, telegram_message_id)
-                                if let Some(tid) = inbound_meta.get("telegram_thread_id") {
-                                    outbound
-                                        .metadata
-                                        .insert("telegram_thread_id".to_string(), tid.clone());
-                                }
-                                if let Some(mid) = inbound_meta.get("telegram_message_id") {
-                                    outbound
-                                        .metadata
-                                        .insert("telegram_message_id".to_string(), mid.clone());
-                                }
-                                // Keep typing indicator alive — agent is still working
-                                outbound
-                                    .metadata
-                                    .insert("keep_typing".to_string(), "true".to_string());
-                                let _ = bus_for_tools.publish_outbound(outbound).await;
-                            }
-                        }
-                        if success {
-                            debug!(tool = %name, latency_ms = latency_ms, "Tool executed successfully");
-                            hooks.after_tool(&name, &result, elapsed, channel_name, chat_id);
-                            if let Some(tx) = tool_feedback_tx.read().await.as_ref() {
-                                let _ = tx.send(ToolFeedback {
-                                    tool_name: name.clone(),
-                                    phase: ToolFeedbackPhase::Done {
-                                        elapsed_ms: latency_ms,
-                                    },
-                                    args_json: Some(raw_args.clone()),
-                                });
-                            }
-                            #[cfg(feature = "panel")]
-                            if let Some(bus) = &event_bus {
-                                bus.send(crate::api::events::PanelEvent::ToolDone {
-                                    tool: name.clone(),
-                                    duration_ms: latency_ms,
-                                });
-                            }
-                        } else {
-                            error!(tool = %name, latency_ms = latency_ms, error = %result, "Tool execution failed");
-                            hooks.on_error(&name, &result, channel_name, chat_id);
-                            if let Some(metrics) = usage_metrics.as_ref() {
-                                metrics.record_error();
-                            }
-                            if let Some(tx) = tool_feedback_tx.read().await.as_ref() {
-                                let _ = tx.send(ToolFeedback {
-                                    tool_name: name.clone(),
-                                    phase: ToolFeedbackPhase::Failed {
-                                        elapsed_ms: latency_ms,
-                                        error: result.clone(),
-                                    },
-                                    args_json: Some(raw_args.clone()),
-                                });
-                            }
-                            #[cfg(feature = "panel")]
-                            if let Some(bus) = &event_bus {
-                                bus.send(crate::api::events::PanelEvent::ToolFailed {
-                                    tool: name.clone(),
-                                    error: result.clone(),
-                                });
-                            }
-                        }
-                        let sanitized =
-                            crate::utils::sanitize::sanitize_tool_result(&result, budget);
-
-                        (id, sanitized, pause)
-                    }
+                    })
                 })
                 .collect();
 
-            let results = if run_sequential {
+            let tool_futures: Vec<_> = response
+                .tool_calls
+                .iter()
+                .zip(repeats)
+                .map(|(tool_call, repeat)| execute_tool_call(&batch, tool_call, repeat))
+                .collect();
+
+            let timed_results = if run_sequential {
                 let mut out = Vec::with_capacity(tool_futures.len());
                 for fut in tool_futures {
-                    out.push(fut.await);
+                    out.push(timed(fut).await);
                 }
                 out
             } else {
-                futures::future::join_all(tool_futures).await
+                run_tool_batch(
+                    tool_futures,
+                    &parallel_safe,
+                    self.config.agents.defaults.max_parallel_tools,
+                )
+                .await
             };
+            let (results, elapsed): (Vec<_>, Vec<_>) = timed_results.into_iter().unzip();
 
             // Record tool names for chain alerting (streaming path)
             let tool_names: Vec<String> = response
@@ -2556,9 +4015,23 @@ impl AgentLoop {
                 .collect();
             chain_tracker.record(&tool_names);
             let results: Vec<(String, String, bool)> = results;
+            if let Some(turn_trace) = &turn_trace {
+                turn_trace.record_tools(&response.tool_calls, &results, &elapsed);
+            }
             let should_pause = results.iter().any(|(_, _, pause)| *pause);
-            for (id, result, _) in &results {
-                session.add_message(Message::tool_result(id, result));
+            for ((id, result, _), elapsed) in results.iter().zip(elapsed) {
+                let message = Message::tool_result(id, result).with_tool_elapsed(elapsed);
+                if self.config.session.dedup_tool_results {
+                    session.add_message_deduped(message);
+                } else {
+                    session.add_message(message);
+                }
+            }
+            if tool_names
+                .iter()
+                .any(|name| name == "memory" || name == "todo")
+            {
+                self.refresh_session_notes(&mut session).await;
             }
 
             // In-loop compaction: check if tool results pushed context over threshold
@@ -2586,10 +4059,20 @@ impl AgentLoop {
                 }
             }
 
+            if turn.token.is_cancelled() {
+                info!(session = %msg.session_key, time_limit = turn.expired(), "Turn stopped");
+                response.content = TURN_STOPPED_REPLY.to_string();
+                break;
+            }
+
             if should_pause {
                 break;
             }
 
+            // In steer mode, messages sent meanwhile join the turn here, so
+            // the model sees them before its next step.
+            self.steer(msg, &mut session).await;
+
             // Increment tool call counter after execution.
             self.tool_call_limit
                 .increment(response.tool_calls.len() as u32);
@@ -2632,10 +4115,7 @@ impl AgentLoop {
                 }
             }
 
-            let tool_definitions = {
-                let tools = self.tools.read().await;
-                tools.definitions_with_options(self.config.agents.defaults.compact_tools)
-            };
+            let tool_definitions = self.tool_definitions_for(&tool_access).await;
 
             // Check token budget before next LLM call
             if self.token_budget.is_exceeded() {
@@ -2644,7 +4124,7 @@ impl AgentLoop {
             }
 
             let mut messages = self
-                .build_resolved_messages(&session, memory_override.as_deref())
+                .build_resolved_messages(&session, memory_override.as_deref(), &model_string)
                 .await;
 
             // Pre-flight context guard (streaming tool loop)
@@ -2670,34 +4150,38 @@ impl AgentLoop {
                             );
                         session.messages = recovered;
                         messages = self
-                            .build_resolved_messages(&session, memory_override.as_deref())
+                            .build_resolved_messages(
+                                &session,
+                                memory_override.as_deref(),
+                                &model_string,
+                            )
                             .await;
                     }
                 }
             }
 
-            if let Some(tx) = self.tool_feedback_tx.read().await.as_ref() {
-                let _ = tx.send(ToolFeedback {
-                    tool_name: String::new(),
-                    phase: ToolFeedbackPhase::Thinking,
-                    args_json: None,
-                });
-            }
+            feedback.send(ToolFeedback {
+                tool_name: String::new(),
+                phase: ToolFeedbackPhase::Thinking,
+                args_json: None,
+            });
 
             response = {
                 let max_retries = self.config.compaction.overflow_retries;
                 let mut last_messages = messages;
                 let mut last_tool_defs = tool_definitions;
-                let mut result = provider
-                    .chat(
+                let mut result = unless_stopped(
+                    &turn.token,
+                    provider.chat(
                         last_messages.clone(),
                         last_tool_defs.clone(),
                         model,
                         options.clone(),
-                    )
-                    .await;
-                let mut attempt = 0u32;
-                while let Err(ref e) = result {
+                    ),
+                )
+                .await;
+                let mut attempt = 0u32;
+                while let Err(ref e) = result {
                     if !Self::is_context_overflow(e) || attempt >= max_retries {
                         break;
                     }
@@ -2722,31 +4206,32 @@ impl AgentLoop {
                     );
                     session.messages = recovered;
                     last_messages = self
-                        .build_resolved_messages(&session, memory_override.as_deref())
+                        .build_resolved_messages(
+                            &session,
+                            memory_override.as_deref(),
+                            &model_string,
+                        )
                         .await;
-                    last_tool_defs = {
-                        let tools = self.tools.read().await;
-                        tools.definitions_with_options(self.config.agents.defaults.compact_tools)
-                    };
-                    result = provider
-                        .chat(
+                    last_tool_defs = self.tool_definitions_for(&tool_access).await;
+                    result = unless_stopped(
+                        &turn.token,
+                        provider.chat(
                             last_messages.clone(),
                             last_tool_defs.clone(),
                             model,
                             options.clone(),
-                        )
-                        .await;
+                        ),
+                    )
+                    .await;
                     attempt += 1;
                 }
                 result?
             };
-            if let Some(tx) = self.tool_feedback_tx.read().await.as_ref() {
-                let _ = tx.send(ToolFeedback {
-                    tool_name: String::new(),
-                    phase: ToolFeedbackPhase::ThinkingDone,
-                    args_json: None,
-                });
-            }
+            feedback.send(ToolFeedback {
+                tool_name: String::new(),
+                phase: ToolFeedbackPhase::ThinkingDone,
+                args_json: None,
+            });
             if let (Some(metrics), Some(usage)) = (usage_metrics.as_ref(), response.usage.as_ref())
             {
                 metrics.record_tokens(usage.prompt_tokens as u64, usage.completion_tokens as u64);
@@ -2757,40 +4242,56 @@ impl AgentLoop {
                 self.token_budget
                     .record(usage.prompt_tokens as u64, usage.completion_tokens as u64);
             }
+            turn_usage.add(&self.call_usage(&model_string, response.usage.as_ref()));
         }
 
-        if let Some(tx) = self.tool_feedback_tx.read().await.as_ref() {
-            let _ = tx.send(ToolFeedback {
-                tool_name: String::new(),
-                phase: ToolFeedbackPhase::ResponseReady,
-                args_json: None,
-            });
+        // Out of tool iterations: stream a final answer without tools.
+        let hit_iteration_limit =
+            iteration >= max_iterations && response.has_tool_calls() && !turn.token.is_cancelled();
+        let mut limit_note = None;
+        if hit_iteration_limit && !self.token_budget.is_exceeded() {
+            info!(
+                iterations = iteration,
+                "Tool loop reached maximum iterations, streaming final answer without tools"
+            );
+            session.add_message(Message::system(&tool_iteration_limit_prompt(
+                max_iterations,
+            )));
+            response.tool_calls.clear();
+            tool_limit_hit = true;
+            limit_note = Some(tool_iteration_limit_note(max_iterations));
         }
+        session
+            .tool_iterations
+            .record_turn(iteration, hit_iteration_limit);
+
+        feedback.send(ToolFeedback {
+            tool_name: String::new(),
+            phase: ToolFeedbackPhase::ResponseReady,
+            args_json: None,
+        });
 
         // Final call: if no more tool calls, use streaming
-        if !response.has_tool_calls() {
+        if !response.has_tool_calls() && !turn.token.is_cancelled() {
             // Re-issue the final call via chat_stream.
             // If the tool call limit was hit, pass empty tools so the model
             // cannot emit further tool calls after the cap was enforced.
             let messages = self
-                .build_resolved_messages(&session, memory_override.as_deref())
+                .build_resolved_messages(&session, memory_override.as_deref(), &model_string)
                 .await;
 
             let tool_definitions = if tool_limit_hit {
                 vec![]
             } else {
-                let tools = self.tools.read().await;
-                tools.definitions_with_options(self.config.agents.defaults.compact_tools)
+                self.tool_definitions_for(&tool_access).await
             };
 
             // Signal that tools are done and response is ready (streaming path)
-            if let Some(tx) = self.tool_feedback_tx.read().await.as_ref() {
-                let _ = tx.send(ToolFeedback {
-                    tool_name: String::new(),
-                    phase: ToolFeedbackPhase::ResponseReady,
-                    args_json: None,
-                });
-            }
+            feedback.send(ToolFeedback {
+                tool_name: String::new(),
+                phase: ToolFeedbackPhase::ResponseReady,
+                args_json: None,
+            });
 
             let stream_rx = provider
                 .chat_stream(messages, tool_definitions, model, options)
@@ -2802,12 +4303,74 @@ impl AgentLoop {
             let session_clone = session.clone();
             let usage_metrics = usage_metrics.clone();
             let metrics_collector = Arc::clone(&metrics_collector);
+            let analytics = self.analytics.clone();
+            let request_length = msg.content.chars().count();
+            let spend = Arc::clone(&self.spend);
+            let pricing = self.config.cost.custom_pricing.clone();
+            let model_name = model_string.clone();
+            let agent = profile.map(|(name, _)| name.to_string());
+            let turn_settings = settings.clone();
+            // The turn's time limit keeps running while the reply streams.
+            let deadline = turn
+                .limit
+                .map(|limit| (tokio::time::Instant::from_std(turn_started) + limit, limit));
 
             tokio::spawn(async move {
                 let mut session = session_clone;
                 let mut stream_rx = stream_rx;
+                let mut streamed = String::new();
+                let mut thinking = Vec::new();
 
-                while let Some(event) = stream_rx.recv().await {
+                loop {
+                    let received = match deadline {
+                        Some((at, _)) => tokio::time::timeout_at(at, stream_rx.recv()).await.ok(),
+                        None => Some(stream_rx.recv().await),
+                    };
+                    let Some(received) = received else {
+                        // Out of time: end the reply with what it has so far.
+                        let limit = deadline.map(|(_, limit)| limit).unwrap_or_default();
+                        warn!(
+                            session = %session.key,
+                            limit_secs = limit.as_secs(),
+                            "Turn stopped at its time limit"
+                        );
+                        let shown = !streamed.trim().is_empty();
+                        let mut produced = produced;
+                        produced.push(streamed);
+                        let content = time_limit_reply(&produced, limit);
+                        let mut reply_msg = Message::assistant(&content);
+                        reply_msg.cancelled = true;
+                        reply_msg.agent = agent;
+                        reply_msg.settings = Some(turn_settings);
+                        record_turn_usage(&spend, &mut session, &mut reply_msg, turn_usage);
+                        session.add_message(reply_msg);
+                        let _ = session_manager.save_from(&session, history_from).await;
+                        if let Some(ref sink) = analytics {
+                            record_turn_analytics(
+                                sink.as_ref(),
+                                &session,
+                                request_length,
+                                &content,
+                                turn_started.elapsed(),
+                            );
+                        }
+                        let rest = if shown {
+                            format!("\n\n{}", TIME_LIMIT_NOTE)
+                        } else {
+                            content.clone()
+                        };
+                        let _ = out_tx.send(StreamEvent::Delta(rest)).await;
+                        let _ = out_tx
+                            .send(StreamEvent::Done {
+                                content,
+                                usage: None,
+                            })
+                            .await;
+                        return;
+                    };
+                    let Some(event) = received else {
+                        break;
+                    };
                     match &event {
                         StreamEvent::Done { content, usage } => {
                             if let Some(usage) = usage.as_ref() {
@@ -2822,8 +4385,34 @@ impl AgentLoop {
                                     usage.completion_tokens as u64,
                                 );
                             }
-                            session.add_message(Message::assistant(content));
-                            let _ = session_manager.save(&session).await;
+                            let mut turn_usage = turn_usage;
+                            turn_usage.add(&usage_cost(&model_name, usage.as_ref(), &pricing));
+                            let mut reply_msg = Message::assistant(content);
+                            reply_msg.agent = agent;
+                            reply_msg.settings = Some(turn_settings);
+                            reply_msg.thinking = thinking;
+                            record_turn_usage(&spend, &mut session, &mut reply_msg, turn_usage);
+                            session.add_message(reply_msg);
+                            let _ = session_manager.save_from(&session, history_from).await;
+                            if let Some(ref sink) = analytics {
+                                record_turn_analytics(
+                                    sink.as_ref(),
+                                    &session,
+                                    request_length,
+                                    content,
+                                    turn_started.elapsed(),
+                                );
+                            }
+                            let event = match limit_note {
+                                Some(ref note) => {
+                                    let _ = out_tx.send(StreamEvent::Delta(note.clone())).await;
+                                    StreamEvent::Done {
+                                        content: format!("{}{}", content, note),
+                                        usage: usage.clone(),
+                                    }
+                                }
+                                None => event,
+                            };
                             let _ = out_tx.send(event).await;
                             return;
                         }
@@ -2833,6 +4422,13 @@ impl AgentLoop {
                             return;
                         }
                         _ => {
+                            match &event {
+                                StreamEvent::Delta(delta) => streamed.push_str(delta),
+                                StreamEvent::Thinking(blocks) => {
+                                    thinking.extend(blocks.iter().cloned())
+                                }
+                                _ => {}
+                            }
                             if out_tx.send(event).await.is_err() {
                                 return;
                             }
@@ -2843,9 +4439,20 @@ impl AgentLoop {
 
             Ok(out_rx)
         } else {
-            // Still has tool calls after max iterations — return non-streaming result
-            session.add_message(Message::assistant(&response.content));
-            self.session_manager.save(&session).await?;
+            // Stopped, or still has tool calls after max iterations — return
+            // non-streaming result
+            turn.close(&mut session, &mut response, produced, None);
+            let mut reply_msg = Message::assistant(&response.content);
+            reply_msg.cancelled = turn.token.is_cancelled();
+            reply_msg.agent = profile.map(|(name, _)| name.to_string());
+            reply_msg.settings = Some(settings);
+            reply_msg.thinking = std::mem::take(&mut response.thinking);
+            record_turn_usage(&self.spend, &mut session, &mut reply_msg, turn_usage);
+            session.add_message(reply_msg);
+            self.session_manager
+                .save_from(&session, history_from)
+                .await?;
+            self.record_turn(&session, msg, &response.content, turn_started);
 
             let (tx, rx) = tokio::sync::mpsc::channel(1);
             let _ = tx
@@ -2858,6 +4465,578 @@ impl AgentLoop {
         }
     }
 
+    /// Tool availability for the conversation `msg` belongs to, from
+    /// `tools.access` and the answering agent profile's tool list.
+    fn tool_access_for(&self, msg: &InboundMessage, profile: Option<&AgentProfile>) -> ToolAccess {
+        let mut access =
+            ToolAccess::resolve(&self.config.tools.access, &msg.channel, &msg.session_key);
+        if let Some(tools) = profile.and_then(|p| p.tools.as_deref()) {
+            access.restrict(tools);
+        }
+        let authorization = &self.config.agents.authorization;
+        if let Some(tools) = Access::of(authorization, msg).tools(authorization) {
+            access.restrict(&tools);
+        }
+        access
+    }
+
+    /// The reply to a sender `agents.authorization` turns away (see
+    /// [`super::authorization`]), or `None` if they may talk to the agent.
+    /// Without a rejection message they get [`ZeptoError::Rejected`] and no
+    /// reply.
+    fn turn_away(&self, msg: &InboundMessage) -> Result<Option<String>> {
+        let config = &self.config.agents.authorization;
+        if Access::of(config, msg) != Access::Denied {
+            return Ok(None);
+        }
+        info!(channel = %msg.channel, sender = %msg.sender_id, "Sender not authorized");
+        if config.rejection_message.is_empty() {
+            return Err(ZeptoError::Rejected(format!(
+                "sender '{}' on {} is not authorized",
+                msg.sender_id, msg.channel
+            )));
+        }
+        Ok(Some(config.rejection_message.clone()))
+    }
+
+    /// Record `msg`'s channel message ID in its session, failing with
+    /// [`ZeptoError::Duplicate`] if the session has seen it before. Runs under
+    /// the session lock, so the ID is saved before the turn starts.
+    async fn check_redelivery(&self, msg: &InboundMessage) -> Result<()> {
+        let window = self.config.agents.defaults.dedup_window;
+        let Some(id) = msg.message_id().filter(|_| window > 0) else {
+            return Ok(());
+        };
+        let fresh = self
+            .session_manager
+            .with_session_mut(&msg.session_key, |session| {
+                session.record_message_id(id, window)
+            })
+            .await?;
+        if fresh {
+            return Ok(());
+        }
+        info!(
+            session = %msg.session_key,
+            channel = %msg.channel,
+            duplicate_id = %id,
+            "Ignoring message delivered twice"
+        );
+        Err(ZeptoError::Duplicate(id.to_string()))
+    }
+
+    /// The channel's welcome (see [`onboarding`]) if `msg` starts a new
+    /// conversation, marking the session so it is sent once.
+    async fn welcome_for(&self, msg: &InboundMessage) -> Result<Option<(String, OnboardingMode)>> {
+        let Some(config) = self.config.agents.onboarding.get(&msg.channel) else {
+            return Ok(None);
+        };
+        if msg.is_heartbeat() {
+            return Ok(None);
+        }
+        let new = self
+            .session_manager
+            .with_session(&msg.session_key, |session| {
+                !session.onboarded && session.messages.is_empty() && session.summary.is_none()
+            })
+            .await?
+            .unwrap_or(true);
+        if !new {
+            return Ok(None);
+        }
+        self.session_manager
+            .with_session_mut(&msg.session_key, |session| session.onboarded = true)
+            .await?;
+
+        let profile = self.agent_profile_for(msg).await?;
+        let access = self.tool_access_for(msg, profile.map(|(_, p)| p));
+        let commands = self.command_list().await;
+        let tools = self.tools.read().await;
+        let mut names = tools.names();
+        names.sort_unstable();
+        names.retain(|name| access.allows(name));
+        info!(session = %msg.session_key, channel = %msg.channel, "Welcoming new conversation");
+        Ok(Some((
+            onboarding::render(config, &commands, &names),
+            config.mode,
+        )))
+    }
+
+    /// Every chat command, one per line (see [`format_command_list`]).
+    async fn command_list(&self) -> String {
+        format_command_list(&self.commands.read().await)
+    }
+
+    /// The reply to `msg` if it is a registered chat command: the command's
+    /// reply, or why the sender may not run it. With
+    /// `agents.defaults.strict_commands`, anything that only looks like a
+    /// command gets a usage or unknown-command reply too.
+    async fn command_reply(&self, msg: &InboundMessage) -> Result<Option<String>> {
+        let Some((name, args)) = commands::parse(&msg.content) else {
+            return Ok(None);
+        };
+        let command = self
+            .commands
+            .read()
+            .await
+            .iter()
+            .find(|c| c.name() == name)
+            .cloned();
+        let strict = self.config.agents.defaults.strict_commands;
+        let Some(command) = command else {
+            return Ok(
+                strict.then(|| format!("Unknown command /{}. Send /help for the commands.", name))
+            );
+        };
+        let config = &self.config.agents.authorization;
+        let access = Access::of(config, msg);
+        let slash_name = format!("/{}", name);
+        if let Some(refusal) = access.refuse_command(config, &slash_name, command.is_admin_only()) {
+            info!(session = %msg.session_key, sender = %msg.sender_id, command = name, "Command refused");
+            return Ok(Some(refusal));
+        }
+        if command.is_session() {
+            return match parse_session_command(&msg.content) {
+                Some(session_command) => self
+                    .handle_session_command(msg, session_command)
+                    .await
+                    .map(Some),
+                // A session command with arguments it doesn't take.
+                None => Ok(strict.then(|| format!("Usage: {}", command.usage()))),
+            };
+        }
+        let profile = self
+            .agent_profile_for(msg)
+            .await?
+            .map(|(profile, _)| profile.to_string());
+        debug!(session = %msg.session_key, command = name, "Running chat command");
+        command
+            .run(
+                msg,
+                args,
+                profile,
+                access.is_admin(),
+                self.session_manager.clone(),
+                self.bus.clone(),
+            )
+            .await
+            .map(Some)
+    }
+
+    /// The agent profile answering in `msg`'s conversation (see
+    /// [`route_agent_profile`]), with its name.
+    async fn agent_profile_for(
+        &self,
+        msg: &InboundMessage,
+    ) -> Result<Option<(&str, &AgentProfile)>> {
+        let chosen = self
+            .session_manager
+            .with_session(&msg.session_key, |session| session.agent_profile.clone())
+            .await?
+            .flatten();
+        Ok(route_agent_profile(
+            &self.config.agents,
+            &msg.session_key,
+            chosen.as_deref(),
+        ))
+    }
+
+    /// This conversation's own settings (`/model`, `/temp`, `/settings`).
+    async fn session_settings(&self, session_key: &str) -> Result<GenerationSettings> {
+        Ok(self
+            .session_manager
+            .with_session(session_key, |session| session.settings.clone())
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// The model and sampling settings a turn on `msg` runs with (see
+    /// [`settings::resolve`]).
+    async fn generation_settings_for(
+        &self,
+        msg: &InboundMessage,
+        profile: Option<&AgentProfile>,
+    ) -> Result<GenerationSettings> {
+        let session = self.session_settings(&msg.session_key).await?;
+        Ok(settings::resolve(
+            &self.config.agents,
+            &msg.channel,
+            msg.settings_override(),
+            &session,
+            profile,
+        ))
+    }
+
+    /// Tool definitions to offer the provider, without disabled tools.
+    async fn tool_definitions_for(&self, access: &ToolAccess) -> Vec<ToolDefinition> {
+        let tools = self.tools.read().await;
+        access.filter(tools.definitions_with_options(self.config.agents.defaults.compact_tools))
+    }
+
+    /// Apply a `/pin`, `/unpin`, `/tag`, `/untag`, `/tools`, `/artifacts`,
+    /// `/plan`, `/stats`, `/dryrun`, `/brief`, `/stop`, `/agent`, `/model`,
+    /// `/temp` or `/settings` chat command.
+    ///
+    /// Creates the session if it does not exist yet so a fresh chat can be
+    /// pinned or tagged before its first exchange.
+    async fn handle_session_command(
+        &self,
+        msg: &InboundMessage,
+        command: SessionCommand,
+    ) -> Result<String> {
+        let session_key = msg.session_key.as_str();
+        match command {
+            SessionCommand::Help => Ok(format!("Commands:\n{}", self.command_list().await)),
+            SessionCommand::Tools => {
+                let profile = self.agent_profile_for(msg).await?;
+                let access = self.tool_access_for(msg, profile.map(|(_, p)| p));
+                let tools = self.tools.read().await;
+                let mut names = tools.names();
+                names.sort_unstable();
+                let (active, disabled): (Vec<&str>, Vec<&str>) =
+                    names.into_iter().partition(|name| access.allows(name));
+                let quotas: Vec<(&str, Quota)> = active
+                    .iter()
+                    .filter_map(|name| Some((*name, tools.quota(name, session_key)?)))
+                    .collect();
+                Ok(format_tool_availability(&active, &disabled, &quotas))
+            }
+            SessionCommand::Artifacts(true) => {
+                if !Access::of(&self.config.agents.authorization, msg).is_admin() {
+                    return Ok("Only admins can clear artifacts.".to_string());
+                }
+                let removed = self.artifacts.clear(session_key).await?;
+                info!(session = %session_key, removed, "Session artifacts cleared");
+                Ok(format!("Removed {} artifact(s).", removed))
+            }
+            SessionCommand::Artifacts(false) => {
+                let artifacts = self.artifacts.list(session_key);
+                if artifacts.is_empty() {
+                    return Ok("This conversation has no artifacts.".to_string());
+                }
+                let lines: Vec<String> = artifacts
+                    .iter()
+                    .map(|a| {
+                        format!(
+                            "- {} ({})",
+                            ArtifactStore::reference(&a.name),
+                            crate::tools::artifact::format_size(a.size)
+                        )
+                    })
+                    .collect();
+                Ok(format!(
+                    "Artifacts ({}):\n{}\nUse /artifacts clear to delete them.",
+                    artifacts.len(),
+                    lines.join("\n")
+                ))
+            }
+            SessionCommand::Pin(pin) => {
+                self.session_manager
+                    .with_session_mut(session_key, |session| session.pinned = pin)
+                    .await?;
+                info!(session = %session_key, pinned = pin, "Session pin state changed");
+                Ok(if pin {
+                    "Conversation pinned. It will be kept during session cleanup.".to_string()
+                } else {
+                    "Conversation unpinned.".to_string()
+                })
+            }
+            SessionCommand::Plan => {
+                let plan = self
+                    .session_manager
+                    .with_session(session_key, |session| session.plan.clone())
+                    .await?
+                    .unwrap_or_default();
+                Ok(if plan.is_empty() {
+                    "This conversation has no plan.".to_string()
+                } else {
+                    format!("Plan:\n{}", crate::tools::todo::format_plan(&plan))
+                })
+            }
+            SessionCommand::Stats => {
+                let usage = self.tool_metrics().await.session(session_key);
+                let mut stats = if usage.is_empty() {
+                    "No tools have run in this conversation yet.".to_string()
+                } else {
+                    format!(
+                        "Tool usage in this conversation:\n{}",
+                        crate::tools::usage::format_usage(&usage)
+                    )
+                };
+                let cost = self
+                    .session_manager
+                    .with_session(session_key, |session| session.usage)
+                    .await?
+                    .unwrap_or_default();
+                if !cost.is_empty() {
+                    stats.push_str(&format!(
+                        "\nCost so far: ${:.4} ({} input / {} output tokens)",
+                        cost.cost_usd, cost.input_tokens, cost.output_tokens
+                    ));
+                }
+                if cost.cache_read_tokens + cost.cache_write_tokens > 0 {
+                    stats.push_str(&format!(
+                        "\nPrompt cache: {} tokens read, {} written, ${:.4} saved",
+                        cost.cache_read_tokens, cost.cache_write_tokens, cost.cache_savings_usd
+                    ));
+                }
+                if cost.thinking_tokens > 0 {
+                    stats.push_str(&format!(
+                        "\nThinking: {} tokens, ${:.4}",
+                        cost.thinking_tokens, cost.thinking_cost_usd
+                    ));
+                }
+                Ok(stats)
+            }
+            SessionCommand::Audit(limit) => {
+                let records = self.command_history(session_key, limit)?;
+                if records.is_empty() {
+                    return Ok("No commands have run in this conversation.".to_string());
+                }
+                let lines: Vec<String> = records
+                    .iter()
+                    .map(|r| format!("- {}", r.summary()))
+                    .collect();
+                Ok(format!(
+                    "Last {} command(s) in this conversation:\n{}",
+                    records.len(),
+                    lines.join("\n")
+                ))
+            }
+            SessionCommand::Stop => Ok("Nothing to stop.".to_string()),
+            SessionCommand::Agent(None) => {
+                let active = self.agent_profile_for(msg).await?.map(|(name, _)| name);
+                Ok(format_agent_profiles(&self.config.agents, active))
+            }
+            SessionCommand::Agent(Some(name)) => {
+                let chosen = (name != "auto").then_some(name);
+                if let Some(ref name) = chosen {
+                    if !self.config.agents.profiles.contains_key(name) {
+                        return Ok(format!(
+                            "Unknown agent '{}'. {}",
+                            name,
+                            format_agent_profiles(&self.config.agents, None)
+                        ));
+                    }
+                }
+                self.session_manager
+                    .with_session_mut(session_key, |session| {
+                        session.agent_profile = chosen.clone()
+                    })
+                    .await?;
+                info!(session = %session_key, agent = ?chosen, "Session agent profile changed");
+                Ok(match chosen {
+                    Some(name) => format!("Switched to agent '{}'.", name),
+                    None => match self.agent_profile_for(msg).await? {
+                        Some((name, _)) => format!("Following routing again: agent '{}'.", name),
+                        None => "Following routing again: no agent profile.".to_string(),
+                    },
+                })
+            }
+            SessionCommand::Settings => {
+                let profile = self.agent_profile_for(msg).await?;
+                let effective = self
+                    .generation_settings_for(msg, profile.map(|(_, p)| p))
+                    .await?;
+                let own = self.session_settings(session_key).await?;
+                Ok(settings::format_settings(&effective, &own))
+            }
+            SessionCommand::Models => {
+                let profile = self.agent_profile_for(msg).await?;
+                let effective = self
+                    .generation_settings_for(msg, profile.map(|(_, p)| p))
+                    .await?;
+                Ok(format!(
+                    "Model: {}\nAvailable: {}\nUse /model <name> to switch, or /model reset to follow the configuration again.",
+                    effective.model.unwrap_or_default(),
+                    settings::available_models(&self.config).join(", ")
+                ))
+            }
+            SessionCommand::Set(setting, value) => {
+                if let (Setting::Model, Some(model)) = (setting, &value) {
+                    let available = settings::available_models(&self.config);
+                    if !available.contains(model) {
+                        return Ok(format!(
+                            "Unknown model '{}'. Available: {}",
+                            model,
+                            available.join(", ")
+                        ));
+                    }
+                }
+                let mut updated = self.session_settings(session_key).await?;
+                if let Err(reason) = setting.apply(&mut updated, value.as_deref()) {
+                    return Ok(reason);
+                }
+                self.session_manager
+                    .with_session_mut(session_key, |session| session.settings = updated)
+                    .await?;
+                info!(session = %session_key, setting = setting.name(), value = ?value, "Session setting changed");
+                Ok(match value {
+                    Some(value) => {
+                        format!("{} set to {} for this conversation.", setting.name(), value)
+                    }
+                    None => format!(
+                        "{} reset; this conversation follows the configuration again.",
+                        setting.name()
+                    ),
+                })
+            }
+            SessionCommand::ResetSettings => {
+                self.session_manager
+                    .with_session_mut(session_key, |session| {
+                        session.settings = GenerationSettings::default()
+                    })
+                    .await?;
+                info!(session = %session_key, "Session settings reset");
+                Ok(
+                    "Settings reset; this conversation follows the configuration again."
+                        .to_string(),
+                )
+            }
+            SessionCommand::DryRun(None) => {
+                let dry_run = self
+                    .session_manager
+                    .with_session(session_key, |session| session.dry_run)
+                    .await?
+                    .flatten()
+                    .unwrap_or(false)
+                    || self.dry_run_forced();
+                Ok(if dry_run {
+                    "Dry-run mode is on: tools that change files or run commands describe what they would do instead. Use /dryrun off to turn it off.".to_string()
+                } else {
+                    "Dry-run mode is off. Use /dryrun on to turn it on.".to_string()
+                })
+            }
+            SessionCommand::DryRun(Some(dry_run)) => {
+                self.session_manager
+                    .with_session_mut(session_key, |session| session.dry_run = Some(dry_run))
+                    .await?;
+                info!(session = %session_key, dry_run, "Session dry-run mode changed");
+                Ok(if dry_run {
+                    "Dry-run mode on. Tools that change files or run commands will describe what they would do instead.".to_string()
+                } else if self.dry_run_forced() {
+                    "Dry-run mode stays on: it is enabled for every conversation by the agent's configuration.".to_string()
+                } else {
+                    "Dry-run mode off. Tools will run normally.".to_string()
+                })
+            }
+            SessionCommand::Brief(None) => {
+                let brief = self
+                    .session_manager
+                    .with_session(session_key, |session| session.workspace_brief)
+                    .await?
+                    .flatten()
+                    .unwrap_or(self.config.agents.defaults.workspace_brief.enabled);
+                Ok(if !self.context_builder.has_workspace_brief() {
+                    "No workspace is set, so there is no workspace brief.".to_string()
+                } else if brief {
+                    "The workspace brief is on: each turn's system prompt lists the workspace's files, git state and README. Use /brief off to turn it off.".to_string()
+                } else {
+                    "The workspace brief is off. Use /brief on to turn it on.".to_string()
+                })
+            }
+            SessionCommand::Brief(Some(brief)) => {
+                self.session_manager
+                    .with_session_mut(session_key, |session| session.workspace_brief = Some(brief))
+                    .await?;
+                info!(session = %session_key, brief, "Session workspace brief changed");
+                Ok(if !self.context_builder.has_workspace_brief() {
+                    "No workspace is set, so there is no workspace brief to show.".to_string()
+                } else if brief {
+                    "Workspace brief on. The model will see the workspace's files, git state and README.".to_string()
+                } else {
+                    "Workspace brief off.".to_string()
+                })
+            }
+            SessionCommand::Trace(None) => {
+                let trace = self
+                    .session_manager
+                    .with_session(session_key, |session| session.trace)
+                    .await?
+                    .flatten()
+                    .unwrap_or(self.config.agents.defaults.trace.enabled);
+                let mut reply = if trace {
+                    "Tracing is on: each turn's provider requests, responses and tool calls are saved for debugging. Use /trace off to turn it off.".to_string()
+                } else {
+                    "Tracing is off. Use /trace on to turn it on.".to_string()
+                };
+                // The turn ID names the trace's directory without showing
+                // where on the host it is.
+                if let Some(last) = self.last_trace(session_key) {
+                    reply.push_str(&format!("\nLast trace: turn {}", last.turn_id()));
+                }
+                Ok(reply)
+            }
+            SessionCommand::Trace(Some(trace)) => {
+                self.session_manager
+                    .with_session_mut(session_key, |session| session.trace = Some(trace))
+                    .await?;
+                info!(session = %session_key, trace, "Session tracing changed");
+                Ok(if trace {
+                    "Tracing on. Each turn's provider requests, responses and tool calls will be saved for debugging.".to_string()
+                } else {
+                    "Tracing off.".to_string()
+                })
+            }
+            SessionCommand::Tag(tags) if tags.is_empty() => {
+                let current = self
+                    .session_manager
+                    .with_session(session_key, |session| session.tags.clone())
+                    .await?
+                    .unwrap_or_default();
+                Ok(if current.is_empty() {
+                    "This conversation has no tags. Usage: /tag <tag> [tag...]".to_string()
+                } else {
+                    format_tags(&current)
+                })
+            }
+            SessionCommand::Tag(tags) => {
+                let invalid: Vec<&str> = tags
+                    .iter()
+                    .filter(|t| crate::session::normalize_tag(t).is_none())
+                    .map(String::as_str)
+                    .collect();
+                if !invalid.is_empty() {
+                    return Ok(format!(
+                        "Invalid tag(s): {}. Tags may contain letters, digits, '-', '_', '.', and ':'.",
+                        invalid.join(", ")
+                    ));
+                }
+                let current = self
+                    .session_manager
+                    .with_session_mut(session_key, |session| {
+                        for tag in &tags {
+                            session.add_tag(tag);
+                        }
+                        session.tags.clone()
+                    })
+                    .await?;
+                info!(session = %session_key, tags = ?current, "Session tags changed");
+                Ok(format_tags(&current))
+            }
+            SessionCommand::Untag(tags) => {
+                if tags.is_empty() {
+                    return Ok("Usage: /untag <tag> [tag...]".to_string());
+                }
+                let current = self
+                    .session_manager
+                    .with_session_mut(session_key, |session| {
+                        for tag in &tags {
+                            session.remove_tag(tag);
+                        }
+                        session.tags.clone()
+                    })
+                    .await?;
+                info!(session = %session_key, tags = ?current, "Session tags changed");
+                Ok(if current.is_empty() {
+                    "All tags removed.".to_string()
+                } else {
+                    format_tags(&current)
+                })
+            }
+        }
+    }
+
     /// Check if a ZeptoError is a context overflow that can be retried via compaction.
     fn is_context_overflow(err: &ZeptoError) -> bool {
         matches!(
@@ -2866,6 +5045,108 @@ impl AgentLoop {
         )
     }
 
+    /// Make a final LLM call without tools, so the model answers from the
+    /// tool results it already has. Compacts the session first if the call
+    /// would not fit, and again on context overflow.
+    #[allow(clippy::too_many_arguments)]
+    async fn synthesize_without_tools(
+        &self,
+        session: &mut crate::session::Session,
+        memory_override: Option<&str>,
+        provider: &dyn LLMProvider,
+        mut stream: Option<&mut ReplyStream>,
+        model: Option<&str>,
+        options: &ChatOptions,
+        usage_metrics: Option<&Arc<UsageMetrics>>,
+    ) -> Result<crate::providers::LLMResponse> {
+        let model_name = model.unwrap_or(&self.config.agents.defaults.model);
+        let mut messages = self
+            .build_resolved_messages(session, memory_override, model_name)
+            .await;
+        // Pre-flight guard for synthesis call
+        if let Some(ref monitor) = self.context_monitor {
+            if let PreflightAction::NeedsCompaction = monitor.preflight_check(&mut messages, &[]) {
+                let ctx_limit = self.config.compaction.context_limit;
+                let cap = self.config.agents.defaults.max_tool_result_bytes;
+                let (recovered, _) = crate::agent::compaction::try_recover_context_with_urgency(
+                    std::mem::take(&mut session.messages),
+                    ctx_limit,
+                    CompactionUrgency::Emergency,
+                    5,
+                    cap,
+                    self.config.compaction.safety_margin,
+                );
+                session.messages = recovered;
+                messages = self
+                    .build_resolved_messages(session, memory_override, model_name)
+                    .await;
+            }
+        }
+        let response = {
+            let max_retries = self.config.compaction.overflow_retries;
+            let mut last_messages = messages;
+            let mut result = reply_stream::chat(
+                provider,
+                stream.as_deref_mut(),
+                last_messages.clone(),
+                vec![],
+                model,
+                options.clone(),
+            )
+            .await;
+            let mut attempt = 0u32;
+            while let Err(ref e) = result {
+                if !Self::is_context_overflow(e) || attempt >= max_retries {
+                    break;
+                }
+                if self.context_monitor.is_none() {
+                    break; // compaction disabled
+                }
+                warn!(
+                    attempt = attempt + 1,
+                    max = max_retries,
+                    "Context overflow in synthesis call, compacting and retrying"
+                );
+                let urgency = Self::overflow_retry_urgency(attempt);
+                let ctx_limit = self.config.compaction.context_limit;
+                let cap = self.config.agents.defaults.max_tool_result_bytes;
+                let (recovered, _) = crate::agent::compaction::try_recover_context_with_urgency(
+                    std::mem::take(&mut session.messages),
+                    ctx_limit,
+                    urgency,
+                    5,
+                    cap,
+                    self.config.compaction.safety_margin,
+                );
+                session.messages = recovered;
+                last_messages = self
+                    .build_resolved_messages(session, memory_override, model_name)
+                    .await;
+                result = reply_stream::chat(
+                    provider,
+                    stream.as_deref_mut(),
+                    last_messages.clone(),
+                    vec![],
+                    model,
+                    options.clone(),
+                )
+                .await;
+                attempt += 1;
+            }
+            result?
+        };
+        if let (Some(metrics), Some(usage)) = (usage_metrics, response.usage.as_ref()) {
+            metrics.record_tokens(usage.prompt_tokens as u64, usage.completion_tokens as u64);
+        }
+        if let Some(usage) = response.usage.as_ref() {
+            self.metrics_collector
+                .record_tokens(usage.prompt_tokens as u64, usage.completion_tokens as u64);
+            self.token_budget
+                .record(usage.prompt_tokens as u64, usage.completion_tokens as u64);
+        }
+        Ok(response)
+    }
+
     /// Map a compaction retry attempt number to a progressively more aggressive urgency.
     fn overflow_retry_urgency(attempt: u32) -> CompactionUrgency {
         match attempt {
@@ -2946,7 +5227,7 @@ impl AgentLoop {
         if response.has_tool_calls() {
             let workspace = self.config.workspace_path();
             let workspace_str = workspace.to_string_lossy();
-            let tool_ctx = ToolContext::new().with_workspace(&workspace_str);
+            let tool_ctx = ToolContext::new().with_sandbox(self.sandbox(&workspace_str));
 
             for tc in &response.tool_calls {
                 let args: serde_json::Value = match serde_json::from_str(&tc.arguments) {
@@ -2990,27 +5271,98 @@ impl AgentLoop {
     /// This centralizes the message preparation logic used in tool loops.
     /// Images are resolved first so that if resolution fails and leaves a
     /// message empty, it will be correctly filtered out.
+    /// The context budget for a request to `model`.
+    fn context_budget_for(&self, model: &str) -> ContextBudget {
+        let compaction = &self.config.compaction;
+        ContextBudget::new(
+            compaction.context_limit_for(model),
+            self.config.agents.defaults.max_tokens as usize,
+        )
+        .with_headroom_ratio(compaction.input_headroom_ratio)
+        .with_safety_margin(compaction.safety_margin)
+    }
+
     async fn build_resolved_messages(
         &self,
         session: &crate::session::Session,
         memory_override: Option<&str>,
+        model: &str,
     ) -> Vec<Message> {
-        let mut msgs = self.context_builder.build_messages_with_memory_override(
+        let (mut msgs, report) = self.context_builder.build_messages_within_budget(
             &session.messages,
             "",
             memory_override,
+            &session.notes,
+            &session.plan,
+            &self.context_budget_for(model),
         );
-
-        // Resolve image file paths to base64 before filtering
-        if let Some(dir) = self.session_manager.sessions_dir() {
-            resolve_images_to_base64(&mut msgs, dir).await;
+        if report.is_trimmed() {
+            info!(session = %session.key, model, %report, "Fitted history into the context budget");
+        } else {
+            debug!(session = %session.key, model, %report, "Context budget");
+        }
+        if let Some(turn_trace) = self.traces.active(&session.key) {
+            turn_trace.record_context(model, &report);
         }
 
-        // Filter out empty user messages only after resolution
-        // (in case image resolution failed and left the message empty)
-        msgs.retain(|m| !(m.role == Role::User && m.content.is_empty() && !m.has_images()));
+        // List the session's artifacts (names and sizes only) in the system prompt
+        if let Some(inventory) = self.artifacts.inventory(&session.key) {
+            if let Some(system) = msgs.first_mut().filter(|m| m.role == Role::System) {
+                system.content.push_str("\n\n");
+                system.content.push_str(&inventory);
+            }
+        }
 
-        msgs
+        // Add the workspace brief when enabled for this session
+        if session
+            .workspace_brief
+            .unwrap_or(self.config.agents.defaults.workspace_brief.enabled)
+        {
+            if let Some(brief) = self.context_builder.workspace_brief() {
+                if let Some(system) = msgs.first_mut().filter(|m| m.role == Role::System) {
+                    system.content.push_str("\n\n");
+                    system.content.push_str(&brief);
+                }
+            }
+        }
+
+        // Add excerpts of the user's documents matching the latest message
+        let query = session
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == Role::User)
+            .map_or("", |m| m.content.as_str());
+        if let Some(excerpts) = self.context_builder.retrieve(query).await {
+            if let Some(system) = msgs.first_mut().filter(|m| m.role == Role::System) {
+                system.content.push_str("\n\n");
+                system.content.push_str(&excerpts);
+            }
+        }
+
+        // Add the answering agent profile's instructions
+        let profile = route_agent_profile(
+            &self.config.agents,
+            &session.key,
+            session.agent_profile.as_deref(),
+        );
+        if let Some((_, profile)) = profile.filter(|(_, p)| !p.system_prompt.is_empty()) {
+            if let Some(system) = msgs.first_mut().filter(|m| m.role == Role::System) {
+                system.content.push_str("\n\n");
+                system.content.push_str(&profile.system_prompt);
+            }
+        }
+
+        // Resolve image file paths to base64 before filtering
+        if let Some(dir) = self.session_manager.sessions_dir() {
+            resolve_images_to_base64(&mut msgs, dir).await;
+        }
+
+        // Filter out empty user messages only after resolution
+        // (in case image resolution failed and left the message empty)
+        msgs.retain(|m| !(m.role == Role::User && m.content.is_empty() && !m.has_images()));
+
+        msgs
     }
 
     async fn session_lock_for(&self, session_key: &str) -> Arc<Mutex<()>> {
@@ -3056,45 +5408,163 @@ impl AgentLoop {
             .unwrap_or((0, 0))
     }
 
-    async fn drain_pending_messages(&self, msg: &InboundMessage) {
-        let pending = {
+    /// The messages to run next for `msg`'s session: those queued while its
+    /// turn ran, in arrival order, or in `collect` and `interrupt` modes one
+    /// message combining each run of senders with the same access. Messages
+    /// from senders `agents.authorization` turns away are dropped.
+    async fn take_pending_messages(&self, msg: &InboundMessage) -> Vec<InboundMessage> {
+        let authorization = &self.config.agents.authorization;
+        let pending: Vec<InboundMessage> = {
             let mut map = self.pending_messages.lock().await;
             map.remove(&msg.session_key).unwrap_or_default()
-        };
+        }
+        .into_iter()
+        .filter(|item| Access::of(authorization, item) != Access::Denied)
+        .collect();
 
         if pending.is_empty() {
-            return;
+            return pending;
         }
 
-        match self.config.agents.defaults.message_queue_mode {
-            crate::config::MessageQueueMode::Collect => {
-                let combined: Vec<String> = pending
-                    .iter()
-                    .enumerate()
-                    .map(|(index, item)| format!("{}. {}", index + 1, item.content))
-                    .collect();
-                let combined_content = format!(
-                    "[Queued messages while I was busy]\n\n{}",
-                    combined.join("\n")
-                );
-                let synthetic = InboundMessage::new(
-                    &msg.channel,
-                    &msg.sender_id,
-                    &msg.chat_id,
-                    &combined_content,
-                );
-                if let Err(e) = self.bus.publish_inbound(synthetic).await {
-                    error!("Failed to re-queue collected messages: {}", e);
-                }
-            }
-            crate::config::MessageQueueMode::Followup => {
-                for pending_msg in pending {
-                    if let Err(e) = self.bus.publish_inbound(pending_msg).await {
-                        error!("Failed to re-queue followup message: {}", e);
+        match queue_mode(&self.config.agents.defaults, &msg.channel) {
+            // Messages that came after a steered turn's last step are
+            // collected too.
+            MessageQueueMode::Collect | MessageQueueMode::Interrupt | MessageQueueMode::Steer => {
+                // Only the combined message runs a turn, so redeliveries are
+                // dropped here instead.
+                let mut fresh = Vec::with_capacity(pending.len());
+                {
+                    let session_lock = self.session_lock_for(&msg.session_key).await;
+                    let _session_guard = session_lock.lock().await;
+                    for item in pending {
+                        if !matches!(
+                            self.check_redelivery(&item).await,
+                            Err(ZeptoError::Duplicate(_))
+                        ) {
+                            fresh.push(item);
+                        }
                     }
                 }
+                // A combined message runs with its first sender's access, so
+                // only messages from senders with the same access share one.
+                fresh
+                    .chunk_by(|a, b| Access::of(authorization, a) == Access::of(authorization, b))
+                    .map(|run| {
+                        let combined: Vec<String> = run
+                            .iter()
+                            .enumerate()
+                            .map(|(index, item)| format!("{}. {}", index + 1, item.content))
+                            .collect();
+                        let combined_content = format!(
+                            "[Queued messages while I was busy]\n\n{}",
+                            combined.join("\n")
+                        );
+                        // From the queued messages' sender: `msg` may be a
+                        // heartbeat.
+                        let mut synthetic = InboundMessage::new(
+                            &msg.channel,
+                            &run[0].sender_id,
+                            &msg.chat_id,
+                            &combined_content,
+                        );
+                        synthetic.session_key = msg.session_key.clone();
+                        synthetic
+                    })
+                    .collect()
             }
+            MessageQueueMode::Followup => pending,
+        }
+    }
+
+    /// `options` with the stop sequences configured for `msg`'s session or
+    /// channel.
+    fn with_stop_sequences(&self, options: ChatOptions, msg: &InboundMessage) -> ChatOptions {
+        match response::resolve(&self.config.agents, &msg.session_key, &msg.channel).stop {
+            Some(stop) if !stop.is_empty() => options.with_stop(stop),
+            _ => options,
+        }
+    }
+
+    /// `response` with the reply constraints of `msg`'s session or channel
+    /// applied. Structured replies are left alone.
+    fn post_process(&self, msg: &InboundMessage, response: String) -> String {
+        if msg.response_format().is_some() {
+            return response;
+        }
+        let options = response::resolve(&self.config.agents, &msg.session_key, &msg.channel);
+        if !response::rewrites(&options) {
+            return response;
+        }
+        response::apply(&options, &response)
+    }
+
+    /// The stream for `msg`'s reply when `agents.defaults.stream_to_channels`
+    /// is on. The CLI has its own streaming and batch runs have no one
+    /// watching, so neither gets one.
+    fn reply_stream_for(&self, msg: &InboundMessage) -> Option<ReplyStream> {
+        let defaults = &self.config.agents.defaults;
+        let is_batch = msg.metadata.get("is_batch").is_some_and(|v| v == "true");
+        // Text streamed before validation could be JSON that is then rejected.
+        let structured = msg.response_format().is_some();
+        // A heartbeat's reply may be dropped, and a rewritten one must not
+        // be shown before it is done.
+        let rewritten = response::rewrites(&response::resolve(
+            &self.config.agents,
+            &msg.session_key,
+            &msg.channel,
+        ));
+        if !defaults.stream_to_channels
+            || msg.channel == "cli"
+            || is_batch
+            || structured
+            || msg.is_heartbeat()
+            || rewritten
+        {
+            return None;
+        }
+        let mut template = OutboundMessage::new(&msg.channel, &msg.chat_id, "");
+        propagate_routing_metadata(&mut template, msg);
+        Some(ReplyStream::new(
+            Arc::clone(&self.bus),
+            template,
+            std::time::Duration::from_millis(defaults.stream_interval_ms),
+        ))
+    }
+
+    /// The progress reporter for `msg`'s turn when
+    /// `agents.defaults.progress_updates` is on, sharing `stream`'s message
+    /// on channels that edit in place.
+    fn turn_progress_for(
+        &self,
+        msg: &InboundMessage,
+        stream: Option<&ReplyStream>,
+    ) -> Option<TurnProgress> {
+        let defaults = &self.config.agents.defaults;
+        let is_batch = msg.metadata.get("is_batch").is_some_and(|v| v == "true");
+        if !defaults.progress_updates || msg.channel == "cli" || is_batch || msg.is_heartbeat() {
+            return None;
         }
+        let mut template = OutboundMessage::new(&msg.channel, &msg.chat_id, "");
+        propagate_routing_metadata(&mut template, msg);
+        let stream_id = stream.map_or_else(
+            || uuid::Uuid::new_v4().to_string(),
+            |stream| stream.stream_id().to_string(),
+        );
+        Some(TurnProgress::start(
+            Arc::clone(&self.bus),
+            template,
+            stream_id,
+            std::time::Duration::from_millis(defaults.progress_interval_ms),
+        ))
+    }
+
+    /// Whether the reply just produced for `session_key` came from the
+    /// response cache, clearing the mark.
+    fn take_cached_reply(&self, session_key: &str) -> bool {
+        self.cached_replies
+            .lock()
+            .map(|mut cached| cached.remove(session_key))
+            .unwrap_or(false)
     }
 
     async fn process_inbound_message(
@@ -3110,10 +5580,38 @@ impl AgentLoop {
             metrics.record_request();
         }
 
+        let heartbeat_from = if msg.is_heartbeat() {
+            self.session_manager
+                .message_count(&msg.session_key)
+                .await
+                .ok()
+        } else {
+            None
+        };
+
         let timeout_duration =
             std::time::Duration::from_secs(self.config.agents.defaults.agent_timeout_secs);
-        let process_result =
-            tokio::time::timeout(timeout_duration, self.process_message(msg)).await;
+        let mut stream = self.reply_stream_for(msg);
+        let mut progress = self.turn_progress_for(msg, stream.as_ref());
+        let process_result = tokio::time::timeout(
+            timeout_duration,
+            self.run_turn(msg, stream.as_mut(), progress.as_ref()),
+        )
+        .await;
+        // No status may follow the reply, whichever way the turn ended.
+        if let Some(progress) = progress.as_mut() {
+            progress.stop().await;
+        }
+        let finish = |outbound: OutboundMessage| {
+            let outbound = match progress.as_ref() {
+                Some(progress) => progress.finish(outbound),
+                None => outbound,
+            };
+            match stream.as_ref() {
+                Some(stream) => stream.finish(outbound),
+                None => outbound,
+            }
+        };
 
         let agent_completed = match process_result {
             Ok(Ok(response)) => {
@@ -3129,9 +5627,19 @@ impl AgentLoop {
                     "Request completed"
                 );
 
+                let quiet = msg.is_heartbeat() && heartbeat::is_nothing_to_report(&response);
+                let response = self.post_process(msg, response);
                 let mut outbound = OutboundMessage::new(&msg.channel, &msg.chat_id, &response);
                 propagate_routing_metadata(&mut outbound, msg);
-                if let Err(e) = self.bus.publish_outbound(outbound).await {
+                if self.take_cached_reply(&msg.session_key) {
+                    outbound = outbound.with_cached();
+                }
+                if quiet {
+                    info!(session = %msg.session_key, "Heartbeat: nothing to report");
+                    if let Some(from) = heartbeat_from {
+                        self.forget_heartbeat(msg, from).await;
+                    }
+                } else if let Err(e) = self.bus.publish_outbound(finish(outbound)).await {
                     error!("Failed to publish outbound message: {}", e);
                     if let Some(metrics) = usage_metrics.as_ref() {
                         metrics.record_error();
@@ -3139,17 +5647,37 @@ impl AgentLoop {
                 }
                 true
             }
+            Ok(Err(ZeptoError::Rejected(reason))) => {
+                info!(reason = %reason, "Message rejected");
+                true
+            }
+            // Already logged with the duplicate ID; the first delivery got the
+            // reply.
+            Ok(Err(ZeptoError::Duplicate(_))) => true,
             Ok(Err(e)) => {
                 let latency_ms = start.elapsed().as_millis() as u64;
-                error!(latency_ms = latency_ms, error = %e, "Request failed");
+                // The chat gets a short reply; the log gets the whole error
+                // under the same reference.
+                let reply = ErrorReply::new(&e, &self.config.agents.error_replies);
+                error!(
+                    latency_ms = latency_ms,
+                    error_ref = %reply.reference,
+                    kind = reply.kind.name(),
+                    error = %error_chain(&e),
+                    details = ?e,
+                    "Request failed"
+                );
                 if let Some(metrics) = usage_metrics.as_ref() {
                     metrics.record_error();
                 }
 
-                let mut error_msg =
-                    OutboundMessage::new(&msg.channel, &msg.chat_id, &format!("Error: {}", e));
-                propagate_routing_metadata(&mut error_msg, msg);
-                self.bus.publish_outbound(error_msg).await.ok();
+                // Nobody asked for a heartbeat, so its failures stay in the log.
+                if !msg.is_heartbeat() {
+                    let mut error_msg =
+                        OutboundMessage::new(&msg.channel, &msg.chat_id, &reply.text);
+                    propagate_routing_metadata(&mut error_msg, msg);
+                    self.bus.publish_outbound(finish(error_msg)).await.ok();
+                }
                 false
             }
             Err(_elapsed) => {
@@ -3159,16 +5687,18 @@ impl AgentLoop {
                     metrics.record_error();
                 }
 
-                let mut timeout_msg = OutboundMessage::new(
-                    &msg.channel,
-                    &msg.chat_id,
-                    &format!(
-                        "Agent run timed out after {}s. Try a simpler request.",
-                        timeout_secs
-                    ),
-                );
-                propagate_routing_metadata(&mut timeout_msg, msg);
-                self.bus.publish_outbound(timeout_msg).await.ok();
+                if !msg.is_heartbeat() {
+                    let mut timeout_msg = OutboundMessage::new(
+                        &msg.channel,
+                        &msg.chat_id,
+                        &format!(
+                            "Agent run timed out after {}s. Try a simpler request.",
+                            timeout_secs
+                        ),
+                    );
+                    propagate_routing_metadata(&mut timeout_msg, msg);
+                    self.bus.publish_outbound(finish(timeout_msg)).await.ok();
+                }
                 false
             }
         };
@@ -3177,8 +5707,6 @@ impl AgentLoop {
         let slo = crate::utils::slo::SessionSLO::evaluate(&self.metrics_collector, agent_completed);
         slo.emit();
         debug!(slo_summary = %slo.summary(), "Session SLO summary");
-
-        self.drain_pending_messages(msg).await;
     }
 
     /// Try to queue a message if the session is busy, or return false if lock is free.
@@ -3190,13 +5718,7 @@ impl AgentLoop {
         let is_busy = session_lock.try_lock().is_err();
 
         if is_busy {
-            // Session is busy, queue the message
-            let mut pending = self.pending_messages.lock().await;
-            pending
-                .entry(msg.session_key.clone())
-                .or_default()
-                .push(msg.clone());
-            debug!(session = %msg.session_key, "Message queued (session busy)");
+            self.queue_pending(msg).await;
             true
         } else {
             // Lock acquired and immediately dropped — caller should process normally
@@ -3205,12 +5727,96 @@ impl AgentLoop {
         }
     }
 
+    /// Add the messages queued for `msg`'s session to `session` as user
+    /// messages when the channel is in steer mode, so a correction reaches
+    /// the running turn before its next model call. Redeliveries are
+    /// dropped, as are messages from senders `agents.authorization` turns
+    /// away; commands, messages flagged by the injection scanner and messages
+    /// from senders with other access than `msg`'s stay queued and run as
+    /// their own turn afterwards. Heartbeats are never steered. Returns the
+    /// number of messages added.
+    async fn steer(&self, msg: &InboundMessage, session: &mut crate::session::Session) -> usize {
+        let defaults = &self.config.agents.defaults;
+        if msg.is_heartbeat() || queue_mode(defaults, &msg.channel) != MessageQueueMode::Steer {
+            return 0;
+        }
+        let queued = {
+            let mut pending = self.pending_messages.lock().await;
+            pending.remove(&msg.session_key).unwrap_or_default()
+        };
+        if queued.is_empty() {
+            return 0;
+        }
+
+        let media_store = self
+            .session_manager
+            .sessions_dir()
+            .map(|dir| crate::session::media::MediaStore::new(dir.to_path_buf()));
+        let scan = self.config.safety.enabled && self.config.safety.injection_check_enabled;
+        let authorization = &self.config.agents.authorization;
+        let access = Access::of(authorization, msg);
+        let mut kept = Vec::new();
+        let mut added = 0;
+        for item in queued {
+            let item_access = Access::of(authorization, &item);
+            if item_access == Access::Denied {
+                continue;
+            }
+            if item_access != access {
+                kept.push(item);
+                continue;
+            }
+            if let Some(id) = item.message_id().filter(|_| defaults.dedup_window > 0) {
+                if !session.record_message_id(id, defaults.dedup_window) {
+                    info!(session = %msg.session_key, duplicate_id = %id, "Ignoring message delivered twice");
+                    continue;
+                }
+            }
+            if item.content.trim_start().starts_with('/') {
+                kept.push(item);
+                continue;
+            }
+            let message = inbound_to_message(&item, media_store.as_ref()).await;
+            if scan && crate::safety::sanitizer::check_injection(&message.content).was_modified {
+                kept.push(item);
+                continue;
+            }
+            session.add_message(message);
+            added += 1;
+        }
+        if !kept.is_empty() {
+            let mut pending = self.pending_messages.lock().await;
+            let entry = pending.entry(msg.session_key.clone()).or_default();
+            // Ahead of anything queued meanwhile, to keep arrival order.
+            entry.splice(0..0, kept);
+        }
+        if added > 0 {
+            info!(session = %msg.session_key, messages = added, "Steered new messages into the running turn");
+        }
+        added
+    }
+
+    /// Queue `msg` until the turn running for its session finishes.
+    async fn queue_pending(&self, msg: &InboundMessage) {
+        let mut pending = self.pending_messages.lock().await;
+        pending
+            .entry(msg.session_key.clone())
+            .or_default()
+            .push(msg.clone());
+        debug!(session = %msg.session_key, "Message queued (session busy)");
+    }
+
     /// Start the agent loop (consuming from message bus).
     ///
     /// This method runs in a loop, consuming messages from the inbound
-    /// channel and publishing responses to the outbound channel.
+    /// channel and publishing responses to the outbound channel. Turns for
+    /// different sessions run concurrently; messages for a session with a
+    /// turn under way are queued until it finishes and then run in arrival
+    /// order. A message whose channel message ID the session has already
+    /// seen is dropped (see `agents.defaults.dedup_window`).
     ///
-    /// The loop continues until `stop()` is called.
+    /// The loop continues until `stop()` is called, then waits for the turns
+    /// under way.
     ///
     /// # Errors
     /// Returns an error if the loop is already running.
@@ -3231,13 +5837,27 @@ impl AgentLoop {
             return Err(ZeptoError::Config("Agent loop already running".into()));
         }
         info!("Starting agent loop");
+        self.stopped_tx.send_replace(false);
 
         // Subscribe fresh and consume any stale stop signal from a previous run.
         let mut shutdown_rx = self.shutdown_tx.subscribe();
         let _ = *shutdown_rx.borrow_and_update();
 
+        // Turns run concurrently across sessions, so one waiting on the user
+        // (an approval prompt or `ask_user`) does not hold up other chats.
+        // A session with a turn in flight gets its messages queued.
+        let mut in_flight = FuturesUnordered::new();
+        let mut busy_sessions: HashSet<String> = HashSet::new();
+        let mut heartbeats = HeartbeatScheduler::default();
+        let mut heartbeat_check = tokio::time::interval(heartbeat::CHECK_INTERVAL);
+        heartbeat_check.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
         loop {
             tokio::select! {
+                biased;
+                Some(session_key) = in_flight.next(), if !in_flight.is_empty() => {
+                    busy_sessions.remove(&session_key);
+                }
                 // Check for shutdown signal
                 _ = shutdown_rx.changed() => {
                     if *shutdown_rx.borrow() {
@@ -3280,46 +5900,48 @@ impl AgentLoop {
                             }
                         }
 
-                        let tenant_id = msg
-                            .metadata
-                            .get("tenant_id")
-                            .filter(|v| !v.is_empty())
-                            .map(String::as_str)
-                            .unwrap_or(&msg.chat_id);
-                        let request_id = uuid::Uuid::new_v4();
-                        let request_span = info_span!(
-                            "request",
-                            request_id = %request_id,
-                            tenant_id = %tenant_id,
-                            chat_id = %msg.chat_id,
-                            session_id = %msg.session_key,
-                            channel = %msg.channel,
-                            sender = %msg.sender_id,
-                        );
-                        let msg_ref = &msg;
-                        async {
-                            // Fast-path: if this session is already processing a
-                            // message, queue instead of blocking the select loop.
-                            // The queued message is drained and re-published to
-                            // the bus after the active request completes.
-                            if self.try_queue_or_process(msg_ref).await {
-                                return;
+                        // Before anything else, so unauthorized senders
+                        // cannot interrupt turns or hold queue slots.
+                        match self.turn_away(&msg) {
+                            Ok(None) => {}
+                            Ok(Some(reply)) => {
+                                self.tell_chat(&msg, &reply).await;
+                                continue;
                             }
+                            Err(_) => continue,
+                        }
 
-                            let usage_metrics = {
-                                let metrics = self.usage_metrics.read().await;
-                                metrics.clone()
-                            };
-                            self.process_inbound_message(msg_ref, usage_metrics).await;
+                        if busy_sessions.contains(&msg.session_key) {
+                            if queue_mode(&self.config.agents.defaults, &msg.channel)
+                                == MessageQueueMode::Interrupt
+                            {
+                                self.cancel(&msg.session_key);
+                            }
+                            self.queue_pending(&msg).await;
+                            continue;
                         }
-                        .instrument(request_span)
-                        .await;
+                        busy_sessions.insert(msg.session_key.clone());
+                        in_flight.push(self.run_session_turns(msg));
                     } else {
                         // Channel closed, exit loop
                         info!("Inbound channel closed");
                         break;
                     }
                 }
+                _ = heartbeat_check.tick(), if !self.config.agents.heartbeats.is_empty() => {
+                    let now = std::time::Instant::now();
+                    for msg in self.due_heartbeats(&mut heartbeats, now).await {
+                        // Never alongside a user turn; the next one is due
+                        // an interval later.
+                        if busy_sessions.contains(&msg.session_key) {
+                            debug!(session = %msg.session_key, "Session busy, skipping heartbeat");
+                            continue;
+                        }
+                        info!(session = %msg.session_key, "Running heartbeat");
+                        busy_sessions.insert(msg.session_key.clone());
+                        in_flight.push(self.run_session_turns(msg));
+                    }
+                }
             }
 
             // Also check the running flag (belt and suspenders)
@@ -3328,11 +5950,194 @@ impl AgentLoop {
             }
         }
 
+        // Let turns already under way finish, as before turns ran
+        // concurrently.
+        while in_flight.next().await.is_some() {}
+
         self.running.store(false, Ordering::SeqCst);
+        self.stopped_tx.send_replace(true);
         info!("Agent loop stopped");
         Ok(())
     }
 
+    /// Run `msg`'s turn, then the messages queued for its session while it
+    /// ran. Returns the session key once the session is idle.
+    async fn run_session_turns(&self, msg: InboundMessage) -> String {
+        // The session stays busy until the messages queued during its turns
+        // have run too, so they run in the order they arrived and none can
+        // overtake another.
+        let session_key = msg.session_key.clone();
+        let mut next = VecDeque::from([msg]);
+        while let Some(msg) = next.pop_front() {
+            // A slot per turn, not per session: the session's next turn
+            // queues behind the others waiting.
+            if let Some(slot) = self.turn_slot(&msg).await {
+                let usage_metrics = {
+                    let metrics = self.usage_metrics.read().await;
+                    metrics.clone()
+                };
+                self.process_inbound_message(&msg, usage_metrics)
+                    .instrument(request_span(&msg))
+                    .await;
+                drop(slot);
+                self.record_turn_load().await;
+            }
+            // Anything still queued once the loop stops waits for the
+            // session's next turn.
+            if next.is_empty() && self.is_running() {
+                next.extend(self.take_pending_messages(&msg).await);
+            }
+        }
+        session_key
+    }
+
+    /// A slot for `msg`'s turn under `agents.defaults.concurrency`, waiting
+    /// for one if needed. Tells the chat when the wait runs long, and sends
+    /// `busy_message` instead when the queue is full (`None`).
+    async fn turn_slot(&self, msg: &InboundMessage) -> Option<TurnSlot> {
+        let concurrency = &self.config.agents.defaults.concurrency;
+        let slot = match self.turns.request(&msg.session_key) {
+            Admission::Ready(slot) => slot,
+            Admission::Full => {
+                warn!(session = %msg.session_key, "Turn queue full, turning message away");
+                if !msg.is_heartbeat() {
+                    self.tell_chat(msg, &concurrency.busy_message).await;
+                }
+                return None;
+            }
+            Admission::Queued(mut queued) => {
+                self.record_turn_load().await;
+                let notice_after = concurrency.queued_notice_secs;
+                if notice_after == 0 || msg.is_heartbeat() {
+                    queued.admitted().await
+                } else {
+                    let wait = std::time::Duration::from_secs(notice_after);
+                    match tokio::time::timeout(wait, queued.admitted()).await {
+                        Ok(slot) => slot,
+                        Err(_) => {
+                            let ahead = queued.ahead();
+                            debug!(session = %msg.session_key, ahead, "Turn still queued");
+                            self.tell_chat(msg, &format!("Queued, {} ahead of you…", ahead))
+                                .await;
+                            queued.admitted().await
+                        }
+                    }
+                }
+            }
+        };
+        self.record_turn_load().await;
+        Some(slot)
+    }
+
+    /// Send `text` to `msg`'s chat outside any turn.
+    async fn tell_chat(&self, msg: &InboundMessage, text: &str) {
+        let mut outbound = OutboundMessage::new(&msg.channel, &msg.chat_id, text);
+        propagate_routing_metadata(&mut outbound, msg);
+        if let Err(e) = self.bus.publish_outbound(outbound).await {
+            error!(error = %e, "Failed to publish message to chat");
+        }
+    }
+
+    /// Report the turns running and queued to the usage metrics.
+    async fn record_turn_load(&self) {
+        if let Some(metrics) = self.usage_metrics.read().await.as_ref() {
+            let load = self.turns.load();
+            metrics.set_turn_load(load.in_flight as u64, load.queued as u64);
+        }
+    }
+
+    /// Heartbeat prompts for the `agents.heartbeats` entries due at `now`,
+    /// one per target session.
+    async fn due_heartbeats(
+        &self,
+        scheduler: &mut HeartbeatScheduler,
+        now: std::time::Instant,
+    ) -> Vec<InboundMessage> {
+        let schedules = &self.config.agents.heartbeats;
+        let due = scheduler.due(schedules, now);
+        if due.is_empty() {
+            return Vec::new();
+        }
+        let sessions = if due.iter().any(|target| !target.contains(':')) {
+            self.session_manager.list().await.unwrap_or_else(|e| {
+                warn!(error = %e, "Failed to list sessions for heartbeats");
+                Vec::new()
+            })
+        } else {
+            Vec::new()
+        };
+        let mut messages = Vec::new();
+        for target in due {
+            let prompt = &schedules[target].prompt;
+            for session_key in heartbeat::targets(target, schedules, &sessions) {
+                match heartbeat::heartbeat_message(&session_key, prompt) {
+                    Some(msg) => messages.push(msg),
+                    None => warn!(target, "Heartbeat target is not a session key or namespace"),
+                }
+            }
+        }
+        messages
+    }
+
+    /// Drop a quiet heartbeat's exchange from its session, so checks with
+    /// nothing to report don't fill the history. `from` is the session's
+    /// length before the turn; nothing is dropped if the history was
+    /// compacted or rotated meanwhile.
+    async fn forget_heartbeat(&self, msg: &InboundMessage, from: usize) {
+        let result = self
+            .session_manager
+            .with_session_mut(&msg.session_key, |session| {
+                let is_prompt = session
+                    .messages
+                    .get(from)
+                    .is_some_and(|m| m.role == Role::User && m.content == msg.content);
+                if is_prompt {
+                    session.messages.truncate(from);
+                }
+            })
+            .await;
+        if let Err(e) = result {
+            warn!(session = %msg.session_key, error = %e, "Failed to drop quiet heartbeat");
+        }
+    }
+
+    /// Shut the agent down gracefully, e.g. on SIGINT or SIGTERM.
+    ///
+    /// Stops taking messages from the bus and gives turns already under way
+    /// up to `timeout` to finish. Turns still running after that are
+    /// cancelled and end with a "Stopped." reply, as with `/stop`. Then waits
+    /// for session writes to land, kills background processes started with
+    /// the `process` tool and shuts down MCP clients. The future returned by
+    /// [`start()`](Self::start) has resolved by the time this returns, unless
+    /// a cancelled turn failed to end within another `timeout`.
+    pub async fn shutdown(&self, timeout: std::time::Duration) {
+        info!(timeout_secs = timeout.as_secs(), "Shutting down agent loop");
+        let mut stopped = self.stopped_tx.subscribe();
+        self.stop();
+        if tokio::time::timeout(timeout, stopped.wait_for(|stopped| *stopped))
+            .await
+            .is_err()
+        {
+            let cancelled = self.bus.cancel_all_turns();
+            warn!(
+                cancelled,
+                "Turns still running at shutdown, cancelling them"
+            );
+            if tokio::time::timeout(timeout, stopped.wait_for(|stopped| *stopped))
+                .await
+                .is_err()
+            {
+                error!("Agent loop did not stop after its turns were cancelled");
+            }
+        }
+        if let Err(e) = self.session_manager.flush().await {
+            warn!(error = %e, "Failed to flush session writes at shutdown");
+        }
+        self.shutdown_processes().await;
+        self.shutdown_mcp_clients().await;
+        info!("Agent shut down");
+    }
+
     /// Stop the agent loop.
     ///
     /// This signals the loop to stop immediately (after completing any
@@ -3348,6 +6153,18 @@ impl AgentLoop {
         let _ = self.shutdown_tx.send(true);
     }
 
+    /// Stop the turn running for `session_key`: its provider request and
+    /// tool calls are abandoned, and it ends with a "Stopped." reply that is
+    /// marked `cancelled` in the session. Returns `false` if no turn is
+    /// running for the session.
+    pub fn cancel(&self, session_key: &str) -> bool {
+        let cancelled = self.bus.cancel_turn(session_key);
+        if cancelled {
+            info!(session = %session_key, "Cancelling running turn");
+        }
+        cancelled
+    }
+
     /// Gracefully shut down all registered MCP clients (reaps stdio child
     /// processes).  Call this once during final teardown — NOT from `stop()`,
     /// which must remain restart-safe.
@@ -3364,6 +6181,13 @@ impl AgentLoop {
         }
     }
 
+    /// Kill all background processes started through the `process` tool and
+    /// wait for them to be reaped. Like `shutdown_mcp_clients()`, call this
+    /// once during final teardown, not from `stop()`.
+    pub async fn shutdown_processes(&self) {
+        self.processes.shutdown().await;
+    }
+
     /// Get a reference to the session manager.
     pub fn session_manager(&self) -> &Arc<SessionManager> {
         &self.session_manager
@@ -3395,11 +6219,12 @@ impl AgentLoop {
         self.streaming.load(Ordering::SeqCst)
     }
 
-    /// Enable or disable dry-run mode.
+    /// Enable or disable dry-run mode for every conversation.
     ///
-    /// When enabled, tool calls are intercepted and a description of
-    /// what *would* happen is returned instead of actually executing
-    /// the tool.
+    /// When enabled, state-changing tools describe what they would do
+    /// instead of acting (see [`crate::tools::Tool::dry_run`]), while
+    /// read-only tools run normally. This takes precedence over a chat's
+    /// `/dryrun off`.
     pub fn set_dry_run(&self, enabled: bool) {
         self.dry_run.store(enabled, Ordering::SeqCst);
     }
@@ -3409,138 +6234,2707 @@ impl AgentLoop {
         self.dry_run.load(Ordering::SeqCst)
     }
 
-    /// Format a dry-run result describing what a tool call would do.
-    fn dry_run_result(
-        name: &str,
-        args: &serde_json::Value,
-        raw_args: &str,
-        budget: usize,
-    ) -> String {
-        let args_display =
-            serde_json::to_string_pretty(args).unwrap_or_else(|_| raw_args.to_string());
-        let sanitized = crate::utils::sanitize::sanitize_tool_result(&args_display, budget);
-        format!(
-            "[DRY RUN] Would execute tool '{}' with arguments: {}",
-            name, sanitized
-        )
+    /// Dry-run mode set for every conversation, by [`Self::set_dry_run`] or
+    /// `tools.dry_run`. `/dryrun off` cannot turn this off.
+    fn dry_run_forced(&self) -> bool {
+        self.is_dry_run() || self.config.tools.dry_run
+    }
+
+    /// The trace of the last traced turn in `session_key`, including one
+    /// still running. `None` if no turn there was traced since the agent
+    /// started.
+    pub fn last_trace(&self, session_key: &str) -> Option<Arc<TurnTrace>> {
+        self.traces.last(session_key)
+    }
+
+    /// Start tracing a turn in `session` if tracing is on for it.
+    fn begin_trace(&self, session: &crate::session::Session) -> Option<ActiveTrace> {
+        session
+            .trace
+            .unwrap_or(self.config.agents.defaults.trace.enabled)
+            .then(|| self.traces.begin(&session.key))
+    }
+
+    /// Filesystem sandbox for `workspace` with the configured deny and
+    /// read-only path lists.
+    fn sandbox(&self, workspace: &str) -> Arc<Sandbox> {
+        Arc::new(Sandbox::from_config(
+            workspace,
+            &self.config.tools.filesystem,
+        ))
+    }
+
+    /// Set tool feedback sender for CLI tool execution display.
+    pub async fn set_tool_feedback(&self, tx: tokio::sync::mpsc::UnboundedSender<ToolFeedback>) {
+        *self.tool_feedback_tx.write().await = Some(tx);
+    }
+
+    /// Set the long-term memory source for per-message prompt injection.
+    pub fn set_ltm(
+        &mut self,
+        ltm: Arc<tokio::sync::Mutex<crate::memory::longterm::LongTermMemory>>,
+    ) {
+        self.ltm = Some(ltm);
+    }
+
+    /// Set the taint engine (shared with kernel for uniform taint tracking).
+    pub fn set_taint(&mut self, taint: Arc<std::sync::RwLock<crate::safety::taint::TaintEngine>>) {
+        self.taint = Some(taint);
+    }
+
+    /// Set the panel event bus for real-time dashboard events.
+    #[cfg(feature = "panel")]
+    pub fn set_event_bus(&mut self, bus: crate::api::events::EventBus) {
+        self.event_bus = Some(bus);
+    }
+
+    /// Get a reference to the token budget tracker.
+    pub fn token_budget(&self) -> &TokenBudget {
+        &self.token_budget
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::TraceKind;
+    use crate::hooks::{HookAction, HookRule};
+    use crate::providers::structured::OutputFormat;
+    use crate::providers::{LLMResponse, StreamEvent, ToolDefinition, Usage};
+    use crate::session::ThinkingBlock;
+    use crate::tools::ToolCategory;
+    use async_trait::async_trait;
+
+    #[derive(Debug)]
+    struct TestProvider {
+        name: &'static str,
+        model: &'static str,
+    }
+
+    struct ToolThenTextProvider {
+        calls: std::sync::Mutex<u8>,
+        tool_name: &'static str,
+        tool_args: &'static str,
+    }
+
+    #[async_trait]
+    impl LLMProvider for TestProvider {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn default_model(&self) -> &str {
+            self.model
+        }
+
+        async fn chat(
+            &self,
+            _messages: Vec<Message>,
+            _tools: Vec<ToolDefinition>,
+            _model: Option<&str>,
+            _options: ChatOptions,
+        ) -> Result<LLMResponse> {
+            Ok(LLMResponse::text("ok"))
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for ToolThenTextProvider {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        fn default_model(&self) -> &str {
+            "test-model"
+        }
+
+        async fn chat(
+            &self,
+            _messages: Vec<Message>,
+            _tools: Vec<ToolDefinition>,
+            _model: Option<&str>,
+            _options: ChatOptions,
+        ) -> Result<LLMResponse> {
+            let mut calls = self.calls.lock().expect("provider call counter poisoned");
+            *calls += 1;
+            if *calls == 1 {
+                Ok(LLMResponse::with_tools(
+                    "",
+                    vec![LLMToolCall::new("call_1", self.tool_name, self.tool_args)],
+                )
+                .with_usage(Usage::new(10, 1)))
+            } else {
+                let call_num = *calls as u32;
+                Ok(LLMResponse::text("done").with_usage(Usage::new(10 + call_num, call_num)))
+            }
+        }
+    }
+
+    /// Answers each call with the next of `replies`, recording the output
+    /// format it was asked for.
+    struct ScriptedProvider {
+        replies: std::sync::Mutex<Vec<&'static str>>,
+        formats: std::sync::Mutex<Vec<OutputFormat>>,
+    }
+
+    impl ScriptedProvider {
+        fn new(replies: &[&'static str]) -> Self {
+            Self {
+                replies: std::sync::Mutex::new(replies.iter().rev().copied().collect()),
+                formats: std::sync::Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for ScriptedProvider {
+        fn name(&self) -> &str {
+            "scripted"
+        }
+
+        fn default_model(&self) -> &str {
+            "test-model"
+        }
+
+        async fn chat(
+            &self,
+            _messages: Vec<Message>,
+            _tools: Vec<ToolDefinition>,
+            _model: Option<&str>,
+            options: ChatOptions,
+        ) -> Result<LLMResponse> {
+            self.formats.lock().unwrap().push(options.output_format);
+            let reply = self.replies.lock().unwrap().pop().expect("no reply left");
+            Ok(LLMResponse::text(reply))
+        }
+    }
+
+    async fn collect_stream_done(
+        mut rx: tokio::sync::mpsc::Receiver<StreamEvent>,
+    ) -> (String, Option<Usage>) {
+        while let Some(event) = rx.recv().await {
+            match event {
+                StreamEvent::Done { content, usage } => return (content, usage),
+                StreamEvent::Delta(_) | StreamEvent::Thinking(_) => {}
+                StreamEvent::ToolCalls(tool_calls) => {
+                    panic!("unexpected tool calls in final stream: {:?}", tool_calls)
+                }
+                StreamEvent::Error(err) => panic!("unexpected stream error: {err}"),
+            }
+        }
+        panic!("stream ended without a Done event");
+    }
+
+    #[test]
+    fn test_help_lists_every_command() {
+        assert_eq!(parse_session_command("/help"), Some(SessionCommand::Help));
+        assert_eq!(parse_session_command("/help me"), None);
+        let list = format_command_list(&default_commands());
+        for command in session_commands() {
+            let name = format!("/{}", command.name());
+            assert!(parse_session_command(&name).is_some(), "{name}");
+            assert!(list.contains(&command.help_line()), "{name}");
+        }
+        assert!(list.contains("- /pin: Keep"));
+        assert!(list.contains("- /dryrun [on|off]: "));
+        assert!(list.contains("- /whoami: "));
+    }
+
+    #[tokio::test]
+    async fn test_new_conversations_are_welcomed_once() {
+        let mut config = Config::default();
+        config.agents.onboarding = HashMap::from([
+            (
+                "telegram".to_string(),
+                crate::config::OnboardingConfig {
+                    message: "Welcome!\n{commands}\nTools: {tools}".to_string(),
+                    ..Default::default()
+                },
+            ),
+            (
+                "slack".to_string(),
+                crate::config::OnboardingConfig {
+                    message: "Hi, see /help.".to_string(),
+                    mode: OnboardingMode::Instead,
+                },
+            ),
+        ]);
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        let provider = Arc::new(SettingsProbeProvider::default());
+        agent.set_provider_arc(provider.clone()).await;
+        agent.register_tool(Box::new(SlowTool)).await;
+
+        let tg = InboundMessage::new("telegram", "user1", "chat1", "hello");
+        let first = agent.process_message(&tg).await.unwrap();
+        assert!(first.starts_with("Welcome!\n- /help: Show these commands\n"));
+        assert!(first.ends_with("Tools: slow\n\nok"), "{first}");
+        assert_eq!(agent.process_message(&tg).await.unwrap(), "ok");
+        let session = agent
+            .session_manager()
+            .get(&tg.session_key)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(session.onboarded);
+        assert!(session
+            .messages
+            .iter()
+            .all(|m| !m.content.contains("Welcome")));
+
+        // Instead of an answer, and never on channels without a welcome.
+        let slack = InboundMessage::new("slack", "user1", "C1", "hello");
+        assert_eq!(
+            agent.process_message(&slack).await.unwrap(),
+            "Hi, see /help."
+        );
+        assert_eq!(provider.calls.lock().unwrap().len(), 2);
+        assert_eq!(agent.process_message(&slack).await.unwrap(), "ok");
+        let discord = InboundMessage::new("discord", "user1", "D1", "hello");
+        assert_eq!(agent.process_message(&discord).await.unwrap(), "ok");
+    }
+
+    #[tokio::test]
+    async fn test_chat_commands_answer_without_the_model() {
+        let agent = AgentLoop::new(
+            Config::default(),
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        let provider = Arc::new(SettingsProbeProvider::default());
+        agent.set_provider_arc(provider.clone()).await;
+        let send = |text: &str| InboundMessage::new("telegram", "user1", "chat1", text);
+
+        assert_eq!(agent.process_message(&send("hello")).await.unwrap(), "ok");
+        assert_eq!(
+            agent.process_message(&send("/history")).await.unwrap(),
+            "[user] hello\n[assistant] ok"
+        );
+        assert_eq!(
+            agent.process_message(&send("/history 1 2")).await.unwrap(),
+            "Usage: /history [n]"
+        );
+        let export = agent.process_message(&send("/export")).await.unwrap();
+        assert!(export.starts_with("# Session telegram:chat1\n"), "{export}");
+        assert!(export.contains("### User\n\nhello"));
+        assert_eq!(
+            agent.process_message(&send("/whoami")).await.unwrap(),
+            "Session: telegram:chat1\nAgent: default"
+        );
+        agent.process_message(&send("/tag vip")).await.unwrap();
+        assert_eq!(
+            agent.process_message(&send("/reset")).await.unwrap(),
+            "Conversation cleared (2 messages)."
+        );
+        let session = agent
+            .session_manager()
+            .get("telegram:chat1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(session.messages.is_empty());
+        assert!(session.tags.contains("vip"));
+        assert_eq!(provider.calls.lock().unwrap().len(), 1);
+
+        // Registered commands are listed and replace their namesakes,
+        // session commands included.
+        agent
+            .register_command(Command::new(
+                "ping",
+                "Check the agent is up",
+                |_ctx| async { Ok("pong".to_string()) },
+            ))
+            .await;
+        agent
+            .register_command(Command::new("whoami", "Say who", |ctx| async move {
+                Ok(ctx.msg.sender_id)
+            }))
+            .await;
+        agent
+            .register_command(Command::new("/plan", "Show the roadmap", |_ctx| async {
+                Ok("ship it".to_string())
+            }))
+            .await;
+        assert_eq!(agent.process_message(&send("/ping")).await.unwrap(), "pong");
+        assert_eq!(
+            agent.process_message(&send("/whoami")).await.unwrap(),
+            "user1"
+        );
+        assert_eq!(
+            agent.process_message(&send("/plan")).await.unwrap(),
+            "ship it"
+        );
+        let help = agent.process_message(&send("/help")).await.unwrap();
+        assert!(help.contains("\n- /history [n]: Show the last messages"));
+        assert!(help.contains("\n- /plan: Show the roadmap\n"), "{help}");
+        assert!(!help.contains("Show the agent's plan"), "{help}");
+        assert!(help.ends_with("\n- /ping: Check the agent is up"), "{help}");
+
+        // Unknown commands go to the model unless commands are strict.
+        assert_eq!(agent.process_message(&send("/nope")).await.unwrap(), "ok");
+        assert_eq!(provider.calls.lock().unwrap().len(), 2);
+        let mut config = Config::default();
+        config.agents.defaults.strict_commands = true;
+        let strict = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        strict.set_provider_arc(provider.clone()).await;
+        assert_eq!(
+            strict.process_message(&send("/nope")).await.unwrap(),
+            "Unknown command /nope. Send /help for the commands."
+        );
+        assert_eq!(
+            strict.process_message(&send("/audit all")).await.unwrap(),
+            "Usage: /audit [n]"
+        );
+        assert_eq!(
+            strict
+                .process_message(&send("/usr/bin is gone"))
+                .await
+                .unwrap(),
+            "ok"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_authorization_limits_senders_and_roles() {
+        use crate::config::{ChannelAuthorization, UserRole};
+
+        async fn ask(bus: &MessageBus, sender: &str, text: &str) -> String {
+            bus.publish_inbound(InboundMessage::new("telegram", sender, sender, text))
+                .await
+                .unwrap();
+            bus.consume_outbound().await.unwrap().content
+        }
+
+        let mut config = Config::default();
+        config.agents.authorization.channels.insert(
+            "telegram".to_string(),
+            ChannelAuthorization {
+                users: HashMap::from([
+                    ("alice".to_string(), UserRole::Admin),
+                    ("bob".to_string(), UserRole::User),
+                ]),
+                chats: HashMap::from([("lobby".to_string(), UserRole::Readonly)]),
+                default_role: None,
+            },
+        );
+        let bus = Arc::new(MessageBus::new());
+        let agent = Arc::new(AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::clone(&bus),
+        ));
+        let provider = Arc::new(SettingsProbeProvider::default());
+        agent.set_provider_arc(provider.clone()).await;
+        let running = {
+            let agent = Arc::clone(&agent);
+            tokio::spawn(async move { agent.start().await })
+        };
+
+        // Strangers are turned away before a session exists.
+        assert_eq!(
+            ask(&bus, "mallory", "hello").await,
+            "Sorry, you're not allowed to use this bot."
+        );
+        assert!(agent
+            .session_manager()
+            .get("telegram:mallory")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(provider.calls.lock().unwrap().is_empty());
+
+        assert_eq!(ask(&bus, "bob", "hello").await, "ok");
+        assert_eq!(
+            ask(&bus, "bob", "/model gpt-4o").await,
+            "Only admins can use /model."
+        );
+        assert_eq!(
+            ask(&bus, "bob", "/reset telegram:alice").await,
+            "Only admins can reset other conversations."
+        );
+        assert_eq!(
+            ask(&bus, "alice", "/reset telegram:bob").await,
+            "Conversation telegram:bob cleared (2 messages)."
+        );
+        assert_ne!(
+            ask(&bus, "alice", "/model").await,
+            "Only admins can use /model."
+        );
+        for command in ["/dryrun off", "/trace on", "/brief on", "/audit"] {
+            let name = command.split(' ').next().unwrap();
+            assert_eq!(
+                ask(&bus, "bob", command).await,
+                format!("Only admins can use {}.", name)
+            );
+        }
+        assert_eq!(
+            ask(&bus, "bob", "/artifacts clear").await,
+            "Only admins can clear artifacts."
+        );
+        assert_eq!(
+            ask(&bus, "bob", "/artifacts").await,
+            "This conversation has no artifacts."
+        );
+        assert_eq!(
+            ask(&bus, "alice", "/artifacts clear").await,
+            "Removed 0 artifact(s)."
+        );
+        assert!(ask(&bus, "alice", "/trace")
+            .await
+            .starts_with("Tracing is off"));
+
+        // Roles limit the tools offered.
+        let readonly = InboundMessage::new("telegram", "carol", "lobby", "hi");
+        let user = InboundMessage::new("telegram", "bob", "bob", "hi");
+        assert!(!agent.tool_access_for(&readonly, None).allows("shell"));
+        assert!(agent.tool_access_for(&user, None).allows("shell"));
+
+        agent.shutdown(std::time::Duration::from_secs(1)).await;
+        running.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_parse_session_command() {
+        assert_eq!(
+            parse_session_command("/pin"),
+            Some(SessionCommand::Pin(true))
+        );
+        assert_eq!(
+            parse_session_command("  /unpin  "),
+            Some(SessionCommand::Pin(false))
+        );
+        assert_eq!(parse_session_command("/pinned"), None);
+        assert_eq!(parse_session_command("/pin this"), None);
+        assert_eq!(parse_session_command("pin this"), None);
+        assert_eq!(
+            parse_session_command("/tag support vip"),
+            Some(SessionCommand::Tag(vec!["support".into(), "vip".into()]))
+        );
+        assert_eq!(
+            parse_session_command("/tag"),
+            Some(SessionCommand::Tag(vec![]))
+        );
+        assert_eq!(
+            parse_session_command("/untag vip"),
+            Some(SessionCommand::Untag(vec!["vip".into()]))
+        );
+        assert_eq!(parse_session_command("/tags"), None);
+        assert_eq!(parse_session_command("/tools"), Some(SessionCommand::Tools));
+        assert_eq!(parse_session_command("/tools all"), None);
+        assert_eq!(
+            parse_session_command("/artifacts"),
+            Some(SessionCommand::Artifacts(false))
+        );
+        assert_eq!(
+            parse_session_command("/artifacts clear"),
+            Some(SessionCommand::Artifacts(true))
+        );
+        assert_eq!(parse_session_command("/artifacts delete"), None);
+        assert_eq!(parse_session_command("/plan"), Some(SessionCommand::Plan));
+        assert_eq!(parse_session_command("/plan show"), None);
+        assert_eq!(parse_session_command("/stats"), Some(SessionCommand::Stats));
+        assert_eq!(
+            parse_session_command("/dryrun"),
+            Some(SessionCommand::DryRun(None))
+        );
+        assert_eq!(
+            parse_session_command("/dryrun on"),
+            Some(SessionCommand::DryRun(Some(true)))
+        );
+        assert_eq!(
+            parse_session_command("/dryrun off"),
+            Some(SessionCommand::DryRun(Some(false)))
+        );
+        assert_eq!(parse_session_command("/dryrun maybe"), None);
+        assert_eq!(
+            parse_session_command("/brief"),
+            Some(SessionCommand::Brief(None))
+        );
+        assert_eq!(
+            parse_session_command("/brief off"),
+            Some(SessionCommand::Brief(Some(false)))
+        );
+        assert_eq!(parse_session_command("/brief all"), None);
+        assert_eq!(
+            parse_session_command("/trace on"),
+            Some(SessionCommand::Trace(Some(true)))
+        );
+        assert_eq!(
+            parse_session_command("/trace"),
+            Some(SessionCommand::Trace(None))
+        );
+        assert_eq!(parse_session_command("/trace all"), None);
+        assert_eq!(parse_session_command("/stop"), Some(SessionCommand::Stop));
+        assert_eq!(parse_session_command("/stop now"), None);
+        assert_eq!(
+            parse_session_command("/agent"),
+            Some(SessionCommand::Agent(None))
+        );
+        assert_eq!(
+            parse_session_command("/agent coding"),
+            Some(SessionCommand::Agent(Some("coding".to_string())))
+        );
+        assert_eq!(parse_session_command("/agent coding now"), None);
+        assert_eq!(parse_session_command("/stats all"), None);
+        assert_eq!(
+            parse_session_command("/audit"),
+            Some(SessionCommand::Audit(AUDIT_DEFAULT_ENTRIES))
+        );
+        assert_eq!(
+            parse_session_command("/audit 3"),
+            Some(SessionCommand::Audit(3))
+        );
+        assert_eq!(
+            parse_session_command("/audit 100000"),
+            Some(SessionCommand::Audit(AUDIT_MAX_ENTRIES))
+        );
+        assert_eq!(parse_session_command("/audit 0"), None);
+        assert_eq!(parse_session_command("/audit all"), None);
+        assert_eq!(
+            parse_session_command("/model"),
+            Some(SessionCommand::Models)
+        );
+        assert_eq!(
+            parse_session_command("/model claude-haiku"),
+            Some(SessionCommand::Set(
+                Setting::Model,
+                Some("claude-haiku".to_string())
+            ))
+        );
+        assert_eq!(
+            parse_session_command("/temp reset"),
+            Some(SessionCommand::Set(Setting::Temperature, None))
+        );
+        assert_eq!(
+            parse_session_command("/settings top_p 0.9"),
+            Some(SessionCommand::Set(Setting::TopP, Some("0.9".to_string())))
+        );
+        assert_eq!(
+            parse_session_command("/settings reset"),
+            Some(SessionCommand::ResetSettings)
+        );
+        assert_eq!(
+            parse_session_command("/think 4096"),
+            Some(SessionCommand::Set(
+                Setting::ThinkingBudget,
+                Some("4096".to_string())
+            ))
+        );
+        assert_eq!(
+            parse_session_command("/think"),
+            Some(SessionCommand::Settings)
+        );
+        assert_eq!(parse_session_command("/settings seed 4"), None);
+        assert_eq!(parse_session_command("/temp 1 2"), None);
+    }
+
+    fn deny_on_telegram(tool: &str) -> Config {
+        let mut config = Config::default();
+        config.tools.access.channels.insert(
+            "telegram".to_string(),
+            crate::config::ToolAccessRule {
+                allow: None,
+                deny: vec![tool.to_string()],
+            },
+        );
+        config
+    }
+
+    #[test]
+    fn test_tool_call_timeout_overrides() {
+        let default = std::time::Duration::from_secs(60);
+        let overrides = HashMap::from([("web_fetch".to_string(), 20), ("fast".to_string(), 0)]);
+        assert_eq!(
+            tool_call_timeout(default, &overrides, "web_fetch"),
+            std::time::Duration::from_secs(20)
+        );
+        assert_eq!(
+            tool_call_timeout(default, &overrides, "fast"),
+            std::time::Duration::from_secs(1)
+        );
+        assert_eq!(tool_call_timeout(default, &overrides, "shell"), default);
+    }
+
+    #[test]
+    fn test_limit_to_turn_budget() {
+        let timeout = std::time::Duration::from_secs(30);
+        let now = std::time::Instant::now();
+        assert_eq!(limit_to_turn_budget(timeout, None), Some((timeout, false)));
+        assert_eq!(
+            limit_to_turn_budget(timeout, Some(now + std::time::Duration::from_secs(600))),
+            Some((timeout, false))
+        );
+        let (cut, by_budget) =
+            limit_to_turn_budget(timeout, Some(now + std::time::Duration::from_secs(5))).unwrap();
+        assert!(by_budget);
+        assert!(cut <= std::time::Duration::from_secs(5));
+        assert_eq!(limit_to_turn_budget(timeout, Some(now)), None);
+        assert!(turn_budget_spent_message("shell", 90).contains("90s tool time budget"));
+    }
+
+    #[test]
+    fn test_turn_error_reply_hides_provider_response() {
+        let replies = crate::config::ErrorRepliesConfig::default();
+        let raw = r#"Claude API error: rate_limit_error - {"request_id":"req_123"}"#;
+        let err = ZeptoError::from(ProviderError::RateLimit(raw.into()));
+        let reply = ErrorReply::new(&err, &replies).text;
+        assert!(reply.contains("rate limiting"));
+        assert!(!reply.contains("req_123"));
+
+        let err = ZeptoError::Provider("HTTP 529: overloaded".into());
+        let reply = ErrorReply::new(&err, &replies).text;
+        assert!(reply.contains("overloaded"));
+        assert!(!reply.contains("529"));
+
+        let err = ZeptoError::Session("bad state".into());
+        let reply = ErrorReply::new(&err, &replies);
+        assert!(!reply.text.contains("bad state"));
+        assert!(reply.text.contains(&reply.reference));
+    }
+
+    struct SlowTool;
+
+    #[async_trait]
+    impl Tool for SlowTool {
+        fn name(&self) -> &str {
+            "slow"
+        }
+        fn description(&self) -> &str {
+            ""
+        }
+        fn parameters(&self) -> serde_json::Value {
+            serde_json::json!({})
+        }
+        fn category(&self) -> ToolCategory {
+            ToolCategory::Memory
+        }
+        async fn execute(
+            &self,
+            _args: serde_json::Value,
+            _ctx: &ToolContext,
+        ) -> std::result::Result<crate::tools::ToolOutput, crate::error::ZeptoError> {
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+            Ok(crate::tools::ToolOutput::llm_only("finally"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_per_tool_timeout_cancels_call_and_records_elapsed() {
+        let mut config = Config::default();
+        config
+            .agents
+            .defaults
+            .tool_timeouts
+            .insert("slow".to_string(), 1);
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        agent
+            .set_provider(Box::new(ToolThenTextProvider {
+                calls: std::sync::Mutex::new(0),
+                tool_name: "slow",
+                tool_args: "{}",
+            }))
+            .await;
+        agent.register_tool(Box::new(SlowTool)).await;
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "go");
+        assert_eq!(agent.process_message(&msg).await.unwrap(), "done");
+        let session = agent
+            .session_manager()
+            .get(&msg.session_key)
+            .await
+            .unwrap()
+            .unwrap();
+        let result = session
+            .messages
+            .iter()
+            .find(|m| m.role == Role::Tool)
+            .expect("tool result recorded");
+        assert_eq!(result.content, "Error: Tool 'slow' timed out after 1s");
+        let elapsed = result.tool_elapsed_ms.expect("elapsed recorded");
+        assert!((1000..30_000).contains(&elapsed), "elapsed {elapsed}ms");
+    }
+
+    /// Thinks before calling a tool, then answers; records the messages of
+    /// each call.
+    #[derive(Default)]
+    struct ThinkingProvider {
+        calls: std::sync::Mutex<Vec<Vec<Message>>>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for ThinkingProvider {
+        fn name(&self) -> &str {
+            "thinking"
+        }
+
+        fn default_model(&self) -> &str {
+            "test-model"
+        }
+
+        async fn chat(
+            &self,
+            messages: Vec<Message>,
+            _tools: Vec<ToolDefinition>,
+            _model: Option<&str>,
+            _options: ChatOptions,
+        ) -> Result<LLMResponse> {
+            let mut calls = self.calls.lock().unwrap();
+            calls.push(messages);
+            let block = |text: &str| ThinkingBlock {
+                thinking: text.to_string(),
+                signature: "sig".to_string(),
+                redacted: None,
+            };
+            if calls.len() == 1 {
+                Ok(
+                    LLMResponse::with_tools("", vec![LLMToolCall::new("call_1", "lookup", "{}")])
+                        .with_thinking(vec![block("Look it up first.")])
+                        .with_usage(Usage::new(10, 40).with_thinking(30)),
+                )
+            } else {
+                Ok(LLMResponse::text("done")
+                    .with_thinking(vec![block("That answers it.")])
+                    .with_usage(Usage::new(20, 10).with_thinking(5)))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_thinking_is_stored_apart_from_content_and_sent_back() {
+        let agent = AgentLoop::new(
+            Config::default(),
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        let provider = Arc::new(ThinkingProvider::default());
+        agent.set_provider_arc(provider.clone()).await;
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "go");
+        assert_eq!(agent.process_message(&msg).await.unwrap(), "done");
+
+        // The tool call's thinking goes back with it in the next call.
+        {
+            let calls = provider.calls.lock().unwrap();
+            let sent = calls[1]
+                .iter()
+                .find(|m| m.tool_calls.is_some())
+                .expect("tool call sent back");
+            assert_eq!(sent.thinking[0].thinking, "Look it up first.");
+        }
+
+        let session = agent
+            .session_manager()
+            .get(&msg.session_key)
+            .await
+            .unwrap()
+            .unwrap();
+        let reply = session.messages.last().unwrap();
+        assert_eq!(reply.content, "done");
+        assert_eq!(reply.thinking[0].thinking, "That answers it.");
+        assert!(session
+            .messages
+            .iter()
+            .all(|m| !m.content.contains("Look it up")));
+        assert_eq!(reply.usage.unwrap().thinking_tokens, 35);
+
+        let pricing = HashMap::from([(
+            "m".to_string(),
+            ModelPricing {
+                input_cost_per_million: 0.0,
+                output_cost_per_million: 1_000_000.0,
+            },
+        )]);
+        let cost = usage_cost("m", Some(&Usage::new(10, 40).with_thinking(30)), &pricing);
+        assert!((cost.cost_usd - 40.0).abs() < 1e-9);
+        assert!((cost.thinking_cost_usd - 30.0).abs() < 1e-9);
+    }
+
+    /// Says it is looking something up and calls the slow tool.
+    struct LookupProvider;
+
+    #[async_trait]
+    impl LLMProvider for LookupProvider {
+        fn name(&self) -> &str {
+            "lookup"
+        }
+
+        fn default_model(&self) -> &str {
+            "test-model"
+        }
+
+        async fn chat(
+            &self,
+            _messages: Vec<Message>,
+            _tools: Vec<ToolDefinition>,
+            _model: Option<&str>,
+            _options: ChatOptions,
+        ) -> Result<LLMResponse> {
+            Ok(LLMResponse::with_tools(
+                "Let me look that up.",
+                vec![LLMToolCall::new("call_1", "slow", "{}")],
+            ))
+        }
+    }
+
+    #[test]
+    fn test_turn_time_limit_per_channel() {
+        let mut defaults = AgentDefaults::default();
+        assert_eq!(turn_time_limit(&defaults, "telegram"), None);
+        defaults.turn_timeout_secs = 120;
+        defaults.turn_timeouts.insert("telegram".to_string(), 30);
+        defaults.turn_timeouts.insert("cli".to_string(), 0);
+        let secs = |channel: &str| turn_time_limit(&defaults, channel).map(|d| d.as_secs());
+        assert_eq!(secs("telegram"), Some(30));
+        assert_eq!(secs("slack"), Some(120));
+        assert_eq!(secs("cli"), None);
+    }
+
+    #[tokio::test]
+    async fn test_turn_time_limit_delivers_partial_reply() {
+        let mut config = Config::default();
+        config
+            .agents
+            .defaults
+            .turn_timeouts
+            .insert("telegram".to_string(), 1);
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        agent.set_provider(Box::new(LookupProvider)).await;
+        agent.register_tool(Box::new(SlowTool)).await;
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "find it");
+        let reply = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            agent.process_message(&msg),
+        )
+        .await
+        .expect("turn ends at its time limit")
+        .unwrap();
+        assert_eq!(
+            reply,
+            "Let me look that up.\n\n(stopped early due to time limit)"
+        );
+
+        // Every tool call has its result, and the reply is marked cancelled.
+        let session = agent
+            .session_manager()
+            .get(&msg.session_key)
+            .await
+            .unwrap()
+            .unwrap();
+        let result = session
+            .messages
+            .iter()
+            .find(|m| m.role == Role::Tool)
+            .expect("tool result recorded");
+        assert_eq!(result.tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(
+            result.content,
+            "Error: Tool 'slow' was cancelled because this turn ran out of time."
+        );
+        let last = session.messages.last().unwrap();
+        assert_eq!(last.content, reply);
+        assert!(last.cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_turn_time_limit_without_output_apologizes() {
+        let mut config = Config::default();
+        config.agents.defaults.turn_timeout_secs = 1;
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        agent.set_provider(Box::new(HangingProvider)).await;
+
+        let msg = InboundMessage::new("slack", "user1", "chat1", "write an essay");
+        let reply = agent.process_message(&msg).await.unwrap();
+        assert!(reply.contains("within the 1s time limit"), "{reply}");
+        assert!(!reply.contains(TIME_LIMIT_NOTE));
+    }
+
+    #[tokio::test]
+    async fn test_turn_usage_is_priced_totalled_and_budgeted() {
+        let mut config = Config::default();
+        config.cost.custom_pricing.insert(
+            config.agents.defaults.model.clone(),
+            ModelPricing {
+                input_cost_per_million: 1000.0,
+                output_cost_per_million: 2000.0,
+            },
+        );
+        config.cost.show_cost = true;
+        config.cost.monthly_budget_usd = Some(0.04);
+        config.cost.budget_action = BudgetAction::Refuse;
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        agent
+            .set_provider(Box::new(ToolThenTextProvider {
+                calls: std::sync::Mutex::new(0),
+                tool_name: "missing",
+                tool_args: "{}",
+            }))
+            .await;
+
+        // Calls use 10+1 and 12+2 tokens: 22 in, 3 out.
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "go");
+        let reply = agent.process_message(&msg).await.unwrap();
+        assert_eq!(
+            reply,
+            "done\n\n(cost: $0.0280 this turn, $0.0280 in this conversation)"
+        );
+        let session = agent
+            .session_manager()
+            .get(&msg.session_key)
+            .await
+            .unwrap()
+            .unwrap();
+        let turn = session.messages.last().unwrap().usage.unwrap();
+        assert_eq!((turn.input_tokens, turn.output_tokens), (22, 3));
+        assert!((session.total_cost() - 0.028).abs() < 1e-9);
+
+        // The next turn (13 in, 3 out) still starts under the budget...
+        agent.process_message(&msg).await.unwrap();
+        assert!((agent.monthly_spend_usd() - 0.047).abs() < 1e-9);
+        // ...and takes the month past it, so the one after is refused.
+        let err = agent.process_message(&msg).await.unwrap_err();
+        assert!(matches!(err, ZeptoError::QuotaRejected(_)), "{err}");
+    }
+
+    #[test]
+    fn test_usage_cost_prices_prompt_cache_and_savings() {
+        let pricing = HashMap::from([(
+            "m".to_string(),
+            ModelPricing {
+                input_cost_per_million: 1_000_000.0,
+                output_cost_per_million: 0.0,
+            },
+        )]);
+        // 100 prompt tokens: 80 read from the cache, 20 sent normally.
+        let usage = Usage::new(100, 0).with_cache(80, 0);
+        let cost = usage_cost("m", Some(&usage), &pricing);
+        assert_eq!((cost.cache_read_tokens, cost.cache_write_tokens), (80, 0));
+        assert!((cost.cost_usd - 28.0).abs() < 1e-9);
+        assert!((cost.cache_savings_usd - 72.0).abs() < 1e-9);
+
+        // Writing the cache costs a little more than not caching.
+        let usage = Usage::new(100, 0).with_cache(0, 100);
+        let cost = usage_cost("m", Some(&usage), &pricing);
+        assert!((cost.cache_savings_usd + 25.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_structured_reply_is_validated_and_retried_once() {
+        let format = OutputFormat::json_schema(
+            "answer",
+            serde_json::json!({
+                "type": "object",
+                "properties": {"answer": {"type": "integer"}},
+                "required": ["answer"]
+            }),
+        );
+        let msg = InboundMessage::new("api", "user1", "chat1", "6 times 7?")
+            .with_response_format(&format);
+
+        let agent = AgentLoop::new(
+            Config::default(),
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        let provider = Arc::new(ScriptedProvider::new(&[
+            "It is 42.",
+            "```json\n{\"answer\": 42}\n```",
+        ]));
+        agent.set_provider_arc(provider.clone()).await;
+        assert_eq!(
+            agent.process_message(&msg).await.unwrap(),
+            r#"{"answer":42}"#
+        );
+        assert_eq!(*provider.formats.lock().unwrap(), vec![format.clone(); 2]);
+        let session = agent
+            .session_manager()
+            .get(&msg.session_key)
+            .await
+            .unwrap()
+            .unwrap();
+        let correction = &session.messages[session.messages.len() - 2];
+        assert!(correction.content.starts_with("[Format]"));
+
+        // A second miss fails the turn instead of returning bad JSON.
+        let agent = AgentLoop::new(
+            Config::default(),
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        let provider = ScriptedProvider::new(&["{}", r#"{"answer": "42"}"#]);
+        agent.set_provider(Box::new(provider)).await;
+        let err = agent.process_message(&msg).await.unwrap_err();
+        assert!(matches!(err, ZeptoError::InvalidOutput(_)), "{err}");
+        assert!(err.to_string().contains("answer"));
+    }
+
+    #[tokio::test]
+    async fn test_hooks_gate_rewrite_and_sign_turns() {
+        use crate::agent::hooks::{AllowlistHook, RegexFilterHook};
+
+        struct Signature;
+
+        #[async_trait]
+        impl AgentHook for Signature {
+            fn name(&self) -> &str {
+                "signature"
+            }
+
+            async fn after_turn(&self, response: &mut OutboundResponse) {
+                response.content.push_str("\n-- bot");
+            }
+        }
+
+        let bus = Arc::new(MessageBus::new());
+        let agent = AgentLoop::new(
+            Config::default(),
+            SessionManager::new_memory(),
+            Arc::clone(&bus),
+        );
+        agent
+            .set_provider(Box::new(ScriptedProvider::new(&["ok"])))
+            .await;
+        agent
+            .register_hook(Box::new(
+                RegexFilterHook::new(&["@bot\\s*"])
+                    .unwrap()
+                    .with_replacement("")
+                    .incoming_only(),
+            ))
+            .await;
+        agent
+            .register_hook(Box::new(AllowlistHook::new(["telegram:alice"])))
+            .await;
+        agent.register_hook(Box::new(Signature)).await;
+
+        let msg = InboundMessage::new("telegram", "alice", "chat1", "@bot hello");
+        assert_eq!(agent.process_message(&msg).await.unwrap(), "ok\n-- bot");
+        let session = agent
+            .session_manager()
+            .get(&msg.session_key)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(session.messages[0].content, "hello");
+
+        // Rejected messages never reach the provider, and get no reply.
+        let stranger = InboundMessage::new("telegram", "mallory", "chat2", "hi");
+        let err = agent.process_message(&stranger).await.unwrap_err();
+        assert!(matches!(err, ZeptoError::Rejected(_)), "{err}");
+        agent.process_inbound_message(&stranger, None).await;
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(50), bus.consume_outbound())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tools_command_reports_access_for_channel() {
+        let agent = AgentLoop::new(
+            deny_on_telegram("lookup"),
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        agent
+            .register_tool(Box::new(StubTool {
+                name: "lookup",
+                category: ToolCategory::Memory,
+            }))
+            .await;
+        agent.register_tool(Box::new(crate::tools::EchoTool)).await;
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "/tools");
+        assert_eq!(
+            agent.process_message(&msg).await.unwrap(),
+            "Active tools (1): echo\nDisabled here: lookup"
+        );
+        let msg = InboundMessage::new("cli", "user1", "chat1", "/tools");
+        assert_eq!(
+            agent.process_message(&msg).await.unwrap(),
+            "Active tools (2): echo, lookup"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tools_command_reports_remaining_quota() {
+        let mut config = Config::default();
+        config
+            .tools
+            .rate_limits
+            .insert("echo".to_string(), "3/min".to_string());
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        agent.register_tool(Box::new(crate::tools::EchoTool)).await;
+
+        let ctx = ToolContext::new().with_channel("telegram", "chat1");
+        agent
+            .tools
+            .read()
+            .await
+            .execute_with_context("echo", serde_json::json!({"message": "hi"}), &ctx)
+            .await
+            .unwrap();
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "/tools");
+        assert_eq!(
+            agent.process_message(&msg).await.unwrap(),
+            "Active tools (1): echo\nRate limits: echo 2 of 3/min left"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disabled_tool_call_is_not_executed() {
+        let agent = AgentLoop::new(
+            deny_on_telegram("lookup"),
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        agent
+            .set_provider(Box::new(ToolThenTextProvider {
+                calls: std::sync::Mutex::new(0),
+                tool_name: "lookup",
+                tool_args: "{}",
+            }))
+            .await;
+        agent
+            .register_tool(Box::new(StubTool {
+                name: "lookup",
+                category: ToolCategory::Memory,
+            }))
+            .await;
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "find it");
+        assert_eq!(agent.process_message(&msg).await.unwrap(), "done");
+        let session = agent
+            .session_manager()
+            .get(&msg.session_key)
+            .await
+            .unwrap()
+            .unwrap();
+        let result = session
+            .messages
+            .iter()
+            .find(|m| m.role == Role::Tool)
+            .expect("tool result recorded");
+        assert_eq!(
+            result.content,
+            "Tool 'lookup' is not available in this context"
+        );
+    }
+
+    /// Asks for a tool whenever tools are offered.
+    struct AlwaysToolsProvider;
+
+    #[async_trait]
+    impl LLMProvider for AlwaysToolsProvider {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        fn default_model(&self) -> &str {
+            "test-model"
+        }
+
+        async fn chat(
+            &self,
+            messages: Vec<Message>,
+            tools: Vec<ToolDefinition>,
+            _model: Option<&str>,
+            _options: ChatOptions,
+        ) -> Result<LLMResponse> {
+            if tools.is_empty() {
+                let told = messages
+                    .iter()
+                    .any(|m| m.role == Role::System && m.content.starts_with("[Tool limit]"));
+                return Ok(LLMResponse::text(if told { "summary" } else { "?" }));
+            }
+            Ok(LLMResponse::with_tools(
+                "",
+                vec![LLMToolCall::new("call", "lookup", "{}")],
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_iteration_limit_ends_with_answer_without_tools() {
+        let mut config = Config::default();
+        config.agents.defaults.max_tool_iterations = 3;
+        config.agents.defaults.loop_guard.enabled = false;
+        let telegram = crate::config::SessionOptions {
+            max_tool_iterations: Some(1),
+            ..Default::default()
+        };
+        let session_manager = SessionManager::new_memory()
+            .with_channel_defaults(HashMap::from([("telegram".to_string(), telegram)]));
+        let agent = AgentLoop::new(config, session_manager, Arc::new(MessageBus::new()));
+        agent.set_provider(Box::new(AlwaysToolsProvider)).await;
+        agent
+            .register_tool(Box::new(StubTool {
+                name: "lookup",
+                category: ToolCategory::Memory,
+            }))
+            .await;
+
+        for (channel, iterations) in [("telegram", 1), ("discord", 3)] {
+            let msg = InboundMessage::new(channel, "user1", "chat1", "find it");
+            let reply = agent.process_message(&msg).await.unwrap();
+            assert_eq!(
+                reply,
+                format!("summary{}", tool_iteration_limit_note(iterations))
+            );
+
+            let session = agent
+                .session_manager()
+                .get(&msg.session_key)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(session.messages.last().unwrap().content, "summary");
+            let tool_results = session
+                .messages
+                .iter()
+                .filter(|m| m.role == Role::Tool)
+                .count();
+            assert_eq!(tool_results, iterations as usize);
+            assert_eq!(session.tool_iterations.last_turn, iterations);
+            assert_eq!(session.tool_iterations.limit_hits, 1);
+            assert!(session.tool_iterations.last_limit_hit.is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeated_identical_tool_call_is_answered_without_running() {
+        let mut config = Config::default();
+        config.agents.defaults.max_tool_iterations = 3;
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        agent.set_provider(Box::new(AlwaysToolsProvider)).await;
+        agent
+            .register_tool(Box::new(StubTool {
+                name: "lookup",
+                category: ToolCategory::Memory,
+            }))
+            .await;
+
+        let msg = InboundMessage::new("cli", "user1", "chat1", "find it");
+        agent.process_message(&msg).await.unwrap();
+        let session = agent
+            .session_manager()
+            .get(&msg.session_key)
+            .await
+            .unwrap()
+            .unwrap();
+        let results: Vec<&str> = session
+            .messages
+            .iter()
+            .filter(|m| m.role == Role::Tool)
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(results.len(), 3);
+        assert_eq!(&results[..2], ["ok", "ok"]);
+        assert_eq!(results[2], repeated_call_message("lookup", 3));
+
+        let metrics = agent.metrics_collector().tool_metrics("lookup").unwrap();
+        assert_eq!((metrics.call_count, metrics.repeat_count), (2, 1));
+    }
+
+    /// A recorded request: system prompt, tool names and model.
+    type ProfileProbeCall = (String, Vec<String>, Option<String>);
+
+    /// Calls `lookup` on the first request and answers every later one,
+    /// recording each request's system prompt, tool names and model.
+    #[derive(Default)]
+    struct ProfileProbeProvider {
+        calls: std::sync::Mutex<Vec<ProfileProbeCall>>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for ProfileProbeProvider {
+        fn name(&self) -> &str {
+            "probe"
+        }
+
+        fn default_model(&self) -> &str {
+            "test-model"
+        }
+
+        async fn chat(
+            &self,
+            messages: Vec<Message>,
+            tools: Vec<ToolDefinition>,
+            model: Option<&str>,
+            _options: ChatOptions,
+        ) -> Result<LLMResponse> {
+            let system = messages
+                .first()
+                .filter(|m| m.role == Role::System)
+                .map(|m| m.content.clone())
+                .unwrap_or_default();
+            let mut tools: Vec<String> = tools.into_iter().map(|t| t.name).collect();
+            tools.sort();
+            let mut calls = self.calls.lock().unwrap();
+            calls.push((system, tools, model.map(str::to_string)));
+            if calls.len() == 1 {
+                return Ok(LLMResponse::with_tools(
+                    "",
+                    vec![LLMToolCall::new("call_1", "lookup", "{}")],
+                ));
+            }
+            Ok(LLMResponse::text("done"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_profiles_route_switch_and_record_turns() {
+        let mut config = Config::default();
+        config.agents.profiles = HashMap::from([
+            (
+                "coding".to_string(),
+                AgentProfile {
+                    system_prompt: "You write code.".to_string(),
+                    tools: Some(vec!["lookup".to_string()]),
+                    model: Some("coder-model".to_string()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "support".to_string(),
+                AgentProfile {
+                    system_prompt: "Be kind.".to_string(),
+                    ..Default::default()
+                },
+            ),
+        ]);
+        config.agents.routes = HashMap::from([("slack".to_string(), "support".to_string())]);
+        config.agents.default_profile = Some("coding".to_string());
+        let default_model = config.agents.defaults.model.clone();
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        let provider = Arc::new(ProfileProbeProvider::default());
+        agent.set_provider_arc(provider.clone()).await;
+        for name in ["lookup", "notes"] {
+            agent
+                .register_tool(Box::new(StubTool {
+                    name,
+                    category: ToolCategory::Memory,
+                }))
+                .await;
+        }
+
+        // The default profile answers, with its prompt, tools and model.
+        let cli = |text: &str| InboundMessage::new("cli", "user1", "chat1", text);
+        agent.process_message(&cli("fix the bug")).await.unwrap();
+        {
+            let calls = provider.calls.lock().unwrap();
+            assert_eq!(calls.len(), 2);
+            let (system, tools, model) = &calls[0];
+            assert!(system.ends_with("You write code."));
+            assert_eq!(tools, &["lookup"]);
+            assert_eq!(model.as_deref(), Some("coder-model"));
+        }
+
+        // `/agent` switches from the next turn on.
+        assert_eq!(
+            agent.process_message(&cli("/agent support")).await.unwrap(),
+            "Switched to agent 'support'."
+        );
+        assert!(agent
+            .process_message(&cli("/agent nobody"))
+            .await
+            .unwrap()
+            .starts_with("Unknown agent 'nobody'."));
+        agent.process_message(&cli("thanks")).await.unwrap();
+        {
+            let calls = provider.calls.lock().unwrap();
+            let (system, tools, model) = &calls[2];
+            assert!(system.ends_with("Be kind."));
+            assert!(!system.contains("You write code."));
+            assert_eq!(tools, &["lookup", "notes"]);
+            assert_eq!(model.as_deref(), Some(default_model.as_str()));
+        }
+
+        // Each turn records who answered; the first turn's tool call and
+        // result stay paired across the switch.
+        let session = agent
+            .session_manager()
+            .get("cli:chat1")
+            .await
+            .unwrap()
+            .unwrap();
+        let answered: Vec<Option<&str>> = session
+            .messages
+            .iter()
+            .filter(|m| m.role == Role::Assistant && m.tool_calls.is_none())
+            .map(|m| m.agent.as_deref())
+            .collect();
+        assert_eq!(answered, [Some("coding"), Some("support")]);
+        let (_, repairs) = crate::session::repair::repair_messages(session.messages.clone());
+        assert_eq!(repairs.orphan_tool_results_removed, 0);
+
+        // Sessions in a routed namespace get the routed profile.
+        let slack = InboundMessage::new("slack", "user2", "C1", "hello");
+        agent.process_message(&slack).await.unwrap();
+        assert!(provider.calls.lock().unwrap()[3].0.ends_with("Be kind."));
+        assert_eq!(
+            agent.process_message(&cli("/agent auto")).await.unwrap(),
+            "Following routing again: agent 'coding'."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_workspace_brief_follows_config_and_brief_command() {
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::write(workspace.path().join("Cargo.toml"), "[package]\n").unwrap();
+        let mut config = Config::default();
+        config.agents.defaults.workspace = workspace.path().to_string_lossy().to_string();
+        config.agents.defaults.workspace_brief.enabled = true;
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        let provider = Arc::new(ProfileProbeProvider::default());
+        agent.set_provider_arc(provider.clone()).await;
+        agent
+            .register_tool(Box::new(StubTool {
+                name: "lookup",
+                category: ToolCategory::Memory,
+            }))
+            .await;
+
+        let cli = |text: &str| InboundMessage::new("cli", "user1", "chat1", text);
+        agent.process_message(&cli("build it")).await.unwrap();
+        {
+            let calls = provider.calls.lock().unwrap();
+            assert!(calls[0].0.contains("## Workspace"));
+            assert!(calls[0].0.contains("Toolchains: Rust (cargo)"));
+        }
+
+        assert!(agent
+            .process_message(&cli("/brief off"))
+            .await
+            .unwrap()
+            .starts_with("Workspace brief off."));
+        agent.process_message(&cli("again")).await.unwrap();
+        assert!(!provider.calls.lock().unwrap()[2].0.contains("## Workspace"));
+        assert!(agent
+            .process_message(&cli("/brief"))
+            .await
+            .unwrap()
+            .starts_with("The workspace brief is off."));
+    }
+
+    #[tokio::test]
+    async fn test_retrieval_adds_matching_excerpts_to_the_system_prompt() {
+        let docs = tempfile::tempdir().unwrap();
+        std::fs::write(
+            docs.path().join("deploy.md"),
+            "# Deploying\n\nRun `make release` and tag the commit.",
+        )
+        .unwrap();
+        let mut config = Config::default();
+        config.agents.defaults.retrieval.dir = Some(docs.path().to_string_lossy().to_string());
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        let provider = Arc::new(ProfileProbeProvider::default());
+        agent.set_provider_arc(provider.clone()).await;
+        agent
+            .register_tool(Box::new(StubTool {
+                name: "lookup",
+                category: ToolCategory::Memory,
+            }))
+            .await;
+
+        let cli = |text: &str| InboundMessage::new("cli", "user1", "chat1", text);
+        agent
+            .process_message(&cli("How do we deploy a release?"))
+            .await
+            .unwrap();
+        agent.process_message(&cli("thanks")).await.unwrap();
+
+        let calls = provider.calls.lock().unwrap();
+        // Both iterations of the first turn see the excerpt.
+        for call in &calls[..2] {
+            assert!(call.0.contains("## Reference Excerpts"));
+            assert!(call.0.contains("[deploy.md:1-3]\n# Deploying"));
+        }
+        assert!(!calls[2].0.contains("## Reference Excerpts"));
+    }
+
+    #[tokio::test]
+    async fn test_trace_command_records_provider_calls_and_tools() {
+        let traces = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.agents.defaults.trace.dir = Some(traces.path().to_string_lossy().to_string());
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        agent
+            .set_provider_arc(Arc::new(ProfileProbeProvider::default()))
+            .await;
+        agent
+            .register_tool(Box::new(StubTool {
+                name: "lookup",
+                category: ToolCategory::Memory,
+            }))
+            .await;
+
+        let cli = |text: &str| InboundMessage::new("cli", "user1", "chat1", text);
+        agent.process_message(&cli("untraced")).await.unwrap();
+        assert!(agent.last_trace("cli:chat1").is_none());
+
+        assert!(agent
+            .process_message(&cli("/trace on"))
+            .await
+            .unwrap()
+            .starts_with("Tracing on."));
+        // A fresh probe so the traced turn starts with the tool call again.
+        agent
+            .set_provider_arc(Arc::new(ProfileProbeProvider::default()))
+            .await;
+        agent.process_message(&cli("look it up")).await.unwrap();
+
+        let trace = agent.last_trace("cli:chat1").expect("turn traced");
+        assert!(!trace.is_open());
+        let entries = trace.entries();
+        let kinds: Vec<TraceKind> = entries.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                TraceKind::Context,
+                TraceKind::Request,
+                TraceKind::Response,
+                TraceKind::Tool,
+                TraceKind::Context,
+                TraceKind::Request,
+                TraceKind::Response,
+            ]
+        );
+        assert_eq!(entries[1].data["provider"], "probe");
+        assert_eq!(entries[3].data["name"], "lookup");
+        assert!(entries[3].data["duration_ms"].is_u64());
+        let dir = trace.dir().unwrap();
+        assert!(dir.starts_with(traces.path().join("cli_chat1")));
+        assert!(dir.join("004-tool.json").exists());
+
+        let status = agent.process_message(&cli("/trace")).await.unwrap();
+        assert!(status.starts_with("Tracing is on"));
+        assert!(status.ends_with(&format!("Last trace: turn {}", trace.turn_id())));
+        assert!(!status.contains(&traces.path().display().to_string()));
+    }
+
+    /// Answers every request, recording its model and options.
+    #[derive(Default)]
+    struct SettingsProbeProvider {
+        calls: std::sync::Mutex<Vec<(Option<String>, ChatOptions)>>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for SettingsProbeProvider {
+        fn name(&self) -> &str {
+            "probe"
+        }
+
+        fn default_model(&self) -> &str {
+            "test-model"
+        }
+
+        async fn chat(
+            &self,
+            _messages: Vec<Message>,
+            _tools: Vec<ToolDefinition>,
+            model: Option<&str>,
+            options: ChatOptions,
+        ) -> Result<LLMResponse> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((model.map(str::to_string), options));
+            Ok(LLMResponse::text("ok"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_response_options_constrain_channel_replies() {
+        let mut config = Config::default();
+        config.agents.defaults.stream_to_channels = true;
+        config.agents.response_options = HashMap::from([(
+            "telegram".to_string(),
+            crate::config::ResponseOptions {
+                stop: Some(vec!["END".to_string()]),
+                max_length: Some(12),
+                strip_markdown: Some(true),
+                ..Default::default()
+            },
+        )]);
+        let bus = Arc::new(MessageBus::new());
+        let agent = AgentLoop::new(config, SessionManager::new_memory(), Arc::clone(&bus));
+        let probe = Arc::new(SettingsProbeProvider::default());
+        agent.set_provider_arc(probe.clone()).await;
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "status?");
+        agent.process_inbound_message(&msg, None).await;
+        assert_eq!(bus.consume_outbound().await.unwrap().content, "ok");
+        let stop = probe.calls.lock().unwrap()[0].1.stop.clone();
+        assert_eq!(stop, Some(vec!["END".to_string()]));
+        assert!(agent.reply_stream_for(&msg).is_none());
+
+        agent
+            .set_provider(Box::new(ScriptedProvider::new(&[
+                "**Done.** All good here.",
+            ])))
+            .await;
+        agent.process_inbound_message(&msg, None).await;
+        assert_eq!(bus.consume_outbound().await.unwrap().content, "Done.");
+    }
+
+    #[tokio::test]
+    async fn test_session_settings_apply_to_later_turns() {
+        let mut config = Config::default();
+        config.agents.defaults.model = "claude-sonnet-4-6".to_string();
+        config.agents.defaults.temperature = 0.7;
+        config.providers.anthropic = Some(crate::config::ProviderConfig {
+            api_key: Some("sk-ant".to_string()),
+            ..Default::default()
+        });
+        config.agents.channel_settings = HashMap::from([(
+            "telegram".to_string(),
+            GenerationSettings {
+                max_tokens: Some(1024),
+                ..Default::default()
+            },
+        )]);
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        let provider = Arc::new(SettingsProbeProvider::default());
+        agent.set_provider_arc(provider.clone()).await;
+        let tg = |text: &str| InboundMessage::new("telegram", "user1", "chat1", text);
+
+        // Unknown models and out-of-range values are refused.
+        assert!(agent
+            .process_message(&tg("/model gpt-9"))
+            .await
+            .unwrap()
+            .starts_with("Unknown model 'gpt-9'."));
+        assert_eq!(
+            agent.process_message(&tg("/temp 3")).await.unwrap(),
+            "temperature must be a number from 0 to 2."
+        );
+
+        assert_eq!(
+            agent
+                .process_message(&tg("/model claude-haiku-4-5-20251001"))
+                .await
+                .unwrap(),
+            "model set to claude-haiku-4-5-20251001 for this conversation."
+        );
+        agent.process_message(&tg("/temp 1.2")).await.unwrap();
+        let shown = agent.process_message(&tg("/settings")).await.unwrap();
+        assert!(shown.contains("- model: claude-haiku-4-5-20251001 (this conversation)"));
+        assert!(shown.contains("- max_tokens: 1024\n"));
+
+        agent.process_message(&tg("brainstorm")).await.unwrap();
+        {
+            let calls = provider.calls.lock().unwrap();
+            let (model, options) = &calls[0];
+            assert_eq!(model.as_deref(), Some("claude-haiku-4-5-20251001"));
+            assert_eq!(options.temperature, Some(1.2));
+            assert_eq!(options.max_tokens, Some(1024));
+        }
+
+        // A message's own override beats the conversation's settings.
+        let pinned = tg("again").with_settings_override(&GenerationSettings {
+            temperature: Some(0.0),
+            ..Default::default()
+        });
+        agent.process_message(&pinned).await.unwrap();
+        assert_eq!(provider.calls.lock().unwrap()[1].1.temperature, Some(0.0));
+
+        // Each reply records the settings it ran with.
+        let session = agent
+            .session_manager()
+            .get("telegram:chat1")
+            .await
+            .unwrap()
+            .unwrap();
+        let temperatures: Vec<Option<f32>> = session
+            .messages
+            .iter()
+            .filter_map(|m| m.settings.as_ref())
+            .map(|s| s.temperature)
+            .collect();
+        assert_eq!(temperatures, [Some(1.2), Some(0.0)]);
+
+        // Resetting follows the configuration again.
+        agent.process_message(&tg("/settings reset")).await.unwrap();
+        agent.process_message(&tg("plain")).await.unwrap();
+        let calls = provider.calls.lock().unwrap();
+        assert_eq!(calls[2].0.as_deref(), Some("claude-sonnet-4-6"));
+        assert_eq!(calls[2].1.temperature, Some(0.7));
+    }
+
+    /// Never answers.
+    struct HangingProvider;
+
+    #[async_trait]
+    impl LLMProvider for HangingProvider {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        fn default_model(&self) -> &str {
+            "test-model"
+        }
+
+        async fn chat(
+            &self,
+            _messages: Vec<Message>,
+            _tools: Vec<ToolDefinition>,
+            _model: Option<&str>,
+            _options: ChatOptions,
+        ) -> Result<LLMResponse> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_in_flight_provider_call() {
+        let agent = Arc::new(AgentLoop::new(
+            Config::default(),
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        ));
+        agent.set_provider(Box::new(HangingProvider)).await;
+        assert!(!agent.cancel("telegram:chat1"));
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "write an essay");
+        let turn = {
+            let agent = Arc::clone(&agent);
+            let msg = msg.clone();
+            tokio::spawn(async move { agent.process_message(&msg).await })
+        };
+        while !agent.cancel(&msg.session_key) {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        let reply = tokio::time::timeout(std::time::Duration::from_secs(5), turn)
+            .await
+            .expect("turn stops")
+            .unwrap()
+            .unwrap();
+        assert_eq!(reply, TURN_STOPPED_REPLY);
+
+        let session = agent
+            .session_manager()
+            .get(&msg.session_key)
+            .await
+            .unwrap()
+            .unwrap();
+        let last = session.messages.last().unwrap();
+        assert_eq!(last.content, TURN_STOPPED_REPLY);
+        assert!(last.cancelled);
+        assert!(!session.messages[session.messages.len() - 2].cancelled);
+    }
+
+    /// Wakes `started` when called, then answers after `delay`.
+    struct SlowProvider {
+        started: Arc<tokio::sync::Notify>,
+        delay: std::time::Duration,
+    }
+
+    #[async_trait]
+    impl LLMProvider for SlowProvider {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        fn default_model(&self) -> &str {
+            "test-model"
+        }
+
+        async fn chat(
+            &self,
+            _messages: Vec<Message>,
+            _tools: Vec<ToolDefinition>,
+            _model: Option<&str>,
+            _options: ChatOptions,
+        ) -> Result<LLMResponse> {
+            self.started.notify_one();
+            tokio::time::sleep(self.delay).await;
+            Ok(LLMResponse::text("finished"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_turns_in_flight() {
+        let bus = Arc::new(MessageBus::new());
+        let agent = Arc::new(AgentLoop::new(
+            Config::default(),
+            SessionManager::new_memory(),
+            Arc::clone(&bus),
+        ));
+        let started = Arc::new(tokio::sync::Notify::new());
+        agent
+            .set_provider(Box::new(SlowProvider {
+                started: Arc::clone(&started),
+                delay: std::time::Duration::from_millis(100),
+            }))
+            .await;
+        let running = {
+            let agent = Arc::clone(&agent);
+            tokio::spawn(async move { agent.start().await })
+        };
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "hello");
+        bus.publish_inbound(msg.clone()).await.unwrap();
+        started.notified().await;
+        agent.shutdown(std::time::Duration::from_secs(5)).await;
+
+        // The turn finished, was saved and answered, and start() returned.
+        tokio::time::timeout(std::time::Duration::from_secs(1), running)
+            .await
+            .expect("start() returns")
+            .unwrap()
+            .unwrap();
+        let session = agent
+            .session_manager()
+            .get(&msg.session_key)
+            .await
+            .unwrap()
+            .unwrap();
+        let last = session.messages.last().unwrap();
+        assert_eq!(last.content, "finished");
+        assert!(!last.cancelled);
+        let reply = bus.consume_outbound().await.unwrap();
+        assert_eq!(reply.content, "finished");
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_cancels_turns_past_the_timeout() {
+        let bus = Arc::new(MessageBus::new());
+        let agent = Arc::new(AgentLoop::new(
+            Config::default(),
+            SessionManager::new_memory(),
+            Arc::clone(&bus),
+        ));
+        let started = Arc::new(tokio::sync::Notify::new());
+        agent
+            .set_provider(Box::new(SlowProvider {
+                started: Arc::clone(&started),
+                delay: std::time::Duration::from_secs(3600),
+            }))
+            .await;
+        let running = {
+            let agent = Arc::clone(&agent);
+            tokio::spawn(async move { agent.start().await })
+        };
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "write an essay");
+        bus.publish_inbound(msg.clone()).await.unwrap();
+        started.notified().await;
+        agent.shutdown(std::time::Duration::from_millis(50)).await;
+
+        tokio::time::timeout(std::time::Duration::from_secs(1), running)
+            .await
+            .expect("start() returns")
+            .unwrap()
+            .unwrap();
+        let session = agent
+            .session_manager()
+            .get(&msg.session_key)
+            .await
+            .unwrap()
+            .unwrap();
+        let last = session.messages.last().unwrap();
+        assert_eq!(last.content, TURN_STOPPED_REPLY);
+        assert!(last.cancelled);
+    }
+
+    /// Echoes the latest user message after `delay`, recording each one.
+    struct EchoProvider {
+        seen: Arc<std::sync::Mutex<Vec<String>>>,
+        delay: std::time::Duration,
+    }
+
+    #[async_trait]
+    impl LLMProvider for EchoProvider {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn default_model(&self) -> &str {
+            "test-model"
+        }
+
+        async fn chat(
+            &self,
+            messages: Vec<Message>,
+            _tools: Vec<ToolDefinition>,
+            _model: Option<&str>,
+            _options: ChatOptions,
+        ) -> Result<LLMResponse> {
+            let content = messages
+                .iter()
+                .rev()
+                .find(|m| m.role == Role::User)
+                .map(|m| m.content.clone())
+                .unwrap_or_default();
+            self.seen.lock().unwrap().push(content.clone());
+            tokio::time::sleep(self.delay).await;
+            Ok(LLMResponse::text(&format!("re: {content}")))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_turn_limit_queues_then_turns_away() {
+        let mut config = Config::default();
+        config.agents.defaults.concurrency.max_turns = 1;
+        config.agents.defaults.concurrency.max_queued = 1;
+        config.agents.defaults.concurrency.queued_notice_secs = 0;
+        let bus = Arc::new(MessageBus::new());
+        let agent = Arc::new(AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::clone(&bus),
+        ));
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        agent
+            .set_provider(Box::new(EchoProvider {
+                seen: Arc::clone(&seen),
+                delay: std::time::Duration::from_millis(200),
+            }))
+            .await;
+        let metrics = Arc::new(UsageMetrics::new());
+        agent.set_usage_metrics(Arc::clone(&metrics)).await;
+        let running = {
+            let agent = Arc::clone(&agent);
+            tokio::spawn(async move { agent.start().await })
+        };
+        let send = |chat: &str, text: &str| {
+            bus.publish_inbound(InboundMessage::new("telegram", "user1", chat, text))
+        };
+
+        send("chat1", "first").await.unwrap();
+        while seen.lock().unwrap().is_empty() {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        send("chat2", "second").await.unwrap();
+        while metrics.queued_turns.load(Ordering::Relaxed) == 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        assert_eq!(metrics.in_flight_turns.load(Ordering::Relaxed), 1);
+
+        // The queue is full: the third chat is told to come back later.
+        send("chat3", "third").await.unwrap();
+        let busy = bus.consume_outbound().await.unwrap();
+        assert_eq!(busy.chat_id, "chat3");
+        assert_eq!(
+            busy.content,
+            "I'm handling a lot of messages right now. Please try again in a minute."
+        );
+
+        // The waiting turn runs once the first is done.
+        assert_eq!(bus.consume_outbound().await.unwrap().content, "re: first");
+        assert_eq!(bus.consume_outbound().await.unwrap().content, "re: second");
+        assert_eq!(*seen.lock().unwrap(), vec!["first", "second"]);
+
+        agent.shutdown(std::time::Duration::from_secs(1)).await;
+        running.await.unwrap().unwrap();
+        assert_eq!(agent.turns.load(), Default::default());
+        assert_eq!(metrics.in_flight_turns.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_redelivered_message_is_answered_once_across_restarts() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let agent_with = |config: Config| {
+            let seen = Arc::clone(&seen);
+            let sessions = SessionManager::with_path(tmp.path().to_path_buf()).unwrap();
+            async move {
+                let agent = AgentLoop::new(config, sessions, Arc::new(MessageBus::new()));
+                agent
+                    .set_provider(Box::new(EchoProvider {
+                        seen,
+                        delay: std::time::Duration::ZERO,
+                    }))
+                    .await;
+                agent
+            }
+        };
+        let hello = InboundMessage::new("telegram", "user1", "chat1", "hello")
+            .with_metadata("telegram_message_id", "7");
+
+        let agent = agent_with(Config::default()).await;
+        assert_eq!(agent.process_message(&hello).await.unwrap(), "re: hello");
+        let err = agent.process_message(&hello).await.unwrap_err();
+        assert!(
+            matches!(err, ZeptoError::Duplicate(ref id) if id == "7"),
+            "{err}"
+        );
+
+        // The IDs are saved with the session, so a restart remembers them.
+        let restarted = agent_with(Config::default()).await;
+        assert!(matches!(
+            restarted.process_message(&hello).await,
+            Err(ZeptoError::Duplicate(_))
+        ));
+        let again = InboundMessage::new("telegram", "user1", "chat1", "hello again")
+            .with_metadata("telegram_message_id", "8");
+        assert!(restarted.process_message(&again).await.is_ok());
+        assert_eq!(*seen.lock().unwrap(), ["hello", "hello again"]);
+
+        let mut config = Config::default();
+        config.agents.defaults.dedup_window = 0;
+        let undeduped = agent_with(config).await;
+        assert!(undeduped.process_message(&hello).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_queued_messages_run_in_arrival_order() {
+        let mut config = Config::default();
+        config.agents.defaults.message_queue_mode = crate::config::MessageQueueMode::Followup;
+        let bus = Arc::new(MessageBus::new());
+        let agent = Arc::new(AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::clone(&bus),
+        ));
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        agent
+            .set_provider(Box::new(EchoProvider {
+                seen: Arc::clone(&seen),
+                delay: std::time::Duration::from_millis(20),
+            }))
+            .await;
+
+        // The first message keeps the session busy while the rest queue up;
+        // one of them is a redelivery.
+        for (id, text) in [("1", "one"), ("2", "two"), ("2", "two"), ("3", "three")] {
+            let msg = InboundMessage::new("telegram", "user1", "chat1", text)
+                .with_metadata("telegram_message_id", id);
+            bus.publish_inbound(msg).await.unwrap();
+        }
+        let running = {
+            let agent = Arc::clone(&agent);
+            tokio::spawn(async move { agent.start().await })
+        };
+
+        let mut replies = Vec::new();
+        for _ in 0..3 {
+            let reply =
+                tokio::time::timeout(std::time::Duration::from_secs(5), bus.consume_outbound())
+                    .await
+                    .expect("reply arrives")
+                    .unwrap();
+            replies.push(reply.content);
+        }
+        assert_eq!(replies, ["re: one", "re: two", "re: three"]);
+        assert_eq!(*seen.lock().unwrap(), ["one", "two", "three"]);
+
+        agent.stop();
+        let _ = tokio::time::timeout(std::time::Duration::from_secs(1), running).await;
+    }
+
+    #[tokio::test]
+    async fn test_queued_messages_keep_their_senders_access() {
+        use crate::config::{ChannelAuthorization, UserRole};
+
+        let mut config = Config::default();
+        config.agents.defaults.message_queue_mode = crate::config::MessageQueueMode::Steer;
+        config.agents.authorization.channels.insert(
+            "telegram".to_string(),
+            ChannelAuthorization {
+                users: HashMap::from([
+                    ("alice".to_string(), UserRole::Admin),
+                    ("bob".to_string(), UserRole::User),
+                    ("carol".to_string(), UserRole::User),
+                ]),
+                ..Default::default()
+            },
+        );
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        let msg = InboundMessage::new("telegram", "alice", "team", "go");
+
+        for (sender, text) in [
+            ("alice", "a1"),
+            ("mallory", "m"),
+            ("bob", "b"),
+            ("carol", "c"),
+            ("alice", "a2"),
+        ] {
+            agent
+                .queue_pending(&InboundMessage::new("telegram", sender, "team", text))
+                .await;
+        }
+        let combined = agent.take_pending_messages(&msg).await;
+        let runs: Vec<(&str, &str)> = combined
+            .iter()
+            .map(|m| (m.sender_id.as_str(), m.content.as_str()))
+            .collect();
+        assert_eq!(
+            runs,
+            [
+                ("alice", "[Queued messages while I was busy]\n\n1. a1"),
+                ("bob", "[Queued messages while I was busy]\n\n1. b\n2. c"),
+                ("alice", "[Queued messages while I was busy]\n\n1. a2"),
+            ]
+        );
+
+        // Only the turn's own access is steered in; strangers are dropped.
+        for (sender, text) in [("bob", "b"), ("mallory", "m"), ("alice", "a")] {
+            agent
+                .queue_pending(&InboundMessage::new("telegram", sender, "team", text))
+                .await;
+        }
+        let mut session = crate::session::Session::new(&msg.session_key);
+        assert_eq!(agent.steer(&msg, &mut session).await, 1);
+        assert_eq!(session.messages[0].content, "a");
+        let pending = agent.pending_messages.lock().await;
+        let left: Vec<&str> = pending[&msg.session_key]
+            .iter()
+            .map(|m| m.sender_id.as_str())
+            .collect();
+        assert_eq!(left, ["bob"]);
+    }
+
+    #[tokio::test]
+    async fn test_steer_mode_folds_queued_messages_into_the_running_turn() {
+        let mut config = Config::default();
+        config.agents.defaults.message_queue_mode = crate::config::MessageQueueMode::Steer;
+        config.agents.defaults.message_queue_modes = HashMap::from([(
+            "telegram".to_string(),
+            crate::config::MessageQueueMode::Collect,
+        )]);
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        agent
+            .register_tool(Box::new(StubTool {
+                name: "deploy",
+                category: ToolCategory::Memory,
+            }))
+            .await;
+
+        for channel in ["slack", "telegram"] {
+            agent
+                .set_provider(Box::new(ToolThenTextProvider {
+                    calls: std::sync::Mutex::new(0),
+                    tool_name: "deploy",
+                    tool_args: "{}",
+                }))
+                .await;
+            // Messages sent while the turn runs: a correction, a
+            // redelivery of it and a command.
+            let correction = InboundMessage::new(channel, "user1", "chat1", "actually use staging")
+                .with_metadata("message_id", "2");
+            for queued in [
+                correction.clone(),
+                correction,
+                InboundMessage::new(channel, "user1", "chat1", "/stats"),
+            ] {
+                agent.queue_pending(&queued).await;
+            }
+            let msg = InboundMessage::new(channel, "user1", "chat1", "deploy to prod");
+            assert_eq!(agent.process_message(&msg).await.unwrap(), "done");
+
+            let session = agent
+                .session_manager
+                .get_or_create(&msg.session_key)
+                .await
+                .unwrap();
+            let users: Vec<&str> = session
+                .messages
+                .iter()
+                .filter(|m| m.role == Role::User)
+                .map(|m| m.content.as_str())
+                .collect();
+            let pending = agent.pending_messages.lock().await;
+            let left: Vec<&str> = pending[&msg.session_key]
+                .iter()
+                .map(|m| m.content.as_str())
+                .collect();
+            if channel == "slack" {
+                assert_eq!(users, ["deploy to prod", "actually use staging"]);
+                // After the tool result, before the next model call.
+                let roles: Vec<Role> = session.messages.iter().map(|m| m.role.clone()).collect();
+                assert_eq!(
+                    roles,
+                    [
+                        Role::User,
+                        Role::Assistant,
+                        Role::Tool,
+                        Role::User,
+                        Role::Assistant
+                    ]
+                );
+                assert_eq!(left, ["/stats"]);
+            } else {
+                assert_eq!(users, ["deploy to prod"]);
+                assert_eq!(left.len(), 3);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_identical_requests_are_answered_from_the_cache() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let bus = Arc::new(MessageBus::new());
+        let mut agent = AgentLoop::new(
+            Config::default(),
+            SessionManager::new_memory(),
+            Arc::clone(&bus),
+        );
+        let cache = Arc::new(std::sync::Mutex::new(ResponseCache::with_path(
+            tmp.path().join("responses.json"),
+            3600,
+            10,
+        )));
+        agent.cache = Some(Arc::clone(&cache));
+        agent
+            .set_provider(Box::new(ScriptedProvider::new(&["all green", "fresh"])))
+            .await;
+
+        // Same request in two new conversations, then one asking to skip
+        // the cache.
+        for (chat, expected, cached) in [
+            ("ci1", "all green", false),
+            ("ci2", "all green", true),
+            ("ci3", "fresh", false),
+        ] {
+            let mut msg = InboundMessage::new("webhook", "ci", chat, "Build status?");
+            if chat == "ci3" {
+                msg = msg.with_metadata(crate::bus::message::NO_CACHE_KEY, "true");
+            }
+            agent.process_inbound_message(&msg, None).await;
+            let reply = bus.consume_outbound().await.unwrap();
+            assert_eq!(reply.content, expected);
+            assert_eq!(reply.is_cached(), cached, "{chat}");
+        }
+        assert_eq!(agent.metrics_collector().cache_hits(), 1);
+        assert_eq!(cache.lock().unwrap().len(), 1);
+
+        // A turn whose first reply calls tools is not stored.
+        agent
+            .register_tool(Box::new(StubTool {
+                name: "deploy",
+                category: ToolCategory::Memory,
+            }))
+            .await;
+        agent
+            .set_provider(Box::new(ToolThenTextProvider {
+                calls: std::sync::Mutex::new(0),
+                tool_name: "deploy",
+                tool_args: "{}",
+            }))
+            .await;
+        let msg = InboundMessage::new("webhook", "ci", "ci4", "Deploy it");
+        assert_eq!(agent.process_message(&msg).await.unwrap(), "done");
+        assert_eq!(cache.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_reports_only_when_there_is_something_to_say() {
+        let mut config = Config::default();
+        config.agents.heartbeats = HashMap::from([(
+            "telegram".to_string(),
+            crate::config::HeartbeatSchedule {
+                interval_secs: 60,
+                prompt: "Check the inbox folder.".to_string(),
+            },
+        )]);
+        let bus = Arc::new(MessageBus::new());
+        let agent = AgentLoop::new(config, SessionManager::new_memory(), Arc::clone(&bus));
+        agent
+            .set_provider(Box::new(ScriptedProvider::new(&[
+                "hi",
+                "HEARTBEAT_OK",
+                "Two new files arrived in the inbox.",
+            ])))
+            .await;
+        let hello = InboundMessage::new("telegram", "user1", "chat1", "hello");
+        agent.process_message(&hello).await.unwrap();
+
+        // Heartbeats come due per session of the namespace.
+        let mut scheduler = HeartbeatScheduler::default();
+        let now = std::time::Instant::now();
+        assert!(agent.due_heartbeats(&mut scheduler, now).await.is_empty());
+        let later = now + std::time::Duration::from_secs(60);
+        let due = agent.due_heartbeats(&mut scheduler, later).await;
+        assert_eq!(due.len(), 1);
+        let heartbeat = due[0].clone();
+        assert_eq!(heartbeat.session_key, "telegram:chat1");
+
+        // Nothing to report: no reply, and no trace in the history.
+        agent.process_inbound_message(&heartbeat, None).await;
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(50), bus.consume_outbound())
+                .await
+                .is_err()
+        );
+        let count = agent.session_manager().message_count("telegram:chat1");
+        assert_eq!(count.await.unwrap(), 2);
+
+        // Something to report: sent to the chat and kept.
+        agent.process_inbound_message(&heartbeat, None).await;
+        let reply = bus.consume_outbound().await.unwrap();
+        assert_eq!(
+            (reply.chat_id.as_str(), reply.content.as_str()),
+            ("chat1", "Two new files arrived in the inbox.")
+        );
+        let count = agent.session_manager().message_count("telegram:chat1");
+        assert_eq!(count.await.unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_tag_command_tags_session_without_provider() {
+        let config = Config::default();
+        let session_manager = SessionManager::new_memory();
+        let bus = Arc::new(MessageBus::new());
+        let agent = AgentLoop::new(config, session_manager, bus);
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "/tag Support vip-user");
+        let reply = agent.process_message(&msg).await.unwrap();
+        assert_eq!(reply, "Tags: support, vip-user");
+        assert_eq!(
+            agent
+                .session_manager()
+                .find_by_tag("vip-user")
+                .await
+                .unwrap(),
+            vec![msg.session_key.clone()]
+        );
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "/untag vip-user");
+        assert_eq!(agent.process_message(&msg).await.unwrap(), "Tags: support");
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "/tag bad!tag");
+        assert!(agent
+            .process_message(&msg)
+            .await
+            .unwrap()
+            .starts_with("Invalid tag"));
+    }
+
+    #[tokio::test]
+    async fn test_pin_command_pins_session_without_provider() {
+        let config = Config::default();
+        let session_manager = SessionManager::new_memory();
+        let bus = Arc::new(MessageBus::new());
+        let agent = AgentLoop::new(config, session_manager, bus);
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "/pin");
+        let reply = agent.process_message(&msg).await.unwrap();
+        assert!(reply.contains("pinned"));
+        assert!(agent
+            .session_manager()
+            .is_pinned(&msg.session_key)
+            .await
+            .unwrap());
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "/unpin");
+        agent.process_message(&msg).await.unwrap();
+        assert!(!agent
+            .session_manager()
+            .is_pinned(&msg.session_key)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_memory_notes_survive_turn_save() {
+        let agent = AgentLoop::new(
+            Config::default(),
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        agent
+            .set_provider(Box::new(ToolThenTextProvider {
+                calls: std::sync::Mutex::new(0),
+                tool_name: "memory",
+                tool_args: r#"{"operation":"add","text":"prefers metric units"}"#,
+            }))
+            .await;
+        agent
+            .register_tool(Box::new(
+                crate::tools::session_memory::SessionMemoryTool::new(
+                    Arc::clone(agent.session_manager()),
+                    10,
+                ),
+            ))
+            .await;
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "I use metric");
+        assert_eq!(agent.process_message(&msg).await.unwrap(), "done");
+
+        let session = agent
+            .session_manager()
+            .get(&msg.session_key)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(session.notes.len(), 1);
+        assert_eq!(session.notes[0].text, "prefers metric units");
+        assert!(session.messages.len() >= 4);
+    }
+
+    #[tokio::test]
+    async fn test_plan_survives_turn_save_and_shows_in_plan_command() {
+        let agent = AgentLoop::new(
+            Config::default(),
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        agent
+            .set_provider(Box::new(ToolThenTextProvider {
+                calls: std::sync::Mutex::new(0),
+                tool_name: "todo",
+                tool_args: r#"{"operation":"set_plan","items":["fetch data","write report"]}"#,
+            }))
+            .await;
+        agent
+            .register_tool(Box::new(crate::tools::todo::TodoTool::new(Arc::clone(
+                agent.session_manager(),
+            ))))
+            .await;
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "make a report");
+        assert_eq!(agent.process_message(&msg).await.unwrap(), "done");
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "/plan");
+        assert_eq!(
+            agent.process_message(&msg).await.unwrap(),
+            "Plan:\n1. [ ] fetch data\n2. [ ] write report"
+        );
+        let msg = InboundMessage::new("telegram", "user1", "chat2", "/plan");
+        assert_eq!(
+            agent.process_message(&msg).await.unwrap(),
+            "This conversation has no plan."
+        );
     }
 
-    /// Set tool feedback sender for CLI tool execution display.
-    pub async fn set_tool_feedback(&self, tx: tokio::sync::mpsc::UnboundedSender<ToolFeedback>) {
-        *self.tool_feedback_tx.write().await = Some(tx);
-    }
+    #[tokio::test]
+    async fn test_tool_metrics_and_stats_command() {
+        // Echo is a shell-category tool, which assistant mode holds for approval.
+        let mut config = Config::default();
+        config.agent_mode.mode = "autonomous".to_string();
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        agent
+            .set_provider(Box::new(ToolThenTextProvider {
+                calls: std::sync::Mutex::new(0),
+                tool_name: "echo",
+                tool_args: r#"{"message":"hello"}"#,
+            }))
+            .await;
+        agent.register_tool(Box::new(crate::tools::EchoTool)).await;
 
-    /// Set the long-term memory source for per-message prompt injection.
-    pub fn set_ltm(
-        &mut self,
-        ltm: Arc<tokio::sync::Mutex<crate::memory::longterm::LongTermMemory>>,
-    ) {
-        self.ltm = Some(ltm);
-    }
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "say hello");
+        assert_eq!(agent.process_message(&msg).await.unwrap(), "done");
 
-    /// Set the taint engine (shared with kernel for uniform taint tracking).
-    pub fn set_taint(&mut self, taint: Arc<std::sync::RwLock<crate::safety::taint::TaintEngine>>) {
-        self.taint = Some(taint);
-    }
+        let usage = agent.tool_metrics().await;
+        assert_eq!(usage.snapshot()["echo"].calls, 1);
+        assert_eq!(usage.session(&msg.session_key)["echo"].errors, 0);
 
-    /// Set the panel event bus for real-time dashboard events.
-    #[cfg(feature = "panel")]
-    pub fn set_event_bus(&mut self, bus: crate::api::events::EventBus) {
-        self.event_bus = Some(bus);
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "/stats");
+        let reply = agent.process_message(&msg).await.unwrap();
+        assert!(
+            reply.starts_with("Tool usage in this conversation:\n- echo: 1 call, 0 errors"),
+            "{}",
+            reply
+        );
+        let msg = InboundMessage::new("telegram", "user1", "chat2", "/stats");
+        assert_eq!(
+            agent.process_message(&msg).await.unwrap(),
+            "No tools have run in this conversation yet."
+        );
     }
 
-    /// Get a reference to the token budget tracker.
-    pub fn token_budget(&self) -> &TokenBudget {
-        &self.token_budget
-    }
-}
+    #[tokio::test]
+    async fn test_audit_command_lists_recorded_commands() {
+        use crate::session::CommandOutcome;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::hooks::{HookAction, HookRule};
-    use crate::providers::{LLMResponse, StreamEvent, ToolDefinition, Usage};
-    use async_trait::async_trait;
+        let agent = AgentLoop::new(
+            Config::default(),
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "/audit");
+        assert_eq!(
+            agent.process_message(&msg).await.unwrap(),
+            "No commands have run in this conversation."
+        );
 
-    #[derive(Debug)]
-    struct TestProvider {
-        name: &'static str,
-        model: &'static str,
-    }
+        let ctx = ToolContext::new()
+            .with_command_log(agent.session_manager().command_log(), &msg.session_key);
+        for (command, code) in [("ls", 0), ("make test", 2)] {
+            ctx.record_command(CommandRecord::new("shell", command, "/ws").finished(
+                CommandOutcome::Exited,
+                Some(code),
+                std::time::Duration::from_millis(5),
+                "",
+            ));
+        }
 
-    struct ToolThenTextProvider {
-        calls: std::sync::Mutex<u8>,
-        tool_name: &'static str,
-        tool_args: &'static str,
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "/audit 1");
+        let reply = agent.process_message(&msg).await.unwrap();
+        assert!(
+            reply.starts_with("Last 1 command(s) in this conversation:\n- "),
+            "{}",
+            reply
+        );
+        assert!(reply.ends_with("`make test` — exit 2, 5ms"), "{}", reply);
+        assert_eq!(
+            agent.command_history(&msg.session_key, 10).unwrap().len(),
+            2
+        );
     }
 
-    #[async_trait]
-    impl LLMProvider for TestProvider {
-        fn name(&self) -> &str {
-            self.name
-        }
+    #[tokio::test]
+    async fn test_conversation_analytics_records_turn() {
+        #[derive(Default)]
+        struct VecSink(std::sync::Mutex<Vec<AnalyticsEvent>>);
 
-        fn default_model(&self) -> &str {
-            self.model
+        impl AnalyticsSink for Arc<VecSink> {
+            fn record(&self, event: AnalyticsEvent) {
+                self.0.lock().unwrap().push(event);
+            }
         }
 
-        async fn chat(
-            &self,
-            _messages: Vec<Message>,
-            _tools: Vec<ToolDefinition>,
-            _model: Option<&str>,
-            _options: ChatOptions,
-        ) -> Result<LLMResponse> {
-            Ok(LLMResponse::text("ok"))
-        }
+        let sink = Arc::new(VecSink::default());
+        let agent = AgentLoop::new(
+            Config::default(),
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        )
+        .with_conversation_analytics(Box::new(Arc::clone(&sink)));
+        agent
+            .set_provider(Box::new(ToolThenTextProvider {
+                calls: std::sync::Mutex::new(0),
+                tool_name: "lookup",
+                tool_args: "{}",
+            }))
+            .await;
+        agent
+            .register_tool(Box::new(StubTool {
+                name: "lookup",
+                category: ToolCategory::Memory,
+            }))
+            .await;
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "find it");
+        assert_eq!(agent.process_message(&msg).await.unwrap(), "done");
+        // Session commands are not turns
+        let pin = InboundMessage::new("telegram", "user1", "chat1", "/pin");
+        agent.process_message(&pin).await.unwrap();
+        let again = InboundMessage::new("telegram", "user1", "chat1", "and again");
+        agent.process_message(&again).await.unwrap();
+
+        let events = sink.0.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].session_key, msg.session_key);
+        assert_eq!(events[0].turn_number, 1);
+        assert_eq!(events[0].user_message_length, 7);
+        assert_eq!(events[0].assistant_message_length, 4);
+        assert_eq!(events[0].tool_calls, vec!["lookup".to_string()]);
+        assert_eq!(events[1].turn_number, 2);
+        assert_eq!(events[1].user_message_length, 9);
+        assert!(events[1].tool_calls.is_empty());
     }
 
-    #[async_trait]
-    impl LLMProvider for ToolThenTextProvider {
-        fn name(&self) -> &str {
-            "test"
-        }
+    #[tokio::test]
+    async fn test_turns_keep_history_beyond_the_context_budget() {
+        let mut config = Config::default();
+        config.compaction.enabled = false;
+        config.compaction.context_limit = 2_000;
+        config.agents.defaults.max_tokens = 100;
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        agent
+            .set_provider(Box::new(EchoProvider {
+                seen: Arc::clone(&seen),
+                delay: std::time::Duration::ZERO,
+            }))
+            .await;
 
-        fn default_model(&self) -> &str {
-            "test-model"
+        // Far more history than fits; the oldest turns are not copied for
+        // the turn but must survive its save.
+        let sessions = agent.session_manager();
+        let mut session = sessions.get_or_create("cli:long").await.unwrap();
+        let filler = "x".repeat(400);
+        for i in 0..50 {
+            session.add_message(Message::user(&format!("q{} {}", i, filler)));
+            session.add_message(Message::assistant(&filler));
         }
+        sessions.save(&session).await.unwrap();
 
-        async fn chat(
-            &self,
-            _messages: Vec<Message>,
-            _tools: Vec<ToolDefinition>,
-            _model: Option<&str>,
-            _options: ChatOptions,
-        ) -> Result<LLMResponse> {
-            let mut calls = self.calls.lock().expect("provider call counter poisoned");
-            *calls += 1;
-            if *calls == 1 {
-                Ok(LLMResponse::with_tools(
-                    "",
-                    vec![LLMToolCall::new("call_1", self.tool_name, self.tool_args)],
-                )
-                .with_usage(Usage::new(10, 1)))
-            } else {
-                let call_num = *calls as u32;
-                Ok(LLMResponse::text("done").with_usage(Usage::new(10 + call_num, call_num)))
-            }
-        }
-    }
+        for (i, content) in ["latest", "again"].into_iter().enumerate() {
+            let msg = InboundMessage::new("cli", "user", "long", content);
+            let reply = agent.process_message(&msg).await.unwrap();
+            assert_eq!(reply, format!("re: {content}"));
 
-    async fn collect_stream_done(
-        mut rx: tokio::sync::mpsc::Receiver<StreamEvent>,
-    ) -> (String, Option<Usage>) {
-        while let Some(event) = rx.recv().await {
-            match event {
-                StreamEvent::Done { content, usage } => return (content, usage),
-                StreamEvent::Delta(_) => {}
-                StreamEvent::ToolCalls(tool_calls) => {
-                    panic!("unexpected tool calls in final stream: {:?}", tool_calls)
-                }
-                StreamEvent::Error(err) => panic!("unexpected stream error: {err}"),
+            let stored = sessions.get("cli:long").await.unwrap().unwrap();
+            assert_eq!(stored.messages.len(), 102 + 2 * i);
+            for (j, message) in stored.messages[..100].iter().step_by(2).enumerate() {
+                assert!(message.content.starts_with(&format!("q{} ", j)));
             }
+            assert_eq!(stored.messages.last().unwrap().content, reply);
         }
-        panic!("stream ended without a Done event");
+        assert_eq!(*seen.lock().unwrap(), ["latest", "again"]);
     }
 
     #[tokio::test]
@@ -3790,6 +9184,116 @@ mod tests {
         assert_eq!(result, "done");
     }
 
+    async fn run_with_channel_approval(reply: Option<&'static str>) -> String {
+        let mut config = Config::default();
+        config.approval.policy = crate::tools::approval::ApprovalPolicyConfig::RequireForTools;
+        config.approval.require_for = vec!["deploy".to_string()];
+        config.approval.ask_in_channel = true;
+        config.approval.reply_timeout_secs = 1;
+        let bus = Arc::new(MessageBus::new());
+        let agent = AgentLoop::new(config, SessionManager::new_memory(), Arc::clone(&bus));
+        agent
+            .set_provider(Box::new(ToolThenTextProvider {
+                calls: std::sync::Mutex::new(0),
+                tool_name: "deploy",
+                tool_args: "{}",
+            }))
+            .await;
+        agent
+            .register_tool(Box::new(StubTool {
+                name: "deploy",
+                category: ToolCategory::Memory,
+            }))
+            .await;
+
+        let replier = tokio::spawn({
+            let bus = Arc::clone(&bus);
+            async move {
+                let prompt = bus.consume_outbound().await.unwrap();
+                assert_eq!(prompt.chat_id, "chat1");
+                assert!(prompt.content.contains("`deploy`"), "{}", prompt.content);
+                if let Some(reply) = reply {
+                    bus.publish_inbound(InboundMessage::new("telegram", "user1", "chat1", reply))
+                        .await
+                        .unwrap();
+                }
+            }
+        });
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "ship it");
+        assert_eq!(agent.process_message(&msg).await.unwrap(), "done");
+        replier.await.unwrap();
+
+        let session = agent
+            .session_manager()
+            .get(&msg.session_key)
+            .await
+            .unwrap()
+            .unwrap();
+        session
+            .messages
+            .iter()
+            .find(|m| m.role == Role::Tool)
+            .expect("tool result recorded")
+            .content
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn test_channel_approval_runs_tool_on_yes() {
+        assert_eq!(run_with_channel_approval(Some("yes")).await, "ok");
+    }
+
+    #[tokio::test]
+    async fn test_channel_approval_denial_and_timeout_explain_refusal() {
+        let denied = run_with_channel_approval(Some("no")).await;
+        assert!(denied.contains("was denied by user approval"), "{denied}");
+        assert!(denied.contains("The user replied: \"no\""), "{denied}");
+
+        let timed_out = run_with_channel_approval(None).await;
+        assert!(timed_out.contains("approval timed out"), "{timed_out}");
+    }
+
+    #[tokio::test]
+    async fn test_tool_middleware_wraps_agent_tool_calls() {
+        let bus = Arc::new(MessageBus::new());
+        let agent = AgentLoop::new(Config::default(), SessionManager::new_memory(), bus);
+        agent
+            .set_provider(Box::new(ToolThenTextProvider {
+                calls: std::sync::Mutex::new(0),
+                tool_name: "lookup",
+                tool_args: "{}",
+            }))
+            .await;
+        agent
+            .register_tool(Box::new(StubTool {
+                name: "lookup",
+                category: ToolCategory::Memory,
+            }))
+            .await;
+        agent
+            .add_tool_middleware(Arc::new(crate::tools::ResultSizeLimitMiddleware::new(1)))
+            .await;
+
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "look it up");
+        assert_eq!(agent.process_message(&msg).await.unwrap(), "done");
+        let session = agent
+            .session_manager()
+            .get(&msg.session_key)
+            .await
+            .unwrap()
+            .unwrap();
+        let result = session
+            .messages
+            .iter()
+            .find(|m| m.role == Role::Tool)
+            .expect("tool result recorded");
+        assert_eq!(
+            result.content,
+            "o\n[Result truncated: showing 1 of 2 bytes]"
+        );
+    }
+
     #[tokio::test]
     async fn test_process_message_trusted_local_session_bypasses_approval() {
         let config = Config::default();
@@ -3880,7 +9384,9 @@ mod tests {
 
     #[tokio::test]
     async fn test_process_message_streaming_records_usage_metrics_and_parse_errors() {
-        let config = Config::default();
+        // Schema validation would answer the parse error before the tool runs.
+        let mut config = Config::default();
+        config.tools.argument_validation.enabled = false;
         let session_manager = SessionManager::new_memory();
         let bus = Arc::new(MessageBus::new());
         let agent = AgentLoop::new(config, session_manager, bus);
@@ -4235,6 +9741,50 @@ mod tests {
         assert!(!agent.is_dry_run());
     }
 
+    #[tokio::test]
+    async fn test_dryrun_off_cannot_disable_config_dry_run() {
+        let mut config = Config::default();
+        config.tools.dry_run = true;
+        config.agent_mode.mode = "autonomous".to_string();
+        let agent = AgentLoop::new(
+            config,
+            SessionManager::new_memory(),
+            Arc::new(MessageBus::new()),
+        );
+        let calls = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        agent
+            .set_provider(Box::new(ToolThenTextProvider {
+                calls: std::sync::Mutex::new(0),
+                tool_name: "write_thing",
+                tool_args: "{}",
+            }))
+            .await;
+        agent
+            .register_tool(Box::new(InstrumentedTool {
+                name: "write_thing",
+                category: ToolCategory::FilesystemWrite,
+                calls: Arc::clone(&calls),
+                fail: false,
+                last_args: None,
+            }))
+            .await;
+
+        let msg = |text: &str| InboundMessage::new("telegram", "user1", "chat1", text);
+        let reply = agent.process_message(&msg("/dryrun off")).await.unwrap();
+        assert!(reply.starts_with("Dry-run mode stays on"), "{}", reply);
+        assert!(agent
+            .process_message(&msg("/dryrun"))
+            .await
+            .unwrap()
+            .starts_with("Dry-run mode is on"));
+
+        assert_eq!(
+            agent.process_message(&msg("write it")).await.unwrap(),
+            "done"
+        );
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+    }
+
     // -----------------------------------------------------------------------
     // Inbound injection scanning tests
     // -----------------------------------------------------------------------
@@ -4466,7 +10016,7 @@ mod tests {
     }
 
     // ----------------------------------------------------------------
-    // needs_sequential_execution tests
+    // parallel tool execution tests
     // ----------------------------------------------------------------
 
     /// Minimal mock tool with configurable name and category.
@@ -4556,24 +10106,12 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_sequential_triggered_by_filesystem_write() {
+    async fn test_parallel_safety_by_category() {
         let reg = registry_with(vec![
             StubTool {
                 name: "write_file",
                 category: ToolCategory::FilesystemWrite,
             },
-            StubTool {
-                name: "read_file",
-                category: ToolCategory::FilesystemRead,
-            },
-        ]);
-        let calls = vec![make_tool_call("write_file"), make_tool_call("read_file")];
-        assert!(needs_sequential_execution(&reg, &calls).await);
-    }
-
-    #[tokio::test]
-    async fn test_sequential_triggered_by_shell() {
-        let reg = registry_with(vec![
             StubTool {
                 name: "shell",
                 category: ToolCategory::Shell,
@@ -4582,46 +10120,89 @@ mod tests {
                 name: "read_file",
                 category: ToolCategory::FilesystemRead,
             },
-        ]);
-        let calls = vec![make_tool_call("shell"), make_tool_call("read_file")];
-        assert!(needs_sequential_execution(&reg, &calls).await);
-    }
-
-    #[tokio::test]
-    async fn test_parallel_when_only_reads() {
-        let reg = registry_with(vec![
-            StubTool {
-                name: "read_file",
-                category: ToolCategory::FilesystemRead,
-            },
             StubTool {
                 name: "web_fetch",
                 category: ToolCategory::NetworkRead,
             },
         ]);
-        let calls = vec![make_tool_call("read_file"), make_tool_call("web_fetch")];
-        assert!(!needs_sequential_execution(&reg, &calls).await);
+        let calls = vec![
+            make_tool_call("write_file"),
+            make_tool_call("shell"),
+            make_tool_call("read_file"),
+            make_tool_call("web_fetch"),
+            // Not in the registry → treated as unsafe.
+            make_tool_call("mystery_tool"),
+        ];
+        assert_eq!(
+            parallel_safety(&reg, &calls).await,
+            vec![false, false, true, true, false]
+        );
+    }
+
+    /// Tracks how many batch calls run at once and the order they start in.
+    #[derive(Default)]
+    struct BatchProbe {
+        running: std::sync::atomic::AtomicUsize,
+        peak: std::sync::atomic::AtomicUsize,
+        started: std::sync::Mutex<Vec<usize>>,
+    }
+
+    impl BatchProbe {
+        async fn call(&self, index: usize) -> usize {
+            self.started.lock().unwrap().push(index);
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            index
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_tool_batch_runs_concurrently_in_order() {
+        let probe = BatchProbe::default();
+        let calls: Vec<_> = (0..4).map(|i| probe.call(i)).collect();
+        let results = run_tool_batch(calls, &[true; 4], 0).await;
+        let outputs: Vec<usize> = results.iter().map(|(i, _)| *i).collect();
+        assert_eq!(outputs, vec![0, 1, 2, 3]);
+        assert_eq!(probe.peak.load(Ordering::SeqCst), 4);
+        assert!(results
+            .iter()
+            .all(|(_, elapsed)| *elapsed >= std::time::Duration::from_millis(50)));
     }
 
     #[tokio::test]
-    async fn test_sequential_for_unknown_tool_fail_safe() {
-        let reg = registry_with(vec![StubTool {
-            name: "read_file",
-            category: ToolCategory::FilesystemRead,
-        }]);
-        // "mystery_tool" is not in the registry → should default to sequential.
-        let calls = vec![make_tool_call("read_file"), make_tool_call("mystery_tool")];
-        assert!(needs_sequential_execution(&reg, &calls).await);
+    async fn test_run_tool_batch_respects_limit() {
+        let probe = BatchProbe::default();
+        let calls: Vec<_> = (0..5).map(|i| probe.call(i)).collect();
+        let results = run_tool_batch(calls, &[true; 5], 2).await;
+        assert_eq!(results.len(), 5);
+        assert_eq!(probe.peak.load(Ordering::SeqCst), 2);
     }
 
     #[tokio::test]
-    async fn test_parallel_for_single_read_tool() {
-        let reg = registry_with(vec![StubTool {
-            name: "memory_search",
-            category: ToolCategory::Memory,
-        }]);
-        let calls = vec![make_tool_call("memory_search")];
-        assert!(!needs_sequential_execution(&reg, &calls).await);
+    async fn test_run_tool_batch_serializes_unsafe_calls() {
+        let unsafe_probe = BatchProbe::default();
+        let safe_probe = BatchProbe::default();
+        let safety = [false, true, false, true, false];
+        let calls: Vec<Pin<Box<dyn Future<Output = usize> + '_>>> = safety
+            .iter()
+            .enumerate()
+            .map(|(i, &safe)| {
+                let probe = if safe { &safe_probe } else { &unsafe_probe };
+                Box::pin(probe.call(i)) as Pin<Box<dyn Future<Output = usize> + '_>>
+            })
+            .collect();
+        let outputs: Vec<usize> = run_tool_batch(calls, &safety, 0)
+            .await
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(outputs, vec![0, 1, 2, 3, 4]);
+        assert_eq!(unsafe_probe.peak.load(Ordering::SeqCst), 1);
+        assert_eq!(*unsafe_probe.started.lock().unwrap(), vec![0, 2, 4]);
+        // Safe calls did not wait for the unsafe chain.
+        assert_eq!(safe_probe.peak.load(Ordering::SeqCst), 2);
     }
 
     // ----------------------------------------------------------------
@@ -4713,6 +10294,11 @@ mod tests {
         } else {
             panic!("Expected Image content part");
         }
+        assert!(
+            result.content.contains("[Image attachment: media/"),
+            "stored image should be referenced in the text: {}",
+            result.content
+        );
     }
 
     // ----------------------------------------------------------------
@@ -4968,4 +10554,12 @@ mod tests {
             _ => panic!("expected ToolDone"),
         }
     }
+
+    #[test]
+    fn test_output_tail_respects_char_boundaries() {
+        assert_eq!(output_tail("short", 100), "short");
+        assert_eq!(output_tail("abcdef", 3), "def");
+        // 'é' is two bytes; a cut through it moves forward.
+        assert_eq!(output_tail("aéb", 2), "b");
+    }
 }
@@ -11,6 +11,10 @@
 //! 4. **Poll relaxation**: Commands matching status/poll patterns get relaxed
 //!    thresholds (configurable multiplier).
 //! 5. **Backoff schedule**: Suggests increasing delays for repeated poll calls.
+//!
+//! Separately, [`LoopGuard::check_repeat`] counts single calls by tool name and
+//! canonical arguments so the agent loop can answer an exact repeat without
+//! running it.
 
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
@@ -46,6 +50,7 @@ pub struct LoopGuardStats {
     pub circuit_breaks: u64,
     pub ping_pong_detections: u64,
     pub outcome_blocks: u64,
+    pub repeats_short_circuited: u64,
 }
 
 /// Signature of a single tool call for hashing.
@@ -79,6 +84,9 @@ pub struct LoopGuard {
     /// Running total of all repetitions across all hashes (for global breaker).
     total_repetitions: u32,
 
+    /// Per single-call fingerprint counters for exact-repeat detection.
+    repeat_counts: HashMap<String, u32>,
+
     /// Stats.
     stats: LoopGuardStats,
 }
@@ -103,6 +111,7 @@ impl LoopGuard {
             call_sequence: Vec::new(),
             outcome_sequence: Vec::new(),
             total_repetitions: 0,
+            repeat_counts: HashMap::new(),
             stats: LoopGuardStats::default(),
         }
     }
@@ -252,6 +261,28 @@ impl LoopGuard {
         None
     }
 
+    /// Count one more request for `call` and say whether it should be
+    /// answered without running it.
+    ///
+    /// Returns `Some(count)` once the same tool has been asked for with the
+    /// same arguments (compared as canonical JSON, so key order and spacing
+    /// don't matter) `repeat_call_limit` times in this turn.
+    pub fn check_repeat(&mut self, call: ToolCallSig<'_>) -> Option<u32> {
+        if !self.config.enabled || self.config.repeat_call_limit == 0 {
+            return None;
+        }
+        let count = self
+            .repeat_counts
+            .entry(hash_call_batch(&[call]))
+            .or_insert(0);
+        *count += 1;
+        if *count < self.config.repeat_call_limit {
+            return None;
+        }
+        self.stats.repeats_short_circuited += 1;
+        Some(*count)
+    }
+
     /// Return a snapshot of the guard's statistics.
     pub fn stats(&self) -> &LoopGuardStats {
         &self.stats
@@ -822,6 +853,31 @@ mod tests {
         assert_eq!(config.outcome_warn_threshold, 2);
         assert_eq!(config.outcome_block_threshold, 3);
         assert_eq!(config.window_size, 200);
+        assert_eq!(config.repeat_call_limit, 3);
+    }
+
+    #[test]
+    fn test_check_repeat_matches_canonical_arguments() {
+        let mut guard = LoopGuard::new(default_config());
+        let first = sig("read_file", r#"{"path": "a.txt", "limit": 10}"#);
+        let reordered = sig("read_file", r#"{"limit":10,"path":"a.txt"}"#);
+        let other = sig("read_file", r#"{"path": "b.txt", "limit": 10}"#);
+
+        assert_eq!(guard.check_repeat(first), None);
+        assert_eq!(guard.check_repeat(other), None);
+        assert_eq!(guard.check_repeat(reordered), None);
+        assert_eq!(guard.check_repeat(first), Some(3));
+        assert_eq!(guard.check_repeat(reordered), Some(4));
+        assert_eq!(guard.check_repeat(other), None);
+        assert_eq!(guard.stats().repeats_short_circuited, 2);
+
+        let mut off = LoopGuard::new(LoopGuardConfig {
+            repeat_call_limit: 0,
+            ..default_config()
+        });
+        for _ in 0..5 {
+            assert_eq!(off.check_repeat(first), None);
+        }
     }
 
     #[test]
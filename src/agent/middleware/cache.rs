@@ -43,12 +43,13 @@ impl Middleware for CacheMiddleware {
     }
 
     async fn handle(&self, ctx: &mut PipelineContext, next: Next<'_>) -> Result<PipelineOutput> {
-        // If no cache is configured, skip entirely.
+        // If no cache is configured or the sender asked for a fresh answer,
+        // skip entirely.
         // Clone the Arc so we don't hold an immutable borrow on ctx.subsystems
         // across the `next.run(ctx).await` call that takes `&mut ctx`.
         let cache_mutex = match ctx.subsystems.cache {
-            Some(ref c) => Arc::clone(c),
-            None => return next.run(ctx).await,
+            Some(ref c) if !ctx.inbound.no_cache() => Arc::clone(c),
+            _ => return next.run(ctx).await,
         };
 
         // Build cache key.  We need the model name and the system prompt
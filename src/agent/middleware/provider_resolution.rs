@@ -130,6 +130,7 @@ mod tests {
                 content: format!("from-{}", self.name),
                 tool_calls: vec![],
                 usage: Some(Usage::new(10, 5)),
+                thinking: vec![],
             })
         }
     }
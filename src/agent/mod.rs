@@ -54,23 +54,51 @@
 //! }
 //! ```
 
+pub mod analytics;
+pub mod approval;
+mod authorization;
 pub mod budget;
+pub mod commands;
 pub mod compaction;
 mod context;
 pub mod context_monitor;
+mod error_reply;
 pub mod facade;
+pub(crate) mod heartbeat;
+pub mod hooks;
 mod r#loop;
 pub mod loop_guard;
 pub mod middleware;
+mod onboarding;
 pub mod pipeline;
+mod progress;
+mod reply_stream;
+mod response;
+pub mod retrieval;
+mod scheduler;
 pub mod scratchpad;
+mod settings;
+pub mod tool_access;
 pub mod tool_call_limit;
+pub(crate) mod trace;
+pub mod workspace_brief;
 
+pub use analytics::{AnalyticsEvent, AnalyticsSink, JsonLinesFileSink};
+pub use approval::{ApprovalPolicy, ChannelApprovalPolicy};
 pub use budget::TokenBudget;
-pub use context::{format_message_envelope, ContextBuilder, RuntimeContext};
+pub use commands::{Command, CommandContext};
+pub use context::{
+    fit_to_budget, format_message_envelope, history_start, ContextBudget, ContextBuilder,
+    ContextReport, RuntimeContext,
+};
 pub use context_monitor::{CompactionStrategy, ContextMonitor, PreflightAction};
 pub use facade::{ZeptoAgent, ZeptoAgentBuilder};
+pub use hooks::{AgentHook, HookDecision, OutboundResponse};
 pub use r#loop::AgentLoop;
 pub use r#loop::{ToolFeedback, ToolFeedbackPhase};
+pub use retrieval::{DirectoryRetriever, RetrievedChunk, Retriever};
 pub use scratchpad::SwarmScratchpad;
+pub use tool_access::ToolAccess;
 pub use tool_call_limit::ToolCallLimitTracker;
+pub use trace::{TraceEntry, TraceKind, TurnTrace};
+pub use workspace_brief::WorkspaceBrief;
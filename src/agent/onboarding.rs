@@ -0,0 +1,110 @@
+//! Welcome for new conversations.
+//!
+//! A channel with an entry in `agents.onboarding` greets each new
+//! conversation on its first message (chat commands don't count). The
+//! welcome lists the chat commands, as `/help` does, and the tools the
+//! conversation can use, and comes before the answer or instead of it. The
+//! session records that it was sent, so it is sent once; rotation keeps the
+//! record.
+
+use tokio::sync::mpsc;
+
+use crate::config::OnboardingConfig;
+use crate::providers::StreamEvent;
+
+/// `config`'s welcome with `{commands}` and `{tools}` filled in.
+pub(crate) fn render(config: &OnboardingConfig, commands: &str, tools: &[&str]) -> String {
+    let tools = if tools.is_empty() {
+        "none".to_string()
+    } else {
+        tools.join(", ")
+    };
+    config
+        .message
+        .replace("{commands}", commands)
+        .replace("{tools}", &tools)
+}
+
+/// `welcome` followed by `reply`, as one reply.
+pub(crate) fn before(welcome: &str, reply: &str) -> String {
+    format!("{}\n\n{}", welcome.trim_end(), reply)
+}
+
+/// `events` with `welcome` streamed ahead of the reply.
+pub(crate) fn before_stream(
+    welcome: String,
+    mut events: mpsc::Receiver<StreamEvent>,
+) -> mpsc::Receiver<StreamEvent> {
+    let (tx, rx) = mpsc::channel(32);
+    tokio::spawn(async move {
+        let lead = before(&welcome, "");
+        if tx.send(StreamEvent::Delta(lead)).await.is_err() {
+            return;
+        }
+        while let Some(event) = events.recv().await {
+            let event = match event {
+                StreamEvent::Done { content, usage } => StreamEvent::Done {
+                    content: before(&welcome, &content),
+                    usage,
+                },
+                other => other,
+            };
+            if tx.send(event).await.is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_fills_commands_and_tools() {
+        let config = OnboardingConfig {
+            message: "Welcome!\n{commands}\nTools: {tools}".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            render(
+                &config,
+                "- /help: Show the commands",
+                &["shell", "web_search"]
+            ),
+            "Welcome!\n- /help: Show the commands\nTools: shell, web_search"
+        );
+        assert!(render(&config, "", &[]).ends_with("Tools: none"));
+    }
+
+    #[tokio::test]
+    async fn test_before_stream_leads_with_the_welcome() {
+        let (tx, events) = mpsc::channel(4);
+        tx.send(StreamEvent::Delta("Hello".to_string()))
+            .await
+            .unwrap();
+        tx.send(StreamEvent::Done {
+            content: "Hello".to_string(),
+            usage: None,
+        })
+        .await
+        .unwrap();
+        drop(tx);
+
+        let mut rx = before_stream("Welcome!".to_string(), events);
+        let mut streamed = String::new();
+        while let Some(event) = rx.recv().await {
+            match event {
+                StreamEvent::Delta(delta) => streamed.push_str(&delta),
+                StreamEvent::Done { content, .. } => {
+                    assert_eq!(content, "Welcome!\n\nHello");
+                    assert_eq!(streamed, content);
+                    return;
+                }
+                _ => {}
+            }
+        }
+        panic!("stream ended without a Done event");
+    }
+}
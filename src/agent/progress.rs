@@ -0,0 +1,297 @@
+//! Telling a chat what a long turn is doing.
+//!
+//! With `agents.defaults.progress_updates` on, the [`ToolFeedback`] events
+//! of a turn (the same ones that drive the CLI shimmer and are traced at
+//! debug level) are turned into a short status: "Thinking…",
+//! "Running shell (ls -la)…", "shell finished (0.4s)". Nothing is sent
+//! before `progress_interval_ms` has passed, and at most one status per
+//! interval after that. Each status is a partial update of the turn's
+//! streamed reply (see [`OutboundMessage::with_stream`]), so channels that
+//! can edit messages keep one status message that the final reply replaces.
+//! The first update also sends a single "Working on it…" line marked with
+//! [`OutboundMessage::with_progress_note`], which only reaches channels that
+//! can't edit.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::debug;
+
+use super::{ToolFeedback, ToolFeedbackPhase};
+use crate::bus::{MessageBus, OutboundMessage};
+
+/// Line sent once to channels that can't show an updating status.
+pub(crate) const WORKING_NOTE: &str = "Working on it…";
+
+/// Longest argument hint shown next to a running tool, in characters.
+const HINT_MAX_CHARS: usize = 40;
+
+/// Where a turn's [`ToolFeedback`] goes: the sender registered with
+/// [`AgentLoop::set_tool_feedback`](super::AgentLoop::set_tool_feedback), if
+/// any, and the turn's [`TurnProgress`].
+#[derive(Clone)]
+pub(crate) struct FeedbackSink {
+    global: Option<mpsc::UnboundedSender<ToolFeedback>>,
+    progress: Option<mpsc::UnboundedSender<ToolFeedback>>,
+}
+
+impl FeedbackSink {
+    pub(crate) fn new(
+        global: Option<mpsc::UnboundedSender<ToolFeedback>>,
+        progress: Option<&TurnProgress>,
+    ) -> Self {
+        Self {
+            global,
+            progress: progress.map(|progress| progress.tx.clone()),
+        }
+    }
+
+    /// Trace `feedback` and hand it to every listener.
+    pub(crate) fn send(&self, feedback: ToolFeedback) {
+        debug!(tool = %feedback.tool_name, phase = ?feedback.phase, "Tool feedback");
+        for tx in [&self.global, &self.progress].into_iter().flatten() {
+            let _ = tx.send(feedback.clone());
+        }
+    }
+}
+
+/// The progress updates of one turn, published by a background task.
+pub(crate) struct TurnProgress {
+    tx: mpsc::UnboundedSender<ToolFeedback>,
+    task: JoinHandle<()>,
+    stream_id: String,
+    /// Whether any status was published.
+    shown: Arc<AtomicBool>,
+}
+
+impl TurnProgress {
+    /// Start publishing progress to the chat `template` is addressed to, as
+    /// updates of the streamed reply `stream_id`.
+    pub(crate) fn start(
+        bus: Arc<MessageBus>,
+        template: OutboundMessage,
+        stream_id: String,
+        interval: Duration,
+    ) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let shown = Arc::new(AtomicBool::new(false));
+        let task = tokio::spawn(publish_progress(
+            bus,
+            template,
+            stream_id.clone(),
+            interval,
+            rx,
+            Arc::clone(&shown),
+        ));
+        Self {
+            tx,
+            task,
+            stream_id,
+            shown,
+        }
+    }
+
+    /// Stop publishing. Returns once no further update can go out, so the
+    /// final reply can't be overtaken by a status.
+    pub(crate) async fn stop(&mut self) {
+        self.task.abort();
+        let _ = (&mut self.task).await;
+    }
+
+    /// Mark `outbound` as the end of the status stream if any status was
+    /// shown, so edit-capable channels replace the status with it.
+    pub(crate) fn finish(&self, outbound: OutboundMessage) -> OutboundMessage {
+        if self.shown.load(Ordering::Acquire) {
+            outbound.with_stream(&self.stream_id, false)
+        } else {
+            outbound
+        }
+    }
+}
+
+impl Drop for TurnProgress {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn publish_progress(
+    bus: Arc<MessageBus>,
+    template: OutboundMessage,
+    stream_id: String,
+    interval: Duration,
+    mut rx: mpsc::UnboundedReceiver<ToolFeedback>,
+    shown: Arc<AtomicBool>,
+) {
+    let mut next_update = Instant::now() + interval;
+    let mut pending: Option<String> = None;
+    let mut noted = false;
+    loop {
+        tokio::select! {
+            feedback = rx.recv() => match feedback {
+                None => break,
+                Some(ToolFeedback { phase: ToolFeedbackPhase::ResponseReady, .. }) => break,
+                Some(feedback) => {
+                    if let Some(status) = describe(&feedback) {
+                        pending = Some(status);
+                    }
+                }
+            },
+            _ = tokio::time::sleep_until(next_update), if pending.is_some() => {
+                next_update = Instant::now() + interval;
+                if !noted {
+                    noted = true;
+                    let mut note = template.clone().with_progress_note();
+                    note.content = WORKING_NOTE.to_string();
+                    if let Err(e) = bus.publish_outbound(note).await {
+                        debug!("Failed to publish progress note: {}", e);
+                    }
+                }
+                let mut update = template.clone().with_stream(&stream_id, true);
+                update.content = pending.take().unwrap_or_default();
+                update
+                    .metadata
+                    .insert("keep_typing".to_string(), "true".to_string());
+                if let Err(e) = bus.publish_outbound(update).await {
+                    debug!("Failed to publish progress update: {}", e);
+                } else {
+                    shown.store(true, Ordering::Release);
+                }
+            }
+        }
+    }
+}
+
+/// The status line for `feedback`, if it changes what the user should see.
+fn describe(feedback: &ToolFeedback) -> Option<String> {
+    let tool = &feedback.tool_name;
+    match &feedback.phase {
+        ToolFeedbackPhase::Thinking => Some("Thinking…".to_string()),
+        ToolFeedbackPhase::Starting => {
+            Some(match feedback.args_json.as_deref().and_then(args_hint) {
+                Some(hint) => format!("Running {} ({})…", tool, hint),
+                None => format!("Running {}…", tool),
+            })
+        }
+        ToolFeedbackPhase::Done { elapsed_ms } => Some(format!(
+            "{} finished ({:.1}s)",
+            tool,
+            *elapsed_ms as f64 / 1000.0
+        )),
+        ToolFeedbackPhase::Failed { .. } => Some(format!("{} failed", tool)),
+        ToolFeedbackPhase::ThinkingDone | ToolFeedbackPhase::ResponseReady => None,
+    }
+}
+
+/// The first line of the argument that says most about a tool call.
+fn args_hint(args_json: &str) -> Option<String> {
+    let args: serde_json::Value = serde_json::from_str(args_json).ok()?;
+    let value = ["command", "path", "file_path", "url", "query"]
+        .iter()
+        .find_map(|key| args.get(*key)?.as_str())?;
+    let line = value.lines().next()?.trim();
+    if line.is_empty() {
+        return None;
+    }
+    if line.chars().count() <= HINT_MAX_CHARS {
+        return Some(line.to_string());
+    }
+    let cut: String = line.chars().take(HINT_MAX_CHARS).collect();
+    Some(format!("{}…", cut))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feedback(tool: &str, phase: ToolFeedbackPhase, args: Option<&str>) -> ToolFeedback {
+        ToolFeedback {
+            tool_name: tool.to_string(),
+            phase,
+            args_json: args.map(str::to_string),
+        }
+    }
+
+    fn progress(bus: &Arc<MessageBus>) -> TurnProgress {
+        TurnProgress::start(
+            Arc::clone(bus),
+            OutboundMessage::new("telegram", "chat1", ""),
+            "r1".to_string(),
+            Duration::from_millis(100),
+        )
+    }
+
+    #[test]
+    fn test_describe_tool_phases() {
+        let starting = feedback(
+            "shell",
+            ToolFeedbackPhase::Starting,
+            Some(r#"{"command": "ls -la\necho done"}"#),
+        );
+        assert_eq!(describe(&starting).unwrap(), "Running shell (ls -la)…");
+        let done = feedback("shell", ToolFeedbackPhase::Done { elapsed_ms: 420 }, None);
+        assert_eq!(describe(&done).unwrap(), "shell finished (0.4s)");
+        let failed = ToolFeedbackPhase::Failed {
+            elapsed_ms: 5,
+            error: "denied".to_string(),
+        };
+        assert_eq!(
+            describe(&feedback("web_fetch", failed, None)).unwrap(),
+            "web_fetch failed"
+        );
+        assert!(describe(&feedback("", ToolFeedbackPhase::ThinkingDone, None)).is_none());
+
+        let long = format!(r#"{{"query": "{}"}}"#, "é".repeat(60));
+        let hint = args_hint(&long).unwrap();
+        assert_eq!(hint.chars().count(), HINT_MAX_CHARS + 1);
+        assert!(args_hint(r#"{"count": 3}"#).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_latest_status_is_published_after_the_interval() {
+        let bus = Arc::new(MessageBus::new());
+        let mut progress = progress(&bus);
+        let sink = FeedbackSink::new(None, Some(&progress));
+        sink.send(feedback("", ToolFeedbackPhase::Thinking, None));
+        sink.send(feedback(
+            "shell",
+            ToolFeedbackPhase::Starting,
+            Some(r#"{"command": "ls"}"#),
+        ));
+
+        let note = bus.consume_outbound().await.unwrap();
+        assert!(note.is_progress_note());
+        assert_eq!(note.content, WORKING_NOTE);
+        let status = bus.consume_outbound().await.unwrap();
+        assert!(status.is_stream_partial());
+        assert_eq!(status.content, "Running shell (ls)…");
+        assert_eq!(status.metadata.get("keep_typing").unwrap(), "true");
+
+        progress.stop().await;
+        let last = progress.finish(OutboundMessage::new("telegram", "chat1", "Done."));
+        assert_eq!(last.stream_id(), Some("r1"));
+        assert!(!last.is_stream_partial());
+    }
+
+    #[tokio::test]
+    async fn test_quick_turns_send_nothing_and_stop_cleanly() {
+        let bus = Arc::new(MessageBus::new());
+        let mut progress = progress(&bus);
+        let sink = FeedbackSink::new(None, Some(&progress));
+        sink.send(feedback("", ToolFeedbackPhase::Thinking, None));
+        progress.stop().await;
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), bus.consume_outbound())
+                .await
+                .is_err()
+        );
+        let last = progress.finish(OutboundMessage::new("telegram", "chat1", "Hi"));
+        assert_eq!(last.stream_id(), None);
+    }
+}
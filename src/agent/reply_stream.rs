@@ -0,0 +1,313 @@
+//! Streaming a reply to its chat while it is being generated.
+//!
+//! With `agents.defaults.stream_to_channels` on, each LLM call of a turn
+//! goes through [`LLMProvider::chat_stream`] and the text received so far is
+//! published as a partial update of one streamed reply (see
+//! [`OutboundMessage::with_stream`]), at most once per
+//! `stream_interval_ms`. A call that finishes within the first interval
+//! sends no update. The turn itself is unchanged: [`chat`] returns the same
+//! [`LLMResponse`] a plain `chat` call would, so the reply is saved to the
+//! session exactly as before, and the finished reply is sent as the
+//! stream's final message. Tool calls are collected until complete.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time::Instant;
+use tracing::debug;
+
+use crate::bus::{MessageBus, OutboundMessage};
+use crate::error::{Result, ZeptoError};
+use crate::providers::{ChatOptions, LLMProvider, LLMResponse, StreamEvent, ToolDefinition};
+use crate::session::Message;
+
+/// The streamed reply of one turn.
+pub(crate) struct ReplyStream {
+    bus: Arc<MessageBus>,
+    /// Channel, chat and routing metadata for the updates.
+    template: OutboundMessage,
+    stream_id: String,
+    interval: Duration,
+    /// Text of the current LLM call so far.
+    text: String,
+    /// Whether the current LLM call finished.
+    complete: bool,
+    /// When the next update may go out.
+    next_update: Instant,
+    /// Whether any update was published.
+    shown: bool,
+}
+
+impl ReplyStream {
+    /// A stream publishing to the chat `template` is addressed to.
+    pub(crate) fn new(bus: Arc<MessageBus>, template: OutboundMessage, interval: Duration) -> Self {
+        Self {
+            bus,
+            template,
+            stream_id: uuid::Uuid::new_v4().to_string(),
+            interval,
+            text: String::new(),
+            complete: false,
+            next_update: Instant::now() + interval,
+            shown: false,
+        }
+    }
+
+    /// The id tying this stream's updates together.
+    pub(crate) fn stream_id(&self) -> &str {
+        &self.stream_id
+    }
+
+    /// Start showing a new LLM call; its text replaces the previous call's.
+    fn begin_call(&mut self) {
+        self.text.clear();
+        self.complete = false;
+        self.next_update = Instant::now() + self.interval;
+    }
+
+    /// Add generated text, publishing an update when one is due.
+    async fn push(&mut self, delta: &str) {
+        self.text.push_str(delta);
+        let now = Instant::now();
+        if now < self.next_update || self.text.trim().is_empty() {
+            return;
+        }
+        self.next_update = now + self.interval;
+        let mut update = self.template.clone().with_stream(&self.stream_id, true);
+        update.content = self.text.clone();
+        update
+            .metadata
+            .insert("keep_typing".to_string(), "true".to_string());
+        if let Err(e) = self.bus.publish_outbound(update).await {
+            debug!("Failed to publish streamed update: {}", e);
+        } else {
+            self.shown = true;
+        }
+    }
+
+    /// Text streamed by an LLM call that was cut off before finishing.
+    pub(crate) fn unfinished_text(&self) -> Option<&str> {
+        Some(self.text.as_str()).filter(|text| !self.complete && !text.trim().is_empty())
+    }
+
+    /// Mark `outbound` as the end of this stream if any update was shown,
+    /// so edit-capable channels replace the partial reply with it.
+    pub(crate) fn finish(&self, outbound: OutboundMessage) -> OutboundMessage {
+        if self.shown {
+            outbound.with_stream(&self.stream_id, false)
+        } else {
+            outbound
+        }
+    }
+}
+
+/// Call `provider`, streaming the reply to `stream` when there is one and
+/// the provider can stream; otherwise a plain `chat` call.
+pub(crate) async fn chat(
+    provider: &dyn LLMProvider,
+    stream: Option<&mut ReplyStream>,
+    messages: Vec<Message>,
+    tools: Vec<ToolDefinition>,
+    model: Option<&str>,
+    options: ChatOptions,
+) -> Result<LLMResponse> {
+    let Some(stream) = stream.filter(|_| provider.supports_streaming()) else {
+        return provider.chat(messages, tools, model, options).await;
+    };
+
+    stream.begin_call();
+    let mut events = provider
+        .chat_stream(messages, tools, model, options)
+        .await?;
+    let mut tool_calls = Vec::new();
+    let mut thinking = Vec::new();
+    while let Some(event) = events.recv().await {
+        match event {
+            StreamEvent::Delta(delta) => stream.push(&delta).await,
+            StreamEvent::ToolCalls(calls) => tool_calls.extend(calls),
+            StreamEvent::Thinking(blocks) => thinking.extend(blocks),
+            StreamEvent::Done { content, usage } => {
+                stream.complete = true;
+                let mut response = LLMResponse::with_tools(&content, tool_calls);
+                response.usage = usage;
+                response.thinking = thinking;
+                return Ok(response);
+            }
+            StreamEvent::Error(e) => return Err(e),
+        }
+    }
+    Err(ZeptoError::Provider(
+        "Stream ended before the reply was complete".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{LLMToolCall, Usage};
+    use async_trait::async_trait;
+    use tokio::sync::mpsc;
+
+    /// Streams its reply in fixed pieces, pausing between them.
+    struct PiecesProvider {
+        pieces: Vec<&'static str>,
+        pause: Duration,
+        streams: bool,
+    }
+
+    #[async_trait]
+    impl LLMProvider for PiecesProvider {
+        async fn chat(
+            &self,
+            _messages: Vec<Message>,
+            _tools: Vec<ToolDefinition>,
+            _model: Option<&str>,
+            _options: ChatOptions,
+        ) -> Result<LLMResponse> {
+            Ok(LLMResponse::text(&self.pieces.concat()))
+        }
+
+        fn default_model(&self) -> &str {
+            "pieces"
+        }
+
+        fn name(&self) -> &str {
+            "pieces"
+        }
+
+        async fn chat_stream(
+            &self,
+            _messages: Vec<Message>,
+            _tools: Vec<ToolDefinition>,
+            _model: Option<&str>,
+            _options: ChatOptions,
+        ) -> Result<mpsc::Receiver<StreamEvent>> {
+            let (tx, rx) = mpsc::channel(8);
+            let pieces = self.pieces.clone();
+            let pause = self.pause;
+            tokio::spawn(async move {
+                for piece in &pieces {
+                    tokio::time::sleep(pause).await;
+                    let _ = tx.send(StreamEvent::Delta(piece.to_string())).await;
+                }
+                let call = LLMToolCall::new("call_1", "echo", "{}");
+                let _ = tx.send(StreamEvent::ToolCalls(vec![call])).await;
+                let _ = tx
+                    .send(StreamEvent::Done {
+                        content: pieces.concat(),
+                        usage: Some(Usage::new(3, 4)),
+                    })
+                    .await;
+            });
+            Ok(rx)
+        }
+
+        fn supports_streaming(&self) -> bool {
+            self.streams
+        }
+    }
+
+    fn stream(bus: &Arc<MessageBus>) -> ReplyStream {
+        ReplyStream::new(
+            Arc::clone(bus),
+            OutboundMessage::new("telegram", "chat1", ""),
+            Duration::from_millis(100),
+        )
+    }
+
+    async fn call(provider: &PiecesProvider, stream: &mut ReplyStream) -> LLMResponse {
+        chat(
+            provider,
+            Some(stream),
+            vec![],
+            vec![],
+            None,
+            ChatOptions::default(),
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn no_more_outbound(bus: &MessageBus) -> bool {
+        tokio::time::timeout(Duration::from_millis(50), bus.consume_outbound())
+            .await
+            .is_err()
+    }
+
+    #[tokio::test]
+    async fn test_partial_text_is_published_while_streaming() {
+        let bus = Arc::new(MessageBus::new());
+        let mut stream = stream(&bus);
+        let provider = PiecesProvider {
+            pieces: vec!["The ", "answer ", "is ", "42."],
+            pause: Duration::from_millis(60),
+            streams: true,
+        };
+
+        let response = call(&provider, &mut stream).await;
+        assert_eq!(response.content, "The answer is 42.");
+        assert_eq!(response.tool_calls.len(), 1);
+        assert_eq!(response.usage.unwrap().completion_tokens, 4);
+
+        // Pieces arrive every 60ms and updates are due every 100ms, so the
+        // first piece alone is never shown.
+        let first = bus.consume_outbound().await.unwrap();
+        assert!(first.is_stream_partial());
+        assert!(first.content.starts_with("The answer"));
+        assert!("The answer is 42.".starts_with(&first.content));
+        assert_eq!(first.metadata.get("keep_typing").unwrap(), "true");
+
+        let last = stream.finish(OutboundMessage::new(
+            "telegram",
+            "chat1",
+            "The answer is 42.",
+        ));
+        assert_eq!(last.stream_id(), first.stream_id());
+        assert!(!last.is_stream_partial());
+    }
+
+    #[tokio::test]
+    async fn test_quick_or_non_streaming_replies_send_no_updates() {
+        let bus = Arc::new(MessageBus::new());
+        let mut stream = stream(&bus);
+        let quick = PiecesProvider {
+            pieces: vec!["Hi"],
+            pause: Duration::from_millis(10),
+            streams: true,
+        };
+        assert_eq!(call(&quick, &mut stream).await.content, "Hi");
+
+        let plain = PiecesProvider {
+            pieces: vec!["Hello ", "there"],
+            pause: Duration::from_millis(150),
+            streams: false,
+        };
+        let response = call(&plain, &mut stream).await;
+        assert_eq!(response.content, "Hello there");
+        assert!(response.tool_calls.is_empty());
+
+        assert!(no_more_outbound(&bus).await);
+        let last = stream.finish(OutboundMessage::new("telegram", "chat1", "Hi"));
+        assert_eq!(last.stream_id(), None);
+    }
+
+    #[tokio::test]
+    async fn test_unfinished_text_keeps_what_a_cut_call_streamed() {
+        let bus = Arc::new(MessageBus::new());
+        let mut stream = stream(&bus);
+        let provider = PiecesProvider {
+            pieces: vec!["The ", "answer ", "is ", "42."],
+            pause: Duration::from_millis(60),
+            streams: true,
+        };
+
+        let cut = tokio::time::timeout(Duration::from_millis(150), call(&provider, &mut stream));
+        assert!(cut.await.is_err());
+        let partial = stream.unfinished_text().unwrap();
+        assert!(partial.starts_with("The "));
+        assert!("The answer is 42.".starts_with(partial));
+
+        call(&provider, &mut stream).await;
+        assert_eq!(stream.unfinished_text(), None);
+    }
+}
@@ -0,0 +1,286 @@
+//! Per-channel constraints on replies.
+//!
+//! Some channels can't show Markdown, cap message length, or should never
+//! see reasoning a model leaks in `<thinking>` tags. Each turn resolves
+//! [`ResponseOptions`] from `agents.response_options`: the entry for the
+//! session key, then the one for the channel. Stop sequences go to the
+//! provider; the agent loop runs the final reply through [`apply`] before
+//! it goes on the bus.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tracing::warn;
+
+use crate::config::{AgentConfig, ResponseOptions};
+
+/// Appended to a reply cut in the middle of a sentence.
+const ELLIPSIS: char = '…';
+
+/// Resolve the options for a turn in `session_key` on `channel`.
+pub(crate) fn resolve(agents: &AgentConfig, session_key: &str, channel: &str) -> ResponseOptions {
+    let options = &agents.response_options;
+    let session = options.get(session_key).cloned().unwrap_or_default();
+    match options.get(channel) {
+        Some(channel) => session.or(channel),
+        None => session,
+    }
+}
+
+/// Whether `options` change the reply text after generation, so partial
+/// replies must not be shown before it is done.
+pub(crate) fn rewrites(options: &ResponseOptions) -> bool {
+    options.max_length.is_some_and(|n| n > 0)
+        || options.strip_markdown == Some(true)
+        || options
+            .strip_patterns
+            .as_ref()
+            .is_some_and(|p| !p.is_empty())
+}
+
+/// `text` with `options` applied: matches of `strip_patterns` removed,
+/// Markdown turned into plain text, then cut to `max_length`.
+pub(crate) fn apply(options: &ResponseOptions, text: &str) -> String {
+    let mut text = text.to_string();
+    if let Some(patterns) = &options.strip_patterns {
+        text = strip_patterns(&text, patterns);
+    }
+    if options.strip_markdown == Some(true) {
+        text = strip_markdown(&text);
+    }
+    if let Some(max_length) = options.max_length.filter(|n| *n > 0) {
+        text = truncate(&text, max_length);
+    }
+    text
+}
+
+/// `text` without the matches of `patterns`. Invalid patterns are skipped.
+fn strip_patterns(text: &str, patterns: &[String]) -> String {
+    let mut text = text.to_string();
+    for pattern in patterns {
+        match Regex::new(pattern) {
+            Ok(re) => text = re.replace_all(&text, "").into_owned(),
+            Err(e) => warn!(pattern = %pattern, error = %e, "Skipping invalid strip pattern"),
+        }
+    }
+    collapse_blank_lines(&text)
+}
+
+static RE_FENCED_CODE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)```[^\n]*\n(.*?)\n?```").unwrap());
+static RE_INLINE_CODE: Lazy<Regex> = Lazy::new(|| Regex::new(r"`([^`\n]+)`").unwrap());
+static RE_IMAGE: Lazy<Regex> = Lazy::new(|| Regex::new(r"!\[([^\]]*)\]\([^)]*\)").unwrap());
+static RE_LINK: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[([^\]]+)\]\(([^)\s]+)\)").unwrap());
+static RE_HEADER: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^#{1,6}[ \t]+").unwrap());
+static RE_BLOCKQUOTE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^>[ \t]?").unwrap());
+static RE_HR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^[ \t]*(?:-{3,}|\*{3,}|_{3,})[ \t]*$").unwrap());
+static RE_BULLET: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^([ \t]*)[*+][ \t]+").unwrap());
+static RE_BOLD_ITALIC: Lazy<Regex> = Lazy::new(|| Regex::new(r"\*\*\*(.+?)\*\*\*").unwrap());
+static RE_BOLD: Lazy<Regex> = Lazy::new(|| Regex::new(r"\*\*(.+?)\*\*|__(.+?)__").unwrap());
+static RE_ITALIC: Lazy<Regex> = Lazy::new(|| Regex::new(r"\*([^*\n]+?)\*").unwrap());
+// The leading space or start of text stands in for a lookbehind so
+// snake_case survives.
+static RE_ITALIC_UNDERSCORE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?P<pre>^|\s)_(?P<body>[^_\n]+?)_(?P<suf>[\s.,;:!?]|$)").unwrap());
+static RE_STRIKETHROUGH: Lazy<Regex> = Lazy::new(|| Regex::new(r"~~(.+?)~~").unwrap());
+static RE_BLANK_LINES: Lazy<Regex> = Lazy::new(|| Regex::new(r"\n{3,}").unwrap());
+
+/// `text` with Markdown formatting turned into plain text. Code keeps its
+/// content, links keep their URL, and `*`/`+` bullets become `-`.
+pub(crate) fn strip_markdown(text: &str) -> String {
+    // Code is set aside first so nothing inside it is taken for formatting.
+    let mut code = Vec::new();
+    let mut hold = |content: &str| {
+        code.push(content.to_string());
+        format!("\u{0}{}\u{0}", code.len() - 1)
+    };
+    let text = RE_FENCED_CODE.replace_all(text, |c: &regex::Captures| hold(&c[1]));
+    let text = RE_INLINE_CODE
+        .replace_all(&text, |c: &regex::Captures| hold(&c[1]))
+        .into_owned();
+
+    let text = RE_IMAGE.replace_all(&text, "$1");
+    let text = RE_LINK.replace_all(&text, |c: &regex::Captures| {
+        if c[1] == c[2] {
+            c[2].to_string()
+        } else {
+            format!("{} ({})", &c[1], &c[2])
+        }
+    });
+    let text = RE_HEADER.replace_all(&text, "");
+    let text = RE_BLOCKQUOTE.replace_all(&text, "");
+    let text = RE_HR.replace_all(&text, "");
+    let text = RE_BULLET.replace_all(&text, "$1- ");
+    let text = RE_BOLD_ITALIC.replace_all(&text, "$1");
+    let text = RE_BOLD.replace_all(&text, "$1$2");
+    let text = RE_ITALIC.replace_all(&text, "$1");
+    let text = RE_ITALIC_UNDERSCORE.replace_all(&text, "$pre$body$suf");
+    let text = RE_STRIKETHROUGH.replace_all(&text, "$1");
+
+    let mut text = text.into_owned();
+    for (i, content) in code.iter().enumerate() {
+        text = text.replace(&format!("\u{0}{}\u{0}", i), content);
+    }
+    collapse_blank_lines(&text)
+}
+
+fn collapse_blank_lines(text: &str) -> String {
+    RE_BLANK_LINES.replace_all(text.trim(), "\n\n").into_owned()
+}
+
+/// `text` cut to at most `max_chars` characters (not bytes). The cut falls
+/// at the last sentence end in the second half of the allowance if there is
+/// one, else at the last space there followed by `…`, else mid-word
+/// followed by `…`.
+pub(crate) fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    // Leave room for the ellipsis; `nth` keeps the cut on a char boundary.
+    let budget = max_chars.saturating_sub(1);
+    let end = text
+        .char_indices()
+        .nth(budget)
+        .map_or(text.len(), |(i, _)| i);
+    let head = &text[..end];
+    let half = head.len() / 2;
+
+    if let Some(cut) = sentence_end(head).filter(|&i| i >= half) {
+        return head[..cut].to_string();
+    }
+    let cut = head
+        .rfind(char::is_whitespace)
+        .filter(|&i| i >= half)
+        .unwrap_or(head.len());
+    let mut out = head[..cut].trim_end().to_string();
+    out.push(ELLIPSIS);
+    out
+}
+
+/// Byte index just past the last sentence end in `text`: `.`, `!` or `?`
+/// (or their full-width forms) followed by whitespace, or a line break.
+fn sentence_end(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+    let mut last = None;
+    while let Some((i, c)) = chars.next() {
+        let next_is_space = chars.peek().is_some_and(|(_, n)| n.is_whitespace());
+        if (matches!(c, '.' | '!' | '?') && next_is_space) || matches!(c, '。' | '！' | '？') {
+            last = Some(i + c.len_utf8());
+        } else if c == '\n' && i > 0 {
+            last = Some(i);
+        }
+    }
+    last.map(|i| text[..i].trim_end().len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_resolve_prefers_session_then_channel() {
+        let agents = AgentConfig {
+            response_options: HashMap::from([
+                (
+                    "sms".to_string(),
+                    ResponseOptions {
+                        max_length: Some(160),
+                        strip_markdown: Some(true),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "sms:+15550100".to_string(),
+                    ResponseOptions {
+                        max_length: Some(480),
+                        ..Default::default()
+                    },
+                ),
+            ]),
+            ..Default::default()
+        };
+
+        let plain = resolve(&agents, "sms:+15550199", "sms");
+        assert_eq!(plain.max_length, Some(160));
+        let session = resolve(&agents, "sms:+15550100", "sms");
+        assert_eq!(session.max_length, Some(480));
+        assert_eq!(session.strip_markdown, Some(true));
+        assert!(rewrites(&session));
+
+        let other = resolve(&agents, "telegram:1", "telegram");
+        assert_eq!(other, ResponseOptions::default());
+        assert!(!rewrites(&other));
+    }
+
+    #[test]
+    fn test_strip_markdown_keeps_text_code_and_urls() {
+        let markdown = "# Plan\n\n**Step one**: run `cargo build --all_features`.\n\
+             * read *the* [guide](https://example.com/guide)\n\
+             + see https://x.io and ![diagram](d.png)\n\n---\n\n\
+             > quoted _note_ in snake_case_name ~~old~~\n\n\n\
+             ```rust\nlet x = **y**;\n```";
+        assert_eq!(
+            strip_markdown(markdown),
+            "Plan\n\nStep one: run cargo build --all_features.\n\
+             - read the guide (https://example.com/guide)\n\
+             - see https://x.io and diagram\n\n\
+             quoted note in snake_case_name old\n\n\
+             let x = **y**;"
+        );
+        assert_eq!(
+            strip_markdown("[https://a.io](https://a.io)"),
+            "https://a.io"
+        );
+    }
+
+    #[test]
+    fn test_strip_patterns_removes_leaked_reasoning() {
+        let patterns = vec![
+            r"(?s)<thinking>.*?</thinking>".to_string(),
+            "[invalid".to_string(),
+        ];
+        let text = "<thinking>\nThe user wants X.\n</thinking>\n\nHere is X.";
+        assert_eq!(strip_patterns(text, &patterns), "Here is X.");
+    }
+
+    #[test]
+    fn test_truncate_prefers_sentence_then_word_boundaries() {
+        let text = "First sentence here. Second one is longer and goes on.";
+        assert_eq!(truncate(text, 100), text);
+        assert_eq!(truncate(text, 40), "First sentence here.");
+        assert_eq!(
+            truncate("one two three four five six", 16),
+            "one two three…"
+        );
+        assert_eq!(truncate("abcdefghij", 5), "abcd…");
+        assert_eq!(truncate("Line one\nline two goes on", 14), "Line one");
+    }
+
+    #[test]
+    fn test_truncate_counts_characters_not_bytes() {
+        let text = "héllo wörld ünïcode 😀😀😀 日本語のテキストです。続きがあります";
+        for max in 1..text.chars().count() {
+            let cut = truncate(text, max);
+            assert!(cut.chars().count() <= max, "{max}: {cut}");
+        }
+        assert_eq!(truncate("😀😀😀😀😀", 3), "😀😀…");
+        assert_eq!(
+            truncate("日本語のテキストです。続きがあります", 14),
+            "日本語のテキストです。"
+        );
+    }
+
+    #[test]
+    fn test_apply_runs_each_step_in_order() {
+        let options = ResponseOptions {
+            stop: Some(vec!["END".to_string()]),
+            max_length: Some(20),
+            strip_markdown: Some(true),
+            strip_patterns: Some(vec![r"(?s)<thinking>.*?</thinking>".to_string()]),
+        };
+        let text = "<thinking>hmm</thinking>**Done.** The build passed on all targets.";
+        assert_eq!(apply(&options, text), "Done. The build…");
+        assert_eq!(apply(&ResponseOptions::default(), text), text);
+    }
+}
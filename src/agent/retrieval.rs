@@ -0,0 +1,448 @@
+//! Retrieval: excerpts from the user's documents added to the context.
+//!
+//! A [`Retriever`] finds the chunks of text most relevant to the latest user
+//! message. The [`ContextBuilder`](crate::agent::ContextBuilder) asks it once
+//! per turn and the agent loop adds the chunks, labelled with their source,
+//! to the system prompt within their own token budget. A retriever that
+//! fails, times out or finds nothing leaves the context as it was.
+//!
+//! [`DirectoryRetriever`] is the built-in one: it splits the text and
+//! Markdown files of `agents.defaults.retrieval.dir` into chunks and ranks
+//! them with BM25 keyword scoring. Embedding-backed retrievers plug in by
+//! implementing the trait and passing it to
+//! [`ContextBuilder::with_retriever`](crate::agent::ContextBuilder::with_retriever).
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+
+use crate::config::RetrievalConfig;
+use crate::error::Result;
+
+/// Estimated characters per token when fitting excerpts to their budget.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// BM25 tuning parameters.
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+/// File extensions indexed by [`DirectoryRetriever`].
+const EXTENSIONS: &[&str] = &["md", "markdown", "mdx", "txt", "rst"];
+
+/// Larger files are skipped.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Files indexed at most; the rest are skipped.
+const MAX_FILES: usize = 2000;
+
+/// A passage of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetrievedChunk {
+    /// Where the passage comes from, e.g. `guide/install.md:12-30`.
+    pub source: String,
+    pub text: String,
+    /// Relevance to the query; higher is better. Only comparable between
+    /// chunks of the same retriever.
+    pub score: f32,
+}
+
+/// Finds passages relevant to a query.
+#[async_trait]
+pub trait Retriever: Send + Sync {
+    /// Up to `k` chunks relevant to `query`, most relevant first.
+    async fn retrieve(&self, query: &str, k: usize) -> Result<Vec<RetrievedChunk>>;
+}
+
+/// The `## Reference Excerpts` section for `chunks`: as many as fit in
+/// `max_tokens`, in order, the first cut to fit if it is too long alone.
+/// `None` when there are no chunks.
+pub(crate) fn format_excerpts(chunks: &[RetrievedChunk], max_tokens: usize) -> Option<String> {
+    let max_chars = max_tokens.saturating_mul(CHARS_PER_TOKEN);
+    let mut out = String::from(
+        "## Reference Excerpts\n\n\
+         Passages from the user's documents that may help with the latest message. \
+         Name the source when you rely on one.",
+    );
+    let mut added = 0;
+    for chunk in chunks {
+        let entry = format!("\n\n[{}]\n{}", chunk.source, chunk.text.trim());
+        if out.len() + entry.len() > max_chars {
+            if added == 0 {
+                // Leave room for the ellipsis.
+                let room = max_chars.saturating_sub(out.len() + '…'.len_utf8());
+                let mut cut = String::new();
+                for c in entry.chars() {
+                    if cut.len() + c.len_utf8() > room {
+                        break;
+                    }
+                    cut.push(c);
+                }
+                if cut.len() > chunk.source.len() + 4 {
+                    out.push_str(cut.trim_end());
+                    out.push('…');
+                    added += 1;
+                }
+            }
+            break;
+        }
+        out.push_str(&entry);
+        added += 1;
+    }
+    (added > 0).then_some(out)
+}
+
+/// Ranks chunks of the text and Markdown files under a directory.
+///
+/// The index is built on first use and again whenever a file is added,
+/// removed or modified.
+#[derive(Debug)]
+pub struct DirectoryRetriever {
+    root: PathBuf,
+    chunk_chars: usize,
+    index: Mutex<Option<(Stamp, Index)>>,
+}
+
+/// File count, total size and newest modification time of the indexed
+/// files, to notice changes.
+type Stamp = (usize, u64, Option<SystemTime>);
+
+#[derive(Debug, Default)]
+struct Index {
+    chunks: Vec<Chunk>,
+    /// Number of chunks each term appears in.
+    doc_freq: HashMap<String, usize>,
+    avg_len: f32,
+}
+
+#[derive(Debug)]
+struct Chunk {
+    source: String,
+    text: String,
+    /// Term frequencies.
+    terms: HashMap<String, u32>,
+    len: usize,
+}
+
+impl DirectoryRetriever {
+    /// A retriever over `root`, splitting files into chunks of about
+    /// `chunk_chars` characters.
+    pub fn new(root: impl Into<PathBuf>, chunk_chars: usize) -> Self {
+        Self {
+            root: root.into(),
+            chunk_chars: chunk_chars.max(200),
+            index: Mutex::new(None),
+        }
+    }
+
+    /// The retriever for `agents.defaults.retrieval`, or `None` when no
+    /// directory is configured.
+    pub fn from_config(config: &RetrievalConfig) -> Option<Self> {
+        let dir = config.dir.as_deref().filter(|d| !d.trim().is_empty())?;
+        Some(Self::new(
+            crate::config::expand_home(dir),
+            config.chunk_chars,
+        ))
+    }
+
+    /// Rank the chunks for `query`, rebuilding the index first if the
+    /// files changed.
+    fn search(&self, query: &str, k: usize) -> Vec<RetrievedChunk> {
+        let files = self.files();
+        let stamp = stamp(&files);
+        let mut guard = self.index.lock().unwrap_or_else(|e| e.into_inner());
+        if guard.as_ref().map(|(s, _)| s) != Some(&stamp) {
+            *guard = Some((stamp, self.build_index(&files)));
+        }
+        let Some((_, index)) = guard.as_ref() else {
+            return Vec::new();
+        };
+        index.rank(query, k)
+    }
+
+    /// The files to index with their metadata, sorted by path.
+    fn files(&self) -> Vec<(PathBuf, fs::Metadata)> {
+        let mut files = Vec::new();
+        let mut pending = vec![self.root.clone()];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                if entry.file_name().to_string_lossy().starts_with('.') {
+                    continue;
+                }
+                let Ok(meta) = entry.metadata() else {
+                    continue;
+                };
+                let path = entry.path();
+                if meta.is_dir() {
+                    pending.push(path);
+                } else if meta.len() <= MAX_FILE_BYTES
+                    && path
+                        .extension()
+                        .and_then(|e| e.to_str())
+                        .is_some_and(|e| EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+                {
+                    files.push((path, meta));
+                }
+            }
+        }
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        files.truncate(MAX_FILES);
+        files
+    }
+
+    fn build_index(&self, files: &[(PathBuf, fs::Metadata)]) -> Index {
+        let mut chunks = Vec::new();
+        for (path, _) in files {
+            let Ok(content) = fs::read_to_string(path) else {
+                continue;
+            };
+            let name = path
+                .strip_prefix(&self.root)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            for (first, last, text) in split_chunks(&content, self.chunk_chars) {
+                let tokens = tokenize(&text);
+                let mut terms = HashMap::new();
+                for token in &tokens {
+                    *terms.entry(token.clone()).or_insert(0) += 1;
+                }
+                chunks.push(Chunk {
+                    source: format!("{}:{}-{}", name, first, last),
+                    text,
+                    terms,
+                    len: tokens.len(),
+                });
+            }
+        }
+        Index::new(chunks)
+    }
+}
+
+#[async_trait]
+impl Retriever for DirectoryRetriever {
+    async fn retrieve(&self, query: &str, k: usize) -> Result<Vec<RetrievedChunk>> {
+        Ok(self.search(query, k))
+    }
+}
+
+impl Index {
+    fn new(chunks: Vec<Chunk>) -> Self {
+        let mut doc_freq = HashMap::new();
+        for chunk in &chunks {
+            for term in chunk.terms.keys() {
+                *doc_freq.entry(term.clone()).or_insert(0) += 1;
+            }
+        }
+        let total: usize = chunks.iter().map(|c| c.len).sum();
+        let avg_len = total as f32 / chunks.len().max(1) as f32;
+        Self {
+            chunks,
+            doc_freq,
+            avg_len: avg_len.max(1.0),
+        }
+    }
+
+    /// The `k` chunks with the highest BM25 score for `query`, leaving out
+    /// chunks that match none of its terms.
+    fn rank(&self, query: &str, k: usize) -> Vec<RetrievedChunk> {
+        let terms: HashSet<String> = tokenize(query).into_iter().collect();
+        let n = self.chunks.len() as f32;
+        let mut scored: Vec<(f32, &Chunk)> = self
+            .chunks
+            .iter()
+            .filter_map(|chunk| {
+                let mut score = 0.0;
+                for term in &terms {
+                    let Some(&tf) = chunk.terms.get(term) else {
+                        continue;
+                    };
+                    let df = self.doc_freq.get(term).copied().unwrap_or(0) as f32;
+                    let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+                    let tf = tf as f32;
+                    let norm = 1.0 - B + B * chunk.len as f32 / self.avg_len;
+                    score += idf * tf * (K1 + 1.0) / (tf + K1 * norm);
+                }
+                (score > 0.0).then_some((score, chunk))
+            })
+            .collect();
+        scored.sort_by(|a, b| {
+            b.0.total_cmp(&a.0)
+                .then_with(|| a.1.source.cmp(&b.1.source))
+        });
+        scored
+            .into_iter()
+            .take(k)
+            .map(|(score, chunk)| RetrievedChunk {
+                source: chunk.source.clone(),
+                text: chunk.text.clone(),
+                score,
+            })
+            .collect()
+    }
+}
+
+fn stamp(files: &[(PathBuf, fs::Metadata)]) -> Stamp {
+    let size = files.iter().map(|(_, m)| m.len()).sum();
+    let newest = files.iter().filter_map(|(_, m)| m.modified().ok()).max();
+    (files.len(), size, newest)
+}
+
+/// Lowercase words of two or more letters or digits.
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| t.chars().count() >= 2)
+        .map(str::to_string)
+        .collect()
+}
+
+/// `content` split into chunks of about `max_chars` characters at
+/// paragraph breaks, as (first line, last line, text) with 1-based lines.
+/// A paragraph longer than `max_chars` is a chunk of its own.
+fn split_chunks(content: &str, max_chars: usize) -> Vec<(usize, usize, String)> {
+    let mut chunks = Vec::new();
+    let mut current: Option<(usize, usize, String)> = None;
+    let mut paragraph: Option<(usize, usize, String)> = None;
+
+    let mut flush_paragraph =
+        |paragraph: &mut Option<(usize, usize, String)>,
+         current: &mut Option<(usize, usize, String)>| {
+            let Some((first, last, text)) = paragraph.take() else {
+                return;
+            };
+            match current {
+                Some((_, end, chunk)) if chunk.len() + text.len() + 2 <= max_chars => {
+                    chunk.push_str("\n\n");
+                    chunk.push_str(&text);
+                    *end = last;
+                }
+                _ => {
+                    if let Some(done) = current.take() {
+                        chunks.push(done);
+                    }
+                    *current = Some((first, last, text));
+                }
+            }
+        };
+
+    for (i, line) in content.lines().enumerate() {
+        let number = i + 1;
+        if line.trim().is_empty() {
+            flush_paragraph(&mut paragraph, &mut current);
+            continue;
+        }
+        match &mut paragraph {
+            Some((_, last, text)) => {
+                text.push('\n');
+                text.push_str(line);
+                *last = number;
+            }
+            None => paragraph = Some((number, number, line.to_string())),
+        }
+    }
+    flush_paragraph(&mut paragraph, &mut current);
+    if let Some(done) = current {
+        chunks.push(done);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn docs() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("guide")).unwrap();
+        fs::write(
+            root.join("guide/install.md"),
+            "# Install\n\nRun the installer script.\n\n## Proxy\n\n\
+             Behind a corporate proxy, set HTTPS_PROXY before running the installer.",
+        )
+        .unwrap();
+        fs::write(
+            root.join("faq.txt"),
+            "Billing questions go to the finance team.",
+        )
+        .unwrap();
+        fs::write(root.join("image.png"), "proxy proxy proxy").unwrap();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::write(root.join(".git/notes.md"), "proxy").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_split_chunks_packs_paragraphs_with_line_ranges() {
+        let content = "one\ntwo\n\nthree\n\n\nfour four four four";
+        assert_eq!(
+            split_chunks(content, 16),
+            [
+                (1, 4, "one\ntwo\n\nthree".to_string()),
+                (7, 7, "four four four four".to_string()),
+            ]
+        );
+        assert!(split_chunks("\n\n", 100).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_directory_retriever_ranks_matching_chunks() {
+        let dir = docs();
+        let retriever = DirectoryRetriever::new(dir.path(), 200);
+
+        let chunks = retriever
+            .retrieve("How do I install behind a proxy?", 3)
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].source, "guide/install.md:1-7");
+        assert!(chunks[0].text.contains("HTTPS_PROXY"));
+
+        assert!(retriever
+            .retrieve("quantum chromodynamics", 3)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // New files are picked up.
+        fs::write(
+            dir.path().join("proxy.md"),
+            "Proxy settings live in ~/.proxyrc.",
+        )
+        .unwrap();
+        let chunks = retriever.retrieve("proxy settings", 3).await.unwrap();
+        assert_eq!(chunks[0].source, "proxy.md:1-1");
+        assert_eq!(chunks.len(), 2);
+    }
+
+    #[test]
+    fn test_format_excerpts_fits_the_budget() {
+        let chunk = |source: &str, text: &str| RetrievedChunk {
+            source: source.to_string(),
+            text: text.to_string(),
+            score: 1.0,
+        };
+        assert!(format_excerpts(&[], 1000).is_none());
+
+        let chunks = [
+            chunk("a.md:1-2", "Alpha."),
+            chunk("b.md:3-3", &"x".repeat(2000)),
+        ];
+        let section = format_excerpts(&chunks, 100).unwrap();
+        assert!(section.starts_with("## Reference Excerpts"));
+        assert!(section.contains("[a.md:1-2]\nAlpha."));
+        assert!(!section.contains("b.md"));
+
+        let section = format_excerpts(&chunks[1..], 100).unwrap();
+        assert!(section.contains("[b.md:3-3]"));
+        assert!(section.ends_with('…'));
+        assert!(section.len() <= 400);
+    }
+}
@@ -0,0 +1,335 @@
+//! Limits on turns running at once.
+//!
+//! `agents.defaults.concurrency` caps the turns the agent loop runs at the
+//! same time, overall (`max_turns`) and by session namespace
+//! (`namespace_limits`). A turn over a limit waits in a single queue and
+//! is let in first come, first served: a waiter whose namespace is full is
+//! passed over for the next one, so a full namespace doesn't hold up the
+//! others. A session gets a slot for one turn at a time, and its next turn
+//! queues behind everyone already waiting, so one busy group chat can't
+//! keep its slot while other chats wait.
+//!
+//! When `max_queued` turns are waiting, more are turned away with
+//! `busy_message`.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::oneshot;
+
+use crate::config::ConcurrencyConfig;
+
+/// Turns running and waiting, as the metrics report them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct TurnLoad {
+    pub(crate) in_flight: usize,
+    pub(crate) queued: usize,
+}
+
+/// The answer to [`TurnScheduler::request`].
+pub(crate) enum Admission {
+    /// The turn may run now.
+    Ready(TurnSlot),
+    /// The turn waits for a slot.
+    Queued(QueuedTurn),
+    /// The queue is full; the turn doesn't run.
+    Full,
+}
+
+struct Waiter {
+    id: u64,
+    namespace: String,
+    admit: oneshot::Sender<()>,
+}
+
+#[derive(Default)]
+struct State {
+    running: usize,
+    running_by_namespace: HashMap<String, usize>,
+    waiting: VecDeque<Waiter>,
+    next_id: u64,
+}
+
+/// Slots for turns, shared by all sessions.
+pub(crate) struct TurnScheduler {
+    config: ConcurrencyConfig,
+    state: Mutex<State>,
+}
+
+impl TurnScheduler {
+    pub(crate) fn new(config: ConcurrencyConfig) -> Arc<Self> {
+        Arc::new(Self {
+            config,
+            state: Mutex::new(State::default()),
+        })
+    }
+
+    /// A slot for a turn in `session_key`'s namespace: now, after a wait,
+    /// or not at all.
+    pub(crate) fn request(self: &Arc<Self>, session_key: &str) -> Admission {
+        let namespace = namespace_of(session_key).to_string();
+        let mut state = self.lock();
+        if self.has_room(&state, &namespace) {
+            self.start(&mut state, &namespace);
+            return Admission::Ready(TurnSlot {
+                scheduler: Arc::clone(self),
+                namespace,
+            });
+        }
+        if self.config.max_queued > 0 && state.waiting.len() >= self.config.max_queued {
+            return Admission::Full;
+        }
+        let (admit, admitted) = oneshot::channel();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.waiting.push_back(Waiter {
+            id,
+            namespace: namespace.clone(),
+            admit,
+        });
+        Admission::Queued(QueuedTurn {
+            scheduler: Arc::clone(self),
+            id,
+            namespace,
+            admitted,
+            done: false,
+        })
+    }
+
+    /// Turns running and waiting now.
+    pub(crate) fn load(&self) -> TurnLoad {
+        let state = self.lock();
+        TurnLoad {
+            in_flight: state.running,
+            queued: state.waiting.len(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn has_room(&self, state: &State, namespace: &str) -> bool {
+        let global = self.config.max_turns == 0 || state.running < self.config.max_turns;
+        let local = match self.config.namespace_limits.get(namespace) {
+            Some(&limit) if limit > 0 => {
+                state
+                    .running_by_namespace
+                    .get(namespace)
+                    .copied()
+                    .unwrap_or(0)
+                    < limit
+            }
+            _ => true,
+        };
+        global && local
+    }
+
+    fn start(&self, state: &mut State, namespace: &str) {
+        state.running += 1;
+        *state
+            .running_by_namespace
+            .entry(namespace.to_string())
+            .or_default() += 1;
+    }
+
+    fn finish(&self, namespace: &str) {
+        let mut state = self.lock();
+        state.running = state.running.saturating_sub(1);
+        if let Some(running) = state.running_by_namespace.get_mut(namespace) {
+            *running = running.saturating_sub(1);
+            if *running == 0 {
+                state.running_by_namespace.remove(namespace);
+            }
+        }
+        self.admit_waiting(&mut state);
+    }
+
+    /// Let in waiting turns, oldest first, while there is room.
+    fn admit_waiting(&self, state: &mut State) {
+        let mut i = 0;
+        while i < state.waiting.len() {
+            if self.config.max_turns > 0 && state.running >= self.config.max_turns {
+                break;
+            }
+            if !self.has_room(state, &state.waiting[i].namespace) {
+                i += 1;
+                continue;
+            }
+            let waiter = state.waiting.remove(i).expect("index in bounds");
+            // A waiter that went away gets no slot.
+            if waiter.admit.send(()).is_ok() {
+                self.start(state, &waiter.namespace);
+            }
+        }
+    }
+
+    /// Forget a waiting turn that gave up, or hand back the slot it was
+    /// given but never took.
+    fn withdraw(&self, id: u64, namespace: &str) {
+        let mut state = self.lock();
+        match state.waiting.iter().position(|w| w.id == id) {
+            Some(i) => {
+                state.waiting.remove(i);
+            }
+            None => {
+                drop(state);
+                self.finish(namespace);
+            }
+        }
+    }
+}
+
+/// The session namespace of `session_key`: the part before the first `:`.
+fn namespace_of(session_key: &str) -> &str {
+    session_key.split(':').next().unwrap_or(session_key)
+}
+
+/// A running turn's slot, given back when dropped.
+pub(crate) struct TurnSlot {
+    scheduler: Arc<TurnScheduler>,
+    namespace: String,
+}
+
+impl Drop for TurnSlot {
+    fn drop(&mut self) {
+        self.scheduler.finish(&self.namespace);
+    }
+}
+
+/// A turn waiting for a slot.
+pub(crate) struct QueuedTurn {
+    scheduler: Arc<TurnScheduler>,
+    id: u64,
+    namespace: String,
+    admitted: oneshot::Receiver<()>,
+    done: bool,
+}
+
+impl QueuedTurn {
+    /// Turns waiting ahead of this one.
+    pub(crate) fn ahead(&self) -> usize {
+        let state = self.scheduler.lock();
+        state
+            .waiting
+            .iter()
+            .position(|w| w.id == self.id)
+            .unwrap_or(0)
+    }
+
+    /// Wait for the slot.
+    pub(crate) async fn admitted(&mut self) -> TurnSlot {
+        // The sender is only dropped after sending, or by `withdraw`, which
+        // needs `self` gone.
+        let _ = (&mut self.admitted).await;
+        self.done = true;
+        TurnSlot {
+            scheduler: Arc::clone(&self.scheduler),
+            namespace: self.namespace.clone(),
+        }
+    }
+}
+
+impl Drop for QueuedTurn {
+    fn drop(&mut self) {
+        if !self.done {
+            self.scheduler.withdraw(self.id, &self.namespace);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler(
+        max_turns: usize,
+        limits: &[(&str, usize)],
+        max_queued: usize,
+    ) -> Arc<TurnScheduler> {
+        TurnScheduler::new(ConcurrencyConfig {
+            max_turns,
+            namespace_limits: limits.iter().map(|(ns, n)| (ns.to_string(), *n)).collect(),
+            max_queued,
+            ..Default::default()
+        })
+    }
+
+    fn ready(admission: Admission) -> TurnSlot {
+        match admission {
+            Admission::Ready(slot) => slot,
+            _ => panic!("expected a slot"),
+        }
+    }
+
+    fn queued(admission: Admission) -> QueuedTurn {
+        match admission {
+            Admission::Queued(turn) => turn,
+            _ => panic!("expected to wait"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_waiting_turns_run_in_order_and_skip_full_namespaces() {
+        let scheduler = scheduler(3, &[("slack", 1)], 0);
+        let slack_a = ready(scheduler.request("slack:a"));
+        let telegram_a = ready(scheduler.request("telegram:a"));
+
+        // Slack is full, but Telegram isn't held up by its waiting turn.
+        let mut slack_b = queued(scheduler.request("slack:b"));
+        let _telegram_b = ready(scheduler.request("telegram:b"));
+        let mut telegram_c = queued(scheduler.request("telegram:c"));
+        assert_eq!(telegram_c.ahead(), 1);
+        assert_eq!(
+            scheduler.load(),
+            TurnLoad {
+                in_flight: 3,
+                queued: 2
+            }
+        );
+
+        // A freed slot passes over Slack while it is full...
+        drop(telegram_a);
+        let _telegram_c = telegram_c.admitted().await;
+        assert_eq!(slack_b.ahead(), 0);
+        // ...and goes to the next Slack turn once it isn't.
+        drop(slack_a);
+        let _slack_b = slack_b.admitted().await;
+        assert_eq!(
+            scheduler.load(),
+            TurnLoad {
+                in_flight: 3,
+                queued: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_queue_cap_and_withdrawn_turns() {
+        let scheduler = scheduler(1, &[], 1);
+        let slot = ready(scheduler.request("telegram:a"));
+        let waiting = queued(scheduler.request("telegram:b"));
+        assert!(matches!(scheduler.request("telegram:c"), Admission::Full));
+
+        // A turn that stops waiting leaves the queue; one admitted but never
+        // started gives its slot back.
+        drop(waiting);
+        assert_eq!(scheduler.load().queued, 0);
+        let waiting = queued(scheduler.request("telegram:b"));
+        drop(slot);
+        assert_eq!(scheduler.load().in_flight, 1);
+        drop(waiting);
+        assert_eq!(scheduler.load(), TurnLoad::default());
+    }
+
+    #[test]
+    fn test_no_limits_never_queue() {
+        let scheduler = TurnScheduler::new(ConcurrencyConfig::default());
+        let slots: Vec<_> = (0..50)
+            .map(|i| ready(scheduler.request(&format!("telegram:{i}"))))
+            .collect();
+        assert_eq!(scheduler.load().in_flight, 50);
+        drop(slots);
+        assert_eq!(scheduler.load().in_flight, 0);
+    }
+}
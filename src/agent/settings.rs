@@ -0,0 +1,407 @@
+//! Per-conversation model and sampling settings.
+//!
+//! Each turn runs with [`GenerationSettings`] resolved one setting at a time
+//! from, in order: the inbound message's override, the conversation's own
+//! settings (changed with `/model`, `/temp`, `/think` and `/settings`), the
+//! agent profile's model and thinking budget, `agents.channel_settings` for
+//! the channel and `agents.defaults`. The agent loop builds the provider call options from
+//! the result and records it on the turn's reply.
+
+use crate::channels::model_switch::KNOWN_MODELS;
+use crate::config::{AgentConfig, AgentProfile, Config, GenerationSettings};
+use crate::providers::{configured_provider_models, configured_provider_names, ChatOptions};
+
+/// Resolve the settings a turn in `channel` runs with. The model,
+/// temperature and max tokens are always set; `top_p` and the thinking
+/// budget only when some level sets them.
+pub(crate) fn resolve(
+    agents: &AgentConfig,
+    channel: &str,
+    turn: GenerationSettings,
+    session: &GenerationSettings,
+    profile: Option<&AgentProfile>,
+) -> GenerationSettings {
+    let profile = GenerationSettings {
+        model: profile.and_then(|p| p.model.clone()),
+        thinking_budget: profile.and_then(|p| p.thinking_budget),
+        ..Default::default()
+    };
+    let defaults = GenerationSettings {
+        model: Some(agents.defaults.model.clone()),
+        temperature: Some(agents.defaults.temperature),
+        max_tokens: Some(agents.defaults.max_tokens),
+        top_p: None,
+        thinking_budget: None,
+    };
+    let mut settings = turn.or(session).or(&profile);
+    if let Some(channel) = agents.channel_settings.get(channel) {
+        settings = settings.or(channel);
+    }
+    settings.or(&defaults)
+}
+
+/// Provider call options for resolved `settings`.
+pub(crate) fn chat_options(settings: &GenerationSettings) -> ChatOptions {
+    let mut options = ChatOptions::new();
+    if let Some(max_tokens) = settings.max_tokens {
+        options = options.with_max_tokens(max_tokens);
+    }
+    if let Some(temperature) = settings.temperature {
+        options = options.with_temperature(temperature);
+    }
+    if let Some(top_p) = settings.top_p {
+        options = options.with_top_p(top_p);
+    }
+    if let Some(budget) = settings.thinking_budget.filter(|b| *b > 0) {
+        options = options.with_thinking_budget(budget);
+    }
+    options
+}
+
+/// Models a conversation may switch to: the known models of configured
+/// providers, then models named in the config (`agents.defaults.model`,
+/// profiles, channel settings, providers' `model`).
+pub(crate) fn available_models(config: &Config) -> Vec<String> {
+    let providers = configured_provider_names(config);
+    let agents = &config.agents;
+    let mut models: Vec<String> = KNOWN_MODELS
+        .iter()
+        .filter(|m| providers.contains(&m.provider))
+        .map(|m| m.model.to_string())
+        .collect();
+    let configured = std::iter::once(agents.defaults.model.clone())
+        .chain(agents.profiles.values().filter_map(|p| p.model.clone()))
+        .chain(
+            agents
+                .channel_settings
+                .values()
+                .filter_map(|s| s.model.clone()),
+        )
+        .chain(
+            configured_provider_models(config)
+                .into_iter()
+                .map(|(_, model)| model),
+        );
+    for model in configured {
+        if !model.is_empty() && !models.contains(&model) {
+            models.push(model);
+        }
+    }
+    models
+}
+
+/// A setting a conversation can change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Setting {
+    Model,
+    Temperature,
+    MaxTokens,
+    TopP,
+    ThinkingBudget,
+}
+
+/// The smallest thinking budget providers accept.
+const MIN_THINKING_BUDGET: u32 = 1024;
+
+impl Setting {
+    /// The setting called `name` (`temp` is short for `temperature`).
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "model" => Some(Self::Model),
+            "temperature" | "temp" => Some(Self::Temperature),
+            "max_tokens" => Some(Self::MaxTokens),
+            "top_p" => Some(Self::TopP),
+            "thinking_budget" | "thinking" => Some(Self::ThinkingBudget),
+            _ => None,
+        }
+    }
+
+    /// The setting's name as `/settings` shows it.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Model => "model",
+            Self::Temperature => "temperature",
+            Self::MaxTokens => "max_tokens",
+            Self::TopP => "top_p",
+            Self::ThinkingBudget => "thinking_budget",
+        }
+    }
+
+    /// Set this setting in `settings` to `value`, or clear it when `value`
+    /// is `None`. Values out of range are refused with a message for the
+    /// user. Models are not checked here; see [`available_models`].
+    pub(crate) fn apply(
+        self,
+        settings: &mut GenerationSettings,
+        value: Option<&str>,
+    ) -> Result<(), String> {
+        match self {
+            Self::Model => settings.model = value.map(str::to_string),
+            Self::Temperature => settings.temperature = parse_in(value, 0.0, 2.0, self)?,
+            Self::TopP => {
+                let top_p = parse_in(value, 0.0, 1.0, self)?;
+                if top_p == Some(0.0) {
+                    return Err("top_p must be above 0 and at most 1.".to_string());
+                }
+                settings.top_p = top_p;
+            }
+            Self::MaxTokens => {
+                let max_tokens = value.map(|v| v.parse::<u32>().ok().filter(|n| *n > 0));
+                if max_tokens == Some(None) {
+                    return Err("max_tokens must be a whole number above 0.".to_string());
+                }
+                settings.max_tokens = max_tokens.flatten();
+            }
+            Self::ThinkingBudget => {
+                let budget = value.map(|v| match v.to_ascii_lowercase().as_str() {
+                    "off" => Some(0),
+                    v => v
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|n| *n == 0 || *n >= MIN_THINKING_BUDGET),
+                });
+                if budget == Some(None) {
+                    return Err(format!(
+                        "thinking_budget must be off, 0 or a whole number of at least {}.",
+                        MIN_THINKING_BUDGET
+                    ));
+                }
+                settings.thinking_budget = budget.flatten();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse a number between `min` and `max` inclusive.
+fn parse_in(
+    value: Option<&str>,
+    min: f32,
+    max: f32,
+    setting: Setting,
+) -> Result<Option<f32>, String> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    match value.parse::<f32>() {
+        Ok(n) if (min..=max).contains(&n) => Ok(Some(n)),
+        _ => Err(format!(
+            "{} must be a number from {} to {}.",
+            setting.name(),
+            min,
+            max
+        )),
+    }
+}
+
+/// `/settings` output: the settings in effect, marking the ones this
+/// conversation sets.
+pub(crate) fn format_settings(
+    effective: &GenerationSettings,
+    session: &GenerationSettings,
+) -> String {
+    let line = |setting: Setting, value: Option<String>, own: bool| {
+        format!(
+            "- {}: {}{}",
+            setting.name(),
+            value.unwrap_or_else(|| "provider default".to_string()),
+            if own { " (this conversation)" } else { "" }
+        )
+    };
+    let lines = [
+        line(
+            Setting::Model,
+            effective.model.clone(),
+            session.model.is_some(),
+        ),
+        line(
+            Setting::Temperature,
+            effective.temperature.map(|t| t.to_string()),
+            session.temperature.is_some(),
+        ),
+        line(
+            Setting::MaxTokens,
+            effective.max_tokens.map(|n| n.to_string()),
+            session.max_tokens.is_some(),
+        ),
+        line(
+            Setting::TopP,
+            effective.top_p.map(|p| p.to_string()),
+            session.top_p.is_some(),
+        ),
+        line(
+            Setting::ThinkingBudget,
+            Some(match effective.thinking_budget {
+                Some(budget) if budget > 0 => format!("{} tokens", budget),
+                _ => "off".to_string(),
+            }),
+            session.thinking_budget.is_some(),
+        ),
+    ];
+    format!(
+        "Settings:\n{}\nChange one with /settings <name> <value> (or /model, /temp, /think); use reset as the value to undo, or /settings reset for all.",
+        lines.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProviderConfig;
+    use std::collections::HashMap;
+
+    fn agents() -> AgentConfig {
+        let mut agents = AgentConfig::default();
+        agents.defaults.model = "claude-sonnet-4-6".to_string();
+        agents.defaults.temperature = 0.7;
+        agents.defaults.max_tokens = 8192;
+        agents.channel_settings = HashMap::from([(
+            "telegram".to_string(),
+            GenerationSettings {
+                model: Some("claude-haiku-4-5-20251001".to_string()),
+                top_p: Some(0.9),
+                ..Default::default()
+            },
+        )]);
+        agents
+    }
+
+    #[test]
+    fn test_resolve_takes_each_setting_from_first_level_that_sets_it() {
+        let agents = agents();
+        let session = GenerationSettings {
+            temperature: Some(1.2),
+            ..Default::default()
+        };
+
+        let plain = resolve(
+            &agents,
+            "slack",
+            GenerationSettings::default(),
+            &session,
+            None,
+        );
+        assert_eq!(plain.model.as_deref(), Some("claude-sonnet-4-6"));
+        assert_eq!(plain.temperature, Some(1.2));
+        assert_eq!(plain.max_tokens, Some(8192));
+        assert_eq!(plain.top_p, None);
+
+        let channel = resolve(
+            &agents,
+            "telegram",
+            GenerationSettings::default(),
+            &session,
+            None,
+        );
+        assert_eq!(channel.model.as_deref(), Some("claude-haiku-4-5-20251001"));
+        assert_eq!(channel.top_p, Some(0.9));
+
+        let profile = AgentProfile {
+            model: Some("gpt-5.4".to_string()),
+            ..Default::default()
+        };
+        let profiled = resolve(
+            &agents,
+            "telegram",
+            GenerationSettings::default(),
+            &session,
+            Some(&profile),
+        );
+        assert_eq!(profiled.model.as_deref(), Some("gpt-5.4"));
+
+        let turn = GenerationSettings {
+            model: Some("claude-opus-4-6".to_string()),
+            temperature: Some(0.0),
+            ..Default::default()
+        };
+        let overridden = resolve(&agents, "telegram", turn, &session, Some(&profile));
+        assert_eq!(overridden.model.as_deref(), Some("claude-opus-4-6"));
+        assert_eq!(overridden.temperature, Some(0.0));
+
+        let options = chat_options(&overridden);
+        assert_eq!(options.max_tokens, Some(8192));
+        assert_eq!(options.temperature, Some(0.0));
+        assert_eq!(options.top_p, Some(0.9));
+    }
+
+    #[test]
+    fn test_apply_validates_values() {
+        let mut settings = GenerationSettings::default();
+        assert_eq!(Setting::parse("TEMP"), Some(Setting::Temperature));
+        assert_eq!(Setting::parse("seed"), None);
+
+        Setting::Temperature
+            .apply(&mut settings, Some("1.5"))
+            .unwrap();
+        Setting::MaxTokens
+            .apply(&mut settings, Some("512"))
+            .unwrap();
+        Setting::TopP.apply(&mut settings, Some("0.5")).unwrap();
+        assert_eq!(settings.temperature, Some(1.5));
+        assert_eq!(settings.max_tokens, Some(512));
+        assert_eq!(settings.top_p, Some(0.5));
+
+        assert!(Setting::Temperature
+            .apply(&mut settings, Some("2.5"))
+            .is_err());
+        assert!(Setting::Temperature
+            .apply(&mut settings, Some("hot"))
+            .is_err());
+        assert!(Setting::MaxTokens.apply(&mut settings, Some("0")).is_err());
+        assert!(Setting::TopP.apply(&mut settings, Some("0")).is_err());
+        assert_eq!(settings.temperature, Some(1.5));
+
+        Setting::Temperature.apply(&mut settings, None).unwrap();
+        assert_eq!(settings.temperature, None);
+    }
+
+    #[test]
+    fn test_thinking_budget_from_profile_or_conversation() {
+        let agents = agents();
+        let profile = AgentProfile {
+            thinking_budget: Some(8000),
+            ..Default::default()
+        };
+        let none = GenerationSettings::default();
+        let thinking = resolve(&agents, "slack", none.clone(), &none, Some(&profile));
+        assert_eq!(chat_options(&thinking).thinking_budget, Some(8000));
+
+        let mut session = GenerationSettings::default();
+        Setting::parse("thinking")
+            .unwrap()
+            .apply(&mut session, Some("off"))
+            .unwrap();
+        let off = resolve(&agents, "slack", none.clone(), &session, Some(&profile));
+        assert_eq!(off.thinking_budget, Some(0));
+        assert_eq!(chat_options(&off).thinking_budget, None);
+        assert!(
+            format_settings(&off, &session).contains("- thinking_budget: off (this conversation)")
+        );
+
+        assert!(Setting::ThinkingBudget
+            .apply(&mut session, Some("500"))
+            .is_err());
+        Setting::ThinkingBudget
+            .apply(&mut session, Some("2048"))
+            .unwrap();
+        assert_eq!(session.thinking_budget, Some(2048));
+    }
+
+    #[test]
+    fn test_available_models_follow_configured_providers() {
+        let mut config = Config {
+            agents: agents(),
+            ..Default::default()
+        };
+        let models = available_models(&config);
+        assert!(models.contains(&"claude-sonnet-4-6".to_string()));
+        assert!(models.contains(&"claude-haiku-4-5-20251001".to_string()));
+        assert!(!models.contains(&"gpt-5.4".to_string()));
+
+        config.providers.openai = Some(ProviderConfig {
+            api_key: Some("sk-openai".to_string()),
+            ..Default::default()
+        });
+        assert!(available_models(&config).contains(&"gpt-5.4".to_string()));
+    }
+}
@@ -0,0 +1,165 @@
+//! Per-channel and per-session tool availability.
+//!
+//! `tools.access` narrows the registered tool set for a conversation: a
+//! channel rule (e.g. `telegram`) and any session rules whose pattern matches
+//! the session key (e.g. `telegram:-100*` for Telegram groups) all apply, and
+//! a tool must pass every one of them. The agent loop filters the tool list
+//! sent to the provider with the resolved [`ToolAccess`] and refuses calls to
+//! anything outside it.
+
+use std::collections::HashSet;
+
+use crate::config::{ToolAccessConfig, ToolAccessRule};
+use crate::providers::ToolDefinition;
+
+/// Tool availability resolved for one channel and session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolAccess {
+    /// Tools permitted by every matching `allow` list. `None` means no
+    /// matching rule had an allow list.
+    allow: Option<HashSet<String>>,
+    /// Tools denied by any matching rule.
+    deny: HashSet<String>,
+}
+
+impl ToolAccess {
+    /// Combine the channel rule and every matching session rule.
+    pub fn resolve(config: &ToolAccessConfig, channel: &str, session_key: &str) -> Self {
+        let mut access = Self::default();
+        if let Some(rule) = config.channels.get(channel) {
+            access.apply(rule);
+        }
+        for (pattern, rule) in &config.sessions {
+            if session_pattern_matches(pattern, session_key) {
+                access.apply(rule);
+            }
+        }
+        access
+    }
+
+    fn apply(&mut self, rule: &ToolAccessRule) {
+        self.deny
+            .extend(rule.deny.iter().map(|n| n.to_ascii_lowercase()));
+        if let Some(allow) = &rule.allow {
+            let allow: HashSet<String> = allow.iter().map(|n| n.to_ascii_lowercase()).collect();
+            self.allow = Some(match self.allow.take() {
+                Some(current) => current.intersection(&allow).cloned().collect(),
+                None => allow,
+            });
+        }
+    }
+
+    /// Narrow the available tools further to those in `allow`, such as an
+    /// agent profile's tool list.
+    pub fn restrict(&mut self, allow: &[String]) {
+        self.apply(&ToolAccessRule {
+            allow: Some(allow.to_vec()),
+            deny: Vec::new(),
+        });
+    }
+
+    /// Whether `name` may be offered to and run by the model here.
+    pub fn allows(&self, name: &str) -> bool {
+        let key = name.to_ascii_lowercase();
+        if self.deny.contains(&key) {
+            return false;
+        }
+        self.allow.as_ref().is_none_or(|allow| allow.contains(&key))
+    }
+
+    /// Drop definitions for tools that are not available here.
+    pub fn filter(&self, definitions: Vec<ToolDefinition>) -> Vec<ToolDefinition> {
+        definitions
+            .into_iter()
+            .filter(|def| self.allows(&def.name))
+            .collect()
+    }
+}
+
+/// Match a session key against a `tools.access.sessions` pattern: exact, or
+/// a prefix when the pattern ends in `*`.
+fn session_pattern_matches(pattern: &str, session_key: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => session_key.starts_with(prefix),
+        None => session_key == pattern,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn rule(allow: Option<&[&str]>, deny: &[&str]) -> ToolAccessRule {
+        ToolAccessRule {
+            allow: allow.map(|names| names.iter().map(|n| n.to_string()).collect()),
+            deny: deny.iter().map(|n| n.to_string()).collect(),
+        }
+    }
+
+    fn config() -> ToolAccessConfig {
+        ToolAccessConfig {
+            channels: HashMap::from([("telegram".to_string(), rule(None, &["cron"]))]),
+            sessions: HashMap::from([
+                ("telegram:-*".to_string(), rule(None, &["shell"])),
+                (
+                    "telegram:-1001".to_string(),
+                    rule(Some(&["web_search", "Shell", "read_file"]), &[]),
+                ),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_unconfigured_allows_everything() {
+        let access = ToolAccess::resolve(&ToolAccessConfig::default(), "telegram", "telegram:1");
+        assert_eq!(access, ToolAccess::default());
+        assert!(access.allows("shell"));
+    }
+
+    #[test]
+    fn test_channel_and_session_rules_combine() {
+        let config = config();
+
+        let private = ToolAccess::resolve(&config, "telegram", "telegram:42");
+        assert!(private.allows("shell"));
+        assert!(!private.allows("cron"));
+
+        let group = ToolAccess::resolve(&config, "telegram", "telegram:-2002");
+        assert!(!group.allows("shell"));
+        assert!(group.allows("read_file"));
+
+        // The allow list narrows further, but cannot undo a deny.
+        let narrowed = ToolAccess::resolve(&config, "telegram", "telegram:-1001");
+        assert!(narrowed.allows("WEB_SEARCH"));
+        assert!(!narrowed.allows("shell"));
+        assert!(!narrowed.allows("write_file"));
+
+        let other = ToolAccess::resolve(&config, "slack", "slack:-1");
+        assert!(other.allows("cron"));
+        assert!(other.allows("shell"));
+    }
+
+    #[test]
+    fn test_filter_definitions() {
+        let access = ToolAccess::resolve(&config(), "telegram", "telegram:-5");
+        let defs = ["shell", "read_file", "cron"]
+            .iter()
+            .map(|name| ToolDefinition {
+                name: name.to_string(),
+                description: String::new(),
+                parameters: serde_json::json!({}),
+            })
+            .collect();
+        let names: Vec<String> = access.filter(defs).into_iter().map(|d| d.name).collect();
+        assert_eq!(names, vec!["read_file"]);
+    }
+
+    #[test]
+    fn test_session_pattern_matches() {
+        assert!(session_pattern_matches("cli:*", "cli:default"));
+        assert!(session_pattern_matches("*", "anything"));
+        assert!(session_pattern_matches("slack:C1", "slack:C1"));
+        assert!(!session_pattern_matches("slack:C1", "slack:C12"));
+    }
+}
@@ -0,0 +1,585 @@
+//! Turn traces: what one turn sent to the provider and what came back.
+//!
+//! When tracing is on for a session (`agents.defaults.trace.enabled`, or
+//! `/trace on`), a turn records its context report, every provider request
+//! and response, and every tool call with its timing. Entries are scrubbed
+//! of secrets and written as numbered JSON files under
+//! `<dir>/<session>/<turn-id>/` (`001-context.json`, `002-request.json`,
+//! …). Each session keeps its newest `keep_turns` turns, and turns older
+//! than `max_age_days` are removed. The last trace of each session stays in
+//! memory for [`AgentLoop::last_trace`](crate::agent::AgentLoop::last_trace).
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::{debug, warn};
+
+use crate::config::{Config, TraceConfig};
+use crate::error::Result;
+use crate::providers::{
+    ChatOptions, LLMProvider, LLMResponse, LLMToolCall, StreamEvent, ToolDefinition,
+};
+use crate::safety::leak_detector::{LeakAction, LeakDetector};
+use crate::session::Message;
+
+use super::context::ContextReport;
+
+/// Directory under [`Config::dir`] traces go to by default.
+const TRACES_DIR: &str = "traces";
+
+static LEAK_DETECTOR: Lazy<LeakDetector> = Lazy::new(LeakDetector::new);
+
+/// What a [`TraceEntry`] records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceKind {
+    /// How the history was fitted into the context budget.
+    Context,
+    /// A request sent to the provider.
+    Request,
+    /// The provider's response to the request before it.
+    Response,
+    /// The provider's error for the request before it.
+    Error,
+    /// A tool call, its result and how long it took.
+    Tool,
+}
+
+impl TraceKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            TraceKind::Context => "context",
+            TraceKind::Request => "request",
+            TraceKind::Response => "response",
+            TraceKind::Error => "error",
+            TraceKind::Tool => "tool",
+        }
+    }
+}
+
+/// One recorded step of a turn.
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceEntry {
+    /// Position in the turn, from 1.
+    pub seq: usize,
+    pub kind: TraceKind,
+    /// Milliseconds since the turn started.
+    pub elapsed_ms: u64,
+    /// What was recorded, with secrets scrubbed.
+    pub data: Value,
+}
+
+/// The trace of one turn.
+#[derive(Debug)]
+pub struct TurnTrace {
+    session_key: String,
+    turn_id: String,
+    dir: Option<PathBuf>,
+    started: Instant,
+    open: AtomicBool,
+    entries: Mutex<Vec<TraceEntry>>,
+}
+
+impl TurnTrace {
+    /// The session the turn ran in.
+    pub fn session_key(&self) -> &str {
+        &self.session_key
+    }
+
+    /// The turn's ID, which sorts in the order turns started.
+    pub fn turn_id(&self) -> &str {
+        &self.turn_id
+    }
+
+    /// Directory holding the turn's JSON files, if it could be created.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Whether the turn is still running.
+    pub fn is_open(&self) -> bool {
+        self.open.load(Ordering::SeqCst)
+    }
+
+    /// The entries recorded so far, in order.
+    pub fn entries(&self) -> Vec<TraceEntry> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Record `data` (scrubbed) and write it to the next numbered file.
+    pub(crate) fn record(&self, kind: TraceKind, data: Value) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = TraceEntry {
+            seq: entries.len() + 1,
+            kind,
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            data: scrub(data),
+        };
+        if let Some(dir) = &self.dir {
+            let path = dir.join(format!("{:03}-{}.json", entry.seq, kind.as_str()));
+            let written = serde_json::to_vec_pretty(&entry)
+                .map_err(std::io::Error::other)
+                .and_then(|json| fs::write(&path, json));
+            if let Err(e) = written {
+                warn!(path = %path.display(), error = %e, "Failed to write trace entry");
+            }
+        }
+        entries.push(entry);
+    }
+
+    pub(crate) fn record_context(&self, model: &str, report: &ContextReport) {
+        self.record(
+            TraceKind::Context,
+            json!({
+                "model": model,
+                "budget_tokens": report.budget_tokens,
+                "estimated_tokens": report.estimated_tokens,
+                "dropped_turns": report.dropped_turns,
+                "dropped_messages": report.dropped_messages,
+                "truncated_results": report.truncated_results,
+            }),
+        );
+    }
+
+    /// Record the results of one batch of `calls`, given in call order.
+    pub(crate) fn record_tools(
+        &self,
+        calls: &[LLMToolCall],
+        results: &[(String, String, bool)],
+        elapsed: &[Duration],
+    ) {
+        for ((call, (_, result, _)), elapsed) in calls.iter().zip(results).zip(elapsed) {
+            let arguments = serde_json::from_str::<Value>(&call.arguments)
+                .unwrap_or_else(|_| Value::String(call.arguments.clone()));
+            self.record(
+                TraceKind::Tool,
+                json!({
+                    "id": call.id,
+                    "name": call.name,
+                    "arguments": arguments,
+                    "result": result,
+                    "duration_ms": elapsed.as_millis() as u64,
+                }),
+            );
+        }
+    }
+
+    fn record_request(
+        &self,
+        provider: &str,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        model: Option<&str>,
+        options: &ChatOptions,
+    ) {
+        self.record(
+            TraceKind::Request,
+            json!({
+                "provider": provider,
+                "model": model,
+                "options": {
+                    "max_tokens": options.max_tokens,
+                    "temperature": options.temperature,
+                    "top_p": options.top_p,
+                    "stop": options.stop,
+                    "output_format": options.output_format,
+                    "thinking_budget": options.thinking_budget,
+                },
+                "messages": messages,
+                "tools": tools,
+            }),
+        );
+    }
+
+    fn record_result(&self, result: &Result<LLMResponse>) {
+        match result {
+            Ok(response) => self.record(
+                TraceKind::Response,
+                serde_json::to_value(response).unwrap_or(Value::Null),
+            ),
+            Err(e) => self.record(TraceKind::Error, json!({ "error": e.to_string() })),
+        }
+    }
+}
+
+/// A trace being recorded. Dropping it, however the turn ends, closes the
+/// trace.
+pub(crate) struct ActiveTrace(Arc<TurnTrace>);
+
+impl ActiveTrace {
+    pub(crate) fn trace(&self) -> &Arc<TurnTrace> {
+        &self.0
+    }
+}
+
+impl std::ops::Deref for ActiveTrace {
+    type Target = TurnTrace;
+
+    fn deref(&self) -> &TurnTrace {
+        &self.0
+    }
+}
+
+impl Drop for ActiveTrace {
+    fn drop(&mut self) {
+        self.0.open.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Starts traces and keeps the last one of each session.
+pub(crate) struct TraceRecorder {
+    root: PathBuf,
+    keep_turns: usize,
+    max_age: Option<Duration>,
+    last: Mutex<HashMap<String, Arc<TurnTrace>>>,
+}
+
+impl TraceRecorder {
+    pub(crate) fn new(config: &TraceConfig) -> Self {
+        let root = config
+            .dir
+            .as_deref()
+            .map(crate::config::expand_home)
+            .unwrap_or_else(|| Config::dir().join(TRACES_DIR));
+        Self {
+            root,
+            keep_turns: config.keep_turns.max(1),
+            max_age: (config.max_age_days > 0)
+                .then(|| Duration::from_secs(config.max_age_days * 24 * 60 * 60)),
+            last: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start the trace of a turn in `session_key`, first removing the
+    /// traces the retention limits no longer allow.
+    pub(crate) fn begin(&self, session_key: &str) -> ActiveTrace {
+        let session_dir = self.root.join(dir_name(session_key));
+        self.prune(&session_dir);
+
+        let turn_id = format!(
+            "{}-{}",
+            chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        );
+        let dir = session_dir.join(&turn_id);
+        let dir = match fs::create_dir_all(&dir) {
+            Ok(()) => Some(dir),
+            Err(e) => {
+                warn!(dir = %dir.display(), error = %e, "Failed to create trace directory; keeping the trace in memory only");
+                None
+            }
+        };
+        debug!(session = %session_key, turn_id = %turn_id, "Tracing turn");
+
+        let trace = Arc::new(TurnTrace {
+            session_key: session_key.to_string(),
+            turn_id,
+            dir,
+            started: Instant::now(),
+            open: AtomicBool::new(true),
+            entries: Mutex::new(Vec::new()),
+        });
+        self.last
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(session_key.to_string(), Arc::clone(&trace));
+        ActiveTrace(trace)
+    }
+
+    /// The last trace started in `session_key`.
+    pub(crate) fn last(&self, session_key: &str) -> Option<Arc<TurnTrace>> {
+        self.last
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(session_key)
+            .cloned()
+    }
+
+    /// The trace of the turn running in `session_key`, if it is traced.
+    pub(crate) fn active(&self, session_key: &str) -> Option<Arc<TurnTrace>> {
+        self.last(session_key).filter(|t| t.is_open())
+    }
+
+    /// Remove turns past `max_age` in every session and all but the newest
+    /// `keep_turns - 1` in `session_dir`, making room for the next one.
+    fn prune(&self, session_dir: &Path) {
+        if let Some(max_age) = self.max_age {
+            for session in subdirs(&self.root) {
+                for turn in subdirs(&session) {
+                    let expired = fs::metadata(&turn)
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|t| SystemTime::now().duration_since(t).ok())
+                        .is_some_and(|age| age > max_age);
+                    if expired {
+                        remove_dir(&turn);
+                    }
+                }
+                if subdirs(&session).is_empty() {
+                    let _ = fs::remove_dir(&session);
+                }
+            }
+        }
+
+        let mut turns = subdirs(session_dir);
+        turns.sort();
+        let excess = turns.len().saturating_sub(self.keep_turns - 1);
+        for turn in &turns[..excess] {
+            remove_dir(turn);
+        }
+    }
+}
+
+/// The directories directly in `dir`.
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.path())
+        .collect()
+}
+
+fn remove_dir(dir: &Path) {
+    if let Err(e) = fs::remove_dir_all(dir) {
+        warn!(dir = %dir.display(), error = %e, "Failed to remove old trace");
+    }
+}
+
+/// `session_key` as a directory name: characters other than letters,
+/// digits, `-`, `_` and `.` become `_`.
+fn dir_name(session_key: &str) -> String {
+    session_key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// `value` with every secret the leak detector finds in its strings
+/// redacted, including the ones it would only block or warn about.
+pub(crate) fn scrub(value: Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(scrub_text(&text)),
+        Value::Array(items) => Value::Array(items.into_iter().map(scrub).collect()),
+        Value::Object(map) => Value::Object(map.into_iter().map(|(k, v)| (k, scrub(v))).collect()),
+        other => other,
+    }
+}
+
+fn scrub_text(text: &str) -> String {
+    let (mut text, detections) = LEAK_DETECTOR.redact(text);
+    for detection in detections {
+        if detection.action != LeakAction::Redact {
+            text = text.replace(&detection.matched_text, "[REDACTED]");
+        }
+    }
+    text
+}
+
+/// Wrap `provider` so its requests and responses go into `trace`.
+pub(crate) fn traced(
+    provider: Arc<dyn LLMProvider>,
+    trace: Option<&ActiveTrace>,
+) -> Arc<dyn LLMProvider> {
+    match trace {
+        Some(trace) => Arc::new(TracedProvider {
+            inner: provider,
+            trace: Arc::clone(trace.trace()),
+        }),
+        None => provider,
+    }
+}
+
+/// A provider that records each call in a turn's trace.
+struct TracedProvider {
+    inner: Arc<dyn LLMProvider>,
+    trace: Arc<TurnTrace>,
+}
+
+#[async_trait]
+impl LLMProvider for TracedProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn default_model(&self) -> &str {
+        self.inner.default_model()
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<Message>,
+        tools: Vec<ToolDefinition>,
+        model: Option<&str>,
+        options: ChatOptions,
+    ) -> Result<LLMResponse> {
+        self.trace
+            .record_request(self.inner.name(), &messages, &tools, model, &options);
+        let result = self.inner.chat(messages, tools, model, options).await;
+        self.trace.record_result(&result);
+        result
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: Vec<Message>,
+        tools: Vec<ToolDefinition>,
+        model: Option<&str>,
+        options: ChatOptions,
+    ) -> Result<tokio::sync::mpsc::Receiver<StreamEvent>> {
+        self.trace
+            .record_request(self.inner.name(), &messages, &tools, model, &options);
+        let mut inner = match self
+            .inner
+            .chat_stream(messages, tools, model, options)
+            .await
+        {
+            Ok(rx) => rx,
+            Err(e) => {
+                self.trace
+                    .record(TraceKind::Error, json!({ "error": e.to_string() }));
+                return Err(e);
+            }
+        };
+
+        // Pass the events on, recording the assembled response at the end.
+        let (tx, rx) = tokio::sync::mpsc::channel(32);
+        let trace = Arc::clone(&self.trace);
+        tokio::spawn(async move {
+            let mut tool_calls = Vec::new();
+            let mut thinking = Vec::new();
+            while let Some(event) = inner.recv().await {
+                match &event {
+                    StreamEvent::ToolCalls(calls) => tool_calls.extend(calls.iter().cloned()),
+                    StreamEvent::Thinking(blocks) => thinking.extend(blocks.iter().cloned()),
+                    StreamEvent::Done { content, usage } => trace.record(
+                        TraceKind::Response,
+                        json!({
+                            "content": content,
+                            "tool_calls": tool_calls,
+                            "usage": usage,
+                            "thinking": thinking,
+                        }),
+                    ),
+                    StreamEvent::Error(e) => {
+                        trace.record(TraceKind::Error, json!({ "error": e.to_string() }))
+                    }
+                    StreamEvent::Delta(_) => {}
+                }
+                if tx.send(event).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(rx)
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.inner.embed(texts).await
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.inner.supports_vision()
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorder(dir: &Path, keep_turns: usize) -> TraceRecorder {
+        TraceRecorder::new(&TraceConfig {
+            enabled: true,
+            dir: Some(dir.to_string_lossy().into_owned()),
+            keep_turns,
+            max_age_days: 7,
+        })
+    }
+
+    #[test]
+    fn test_entries_are_numbered_files_with_secrets_scrubbed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let recorder = recorder(tmp.path(), 5);
+        let trace = recorder.begin("telegram:42");
+        trace.record_context("gpt-5", &ContextReport::default());
+        trace.record(
+            TraceKind::Request,
+            json!({ "messages": ["my key is sk-ant-REDACTED"] }),
+        );
+
+        let dir = trace.dir().unwrap().to_path_buf();
+        assert!(dir.starts_with(tmp.path().join("telegram_42")));
+        let mut files: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["001-context.json", "002-request.json"]);
+
+        let written = fs::read_to_string(dir.join("002-request.json")).unwrap();
+        assert!(!written.contains("abcdefghijklmnopqrstuvwxyz0123456789"));
+        let entry: Value = serde_json::from_str(&written).unwrap();
+        assert_eq!(entry["seq"], 2);
+        assert_eq!(entry["kind"], "request");
+    }
+
+    #[test]
+    fn test_last_trace_closes_when_the_turn_ends() {
+        let tmp = tempfile::tempdir().unwrap();
+        let recorder = recorder(tmp.path(), 5);
+        assert!(recorder.last("cli:a").is_none());
+
+        let active = recorder.begin("cli:a");
+        assert!(recorder.active("cli:a").is_some());
+        assert!(recorder.active("cli:b").is_none());
+        drop(active);
+
+        let last = recorder.last("cli:a").unwrap();
+        assert!(!last.is_open());
+        assert!(recorder.active("cli:a").is_none());
+    }
+
+    #[test]
+    fn test_keeps_newest_turns_per_session() {
+        let tmp = tempfile::tempdir().unwrap();
+        let recorder = recorder(tmp.path(), 2);
+        let ids: Vec<String> = (0..4)
+            .map(|_| {
+                std::thread::sleep(Duration::from_millis(2));
+                recorder.begin("cli:a").turn_id().to_string()
+            })
+            .collect();
+        recorder.begin("cli:b");
+
+        let mut kept: Vec<String> = subdirs(&tmp.path().join("cli_a"))
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        kept.sort();
+        assert_eq!(kept, ids[2..]);
+        assert_eq!(subdirs(&tmp.path().join("cli_b")).len(), 1);
+    }
+}
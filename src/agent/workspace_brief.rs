@@ -0,0 +1,393 @@
+//! Workspace brief: a project map for the system prompt.
+//!
+//! Coding sessions go better when the model knows the project up front. The
+//! brief lists the workspace's files (a few levels deep, skipping the
+//! `tools.list_dir.ignore` names and denied paths), the languages and
+//! toolchains its manifest files point to, the current git branch with the
+//! changed files, and the start of the README, cut to
+//! `agents.defaults.workspace_brief.max_tokens`.
+//!
+//! Walking the tree and asking git are not free, so the brief is cached and
+//! only generated again once the modification time of the workspace
+//! directory (or of `.git/HEAD`, for branch switches) changes.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::config::{Config, WorkspaceBriefConfig};
+use crate::security::Sandbox;
+
+/// Estimated characters per token when fitting the brief to its budget.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Changed files listed by name; the rest are only counted.
+const MAX_CHANGED_FILES: usize = 20;
+
+/// Manifest files and the language or toolchain each points to.
+const MANIFESTS: &[(&str, &str)] = &[
+    ("Cargo.toml", "Rust (cargo)"),
+    ("package.json", "JavaScript/TypeScript (npm)"),
+    ("pnpm-lock.yaml", "pnpm"),
+    ("yarn.lock", "yarn"),
+    ("bun.lockb", "bun"),
+    ("pyproject.toml", "Python (pyproject)"),
+    ("requirements.txt", "Python (pip)"),
+    ("setup.py", "Python (setuptools)"),
+    ("go.mod", "Go (go modules)"),
+    ("pom.xml", "Java (Maven)"),
+    ("build.gradle", "Java/Kotlin (Gradle)"),
+    ("build.gradle.kts", "Kotlin (Gradle)"),
+    ("Gemfile", "Ruby (Bundler)"),
+    ("composer.json", "PHP (Composer)"),
+    ("mix.exs", "Elixir (Mix)"),
+    ("Package.swift", "Swift (SwiftPM)"),
+    ("CMakeLists.txt", "C/C++ (CMake)"),
+    ("Makefile", "make"),
+    ("Dockerfile", "Docker"),
+];
+
+/// README names looked for, in order.
+const READMES: &[&str] = &["README.md", "README", "README.rst", "README.txt"];
+
+/// Generates and caches the brief of one workspace.
+#[derive(Debug)]
+pub struct WorkspaceBrief {
+    root: PathBuf,
+    config: WorkspaceBriefConfig,
+    ignore: Vec<String>,
+    /// Denied paths are left out of the tree and never read.
+    sandbox: Sandbox,
+    /// The last brief and the workspace modification time it was made at.
+    cache: Mutex<Option<(SystemTime, Option<String>)>>,
+}
+
+impl WorkspaceBrief {
+    /// A brief of the directory `root`, skipping nothing.
+    pub fn new(root: impl Into<PathBuf>, config: WorkspaceBriefConfig) -> Self {
+        let root = root.into();
+        let sandbox = Sandbox::new(&root.to_string_lossy());
+        Self {
+            root,
+            config,
+            ignore: Vec::new(),
+            sandbox,
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// The brief of the configured workspace, skipping the `list_dir`
+    /// ignore list and denied paths. `None` when the workspace directory
+    /// does not exist.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let root = config.workspace_path();
+        if !root.is_dir() {
+            return None;
+        }
+        let sandbox = Sandbox::from_config(&root.to_string_lossy(), &config.tools.filesystem);
+        Some(
+            Self::new(root, config.agents.defaults.workspace_brief.clone())
+                .with_ignore(config.tools.list_dir.ignore.clone())
+                .with_sandbox(sandbox),
+        )
+    }
+
+    /// Skip files and directories with these names.
+    pub fn with_ignore(mut self, ignore: Vec<String>) -> Self {
+        self.ignore = ignore;
+        self
+    }
+
+    /// Leave out the paths `sandbox` denies.
+    pub fn with_sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// The brief, from the cache unless the workspace changed since it was
+    /// made. `None` when the workspace is gone or empty.
+    pub fn render(&self) -> Option<String> {
+        let stamp = self.stamp()?;
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((cached_at, brief)) = cache.as_ref() {
+            if *cached_at == stamp {
+                return brief.clone();
+            }
+        }
+        let brief = self.generate();
+        *cache = Some((stamp, brief.clone()));
+        brief
+    }
+
+    /// Latest modification time of the workspace directory and `.git/HEAD`.
+    fn stamp(&self) -> Option<SystemTime> {
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let root = modified(&self.root)?;
+        Some(match modified(&self.root.join(".git").join("HEAD")) {
+            Some(head) => root.max(head),
+            None => root,
+        })
+    }
+
+    fn generate(&self) -> Option<String> {
+        let mut lines = Vec::new();
+        let toolchains = self.toolchains();
+        if !toolchains.is_empty() {
+            lines.push(format!("Toolchains: {}", toolchains.join(", ")));
+        }
+        if let Some(git) = self.git_state() {
+            lines.push(git);
+        }
+        let tree = self.tree();
+        if !tree.is_empty() {
+            lines.push(String::new());
+            lines.push("Files:".to_string());
+            lines.extend(tree);
+        }
+        if let Some((name, readme)) = self.readme() {
+            lines.push(String::new());
+            lines.push(format!(
+                "{} (first {} lines):",
+                name, self.config.readme_lines
+            ));
+            lines.extend(readme);
+        }
+        if lines.is_empty() {
+            return None;
+        }
+        let header = format!("## Workspace\n\nRoot: {}", self.root.display());
+        Some(fit_lines(
+            &header,
+            &lines,
+            self.config.max_tokens * CHARS_PER_TOKEN,
+        ))
+    }
+
+    fn toolchains(&self) -> Vec<&'static str> {
+        MANIFESTS
+            .iter()
+            .filter(|(file, _)| self.root.join(file).is_file())
+            .map(|(_, toolchain)| *toolchain)
+            .collect()
+    }
+
+    /// `Git: branch main, 2 changed files: ...`, or `None` outside a repo.
+    fn git_state(&self) -> Option<String> {
+        let branch = git(&self.root, &["rev-parse", "--abbrev-ref", "HEAD"])?;
+        let status = git(&self.root, &["status", "--porcelain"])?;
+        let changed: Vec<&str> = status
+            .lines()
+            .filter_map(|line| line.get(3..))
+            .filter(|path| !self.sandbox.is_denied(&self.root.join(path)))
+            .collect();
+        let mut line = format!("Git: branch {}", branch.trim());
+        if changed.is_empty() {
+            line.push_str(", clean");
+        } else {
+            line.push_str(&format!(
+                ", {} changed file{}: {}",
+                changed.len(),
+                if changed.len() == 1 { "" } else { "s" },
+                changed
+                    .iter()
+                    .take(MAX_CHANGED_FILES)
+                    .copied()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+            if changed.len() > MAX_CHANGED_FILES {
+                line.push_str(", ...");
+            }
+        }
+        Some(line)
+    }
+
+    /// The file tree, directories first at each level, indented two spaces
+    /// per level.
+    fn tree(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        self.visit(&self.root, 0, &mut lines);
+        if lines.len() > self.config.max_entries {
+            lines.truncate(self.config.max_entries);
+            lines.push("...".to_string());
+        }
+        lines
+    }
+
+    fn visit(&self, dir: &Path, depth: usize, lines: &mut Vec<String>) {
+        if depth >= self.config.max_depth || lines.len() > self.config.max_entries {
+            return;
+        }
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        let mut entries: Vec<(bool, String, PathBuf)> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if self.ignore.contains(&name) || self.sandbox.is_denied(&entry.path()) {
+                    return None;
+                }
+                // Does not follow symlinks.
+                let is_dir = entry.file_type().ok()?.is_dir();
+                Some((!is_dir, name, entry.path()))
+            })
+            .collect();
+        entries.sort();
+        let indent = "  ".repeat(depth);
+        for (is_file, name, path) in entries {
+            if lines.len() > self.config.max_entries {
+                return;
+            }
+            if is_file {
+                lines.push(format!("{}{}", indent, name));
+            } else {
+                lines.push(format!("{}{}/", indent, name));
+                self.visit(&path, depth + 1, lines);
+            }
+        }
+    }
+
+    /// The README's name and first lines.
+    fn readme(&self) -> Option<(&'static str, Vec<String>)> {
+        if self.config.readme_lines == 0 {
+            return None;
+        }
+        READMES.iter().find_map(|name| {
+            let path = self.root.join(name);
+            if self.sandbox.is_denied(&path) {
+                return None;
+            }
+            let content = std::fs::read_to_string(&path).ok()?;
+            let lines: Vec<String> = content
+                .lines()
+                .take(self.config.readme_lines)
+                .map(str::to_string)
+                .collect();
+            Some((*name, lines))
+        })
+    }
+}
+
+/// Run `git args` in `dir`, returning its output if it succeeds.
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// `header` followed by as many of `lines` as fit in `max_chars`, noting
+/// when some were left out.
+fn fit_lines(header: &str, lines: &[String], max_chars: usize) -> String {
+    let mut out = header.to_string();
+    for (i, line) in lines.iter().enumerate() {
+        if out.len() + line.len() + 1 > max_chars {
+            out.push_str(&format!("\n[{} more lines left out]", lines.len() - i));
+            break;
+        }
+        out.push('\n');
+        out.push_str(line);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+        std::fs::write(root.join("README.md"), "# Demo\n\nA demo project.\nMore.\n").unwrap();
+        std::fs::write(root.join(".env"), "SECRET=1\n").unwrap();
+        std::fs::create_dir_all(root.join("src/bin")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(root.join("src/bin/deep.rs"), "fn main() {}\n").unwrap();
+        std::fs::create_dir(root.join("target")).unwrap();
+        std::fs::write(root.join("target/out"), "").unwrap();
+        dir
+    }
+
+    fn config() -> WorkspaceBriefConfig {
+        WorkspaceBriefConfig {
+            enabled: true,
+            readme_lines: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_brief_maps_the_workspace() {
+        let dir = workspace();
+        let brief = WorkspaceBrief::new(dir.path(), config())
+            .with_ignore(vec!["target".to_string()])
+            .with_sandbox(Sandbox::new(&dir.path().to_string_lossy()).with_denied_paths(&[".env"]));
+        let text = brief.render().unwrap();
+
+        assert!(text.starts_with("## Workspace\n\nRoot: "));
+        assert!(text.contains("Toolchains: Rust (cargo)"));
+        assert!(text.contains("\nsrc/\n  bin/\n  main.rs\nCargo.toml\nREADME.md\n"));
+        // Two levels deep, without ignored or denied entries.
+        assert!(!text.contains("deep.rs"));
+        assert!(!text.contains("target"));
+        assert!(!text.contains(".env"));
+        assert!(text.contains("README.md (first 3 lines):\n# Demo\n\nA demo project."));
+        assert!(!text.contains("More."));
+    }
+
+    #[test]
+    fn test_brief_is_cached_until_the_workspace_changes() {
+        let dir = workspace();
+        let brief = WorkspaceBrief::new(dir.path(), config());
+        let first = brief.render().unwrap();
+
+        // A change below the top level keeps the cached brief.
+        std::fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+        assert_eq!(brief.render().unwrap(), first);
+
+        let stamp = brief.stamp().unwrap();
+        *brief.cache.lock().unwrap() = Some((stamp, Some("cached".to_string())));
+        assert_eq!(brief.render().as_deref(), Some("cached"));
+        *brief.cache.lock().unwrap() = Some((SystemTime::UNIX_EPOCH, None));
+        assert!(brief.render().unwrap().contains("lib.rs"));
+    }
+
+    #[test]
+    fn test_brief_fits_its_token_budget() {
+        let dir = workspace();
+        for i in 0..50 {
+            std::fs::write(dir.path().join(format!("file_{:02}.txt", i)), "").unwrap();
+        }
+        let brief = WorkspaceBrief::new(
+            dir.path(),
+            WorkspaceBriefConfig {
+                max_tokens: 60,
+                ..config()
+            },
+        );
+        let text = brief.render().unwrap();
+        assert!(text.len() <= 60 * CHARS_PER_TOKEN + 40, "{}", text.len());
+        assert!(text.ends_with("more lines left out]"));
+    }
+
+    #[test]
+    fn test_no_brief_without_a_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        assert_eq!(WorkspaceBrief::new(&missing, config()).render(), None);
+        assert_eq!(WorkspaceBrief::new(dir.path(), config()).render(), None);
+
+        let mut config = Config::default();
+        config.agents.defaults.workspace = missing.to_string_lossy().to_string();
+        assert!(WorkspaceBrief::from_config(&config).is_none());
+    }
+}
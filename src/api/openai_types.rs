@@ -305,6 +305,13 @@ pub fn messages_from_openai(msgs: &[ChatMessage]) -> Result<Vec<Message>, String
                 content_parts: vec![crate::session::ContentPart::Text { text: content }],
                 tool_calls,
                 tool_call_id: m.tool_call_id.clone(),
+                tool_elapsed_ms: None,
+                cancelled: false,
+                usage: None,
+                agent: None,
+                settings: None,
+                thinking: Vec::new(),
+                stable_prefix_len: None,
             })
         })
         .collect()
@@ -481,6 +488,8 @@ pub fn chunk_from_stream_event(
             Some(tool_calls_chunk(calls, model, id, created))
         }
         StreamEvent::ToolCalls(_) => None,
+        // Reasoning is never part of the reply.
+        StreamEvent::Thinking(_) => None,
     }
 }
 
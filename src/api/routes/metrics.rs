@@ -32,6 +32,7 @@ pub async fn get_metrics(State(state): State<Arc<AppState>>) -> Json<Value> {
                 json!({
                     "call_count": m.call_count,
                     "error_count": m.error_count,
+                    "repeat_count": m.repeat_count,
                     "success_rate": m.success_rate(),
                     "avg_ms": m.average_duration().map(|d| d.as_millis()),
                 }),
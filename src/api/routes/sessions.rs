@@ -1,9 +1,9 @@
 //! Session management routes.
 
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::Json;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::sync::Arc;
 
@@ -16,27 +16,48 @@ struct SessionSummary {
     message_count: usize,
     created_at: String,
     updated_at: String,
+    pinned: bool,
+    tags: Vec<String>,
 }
 
-pub async fn list_sessions(State(state): State<Arc<AppState>>) -> Json<Value> {
+/// Query parameters for `GET /api/sessions`.
+#[derive(Debug, Default, Deserialize)]
+pub struct ListSessionsQuery {
+    /// Only return sessions carrying this tag.
+    tag: Option<String>,
+}
+
+pub async fn list_sessions(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListSessionsQuery>,
+) -> Json<Value> {
     let Some(ref manager) = state.session_manager else {
         return Json(json!({ "sessions": [] }));
     };
 
-    let keys = match manager.list().await {
+    let keys = match query.tag {
+        Some(ref tag) => manager.find_by_tag(tag).await,
+        None => manager.list().await,
+    };
+    let keys = match keys {
         Ok(k) => k,
         Err(_) => return Json(json!({ "sessions": [] })),
     };
 
     let mut summaries = Vec::with_capacity(keys.len());
     for key in &keys {
-        if let Ok(Some(session)) = manager.get(key).await {
-            summaries.push(SessionSummary {
+        let summary = manager
+            .with_session(key, |session| SessionSummary {
                 key: session.key.clone(),
                 message_count: session.messages.len(),
                 created_at: session.created_at.to_rfc3339(),
                 updated_at: session.updated_at.to_rfc3339(),
-            });
+                pinned: session.pinned,
+                tags: session.tags.iter().cloned().collect(),
+            })
+            .await;
+        if let Ok(Some(summary)) = summary {
+            summaries.push(summary);
         }
     }
 
@@ -82,6 +103,38 @@ pub async fn delete_session(
     }
 }
 
+/// Handler for `GET /api/sessions/{key}/stats`.
+///
+/// Returns the session's message count, token usage and estimated cost, and
+/// per-tool usage (calls, errors, p50/p95 duration, output bytes). Tools are empty when no usage collector
+/// is wired or the session has not called any tool.
+pub async fn get_session_stats(
+    State(state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+) -> Json<Value> {
+    let (message_count, usage) = match state.session_manager {
+        Some(ref manager) => manager
+            .with_session(&key, |session| (session.messages.len(), session.usage))
+            .await
+            .ok()
+            .flatten()
+            .unzip(),
+        None => (None, None),
+    };
+    let tools = state
+        .tool_usage
+        .as_ref()
+        .map(|usage| usage.session(&key))
+        .unwrap_or_default();
+
+    Json(json!({
+        "key": key,
+        "message_count": message_count,
+        "usage": usage,
+        "tools": tools,
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,7 +146,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_list_sessions_no_manager() {
-        let Json(body) = list_sessions(test_state()).await;
+        let Json(body) = list_sessions(test_state(), Query(ListSessionsQuery::default())).await;
         assert!(body["sessions"].is_array());
         assert_eq!(body["sessions"].as_array().unwrap().len(), 0);
     }
@@ -122,11 +175,36 @@ mod tests {
         let mut state = AppState::new("tok".into(), EventBus::new(16));
         state.session_manager = Some(manager);
 
-        let Json(body) = list_sessions(State(Arc::new(state))).await;
+        let Json(body) =
+            list_sessions(State(Arc::new(state)), Query(ListSessionsQuery::default())).await;
         let sessions = body["sessions"].as_array().expect("sessions array");
         assert_eq!(sessions.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_list_sessions_filtered_by_tag() {
+        use crate::session::SessionManager;
+
+        let manager = Arc::new(SessionManager::new_memory());
+        for key in ["chan:1", "chan:2"] {
+            let session = manager.get_or_create(key).await.unwrap();
+            manager.save(&session).await.unwrap();
+        }
+        manager.add_tag("chan:2", "vip").await.unwrap();
+
+        let mut state = AppState::new("tok".into(), EventBus::new(16));
+        state.session_manager = Some(manager);
+
+        let query = ListSessionsQuery {
+            tag: Some("vip".into()),
+        };
+        let Json(body) = list_sessions(State(Arc::new(state)), Query(query)).await;
+        let sessions = body["sessions"].as_array().expect("sessions array");
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0]["key"], "chan:2");
+        assert_eq!(sessions[0]["tags"][0], "vip");
+    }
+
     #[tokio::test]
     async fn test_get_session_found() {
         use crate::session::SessionManager;
@@ -156,6 +234,43 @@ mod tests {
         assert_eq!(status, StatusCode::NOT_FOUND);
     }
 
+    #[tokio::test]
+    async fn test_get_session_stats() {
+        use crate::session::SessionManager;
+        use crate::tools::ToolUsage;
+        use std::time::Duration;
+
+        let manager = Arc::new(SessionManager::new_memory());
+        let mut session = manager.get_or_create("chan:7").await.unwrap();
+        session.record_usage(&crate::session::UsageCost {
+            input_tokens: 1200,
+            output_tokens: 300,
+            cost_usd: 0.0081,
+            ..Default::default()
+        });
+        manager.save(&session).await.unwrap();
+        let usage = Arc::new(ToolUsage::new());
+        usage.record("shell", "chan:7", Duration::from_millis(40), false, 12);
+        usage.record("shell", "chan:8", Duration::from_millis(40), false, 12);
+
+        let mut state = AppState::new("tok".into(), EventBus::new(16));
+        state.session_manager = Some(manager);
+        state.tool_usage = Some(usage);
+
+        let Json(body) = get_session_stats(State(Arc::new(state)), Path("chan:7".into())).await;
+        assert_eq!(body["message_count"], 0);
+        assert_eq!(body["usage"]["input_tokens"], 1200);
+        assert_eq!(body["usage"]["cost_usd"], 0.0081);
+        assert_eq!(body["tools"]["shell"]["calls"], 1);
+        assert_eq!(body["tools"]["shell"]["p95_ms"], 40);
+        assert_eq!(body["tools"]["shell"]["output_bytes"], 12);
+
+        let Json(body) = get_session_stats(test_state(), Path("chan:7".into())).await;
+        assert!(body["message_count"].is_null());
+        assert!(body["usage"].is_null());
+        assert_eq!(body["tools"], json!({}));
+    }
+
     #[tokio::test]
     async fn test_delete_session_with_manager() {
         use crate::session::SessionManager;
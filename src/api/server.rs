@@ -46,6 +46,8 @@ pub struct AppState {
     pub usage_metrics: Option<Arc<crate::health::UsageMetrics>>,
     /// Per-tool call stats and token tracking for the current session.
     pub metrics_collector: Option<Arc<crate::utils::metrics::MetricsCollector>>,
+    /// Per-tool usage from the agent's tool registry, globally and per session.
+    pub tool_usage: Option<Arc<crate::tools::ToolUsage>>,
     // ── OpenAI-compatible API fields ─────────────────────────────────────
     /// LLM provider for `/v1/chat/completions` pass-through.
     pub provider: Option<Arc<dyn crate::providers::LLMProvider>>,
@@ -69,6 +71,7 @@ impl AppState {
             health_registry: None,
             usage_metrics: None,
             metrics_collector: None,
+            tool_usage: None,
             provider: None,
             config: None,
         }
@@ -131,6 +134,10 @@ pub fn build_router(
             get(super::routes::sessions::get_session)
                 .delete(super::routes::sessions::delete_session),
         )
+        .route(
+            "/api/sessions/{key}/stats",
+            get(super::routes::sessions::get_session_stats),
+        )
         // Channels
         .route("/api/channels", get(super::routes::channels::list_channels))
         // Cron
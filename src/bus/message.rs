@@ -6,6 +6,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::config::GenerationSettings;
+use crate::providers::structured::OutputFormat;
+
 /// Represents an incoming message from a channel (e.g., Telegram, Discord, etc.)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InboundMessage {
@@ -25,6 +28,55 @@ pub struct InboundMessage {
     pub metadata: HashMap<String, String>,
 }
 
+/// Metadata key tying together the outbound messages of one streamed reply.
+pub const STREAM_ID_KEY: &str = "stream_id";
+
+/// Metadata key saying whether a streamed message holds the reply so far
+/// ([`STREAM_PARTIAL`]) or the finished reply ([`STREAM_FINAL`]).
+pub const STREAM_STATE_KEY: &str = "stream_state";
+
+/// [`STREAM_STATE_KEY`] value for the text generated so far.
+pub const STREAM_PARTIAL: &str = "partial";
+
+/// [`STREAM_STATE_KEY`] value for the finished reply.
+pub const STREAM_FINAL: &str = "final";
+
+/// Metadata key marking a "still working" note meant only for channels that
+/// can't show partial updates.
+pub const PROGRESS_NOTE_KEY: &str = "progress_note";
+
+/// Metadata key holding the JSON-encoded [`OutputFormat`] the reply to an
+/// inbound message must follow.
+pub const RESPONSE_FORMAT_KEY: &str = "response_format";
+
+/// Metadata key holding JSON-encoded [`GenerationSettings`] that override
+/// the conversation's for one turn.
+pub const SETTINGS_OVERRIDE_KEY: &str = "settings_override";
+
+/// Metadata key naming a model to use for one turn (set by channels'
+/// `/model` switching). A model in [`SETTINGS_OVERRIDE_KEY`] wins over it.
+pub const MODEL_OVERRIDE_KEY: &str = "model_override";
+
+/// Metadata key marking an inbound message as a heartbeat: a prompt the
+/// agent runs on a schedule rather than one a user sent.
+pub const HEARTBEAT_KEY: &str = "heartbeat";
+
+/// Metadata key asking that an inbound message be answered by the provider
+/// even when `cache.enabled` holds a reply for the same request.
+pub const NO_CACHE_KEY: &str = "no_cache";
+
+/// Metadata key marking a reply served from the response cache.
+pub const CACHED_KEY: &str = "cached";
+
+/// Metadata keys under which channels put the platform's ID for an inbound
+/// message, in the order they are checked.
+pub const MESSAGE_ID_KEYS: &[&str] = &[
+    "message_id",
+    "telegram_message_id",
+    "discord_message_id",
+    "whatsapp_message_id",
+];
+
 /// Represents an outgoing message to be sent via a channel
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutboundMessage {
@@ -138,6 +190,92 @@ impl InboundMessage {
     pub fn has_media(&self) -> bool {
         !self.media.is_empty()
     }
+
+    /// Asks for the reply in `format` (builder pattern). With a JSON schema,
+    /// the reply content is JSON that matches it.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::bus::message::InboundMessage;
+    /// use zeptoclaw::providers::structured::OutputFormat;
+    ///
+    /// let msg = InboundMessage::new("api", "user123", "chat456", "List three colors")
+    ///     .with_response_format(&OutputFormat::json());
+    /// assert_eq!(msg.response_format(), Some(OutputFormat::json()));
+    /// ```
+    pub fn with_response_format(self, format: &OutputFormat) -> Self {
+        let encoded = serde_json::to_string(format).unwrap_or_default();
+        self.with_metadata(RESPONSE_FORMAT_KEY, &encoded)
+    }
+
+    /// The format the reply must follow, if one other than plain text was
+    /// requested.
+    pub fn response_format(&self) -> Option<OutputFormat> {
+        let format: OutputFormat =
+            serde_json::from_str(self.metadata.get(RESPONSE_FORMAT_KEY)?).ok()?;
+        format.is_json().then_some(format)
+    }
+
+    /// Overrides `settings` for this turn only (builder pattern).
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::bus::message::InboundMessage;
+    /// use zeptoclaw::config::GenerationSettings;
+    ///
+    /// let settings = GenerationSettings {
+    ///     temperature: Some(0.0),
+    ///     ..Default::default()
+    /// };
+    /// let msg = InboundMessage::new("api", "user123", "chat456", "Pick a number")
+    ///     .with_settings_override(&settings);
+    /// assert_eq!(msg.settings_override(), settings);
+    /// ```
+    pub fn with_settings_override(self, settings: &GenerationSettings) -> Self {
+        let encoded = serde_json::to_string(settings).unwrap_or_default();
+        self.with_metadata(SETTINGS_OVERRIDE_KEY, &encoded)
+    }
+
+    /// The settings this message overrides for its turn, including a model
+    /// named by [`MODEL_OVERRIDE_KEY`].
+    pub fn settings_override(&self) -> GenerationSettings {
+        let mut settings: GenerationSettings = self
+            .metadata
+            .get(SETTINGS_OVERRIDE_KEY)
+            .and_then(|s| serde_json::from_str(s).ok())
+            .unwrap_or_default();
+        if settings.model.is_none() {
+            settings.model = self
+                .metadata
+                .get(MODEL_OVERRIDE_KEY)
+                .filter(|m| !m.is_empty())
+                .cloned();
+        }
+        settings
+    }
+
+    /// Whether this is a heartbeat prompt (see [`HEARTBEAT_KEY`]).
+    pub fn is_heartbeat(&self) -> bool {
+        self.metadata
+            .get(HEARTBEAT_KEY)
+            .is_some_and(|v| v == "true")
+    }
+
+    /// Whether the sender asked to bypass the response cache (see
+    /// [`NO_CACHE_KEY`]).
+    pub fn no_cache(&self) -> bool {
+        self.metadata.get(NO_CACHE_KEY).is_some_and(|v| v == "true")
+    }
+
+    /// The channel's own ID for this message, if it sent one. A channel that
+    /// delivers a message again sends the same ID.
+    pub fn message_id(&self) -> Option<&str> {
+        MESSAGE_ID_KEYS
+            .iter()
+            .find_map(|key| self.metadata.get(*key))
+            .map(String::as_str)
+            .filter(|id| !id.is_empty())
+    }
 }
 
 impl OutboundMessage {
@@ -200,6 +338,75 @@ impl OutboundMessage {
     pub fn reply_to(msg: &InboundMessage, content: &str) -> Self {
         Self::new(&msg.channel, &msg.chat_id, content)
     }
+
+    /// Marks this message as part of the streamed reply `stream_id`: either
+    /// the whole text generated so far (`partial`), to replace the previous
+    /// update, or the finished reply.
+    ///
+    /// Channels that can edit messages show partial updates in place; the
+    /// others only receive the final message.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::bus::message::OutboundMessage;
+    ///
+    /// let update = OutboundMessage::new("telegram", "chat456", "Hel").with_stream("r1", true);
+    /// assert_eq!(update.stream_id(), Some("r1"));
+    /// assert!(update.is_stream_partial());
+    /// ```
+    pub fn with_stream(mut self, stream_id: &str, partial: bool) -> Self {
+        self.metadata
+            .insert(STREAM_ID_KEY.to_string(), stream_id.to_string());
+        let state = if partial {
+            STREAM_PARTIAL
+        } else {
+            STREAM_FINAL
+        };
+        self.metadata
+            .insert(STREAM_STATE_KEY.to_string(), state.to_string());
+        self
+    }
+
+    /// The streamed reply this message belongs to, if any.
+    pub fn stream_id(&self) -> Option<&str> {
+        self.metadata.get(STREAM_ID_KEY).map(String::as_str)
+    }
+
+    /// Whether this is an in-progress update of a streamed reply.
+    pub fn is_stream_partial(&self) -> bool {
+        self.metadata
+            .get(STREAM_STATE_KEY)
+            .is_some_and(|state| state == STREAM_PARTIAL)
+    }
+
+    /// Marks this message as a "still working" note. Channels that can edit
+    /// messages show progress as partial updates instead and never get it.
+    pub fn with_progress_note(mut self) -> Self {
+        self.metadata
+            .insert(PROGRESS_NOTE_KEY.to_string(), "true".to_string());
+        self
+    }
+
+    /// Whether this is a "still working" note.
+    pub fn is_progress_note(&self) -> bool {
+        self.metadata
+            .get(PROGRESS_NOTE_KEY)
+            .is_some_and(|value| value == "true")
+    }
+
+    /// Marks this message as a reply served from the response cache.
+    pub fn with_cached(mut self) -> Self {
+        self.metadata
+            .insert(CACHED_KEY.to_string(), "true".to_string());
+        self
+    }
+
+    /// Whether this reply was served from the response cache.
+    pub fn is_cached(&self) -> bool {
+        self.metadata
+            .get(CACHED_KEY)
+            .is_some_and(|value| value == "true")
+    }
 }
 
 impl MediaAttachment {
@@ -307,6 +514,69 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_inbound_response_format() {
+        let format = OutputFormat::json_schema(
+            "colors",
+            serde_json::json!({"type": "array", "items": {"type": "string"}}),
+        );
+        let msg =
+            InboundMessage::new("api", "user1", "chat1", "Colors?").with_response_format(&format);
+        assert_eq!(msg.response_format(), Some(format));
+
+        let plain = InboundMessage::new("api", "user1", "chat1", "Hi");
+        assert!(plain.response_format().is_none());
+        let text = plain.clone().with_response_format(&OutputFormat::Text);
+        assert!(text.response_format().is_none());
+        let garbled = plain.with_metadata(RESPONSE_FORMAT_KEY, "{nope");
+        assert!(garbled.response_format().is_none());
+    }
+
+    #[test]
+    fn test_inbound_settings_override() {
+        let plain = InboundMessage::new("api", "user1", "chat1", "Hi");
+        assert!(plain.settings_override().is_empty());
+
+        let switched = plain.clone().with_metadata(MODEL_OVERRIDE_KEY, "gpt-5.4");
+        assert_eq!(
+            switched.settings_override().model.as_deref(),
+            Some("gpt-5.4")
+        );
+
+        let settings = GenerationSettings {
+            model: Some("claude-opus-4-6".to_string()),
+            max_tokens: Some(256),
+            ..Default::default()
+        };
+        let both = switched.with_settings_override(&settings);
+        assert_eq!(both.settings_override(), settings);
+    }
+
+    #[test]
+    fn test_inbound_message_id() {
+        let msg = InboundMessage::new("telegram", "user1", "chat1", "Hi");
+        assert_eq!(msg.message_id(), None);
+        let msg = msg.with_metadata("telegram_message_id", "42");
+        assert_eq!(msg.message_id(), Some("42"));
+        let email = InboundMessage::new("email", "a@b.c", "a@b.c", "Hi")
+            .with_metadata("message_id", "<abc@mail>");
+        assert_eq!(email.message_id(), Some("<abc@mail>"));
+        let empty =
+            InboundMessage::new("discord", "u", "c", "Hi").with_metadata("discord_message_id", "");
+        assert_eq!(empty.message_id(), None);
+    }
+
+    #[test]
+    fn test_cache_flags() {
+        let msg = InboundMessage::new("webhook", "hook", "ci", "Status?");
+        assert!(!msg.no_cache());
+        assert!(msg.with_metadata(NO_CACHE_KEY, "true").no_cache());
+
+        let reply = OutboundMessage::new("webhook", "ci", "All green");
+        assert!(!reply.is_cached());
+        assert!(reply.with_cached().is_cached());
+    }
+
     #[test]
     fn test_outbound_message_creation() {
         let msg = OutboundMessage::new("telegram", "chat456", "Response");
@@ -341,6 +611,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_outbound_stream_markers() {
+        let plain = OutboundMessage::new("telegram", "chat456", "Hi");
+        assert_eq!(plain.stream_id(), None);
+        assert!(!plain.is_stream_partial());
+
+        let last = plain.with_stream("r1", false);
+        assert_eq!(last.stream_id(), Some("r1"));
+        assert!(!last.is_stream_partial());
+        assert_eq!(last.metadata.get(STREAM_STATE_KEY).unwrap(), STREAM_FINAL);
+    }
+
     #[test]
     fn test_outbound_reply_to_inbound() {
         let inbound = InboundMessage::new("telegram", "user123", "chat456", "Hello");
@@ -45,13 +45,26 @@ pub mod message;
 pub use message::{InboundMessage, MediaAttachment, MediaType, OutboundMessage};
 
 use crate::error::{Result, ZeptoError};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::mpsc;
+use tokio::sync::oneshot;
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 
 /// Default buffer size for message channels
 const DEFAULT_BUFFER_SIZE: usize = 100;
 
+/// Decides whether a sender may stop turns and answer waiting prompts.
+type SenderCheck = Arc<dyn Fn(&InboundMessage) -> bool + Send + Sync>;
+
+/// A session waiting for its next inbound message.
+struct ReplyWaiter {
+    /// Only this sender's message answers, when set.
+    sender_id: Option<String>,
+    tx: oneshot::Sender<InboundMessage>,
+}
+
 /// The central message bus for routing messages between channels and agents.
 ///
 /// The `MessageBus` maintains two separate channels:
@@ -69,6 +82,22 @@ pub struct MessageBus {
     outbound_tx: mpsc::Sender<OutboundMessage>,
     /// Receiver for outbound messages (wrapped in Arc<Mutex> for shared access)
     outbound_rx: Arc<Mutex<mpsc::Receiver<OutboundMessage>>>,
+    /// Sessions waiting for their next inbound message, keyed by session key.
+    /// A matching message is handed to the waiter instead of being queued.
+    reply_waiters: Arc<std::sync::Mutex<HashMap<String, ReplyWaiter>>>,
+    /// Cancellation tokens of the turns currently running, keyed by session
+    /// key. A "stop" message for the session cancels the token.
+    active_turns: Arc<std::sync::Mutex<HashMap<String, CancellationToken>>>,
+    /// Senders failing this check are queued untouched, for the agent to
+    /// turn away, instead of stopping a turn or answering a waiter.
+    sender_check: Arc<std::sync::RwLock<Option<SenderCheck>>>,
+}
+
+/// Whether `text` asks to stop the running turn: `stop` or `/stop`, in any
+/// case.
+pub fn is_stop_command(text: &str) -> bool {
+    let text = text.trim();
+    text.eq_ignore_ascii_case("stop") || text.eq_ignore_ascii_case("/stop")
 }
 
 impl MessageBus {
@@ -106,6 +135,9 @@ impl MessageBus {
             inbound_rx: Arc::new(Mutex::new(inbound_rx)),
             outbound_tx,
             outbound_rx: Arc::new(Mutex::new(outbound_rx)),
+            reply_waiters: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            active_turns: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            sender_check: Arc::new(std::sync::RwLock::new(None)),
         }
     }
 
@@ -132,6 +164,9 @@ impl MessageBus {
     /// }
     /// ```
     pub async fn publish_inbound(&self, msg: InboundMessage) -> Result<()> {
+        let Some(msg) = self.intercept(msg) else {
+            return Ok(());
+        };
         self.inbound_tx
             .send(msg)
             .await
@@ -209,6 +244,178 @@ impl MessageBus {
         self.outbound_rx.lock().await.recv().await
     }
 
+    /// Takes the next inbound message for `session_key` out of the normal
+    /// flow.
+    ///
+    /// Until it is resolved or dropped, the next message published for that
+    /// session is sent to the returned receiver instead of the inbound queue.
+    /// Messages for other sessions are queued as usual. This lets a running
+    /// turn wait for a reply (e.g. a tool approval) while the consumer of
+    /// [`MessageBus::consume_inbound`] is busy with that turn. Registering
+    /// again for the same session replaces the previous waiter.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::bus::{MessageBus, InboundMessage};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let bus = MessageBus::new();
+    ///     let reply = bus.wait_for_reply("telegram:chat456");
+    ///
+    ///     let msg = InboundMessage::new("telegram", "user123", "chat456", "yes");
+    ///     bus.publish_inbound(msg).await.unwrap();
+    ///
+    ///     assert_eq!(reply.await.unwrap().content, "yes");
+    /// }
+    /// ```
+    pub fn wait_for_reply(&self, session_key: &str) -> oneshot::Receiver<InboundMessage> {
+        self.register_waiter(session_key, None)
+    }
+
+    /// Like [`wait_for_reply`](Self::wait_for_reply), but only a message from
+    /// `sender_id` answers; other senders' messages in the session are queued
+    /// and the wait goes on.
+    pub fn wait_for_reply_from(
+        &self,
+        session_key: &str,
+        sender_id: &str,
+    ) -> oneshot::Receiver<InboundMessage> {
+        self.register_waiter(session_key, Some(sender_id.to_string()))
+    }
+
+    fn register_waiter(
+        &self,
+        session_key: &str,
+        sender_id: Option<String>,
+    ) -> oneshot::Receiver<InboundMessage> {
+        let (tx, rx) = oneshot::channel();
+        self.reply_waiters
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(session_key.to_string(), ReplyWaiter { sender_id, tx });
+        rx
+    }
+
+    /// Stops waiting for a reply in `session_key`; later messages are queued
+    /// normally again.
+    pub fn cancel_reply(&self, session_key: &str) {
+        self.reply_waiters
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(session_key);
+    }
+
+    /// Register a running turn for `session_key` and return the token that
+    /// is cancelled when the turn is stopped. Call [`end_turn`](Self::end_turn)
+    /// when the turn finishes.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::bus::{MessageBus, InboundMessage};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let bus = MessageBus::new();
+    ///     let token = bus.begin_turn("telegram:chat456");
+    ///
+    ///     let stop = InboundMessage::new("telegram", "user123", "chat456", "stop");
+    ///     bus.publish_inbound(stop).await.unwrap();
+    ///
+    ///     assert!(token.is_cancelled());
+    ///     bus.end_turn("telegram:chat456");
+    /// }
+    /// ```
+    pub fn begin_turn(&self, session_key: &str) -> CancellationToken {
+        let token = CancellationToken::new();
+        self.active_turns
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(session_key.to_string(), token.clone());
+        token
+    }
+
+    /// Forget the running turn for `session_key`.
+    pub fn end_turn(&self, session_key: &str) {
+        self.active_turns
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(session_key);
+    }
+
+    /// Cancel the running turn for `session_key`, e.g. when its channel
+    /// disconnects. Returns `false` if no turn is running.
+    pub fn cancel_turn(&self, session_key: &str) -> bool {
+        let turns = self.active_turns.lock().unwrap_or_else(|e| e.into_inner());
+        match turns.get(session_key) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancel every running turn, e.g. when shutting down. Returns how many
+    /// were running.
+    pub fn cancel_all_turns(&self) -> usize {
+        let turns = self.active_turns.lock().unwrap_or_else(|e| e.into_inner());
+        for token in turns.values() {
+            token.cancel();
+        }
+        turns.len()
+    }
+
+    /// Only let senders passing `check` stop a running turn or answer a
+    /// waiting prompt; messages from anyone else go to the inbound queue as
+    /// they are. The agent loop installs this for `agents.authorization`.
+    pub fn set_sender_check(
+        &self,
+        check: impl Fn(&InboundMessage) -> bool + Send + Sync + 'static,
+    ) {
+        *self.sender_check.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(check));
+    }
+
+    /// Apply a stop command or hand `msg` to a waiter. Returns the message
+    /// back when it should be queued.
+    fn intercept(&self, msg: InboundMessage) -> Option<InboundMessage> {
+        let allowed = self
+            .sender_check
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_none_or(|check| check(&msg));
+        if !allowed {
+            return Some(msg);
+        }
+        if is_stop_command(&msg.content) && self.cancel_turn(&msg.session_key) {
+            return None;
+        }
+        self.deliver_reply(msg)
+    }
+
+    /// Hand `msg` to a waiter for its session, if any. Returns the message
+    /// back when it should be queued instead.
+    fn deliver_reply(&self, msg: InboundMessage) -> Option<InboundMessage> {
+        let waiter = {
+            let mut waiters = self.reply_waiters.lock().unwrap_or_else(|e| e.into_inner());
+            let answers = waiters.get(&msg.session_key).is_some_and(|waiter| {
+                waiter
+                    .sender_id
+                    .as_ref()
+                    .is_none_or(|sender| *sender == msg.sender_id)
+            });
+            if !answers {
+                return Some(msg);
+            }
+            waiters.remove(&msg.session_key)
+        };
+        match waiter {
+            Some(waiter) => waiter.tx.send(msg).err(),
+            None => Some(msg),
+        }
+    }
+
     /// Returns a clone of the inbound message sender.
     ///
     /// This is useful for giving multiple channels their own sender
@@ -253,6 +460,9 @@ impl MessageBus {
     /// - `Err(ZeptoError::BusClosed)` if the channel is closed
     /// - `Err(ZeptoError::Channel)` if the buffer is full
     pub fn try_publish_inbound(&self, msg: InboundMessage) -> Result<()> {
+        let Some(msg) = self.intercept(msg) else {
+            return Ok(());
+        };
         self.inbound_tx.try_send(msg).map_err(|e| match e {
             mpsc::error::TrySendError::Full(_) => {
                 ZeptoError::Channel("inbound buffer full".to_string())
@@ -288,6 +498,9 @@ impl Clone for MessageBus {
             inbound_rx: Arc::clone(&self.inbound_rx),
             outbound_tx: self.outbound_tx.clone(),
             outbound_rx: Arc::clone(&self.outbound_rx),
+            reply_waiters: Arc::clone(&self.reply_waiters),
+            active_turns: Arc::clone(&self.active_turns),
+            sender_check: Arc::clone(&self.sender_check),
         }
     }
 }
@@ -510,4 +723,101 @@ mod tests {
         assert_eq!(outgoing.chat_id, "chat456");
         assert_eq!(outgoing.content, "Hello human!");
     }
+
+    #[tokio::test]
+    async fn test_bus_wait_for_reply_only_takes_its_session() {
+        let bus = MessageBus::new();
+        let reply = bus.wait_for_reply("telegram:chat456");
+
+        let other = InboundMessage::new("telegram", "user9", "chat999", "unrelated");
+        bus.publish_inbound(other).await.unwrap();
+        let answer = InboundMessage::new("telegram", "user123", "chat456", "yes");
+        bus.publish_inbound(answer).await.unwrap();
+        let next = InboundMessage::new("telegram", "user123", "chat456", "thanks");
+        bus.publish_inbound(next).await.unwrap();
+
+        assert_eq!(reply.await.unwrap().content, "yes");
+        assert_eq!(bus.consume_inbound().await.unwrap().content, "unrelated");
+        assert_eq!(bus.consume_inbound().await.unwrap().content, "thanks");
+    }
+
+    #[tokio::test]
+    async fn test_bus_wait_for_reply_from_only_takes_its_sender() {
+        let bus = MessageBus::new();
+        let reply = bus.wait_for_reply_from("telegram:group", "user1");
+
+        let other = InboundMessage::new("telegram", "user2", "group", "yes");
+        bus.publish_inbound(other).await.unwrap();
+        let answer = InboundMessage::new("telegram", "user1", "group", "no");
+        bus.publish_inbound(answer).await.unwrap();
+
+        assert_eq!(reply.await.unwrap().content, "no");
+        assert_eq!(bus.consume_inbound().await.unwrap().sender_id, "user2");
+    }
+
+    #[tokio::test]
+    async fn test_bus_stop_cancels_running_turn() {
+        let bus = MessageBus::new();
+
+        // Without a running turn, "stop" is an ordinary message.
+        bus.publish_inbound(InboundMessage::new("telegram", "u", "chat1", "stop"))
+            .await
+            .unwrap();
+        assert_eq!(bus.consume_inbound().await.unwrap().content, "stop");
+
+        let token = bus.begin_turn("telegram:chat1");
+        bus.publish_inbound(InboundMessage::new("telegram", "u", "chat2", "/stop"))
+            .await
+            .unwrap();
+        assert!(!token.is_cancelled());
+        bus.try_publish_inbound(InboundMessage::new("telegram", "u", "chat1", " STOP "))
+            .unwrap();
+        assert!(token.is_cancelled());
+        assert_eq!(bus.consume_inbound().await.unwrap().content, "/stop");
+
+        bus.end_turn("telegram:chat1");
+        assert!(!bus.cancel_turn("telegram:chat1"));
+    }
+
+    #[tokio::test]
+    async fn test_bus_cancelled_or_dropped_reply_is_queued() {
+        let bus = MessageBus::new();
+
+        let _reply = bus.wait_for_reply("telegram:chat1");
+        bus.cancel_reply("telegram:chat1");
+        bus.publish_inbound(InboundMessage::new("telegram", "u", "chat1", "a"))
+            .await
+            .unwrap();
+
+        drop(bus.wait_for_reply("telegram:chat1"));
+        bus.try_publish_inbound(InboundMessage::new("telegram", "u", "chat1", "b"))
+            .unwrap();
+
+        assert_eq!(bus.consume_inbound().await.unwrap().content, "a");
+        assert_eq!(bus.consume_inbound().await.unwrap().content, "b");
+    }
+
+    #[tokio::test]
+    async fn test_bus_sender_check_keeps_others_from_stopping_or_replying() {
+        let bus = MessageBus::new();
+        bus.clone()
+            .set_sender_check(|msg: &InboundMessage| msg.sender_id == "owner");
+        let token = bus.begin_turn("telegram:chat1");
+        let reply = bus.wait_for_reply("telegram:chat1");
+
+        bus.publish_inbound(InboundMessage::new("telegram", "intruder", "chat1", "stop"))
+            .await
+            .unwrap();
+        bus.publish_inbound(InboundMessage::new("telegram", "intruder", "chat1", "yes"))
+            .await
+            .unwrap();
+        assert!(!token.is_cancelled());
+        assert_eq!(bus.consume_inbound().await.unwrap().content, "stop");
+        assert_eq!(bus.consume_inbound().await.unwrap().content, "yes");
+
+        bus.publish_inbound(InboundMessage::new("telegram", "owner", "chat1", "no"))
+            .await
+            .unwrap();
+        assert_eq!(reply.await.unwrap().content, "no");
+    }
 }
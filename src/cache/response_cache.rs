@@ -1,8 +1,9 @@
 //! LLM response cache with TTL expiry and LRU eviction.
 //!
 //! Persists to `~/.zeptoclaw/cache/responses.json`. Cache key is a SHA-256
-//! digest of `(model, system_prompt, user_prompt)`. Entries expire after a
-//! configurable TTL and are evicted LRU when the store reaches capacity.
+//! digest of `(model, system_prompt, user_prompt)`, or for the agent loop of
+//! the whole request (see [`ResponseCache::conversation_key`]). Entries expire
+//! after a configurable TTL and are evicted LRU when the store reaches capacity.
 
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -11,6 +12,9 @@ use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::{debug, warn};
 
+use crate::providers::{ChatOptions, ToolDefinition};
+use crate::session::Message;
+
 /// A single cached LLM response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheEntry {
@@ -90,6 +94,57 @@ impl ResponseCache {
         hex::encode(hasher.finalize())
     }
 
+    /// Build a cache key for a whole provider request: SHA-256 of the model,
+    /// every message as the provider sees it (the system prompt included),
+    /// the tool schemas offered and the sampling options (temperature, token
+    /// limit, stop sequences, output format, thinking budget).
+    ///
+    /// Tools are hashed in name order, so registration order doesn't matter.
+    /// Message fields kept for diagnostics only (usage, timings) are left out.
+    pub fn conversation_key(
+        model: &str,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        options: &ChatOptions,
+    ) -> String {
+        fn update(hasher: &mut Sha256, bytes: &[u8]) {
+            hasher.update((bytes.len() as u64).to_le_bytes());
+            hasher.update(bytes);
+        }
+
+        let mut hasher = Sha256::new();
+        update(&mut hasher, model.as_bytes());
+        hasher.update((messages.len() as u64).to_le_bytes());
+        for message in messages {
+            let visible = serde_json::json!({
+                "role": message.role,
+                "content": message.content,
+                "content_parts": message.content_parts,
+                "tool_calls": message.tool_calls,
+                "tool_call_id": message.tool_call_id,
+            });
+            update(&mut hasher, visible.to_string().as_bytes());
+        }
+        let mut tools: Vec<&ToolDefinition> = tools.iter().collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        hasher.update((tools.len() as u64).to_le_bytes());
+        for tool in tools {
+            update(&mut hasher, tool.name.as_bytes());
+            update(&mut hasher, tool.description.as_bytes());
+            update(&mut hasher, tool.parameters.to_string().as_bytes());
+        }
+        let sampling = serde_json::json!({
+            "max_tokens": options.max_tokens,
+            "temperature": options.temperature,
+            "top_p": options.top_p,
+            "stop": options.stop,
+            "output_format": options.output_format,
+            "thinking_budget": options.thinking_budget,
+        });
+        update(&mut hasher, sampling.to_string().as_bytes());
+        hex::encode(hasher.finalize())
+    }
+
     /// Look up a cached response. Returns `None` if the key is absent or expired.
     ///
     /// On hit, updates `accessed_at` and increments `hit_count` in memory.
@@ -403,6 +458,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_conversation_key_covers_history_and_tools() {
+        let history = vec![
+            Message::system("sys"),
+            Message::user("hello"),
+            Message::assistant("hi"),
+            Message::user("again"),
+        ];
+        let tools = vec![
+            ToolDefinition::new("a", "first", serde_json::json!({"type": "object"})),
+            ToolDefinition::new("b", "second", serde_json::json!({"type": "object"})),
+        ];
+        let options = ChatOptions::new().with_temperature(0.7);
+        let key = ResponseCache::conversation_key("gpt-4", &history, &tools, &options);
+
+        let reordered: Vec<_> = tools.iter().rev().cloned().collect();
+        assert_eq!(
+            key,
+            ResponseCache::conversation_key("gpt-4", &history, &reordered, &options)
+        );
+        let mut timed = history.clone();
+        timed[2].tool_elapsed_ms = Some(12);
+        assert_eq!(
+            key,
+            ResponseCache::conversation_key("gpt-4", &timed, &tools, &options)
+        );
+
+        let mut edited = history.clone();
+        edited[2].content = "hello".into();
+        assert_ne!(
+            key,
+            ResponseCache::conversation_key("gpt-4", &edited, &tools, &options)
+        );
+        assert_ne!(
+            key,
+            ResponseCache::conversation_key("gpt-4", &history[..3], &tools, &options)
+        );
+        assert_ne!(
+            key,
+            ResponseCache::conversation_key("gpt-4", &history, &tools[..1], &options)
+        );
+        assert_ne!(
+            key,
+            ResponseCache::conversation_key("claude", &history, &tools, &options)
+        );
+        for changed in [
+            ChatOptions::new().with_temperature(0.2),
+            options.clone().with_max_tokens(64),
+            options.clone().with_thinking_budget(1024),
+        ] {
+            assert_ne!(
+                key,
+                ResponseCache::conversation_key("gpt-4", &history, &tools, &changed)
+            );
+        }
+    }
+
     #[test]
     fn test_max_entries_zero_clamped() {
         let cache = ResponseCache {
@@ -489,6 +489,9 @@ impl AcpChannel {
             debug!(session_id = %params.session_id, "ACP: session/cancel for unknown session, ignoring");
         } else if let Some(pending) = state.pending.get_mut(&params.session_id) {
             pending.cancelled = true;
+            // Stop the tools of the running turn too, not just its reply.
+            self.bus
+                .cancel_turn(&format!("{}:{}", ACP_CHANNEL_NAME, params.session_id));
             debug!(session_id = %params.session_id, "ACP: marked prompt as cancelled");
         }
         // Send a result for requests; stay silent for notifications.
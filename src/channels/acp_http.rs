@@ -434,6 +434,7 @@ impl AcpHttpChannel {
     async fn do_session_cancel(
         state: &Arc<Mutex<AcpHttpState>>,
         base_config: &BaseChannelConfig,
+        bus: &MessageBus,
         id: Option<serde_json::Value>,
         params: Option<serde_json::Value>,
     ) -> String {
@@ -446,6 +447,8 @@ impl AcpHttpChannel {
             let mut st = state.lock().await;
             if let Some(pending) = st.pending.get_mut(&p.session_id) {
                 pending.cancelled = true;
+                // Stop the tools of the running turn too, not just its reply.
+                bus.cancel_turn(&format!("{}:{}", ACP_HTTP_CHANNEL_NAME, p.session_id));
                 debug!(session_id = %p.session_id, "ACP-HTTP: marked prompt as cancelled");
             }
         }
@@ -846,6 +849,7 @@ impl AcpHttpChannel {
                     let mut st = state.lock().await;
                     if let Some(pending) = st.pending.get_mut(&p.session_id) {
                         pending.cancelled = true;
+                        bus.cancel_turn(&format!("{}:{}", ACP_HTTP_CHANNEL_NAME, p.session_id));
                         debug!(session_id = %p.session_id, "ACP-HTTP: marked prompt as cancelled (notification)");
                     }
                 }
@@ -867,7 +871,7 @@ impl AcpHttpChannel {
                 let _ = stream.write_all(resp.as_bytes()).await;
             }
             "session/cancel" => {
-                let body = Self::do_session_cancel(&state, &base_config, id, params).await;
+                let body = Self::do_session_cancel(&state, &base_config, &bus, id, params).await;
                 let resp = Self::http_200(&body, open_cors);
                 let _ = stream.write_all(resp.as_bytes()).await;
             }
@@ -1541,6 +1545,7 @@ mod tests {
         let body = AcpHttpChannel::do_session_cancel(
             &ch.state,
             &ch.base_config,
+            &ch.bus,
             Some(serde_json::json!(42)),
             Some(serde_json::json!({ "sessionId": session_id })),
         )
@@ -300,7 +300,6 @@ impl EmailChannel {
             .map_err(|e| ZeptoError::Channel(format!("IMAP FETCH stream failed: {e}")))?;
 
         let parser = MessageParser::default();
-        let inbound_tx = self.bus.inbound_sender();
 
         for raw in &raw_messages {
             let body = match raw.body() {
@@ -373,7 +372,7 @@ impl EmailChannel {
                 }
             }
 
-            if inbound_tx.send(inbound).await.is_err() {
+            if self.bus.publish_inbound(inbound).await.is_err() {
                 return Ok(());
             }
         }
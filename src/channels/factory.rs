@@ -32,17 +32,22 @@ pub async fn register_configured_channels(
                 warn!("Telegram channel enabled but token is empty");
             } else {
                 manager
-                    .register(Box::new(TelegramChannel::new(
-                        telegram_config.clone(),
-                        bus.clone(),
-                        config.agents.defaults.model.clone(),
-                        configured_provider_names(config)
-                            .into_iter()
-                            .map(|name| name.to_string())
-                            .collect(),
-                        configured_provider_models(config),
-                        !matches!(config.memory.backend, MemoryBackend::Disabled),
-                    )))
+                    .register(Box::new(
+                        TelegramChannel::new(
+                            telegram_config.clone(),
+                            bus.clone(),
+                            config.agents.defaults.model.clone(),
+                            configured_provider_names(config)
+                                .into_iter()
+                                .map(|name| name.to_string())
+                                .collect(),
+                            configured_provider_models(config),
+                            !matches!(config.memory.backend, MemoryBackend::Disabled),
+                        )
+                        .with_transcriber(
+                            crate::transcription::TranscriberService::from_config(config),
+                        ),
+                    ))
                     .await;
                 info!("Registered Telegram channel");
             }
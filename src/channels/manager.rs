@@ -520,6 +520,9 @@ impl ChannelManager {
 
         if let Some(channel) = channel {
             let channel = channel.lock().await;
+            if !accepts(&**channel, &msg) {
+                return Ok(());
+            }
             channel.send(msg).await
         } else {
             // Pseudo-channels (e.g. "heartbeat") have no outbound handler — debug-level only
@@ -537,6 +540,16 @@ impl ChannelManager {
     }
 }
 
+/// Whether `msg` should reach `channel`: partial updates of a streamed reply
+/// only go to channels that can edit them into place, and "still working"
+/// notes only to the others.
+fn accepts(channel: &dyn Channel, msg: &OutboundMessage) -> bool {
+    if msg.is_progress_note() {
+        return !channel.supports_edits();
+    }
+    !msg.is_stream_partial() || channel.supports_edits()
+}
+
 /// Background task that dispatches outbound messages from the bus to channels.
 ///
 /// This function runs in a loop, consuming outbound messages from the bus
@@ -574,6 +587,9 @@ async fn dispatch_outbound(
 
                     if let Some(channel) = channel {
                         let channel = channel.lock().await;
+                        if !accepts(&**channel, &msg) {
+                            continue;
+                        }
                         if let Err(e) = channel.send(msg).await {
                             error!("Failed to send message to {}: {}", channel_name, e);
                         }
@@ -814,6 +830,99 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    /// A mock channel that records what it is sent.
+    struct RecordingChannel {
+        name: String,
+        edits: bool,
+        sent: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Channel for RecordingChannel {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        async fn start(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn stop(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn send(&self, msg: OutboundMessage) -> Result<()> {
+            self.sent.lock().unwrap().push(msg.content);
+            Ok(())
+        }
+
+        fn is_running(&self) -> bool {
+            true
+        }
+
+        fn is_allowed(&self, _user_id: &str) -> bool {
+            true
+        }
+
+        fn supports_edits(&self) -> bool {
+            self.edits
+        }
+    }
+
+    #[tokio::test]
+    async fn test_partial_stream_updates_only_reach_edit_capable_channels() {
+        let manager = ChannelManager::new(Arc::new(MessageBus::new()), Config::default());
+        let mut sent = Vec::new();
+        for (name, edits) in [("editing", true), ("plain", false)] {
+            let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+            manager
+                .register(Box::new(RecordingChannel {
+                    name: name.to_string(),
+                    edits,
+                    sent: Arc::clone(&log),
+                }))
+                .await;
+            sent.push(log);
+        }
+
+        for name in ["editing", "plain"] {
+            let partial = OutboundMessage::new(name, "c", "Hel").with_stream("r1", true);
+            let last = OutboundMessage::new(name, "c", "Hello").with_stream("r1", false);
+            manager.send(name, partial).await.unwrap();
+            manager.send(name, last).await.unwrap();
+        }
+
+        assert_eq!(*sent[0].lock().unwrap(), ["Hel", "Hello"]);
+        assert_eq!(*sent[1].lock().unwrap(), ["Hello"]);
+    }
+
+    #[tokio::test]
+    async fn test_progress_notes_only_reach_channels_without_edits() {
+        let manager = ChannelManager::new(Arc::new(MessageBus::new()), Config::default());
+        let mut sent = Vec::new();
+        for (name, edits) in [("editing", true), ("plain", false)] {
+            let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+            manager
+                .register(Box::new(RecordingChannel {
+                    name: name.to_string(),
+                    edits,
+                    sent: Arc::clone(&log),
+                }))
+                .await;
+            sent.push(log);
+        }
+
+        for name in ["editing", "plain"] {
+            let note = OutboundMessage::new(name, "c", "Working").with_progress_note();
+            let status = OutboundMessage::new(name, "c", "Running shell").with_stream("r1", true);
+            manager.send(name, note).await.unwrap();
+            manager.send(name, status).await.unwrap();
+        }
+
+        assert_eq!(*sent[0].lock().unwrap(), ["Running shell"]);
+        assert_eq!(*sent[1].lock().unwrap(), ["Working"]);
+    }
+
     #[tokio::test]
     async fn test_channel_allowlist() {
         let channel = MockChannel::with_allowlist("test", vec!["user1".to_string()]);
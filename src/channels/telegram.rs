@@ -54,6 +54,8 @@ use crate::config::TelegramConfig;
 use crate::error::{Result, ZeptoError};
 use crate::memory::builtin_searcher::BuiltinSearcher;
 use crate::memory::longterm::LongTermMemory;
+use crate::transcription::{TranscriberService, VOICE_MESSAGE_PLACEHOLDER};
+use crate::utils::string::escape_html;
 
 /// Synthetic text used when a photo is sent without a caption.
 const BARE_PHOTO_PLACEHOLDER: &str = "Please analyze this image.";
@@ -82,6 +84,12 @@ struct AllowUsernames(bool);
 struct ReactionsEnabled(bool);
 #[derive(Clone)]
 struct DefaultModel(String);
+/// HTTP client and optional voice transcriber for inbound media.
+#[derive(Clone)]
+struct MediaDep {
+    http_client: reqwest::Client,
+    transcriber: Option<Arc<TranscriberService>>,
+}
 #[derive(Clone)]
 struct ConfiguredProviders {
     names: Vec<String>,
@@ -137,12 +145,6 @@ static RE_NUMBERED_LIST: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^[ \t]*\d+\
 static RE_BLOCKQUOTE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^&gt;\s?(.*)$").unwrap());
 static RE_HR: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^-{3,}\s*$").unwrap());
 
-fn escape_html(s: &str) -> String {
-    s.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-}
-
 /// Validate that HTML tags are properly nested (no crossing tags).
 /// Returns `true` when the tag structure is well-formed.
 fn html_tags_valid(html: &str) -> bool {
@@ -347,16 +349,21 @@ fn telegram_allowlist_allows(
             }))
 }
 
-/// Downloads a photo from Telegram's file API and returns it as a [`MediaAttachment`].
+/// Largest voice or audio file downloaded for transcription (Whisper APIs
+/// accept up to 25 MB).
+const MAX_VOICE_SIZE: usize = 25 * 1024 * 1024;
+
+/// Downloads a file from Telegram's file API, returning its bytes and the
+/// response content type.
 ///
 /// Returns `None` (with a `warn!` log) on any failure: timeout, network error,
-/// empty file path, oversized image, or byte-read error.
-async fn download_telegram_photo(
+/// empty file path, a file over `max_bytes`, or byte-read error.
+async fn download_telegram_file(
     bot: &teloxide::Bot,
     file_id: teloxide::types::FileId,
     http_client: &reqwest::Client,
-) -> Option<MediaAttachment> {
-    use crate::session::media::MAX_IMAGE_SIZE;
+    max_bytes: usize,
+) -> Option<(Vec<u8>, Option<String>)> {
     use teloxide::prelude::Requester;
 
     let file = match tokio::time::timeout(Duration::from_secs(15), bot.get_file(file_id)).await {
@@ -372,7 +379,11 @@ async fn download_telegram_photo(
     };
 
     if file.path.is_empty() {
-        warn!("Telegram file path is empty for photo");
+        warn!("Telegram file path is empty");
+        return None;
+    }
+    if file.size as usize > max_bytes {
+        warn!("Telegram file too large: {} bytes", file.size);
         return None;
     }
 
@@ -385,38 +396,73 @@ async fn download_telegram_photo(
     let resp = match http_client.get(&download_url).send().await {
         Ok(r) => r,
         Err(e) => {
-            warn!("Failed to download Telegram photo: {}", e);
+            warn!("Failed to download Telegram file: {}", e);
             return None;
         }
     };
 
-    let mime_type = resp
+    let content_type = resp
         .headers()
         .get("content-type")
         .and_then(|h| h.to_str().ok())
-        .filter(|ct| ct.starts_with("image/"))
-        .unwrap_or("image/jpeg")
-        .to_string();
+        .map(str::to_string);
 
     let bytes = match resp.bytes().await {
         Ok(b) => b,
         Err(e) => {
-            warn!("Failed to read Telegram photo bytes: {}", e);
+            warn!("Failed to read Telegram file bytes: {}", e);
             return None;
         }
     };
 
-    if bytes.len() > MAX_IMAGE_SIZE {
-        warn!("Telegram photo too large: {} bytes", bytes.len());
+    if bytes.len() > max_bytes {
+        warn!("Telegram file too large: {} bytes", bytes.len());
         return None;
     }
+    Some((bytes.to_vec(), content_type))
+}
+
+/// Downloads a photo from Telegram's file API and returns it as a [`MediaAttachment`].
+///
+/// Returns `None` on any failure; see [`download_telegram_file`].
+async fn download_telegram_photo(
+    bot: &teloxide::Bot,
+    file_id: teloxide::types::FileId,
+    http_client: &reqwest::Client,
+) -> Option<MediaAttachment> {
+    use crate::session::media::MAX_IMAGE_SIZE;
+
+    let (bytes, content_type) =
+        download_telegram_file(bot, file_id, http_client, MAX_IMAGE_SIZE).await?;
+    let mime_type = content_type
+        .filter(|ct| ct.starts_with("image/"))
+        .unwrap_or_else(|| "image/jpeg".to_string());
     Some(
         MediaAttachment::new(MediaType::Image)
-            .with_data(bytes.to_vec())
+            .with_data(bytes)
             .with_mime_type(&mime_type),
     )
 }
 
+/// Downloads a voice note or audio file and returns it as an audio
+/// [`MediaAttachment`]. `mime_type` comes from the Telegram message, since
+/// the file API serves everything as `application/octet-stream`.
+async fn download_telegram_audio(
+    bot: &teloxide::Bot,
+    file_id: teloxide::types::FileId,
+    mime_type: &str,
+    filename: &str,
+    http_client: &reqwest::Client,
+) -> Option<MediaAttachment> {
+    let (bytes, _) = download_telegram_file(bot, file_id, http_client, MAX_VOICE_SIZE).await?;
+    Some(
+        MediaAttachment::new(MediaType::Audio)
+            .with_data(bytes)
+            .with_mime_type(mime_type)
+            .with_filename(filename),
+    )
+}
+
 /// Telegram channel implementation using teloxide.
 ///
 /// This channel connects to Telegram's Bot API to receive and send messages.
@@ -462,9 +508,69 @@ pub struct TelegramChannel {
     typing_generation: Arc<std::sync::atomic::AtomicU64>,
     /// Shared HTTP client for downloading media (connection pool reuse).
     http_client: reqwest::Client,
+    /// Transcribes voice notes and audio files; `None` leaves them as
+    /// `[Voice Message]`.
+    transcriber: Option<Arc<TranscriberService>>,
+    /// Message showing each streamed reply in progress, keyed by
+    /// "chat_id:stream_id".
+    streams: Arc<DashMap<String, i32>>,
 }
 
 impl TelegramChannel {
+    /// Show the text generated so far for a streamed reply: the first update
+    /// sends a message, later ones edit it. Updates are plain text, since
+    /// half-written markdown does not render; the final reply is formatted.
+    async fn show_stream_update(
+        &self,
+        bot: &teloxide::Bot,
+        chat_id: i64,
+        thread_id: Option<i32>,
+        reply_msg_id: Option<i32>,
+        stream_key: &str,
+        text: &str,
+    ) -> Result<()> {
+        use teloxide::prelude::*;
+        use teloxide::types::{ChatId, MessageId, ReplyParameters};
+
+        // Only the start of a reply too long for one message is previewed.
+        let mut preview = chunk_message(text.trim_end(), TELEGRAM_MAX_MESSAGE_LEN - 1)
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        if preview.is_empty() {
+            return Ok(());
+        }
+        preview.push('…');
+
+        let existing = self.streams.get(stream_key).map(|entry| *entry);
+        match existing {
+            Some(message_id) => {
+                if let Err(e) = bot
+                    .edit_message_text(ChatId(chat_id), MessageId(message_id), preview)
+                    .await
+                {
+                    debug!("Failed to update streamed reply: {}", e);
+                }
+            }
+            None => {
+                let mut req = bot.send_message(ChatId(chat_id), preview);
+                if let Some(tid) = thread_id {
+                    req = req.message_thread_id(teloxide::types::ThreadId(MessageId(tid)));
+                }
+                if let Some(id) = reply_msg_id {
+                    req = req.reply_parameters(
+                        ReplyParameters::new(MessageId(id)).allow_sending_without_reply(),
+                    );
+                }
+                let sent = req.await.map_err(|e| {
+                    ZeptoError::Channel(format!("Failed to send streamed reply: {}", e))
+                })?;
+                self.streams.insert(stream_key.to_string(), sent.id.0);
+            }
+        }
+        Ok(())
+    }
+
     /// Creates a new Telegram channel with the given configuration.
     ///
     /// # Arguments
@@ -552,9 +658,17 @@ impl TelegramChannel {
                 .timeout(Duration::from_secs(30))
                 .build()
                 .expect("Failed to build HTTP client"),
+            transcriber: None,
+            streams: Arc::new(DashMap::new()),
         }
     }
 
+    /// Transcribe inbound voice notes and audio files with `transcriber`.
+    pub fn with_transcriber(mut self, transcriber: Option<TranscriberService>) -> Self {
+        self.transcriber = transcriber.map(Arc::new);
+        self
+    }
+
     /// Returns a reference to the Telegram configuration.
     pub fn telegram_config(&self) -> &TelegramConfig {
         &self.config
@@ -651,7 +765,10 @@ impl Channel for TelegramChannel {
         };
         let longterm_memory = self.longterm_memory.clone();
         let reactions_enabled = ReactionsEnabled(self.config.reactions);
-        let http_client = self.http_client.clone();
+        let media_dep = MediaDep {
+            http_client: self.http_client.clone(),
+            transcriber: self.transcriber.clone(),
+        };
         // Share the same running flag with the spawned task so state stays in sync
         let running_clone = Arc::clone(&self.running);
 
@@ -744,7 +861,9 @@ impl Channel for TelegramChannel {
                          DefaultModel(default_model): DefaultModel,
                          configured_providers_dep: ConfiguredProviders,
                          longterm_memory: Option<Arc<Mutex<LongTermMemory>>>,
-                         http_client: reqwest::Client| async move {
+                         media_dep: MediaDep| async move {
+                            let http_client = media_dep.http_client;
+                            let transcriber = media_dep.transcriber;
                             let model_overrides = overrides_dep.model;
                             let persona_overrides = overrides_dep.persona;
                             let typing_indicators = overrides_dep.typing;
@@ -850,17 +969,20 @@ impl Channel for TelegramChannel {
                                 });
                             }
 
-                            // Process text messages, captions, and bare photo/image messages
+                            // Process text messages, captions, bare photo/image messages
+                            // and voice notes / audio files
                             let has_photo = msg.photo().is_some();
                             let has_image_doc = msg.document()
                                 .and_then(|d| d.mime_type.as_ref())
                                 .map(|m| m.as_ref().starts_with("image/"))
                                 .unwrap_or(false);
                             let has_image = has_photo || has_image_doc;
+                            let has_voice = msg.voice().is_some() || msg.audio().is_some();
 
                             if let Some(text) = msg.text()
                                 .or_else(|| msg.caption())
                                 .or(if has_image { Some(BARE_PHOTO_PLACEHOLDER) } else { None })
+                                .or(if has_voice { Some(VOICE_MESSAGE_PLACEHOLDER) } else { None })
                             {
                                 let chat_id = msg.chat.id.0.to_string();
                                 let chat_id_num = msg.chat.id.0;
@@ -1163,6 +1285,33 @@ impl Channel for TelegramChannel {
                                     }
                                 }
 
+                                // Download voice notes / audio files and transcribe them
+                                let voice_file = msg
+                                    .voice()
+                                    .map(|v| (v.file.id.clone(), v.mime_type.as_ref().map(|m| m.to_string()), "voice.ogg".to_string()))
+                                    .or_else(|| msg.audio().map(|a| (
+                                        a.file.id.clone(),
+                                        a.mime_type.as_ref().map(|m| m.to_string()),
+                                        a.file_name.clone().unwrap_or_else(|| "audio.mp3".to_string()),
+                                    )));
+                                if let Some((file_id, mime, filename)) = voice_file {
+                                    let mime = mime.unwrap_or_else(|| "audio/ogg".to_string());
+                                    if let Some(media) = download_telegram_audio(&bot, file_id, &mime, &filename, &http_client).await {
+                                        if let (Some(svc), Some(data)) = (transcriber.as_ref(), media.data.clone()) {
+                                            let transcript = svc.transcribe(data, &mime).await;
+                                            if transcript != VOICE_MESSAGE_PLACEHOLDER {
+                                                let voice = format!("[Voice: {}]", transcript);
+                                                inbound.content = if text == VOICE_MESSAGE_PLACEHOLDER {
+                                                    voice
+                                                } else {
+                                                    format!("{}\n\n{}", text, voice)
+                                                };
+                                            }
+                                        }
+                                        inbound = inbound.with_media(media);
+                                    }
+                                }
+
                                 if !image_ok && has_image {
                                     let req = bot.send_message(
                                         teloxide::types::ChatId(chat_id_num),
@@ -1208,7 +1357,7 @@ impl Channel for TelegramChannel {
                         default_model,
                         configured_providers,
                         longterm_memory,
-                        http_client
+                        media_dep
                     ])
                     .build();
 
@@ -1330,7 +1479,51 @@ impl Channel for TelegramChannel {
             .or(msg.metadata.get("telegram_message_id").map(|s| s.as_str()))
             .and_then(|s| s.parse().ok());
 
+        // A streamed reply is shown in one message that each update edits;
+        // the final reply takes its place when it fits in one message.
+        let mut edited_in_place = false;
+        if let Some(stream_id) = msg.stream_id() {
+            let stream_key = format!("{}:{}", chat_id, stream_id);
+            if msg.is_stream_partial() {
+                return self
+                    .show_stream_update(
+                        bot,
+                        chat_id,
+                        thread_id,
+                        reply_msg_id,
+                        &stream_key,
+                        &msg.content,
+                    )
+                    .await;
+            }
+            if let Some((_, preview_id)) = self.streams.remove(&stream_key) {
+                if chunks.len() == 1 {
+                    match bot
+                        .edit_message_text(
+                            ChatId(chat_id),
+                            MessageId(preview_id),
+                            chunks[0].clone(),
+                        )
+                        .parse_mode(ParseMode::Html)
+                        .await
+                    {
+                        Ok(_) => edited_in_place = true,
+                        Err(e) if e.to_string().contains("not modified") => edited_in_place = true,
+                        Err(e) => debug!("Failed to edit streamed reply, sending it anew: {}", e),
+                    }
+                }
+                if !edited_in_place {
+                    let _ = bot
+                        .delete_message(ChatId(chat_id), MessageId(preview_id))
+                        .await;
+                }
+            }
+        }
+
         for (i, chunk) in chunks.iter().enumerate() {
+            if edited_in_place {
+                break;
+            }
             let mut req = bot
                 .send_message(ChatId(chat_id), chunk.clone())
                 .parse_mode(ParseMode::Html);
@@ -1402,6 +1595,10 @@ impl Channel for TelegramChannel {
         self.running.load(Ordering::SeqCst)
     }
 
+    fn supports_edits(&self) -> bool {
+        true
+    }
+
     /// Checks if a user is allowed to use this channel.
     ///
     /// Uses the base configuration's allowlist logic.
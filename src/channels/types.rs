@@ -107,6 +107,16 @@ pub trait Channel: Send + Sync {
     ///
     /// `true` if the user is allowed, `false` otherwise.
     fn is_allowed(&self, user_id: &str) -> bool;
+
+    /// Whether the channel can edit a message it already sent.
+    ///
+    /// Edit-capable channels receive the partial updates of a streamed reply
+    /// (see [`OutboundMessage::with_stream`]) and should show each one in
+    /// place of the last, then the final message in their stead. Other
+    /// channels only ever receive the final message. Defaults to `false`.
+    fn supports_edits(&self) -> bool {
+        false
+    }
 }
 
 /// Base configuration shared by all channels.
@@ -155,7 +155,7 @@ pub(crate) async fn cmd_agent(
     // Enable dry-run mode if requested
     if dry_run {
         agent.set_dry_run(true);
-        eprintln!("[DRY RUN] Tool execution disabled — showing what would happen");
+        eprintln!("[DRY RUN] Tools that change state will describe what they would do");
     }
 
     // Set up tool execution feedback (shows progress on stderr)
@@ -293,7 +293,7 @@ pub(crate) async fn cmd_agent(
                                 eprintln!("{}", format_cli_error(&e));
                                 std::process::exit(1);
                             }
-                            StreamEvent::ToolCalls(_) => {}
+                            StreamEvent::ToolCalls(_) | StreamEvent::Thinking(_) => {}
                         }
                     }
                     println!(); // newline after streaming
@@ -660,7 +660,7 @@ pub(crate) async fn cmd_agent(
                                 StreamEvent::Error(e) => {
                                     eprintln!("{}", format_cli_error(&e));
                                 }
-                                StreamEvent::ToolCalls(_) => {}
+                                StreamEvent::ToolCalls(_) | StreamEvent::Thinking(_) => {}
                             }
                         }
                         println!();
@@ -19,7 +19,12 @@ use zeptoclaw::session::SessionManager;
 use zeptoclaw::skills::SkillsLoader;
 use zeptoclaw::tools::approval::ApprovalPolicyConfig;
 use zeptoclaw::tools::delegate::DelegateTool;
+use zeptoclaw::tools::send_message::SendMessageTool;
+use zeptoclaw::tools::session_memory::SessionMemoryTool;
 use zeptoclaw::tools::spawn::SpawnTool;
+use zeptoclaw::tools::spawn_agent::SpawnAgentTool;
+use zeptoclaw::tools::todo::TodoTool;
+use zeptoclaw::tools::transcript::TranscriptFormatterTool;
 
 /// Read a line from stdin, trimming whitespace.
 pub(crate) fn read_line() -> Result<String> {
@@ -343,6 +348,41 @@ pub(crate) async fn create_agent_with_template(
             .await;
     }
 
+    // The memory tool must share the agent's session manager so notes written
+    // mid-turn are not overwritten when the turn's session is saved.
+    if filter.is_enabled("memory") {
+        agent
+            .register_tool(Box::new(SessionMemoryTool::new(
+                Arc::clone(agent.session_manager()),
+                config.session.max_notes,
+            )))
+            .await;
+    }
+    if filter.is_enabled("todo") {
+        agent
+            .register_tool(Box::new(TodoTool::new(Arc::clone(agent.session_manager()))))
+            .await;
+    }
+    if filter.is_enabled("transcript") {
+        agent
+            .register_tool(Box::new(TranscriptFormatterTool::new(Arc::clone(
+                agent.session_manager(),
+            ))))
+            .await;
+    }
+
+    // Register send_message tool (needs the bus and the session manager so
+    // sent messages land in the destination chat's history).
+    if config.tools.send_message.enabled && filter.is_enabled("send_message") {
+        agent
+            .register_tool(Box::new(SendMessageTool::new(
+                Arc::clone(agent.bus()),
+                Arc::clone(agent.session_manager()),
+                config.tools.send_message.clone(),
+            )))
+            .await;
+    }
+
     // Register Google Workspace tool (deferred from kernel registrar because it
     // needs async OAuth token resolution).
     #[cfg(feature = "google")]
@@ -454,6 +494,29 @@ pub(crate) async fn create_agent_with_template(
             warn!("Swarm enabled but no provider configured — delegate tool not registered");
         }
     }
+    #[cfg(feature = "tool-vision")]
+    if filter.is_enabled("analyze_image") {
+        if let Some(provider) = agent.provider().await {
+            let mut tool =
+                zeptoclaw::tools::AnalyzeImageTool::new(provider, config.tools.vision.clone());
+            if let Some(dir) = agent.session_manager().sessions_dir() {
+                tool = tool.with_media_root(dir);
+            }
+            agent.register_tool(Box::new(tool)).await;
+            info!("Registered analyze_image tool");
+        }
+    }
+    if filter.is_enabled("spawn_agent") && config.swarm.enabled && config.swarm.max_depth > 0 {
+        if let Some(provider) = agent.provider().await {
+            agent
+                .register_tool(Box::new(SpawnAgentTool::new(
+                    config.clone(),
+                    provider,
+                    agent.bus().clone(),
+                )))
+                .await;
+        }
+    }
 
     Ok(agent)
 }
@@ -50,6 +50,7 @@ pub(crate) async fn cmd_gateway(
                     "shell".to_string(),
                     "write_file".to_string(),
                     "edit_file".to_string(),
+                    "apply_patch".to_string(),
                 ];
             }
             Ok(false) => {}
@@ -293,6 +294,17 @@ pub(crate) async fn cmd_gateway(
     let mut agent = if !containerized {
         let agent = create_agent(config.clone(), bus.clone()).await?;
         agent.set_usage_metrics(Arc::clone(&metrics)).await;
+        if config
+            .session
+            .channel_defaults
+            .values()
+            .any(|o| o.ttl_secs.is_some())
+        {
+            agent.session_manager().start_expiry_task(
+                std::time::Duration::from_secs(zeptoclaw::session::SESSION_EXPIRY_INTERVAL_SECS),
+                usage_shutdown_tx.subscribe(),
+            );
+        }
         Some(agent)
     } else {
         None
@@ -440,9 +452,11 @@ pub(crate) async fn cmd_gateway(
         ConfigWatcher::default_path(Duration::from_secs(30)).watch(reload_tx, reload_shutdown_rx),
     );
 
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
     loop {
         tokio::select! {
-            _ = tokio::signal::ctrl_c() => {
+            _ = &mut shutdown => {
                 break;
             }
             maybe_cfg = reload_rx.recv() => {
@@ -466,8 +480,11 @@ pub(crate) async fn cmd_gateway(
                 // Rebuild in-process agent to apply provider + safety changes.
                 if !containerized {
                     if let Some(ref running_agent) = agent {
-                        running_agent.stop();
-                        running_agent.shutdown_mcp_clients().await;
+                        running_agent
+                            .shutdown(Duration::from_secs(
+                                old_config.agents.defaults.shutdown_timeout_secs,
+                            ))
+                            .await;
                     }
                     if let Some(handle) = agent_handle.take() {
                         let _ = tokio::time::timeout(Duration::from_secs(5), handle).await;
@@ -542,10 +559,14 @@ pub(crate) async fn cmd_gateway(
         service.stop().await;
     }
 
-    // Stop agent or proxy
+    // Stop agent or proxy. Turns under way get to finish and send their
+    // replies before the channels stop.
     if let Some(ref agent) = agent {
-        agent.stop();
-        agent.shutdown_mcp_clients().await;
+        agent
+            .shutdown(Duration::from_secs(
+                config.agents.defaults.shutdown_timeout_secs,
+            ))
+            .await;
     }
     if let Some(ref proxy) = proxy {
         proxy.stop();
@@ -578,6 +599,26 @@ pub(crate) async fn cmd_gateway(
     Ok(())
 }
 
+/// Resolves on Ctrl+C, or on SIGTERM (sent by `docker stop` and systemd) on
+/// Unix.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => info!("Received SIGTERM"),
+                }
+                return;
+            }
+            Err(e) => warn!("Cannot listen for SIGTERM: {}", e),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
 /// Validate that Docker is available.
 async fn validate_docker_available(docker_binary: &str) -> Result<()> {
     if !zeptoclaw::gateway::is_docker_available_with_binary(docker_binary).await {
@@ -22,23 +22,31 @@ pub(crate) async fn cmd_history(action: HistoryAction) -> Result<()> {
             let shown = entries.len().min(limit);
             println!("Showing {} of {} conversation(s):", shown, entries.len());
             for entry in entries.iter().take(limit) {
+                let pin = if entry.pinned { " [pinned]" } else { "" };
                 println!(
-                    "- {} | {} msgs | {} | {}",
-                    entry.session_key, entry.message_count, entry.last_updated, entry.title
+                    "- {}{} | {} msgs | {} | {}",
+                    entry.session_key, pin, entry.message_count, entry.last_updated, entry.title
                 );
             }
         }
         HistoryAction::Show { query } => {
-            let Some(entry) = history.find_conversation(&query)? else {
-                anyhow::bail!("No conversation found for query '{}'", query);
-            };
-
             let manager = SessionManager::new().with_context(|| "Failed to open session store")?;
-            let Some(session) = manager.get(&entry.session_key).await? else {
-                anyhow::bail!(
-                    "Conversation '{}' exists in index but could not be loaded",
-                    entry.session_key
-                );
+            // Any session can be shown by exact key (e.g. a `spawn_agent:` transcript);
+            // titles only match CLI conversations.
+            let session = match manager.get(&query).await? {
+                Some(session) => session,
+                None => {
+                    let Some(entry) = history.find_conversation(&query)? else {
+                        anyhow::bail!("No conversation found for query '{}'", query);
+                    };
+                    let Some(session) = manager.get(&entry.session_key).await? else {
+                        anyhow::bail!(
+                            "Conversation '{}' exists in index but could not be loaded",
+                            entry.session_key
+                        );
+                    };
+                    session
+                }
             };
 
             println!("Session: {}", session.key);
@@ -50,7 +58,10 @@ pub(crate) async fn cmd_history(action: HistoryAction) -> Result<()> {
             println!();
 
             for message in session.messages {
-                println!("[{}]", role_label(&message.role));
+                match message.agent {
+                    Some(ref agent) => println!("[{} ({})]", role_label(&message.role), agent),
+                    None => println!("[{}]", role_label(&message.role)),
+                }
                 println!("{}", message.content);
                 println!();
             }
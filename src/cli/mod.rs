@@ -71,7 +71,7 @@ enum Commands {
         /// Disable streaming (streaming is on by default)
         #[arg(long)]
         no_stream: bool,
-        /// Show what tools would be called without executing them
+        /// Have state-changing tools describe what they would do instead of running
         #[arg(long)]
         dry_run: bool,
         /// Agent mode: observer (read-only), assistant (read/write + approval), autonomous (full access)
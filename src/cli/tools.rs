@@ -50,6 +50,13 @@ const TOOLS: &[ToolInfo] = &[
         config_hint: "",
         opt_in: false,
     },
+    ToolInfo {
+        name: "apply_patch",
+        description: "Apply a multi-file unified diff",
+        requires_config: false,
+        config_hint: "",
+        opt_in: false,
+    },
     ToolInfo {
         name: "shell",
         description: "Execute shell commands (with runtime isolation)",
@@ -92,6 +99,13 @@ const TOOLS: &[ToolInfo] = &[
         config_hint: "",
         opt_in: false,
     },
+    ToolInfo {
+        name: "scratchpad",
+        description: "Key-value scratchpad shared per channel (set/get/list/delete)",
+        requires_config: false,
+        config_hint: "",
+        opt_in: false,
+    },
     ToolInfo {
         name: "message",
         description: "Send proactive messages to channels",
@@ -99,6 +113,13 @@ const TOOLS: &[ToolInfo] = &[
         config_hint: "Configure at least one channel (telegram, slack, discord)",
         opt_in: false,
     },
+    ToolInfo {
+        name: "ask_user",
+        description: "Ask the user a question mid-task and wait for the answer",
+        requires_config: false,
+        config_hint: "",
+        opt_in: false,
+    },
     ToolInfo {
         name: "cron",
         description: "Schedule recurring tasks",
@@ -120,6 +141,13 @@ const TOOLS: &[ToolInfo] = &[
         config_hint: "",
         opt_in: false,
     },
+    ToolInfo {
+        name: "spawn_agent",
+        description: "Run a task in a fresh sub-agent",
+        requires_config: false,
+        config_hint: "",
+        opt_in: false,
+    },
     ToolInfo {
         name: "whatsapp_send",
         description: "Send WhatsApp messages via Cloud API",
@@ -371,7 +399,7 @@ mod tests {
 
     #[test]
     fn test_tools_list_count() {
-        assert_eq!(TOOLS.len(), 23);
+        assert_eq!(TOOLS.len(), 27);
     }
 
     #[test]
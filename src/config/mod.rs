@@ -167,20 +167,78 @@ impl Config {
                 self.agents.defaults.agent_timeout_secs = v;
             }
         }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_SHUTDOWN_TIMEOUT_SECS") {
+            if let Ok(v) = val.parse() {
+                self.agents.defaults.shutdown_timeout_secs = v;
+            }
+        }
         if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_TOOL_TIMEOUT_SECS") {
             if let Ok(v) = val.parse() {
                 self.agents.defaults.tool_timeout_secs = v;
             }
         }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_TURN_TOOL_BUDGET_SECS") {
+            if let Ok(v) = val.parse() {
+                self.agents.defaults.turn_tool_budget_secs = v;
+            }
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_TURN_TIMEOUT_SECS") {
+            if let Ok(v) = val.parse() {
+                self.agents.defaults.turn_timeout_secs = v;
+            }
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_MAX_PARALLEL_TOOLS") {
+            if let Ok(v) = val.parse() {
+                self.agents.defaults.max_parallel_tools = v;
+            }
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_CONCURRENCY_MAX_TURNS") {
+            if let Ok(v) = val.parse() {
+                self.agents.defaults.concurrency.max_turns = v;
+            }
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_CONCURRENCY_MAX_QUEUED") {
+            if let Ok(v) = val.parse() {
+                self.agents.defaults.concurrency.max_queued = v;
+            }
+        }
         if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_TOKEN_BUDGET") {
             if let Ok(v) = val.parse() {
                 self.agents.defaults.token_budget = v;
             }
         }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_STREAM_TO_CHANNELS") {
+            self.agents.defaults.stream_to_channels = val == "true" || val == "1";
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_STREAM_INTERVAL_MS") {
+            if let Ok(v) = val.parse() {
+                self.agents.defaults.stream_interval_ms = v;
+            }
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_PROGRESS_UPDATES") {
+            self.agents.defaults.progress_updates = val == "true" || val == "1";
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_PROGRESS_INTERVAL_MS") {
+            if let Ok(v) = val.parse() {
+                self.agents.defaults.progress_interval_ms = v;
+            }
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_STRICT_COMMANDS") {
+            self.agents.defaults.strict_commands = val == "true" || val == "1";
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_DEDUP_WINDOW") {
+            if let Ok(v) = val.parse() {
+                self.agents.defaults.dedup_window = v;
+            }
+        }
         if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_MESSAGE_QUEUE_MODE") {
             match val.trim().to_ascii_lowercase().as_str() {
                 "collect" => self.agents.defaults.message_queue_mode = MessageQueueMode::Collect,
                 "followup" => self.agents.defaults.message_queue_mode = MessageQueueMode::Followup,
+                "interrupt" => {
+                    self.agents.defaults.message_queue_mode = MessageQueueMode::Interrupt
+                }
+                "steer" => self.agents.defaults.message_queue_mode = MessageQueueMode::Steer,
                 _ => {}
             }
         }
@@ -249,6 +307,11 @@ impl Config {
                 self.agents.defaults.loop_guard.window_size = v;
             }
         }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_LOOP_GUARD_REPEAT_CALL_LIMIT") {
+            if let Ok(v) = val.parse() {
+                self.agents.defaults.loop_guard.repeat_call_limit = v;
+            }
+        }
         if let Ok(val) = std::env::var("ZEPTOCLAW_AGENTS_DEFAULTS_MAX_TOOL_RESULT_BYTES") {
             if let Ok(v) = val.parse() {
                 self.agents.defaults.max_tool_result_bytes = v;
@@ -333,6 +396,7 @@ impl Config {
 
         // Cache
         self.apply_cache_env_overrides();
+        self.apply_cost_env_overrides();
 
         // Agent mode
         if let Ok(val) = std::env::var("ZEPTOCLAW_SECURITY_AGENT_MODE") {
@@ -346,6 +410,34 @@ impl Config {
         if let Ok(val) = std::env::var("ZEPTOCLAW_SESSION_AUTO_REPAIR") {
             self.session.auto_repair = val.eq_ignore_ascii_case("true") || val == "1";
         }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_SESSION_LOCK_TIMEOUT_MS") {
+            if let Ok(ms) = val.parse::<u64>() {
+                self.session.lock_timeout_ms = ms;
+            }
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_SESSION_DEDUP_TOOL_RESULTS") {
+            self.session.dedup_tool_results = val.eq_ignore_ascii_case("true") || val == "1";
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_SESSION_MAX_NOTES") {
+            if let Ok(n) = val.parse::<usize>() {
+                self.session.max_notes = n;
+            }
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_SESSION_ROTATE_MAX_MESSAGES") {
+            if let Ok(n) = val.parse::<usize>() {
+                self.session.rotate_max_messages = n;
+            }
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_SESSION_ROTATE_MAX_AGE_DAYS") {
+            if let Ok(days) = val.parse::<u64>() {
+                self.session.rotate_max_age_days = days;
+            }
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_SESSION_ROTATE_CARRY_OVER_MESSAGES") {
+            if let Ok(n) = val.parse::<usize>() {
+                self.session.rotate_carry_over_messages = n;
+            }
+        }
 
         // Transcription
         if let Ok(val) = std::env::var("ZEPTOCLAW_TRANSCRIPTION_MODEL") {
@@ -391,6 +483,12 @@ impl Config {
                 .get_or_insert_with(ProviderConfig::default);
             provider.api_base = Some(val);
         }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_PROVIDERS_ANTHROPIC_PROMPT_CACHING") {
+            self.providers
+                .anthropic
+                .get_or_insert_with(ProviderConfig::default)
+                .prompt_caching = val == "true" || val == "1";
+        }
 
         // OpenAI
         if let Ok(val) = std::env::var("ZEPTOCLAW_PROVIDERS_OPENAI_API_KEY") {
@@ -521,6 +619,12 @@ impl Config {
                 .get_or_insert_with(ProviderConfig::default);
             provider.api_base = Some(val);
         }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_PROVIDERS_OLLAMA_KEEP_ALIVE") {
+            self.providers
+                .ollama
+                .get_or_insert_with(ProviderConfig::default)
+                .keep_alive = Some(val);
+        }
 
         // Nvidia NIM
         if let Ok(val) = std::env::var("ZEPTOCLAW_PROVIDERS_NVIDIA_API_KEY") {
@@ -615,6 +719,12 @@ impl Config {
                 .get_or_insert_with(ProviderConfig::default)
                 .api_base = Some(v);
         }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_PROVIDERS_BEDROCK_REGION") {
+            self.providers
+                .bedrock
+                .get_or_insert_with(ProviderConfig::default)
+                .region = Some(v);
+        }
 
         // xAI (Grok)
         if let Ok(val) = std::env::var("ZEPTOCLAW_PROVIDERS_XAI_API_KEY") {
@@ -1180,6 +1290,40 @@ impl Config {
             }
         }
 
+        // Human approval tool configuration
+        if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_HUMAN_APPROVAL_TELEGRAM_BOT_TOKEN") {
+            self.tools.human_approval.telegram_bot_token = Some(val);
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_HUMAN_APPROVAL_TELEGRAM_CHAT_ID") {
+            self.tools.human_approval.telegram_chat_id = Some(val);
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_HUMAN_APPROVAL_SLACK_BOT_TOKEN") {
+            self.tools.human_approval.slack_bot_token = Some(val);
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_HUMAN_APPROVAL_SLACK_CHANNEL") {
+            self.tools.human_approval.slack_channel = Some(val);
+        }
+
+        // Container registry tool configuration
+        if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_CONTAINER_REGISTRY_DOCKERHUB_USERNAME") {
+            self.tools.container_registry.dockerhub_username = Some(val);
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_CONTAINER_REGISTRY_DOCKERHUB_TOKEN") {
+            self.tools.container_registry.dockerhub_token = Some(val);
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_CONTAINER_REGISTRY_GHCR_USERNAME") {
+            self.tools.container_registry.ghcr_username = Some(val);
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_CONTAINER_REGISTRY_GHCR_TOKEN") {
+            self.tools.container_registry.ghcr_token = Some(val);
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_CONTAINER_REGISTRY_SNYK_TOKEN") {
+            self.tools.container_registry.snyk_token = Some(val);
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_CONTAINER_REGISTRY_SNYK_ORG_ID") {
+            self.tools.container_registry.snyk_org_id = Some(val);
+        }
+
         // Google Sheets tool configuration
         if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_GOOGLE_SHEETS_ACCESS_TOKEN") {
             self.tools.google_sheets.access_token = Some(val);
@@ -1212,9 +1356,109 @@ impl Config {
         if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_TRANSCRIBE_ENABLED") {
             self.tools.transcribe.enabled = v == "true" || v == "1";
         }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_TRANSCRIBE_BACKEND") {
+            self.tools.transcribe.backend = v;
+        }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_TRANSCRIBE_API_KEY") {
+            self.tools.transcribe.api_key = Some(v);
+        }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_TRANSCRIBE_LANGUAGE") {
+            self.tools.transcribe.language = Some(v);
+        }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_SPEAK_API_KEY") {
+            self.tools.speak.api_key = Some(v);
+        }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_SPEAK_ENABLED") {
+            self.tools.speak.enabled = v == "true" || v == "1";
+        }
         if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_CODING_TOOLS") {
             self.tools.coding_tools = v == "true" || v == "1";
         }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_DRY_RUN") {
+            self.tools.dry_run = v == "true" || v == "1";
+        }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_SHELL_MAX_OUTPUT_BYTES") {
+            if let Ok(n) = v.parse() {
+                self.tools.shell.max_output_bytes = n;
+            }
+        }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_SHELL_DEFAULT_SHELL") {
+            if let Some(shell) = ShellKind::from_name(v.trim()) {
+                self.tools.shell.default_shell = Some(shell);
+            }
+        }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_ASK_USER_TIMEOUT_SECS") {
+            if let Ok(n) = v.parse() {
+                self.tools.ask_user.timeout_secs = n;
+            }
+        }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_PROCESS_MAX_PROCESSES") {
+            if let Ok(n) = v.parse() {
+                self.tools.process.max_processes = n;
+            }
+        }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_WRITE_FILE_MAX_FILE_BYTES") {
+            if let Ok(n) = v.parse() {
+                self.tools.write_file.max_file_bytes = n;
+            }
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_FILESYSTEM_DENIED_PATHS") {
+            self.tools.filesystem.denied_paths = val
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_FILESYSTEM_READ_ONLY_PATHS") {
+            self.tools.filesystem.read_only_paths = val
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_LIST_DIR_IGNORE") {
+            self.tools.list_dir.ignore = val
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_HTTP_FETCH_ENABLED") {
+            self.tools.http_fetch.enabled = v.eq_ignore_ascii_case("true") || v == "1";
+        }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_DOWNLOAD_ENABLED") {
+            self.tools.download.enabled = v.eq_ignore_ascii_case("true") || v == "1";
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_HTTP_FETCH_ALLOWED_HOSTS") {
+            self.tools.http_fetch.allowed_hosts = val
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_TOOLS_HTTP_FETCH_DENIED_HOSTS") {
+            self.tools.http_fetch.denied_hosts = val
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_ARGUMENT_VALIDATION_ENABLED") {
+            self.tools.argument_validation.enabled = v.eq_ignore_ascii_case("true") || v == "1";
+        }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_ARGUMENT_VALIDATION_COERCE") {
+            self.tools.argument_validation.coerce = v.eq_ignore_ascii_case("true") || v == "1";
+        }
+        if let Ok(v) = std::env::var("ZEPTOCLAW_TOOLS_HTTP_FETCH_MAX_RESPONSE_BYTES") {
+            if let Ok(n) = v.parse() {
+                self.tools.http_fetch.max_response_bytes = n;
+            }
+        }
     }
 
     /// Apply memory-specific environment variable overrides.
@@ -1482,6 +1726,11 @@ impl Config {
                 _ => {}
             }
         }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_ROUTINES_CATCH_UP_WINDOW_SECS") {
+            if let Ok(v) = val.parse::<u64>() {
+                self.routines.catch_up_window_secs = v;
+            }
+        }
         if let Ok(v) = std::env::var("ZEPTOCLAW_HEALTH_ENABLED") {
             self.health.enabled = v == "true" || v == "1";
         }
@@ -1530,6 +1779,25 @@ impl Config {
         }
     }
 
+    /// Apply cost tracking environment variable overrides.
+    fn apply_cost_env_overrides(&mut self) {
+        if let Ok(val) = std::env::var("ZEPTOCLAW_COST_SHOW_COST") {
+            self.cost.show_cost = val.eq_ignore_ascii_case("true") || val == "1";
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_COST_MONTHLY_BUDGET_USD") {
+            if let Ok(v) = val.parse::<f64>() {
+                self.cost.monthly_budget_usd = (v > 0.0).then_some(v);
+            }
+        }
+        if let Ok(val) = std::env::var("ZEPTOCLAW_COST_BUDGET_ACTION") {
+            match val.trim().to_ascii_lowercase().as_str() {
+                "warn" => self.cost.budget_action = crate::utils::cost::BudgetAction::Warn,
+                "refuse" => self.cost.budget_action = crate::utils::cost::BudgetAction::Refuse,
+                _ => {}
+            }
+        }
+    }
+
     /// Apply device pairing environment variable overrides.
     fn apply_pairing_env_overrides(&mut self) {
         if let Ok(val) = std::env::var("ZEPTOCLAW_SECURITY_PAIRING_ENABLED") {
@@ -2267,6 +2535,8 @@ mod tests {
     fn test_agent_timeout_default() {
         let config = Config::default();
         assert_eq!(config.agents.defaults.agent_timeout_secs, 300);
+        assert_eq!(config.agents.defaults.shutdown_timeout_secs, 30);
+        assert_eq!(config.agents.defaults.dedup_window, 100);
     }
 
     #[test]
@@ -2285,6 +2555,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_message_queue_mode_interrupt_from_json() {
+        let json = r#"{"agents": {"defaults": {"message_queue_mode": "interrupt"}}}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            config.agents.defaults.message_queue_mode,
+            MessageQueueMode::Interrupt
+        );
+    }
+
+    #[test]
+    fn test_message_queue_mode_steer_per_channel_from_json() {
+        let json = r#"{"agents": {"defaults": {
+            "message_queue_mode": "steer",
+            "message_queue_modes": {"telegram": "collect"}
+        }}}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            config.agents.defaults.message_queue_mode,
+            MessageQueueMode::Steer
+        );
+        assert_eq!(
+            config.agents.defaults.message_queue_modes["telegram"],
+            MessageQueueMode::Collect
+        );
+    }
+
     #[test]
     fn test_message_queue_mode_from_json() {
         let json = r#"{"agents": {"defaults": {"message_queue_mode": "followup"}}}"#;
@@ -2385,6 +2682,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_env_override_cost_budget() {
+        std::env::set_var("ZEPTOCLAW_COST_MONTHLY_BUDGET_USD", "25");
+        std::env::set_var("ZEPTOCLAW_COST_BUDGET_ACTION", "refuse");
+        let mut config = Config::default();
+        config.apply_env_overrides();
+        assert_eq!(config.cost.monthly_budget_usd, Some(25.0));
+        assert_eq!(
+            config.cost.budget_action,
+            crate::utils::cost::BudgetAction::Refuse
+        );
+        std::env::remove_var("ZEPTOCLAW_COST_MONTHLY_BUDGET_USD");
+        std::env::remove_var("ZEPTOCLAW_COST_BUDGET_ACTION");
+    }
+
     #[test]
     fn test_env_override_anthropic_quota_max_cost() {
         std::env::set_var("ZEPTOCLAW_PROVIDERS_ANTHROPIC_QUOTA_MAX_COST_USD", "5.5");
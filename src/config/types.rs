@@ -321,9 +321,12 @@ impl Default for R8rBridgeConfig {
 
 /// LLM response cache configuration.
 ///
-/// When enabled, caches LLM responses keyed by SHA-256 of
-/// `(model, system_prompt, user_prompt)`. Supports TTL expiry and LRU eviction.
-/// Persists to `~/.zeptoclaw/cache/responses.json`.
+/// When enabled, the agent loop answers a request identical to an earlier
+/// one (same model, messages and tool schemas, keyed by SHA-256) from the
+/// cache instead of the provider. Only replies that called no tools are
+/// stored, and inbound messages with `no_cache` set are never answered from
+/// it. Supports TTL expiry and LRU eviction. Persists to
+/// `~/.zeptoclaw/cache/responses.json`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct CacheConfig {
@@ -381,14 +384,77 @@ impl Default for PairingConfig {
 pub struct SessionConfig {
     /// Automatically repair malformed conversation histories when loaded.
     pub auto_repair: bool,
+    /// Maximum time (in milliseconds) to wait for the cross-process sessions
+    /// directory lock before failing with `ZeptoError::Locked`.
+    pub lock_timeout_ms: u64,
+    /// Archive a session and start a fresh one under the same key once it
+    /// holds this many messages (0 = disabled).
+    pub rotate_max_messages: usize,
+    /// Archive a session once it is this many days old (0 = disabled).
+    pub rotate_max_age_days: u64,
+    /// Number of trailing messages carried into the fresh session on rotation.
+    /// Capped at one below `rotate_max_messages`.
+    pub rotate_carry_over_messages: usize,
+    /// Carry the old session's summary into the fresh session on rotation.
+    pub rotate_carry_over_summary: bool,
+    /// Replace a tool result identical to the previous result of the same
+    /// tool with a short marker (exact matches only).
+    pub dedup_tool_results: bool,
+    /// Maximum memory notes kept per session; the oldest are evicted first.
+    pub max_notes: usize,
+    /// Defaults for newly created sessions, keyed by session-key namespace
+    /// (the part before the first `:`, e.g. `telegram` or `cli`).
+    pub channel_defaults: HashMap<String, SessionOptions>,
 }
 
 impl Default for SessionConfig {
     fn default() -> Self {
-        Self { auto_repair: true }
+        Self {
+            auto_repair: true,
+            lock_timeout_ms: 5_000,
+            rotate_max_messages: 0,
+            rotate_max_age_days: 0,
+            rotate_carry_over_messages: 0,
+            rotate_carry_over_summary: true,
+            dedup_tool_results: false,
+            max_notes: 50,
+            channel_defaults: HashMap::new(),
+        }
     }
 }
 
+/// How a session's history is shortened once it exceeds `max_messages`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrimStrategy {
+    /// Drop the oldest messages.
+    #[default]
+    DropOldest,
+    /// Keep the opening user message and drop the oldest messages after it.
+    KeepFirst,
+}
+
+/// Options applied to a session when it is first created.
+///
+/// They are stored on the session, so later config changes only affect new
+/// sessions.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionOptions {
+    /// Delete the session after this many seconds without activity.
+    /// Pinned sessions are never expired.
+    pub ttl_secs: Option<u64>,
+    /// Trim the history to at most this many messages before each turn.
+    pub max_messages: Option<usize>,
+    /// Which messages to drop when trimming.
+    pub trim_strategy: TrimStrategy,
+    /// Pin new sessions so they survive cleanup.
+    pub pinned_by_default: bool,
+    /// Tool iterations allowed per turn, overriding
+    /// `agents.defaults.max_tool_iterations`.
+    pub max_tool_iterations: Option<u32>,
+}
+
 // ============================================================================
 // Health Server Configuration
 // ============================================================================
@@ -458,6 +524,20 @@ pub struct CompactionConfig {
     /// Maximum overflow retries before giving up. Default: 3.
     #[serde(default = "default_overflow_retries")]
     pub overflow_retries: u32,
+    /// Context window in tokens per model name, for models whose window
+    /// differs from `context_limit`.
+    pub model_context_limits: HashMap<String, usize>,
+}
+
+impl CompactionConfig {
+    /// Context window of `model`: its `model_context_limits` entry, else
+    /// `context_limit`.
+    pub fn context_limit_for(&self, model: &str) -> usize {
+        self.model_context_limits
+            .get(model)
+            .copied()
+            .unwrap_or(self.context_limit)
+    }
 }
 
 fn default_input_headroom_ratio() -> f64 {
@@ -485,6 +565,7 @@ impl Default for CompactionConfig {
             single_tool_result_share: default_single_tool_result_share(),
             safety_margin: default_safety_margin(),
             overflow_retries: default_overflow_retries(),
+            model_context_limits: HashMap::new(),
         }
     }
 }
@@ -504,11 +585,13 @@ pub struct McpConfig {
 /// Configuration for a single MCP server.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpServerConfig {
-    /// Human-readable server name (used as tool name prefix).
+    /// Human-readable server name; its tools are exposed as
+    /// `mcp_<name>_<tool>`.
     pub name: String,
     /// Server URL endpoint (for HTTP transport).
     pub url: Option<String>,
-    /// Server command (for stdio transport).
+    /// Server command (for stdio transport). The process is respawned, with
+    /// exponential backoff, if it exits.
     pub command: Option<String>,
     /// Server command arguments (for stdio transport).
     pub args: Option<Vec<String>>,
@@ -543,6 +626,10 @@ pub struct RoutinesConfig {
     /// Policy for missed schedules when process restarts.
     #[serde(default)]
     pub on_miss: crate::cron::OnMiss,
+    /// With `on_miss = "run_once"`, only catch up on runs missed within
+    /// this many seconds (0 = no limit).
+    #[serde(default)]
+    pub catch_up_window_secs: u64,
 }
 
 impl Default for RoutinesConfig {
@@ -553,6 +640,7 @@ impl Default for RoutinesConfig {
             max_concurrent: 3,
             jitter_ms: 0,
             on_miss: crate::cron::OnMiss::Skip,
+            catch_up_window_secs: 0,
         }
     }
 }
@@ -668,6 +756,385 @@ impl Default for TranscriptionConfig {
 pub struct AgentConfig {
     /// Default agent settings
     pub defaults: AgentDefaults,
+    /// Named agent profiles a conversation can be routed to (e.g. `coding`,
+    /// `support`).
+    pub profiles: HashMap<String, AgentProfile>,
+    /// Profile by session namespace, the part of the session key before the
+    /// first `:` (e.g. `"slack": "support"`).
+    pub routes: HashMap<String, String>,
+    /// Profile for conversations that no route or `/agent` command selects.
+    /// `None` (the default) answers them without a profile.
+    pub default_profile: Option<String>,
+    /// Generation settings by channel name (e.g. a cheaper model for
+    /// `"telegram"`), over `defaults`.
+    pub channel_settings: HashMap<String, GenerationSettings>,
+    /// Replies sent to the chat when a turn fails.
+    pub error_replies: ErrorRepliesConfig,
+    /// Heartbeats by session key (e.g. `"telegram:12345"`) or by namespace
+    /// (e.g. `"telegram"`, for each of its sessions without its own entry).
+    pub heartbeats: HashMap<String, HeartbeatSchedule>,
+    /// Reply constraints by session key (e.g. `"sms:+15550100"`) or channel
+    /// name (e.g. `"sms"`), the session's entry first.
+    pub response_options: HashMap<String, ResponseOptions>,
+    /// Welcome sent on the first message of a new conversation, by channel
+    /// name. Channels without an entry send none.
+    pub onboarding: HashMap<String, OnboardingConfig>,
+    /// Who may talk to the agent on each channel, and with which role.
+    pub authorization: AuthorizationConfig,
+}
+
+/// Senders allowed to talk to the agent, by channel, and what their roles
+/// may do.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthorizationConfig {
+    /// Rules by channel name (e.g. `"telegram"`). Channels without an entry
+    /// let everyone in with full access.
+    pub channels: HashMap<String, ChannelAuthorization>,
+    /// Permissions by role, replacing the role's defaults.
+    pub roles: HashMap<UserRole, RolePermissions>,
+    /// Reply sent to senders who aren't allowed. Empty drops their messages
+    /// without a reply.
+    pub rejection_message: String,
+}
+
+impl Default for AuthorizationConfig {
+    fn default() -> Self {
+        Self {
+            channels: HashMap::new(),
+            roles: HashMap::new(),
+            rejection_message: "Sorry, you're not allowed to use this bot.".to_string(),
+        }
+    }
+}
+
+/// The senders allowed on one channel, with their roles.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelAuthorization {
+    /// Role by sender ID, as the channel reports it.
+    pub users: HashMap<String, UserRole>,
+    /// Role by chat ID, for everyone in the chat without an entry in
+    /// `users`.
+    pub chats: HashMap<String, UserRole>,
+    /// Role for everyone else. `None` (the default) turns them away.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_role: Option<UserRole>,
+}
+
+/// What a sender may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserRole {
+    /// Everything, including the commands that change the model or tools
+    /// and resetting other conversations.
+    Admin,
+    /// Every tool and every command but the admin ones.
+    User,
+    /// No tools, and only the commands that show things.
+    Readonly,
+}
+
+/// The tools and chat commands a role may use.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RolePermissions {
+    /// Only these tools. `None` allows every tool.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<String>>,
+    /// Only these chat commands (e.g. `"/help"`). `None` allows every
+    /// command; admin commands stay admin-only either way.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commands: Option<Vec<String>>,
+}
+
+/// Welcome for a channel's new conversations.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OnboardingConfig {
+    /// The welcome. `{commands}` is replaced with the chat commands as
+    /// `/help` lists them and `{tools}` with the tools the conversation can
+    /// use.
+    pub message: String,
+    /// Whether the welcome comes before the answer to the first message or
+    /// instead of it.
+    pub mode: OnboardingMode,
+}
+
+impl Default for OnboardingConfig {
+    fn default() -> Self {
+        Self {
+            message: "Hi! Ask me anything.\n\nCommands:\n{commands}\n\nTools I can use: {tools}"
+                .to_string(),
+            mode: OnboardingMode::default(),
+        }
+    }
+}
+
+/// Where a welcome goes relative to the answer to the first message.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnboardingMode {
+    /// The welcome, then the answer, in one reply.
+    #[default]
+    Before,
+    /// Only the welcome; the first message is not answered.
+    Instead,
+}
+
+/// Constraints on the replies sent to a channel, each optional.
+///
+/// Stop sequences are passed to the provider; the rest is applied to the
+/// final reply before it is sent, in order: `strip_patterns`,
+/// `strip_markdown`, `max_length`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResponseOptions {
+    /// Sequences that end generation when the model produces them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    /// Longest reply in characters, cut at a sentence end when possible.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// Turn Markdown into plain text, for channels that show it raw.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strip_markdown: Option<bool>,
+    /// Regular expressions whose matches are removed, e.g.
+    /// `"(?s)<thinking>.*?</thinking>"` for leaked reasoning.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strip_patterns: Option<Vec<String>>,
+}
+
+impl ResponseOptions {
+    /// These options, with the ones not set taken from `fallback`.
+    pub fn or(self, fallback: &ResponseOptions) -> Self {
+        Self {
+            stop: self.stop.or_else(|| fallback.stop.clone()),
+            max_length: self.max_length.or(fallback.max_length),
+            strip_markdown: self.strip_markdown.or(fallback.strip_markdown),
+            strip_patterns: self
+                .strip_patterns
+                .or_else(|| fallback.strip_patterns.clone()),
+        }
+    }
+}
+
+/// A standing instruction the agent follows up on a schedule, without a
+/// user message.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeartbeatSchedule {
+    /// Seconds between heartbeats, at least 30. 0 (the default) turns the
+    /// heartbeat off.
+    pub interval_secs: u64,
+    /// What the agent is asked each time, e.g. "Check ~/inbox for new
+    /// files and summarize them."
+    pub prompt: String,
+}
+
+/// Reply templates for failed turns, by kind of failure. `{ref}` in a
+/// template becomes the error's reference ID, which is also logged with the
+/// full error. Unset kinds use the built-in reply.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ErrorRepliesConfig {
+    /// The provider refused the API key, account or model.
+    pub provider_auth: Option<String>,
+    /// The provider is rate limiting, or a usage limit was reached.
+    pub rate_limit: Option<String>,
+    /// The provider is overloaded, failing or not responding.
+    pub provider_unavailable: Option<String>,
+    /// The conversation no longer fits the model's context window.
+    pub context_too_long: Option<String>,
+    /// A tool or MCP server failed.
+    pub tool_failure: Option<String>,
+    /// Anything else.
+    pub internal: Option<String>,
+}
+
+/// Model and sampling settings for provider calls, each optional.
+///
+/// A turn uses, for each setting, the first one set in: the message's own
+/// override, the conversation (`/model`, `/temp`, `/think`, `/settings`),
+/// the agent profile (model and thinking budget only),
+/// `agents.channel_settings` and `agents.defaults`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerationSettings {
+    /// Model to call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Sampling temperature, 0.0 to 2.0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Maximum tokens in a response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Nucleus sampling threshold, above 0.0 and at most 1.0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// Tokens the model may spend on extended thinking before answering,
+    /// on models that support it. 0 turns thinking off.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking_budget: Option<u32>,
+}
+
+impl GenerationSettings {
+    /// Whether no setting is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// These settings, with the ones not set taken from `fallback`.
+    pub fn or(self, fallback: &GenerationSettings) -> Self {
+        Self {
+            model: self.model.or_else(|| fallback.model.clone()),
+            temperature: self.temperature.or(fallback.temperature),
+            max_tokens: self.max_tokens.or(fallback.max_tokens),
+            top_p: self.top_p.or(fallback.top_p),
+            thinking_budget: self.thinking_budget.or(fallback.thinking_budget),
+        }
+    }
+}
+
+/// A persona the agent can answer as: its own instructions, tools and model.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgentProfile {
+    /// Instructions added to the system prompt while this profile answers.
+    pub system_prompt: String,
+    /// Only these tools are offered, within what `tools.access` allows.
+    /// `None` (the default) keeps every available tool.
+    pub tools: Option<Vec<String>>,
+    /// Model used instead of `agents.defaults.model`. A per-message model
+    /// override still wins.
+    pub model: Option<String>,
+    /// Extended thinking budget for this profile's turns, unless the
+    /// conversation sets its own with `/think`.
+    pub thinking_budget: Option<u32>,
+}
+
+/// Workspace brief: a project map (file tree, README start, git branch and
+/// changed files, toolchains) added to the system prompt. Conversations can
+/// turn it on or off with `/brief`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceBriefConfig {
+    /// Add the brief to conversations that haven't chosen with `/brief`.
+    /// Default: false.
+    pub enabled: bool,
+    /// Directory levels shown in the file tree. Default: 2.
+    pub max_depth: usize,
+    /// Entries shown in the file tree. Default: 100.
+    pub max_entries: usize,
+    /// Lines of the README included. Default: 20.
+    pub readme_lines: usize,
+    /// Estimated tokens the whole brief may use; it is cut to fit.
+    /// Default: 1500.
+    pub max_tokens: usize,
+}
+
+impl Default for WorkspaceBriefConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_depth: 2,
+            max_entries: 100,
+            readme_lines: 20,
+            max_tokens: 1500,
+        }
+    }
+}
+
+/// Retrieval: passages from a folder of documents added to the system
+/// prompt when they match the latest user message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetrievalConfig {
+    /// Directory of text and Markdown files to search. Retrieval is off
+    /// when unset. Default: unset.
+    pub dir: Option<String>,
+    /// Passages added per turn at most. Default: 4.
+    pub top_k: usize,
+    /// Estimated tokens the passages may use together. Default: 1500.
+    pub max_tokens: usize,
+    /// Approximate size of a passage in characters. Default: 1200.
+    pub chunk_chars: usize,
+}
+
+impl Default for RetrievalConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            top_k: 4,
+            max_tokens: 1500,
+            chunk_chars: 1200,
+        }
+    }
+}
+
+/// Turn traces: each turn's provider requests and responses, tool calls
+/// and context report, written as JSON files for debugging. Conversations
+/// can turn tracing on or off with `/trace`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TraceConfig {
+    /// Trace conversations that haven't chosen with `/trace`.
+    /// Default: false.
+    pub enabled: bool,
+    /// Directory the traces are written to. Default: `~/.zeptoclaw/traces`.
+    pub dir: Option<String>,
+    /// Newest turns kept per conversation. Default: 20.
+    pub keep_turns: usize,
+    /// Days a trace is kept; 0 keeps traces until `keep_turns` removes
+    /// them. Default: 7.
+    pub max_age_days: u64,
+}
+
+impl Default for TraceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: None,
+            keep_turns: 20,
+            max_age_days: 7,
+        }
+    }
+}
+
+/// Limits on turns running at once. Turns over a limit wait in one
+/// first-come, first-served queue; a session's next turn goes to the back
+/// of it, so one busy chat can't hold a slot while others wait.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConcurrencyConfig {
+    /// Turns running at once across all sessions. 0 (the default) = no
+    /// limit.
+    pub max_turns: usize,
+    /// Turns running at once by session namespace, the part of the session
+    /// key before the first `:` (e.g. `{"telegram": 4}`).
+    pub namespace_limits: HashMap<String, usize>,
+    /// Turns that may wait for a slot; more are answered with
+    /// `busy_message`. 0 = no cap. Default: 100.
+    pub max_queued: usize,
+    /// Seconds a turn waits before its chat is told how many turns are
+    /// ahead of it. 0 = never. Default: 10.
+    pub queued_notice_secs: u64,
+    /// Reply to messages that find the queue full.
+    pub busy_message: String,
+}
+
+impl Default for ConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            max_turns: 0,
+            namespace_limits: HashMap::new(),
+            max_queued: 100,
+            queued_notice_secs: 10,
+            busy_message: "I'm handling a lot of messages right now. Please try again in a minute."
+                .to_string(),
+        }
+    }
 }
 
 /// Configuration for the multi-layered tool loop guard.
@@ -698,6 +1165,10 @@ pub struct LoopGuardConfig {
     /// prevent unbounded memory growth and false-positive warnings.
     #[serde(default = "default_window_size")]
     pub window_size: u32,
+    /// How many times the same tool may be asked for with the same arguments
+    /// in one turn: from this call on, identical calls are answered with a
+    /// note instead of running (with 3, the third is not run). 0 disables.
+    pub repeat_call_limit: u32,
 }
 
 fn default_window_size() -> u32 {
@@ -716,6 +1187,7 @@ impl Default for LoopGuardConfig {
             outcome_warn_threshold: 2,
             outcome_block_threshold: 3,
             window_size: default_window_size(),
+            repeat_call_limit: 3,
         }
     }
 }
@@ -736,12 +1208,57 @@ pub struct AgentDefaults {
     pub max_tool_iterations: u32,
     /// Maximum wall-clock time (seconds) for a single agent run.
     pub agent_timeout_secs: u64,
+    /// Time (seconds) turns under way at shutdown get to finish before they
+    /// are cancelled.
+    pub shutdown_timeout_secs: u64,
     /// Maximum wall-clock time (seconds) for a single tool call. 0 = use agent_timeout_secs.
     pub tool_timeout_secs: u64,
+    /// Per-tool timeout overrides in seconds, keyed by tool name
+    /// (e.g. `{"web_fetch": 20, "shell": 120}`).
+    #[serde(default)]
+    pub tool_timeouts: HashMap<String, u64>,
+    /// Wall-clock budget (seconds) for tool calls in one turn, counted from
+    /// the start of the turn. Once spent, running tools are cancelled and
+    /// further calls are skipped with an explanation. 0 = no turn budget.
+    #[serde(default)]
+    pub turn_tool_budget_secs: u64,
+    /// Wall-clock limit (seconds) for a whole turn. Once reached, the turn
+    /// is cancelled and answers with what it has so far. 0 = no limit.
+    pub turn_timeout_secs: u64,
+    /// Per-channel turn limits in seconds, keyed by channel name
+    /// (e.g. `{"telegram": 60}`), over `turn_timeout_secs`.
+    pub turn_timeouts: HashMap<String, u64>,
+    /// Maximum tool calls from one assistant message run at the same time.
+    /// 0 = no limit.
+    pub max_parallel_tools: usize,
+    /// Limits on turns running at once, across sessions and by namespace.
+    #[serde(default)]
+    pub concurrency: ConcurrencyConfig,
     /// How to handle messages arriving during an active run.
     pub message_queue_mode: MessageQueueMode,
+    /// Per-channel queue modes, keyed by channel name
+    /// (e.g. `{"telegram": "collect"}`), over `message_queue_mode`.
+    pub message_queue_modes: HashMap<String, MessageQueueMode>,
+    /// Number of recent channel message IDs remembered per session, so a
+    /// message a channel delivers twice is answered once. 0 = no dedup.
+    pub dedup_window: usize,
     /// Whether to stream the final LLM response token-by-token in CLI mode.
     pub streaming: bool,
+    /// Stream replies to chat channels as they are generated. Channels that
+    /// can edit messages show the reply growing in place; the others still
+    /// receive only the finished reply.
+    pub stream_to_channels: bool,
+    /// Milliseconds between streamed updates sent to a channel.
+    pub stream_interval_ms: u64,
+    /// Tell chat channels what a long turn is doing ("Running shell…").
+    /// Channels that can edit messages keep one status message up to date;
+    /// the others get a single "Working on it…" line.
+    pub progress_updates: bool,
+    /// Milliseconds before the first progress update and between updates.
+    pub progress_interval_ms: u64,
+    /// Answer messages that look like a chat command but name none with
+    /// "Unknown command" instead of passing them to the model.
+    pub strict_commands: bool,
     /// Per-session token budget (input + output). 0 = unlimited.
     pub token_budget: u64,
     /// Use compact (shorter) tool descriptions to save tokens.
@@ -761,6 +1278,15 @@ pub struct AgentDefaults {
     /// Loop guard configuration for repeated tool-call detection.
     #[serde(default)]
     pub loop_guard: LoopGuardConfig,
+    /// Project map added to the system prompt for coding sessions.
+    #[serde(default)]
+    pub workspace_brief: WorkspaceBriefConfig,
+    /// Passages from a folder of documents added to the system prompt.
+    #[serde(default)]
+    pub retrieval: RetrievalConfig,
+    /// Per-turn traces for debugging.
+    #[serde(default)]
+    pub trace: TraceConfig,
     /// Maximum bytes allowed per tool result before truncation.
     #[serde(default = "default_max_tool_result_bytes")]
     pub max_tool_result_bytes: usize,
@@ -815,15 +1341,32 @@ impl Default for AgentDefaults {
             temperature: 0.7,
             max_tool_iterations: 20,
             agent_timeout_secs: 300,
+            shutdown_timeout_secs: 30,
             tool_timeout_secs: 0,
+            tool_timeouts: HashMap::new(),
+            turn_tool_budget_secs: 0,
+            turn_timeout_secs: 0,
+            turn_timeouts: HashMap::new(),
+            max_parallel_tools: 4,
+            concurrency: ConcurrencyConfig::default(),
             message_queue_mode: MessageQueueMode::default(),
+            message_queue_modes: HashMap::new(),
+            dedup_window: 100,
             streaming: true,
+            stream_to_channels: false,
+            stream_interval_ms: 1500,
+            progress_updates: false,
+            progress_interval_ms: 3000,
+            strict_commands: false,
             token_budget: 0,
             compact_tools: false,
             tool_profile: None,
             active_hand: None,
             timezone: default_timezone(),
             loop_guard: LoopGuardConfig::default(),
+            workspace_brief: WorkspaceBriefConfig::default(),
+            retrieval: RetrievalConfig::default(),
+            trace: TraceConfig::default(),
             max_tool_result_bytes: default_max_tool_result_bytes(),
             max_tool_calls: None,
             system_prompt: None,
@@ -840,6 +1383,13 @@ pub enum MessageQueueMode {
     Collect,
     /// Buffer messages, replay each as a separate run after current finishes.
     Followup,
+    /// Stop the current run, then handle the buffered messages as one, like
+    /// `Collect`.
+    Interrupt,
+    /// Add buffered messages to the current run before its next model call,
+    /// so the model can change course between tool calls. Messages arriving
+    /// after its last call are handled as in `Collect`.
+    Steer,
 }
 
 // ============================================================================
@@ -1564,7 +2114,8 @@ pub struct ProvidersConfig {
     /// Azure OpenAI configuration (OpenAI-compatible with api-key header).
     #[serde(default)]
     pub azure: Option<ProviderConfig>,
-    /// Amazon Bedrock configuration (OpenAI-compatible endpoint; SigV4 required externally).
+    /// Amazon Bedrock configuration (native Converse API with the
+    /// `provider-bedrock` feature, else an OpenAI-compatible SigV4 proxy).
     #[serde(default)]
     pub bedrock: Option<ProviderConfig>,
     /// xAI (Grok) configuration (OpenAI-compatible).
@@ -1616,6 +2167,18 @@ pub struct ProviderConfig {
     /// API version query param, e.g. "2024-08-01-preview" for Azure.
     #[serde(default)]
     pub api_version: Option<String>,
+    /// Cache the system prompt and tool definitions between calls with
+    /// Anthropic prompt caching. Ignored by other providers.
+    #[serde(default)]
+    pub prompt_caching: bool,
+    /// How long Ollama keeps the model loaded after a request (e.g. "30m",
+    /// "-1" to keep it loaded). Ignored by other providers.
+    #[serde(default)]
+    pub keep_alive: Option<String>,
+    /// AWS region for Bedrock (e.g. "us-east-1"); defaults to the usual AWS
+    /// settings. Ignored by other providers.
+    #[serde(default)]
+    pub region: Option<String>,
 }
 
 impl ProviderConfig {
@@ -1670,7 +2233,7 @@ pub struct RetryConfig {
 impl Default for RetryConfig {
     fn default() -> Self {
         Self {
-            enabled: false,
+            enabled: true,
             max_retries: 3,
             base_delay_ms: 1_000,
             max_delay_ms: 30_000,
@@ -1798,23 +2361,179 @@ impl Default for GatewayConfig {
 // ============================================================================
 
 /// Voice transcription tool configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+///
+/// Also used for voice messages arriving on channels when
+/// `transcription.enabled` is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct TranscribeConfig {
     /// Enable the transcribe tool
     #[serde(default)]
     pub enabled: bool,
+    /// Speech-to-text backend: "groq", "openai" or "whisper_cpp". Default: "groq".
+    pub backend: String,
     /// Groq API key for Whisper transcription
     pub groq_api_key: Option<String>,
-    /// Whisper model to use
+    /// API key for the "openai" backend
+    pub api_key: Option<String>,
+    /// Override the API base URL of the "groq" or "openai" backend
+    pub api_base: Option<String>,
+    /// Whisper model to use. The "openai" backend uses "whisper-1" unless
+    /// set to something other than the default.
     #[serde(default = "default_transcribe_model")]
     pub model: String,
+    /// ISO-639-1 language hint such as "en"; auto-detected when unset.
+    pub language: Option<String>,
+    /// Longest audio accepted, in seconds. Default: 600.
+    pub max_duration_secs: u64,
+    /// whisper.cpp CLI binary for the "whisper_cpp" backend. Default: "whisper-cli".
+    pub whisper_cpp_binary: String,
+    /// whisper.cpp model file (ggml .bin), required by the "whisper_cpp" backend
+    pub whisper_cpp_model: Option<String>,
 }
 
 fn default_transcribe_model() -> String {
     "whisper-large-v3-turbo".to_string()
 }
 
+impl Default for TranscribeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: "groq".to_string(),
+            groq_api_key: None,
+            api_key: None,
+            api_base: None,
+            model: default_transcribe_model(),
+            language: None,
+            max_duration_secs: 600,
+            whisper_cpp_binary: "whisper-cli".to_string(),
+            whisper_cpp_model: None,
+        }
+    }
+}
+
+/// Sandbox wrapped around `run_python` scripts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PythonSandbox {
+    /// Run the interpreter directly (rlimits and network env still apply).
+    #[default]
+    None,
+    /// Bubblewrap: read-only root, writable workspace, no network.
+    Bwrap,
+    /// Firejail with `--net=none`.
+    Firejail,
+}
+
+/// `run_python` tool configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunPythonConfig {
+    /// Enable the run_python tool
+    pub enabled: bool,
+    /// Interpreter to run scripts with. Default: "python3".
+    pub interpreter: String,
+    /// Wall-clock timeout in seconds; calls may ask for less. Default: 60.
+    pub timeout_secs: u64,
+    /// CPU time limit in seconds (RLIMIT_CPU, Unix only). Default: 30.
+    pub cpu_time_secs: u64,
+    /// Address space limit in MB (RLIMIT_AS, Unix only). Default: 1024.
+    pub memory_mb: u64,
+    /// Sandbox to wrap scripts in. Falls back to none, with a warning, when
+    /// the binary is not on PATH. Default: none.
+    pub sandbox: PythonSandbox,
+}
+
+impl Default for RunPythonConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interpreter: "python3".to_string(),
+            timeout_secs: 60,
+            cpu_time_secs: 30,
+            memory_mb: 1024,
+            sandbox: PythonSandbox::None,
+        }
+    }
+}
+
+/// `send_message` tool configuration (proactive messages to other channels).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SendMessageToolConfig {
+    /// Enable the send_message tool
+    pub enabled: bool,
+    /// Destinations the tool may message, as "channel:chat_id". Use
+    /// "channel:*" to allow every chat on a channel. Empty allows none.
+    pub allowed_destinations: Vec<String>,
+    /// Most messages per destination in any one-hour window. Default: 10.
+    pub max_per_hour: u32,
+    /// Append sent messages to the destination chat's session. Default: true.
+    pub record_in_session: bool,
+}
+
+impl Default for SendMessageToolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_destinations: Vec::new(),
+            max_per_hour: 10,
+            record_in_session: true,
+        }
+    }
+}
+
+/// `ask_user` tool configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AskUserToolConfig {
+    /// Seconds to wait for the user's answer before the tool returns "no
+    /// answer". The wait counts toward `agents.defaults.agent_timeout_secs`.
+    /// Default: 120.
+    pub timeout_secs: u64,
+}
+
+impl Default for AskUserToolConfig {
+    fn default() -> Self {
+        Self { timeout_secs: 120 }
+    }
+}
+
+/// Text-to-speech tool configuration (OpenAI-compatible `audio/speech` API).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeakConfig {
+    /// Enable the speak tool
+    pub enabled: bool,
+    /// API key for the speech endpoint
+    pub api_key: Option<String>,
+    /// API base URL. Default: "https://api.openai.com/v1".
+    pub api_base: String,
+    /// Speech model. Default: "tts-1".
+    pub model: String,
+    /// Voice name. Default: "alloy".
+    pub voice: String,
+    /// Output format: "opus" (OGG, used for voice messages) or "mp3". Default: "opus".
+    pub format: String,
+    /// Longest text accepted, in characters. Default: 4096.
+    pub max_chars: usize,
+}
+
+impl Default for SpeakConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_key: None,
+            api_base: "https://api.openai.com/v1".to_string(),
+            model: "tts-1".to_string(),
+            voice: "alloy".to_string(),
+            format: "opus".to_string(),
+            max_chars: 4096,
+        }
+    }
+}
+
 /// Tools configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
@@ -1826,6 +2545,12 @@ pub struct ToolsConfig {
     pub browser: BrowserConfig,
     /// WhatsApp Cloud API tool configuration
     pub whatsapp: WhatsAppToolConfig,
+    /// Human approval tool configuration (Telegram or Slack sign-off)
+    #[serde(default)]
+    pub human_approval: HumanApprovalToolConfig,
+    /// Container registry tool configuration (Docker Hub, GHCR, Snyk)
+    #[serde(default)]
+    pub container_registry: ContainerRegistryToolConfig,
     /// Google Sheets tool configuration
     pub google_sheets: GoogleSheetsToolConfig,
     /// Google Workspace tool configuration (Gmail + Calendar)
@@ -1836,9 +2561,70 @@ pub struct ToolsConfig {
     /// Voice transcription tool configuration
     #[serde(default)]
     pub transcribe: TranscribeConfig,
+    /// Text-to-speech tool configuration
+    #[serde(default)]
+    pub speak: SpeakConfig,
+    /// Proactive cross-channel messaging tool configuration
+    #[serde(default)]
+    pub send_message: SendMessageToolConfig,
+    /// `ask_user` tool configuration (mid-turn questions to the user)
+    #[serde(default)]
+    pub ask_user: AskUserToolConfig,
+    /// Sandboxed Python execution tool configuration
+    #[serde(default)]
+    pub run_python: RunPythonConfig,
     /// Skills marketplace (ClawHub) configuration
     #[serde(default)]
     pub skills: SkillsMarketplaceConfig,
+    /// Shell tool configuration
+    #[serde(default)]
+    pub shell: ShellToolConfig,
+    /// Background process tool configuration
+    #[serde(default)]
+    pub process: ProcessToolConfig,
+    /// Workspace paths hidden from or read-only to the file tools
+    #[serde(default)]
+    pub filesystem: FilesystemToolsConfig,
+    /// File write tool configuration
+    #[serde(default)]
+    pub write_file: WriteFileToolConfig,
+    /// Directory listing tool configuration
+    #[serde(default)]
+    pub list_dir: ListDirToolConfig,
+    /// HTTP fetch tool configuration
+    #[serde(default)]
+    pub http_fetch: HttpFetchConfig,
+    /// SQLite query tool configuration (`tool-sqlite` feature)
+    #[serde(default)]
+    pub sqlite: SqliteToolConfig,
+    /// Git tool configuration
+    #[serde(default)]
+    pub git: GitToolConfig,
+    /// Download tool configuration (hosts are governed by `http_fetch`)
+    #[serde(default)]
+    pub download: DownloadToolConfig,
+    /// Archive tool limits
+    #[serde(default)]
+    pub archive: ArchiveToolConfig,
+    /// `extract_text` tool configuration (optional OCR for scanned PDFs)
+    #[serde(default)]
+    pub extract_text: ExtractTextToolConfig,
+    /// Image analysis tool configuration (`tool-vision` feature)
+    #[serde(default)]
+    pub vision: VisionToolConfig,
+    /// Per-channel and per-session tool allow/deny lists
+    #[serde(default)]
+    pub access: ToolAccessConfig,
+    /// Validation of tool arguments against each tool's schema
+    #[serde(default)]
+    pub argument_validation: ArgValidationConfig,
+    /// Per-tool call limits keyed by tool name, e.g. `"web_search": "10/min"`.
+    /// Counted separately for each session. Units: sec, min, hour, day.
+    #[serde(default)]
+    pub rate_limits: HashMap<String, String>,
+    /// Project commands exposed as tools (see [`ScriptToolDef`])
+    #[serde(default)]
+    pub custom: Vec<ScriptToolDef>,
     /// Enable coding-specific tools (grep, find). Default: false.
     ///
     /// These tools assume a laptop/server environment with bash available.
@@ -1849,11 +2635,472 @@ pub struct ToolsConfig {
     /// Example: `"tools": { "coding_tools": true }`
     #[serde(default)]
     pub coding_tools: bool,
+    /// Dry-run mode: state-changing tools (shell, file writes, git commit,
+    /// archive extract, ...) describe what they would do instead of acting,
+    /// while read-only tools run normally. A chat can turn it on for itself
+    /// with `/dryrun on`, but `/dryrun off` cannot override this.
+    ///
+    /// Example: `"tools": { "dry_run": true }`
+    #[serde(default)]
+    pub dry_run: bool,
     /// Tools to deny (disable). Set by startup guard in degraded mode.
     #[serde(default)]
     pub deny: Vec<String>,
 }
 
+/// Configuration for the shell tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShellToolConfig {
+    /// Maximum bytes kept from each of stdout and stderr; longer output keeps
+    /// its head and tail around a truncation marker. Default: 32KB.
+    pub max_output_bytes: usize,
+    /// Command policy applied on top of the built-in blocklist.
+    pub policy: ShellPolicyConfig,
+    /// Shell that interprets commands unless a call picks another. Default:
+    /// detected for the platform (see [`ShellKind::detect`]).
+    pub default_shell: Option<ShellKind>,
+}
+
+impl Default for ShellToolConfig {
+    fn default() -> Self {
+        Self {
+            max_output_bytes: 32 * 1024,
+            policy: ShellPolicyConfig::default(),
+            default_shell: None,
+        }
+    }
+}
+
+/// Shell used to interpret shell tool commands.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShellKind {
+    /// POSIX `sh -c` (default on Unix).
+    #[default]
+    Sh,
+    /// `bash -c`.
+    Bash,
+    /// `zsh -c`.
+    Zsh,
+    /// `cmd /C` (Windows only).
+    Cmd,
+    /// `powershell -Command` on Windows, `pwsh -Command` elsewhere.
+    PowerShell,
+}
+
+impl ShellKind {
+    /// All shells, in the order they are documented.
+    pub const ALL: [ShellKind; 5] = [
+        ShellKind::Sh,
+        ShellKind::Bash,
+        ShellKind::Zsh,
+        ShellKind::Cmd,
+        ShellKind::PowerShell,
+    ];
+
+    /// Config and parameter name, e.g. `"bash"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ShellKind::Sh => "sh",
+            ShellKind::Bash => "bash",
+            ShellKind::Zsh => "zsh",
+            ShellKind::Cmd => "cmd",
+            ShellKind::PowerShell => "powershell",
+        }
+    }
+
+    /// Parse a config or parameter name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|shell| shell.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Executable to run.
+    pub fn program(self) -> &'static str {
+        match self {
+            ShellKind::PowerShell if !cfg!(windows) => "pwsh",
+            ShellKind::Cmd => "cmd",
+            other => other.name(),
+        }
+    }
+
+    /// Flag that makes the shell run the next argument as a command.
+    pub fn command_flag(self) -> &'static str {
+        match self {
+            ShellKind::Sh | ShellKind::Bash | ShellKind::Zsh => "-c",
+            ShellKind::Cmd => "/C",
+            ShellKind::PowerShell => "-Command",
+        }
+    }
+
+    /// Whether the shell is Windows-only.
+    pub fn is_windows_only(self) -> bool {
+        self == ShellKind::Cmd
+    }
+
+    /// Platform default: `sh` on Unix; on Windows PowerShell when it is on
+    /// `PATH`, otherwise `cmd`. Detected once and cached.
+    pub fn detect() -> Self {
+        static DETECTED: once_cell::sync::OnceCell<ShellKind> = once_cell::sync::OnceCell::new();
+        *DETECTED.get_or_init(|| {
+            if !cfg!(windows) {
+                return ShellKind::Sh;
+            }
+            let has_powershell = std::process::Command::new("where")
+                .arg("powershell")
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status()
+                .is_ok_and(|s| s.success());
+            if has_powershell {
+                ShellKind::PowerShell
+            } else {
+                ShellKind::Cmd
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for ShellKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// What the shell policy does with commands that are not on its allowlist.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShellPolicyMode {
+    /// Run them (default).
+    #[default]
+    Allow,
+    /// Refuse them.
+    Deny,
+    /// Ask a human (via the human approval channel) before running them.
+    Ask,
+}
+
+/// Shell command policy. See [`crate::security::ShellPolicy`].
+///
+/// Example:
+/// `"policy": { "mode": "ask", "allow": ["git status", "cargo"], "deny": ["\\bsudo\\b"] }`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShellPolicyConfig {
+    /// How to treat commands that are not allowlisted.
+    pub mode: ShellPolicyMode,
+    /// Allowed command prefixes, e.g. `"cargo"` or `"git status"`. Every
+    /// segment of a compound command must match one.
+    pub allow: Vec<String>,
+    /// Regex patterns that always block a command, whatever the mode.
+    pub deny: Vec<String>,
+    /// Block redirections, `tee`, `cp`, and `mv` that write outside the
+    /// workspace.
+    pub restrict_writes_to_workspace: bool,
+}
+
+/// Configuration for the background process tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessToolConfig {
+    /// Maximum background processes running at once. Default: 8.
+    pub max_processes: usize,
+    /// Bytes of stdout and stderr kept per process. Default: 64KB.
+    pub log_tail_bytes: usize,
+}
+
+impl Default for ProcessToolConfig {
+    fn default() -> Self {
+        Self {
+            max_processes: 8,
+            log_tail_bytes: 64 * 1024,
+        }
+    }
+}
+
+/// Configuration for the file write tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WriteFileToolConfig {
+    /// Largest file, in bytes, `write_file` may produce or `edit_file` may
+    /// touch. Default: 10MB.
+    pub max_file_bytes: u64,
+}
+
+impl Default for WriteFileToolConfig {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: 10 * 1024 * 1024,
+        }
+    }
+}
+
+/// Paths the file tools treat specially, on top of workspace containment.
+///
+/// Patterns are globs relative to the workspace. One without a `/` matches
+/// a file or directory name at any depth (`.env`); one with a `/` matches
+/// from the workspace root (`.git/config`, `secrets/*.pem`). A matching
+/// directory covers everything inside it.
+///
+/// Example: `"filesystem": { "denied_paths": [".env", ".git/config"],
+///                           "read_only_paths": ["vendor"] }`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilesystemToolsConfig {
+    /// Paths no tool may read, list or write.
+    pub denied_paths: Vec<String>,
+    /// Paths tools may read but not write, edit or extract into.
+    pub read_only_paths: Vec<String>,
+}
+
+/// Configuration for the directory listing tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListDirToolConfig {
+    /// File and directory names skipped while listing.
+    /// Default: `.git`, `node_modules`, `target`.
+    pub ignore: Vec<String>,
+    /// Directory levels searched when a pattern is given. Default: 8.
+    pub max_depth: usize,
+}
+
+impl Default for ListDirToolConfig {
+    fn default() -> Self {
+        Self {
+            ignore: vec![
+                ".git".to_string(),
+                "node_modules".to_string(),
+                "target".to_string(),
+            ],
+            max_depth: 8,
+        }
+    }
+}
+
+/// Configuration for the git tool.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitToolConfig {
+    /// Name recorded on commits made by the tool. Default: git's own config.
+    pub author_name: Option<String>,
+    /// Email recorded on commits made by the tool. Default: git's own config.
+    pub author_email: Option<String>,
+}
+
+/// Configuration for the SQLite query tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SqliteToolConfig {
+    /// Open databases read-write and accept statements other than SELECT
+    /// and PRAGMA. Default: false.
+    pub allow_write: bool,
+    /// Rows returned per query; the `max_rows` argument can only lower it.
+    /// Default: 100.
+    pub max_rows: usize,
+    /// Seconds a query may run before it is interrupted. Default: 10.
+    pub timeout_secs: u64,
+    /// Cell values longer than this many characters are truncated.
+    /// Default: 200.
+    pub max_cell_chars: usize,
+}
+
+impl Default for SqliteToolConfig {
+    fn default() -> Self {
+        Self {
+            allow_write: false,
+            max_rows: 100,
+            timeout_secs: 10,
+            max_cell_chars: 200,
+        }
+    }
+}
+
+/// Checking tool call arguments against the tool's declared JSON schema
+/// before it runs.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArgValidationConfig {
+    /// Reject calls whose arguments do not match the schema, listing each
+    /// problem so the model can retry. Default: true.
+    pub enabled: bool,
+    /// Fix near-misses instead of rejecting them: numeric strings to numbers,
+    /// "true"/"false" to booleans, numbers to strings. Default: true.
+    pub coerce: bool,
+}
+
+impl Default for ArgValidationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            coerce: true,
+        }
+    }
+}
+
+/// Tool availability by channel and session.
+///
+/// Every matching rule applies: a tool is offered to the model only if no
+/// matching rule denies it and it is in every matching `allow` list.
+///
+/// Example:
+/// `"access": { "channels": { "telegram": { "deny": ["cron"] } },
+///              "sessions": { "telegram:-*": { "deny": ["shell"] } } }`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolAccessConfig {
+    /// Rules keyed by channel name (`telegram`, `slack`, `cli`, ...).
+    pub channels: HashMap<String, ToolAccessRule>,
+    /// Rules keyed by session key; a trailing `*` matches a key prefix
+    /// (e.g. `telegram:-*` for every Telegram group).
+    pub sessions: HashMap<String, ToolAccessRule>,
+}
+
+/// Allow/deny lists of tool names for one channel or session pattern.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolAccessRule {
+    /// Only these tools are available. `None` (the default) allows all.
+    pub allow: Option<Vec<String>>,
+    /// These tools are never available.
+    pub deny: Vec<String>,
+}
+
+/// Configuration for the HTTP fetch tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpFetchConfig {
+    /// Register the tool. It can send any method to any public host unless
+    /// `allowed_hosts` says otherwise. Default: false.
+    pub enabled: bool,
+    /// Hosts the tool may fetch from; `*.example.com` also matches
+    /// subdomains. Empty (the default) allows any public host.
+    pub allowed_hosts: Vec<String>,
+    /// Hosts that are always refused, checked before `allowed_hosts`.
+    pub denied_hosts: Vec<String>,
+    /// Allow localhost, private and link-local addresses. Default: false.
+    pub allow_private_hosts: bool,
+    /// Maximum response body size in bytes. Default: 256KB.
+    pub max_response_bytes: usize,
+    /// Timeout for the whole request, redirects included. Default: 30.
+    pub timeout_secs: u64,
+    /// Redirects followed before giving up. Default: 5.
+    pub max_redirects: usize,
+}
+
+impl Default for HttpFetchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_hosts: Vec::new(),
+            denied_hosts: Vec::new(),
+            allow_private_hosts: false,
+            max_response_bytes: 256 * 1024,
+            timeout_secs: 30,
+            max_redirects: 5,
+        }
+    }
+}
+
+/// Configuration for the download tool. Which hosts may be downloaded from
+/// is governed by [`HttpFetchConfig`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadToolConfig {
+    /// Register the tool. Default: false.
+    pub enabled: bool,
+    /// Maximum file size in bytes; larger downloads are aborted. Default: 50MB.
+    pub max_bytes: u64,
+    /// Timeout for the whole download in seconds. Default: 300.
+    pub timeout_secs: u64,
+}
+
+impl Default for DownloadToolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: 50 * 1024 * 1024,
+            timeout_secs: 300,
+        }
+    }
+}
+
+/// Limits for the archive tool's extraction and creation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveToolConfig {
+    /// Maximum total uncompressed size of an extracted archive. Default: 256MB.
+    pub max_extract_bytes: u64,
+    /// Maximum number of entries extracted or archived. Default: 10000.
+    pub max_files: usize,
+}
+
+impl Default for ArchiveToolConfig {
+    fn default() -> Self {
+        Self {
+            max_extract_bytes: 256 * 1024 * 1024,
+            max_files: 10_000,
+        }
+    }
+}
+
+/// Configuration for the `extract_text` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExtractTextToolConfig {
+    /// OCR program run on PDFs without a text layer (scanned pages). It must
+    /// print the recognized text on stdout. Unset disables OCR.
+    pub ocr_command: Option<String>,
+    /// Arguments for `ocr_command`; `{path}` is replaced by the PDF's path,
+    /// which is appended when no argument mentions it.
+    pub ocr_args: Vec<String>,
+    /// Seconds the OCR program may run. Default: 120.
+    pub ocr_timeout_secs: u64,
+}
+
+impl Default for ExtractTextToolConfig {
+    fn default() -> Self {
+        Self {
+            ocr_command: None,
+            ocr_args: Vec::new(),
+            ocr_timeout_secs: 120,
+        }
+    }
+}
+
+/// Configuration for the `analyze_image` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VisionToolConfig {
+    /// Model to ask; the provider's default model when unset.
+    pub model: Option<String>,
+    /// Largest image file accepted, before any downscaling. Default: 20MB.
+    pub max_image_bytes: usize,
+    /// Longest side in pixels sent to the provider; larger images are
+    /// downscaled. Default: 1568.
+    pub max_dimension: u32,
+    /// Largest encoded image sent to the provider. Default: 4MB.
+    pub max_upload_bytes: usize,
+    /// Analyses kept in the content-hash cache. Default: 500.
+    pub cache_entries: usize,
+    /// How long a cached analysis is reused, in seconds. Default: 30 days.
+    pub cache_ttl_secs: u64,
+}
+
+impl Default for VisionToolConfig {
+    fn default() -> Self {
+        Self {
+            model: None,
+            max_image_bytes: 20 * 1024 * 1024,
+            max_dimension: 1568,
+            max_upload_bytes: 4 * 1024 * 1024,
+            cache_entries: 500,
+            cache_ttl_secs: 30 * 24 * 3600,
+        }
+    }
+}
+
 /// Configuration for the HTTP request tool.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct HttpRequestConfig {
@@ -1970,6 +3217,64 @@ impl Default for WhatsAppToolConfig {
     }
 }
 
+/// Human approval tool configuration.
+///
+/// The tool posts a question to one approval channel and polls for the
+/// answer. Telegram takes precedence when both are configured. Use a
+/// dedicated Telegram bot: `getUpdates` polling conflicts with a Telegram
+/// channel running on the same bot token, so that token is refused.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HumanApprovalToolConfig {
+    /// Bot token of the dedicated approval bot
+    pub telegram_bot_token: Option<String>,
+    /// Chat ID that receives approval requests
+    pub telegram_chat_id: Option<String>,
+    /// Slack bot token (`xoxb-...`) with `chat:write` and history scopes
+    pub slack_bot_token: Option<String>,
+    /// Slack channel ID that receives approval requests
+    pub slack_channel: Option<String>,
+    /// Seconds between polls for a response. Default: 5.
+    pub poll_interval_secs: u64,
+    /// Upper bound for the per-call `timeout_secs` argument. Default: 3600.
+    pub max_timeout_secs: u64,
+}
+
+impl Default for HumanApprovalToolConfig {
+    fn default() -> Self {
+        Self {
+            telegram_bot_token: None,
+            telegram_chat_id: None,
+            slack_bot_token: None,
+            slack_channel: None,
+            poll_interval_secs: 5,
+            max_timeout_secs: 3600,
+        }
+    }
+}
+
+/// Container registry tool configuration.
+///
+/// Public images on Docker Hub and GHCR need no credentials. Tokens raise
+/// Docker Hub pull-rate limits and unlock private repositories; Snyk
+/// credentials enable the `check_vulnerabilities` operation.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ContainerRegistryToolConfig {
+    /// Docker Hub username
+    pub dockerhub_username: Option<String>,
+    /// Docker Hub personal access token
+    pub dockerhub_token: Option<String>,
+    /// GitHub username for GHCR
+    pub ghcr_username: Option<String>,
+    /// GitHub token with `read:packages` scope
+    pub ghcr_token: Option<String>,
+    /// Snyk API token
+    pub snyk_token: Option<String>,
+    /// Snyk organization ID whose monitored projects are queried
+    pub snyk_org_id: Option<String>,
+}
+
 /// Google Sheets tool configuration.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
@@ -2086,6 +3391,8 @@ pub struct MemoryConfig {
     /// Memory hygiene scheduler configuration.
     #[serde(default)]
     pub hygiene: crate::memory::hygiene::HygieneConfig,
+    /// Byte quota per namespace for the `scratchpad` tool. Default: 64KB.
+    pub scratchpad_max_bytes: usize,
 }
 
 impl Default for MemoryConfig {
@@ -2103,6 +3410,7 @@ impl Default for MemoryConfig {
             hnsw_index_path: None,
             tantivy_index_path: None,
             hygiene: crate::memory::hygiene::HygieneConfig::default(),
+            scratchpad_max_bytes: 64 * 1024,
         }
     }
 }
@@ -2621,6 +3929,35 @@ pub struct CustomToolDef {
     pub env: Option<HashMap<String, String>>,
 }
 
+/// A `tools.custom` entry: a project command exposed as a tool.
+///
+/// `command` is split into arguments like a shell would; `{param}`
+/// placeholders are filled per call within their argument, never spliced
+/// into the command line. Invalid entries fail startup.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScriptToolDef {
+    /// Tool name (letters, digits and underscores, starting with a letter).
+    pub name: String,
+    /// Description shown to the model.
+    pub description: String,
+    /// JSON schema for the arguments (`"type": "object"`). Every
+    /// placeholder must be one of its `properties`. Omit for no arguments.
+    #[serde(default)]
+    pub parameters: Option<serde_json::Value>,
+    /// Command template, e.g. `cargo test -p {package}`. Single program,
+    /// no pipes or redirects.
+    pub command: String,
+    /// Environment variables; values may contain placeholders.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Working directory; relative paths are resolved against the workspace.
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    /// Timeout in seconds (default: 60).
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2788,6 +4125,18 @@ mod tests {
         assert!(defaults.tool_profile.is_none());
     }
 
+    #[test]
+    fn test_http_fetch_tools_default_off() {
+        let tools = ToolsConfig::default();
+        assert!(!tools.http_fetch.enabled);
+        assert!(!tools.download.enabled);
+
+        let json = r#"{"tools": {"http_fetch": {"enabled": true}}}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert!(config.tools.http_fetch.enabled);
+        assert_eq!(config.tools.http_fetch.max_redirects, 5);
+    }
+
     #[test]
     fn test_compact_tools_deserialize() {
         let json =
@@ -2813,6 +4162,15 @@ mod tests {
         assert_eq!(config.jitter_ms, 5000);
     }
 
+    #[test]
+    fn test_routines_config_catch_up_window() {
+        assert_eq!(RoutinesConfig::default().catch_up_window_secs, 0);
+        let json = r#"{"enabled": true, "cron_interval_secs": 60, "max_concurrent": 3, "on_miss": "run_once", "catch_up_window_secs": 3600}"#;
+        let config: RoutinesConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.on_miss, crate::cron::OnMiss::RunOnce);
+        assert_eq!(config.catch_up_window_secs, 3600);
+    }
+
     #[test]
     fn test_tunnel_config_defaults() {
         let config = TunnelConfig::default();
@@ -55,15 +55,32 @@ const KNOWN_AGENTS_DEFAULTS: &[&str] = &[
     "temperature",
     "max_tool_iterations",
     "agent_timeout_secs",
+    "shutdown_timeout_secs",
     "tool_timeout_secs",
+    "tool_timeouts",
+    "turn_tool_budget_secs",
+    "turn_timeout_secs",
+    "turn_timeouts",
+    "max_parallel_tools",
+    "concurrency",
     "message_queue_mode",
+    "message_queue_modes",
+    "dedup_window",
     "streaming",
+    "stream_to_channels",
+    "stream_interval_ms",
+    "progress_updates",
+    "progress_interval_ms",
+    "strict_commands",
     "token_budget",
     "compact_tools",
     "tool_profile",
     "active_hand",
     "timezone",
     "loop_guard",
+    "workspace_brief",
+    "retrieval",
+    "trace",
     "max_tool_result_bytes",
     "max_tool_calls",
     "system_prompt",
@@ -79,6 +96,7 @@ const KNOWN_LOOP_GUARD: &[&str] = &[
     "outcome_warn_threshold",
     "outcome_block_threshold",
     "window_size",
+    "repeat_call_limit",
 ];
 
 #[allow(dead_code)]
@@ -565,7 +583,8 @@ pub fn validate_model_provider_compat(config: &crate::config::Config) -> Vec<Dia
     diags
 }
 
-/// Validate custom tool definitions.
+/// Validate custom tool definitions, tool rate limits and agent profile
+/// names.
 pub fn validate_custom_tools(config: &crate::config::Config) -> Vec<String> {
     let mut warnings = Vec::new();
     let name_re = regex::Regex::new(r"^[a-zA-Z][a-zA-Z0-9_]*$").unwrap();
@@ -577,18 +596,33 @@ pub fn validate_custom_tools(config: &crate::config::Config) -> Vec<String> {
         "write_file",
         "list_dir",
         "edit_file",
+        "apply_patch",
         "shell",
+        "process",
         "web_search",
         "web_fetch",
+        "http_fetch",
+        "download",
+        "archive",
+        "extract_text",
+        "analyze_image",
+        "speak",
+        "calc",
+        "send_message",
+        "todo",
+        "run_python",
         "message",
+        "ask_user",
         "memory_search",
         "memory_get",
         "longterm_memory",
+        "scratchpad",
         "whatsapp_send",
         "google_sheets",
         "cron",
         "spawn",
         "delegate",
+        "spawn_agent",
         "r8r",
     ]
     .iter()
@@ -619,12 +653,99 @@ pub fn validate_custom_tools(config: &crate::config::Config) -> Vec<String> {
             ));
         }
     }
+    for tool in &config.tools.custom {
+        if let Err(e) = crate::tools::script::ScriptTool::check_def(tool) {
+            warnings.push(e.to_string());
+        }
+        if builtin_names.contains(tool.name.as_str()) {
+            warnings.push(format!(
+                "tools.custom tool '{}': name conflicts with built-in tool",
+                tool.name
+            ));
+        }
+    }
+    let mut limited: Vec<_> = config.tools.rate_limits.iter().collect();
+    limited.sort();
+    for (tool, spec) in limited {
+        if let Err(e) = crate::tools::rate_limit::RateLimit::parse(spec) {
+            warnings.push(format!("tools.rate_limits.{}: {}", tool, e));
+        }
+    }
+    let agents = &config.agents;
+    let mut routes: Vec<_> = agents.routes.iter().collect();
+    routes.sort();
+    for (namespace, profile) in routes {
+        if !agents.profiles.contains_key(profile) {
+            warnings.push(format!(
+                "agents.routes.{}: unknown agent profile '{}'",
+                namespace, profile
+            ));
+        }
+    }
+    let mut heartbeats: Vec<_> = agents.heartbeats.iter().collect();
+    heartbeats.sort_by(|a, b| a.0.cmp(b.0));
+    for (target, heartbeat) in heartbeats {
+        if heartbeat.interval_secs > 0 && heartbeat.prompt.trim().is_empty() {
+            warnings.push(format!(
+                "agents.heartbeats.{}: prompt is empty; the heartbeat will not run",
+                target
+            ));
+        }
+    }
+    let mut response_options: Vec<_> = agents.response_options.iter().collect();
+    response_options.sort_by(|a, b| a.0.cmp(b.0));
+    for (target, options) in response_options {
+        for pattern in options.strip_patterns.iter().flatten() {
+            if let Err(e) = regex::Regex::new(pattern) {
+                warnings.push(format!(
+                    "agents.response_options.{}: '{}' is not a valid regex ({}); it is ignored",
+                    target, pattern, e
+                ));
+            }
+        }
+        if options.max_length == Some(0) {
+            warnings.push(format!(
+                "agents.response_options.{}: max_length 0 is ignored",
+                target
+            ));
+        }
+    }
+    if let Some(dir) = &agents.defaults.retrieval.dir {
+        if !crate::config::expand_home(dir).is_dir() {
+            warnings.push(format!(
+                "agents.defaults.retrieval.dir: '{}' is not a directory; nothing will be retrieved",
+                dir
+            ));
+        }
+    }
+    if let Some(profile) = &agents.default_profile {
+        if !agents.profiles.contains_key(profile) {
+            warnings.push(format!(
+                "agents.default_profile: unknown agent profile '{}'",
+                profile
+            ));
+        }
+    }
+    let filesystem = &config.tools.filesystem;
+    for (key, patterns) in [
+        ("denied_paths", &filesystem.denied_paths),
+        ("read_only_paths", &filesystem.read_only_paths),
+    ] {
+        for pattern in patterns {
+            if let Err(e) = glob::Pattern::new(pattern.trim()) {
+                warnings.push(format!(
+                    "tools.filesystem.{}: '{}' is not a valid glob ({}); matching it literally",
+                    key, pattern, e
+                ));
+            }
+        }
+    }
     warnings
 }
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{Config, CustomToolDef};
+    use crate::config::{Config, CustomToolDef, ScriptToolDef};
     use serde_json::json;
 
     #[test]
@@ -842,6 +963,32 @@ mod tests {
         assert!(warnings.iter().any(|w| w.contains("conflicts")));
     }
 
+    #[test]
+    fn test_validate_script_tool_errors() {
+        let mut config = Config::default();
+        config.tools.custom = vec![
+            ScriptToolDef {
+                name: "deploy".to_string(),
+                description: "Deploy".to_string(),
+                command: "./deploy.sh {env}".to_string(),
+                ..Default::default()
+            },
+            ScriptToolDef {
+                name: "shell".to_string(),
+                description: "Conflict".to_string(),
+                command: "echo hi".to_string(),
+                ..Default::default()
+            },
+        ];
+        let warnings = validate_custom_tools(&config);
+        assert!(warnings
+            .iter()
+            .any(|w| w.contains("'deploy'") && w.contains("env")));
+        assert!(warnings
+            .iter()
+            .any(|w| w.contains("'shell'") && w.contains("conflicts")));
+    }
+
     #[test]
     fn test_validate_custom_tool_empty_command() {
         let config = Config {
@@ -878,6 +1025,55 @@ mod tests {
         assert!(warnings.iter().any(|w| w.contains("60 chars")));
     }
 
+    #[test]
+    fn test_validate_tool_rate_limits() {
+        let mut config = Config::default();
+        config
+            .tools
+            .rate_limits
+            .insert("web_search".into(), "10/min".into());
+        config
+            .tools
+            .rate_limits
+            .insert("shell".into(), "often".into());
+        let warnings = validate_custom_tools(&config);
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert!(warnings[0].starts_with("tools.rate_limits.shell:"));
+    }
+
+    #[test]
+    fn test_validate_filesystem_path_globs() {
+        let mut config = Config::default();
+        config.tools.filesystem.denied_paths = vec![".env".into(), "secrets/[".into()];
+        config.tools.filesystem.read_only_paths = vec!["vendor/**".into()];
+        let warnings = validate_custom_tools(&config);
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert!(warnings[0].starts_with("tools.filesystem.denied_paths:"));
+    }
+
+    #[test]
+    fn test_validate_agent_profile_names() {
+        let mut config = Config::default();
+        config
+            .agents
+            .profiles
+            .insert("coding".into(), Default::default());
+        config.agents.routes.insert("cli".into(), "coding".into());
+        config
+            .agents
+            .routes
+            .insert("slack".into(), "support".into());
+        config.agents.default_profile = Some("helper".into());
+        let warnings = validate_custom_tools(&config);
+        assert_eq!(
+            warnings,
+            vec![
+                "agents.routes.slack: unknown agent profile 'support'",
+                "agents.default_profile: unknown agent profile 'helper'",
+            ]
+        );
+    }
+
     #[test]
     fn test_validate_compact_tools_known() {
         let json = json!({"agents": {"defaults": {"compact_tools": true}}});
@@ -228,6 +228,9 @@ pub enum OnMiss {
     #[default]
     Skip,
     /// Execute one missed run immediately, then reschedule.
+    ///
+    /// Combine with [`CronService::with_catch_up_window`] to skip runs that
+    /// are too stale to be worth delivering.
     RunOnce,
 }
 
@@ -239,6 +242,7 @@ pub struct CronService {
     running: Arc<AtomicBool>,
     handle: Arc<RwLock<Option<JoinHandle<()>>>>,
     jitter_ms: u64,
+    catch_up_window_ms: i64,
 }
 
 impl CronService {
@@ -256,9 +260,17 @@ impl CronService {
             running: Arc::new(AtomicBool::new(false)),
             handle: Arc::new(RwLock::new(None)),
             jitter_ms,
+            catch_up_window_ms: 0,
         }
     }
 
+    /// Only catch up on missed runs that were due within the last
+    /// `secs` seconds; older misses are skipped. `0` means no limit.
+    pub fn with_catch_up_window(mut self, secs: u64) -> Self {
+        self.catch_up_window_ms = i64::try_from(secs.saturating_mul(1000)).unwrap_or(i64::MAX);
+        self
+    }
+
     /// Start scheduler loop (idempotent).
     pub async fn start(&self, on_miss: &OnMiss) -> Result<()> {
         if self.running.swap(true, Ordering::SeqCst) {
@@ -286,7 +298,15 @@ impl CronService {
                                     // recently (crash between dispatch and save),
                                     // skip to avoid duplicate delivery.
                                     let already_ran = should_skip_missed_dispatch(job, next);
-                                    if already_ran {
+                                    let too_stale = self.catch_up_window_ms > 0
+                                        && now - next > self.catch_up_window_ms;
+                                    if too_stale {
+                                        info!(
+                                            job_id = %job.id,
+                                            job_name = %job.name,
+                                            "Skipping missed schedule (outside catch-up window)"
+                                        );
+                                    } else if already_ran {
                                         info!(
                                             job_id = %job.id,
                                             job_name = %job.name,
@@ -766,6 +786,54 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_start_run_once_respects_catch_up_window() {
+        let temp = tempdir().unwrap();
+        let bus = Arc::new(MessageBus::new());
+        let store_path = temp.path().join("jobs.json");
+
+        let recent = now_ms() - 30_000;
+        let job = |id: &str, message: &str, next: i64| {
+            serde_json::json!({
+                "id": id,
+                "name": id,
+                "enabled": true,
+                "schedule": { "kind": "every", "every_ms": 3_600_000 },
+                "payload": { "message": message, "channel": "cli", "chat_id": "cli" },
+                "state": { "next_run_at_ms": next },
+                "created_at_ms": 1,
+                "updated_at_ms": 1,
+                "delete_after_run": false
+            })
+        };
+        let json = serde_json::json!({
+            "version": 1,
+            "jobs": [job("stale", "stale_run", 1), job("recent", "recent_run", recent)]
+        });
+        tokio::fs::write(&store_path, serde_json::to_string_pretty(&json).unwrap())
+            .await
+            .unwrap();
+
+        let service = CronService::new(store_path, bus.clone()).with_catch_up_window(600);
+        service.start(&OnMiss::RunOnce).await.unwrap();
+        service.stop().await;
+
+        let msg = tokio::time::timeout(std::time::Duration::from_secs(2), bus.consume_inbound())
+            .await
+            .expect("recent missed job should be dispatched")
+            .expect("bus should have a message");
+        assert_eq!(msg.content, "recent_run");
+        let extra =
+            tokio::time::timeout(std::time::Duration::from_millis(100), bus.consume_inbound())
+                .await;
+        assert!(extra.is_err(), "stale missed job should not be dispatched");
+
+        // Both jobs are rescheduled to the future.
+        for job in service.list_jobs(true).await {
+            assert!(job.state.next_run_at_ms.unwrap() > now_ms());
+        }
+    }
+
     #[test]
     fn test_error_backoff_schedule() {
         assert_eq!(error_backoff_ms(0), 0);
@@ -5,8 +5,14 @@
 //! `Error` trait implementations.
 
 use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use thiserror::Error;
 
+/// Start of the note a provider error message carries when the server sent
+/// a `Retry-After` header, e.g. `" [retry after 12.5s]"`.
+const RETRY_AFTER_NOTE: &str = " [retry after ";
+
 // ============================================================================
 // Provider Error Classification
 // ============================================================================
@@ -96,6 +102,70 @@ impl ProviderError {
         )
     }
 
+    /// Note the server's `Retry-After` wait on a retryable error, so a retry
+    /// layer can honor it (see [`retry_after`](Self::retry_after)). Other
+    /// errors are returned unchanged.
+    pub fn with_retry_after(self, retry_after: Option<Duration>) -> Self {
+        let Some(wait) = retry_after.filter(|_| self.is_retryable()) else {
+            return self;
+        };
+        let note = |msg: String| format!("{}{}{}s]", msg, RETRY_AFTER_NOTE, wait.as_secs_f64());
+        match self {
+            ProviderError::RateLimit(msg) => ProviderError::RateLimit(note(msg)),
+            ProviderError::ServerError(msg) => ProviderError::ServerError(note(msg)),
+            ProviderError::Timeout(msg) => ProviderError::Timeout(note(msg)),
+            ProviderError::Overloaded(msg) => ProviderError::Overloaded(note(msg)),
+            other => other,
+        }
+    }
+
+    /// How long the server asked to wait before retrying, if it said.
+    pub fn retry_after(&self) -> Option<Duration> {
+        let msg = match self {
+            ProviderError::RateLimit(msg)
+            | ProviderError::ServerError(msg)
+            | ProviderError::Timeout(msg)
+            | ProviderError::Overloaded(msg) => msg,
+            _ => return None,
+        };
+        let start = msg.rfind(RETRY_AFTER_NOTE)? + RETRY_AFTER_NOTE.len();
+        let secs: f64 = msg[start..].strip_suffix("s]")?.parse().ok()?;
+        Duration::try_from_secs_f64(secs).ok()
+    }
+
+    /// A short explanation for the user of a request that failed with this
+    /// error, without the provider's raw response.
+    pub fn user_message(&self) -> &'static str {
+        match self {
+            ProviderError::Auth(_) => {
+                "The AI provider rejected the API key. Please check the provider configuration."
+            }
+            ProviderError::Billing(_) => {
+                "The AI provider account has a billing problem, so I can't answer right now."
+            }
+            ProviderError::RateLimit(_) => {
+                "The AI provider is rate limiting requests right now. Please try again in a minute."
+            }
+            ProviderError::ServerError(_) | ProviderError::Overloaded(_) => {
+                "The AI provider is overloaded or having problems right now. Please try again in a few minutes."
+            }
+            ProviderError::Timeout(_) => {
+                "The AI provider took too long to respond. Please try again."
+            }
+            ProviderError::ModelNotFound(_) => {
+                "The configured model isn't available from the AI provider. Please check the model setting."
+            }
+            ProviderError::ContextOverflow(_) => {
+                "This conversation is too long for the model. Try /new to start a fresh one."
+            }
+            ProviderError::InvalidRequest(_)
+            | ProviderError::Format(_)
+            | ProviderError::Unknown(_) => {
+                "The AI provider couldn't handle this request. Please try again or rephrase it."
+            }
+        }
+    }
+
     /// Returns the HTTP status code associated with this error, if applicable.
     pub fn status_code(&self) -> Option<u16> {
         match self {
@@ -152,6 +222,19 @@ pub enum ZeptoError {
     #[error("Session error: {0}")]
     Session(String),
 
+    /// Session persistence failure, with the session and file involved.
+    #[error("Session {op} failed{} ({}): {source}", session_key_context(.key), .path.display())]
+    SessionIo {
+        /// Operation that failed.
+        op: SessionOp,
+        /// Session key, when the failure concerns a single session.
+        key: Option<String>,
+        /// Session file, or the sessions directory for `list`.
+        path: PathBuf,
+        /// Underlying I/O or serialization error.
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
     /// Standard I/O errors
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -195,6 +278,74 @@ pub enum ZeptoError {
     /// Provider quota exceeded and the configured action is "reject" (no fallback).
     #[error("Quota rejected: {0}")]
     QuotaRejected(String),
+
+    /// A cross-process lock could not be acquired within the configured timeout.
+    #[error("Locked: {0}")]
+    Locked(String),
+
+    /// The operation was stopped because its turn was cancelled.
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+
+    /// The model's answer did not match the requested output format.
+    #[error("Invalid output: {0}")]
+    InvalidOutput(String),
+
+    /// An agent hook refused the message.
+    #[error("Rejected: {0}")]
+    Rejected(String),
+
+    /// An agent hook failed (e.g. panicked).
+    #[error("Hook error: {0}")]
+    Hook(String),
+
+    /// The channel delivered a message the session has already seen.
+    #[error("Duplicate message: {0}")]
+    Duplicate(String),
+}
+
+/// Session persistence operation, reported by [`ZeptoError::SessionIo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionOp {
+    Load,
+    Save,
+    Delete,
+    List,
+}
+
+impl fmt::Display for SessionOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            SessionOp::Load => "load",
+            SessionOp::Save => "save",
+            SessionOp::Delete => "delete",
+            SessionOp::List => "list",
+        };
+        f.write_str(op)
+    }
+}
+
+fn session_key_context(key: &Option<String>) -> String {
+    key.as_deref()
+        .map(|k| format!(" for '{}'", k))
+        .unwrap_or_default()
+}
+
+impl ZeptoError {
+    /// Wrap a session persistence error with the operation, key, and path.
+    pub fn session_io(
+        op: SessionOp,
+        key: Option<&str>,
+        path: &Path,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        ZeptoError::SessionIo {
+            op,
+            key: key.map(str::to_string),
+            path: path.to_path_buf(),
+            source: source.into(),
+        }
+    }
 }
 
 /// A specialized `Result` type for ZeptoClaw operations.
@@ -242,6 +393,41 @@ mod tests {
         let _ = ZeptoError::Mcp("test".into());
         let _ = ZeptoError::QuotaExceeded("test".into());
         let _ = ZeptoError::QuotaRejected("test".into());
+        let _ = ZeptoError::Locked("test".into());
+        let _ = ZeptoError::Cancelled("test".into());
+        let _ = ZeptoError::InvalidOutput("test".into());
+        let _ = ZeptoError::Rejected("test".into());
+        let _ = ZeptoError::Hook("test".into());
+        let _ = ZeptoError::Duplicate("test".into());
+        let _ = ZeptoError::session_io(
+            SessionOp::Load,
+            Some("test"),
+            Path::new("test.json"),
+            "test",
+        );
+    }
+
+    #[test]
+    fn test_session_io_display_includes_context() {
+        let io_err = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        let err = ZeptoError::session_io(
+            SessionOp::Save,
+            Some("telegram:42"),
+            Path::new("/data/sessions/telegram%3A42.json"),
+            io_err,
+        );
+        assert_eq!(
+            err.to_string(),
+            "Session save failed for 'telegram:42' (/data/sessions/telegram%3A42.json): denied"
+        );
+        assert!(std::error::Error::source(&err).is_some());
+
+        let err =
+            ZeptoError::session_io(SessionOp::List, None, Path::new("/data/sessions"), "gone");
+        assert_eq!(
+            err.to_string(),
+            "Session list failed (/data/sessions): gone"
+        );
     }
 
     #[test]
@@ -318,6 +504,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_provider_error_retry_after_round_trip() {
+        let wait = Some(Duration::from_millis(1500));
+        let err = ProviderError::RateLimit("slow down".into()).with_retry_after(wait);
+        assert_eq!(err.retry_after(), wait);
+        assert!(err.to_string().starts_with("Rate limit error: slow down"));
+
+        // No header, or an error that is never retried: nothing recorded.
+        assert_eq!(ProviderError::ServerError("500".into()).retry_after(), None);
+        let err = ProviderError::Auth("401".into()).with_retry_after(wait);
+        assert_eq!(err.retry_after(), None);
+        assert_eq!(err.to_string(), "Authentication error: 401");
+    }
+
     #[test]
     fn test_provider_error_should_fallback() {
         // Should fallback
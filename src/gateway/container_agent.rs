@@ -336,7 +336,7 @@ impl ContainerAgentProxy {
 
                 match response.result {
                     AgentResult::Success { content, session } => {
-                        self.persist_session_snapshot(&message.session_key, session)
+                        self.persist_session_snapshot(&message.session_key, session.map(|s| *s))
                             .await;
                         OutboundMessage::new(&message.channel, &message.chat_id, &content)
                     }
@@ -92,7 +92,7 @@ pub enum AgentResult {
         /// Response content
         content: String,
         /// Updated session state
-        session: Option<Session>,
+        session: Option<Box<Session>>,
     },
     /// Processing failed
     Error {
@@ -110,7 +110,7 @@ impl AgentResponse {
             request_id: request_id.to_string(),
             result: AgentResult::Success {
                 content: content.to_string(),
-                session,
+                session: session.map(Box::new),
             },
             usage: None,
         }
@@ -332,9 +332,11 @@ impl HealthRegistry {
             let input_tokens = m.input_tokens.load(Ordering::Relaxed);
             let output_tokens = m.output_tokens.load(Ordering::Relaxed);
             let errors = m.errors.load(Ordering::Relaxed);
+            let in_flight_turns = m.in_flight_turns.load(Ordering::Relaxed);
+            let queued_turns = m.queued_turns.load(Ordering::Relaxed);
             json.push_str(&format!(
-                ",\"usage\":{{\"requests\":{},\"tool_calls\":{},\"input_tokens\":{},\"output_tokens\":{},\"errors\":{}}}",
-                requests, tool_calls, input_tokens, output_tokens, errors
+                ",\"usage\":{{\"requests\":{},\"tool_calls\":{},\"input_tokens\":{},\"output_tokens\":{},\"errors\":{},\"in_flight_turns\":{},\"queued_turns\":{}}}",
+                requests, tool_calls, input_tokens, output_tokens, errors, in_flight_turns, queued_turns
             ));
         }
 
@@ -366,6 +368,10 @@ pub struct UsageMetrics {
     pub output_tokens: AtomicU64,
     /// Total errors encountered.
     pub errors: AtomicU64,
+    /// Turns running now.
+    pub in_flight_turns: AtomicU64,
+    /// Turns waiting for a slot under `agents.defaults.concurrency`.
+    pub queued_turns: AtomicU64,
     /// Whether the gateway is ready to accept requests.
     pub ready: AtomicBool,
 }
@@ -379,6 +385,8 @@ impl UsageMetrics {
             input_tokens: AtomicU64::new(0),
             output_tokens: AtomicU64::new(0),
             errors: AtomicU64::new(0),
+            in_flight_turns: AtomicU64::new(0),
+            queued_turns: AtomicU64::new(0),
             ready: AtomicBool::new(false),
         }
     }
@@ -404,6 +412,12 @@ impl UsageMetrics {
         self.errors.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Set the number of turns running and waiting.
+    pub fn set_turn_load(&self, in_flight: u64, queued: u64) {
+        self.in_flight_turns.store(in_flight, Ordering::Relaxed);
+        self.queued_turns.store(queued, Ordering::Relaxed);
+    }
+
     /// Set the ready flag.
     pub fn set_ready(&self, ready: bool) {
         self.ready.store(ready, Ordering::SeqCst);
@@ -419,6 +433,8 @@ impl UsageMetrics {
             input_tokens = self.input_tokens.load(Ordering::Relaxed),
             output_tokens = self.output_tokens.load(Ordering::Relaxed),
             errors = self.errors.load(Ordering::Relaxed),
+            in_flight_turns = self.in_flight_turns.load(Ordering::Relaxed),
+            queued_turns = self.queued_turns.load(Ordering::Relaxed),
             "Usage metrics"
         );
     }
@@ -573,12 +589,14 @@ pub async fn start_health_server_legacy(
                                     ));
                                 }
                                 parts.push(format!(
-                                    "\"usage\":{{\"requests\":{},\"tool_calls\":{},\"input_tokens\":{},\"output_tokens\":{},\"errors\":{}}}",
+                                    "\"usage\":{{\"requests\":{},\"tool_calls\":{},\"input_tokens\":{},\"output_tokens\":{},\"errors\":{},\"in_flight_turns\":{},\"queued_turns\":{}}}",
                                     metrics.requests.load(Ordering::Relaxed),
                                     metrics.tool_calls.load(Ordering::Relaxed),
                                     metrics.input_tokens.load(Ordering::Relaxed),
                                     metrics.output_tokens.load(Ordering::Relaxed),
                                     metrics.errors.load(Ordering::Relaxed),
+                                    metrics.in_flight_turns.load(Ordering::Relaxed),
+                                    metrics.queued_turns.load(Ordering::Relaxed),
                                 ));
                                 ("200 OK", format!("{{{}}}", parts.join(",")))
                             }
@@ -1221,6 +1239,7 @@ mod tests {
         metrics.record_tool_calls(5);
         metrics.record_tokens(1000, 500);
         metrics.record_error();
+        metrics.set_turn_load(2, 3);
         reg.set_metrics(Arc::clone(&metrics));
 
         let json = reg.render_health_json();
@@ -1229,6 +1248,8 @@ mod tests {
         assert!(json.contains("\"input_tokens\":1000"));
         assert!(json.contains("\"output_tokens\":500"));
         assert!(json.contains("\"errors\":1"));
+        assert!(json.contains("\"in_flight_turns\":2"));
+        assert!(json.contains("\"queued_turns\":3"));
     }
 
     #[test]
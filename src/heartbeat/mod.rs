@@ -3,5 +3,5 @@
 mod service;
 mod template;
 
-pub use service::{HeartbeatResult, HeartbeatService, HEARTBEAT_PROMPT};
+pub use service::{HeartbeatResult, HeartbeatService, HEARTBEAT_OK, HEARTBEAT_PROMPT};
 pub use template::{ensure_heartbeat_file, HEARTBEAT_TEMPLATE};
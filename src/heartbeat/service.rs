@@ -9,9 +9,14 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
 
+use crate::bus::message::HEARTBEAT_KEY;
 use crate::bus::{InboundMessage, MessageBus};
 use crate::error::Result;
 
+/// Reply meaning a heartbeat found nothing to report; it is not sent to the
+/// chat.
+pub const HEARTBEAT_OK: &str = "HEARTBEAT_OK";
+
 /// Prompt sent to the agent when heartbeat is triggered.
 pub const HEARTBEAT_PROMPT: &str = r#"Read HEARTBEAT.md in your workspace (if it exists).
 Follow any actionable items listed there.
@@ -222,7 +227,8 @@ impl HeartbeatService {
             return HeartbeatResult::ok(true, false, false);
         }
 
-        let message = InboundMessage::new(channel, "system", chat_id, HEARTBEAT_PROMPT);
+        let message = InboundMessage::new(channel, "system", chat_id, HEARTBEAT_PROMPT)
+            .with_metadata(HEARTBEAT_KEY, "true");
         match bus.publish_inbound(message).await {
             Ok(_) => {
                 info!("Heartbeat delivered to bus");
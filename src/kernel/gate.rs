@@ -35,7 +35,7 @@ fn audit_specific_action(name: &str) -> Option<AuditAction> {
     match name {
         "shell" | "shell_execute" => Some(AuditAction::ShellExec),
         "web_fetch" | "web_search" => Some(AuditAction::NetworkAccess),
-        "spawn" | "delegate" => Some(AuditAction::AgentSpawn),
+        "spawn" | "delegate" | "spawn_agent" => Some(AuditAction::AgentSpawn),
         _ => None,
     }
 }
@@ -109,6 +109,10 @@ fn scan_tool_input(safety_layer: &SafetyLayer, name: &str, input: &Value) -> Opt
                 .filter_map(|field| input.get(*field).and_then(|value| value.as_str()))
                 .find_map(|content| check(content, &file_body_options))
         }
+        "apply_patch" => input
+            .get("patch")
+            .and_then(|value| value.as_str())
+            .and_then(|patch| check(patch, &file_body_options)),
         _ => {
             let input_str = serde_json::to_string(input).unwrap_or_default();
             check(&input_str, &ScanOptions::default())
@@ -229,8 +233,8 @@ mod tests {
 
     fn setup_filesystem_registry() -> ToolRegistry {
         let mut registry = ToolRegistry::new();
-        registry.register(Box::new(WriteFileTool));
-        registry.register(Box::new(EditFileTool));
+        registry.register(Box::new(WriteFileTool::new()));
+        registry.register(Box::new(EditFileTool::new()));
         registry
     }
 
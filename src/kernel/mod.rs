@@ -159,15 +159,18 @@ impl ZeptoKernel {
 
         // 7. Cron service
         let cron_store_path = Config::dir().join("cron").join("jobs.json");
-        let cron_service = Arc::new(CronService::with_jitter(
-            cron_store_path,
-            bus.clone(),
-            config.routines.jitter_ms,
-        ));
+        let cron_service = Arc::new(
+            CronService::with_jitter(cron_store_path, bus.clone(), config.routines.jitter_ms)
+                .with_catch_up_window(config.routines.catch_up_window_secs),
+        );
         cron_service.start(&config.routines.on_miss).await?;
 
         // 8. Register all tools
-        let mut tools = ToolRegistry::new();
+        let mut tools = ToolRegistry::new()
+            .with_arg_validation(config.tools.argument_validation)
+            .with_rate_limiter(crate::tools::rate_limit::ToolRateLimiter::from_config(
+                &config.tools.rate_limits,
+            ));
         let deps = registrar::ToolDeps {
             runtime,
             bus,
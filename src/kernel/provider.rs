@@ -12,7 +12,8 @@ use crate::auth::{self, AuthMethod};
 use crate::config::Config;
 use crate::providers::{
     provider_config_by_name, resolve_runtime_providers, ClaudeProvider, FallbackProvider,
-    GeminiProvider, LLMProvider, OpenAIProvider, RetryProvider, RuntimeProviderSelection,
+    GeminiProvider, LLMProvider, OllamaProvider, OpenAIProvider, RetryProvider,
+    RuntimeProviderSelection,
 };
 
 /// Build the complete provider chain from config.
@@ -32,8 +33,8 @@ pub async fn build_provider_chain(
 /// Create a provider from a runtime selection entry.
 ///
 /// Maps backend name ("anthropic", "openai") to the corresponding provider
-/// struct, handling Gemini routing, OAuth credentials, and OpenAI-compatible
-/// presets.
+/// struct, handling Gemini, Ollama and Bedrock routing, OAuth credentials, and
+/// OpenAI-compatible presets.
 ///
 /// Moved from `cli/common.rs:139–199`.
 pub async fn provider_from_runtime_selection(
@@ -43,13 +44,14 @@ pub async fn provider_from_runtime_selection(
     match selection.backend {
         "anthropic" => {
             // Use credential-aware constructor when OAuth token is available
-            if selection.credential.is_bearer() {
-                Some(Box::new(ClaudeProvider::with_credential(
-                    selection.credential.clone(),
-                )))
+            let provider = if selection.credential.is_bearer() {
+                ClaudeProvider::with_credential(selection.credential.clone())
             } else {
-                Some(Box::new(ClaudeProvider::new(&selection.api_key)))
-            }
+                ClaudeProvider::new(&selection.api_key)
+            };
+            Some(Box::new(
+                provider.with_prompt_caching(selection.prompt_caching),
+            ))
         }
         "openai" => {
             // Route ALL Gemini selections through the native GeminiProvider, which
@@ -74,6 +76,40 @@ pub async fn provider_from_runtime_selection(
                 return GeminiProvider::from_config(api_key, model, prefer_oauth)
                     .map(|p| Box::new(p) as Box<dyn LLMProvider>);
             }
+            // Ollama gets its native chat API, for keep_alive and models
+            // without tool calling.
+            if selection.name == "ollama" {
+                let host = selection
+                    .api_base
+                    .as_deref()
+                    .unwrap_or(crate::providers::ollama::DEFAULT_OLLAMA_HOST);
+                let provider =
+                    OllamaProvider::new(host).with_keep_alive(selection.keep_alive.clone());
+                return Some(Box::new(provider));
+            }
+            // Bedrock without an api_base proxy calls the Converse API
+            // directly, signed with the AWS credential chain.
+            if selection.name == "bedrock" && selection.api_base.is_none() {
+                #[cfg(feature = "provider-bedrock")]
+                {
+                    let provider = crate::providers::BedrockProvider::from_env(
+                        selection.region.as_deref(),
+                        selection.model.as_deref(),
+                    )
+                    .await;
+                    return Some(Box::new(provider));
+                }
+                #[cfg(not(feature = "provider-bedrock"))]
+                {
+                    tracing::warn!(
+                        "Bedrock provider is configured but this build was compiled without the \
+                         `provider-bedrock` feature. Rebuild with `cargo build --release --features \
+                         provider-bedrock`, or set providers.bedrock.api_base to an \
+                         OpenAI-compatible SigV4 proxy."
+                    );
+                    return None;
+                }
+            }
             let api_base = match selection.api_base.as_deref() {
                 Some(base) => base,
                 None if selection.name == "openai" => "https://api.openai.com/v1",
@@ -383,11 +419,11 @@ mod tests {
     }
 
     #[test]
-    fn test_retry_wrapper_disabled_by_default() {
+    fn test_retry_wrapper_enabled_by_default() {
         let config = Config::default();
         assert!(
-            !config.providers.retry.enabled,
-            "Retry should be disabled by default"
+            config.providers.retry.enabled,
+            "Retry should be enabled by default"
         );
     }
 
@@ -7,7 +7,7 @@ use std::collections::HashSet;
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use tracing::{info, warn};
+use tracing::{error, info, warn};
 
 use crate::bus::MessageBus;
 use crate::config::templates::AgentTemplate;
@@ -230,13 +230,24 @@ pub async fn register_all_tools(
         registry.register(Box::new(ReadFileTool));
     }
     if filter.is_enabled("write_file") {
-        registry.register(Box::new(WriteFileTool));
+        registry.register(Box::new(
+            WriteFileTool::new().with_max_file_bytes(config.tools.write_file.max_file_bytes),
+        ));
     }
     if filter.is_enabled("list_dir") {
-        registry.register(Box::new(ListDirTool));
+        registry.register(Box::new(
+            ListDirTool::new()
+                .with_ignore(config.tools.list_dir.ignore.clone())
+                .with_max_depth(config.tools.list_dir.max_depth),
+        ));
     }
     if filter.is_enabled("edit_file") {
-        registry.register(Box::new(EditFileTool));
+        registry.register(Box::new(
+            EditFileTool::new().with_max_file_bytes(config.tools.write_file.max_file_bytes),
+        ));
+    }
+    if filter.is_enabled("apply_patch") {
+        registry.register(Box::new(crate::tools::ApplyPatchTool));
     }
 
     // --- Group 1b: Coding tools (default-off, enabled by "coding" template tag) ---
@@ -253,7 +264,9 @@ pub async fn register_all_tools(
     let coding_tools_on =
         coding_profile_active || has_explicit_profile || config.tools.coding_tools;
     if coding_tools_on && filter.is_enabled("grep") {
-        registry.register(Box::new(crate::tools::grep::GrepTool));
+        registry.register(Box::new(
+            crate::tools::grep::GrepTool::new().with_ignore(config.tools.list_dir.ignore.clone()),
+        ));
         info!("Registered grep tool (coding profile)");
     }
     if coding_tools_on && filter.is_enabled("find") {
@@ -262,19 +275,60 @@ pub async fn register_all_tools(
     }
 
     // --- Group 2: Runtime-dependent ---
-    if filter.is_enabled("shell") {
-        registry.register(Box::new(ShellTool::with_security_and_runtime(
-            shell_config.clone(),
-            Arc::clone(&deps.runtime),
-        )));
+    // Shared by shell and process. Fail closed: a broken policy must not
+    // leave either unrestricted.
+    let policy_config = &config.tools.shell.policy;
+    let shell_policy = match crate::security::ShellPolicy::from_config(policy_config) {
+        Ok(policy) => Some(policy),
+        Err(e) => {
+            error!("Shell and process tools disabled: {}", e);
+            None
+        }
+    };
+    if let Some(policy) = shell_policy.clone().filter(|_| filter.is_enabled("shell")) {
+        let mut tool =
+            ShellTool::with_security_and_runtime(shell_config.clone(), Arc::clone(&deps.runtime))
+                .with_max_output_bytes(config.tools.shell.max_output_bytes)
+                .with_policy(policy);
+        if let Some(shell) = config.tools.shell.default_shell {
+            tool = tool.with_shell(shell);
+        }
+        if policy_config.mode == crate::config::ShellPolicyMode::Ask {
+            match crate::tools::HumanApprovalTool::from_config(
+                &config.tools.human_approval,
+                config.channels.telegram.as_ref().map(|t| t.token.as_str()),
+            ) {
+                Some(approver) => tool = tool.with_approver(Arc::new(approver)),
+                None => warn!(
+                    "Shell policy mode is 'ask' but human approval is not configured; \
+                     unlisted commands will be refused"
+                ),
+            }
+        }
+        registry.register(Box::new(tool));
+    }
+    if let Some(policy) = shell_policy.filter(|_| filter.is_enabled("process")) {
+        // Background processes run on the host, outside any container runtime.
+        if deps.runtime.name() == "native" {
+            registry.register(Box::new(
+                crate::tools::ProcessTool::with_security(shell_config.clone()).with_policy(policy),
+            ));
+            info!("Registered process tool");
+        } else {
+            info!(
+                runtime = deps.runtime.name(),
+                "Skipping process tool: it cannot run inside a container runtime"
+            );
+        }
     }
 
     // --- Group 3: Git ---
     if filter.is_enabled("git") {
         if crate::tools::GitTool::is_available() {
-            registry.register(Box::new(crate::tools::GitTool::with_security(
-                shell_config.clone(),
-            )));
+            registry.register(Box::new(
+                crate::tools::GitTool::with_security(shell_config.clone())
+                    .with_config(config.tools.git.clone()),
+            ));
             info!("Registered git tool");
         } else {
             tracing::debug!("git binary not found, skipping git tool");
@@ -373,6 +427,19 @@ pub async fn register_all_tools(
     }
 
     // --- Group 5: HTTP request ---
+    if config.tools.http_fetch.enabled && filter.is_enabled("http_fetch") {
+        registry.register(Box::new(crate::tools::HttpFetchTool::new(
+            config.tools.http_fetch.clone(),
+        )));
+        info!("Registered http_fetch tool");
+    }
+    if config.tools.download.enabled && filter.is_enabled("download") {
+        registry.register(Box::new(crate::tools::DownloadTool::new(
+            config.tools.http_fetch.clone(),
+            config.tools.download.clone(),
+        )));
+        info!("Registered download tool");
+    }
     if filter.is_enabled("http_request") {
         if let Some(http_cfg) = &config.tools.http_request {
             if !http_cfg.allowed_domains.is_empty() {
@@ -386,6 +453,13 @@ pub async fn register_all_tools(
         }
     }
 
+    if filter.is_enabled("container_registry") {
+        registry.register(Box::new(crate::tools::ContainerRegistryTool::new(
+            config.tools.container_registry.clone(),
+        )));
+        info!("Registered container_registry tool");
+    }
+
     // --- Group 6: Document tools ---
     if filter.is_enabled("pdf_read") {
         let workspace_str = config.workspace_path().to_string_lossy().into_owned();
@@ -397,6 +471,29 @@ pub async fn register_all_tools(
         registry.register(Box::new(crate::tools::DocxReadTool::new(workspace_str)));
         info!("Registered docx_read tool");
     }
+    if filter.is_enabled("extract_text") {
+        let workspace_str = config.workspace_path().to_string_lossy().into_owned();
+        registry.register(Box::new(crate::tools::ExtractTextTool::new(
+            workspace_str,
+            config.tools.extract_text.clone(),
+        )));
+        info!("Registered extract_text tool");
+    }
+    if filter.is_enabled("archive") {
+        registry.register(Box::new(crate::tools::ArchiveTool::new(
+            config.tools.archive.clone(),
+        )));
+        info!("Registered archive tool");
+    }
+    #[cfg(feature = "tool-sqlite")]
+    if filter.is_enabled("sqlite_query") {
+        let workspace_str = config.workspace_path().to_string_lossy().into_owned();
+        registry.register(Box::new(crate::tools::SqliteQueryTool::new(
+            workspace_str,
+            config.tools.sqlite.clone(),
+        )));
+        info!("Registered sqlite_query tool");
+    }
 
     // --- Group 7: Channel/messaging tools ---
     if filter.is_enabled("message") {
@@ -421,6 +518,16 @@ pub async fn register_all_tools(
         }
     }
 
+    if filter.is_enabled("human_approval") {
+        if let Some(tool) = crate::tools::HumanApprovalTool::from_config(
+            &config.tools.human_approval,
+            config.channels.telegram.as_ref().map(|t| t.token.as_str()),
+        ) {
+            registry.register(Box::new(tool));
+            info!("Registered human_approval tool");
+        }
+    }
+
     // --- Group 8: Google tools ---
     if filter.is_enabled("google_sheets") {
         if let Some(access_token) = config.tools.google_sheets.access_token.as_deref() {
@@ -475,6 +582,16 @@ pub async fn register_all_tools(
                 warn!("longterm_memory tool enabled but LTM failed to initialize");
             }
         }
+        if filter.is_enabled("scratchpad") {
+            match crate::memory::scratchpad::ScratchpadStore::new(
+                config.memory.scratchpad_max_bytes,
+            ) {
+                Ok(store) => {
+                    registry.register(Box::new(crate::tools::ScratchpadTool::new(Arc::new(store))));
+                }
+                Err(e) => warn!("scratchpad tool enabled but store failed to load: {}", e),
+            }
+        }
         info!(
             "Registered memory tools (backend: {})",
             deps.memory_searcher.name()
@@ -482,11 +599,30 @@ pub async fn register_all_tools(
     } else {
         info!("Memory tools are disabled");
     }
+    if filter.is_enabled("knowledge_graph") {
+        registry.register(Box::new(crate::tools::KnowledgeGraphTool::new()));
+        info!("Registered knowledge_graph tool");
+    }
 
     // --- Group 10: Interaction tools ---
     if filter.is_enabled("ask_clarification") {
         registry.register(Box::new(crate::tools::clarification::AskClarificationTool));
     }
+    if filter.is_enabled("ask_user") {
+        registry.register(Box::new(crate::tools::AskUserTool::new(
+            Arc::clone(&deps.bus),
+            &config.tools.ask_user,
+        )));
+    }
+    if filter.is_enabled("conditional") {
+        registry.register(Box::new(crate::tools::ConditionalTool));
+    }
+    if filter.is_enabled("calc") {
+        registry.register(Box::new(crate::tools::CalcTool));
+    }
+    if filter.is_enabled("semver") {
+        registry.register(Box::new(crate::tools::SemanticVersionTool));
+    }
 
     // --- Group 11: Scheduling/cron ---
     if filter.is_enabled("cron") {
@@ -527,24 +663,43 @@ pub async fn register_all_tools(
         }
     }
 
-    // --- Group 12: Transcription ---
-    if config.tools.transcribe.enabled {
-        if let Some(api_key) = &config.tools.transcribe.groq_api_key {
-            if filter.is_enabled("transcribe") {
-                match crate::tools::TranscribeTool::new(api_key, &config.tools.transcribe.model) {
-                    Ok(tool) => {
-                        registry.register(Box::new(tool));
-                        info!(
-                            "Registered transcribe tool (model: {})",
-                            config.tools.transcribe.model
-                        );
-                    }
-                    Err(e) => warn!("Failed to initialize transcribe tool: {}", e),
-                }
+    // --- Group 12: Transcription and speech ---
+    if config.tools.transcribe.enabled && filter.is_enabled("transcribe") {
+        match crate::tools::TranscribeTool::from_config(&config.tools.transcribe) {
+            Ok(tool) => {
+                info!("Registered transcribe tool ({})", tool.backend_label());
+                registry.register(Box::new(tool));
             }
+            Err(e) => warn!("Failed to initialize transcribe tool: {}", e),
+        }
+    }
+    if config.tools.speak.enabled && filter.is_enabled("speak") {
+        match crate::tools::SpeakTool::new(config.tools.speak.clone()) {
+            Ok(tool) => {
+                registry.register(Box::new(tool));
+                info!(
+                    "Registered speak tool (model: {}, voice: {})",
+                    config.tools.speak.model, config.tools.speak.voice
+                );
+            }
+            Err(e) => warn!("Failed to initialize speak tool: {}", e),
         }
     }
 
+    // --- Group 12b: Python execution ---
+    if config.tools.run_python.enabled && filter.is_enabled("run_python") {
+        let tool = crate::tools::RunPythonTool::new(
+            config.tools.run_python.clone(),
+            config.tools.shell.max_output_bytes,
+        );
+        info!(
+            "Registered run_python tool ({}, sandbox: {:?})",
+            config.tools.run_python.interpreter,
+            tool.sandbox()
+        );
+        registry.register(Box::new(tool));
+    }
+
     // --- Group 13: Reminders ---
     if filter.is_enabled("reminder") {
         match crate::tools::reminder::ReminderTool::new(Some(Arc::clone(&deps.cron_service))) {
@@ -667,6 +822,24 @@ pub async fn register_all_tools(
             }
             Err(e) => warn!(error = %e, "Plugin discovery failed"),
         }
+
+        #[cfg(feature = "wasm-plugins")]
+        for tool in
+            crate::tools::wasm_plugin::discover_wasm_plugins(&plugin_dirs, &config.plugins.wasm)
+        {
+            let name = tool.name().to_string();
+            if !config.plugins.is_plugin_permitted(&name) || !filter.is_enabled(&name) {
+                info!(tool = %name, "WASM plugin skipped by config");
+                continue;
+            }
+            if registry.has(&name) {
+                warn!(tool = %name, "WASM plugin name conflicts with an existing tool, skipping");
+                continue;
+            }
+            registry.register(Box::new(tool));
+            external_tool_names.insert(name.clone());
+            info!(tool = %name, "Registered WASM plugin tool");
+        }
     }
 
     // --- Group 17: Composed tools ---
@@ -703,6 +876,29 @@ pub async fn register_all_tools(
         info!(tool = %tool_def.name, "Registered custom CLI tool");
     }
 
+    // --- Group 18b: Script tools from tools.custom ---
+    // Unlike custom_tools, a broken definition fails startup.
+    for tool_def in &config.tools.custom {
+        if !filter.is_enabled(&tool_def.name) {
+            continue;
+        }
+        if registry.has(&tool_def.name) {
+            anyhow::bail!(
+                "tools.custom tool '{}': name conflicts with an existing tool",
+                tool_def.name
+            );
+        }
+        let tool = crate::tools::script::ScriptTool::from_def(
+            tool_def,
+            Arc::clone(&deps.runtime),
+            shell_config.clone(),
+            config.tools.shell.max_output_bytes,
+        )?;
+        registry.register(Box::new(tool));
+        external_tool_names.insert(tool_def.name.clone());
+        info!(tool = %tool_def.name, "Registered custom script tool");
+    }
+
     // --- Group 19: MCP server tools (async) ---
     let mut mcp_clients: Vec<Arc<McpClient>> = Vec::new();
     {
@@ -783,11 +979,10 @@ pub async fn register_all_tools(
                 Ok(tools) => {
                     let mut registered_count = 0usize;
                     for tool in tools {
-                        let prefixed_name = format!("{}_{}", server.name, tool.name);
-                        if !filter.is_enabled(&prefixed_name) {
+                        let prefixed = McpToolWrapper::exposed_name(&server.name, &tool.name);
+                        if !filter.is_enabled(&prefixed) {
                             continue;
                         }
-                        let prefixed = format!("{}_{}", server.name, tool.name);
                         registry.register(Box::new(McpToolWrapper::new(
                             &server.name,
                             &tool.name,
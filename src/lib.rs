@@ -53,7 +53,7 @@ pub use channels::{
 };
 pub use config::Config;
 pub use cron::{CronJob, CronPayload, CronSchedule, CronService, OnMiss};
-pub use error::{ProviderError, Result, ZeptoError};
+pub use error::{ProviderError, Result, SessionOp, ZeptoError};
 pub use heartbeat::{ensure_heartbeat_file, HeartbeatResult, HeartbeatService, HEARTBEAT_PROMPT};
 pub use providers::{
     ChatOptions, ClaudeProvider, LLMProvider, LLMResponse, LLMToolCall, OpenAIProvider,
@@ -81,7 +81,7 @@ pub use health::{
 pub use runtime::AppleContainerRuntime;
 pub use security::{
     validate_extra_mounts, validate_path_in_workspace, AgentMode, AgentModeConfig,
-    CategoryPermission, DeviceInfo, ModePolicy, PairedDevice, PairingManager, SafePath,
+    CategoryPermission, DeviceInfo, ModePolicy, PairedDevice, PairingManager, SafePath, Sandbox,
     ShellAllowlistMode, ShellSecurityConfig,
 };
 pub use session::{Message, Role, Session, SessionManager, ToolCall};
@@ -93,8 +93,11 @@ pub use tools::AndroidTool;
 pub use tools::GoogleTool;
 pub use tools::{
     composed::CreateToolTool, cron::CronTool, custom::CustomTool, delegate::DelegateTool,
-    spawn::SpawnTool, BinaryPluginTool, BrowserTool, DocxReadTool, EchoTool, FindTool, GitTool,
-    GoogleSheetsTool, GrepTool, HardwareTool, HttpRequestTool, MemoryGetTool, MemorySearchTool,
-    MessageTool, PdfReadTool, ProjectTool, R8rTool, ReminderTool, SearxngSearchTool, StripeTool,
-    Tool, ToolCategory, ToolContext, ToolRegistry, WebFetchTool, WebSearchTool, WhatsAppTool,
+    session_memory::SessionMemoryTool, spawn::SpawnTool, BinaryPluginTool, BrowserTool,
+    ConditionalTool, ContainerRegistryTool, DocxReadTool, EchoTool, ExtractTextTool, FindTool,
+    GitTool, GoogleSheetsTool, GrepTool, HardwareTool, HttpFetchTool, HttpRequestTool,
+    HumanApprovalTool, KnowledgeGraphTool, MemoryGetTool, MemorySearchTool, MessageTool,
+    PdfReadTool, ProcessTool, ProjectTool, R8rTool, ReminderTool, SearxngSearchTool,
+    SemanticVersionTool, StripeTool, Tool, ToolCategory, ToolContext, ToolProgress, ToolRegistry,
+    TranscriptFormatterTool, WebFetchTool, WebSearchTool, WhatsAppTool,
 };
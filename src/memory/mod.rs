@@ -10,6 +10,7 @@ pub mod factory;
 pub mod hnsw_searcher;
 pub mod hygiene;
 pub mod longterm;
+pub mod scratchpad;
 pub mod snapshot;
 pub mod traits;
 
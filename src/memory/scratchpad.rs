@@ -0,0 +1,247 @@
+//! Scratchpad store — namespaced key-value memory shared across sessions.
+//!
+//! Unlike long-term memory, entries carry no category, tags or decay: the
+//! store is a plain map the agent reads and writes by key. Keys are scoped to
+//! a namespace (by default the channel name), so every Telegram chat shares
+//! one scratchpad that is separate from CLI sessions. Each namespace has a
+//! byte quota counted over its keys and values. Stored as a single JSON file
+//! at `~/.zeptoclaw/memory/scratchpad.json`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use tokio::sync::RwLock;
+
+use crate::config::Config;
+use crate::error::{Result, ZeptoError};
+
+type Namespaces = HashMap<String, BTreeMap<String, String>>;
+
+/// Persistent namespaced key-value store.
+#[derive(Debug)]
+pub struct ScratchpadStore {
+    entries: RwLock<Namespaces>,
+    /// `None` keeps entries in memory only (tests).
+    storage_path: Option<PathBuf>,
+    max_bytes: usize,
+}
+
+/// Bytes an entry counts against its namespace quota.
+fn entry_size(key: &str, value: &str) -> usize {
+    key.len() + value.len()
+}
+
+impl ScratchpadStore {
+    /// Open the store at the default path, allowing `max_bytes` per namespace.
+    pub fn new(max_bytes: usize) -> Result<Self> {
+        Self::with_path(
+            Config::dir().join("memory").join("scratchpad.json"),
+            max_bytes,
+        )
+    }
+
+    /// Open the store at a custom path, loading any existing entries.
+    pub fn with_path(path: PathBuf, max_bytes: usize) -> Result<Self> {
+        let entries = Self::load(&path)?;
+        Ok(Self {
+            entries: RwLock::new(entries),
+            storage_path: Some(path),
+            max_bytes,
+        })
+    }
+
+    /// Create a store that is never written to disk.
+    pub fn new_memory(max_bytes: usize) -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+            storage_path: None,
+            max_bytes,
+        }
+    }
+
+    /// Per-namespace quota in bytes.
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Bytes currently used by `namespace`.
+    pub async fn usage(&self, namespace: &str) -> usize {
+        self.entries
+            .read()
+            .await
+            .get(namespace)
+            .map(|map| map.iter().map(|(k, v)| entry_size(k, v)).sum())
+            .unwrap_or(0)
+    }
+
+    /// Insert or replace `key`. Returns `true` when an existing value was
+    /// replaced. Fails without changing anything if the namespace would
+    /// exceed its quota.
+    pub async fn set(&self, namespace: &str, key: &str, value: &str) -> Result<bool> {
+        let mut entries = self.entries.write().await;
+        let map = entries.get(namespace);
+        let used: usize = map
+            .map(|m| m.iter().map(|(k, v)| entry_size(k, v)).sum())
+            .unwrap_or(0);
+        let previous = map
+            .and_then(|m| m.get(key))
+            .map_or(0, |v| entry_size(key, v));
+        let needed = used - previous + entry_size(key, value);
+        if needed > self.max_bytes {
+            return Err(ZeptoError::Tool(format!(
+                "Scratchpad quota exceeded: namespace '{}' would use {} of {} bytes. \
+                 Delete unused keys or store a shorter value.",
+                namespace, needed, self.max_bytes
+            )));
+        }
+
+        let replaced = entries
+            .entry(namespace.to_string())
+            .or_default()
+            .insert(key.to_string(), value.to_string())
+            .is_some();
+        self.save(&entries).await?;
+        Ok(replaced)
+    }
+
+    /// Value stored under `key`.
+    pub async fn get(&self, namespace: &str, key: &str) -> Option<String> {
+        self.entries
+            .read()
+            .await
+            .get(namespace)
+            .and_then(|m| m.get(key).cloned())
+    }
+
+    /// Keys and values whose key starts with `prefix`, in key order.
+    pub async fn list(&self, namespace: &str, prefix: &str) -> Vec<(String, String)> {
+        self.entries
+            .read()
+            .await
+            .get(namespace)
+            .map(|m| {
+                m.range(prefix.to_string()..)
+                    .take_while(|(k, _)| k.starts_with(prefix))
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Remove `key`. Returns `true` if it existed.
+    pub async fn delete(&self, namespace: &str, key: &str) -> Result<bool> {
+        let mut entries = self.entries.write().await;
+        let Some(map) = entries.get_mut(namespace) else {
+            return Ok(false);
+        };
+        if map.remove(key).is_none() {
+            return Ok(false);
+        }
+        if map.is_empty() {
+            entries.remove(namespace);
+        }
+        self.save(&entries).await?;
+        Ok(true)
+    }
+
+    /// Write all namespaces to disk via a temp file and rename.
+    async fn save(&self, entries: &Namespaces) -> Result<()> {
+        let Some(ref path) = self.storage_path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let json = serde_json::to_string_pretty(entries)?;
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, json).await?;
+        tokio::fs::rename(&tmp, path).await?;
+        Ok(())
+    }
+
+    fn load(path: &PathBuf) -> Result<Namespaces> {
+        if !path.exists() {
+            return Ok(HashMap::new());
+        }
+        let content = std::fs::read_to_string(path)?;
+        if content.trim().is_empty() {
+            return Ok(HashMap::new());
+        }
+        serde_json::from_str(&content).map_err(|e| {
+            ZeptoError::Config(format!(
+                "Failed to parse scratchpad {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_set_get_delete() {
+        let store = ScratchpadStore::new_memory(1024);
+        assert!(!store.set("cli", "todo", "buy milk").await.unwrap());
+        assert!(store.set("cli", "todo", "buy bread").await.unwrap());
+        assert_eq!(store.get("cli", "todo").await.as_deref(), Some("buy bread"));
+        assert!(store.delete("cli", "todo").await.unwrap());
+        assert!(!store.delete("cli", "todo").await.unwrap());
+        assert_eq!(store.get("cli", "todo").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_namespaces_are_isolated() {
+        let store = ScratchpadStore::new_memory(1024);
+        store.set("telegram", "k", "tg").await.unwrap();
+        store.set("cli", "k", "cli").await.unwrap();
+        assert_eq!(store.get("telegram", "k").await.as_deref(), Some("tg"));
+        assert_eq!(store.get("cli", "k").await.as_deref(), Some("cli"));
+        assert_eq!(store.get("slack", "k").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_list_by_prefix() {
+        let store = ScratchpadStore::new_memory(1024);
+        for key in ["project:a", "project:b", "projects", "user:name"] {
+            store.set("cli", key, "v").await.unwrap();
+        }
+        let keys: Vec<String> = store
+            .list("cli", "project:")
+            .await
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(keys, ["project:a", "project:b"]);
+        assert_eq!(store.list("cli", "").await.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_quota() {
+        let store = ScratchpadStore::new_memory(20);
+        store.set("cli", "a", "0123456789").await.unwrap();
+        assert_eq!(store.usage("cli").await, 11);
+        let err = store.set("cli", "b", "0123456789").await.unwrap_err();
+        assert!(err.to_string().contains("quota exceeded"), "{err}");
+        assert_eq!(store.get("cli", "b").await, None);
+        // Replacing a value only counts the difference.
+        store.set("cli", "a", "0123456789abcdefgh").await.unwrap();
+        // Other namespaces have their own quota.
+        store.set("telegram", "b", "0123456789").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_persists_across_reopen() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("memory").join("scratchpad.json");
+        let store = ScratchpadStore::with_path(path.clone(), 1024).unwrap();
+        store.set("cli", "k", "v").await.unwrap();
+        drop(store);
+
+        let reopened = ScratchpadStore::with_path(path, 1024).unwrap();
+        assert_eq!(reopened.get("cli", "k").await.as_deref(), Some("v"));
+    }
+}
@@ -6,6 +6,9 @@
 //! with parameter interpolation, making it easy to extend ZeptoClaw's tool
 //! set without writing Rust code.
 //!
+//! With the `wasm-plugins` feature, `*.wasm` files in the same directories
+//! are loaded as sandboxed tools too; see `crate::tools::wasm_plugin`.
+//!
 //! # Architecture
 //!
 //! - **types**: Core data structures (`PluginManifest`, `PluginToolDef`, `Plugin`, `PluginConfig`)
@@ -76,5 +79,7 @@ pub mod watcher;
 
 pub use loader::{discover_plugins, load_plugin, validate_binary_path, validate_manifest};
 pub use registry::PluginRegistry;
-pub use types::{BinaryPluginConfig, Plugin, PluginConfig, PluginManifest, PluginToolDef};
+pub use types::{
+    BinaryPluginConfig, Plugin, PluginConfig, PluginManifest, PluginToolDef, WasmPluginLimits,
+};
 pub use watcher::{check_binary_health, PluginWatcher};
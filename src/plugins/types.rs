@@ -221,6 +221,10 @@ pub struct PluginConfig {
     /// Blocklist takes precedence over allowlist.
     #[serde(default)]
     pub blocked_plugins: Vec<String>,
+
+    /// Per-invocation limits for `.wasm` plugins (`wasm-plugins` feature).
+    #[serde(default)]
+    pub wasm: WasmPluginLimits,
 }
 
 impl Default for PluginConfig {
@@ -230,6 +234,7 @@ impl Default for PluginConfig {
             plugin_dirs: default_plugin_dirs(),
             allowed_plugins: Vec::new(),
             blocked_plugins: Vec::new(),
+            wasm: WasmPluginLimits::default(),
         }
     }
 }
@@ -251,6 +256,31 @@ impl PluginConfig {
     }
 }
 
+/// Resource limits applied to every WASM plugin call.
+///
+/// Each call runs in a fresh instance; exceeding any limit traps the call,
+/// which is reported to the model as a tool error.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WasmPluginLimits {
+    /// Fuel (roughly, WebAssembly instructions) available to one call.
+    pub fuel: u64,
+    /// Largest linear memory a plugin may grow to, in MiB.
+    pub max_memory_mb: u64,
+    /// Wall-clock limit for one call, in seconds.
+    pub timeout_secs: u64,
+}
+
+impl Default for WasmPluginLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 1_000_000_000,
+            max_memory_mb: 64,
+            timeout_secs: 10,
+        }
+    }
+}
+
 /// Returns the default plugin directories.
 fn default_plugin_dirs() -> Vec<String> {
     vec!["~/.zeptoclaw/plugins".to_string()]
@@ -416,6 +446,15 @@ mod tests {
         assert!(config.blocked_plugins.is_empty());
     }
 
+    #[test]
+    fn test_plugin_config_wasm_limits_partial() {
+        let config: PluginConfig =
+            serde_json::from_str(r#"{"wasm": {"timeout_secs": 2}}"#).unwrap();
+        assert_eq!(config.wasm.timeout_secs, 2);
+        assert_eq!(config.wasm.fuel, 1_000_000_000);
+        assert_eq!(config.wasm.max_memory_mb, 64);
+    }
+
     #[test]
     fn test_plugin_config_is_plugin_permitted_all_allowed() {
         let config = PluginConfig::default();
@@ -430,6 +469,7 @@ mod tests {
             plugin_dirs: vec![],
             allowed_plugins: vec!["good-plugin".to_string()],
             blocked_plugins: vec![],
+            wasm: Default::default(),
         };
         assert!(config.is_plugin_permitted("good-plugin"));
         assert!(!config.is_plugin_permitted("other-plugin"));
@@ -442,6 +482,7 @@ mod tests {
             plugin_dirs: vec![],
             allowed_plugins: vec![],
             blocked_plugins: vec!["bad-plugin".to_string()],
+            wasm: Default::default(),
         };
         assert!(!config.is_plugin_permitted("bad-plugin"));
         assert!(config.is_plugin_permitted("good-plugin"));
@@ -454,6 +495,7 @@ mod tests {
             plugin_dirs: vec![],
             allowed_plugins: vec!["my-plugin".to_string()],
             blocked_plugins: vec!["my-plugin".to_string()],
+            wasm: Default::default(),
         };
         // Blocklist takes precedence
         assert!(!config.is_plugin_permitted("my-plugin"));
@@ -0,0 +1,675 @@
+//! Amazon Bedrock provider for Claude models.
+//!
+//! Calls the Bedrock Runtime `Converse` API through the AWS SDK, so requests
+//! are signed with SigV4 using the standard AWS credential chain
+//! (environment, `~/.aws` profiles and SSO, web identity, ECS and EC2 roles).
+//! The region comes from `providers.bedrock.region`, else the usual AWS
+//! settings.
+//!
+//! The SDK's own retries are off; throttling and server errors come back as
+//! retryable [`ProviderError`]s for the retry and fallback layers, and
+//! validation errors as terminal ones.
+//!
+//! Feature-gated behind `provider-bedrock`. Without it, a Bedrock entry
+//! needs `api_base` pointing at a SigV4 proxy.
+
+use async_trait::async_trait;
+use aws_sdk_bedrockruntime::config::retry::RetryConfig;
+use aws_sdk_bedrockruntime::config::Region;
+use aws_sdk_bedrockruntime::error::{DisplayErrorContext, ProvideErrorMetadata, SdkError};
+use aws_sdk_bedrockruntime::operation::converse::{ConverseError, ConverseOutput};
+use aws_sdk_bedrockruntime::primitives::Blob;
+use aws_sdk_bedrockruntime::types::{
+    ContentBlock, ConversationRole, ImageBlock, ImageFormat, ImageSource as BedrockImageSource,
+    InferenceConfiguration, Message as BedrockMessage, SystemContentBlock, Tool, ToolConfiguration,
+    ToolInputSchema, ToolResultBlock, ToolResultContentBlock, ToolSpecification, ToolUseBlock,
+};
+use aws_sdk_bedrockruntime::Client;
+use aws_smithy_types::{Document, Number};
+use base64::Engine;
+use serde_json::Value;
+use tracing::{debug, info};
+
+use crate::error::{ProviderError, Result, ZeptoError};
+use crate::session::{ContentPart, ImageSource, Message, Role};
+
+use super::{
+    classify_error_message, ChatOptions, LLMProvider, LLMResponse, LLMToolCall, ToolDefinition,
+    Usage,
+};
+
+/// The model used when none is configured.
+const DEFAULT_MODEL: &str = "anthropic.claude-3-5-sonnet-20241022-v2:0";
+
+/// Bedrock provider, for Claude through Amazon Bedrock.
+///
+/// # Example
+/// ```rust,ignore
+/// use zeptoclaw::providers::BedrockProvider;
+///
+/// let provider = BedrockProvider::from_env(Some("eu-central-1"), None).await;
+/// ```
+pub struct BedrockProvider {
+    client: Client,
+    /// The model ID used when a turn doesn't name a Bedrock one.
+    model: String,
+}
+
+impl BedrockProvider {
+    /// A provider using the AWS credential chain, in `region` if given.
+    /// `model` is a Bedrock model ID or inference profile.
+    pub async fn from_env(region: Option<&str>, model: Option<&str>) -> Self {
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .retry_config(RetryConfig::disabled());
+        if let Some(region) = region.filter(|r| !r.is_empty()) {
+            loader = loader.region(Region::new(region.to_string()));
+        }
+        let sdk_config = loader.load().await;
+        Self::with_client(Client::new(&sdk_config), model)
+    }
+
+    /// A provider using an already configured Bedrock Runtime client.
+    pub fn with_client(client: Client, model: Option<&str>) -> Self {
+        Self {
+            client,
+            model: model
+                .filter(|m| !m.is_empty())
+                .unwrap_or(DEFAULT_MODEL)
+                .to_string(),
+        }
+    }
+
+    /// The model ID for a turn: the one asked for when it is a Bedrock ID,
+    /// else the configured one. Claude API names like `claude-sonnet-4-5`
+    /// mean nothing to Bedrock.
+    fn model_id<'a>(&'a self, model: Option<&'a str>) -> &'a str {
+        match model {
+            Some(model) if is_bedrock_model_id(model) => model,
+            _ => &self.model,
+        }
+    }
+}
+
+/// Whether `model` is a Bedrock model ID (`anthropic.claude-...-v2:0`), an
+/// inference profile (`us.anthropic.claude-...`) or an ARN.
+fn is_bedrock_model_id(model: &str) -> bool {
+    model.starts_with("arn:") || (model.contains('.') && model.contains(':'))
+}
+
+// ============================================================================
+// Conversion Functions
+// ============================================================================
+
+fn build_error(e: impl std::fmt::Display) -> ZeptoError {
+    ZeptoError::Provider(format!("Failed to build Bedrock request: {}", e))
+}
+
+fn json_to_document(value: Value) -> Document {
+    match value {
+        Value::Null => Document::Null,
+        Value::Bool(b) => Document::Bool(b),
+        Value::Number(n) => Document::Number(if let Some(u) = n.as_u64() {
+            Number::PosInt(u)
+        } else if let Some(i) = n.as_i64() {
+            Number::NegInt(i)
+        } else {
+            Number::Float(n.as_f64().unwrap_or(0.0))
+        }),
+        Value::String(s) => Document::String(s),
+        Value::Array(items) => Document::Array(items.into_iter().map(json_to_document).collect()),
+        Value::Object(map) => Document::Object(
+            map.into_iter()
+                .map(|(k, v)| (k, json_to_document(v)))
+                .collect(),
+        ),
+    }
+}
+
+fn document_to_json(doc: &Document) -> Value {
+    match doc {
+        Document::Null => Value::Null,
+        Document::Bool(b) => Value::Bool(*b),
+        Document::Number(Number::PosInt(u)) => Value::from(*u),
+        Document::Number(Number::NegInt(i)) => Value::from(*i),
+        Document::Number(Number::Float(f)) => Value::from(*f),
+        Document::String(s) => Value::String(s.clone()),
+        Document::Array(items) => Value::Array(items.iter().map(document_to_json).collect()),
+        Document::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), document_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// An image block for an inline image; other sources can't be sent.
+fn image_block(source: &ImageSource, media_type: &str) -> Result<Option<ContentBlock>> {
+    let ImageSource::Base64 { data } = source else {
+        return Ok(None);
+    };
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(build_error)?;
+    let format = media_type.strip_prefix("image/").unwrap_or(media_type);
+    let image = ImageBlock::builder()
+        .format(ImageFormat::from(format))
+        .source(BedrockImageSource::Bytes(Blob::new(bytes)))
+        .build()
+        .map_err(build_error)?;
+    Ok(Some(ContentBlock::Image(image)))
+}
+
+/// The system prompt and the conversation as Converse wants them: tool
+/// results in user messages, and no two messages in a row from the same
+/// role.
+fn convert_messages(
+    messages: Vec<Message>,
+) -> Result<(Vec<SystemContentBlock>, Vec<BedrockMessage>)> {
+    let mut system = Vec::new();
+    let mut turns: Vec<(ConversationRole, Vec<ContentBlock>)> = Vec::new();
+
+    for msg in messages {
+        let (role, mut blocks) = match msg.role {
+            Role::System => {
+                if !msg.content.is_empty() {
+                    system.push(SystemContentBlock::Text(msg.content));
+                }
+                continue;
+            }
+            Role::User => {
+                let mut blocks = Vec::new();
+                if msg.has_images() {
+                    for part in &msg.content_parts {
+                        match part {
+                            ContentPart::Text { text } if !text.is_empty() => {
+                                blocks.push(ContentBlock::Text(text.clone()));
+                            }
+                            ContentPart::Text { .. } => {}
+                            ContentPart::Image { source, media_type } => {
+                                blocks.extend(image_block(source, media_type)?);
+                            }
+                        }
+                    }
+                } else if !msg.content.is_empty() {
+                    blocks.push(ContentBlock::Text(msg.content));
+                }
+                (ConversationRole::User, blocks)
+            }
+            Role::Assistant => {
+                let mut blocks = Vec::new();
+                if !msg.content.is_empty() {
+                    blocks.push(ContentBlock::Text(msg.content));
+                }
+                for tc in msg.tool_calls.unwrap_or_default() {
+                    let input = serde_json::from_str(&tc.arguments)
+                        .unwrap_or_else(|_| serde_json::json!({}));
+                    let tool_use = ToolUseBlock::builder()
+                        .tool_use_id(tc.id)
+                        .name(tc.name)
+                        .input(json_to_document(input))
+                        .build()
+                        .map_err(build_error)?;
+                    blocks.push(ContentBlock::ToolUse(tool_use));
+                }
+                (ConversationRole::Assistant, blocks)
+            }
+            Role::Tool => {
+                let Some(tool_call_id) = msg.tool_call_id else {
+                    continue;
+                };
+                let result = ToolResultBlock::builder()
+                    .tool_use_id(tool_call_id)
+                    .content(ToolResultContentBlock::Text(msg.content))
+                    .build()
+                    .map_err(build_error)?;
+                (
+                    ConversationRole::User,
+                    vec![ContentBlock::ToolResult(result)],
+                )
+            }
+        };
+        if blocks.is_empty() {
+            continue;
+        }
+        match turns.last_mut() {
+            Some((last, last_blocks)) if *last == role => last_blocks.append(&mut blocks),
+            _ => turns.push((role, blocks)),
+        }
+    }
+
+    let messages = turns
+        .into_iter()
+        .map(|(role, blocks)| {
+            BedrockMessage::builder()
+                .role(role)
+                .set_content(Some(blocks))
+                .build()
+                .map_err(build_error)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((system, messages))
+}
+
+fn convert_tools(tools: Vec<ToolDefinition>) -> Result<Option<ToolConfiguration>> {
+    if tools.is_empty() {
+        return Ok(None);
+    }
+    let tools = tools
+        .into_iter()
+        .map(|t| {
+            ToolSpecification::builder()
+                .name(t.name)
+                .description(t.description)
+                .input_schema(ToolInputSchema::Json(json_to_document(t.parameters)))
+                .build()
+                .map(Tool::ToolSpec)
+                .map_err(build_error)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    ToolConfiguration::builder()
+        .set_tools(Some(tools))
+        .build()
+        .map(Some)
+        .map_err(build_error)
+}
+
+fn inference_config(options: &ChatOptions) -> InferenceConfiguration {
+    InferenceConfiguration::builder()
+        .set_max_tokens(options.max_tokens.map(|n| n.min(i32::MAX as u32) as i32))
+        .set_temperature(options.temperature)
+        .set_top_p(options.top_p)
+        .set_stop_sequences(options.stop.clone())
+        .build()
+}
+
+fn convert_response(output: &ConverseOutput) -> LLMResponse {
+    let mut content = String::new();
+    let mut tool_calls = Vec::new();
+    if let Some(message) = output.output().and_then(|o| o.as_message().ok()) {
+        for block in message.content() {
+            match block {
+                ContentBlock::Text(text) => content.push_str(text),
+                ContentBlock::ToolUse(tool_use) => tool_calls.push(LLMToolCall::new(
+                    tool_use.tool_use_id(),
+                    tool_use.name(),
+                    &document_to_json(tool_use.input()).to_string(),
+                )),
+                _ => {}
+            }
+        }
+    }
+
+    let response = if tool_calls.is_empty() {
+        LLMResponse::text(&content)
+    } else {
+        LLMResponse::with_tools(&content, tool_calls)
+    };
+    match output.usage() {
+        Some(usage) => response.with_usage(Usage::new(
+            usage.input_tokens().max(0) as u32,
+            usage.output_tokens().max(0) as u32,
+        )),
+        None => response,
+    }
+}
+
+/// The provider error for a Bedrock exception, by its error code.
+/// Throttling and server-side trouble are retryable; a request Bedrock
+/// rejects is not, except one too long for the model, which compaction can
+/// fix.
+fn classify_exception(code: &str, message: String) -> ProviderError {
+    match code {
+        "ThrottlingException" | "ServiceQuotaExceededException" => {
+            ProviderError::RateLimit(message)
+        }
+        "ServiceUnavailableException" | "ModelNotReadyException" => {
+            ProviderError::Overloaded(message)
+        }
+        "InternalServerException" | "ModelErrorException" => ProviderError::ServerError(message),
+        "ModelTimeoutException" => ProviderError::Timeout(message),
+        "AccessDeniedException" | "UnrecognizedClientException" | "ExpiredTokenException" => {
+            ProviderError::Auth(message)
+        }
+        "ResourceNotFoundException" => ProviderError::ModelNotFound(message),
+        "ValidationException" => match classify_error_message(&message) {
+            overflow @ ProviderError::ContextOverflow(_) => overflow,
+            _ => ProviderError::InvalidRequest(message),
+        },
+        _ => classify_error_message(&message),
+    }
+}
+
+fn convert_error(err: SdkError<ConverseError>) -> ZeptoError {
+    match &err {
+        SdkError::ServiceError(service) => {
+            let e = service.err();
+            let code = e.code().unwrap_or("UnknownError");
+            let message = format!("Bedrock {}: {}", code, e.message().unwrap_or("no message"));
+            ZeptoError::from(classify_exception(code, message))
+        }
+        SdkError::TimeoutError(_) => ZeptoError::from(ProviderError::Timeout(format!(
+            "Bedrock request timed out: {}",
+            DisplayErrorContext(&err)
+        ))),
+        _ => ZeptoError::Provider(format!(
+            "Bedrock request failed: {}",
+            DisplayErrorContext(&err)
+        )),
+    }
+}
+
+// ============================================================================
+// LLMProvider Implementation
+// ============================================================================
+
+#[async_trait]
+impl LLMProvider for BedrockProvider {
+    async fn chat(
+        &self,
+        messages: Vec<Message>,
+        tools: Vec<ToolDefinition>,
+        model: Option<&str>,
+        options: ChatOptions,
+    ) -> Result<LLMResponse> {
+        let model = self.model_id(model);
+        let (system, messages) = convert_messages(messages)?;
+        debug!("Bedrock Converse request to model {}", model);
+
+        let output = self
+            .client
+            .converse()
+            .model_id(model)
+            .set_system((!system.is_empty()).then_some(system))
+            .set_messages(Some(messages))
+            .set_tool_config(convert_tools(tools)?)
+            .inference_config(inference_config(&options))
+            .send()
+            .await
+            .map_err(convert_error)?;
+
+        info!("Bedrock response received");
+        Ok(convert_response(&output))
+    }
+
+    fn default_model(&self) -> &str {
+        &self.model
+    }
+
+    fn name(&self) -> &str {
+        "bedrock"
+    }
+
+    fn supports_vision(&self) -> bool {
+        true
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::ToolCall;
+    use aws_sdk_bedrockruntime::config::{BehaviorVersion, Credentials};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    /// A `Converse` reply using a tool, as Bedrock sends it.
+    const TOOL_USE_FIXTURE: &str = r#"{
+        "output": {
+            "message": {
+                "role": "assistant",
+                "content": [
+                    {"text": "Let me look that up."},
+                    {"toolUse": {
+                        "toolUseId": "tooluse_kZJMlvQmRJ6eAyJE5GIl7Q",
+                        "name": "web_search",
+                        "input": {"query": "weather in Oslo", "limit": 3}
+                    }}
+                ]
+            }
+        },
+        "stopReason": "tool_use",
+        "usage": {"inputTokens": 412, "outputTokens": 61, "totalTokens": 473},
+        "metrics": {"latencyMs": 1284}
+    }"#;
+
+    fn provider(endpoint: &str) -> BedrockProvider {
+        let config = aws_sdk_bedrockruntime::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(Credentials::new(
+                "AKIDEXAMPLE",
+                "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY",
+                None,
+                None,
+                "test",
+            ))
+            .endpoint_url(endpoint)
+            .retry_config(RetryConfig::disabled())
+            .build();
+        BedrockProvider::with_client(Client::from_conf(config), None)
+    }
+
+    /// Serve `responses` on localhost, one per connection, in order.
+    /// Returns the endpoint URL and the request heads received.
+    async fn serve(responses: Vec<String>) -> (String, Arc<Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&requests);
+        tokio::spawn(async move {
+            for response in responses {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = socket.read(&mut buf).await.unwrap_or(0);
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text[..end]
+                            .lines()
+                            .find_map(|l| {
+                                let (name, value) = l.split_once(':')?;
+                                name.eq_ignore_ascii_case("content-length")
+                                    .then(|| value.trim().parse::<usize>().ok())?
+                            })
+                            .unwrap_or(0);
+                        if request.len() >= end + 4 + length {
+                            break;
+                        }
+                    }
+                }
+                seen.lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&request).to_string());
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        (format!("http://{}", addr), requests)
+    }
+
+    fn http_response(status: &str, headers: &[&str], body: &str) -> String {
+        let mut out = format!(
+            "HTTP/1.1 {}\r\ncontent-type: application/json\r\nconnection: close\r\ncontent-length: {}\r\n",
+            status,
+            body.len()
+        );
+        for header in headers {
+            out.push_str(header);
+            out.push_str("\r\n");
+        }
+        out.push_str("\r\n");
+        out.push_str(body);
+        out
+    }
+
+    #[test]
+    fn test_model_id_prefers_bedrock_ids() {
+        let provider = provider("http://localhost:1");
+        assert_eq!(provider.model_id(Some("claude-sonnet-4-5")), DEFAULT_MODEL);
+        assert_eq!(
+            provider.model_id(Some("us.anthropic.claude-sonnet-4-20250514-v1:0")),
+            "us.anthropic.claude-sonnet-4-20250514-v1:0"
+        );
+        assert_eq!(provider.model_id(None), DEFAULT_MODEL);
+    }
+
+    #[test]
+    fn test_convert_messages_groups_tool_results_into_user_turns() {
+        let (system, messages) = convert_messages(vec![
+            Message::system("You are helpful."),
+            Message::user("Weather in Oslo and Bergen?"),
+            Message::assistant_with_tools(
+                "",
+                vec![
+                    ToolCall::new("tu_1", "web_search", r#"{"query":"oslo"}"#),
+                    ToolCall::new("tu_2", "web_search", r#"{"query":"bergen"}"#),
+                ],
+            ),
+            Message::tool_result("tu_1", "Rain"),
+            Message::tool_result("tu_2", "Sun"),
+            Message::user("And tomorrow?"),
+        ])
+        .unwrap();
+
+        assert_eq!(system.len(), 1);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].role(), &ConversationRole::Assistant);
+        let tool_use = messages[1].content()[1].as_tool_use().unwrap();
+        assert_eq!(tool_use.tool_use_id(), "tu_2");
+        assert_eq!(
+            document_to_json(tool_use.input()),
+            json!({"query": "bergen"})
+        );
+        // Both results and the next question share one user turn.
+        assert_eq!(messages[2].role(), &ConversationRole::User);
+        assert_eq!(messages[2].content().len(), 3);
+        assert!(messages[2].content()[0].is_tool_result());
+    }
+
+    #[test]
+    fn test_classify_exception_retryable_or_terminal() {
+        let error = |code| classify_exception(code, format!("Bedrock {code}: nope"));
+        assert!(error("ThrottlingException").is_retryable());
+        assert!(error("ServiceUnavailableException").is_retryable());
+        assert!(error("ModelTimeoutException").is_retryable());
+        assert!(matches!(
+            error("ValidationException"),
+            ProviderError::InvalidRequest(_)
+        ));
+        assert!(matches!(
+            classify_exception(
+                "ValidationException",
+                "Input is too long for requested model.".into()
+            ),
+            ProviderError::ContextOverflow(_)
+        ));
+        assert!(matches!(
+            error("AccessDeniedException"),
+            ProviderError::Auth(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_chat_against_mock_endpoint() {
+        let (endpoint, requests) =
+            serve(vec![http_response("200 OK", &[], TOOL_USE_FIXTURE)]).await;
+        let provider = provider(&endpoint);
+        let tools = vec![ToolDefinition::new(
+            "web_search",
+            "Search the web",
+            json!({"type": "object", "properties": {"query": {"type": "string"}}}),
+        )];
+
+        let response = provider
+            .chat(
+                vec![
+                    Message::system("You are helpful."),
+                    Message::user("What's the weather in Oslo?"),
+                ],
+                tools,
+                Some("claude-sonnet-4-5"),
+                ChatOptions::new().with_max_tokens(1024),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.content, "Let me look that up.");
+        assert_eq!(response.tool_calls[0].id, "tooluse_kZJMlvQmRJ6eAyJE5GIl7Q");
+        assert_eq!(response.tool_calls[0].name, "web_search");
+        assert_eq!(
+            serde_json::from_str::<Value>(&response.tool_calls[0].arguments).unwrap(),
+            json!({"query": "weather in Oslo", "limit": 3})
+        );
+        let usage = response.usage.unwrap();
+        assert_eq!((usage.prompt_tokens, usage.completion_tokens), (412, 61));
+
+        let requests = requests.lock().unwrap();
+        let request = &requests[0];
+        assert!(request.starts_with("POST /model/anthropic.claude-3-5-sonnet-20241022-v2"));
+        assert!(request
+            .to_ascii_lowercase()
+            .contains("authorization: aws4-hmac-sha256"));
+        let body: Value =
+            serde_json::from_str(&request[request.find("\r\n\r\n").unwrap() + 4..]).unwrap();
+        assert_eq!(body["system"][0]["text"], "You are helpful.");
+        assert_eq!(body["inferenceConfig"]["maxTokens"], 1024);
+        assert_eq!(
+            body["toolConfig"]["tools"][0]["toolSpec"]["name"],
+            "web_search"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chat_maps_exceptions() {
+        let (endpoint, _) = serve(vec![
+            http_response(
+                "429 Too Many Requests",
+                &["x-amzn-ErrorType: ThrottlingException"],
+                r#"{"message":"Too many requests, please wait before trying again."}"#,
+            ),
+            http_response(
+                "400 Bad Request",
+                &["x-amzn-ErrorType: ValidationException"],
+                r#"{"message":"The provided model identifier is invalid."}"#,
+            ),
+        ])
+        .await;
+        let provider = provider(&endpoint);
+        let ask = || {
+            provider.chat(
+                vec![Message::user("hi")],
+                vec![],
+                None,
+                ChatOptions::default(),
+            )
+        };
+
+        match ask().await {
+            Err(ZeptoError::ProviderTyped(err @ ProviderError::RateLimit(_))) => {
+                assert!(err.is_retryable());
+                assert!(err.to_string().contains("ThrottlingException"), "{err}");
+            }
+            other => panic!("expected throttling, got {other:?}"),
+        }
+        match ask().await {
+            Err(ZeptoError::ProviderTyped(err @ ProviderError::InvalidRequest(_))) => {
+                assert!(!err.is_retryable());
+                assert!(!err.should_fallback());
+            }
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+}
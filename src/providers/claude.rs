@@ -3,6 +3,18 @@
 //! This module implements the `LLMProvider` trait for Claude/Anthropic's API,
 //! handling message conversion, tool calls, and response parsing.
 //!
+//! With prompt caching on ([`ClaudeProvider::with_prompt_caching`]), the tool
+//! definitions and the unchanging start of the system prompt (see
+//! [`Message::stable_prefix_len`]) are marked with `cache_control`
+//! breakpoints. The API then serves that prefix from its cache on later
+//! calls, and the response usage reports the tokens read from and written to
+//! the cache.
+//!
+//! With a thinking budget ([`ChatOptions::thinking_budget`]) on a model that
+//! supports extended thinking, the reply's thinking blocks come back in
+//! [`LLMResponse::thinking`]. They are sent back unchanged with the
+//! assistant's tool calls, as the API requires within a tool-use loop.
+//!
 //! # Example
 //!
 //! ```rust,ignore
@@ -32,8 +44,10 @@ use serde::{Deserialize, Serialize};
 use tracing::warn;
 
 use crate::error::{Result, ZeptoError};
-use crate::session::{ContentPart, ImageSource, Message, Role, ToolCall};
+use crate::session::{ContentPart, ImageSource, Message, Role, ThinkingBlock, ToolCall};
 
+use super::retry::parse_retry_after;
+use super::structured::{unwrap_forced_output, STRUCTURED_OUTPUT_TOOL};
 use super::{
     parse_provider_error, ChatOptions, LLMProvider, LLMResponse, LLMToolCall, ToolDefinition, Usage,
 };
@@ -51,6 +65,9 @@ const DEFAULT_MODEL: &str = match option_env!("ZEPTOCLAW_CLAUDE_DEFAULT_MODEL")
 /// The Anthropic API version header value.
 const ANTHROPIC_VERSION: &str = "2023-06-01";
 
+/// The smallest thinking budget the API accepts.
+const MIN_THINKING_BUDGET: u32 = 1024;
+
 /// Claude/Anthropic LLM provider.
 ///
 /// Implements the `LLMProvider` trait for Anthropic's Claude API.
@@ -60,6 +77,8 @@ pub struct ClaudeProvider {
     credential: crate::auth::ResolvedCredential,
     /// HTTP client for making requests
     client: Client,
+    /// Whether to mark the tools and system prompt for prompt caching.
+    prompt_caching: bool,
 }
 
 impl ClaudeProvider {
@@ -83,6 +102,7 @@ impl ClaudeProvider {
                 .timeout(std::time::Duration::from_secs(120))
                 .build()
                 .unwrap_or_else(|_| Client::new()),
+            prompt_caching: false,
         }
     }
 
@@ -96,6 +116,7 @@ impl ClaudeProvider {
                 .timeout(std::time::Duration::from_secs(120))
                 .build()
                 .unwrap_or_else(|_| Client::new()),
+            prompt_caching: false,
         }
     }
 
@@ -111,9 +132,29 @@ impl ClaudeProvider {
         Self {
             credential: crate::auth::ResolvedCredential::ApiKey(api_key.to_string()),
             client,
+            prompt_caching: false,
         }
     }
 
+    /// Cache the tool definitions and the unchanging start of the system
+    /// prompt between calls, on models that support prompt caching.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::providers::claude::ClaudeProvider;
+    ///
+    /// let provider = ClaudeProvider::new("sk-ant-api03-xxx").with_prompt_caching(true);
+    /// ```
+    pub fn with_prompt_caching(mut self, enabled: bool) -> Self {
+        self.prompt_caching = enabled;
+        self
+    }
+
+    /// Whether requests to `model` get `cache_control` breakpoints.
+    fn caches_prompt(&self, model: &str) -> bool {
+        self.prompt_caching && supports_prompt_caching(model)
+    }
+
     /// Build auth headers based on the resolved credential type.
     ///
     /// - API key: sends `x-api-key` header
@@ -167,32 +208,54 @@ impl LLMProvider for ClaudeProvider {
         options: ChatOptions,
     ) -> Result<LLMResponse> {
         let model = model.unwrap_or(DEFAULT_MODEL);
+        let caching = self.caches_prompt(model);
+        let stable_prefix_len = stable_prefix_len(&messages);
+
+        // A schema is enforced by forcing a tool that takes the answer as
+        // input, unless the model needs to be free to call the real tools.
+        let forced_tool = if tools.is_empty() {
+            options.output_format.to_forced_tool()
+        } else {
+            None
+        };
+        // The API rejects thinking together with a forced tool.
+        let thinking = thinking_config(model, options.thinking_budget, forced_tool.is_some());
 
         // Convert messages to Claude format, extracting system message
-        let (mut system, claude_messages) = convert_messages(messages)?;
+        let (mut system, claude_messages) =
+            convert_messages(messages_for_request(messages, thinking.is_some()))?;
 
-        // Append structured output instructions to system prompt if needed
-        if let Some(suffix) = options.output_format.to_claude_system_suffix() {
-            let base = system.unwrap_or_default();
-            system = Some(format!("{}{}", base, suffix));
-        }
+        let (tools, tool_choice) = match forced_tool {
+            Some(tool) => (
+                vec![tool],
+                Some(serde_json::json!({"type": "tool", "name": STRUCTURED_OUTPUT_TOOL})),
+            ),
+            None => {
+                // Append structured output instructions to system prompt if needed
+                if let Some(suffix) = options.output_format.to_claude_system_suffix() {
+                    let base = system.unwrap_or_default();
+                    system = Some(format!("{}{}", base, suffix));
+                }
+                (tools, None)
+            }
+        };
+        let forced = tool_choice.is_some();
 
         // Build request
-        let request = ClaudeRequest {
+        let mut request = ClaudeRequest {
             model: model.to_string(),
             max_tokens: options.max_tokens.unwrap_or(8192),
             messages: claude_messages,
-            system,
-            tools: if tools.is_empty() {
-                None
-            } else {
-                Some(convert_tools(tools))
-            },
+            system: system.map(|system| system_prompt(system, stable_prefix_len, caching)),
+            tools: request_tools(tools, caching),
+            tool_choice,
             temperature: options.temperature,
             top_p: options.top_p,
             stop_sequences: options.stop,
             stream: None,
+            thinking: None,
         };
+        request.enable_thinking(thinking);
 
         // Send request
         let response = self
@@ -207,6 +270,7 @@ impl LLMProvider for ClaudeProvider {
 
         if !response.status().is_success() {
             let status = response.status().as_u16();
+            let retry_after = parse_retry_after(response.headers());
             let error_text = response.text().await.unwrap_or_default();
 
             // Build a human-readable body for the typed error
@@ -221,11 +285,17 @@ impl LLMProvider for ClaudeProvider {
                 format!("Claude API error: {}", error_text)
             };
 
-            return Err(ZeptoError::from(parse_provider_error(status, &body)));
+            return Err(ZeptoError::from(
+                parse_provider_error(status, &body).with_retry_after(retry_after),
+            ));
         }
 
         let claude_response: ClaudeResponse = response.json().await?;
-        Ok(convert_response(claude_response))
+        let mut response = convert_response(claude_response);
+        if forced {
+            unwrap_forced_output(&mut response);
+        }
+        Ok(response)
     }
 
     async fn chat_stream(
@@ -239,7 +309,11 @@ impl LLMProvider for ClaudeProvider {
         use futures::StreamExt;
 
         let model = model.unwrap_or(DEFAULT_MODEL);
-        let (mut system, claude_messages) = convert_messages(messages)?;
+        let caching = self.caches_prompt(model);
+        let stable_prefix_len = stable_prefix_len(&messages);
+        let thinking = thinking_config(model, options.thinking_budget, false);
+        let (mut system, claude_messages) =
+            convert_messages(messages_for_request(messages, thinking.is_some()))?;
 
         // Append structured output instructions to system prompt if needed
         if let Some(suffix) = options.output_format.to_claude_system_suffix() {
@@ -247,21 +321,20 @@ impl LLMProvider for ClaudeProvider {
             system = Some(format!("{}{}", base, suffix));
         }
 
-        let request = ClaudeRequest {
+        let mut request = ClaudeRequest {
             model: model.to_string(),
             max_tokens: options.max_tokens.unwrap_or(8192),
             messages: claude_messages,
-            system,
-            tools: if tools.is_empty() {
-                None
-            } else {
-                Some(convert_tools(tools))
-            },
+            system: system.map(|system| system_prompt(system, stable_prefix_len, caching)),
+            tools: request_tools(tools, caching),
             temperature: options.temperature,
             top_p: options.top_p,
+            tool_choice: None,
             stop_sequences: options.stop,
             stream: Some(true),
+            thinking: None,
         };
+        request.enable_thinking(thinking);
 
         let response = self
             .client
@@ -275,6 +348,7 @@ impl LLMProvider for ClaudeProvider {
 
         if !response.status().is_success() {
             let status = response.status().as_u16();
+            let retry_after = parse_retry_after(response.headers());
             let error_text = response.text().await.unwrap_or_default();
             let body = if let Ok(error_response) =
                 serde_json::from_str::<ClaudeErrorResponse>(&error_text)
@@ -286,7 +360,9 @@ impl LLMProvider for ClaudeProvider {
             } else {
                 format!("Claude API error: {}", error_text)
             };
-            return Err(ZeptoError::from(parse_provider_error(status, &body)));
+            return Err(ZeptoError::from(
+                parse_provider_error(status, &body).with_retry_after(retry_after),
+            ));
         }
 
         let (tx, rx) = tokio::sync::mpsc::channel::<StreamEvent>(32);
@@ -298,8 +374,11 @@ impl LLMProvider for ClaudeProvider {
             let mut current_tool_id: Option<String> = None;
             let mut current_tool_name: Option<String> = None;
             let mut current_tool_json = String::new();
+            let mut thinking: Vec<ThinkingBlock> = Vec::new();
             let mut input_tokens: u32 = 0;
             let mut output_tokens: u32 = 0;
+            let mut cache_read_tokens: u32 = 0;
+            let mut cache_write_tokens: u32 = 0;
             let mut line_buffer = String::new();
 
             tokio::pin!(byte_stream);
@@ -351,15 +430,31 @@ impl LLMProvider for ClaudeProvider {
                             if let Some(msg) = &sse.message {
                                 if let Some(usage) = &msg.usage {
                                     input_tokens = usage.input_tokens.unwrap_or(0);
+                                    cache_read_tokens = usage.cache_read_input_tokens.unwrap_or(0);
+                                    cache_write_tokens =
+                                        usage.cache_creation_input_tokens.unwrap_or(0);
                                 }
                             }
                         }
                         "content_block_start" => {
                             if let Some(block) = &sse.content_block {
-                                if block.block_type == "tool_use" {
-                                    current_tool_id = block.id.clone();
-                                    current_tool_name = block.name.clone();
-                                    current_tool_json.clear();
+                                match block.block_type.as_str() {
+                                    "tool_use" => {
+                                        current_tool_id = block.id.clone();
+                                        current_tool_name = block.name.clone();
+                                        current_tool_json.clear();
+                                    }
+                                    "thinking" => thinking.push(ThinkingBlock {
+                                        thinking: block.thinking.clone().unwrap_or_default(),
+                                        signature: block.signature.clone().unwrap_or_default(),
+                                        redacted: None,
+                                    }),
+                                    "redacted_thinking" => thinking.push(ThinkingBlock {
+                                        thinking: String::new(),
+                                        signature: String::new(),
+                                        redacted: block.data.clone(),
+                                    }),
+                                    _ => {}
                                 }
                             }
                         }
@@ -383,6 +478,20 @@ impl LLMProvider for ClaudeProvider {
                                             current_tool_json.push_str(json_chunk);
                                         }
                                     }
+                                    Some("thinking_delta") => {
+                                        if let (Some(block), Some(text)) =
+                                            (thinking.last_mut(), &delta.thinking)
+                                        {
+                                            block.thinking.push_str(text);
+                                        }
+                                    }
+                                    Some("signature_delta") => {
+                                        if let (Some(block), Some(signature)) =
+                                            (thinking.last_mut(), &delta.signature)
+                                        {
+                                            block.signature.push_str(signature);
+                                        }
+                                    }
                                     _ => {}
                                 }
                             }
@@ -405,12 +514,24 @@ impl LLMProvider for ClaudeProvider {
                             }
                         }
                         "message_stop" => {
+                            let thinking_tokens = estimate_thinking_tokens(&thinking);
+                            if !thinking.is_empty() {
+                                let _ = tx
+                                    .send(StreamEvent::Thinking(std::mem::take(&mut thinking)))
+                                    .await;
+                            }
                             if !tool_calls.is_empty() {
                                 let _ = tx
                                     .send(StreamEvent::ToolCalls(std::mem::take(&mut tool_calls)))
                                     .await;
                             }
-                            let usage = super::Usage::new(input_tokens, output_tokens);
+                            let usage = cached_usage(
+                                input_tokens,
+                                output_tokens,
+                                cache_read_tokens,
+                                cache_write_tokens,
+                            )
+                            .with_thinking(thinking_tokens);
                             let _ = tx
                                 .send(StreamEvent::Done {
                                     content: assembled_content.clone(),
@@ -424,12 +545,22 @@ impl LLMProvider for ClaudeProvider {
                 }
             }
 
+            let thinking_tokens = estimate_thinking_tokens(&thinking);
+            if !thinking.is_empty() {
+                let _ = tx.send(StreamEvent::Thinking(thinking)).await;
+            }
             if !tool_calls.is_empty() {
                 let _ = tx
                     .send(StreamEvent::ToolCalls(std::mem::take(&mut tool_calls)))
                     .await;
             }
-            let usage = super::Usage::new(input_tokens, output_tokens);
+            let usage = cached_usage(
+                input_tokens,
+                output_tokens,
+                cache_read_tokens,
+                cache_write_tokens,
+            )
+            .with_thinking(thinking_tokens);
             let _ = tx
                 .send(StreamEvent::Done {
                     content: assembled_content,
@@ -448,6 +579,14 @@ impl LLMProvider for ClaudeProvider {
     fn name(&self) -> &str {
         "claude"
     }
+
+    fn supports_vision(&self) -> bool {
+        true
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
 }
 
 // ============================================================================
@@ -465,10 +604,13 @@ struct ClaudeRequest {
     messages: Vec<ClaudeMessage>,
     /// System prompt (separate from messages in Claude API)
     #[serde(skip_serializing_if = "Option::is_none")]
-    system: Option<String>,
+    system: Option<ClaudeSystem>,
     /// Available tools
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<ClaudeTool>>,
+    /// Which tool the model must call, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<serde_json::Value>,
     /// Temperature for sampling
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
@@ -481,6 +623,73 @@ struct ClaudeRequest {
     /// Whether to stream the response
     #[serde(skip_serializing_if = "Option::is_none")]
     stream: Option<bool>,
+    /// Extended thinking, when on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<ClaudeThinking>,
+}
+
+impl ClaudeRequest {
+    /// Turn on extended thinking with `thinking`, if any. The budget counts
+    /// against `max_tokens`, so it is added on top, and the API only allows
+    /// the default sampling with thinking on.
+    fn enable_thinking(&mut self, thinking: Option<ClaudeThinking>) {
+        if let Some(thinking) = thinking {
+            self.max_tokens = self.max_tokens.saturating_add(thinking.budget_tokens);
+            self.temperature = None;
+            self.top_p = None;
+            self.thinking = Some(thinking);
+        }
+    }
+}
+
+/// The `thinking` field of a request.
+#[derive(Debug, Clone, Serialize)]
+struct ClaudeThinking {
+    /// Always "enabled"
+    #[serde(rename = "type")]
+    thinking_type: &'static str,
+    /// Tokens the model may spend thinking
+    budget_tokens: u32,
+}
+
+/// The system prompt: plain text, or text blocks when part of it is cached.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum ClaudeSystem {
+    /// The whole prompt as one string
+    Text(String),
+    /// The prompt split into text blocks
+    Blocks(Vec<ClaudeSystemBlock>),
+}
+
+/// A text block of the system prompt.
+#[derive(Debug, Serialize)]
+struct ClaudeSystemBlock {
+    /// Always "text"
+    #[serde(rename = "type")]
+    block_type: &'static str,
+    /// The text
+    text: String,
+    /// Cache breakpoint ending the cached prefix, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_control: Option<CacheControl>,
+}
+
+/// A prompt cache breakpoint: the request up to and including the block it
+/// is on is cached.
+#[derive(Debug, Clone, Serialize)]
+struct CacheControl {
+    /// Always "ephemeral" (the 5-minute cache)
+    #[serde(rename = "type")]
+    cache_type: &'static str,
+}
+
+impl CacheControl {
+    fn ephemeral() -> Self {
+        Self {
+            cache_type: "ephemeral",
+        }
+    }
 }
 
 /// A message in Claude's format.
@@ -527,6 +736,12 @@ enum ClaudeContentBlock {
     /// Image content (base64-encoded)
     #[serde(rename = "image")]
     Image { source: ClaudeImageSource },
+    /// Extended thinking, signed by the API
+    #[serde(rename = "thinking")]
+    Thinking { thinking: String, signature: String },
+    /// Extended thinking the API encrypted
+    #[serde(rename = "redacted_thinking")]
+    RedactedThinking { data: String },
 }
 
 /// Source descriptor for an image sent to the Claude API.
@@ -550,6 +765,9 @@ struct ClaudeTool {
     description: String,
     /// JSON Schema for tool parameters
     input_schema: serde_json::Value,
+    /// Cache breakpoint (on the last tool, when caching)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_control: Option<CacheControl>,
 }
 
 // ============================================================================
@@ -582,12 +800,18 @@ struct ClaudeError {
 }
 
 /// Claude token usage.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct ClaudeUsage {
-    /// Tokens in the input
+    /// Tokens in the input after the last cache breakpoint
     input_tokens: u32,
     /// Tokens in the output
     output_tokens: u32,
+    /// Input tokens written to the prompt cache
+    #[serde(default)]
+    cache_creation_input_tokens: Option<u32>,
+    /// Input tokens read from the prompt cache
+    #[serde(default)]
+    cache_read_input_tokens: Option<u32>,
 }
 
 // ============================================================================
@@ -622,6 +846,10 @@ struct SseDelta {
     #[serde(default)]
     partial_json: Option<String>,
     #[serde(default)]
+    thinking: Option<String>,
+    #[serde(default)]
+    signature: Option<String>,
+    #[serde(default)]
     stop_reason: Option<String>,
 }
 
@@ -636,6 +864,12 @@ struct SseContentBlock {
     name: Option<String>,
     #[serde(default)]
     text: Option<String>,
+    #[serde(default)]
+    thinking: Option<String>,
+    #[serde(default)]
+    signature: Option<String>,
+    #[serde(default)]
+    data: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -644,6 +878,10 @@ struct SseUsage {
     input_tokens: Option<u32>,
     #[serde(default)]
     output_tokens: Option<u32>,
+    #[serde(default)]
+    cache_creation_input_tokens: Option<u32>,
+    #[serde(default)]
+    cache_read_input_tokens: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -736,7 +974,9 @@ fn convert_messages(messages: Vec<Message>) -> Result<(Option<String>, Vec<Claud
 
                 // Check if this message has tool calls
                 if let Some(tool_calls) = msg.tool_calls {
-                    let mut blocks: Vec<ClaudeContentBlock> = Vec::new();
+                    // Thinking leads the turn, as the API returned it
+                    let mut blocks: Vec<ClaudeContentBlock> =
+                        msg.thinking.into_iter().map(thinking_block).collect();
 
                     // Add text content if present
                     if !msg.content.is_empty() {
@@ -800,14 +1040,139 @@ fn convert_tools(tools: Vec<ToolDefinition>) -> Vec<ClaudeTool> {
             name: t.name,
             description: t.description,
             input_schema: t.parameters,
+            cache_control: None,
         })
         .collect()
 }
 
+/// The messages to send, without thinking blocks when thinking is off.
+/// The API only takes them back while thinking is on.
+fn messages_for_request(mut messages: Vec<Message>, thinking: bool) -> Vec<Message> {
+    if !thinking {
+        for msg in &mut messages {
+            msg.thinking.clear();
+        }
+    }
+    messages
+}
+
+/// A stored thinking block in Claude's format.
+fn thinking_block(block: ThinkingBlock) -> ClaudeContentBlock {
+    match block.redacted {
+        Some(data) => ClaudeContentBlock::RedactedThinking { data },
+        None => ClaudeContentBlock::Thinking {
+            thinking: block.thinking,
+            signature: block.signature,
+        },
+    }
+}
+
+/// Whether `model` supports extended thinking (Claude 3.7 and later).
+fn supports_thinking(model: &str) -> bool {
+    supports_prompt_caching(model)
+        && (!model.starts_with("claude-3") || model.starts_with("claude-3-7"))
+}
+
+/// The request's thinking setting for `budget` (zero or unset is off). Off
+/// on models without extended thinking and when a tool is forced.
+fn thinking_config(model: &str, budget: Option<u32>, forced_tool: bool) -> Option<ClaudeThinking> {
+    let budget = budget.filter(|&b| b > 0)?;
+    if forced_tool || !supports_thinking(model) {
+        return None;
+    }
+    Some(ClaudeThinking {
+        thinking_type: "enabled",
+        budget_tokens: budget.max(MIN_THINKING_BUDGET),
+    })
+}
+
+/// Tokens spent on `thinking`. Claude bills thinking as output without
+/// reporting it apart, so this is estimated from the text (redacted blocks
+/// can't be counted).
+fn estimate_thinking_tokens(thinking: &[ThinkingBlock]) -> u32 {
+    let chars: usize = thinking.iter().map(|b| b.thinking.chars().count()).sum();
+    chars.div_ceil(4) as u32
+}
+
+/// Whether `model` supports prompt caching (Claude 3 and later).
+fn supports_prompt_caching(model: &str) -> bool {
+    model.starts_with("claude-")
+        && !model.starts_with("claude-2")
+        && !model.starts_with("claude-instant")
+}
+
+/// The byte length of the unchanging start of the system prompt in
+/// `messages`, if the context builder marked one.
+fn stable_prefix_len(messages: &[Message]) -> Option<usize> {
+    messages
+        .iter()
+        .rev()
+        .find(|m| m.role == Role::System)
+        .and_then(|m| m.stable_prefix_len)
+}
+
+/// The request's system prompt. When caching, the first `stable_prefix_len`
+/// bytes (all of it if unmarked) become a block with a cache breakpoint and
+/// the rest, which changes from turn to turn, follows uncached.
+fn system_prompt(system: String, stable_prefix_len: Option<usize>, caching: bool) -> ClaudeSystem {
+    if !caching {
+        return ClaudeSystem::Text(system);
+    }
+    let split = stable_prefix_len
+        .filter(|&len| len > 0 && system.is_char_boundary(len))
+        .unwrap_or(system.len());
+    let (stable, rest) = system.split_at(split);
+    let mut blocks = vec![ClaudeSystemBlock {
+        block_type: "text",
+        text: stable.to_string(),
+        cache_control: Some(CacheControl::ephemeral()),
+    }];
+    if !rest.trim().is_empty() {
+        blocks.push(ClaudeSystemBlock {
+            block_type: "text",
+            text: rest.to_string(),
+            cache_control: None,
+        });
+    }
+    ClaudeSystem::Blocks(blocks)
+}
+
+/// The request's tools, with a cache breakpoint after the last one when
+/// caching. Tools come first in the cached prefix, so they stay cached even
+/// when the system prompt changes.
+fn request_tools(tools: Vec<ToolDefinition>, caching: bool) -> Option<Vec<ClaudeTool>> {
+    if tools.is_empty() {
+        return None;
+    }
+    let mut tools = convert_tools(tools);
+    if caching {
+        if let Some(last) = tools.last_mut() {
+            last.cache_control = Some(CacheControl::ephemeral());
+        }
+    }
+    Some(tools)
+}
+
+/// Usage from Claude's counts. Claude reports cached input tokens apart
+/// from `input_tokens`; they are included in the prompt tokens here.
+fn cached_usage(
+    input_tokens: u32,
+    output_tokens: u32,
+    cache_read_tokens: u32,
+    cache_write_tokens: u32,
+) -> Usage {
+    Usage::new(
+        input_tokens + cache_read_tokens + cache_write_tokens,
+        output_tokens,
+    )
+    .with_cache(cache_read_tokens, cache_write_tokens)
+}
+
 /// Convert Claude API response to ZeptoClaw LLMResponse.
 fn convert_response(response: ClaudeResponse) -> LLMResponse {
     let mut content = String::new();
     let mut tool_calls: Vec<LLMToolCall> = Vec::new();
+    let mut thinking: Vec<ThinkingBlock> = Vec::new();
 
     for block in response.content {
         match block {
@@ -828,15 +1193,35 @@ fn convert_response(response: ClaudeResponse) -> LLMResponse {
             ClaudeContentBlock::Image { .. } => {
                 // Images in responses are not expected, but handle gracefully
             }
+            ClaudeContentBlock::Thinking {
+                thinking: text,
+                signature,
+            } => thinking.push(ThinkingBlock {
+                thinking: text,
+                signature,
+                redacted: None,
+            }),
+            ClaudeContentBlock::RedactedThinking { data } => thinking.push(ThinkingBlock {
+                thinking: String::new(),
+                signature: String::new(),
+                redacted: Some(data),
+            }),
         }
     }
 
-    let usage = Usage::new(response.usage.input_tokens, response.usage.output_tokens);
+    let usage = cached_usage(
+        response.usage.input_tokens,
+        response.usage.output_tokens,
+        response.usage.cache_read_input_tokens.unwrap_or(0),
+        response.usage.cache_creation_input_tokens.unwrap_or(0),
+    )
+    .with_thinking(estimate_thinking_tokens(&thinking));
 
     LLMResponse {
         content,
         tool_calls,
         usage: Some(usage),
+        thinking,
     }
 }
 
@@ -1010,6 +1395,7 @@ mod tests {
             usage: ClaudeUsage {
                 input_tokens: 10,
                 output_tokens: 5,
+                ..Default::default()
             },
             stop_reason: Some("end_turn".to_string()),
         };
@@ -1042,6 +1428,7 @@ mod tests {
             usage: ClaudeUsage {
                 input_tokens: 20,
                 output_tokens: 30,
+                ..Default::default()
             },
             stop_reason: Some("tool_use".to_string()),
         };
@@ -1072,6 +1459,7 @@ mod tests {
             usage: ClaudeUsage {
                 input_tokens: 10,
                 output_tokens: 10,
+                ..Default::default()
             },
             stop_reason: Some("end_turn".to_string()),
         };
@@ -1090,12 +1478,14 @@ mod tests {
                 role: "user".to_string(),
                 content: ClaudeContent::Text("Hello".to_string()),
             }],
-            system: Some("You are helpful.".to_string()),
+            system: Some(ClaudeSystem::Text("You are helpful.".to_string())),
             tools: None,
+            tool_choice: None,
             temperature: Some(0.7),
             top_p: None,
             stop_sequences: None,
             stream: None,
+            thinking: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -1117,10 +1507,12 @@ mod tests {
             messages: vec![],
             system: None,
             tools: None,
+            tool_choice: None,
             temperature: None,
             top_p: None,
             stop_sequences: None,
             stream: None,
+            thinking: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -1133,6 +1525,70 @@ mod tests {
         assert!(!json.contains("stop_sequences"));
     }
 
+    #[test]
+    fn test_prompt_caching_marks_stable_system_prefix_and_last_tool() {
+        let tools = || {
+            ["read_file", "shell"]
+                .iter()
+                .map(|name| ToolDefinition {
+                    name: name.to_string(),
+                    description: String::new(),
+                    parameters: serde_json::json!({"type": "object"}),
+                })
+                .collect::<Vec<_>>()
+        };
+        let system = "Base prompt.\n\nCurrent time: 12:00".to_string();
+
+        let cached = serde_json::to_value(system_prompt(system.clone(), Some(12), true)).unwrap();
+        assert_eq!(
+            cached,
+            serde_json::json!([
+                {"type": "text", "text": "Base prompt.", "cache_control": {"type": "ephemeral"}},
+                {"type": "text", "text": "\n\nCurrent time: 12:00"}
+            ])
+        );
+        // Without a marked prefix, the whole prompt is cached.
+        let whole = serde_json::to_value(system_prompt(system.clone(), None, true)).unwrap();
+        assert_eq!(whole.as_array().unwrap().len(), 1);
+        assert_eq!(whole[0]["cache_control"]["type"], "ephemeral");
+
+        let tools_json = serde_json::to_value(request_tools(tools(), true)).unwrap();
+        assert!(tools_json[0].get("cache_control").is_none());
+        assert_eq!(tools_json[1]["cache_control"]["type"], "ephemeral");
+
+        // Caching off: plain string prompt and no markers.
+        let plain = serde_json::to_value(system_prompt(system.clone(), Some(12), false)).unwrap();
+        assert_eq!(plain, serde_json::json!(system));
+        let tools_json = serde_json::to_string(&request_tools(tools(), false)).unwrap();
+        assert!(!tools_json.contains("cache_control"));
+
+        let provider = ClaudeProvider::new("key").with_prompt_caching(true);
+        assert!(provider.caches_prompt("claude-sonnet-4-6"));
+        assert!(!provider.caches_prompt("claude-2.1"));
+        assert!(!ClaudeProvider::new("key").caches_prompt("claude-sonnet-4-6"));
+    }
+
+    #[test]
+    fn test_convert_response_counts_cached_input_tokens() {
+        let response: ClaudeResponse = serde_json::from_value(serde_json::json!({
+            "content": [{"type": "text", "text": "Hi"}],
+            "usage": {
+                "input_tokens": 50,
+                "output_tokens": 20,
+                "cache_creation_input_tokens": 100,
+                "cache_read_input_tokens": 2000
+            },
+            "stop_reason": "end_turn"
+        }))
+        .unwrap();
+
+        let usage = convert_response(response).usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 2150);
+        assert_eq!(usage.completion_tokens, 20);
+        assert_eq!(usage.cache_read_tokens, 2000);
+        assert_eq!(usage.cache_write_tokens, 100);
+    }
+
     #[test]
     fn test_content_block_serialization() {
         // Text block
@@ -1201,10 +1657,12 @@ mod tests {
             messages: vec![],
             system: None,
             tools: None,
+            tool_choice: None,
             temperature: None,
             top_p: None,
             stop_sequences: None,
             stream: Some(true),
+            thinking: None,
         };
         let json = serde_json::to_string(&request).unwrap();
         assert!(json.contains(r#""stream":true"#));
@@ -1218,15 +1676,100 @@ mod tests {
             messages: vec![],
             system: None,
             tools: None,
+            tool_choice: None,
             temperature: None,
             top_p: None,
             stop_sequences: None,
             stream: None,
+            thinking: None,
         };
         let json = serde_json::to_string(&request).unwrap();
         assert!(!json.contains("stream"));
     }
 
+    #[test]
+    fn test_thinking_request_raises_max_tokens_and_drops_sampling() {
+        assert!(thinking_config("claude-sonnet-4-6", Some(0), false).is_none());
+        assert!(thinking_config("claude-3-5-sonnet-20241022", Some(4096), false).is_none());
+        assert!(thinking_config("claude-sonnet-4-6", Some(4096), true).is_none());
+        assert!(thinking_config("claude-3-7-sonnet-latest", Some(4096), false).is_some());
+
+        let mut request = ClaudeRequest {
+            model: "claude-sonnet-4-6".to_string(),
+            max_tokens: 1000,
+            messages: vec![],
+            system: None,
+            tools: None,
+            tool_choice: None,
+            temperature: Some(0.2),
+            top_p: Some(0.9),
+            stop_sequences: None,
+            stream: None,
+            thinking: None,
+        };
+        request.enable_thinking(thinking_config("claude-sonnet-4-6", Some(4096), false));
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["max_tokens"], 5096);
+        assert_eq!(json["thinking"]["type"], "enabled");
+        assert_eq!(json["thinking"]["budget_tokens"], 4096);
+        assert!(json.get("temperature").is_none());
+        assert!(json.get("top_p").is_none());
+    }
+
+    #[test]
+    fn test_convert_response_keeps_thinking_out_of_content() {
+        let response: ClaudeResponse = serde_json::from_value(serde_json::json!({
+            "content": [
+                {"type": "thinking", "thinking": "Check the file first.", "signature": "sig1"},
+                {"type": "redacted_thinking", "data": "enc"},
+                {"type": "text", "text": "Reading it."},
+                {"type": "tool_use", "id": "toolu_1", "name": "read_file", "input": {}}
+            ],
+            "usage": {"input_tokens": 10, "output_tokens": 40},
+            "stop_reason": "tool_use"
+        }))
+        .unwrap();
+
+        let llm_response = convert_response(response);
+        assert_eq!(llm_response.content, "Reading it.");
+        assert_eq!(llm_response.thinking.len(), 2);
+        assert_eq!(llm_response.thinking[0].thinking, "Check the file first.");
+        assert_eq!(llm_response.thinking[0].signature, "sig1");
+        assert_eq!(llm_response.thinking[1].redacted.as_deref(), Some("enc"));
+        let usage = llm_response.usage.unwrap();
+        assert_eq!(usage.thinking_tokens, 6);
+    }
+
+    #[test]
+    fn test_thinking_is_sent_back_with_tool_use_only_when_on() {
+        let mut assistant = Message::assistant_with_tools(
+            "Reading it.",
+            vec![ToolCall::new("toolu_1", "read_file", "{}")],
+        );
+        assistant.thinking = vec![ThinkingBlock {
+            thinking: "Check the file first.".to_string(),
+            signature: "sig1".to_string(),
+            redacted: None,
+        }];
+        let messages = vec![
+            Message::user("Read it"),
+            assistant,
+            Message::tool_result("toolu_1", "contents"),
+        ];
+
+        let (_, on) = convert_messages(messages_for_request(messages.clone(), true)).unwrap();
+        let blocks = serde_json::to_value(&on[1].content).unwrap();
+        assert_eq!(blocks[0]["type"], "thinking");
+        assert_eq!(blocks[0]["signature"], "sig1");
+        assert_eq!(blocks[1]["type"], "text");
+        assert_eq!(blocks[2]["type"], "tool_use");
+
+        let (_, off) = convert_messages(messages_for_request(messages, false)).unwrap();
+        let blocks = serde_json::to_value(&off[1].content).unwrap();
+        assert_eq!(blocks[0]["type"], "text");
+    }
+
     #[test]
     fn test_parse_sse_content_block_delta() {
         let line = r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hello"}}"#;
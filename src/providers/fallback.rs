@@ -378,6 +378,17 @@ impl LLMProvider for FallbackProvider {
     async fn embed(&self, texts: &[String]) -> crate::error::Result<Vec<Vec<f32>>> {
         self.primary.embed(texts).await
     }
+
+    /// Image requests are only safe when both sides of the chain accept them.
+    fn supports_vision(&self) -> bool {
+        self.primary.supports_vision() && self.fallback.supports_vision()
+    }
+
+    /// Either side may serve the stream; one that cannot stream still
+    /// delivers its reply, just in one piece.
+    fn supports_streaming(&self) -> bool {
+        self.primary.supports_streaming() || self.fallback.supports_streaming()
+    }
 }
 
 #[cfg(test)]
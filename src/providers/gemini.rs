@@ -14,6 +14,7 @@ use tracing::{debug, warn};
 use crate::error::{Result, ZeptoError};
 use crate::session::{ContentPart, ImageSource, Message, Role};
 
+use super::retry::parse_retry_after;
 use super::{parse_provider_error, ChatOptions, LLMProvider, LLMResponse, ToolDefinition, Usage};
 
 /// Gemini v1beta REST API base.
@@ -402,6 +403,7 @@ impl LLMProvider for GeminiProvider {
         }
 
         let status = response.status().as_u16();
+        let retry_after = parse_retry_after(response.headers());
         let error_text = response.text().await.unwrap_or_default();
 
         // Try to extract a useful message from the Gemini error body.
@@ -414,7 +416,9 @@ impl LLMProvider for GeminiProvider {
             })
             .unwrap_or_else(|| format!("Gemini API error: {}", error_text));
 
-        Err(ZeptoError::from(parse_provider_error(status, &body_msg)))
+        Err(ZeptoError::from(
+            parse_provider_error(status, &body_msg).with_retry_after(retry_after),
+        ))
     }
 
     fn default_model(&self) -> &str {
@@ -424,6 +428,10 @@ impl LLMProvider for GeminiProvider {
     fn name(&self) -> &str {
         "gemini-native"
     }
+
+    fn supports_vision(&self) -> bool {
+        true
+    }
 }
 
 // ── Tests ─────────────────────────────────────────────────────────────────────
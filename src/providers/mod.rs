@@ -21,11 +21,14 @@
 //! }
 //! ```
 
+#[cfg(feature = "provider-bedrock")]
+pub mod bedrock;
 pub mod claude;
 pub mod cooldown;
 pub mod error_classifier;
 pub mod fallback;
 pub mod gemini;
+pub mod ollama;
 pub mod openai;
 pub mod plugin;
 pub mod quota;
@@ -61,11 +64,14 @@ pub const RUNTIME_SUPPORTED_PROVIDERS: &[&str] = &[
 
 use crate::error::ProviderError;
 
+#[cfg(feature = "provider-bedrock")]
+pub use bedrock::BedrockProvider;
 pub use claude::ClaudeProvider;
 pub use cooldown::{CooldownTracker, FailoverReason};
 pub use error_classifier::classify_error_message;
 pub use fallback::FallbackProvider;
 pub use gemini::GeminiProvider;
+pub use ollama::OllamaProvider;
 pub use openai::OpenAIProvider;
 pub use plugin::ProviderPlugin;
 pub use quota::{
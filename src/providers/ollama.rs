@@ -0,0 +1,986 @@
+//! Native Ollama provider for local models.
+//!
+//! Talks to Ollama's own chat API (`/api/chat`) rather than its OpenAI
+//! compatibility layer, so requests can carry `keep_alive` and replies stream
+//! as newline-delimited JSON.
+//!
+//! Models with native tool calling get the tools as usual. A model without it
+//! makes Ollama answer "does not support tools"; the request is then sent
+//! again with the tools described in the system prompt, and a reply that is
+//! only `{"tool": "name", "arguments": {...}}` is read back as a tool call.
+//! The model is remembered, so only its first request is refused.
+
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use tracing::{debug, info};
+
+use crate::error::{Result, ZeptoError};
+use crate::session::{ContentPart, ImageSource, Message, Role};
+
+use super::retry::parse_retry_after;
+use super::{
+    parse_provider_error, ChatOptions, LLMProvider, LLMResponse, LLMToolCall, OutputFormat,
+    StreamEvent, ToolDefinition, Usage,
+};
+
+/// Where Ollama listens by default.
+pub const DEFAULT_OLLAMA_HOST: &str = "http://localhost:11434";
+
+/// The model used when none is configured.
+const DEFAULT_MODEL: &str = "llama3.2";
+
+/// What Ollama says when a model has no native tool calling.
+const TOOLS_UNSUPPORTED: &str = "does not support tools";
+
+// ============================================================================
+// Ollama API Types
+// ============================================================================
+
+/// `/api/chat` request body.
+#[derive(Debug, Serialize)]
+struct OllamaRequest<'a> {
+    model: &'a str,
+    messages: Vec<OllamaMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<Value>,
+    stream: bool,
+    /// `"json"` or a JSON schema the reply must follow.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<Value>,
+    /// How long the model stays loaded afterwards: a duration string
+    /// (`"30m"`) or seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<Value>,
+}
+
+/// A message in a request.
+#[derive(Debug, Serialize)]
+struct OllamaMessage {
+    role: &'static str,
+    content: String,
+    /// Base64 images, for vision models.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    images: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<OllamaToolCall>,
+    /// The tool a `tool` message is the result of.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_name: Option<String>,
+}
+
+/// A tool call. Ollama gives them no IDs and passes arguments as an object.
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaToolCall {
+    function: OllamaFunctionCall,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaFunctionCall {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+/// A reply, or one line of a streamed reply.
+#[derive(Debug, Default, Deserialize)]
+struct OllamaResponse {
+    #[serde(default)]
+    message: Option<OllamaResponseMessage>,
+    #[serde(default)]
+    done: bool,
+    /// Tokens in the prompt (final line only).
+    #[serde(default)]
+    prompt_eval_count: Option<u32>,
+    /// Tokens generated (final line only).
+    #[serde(default)]
+    eval_count: Option<u32>,
+    /// Set instead of the rest when a streamed request fails midway.
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OllamaResponseMessage {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    tool_calls: Vec<OllamaToolCall>,
+}
+
+/// Error body: `{"error": "..."}`.
+#[derive(Debug, Deserialize)]
+struct OllamaError {
+    error: String,
+}
+
+impl OllamaResponse {
+    fn usage(&self) -> Option<Usage> {
+        if self.prompt_eval_count.is_none() && self.eval_count.is_none() {
+            return None;
+        }
+        Some(Usage::new(
+            self.prompt_eval_count.unwrap_or(0),
+            self.eval_count.unwrap_or(0),
+        ))
+    }
+}
+
+// ============================================================================
+// Provider
+// ============================================================================
+
+/// Ollama provider, for models served by a local or LAN Ollama.
+///
+/// # Example
+/// ```rust,ignore
+/// use zeptoclaw::providers::OllamaProvider;
+///
+/// let provider = OllamaProvider::new("http://gpu-box:11434").with_keep_alive(Some("30m".into()));
+/// ```
+pub struct OllamaProvider {
+    /// Server URL, without a trailing `/` or `/v1`.
+    host: String,
+    keep_alive: Option<String>,
+    client: Client,
+    /// Models that turned down native tools and get them in the prompt.
+    prompt_tool_models: Mutex<HashSet<String>>,
+}
+
+impl OllamaProvider {
+    /// A provider for the Ollama server at `host`. An OpenAI-style base URL
+    /// (`http://localhost:11434/v1`) works too.
+    pub fn new(host: &str) -> Self {
+        let host = host.trim_end_matches('/');
+        let host = host
+            .strip_suffix("/v1")
+            .unwrap_or(host)
+            .trim_end_matches('/');
+        Self {
+            host: host.to_string(),
+            keep_alive: None,
+            client: Client::new(),
+            prompt_tool_models: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Keep the model loaded for `keep_alive` after each request (`"30m"`,
+    /// `"-1"` for ever, `"0"` to unload at once). `None` leaves it to Ollama.
+    pub fn with_keep_alive(mut self, keep_alive: Option<String>) -> Self {
+        self.keep_alive = keep_alive.filter(|k| !k.trim().is_empty());
+        self
+    }
+
+    /// The server URL this provider talks to.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    fn chat_url(&self) -> String {
+        format!("{}/api/chat", self.host)
+    }
+
+    fn tools_in_prompt(&self, model: &str) -> bool {
+        self.prompt_tool_models
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(model)
+    }
+
+    /// Explain a request that didn't get an answer; a refused connection
+    /// nearly always means Ollama isn't running.
+    fn request_error(&self, e: reqwest::Error) -> ZeptoError {
+        if e.is_connect() {
+            ZeptoError::Provider(format!(
+                "Can't reach Ollama at {}. Is Ollama running? Start it with `ollama serve` \
+                 or set providers.ollama.api_base. ({})",
+                self.host, e
+            ))
+        } else {
+            ZeptoError::Provider(format!("Ollama request failed: {}", e))
+        }
+    }
+
+    /// Post a chat request, sending it again with the tools in the prompt
+    /// if the model has no native tool calling. Returns the response and
+    /// whether the tools went in the prompt.
+    async fn send(
+        &self,
+        model: &str,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        options: &ChatOptions,
+        stream: bool,
+    ) -> Result<(reqwest::Response, bool)> {
+        let mut tools_in_prompt = !tools.is_empty() && self.tools_in_prompt(model);
+        loop {
+            let request = build_request(
+                model,
+                messages,
+                tools,
+                options,
+                tools_in_prompt,
+                stream,
+                self.keep_alive.as_deref(),
+            );
+            debug!(model, stream, tools_in_prompt, "Ollama request");
+            let response = self
+                .client
+                .post(self.chat_url())
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| self.request_error(e))?;
+            if response.status().is_success() {
+                return Ok((response, tools_in_prompt));
+            }
+
+            let status = response.status();
+            let retry_after = parse_retry_after(response.headers());
+            let text = response.text().await.unwrap_or_default();
+            let message = serde_json::from_str::<OllamaError>(&text)
+                .map(|e| e.error)
+                .unwrap_or(text);
+            if status == StatusCode::BAD_REQUEST
+                && !tools_in_prompt
+                && !tools.is_empty()
+                && message.contains(TOOLS_UNSUPPORTED)
+            {
+                info!(
+                    model,
+                    "Ollama model has no native tool calling; describing tools in the prompt"
+                );
+                self.prompt_tool_models
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(model.to_string());
+                tools_in_prompt = true;
+                continue;
+            }
+            return Err(ZeptoError::from(
+                parse_provider_error(status.as_u16(), &format!("Ollama API error: {}", message))
+                    .with_retry_after(retry_after),
+            ));
+        }
+    }
+}
+
+// ============================================================================
+// Conversion Functions
+// ============================================================================
+
+/// `keep_alive` as Ollama takes it: whole numbers are seconds, anything
+/// else a duration string.
+fn keep_alive_value(keep_alive: &str) -> Value {
+    match keep_alive.trim().parse::<i64>() {
+        Ok(secs) => json!(secs),
+        Err(_) => json!(keep_alive.trim()),
+    }
+}
+
+/// Sampling options under Ollama's names; `None` when all are defaults.
+fn build_options(options: &ChatOptions) -> Option<Value> {
+    let mut out = serde_json::Map::new();
+    if let Some(t) = options.temperature {
+        out.insert("temperature".into(), json!(t));
+    }
+    if let Some(p) = options.top_p {
+        out.insert("top_p".into(), json!(p));
+    }
+    if let Some(n) = options.max_tokens {
+        out.insert("num_predict".into(), json!(n));
+    }
+    if let Some(stop) = &options.stop {
+        out.insert("stop".into(), json!(stop));
+    }
+    (!out.is_empty()).then_some(Value::Object(out))
+}
+
+fn build_request<'a>(
+    model: &'a str,
+    messages: &[Message],
+    tools: &[ToolDefinition],
+    options: &ChatOptions,
+    tools_in_prompt: bool,
+    stream: bool,
+    keep_alive: Option<&str>,
+) -> OllamaRequest<'a> {
+    let (messages, tools) = if tools_in_prompt {
+        (
+            convert_messages_with_prompt_tools(messages, tools),
+            Vec::new(),
+        )
+    } else {
+        (convert_messages(messages), convert_tools(tools))
+    };
+    let format = match &options.output_format {
+        OutputFormat::Text => None,
+        OutputFormat::Json => Some(json!("json")),
+        OutputFormat::JsonSchema { schema, .. } => Some(schema.clone()),
+    };
+    OllamaRequest {
+        model,
+        messages,
+        tools,
+        stream,
+        format,
+        keep_alive: keep_alive.map(keep_alive_value),
+        options: build_options(options),
+    }
+}
+
+fn role_name(role: &Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+        Role::Tool => "tool",
+    }
+}
+
+/// Tool names by call ID, for the `tool_name` of tool results.
+fn tool_names(messages: &[Message]) -> HashMap<&str, &str> {
+    messages
+        .iter()
+        .filter_map(|m| m.tool_calls.as_ref())
+        .flatten()
+        .map(|tc| (tc.id.as_str(), tc.name.as_str()))
+        .collect()
+}
+
+/// The base64 images of a message. Only inline images can be sent.
+fn images(msg: &Message) -> Vec<String> {
+    msg.content_parts
+        .iter()
+        .filter_map(|part| match part {
+            ContentPart::Image {
+                source: ImageSource::Base64 { data },
+                ..
+            } => Some(data.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Arguments as a JSON object; Ollama rejects them as a string.
+fn arguments_value(arguments: &str) -> Value {
+    serde_json::from_str(arguments).unwrap_or_else(|_| json!({}))
+}
+
+fn convert_messages(messages: &[Message]) -> Vec<OllamaMessage> {
+    let names = tool_names(messages);
+    messages
+        .iter()
+        .map(|msg| OllamaMessage {
+            role: role_name(&msg.role),
+            content: msg.content.clone(),
+            images: images(msg),
+            tool_calls: msg
+                .tool_calls
+                .iter()
+                .flatten()
+                .map(|tc| OllamaToolCall {
+                    function: OllamaFunctionCall {
+                        name: tc.name.clone(),
+                        arguments: arguments_value(&tc.arguments),
+                    },
+                })
+                .collect(),
+            tool_name: msg
+                .tool_call_id
+                .as_deref()
+                .and_then(|id| names.get(id))
+                .map(|name| name.to_string()),
+        })
+        .collect()
+}
+
+fn convert_tools(tools: &[ToolDefinition]) -> Vec<Value> {
+    tools
+        .iter()
+        .map(|t| {
+            json!({
+                "type": "function",
+                "function": {
+                    "name": t.name,
+                    "description": t.description,
+                    "parameters": t.parameters,
+                }
+            })
+        })
+        .collect()
+}
+
+/// System prompt text describing `tools` and how to call them, for models
+/// without native tool calling.
+fn prompt_tools_instructions(tools: &[ToolDefinition]) -> String {
+    let mut out = String::from("You can use these tools:\n");
+    for tool in tools {
+        out.push_str(&format!(
+            "\n- {}: {}\n  Arguments (JSON Schema): {}\n",
+            tool.name, tool.description, tool.parameters
+        ));
+    }
+    out.push_str(
+        "\nTo use a tool, reply with only a JSON object and nothing else:\n\
+         {\"tool\": \"<tool name>\", \"arguments\": {<arguments>}}\n\
+         The result comes back in the next message. When you don't need a tool, \
+         answer normally.",
+    );
+    out
+}
+
+/// Messages for a model that gets its tools in the prompt: the instructions
+/// join the system prompt, earlier tool calls are written the way the model
+/// is asked to write them, and tool results become user messages.
+fn convert_messages_with_prompt_tools(
+    messages: &[Message],
+    tools: &[ToolDefinition],
+) -> Vec<OllamaMessage> {
+    let names = tool_names(messages);
+    let instructions = prompt_tools_instructions(tools);
+    let mut out: Vec<OllamaMessage> = Vec::with_capacity(messages.len() + 1);
+    let mut instructed = false;
+    for msg in messages {
+        let (role, content) = match msg.role {
+            Role::System if !instructed => {
+                instructed = true;
+                ("system", format!("{}\n\n{}", msg.content, instructions))
+            }
+            Role::Assistant if msg.has_tool_calls() => {
+                let calls: Vec<String> = msg
+                    .tool_calls
+                    .iter()
+                    .flatten()
+                    .map(|tc| {
+                        json!({"tool": tc.name, "arguments": arguments_value(&tc.arguments)})
+                            .to_string()
+                    })
+                    .collect();
+                let content = if msg.content.trim().is_empty() {
+                    calls.join("\n")
+                } else {
+                    format!("{}\n{}", msg.content, calls.join("\n"))
+                };
+                ("assistant", content)
+            }
+            Role::Tool => {
+                let name = msg
+                    .tool_call_id
+                    .as_deref()
+                    .and_then(|id| names.get(id))
+                    .copied()
+                    .unwrap_or("tool");
+                ("user", format!("Result of {}:\n{}", name, msg.content))
+            }
+            _ => (role_name(&msg.role), msg.content.clone()),
+        };
+        out.push(OllamaMessage {
+            role,
+            content,
+            images: images(msg),
+            tool_calls: Vec::new(),
+            tool_name: None,
+        });
+    }
+    if !instructed {
+        out.insert(
+            0,
+            OllamaMessage {
+                role: "system",
+                content: instructions,
+                images: Vec::new(),
+                tool_calls: Vec::new(),
+                tool_name: None,
+            },
+        );
+    }
+    out
+}
+
+/// A fresh ID for a tool call; Ollama doesn't give them one.
+fn tool_call_id() -> String {
+    format!("call_{}", uuid::Uuid::new_v4().simple())
+}
+
+fn convert_tool_calls(calls: Vec<OllamaToolCall>) -> Vec<LLMToolCall> {
+    calls
+        .into_iter()
+        .map(|tc| {
+            let arguments = match tc.function.arguments {
+                Value::Null => "{}".to_string(),
+                Value::String(s) => s,
+                other => other.to_string(),
+            };
+            LLMToolCall::new(&tool_call_id(), &tc.function.name, &arguments)
+        })
+        .collect()
+}
+
+/// The tool call in `content` if it is only a `{"tool": ..., "arguments":
+/// ...}` object (optionally in a code block) naming one of `tools`.
+fn parse_prompt_tool_call(content: &str, tools: &[ToolDefinition]) -> Option<LLMToolCall> {
+    let text = content.trim();
+    let text = text
+        .strip_prefix("```json")
+        .or_else(|| text.strip_prefix("```"))
+        .and_then(|t| t.strip_suffix("```"))
+        .unwrap_or(text)
+        .trim();
+    let value: Value = serde_json::from_str(text).ok()?;
+    let name = value.get("tool")?.as_str()?;
+    if !tools.iter().any(|t| t.name == name) {
+        return None;
+    }
+    let arguments = match value.get("arguments") {
+        Some(args @ Value::Object(_)) => args.to_string(),
+        None | Some(Value::Null) => "{}".to_string(),
+        Some(_) => return None,
+    };
+    Some(LLMToolCall::new(&tool_call_id(), name, &arguments))
+}
+
+/// Build the reply from the text and native tool calls, reading a tool
+/// call out of the text when the tools were in the prompt.
+fn finish_response(
+    content: String,
+    tool_calls: Vec<OllamaToolCall>,
+    usage: Option<Usage>,
+    prompt_tools: Option<&[ToolDefinition]>,
+) -> LLMResponse {
+    let mut tool_calls = convert_tool_calls(tool_calls);
+    let mut content = content;
+    if let Some(call) = prompt_tools.and_then(|tools| parse_prompt_tool_call(&content, tools)) {
+        tool_calls.push(call);
+        content.clear();
+    }
+    let response = if tool_calls.is_empty() {
+        LLMResponse::text(&content)
+    } else {
+        LLMResponse::with_tools(&content, tool_calls)
+    };
+    match usage {
+        Some(usage) => response.with_usage(usage),
+        None => response,
+    }
+}
+
+// ============================================================================
+// LLMProvider Implementation
+// ============================================================================
+
+#[async_trait]
+impl LLMProvider for OllamaProvider {
+    async fn chat(
+        &self,
+        messages: Vec<Message>,
+        tools: Vec<ToolDefinition>,
+        model: Option<&str>,
+        options: ChatOptions,
+    ) -> Result<LLMResponse> {
+        let model = model.unwrap_or(DEFAULT_MODEL);
+        let (response, tools_in_prompt) =
+            self.send(model, &messages, &tools, &options, false).await?;
+        let response: OllamaResponse = response
+            .json()
+            .await
+            .map_err(|e| ZeptoError::Provider(format!("Failed to parse Ollama response: {}", e)))?;
+        info!("Ollama response received");
+
+        let usage = response.usage();
+        let message = response.message.unwrap_or_default();
+        Ok(finish_response(
+            message.content,
+            message.tool_calls,
+            usage,
+            tools_in_prompt.then_some(tools.as_slice()),
+        ))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: Vec<Message>,
+        tools: Vec<ToolDefinition>,
+        model: Option<&str>,
+        options: ChatOptions,
+    ) -> Result<tokio::sync::mpsc::Receiver<StreamEvent>> {
+        use futures::StreamExt;
+
+        let model = model.unwrap_or(DEFAULT_MODEL);
+        let (response, tools_in_prompt) =
+            self.send(model, &messages, &tools, &options, true).await?;
+        let (tx, rx) = tokio::sync::mpsc::channel::<StreamEvent>(32);
+        let byte_stream = response.bytes_stream();
+
+        tokio::spawn(async move {
+            let mut content = String::new();
+            let mut tool_calls = Vec::new();
+            let mut usage = None;
+            let mut line_buffer = String::new();
+            tokio::pin!(byte_stream);
+
+            'read: while let Some(chunk) = byte_stream.next().await {
+                let chunk = match chunk {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        let _ = tx
+                            .send(StreamEvent::Error(ZeptoError::Provider(format!(
+                                "Stream read error: {}",
+                                e
+                            ))))
+                            .await;
+                        return;
+                    }
+                };
+                line_buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+                while let Some(newline_pos) = line_buffer.find('\n') {
+                    let line = line_buffer[..newline_pos].trim().to_string();
+                    line_buffer = line_buffer[newline_pos + 1..].to_string();
+                    if line.is_empty() {
+                        continue;
+                    }
+                    let Ok(part) = serde_json::from_str::<OllamaResponse>(&line) else {
+                        continue;
+                    };
+                    if let Some(error) = part.error {
+                        let _ = tx
+                            .send(StreamEvent::Error(ZeptoError::Provider(format!(
+                                "Ollama API error: {}",
+                                error
+                            ))))
+                            .await;
+                        return;
+                    }
+                    let done = part.done;
+                    let part_usage = part.usage();
+                    if let Some(message) = part.message {
+                        tool_calls.extend(message.tool_calls);
+                        if !message.content.is_empty() {
+                            content.push_str(&message.content);
+                            // A prompt-tools reply may turn out to be a tool
+                            // call, so it is only sent once complete.
+                            if !tools_in_prompt
+                                && tx.send(StreamEvent::Delta(message.content)).await.is_err()
+                            {
+                                return;
+                            }
+                        }
+                    }
+                    if done {
+                        usage = part_usage;
+                        break 'read;
+                    }
+                }
+            }
+
+            let response = finish_response(
+                content,
+                tool_calls,
+                usage,
+                tools_in_prompt.then_some(tools.as_slice()),
+            );
+            if tools_in_prompt && !response.content.is_empty() {
+                let _ = tx.send(StreamEvent::Delta(response.content.clone())).await;
+            }
+            if !response.tool_calls.is_empty() {
+                let _ = tx.send(StreamEvent::ToolCalls(response.tool_calls)).await;
+            }
+            let _ = tx
+                .send(StreamEvent::Done {
+                    content: response.content,
+                    usage: response.usage,
+                })
+                .await;
+        });
+
+        Ok(rx)
+    }
+
+    fn default_model(&self) -> &str {
+        DEFAULT_MODEL
+    }
+
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    fn supports_vision(&self) -> bool {
+        true
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::ToolCall;
+    use std::sync::Arc;
+
+    fn search_tool() -> ToolDefinition {
+        ToolDefinition::new(
+            "web_search",
+            "Search the web",
+            json!({"type": "object", "properties": {"query": {"type": "string"}}}),
+        )
+    }
+
+    #[test]
+    fn test_new_accepts_an_openai_style_base_url() {
+        assert_eq!(
+            OllamaProvider::new("http://localhost:11434/v1/").host(),
+            "http://localhost:11434"
+        );
+        assert_eq!(
+            OllamaProvider::new("http://gpu-box:11434").host(),
+            "http://gpu-box:11434"
+        );
+    }
+
+    #[test]
+    fn test_build_request_native_tools_and_options() {
+        let messages = vec![
+            Message::user("Weather in Oslo?"),
+            Message::assistant_with_tools(
+                "",
+                vec![ToolCall::new(
+                    "call_1",
+                    "web_search",
+                    r#"{"query":"oslo weather"}"#,
+                )],
+            ),
+            Message::tool_result("call_1", "Rain, 8°C"),
+        ];
+        let options = ChatOptions::new()
+            .with_max_tokens(256)
+            .with_temperature(0.2);
+        let request = build_request(
+            "qwen2.5",
+            &messages,
+            &[search_tool()],
+            &options,
+            false,
+            false,
+            Some("30m"),
+        );
+        let body = serde_json::to_value(&request).unwrap();
+
+        assert_eq!(body["model"], "qwen2.5");
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["keep_alive"], "30m");
+        assert_eq!(body["options"]["num_predict"], 256);
+        assert_eq!(body["tools"][0]["function"]["name"], "web_search");
+        assert_eq!(
+            body["messages"][1]["tool_calls"][0]["function"]["arguments"]["query"],
+            "oslo weather"
+        );
+        assert_eq!(body["messages"][2]["role"], "tool");
+        assert_eq!(body["messages"][2]["tool_name"], "web_search");
+        assert_eq!(keep_alive_value("-1"), json!(-1));
+    }
+
+    #[test]
+    fn test_prompt_tools_round_trip() {
+        let tools = [search_tool()];
+        let messages = vec![
+            Message::system("You are helpful."),
+            Message::user("Weather in Oslo?"),
+            Message::assistant_with_tools(
+                "",
+                vec![ToolCall::new(
+                    "call_1",
+                    "web_search",
+                    r#"{"query":"oslo weather"}"#,
+                )],
+            ),
+            Message::tool_result("call_1", "Rain, 8°C"),
+        ];
+        let converted = convert_messages_with_prompt_tools(&messages, &tools);
+        assert!(converted[0].content.starts_with("You are helpful."));
+        assert!(converted[0]
+            .content
+            .contains("- web_search: Search the web"));
+        assert_eq!(
+            serde_json::from_str::<Value>(&converted[2].content).unwrap(),
+            json!({"tool": "web_search", "arguments": {"query": "oslo weather"}})
+        );
+        assert_eq!(converted[3].role, "user");
+        assert_eq!(converted[3].content, "Result of web_search:\nRain, 8°C");
+
+        let call = parse_prompt_tool_call(
+            "```json\n{\"tool\": \"web_search\", \"arguments\": {\"query\": \"bergen\"}}\n```",
+            &tools,
+        )
+        .unwrap();
+        assert_eq!(call.name, "web_search");
+        assert_eq!(call.arguments, r#"{"query":"bergen"}"#);
+        assert!(parse_prompt_tool_call(r#"{"tool": "rm_rf", "arguments": {}}"#, &tools).is_none());
+        assert!(parse_prompt_tool_call("It's raining.", &tools).is_none());
+    }
+
+    /// Serve `responses` on localhost, one per connection, in order.
+    /// Returns the server URL and the request bodies received.
+    async fn serve(responses: Vec<String>) -> (String, Arc<Mutex<Vec<Value>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&bodies);
+        tokio::spawn(async move {
+            for response in responses {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                let body = loop {
+                    let n = socket.read(&mut buf).await.unwrap_or(0);
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    let Some(end) = text.find("\r\n\r\n") else {
+                        if n == 0 {
+                            break String::new();
+                        }
+                        continue;
+                    };
+                    let length = text[..end]
+                        .lines()
+                        .find_map(|l| {
+                            let (name, value) = l.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().ok())?
+                        })
+                        .unwrap_or(0);
+                    if n == 0 || request.len() >= end + 4 + length {
+                        break text[end + 4..].to_string();
+                    }
+                };
+                seen.lock()
+                    .unwrap()
+                    .push(serde_json::from_str(&body).unwrap_or(Value::Null));
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        (format!("http://{}", addr), bodies)
+    }
+
+    fn http_response(status: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\ncontent-type: application/json\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )
+    }
+
+    #[tokio::test]
+    async fn test_chat_falls_back_to_prompt_tools() {
+        let (host, bodies) = serve(vec![
+            http_response(
+                "400 Bad Request",
+                r#"{"error":"registry.ollama.ai/library/gemma2:2b does not support tools"}"#,
+            ),
+            http_response(
+                "200 OK",
+                r#"{"model":"gemma2:2b","message":{"role":"assistant","content":"{\"tool\": \"web_search\", \"arguments\": {\"query\": \"oslo\"}}"},"done":true,"prompt_eval_count":120,"eval_count":18}"#,
+            ),
+        ])
+        .await;
+        let provider = OllamaProvider::new(&host);
+
+        let response = provider
+            .chat(
+                vec![Message::user("Weather in Oslo?")],
+                vec![search_tool()],
+                Some("gemma2:2b"),
+                ChatOptions::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.tool_calls.len(), 1);
+        assert_eq!(response.tool_calls[0].name, "web_search");
+        assert_eq!(response.content, "");
+        assert_eq!(response.usage.unwrap().prompt_tokens, 120);
+
+        let bodies = bodies.lock().unwrap();
+        assert!(bodies[0]["tools"].is_array());
+        assert!(bodies[1].get("tools").is_none());
+        assert_eq!(bodies[1]["messages"][0]["role"], "system");
+        assert!(provider.tools_in_prompt("gemma2:2b"));
+    }
+
+    #[tokio::test]
+    async fn test_chat_stream_native_tool_call() {
+        let stream = [
+            r#"{"message":{"role":"assistant","content":"Let me "},"done":false}"#,
+            r#"{"message":{"role":"assistant","content":"check."},"done":false}"#,
+            r#"{"message":{"role":"assistant","content":"","tool_calls":[{"function":{"name":"web_search","arguments":{"query":"oslo"}}}]},"done":false}"#,
+            r#"{"message":{"role":"assistant","content":""},"done":true,"prompt_eval_count":50,"eval_count":9}"#,
+        ]
+        .join("\n")
+            + "\n";
+        let (host, _) = serve(vec![http_response("200 OK", &stream)]).await;
+        let provider = OllamaProvider::new(&host);
+
+        let mut rx = provider
+            .chat_stream(
+                vec![Message::user("Weather in Oslo?")],
+                vec![search_tool()],
+                Some("qwen2.5"),
+                ChatOptions::default(),
+            )
+            .await
+            .unwrap();
+        let mut deltas = String::new();
+        let mut calls = Vec::new();
+        let mut done = None;
+        while let Some(event) = rx.recv().await {
+            match event {
+                StreamEvent::Delta(text) => deltas.push_str(&text),
+                StreamEvent::ToolCalls(tcs) => calls = tcs,
+                StreamEvent::Done { content, usage } => done = Some((content, usage)),
+                other => panic!("unexpected event {other:?}"),
+            }
+        }
+        assert_eq!(deltas, "Let me check.");
+        assert_eq!(calls[0].name, "web_search");
+        assert_eq!(calls[0].arguments, r#"{"query":"oslo"}"#);
+        let (content, usage) = done.unwrap();
+        assert_eq!(content, "Let me check.");
+        assert_eq!(usage.unwrap().completion_tokens, 9);
+    }
+
+    #[tokio::test]
+    async fn test_connection_refused_asks_if_ollama_is_running() {
+        // Bind and drop a listener to get a port nothing listens on.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let provider = OllamaProvider::new(&format!("http://{}", addr));
+        let err = provider
+            .chat(
+                vec![Message::user("hi")],
+                vec![],
+                None,
+                ChatOptions::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Is Ollama running?"), "{err}");
+    }
+}
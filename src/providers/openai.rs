@@ -36,6 +36,7 @@ use tracing::{debug, info};
 use crate::error::{Result, ZeptoError};
 use crate::session::{ContentPart, ImageSource, Message, Role};
 
+use super::retry::parse_retry_after;
 use super::{
     parse_provider_error, ChatOptions, LLMProvider, LLMResponse, LLMToolCall, ToolDefinition, Usage,
 };
@@ -222,6 +223,28 @@ struct OpenAIUsage {
     prompt_tokens: u32,
     /// Tokens in the completion
     completion_tokens: u32,
+    /// Breakdown of the completion tokens
+    #[serde(default)]
+    completion_tokens_details: Option<OpenAICompletionTokensDetails>,
+}
+
+impl OpenAIUsage {
+    fn to_usage(&self) -> Usage {
+        let reasoning_tokens = self
+            .completion_tokens_details
+            .as_ref()
+            .and_then(|d| d.reasoning_tokens)
+            .unwrap_or(0);
+        Usage::new(self.prompt_tokens, self.completion_tokens).with_thinking(reasoning_tokens)
+    }
+}
+
+/// Breakdown of OpenAI completion tokens.
+#[derive(Debug, Deserialize)]
+struct OpenAICompletionTokensDetails {
+    /// Tokens reasoning models spent before answering (billed as output)
+    #[serde(default)]
+    reasoning_tokens: Option<u32>,
 }
 
 /// OpenAI streaming chunk response body.
@@ -638,8 +661,7 @@ fn convert_response(response: OpenAIResponse) -> LLMResponse {
     };
 
     if let Some(usage) = response.usage {
-        llm_response =
-            llm_response.with_usage(Usage::new(usage.prompt_tokens, usage.completion_tokens));
+        llm_response = llm_response.with_usage(usage.to_usage());
     }
 
     llm_response
@@ -683,10 +705,7 @@ fn apply_stream_chunk(
     usage: &mut Option<Usage>,
 ) -> Vec<String> {
     if let Some(chunk_usage) = chunk.usage {
-        *usage = Some(Usage::new(
-            chunk_usage.prompt_tokens,
-            chunk_usage.completion_tokens,
-        ));
+        *usage = Some(chunk_usage.to_usage());
     }
 
     let mut deltas = Vec::new();
@@ -802,6 +821,7 @@ impl LLMProvider for OpenAIProvider {
             }
 
             let status = response.status();
+            let retry_after = parse_retry_after(response.headers());
             let error_text = response.text().await.unwrap_or_default();
 
             // Retry once for models that require max_completion_tokens.
@@ -833,10 +853,9 @@ impl LLMProvider for OpenAIProvider {
                 format!("OpenAI API error: {}", error_text)
             };
 
-            return Err(ZeptoError::from(parse_provider_error(
-                status.as_u16(),
-                &body,
-            )));
+            return Err(ZeptoError::from(
+                parse_provider_error(status.as_u16(), &body).with_retry_after(retry_after),
+            ));
         }
     }
 
@@ -971,6 +990,7 @@ impl LLMProvider for OpenAIProvider {
             }
 
             let status = response.status();
+            let retry_after = parse_retry_after(response.headers());
             let error_text = response.text().await.unwrap_or_default();
 
             // Retry once for models that require max_completion_tokens.
@@ -1001,10 +1021,9 @@ impl LLMProvider for OpenAIProvider {
                 format!("OpenAI API error: {}", error_text)
             };
 
-            return Err(ZeptoError::from(parse_provider_error(
-                status.as_u16(),
-                &body,
-            )));
+            return Err(ZeptoError::from(
+                parse_provider_error(status.as_u16(), &body).with_retry_after(retry_after),
+            ));
         }
     }
 
@@ -1074,6 +1093,14 @@ impl LLMProvider for OpenAIProvider {
     fn name(&self) -> &str {
         "openai"
     }
+
+    fn supports_vision(&self) -> bool {
+        true
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
 }
 
 // ============================================================================
@@ -1083,7 +1110,9 @@ impl LLMProvider for OpenAIProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::ProviderError;
     use crate::session::{ContentPart, ImageSource, Message, ToolCall};
+    use std::sync::Arc;
 
     #[test]
     fn test_openai_provider_creation() {
@@ -1324,6 +1353,7 @@ mod tests {
             usage: Some(OpenAIUsage {
                 prompt_tokens: 10,
                 completion_tokens: 5,
+                completion_tokens_details: None,
             }),
         };
         let converted = convert_response(response);
@@ -1553,6 +1583,22 @@ mod tests {
         assert!(!is_max_tokens_unsupported_error(err));
     }
 
+    #[test]
+    fn test_convert_response_counts_reasoning_tokens() {
+        let response: OpenAIResponse = serde_json::from_value(serde_json::json!({
+            "choices": [{"message": {"content": "42"}}],
+            "usage": {
+                "prompt_tokens": 10,
+                "completion_tokens": 300,
+                "completion_tokens_details": {"reasoning_tokens": 256}
+            }
+        }))
+        .unwrap();
+        let usage = convert_response(response).usage.unwrap();
+        assert_eq!(usage.completion_tokens, 300);
+        assert_eq!(usage.thinking_tokens, 256);
+    }
+
     #[test]
     fn test_apply_stream_chunk_collects_text_and_usage() {
         let chunk = OpenAIStreamChunk {
@@ -1565,6 +1611,7 @@ mod tests {
             usage: Some(OpenAIUsage {
                 prompt_tokens: 10,
                 completion_tokens: 5,
+                completion_tokens_details: None,
             }),
         };
 
@@ -2029,4 +2076,216 @@ mod tests {
         assert_eq!(name, "Authorization");
         assert_eq!(value, "Bearer sk-real-key");
     }
+
+    // ------------------------------------------------------------------------
+    // Against a mock server, with responses recorded from the API
+    // ------------------------------------------------------------------------
+
+    /// A `chat/completions` reply calling a tool, as OpenAI sends it.
+    const TOOL_CALL_FIXTURE: &str = r#"{
+        "id": "chatcmpl-B9MBs8CjcvOU2jLn4n570S5qMJKcT",
+        "object": "chat.completion",
+        "created": 1741569952,
+        "model": "gpt-4o-mini-2024-07-18",
+        "choices": [{
+            "index": 0,
+            "message": {
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_2xyRsTf1XQdT8mGZ",
+                    "type": "function",
+                    "function": {"name": "web_search", "arguments": "{\"query\":\"weather in Oslo\"}"}
+                }],
+                "refusal": null
+            },
+            "logprobs": null,
+            "finish_reason": "tool_calls"
+        }],
+        "usage": {
+            "prompt_tokens": 82,
+            "completion_tokens": 17,
+            "total_tokens": 99,
+            "prompt_tokens_details": {"cached_tokens": 0, "audio_tokens": 0},
+            "completion_tokens_details": {"reasoning_tokens": 0, "audio_tokens": 0}
+        },
+        "service_tier": "default",
+        "system_fingerprint": "fp_06737a9306"
+    }"#;
+
+    /// The error body OpenAI sends with a 401.
+    const AUTH_ERROR_FIXTURE: &str = r#"{
+        "error": {
+            "message": "Incorrect API key provided: sk-wrong. You can find your API key at https://platform.openai.com/account/api-keys.",
+            "type": "invalid_request_error",
+            "param": null,
+            "code": "invalid_api_key"
+        }
+    }"#;
+
+    /// The error body OpenAI sends with a 429.
+    const RATE_LIMIT_FIXTURE: &str = r#"{
+        "error": {
+            "message": "Rate limit reached for gpt-4o-mini on requests per min (RPM): Limit 3, Used 3, Requested 1.",
+            "type": "requests",
+            "param": null,
+            "code": "rate_limit_exceeded"
+        }
+    }"#;
+
+    /// Serve `responses` on localhost, one per connection, in order.
+    /// Returns the base URL and the requests received.
+    async fn serve(responses: Vec<String>) -> (String, Arc<std::sync::Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = Arc::clone(&requests);
+        tokio::spawn(async move {
+            for response in responses {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                // Read the headers, then as much body as they announce.
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = socket.read(&mut buf).await.unwrap_or(0);
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text[..end]
+                            .lines()
+                            .find_map(|l| {
+                                let (name, value) = l.split_once(':')?;
+                                name.eq_ignore_ascii_case("content-length")
+                                    .then(|| value.trim().parse::<usize>().ok())?
+                            })
+                            .unwrap_or(0);
+                        if request.len() >= end + 4 + length {
+                            break;
+                        }
+                    }
+                }
+                seen.lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&request).to_string());
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        (format!("http://{}/v1", addr), requests)
+    }
+
+    fn http_response(status: &str, headers: &[&str], body: &str) -> String {
+        let mut out = format!(
+            "HTTP/1.1 {}\r\ncontent-type: application/json\r\nconnection: close\r\ncontent-length: {}\r\n",
+            status,
+            body.len()
+        );
+        for header in headers {
+            out.push_str(header);
+            out.push_str("\r\n");
+        }
+        out.push_str("\r\n");
+        out.push_str(body);
+        out
+    }
+
+    #[tokio::test]
+    async fn test_chat_against_mock_server_with_tool_calls() {
+        let (base, requests) = serve(vec![http_response("200 OK", &[], TOOL_CALL_FIXTURE)]).await;
+        let provider = OpenAIProvider::with_base_url("sk-test", &base);
+        let tools = vec![ToolDefinition::new(
+            "web_search",
+            "Search the web",
+            serde_json::json!({"type": "object", "properties": {"query": {"type": "string"}}}),
+        )];
+
+        let response = provider
+            .chat(
+                vec![
+                    Message::system("You are helpful."),
+                    Message::user("What's the weather in Oslo?"),
+                ],
+                tools,
+                Some("gpt-4o-mini"),
+                ChatOptions::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.content, "");
+        assert_eq!(response.tool_calls.len(), 1);
+        assert_eq!(response.tool_calls[0].id, "call_2xyRsTf1XQdT8mGZ");
+        assert_eq!(response.tool_calls[0].name, "web_search");
+        assert_eq!(
+            response.tool_calls[0].arguments,
+            r#"{"query":"weather in Oslo"}"#
+        );
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 82);
+        assert_eq!(usage.completion_tokens, 17);
+
+        let requests = requests.lock().unwrap();
+        let request = &requests[0];
+        assert!(request.starts_with("POST /v1/chat/completions "));
+        assert!(request
+            .to_ascii_lowercase()
+            .contains("authorization: bearer sk-test"));
+        let body: serde_json::Value =
+            serde_json::from_str(&request[request.find("\r\n\r\n").unwrap() + 4..]).unwrap();
+        assert_eq!(body["model"], "gpt-4o-mini");
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(
+            body["messages"][1]["content"],
+            "What's the weather in Oslo?"
+        );
+        assert_eq!(body["tools"][0]["type"], "function");
+        assert_eq!(body["tools"][0]["function"]["name"], "web_search");
+    }
+
+    #[tokio::test]
+    async fn test_chat_against_mock_server_maps_http_errors() {
+        let (base, _) = serve(vec![
+            http_response("401 Unauthorized", &[], AUTH_ERROR_FIXTURE),
+            http_response(
+                "429 Too Many Requests",
+                &["retry-after: 20"],
+                RATE_LIMIT_FIXTURE,
+            ),
+            http_response("503 Service Unavailable", &[], "upstream connect error"),
+        ])
+        .await;
+        let provider = OpenAIProvider::with_base_url("sk-wrong", &base);
+        let ask = || {
+            provider.chat(
+                vec![Message::user("hi")],
+                vec![],
+                Some("gpt-4o-mini"),
+                ChatOptions::default(),
+            )
+        };
+
+        match ask().await {
+            Err(ZeptoError::ProviderTyped(ProviderError::Auth(msg))) => {
+                assert!(msg.contains("Incorrect API key provided"), "{msg}");
+            }
+            other => panic!("expected an auth error, got {other:?}"),
+        }
+        match ask().await {
+            Err(ZeptoError::ProviderTyped(err @ ProviderError::RateLimit(_))) => {
+                assert_eq!(err.retry_after(), Some(std::time::Duration::from_secs(20)));
+            }
+            other => panic!("expected a rate limit error, got {other:?}"),
+        }
+        assert!(matches!(
+            ask().await,
+            Err(ZeptoError::ProviderTyped(ProviderError::ServerError(_)))
+        ));
+    }
 }
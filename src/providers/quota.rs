@@ -120,8 +120,9 @@ pub enum QuotaCheckResult {
 pub struct QuotaStore {
     /// In-memory state: provider name → usage for the current period.
     state: Mutex<HashMap<String, QuotaUsage>>,
-    /// Path of the JSON file used for persistence.
-    path: PathBuf,
+    /// Path of the JSON file used for persistence; `None` keeps usage in
+    /// memory only.
+    path: Option<PathBuf>,
 }
 
 /// Fraction of quota utilisation at or above which a warning is issued.
@@ -136,7 +137,7 @@ impl QuotaStore {
         let state = load_state(&path);
         Self {
             state: Mutex::new(state),
-            path,
+            path: Some(path),
         }
     }
 
@@ -146,7 +147,15 @@ impl QuotaStore {
         let state = load_state(&path);
         Self {
             state: Mutex::new(state),
-            path,
+            path: Some(path),
+        }
+    }
+
+    /// A store that is never written to disk.
+    pub fn in_memory() -> Self {
+        Self {
+            state: Mutex::new(HashMap::new()),
+            path: None,
         }
     }
 
@@ -253,7 +262,7 @@ impl QuotaStore {
         let snapshot: HashMap<String, QuotaUsage> = guard.clone();
         drop(guard);
 
-        persist_state(&self.path, &snapshot);
+        self.persist(&snapshot);
     }
 
     /// Return a point-in-time snapshot of all provider usage entries.
@@ -280,7 +289,7 @@ impl QuotaStore {
         guard.remove(name);
         let snapshot: HashMap<String, QuotaUsage> = guard.clone();
         drop(guard);
-        persist_state(&self.path, &snapshot);
+        self.persist(&snapshot);
     }
 
     /// Reset usage for all providers and persist the change.
@@ -294,7 +303,13 @@ impl QuotaStore {
         };
         guard.clear();
         drop(guard);
-        persist_state(&self.path, &HashMap::new());
+        self.persist(&HashMap::new());
+    }
+
+    fn persist(&self, state: &HashMap<String, QuotaUsage>) {
+        if let Some(path) = self.path.as_deref() {
+            persist_state(path, state);
+        }
     }
 }
 
@@ -459,6 +474,14 @@ impl crate::providers::LLMProvider for QuotaProvider {
     async fn embed(&self, texts: &[String]) -> crate::error::Result<Vec<Vec<f32>>> {
         self.inner.embed(texts).await
     }
+
+    fn supports_vision(&self) -> bool {
+        self.inner.supports_vision()
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -515,7 +538,7 @@ mod tests {
     fn store_in_tmpdir(tmp: &TempDir) -> QuotaStore {
         QuotaStore {
             state: Mutex::new(HashMap::new()),
-            path: tmp.path().join("usage.json"),
+            path: Some(tmp.path().join("usage.json")),
         }
     }
 
@@ -741,6 +764,14 @@ mod tests {
 
     // --- Serde roundtrips ---
 
+    #[test]
+    fn test_in_memory_store_writes_nothing() {
+        let store = QuotaStore::in_memory();
+        store.record("total", &QuotaPeriod::Monthly, 2.5, 100);
+        assert!((store.snapshot()["total"].cost_usd - 2.5).abs() < 1e-9);
+        assert!(store.path.is_none());
+    }
+
     #[test]
     fn test_quota_period_serde() {
         let encoded = serde_json::to_string(&QuotaPeriod::Daily).unwrap();
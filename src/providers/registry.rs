@@ -47,6 +47,12 @@ pub struct RuntimeProviderSelection {
     pub auth_header: Option<String>,
     /// Effective API version param for this provider.
     pub api_version: Option<String>,
+    /// Whether to use prompt caching (Anthropic only).
+    pub prompt_caching: bool,
+    /// How long the model stays loaded after a request (Ollama only).
+    pub keep_alive: Option<String>,
+    /// AWS region (Bedrock only).
+    pub region: Option<String>,
 }
 
 /// Provider registry in priority order.
@@ -187,11 +193,11 @@ pub const PROVIDER_REGISTRY: &[ProviderSpec] = &[
         name: "bedrock",
         model_keywords: &["bedrock", "anthropic.claude", "meta.llama", "amazon.titan"],
         runtime_supported: true,
-        default_base_url: None, // User must configure api_base pointing to a SigV4 proxy or Bedrock API key endpoint
+        default_base_url: None, // native Converse API, or api_base pointing to a SigV4 proxy
         backend: "openai",
-        default_auth_header: None, // AWS SigV4 required; not yet implemented natively
+        default_auth_header: None,
         default_api_version: None,
-        api_key_required: true,
+        api_key_required: false, // native path signs with the AWS credential chain
     },
     ProviderSpec {
         name: "xai",
@@ -486,6 +492,9 @@ pub fn resolve_runtime_providers(config: &Config) -> Vec<RuntimeProviderSelectio
             model: provider.and_then(|p| p.model.clone()),
             auth_header: effective_auth_header,
             api_version: effective_api_version,
+            prompt_caching: provider.is_some_and(|p| p.prompt_caching),
+            keep_alive: provider.and_then(|p| p.keep_alive.clone()),
+            region: provider.and_then(|p| p.region.clone()),
         });
     }
 
@@ -956,6 +965,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bedrock_resolves_without_key_for_aws_credentials() {
+        let mut config = Config::default();
+        config.providers.bedrock = Some(ProviderConfig {
+            region: Some("eu-central-1".to_string()),
+            ..Default::default()
+        });
+
+        let selected = resolve_runtime_provider(&config).expect("should resolve");
+        assert_eq!(selected.name, "bedrock");
+        assert!(selected.api_key.is_empty());
+        assert!(selected.api_base.is_none());
+        assert_eq!(selected.region.as_deref(), Some("eu-central-1"));
+    }
+
     #[test]
     fn test_standard_provider_auth_header_is_none() {
         let mut config = Config::default();
@@ -17,6 +17,8 @@
 //! // Use `provider` as any other LLMProvider — retries happen automatically.
 //! ```
 
+use std::time::{Duration, Instant};
+
 use async_trait::async_trait;
 use tracing::warn;
 
@@ -126,6 +128,54 @@ impl RetryProvider {
     fn budget_exceeded(&self, start: std::time::Instant) -> bool {
         self.retry_budget_ms > 0 && start.elapsed().as_millis() as u64 >= self.retry_budget_ms
     }
+
+    /// Sleep before retry `attempt` (1-indexed) of a request that failed with
+    /// `err`.
+    ///
+    /// The delay is the exponential backoff, or the server's `Retry-After` when
+    /// that is longer. Returns `false` without sleeping when the budget is spent
+    /// or the delay would run past it.
+    async fn backoff(&self, request: &str, attempt: u32, err: &ZeptoError, start: Instant) -> bool {
+        let backoff_ms = compute_delay(
+            attempt - 1,
+            self.base_delay_ms,
+            self.max_delay_ms,
+            jitter_ms(self.base_delay_ms),
+        );
+        let retry_after = server_retry_after(err);
+        let delay = retry_after.map_or(Duration::from_millis(backoff_ms), |wait| {
+            wait.max(Duration::from_millis(backoff_ms))
+        });
+
+        if self.budget_exceeded(start)
+            || (self.retry_budget_ms > 0
+                && start.elapsed().saturating_add(delay)
+                    > Duration::from_millis(self.retry_budget_ms))
+        {
+            warn!(
+                provider = self.inner.name(),
+                request = request,
+                elapsed_ms = start.elapsed().as_millis() as u64,
+                delay_ms = delay.as_millis() as u64,
+                budget_ms = self.retry_budget_ms,
+                "Retry budget exhausted"
+            );
+            return false;
+        }
+
+        warn!(
+            provider = self.inner.name(),
+            request = request,
+            attempt = attempt,
+            max_retries = self.max_retries,
+            delay_ms = delay.as_millis() as u64,
+            retry_after_ms = retry_after.map(|d| d.as_millis() as u64),
+            error = %err,
+            "Retrying request after transient error"
+        );
+        tokio::time::sleep(delay).await;
+        true
+    }
 }
 
 fn is_context_window_exceeded(msg: &str) -> bool {
@@ -189,6 +239,9 @@ pub fn is_retryable(err: &ZeptoError) -> bool {
             }
             // Transient signals — always retry
             (lower.contains("rate limit") || lower.contains("rate_limit"))
+                || lower.contains("connection reset")
+                || lower.contains("connection closed")
+                || lower.contains("error sending request")
                 || lower.contains("429")
                 || lower.contains("500")
                 || lower.contains("overload")
@@ -198,10 +251,55 @@ pub fn is_retryable(err: &ZeptoError) -> bool {
                 || lower.contains("504")
                 || lower.contains("timeout")
         }
+        // Dropped connections and timeouts before a response arrived
+        ZeptoError::Http(e) => e.is_timeout() || e.is_connect() || e.is_request(),
         _ => false,
     }
 }
 
+/// How long the server asked to wait before retrying `err`, if it said.
+fn server_retry_after(err: &ZeptoError) -> Option<Duration> {
+    match err {
+        ZeptoError::ProviderTyped(pe) => pe.retry_after(),
+        _ => None,
+    }
+}
+
+/// Read the wait a provider asked for from its response headers.
+///
+/// Understands `retry-after-ms` (OpenAI) and `retry-after` given either as
+/// seconds or as an HTTP date.
+pub fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+    };
+
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.parse::<f64>().ok()) {
+        return Duration::try_from_secs_f64(ms / 1000.0).ok();
+    }
+    let value = header("retry-after")?;
+    if let Ok(secs) = value.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs).ok();
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (at.with_timezone(&chrono::Utc) - chrono::Utc::now())
+        .to_std()
+        .ok()
+}
+
+/// Jitter in `[0, base_delay_ms)` for backoff delays.
+fn jitter_ms(base_delay_ms: u64) -> u64 {
+    // Use nanosecond component of system time as a lightweight jitter source.
+    // This avoids adding the `rand` crate while still decorrelating concurrent retries.
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64 % (base_delay_ms.max(1)))
+        .unwrap_or(0)
+}
+
 /// Compute and sleep for the backoff delay for a given retry attempt.
 ///
 /// Delay formula: `min(base_delay_ms * 2^attempt + jitter, max_delay_ms)`
@@ -215,16 +313,12 @@ pub fn is_retryable(err: &ZeptoError) -> bool {
 /// * `base_delay_ms` - Base delay in milliseconds
 /// * `max_delay_ms` - Maximum delay cap in milliseconds
 pub async fn delay_with_jitter(attempt: u32, base_delay_ms: u64, max_delay_ms: u64) {
-    let exponential = base_delay_ms.saturating_mul(1u64 << attempt.min(16));
-
-    // Use nanosecond component of system time as a lightweight jitter source.
-    // This avoids adding the `rand` crate while still decorrelating concurrent retries.
-    let jitter_ms = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.subsec_nanos() as u64 % (base_delay_ms.max(1)))
-        .unwrap_or(0);
-
-    let delay = exponential.saturating_add(jitter_ms).min(max_delay_ms);
+    let delay = compute_delay(
+        attempt,
+        base_delay_ms,
+        max_delay_ms,
+        jitter_ms(base_delay_ms),
+    );
 
     tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
 }
@@ -272,26 +366,10 @@ impl LLMProvider for RetryProvider {
 
         // Retry attempts (clone for each since we may need the originals again)
         for attempt in 0..self.max_retries {
-            if attempt > 0 {
-                if self.budget_exceeded(start) {
-                    warn!(
-                        provider = self.inner.name(),
-                        elapsed_ms = start.elapsed().as_millis() as u64,
-                        budget_ms = self.retry_budget_ms,
-                        "Retry budget exhausted for chat request"
-                    );
-                    return Err(last_err.unwrap());
+            if let Some(err) = last_err.take() {
+                if !self.backoff("chat", attempt, &err, start).await {
+                    return Err(err);
                 }
-                if let Some(ref err) = last_err {
-                    warn!(
-                        provider = self.inner.name(),
-                        attempt = attempt,
-                        max_retries = self.max_retries,
-                        error = %err,
-                        "Retrying chat request after transient error"
-                    );
-                }
-                delay_with_jitter(attempt - 1, self.base_delay_ms, self.max_delay_ms).await;
             }
 
             match self
@@ -310,26 +388,10 @@ impl LLMProvider for RetryProvider {
         }
 
         // Final attempt — move instead of clone
-        if self.max_retries > 0 {
-            if self.budget_exceeded(start) {
-                warn!(
-                    provider = self.inner.name(),
-                    elapsed_ms = start.elapsed().as_millis() as u64,
-                    budget_ms = self.retry_budget_ms,
-                    "Retry budget exhausted for chat request"
-                );
-                return Err(last_err.unwrap());
-            }
-            if let Some(ref err) = last_err {
-                warn!(
-                    provider = self.inner.name(),
-                    attempt = self.max_retries,
-                    max_retries = self.max_retries,
-                    error = %err,
-                    "Retrying chat request after transient error"
-                );
+        if let Some(err) = last_err {
+            if !self.backoff("chat", self.max_retries, &err, start).await {
+                return Err(err);
             }
-            delay_with_jitter(self.max_retries - 1, self.base_delay_ms, self.max_delay_ms).await;
         }
         self.inner.chat(messages, tools, model, options).await
     }
@@ -346,26 +408,10 @@ impl LLMProvider for RetryProvider {
 
         // Retry attempts (clone for each since we may need the originals again)
         for attempt in 0..self.max_retries {
-            if attempt > 0 {
-                if self.budget_exceeded(start) {
-                    warn!(
-                        provider = self.inner.name(),
-                        elapsed_ms = start.elapsed().as_millis() as u64,
-                        budget_ms = self.retry_budget_ms,
-                        "Retry budget exhausted for chat_stream request"
-                    );
-                    return Err(last_err.unwrap());
-                }
-                if let Some(ref err) = last_err {
-                    warn!(
-                        provider = self.inner.name(),
-                        attempt = attempt,
-                        max_retries = self.max_retries,
-                        error = %err,
-                        "Retrying chat_stream request after transient error"
-                    );
+            if let Some(err) = last_err.take() {
+                if !self.backoff("chat_stream", attempt, &err, start).await {
+                    return Err(err);
                 }
-                delay_with_jitter(attempt - 1, self.base_delay_ms, self.max_delay_ms).await;
             }
 
             match self
@@ -384,26 +430,13 @@ impl LLMProvider for RetryProvider {
         }
 
         // Final attempt — move instead of clone
-        if self.max_retries > 0 {
-            if self.budget_exceeded(start) {
-                warn!(
-                    provider = self.inner.name(),
-                    elapsed_ms = start.elapsed().as_millis() as u64,
-                    budget_ms = self.retry_budget_ms,
-                    "Retry budget exhausted for chat_stream request"
-                );
-                return Err(last_err.unwrap());
-            }
-            if let Some(ref err) = last_err {
-                warn!(
-                    provider = self.inner.name(),
-                    attempt = self.max_retries,
-                    max_retries = self.max_retries,
-                    error = %err,
-                    "Retrying chat_stream request after transient error"
-                );
+        if let Some(err) = last_err {
+            if !self
+                .backoff("chat_stream", self.max_retries, &err, start)
+                .await
+            {
+                return Err(err);
             }
-            delay_with_jitter(self.max_retries - 1, self.base_delay_ms, self.max_delay_ms).await;
         }
         self.inner
             .chat_stream(messages, tools, model, options)
@@ -417,6 +450,14 @@ impl LLMProvider for RetryProvider {
     async fn embed(&self, texts: &[String]) -> crate::error::Result<Vec<Vec<f32>>> {
         self.inner.embed(texts).await
     }
+
+    fn supports_vision(&self) -> bool {
+        self.inner.supports_vision()
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
 }
 
 #[cfg(test)]
@@ -558,7 +599,7 @@ mod tests {
 
     #[test]
     fn test_is_retryable_generic_error() {
-        let err = ZeptoError::Provider("Connection reset by peer".to_string());
+        let err = ZeptoError::Provider("Unexpected response shape".to_string());
         assert!(!is_retryable(&err));
     }
 
@@ -1101,4 +1142,111 @@ mod tests {
         let config = RetryConfig::default();
         assert_eq!(config.retry_budget_ms, 45_000);
     }
+
+    #[test]
+    fn test_parse_retry_after_headers() {
+        use reqwest::header::{HeaderMap, HeaderValue};
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+
+        headers.insert("retry-after", HeaderValue::from_static("7"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(7)));
+
+        headers.insert("retry-after-ms", HeaderValue::from_static("250"));
+        assert_eq!(
+            parse_retry_after(&headers),
+            Some(Duration::from_millis(250))
+        );
+
+        let mut headers = HeaderMap::new();
+        let at = chrono::Utc::now() + chrono::Duration::seconds(30);
+        headers.insert(
+            "retry-after",
+            HeaderValue::from_str(&at.to_rfc2822()).unwrap(),
+        );
+        let wait = parse_retry_after(&headers).unwrap();
+        assert!(wait > Duration::from_secs(25) && wait <= Duration::from_secs(30));
+
+        headers.insert("retry-after", HeaderValue::from_static("soon"));
+        assert_eq!(parse_retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_is_retryable_connection_reset() {
+        let err = ZeptoError::Provider(
+            "OpenAI request failed: error sending request: connection reset by peer".to_string(),
+        );
+        assert!(is_retryable(&err));
+    }
+
+    /// Fails once with a rate limit carrying `Retry-After`, then succeeds.
+    struct RetryAfterProvider {
+        calls: std::sync::atomic::AtomicU32,
+        retry_after: Duration,
+    }
+
+    #[async_trait]
+    impl LLMProvider for RetryAfterProvider {
+        fn name(&self) -> &str {
+            "retry-after"
+        }
+
+        fn default_model(&self) -> &str {
+            "test-model"
+        }
+
+        async fn chat(
+            &self,
+            _messages: Vec<Message>,
+            _tools: Vec<ToolDefinition>,
+            _model: Option<&str>,
+            _options: ChatOptions,
+        ) -> Result<LLMResponse> {
+            use crate::error::ProviderError;
+            if self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                Err(ProviderError::RateLimit("429".into())
+                    .with_retry_after(Some(self.retry_after))
+                    .into())
+            } else {
+                Ok(LLMResponse::text("recovered"))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_provider_honors_retry_after() {
+        let inner = RetryAfterProvider {
+            calls: std::sync::atomic::AtomicU32::new(0),
+            retry_after: Duration::from_millis(80),
+        };
+        let provider = RetryProvider::new(Box::new(inner))
+            .with_base_delay_ms(1)
+            .with_max_delay_ms(5);
+
+        let start = std::time::Instant::now();
+        let result = provider
+            .chat(vec![], vec![], None, ChatOptions::default())
+            .await;
+
+        assert_eq!(result.unwrap().content, "recovered");
+        assert!(start.elapsed() >= Duration::from_millis(75));
+    }
+
+    #[tokio::test]
+    async fn test_retry_provider_gives_up_when_retry_after_exceeds_budget() {
+        let inner = RetryAfterProvider {
+            calls: std::sync::atomic::AtomicU32::new(0),
+            retry_after: Duration::from_secs(120),
+        };
+        let provider = RetryProvider::new(Box::new(inner)).with_retry_budget_ms(1_000);
+
+        let start = std::time::Instant::now();
+        let result = provider
+            .chat(vec![], vec![], None, ChatOptions::default())
+            .await;
+
+        assert!(result.unwrap_err().to_string().contains("retry after 120s"));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
 }
@@ -419,6 +419,15 @@ impl LLMProvider for RotationProvider {
         let index = self.select_provider_index();
         self.providers[index].0.embed(texts).await
     }
+
+    /// Any provider in the rotation may serve a request, so all must accept images.
+    fn supports_vision(&self) -> bool {
+        self.providers.iter().all(|(p, _)| p.supports_vision())
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.providers.iter().any(|(p, _)| p.supports_streaming())
+    }
 }
 
 #[cfg(test)]
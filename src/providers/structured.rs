@@ -12,7 +12,9 @@
 //! Each provider handles structured output differently:
 //!
 //! - **OpenAI**: Uses the native `response_format` parameter.
-//! - **Claude**: Appends JSON instructions to the system prompt.
+//! - **Claude**: Forces a call to the [`STRUCTURED_OUTPUT_TOOL`] whose input
+//!   schema is the requested schema when the request offers no other tools;
+//!   otherwise appends JSON instructions to the system prompt.
 //!
 //! # Example
 //!
@@ -43,6 +45,13 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::providers::{LLMResponse, ToolDefinition};
+use crate::tools::schema::validate_args;
+
+/// Tool a provider without a JSON mode is made to call, with the answer as
+/// its input.
+pub const STRUCTURED_OUTPUT_TOOL: &str = "structured_output";
+
 /// Output format configuration for LLM responses.
 ///
 /// Controls whether the LLM should return plain text or structured JSON.
@@ -60,7 +69,8 @@ pub enum OutputFormat {
     /// JSON Schema mode — instructs the LLM to return JSON matching a schema.
     ///
     /// For OpenAI: sets `response_format: { "type": "json_schema", "json_schema": {...} }`
-    /// For Claude: adds schema instruction to system prompt
+    /// For Claude: forces the [`STRUCTURED_OUTPUT_TOOL`] when no other tools
+    /// are offered, else adds schema instruction to system prompt
     JsonSchema {
         /// Name for the schema (used by OpenAI's `json_schema` response format).
         name: String,
@@ -210,6 +220,30 @@ impl OutputFormat {
         }
     }
 
+    /// The tool whose forced call carries a `JsonSchema` answer as its input,
+    /// for providers that can force a tool but have no JSON mode. `None` for
+    /// the other formats.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::providers::structured::{OutputFormat, STRUCTURED_OUTPUT_TOOL};
+    /// use serde_json::json;
+    ///
+    /// let format = OutputFormat::json_schema("answer", json!({"type": "object"}));
+    /// assert_eq!(format.to_forced_tool().unwrap().name, STRUCTURED_OUTPUT_TOOL);
+    /// assert!(OutputFormat::json().to_forced_tool().is_none());
+    /// ```
+    pub fn to_forced_tool(&self) -> Option<ToolDefinition> {
+        match self {
+            OutputFormat::JsonSchema { name, schema, .. } => Some(ToolDefinition::new(
+                STRUCTURED_OUTPUT_TOOL,
+                &format!("Return the answer ({}) as this tool's input.", name),
+                schema.clone(),
+            )),
+            _ => None,
+        }
+    }
+
     /// Generate a system prompt suffix for Claude to request JSON output.
     ///
     /// Claude does not have a native `response_format` parameter, so we
@@ -245,12 +279,28 @@ impl OutputFormat {
     }
 }
 
+/// Turn the forced [`STRUCTURED_OUTPUT_TOOL`] call in `response` into its
+/// text content, as if the model had answered with the JSON directly.
+pub fn unwrap_forced_output(response: &mut LLMResponse) {
+    if let Some(index) = response
+        .tool_calls
+        .iter()
+        .position(|call| call.name == STRUCTURED_OUTPUT_TOOL)
+    {
+        response.content = response.tool_calls.remove(index).arguments;
+    }
+}
+
 /// Validate a response string against the expected output format.
 ///
+/// A surrounding Markdown code fence is ignored.
+///
 /// - For `Text`: returns `Err` since validation is not applicable.
 /// - For `Json`: parses the response as JSON and returns the parsed value.
-/// - For `JsonSchema`: parses the response as JSON, then checks that all
-///   required top-level keys (from the schema's `"required"` field) are present.
+/// - For `JsonSchema`: parses the response as JSON, checks that all
+///   required top-level keys (from the schema's `"required"` field) are
+///   present, then validates the value against the schema (types,
+///   `required`, `enum`, nested `properties` and `items`).
 ///
 /// # Arguments
 /// * `response` - The raw response string from the LLM
@@ -275,13 +325,14 @@ pub fn validate_json_response(
     response: &str,
     format: &OutputFormat,
 ) -> Result<serde_json::Value, String> {
+    let response = strip_code_fence(response);
     match format {
         OutputFormat::Text => Err("Not in JSON mode".to_string()),
         OutputFormat::Json => {
             serde_json::from_str(response).map_err(|e| format!("Invalid JSON: {}", e))
         }
         OutputFormat::JsonSchema { schema, .. } => {
-            let value: serde_json::Value =
+            let mut value: serde_json::Value =
                 serde_json::from_str(response).map_err(|e| format!("Invalid JSON: {}", e))?;
 
             // Check required top-level keys if the schema specifies them
@@ -297,11 +348,39 @@ pub fn validate_json_response(
                 }
             }
 
+            let violations = validate_args(schema, &mut value, false);
+            if !violations.is_empty() {
+                let problems: Vec<String> = violations
+                    .iter()
+                    .map(|v| {
+                        let path = if v.path.is_empty() { "value" } else { &v.path };
+                        format!("{}: {}", path, v.message)
+                    })
+                    .collect();
+                return Err(format!("Does not match schema: {}", problems.join("; ")));
+            }
+
             Ok(value)
         }
     }
 }
 
+/// `text` without a surrounding Markdown code fence (` ```json ... ``` `).
+fn strip_code_fence(text: &str) -> &str {
+    let trimmed = text.trim();
+    let Some(body) = trimmed
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+    else {
+        return trimmed;
+    };
+    // Drop the info string (e.g. "json") on the opening line.
+    match body.split_once('\n') {
+        Some((_, code)) => code.trim(),
+        None => body.trim(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -556,4 +635,49 @@ mod tests {
         let cloned = original.clone();
         assert_eq!(original, cloned);
     }
+
+    #[test]
+    fn test_validate_json_schema_checks_types_and_fences() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "tags": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["name"]
+        });
+        let format = OutputFormat::json_schema("person", schema);
+
+        let fenced = "```json\n{\"name\": \"Alice\", \"tags\": [\"a\"]}\n```";
+        assert_eq!(
+            validate_json_response(fenced, &format).unwrap()["name"],
+            "Alice"
+        );
+
+        let err = validate_json_response(r#"{"name": 3, "tags": ["a", 1]}"#, &format).unwrap_err();
+        assert!(err.starts_with("Does not match schema:"));
+        assert!(err.contains("name"));
+        assert!(err.contains("tags[1]"));
+    }
+
+    #[test]
+    fn test_forced_tool_call_becomes_content() {
+        let schema = json!({"type": "object", "required": ["ok"]});
+        let tool = OutputFormat::json_schema("result", schema.clone())
+            .to_forced_tool()
+            .unwrap();
+        assert_eq!(tool.parameters, schema);
+
+        let mut response = LLMResponse::with_tools(
+            "",
+            vec![crate::providers::LLMToolCall::new(
+                "call_1",
+                STRUCTURED_OUTPUT_TOOL,
+                r#"{"ok": true}"#,
+            )],
+        );
+        unwrap_forced_output(&mut response);
+        assert_eq!(response.content, r#"{"ok": true}"#);
+        assert!(response.tool_calls.is_empty());
+    }
 }
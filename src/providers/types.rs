@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::error::{Result, ZeptoError};
 use crate::providers::structured::OutputFormat;
-use crate::session::Message;
+use crate::session::{Message, ThinkingBlock};
 
 /// Events emitted during streaming LLM responses.
 #[derive(Debug)]
@@ -17,6 +17,9 @@ pub enum StreamEvent {
     Delta(String),
     /// Tool calls detected mid-stream (triggers fallback to non-streaming tool loop).
     ToolCalls(Vec<LLMToolCall>),
+    /// Reasoning blocks the model wrote before its answer (extended
+    /// thinking). Not part of the reply text.
+    Thinking(Vec<ThinkingBlock>),
     /// Stream complete — carries the full assembled content and usage stats.
     Done {
         content: String,
@@ -126,11 +129,14 @@ pub trait LLMProvider: Send + Sync {
         options: ChatOptions,
     ) -> Result<tokio::sync::mpsc::Receiver<StreamEvent>> {
         let response = self.chat(messages, tools, model, options).await?;
-        let (tx, rx) = tokio::sync::mpsc::channel(3);
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
         let tool_calls = response.tool_calls;
         let content = response.content;
         let usage = response.usage;
 
+        if !response.thinking.is_empty() {
+            let _ = tx.send(StreamEvent::Thinking(response.thinking)).await;
+        }
         if !content.is_empty() {
             let _ = tx.send(StreamEvent::Delta(content.clone())).await;
         }
@@ -153,6 +159,23 @@ pub trait LLMProvider: Send + Sync {
             "Embedding not supported by this provider".into(),
         ))
     }
+
+    /// Whether this provider accepts image content parts in user messages.
+    ///
+    /// Defaults to `false`; providers with a vision-capable API override it.
+    fn supports_vision(&self) -> bool {
+        false
+    }
+
+    /// Whether [`chat_stream`](Self::chat_stream) yields text as it is
+    /// generated rather than all at once.
+    ///
+    /// Defaults to `false`, matching the default `chat_stream`; callers then
+    /// use `chat` directly. Providers that override `chat_stream` with real
+    /// streaming override this too.
+    fn supports_streaming(&self) -> bool {
+        false
+    }
 }
 
 /// Options for chat completion requests.
@@ -170,6 +193,9 @@ pub struct ChatOptions {
     pub stop: Option<Vec<String>>,
     /// Output format (text, JSON, or JSON schema)
     pub output_format: OutputFormat,
+    /// Tokens the model may spend reasoning before it answers (extended
+    /// thinking), on providers and models that support it
+    pub thinking_budget: Option<u32>,
 }
 
 impl ChatOptions {
@@ -274,6 +300,23 @@ impl ChatOptions {
         self.output_format = output_format;
         self
     }
+
+    /// Let the model reason for up to `budget` tokens before answering.
+    ///
+    /// # Arguments
+    /// * `budget` - Reasoning tokens allowed, on top of `max_tokens`
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::providers::ChatOptions;
+    ///
+    /// let options = ChatOptions::new().with_thinking_budget(4096);
+    /// assert_eq!(options.thinking_budget, Some(4096));
+    /// ```
+    pub fn with_thinking_budget(mut self, budget: u32) -> Self {
+        self.thinking_budget = Some(budget);
+        self
+    }
 }
 
 /// Response from an LLM chat completion request.
@@ -285,6 +328,9 @@ pub struct LLMResponse {
     pub tool_calls: Vec<LLMToolCall>,
     /// Token usage information (if available)
     pub usage: Option<Usage>,
+    /// Reasoning the model wrote before answering (extended thinking)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub thinking: Vec<ThinkingBlock>,
 }
 
 impl LLMResponse {
@@ -306,6 +352,7 @@ impl LLMResponse {
             content: content.to_string(),
             tool_calls: vec![],
             usage: None,
+            thinking: vec![],
         }
     }
 
@@ -328,6 +375,7 @@ impl LLMResponse {
             content: content.to_string(),
             tool_calls,
             usage: None,
+            thinking: vec![],
         }
     }
 
@@ -352,6 +400,15 @@ impl LLMResponse {
         self.usage = Some(usage);
         self
     }
+
+    /// Set the reasoning blocks the model wrote before answering.
+    ///
+    /// # Arguments
+    /// * `thinking` - The blocks, as the provider returned them
+    pub fn with_thinking(mut self, thinking: Vec<ThinkingBlock>) -> Self {
+        self.thinking = thinking;
+        self
+    }
 }
 
 /// A tool call made by the LLM.
@@ -424,6 +481,18 @@ pub struct Usage {
     pub completion_tokens: u32,
     /// Total tokens used (prompt + completion)
     pub total_tokens: u32,
+    /// Prompt tokens read from the provider's prompt cache (included in
+    /// `prompt_tokens`)
+    #[serde(default)]
+    pub cache_read_tokens: u32,
+    /// Prompt tokens written to the provider's prompt cache (included in
+    /// `prompt_tokens`)
+    #[serde(default)]
+    pub cache_write_tokens: u32,
+    /// Completion tokens spent on reasoning (included in
+    /// `completion_tokens`)
+    #[serde(default)]
+    pub thinking_tokens: u32,
 }
 
 impl Usage {
@@ -445,8 +514,42 @@ impl Usage {
             prompt_tokens,
             completion_tokens,
             total_tokens: prompt_tokens + completion_tokens,
+            cache_read_tokens: 0,
+            cache_write_tokens: 0,
+            thinking_tokens: 0,
         }
     }
+
+    /// Record how many of the prompt tokens were read from and written to
+    /// the provider's prompt cache.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::providers::Usage;
+    ///
+    /// let usage = Usage::new(1200, 50).with_cache(1000, 0);
+    /// assert_eq!(usage.cache_read_tokens, 1000);
+    /// ```
+    pub fn with_cache(mut self, read_tokens: u32, write_tokens: u32) -> Self {
+        self.cache_read_tokens = read_tokens;
+        self.cache_write_tokens = write_tokens;
+        self
+    }
+
+    /// Record how many of the completion tokens the model spent reasoning.
+    /// Counts above `completion_tokens` are capped to it.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::providers::Usage;
+    ///
+    /// let usage = Usage::new(100, 900).with_thinking(700);
+    /// assert_eq!(usage.thinking_tokens, 700);
+    /// ```
+    pub fn with_thinking(mut self, tokens: u32) -> Self {
+        self.thinking_tokens = tokens.min(self.completion_tokens);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -459,6 +562,7 @@ mod tests {
             content: "Hello".to_string(),
             tool_calls: vec![],
             usage: None,
+            thinking: vec![],
         };
         assert_eq!(response.content, "Hello");
         assert!(!response.has_tool_calls());
@@ -587,6 +691,15 @@ mod tests {
         assert_eq!(usage.total_tokens, 150);
     }
 
+    #[test]
+    fn test_usage_thinking_is_part_of_completion() {
+        let usage = Usage::new(100, 50).with_thinking(80);
+        assert_eq!(usage.thinking_tokens, 50);
+        let usage = Usage::new(100, 50).with_thinking(20);
+        assert_eq!(usage.thinking_tokens, 20);
+        assert_eq!(usage.total_tokens, 150);
+    }
+
     #[test]
     fn test_llm_response_serialization() {
         let response = LLMResponse::text("Hello");
@@ -713,7 +826,12 @@ mod tests {
                 Ok(LLMResponse::with_tools(
                     "",
                     vec![LLMToolCall::new("call_1", "search", r#"{"q":"rust"}"#)],
-                ))
+                )
+                .with_thinking(vec![ThinkingBlock {
+                    thinking: "search first".to_string(),
+                    signature: "sig".to_string(),
+                    redacted: None,
+                }]))
             }
 
             fn default_model(&self) -> &str {
@@ -731,6 +849,11 @@ mod tests {
             .await
             .unwrap();
 
+        match rx.recv().await.unwrap() {
+            StreamEvent::Thinking(thinking) => assert_eq!(thinking[0].thinking, "search first"),
+            other => panic!("Expected Thinking event, got: {other:?}"),
+        }
+
         match rx.recv().await.unwrap() {
             StreamEvent::ToolCalls(tool_calls) => {
                 assert_eq!(tool_calls.len(), 1);
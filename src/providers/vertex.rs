@@ -23,6 +23,7 @@ use crate::error::{Result, ZeptoError};
 use crate::session::{ContentPart, ImageSource, Message, Role};
 
 use super::gemini::GeminiProvider;
+use super::retry::parse_retry_after;
 use super::{parse_provider_error, ChatOptions, LLMProvider, LLMResponse, ToolDefinition};
 
 /// Default model when none is configured or passed at call time.
@@ -301,6 +302,7 @@ impl LLMProvider for VertexProvider {
         }
 
         let status = response.status().as_u16();
+        let retry_after = parse_retry_after(response.headers());
         let error_text = response.text().await.unwrap_or_default();
 
         // Try to extract a useful message from the Vertex AI error body.
@@ -313,7 +315,9 @@ impl LLMProvider for VertexProvider {
             })
             .unwrap_or_else(|| format!("Vertex AI API error: {}", error_text));
 
-        Err(ZeptoError::from(parse_provider_error(status, &body_msg)))
+        Err(ZeptoError::from(
+            parse_provider_error(status, &body_msg).with_retry_after(retry_after),
+        ))
     }
 
     fn default_model(&self) -> &str {
@@ -323,6 +327,10 @@ impl LLMProvider for VertexProvider {
     fn name(&self) -> &str {
         "vertex"
     }
+
+    fn supports_vision(&self) -> bool {
+        true
+    }
 }
 
 // ── Tests ─────────────────────────────────────────────────────────────────────
@@ -21,6 +21,7 @@ use std::time::Duration;
 use tokio::process::Command;
 use tracing::warn;
 
+use super::io::output_with_stdin;
 use super::types::{CommandOutput, ContainerConfig, ContainerRuntime, RuntimeError, RuntimeResult};
 
 /// Apple Container runtime for macOS
@@ -180,11 +181,14 @@ impl ContainerRuntime for AppleContainerRuntime {
             args.push(format!("{}={}", key, value));
         }
 
+        // Keep stdin attached so input reaches the container
+        if config.stdin.is_some() {
+            args.push("--interactive".to_string());
+        }
+
         // Add the command
         args.push("--".to_string());
-        args.push("sh".to_string());
-        args.push("-c".to_string());
-        args.push(command.to_string());
+        args.extend(config.shell_invocation(command));
 
         let mut cmd = Command::new("container");
         cmd.args(&args)
@@ -192,10 +196,13 @@ impl ContainerRuntime for AppleContainerRuntime {
             .stderr(Stdio::piped());
 
         // Execute with timeout
-        let output = tokio::time::timeout(Duration::from_secs(config.timeout_secs), cmd.output())
-            .await
-            .map_err(|_| RuntimeError::Timeout(config.timeout_secs))?
-            .map_err(|e| RuntimeError::ExecutionFailed(e.to_string()))?;
+        let output = tokio::time::timeout(
+            Duration::from_secs(config.timeout_secs),
+            output_with_stdin(&mut cmd, config.stdin.as_deref()),
+        )
+        .await
+        .map_err(|_| RuntimeError::Timeout(config.timeout_secs))?
+        .map_err(|e| RuntimeError::ExecutionFailed(e.to_string()))?;
 
         Ok(CommandOutput::new(
             String::from_utf8_lossy(&output.stdout).to_string(),
@@ -6,7 +6,7 @@
 
 use async_trait::async_trait;
 
-use crate::config::BubblewrapConfig;
+use crate::config::{BubblewrapConfig, ShellKind};
 use crate::runtime::types::{
     CommandOutput, ContainerConfig, ContainerRuntime, RuntimeError, RuntimeResult,
 };
@@ -25,6 +25,16 @@ impl BubblewrapRuntime {
     ///
     /// `workspace` is an optional host path that is bind-mounted read-write.
     pub fn build_args(&self, command: &str, workspace: Option<&str>) -> Vec<String> {
+        self.build_shell_args(ShellKind::Sh, command, workspace)
+    }
+
+    /// Like [`build_args`](Self::build_args), running `command` in `shell`.
+    pub fn build_shell_args(
+        &self,
+        shell: ShellKind,
+        command: &str,
+        workspace: Option<&str>,
+    ) -> Vec<String> {
         let mut args: Vec<String> = Vec::new();
 
         for path in &self.config.ro_binds {
@@ -59,8 +69,8 @@ impl BubblewrapRuntime {
             args.push(extra.clone());
         }
 
-        args.push("sh".to_string());
-        args.push("-c".to_string());
+        args.push(shell.program().to_string());
+        args.push(shell.command_flag().to_string());
         args.push(command.to_string());
         args
     }
@@ -110,8 +120,10 @@ impl ContainerRuntime for BubblewrapRuntime {
             use std::time::Duration;
             use tokio::process::Command;
 
+            use crate::runtime::io::output_with_stdin;
+
             let workspace = config.workdir.as_ref().and_then(|p| p.to_str());
-            let args = self.build_args(command, workspace);
+            let args = self.build_shell_args(config.shell, command, workspace);
 
             let mut cmd = Command::new("bwrap");
             for arg in &args {
@@ -121,15 +133,20 @@ impl ContainerRuntime for BubblewrapRuntime {
             if let Some(ref workdir) = config.workdir {
                 cmd.current_dir(workdir);
             }
+            if config.clear_env {
+                cmd.env_clear();
+            }
             for (k, v) in &config.env {
                 cmd.env(k, v);
             }
 
-            let output =
-                tokio::time::timeout(Duration::from_secs(config.timeout_secs), cmd.output())
-                    .await
-                    .map_err(|_| RuntimeError::Timeout(config.timeout_secs))?
-                    .map_err(|e| RuntimeError::ExecutionFailed(e.to_string()))?;
+            let output = tokio::time::timeout(
+                Duration::from_secs(config.timeout_secs),
+                output_with_stdin(&mut cmd, config.stdin.as_deref()),
+            )
+            .await
+            .map_err(|_| RuntimeError::Timeout(config.timeout_secs))?
+            .map_err(|e| RuntimeError::ExecutionFailed(e.to_string()))?;
 
             Ok(CommandOutput::new(
                 String::from_utf8_lossy(&output.stdout).to_string(),
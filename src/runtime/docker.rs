@@ -7,6 +7,7 @@ use std::process::Stdio;
 use std::time::Duration;
 use tokio::process::Command;
 
+use super::io::output_with_stdin;
 use super::types::{CommandOutput, ContainerConfig, ContainerRuntime, RuntimeError, RuntimeResult};
 
 /// Docker runtime that executes commands in isolated containers
@@ -172,11 +173,14 @@ impl ContainerRuntime for DockerRuntime {
             args.push(format!("{}={}", key, value));
         }
 
+        // Keep stdin attached so input reaches the container
+        if config.stdin.is_some() {
+            args.push("-i".to_string());
+        }
+
         // Add image and command
         args.push(self.image.clone());
-        args.push("sh".to_string());
-        args.push("-c".to_string());
-        args.push(command.to_string());
+        args.extend(config.shell_invocation(command));
 
         let mut cmd = Command::new("docker");
         cmd.args(&args)
@@ -184,10 +188,13 @@ impl ContainerRuntime for DockerRuntime {
             .stderr(Stdio::piped());
 
         // Execute with timeout
-        let output = tokio::time::timeout(Duration::from_secs(config.timeout_secs), cmd.output())
-            .await
-            .map_err(|_| RuntimeError::Timeout(config.timeout_secs))?
-            .map_err(|e| RuntimeError::ExecutionFailed(e.to_string()))?;
+        let output = tokio::time::timeout(
+            Duration::from_secs(config.timeout_secs),
+            output_with_stdin(&mut cmd, config.stdin.as_deref()),
+        )
+        .await
+        .map_err(|_| RuntimeError::Timeout(config.timeout_secs))?
+        .map_err(|e| RuntimeError::ExecutionFailed(e.to_string()))?;
 
         Ok(CommandOutput::new(
             String::from_utf8_lossy(&output.stdout).to_string(),
@@ -5,7 +5,7 @@
 
 use async_trait::async_trait;
 
-use crate::config::FirejailConfig;
+use crate::config::{FirejailConfig, ShellKind};
 use crate::runtime::types::{
     CommandOutput, ContainerConfig, ContainerRuntime, RuntimeError, RuntimeResult,
 };
@@ -37,6 +37,11 @@ impl FirejailRuntime {
     /// 3. `--` separator
     /// 4. `sh -c <command>`
     pub fn build_args(&self, command: &str) -> Vec<String> {
+        self.build_shell_args(ShellKind::Sh, command)
+    }
+
+    /// Like [`build_args`](Self::build_args), running `command` in `shell`.
+    pub fn build_shell_args(&self, shell: ShellKind, command: &str) -> Vec<String> {
         let mut args: Vec<String> = Vec::new();
 
         // Profile selection: explicit path or --noprofile
@@ -52,8 +57,8 @@ impl FirejailRuntime {
 
         // Separator before the shell command
         args.push("--".to_string());
-        args.push("sh".to_string());
-        args.push("-c".to_string());
+        args.push(shell.program().to_string());
+        args.push(shell.command_flag().to_string());
         args.push(command.to_string());
 
         args
@@ -72,6 +77,8 @@ impl ContainerRuntime for FirejailRuntime {
             use std::process::Stdio;
             use tokio::process::Command;
 
+            use crate::runtime::io::output_with_stdin;
+
             Command::new("which")
                 .arg("firejail")
                 .stdout(Stdio::null())
@@ -105,7 +112,7 @@ impl ContainerRuntime for FirejailRuntime {
             use std::time::Duration;
             use tokio::process::Command;
 
-            let args = self.build_args(command);
+            let args = self.build_shell_args(config.shell, command);
             let mut cmd = Command::new("firejail");
             cmd.args(&args)
                 .stdout(Stdio::piped())
@@ -114,15 +121,20 @@ impl ContainerRuntime for FirejailRuntime {
             if let Some(ref workdir) = config.workdir {
                 cmd.current_dir(workdir);
             }
+            if config.clear_env {
+                cmd.env_clear();
+            }
             for (k, v) in &config.env {
                 cmd.env(k, v);
             }
 
-            let output =
-                tokio::time::timeout(Duration::from_secs(config.timeout_secs), cmd.output())
-                    .await
-                    .map_err(|_| RuntimeError::Timeout(config.timeout_secs))?
-                    .map_err(|e| RuntimeError::ExecutionFailed(e.to_string()))?;
+            let output = tokio::time::timeout(
+                Duration::from_secs(config.timeout_secs),
+                output_with_stdin(&mut cmd, config.stdin.as_deref()),
+            )
+            .await
+            .map_err(|_| RuntimeError::Timeout(config.timeout_secs))?
+            .map_err(|e| RuntimeError::ExecutionFailed(e.to_string()))?;
 
             Ok(CommandOutput::new(
                 String::from_utf8_lossy(&output.stdout).to_string(),
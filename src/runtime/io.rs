@@ -0,0 +1,135 @@
+//! Child process I/O shared by the runtimes.
+//!
+//! Feeding stdin and collecting output must happen concurrently: a child that
+//! fills its stdout pipe stops reading stdin, and a parent that writes all of
+//! stdin before reading anything then waits forever.
+
+use std::io;
+use std::process::{Output, Stdio};
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+
+use super::types::{OutputSink, OutputStream};
+
+/// Stdin setting for a child: a pipe when there is input to feed, otherwise
+/// `/dev/null` so the command never reads the host's stdin.
+pub(crate) fn stdin_stdio(input: Option<&str>) -> Stdio {
+    if input.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    }
+}
+
+/// Write `input` to the child's stdin while collecting its output.
+///
+/// Stdin is closed once the input is written so the child sees EOF. A child
+/// that exits or closes stdin without reading everything is not an error.
+pub(crate) async fn wait_with_stdin(mut child: Child, input: Option<&str>) -> io::Result<Output> {
+    let pipe = child.stdin.take();
+    let (written, output) = tokio::join!(write_stdin(pipe, input), child.wait_with_output());
+    let output = output?;
+    written?;
+    Ok(output)
+}
+
+/// Write `input` to a child's stdin pipe, then close it.
+///
+/// A child that exits or closes stdin without reading everything is not an
+/// error.
+pub(crate) async fn write_stdin(pipe: Option<ChildStdin>, input: Option<&str>) -> io::Result<()> {
+    let (Some(mut pipe), Some(input)) = (pipe, input) else {
+        return Ok(());
+    };
+    match pipe.write_all(input.as_bytes()).await {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+    // `pipe` drops here, closing the child's stdin.
+}
+
+/// Read `reader` to the end, appending everything to `captured` and passing
+/// each line (including its newline) to `on_output` as soon as it arrives.
+///
+/// A final line without a trailing newline is still delivered.
+pub(crate) async fn read_lines<R: AsyncRead + Unpin>(
+    reader: R,
+    stream: OutputStream,
+    captured: &mut Vec<u8>,
+    on_output: &OutputSink<'_>,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).await? == 0 {
+            return Ok(());
+        }
+        captured.extend_from_slice(&line);
+        on_output(stream, &String::from_utf8_lossy(&line));
+    }
+}
+
+/// Spawn `cmd` with `input` on stdin and wait for its output.
+///
+/// Equivalent to [`Command::output`] apart from the stdin handling.
+pub(crate) async fn output_with_stdin(
+    cmd: &mut Command,
+    input: Option<&str>,
+) -> io::Result<Output> {
+    let child = cmd.stdin(stdin_stdio(input)).spawn()?;
+    wait_with_stdin(child, input).await
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_large_input_does_not_deadlock() {
+        // Well past a pipe buffer in both directions.
+        let input = "x".repeat(1024 * 1024);
+        let mut cmd = Command::new("cat");
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let output = output_with_stdin(&mut cmd, Some(&input)).await.unwrap();
+        assert_eq!(output.stdout.len(), input.len());
+    }
+
+    #[tokio::test]
+    async fn test_child_closing_stdin_early_is_not_an_error() {
+        let input = "y\n".repeat(512 * 1024);
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg("head -n 1")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = output_with_stdin(&mut cmd, Some(&input)).await.unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"y\n");
+    }
+
+    #[tokio::test]
+    async fn test_read_lines_delivers_each_line() {
+        let seen = std::sync::Mutex::new(Vec::new());
+        let mut captured = Vec::new();
+        read_lines(
+            &b"one\ntwo\nthree"[..],
+            OutputStream::Stdout,
+            &mut captured,
+            &|_, line: &str| seen.lock().unwrap().push(line.to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec!["one\n", "two\n", "three"]);
+        assert_eq!(captured, b"one\ntwo\nthree");
+    }
+
+    #[tokio::test]
+    async fn test_no_input_reads_eof() {
+        let mut cmd = Command::new("cat");
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let output = output_with_stdin(&mut cmd, None).await.unwrap();
+        assert!(output.stdout.is_empty());
+    }
+}
@@ -104,13 +104,21 @@ fn execute_with_landlock_inner(
     use std::process::Stdio;
     use std::time::Duration;
 
-    let mut cmd = std::process::Command::new("sh");
-    cmd.arg("-c").arg(command);
+    let mut cmd = std::process::Command::new(config.shell.program());
+    cmd.arg(config.shell.command_flag()).arg(command);
     cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    cmd.stdin(if config.stdin.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    });
 
     if let Some(ref workdir) = config.workdir {
         cmd.current_dir(workdir);
     }
+    if config.clear_env {
+        cmd.env_clear();
+    }
     for (k, v) in &config.env {
         cmd.env(k, v);
     }
@@ -133,10 +141,20 @@ fn execute_with_landlock_inner(
 
     // Spawn and wait with timeout via thread + channel.
     let timeout = Duration::from_secs(config.timeout_secs);
-    let child = cmd
+    let mut child = cmd
         .spawn()
         .map_err(|e| RuntimeError::ExecutionFailed(format!("Failed to spawn command: {e}")))?;
 
+    // Feed stdin from its own thread so a child blocked on a full stdout pipe
+    // cannot deadlock against us. Dropping the pipe afterwards sends EOF; a
+    // child that stops reading early just makes the write fail.
+    if let (Some(mut pipe), Some(input)) = (child.stdin.take(), config.stdin.clone()) {
+        std::thread::spawn(move || {
+            use std::io::Write;
+            let _ = pipe.write_all(input.as_bytes());
+        });
+    }
+
     let (tx, rx) = std::sync::mpsc::channel();
     std::thread::spawn(move || {
         let _ = tx.send(child.wait_with_output());
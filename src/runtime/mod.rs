@@ -15,6 +15,7 @@ pub mod bubblewrap;
 pub mod docker;
 pub mod factory;
 pub mod firejail;
+pub(crate) mod io;
 pub mod landlock;
 pub mod native;
 pub mod types;
@@ -27,4 +28,7 @@ pub use factory::{available_runtimes, create_runtime};
 pub use firejail::FirejailRuntime;
 pub use landlock::LandlockRuntime;
 pub use native::NativeRuntime;
-pub use types::{CommandOutput, ContainerConfig, ContainerRuntime, RuntimeError, RuntimeResult};
+pub use types::{
+    CommandOutput, ContainerConfig, ContainerRuntime, OutputSink, OutputStream, RuntimeError,
+    RuntimeResult,
+};
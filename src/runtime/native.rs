@@ -8,7 +8,14 @@ use std::process::Stdio;
 use std::time::Duration;
 use tokio::process::Command;
 
-use super::types::{CommandOutput, ContainerConfig, ContainerRuntime, RuntimeError, RuntimeResult};
+#[cfg(windows)]
+use crate::config::ShellKind;
+
+use super::io::{read_lines, stdin_stdio, wait_with_stdin, write_stdin};
+use super::types::{
+    CommandOutput, ContainerConfig, ContainerRuntime, OutputSink, OutputStream, RuntimeError,
+    RuntimeResult,
+};
 
 /// Native runtime that executes commands directly on the host
 #[derive(Debug, Clone, Default)]
@@ -21,6 +28,182 @@ impl NativeRuntime {
     }
 }
 
+/// Build the shell invocation for `command` with piped output.
+fn build_command(command: &str, config: &ContainerConfig) -> Command {
+    let mut cmd = Command::new(config.shell.program());
+    cmd.arg(config.shell.command_flag());
+    // cmd.exe does its own parsing of the command line, so the command must
+    // reach it unquoted.
+    #[cfg(windows)]
+    if config.shell == ShellKind::Cmd {
+        cmd.raw_arg(command);
+    } else {
+        cmd.arg(command);
+    }
+    #[cfg(not(windows))]
+    cmd.arg(command);
+
+    // Set working directory if specified
+    if let Some(ref workdir) = config.workdir {
+        cmd.current_dir(workdir);
+    }
+
+    // Set environment variables
+    if config.clear_env {
+        cmd.env_clear();
+    }
+    for (key, value) in &config.env {
+        cmd.env(key, value);
+    }
+
+    // Capture output
+    cmd.stdin(stdin_stdio(config.stdin.as_deref()))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    // Lead a new process group so a timeout can take down everything the
+    // shell spawned (pipelines, background jobs), not just `sh` itself.
+    // Windows gets the same through a job object in `ProcessTree::of`.
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    cmd
+}
+
+/// Everything a spawned command starts: its process group on Unix, a job
+/// object holding it on Windows.
+///
+/// Dropping a timed-out child only kills the direct child (`sh` or
+/// `cmd.exe`); killing the tree also reaches its children and grandchildren,
+/// which would otherwise keep running.
+pub(crate) struct ProcessTree {
+    #[cfg(unix)]
+    pgid: Option<i32>,
+    #[cfg(windows)]
+    job: Option<job::Job>,
+}
+
+impl ProcessTree {
+    /// The tree led by `child`, which on Unix must have been spawned with
+    /// `process_group(0)`.
+    ///
+    /// On Windows the child is assigned to a new job object here, so anything
+    /// it starts before the assignment lands escapes the job. A shell is still
+    /// starting up at that point, so in practice the whole tree is covered.
+    pub(crate) fn of(child: &tokio::process::Child) -> Self {
+        Self {
+            #[cfg(unix)]
+            pgid: child.id().and_then(|p| i32::try_from(p).ok()),
+            #[cfg(windows)]
+            job: child.id().and_then(job::Job::assign),
+        }
+    }
+
+    /// Kill every process in the tree. Processes that already exited are
+    /// skipped; without process groups or job objects this does nothing and
+    /// only `kill_on_drop` applies.
+    pub(crate) fn kill(&self) {
+        #[cfg(unix)]
+        if let Some(pgid) = self.pgid {
+            // SAFETY: killpg only sends a signal. If the group is already gone
+            // it fails with ESRCH, which is fine to ignore.
+            unsafe {
+                libc::killpg(pgid, libc::SIGKILL);
+            }
+        }
+        #[cfg(windows)]
+        if let Some(job) = &self.job {
+            job.terminate();
+        }
+    }
+}
+
+#[cfg(windows)]
+mod job {
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, TerminateJobObject,
+    };
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, PROCESS_SET_QUOTA, PROCESS_TERMINATE,
+    };
+
+    /// A job object holding a spawned command. Processes started by a member
+    /// join the job too.
+    ///
+    /// The job is created without `JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE`, so
+    /// closing the handle after a normal exit leaves background processes
+    /// running, the same as an untouched process group on Unix.
+    pub(super) struct Job(HANDLE);
+
+    // SAFETY: a job handle is a kernel handle, usable from any thread.
+    unsafe impl Send for Job {}
+    unsafe impl Sync for Job {}
+
+    impl Job {
+        /// Put process `pid` into a new job. `None` if the job cannot be
+        /// created or the process cannot be assigned, e.g. when it already
+        /// exited or sits in a job that forbids nesting.
+        pub(super) fn assign(pid: u32) -> Option<Self> {
+            // SAFETY: plain Win32 calls on handles owned here. `Job` closes
+            // the job handle on drop, and the process handle is closed below.
+            unsafe {
+                let handle = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+                if handle.is_null() {
+                    return None;
+                }
+                let job = Job(handle);
+                let process = OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, 0, pid);
+                if process.is_null() {
+                    return None;
+                }
+                let assigned = AssignProcessToJobObject(job.0, process) != 0;
+                CloseHandle(process);
+                assigned.then_some(job)
+            }
+        }
+
+        /// Terminate every process in the job.
+        pub(super) fn terminate(&self) {
+            // SAFETY: the handle is valid until drop. Terminating an empty
+            // job is a no-op.
+            unsafe {
+                TerminateJobObject(self.0, 1);
+            }
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            // SAFETY: the handle came from CreateJobObjectW and is closed
+            // only here.
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+}
+
+/// Kills the process tree when dropped unless disarmed, so a caller that
+/// abandons the execution future (a stopped turn) does not leave the
+/// shell's children running after `kill_on_drop` has taken `sh` down.
+struct ProcessGroupGuard<'a>(Option<&'a ProcessTree>);
+
+impl ProcessGroupGuard<'_> {
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for ProcessGroupGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(tree) = self.0.take() {
+            tree.kill();
+        }
+    }
+}
+
 #[async_trait]
 impl ContainerRuntime for NativeRuntime {
     fn name(&self) -> &str {
@@ -37,27 +220,26 @@ impl ContainerRuntime for NativeRuntime {
         command: &str,
         config: &ContainerConfig,
     ) -> RuntimeResult<CommandOutput> {
-        let mut cmd = Command::new("sh");
-        cmd.arg("-c").arg(command);
-
-        // Set working directory if specified
-        if let Some(ref workdir) = config.workdir {
-            cmd.current_dir(workdir);
-        }
-
-        // Set environment variables
-        for (key, value) in &config.env {
-            cmd.env(key, value);
-        }
-
-        // Capture output
-        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let child = build_command(command, config)
+            .spawn()
+            .map_err(|e| RuntimeError::ExecutionFailed(e.to_string()))?;
+        let tree = ProcessTree::of(&child);
+        let group = ProcessGroupGuard(Some(&tree));
 
         // Execute with timeout
-        let output = tokio::time::timeout(Duration::from_secs(config.timeout_secs), cmd.output())
-            .await
-            .map_err(|_| RuntimeError::Timeout(config.timeout_secs))?
-            .map_err(|e| RuntimeError::ExecutionFailed(e.to_string()))?;
+        let output = match tokio::time::timeout(
+            Duration::from_secs(config.timeout_secs),
+            wait_with_stdin(child, config.stdin.as_deref()),
+        )
+        .await
+        {
+            Ok(output) => output.map_err(|e| RuntimeError::ExecutionFailed(e.to_string()))?,
+            Err(_) => {
+                tree.kill();
+                return Err(RuntimeError::Timeout(config.timeout_secs));
+            }
+        };
+        group.disarm();
 
         Ok(CommandOutput::new(
             String::from_utf8_lossy(&output.stdout).to_string(),
@@ -65,6 +247,67 @@ impl ContainerRuntime for NativeRuntime {
             output.status.code(),
         ))
     }
+
+    async fn execute_streaming(
+        &self,
+        command: &str,
+        config: &ContainerConfig,
+        on_output: &OutputSink<'_>,
+    ) -> RuntimeResult<CommandOutput> {
+        let mut child = build_command(command, config)
+            .spawn()
+            .map_err(|e| RuntimeError::ExecutionFailed(e.to_string()))?;
+        let tree = ProcessTree::of(&child);
+        let group = ProcessGroupGuard(Some(&tree));
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+
+        let mut stdout_buf = Vec::new();
+        let mut stderr_buf = Vec::new();
+        let run = async {
+            let (written, out, err) = tokio::join!(
+                write_stdin(stdin, config.stdin.as_deref()),
+                async {
+                    match stdout {
+                        Some(s) => {
+                            read_lines(s, OutputStream::Stdout, &mut stdout_buf, on_output).await
+                        }
+                        None => Ok(()),
+                    }
+                },
+                async {
+                    match stderr {
+                        Some(s) => {
+                            read_lines(s, OutputStream::Stderr, &mut stderr_buf, on_output).await
+                        }
+                        None => Ok(()),
+                    }
+                },
+            );
+            out?;
+            err?;
+            written?;
+            child.wait().await
+        };
+
+        // Dropping `run` on timeout releases the buffers with whatever the
+        // readers had captured.
+        let status = tokio::time::timeout(Duration::from_secs(config.timeout_secs), run).await;
+        let stdout = String::from_utf8_lossy(&stdout_buf).to_string();
+        let stderr = String::from_utf8_lossy(&stderr_buf).to_string();
+        match status {
+            Ok(status) => {
+                group.disarm();
+                let status = status.map_err(|e| RuntimeError::ExecutionFailed(e.to_string()))?;
+                Ok(CommandOutput::new(stdout, stderr, status.code()))
+            }
+            Err(_) => {
+                tree.kill();
+                Ok(CommandOutput::timed_out(stdout, stderr))
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -134,6 +377,62 @@ mod tests {
         assert_eq!(output.exit_code, Some(42));
     }
 
+    #[tokio::test]
+    async fn test_native_runtime_stdin() {
+        let runtime = NativeRuntime::new();
+        let config = ContainerConfig::new().with_stdin("b\na\n");
+
+        let output = runtime.execute("sort", &config).await.unwrap();
+        assert!(output.success());
+        assert_eq!(output.stdout, "a\nb\n");
+    }
+
+    #[tokio::test]
+    async fn test_native_runtime_streaming_delivers_lines_as_they_arrive() {
+        let runtime = NativeRuntime::new();
+        let config = ContainerConfig::new();
+        let started = std::time::Instant::now();
+        let arrivals = std::sync::Mutex::new(Vec::new());
+
+        let output = runtime
+            .execute_streaming(
+                "echo first; sleep 1; echo second >&2",
+                &config,
+                &|stream, line: &str| {
+                    arrivals
+                        .lock()
+                        .unwrap()
+                        .push((stream, line.to_string(), started.elapsed()))
+                },
+            )
+            .await
+            .unwrap();
+
+        assert!(output.success());
+        assert_eq!(output.stdout, "first\n");
+        assert_eq!(output.stderr, "second\n");
+        let arrivals = arrivals.into_inner().unwrap();
+        assert_eq!(arrivals.len(), 2);
+        assert_eq!(arrivals[0].0, OutputStream::Stdout);
+        assert_eq!(arrivals[1].0, OutputStream::Stderr);
+        // The first line was seen before the command finished.
+        assert!(arrivals[0].2 < Duration::from_millis(900));
+    }
+
+    #[tokio::test]
+    async fn test_native_runtime_streaming_timeout_keeps_partial_output() {
+        let runtime = NativeRuntime::new();
+        let config = ContainerConfig::new().with_timeout(1);
+
+        let output = runtime
+            .execute_streaming("echo started; sleep 10; echo never", &config, &|_, _| {})
+            .await
+            .unwrap();
+        assert!(output.timed_out);
+        assert_eq!(output.exit_code, None);
+        assert_eq!(output.stdout, "started\n");
+    }
+
     #[tokio::test]
     async fn test_native_runtime_timeout() {
         let runtime = NativeRuntime::new();
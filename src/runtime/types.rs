@@ -4,6 +4,8 @@ use async_trait::async_trait;
 use std::path::PathBuf;
 use thiserror::Error;
 
+use crate::config::ShellKind;
+
 /// Errors that can occur during runtime operations
 #[derive(Error, Debug)]
 pub enum RuntimeError {
@@ -38,8 +40,11 @@ pub struct CommandOutput {
     pub stdout: String,
     /// Standard error
     pub stderr: String,
-    /// Exit code (None if killed by signal)
+    /// Exit code (None if killed by signal or timed out)
     pub exit_code: Option<i32>,
+    /// The command was killed at its timeout; the output is what it had
+    /// produced by then (streaming execution only)
+    pub timed_out: bool,
 }
 
 impl CommandOutput {
@@ -49,6 +54,17 @@ impl CommandOutput {
             stdout,
             stderr,
             exit_code,
+            timed_out: false,
+        }
+    }
+
+    /// Output captured before the command was killed at its timeout
+    pub fn timed_out(stdout: String, stderr: String) -> Self {
+        Self {
+            stdout,
+            stderr,
+            exit_code: None,
+            timed_out: true,
         }
     }
 
@@ -78,10 +94,26 @@ impl CommandOutput {
             }
         }
 
+        if self.timed_out {
+            result.push_str("\n[Timed out, partial output]");
+        }
+
         result
     }
 }
 
+/// Which stream a line of output came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    /// Standard output
+    Stdout,
+    /// Standard error
+    Stderr,
+}
+
+/// Receives output line by line during streaming execution
+pub type OutputSink<'a> = dyn Fn(OutputStream, &str) + Send + Sync + 'a;
+
 /// Configuration for a container execution
 #[derive(Debug, Clone, Default)]
 pub struct ContainerConfig {
@@ -91,8 +123,15 @@ pub struct ContainerConfig {
     pub mounts: Vec<(PathBuf, PathBuf, bool)>,
     /// Environment variables
     pub env: Vec<(String, String)>,
+    /// Start from an empty environment instead of inheriting the host's
+    /// (host-process runtimes only; container images never inherit it)
+    pub clear_env: bool,
+    /// Text written to the command's stdin (stdin is empty when unset)
+    pub stdin: Option<String>,
     /// Command timeout in seconds
     pub timeout_secs: u64,
+    /// Shell that interprets the command
+    pub shell: ShellKind,
 }
 
 impl ContainerConfig {
@@ -100,10 +139,26 @@ impl ContainerConfig {
     pub fn new() -> Self {
         Self {
             timeout_secs: 60,
+            shell: ShellKind::detect(),
             ..Default::default()
         }
     }
 
+    /// Set the shell that interprets the command
+    pub fn with_shell(mut self, shell: ShellKind) -> Self {
+        self.shell = shell;
+        self
+    }
+
+    /// Program and arguments that run `command` in the configured shell
+    pub fn shell_invocation(&self, command: &str) -> [String; 3] {
+        [
+            self.shell.program().to_string(),
+            self.shell.command_flag().to_string(),
+            command.to_string(),
+        ]
+    }
+
     /// Set working directory
     pub fn with_workdir(mut self, workdir: PathBuf) -> Self {
         self.workdir = Some(workdir);
@@ -122,6 +177,18 @@ impl ContainerConfig {
         self
     }
 
+    /// Start from an empty environment instead of inheriting the host's
+    pub fn with_clear_env(mut self, clear_env: bool) -> Self {
+        self.clear_env = clear_env;
+        self
+    }
+
+    /// Feed `input` to the command's stdin
+    pub fn with_stdin(mut self, input: impl Into<String>) -> Self {
+        self.stdin = Some(input.into());
+        self
+    }
+
     /// Set timeout
     pub fn with_timeout(mut self, secs: u64) -> Self {
         self.timeout_secs = secs;
@@ -144,6 +211,36 @@ pub trait ContainerRuntime: Send + Sync {
         command: &str,
         config: &ContainerConfig,
     ) -> RuntimeResult<CommandOutput>;
+
+    /// Execute a command, passing each line of output to `on_output` as it
+    /// is produced.
+    ///
+    /// Unlike [`execute`](Self::execute), a timeout is not an error: the
+    /// output captured so far is returned with `timed_out` set. The default
+    /// implementation cannot observe output before the command finishes, so
+    /// it replays the lines afterwards and loses them on timeout.
+    async fn execute_streaming(
+        &self,
+        command: &str,
+        config: &ContainerConfig,
+        on_output: &OutputSink<'_>,
+    ) -> RuntimeResult<CommandOutput> {
+        match self.execute(command, config).await {
+            Ok(output) => {
+                for line in output.stdout.split_inclusive('\n') {
+                    on_output(OutputStream::Stdout, line);
+                }
+                for line in output.stderr.split_inclusive('\n') {
+                    on_output(OutputStream::Stderr, line);
+                }
+                Ok(output)
+            }
+            Err(RuntimeError::Timeout(_)) => {
+                Ok(CommandOutput::timed_out(String::new(), String::new()))
+            }
+            Err(e) => Err(e),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -184,6 +281,13 @@ mod tests {
         assert!(formatted.contains("[Exit code: 1]"));
     }
 
+    #[test]
+    fn test_command_output_format_timed_out() {
+        let output = CommandOutput::timed_out("partial\n".to_string(), String::new());
+        assert!(!output.success());
+        assert_eq!(output.format(), "partial\n\n[Timed out, partial output]");
+    }
+
     #[test]
     fn test_container_config_builder() {
         let config = ContainerConfig::new()
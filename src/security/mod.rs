@@ -1,14 +1,17 @@
 //! Security module for ZeptoClaw
 //!
-//! This module provides security utilities including path validation
-//! and command filtering to prevent malicious tool execution.
+//! This module provides security utilities including path validation, the
+//! filesystem sandbox, and command filtering to prevent malicious tool
+//! execution.
 
 pub mod agent_mode;
 pub mod encryption;
 pub mod mount;
 pub mod pairing;
 pub mod path;
+pub mod sandbox;
 pub mod shell;
+pub mod shell_policy;
 
 pub use agent_mode::{AgentMode, AgentModeConfig, CategoryPermission, ModePolicy};
 pub use encryption::{is_secret_field, resolve_master_key, SecretEncryption};
@@ -18,4 +21,6 @@ pub use path::{
     check_hardlink_write, ensure_directory_chain_secure, revalidate_path,
     validate_path_in_workspace, SafePath,
 };
+pub use sandbox::Sandbox;
 pub use shell::{ShellAllowlistMode, ShellSecurityConfig};
+pub use shell_policy::{CommandApprover, PolicyDecision, ShellPolicy};
@@ -438,7 +438,7 @@ pub fn check_hardlink_write(path: &Path) -> Result<()> {
 ///
 /// If the resulting path exists on the filesystem, it returns the canonical path.
 /// Otherwise, it returns the normalized path.
-fn normalize_path(path: &Path) -> PathBuf {
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
     let mut normalized = PathBuf::new();
 
     for component in path.components() {
@@ -0,0 +1,446 @@
+//! Filesystem sandbox shared by the file-touching tools
+//!
+//! [`Sandbox`] is the one place tool-supplied paths are resolved: it keeps
+//! them inside the workspace (rejecting traversal and symlink escapes) and
+//! applies the configured denied and read-only path lists, so every tool
+//! enforces the same rules.
+
+use std::path::{Component, Path, PathBuf};
+
+use glob::{MatchOptions, Pattern};
+
+use crate::audit::{log_audit_event, AuditCategory, AuditSeverity};
+use crate::config::FilesystemToolsConfig;
+use crate::error::{Result, ZeptoError};
+
+use super::path::{normalize_path, revalidate_path, validate_path_in_workspace};
+
+/// `*` stops at `/`, so `secrets/*` does not reach into subdirectories
+/// (the directory rule covers those anyway).
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// One entry of a denied or read-only path list.
+#[derive(Debug, Clone)]
+struct PathRule {
+    /// The pattern as configured, for error messages.
+    source: String,
+    pattern: Pattern,
+    /// Matched from the workspace root rather than against any name.
+    anchored: bool,
+}
+
+impl PathRule {
+    fn new(source: &str) -> Option<Self> {
+        let trimmed = source.trim();
+        let anchored = trimmed.contains('/');
+        let glob = trimmed
+            .trim_start_matches("./")
+            .trim_start_matches('/')
+            .trim_end_matches('/');
+        if glob.is_empty() {
+            return None;
+        }
+        // An invalid glob still denies something: the literal path.
+        let pattern = Pattern::new(glob)
+            .or_else(|_| Pattern::new(&Pattern::escape(glob)))
+            .ok()?;
+        Some(Self {
+            source: trimmed.to_string(),
+            pattern,
+            anchored,
+        })
+    }
+
+    /// Whether the rule covers the workspace-relative path `components`,
+    /// itself or through one of its parent directories.
+    fn matches(&self, components: &[String]) -> bool {
+        if self.anchored {
+            (1..=components.len()).any(|n| {
+                self.pattern
+                    .matches_with(&components[..n].join("/"), MATCH_OPTIONS)
+            })
+        } else {
+            components
+                .iter()
+                .any(|name| self.pattern.matches_with(name, MATCH_OPTIONS))
+        }
+    }
+}
+
+fn compile(patterns: &[impl AsRef<str>]) -> Vec<PathRule> {
+    patterns
+        .iter()
+        .filter_map(|p| PathRule::new(p.as_ref()))
+        .collect()
+}
+
+/// How a tool is about to use a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Read,
+    Write,
+}
+
+/// Workspace containment plus denied and read-only path lists.
+///
+/// Tools get one from [`ToolContext::sandbox`](crate::tools::ToolContext::sandbox)
+/// and resolve every path argument through it before touching the
+/// filesystem.
+///
+/// # Example
+/// ```
+/// use zeptoclaw::security::Sandbox;
+///
+/// let sandbox = Sandbox::new("/workspace")
+///     .with_denied_paths(&[".env"])
+///     .with_read_only_paths(&["vendor"]);
+///
+/// assert!(sandbox.resolve("src/main.rs").is_ok());
+/// assert!(sandbox.resolve("../etc/passwd").is_err());
+/// assert!(sandbox.resolve("app/.env").is_err());
+/// assert!(sandbox.resolve("vendor/lib.rs").is_ok());
+/// assert!(sandbox.resolve_for_write("vendor/lib.rs").is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    workspace: String,
+    /// Canonical workspace, for relating resolved paths back to it.
+    root: PathBuf,
+    denied: Vec<PathRule>,
+    read_only: Vec<PathRule>,
+}
+
+impl Sandbox {
+    /// A sandbox confining paths to `workspace`, with no extra rules.
+    pub fn new(workspace: &str) -> Self {
+        let root = Path::new(workspace)
+            .canonicalize()
+            .unwrap_or_else(|_| normalize_path(Path::new(workspace)));
+        Self {
+            workspace: workspace.to_string(),
+            root,
+            denied: Vec::new(),
+            read_only: Vec::new(),
+        }
+    }
+
+    /// A sandbox for `workspace` with the configured path lists.
+    pub fn from_config(workspace: &str, config: &FilesystemToolsConfig) -> Self {
+        Self::new(workspace)
+            .with_denied_paths(&config.denied_paths)
+            .with_read_only_paths(&config.read_only_paths)
+    }
+
+    /// Refuse every access to paths matching `patterns`; see
+    /// [`FilesystemToolsConfig`] for the pattern syntax.
+    pub fn with_denied_paths(mut self, patterns: &[impl AsRef<str>]) -> Self {
+        self.denied = compile(patterns);
+        self
+    }
+
+    /// Refuse writes to paths matching `patterns`.
+    pub fn with_read_only_paths(mut self, patterns: &[impl AsRef<str>]) -> Self {
+        self.read_only = compile(patterns);
+        self
+    }
+
+    /// The workspace directory, as configured.
+    pub fn workspace(&self) -> &str {
+        &self.workspace
+    }
+
+    /// Resolve a path a tool wants to read: relative paths are joined to
+    /// the workspace, and the result must stay inside it, through any
+    /// symlinks, and must not be denied. The path need not exist.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf> {
+        self.resolve_as(path, Access::Read)
+    }
+
+    /// Like [`resolve`](Self::resolve), for a path a tool wants to create
+    /// or change; read-only paths are refused too.
+    pub fn resolve_for_write(&self, path: &str) -> Result<PathBuf> {
+        self.resolve_as(path, Access::Write)
+    }
+
+    /// Re-check an already resolved path right before reading it, or check
+    /// one found by walking a directory.
+    pub fn check(&self, path: &Path) -> Result<()> {
+        self.check_as(path, Access::Read)
+    }
+
+    /// Re-check an already resolved path right before writing it.
+    pub fn check_write(&self, path: &Path) -> Result<()> {
+        self.check_as(path, Access::Write)
+    }
+
+    /// Whether `path` falls under a denied path. Directory walks use this to
+    /// skip entries silently instead of failing.
+    pub fn is_denied(&self, path: &Path) -> bool {
+        self.matching_rule(&self.denied, path).is_some()
+    }
+
+    fn resolve_as(&self, path: &str, access: Access) -> Result<PathBuf> {
+        let resolved = validate_path_in_workspace(path, &self.workspace)?.into_path_buf();
+        self.check_as(&resolved, access)?;
+        Ok(resolved)
+    }
+
+    fn check_as(&self, path: &Path, access: Access) -> Result<()> {
+        revalidate_path(path, &self.workspace)?;
+        if let Some(rule) = self.matching_rule(&self.denied, path) {
+            return Err(self.refuse(
+                "denied_path",
+                format!(
+                    "Access denied: '{}' matches denied path '{}'",
+                    self.display(path),
+                    rule.source
+                ),
+            ));
+        }
+        if access == Access::Write {
+            if let Some(rule) = self.matching_rule(&self.read_only, path) {
+                return Err(self.refuse(
+                    "read_only_path",
+                    format!(
+                        "Write refused: '{}' is read-only (matches '{}')",
+                        self.display(path),
+                        rule.source
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// First rule covering `path`, as named or as reached through symlinks,
+    /// so a link cannot be used to get at a listed path.
+    fn matching_rule<'a>(&self, rules: &'a [PathRule], path: &Path) -> Option<&'a PathRule> {
+        if rules.is_empty() {
+            return None;
+        }
+        let named = self.relative_components(&normalize_path(path));
+        let real = self.relative_components(&real_path(path));
+        rules.iter().find(|rule| {
+            [&named, &real]
+                .into_iter()
+                .flatten()
+                .any(|c| rule.matches(c))
+        })
+    }
+
+    /// `path` relative to the workspace, or `None` if it is outside it.
+    fn relative_components(&self, path: &Path) -> Option<Vec<String>> {
+        let relative = path
+            .strip_prefix(&self.root)
+            .or_else(|_| path.strip_prefix(&self.workspace))
+            .ok()?;
+        Some(
+            relative
+                .components()
+                .filter_map(|c| match c {
+                    Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                    _ => None,
+                })
+                .collect(),
+        )
+    }
+
+    fn display(&self, path: &Path) -> String {
+        match self.relative_components(&normalize_path(path)) {
+            Some(components) if !components.is_empty() => components.join("/"),
+            _ => path.display().to_string(),
+        }
+    }
+
+    fn refuse(&self, event: &str, message: String) -> ZeptoError {
+        log_audit_event(
+            AuditCategory::PathSecurity,
+            AuditSeverity::Critical,
+            event,
+            &message,
+            true,
+        );
+        ZeptoError::SecurityViolation(message)
+    }
+}
+
+/// `path` with symlinks resolved as far as it exists; the missing tail (a
+/// file about to be created) is appended unchanged.
+fn real_path(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return missing
+                .iter()
+                .rev()
+                .fold(canonical, |acc: PathBuf, name| acc.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return normalize_path(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::symlink;
+    use tempfile::tempdir;
+
+    fn sandbox(dir: &Path) -> Sandbox {
+        Sandbox::new(dir.to_str().unwrap())
+            .with_denied_paths(&[".env", ".git/config", "secrets/*.pem"])
+            .with_read_only_paths(&["vendor", "/Cargo.lock"])
+    }
+
+    #[test]
+    fn test_resolve_keeps_paths_inside_workspace() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        let sandbox = sandbox(dir.path());
+
+        let resolved = sandbox.resolve("src/main.rs").unwrap();
+        assert!(resolved.ends_with("src/main.rs"));
+        assert!(resolved.starts_with(dir.path().canonicalize().unwrap()));
+        assert!(sandbox.resolve("../outside.txt").is_err());
+        assert!(sandbox.resolve("/etc/passwd").is_err());
+        assert!(sandbox.resolve("src/%2e%2e/%2e%2e/etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_resolve_allows_paths_that_do_not_exist_yet() {
+        let dir = tempdir().unwrap();
+        let sandbox = sandbox(dir.path());
+
+        let resolved = sandbox.resolve_for_write("new/dir/file.txt").unwrap();
+        assert!(resolved.ends_with("new/dir/file.txt"));
+        assert!(!resolved.exists());
+        assert!(sandbox.resolve_for_write("new/../../escape.txt").is_err());
+    }
+
+    #[test]
+    fn test_denied_paths_match_names_anchored_paths_and_directories() {
+        let dir = tempdir().unwrap();
+        let sandbox = sandbox(dir.path());
+
+        for denied in [
+            ".env",
+            "app/.env",
+            ".git/config",
+            "secrets/key.pem",
+            "./.env",
+        ] {
+            let err = sandbox.resolve(denied).unwrap_err();
+            assert!(
+                matches!(err, ZeptoError::SecurityViolation(ref m) if m.contains("denied path")),
+                "{} should be denied, got {}",
+                denied,
+                err
+            );
+            assert!(sandbox.resolve_for_write(denied).is_err());
+        }
+        for allowed in [".env.example", "app/.git/config", "secrets/nested/key.pem"] {
+            assert!(
+                sandbox.resolve(allowed).is_ok(),
+                "{} should be allowed",
+                allowed
+            );
+        }
+
+        // A denied directory covers its contents.
+        let sandbox = Sandbox::new(dir.path().to_str().unwrap()).with_denied_paths(&[".git"]);
+        assert!(sandbox.resolve(".git/hooks/pre-commit").is_err());
+        assert!(sandbox.is_denied(&dir.path().join(".git/HEAD")));
+        assert!(!sandbox.is_denied(&dir.path().join("src/git.rs")));
+    }
+
+    #[test]
+    fn test_read_only_paths_refuse_writes_only() {
+        let dir = tempdir().unwrap();
+        let sandbox = sandbox(dir.path());
+
+        assert!(sandbox.resolve("vendor/lib/mod.rs").is_ok());
+        assert!(sandbox.resolve("Cargo.lock").is_ok());
+        let err = sandbox.resolve_for_write("vendor/lib/mod.rs").unwrap_err();
+        assert!(err.to_string().contains("read-only"));
+        assert!(sandbox.resolve_for_write("Cargo.lock").is_err());
+        // Anchored to the root, so a nested lock file is writable.
+        assert!(sandbox.resolve_for_write("crates/a/Cargo.lock").is_ok());
+        assert!(sandbox.check_write(&dir.path().join("vendor")).is_err());
+    }
+
+    #[test]
+    fn test_symlink_pointing_outside_workspace_is_rejected() {
+        let dir = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+        symlink(outside.path(), dir.path().join("escape")).unwrap();
+        symlink(
+            outside.path().join("secret.txt"),
+            dir.path().join("secret.txt"),
+        )
+        .unwrap();
+        let sandbox = sandbox(dir.path());
+
+        assert!(sandbox.resolve("escape/secret.txt").is_err());
+        assert!(sandbox.resolve("secret.txt").is_err());
+        // Writing a new file through the escaping directory is refused too.
+        assert!(sandbox.resolve_for_write("escape/new.txt").is_err());
+        assert!(sandbox.check(&dir.path().join("escape")).is_err());
+    }
+
+    #[test]
+    fn test_symlink_inside_workspace_to_listed_path_is_caught() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(".env"), "TOKEN=x").unwrap();
+        fs::create_dir(dir.path().join("vendor")).unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        symlink(dir.path().join(".env"), dir.path().join("settings")).unwrap();
+        symlink(dir.path().join("vendor"), dir.path().join("deps")).unwrap();
+        symlink(dir.path().join("src"), dir.path().join("code")).unwrap();
+        let sandbox = sandbox(dir.path());
+
+        assert!(sandbox.resolve("settings").is_err());
+        assert!(sandbox.resolve("deps/new.rs").is_ok());
+        assert!(sandbox.resolve_for_write("deps/new.rs").is_err());
+        // Links that stay inside and reach nothing listed are fine.
+        assert!(sandbox.resolve_for_write("code/new.rs").is_ok());
+    }
+
+    #[test]
+    fn test_dangling_symlink_is_rejected() {
+        let dir = tempdir().unwrap();
+        symlink("/nonexistent/target", dir.path().join("dangling")).unwrap();
+        let sandbox = sandbox(dir.path());
+
+        assert!(sandbox.resolve("dangling").is_err());
+        assert!(sandbox.resolve_for_write("dangling").is_err());
+    }
+
+    #[test]
+    fn test_from_config_and_blank_patterns() {
+        let dir = tempdir().unwrap();
+        let config = FilesystemToolsConfig {
+            denied_paths: vec!["".to_string(), " id_rsa ".to_string(), "[bad".to_string()],
+            read_only_paths: vec!["/".to_string()],
+        };
+        let sandbox = Sandbox::from_config(dir.path().to_str().unwrap(), &config);
+
+        assert!(sandbox.resolve("keys/id_rsa").is_err());
+        // An invalid glob is matched literally.
+        assert!(sandbox.resolve("[bad").is_err());
+        assert!(sandbox.resolve_for_write("notes.txt").is_ok());
+        assert_eq!(sandbox.workspace(), dir.path().to_str().unwrap());
+    }
+}
@@ -207,29 +207,29 @@ fn build_glob_regex(command: &str) -> Option<Regex> {
 
 /// A parsed command segment — one "simple command" between shell metacharacters.
 #[derive(Debug, Clone)]
-struct CommandSegment {
+pub(crate) struct CommandSegment {
     /// Executable name with path stripped (e.g., "/usr/bin/git" -> "git").
-    binary: String,
+    pub(crate) binary: String,
     /// Arguments after the binary.
-    args: Vec<String>,
+    pub(crate) args: Vec<String>,
     /// Original raw segment string for backward-compatible regex checks.
-    raw: String,
+    pub(crate) raw: String,
 }
 
 /// Result of tokenizing a command string.
-struct TokenizeResult {
+pub(crate) struct TokenizeResult {
     /// Parsed command segments.
-    segments: Vec<CommandSegment>,
+    pub(crate) segments: Vec<CommandSegment>,
     /// True if any unquoted shell metacharacter was encountered during parsing,
     /// even if it produced only one non-empty segment (e.g., a bare `$(cmd)`).
-    has_metachar: bool,
+    pub(crate) has_metachar: bool,
 }
 
 /// Split a shell command string into segments on unquoted metacharacters
 /// (`;`, `|`, `&&`, `||`, `&`, backtick, `$(`), respecting single/double
 /// quotes and backslash escaping. Redirection operators (`>&`, `&>`) are
 /// not treated as command chaining.
-fn tokenize_command(command: &str) -> TokenizeResult {
+pub(crate) fn tokenize_command(command: &str) -> TokenizeResult {
     let mut segments = Vec::new();
     let mut current_segment = String::new();
     let mut in_single_quote = false;
@@ -0,0 +1,194 @@
+//! On-disk encodings for session files.
+//!
+//! JSON is always available and is the default. MessagePack and CBOR are
+//! compact binary alternatives behind the `session-msgpack` and
+//! `session-cbor` features. Each format writes its own file extension, and
+//! loading picks the decoder from the extension (falling back to the leading
+//! bytes), so one sessions directory can hold a mix of formats while it is
+//! being migrated with [`SessionManager::convert`](super::SessionManager::convert).
+
+use std::path::Path;
+
+use super::types::Session;
+
+/// Boxed error returned by encoding and decoding.
+pub type CodecError = Box<dyn std::error::Error + Send + Sync>;
+
+/// File extensions of every known session format, including formats whose
+/// feature is disabled in this build.
+pub const KNOWN_EXTENSIONS: [&str; 3] = ["json", "msgpack", "cbor"];
+
+/// Serialization format for session files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Codec {
+    /// Pretty-printed JSON (`.json`).
+    #[default]
+    Json,
+    /// MessagePack with named fields (`.msgpack`).
+    #[cfg(feature = "session-msgpack")]
+    MessagePack,
+    /// CBOR (`.cbor`).
+    #[cfg(feature = "session-cbor")]
+    Cbor,
+}
+
+impl Codec {
+    /// File extension written for this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Codec::Json => "json",
+            #[cfg(feature = "session-msgpack")]
+            Codec::MessagePack => "msgpack",
+            #[cfg(feature = "session-cbor")]
+            Codec::Cbor => "cbor",
+        }
+    }
+
+    /// Format for a file extension, if it is enabled in this build.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            "json" => Some(Codec::Json),
+            #[cfg(feature = "session-msgpack")]
+            "msgpack" => Some(Codec::MessagePack),
+            #[cfg(feature = "session-cbor")]
+            "cbor" => Some(Codec::Cbor),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from the first bytes of an encoded session.
+    ///
+    /// Sessions encode as maps, so JSON starts with `{`, MessagePack with a
+    /// map marker (`0x80..=0x8f`, `0xde`, `0xdf`) and CBOR with a major type 5
+    /// header (`0xa0..=0xbf`).
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        match bytes.iter().copied().find(|b| !b.is_ascii_whitespace())? {
+            b'{' => Some(Codec::Json),
+            #[cfg(feature = "session-msgpack")]
+            0x80..=0x8f | 0xde | 0xdf => Some(Codec::MessagePack),
+            #[cfg(feature = "session-cbor")]
+            0xa0..=0xbf => Some(Codec::Cbor),
+            _ => None,
+        }
+    }
+
+    /// Determine the format of a session file from its extension, falling
+    /// back to [`sniff`](Self::sniff) for unknown extensions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the extension names a format whose feature is
+    /// disabled, or if the format cannot be recognized.
+    pub fn detect(path: &Path, bytes: &[u8]) -> Result<Self, CodecError> {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        if let Some(codec) = Self::from_extension(ext) {
+            return Ok(codec);
+        }
+        if KNOWN_EXTENSIONS.contains(&ext) {
+            return Err(format!(
+                "'.{}' session files require the `session-{}` feature",
+                ext, ext
+            )
+            .into());
+        }
+        Self::sniff(bytes).ok_or_else(|| "unrecognized session file format".into())
+    }
+
+    /// Encode a session.
+    pub fn encode(self, session: &Session) -> Result<Vec<u8>, CodecError> {
+        match self {
+            Codec::Json => Ok(serde_json::to_vec_pretty(session)?),
+            // Named fields keep `#[serde(default)]` and skipped fields working.
+            #[cfg(feature = "session-msgpack")]
+            Codec::MessagePack => Ok(rmp_serde::to_vec_named(session)?),
+            #[cfg(feature = "session-cbor")]
+            Codec::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(session, &mut bytes)?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Decode a session.
+    pub fn decode(self, bytes: &[u8]) -> Result<Session, CodecError> {
+        match self {
+            Codec::Json => Ok(serde_json::from_slice(bytes)?),
+            #[cfg(feature = "session-msgpack")]
+            Codec::MessagePack => Ok(rmp_serde::from_slice(bytes)?),
+            #[cfg(feature = "session-cbor")]
+            Codec::Cbor => Ok(ciborium::from_reader(bytes)?),
+        }
+    }
+}
+
+/// Decode a session file of any enabled format.
+pub fn decode_file(path: &Path, bytes: &[u8]) -> Result<Session, CodecError> {
+    Codec::detect(path, bytes)?.decode(bytes)
+}
+
+/// Whether `path` has the extension of a known session format.
+pub fn is_session_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| KNOWN_EXTENSIONS.contains(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::Message;
+
+    fn sample() -> Session {
+        let mut session = Session::new("telegram:42");
+        session.add_message(Message::user("hello"));
+        session.add_message(Message::assistant("hi there"));
+        session.add_tag("vip");
+        session
+    }
+
+    #[allow(unused_mut)]
+    fn all_codecs() -> Vec<Codec> {
+        let mut codecs = vec![Codec::Json];
+        #[cfg(feature = "session-msgpack")]
+        codecs.push(Codec::MessagePack);
+        #[cfg(feature = "session-cbor")]
+        codecs.push(Codec::Cbor);
+        codecs
+    }
+
+    #[test]
+    fn test_round_trip_and_sniff() {
+        let session = sample();
+        for codec in all_codecs() {
+            let bytes = codec.encode(&session).unwrap();
+            assert_eq!(Codec::sniff(&bytes), Some(codec));
+            let decoded = codec.decode(&bytes).unwrap();
+            assert_eq!(decoded.key, session.key);
+            assert_eq!(decoded.messages.len(), 2);
+            assert_eq!(decoded.tags, session.tags);
+            assert_eq!(decoded.created_at, session.created_at);
+        }
+    }
+
+    #[test]
+    fn test_detect_prefers_extension_then_bytes() {
+        let bytes = Codec::Json.encode(&sample()).unwrap();
+        assert_eq!(
+            Codec::detect(Path::new("a.json"), &bytes).unwrap(),
+            Codec::Json
+        );
+        assert_eq!(
+            Codec::detect(Path::new("a.bak"), &bytes).unwrap(),
+            Codec::Json
+        );
+        assert!(Codec::detect(Path::new("a.bak"), b"\x00\x01").is_err());
+    }
+
+    #[cfg(not(feature = "session-msgpack"))]
+    #[test]
+    fn test_disabled_format_names_feature() {
+        let err = Codec::detect(Path::new("a.msgpack"), b"\x81").unwrap_err();
+        assert!(err.to_string().contains("session-msgpack"));
+    }
+}
@@ -0,0 +1,286 @@
+//! Append-only log of the commands the agent ran.
+//!
+//! The chat transcript gets trimmed and compacted, so it is a poor audit
+//! trail. The shell and process tools append one [`CommandRecord`] per
+//! command through [`ToolContext::record_command`] instead. A persistent log
+//! keeps one JSONL file per session namespace (the part of the key before
+//! the first `:`) under `<sessions>/commands/`. Records are never rewritten.
+//!
+//! [`ToolContext::record_command`]: crate::tools::ToolContext::record_command
+
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::SessionManager;
+use crate::error::Result;
+
+/// Hex digits of the output's SHA-256 kept in a record.
+const OUTPUT_HASH_LEN: usize = 16;
+
+/// How a command ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandOutcome {
+    /// Ran to completion; see `exit_code`.
+    Exited,
+    /// Stopped by the tool's timeout.
+    TimedOut,
+    /// Killed on request (`process` action `kill`, or shutdown).
+    Killed,
+    /// Stopped because the turn was cancelled.
+    Cancelled,
+    /// Could not be run or waited for.
+    Failed,
+}
+
+impl std::fmt::Display for CommandOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CommandOutcome::Exited => "exited",
+            CommandOutcome::TimedOut => "timed out",
+            CommandOutcome::Killed => "killed",
+            CommandOutcome::Cancelled => "cancelled",
+            CommandOutcome::Failed => "failed",
+        })
+    }
+}
+
+/// One command the agent ran.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandRecord {
+    /// When the command started.
+    pub timestamp: DateTime<Utc>,
+    pub session_key: String,
+    /// Tool that ran it (`shell` or `process`).
+    pub tool: String,
+    pub command: String,
+    /// Directory it ran in; empty if the tool used its own default.
+    pub cwd: String,
+    pub outcome: CommandOutcome,
+    /// Exit code, when the command exited normally.
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    /// Size of the output (stdout and stderr) before it was cut down for
+    /// the model; for background processes, of the kept tail.
+    pub output_bytes: usize,
+    /// Leading hex digits of the output's SHA-256, to match a record with
+    /// output kept elsewhere without storing the output itself.
+    pub output_hash: String,
+}
+
+impl CommandRecord {
+    /// A record for `command` starting now; finish it with
+    /// [`finished`](Self::finished).
+    pub fn new(tool: &str, command: &str, cwd: &str) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            session_key: String::new(),
+            tool: tool.to_string(),
+            command: command.to_string(),
+            cwd: cwd.to_string(),
+            outcome: CommandOutcome::Exited,
+            exit_code: None,
+            duration_ms: 0,
+            output_bytes: 0,
+            output_hash: String::new(),
+        }
+    }
+
+    /// Fill in how the command ended.
+    pub fn finished(
+        mut self,
+        outcome: CommandOutcome,
+        exit_code: Option<i32>,
+        duration: Duration,
+        output: &str,
+    ) -> Self {
+        self.outcome = outcome;
+        self.exit_code = exit_code;
+        self.duration_ms = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+        self.output_bytes = output.len();
+        let digest = hex::encode(Sha256::digest(output.as_bytes()));
+        self.output_hash = digest[..OUTPUT_HASH_LEN].to_string();
+        self
+    }
+
+    /// One line for `/audit`.
+    pub fn summary(&self) -> String {
+        let outcome = match (self.outcome, self.exit_code) {
+            (CommandOutcome::Exited, Some(code)) => format!("exit {}", code),
+            (outcome, _) => outcome.to_string(),
+        };
+        format!(
+            "{} `{}` — {}, {}ms",
+            self.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            crate::utils::string::preview(&self.command, 80),
+            outcome,
+            self.duration_ms
+        )
+    }
+}
+
+#[derive(Debug)]
+enum Store {
+    Dir(PathBuf),
+    Memory(Mutex<Vec<CommandRecord>>),
+}
+
+/// The command log for all sessions of one [`SessionManager`].
+#[derive(Debug)]
+pub struct CommandLog {
+    store: Store,
+    /// Serializes appends from this process so lines never interleave.
+    write_lock: Mutex<()>,
+}
+
+impl CommandLog {
+    /// A log writing `<dir>/<namespace>.jsonl` files. The directory is
+    /// created with the first record.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            store: Store::Dir(dir.into()),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// A log kept in memory, for managers without persistence.
+    pub fn memory() -> Self {
+        Self {
+            store: Store::Memory(Mutex::new(Vec::new())),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// File holding the records of `session_key`'s namespace.
+    fn file(dir: &Path, session_key: &str) -> PathBuf {
+        let namespace = session_key.split(':').next().unwrap_or(session_key);
+        let name = if namespace.is_empty() {
+            "default".to_string()
+        } else {
+            SessionManager::sanitize_key(namespace)
+        };
+        dir.join(format!("{}.jsonl", name))
+    }
+
+    /// Append `record`.
+    pub fn append(&self, record: &CommandRecord) -> Result<()> {
+        match &self.store {
+            Store::Memory(records) => records.lock().unwrap().push(record.clone()),
+            Store::Dir(dir) => {
+                let mut line = serde_json::to_string(record)?;
+                line.push('\n');
+                let _guard = self.write_lock.lock().unwrap();
+                std::fs::create_dir_all(dir)?;
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(Self::file(dir, &record.session_key))?;
+                file.write_all(line.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The last `limit` records of `session_key`, oldest first. Lines that
+    /// do not parse (a write cut short by a crash) are skipped.
+    pub fn recent(&self, session_key: &str, limit: usize) -> Result<Vec<CommandRecord>> {
+        let mut records: Vec<CommandRecord> = match &self.store {
+            Store::Memory(records) => records
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|r| r.session_key == session_key)
+                .cloned()
+                .collect(),
+            Store::Dir(dir) => {
+                let file = match std::fs::File::open(Self::file(dir, session_key)) {
+                    Ok(file) => file,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+                    Err(e) => return Err(e.into()),
+                };
+                let mut records = Vec::new();
+                for line in BufReader::new(file).lines() {
+                    let Ok(record) = serde_json::from_str::<CommandRecord>(&line?) else {
+                        continue;
+                    };
+                    if record.session_key == session_key {
+                        records.push(record);
+                    }
+                }
+                records
+            }
+        };
+        let skip = records.len().saturating_sub(limit);
+        records.drain(..skip);
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn record(session: &str, command: &str) -> CommandRecord {
+        let mut record = CommandRecord::new("shell", command, "/ws").finished(
+            CommandOutcome::Exited,
+            Some(0),
+            Duration::from_millis(12),
+            "hello\n",
+        );
+        record.session_key = session.to_string();
+        record
+    }
+
+    #[test]
+    fn test_records_are_appended_per_namespace_and_read_back() {
+        let dir = tempdir().unwrap();
+        let log = CommandLog::new(dir.path().join("commands"));
+        log.append(&record("telegram:1", "ls")).unwrap();
+        log.append(&record("telegram:2", "pwd")).unwrap();
+        log.append(&record("telegram:1", "make")).unwrap();
+        log.append(&record("cli:main", "cargo test")).unwrap();
+
+        let telegram = std::fs::read_to_string(dir.path().join("commands/telegram.jsonl")).unwrap();
+        assert_eq!(telegram.lines().count(), 3);
+        assert!(dir.path().join("commands/cli.jsonl").exists());
+
+        let recent = log.recent("telegram:1", 10).unwrap();
+        let commands: Vec<_> = recent.iter().map(|r| r.command.as_str()).collect();
+        assert_eq!(commands, ["ls", "make"]);
+        assert_eq!(log.recent("telegram:1", 1).unwrap()[0].command, "make");
+        assert!(log.recent("slack:1", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_recent_skips_damaged_lines() {
+        let dir = tempdir().unwrap();
+        let log = CommandLog::new(dir.path());
+        log.append(&record("cli:main", "ls")).unwrap();
+        let path = dir.path().join("cli.jsonl");
+        let mut content = std::fs::read_to_string(&path).unwrap();
+        content.push_str("{\"timestamp\": \"2026-");
+        std::fs::write(&path, content).unwrap();
+
+        assert_eq!(log.recent("cli:main", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_finished_hashes_output_and_summarizes() {
+        let record = record("cli:main", "echo hello");
+        assert_eq!(record.output_bytes, 6);
+        assert_eq!(record.output_hash.len(), OUTPUT_HASH_LEN);
+        assert!(record.summary().ends_with("`echo hello` — exit 0, 12ms"));
+
+        let memory = CommandLog::memory();
+        memory.append(&record).unwrap();
+        assert_eq!(memory.recent("cli:main", 5).unwrap(), vec![record]);
+    }
+}
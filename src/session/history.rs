@@ -6,12 +6,13 @@
 //! whose key starts with `"cli:"`.
 
 use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
 
 use crate::config::Config;
 use crate::error::{Result, ZeptoError};
+use crate::session::lock::{self, DirLock, LockMode};
 use crate::session::{Message, Role, Session};
 
 /// Metadata for a saved CLI conversation.
@@ -27,6 +28,9 @@ pub struct ConversationEntry {
     pub last_updated: String,
     /// File size in bytes
     pub file_size: u64,
+    /// Whether the session is pinned (exempt from cleanup)
+    #[serde(default)]
+    pub pinned: bool,
 }
 
 /// Manages CLI conversation history on disk.
@@ -66,7 +70,8 @@ impl ConversationHistory {
 
     /// List all CLI conversations, sorted by `last_updated` descending (newest first).
     ///
-    /// Scans the session directory for `cli%3A*.json` files, reads each one to
+    /// Scans the session directory for `cli%3A*` session files (in any
+    /// [`Codec`](super::Codec) format), reads each one to
     /// extract metadata, and returns a sorted list of conversation entries.
     ///
     /// Non-CLI sessions (e.g., telegram, slack) are ignored.
@@ -75,6 +80,12 @@ impl ConversationHistory {
     ///
     /// Returns an error if reading the directory or any session file fails.
     pub fn list_conversations(&self) -> Result<Vec<ConversationEntry>> {
+        let _lock = self.lock(LockMode::Shared)?;
+        self.scan_conversations()
+    }
+
+    /// Read CLI conversations from disk. Callers must hold the directory lock.
+    fn scan_conversations(&self) -> Result<Vec<ConversationEntry>> {
         let mut entries = Vec::new();
 
         let dir_entries = std::fs::read_dir(&self.storage_path)?;
@@ -82,23 +93,23 @@ impl ConversationHistory {
             let entry = entry?;
             let path = entry.path();
 
-            // Only look at .json files whose sanitized name starts with cli%3A
+            // Only look at session files whose sanitized name starts with cli%3A
             let file_name = match path.file_name().and_then(|n| n.to_str()) {
                 Some(name) => name.to_string(),
                 None => continue,
             };
 
-            if !file_name.ends_with(".json") || !file_name.starts_with("cli%3A") {
+            if !super::codec::is_session_file(&path) || !file_name.starts_with("cli%3A") {
                 continue;
             }
 
             // Read and parse the session
-            let content = match std::fs::read_to_string(&path) {
+            let content = match std::fs::read(&path) {
                 Ok(c) => c,
                 Err(_) => continue,
             };
 
-            let session: Session = match serde_json::from_str(&content) {
+            let session: Session = match super::codec::decode_file(&path, &content) {
                 Ok(s) => s,
                 Err(_) => continue,
             };
@@ -116,6 +127,7 @@ impl ConversationHistory {
                 message_count: session.messages.len(),
                 last_updated: session.updated_at.to_rfc3339(),
                 file_size,
+                pinned: session.pinned,
             });
         }
 
@@ -198,6 +210,7 @@ impl ConversationHistory {
     ///
     /// Conversations are sorted by `last_updated` descending, so the newest
     /// `keep_count` are preserved and the rest are deleted from disk.
+    /// Pinned conversations are never deleted.
     ///
     /// # Returns
     ///
@@ -207,26 +220,33 @@ impl ConversationHistory {
     ///
     /// Returns an error if listing or deleting session files fails.
     pub fn cleanup_old(&self, keep_count: usize) -> Result<usize> {
-        let conversations = self.list_conversations()?;
+        let _lock = self.lock(LockMode::Exclusive)?;
+        let conversations = self.scan_conversations()?;
 
         if conversations.len() <= keep_count {
             return Ok(0);
         }
 
-        let to_delete = &conversations[keep_count..];
+        let to_delete = conversations[keep_count..].iter().filter(|e| !e.pinned);
         let mut deleted = 0;
 
         for entry in to_delete {
             let sanitized = Self::sanitize_key(&entry.session_key);
-            let file_path = self.storage_path.join(format!("{}.json", sanitized));
-            if file_path.exists() {
-                std::fs::remove_file(&file_path).map_err(|e| {
-                    ZeptoError::Session(format!(
-                        "Failed to delete session file {}: {}",
-                        file_path.display(),
-                        e
-                    ))
-                })?;
+            let mut removed = false;
+            for ext in super::codec::KNOWN_EXTENSIONS {
+                let file_path = self.storage_path.join(format!("{}.{}", sanitized, ext));
+                if file_path.exists() {
+                    std::fs::remove_file(&file_path).map_err(|e| {
+                        ZeptoError::Session(format!(
+                            "Failed to delete session file {}: {}",
+                            file_path.display(),
+                            e
+                        ))
+                    })?;
+                    removed = true;
+                }
+            }
+            if removed {
                 deleted += 1;
             }
         }
@@ -234,6 +254,12 @@ impl ConversationHistory {
         Ok(deleted)
     }
 
+    /// Acquire the cross-process sessions directory lock.
+    fn lock(&self, mode: LockMode) -> Result<Option<DirLock>> {
+        let timeout = Duration::from_millis(Config::get().session.lock_timeout_ms);
+        lock::acquire_blocking(&self.storage_path, mode, timeout)
+    }
+
     /// Sanitize a session key for use as a filename (matches `SessionManager::sanitize_key`).
     fn sanitize_key(key: &str) -> String {
         let mut result = String::with_capacity(key.len() * 3);
@@ -289,6 +315,7 @@ mod tests {
             message_count: 3,
             last_updated: "2025-11-14T22:13:20+00:00".to_string(),
             file_size: 256,
+            pinned: false,
         };
 
         assert_eq!(entry.session_key, "cli:1700000000");
@@ -573,6 +600,31 @@ mod tests {
         assert!(dir.join("cli%3A5000.json").exists());
     }
 
+    #[test]
+    fn test_cleanup_old_skips_pinned() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+
+        write_test_session(dir, "cli:1000", "Session one", "2025-01-01T00:00:00Z");
+        write_test_session(dir, "cli:2000", "Session two", "2025-02-01T00:00:00Z");
+        write_test_session(dir, "cli:3000", "Session three", "2025-03-01T00:00:00Z");
+
+        // Pin the oldest session by rewriting it with the flag set
+        let path = dir.join("cli%3A1000.json");
+        let mut json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        json["pinned"] = serde_json::Value::Bool(true);
+        std::fs::write(&path, serde_json::to_string(&json).unwrap()).unwrap();
+
+        let history = ConversationHistory::with_path(dir.to_path_buf()).unwrap();
+        let deleted = history.cleanup_old(1).unwrap();
+        assert_eq!(deleted, 1);
+
+        assert!(dir.join("cli%3A1000.json").exists());
+        assert!(!dir.join("cli%3A2000.json").exists());
+        assert!(dir.join("cli%3A3000.json").exists());
+    }
+
     #[test]
     fn test_cleanup_old_nothing_to_delete() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,414 @@
+//! Standalone HTML export of a conversation session.
+//!
+//! Produces a single self-contained page (inline CSS, no scripts) meant for
+//! sharing transcripts with people who will never open a JSON session file:
+//! role-colored message bubbles, collapsible tool-call and tool-result
+//! sections, and fenced code blocks run through a small keyword/string/
+//! comment highlighter.
+//!
+//! Session messages do not carry individual timestamps, so only the session's
+//! creation and last-update times are shown.
+
+use std::collections::HashMap;
+
+use super::{Message, Role, Session};
+use crate::utils::string::escape_html;
+
+/// Options for [`render`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HtmlExportOptions {
+    /// Leave system messages out of the page.
+    pub hide_system_messages: bool,
+    /// Replace tool-call arguments with a placeholder. Useful when arguments
+    /// may contain paths, credentials, or customer data.
+    pub redact_tool_arguments: bool,
+}
+
+/// Shown in place of tool-call arguments when they are redacted.
+pub const REDACTED_ARGUMENTS: &str = "[arguments redacted]";
+
+const STYLE: &str = "\
+body{font-family:-apple-system,BlinkMacSystemFont,\"Segoe UI\",Helvetica,Arial,sans-serif;\
+background:#f5f5f7;color:#1d1d1f;margin:0;padding:24px}\
+main{max-width:820px;margin:0 auto}\
+header{max-width:820px;margin:0 auto 24px}\
+h1{font-size:1.4em;margin:0 0 4px}\
+.meta{color:#6e6e73;font-size:.85em;margin:0}\
+.summary{background:#fff8e1;border-left:4px solid #f5b400;padding:8px 12px;margin-top:12px}\
+.msg{border-radius:12px;padding:10px 14px;margin:10px 0;max-width:85%;overflow-wrap:anywhere}\
+.role{font-size:.75em;font-weight:600;text-transform:uppercase;color:#6e6e73;margin-bottom:4px}\
+.user{background:#d7e9ff;margin-left:auto}\
+.assistant{background:#fff;border:1px solid #e0e0e5}\
+.system{background:#eeeef0;color:#3a3a3c;font-style:italic;max-width:100%}\
+.tool{background:#f0f7f0;border:1px solid #cfe3cf;max-width:100%}\
+details{margin-top:6px}\
+summary{cursor:pointer;font-size:.85em;color:#3a3a3c}\
+p{margin:4px 0}\
+pre{background:#1e1e2e;color:#e0e0e0;padding:10px;border-radius:8px;overflow-x:auto;font-size:.85em}\
+.kw{color:#c792ea}.str{color:#c3e88d}.com{color:#7f848e;font-style:italic}.num{color:#f78c6c}";
+
+const KEYWORDS: &[&str] = &[
+    "and",
+    "as",
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "def",
+    "default",
+    "do",
+    "done",
+    "elif",
+    "else",
+    "enum",
+    "except",
+    "export",
+    "false",
+    "False",
+    "fi",
+    "finally",
+    "fn",
+    "for",
+    "from",
+    "func",
+    "function",
+    "if",
+    "impl",
+    "import",
+    "in",
+    "interface",
+    "let",
+    "loop",
+    "match",
+    "mod",
+    "mut",
+    "new",
+    "None",
+    "not",
+    "null",
+    "or",
+    "package",
+    "pub",
+    "raise",
+    "return",
+    "self",
+    "Self",
+    "static",
+    "struct",
+    "switch",
+    "then",
+    "this",
+    "throw",
+    "trait",
+    "true",
+    "True",
+    "try",
+    "type",
+    "use",
+    "var",
+    "while",
+    "with",
+    "yield",
+];
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn push_span(out: &mut String, class: &str, text: &str) {
+    out.push_str(&format!(
+        "<span class=\"{}\">{}</span>",
+        class,
+        escape_html(text)
+    ));
+}
+
+/// Highlight `code` with keyword, string, number, and comment spans.
+///
+/// This is deliberately language-agnostic: `lang` only decides whether `#`
+/// or `//` starts a line comment and whether `'` opens a string (it does
+/// not in Rust, where it usually marks a lifetime).
+pub fn highlight(code: &str, lang: &str) -> String {
+    let lang = lang.to_ascii_lowercase();
+    let hash_comments = matches!(
+        lang.as_str(),
+        "python" | "py" | "sh" | "bash" | "shell" | "zsh" | "ruby" | "rb" | "yaml" | "yml" | "toml"
+    );
+    let single_quote_strings = !matches!(lang.as_str(), "rust" | "rs");
+
+    let chars: Vec<char> = code.chars().collect();
+    let mut out = String::with_capacity(code.len() * 2);
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let starts_comment = if hash_comments {
+            c == '#'
+        } else {
+            c == '/' && chars.get(i + 1) == Some(&'/')
+        };
+
+        if starts_comment {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            push_span(&mut out, "com", &chars[start..i].iter().collect::<String>());
+        } else if c == '"' || (c == '\'' && single_quote_strings) {
+            i += 1;
+            while i < chars.len() && chars[i] != c && chars[i] != '\n' {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            i = i.min(chars.len());
+            // Include the closing quote when there is one.
+            if chars.get(i) == Some(&c) {
+                i += 1;
+            }
+            push_span(&mut out, "str", &chars[start..i].iter().collect::<String>());
+        } else if c.is_ascii_digit() && (i == 0 || !is_ident_char(chars[i - 1])) {
+            while i < chars.len() && (is_ident_char(chars[i]) || chars[i] == '.') {
+                i += 1;
+            }
+            push_span(&mut out, "num", &chars[start..i].iter().collect::<String>());
+        } else if is_ident_char(c) {
+            while i < chars.len() && is_ident_char(chars[i]) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if KEYWORDS.contains(&word.as_str()) {
+                push_span(&mut out, "kw", &word);
+            } else {
+                out.push_str(&escape_html(&word));
+            }
+        } else {
+            out.push_str(&escape_html(&c.to_string()));
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Render message text: fenced code blocks become highlighted `<pre>`
+/// blocks, everything else becomes paragraphs.
+fn render_text(text: &str) -> String {
+    let mut out = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code: Option<(String, Vec<&str>)> = None;
+
+    let flush_paragraph = |out: &mut String, paragraph: &mut Vec<&str>| {
+        if !paragraph.is_empty() {
+            let lines: Vec<String> = paragraph.iter().map(|l| escape_html(l)).collect();
+            out.push_str(&format!("<p>{}</p>\n", lines.join("<br>")));
+            paragraph.clear();
+        }
+    };
+    let flush_code = |out: &mut String, lang: &str, lines: &[&str]| {
+        let class = if lang.is_empty() {
+            String::new()
+        } else {
+            format!(" class=\"language-{}\"", escape_html(lang))
+        };
+        out.push_str(&format!(
+            "<pre><code{}>{}</code></pre>\n",
+            class,
+            highlight(&lines.join("\n"), lang)
+        ));
+    };
+
+    for line in text.lines() {
+        let fence = line.trim_start().strip_prefix("```");
+        match (&mut code, fence) {
+            (Some((lang, lines)), Some(_)) => {
+                flush_code(&mut out, lang, lines);
+                code = None;
+            }
+            (Some((_, lines)), None) => lines.push(line),
+            (None, Some(lang)) => {
+                flush_paragraph(&mut out, &mut paragraph);
+                code = Some((lang.trim().to_string(), Vec::new()));
+            }
+            (None, None) if line.trim().is_empty() => flush_paragraph(&mut out, &mut paragraph),
+            (None, None) => paragraph.push(line),
+        }
+    }
+    // An unterminated fence still renders as code.
+    if let Some((lang, lines)) = &code {
+        flush_code(&mut out, lang, lines);
+    }
+    flush_paragraph(&mut out, &mut paragraph);
+    out
+}
+
+fn render_message(
+    out: &mut String,
+    msg: &Message,
+    tool_names: &HashMap<&str, &str>,
+    options: &HtmlExportOptions,
+) {
+    match msg.role {
+        Role::User | Role::Assistant | Role::System => {
+            let (class, label) = match msg.role {
+                Role::User => ("user", "User"),
+                Role::Assistant => ("assistant", "Assistant"),
+                _ => ("system", "System"),
+            };
+            out.push_str(&format!(
+                "<div class=\"msg {}\">\n<div class=\"role\">{}</div>\n",
+                class, label
+            ));
+            out.push_str(&render_text(&msg.content));
+            for call in msg.tool_calls.iter().flatten() {
+                let arguments = if options.redact_tool_arguments {
+                    REDACTED_ARGUMENTS.to_string()
+                } else {
+                    // Pretty-print JSON arguments; fall back to the raw string.
+                    serde_json::from_str::<serde_json::Value>(&call.arguments)
+                        .ok()
+                        .and_then(|v| serde_json::to_string_pretty(&v).ok())
+                        .unwrap_or_else(|| call.arguments.clone())
+                };
+                out.push_str(&format!(
+                    "<details class=\"tool-call\">\n<summary>Tool call: {}</summary>\n\
+                     <pre><code>{}</code></pre>\n</details>\n",
+                    escape_html(&call.name),
+                    escape_html(&arguments)
+                ));
+            }
+            out.push_str("</div>\n");
+        }
+        Role::Tool => {
+            let name = msg
+                .tool_call_id
+                .as_deref()
+                .and_then(|id| tool_names.get(id))
+                .copied()
+                .unwrap_or("tool");
+            out.push_str(&format!(
+                "<div class=\"msg tool\">\n<details class=\"tool-result\">\n\
+                 <summary>Tool result: {}</summary>\n<pre><code>{}</code></pre>\n\
+                 </details>\n</div>\n",
+                escape_html(name),
+                escape_html(&msg.content)
+            ));
+        }
+    }
+}
+
+/// Render `session` as a standalone HTML page.
+pub fn render(session: &Session, options: &HtmlExportOptions) -> String {
+    let tool_names: HashMap<&str, &str> = session
+        .messages
+        .iter()
+        .filter_map(|m| m.tool_calls.as_ref())
+        .flatten()
+        .map(|tc| (tc.id.as_str(), tc.name.as_str()))
+        .collect();
+
+    let title = escape_html(&session.key);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>Session {}</title>\n<style>{}</style>\n</head>\n<body>\n<header>\n\
+         <h1>{}</h1>\n<p class=\"meta\">Started {} &middot; Last updated {} &middot; {} messages</p>\n",
+        title,
+        STYLE,
+        title,
+        session.created_at.format("%Y-%m-%d %H:%M UTC"),
+        session.updated_at.format("%Y-%m-%d %H:%M UTC"),
+        session.messages.len()
+    );
+    if let Some(summary) = session.summary.as_deref().filter(|s| !s.is_empty()) {
+        out.push_str(&format!(
+            "<div class=\"summary\"><strong>Earlier conversation:</strong> {}</div>\n",
+            escape_html(summary)
+        ));
+    }
+    out.push_str("</header>\n<main>\n");
+    for msg in &session.messages {
+        if options.hide_system_messages && msg.role == Role::System {
+            continue;
+        }
+        render_message(&mut out, msg, &tool_names, options);
+    }
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::ToolCall;
+    use chrono::{TimeZone, Utc};
+
+    const GOLDEN_PATH: &str = "src/session/testdata/session_export.html";
+
+    fn fixture_session() -> Session {
+        let mut session = Session::new("telegram:42");
+        session.created_at = Utc.with_ymd_and_hms(2025, 3, 1, 9, 30, 0).unwrap();
+        session.updated_at = Utc.with_ymd_and_hms(2025, 3, 1, 9, 42, 0).unwrap();
+        session.messages = vec![
+            Message::system("You are a helpful assistant."),
+            Message::user("How do I read a file in Rust? <asap>"),
+            Message::assistant_with_tools(
+                "Let me check the docs.",
+                vec![ToolCall::new(
+                    "call_1",
+                    "web_search",
+                    r#"{"query":"rust read file"}"#,
+                )],
+            ),
+            Message::tool_result("call_1", "std::fs::read_to_string reads a whole file."),
+            Message::assistant(
+                "Use `read_to_string`:\n\n```rust\n// Read the whole file\nlet text = std::fs::read_to_string(\"notes.txt\")?;\nlet n = 42;\n```\n\nThat's it.",
+            ),
+        ];
+        session
+    }
+
+    #[test]
+    fn test_render_matches_golden_file() {
+        let html = render(&fixture_session(), &HtmlExportOptions::default());
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(GOLDEN_PATH, &html).unwrap();
+        }
+        assert_eq!(html, include_str!("testdata/session_export.html"));
+    }
+
+    #[test]
+    fn test_render_options_hide_system_and_redact_arguments() {
+        let options = HtmlExportOptions {
+            hide_system_messages: true,
+            redact_tool_arguments: true,
+        };
+        let html = render(&fixture_session(), &options);
+        assert!(!html.contains("You are a helpful assistant."));
+        assert!(!html.contains("rust read file"));
+        assert!(html.contains(REDACTED_ARGUMENTS));
+        assert!(html.contains("Tool result: web_search"));
+    }
+
+    #[test]
+    fn test_highlight_spans() {
+        let html = highlight("let s = \"a<b\"; // done", "rust");
+        assert_eq!(
+            html,
+            "<span class=\"kw\">let</span> s = <span class=\"str\">&quot;a&lt;b&quot;</span>; \
+             <span class=\"com\">// done</span>"
+        );
+        let html = highlight("x = 1  # one", "python");
+        assert!(html.contains("<span class=\"num\">1</span>"));
+        assert!(html.contains("<span class=\"com\"># one</span>"));
+    }
+
+    #[test]
+    fn test_render_text_unterminated_fence() {
+        let html = render_text("before\n```\nopen code");
+        assert_eq!(html, "<p>before</p>\n<pre><code>open code</code></pre>\n");
+    }
+}
@@ -0,0 +1,144 @@
+//! Cross-process advisory locking for the file session store.
+//!
+//! A single lock file (`.sessions.lock`) in the sessions directory guards
+//! disk reads (shared) and writes (exclusive), so two ZeptoClaw processes
+//! pointed at the same directory do not interleave load-modify-save cycles.
+//!
+//! Locks are advisory (`flock` on Unix, `LockFileEx` on Windows). On
+//! platforms without lock support, acquisition is a no-op rather than an
+//! error. Acquisition that cannot complete within the configured timeout
+//! fails with [`ZeptoError::Locked`].
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use tracing::debug;
+
+use crate::error::{Result, ZeptoError};
+
+/// Name of the lock file inside the sessions directory.
+pub const LOCK_FILE_NAME: &str = ".sessions.lock";
+
+/// Delay between lock acquisition attempts.
+const RETRY_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Held directory lock. Released when dropped.
+#[derive(Debug)]
+pub struct DirLock {
+    _file: File,
+}
+
+/// Lock mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    /// Multiple readers may hold the lock at once.
+    Shared,
+    /// A single writer holds the lock.
+    Exclusive,
+}
+
+enum Attempt {
+    Acquired(DirLock),
+    Busy,
+    Unsupported,
+}
+
+fn try_once(dir: &Path, mode: LockMode) -> Result<Attempt> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(dir.join(LOCK_FILE_NAME))?;
+    let result = match mode {
+        LockMode::Shared => file.try_lock_shared(),
+        LockMode::Exclusive => file.try_lock(),
+    };
+    match result {
+        Ok(()) => Ok(Attempt::Acquired(DirLock { _file: file })),
+        Err(TryLockError::WouldBlock) => Ok(Attempt::Busy),
+        Err(TryLockError::Error(e)) if e.kind() == ErrorKind::Unsupported => {
+            Ok(Attempt::Unsupported)
+        }
+        Err(TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+fn timed_out(dir: &Path, timeout: Duration) -> ZeptoError {
+    ZeptoError::Locked(format!(
+        "sessions directory {} is locked by another process (waited {}ms)",
+        dir.display(),
+        timeout.as_millis()
+    ))
+}
+
+/// Acquire the directory lock, waiting up to `timeout`.
+///
+/// Returns `Ok(None)` when the platform does not support file locking.
+pub async fn acquire(dir: &Path, mode: LockMode, timeout: Duration) -> Result<Option<DirLock>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match try_once(dir, mode)? {
+            Attempt::Acquired(lock) => return Ok(Some(lock)),
+            Attempt::Unsupported => {
+                debug!("File locking unsupported on this platform; continuing unlocked");
+                return Ok(None);
+            }
+            Attempt::Busy if Instant::now() >= deadline => return Err(timed_out(dir, timeout)),
+            Attempt::Busy => tokio::time::sleep(RETRY_INTERVAL).await,
+        }
+    }
+}
+
+/// Blocking variant of [`acquire`] for synchronous callers.
+pub fn acquire_blocking(dir: &Path, mode: LockMode, timeout: Duration) -> Result<Option<DirLock>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match try_once(dir, mode)? {
+            Attempt::Acquired(lock) => return Ok(Some(lock)),
+            Attempt::Unsupported => return Ok(None),
+            Attempt::Busy if Instant::now() >= deadline => return Err(timed_out(dir, timeout)),
+            Attempt::Busy => std::thread::sleep(RETRY_INTERVAL),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_exclusive_lock_times_out_while_held() {
+        let dir = TempDir::new().unwrap();
+        let held = acquire(dir.path(), LockMode::Exclusive, Duration::from_secs(1))
+            .await
+            .unwrap();
+        if held.is_none() {
+            return; // locking unsupported on this platform
+        }
+
+        let err = acquire(dir.path(), LockMode::Exclusive, Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ZeptoError::Locked(_)));
+
+        drop(held);
+        assert!(
+            acquire(dir.path(), LockMode::Exclusive, Duration::from_millis(50))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_shared_locks_coexist() {
+        let dir = TempDir::new().unwrap();
+        let a = acquire_blocking(dir.path(), LockMode::Shared, Duration::from_millis(50)).unwrap();
+        let b = acquire_blocking(dir.path(), LockMode::Shared, Duration::from_millis(50));
+        assert!(b.is_ok());
+        drop(a);
+    }
+}
@@ -0,0 +1,135 @@
+//! Markdown export of a conversation session.
+//!
+//! A plain transcript for notes, tickets or the chat itself (`/export`):
+//! one heading per message, tool calls listed under the message that made
+//! them and tool results in code blocks. System messages are left out; they
+//! are the prompt, not the conversation.
+
+use std::collections::HashMap;
+
+use super::{Message, Role, Session};
+
+/// A code block holding `text`, fenced with more backticks than `text`
+/// contains in a row.
+fn code_block(text: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}\n{}\n{}", fence, text.trim_end(), fence)
+}
+
+fn render_message(out: &mut String, msg: &Message, tool_names: &HashMap<&str, &str>) {
+    match msg.role {
+        Role::System => {}
+        Role::User | Role::Assistant => {
+            let label = if msg.role == Role::User {
+                "User"
+            } else {
+                "Assistant"
+            };
+            match &msg.agent {
+                Some(agent) => out.push_str(&format!("### {} ({})\n\n", label, agent)),
+                None => out.push_str(&format!("### {}\n\n", label)),
+            }
+            if !msg.content.trim().is_empty() {
+                out.push_str(msg.content.trim_end());
+                out.push_str("\n\n");
+            }
+            let calls: Vec<_> = msg.tool_calls.iter().flatten().collect();
+            for call in &calls {
+                out.push_str(&format!(
+                    "- Called `{}` with `{}`\n",
+                    call.name,
+                    call.arguments.replace('`', "'")
+                ));
+            }
+            if !calls.is_empty() {
+                out.push('\n');
+            }
+        }
+        Role::Tool => {
+            let name = msg
+                .tool_call_id
+                .as_deref()
+                .and_then(|id| tool_names.get(id))
+                .copied()
+                .unwrap_or("tool");
+            out.push_str(&format!(
+                "### Tool result: {}\n\n{}\n\n",
+                name,
+                code_block(&msg.content)
+            ));
+        }
+    }
+}
+
+/// Render `session` as a Markdown transcript.
+pub fn render(session: &Session) -> String {
+    let tool_names: HashMap<&str, &str> = session
+        .messages
+        .iter()
+        .filter_map(|m| m.tool_calls.as_ref())
+        .flatten()
+        .map(|tc| (tc.id.as_str(), tc.name.as_str()))
+        .collect();
+
+    let mut out = format!(
+        "# Session {}\n\nStarted {} · Last updated {} · {} messages\n\n",
+        session.key,
+        session.created_at.format("%Y-%m-%d %H:%M UTC"),
+        session.updated_at.format("%Y-%m-%d %H:%M UTC"),
+        session.messages.len()
+    );
+    if let Some(summary) = session.summary.as_deref().filter(|s| !s.is_empty()) {
+        out.push_str(&format!("> **Earlier conversation:** {}\n\n", summary));
+    }
+    for msg in &session.messages {
+        render_message(&mut out, msg, &tool_names);
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::ToolCall;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_render_transcript() {
+        let mut session = Session::new("telegram:42");
+        session.created_at = Utc.with_ymd_and_hms(2025, 3, 1, 9, 30, 0).unwrap();
+        session.updated_at = Utc.with_ymd_and_hms(2025, 3, 1, 9, 42, 0).unwrap();
+        session.messages = vec![
+            Message::system("You are a helpful assistant."),
+            Message::user("How do I read a file in Rust?"),
+            Message::assistant_with_tools(
+                "Let me check the docs.",
+                vec![ToolCall::new(
+                    "call_1",
+                    "web_search",
+                    r#"{"query":"rust read file"}"#,
+                )],
+            ),
+            Message::tool_result("call_1", "Use ```read_to_string```."),
+            Message::assistant("Use `std::fs::read_to_string`."),
+        ];
+
+        assert_eq!(
+            render(&session),
+            "# Session telegram:42\n\n\
+             Started 2025-03-01 09:30 UTC · Last updated 2025-03-01 09:42 UTC · 5 messages\n\n\
+             ### User\n\nHow do I read a file in Rust?\n\n\
+             ### Assistant\n\nLet me check the docs.\n\n\
+             - Called `web_search` with `{\"query\":\"rust read file\"}`\n\n\
+             ### Tool result: web_search\n\n````\nUse ```read_to_string```.\n````\n\n\
+             ### Assistant\n\nUse `std::fs::read_to_string`.\n"
+        );
+    }
+}
@@ -26,22 +26,45 @@
 //! }
 //! ```
 
+pub mod codec;
+pub mod command_log;
 pub mod history;
+pub mod html;
+pub mod langsmith;
+pub mod lock;
+pub mod markdown;
 pub mod media;
 pub mod repair;
+pub mod rotation;
 pub mod types;
 
+pub use codec::Codec;
+pub use command_log::{CommandLog, CommandOutcome, CommandRecord};
 pub use history::ConversationHistory;
+pub use html::HtmlExportOptions;
 pub use repair::{repair_messages, RepairStats};
-pub use types::{ContentPart, ImageSource, Message, Role, Session, ToolCall};
-
-use crate::config::Config;
-use crate::error::Result;
+pub use rotation::RotationPolicy;
+pub use types::{
+    normalize_tag, ContentPart, ImageSource, MemoryNote, Message, PlanItem, Role, Session,
+    ThinkingBlock, ToolCall, ToolIterationStats, UsageCost, DUPLICATE_TOOL_RESULT_MARKER,
+};
+
+use crate::config::{Config, SessionOptions};
+use crate::error::{Result, SessionOp, ZeptoError};
+use lock::{DirLock, LockMode};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
-use tracing::warn;
+use tracing::{info, warn};
+
+/// How often the gateway expires idle sessions (see
+/// [`SessionManager::expire_idle`]).
+pub const SESSION_EXPIRY_INTERVAL_SECS: u64 = 300;
+
+/// Directory under the sessions directory holding the command log.
+const COMMAND_LOG_DIR: &str = "commands";
 
 /// Session manager for storing and retrieving conversation sessions.
 ///
@@ -59,11 +82,25 @@ use tracing::warn;
 /// When created with `new()`, sessions are persisted to disk in the
 /// `~/.zeptoclaw/sessions/` directory. Use `new_memory()` for testing
 /// or when persistence is not needed.
+///
+/// Disk access is guarded by an advisory directory lock (see [`lock`]) so
+/// several processes can share one sessions directory without clobbering
+/// each other's writes.
 pub struct SessionManager {
     /// In-memory cache of sessions
     sessions: Arc<RwLock<HashMap<String, Session>>>,
     /// Optional path for file-based persistence
     storage_path: Option<PathBuf>,
+    /// Maximum wait for the cross-process directory lock
+    lock_timeout: Duration,
+    /// Optional policy for archiving very long-running sessions
+    rotation: Option<RotationPolicy>,
+    /// Options for new sessions, keyed by session-key namespace
+    channel_defaults: HashMap<String, SessionOptions>,
+    /// Format used when writing session files
+    codec: Codec,
+    /// Commands run by the agent's tools, under `<storage>/commands/`
+    command_log: Arc<CommandLog>,
 }
 
 impl SessionManager {
@@ -87,7 +124,12 @@ impl SessionManager {
         std::fs::create_dir_all(&storage_path)?;
         Ok(Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
+            command_log: Arc::new(CommandLog::new(storage_path.join(COMMAND_LOG_DIR))),
             storage_path: Some(storage_path),
+            lock_timeout: Self::configured_lock_timeout(),
+            rotation: RotationPolicy::from_config(&Config::get().session),
+            channel_defaults: Config::get().session.channel_defaults.clone(),
+            codec: Codec::default(),
         })
     }
 
@@ -106,6 +148,11 @@ impl SessionManager {
         Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             storage_path: None,
+            lock_timeout: Self::configured_lock_timeout(),
+            rotation: RotationPolicy::from_config(&Config::get().session),
+            channel_defaults: Config::get().session.channel_defaults.clone(),
+            codec: Codec::default(),
+            command_log: Arc::new(CommandLog::memory()),
         }
     }
 
@@ -129,16 +176,117 @@ impl SessionManager {
         std::fs::create_dir_all(&path)?;
         Ok(Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
+            command_log: Arc::new(CommandLog::new(path.join(COMMAND_LOG_DIR))),
             storage_path: Some(path),
+            lock_timeout: Self::configured_lock_timeout(),
+            rotation: RotationPolicy::from_config(&Config::get().session),
+            channel_defaults: Config::get().session.channel_defaults.clone(),
+            codec: Codec::default(),
         })
     }
 
+    /// Override how long disk operations wait for the cross-process
+    /// directory lock before failing with `ZeptoError::Locked`.
+    pub fn with_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// Set the rotation policy for very long-running sessions, replacing any
+    /// policy from config. See [`rotation`] for details.
+    pub fn with_rotation(mut self, policy: RotationPolicy) -> Self {
+        self.rotation = Some(policy);
+        self
+    }
+
+    /// Set the format used when writing session files (JSON by default).
+    ///
+    /// Files in other formats are still read, and are rewritten in this
+    /// format the next time the session is saved.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::session::{Codec, SessionManager};
+    ///
+    /// let manager = SessionManager::new_memory().with_codec(Codec::Json);
+    /// ```
+    pub fn with_codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Set the per-namespace options for new sessions, replacing the
+    /// `session.channel_defaults` map from config.
+    pub fn with_channel_defaults(mut self, defaults: HashMap<String, SessionOptions>) -> Self {
+        self.channel_defaults = defaults;
+        self
+    }
+
+    /// The log the shell and process tools append the commands they run to.
+    pub fn command_log(&self) -> Arc<CommandLog> {
+        Arc::clone(&self.command_log)
+    }
+
+    /// The last `limit` commands run in session `key`, oldest first.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::session::SessionManager;
+    ///
+    /// let manager = SessionManager::new_memory();
+    /// assert!(manager.recent_commands("cli:main", 10).unwrap().is_empty());
+    /// ```
+    pub fn recent_commands(&self, key: &str, limit: usize) -> Result<Vec<CommandRecord>> {
+        self.command_log.recent(key, limit)
+    }
+
+    /// Options configured for new sessions under `key`'s namespace (the part
+    /// before the first `:`), or the defaults if none are configured.
+    fn default_options_for(&self, key: &str) -> SessionOptions {
+        let namespace = key.split(':').next().unwrap_or(key);
+        self.channel_defaults
+            .get(namespace)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// A fresh session with its namespace's default options applied.
+    fn new_session(&self, key: &str) -> Session {
+        let options = self.default_options_for(key);
+        let mut session = Session::new(key);
+        session.pinned = options.pinned_by_default;
+        session.options = Some(options);
+        session
+    }
+
+    /// Resolve the options governing a session.
+    ///
+    /// Sessions keep the options they were created with, so changing
+    /// `session.channel_defaults` only affects new sessions. Sessions that do
+    /// not exist yet, or predate per-channel options, resolve to the current
+    /// defaults for their namespace.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if loading the session from disk fails.
+    pub async fn options_for(&self, key: &str) -> Result<SessionOptions> {
+        let stored = self
+            .with_session(key, |s| s.options.clone())
+            .await?
+            .flatten();
+        Ok(stored.unwrap_or_else(|| self.default_options_for(key)))
+    }
+
     /// Get an existing session or create a new one.
     ///
     /// If the session exists in memory, it is returned immediately.
     /// If persistence is enabled and the session exists on disk, it
     /// is loaded into memory. Otherwise, a new empty session is created.
     ///
+    /// When a rotation policy is set and the session crossed its threshold,
+    /// the session is archived (see [`history`](Self::history)) and a fresh
+    /// one is returned under the same key.
+    ///
     /// # Arguments
     /// * `key` - Unique session identifier
     ///
@@ -158,34 +306,118 @@ impl SessionManager {
     /// }
     /// ```
     pub async fn get_or_create(&self, key: &str) -> Result<Session> {
-        // Check in-memory cache first
-        {
-            let sessions = self.sessions.read().await;
-            if let Some(session) = sessions.get(key) {
-                return Ok(session.clone());
-            }
-        }
+        Ok(self.get_or_create_from(key, |_| 0).await?.0)
+    }
 
-        // Try loading from disk if persistence is enabled
-        if let Some(ref storage_path) = self.storage_path {
-            let file_path = storage_path.join(format!("{}.json", Self::sanitize_key(key)));
-            if file_path.exists() {
-                let content = tokio::fs::read_to_string(&file_path).await?;
-                let mut session: Session = serde_json::from_str(&content)?;
-                self.maybe_repair_loaded_session(&mut session, "get_or_create");
+    /// Get or create a session like [`get_or_create`](Self::get_or_create),
+    /// copying only the messages from the index `start` picks on.
+    ///
+    /// `start` runs against the cached session in place, under the cache
+    /// lock. The earlier messages are not copied; save the session with
+    /// [`save_from`](Self::save_from) and the index returned to keep them in
+    /// front of its messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if loading from disk or rotating the session fails.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::session::{SessionManager, Message};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let manager = SessionManager::new_memory();
+    ///     let mut session = manager.get_or_create("test").await.unwrap();
+    ///     session.add_message(Message::user("one"));
+    ///     session.add_message(Message::user("two"));
+    ///     manager.save(&session).await.unwrap();
+    ///
+    ///     let (mut tail, start) = manager
+    ///         .get_or_create_from("test", |s| s.messages.len() - 1)
+    ///         .await
+    ///         .unwrap();
+    ///     assert_eq!((tail.messages.len(), start), (1, 1));
+    ///     tail.add_message(Message::user("three"));
+    ///     manager.save_from(&tail, start).await.unwrap();
+    ///     assert_eq!(manager.message_count("test").await.unwrap(), 3);
+    /// }
+    /// ```
+    pub async fn get_or_create_from<F>(&self, key: &str, start: F) -> Result<(Session, usize)>
+    where
+        F: FnOnce(&Session) -> usize,
+    {
+        if !self.ensure_cached(key).await? {
+            let mut sessions = self.sessions.write().await;
+            sessions
+                .entry(key.to_string())
+                .or_insert_with(|| self.new_session(key));
+        }
 
-                // Cache it in memory
-                let mut sessions = self.sessions.write().await;
-                sessions.insert(key.to_string(), session.clone());
-                return Ok(session);
+        if let Some(ref policy) = self.rotation {
+            let now = chrono::Utc::now();
+            let due = {
+                let sessions = self.sessions.read().await;
+                sessions
+                    .get(key)
+                    .filter(|s| policy.should_rotate(s, now))
+                    .cloned()
+            };
+            if let Some(session) = due {
+                return Ok((self.rotate(session, policy).await?, 0));
             }
         }
 
-        // Create new session
-        let session = Session::new(key);
         let mut sessions = self.sessions.write().await;
-        sessions.insert(key.to_string(), session.clone());
-        Ok(session)
+        let session = sessions
+            .entry(key.to_string())
+            .or_insert_with(|| self.new_session(key));
+        let start = start(session).min(session.messages.len());
+        // Copy everything but the messages, then just the ones asked for.
+        let messages = std::mem::take(&mut session.messages);
+        let mut copy = session.clone();
+        session.messages = messages;
+        copy.messages = session.messages[start..].to_vec();
+        Ok((copy, start))
+    }
+
+    /// Archive `session` under a dated key and start a fresh generation.
+    async fn rotate(&self, session: Session, policy: &RotationPolicy) -> Result<Session> {
+        let key = session.key.clone();
+        let existing = self.history(&key).await?;
+        let archive_key = rotation::archive_key(&key, chrono::Utc::now().date_naive(), &existing);
+
+        let fresh = policy.successor(&session);
+        let mut archived = session;
+        archived.key = archive_key.clone();
+        // Archive first: if the second save fails, the conversation is still
+        // intact under the archive key.
+        self.save(&archived).await?;
+        self.save(&fresh).await?;
+
+        info!(
+            session = %key,
+            archived_as = %archive_key,
+            messages = archived.messages.len(),
+            "Rotated long-running session"
+        );
+        Ok(fresh)
+    }
+
+    /// List archived generations of a session, oldest first.
+    ///
+    /// Generations are created by rotation and keyed `<key>@YYYY-MM-DD`
+    /// (with a `.N` suffix for repeated rotations on the same day). They can
+    /// be loaded with [`get`](Self::get) like any other session.
+    pub async fn history(&self, key: &str) -> Result<Vec<String>> {
+        let mut generations: Vec<_> = self
+            .list()
+            .await?
+            .into_iter()
+            .filter_map(|k| rotation::archive_generation(key, &k).map(|g| (g, k)))
+            .collect();
+        generations.sort();
+        Ok(generations.into_iter().map(|(_, k)| k).collect())
     }
 
     /// Get a session by key without creating it.
@@ -210,21 +442,232 @@ impl SessionManager {
         }
 
         // Try loading from disk if persistence is enabled
-        if let Some(ref storage_path) = self.storage_path {
-            let file_path = storage_path.join(format!("{}.json", Self::sanitize_key(key)));
-            if file_path.exists() {
-                let content = tokio::fs::read_to_string(&file_path).await?;
-                let mut session: Session = serde_json::from_str(&content)?;
-                self.maybe_repair_loaded_session(&mut session, "get");
+        if let Some(session) = self.read_from_disk(key, "get").await? {
+            // Cache it in memory
+            let mut sessions = self.sessions.write().await;
+            sessions.insert(key.to_string(), session.clone());
+            return Ok(Some(session));
+        }
 
-                // Cache it in memory
-                let mut sessions = self.sessions.write().await;
-                sessions.insert(key.to_string(), session.clone());
-                return Ok(Some(session));
+        Ok(None)
+    }
+
+    /// Run a closure against a session in place, without cloning it.
+    ///
+    /// The session is loaded into the cache from disk if needed, and the
+    /// closure runs under the cache read lock — keep it short and do not
+    /// call back into the manager from inside it.
+    ///
+    /// # Returns
+    ///
+    /// `Some(result)` if the session exists, `None` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if loading from disk fails.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::session::{SessionManager, Message};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let manager = SessionManager::new_memory();
+    ///     let mut session = manager.get_or_create("test").await.unwrap();
+    ///     session.add_message(Message::user("Hello"));
+    ///     manager.save(&session).await.unwrap();
+    ///
+    ///     let len = manager.with_session("test", |s| s.messages.len()).await.unwrap();
+    ///     assert_eq!(len, Some(1));
+    /// }
+    /// ```
+    pub async fn with_session<F, R>(&self, key: &str, f: F) -> Result<Option<R>>
+    where
+        F: FnOnce(&Session) -> R,
+    {
+        if !self.ensure_cached(key).await? {
+            return Ok(None);
+        }
+        let sessions = self.sessions.read().await;
+        Ok(sessions.get(key).map(f))
+    }
+
+    /// Mutate a session in place and persist the result.
+    ///
+    /// Creates the session if it does not exist (same semantics as
+    /// `get_or_create`). The closure runs under the cache write lock, so the
+    /// mutation is atomic with respect to other manager calls in this process.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if loading from or writing to disk fails.
+    pub async fn with_session_mut<F, R>(&self, key: &str, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut Session) -> R,
+    {
+        let _lock = self.lock_storage(LockMode::Exclusive).await?;
+        // Re-read under the lock so changes written by other processes since
+        // this one cached the session are not lost.
+        let on_disk = self.load_file(key, "with_session_mut").await?;
+        let (result, content) = {
+            let mut sessions = self.sessions.write().await;
+            if let Some(session) = on_disk {
+                sessions.insert(key.to_string(), session);
             }
+            let session = sessions
+                .entry(key.to_string())
+                .or_insert_with(|| self.new_session(key));
+            let result = f(session);
+            let content =
+                match self.session_file(key, self.codec) {
+                    Some(path) => Some(self.codec.encode(session).map_err(|e| {
+                        ZeptoError::session_io(SessionOp::Save, Some(key), &path, e)
+                    })?),
+                    None => None,
+                };
+            (result, content)
+        };
+
+        if let Some(content) = content {
+            self.write_file(key, self.codec, content).await?;
         }
 
-        Ok(None)
+        Ok(result)
+    }
+
+    /// Get the number of messages in a session without cloning it.
+    ///
+    /// Returns `0` for sessions that do not exist.
+    pub async fn message_count(&self, key: &str) -> Result<usize> {
+        Ok(self
+            .with_session(key, |s| s.messages.len())
+            .await?
+            .unwrap_or(0))
+    }
+
+    /// Get the last `n` messages of a session without cloning the full history.
+    ///
+    /// Returns an empty vector for sessions that do not exist.
+    pub async fn recent_messages(&self, key: &str, n: usize) -> Result<Vec<Message>> {
+        Ok(self
+            .with_session(key, |s| {
+                let start = s.messages.len().saturating_sub(n);
+                s.messages[start..].to_vec()
+            })
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// Ensure a session is in the in-memory cache, loading it from disk if
+    /// needed. Returns `false` if the session does not exist anywhere.
+    async fn ensure_cached(&self, key: &str) -> Result<bool> {
+        if self.sessions.read().await.contains_key(key) {
+            return Ok(true);
+        }
+
+        if let Some(session) = self.read_from_disk(key, "ensure_cached").await? {
+            let mut sessions = self.sessions.write().await;
+            sessions.entry(key.to_string()).or_insert(session);
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// Path of a session's file in `codec`'s format, if persistence is enabled.
+    fn session_file(&self, key: &str, codec: Codec) -> Option<PathBuf> {
+        self.storage_path
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.{}", Self::sanitize_key(key), codec.extension())))
+    }
+
+    /// Existing files for a session in any known format.
+    fn existing_files(&self, key: &str) -> Vec<PathBuf> {
+        let Some(ref dir) = self.storage_path else {
+            return Vec::new();
+        };
+        let stem = Self::sanitize_key(key);
+        codec::KNOWN_EXTENSIONS
+            .iter()
+            .map(|ext| dir.join(format!("{}.{}", stem, ext)))
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    /// The file to load a session from: the configured format if present,
+    /// otherwise any other known format.
+    fn find_file(&self, key: &str) -> Option<PathBuf> {
+        self.session_file(key, self.codec)
+            .filter(|path| path.exists())
+            .or_else(|| self.existing_files(key).into_iter().next())
+    }
+
+    /// Acquire the cross-process sessions directory lock.
+    ///
+    /// Returns `Ok(None)` for in-memory managers and on platforms without
+    /// file locking support.
+    async fn lock_storage(&self, mode: LockMode) -> Result<Option<DirLock>> {
+        match self.storage_path {
+            Some(ref dir) => lock::acquire(dir, mode, self.lock_timeout).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Load a session from disk under a shared directory lock.
+    async fn read_from_disk(&self, key: &str, source: &str) -> Result<Option<Session>> {
+        if self.find_file(key).is_none() {
+            return Ok(None);
+        }
+        let _lock = self.lock_storage(LockMode::Shared).await?;
+        self.load_file(key, source).await
+    }
+
+    /// Load a session file. Callers must already hold the directory lock.
+    async fn load_file(&self, key: &str, source: &str) -> Result<Option<Session>> {
+        let Some(file_path) = self.find_file(key) else {
+            return Ok(None);
+        };
+        let load_err = |e: codec::CodecError| {
+            ZeptoError::session_io(SessionOp::Load, Some(key), &file_path, e)
+        };
+        let content = tokio::fs::read(&file_path)
+            .await
+            .map_err(|e| load_err(e.into()))?;
+        let mut session = codec::decode_file(&file_path, &content).map_err(load_err)?;
+        self.maybe_repair_loaded_session(&mut session, source);
+        Ok(Some(session))
+    }
+
+    /// Write a session encoded with `codec`, removing copies in other
+    /// formats. Callers must already hold the exclusive lock.
+    async fn write_file(&self, key: &str, codec: Codec, content: Vec<u8>) -> Result<()> {
+        if let Some(file_path) = self.session_file(key, codec) {
+            // Write a temp file and rename it over the session file, so a
+            // process killed mid-write never leaves a half-written session.
+            let tmp_path = file_path.with_extension(format!("{}.tmp", codec.extension()));
+            let save_err = |e: std::io::Error| {
+                ZeptoError::session_io(SessionOp::Save, Some(key), &file_path, e)
+            };
+            tokio::fs::write(&tmp_path, content)
+                .await
+                .map_err(save_err)?;
+            if let Err(e) = tokio::fs::rename(&tmp_path, &file_path).await {
+                let _ = tokio::fs::remove_file(&tmp_path).await;
+                return Err(save_err(e));
+            }
+            for stale in self.existing_files(key) {
+                if stale != file_path {
+                    tokio::fs::remove_file(&stale).await.map_err(|e| {
+                        ZeptoError::session_io(SessionOp::Save, Some(key), &stale, e)
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn configured_lock_timeout() -> Duration {
+        Duration::from_millis(Config::get().session.lock_timeout_ms)
     }
 
     /// Save a session to both memory and disk (if persistence is enabled).
@@ -256,15 +699,87 @@ impl SessionManager {
         }
 
         // Write to disk if persistence is enabled
-        if let Some(ref storage_path) = self.storage_path {
-            let file_path = storage_path.join(format!("{}.json", Self::sanitize_key(&session.key)));
-            let content = serde_json::to_string_pretty(session)?;
-            tokio::fs::write(&file_path, content).await?;
+        if let Some(file_path) = self.session_file(&session.key, self.codec) {
+            let content = self.codec.encode(session).map_err(|e| {
+                ZeptoError::session_io(SessionOp::Save, Some(&session.key), &file_path, e)
+            })?;
+            let _lock = self.lock_storage(LockMode::Exclusive).await?;
+            self.write_file(&session.key, self.codec, content).await?;
         }
 
         Ok(())
     }
 
+    /// Save a session loaded with
+    /// [`get_or_create_from`](Self::get_or_create_from): its messages replace
+    /// the stored ones from `start` on, and the turn's own bookkeeping (usage,
+    /// turn count, tool iteration stats, recent message IDs, `updated_at`) is
+    /// taken from it. Everything else stays as stored, so tags, notes or settings
+    /// changed while the turn ran are kept.
+    ///
+    /// The stored session is re-read from disk under the lock, so an evicted
+    /// cache or another process's writes don't lose the messages before
+    /// `start`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ZeptoError::Session` if the stored session no longer has
+    /// `start` messages (it was reset, or is gone), rather than writing the
+    /// tail over it; or an error if writing to disk fails.
+    pub async fn save_from(&self, session: &Session, start: usize) -> Result<()> {
+        if start == 0 {
+            return self.save(session).await;
+        }
+
+        let key = session.key.as_str();
+        let _lock = self.lock_storage(LockMode::Exclusive).await?;
+        let on_disk = self.load_file(key, "save_from").await?;
+        let content =
+            {
+                let mut sessions = self.sessions.write().await;
+                if let Some(stored) = on_disk {
+                    sessions.insert(key.to_string(), stored);
+                }
+                let Some(stored) = sessions
+                    .get_mut(key)
+                    .filter(|stored| stored.messages.len() >= start)
+                else {
+                    return Err(ZeptoError::Session(format!(
+                        "Session '{}' no longer has the {} messages this turn continued from",
+                        key, start
+                    )));
+                };
+                stored.messages.truncate(start);
+                stored.messages.extend(session.messages.iter().cloned());
+                stored.updated_at = session.updated_at;
+                stored.recent_message_ids = session.recent_message_ids.clone();
+                stored.tool_iterations = session.tool_iterations.clone();
+                stored.usage = session.usage;
+                stored.turns = session.turns;
+                stored.onboarded |= session.onboarded;
+                match self.session_file(key, self.codec) {
+                    Some(path) => Some(self.codec.encode(stored).map_err(|e| {
+                        ZeptoError::session_io(SessionOp::Save, Some(key), &path, e)
+                    })?),
+                    None => None,
+                }
+            };
+
+        if let Some(content) = content {
+            self.write_file(key, self.codec, content).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Wait for session writes in progress, in this or another process, to
+    /// finish. Saves write through to disk, so once this returns every
+    /// session saved so far is stored in full.
+    pub async fn flush(&self) -> Result<()> {
+        let _lock = self.lock_storage(LockMode::Exclusive).await?;
+        Ok(())
+    }
+
     /// Delete a session from both memory and disk.
     ///
     /// # Arguments
@@ -292,17 +807,46 @@ impl SessionManager {
             sessions.remove(key);
         }
 
-        // Remove from disk if persistence is enabled
-        if let Some(ref storage_path) = self.storage_path {
-            let file_path = storage_path.join(format!("{}.json", Self::sanitize_key(key)));
-            if file_path.exists() {
-                tokio::fs::remove_file(&file_path).await?;
+        // Remove from disk (in every format) if persistence is enabled
+        if self.storage_path.is_some() {
+            let _lock = self.lock_storage(LockMode::Exclusive).await?;
+            for file_path in self.existing_files(key) {
+                tokio::fs::remove_file(&file_path).await.map_err(|e| {
+                    ZeptoError::session_io(SessionOp::Delete, Some(key), &file_path, e)
+                })?;
             }
         }
 
         Ok(())
     }
 
+    /// Rewrite a stored session in another format.
+    ///
+    /// Use this to migrate a sessions directory one session at a time. The
+    /// session is read in whatever format it is currently stored in, and the
+    /// old file is removed once the new one is written. Note that later saves
+    /// through a manager configured with a different codec rewrite the file
+    /// in that manager's format.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ZeptoError::NotFound` if the session has no file on disk, or
+    /// an error if reading, encoding, or writing fails.
+    pub async fn convert(&self, key: &str, codec: Codec) -> Result<()> {
+        let _lock = self.lock_storage(LockMode::Exclusive).await?;
+        let session = self
+            .load_file(key, "convert")
+            .await?
+            .ok_or_else(|| ZeptoError::NotFound(format!("session '{}'", key)))?;
+        if let Some(file_path) = self.session_file(key, codec) {
+            let content = codec
+                .encode(&session)
+                .map_err(|e| ZeptoError::session_io(SessionOp::Save, Some(key), &file_path, e))?;
+            self.write_file(key, codec, content).await?;
+        }
+        Ok(())
+    }
+
     /// List all session keys.
     ///
     /// Returns session keys from both memory and disk (if persistence is enabled).
@@ -338,13 +882,16 @@ impl SessionManager {
         // Get keys from disk if persistence is enabled
         // We read each session file to get the actual key (not the sanitized filename)
         if let Some(ref storage_path) = self.storage_path {
-            let mut dir_entries = tokio::fs::read_dir(storage_path).await?;
-            while let Some(entry) = dir_entries.next_entry().await? {
+            let _lock = self.lock_storage(LockMode::Shared).await?;
+            let list_err =
+                |e: std::io::Error| ZeptoError::session_io(SessionOp::List, None, storage_path, e);
+            let mut dir_entries = tokio::fs::read_dir(storage_path).await.map_err(list_err)?;
+            while let Some(entry) = dir_entries.next_entry().await.map_err(list_err)? {
                 let path = entry.path();
-                if path.extension().map(|e| e == "json").unwrap_or(false) {
+                if codec::is_session_file(&path) {
                     // Read the session file to get the actual key
-                    if let Ok(content) = tokio::fs::read_to_string(&path).await {
-                        if let Ok(session) = serde_json::from_str::<Session>(&content) {
+                    if let Ok(content) = tokio::fs::read(&path).await {
+                        if let Ok(session) = codec::decode_file(&path, &content) {
                             if !keys.contains(&session.key) {
                                 keys.push(session.key);
                             }
@@ -376,12 +923,222 @@ impl SessionManager {
         }
 
         // Check disk
-        if let Some(ref storage_path) = self.storage_path {
-            let file_path = storage_path.join(format!("{}.json", Self::sanitize_key(key)));
-            return file_path.exists();
+        self.find_file(key).is_some()
+    }
+
+    /// Pin a session so cleanup routines never remove it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ZeptoError::NotFound` if the session does not exist, or an
+    /// error if persisting the updated session fails.
+    pub async fn pin(&self, key: &str) -> Result<()> {
+        self.set_pinned(key, true).await
+    }
+
+    /// Remove the pin from a session, making it eligible for cleanup again.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ZeptoError::NotFound` if the session does not exist, or an
+    /// error if persisting the updated session fails.
+    pub async fn unpin(&self, key: &str) -> Result<()> {
+        self.set_pinned(key, false).await
+    }
+
+    /// Check whether a session is pinned.
+    ///
+    /// Returns `false` for sessions that do not exist.
+    pub async fn is_pinned(&self, key: &str) -> Result<bool> {
+        Ok(self.get(key).await?.is_some_and(|s| s.pinned))
+    }
+
+    /// Delete unpinned sessions that have been idle longer than their
+    /// `ttl_secs` (see [`options_for`](Self::options_for)).
+    ///
+    /// Intended for periodic maintenance. Returns the deleted keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if listing, loading, or deleting sessions fails.
+    pub async fn expire_idle(&self) -> Result<Vec<String>> {
+        let now = chrono::Utc::now();
+        let mut expired = Vec::new();
+        for key in self.list().await? {
+            let stale = self
+                .with_session(&key, |s| {
+                    let ttl = s
+                        .options
+                        .as_ref()
+                        .map_or_else(|| self.default_options_for(&s.key), Clone::clone)
+                        .ttl_secs;
+                    ttl.is_some_and(|ttl| {
+                        !s.pinned
+                            && (now - s.updated_at).num_seconds()
+                                >= i64::try_from(ttl).unwrap_or(i64::MAX)
+                    })
+                })
+                .await?
+                .unwrap_or(false);
+            if stale {
+                self.delete(&key).await?;
+                expired.push(key);
+            }
         }
+        if !expired.is_empty() {
+            info!(count = expired.len(), "Expired idle sessions");
+        }
+        Ok(expired)
+    }
+
+    /// Start a background task that runs [`expire_idle`](Self::expire_idle)
+    /// every `interval` until `shutdown_rx` signals `true`.
+    pub fn start_expiry_task(
+        &self,
+        interval: Duration,
+        mut shutdown_rx: tokio::sync::watch::Receiver<bool>,
+    ) -> tokio::task::JoinHandle<()> {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {
+                        if let Err(e) = manager.expire_idle().await {
+                            warn!(error = %e, "Session expiry failed");
+                        }
+                    }
+                    _ = shutdown_rx.changed() => {
+                        if *shutdown_rx.borrow() {
+                            break;
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    async fn set_pinned(&self, key: &str, pinned: bool) -> Result<()> {
+        let mut session = self
+            .get(key)
+            .await?
+            .ok_or_else(|| ZeptoError::NotFound(format!("session '{}'", key)))?;
+        if session.pinned == pinned {
+            return Ok(());
+        }
+        session.pinned = pinned;
+        self.save(&session).await
+    }
+
+    /// Add a tag to a session and persist it.
+    ///
+    /// Returns `true` if the tag was newly added.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ZeptoError::NotFound` if the session does not exist,
+    /// `ZeptoError::Session` if the tag is invalid (see [`normalize_tag`]), or
+    /// an error if persisting the updated session fails.
+    pub async fn add_tag(&self, key: &str, tag: &str) -> Result<bool> {
+        let tag = normalize_tag(tag)
+            .ok_or_else(|| ZeptoError::Session(format!("Invalid tag '{}'", tag)))?;
+        if !self.exists(key).await {
+            return Err(ZeptoError::NotFound(format!("session '{}'", key)));
+        }
+        self.with_session_mut(key, |session| session.add_tag(&tag))
+            .await
+    }
+
+    /// Remove a tag from a session and persist it.
+    ///
+    /// Returns `true` if the tag was present.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ZeptoError::NotFound` if the session does not exist, or an
+    /// error if persisting the updated session fails.
+    pub async fn remove_tag(&self, key: &str, tag: &str) -> Result<bool> {
+        if !self.exists(key).await {
+            return Err(ZeptoError::NotFound(format!("session '{}'", key)));
+        }
+        self.with_session_mut(key, |session| session.remove_tag(tag))
+            .await
+    }
 
-        false
+    /// List the keys of all sessions carrying `tag`, sorted.
+    ///
+    /// Tag matching is case-insensitive. Invalid tags match nothing.
+    pub async fn find_by_tag(&self, tag: &str) -> Result<Vec<String>> {
+        let Some(tag) = normalize_tag(tag) else {
+            return Ok(Vec::new());
+        };
+        let mut matches = Vec::new();
+        for key in self.list().await? {
+            if self.with_session(&key, |s| s.tags.contains(&tag)).await? == Some(true) {
+                matches.push(key);
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Render a session as a standalone HTML page for sharing.
+    ///
+    /// See [`html::render`] for the layout.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ZeptoError::NotFound` if the session does not exist.
+    pub async fn export_html(&self, key: &str, options: &HtmlExportOptions) -> Result<String> {
+        let session = self
+            .get(key)
+            .await?
+            .ok_or_else(|| ZeptoError::NotFound(format!("session '{}'", key)))?;
+        Ok(html::render(&session, options))
+    }
+
+    /// Render a session as a Markdown transcript (see [`markdown::render`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ZeptoError::NotFound` if the session does not exist.
+    pub async fn export_markdown(&self, key: &str) -> Result<String> {
+        let session = self
+            .get(key)
+            .await?
+            .ok_or_else(|| ZeptoError::NotFound(format!("session '{}'", key)))?;
+        Ok(markdown::render(&session))
+    }
+
+    /// Upload a session to LangSmith as a single trace.
+    ///
+    /// Each conversation turn becomes a child run (inputs: the user message;
+    /// outputs: the final assistant reply plus tool calls/results) under a
+    /// root run for the session. See [`langsmith::LangSmithExporter`].
+    ///
+    /// # Arguments
+    /// * `key` - Session to export
+    /// * `project` - LangSmith project (session) name
+    /// * `exporter` - Client for the LangSmith API
+    ///
+    /// # Returns
+    ///
+    /// The trace ID of the upload.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ZeptoError::NotFound` if the session does not exist, or an
+    /// error if the HTTP request fails or LangSmith rejects the batch.
+    pub async fn export_to_langsmith(
+        &self,
+        key: &str,
+        project: &str,
+        exporter: &langsmith::LangSmithExporter,
+    ) -> Result<String> {
+        let session = self
+            .get(key)
+            .await?
+            .ok_or_else(|| ZeptoError::NotFound(format!("session '{}'", key)))?;
+        exporter.export(&session, project).await
     }
 
     /// Clear all sessions from memory (does not affect disk).
@@ -494,6 +1251,11 @@ impl Clone for SessionManager {
         Self {
             sessions: Arc::clone(&self.sessions),
             storage_path: self.storage_path.clone(),
+            lock_timeout: self.lock_timeout,
+            rotation: self.rotation.clone(),
+            channel_defaults: self.channel_defaults.clone(),
+            codec: self.codec,
+            command_log: Arc::clone(&self.command_log),
         }
     }
 }
@@ -631,6 +1393,36 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_save_replaces_file_without_leftovers() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_path(temp_dir.path().to_path_buf()).unwrap();
+        let mut session = manager.get_or_create("atomic").await.unwrap();
+        for message in [Message::user("first"), Message::assistant("second")] {
+            session.add_message(message);
+            manager.save(&session).await.unwrap();
+        }
+        manager.flush().await.unwrap();
+
+        let files: Vec<String> = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert!(files.contains(&"atomic.json".to_string()), "{:?}", files);
+        assert!(
+            !files.iter().any(|name| name.ends_with(".tmp")),
+            "{:?}",
+            files
+        );
+        let reloaded = SessionManager::with_path(temp_dir.path().to_path_buf())
+            .unwrap()
+            .get("atomic")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(reloaded.messages.len(), 2);
+    }
+
     #[tokio::test]
     async fn test_file_persistence_delete() {
         let temp_dir = TempDir::new().unwrap();
@@ -826,10 +1618,469 @@ mod tests {
         assert_eq!(loaded.messages[4].role, Role::Assistant);
     }
 
+    #[tokio::test]
+    async fn test_pin_and_unpin_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_path(temp_dir.path().to_path_buf()).unwrap();
+        let session = manager.get_or_create("telegram:pin").await.unwrap();
+        manager.save(&session).await.unwrap();
+
+        manager.pin("telegram:pin").await.unwrap();
+        assert!(manager.is_pinned("telegram:pin").await.unwrap());
+
+        // Pin survives a cache flush (persisted to disk)
+        manager.clear_cache().await;
+        assert!(manager.is_pinned("telegram:pin").await.unwrap());
+
+        manager.unpin("telegram:pin").await.unwrap();
+        assert!(!manager.is_pinned("telegram:pin").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_tags_persist_and_find_by_tag() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().to_path_buf();
+        {
+            let manager = SessionManager::with_path(path.clone()).unwrap();
+            for key in ["slack:a", "slack:b", "slack:c"] {
+                let s = manager.get_or_create(key).await.unwrap();
+                manager.save(&s).await.unwrap();
+            }
+
+            assert!(manager.add_tag("slack:a", "Support").await.unwrap());
+            assert!(!manager.add_tag("slack:a", "support").await.unwrap());
+            assert!(manager.add_tag("slack:c", "support").await.unwrap());
+            assert!(manager.add_tag("slack:b", "dev").await.unwrap());
+            assert!(manager.add_tag("slack:b", "bad tag").await.is_err());
+        }
+
+        // Fresh manager reads tags back from disk
+        let manager = SessionManager::with_path(path).unwrap();
+        assert_eq!(
+            manager.find_by_tag("SUPPORT").await.unwrap(),
+            vec!["slack:a".to_string(), "slack:c".to_string()]
+        );
+        assert!(manager.remove_tag("slack:a", "support").await.unwrap());
+        assert_eq!(
+            manager.find_by_tag("support").await.unwrap(),
+            vec!["slack:c".to_string()]
+        );
+        assert!(manager.find_by_tag("missing").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_or_create_rotates_long_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_path(temp_dir.path().to_path_buf())
+            .unwrap()
+            .with_rotation(RotationPolicy {
+                max_messages: Some(4),
+                carry_over_messages: 2,
+                ..Default::default()
+            });
+
+        for round in 0..2 {
+            let mut session = manager.get_or_create("group:1").await.unwrap();
+            session.add_message(Message::user(&format!("q{}", round)));
+            session.add_message(Message::assistant(&format!("a{}", round)));
+            manager.save(&session).await.unwrap();
+        }
+        assert!(manager.history("group:1").await.unwrap().is_empty());
+
+        // Threshold reached: the next lookup rotates transparently.
+        let fresh = manager.get_or_create("group:1").await.unwrap();
+        assert_eq!(fresh.key, "group:1");
+        let carried: Vec<&str> = fresh.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(carried, vec!["q1", "a1"]);
+
+        let history = manager.history("group:1").await.unwrap();
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        assert_eq!(history, vec![format!("group:1@{}", today)]);
+        let archived = manager.get(&history[0]).await.unwrap().unwrap();
+        assert_eq!(archived.messages.len(), 4);
+
+        // The rotated generation survives a restart.
+        let reloaded = SessionManager::with_path(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(reloaded.history("group:1").await.unwrap(), history);
+    }
+
+    #[tokio::test]
+    async fn test_tag_missing_session_errors() {
+        let manager = SessionManager::new_memory();
+        let err = manager.add_tag("nope", "dev").await.unwrap_err();
+        assert!(matches!(err, ZeptoError::NotFound(_)));
+        assert!(!manager.exists("nope").await);
+    }
+
+    #[tokio::test]
+    async fn test_pin_missing_session_errors() {
+        let manager = SessionManager::new_memory();
+        let err = manager.pin("missing").await.unwrap_err();
+        assert!(matches!(err, ZeptoError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_convert_missing_session_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_path(temp_dir.path().to_path_buf()).unwrap();
+        let err = manager.convert("missing", Codec::Json).await.unwrap_err();
+        assert!(matches!(err, ZeptoError::NotFound(_)));
+    }
+
+    #[cfg(feature = "session-msgpack")]
+    #[tokio::test]
+    async fn test_mixed_formats_load_and_convert() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().to_path_buf();
+        let packed = SessionManager::with_path(path.clone())
+            .unwrap()
+            .with_codec(Codec::MessagePack);
+        let mut session = packed.get_or_create("telegram:42").await.unwrap();
+        session.add_message(Message::user("hello"));
+        packed.save(&session).await.unwrap();
+        assert!(path.join("telegram%3A42.msgpack").exists());
+
+        // A JSON manager reads the MessagePack file and lists it.
+        let json = SessionManager::with_path(path.clone()).unwrap();
+        let loaded = json.get("telegram:42").await.unwrap().unwrap();
+        assert_eq!(loaded.messages.len(), 1);
+        assert_eq!(json.list().await.unwrap(), vec!["telegram:42".to_string()]);
+
+        json.convert("telegram:42", Codec::Json).await.unwrap();
+        assert!(path.join("telegram%3A42.json").exists());
+        assert!(!path.join("telegram%3A42.msgpack").exists());
+    }
+
+    fn telegram_defaults(options: SessionOptions) -> HashMap<String, SessionOptions> {
+        HashMap::from([("telegram".to_string(), options)])
+    }
+
+    #[tokio::test]
+    async fn test_channel_defaults_are_stored_on_new_sessions() {
+        let options = SessionOptions {
+            max_messages: Some(20),
+            pinned_by_default: true,
+            ..Default::default()
+        };
+        let manager =
+            SessionManager::new_memory().with_channel_defaults(telegram_defaults(options.clone()));
+
+        let session = manager.get_or_create("telegram:1").await.unwrap();
+        assert!(session.pinned);
+        assert_eq!(session.options.as_ref(), Some(&options));
+        let other = manager.get_or_create("cli:1").await.unwrap();
+        assert!(!other.pinned);
+        assert_eq!(other.options, Some(SessionOptions::default()));
+
+        // Existing sessions keep what they were created with.
+        let changed = manager.clone().with_channel_defaults(HashMap::new());
+        assert_eq!(changed.options_for("telegram:1").await.unwrap(), options);
+        assert_eq!(
+            changed.options_for("telegram:2").await.unwrap(),
+            SessionOptions::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expire_idle_skips_pinned_and_fresh_sessions() {
+        let manager =
+            SessionManager::new_memory().with_channel_defaults(telegram_defaults(SessionOptions {
+                ttl_secs: Some(60),
+                ..Default::default()
+            }));
+        for key in [
+            "telegram:old",
+            "telegram:pinned",
+            "telegram:fresh",
+            "cli:old",
+        ] {
+            let mut session = manager.get_or_create(key).await.unwrap();
+            if key != "telegram:fresh" {
+                session.updated_at = chrono::Utc::now() - chrono::Duration::hours(1);
+            }
+            session.pinned = key == "telegram:pinned";
+            manager.save(&session).await.unwrap();
+        }
+
+        let expired = manager.expire_idle().await.unwrap();
+        assert_eq!(expired, vec!["telegram:old".to_string()]);
+        assert!(!manager.exists("telegram:old").await);
+        assert!(manager.exists("cli:old").await);
+    }
+
+    #[tokio::test]
+    async fn test_export_to_langsmith_missing_session() {
+        let manager = SessionManager::new_memory();
+        let err = manager
+            .export_to_langsmith(
+                "missing",
+                "proj",
+                &langsmith::LangSmithExporter::new("key").unwrap(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ZeptoError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_with_session_reads_in_place() {
+        let manager = SessionManager::new_memory();
+        assert!(manager
+            .with_session("missing", |s| s.messages.len())
+            .await
+            .unwrap()
+            .is_none());
+
+        let mut session = manager.get_or_create("s").await.unwrap();
+        session.add_message(Message::user("one"));
+        session.add_message(Message::assistant("two"));
+        session.add_message(Message::user("three"));
+        manager.save(&session).await.unwrap();
+
+        assert_eq!(manager.message_count("s").await.unwrap(), 3);
+        assert_eq!(manager.message_count("missing").await.unwrap(), 0);
+
+        let recent = manager.recent_messages("s", 2).await.unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].content, "two");
+        assert_eq!(recent[1].content, "three");
+        assert_eq!(manager.recent_messages("s", 10).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_get_or_create_from_copies_only_the_tail() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_path(temp_dir.path().to_path_buf()).unwrap();
+        let mut session = manager.get_or_create("s").await.unwrap();
+        for i in 0..6 {
+            let text = format!("m{}", i);
+            session.add_message(if i % 2 == 0 {
+                Message::user(&text)
+            } else {
+                Message::assistant(&text)
+            });
+        }
+        session.pinned = true;
+        manager.save(&session).await.unwrap();
+
+        let (mut tail, start) = manager
+            .get_or_create_from("s", |s| s.messages.len() - 2)
+            .await
+            .unwrap();
+        assert_eq!(start, 4);
+        assert!(tail.pinned);
+        let contents: Vec<_> = tail.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["m4", "m5"]);
+
+        // A command changes the stored session while the turn runs.
+        manager
+            .with_session_mut("s", |s| {
+                s.pinned = false;
+                s.tags.insert("vip".to_string());
+            })
+            .await
+            .unwrap();
+        tail.add_message(Message::user("m6"));
+        tail.usage.input_tokens = 42;
+        manager.save_from(&tail, start).await.unwrap();
+
+        // The messages left in place come back in front, in memory and on
+        // disk, and the command's changes are not overwritten by the turn.
+        let fresh = SessionManager::with_path(temp_dir.path().to_path_buf()).unwrap();
+        for manager in [&manager, &fresh] {
+            let stored = manager.get("s").await.unwrap().unwrap();
+            let contents: Vec<_> = stored.messages.iter().map(|m| m.content.as_str()).collect();
+            assert_eq!(contents, ["m0", "m1", "m2", "m3", "m4", "m5", "m6"]);
+            assert!(!stored.pinned);
+            assert!(stored.tags.contains("vip"));
+            assert_eq!(stored.usage.input_tokens, 42);
+        }
+
+        // An index past the end copies no messages.
+        let (tail, start) = manager.get_or_create_from("s", |_| 100).await.unwrap();
+        assert!(tail.messages.is_empty());
+        assert_eq!(start, 7);
+    }
+
+    #[tokio::test]
+    async fn test_save_from_reads_the_prefix_from_disk_after_eviction() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_path(temp_dir.path().to_path_buf()).unwrap();
+        let mut session = manager.get_or_create("s").await.unwrap();
+        session.add_message(Message::user("m0"));
+        session.add_message(Message::assistant("m1"));
+        manager.save(&session).await.unwrap();
+
+        let (mut tail, start) = manager.get_or_create_from("s", |_| 1).await.unwrap();
+        manager.clear_cache().await;
+        tail.add_message(Message::user("m2"));
+        manager.save_from(&tail, start).await.unwrap();
+
+        let stored = manager.get("s").await.unwrap().unwrap();
+        let contents: Vec<_> = stored.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["m0", "m1", "m2"]);
+    }
+
+    #[tokio::test]
+    async fn test_save_from_refuses_a_shortened_history() {
+        let manager = SessionManager::new_memory();
+        let mut session = manager.get_or_create("s").await.unwrap();
+        session.add_message(Message::user("m0"));
+        session.add_message(Message::assistant("m1"));
+        manager.save(&session).await.unwrap();
+
+        let (mut tail, start) = manager.get_or_create_from("s", |_| 1).await.unwrap();
+        // The conversation is reset while the turn runs.
+        manager
+            .with_session_mut("s", |s| s.messages.clear())
+            .await
+            .unwrap();
+        tail.add_message(Message::user("m2"));
+        let err = manager.save_from(&tail, start).await.unwrap_err();
+        assert!(matches!(err, ZeptoError::Session(_)), "{err}");
+        assert_eq!(manager.message_count("s").await.unwrap(), 0);
+
+        // An evicted memory-only session has no prefix to keep either.
+        manager.clear_cache().await;
+        assert!(manager.save_from(&tail, start).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_with_session_mut_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage_path = temp_dir.path().to_path_buf();
+
+        {
+            let manager = SessionManager::with_path(storage_path.clone()).unwrap();
+            let len = manager
+                .with_session_mut("telegram:mut", |s| {
+                    s.add_message(Message::user("Hello"));
+                    s.messages.len()
+                })
+                .await
+                .unwrap();
+            assert_eq!(len, 1);
+        }
+
+        // A fresh manager reads the mutation back from disk
+        let manager = SessionManager::with_path(storage_path).unwrap();
+        assert_eq!(manager.message_count("telegram:mut").await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_save_fails_with_locked_when_directory_lock_held() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = SessionManager::with_path(temp_dir.path().to_path_buf())
+            .unwrap()
+            .with_lock_timeout(Duration::from_millis(50));
+
+        let held = lock::acquire(temp_dir.path(), LockMode::Exclusive, Duration::from_secs(1))
+            .await
+            .unwrap();
+        if held.is_none() {
+            return; // locking unsupported on this platform
+        }
+
+        let session = Session::new("locked");
+        let err = manager.save(&session).await.unwrap_err();
+        assert!(matches!(err, ZeptoError::Locked(_)));
+
+        drop(held);
+        manager.save(&session).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_load_error_names_key_and_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("telegram%3A42.json");
+        std::fs::write(&file, "not json").unwrap();
+        let manager = SessionManager::with_path(temp_dir.path().to_path_buf()).unwrap();
+
+        let err = manager.get("telegram:42").await.unwrap_err();
+        assert!(matches!(
+            err,
+            ZeptoError::SessionIo {
+                op: SessionOp::Load,
+                ..
+            }
+        ));
+        let message = err.to_string();
+        assert!(message.contains("'telegram:42'"), "{}", message);
+        assert!(message.contains(&file.display().to_string()), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn test_save_error_names_key_and_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("blocked.json");
+        // A directory where the session file should be makes the write fail.
+        std::fs::create_dir(&file).unwrap();
+        let manager = SessionManager::with_path(temp_dir.path().to_path_buf()).unwrap();
+
+        let err = manager.save(&Session::new("blocked")).await.unwrap_err();
+        let message = err.to_string();
+        assert!(
+            message.starts_with("Session save failed for 'blocked'"),
+            "{}",
+            message
+        );
+        assert!(message.contains(&file.display().to_string()), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn test_with_session_mut_merges_writes_from_other_manager() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().to_path_buf();
+        // Two managers stand in for two processes sharing a directory.
+        let a = SessionManager::with_path(path.clone()).unwrap();
+        let b = SessionManager::with_path(path).unwrap();
+
+        a.with_session_mut("shared", |s| s.add_message(Message::user("from a")))
+            .await
+            .unwrap();
+        // b caches the session, then a appends again behind b's back
+        assert_eq!(b.message_count("shared").await.unwrap(), 1);
+        a.with_session_mut("shared", |s| {
+            s.add_message(Message::assistant("from a again"))
+        })
+        .await
+        .unwrap();
+
+        let count = b
+            .with_session_mut("shared", |s| {
+                s.add_message(Message::user("from b"));
+                s.messages.len()
+            })
+            .await
+            .unwrap();
+        assert_eq!(count, 3);
+    }
+
     #[tokio::test]
     async fn test_session_default() {
         let manager = SessionManager::default();
         let session = manager.get_or_create("test").await.unwrap();
         assert!(session.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_export_html() {
+        let manager = SessionManager::new_memory();
+        let mut session = manager.get_or_create("telegram:1").await.unwrap();
+        session.add_message(Message::user("hello <world>"));
+        manager.save(&session).await.unwrap();
+
+        let html = manager
+            .export_html("telegram:1", &HtmlExportOptions::default())
+            .await
+            .unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("hello &lt;world&gt;"));
+
+        let err = manager
+            .export_html("missing", &HtmlExportOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ZeptoError::NotFound(_)));
+    }
 }
@@ -0,0 +1,270 @@
+//! Rotation of very long-running sessions.
+//!
+//! Group chats can run for months under a single session key. When a
+//! [`RotationPolicy`] is configured, [`SessionManager::get_or_create`]
+//! archives a session that crossed the message-count or age threshold as
+//! `<key>@<YYYY-MM-DD>` and returns a fresh session under the original key,
+//! optionally seeded with the old summary and the last few messages.
+//! Channels keep calling `get_or_create` and never see the rotation.
+//!
+//! [`SessionManager::get_or_create`]: super::SessionManager::get_or_create
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use super::{Role, Session};
+use crate::config::SessionConfig;
+
+/// Separator between a session key and its archive date.
+pub const ARCHIVE_SEPARATOR: char = '@';
+
+/// When to rotate a session and what to carry into the fresh one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RotationPolicy {
+    /// Rotate once the session holds at least this many messages.
+    pub max_messages: Option<usize>,
+    /// Rotate once the session is at least this old.
+    pub max_age: Option<Duration>,
+    /// Number of trailing messages to copy into the fresh session. The copy
+    /// starts at a user message so no orphaned tool results are carried.
+    pub carry_over_messages: usize,
+    /// Copy the old session's summary into the fresh session.
+    pub carry_over_summary: bool,
+}
+
+impl Default for RotationPolicy {
+    fn default() -> Self {
+        Self {
+            max_messages: None,
+            max_age: None,
+            carry_over_messages: 0,
+            carry_over_summary: true,
+        }
+    }
+}
+
+impl RotationPolicy {
+    /// Build a policy from config. Returns `None` when both thresholds are
+    /// disabled (zero). The carry-over is clamped below `max_messages`, so a
+    /// fresh session never starts over the threshold and rotates again.
+    pub fn from_config(config: &SessionConfig) -> Option<Self> {
+        let max_messages = (config.rotate_max_messages > 0).then_some(config.rotate_max_messages);
+        let max_age = (config.rotate_max_age_days > 0)
+            .then(|| Duration::days(config.rotate_max_age_days as i64));
+        if max_messages.is_none() && max_age.is_none() {
+            return None;
+        }
+        Some(Self {
+            max_messages,
+            max_age,
+            carry_over_messages: max_messages.map_or(config.rotate_carry_over_messages, |max| {
+                config.rotate_carry_over_messages.min(max - 1)
+            }),
+            carry_over_summary: config.rotate_carry_over_summary,
+        })
+    }
+
+    /// Whether `session` has crossed a rotation threshold at `now`.
+    /// Empty sessions are never rotated.
+    pub fn should_rotate(&self, session: &Session, now: DateTime<Utc>) -> bool {
+        if session.messages.is_empty() {
+            return false;
+        }
+        let too_long = self
+            .max_messages
+            .is_some_and(|max| session.messages.len() >= max);
+        let too_old = self
+            .max_age
+            .is_some_and(|max| now - session.created_at >= max);
+        too_long || too_old
+    }
+
+    /// Build the fresh session that replaces `old` under the same key.
+    ///
+    /// Pin state, tags, memory notes, generation settings and whether the
+    /// welcome was sent are kept: they describe the conversation, not a
+    /// single generation of it.
+    pub fn successor(&self, old: &Session) -> Session {
+        let mut fresh = Session::new(&old.key);
+        fresh.pinned = old.pinned;
+        fresh.tags = old.tags.clone();
+        fresh.notes = old.notes.clone();
+        fresh.settings = old.settings.clone();
+        fresh.options = old.options.clone();
+        fresh.onboarded = old.onboarded;
+        if self.carry_over_summary {
+            fresh.summary = old.summary.clone();
+        }
+        if self.carry_over_messages > 0 {
+            let from = old.messages.len().saturating_sub(self.carry_over_messages);
+            if let Some(start) = old.messages[from..]
+                .iter()
+                .position(|m| m.role == Role::User)
+            {
+                fresh.messages = old.messages[from + start..].to_vec();
+            }
+        }
+        fresh
+    }
+}
+
+/// Parse the generation of an archive key belonging to `key`.
+///
+/// `<key>@2024-06-01` is generation `(2024-06-01, 1)`; a second rotation on
+/// the same day is `<key>@2024-06-01.2`. Returns `None` for keys that are not
+/// archives of `key` (including unrelated keys that merely share a prefix,
+/// such as `email:bob@example.com` for `email:bob`).
+pub fn archive_generation(key: &str, candidate: &str) -> Option<(NaiveDate, u32)> {
+    let rest = candidate
+        .strip_prefix(key)?
+        .strip_prefix(ARCHIVE_SEPARATOR)?;
+    let (date, n) = match rest.split_once('.') {
+        Some((date, n)) => (date, n.parse::<u32>().ok().filter(|n| *n >= 2)?),
+        None => (rest, 1),
+    };
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    Some((date, n))
+}
+
+/// Choose the archive key for rotating `key` on `date`, given the keys of
+/// generations that already exist.
+pub fn archive_key(key: &str, date: NaiveDate, existing: &[String]) -> String {
+    let base = format!("{}{}{}", key, ARCHIVE_SEPARATOR, date.format("%Y-%m-%d"));
+    let taken = existing
+        .iter()
+        .filter_map(|k| archive_generation(key, k))
+        .filter(|(d, _)| *d == date)
+        .map(|(_, n)| n)
+        .max();
+    match taken {
+        None => base,
+        Some(n) => format!("{}.{}", base, n + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::Message;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_should_rotate_thresholds() {
+        let policy = RotationPolicy {
+            max_messages: Some(3),
+            max_age: Some(Duration::days(30)),
+            ..Default::default()
+        };
+        let mut session = Session::new("k");
+        let now = Utc::now();
+        assert!(!policy.should_rotate(&session, now + Duration::days(60)));
+
+        session.add_message(Message::user("a"));
+        assert!(!policy.should_rotate(&session, now));
+        assert!(policy.should_rotate(&session, now + Duration::days(31)));
+
+        session.add_message(Message::assistant("b"));
+        session.add_message(Message::user("c"));
+        assert!(policy.should_rotate(&session, now));
+    }
+
+    #[test]
+    fn test_successor_carries_tail_from_user_boundary() {
+        let policy = RotationPolicy {
+            carry_over_messages: 3,
+            ..Default::default()
+        };
+        let mut old = Session::new("group:1");
+        old.pinned = true;
+        old.onboarded = true;
+        old.add_tag("vip");
+        old.set_summary("earlier stuff");
+        old.add_message(Message::user("q1"));
+        old.add_message(Message::assistant("a1"));
+        old.add_message(Message::user("q2"));
+        old.add_message(Message::assistant("a2"));
+
+        let fresh = policy.successor(&old);
+        assert_eq!(fresh.key, "group:1");
+        assert!(fresh.pinned);
+        assert!(fresh.onboarded);
+        assert!(fresh.has_tag("vip"));
+        assert_eq!(fresh.summary.as_deref(), Some("earlier stuff"));
+        // Tail of 3 would start at "a1"; carry from the next user message.
+        let contents: Vec<&str> = fresh.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["q2", "a2"]);
+    }
+
+    #[test]
+    fn test_archive_generation_parsing() {
+        assert_eq!(
+            archive_generation("email:bob", "email:bob@2024-06-01"),
+            Some((date("2024-06-01"), 1))
+        );
+        assert_eq!(
+            archive_generation("email:bob", "email:bob@2024-06-01.3"),
+            Some((date("2024-06-01"), 3))
+        );
+        assert_eq!(
+            archive_generation("email:bob", "email:bob@example.com"),
+            None
+        );
+        assert_eq!(archive_generation("email:bob", "email:bob"), None);
+        assert_eq!(
+            archive_generation("email:bob", "email:bob@2024-06-01.1"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_archive_key_avoids_collisions() {
+        let d = date("2024-06-01");
+        assert_eq!(archive_key("k", d, &[]), "k@2024-06-01");
+        let existing = vec!["k@2024-05-01".to_string(), "k@2024-06-01".to_string()];
+        assert_eq!(archive_key("k", d, &existing), "k@2024-06-01.2");
+        let existing = vec!["k@2024-06-01".to_string(), "k@2024-06-01.2".to_string()];
+        assert_eq!(archive_key("k", d, &existing), "k@2024-06-01.3");
+    }
+
+    #[test]
+    fn test_from_config_disabled_by_default() {
+        assert!(RotationPolicy::from_config(&SessionConfig::default()).is_none());
+        let config = SessionConfig {
+            rotate_max_messages: 500,
+            ..Default::default()
+        };
+        let policy = RotationPolicy::from_config(&config).unwrap();
+        assert_eq!(policy.max_messages, Some(500));
+        assert_eq!(policy.max_age, None);
+    }
+
+    #[test]
+    fn test_from_config_clamps_carry_over_below_max_messages() {
+        let config = SessionConfig {
+            rotate_max_messages: 10,
+            rotate_carry_over_messages: 25,
+            ..Default::default()
+        };
+        let policy = RotationPolicy::from_config(&config).unwrap();
+        assert_eq!(policy.carry_over_messages, 9);
+
+        let mut old = Session::new("telegram:1");
+        for i in 0..10 {
+            old.add_message(Message::user(&format!("m{i}")));
+        }
+        assert!(policy.should_rotate(&old, Utc::now()));
+        let fresh = policy.successor(&old);
+        assert_eq!(fresh.messages.len(), 9);
+        assert!(!policy.should_rotate(&fresh, Utc::now()));
+
+        let config = SessionConfig {
+            rotate_max_age_days: 30,
+            rotate_carry_over_messages: 25,
+            ..Default::default()
+        };
+        let policy = RotationPolicy::from_config(&config).unwrap();
+        assert_eq!(policy.carry_over_messages, 25);
+    }
+}
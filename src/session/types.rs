@@ -3,9 +3,151 @@
 //! This module defines the core types for session and conversation management,
 //! including messages, roles, and tool calls.
 
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::config::{GenerationSettings, SessionOptions, TrimStrategy};
+
+/// Content that replaces a tool result identical to the previous result of
+/// the same tool (see [`Session::add_message_deduped`]).
+pub const DUPLICATE_TOOL_RESULT_MARKER: &str = "[identical to previous result]";
+
+/// Maximum length of a single session tag.
+pub const MAX_TAG_LEN: usize = 64;
+
+/// Normalize a user-supplied tag.
+///
+/// Tags are trimmed and lowercased. Returns `None` for empty tags, tags
+/// longer than [`MAX_TAG_LEN`], or tags containing whitespace or characters
+/// other than ASCII alphanumerics, `-`, `_`, `.`, and `:`.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().to_ascii_lowercase();
+    let valid = !tag.is_empty()
+        && tag.len() <= MAX_TAG_LEN
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+    valid.then_some(tag)
+}
+
+/// A durable fact about the conversation (e.g. "prefers metric units").
+///
+/// Notes live outside the message history, so trimming, compaction, and
+/// summarization never drop them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryNote {
+    /// The fact itself.
+    pub text: String,
+    /// When the note was recorded.
+    pub created_at: DateTime<Utc>,
+    /// Who recorded it (e.g. "agent" for the `memory` tool).
+    pub source: String,
+}
+
+/// One step of the agent's plan for a multi-step task.
+///
+/// Like notes, the plan lives outside the message history and is shown to
+/// the model on every turn.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanItem {
+    /// What the step is.
+    pub text: String,
+    /// Whether the step has been checked off.
+    #[serde(default)]
+    pub done: bool,
+}
+
+/// How deep the tool loop of a session's turns went, for later analysis of
+/// conversations that keep hitting `max_tool_iterations`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolIterationStats {
+    /// Tool iterations in the most recent turn.
+    pub last_turn: u32,
+    /// Most tool iterations in any turn.
+    pub max_turn: u32,
+    /// Turns stopped by the iteration limit.
+    pub limit_hits: u32,
+    /// When the limit last stopped a turn.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_limit_hit: Option<DateTime<Utc>>,
+}
+
+impl ToolIterationStats {
+    /// Record a turn that ran `iterations` tool iterations, noting whether
+    /// the limit stopped it.
+    pub fn record_turn(&mut self, iterations: u32, hit_limit: bool) {
+        self.last_turn = iterations;
+        self.max_turn = self.max_turn.max(iterations);
+        if hit_limit {
+            self.limit_hits += 1;
+            self.last_limit_hit = Some(Utc::now());
+        }
+    }
+
+    /// Whether no turn has used tools yet.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Tokens used by provider calls and what they cost, for one turn or a
+/// whole session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageCost {
+    /// Prompt tokens sent.
+    pub input_tokens: u64,
+    /// Completion tokens received.
+    pub output_tokens: u64,
+    /// Estimated cost in USD; calls to models without known pricing count
+    /// as free.
+    pub cost_usd: f64,
+    /// Prompt tokens read from the provider's prompt cache (part of
+    /// `input_tokens`).
+    #[serde(skip_serializing_if = "is_zero")]
+    pub cache_read_tokens: u64,
+    /// Prompt tokens written to the provider's prompt cache (part of
+    /// `input_tokens`).
+    #[serde(skip_serializing_if = "is_zero")]
+    pub cache_write_tokens: u64,
+    /// Estimated USD prompt caching saved, net of the cost of cache writes.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub cache_savings_usd: f64,
+    /// Completion tokens the model spent reasoning (part of
+    /// `output_tokens`).
+    #[serde(skip_serializing_if = "is_zero")]
+    pub thinking_tokens: u64,
+    /// Estimated USD of `thinking_tokens` (part of `cost_usd`).
+    #[serde(skip_serializing_if = "is_zero")]
+    pub thinking_cost_usd: f64,
+}
+
+fn is_zero<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+impl UsageCost {
+    /// Add `other` to this total.
+    pub fn add(&mut self, other: &UsageCost) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cost_usd += other.cost_usd;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_write_tokens += other.cache_write_tokens;
+        self.cache_savings_usd += other.cache_savings_usd;
+        self.thinking_tokens += other.thinking_tokens;
+        self.thinking_cost_usd += other.thinking_cost_usd;
+    }
+
+    /// Whether nothing was used.
+    pub fn is_empty(&self) -> bool {
+        self.input_tokens == 0 && self.output_tokens == 0 && self.cost_usd == 0.0
+    }
+}
+
 /// A conversation session containing messages and metadata.
 ///
 /// Sessions are identified by a unique key and store the full conversation
@@ -22,6 +164,60 @@ pub struct Session {
     pub created_at: DateTime<Utc>,
     /// When this session was last modified
     pub updated_at: DateTime<Utc>,
+    /// Pinned sessions are exempt from cleanup and eviction.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Free-form labels (e.g. "support", "vip-user"), normalized to lowercase.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+    /// Durable notes about the user or conversation, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<MemoryNote>,
+    /// The agent's current plan, maintained with the `todo` tool.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plan: Vec<PlanItem>,
+    /// Options resolved when the session was created (see
+    /// [`SessionManager::options_for`](super::SessionManager::options_for)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<SessionOptions>,
+    /// Dry-run mode set with `/dryrun`; `tools.dry_run` keeps it on regardless.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dry_run: Option<bool>,
+    /// Workspace brief set with `/brief`; `None` follows
+    /// `agents.defaults.workspace_brief.enabled`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_brief: Option<bool>,
+    /// Turn tracing set with `/trace`; `None` follows
+    /// `agents.defaults.trace.enabled`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<bool>,
+    /// Agent profile chosen with `/agent <name>`; `None` follows
+    /// `agents.routes` and `agents.default_profile`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_profile: Option<String>,
+    /// Model and sampling settings set with `/model`, `/temp` and
+    /// `/settings`; unset ones follow the agent profile and config.
+    #[serde(default, skip_serializing_if = "GenerationSettings::is_empty")]
+    pub settings: GenerationSettings,
+    /// Channel message IDs of the latest inbound messages, oldest first, so
+    /// a message the channel delivers again is not answered twice.
+    #[serde(default, skip_serializing_if = "VecDeque::is_empty")]
+    pub recent_message_ids: VecDeque<String>,
+    /// Tool-loop depth of this session's turns.
+    #[serde(default, skip_serializing_if = "ToolIterationStats::is_empty")]
+    pub tool_iterations: ToolIterationStats,
+    /// Usage of all turns so far. Kept apart from the per-turn figures on
+    /// assistant messages so trimming and compaction don't lose it.
+    #[serde(default, skip_serializing_if = "UsageCost::is_empty")]
+    pub usage: UsageCost,
+    /// Turns started so far. Counted apart from the messages so trimming
+    /// and compaction don't lose it.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub turns: u64,
+    /// Whether this conversation got its channel's welcome (see
+    /// `agents.onboarding`), so it is sent only once.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub onboarded: bool,
 }
 
 impl Session {
@@ -45,9 +241,63 @@ impl Session {
             summary: None,
             created_at: now,
             updated_at: now,
+            pinned: false,
+            tags: BTreeSet::new(),
+            notes: Vec::new(),
+            plan: Vec::new(),
+            options: None,
+            dry_run: None,
+            workspace_brief: None,
+            trace: None,
+            agent_profile: None,
+            settings: GenerationSettings::default(),
+            recent_message_ids: VecDeque::new(),
+            tool_iterations: ToolIterationStats::default(),
+            usage: UsageCost::default(),
+            turns: 0,
+            onboarded: false,
         }
     }
 
+    /// Remember the channel message ID `id`, keeping the latest `window`
+    /// IDs. Returns `false` if `id` is already among them.
+    pub fn record_message_id(&mut self, id: &str, window: usize) -> bool {
+        if self.recent_message_ids.iter().any(|seen| seen == id) {
+            return false;
+        }
+        self.recent_message_ids.push_back(id.to_string());
+        while self.recent_message_ids.len() > window {
+            self.recent_message_ids.pop_front();
+        }
+        true
+    }
+
+    /// Add a finished turn's usage to the session total.
+    pub fn record_usage(&mut self, usage: &UsageCost) {
+        self.usage.add(usage);
+    }
+
+    /// Estimated cost in USD of every turn of this session.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::session::{Session, UsageCost};
+    ///
+    /// let mut session = Session::new("test");
+    /// let turn = UsageCost {
+    ///     input_tokens: 1000,
+    ///     output_tokens: 200,
+    ///     cost_usd: 0.006,
+    ///     ..Default::default()
+    /// };
+    /// session.record_usage(&turn);
+    /// session.record_usage(&turn);
+    /// assert!((session.total_cost() - 0.012).abs() < 1e-9);
+    /// ```
+    pub fn total_cost(&self) -> f64 {
+        self.usage.cost_usd
+    }
+
     /// Add a message to this session.
     ///
     /// Also updates the `updated_at` timestamp.
@@ -68,6 +318,109 @@ impl Session {
         self.updated_at = Utc::now();
     }
 
+    /// Add a message, collapsing a tool result identical to the previous
+    /// result of the same tool into [`DUPLICATE_TOOL_RESULT_MARKER`].
+    ///
+    /// Conservative by design: only `Role::Tool` messages are considered,
+    /// only exact content matches count, and the first copy is kept intact.
+    /// The tool name is resolved through the assistant tool call that the
+    /// result answers. Returns `true` if the content was replaced.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::session::{Message, Session, ToolCall};
+    ///
+    /// let mut session = Session::new("test");
+    /// for id in ["c1", "c2"] {
+    ///     session.add_message(Message::assistant_with_tools("", vec![ToolCall::new(id, "ls", "{}")]));
+    ///     session.add_message_deduped(Message::tool_result(id, "a.txt"));
+    /// }
+    /// assert_eq!(session.messages[1].content, "a.txt");
+    /// assert_eq!(session.messages[3].content, "[identical to previous result]");
+    /// ```
+    pub fn add_message_deduped(&mut self, mut message: Message) -> bool {
+        let duplicate = message.role == Role::Tool
+            && message.content != DUPLICATE_TOOL_RESULT_MARKER
+            && self
+                .previous_tool_result(&message)
+                .is_some_and(|prev| prev.content == message.content);
+        if duplicate {
+            message.content = DUPLICATE_TOOL_RESULT_MARKER.to_string();
+            message.content_parts = vec![ContentPart::Text {
+                text: DUPLICATE_TOOL_RESULT_MARKER.to_string(),
+            }];
+        }
+        self.add_message(message);
+        duplicate
+    }
+
+    /// Find the content of the previous result of the same tool as `result`.
+    ///
+    /// Markers are skipped: each one stands for the same content as the
+    /// result before it, so the latest real copy is the effective previous
+    /// result.
+    fn previous_tool_result(&self, result: &Message) -> Option<&Message> {
+        // Tool names by call ID in one pass; a later call with the same ID
+        // wins, as the latest call is the one a result answers.
+        let names: HashMap<&str, &str> = self
+            .messages
+            .iter()
+            .filter_map(|m| m.tool_calls.as_ref())
+            .flatten()
+            .map(|tc| (tc.id.as_str(), tc.name.as_str()))
+            .collect();
+        let name_of = |m: &Message| {
+            m.tool_call_id
+                .as_deref()
+                .and_then(|id| names.get(id).copied())
+        };
+        let name = name_of(result)?;
+        self.messages.iter().rev().find(|m| {
+            m.role == Role::Tool
+                && m.content != DUPLICATE_TOOL_RESULT_MARKER
+                && name_of(m) == Some(name)
+        })
+    }
+
+    /// Answer the tool calls of the last assistant message that have no
+    /// result yet with `result(call)`, so the transcript never holds a tool
+    /// call without its result. Returns how many results were added.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::session::{Message, Session, ToolCall};
+    ///
+    /// let mut session = Session::new("test");
+    /// session.add_message(Message::assistant_with_tools(
+    ///     "",
+    ///     vec![ToolCall::new("c1", "ls", "{}"), ToolCall::new("c2", "ls", "{}")],
+    /// ));
+    /// session.add_message(Message::tool_result("c1", "a.txt"));
+    /// assert_eq!(session.answer_pending_tool_calls(|_| "cancelled".to_string()), 1);
+    /// assert_eq!(session.messages[2].tool_call_id.as_deref(), Some("c2"));
+    /// ```
+    pub fn answer_pending_tool_calls(&mut self, result: impl Fn(&ToolCall) -> String) -> usize {
+        let Some(start) = self.messages.iter().rposition(Message::has_tool_calls) else {
+            return 0;
+        };
+        let answered: Vec<&str> = self.messages[start + 1..]
+            .iter()
+            .filter_map(|m| m.tool_call_id.as_deref())
+            .collect();
+        let pending: Vec<Message> = self.messages[start]
+            .tool_calls
+            .iter()
+            .flatten()
+            .filter(|call| !answered.contains(&call.id.as_str()))
+            .map(|call| Message::tool_result(&call.id, &result(call)))
+            .collect();
+        let added = pending.len();
+        for message in pending {
+            self.add_message(message);
+        }
+        added
+    }
+
     /// Clear all messages and summary from this session.
     ///
     /// Also updates the `updated_at` timestamp.
@@ -117,6 +470,162 @@ impl Session {
     pub fn messages_by_role(&self, role: Role) -> Vec<&Message> {
         self.messages.iter().filter(|m| m.role == role).collect()
     }
+
+    /// Add a tag to this session.
+    ///
+    /// Returns `true` if the tag was newly added, `false` if it was already
+    /// present or is invalid (see [`normalize_tag`]).
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::session::Session;
+    ///
+    /// let mut session = Session::new("test");
+    /// assert!(session.add_tag("Support"));
+    /// assert!(session.has_tag("support"));
+    /// ```
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        match normalize_tag(tag) {
+            Some(tag) => self.tags.insert(tag),
+            None => false,
+        }
+    }
+
+    /// Remove a tag from this session. Returns `true` if it was present.
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        normalize_tag(tag).is_some_and(|tag| self.tags.remove(&tag))
+    }
+
+    /// Check whether this session carries a tag (case-insensitive).
+    pub fn has_tag(&self, tag: &str) -> bool {
+        normalize_tag(tag).is_some_and(|tag| self.tags.contains(&tag))
+    }
+
+    /// Record a note, evicting the oldest notes beyond `max_notes`.
+    ///
+    /// Returns `false` if the text is empty or an identical note (ignoring
+    /// case and surrounding whitespace) already exists.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::session::Session;
+    ///
+    /// let mut session = Session::new("test");
+    /// assert!(session.add_note("Prefers metric units", "agent", 10));
+    /// assert!(!session.add_note("prefers metric units", "agent", 10));
+    /// assert_eq!(session.notes.len(), 1);
+    /// ```
+    pub fn add_note(&mut self, text: &str, source: &str, max_notes: usize) -> bool {
+        let text = text.trim();
+        if text.is_empty() || self.find_note(text).is_some() {
+            return false;
+        }
+        self.notes.push(MemoryNote {
+            text: text.to_string(),
+            created_at: Utc::now(),
+            source: source.to_string(),
+        });
+        let excess = self.notes.len().saturating_sub(max_notes.max(1));
+        self.notes.drain(..excess);
+        self.updated_at = Utc::now();
+        true
+    }
+
+    /// Remove the note matching `text` (ignoring case and surrounding
+    /// whitespace). Returns the removed note.
+    pub fn remove_note(&mut self, text: &str) -> Option<MemoryNote> {
+        let index = self.find_note(text.trim())?;
+        self.updated_at = Utc::now();
+        Some(self.notes.remove(index))
+    }
+
+    /// Replace the plan with `items`, all unchecked. Blank items are
+    /// skipped.
+    pub fn set_plan<S: AsRef<str>>(&mut self, items: &[S]) {
+        self.plan = items
+            .iter()
+            .map(|item| item.as_ref().trim())
+            .filter(|item| !item.is_empty())
+            .map(|text| PlanItem {
+                text: text.to_string(),
+                done: false,
+            })
+            .collect();
+        self.updated_at = Utc::now();
+    }
+
+    /// Mark plan item `index` (1-based) as done. Returns the item, or `None`
+    /// if there is no such item.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::session::Session;
+    ///
+    /// let mut session = Session::new("test");
+    /// session.set_plan(&["fetch data", "write report"]);
+    /// assert_eq!(session.check_plan_item(2).unwrap().text, "write report");
+    /// assert!(session.check_plan_item(3).is_none());
+    /// assert!(!session.plan[0].done && session.plan[1].done);
+    /// ```
+    pub fn check_plan_item(&mut self, index: usize) -> Option<&PlanItem> {
+        let item = self.plan.get_mut(index.checked_sub(1)?)?;
+        item.done = true;
+        self.updated_at = Utc::now();
+        Some(item)
+    }
+
+    /// Shorten the history to at most `max_messages` messages.
+    ///
+    /// The kept tail always starts at a user message so tool results are
+    /// never separated from the call that produced them, which may leave
+    /// fewer than `max_messages`. With [`TrimStrategy::KeepFirst`] the
+    /// opening user message (and anything before it) is kept as well.
+    /// Returns the number of messages removed.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::config::TrimStrategy;
+    /// use zeptoclaw::session::{Message, Session};
+    ///
+    /// let mut session = Session::new("test");
+    /// for i in 0..4 {
+    ///     session.add_message(Message::user(&format!("q{}", i)));
+    ///     session.add_message(Message::assistant(&format!("a{}", i)));
+    /// }
+    /// assert_eq!(session.trim(4, TrimStrategy::DropOldest), 4);
+    /// assert_eq!(session.messages[0].content, "q2");
+    /// ```
+    pub fn trim(&mut self, max_messages: usize, strategy: TrimStrategy) -> usize {
+        let len = self.messages.len();
+        if len <= max_messages {
+            return 0;
+        }
+        let head = match strategy {
+            TrimStrategy::DropOldest => 0,
+            TrimStrategy::KeepFirst => self
+                .messages
+                .iter()
+                .position(|m| m.role == Role::User)
+                .map_or(0, |i| i + 1),
+        };
+        let start = len - max_messages.saturating_sub(head).min(len - head);
+        let start = self.messages[start..]
+            .iter()
+            .position(|m| m.role == Role::User)
+            .map_or(len, |i| start + i);
+        if start <= head {
+            return 0;
+        }
+        self.messages.drain(head..start);
+        self.updated_at = Utc::now();
+        start - head
+    }
+
+    fn find_note(&self, text: &str) -> Option<usize> {
+        self.notes
+            .iter()
+            .position(|n| n.text.eq_ignore_ascii_case(text))
+    }
 }
 
 /// A content part within a message — either text or an image.
@@ -163,6 +672,36 @@ pub struct Message {
     /// ID of the tool call this message is responding to (for tool results)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_call_id: Option<String>,
+    /// How long the tool call took, in milliseconds (for tool results).
+    /// Recorded for diagnostics only; never sent to providers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_elapsed_ms: Option<u64>,
+    /// Set on the final assistant message of a turn the user stopped, whose
+    /// tool calls and answer may be incomplete.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cancelled: bool,
+    /// Usage of the turn this message ends (on the final assistant message
+    /// of a turn).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<UsageCost>,
+    /// Agent profile that answered (on the final assistant message of a
+    /// turn run under a profile).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    /// Model and sampling settings the turn ran with (on the final
+    /// assistant message of a turn).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<GenerationSettings>,
+    /// Reasoning the model wrote before this reply (extended thinking).
+    /// Never part of `content`: it is not shown to users and only goes back
+    /// to the provider where its API requires it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub thinking: Vec<ThinkingBlock>,
+    /// Length in bytes of the start of a system prompt that is the same
+    /// every turn. Providers with prompt caching cache up to here, so the
+    /// parts that change (time, memory, plan) don't invalidate the cache.
+    #[serde(skip)]
+    pub stable_prefix_len: Option<usize>,
 }
 
 impl Message {
@@ -187,6 +726,13 @@ impl Message {
             }],
             tool_calls: None,
             tool_call_id: None,
+            tool_elapsed_ms: None,
+            cancelled: false,
+            usage: None,
+            agent: None,
+            settings: None,
+            thinking: Vec::new(),
+            stable_prefix_len: None,
         }
     }
 
@@ -211,6 +757,13 @@ impl Message {
             }],
             tool_calls: None,
             tool_call_id: None,
+            tool_elapsed_ms: None,
+            cancelled: false,
+            usage: None,
+            agent: None,
+            settings: None,
+            thinking: Vec::new(),
+            stable_prefix_len: None,
         }
     }
 
@@ -237,6 +790,13 @@ impl Message {
             }],
             tool_calls: None,
             tool_call_id: None,
+            tool_elapsed_ms: None,
+            cancelled: false,
+            usage: None,
+            agent: None,
+            settings: None,
+            thinking: Vec::new(),
+            stable_prefix_len: None,
         }
     }
 
@@ -265,9 +825,22 @@ impl Message {
             }],
             tool_calls: None,
             tool_call_id: Some(tool_call_id.to_string()),
+            tool_elapsed_ms: None,
+            cancelled: false,
+            usage: None,
+            agent: None,
+            settings: None,
+            thinking: Vec::new(),
+            stable_prefix_len: None,
         }
     }
 
+    /// Record how long the tool call behind this result took.
+    pub fn with_tool_elapsed(mut self, elapsed: std::time::Duration) -> Self {
+        self.tool_elapsed_ms = Some(elapsed.as_millis() as u64);
+        self
+    }
+
     /// Create an assistant message with tool calls.
     ///
     /// # Arguments
@@ -291,6 +864,13 @@ impl Message {
             }],
             tool_calls: Some(tool_calls),
             tool_call_id: None,
+            tool_elapsed_ms: None,
+            cancelled: false,
+            usage: None,
+            agent: None,
+            settings: None,
+            thinking: Vec::new(),
+            stable_prefix_len: None,
         }
     }
 
@@ -306,6 +886,13 @@ impl Message {
             content_parts: parts,
             tool_calls: None,
             tool_call_id: None,
+            tool_elapsed_ms: None,
+            cancelled: false,
+            usage: None,
+            agent: None,
+            settings: None,
+            thinking: Vec::new(),
+            stable_prefix_len: None,
         }
     }
 
@@ -368,6 +955,23 @@ impl std::fmt::Display for Role {
     }
 }
 
+/// A block of reasoning a model wrote before answering (extended thinking).
+///
+/// Providers sign their blocks and may require them back unchanged, so they
+/// are stored as received.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ThinkingBlock {
+    /// The reasoning text; empty for a redacted block.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub thinking: String,
+    /// The provider's signature over the block.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub signature: String,
+    /// Encrypted reasoning the provider redacted, to be sent back as is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redacted: Option<String>,
+}
+
 /// A tool call made by the assistant.
 ///
 /// Tool calls represent requests to execute specific tools with given arguments.
@@ -563,6 +1167,189 @@ mod tests {
         assert_eq!(parsed.messages[1].role, Role::Assistant);
     }
 
+    #[test]
+    fn test_session_pinned_defaults_false_for_old_files() {
+        let json = r#"{
+            "key": "legacy",
+            "messages": [],
+            "summary": null,
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": "2025-01-01T00:00:00Z"
+        }"#;
+        let parsed: Session = serde_json::from_str(json).unwrap();
+        assert!(!parsed.pinned);
+        assert!(parsed.tags.is_empty());
+    }
+
+    #[test]
+    fn test_add_message_deduped_collapses_repeated_tool_results() {
+        fn call(session: &mut Session, id: &str, tool: &str, output: &str) -> bool {
+            session.add_message(Message::assistant_with_tools(
+                "",
+                vec![ToolCall::new(id, tool, "{}")],
+            ));
+            session.add_message_deduped(Message::tool_result(id, output))
+        }
+
+        let mut session = Session::new("test");
+        assert!(!call(&mut session, "c1", "web_fetch", "big page"));
+        assert!(call(&mut session, "c2", "web_fetch", "big page"));
+        // A chain of repeats still collapses against the original copy
+        assert!(call(&mut session, "c3", "web_fetch", "big page"));
+        // Same content from a different tool is kept
+        assert!(!call(&mut session, "c4", "shell", "big page"));
+        // Changed output is kept, and the next repeat compares against it
+        assert!(!call(&mut session, "c5", "web_fetch", "new page"));
+        assert!(!call(&mut session, "c6", "web_fetch", "big page"));
+        // A call ID reused by a later turn names the latest call's tool
+        assert!(call(&mut session, "c1", "shell", "big page"));
+
+        assert_eq!(session.messages[1].content, "big page");
+        assert_eq!(session.messages[3].content, DUPLICATE_TOOL_RESULT_MARKER);
+        assert_eq!(
+            session.messages[3].tool_call_id.as_deref(),
+            Some("c2"),
+            "marker keeps its tool_call_id so the call/result pairing stays valid"
+        );
+        assert_eq!(session.messages[5].content, DUPLICATE_TOOL_RESULT_MARKER);
+    }
+
+    #[test]
+    fn test_add_message_deduped_ignores_non_tool_messages() {
+        let mut session = Session::new("test");
+        assert!(!session.add_message_deduped(Message::user("same")));
+        assert!(!session.add_message_deduped(Message::user("same")));
+        assert_eq!(session.messages[1].content, "same");
+    }
+
+    #[test]
+    fn test_answer_pending_tool_calls_only_adds_missing_results() {
+        let mut session = Session::new("test");
+        session.add_message(Message::assistant_with_tools(
+            "",
+            vec![ToolCall::new("c1", "ls", "{}")],
+        ));
+        session.add_message(Message::tool_result("c1", "a.txt"));
+        assert_eq!(session.answer_pending_tool_calls(|_| "x".to_string()), 0);
+
+        session.add_message(Message::assistant_with_tools(
+            "Checking both.",
+            vec![
+                ToolCall::new("c2", "shell", "{}"),
+                ToolCall::new("c3", "web_fetch", "{}"),
+            ],
+        ));
+        session.add_message(Message::tool_result("c3", "page"));
+        let added =
+            session.answer_pending_tool_calls(|call| format!("{} was cancelled", call.name));
+        assert_eq!(added, 1);
+        let last = session.last_message().unwrap();
+        assert_eq!(last.tool_call_id.as_deref(), Some("c2"));
+        assert_eq!(last.content, "shell was cancelled");
+        assert_eq!(session.answer_pending_tool_calls(|_| "x".to_string()), 0);
+    }
+
+    #[test]
+    fn test_session_tags() {
+        let mut session = Session::new("test");
+        assert!(session.add_tag(" VIP-User "));
+        assert!(!session.add_tag("vip-user"));
+        assert!(!session.add_tag("has space"));
+        assert!(!session.add_tag(""));
+        assert!(session.add_tag("dev"));
+        assert_eq!(
+            session.tags.iter().collect::<Vec<_>>(),
+            vec!["dev", "vip-user"]
+        );
+
+        let json = serde_json::to_string(&session).unwrap();
+        let parsed: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.tags, session.tags);
+
+        assert!(session.remove_tag("DEV"));
+        assert!(!session.remove_tag("dev"));
+        assert!(!session.has_tag("dev"));
+        assert!(session.has_tag("vip-user"));
+    }
+
+    #[test]
+    fn test_session_notes_evict_oldest_and_survive_clear_of_messages() {
+        let mut session = Session::new("test");
+        assert!(session.add_note("works at ACME", "agent", 2));
+        assert!(session.add_note("prefers metric units", "agent", 2));
+        assert!(session.add_note("has a cat", "agent", 2));
+        let texts: Vec<&str> = session.notes.iter().map(|n| n.text.as_str()).collect();
+        assert_eq!(texts, vec!["prefers metric units", "has a cat"]);
+
+        session.add_message(Message::user("hi"));
+        session.messages.clear();
+        session.set_summary("summary");
+        assert_eq!(session.notes.len(), 2);
+
+        let json = serde_json::to_string(&session).unwrap();
+        let parsed: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.notes, session.notes);
+
+        let removed = session.remove_note(" HAS A CAT ").unwrap();
+        assert_eq!(removed.source, "agent");
+        assert!(session.remove_note("has a cat").is_none());
+    }
+
+    #[test]
+    fn test_record_message_id_keeps_a_bounded_window() {
+        let mut session = Session::new("test");
+        assert!(session.record_message_id("1", 2));
+        assert!(!session.record_message_id("1", 2));
+        assert!(session.record_message_id("2", 2));
+        assert!(session.record_message_id("3", 2));
+        assert_eq!(session.recent_message_ids, ["2", "3"]);
+        // "1" fell out of the window and counts as new again.
+        assert!(session.record_message_id("1", 2));
+
+        let json = serde_json::to_string(&session).unwrap();
+        let parsed: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.recent_message_ids, ["3", "1"]);
+        let fresh = serde_json::to_string(&Session::new("test")).unwrap();
+        assert!(!fresh.contains("recent_message_ids"));
+    }
+
+    #[test]
+    fn test_trim_keeps_tool_results_with_their_call() {
+        let mut session = Session::new("test");
+        session.add_message(Message::user("first"));
+        session.add_message(Message::assistant("ok"));
+        session.add_message(Message::user("run it"));
+        session.add_message(Message::assistant_with_tools(
+            "",
+            vec![ToolCall::new("call_1", "shell", "{}")],
+        ));
+        session.add_message(Message::tool_result("call_1", "done"));
+        session.add_message(Message::assistant("finished"));
+        session.add_message(Message::user("thanks"));
+
+        // A cut at index 4 would orphan the tool result, so the whole turn goes.
+        assert_eq!(session.trim(3, TrimStrategy::DropOldest), 6);
+        assert_eq!(session.messages.len(), 1);
+        assert_eq!(session.messages[0].content, "thanks");
+        assert_eq!(session.trim(3, TrimStrategy::DropOldest), 0);
+    }
+
+    #[test]
+    fn test_trim_keep_first_preserves_opening_message() {
+        let mut session = Session::new("test");
+        for i in 0..5 {
+            session.add_message(Message::user(&format!("q{}", i)));
+            session.add_message(Message::assistant(&format!("a{}", i)));
+        }
+        assert_eq!(session.trim(5, TrimStrategy::KeepFirst), 5);
+        let contents: Vec<&str> = session
+            .messages
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(contents, vec!["q0", "q3", "a3", "q4", "a4"]);
+    }
+
     #[test]
     fn test_message_serialization_skips_none() {
         let msg = Message::user("Hello");
@@ -0,0 +1,546 @@
+//! Image analysis tool — describe an image with a vision-capable provider.
+//!
+//! `analyze_image` reads an image from the workspace or from a chat
+//! attachment (the `media/<hash>.<ext>` reference noted on inbound messages),
+//! downscales it to `tools.vision.max_dimension`, re-encodes it if needed to
+//! fit `tools.vision.max_upload_bytes`, and sends it to the provider with the
+//! caller's instruction. Results are cached by image content, instruction and
+//! model in `~/.zeptoclaw/cache/vision.json`, so asking the same question
+//! about the same image again does not call the provider.
+
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use base64::Engine as _;
+use image::{DynamicImage, ImageFormat};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::cache::ResponseCache;
+use crate::config::{Config, VisionToolConfig};
+use crate::error::{Result, ZeptoError};
+use crate::providers::{ChatOptions, LLMProvider};
+use crate::session::{ContentPart, ImageSource, Message};
+
+use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Instruction used when the caller gives none.
+const DEFAULT_PROMPT: &str = "Describe this image in detail. Transcribe any visible text exactly.";
+
+/// Output token cap for the description.
+const MAX_DESCRIPTION_TOKENS: u32 = 1024;
+
+/// An image ready to send: encoded bytes and their MIME type.
+#[derive(Debug)]
+struct PreparedImage {
+    data: Vec<u8>,
+    media_type: &'static str,
+}
+
+/// MIME type for formats providers accept as-is.
+fn provider_media_type(format: ImageFormat) -> Option<&'static str> {
+    match format {
+        ImageFormat::Jpeg => Some("image/jpeg"),
+        ImageFormat::Png => Some("image/png"),
+        ImageFormat::Gif => Some("image/gif"),
+        ImageFormat::WebP => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Encode as PNG when the image has transparency, JPEG otherwise.
+fn encode(image: &DynamicImage) -> Result<PreparedImage> {
+    let mut data = Vec::new();
+    let media_type = if image.color().has_alpha() {
+        image
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+            .map_err(|e| ZeptoError::Tool(format!("Failed to encode image: {}", e)))?;
+        "image/png"
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Jpeg)
+            .map_err(|e| ZeptoError::Tool(format!("Failed to encode image: {}", e)))?;
+        "image/jpeg"
+    };
+    Ok(PreparedImage { data, media_type })
+}
+
+/// Downscale and re-encode `bytes` until it fits the configured limits.
+///
+/// Images that are already small enough and in a provider-supported format
+/// are passed through untouched.
+fn prepare_image(bytes: &[u8], config: &VisionToolConfig) -> Result<PreparedImage> {
+    let format = image::guess_format(bytes)
+        .map_err(|_| ZeptoError::Tool("Not a recognised image file".to_string()))?;
+    let image = image::load_from_memory_with_format(bytes, format)
+        .map_err(|e| ZeptoError::Tool(format!("Failed to decode image: {}", e)))?;
+
+    let max_dimension = config.max_dimension.max(1);
+    let fits = image.width() <= max_dimension
+        && image.height() <= max_dimension
+        && bytes.len() <= config.max_upload_bytes;
+    if let (true, Some(media_type)) = (fits, provider_media_type(format)) {
+        return Ok(PreparedImage {
+            data: bytes.to_vec(),
+            media_type,
+        });
+    }
+
+    let mut bound = max_dimension;
+    loop {
+        let scaled = if image.width() > bound || image.height() > bound {
+            image.resize(bound, bound, image::imageops::FilterType::Triangle)
+        } else {
+            image.clone()
+        };
+        let prepared = encode(&scaled)?;
+        if prepared.data.len() <= config.max_upload_bytes {
+            return Ok(prepared);
+        }
+        if bound <= 64 {
+            return Err(ZeptoError::Tool(format!(
+                "Image does not fit in {} bytes even after downscaling",
+                config.max_upload_bytes
+            )));
+        }
+        bound = (scaled.width().max(scaled.height()) * 3 / 4).max(64);
+    }
+}
+
+/// Cache key over the image content, instruction and model.
+fn cache_key(bytes: &[u8], prompt: &str, model: &str) -> String {
+    let digest = hex::encode(Sha256::digest(bytes));
+    ResponseCache::cache_key(model, &digest, prompt)
+}
+
+/// Whether `reference` looks like a `media/<hash>.<ext>` attachment path.
+fn is_media_reference(reference: &str) -> bool {
+    let Some(file) = reference.strip_prefix("media/") else {
+        return false;
+    };
+    let Some((stem, ext)) = file.split_once('.') else {
+        return false;
+    };
+    !stem.is_empty()
+        && stem.chars().all(|c| c.is_ascii_hexdigit())
+        && matches!(ext, "jpg" | "png" | "gif" | "webp")
+}
+
+/// Tool that asks a vision-capable provider to describe an image.
+pub struct AnalyzeImageTool {
+    provider: Arc<dyn LLMProvider>,
+    config: VisionToolConfig,
+    /// Directory chat attachments are stored under (the sessions directory).
+    media_root: Option<PathBuf>,
+    cache: Mutex<ResponseCache>,
+}
+
+impl AnalyzeImageTool {
+    /// Create the tool, caching results in `~/.zeptoclaw/cache/vision.json`.
+    pub fn new(provider: Arc<dyn LLMProvider>, config: VisionToolConfig) -> Self {
+        let cache_path = Config::dir().join("cache").join("vision.json");
+        Self::with_cache_path(provider, config, cache_path)
+    }
+
+    /// Create the tool with its cache at `cache_path`.
+    pub fn with_cache_path(
+        provider: Arc<dyn LLMProvider>,
+        config: VisionToolConfig,
+        cache_path: impl Into<PathBuf>,
+    ) -> Self {
+        let cache =
+            ResponseCache::with_path(cache_path, config.cache_ttl_secs, config.cache_entries);
+        Self {
+            provider,
+            config,
+            media_root: None,
+            cache: Mutex::new(cache),
+        }
+    }
+
+    /// Resolve `media/...` attachment references under `dir`.
+    pub fn with_media_root(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.media_root = Some(dir.into());
+        self
+    }
+
+    fn model(&self) -> &str {
+        self.config
+            .model
+            .as_deref()
+            .unwrap_or_else(|| self.provider.default_model())
+    }
+
+    fn resolve_source(&self, args: &Value, ctx: &ToolContext) -> Result<PathBuf> {
+        let arg = |key: &str| {
+            args.get(key)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+        };
+        match (arg("path"), arg("attachment")) {
+            (Some(path), None) => {
+                let sandbox = ctx.sandbox().ok_or_else(|| {
+                    ZeptoError::SecurityViolation(
+                        "Workspace not configured; analyze_image requires a workspace".to_string(),
+                    )
+                })?;
+                let path = ctx.resolve_input_path(path)?;
+                sandbox.resolve(&path)
+            }
+            (None, Some(reference)) => {
+                if !is_media_reference(reference) {
+                    return Err(ZeptoError::Tool(format!(
+                        "Invalid attachment reference '{}': expected media/<hash>.<ext>",
+                        reference
+                    )));
+                }
+                let root = self.media_root.as_deref().ok_or_else(|| {
+                    ZeptoError::Tool("Chat attachments are not stored in this mode".to_string())
+                })?;
+                Ok(root.join(reference))
+            }
+            (Some(_), Some(_)) => Err(ZeptoError::Tool(
+                "Pass either 'path' or 'attachment', not both".to_string(),
+            )),
+            (None, None) => Err(ZeptoError::Tool(
+                "Missing 'path' or 'attachment'".to_string(),
+            )),
+        }
+    }
+}
+
+async fn read_limited(path: &Path, max_bytes: usize) -> Result<Vec<u8>> {
+    let meta = tokio::fs::metadata(path)
+        .await
+        .map_err(|e| ZeptoError::Tool(format!("Cannot read '{}': {}", path.display(), e)))?;
+    if meta.len() > max_bytes as u64 {
+        return Err(ZeptoError::Tool(format!(
+            "Image is {} bytes, over the {} byte limit (tools.vision.max_image_bytes)",
+            meta.len(),
+            max_bytes
+        )));
+    }
+    Ok(tokio::fs::read(path).await?)
+}
+
+#[async_trait]
+impl Tool for AnalyzeImageTool {
+    fn name(&self) -> &str {
+        "analyze_image"
+    }
+
+    fn description(&self) -> &str {
+        "Look at an image and describe it. Give either a workspace 'path' or a chat \
+         'attachment' reference (media/<hash>.<ext>). Use 'prompt' to ask something \
+         specific, e.g. 'What error is shown in this screenshot?'."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Describe an image"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::FilesystemRead
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Image file in the workspace"
+                },
+                "attachment": {
+                    "type": "string",
+                    "description": "Chat attachment reference, e.g. media/1a2b3c4d5e6f7a8b.jpg"
+                },
+                "prompt": {
+                    "type": "string",
+                    "description": "What to look for (default: a detailed description including any text)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        if !self.provider.supports_vision() {
+            return Err(ZeptoError::Tool(format!(
+                "The active provider '{}' does not accept images; configure a vision-capable \
+                 provider (Anthropic, OpenAI or Gemini) to use analyze_image",
+                self.provider.name()
+            )));
+        }
+
+        let source = self.resolve_source(&args, ctx)?;
+        let prompt = args
+            .get("prompt")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .unwrap_or(DEFAULT_PROMPT)
+            .to_string();
+        let bytes = read_limited(&source, self.config.max_image_bytes).await?;
+
+        let model = self.model().to_string();
+        let key = cache_key(&bytes, &prompt, &model);
+        if let Some(cached) = self.cache.lock().unwrap().get(&key) {
+            return Ok(ToolOutput::llm_only(cached));
+        }
+
+        let config = self.config.clone();
+        let prepared = tokio::task::spawn_blocking(move || prepare_image(&bytes, &config))
+            .await
+            .map_err(|e| ZeptoError::Tool(format!("Task panicked: {e}")))??;
+
+        let message = Message::user_with_images(
+            &prompt,
+            vec![ContentPart::Image {
+                source: ImageSource::Base64 {
+                    data: base64::engine::general_purpose::STANDARD.encode(&prepared.data),
+                },
+                media_type: prepared.media_type.to_string(),
+            }],
+        );
+        let response = self
+            .provider
+            .chat(
+                vec![message],
+                vec![],
+                Some(&model),
+                ChatOptions::new().with_max_tokens(MAX_DESCRIPTION_TOKENS),
+            )
+            .await?;
+        let description = response.content.trim().to_string();
+        if description.is_empty() {
+            return Err(ZeptoError::Tool(
+                "The provider returned an empty description".to_string(),
+            ));
+        }
+
+        let tokens = response
+            .usage
+            .map_or((description.len() / 4) as u32, |u| u.completion_tokens);
+        self.cache
+            .lock()
+            .unwrap()
+            .put(key, description.clone(), tokens);
+        Ok(ToolOutput::llm_only(description))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{LLMResponse, ToolDefinition};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::TempDir;
+
+    /// Records the images it receives and answers with a fixed description.
+    struct MockVisionProvider {
+        vision: bool,
+        calls: AtomicUsize,
+        last_image: Mutex<Option<(String, Vec<u8>)>>,
+    }
+
+    impl MockVisionProvider {
+        fn new(vision: bool) -> Arc<Self> {
+            Arc::new(Self {
+                vision,
+                calls: AtomicUsize::new(0),
+                last_image: Mutex::new(None),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for MockVisionProvider {
+        async fn chat(
+            &self,
+            messages: Vec<Message>,
+            _tools: Vec<ToolDefinition>,
+            _model: Option<&str>,
+            _options: ChatOptions,
+        ) -> Result<LLMResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if let Some(ContentPart::Image {
+                source: ImageSource::Base64 { data },
+                media_type,
+            }) = messages[0].images().first().copied()
+            {
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(data)
+                    .unwrap();
+                *self.last_image.lock().unwrap() = Some((media_type.clone(), bytes));
+            }
+            Ok(LLMResponse::text("A red square."))
+        }
+
+        fn default_model(&self) -> &str {
+            "mock-vision"
+        }
+
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        fn supports_vision(&self) -> bool {
+            self.vision
+        }
+    }
+
+    struct Fixture {
+        dir: TempDir,
+        ctx: ToolContext,
+        provider: Arc<MockVisionProvider>,
+        tool: AnalyzeImageTool,
+    }
+
+    fn fixture(vision: bool, config: VisionToolConfig) -> Fixture {
+        let dir = TempDir::new().unwrap();
+        let workspace = dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        let ctx = ToolContext::new().with_workspace(workspace.to_str().unwrap());
+        let provider = MockVisionProvider::new(vision);
+        let tool = AnalyzeImageTool::with_cache_path(
+            provider.clone(),
+            config,
+            dir.path().join("vision.json"),
+        )
+        .with_media_root(dir.path().join("sessions"));
+        Fixture {
+            dir,
+            ctx,
+            provider,
+            tool,
+        }
+    }
+
+    fn write_png(path: &Path, width: u32, height: u32) {
+        image::RgbImage::from_pixel(width, height, image::Rgb([200, 0, 0]))
+            .save_with_format(path, ImageFormat::Png)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_describes_workspace_image() {
+        let f = fixture(true, VisionToolConfig::default());
+        write_png(&f.dir.path().join("workspace/shot.png"), 40, 30);
+
+        let out = f
+            .tool
+            .execute(json!({"path": "shot.png"}), &f.ctx)
+            .await
+            .unwrap();
+        assert_eq!(out.for_llm, "A red square.");
+        let (media_type, _) = f.provider.last_image.lock().unwrap().clone().unwrap();
+        assert_eq!(media_type, "image/png", "small images are sent unchanged");
+    }
+
+    #[tokio::test]
+    async fn test_large_image_is_downscaled() {
+        let f = fixture(
+            true,
+            VisionToolConfig {
+                max_dimension: 100,
+                ..Default::default()
+            },
+        );
+        write_png(&f.dir.path().join("workspace/big.png"), 400, 200);
+
+        f.tool
+            .execute(json!({"path": "big.png"}), &f.ctx)
+            .await
+            .unwrap();
+        let (media_type, bytes) = f.provider.last_image.lock().unwrap().clone().unwrap();
+        assert_eq!(media_type, "image/jpeg");
+        let sent = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((sent.width(), sent.height()), (100, 50));
+    }
+
+    #[tokio::test]
+    async fn test_same_image_and_prompt_is_cached() {
+        let f = fixture(true, VisionToolConfig::default());
+        write_png(&f.dir.path().join("workspace/a.png"), 10, 10);
+        std::fs::copy(
+            f.dir.path().join("workspace/a.png"),
+            f.dir.path().join("workspace/copy.png"),
+        )
+        .unwrap();
+
+        for path in ["a.png", "copy.png"] {
+            f.tool.execute(json!({"path": path}), &f.ctx).await.unwrap();
+        }
+        assert_eq!(f.provider.calls.load(Ordering::SeqCst), 1);
+
+        f.tool
+            .execute(
+                json!({"path": "a.png", "prompt": "Count the squares"}),
+                &f.ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(f.provider.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_provider_without_vision_is_rejected() {
+        let f = fixture(false, VisionToolConfig::default());
+        write_png(&f.dir.path().join("workspace/a.png"), 10, 10);
+        let err = f
+            .tool
+            .execute(json!({"path": "a.png"}), &f.ctx)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("does not accept images"),
+            "{}",
+            err
+        );
+        assert_eq!(f.provider.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_size_cap() {
+        let f = fixture(
+            true,
+            VisionToolConfig {
+                max_image_bytes: 16,
+                ..Default::default()
+            },
+        );
+        write_png(&f.dir.path().join("workspace/a.png"), 10, 10);
+        let err = f
+            .tool
+            .execute(json!({"path": "a.png"}), &f.ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("max_image_bytes"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_attachment_reference() {
+        let f = fixture(true, VisionToolConfig::default());
+        std::fs::create_dir_all(f.dir.path().join("sessions/media")).unwrap();
+        write_png(&f.dir.path().join("sessions/media/0123abcd.png"), 10, 10);
+
+        let out = f
+            .tool
+            .execute(json!({"attachment": "media/0123abcd.png"}), &f.ctx)
+            .await
+            .unwrap();
+        assert_eq!(out.for_llm, "A red square.");
+
+        for bad in ["media/../secret.png", "../media/x.png", "media/abc.txt"] {
+            let err = f
+                .tool
+                .execute(json!({"attachment": bad}), &f.ctx)
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("Invalid attachment"), "{}", err);
+        }
+    }
+}
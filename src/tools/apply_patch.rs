@@ -0,0 +1,948 @@
+//! Apply-patch tool — apply unified diffs to workspace files natively.
+//!
+//! Accepts the multi-file diffs produced by `diff -u` and `git diff`. Each
+//! hunk is first looked for at the line its header names, then at the
+//! nearest offset where its lines match. With fuzz `N`, up to `N` lines of
+//! leading and trailing context may be ignored, as in `patch(1)`. Lines are
+//! compared ignoring trailing whitespace, so CRLF files and CRLF patches
+//! apply cleanly, and the file keeps its line ending style.
+//!
+//! A file is written only if every one of its hunks applies; rejected hunks
+//! are reported with the lines the file actually has where the hunk was
+//! expected, so the model can fix the diff and retry.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::error::{Result, ZeptoError};
+use crate::security::Sandbox;
+
+use super::filesystem::write_file_secure;
+use super::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Fuzz used when the call does not set one, as in `patch(1)`.
+const DEFAULT_FUZZ: usize = 2;
+
+/// Highest fuzz accepted.
+const MAX_FUZZ: usize = 3;
+
+/// File lines shown on each side of a rejected hunk's expected position.
+const REJECT_CONTEXT_LINES: usize = 3;
+
+/// Most hunk lines quoted in a rejection report.
+const REJECT_MAX_EXPECTED_LINES: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineKind {
+    Context,
+    Remove,
+    Add,
+}
+
+#[derive(Debug)]
+struct Hunk {
+    old_start: usize,
+    old_count: usize,
+    new_start: usize,
+    new_count: usize,
+    lines: Vec<(LineKind, String)>,
+    /// `\ No newline at end of file` followed the new side's last line.
+    no_newline_at_end: bool,
+}
+
+impl Hunk {
+    fn header(&self) -> String {
+        format!(
+            "@@ -{},{} +{},{} @@",
+            self.old_start, self.old_count, self.new_start, self.new_count
+        )
+    }
+
+    fn old_lines(lines: &[(LineKind, String)]) -> Vec<&str> {
+        lines
+            .iter()
+            .filter(|(kind, _)| *kind != LineKind::Add)
+            .map(|(_, text)| text.as_str())
+            .collect()
+    }
+
+    fn leading_context(&self) -> usize {
+        self.lines
+            .iter()
+            .take_while(|(kind, _)| *kind == LineKind::Context)
+            .count()
+    }
+
+    fn trailing_context(&self) -> usize {
+        self.lines
+            .iter()
+            .rev()
+            .take_while(|(kind, _)| *kind == LineKind::Context)
+            .count()
+    }
+}
+
+/// The changes to one file.
+#[derive(Debug)]
+struct FilePatch {
+    /// `None` for `/dev/null`, i.e. a new file.
+    old_path: Option<String>,
+    /// `None` for `/dev/null`, i.e. a deleted file.
+    new_path: Option<String>,
+    hunks: Vec<Hunk>,
+}
+
+impl FilePatch {
+    fn label(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or("/dev/null")
+    }
+}
+
+/// Path from a `---`/`+++` header: drops any timestamp after a tab and maps
+/// `/dev/null` to `None`.
+fn header_path(rest: &str) -> Option<String> {
+    let path = rest.split('\t').next().unwrap_or(rest).trim();
+    (path != "/dev/null" && !path.is_empty()).then(|| path.to_string())
+}
+
+/// Parse `@@ -a,b +c,d @@`. A missing count means 1.
+fn parse_hunk_header(line: &str) -> std::result::Result<(usize, usize, usize, usize), String> {
+    let err = || format!("malformed hunk header {:?}", line);
+    let inner = line
+        .strip_prefix("@@")
+        .and_then(|rest| rest.split("@@").next())
+        .ok_or_else(err)?;
+    let range = |prefix: char| -> std::result::Result<(usize, usize), String> {
+        let part = inner
+            .split_whitespace()
+            .find_map(|s| s.strip_prefix(prefix))
+            .ok_or_else(err)?;
+        let (start, count) = part.split_once(',').unwrap_or((part, "1"));
+        Ok((
+            start.parse().map_err(|_| err())?,
+            count.parse().map_err(|_| err())?,
+        ))
+    };
+    let (old_start, old_count) = range('-')?;
+    let (new_start, new_count) = range('+')?;
+    Ok((old_start, old_count, new_start, new_count))
+}
+
+/// Parse a unified diff covering one or more files.
+///
+/// Hunk bodies are read by their header counts, so removed lines that start
+/// with `--` are not mistaken for file headers, and an empty line inside a
+/// hunk is taken as blank context (editors often strip the leading space).
+fn parse_patch(text: &str) -> std::result::Result<Vec<FilePatch>, String> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut pending_old: Option<Option<String>> = None;
+    let mut remaining = (0usize, 0usize);
+
+    for line in text.lines() {
+        if line.starts_with('\\') {
+            // "\ No newline at end of file" for the line just read; it only
+            // matters for the new side.
+            if let Some(hunk) = files.last_mut().and_then(|f| f.hunks.last_mut()) {
+                if hunk
+                    .lines
+                    .last()
+                    .is_some_and(|(kind, _)| *kind != LineKind::Remove)
+                {
+                    hunk.no_newline_at_end = true;
+                }
+            }
+            continue;
+        }
+
+        if remaining != (0, 0) {
+            let file = files.last_mut().expect("hunk in progress");
+            let label = file.label().to_string();
+            let hunk = file.hunks.last_mut().expect("hunk in progress");
+            let (kind, body) = match line.chars().next() {
+                Some(' ') => (LineKind::Context, &line[1..]),
+                Some('-') => (LineKind::Remove, &line[1..]),
+                Some('+') => (LineKind::Add, &line[1..]),
+                None => (LineKind::Context, ""),
+                Some(_) => {
+                    return Err(format!(
+                        "hunk {} in {} ends early: expected {} more old and {} more new line(s), \
+                         got {:?}",
+                        hunk.header(),
+                        label,
+                        remaining.0,
+                        remaining.1,
+                        line
+                    ))
+                }
+            };
+            let (old_left, new_left) = &mut remaining;
+            if kind != LineKind::Add {
+                *old_left = old_left.checked_sub(1).ok_or_else(|| {
+                    format!(
+                        "hunk {} in {} has more old lines than declared",
+                        hunk.header(),
+                        label
+                    )
+                })?;
+            }
+            if kind != LineKind::Remove {
+                *new_left = new_left.checked_sub(1).ok_or_else(|| {
+                    format!(
+                        "hunk {} in {} has more new lines than declared",
+                        hunk.header(),
+                        label
+                    )
+                })?;
+            }
+            hunk.lines.push((kind, body.to_string()));
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("--- ") {
+            pending_old = Some(header_path(rest));
+        } else if let Some(rest) = line.strip_prefix("+++ ") {
+            let old_path = pending_old
+                .take()
+                .ok_or_else(|| format!("'+++ {}' without a preceding '---' line", rest))?;
+            files.push(FilePatch {
+                old_path,
+                new_path: header_path(rest),
+                hunks: Vec::new(),
+            });
+        } else if line.starts_with("@@") {
+            let file = files
+                .last_mut()
+                .ok_or_else(|| "hunk before any '---'/'+++' file header".to_string())?;
+            let (old_start, old_count, new_start, new_count) = parse_hunk_header(line)?;
+            file.hunks.push(Hunk {
+                old_start,
+                old_count,
+                new_start,
+                new_count,
+                lines: Vec::new(),
+                no_newline_at_end: false,
+            });
+            remaining = (old_count, new_count);
+        }
+        // Anything else (`diff --git`, `index`, commentary) is ignored.
+    }
+
+    if remaining != (0, 0) {
+        return Err(format!(
+            "patch ends inside a hunk: expected {} more old and {} more new line(s)",
+            remaining.0, remaining.1
+        ));
+    }
+    if files.is_empty() {
+        return Err("no '---'/'+++' file headers found".to_string());
+    }
+    if let Some(file) = files
+        .iter()
+        .find(|f| f.hunks.is_empty() && f.old_path.is_some() && f.new_path.is_some())
+    {
+        return Err(format!("{} has no hunks", file.label()));
+    }
+    Ok(files)
+}
+
+/// How one hunk applied.
+#[derive(Debug)]
+struct Applied {
+    /// Lines between where the header said and where the hunk matched.
+    offset: isize,
+    fuzz: usize,
+}
+
+/// A hunk that did not apply.
+#[derive(Debug)]
+struct Rejected {
+    number: usize,
+    header: String,
+    expected: Vec<String>,
+    /// 1-based line the hunk was expected at, and the file's lines there.
+    found_at: usize,
+    found: Vec<String>,
+}
+
+/// A file's text as lines without terminators.
+struct Text {
+    lines: Vec<String>,
+    eol: &'static str,
+    final_newline: bool,
+}
+
+impl Text {
+    fn parse(content: &str) -> Self {
+        Self {
+            lines: content.lines().map(str::to_string).collect(),
+            eol: if content.contains("\r\n") {
+                "\r\n"
+            } else {
+                "\n"
+            },
+            final_newline: content.is_empty() || content.ends_with('\n'),
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = self.lines.join(self.eol);
+        if self.final_newline && !self.lines.is_empty() {
+            out.push_str(self.eol);
+        }
+        out
+    }
+}
+
+fn lines_match(file: &str, patch: &str) -> bool {
+    file.trim_end() == patch.trim_end()
+}
+
+/// Nearest position at or after `min_pos` where `pattern` matches, searching
+/// outward from `expected`.
+fn find_match(
+    lines: &[String],
+    pattern: &[&str],
+    expected: usize,
+    min_pos: usize,
+) -> Option<usize> {
+    let last = lines.len().checked_sub(pattern.len())?;
+    if min_pos > last {
+        return None;
+    }
+    let expected = expected.clamp(min_pos, last);
+    let matches_at = |pos: usize| {
+        lines[pos..pos + pattern.len()]
+            .iter()
+            .zip(pattern)
+            .all(|(file, patch)| lines_match(file, patch))
+    };
+    (0..=last - min_pos).find_map(|distance| {
+        [
+            expected.checked_add(distance),
+            expected.checked_sub(distance),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|pos| (min_pos..=last).contains(pos))
+        .find(|&pos| matches_at(pos))
+    })
+}
+
+/// Apply `hunks` to `text` in order. Returns how each hunk applied, or the
+/// rejected hunks; `text` is only meaningful when nothing was rejected.
+fn apply_hunks(
+    text: &mut Text,
+    hunks: &[Hunk],
+    max_fuzz: usize,
+) -> std::result::Result<Vec<Applied>, Vec<Rejected>> {
+    let mut applied = Vec::new();
+    let mut rejected = Vec::new();
+    let mut delta: isize = 0;
+    let mut min_pos = 0usize;
+    // Rejections quote the unpatched file, since that is what a retried
+    // patch applies to; `growth` maps positions back to it.
+    let original = text.lines.clone();
+    let mut growth: isize = 0;
+
+    for (i, hunk) in hunks.iter().enumerate() {
+        let old_all = Hunk::old_lines(&hunk.lines);
+        // Position in the original file of the hunk's first old line; a hunk
+        // with no old lines inserts after line `old_start`.
+        let origin = if old_all.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let (lead, trail) = (hunk.leading_context(), hunk.trailing_context());
+
+        let mut result = None;
+        let mut tried = None;
+        for fuzz in 0..=max_fuzz {
+            let drop_lead = fuzz.min(lead);
+            let drop_trail = fuzz.min(trail);
+            if tried == Some((drop_lead, drop_trail)) {
+                break;
+            }
+            tried = Some((drop_lead, drop_trail));
+            if drop_lead + drop_trail >= hunk.lines.len() {
+                break;
+            }
+            let body = &hunk.lines[drop_lead..hunk.lines.len() - drop_trail];
+            let pattern = Hunk::old_lines(body);
+            if pattern.is_empty() && !old_all.is_empty() {
+                break;
+            }
+            let expected = (origin + drop_lead) as isize + delta;
+            let expected = usize::try_from(expected).unwrap_or(0);
+            if let Some(pos) = find_match(&text.lines, &pattern, expected, min_pos) {
+                result = Some((pos, body, pattern.len(), fuzz, origin + drop_lead, expected));
+                break;
+            }
+        }
+
+        let Some((pos, body, old_len, fuzz, old_pos, expected)) = result else {
+            let expected_at = usize::try_from(origin as isize + delta - growth).unwrap_or(0);
+            let from = expected_at
+                .saturating_sub(REJECT_CONTEXT_LINES)
+                .min(original.len());
+            let to = (expected_at + old_all.len() + REJECT_CONTEXT_LINES).min(original.len());
+            rejected.push(Rejected {
+                number: i + 1,
+                header: hunk.header(),
+                expected: old_all.iter().map(|l| l.to_string()).collect(),
+                found_at: from + 1,
+                found: original[from..to].to_vec(),
+            });
+            continue;
+        };
+
+        let mut replacement = Vec::new();
+        let mut file_pos = pos;
+        for (kind, line) in body {
+            match kind {
+                LineKind::Context => {
+                    replacement.push(text.lines[file_pos].clone());
+                    file_pos += 1;
+                }
+                LineKind::Remove => file_pos += 1,
+                LineKind::Add => replacement.push(line.clone()),
+            }
+        }
+        let new_len = replacement.len();
+        text.lines.splice(pos..pos + old_len, replacement);
+        if pos + new_len == text.lines.len() {
+            text.final_newline = !hunk.no_newline_at_end;
+        }
+        // Carry this hunk's offset forward, as patch(1) does.
+        delta = (pos + new_len) as isize - (old_pos + old_len) as isize;
+        growth += new_len as isize - old_len as isize;
+        min_pos = pos + new_len;
+        applied.push(Applied {
+            offset: pos as isize - expected as isize,
+            fuzz,
+        });
+    }
+
+    if rejected.is_empty() {
+        Ok(applied)
+    } else {
+        Err(rejected)
+    }
+}
+
+/// Outcome for one file of the patch.
+enum FileOutcome {
+    Created,
+    Deleted,
+    Modified {
+        hunks: Vec<Applied>,
+        renamed_from: Option<String>,
+    },
+    Failed(String),
+    Rejected {
+        total: usize,
+        hunks: Vec<Rejected>,
+    },
+}
+
+/// Tool that applies unified diffs to workspace files.
+///
+/// # Parameters
+/// - `patch`: unified diff text, one or more files (required)
+/// - `fuzz`: context lines that may be ignored at each end of a hunk,
+///   0-3, default 2 (optional)
+/// - `dry_run`: check that the patch applies without writing (optional;
+///   always on when [`ToolContext::dry_run`] is set)
+pub struct ApplyPatchTool;
+
+impl ApplyPatchTool {
+    /// Resolve a header path in the workspace. A git-style `a/` or `b/`
+    /// prefix is dropped unless the path exists with it. Every file a patch
+    /// names is written or removed, so read-only paths are refused.
+    fn resolve(path: &str, sandbox: &Sandbox) -> Result<(String, PathBuf)> {
+        let stripped = path
+            .strip_prefix("a/")
+            .or_else(|| path.strip_prefix("b/"))
+            .filter(|_| !Path::new(sandbox.workspace()).join(path).exists())
+            .unwrap_or(path);
+        let resolved = sandbox.resolve_for_write(stripped)?;
+        Ok((stripped.to_string(), resolved))
+    }
+
+    async fn apply_file(
+        file: &FilePatch,
+        sandbox: &Sandbox,
+        fuzz: usize,
+        dry_run: bool,
+    ) -> (String, FileOutcome) {
+        let label = file.label().to_string();
+        match Self::try_apply_file(file, sandbox, fuzz, dry_run).await {
+            Ok((label, outcome)) => (label, outcome),
+            Err(e) => (label, FileOutcome::Failed(e.to_string())),
+        }
+    }
+
+    async fn try_apply_file(
+        file: &FilePatch,
+        sandbox: &Sandbox,
+        fuzz: usize,
+        dry_run: bool,
+    ) -> Result<(String, FileOutcome)> {
+        let old = file
+            .old_path
+            .as_deref()
+            .map(|p| Self::resolve(p, sandbox))
+            .transpose()?;
+        let new = file
+            .new_path
+            .as_deref()
+            .map(|p| Self::resolve(p, sandbox))
+            .transpose()?;
+        let label = new
+            .as_ref()
+            .or(old.as_ref())
+            .map(|(name, _)| name.clone())
+            .unwrap_or_default();
+
+        if let (Some((_, old_path)), Some((new_name, new_path))) = (&old, &new) {
+            if old_path != new_path && new_path.exists() {
+                return Err(ZeptoError::Tool(format!(
+                    "{} already exists; the patch would rename onto it",
+                    new_name
+                )));
+            }
+        }
+
+        let original = match &old {
+            Some((name, path)) => {
+                sandbox.check(path)?;
+                tokio::fs::read_to_string(path)
+                    .await
+                    .map_err(|e| ZeptoError::Tool(format!("cannot read {}: {}", name, e)))?
+            }
+            None => {
+                if let Some((name, path)) = &new {
+                    if path.exists() {
+                        return Err(ZeptoError::Tool(format!(
+                            "{} already exists but the patch creates it from /dev/null",
+                            name
+                        )));
+                    }
+                }
+                String::new()
+            }
+        };
+
+        let mut text = Text::parse(&original);
+        let applied = match apply_hunks(&mut text, &file.hunks, fuzz) {
+            Ok(applied) => applied,
+            Err(rejected) => {
+                return Ok((
+                    label,
+                    FileOutcome::Rejected {
+                        total: file.hunks.len(),
+                        hunks: rejected,
+                    },
+                ))
+            }
+        };
+
+        let outcome = match (&old, &new) {
+            (Some((name, path)), None) => {
+                if !text.lines.is_empty() {
+                    return Err(ZeptoError::Tool(format!(
+                        "patch deletes {} but {} line(s) would remain",
+                        name,
+                        text.lines.len()
+                    )));
+                }
+                if !dry_run {
+                    sandbox.check_write(path)?;
+                    tokio::fs::remove_file(path).await?;
+                }
+                FileOutcome::Deleted
+            }
+            (_, Some((new_name, new_path))) => {
+                if !dry_run {
+                    write_file_secure(new_path, sandbox.workspace(), text.render().as_bytes())
+                        .await?;
+                }
+                match &old {
+                    None => FileOutcome::Created,
+                    Some((old_name, old_path)) => {
+                        let renamed = old_path != new_path;
+                        if renamed && !dry_run {
+                            sandbox.check_write(old_path)?;
+                            tokio::fs::remove_file(old_path).await?;
+                        }
+                        FileOutcome::Modified {
+                            hunks: applied,
+                            renamed_from: (renamed && old_name != new_name)
+                                .then(|| old_name.clone()),
+                        }
+                    }
+                }
+            }
+            (None, None) => return Err(ZeptoError::Tool("both paths are /dev/null".into())),
+        };
+        Ok((label, outcome))
+    }
+}
+
+/// Human summary of how a file's hunks applied.
+fn describe_applied(hunks: &[Applied]) -> String {
+    let notes: Vec<String> = hunks
+        .iter()
+        .enumerate()
+        .filter(|(_, h)| h.offset != 0 || h.fuzz != 0)
+        .map(|(i, h)| {
+            let mut note = format!("hunk {}", i + 1);
+            if h.offset != 0 {
+                let _ = write!(note, " at offset {:+}", h.offset);
+            }
+            if h.fuzz != 0 {
+                let _ = write!(note, " with fuzz {}", h.fuzz);
+            }
+            note
+        })
+        .collect();
+    let mut out = format!(
+        "{} hunk{} applied",
+        hunks.len(),
+        if hunks.len() == 1 { "" } else { "s" }
+    );
+    if !notes.is_empty() {
+        let _ = write!(out, " ({})", notes.join(", "));
+    }
+    out
+}
+
+fn format_rejection(label: &str, hunk: &Rejected) -> String {
+    let mut out = format!(
+        "{} hunk {} {}: lines not found\nExpected (context and removed lines):\n",
+        label, hunk.number, hunk.header
+    );
+    for line in hunk.expected.iter().take(REJECT_MAX_EXPECTED_LINES) {
+        let _ = writeln!(out, "  {}", line);
+    }
+    if hunk.expected.len() > REJECT_MAX_EXPECTED_LINES {
+        let _ = writeln!(
+            out,
+            "  ... {} more",
+            hunk.expected.len() - REJECT_MAX_EXPECTED_LINES
+        );
+    }
+    if hunk.found.is_empty() {
+        out.push_str("The file has no lines there.\n");
+    } else {
+        out.push_str("The file has:\n");
+        for (i, line) in hunk.found.iter().enumerate() {
+            let _ = writeln!(out, "  {:>5} | {}", hunk.found_at + i, line);
+        }
+    }
+    out
+}
+
+#[async_trait]
+impl Tool for ApplyPatchTool {
+    fn name(&self) -> &str {
+        "apply_patch"
+    }
+
+    fn description(&self) -> &str {
+        "Apply a unified diff (diff -u / git diff format, one or more files) to workspace files. \
+         Hunks may be offset and, with fuzz, have mismatched outer context. Use /dev/null as the \
+         old path to create a file. Reports each file and any rejected hunks with the file's \
+         actual lines. Set dry_run to check without writing."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Apply unified diff"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::FilesystemWrite
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "patch": {
+                    "type": "string",
+                    "description": "Unified diff with ---/+++ file headers and @@ hunks"
+                },
+                "fuzz": {
+                    "type": "integer",
+                    "description": "Context lines that may mismatch at each end of a hunk (0-3, default 2)"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Check that the patch applies without changing any file"
+                }
+            },
+            "required": ["patch"]
+        })
+    }
+
+    async fn dry_run(&self, _args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        // `execute` honours `ctx.dry_run` itself and reports which hunks
+        // would apply, which says more than a one-line description.
+        Ok(DryRun::Execute)
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let patch = args
+            .get("patch")
+            .and_then(Value::as_str)
+            .filter(|p| !p.trim().is_empty())
+            .ok_or_else(|| ZeptoError::Tool("Missing 'patch' parameter".into()))?;
+        let fuzz = args
+            .get("fuzz")
+            .and_then(Value::as_u64)
+            .map(|f| f as usize)
+            .unwrap_or(DEFAULT_FUZZ);
+        if fuzz > MAX_FUZZ {
+            return Err(ZeptoError::Tool(format!(
+                "fuzz must be between 0 and {}",
+                MAX_FUZZ
+            )));
+        }
+        let dry_run = ctx.dry_run
+            || args
+                .get("dry_run")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+        let sandbox = ctx.sandbox().ok_or_else(|| {
+            ZeptoError::SecurityViolation(
+                "Workspace not configured; apply_patch requires a workspace".to_string(),
+            )
+        })?;
+
+        let files = parse_patch(patch)
+            .map_err(|e| ZeptoError::Tool(format!("Could not parse patch: {}", e)))?;
+
+        let mut lines = Vec::new();
+        let mut rejections = Vec::new();
+        let mut failed = 0;
+        for file in &files {
+            let (label, outcome) = Self::apply_file(file, &sandbox, fuzz, dry_run).await;
+            let line = match outcome {
+                FileOutcome::Created => format!("{}: created", label),
+                FileOutcome::Deleted => format!("{}: deleted", label),
+                FileOutcome::Modified {
+                    hunks,
+                    renamed_from,
+                } => match renamed_from {
+                    Some(from) => {
+                        format!(
+                            "{}: {}, renamed from {}",
+                            label,
+                            describe_applied(&hunks),
+                            from
+                        )
+                    }
+                    None => format!("{}: {}", label, describe_applied(&hunks)),
+                },
+                FileOutcome::Failed(reason) => {
+                    failed += 1;
+                    format!("{}: FAILED, {}", label, reason)
+                }
+                FileOutcome::Rejected { total, hunks } => {
+                    failed += 1;
+                    rejections.extend(hunks.iter().map(|h| format_rejection(&label, h)));
+                    format!(
+                        "{}: FAILED, {} of {} hunk(s) rejected; file not changed",
+                        label,
+                        hunks.len(),
+                        total
+                    )
+                }
+            };
+            lines.push(line);
+        }
+
+        let mut report = format!(
+            "{} {} of {} file(s){}",
+            if dry_run { "Dry run:" } else { "Patched" },
+            files.len() - failed,
+            files.len(),
+            if dry_run {
+                " would apply; nothing was written"
+            } else {
+                ""
+            }
+        );
+        for line in &lines {
+            report.push_str("\n- ");
+            report.push_str(line);
+        }
+        if !rejections.is_empty() {
+            report.push_str("\n\nRejected hunks:\n\n");
+            report.push_str(&rejections.join("\n"));
+            report.push_str("\nFix the rejected hunks to match the file's lines and apply again.");
+        }
+
+        Ok(if failed > 0 {
+            ToolOutput::error(report)
+        } else {
+            ToolOutput::llm_only(report)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    async fn apply(dir: &Path, args: Value) -> ToolOutput {
+        let ctx = ToolContext::new().with_workspace(dir.to_str().unwrap());
+        ApplyPatchTool.execute(args, &ctx).await.unwrap()
+    }
+
+    #[test]
+    fn test_parse_multi_file_git_diff() {
+        let patch = "diff --git a/src/a.rs b/src/a.rs\n\
+                     index 1111111..2222222 100644\n\
+                     --- a/src/a.rs\n\
+                     +++ b/src/a.rs\n\
+                     @@ -1,2 +1,2 @@\n \
+                     keep\n\
+                     --- removed sql comment\n\
+                     +++ added sql comment\n\
+                     --- /dev/null\n\
+                     +++ b/new.txt\t2024-01-01 00:00:00\n\
+                     @@ -0,0 +1 @@\n\
+                     +hello\n\
+                     \\ No newline at end of file\n";
+        let files = parse_patch(patch).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].old_path.as_deref(), Some("a/src/a.rs"));
+        assert_eq!(files[0].hunks[0].lines.len(), 3);
+        assert_eq!(
+            files[0].hunks[0].lines[1],
+            (LineKind::Remove, "-- removed sql comment".to_string())
+        );
+        assert_eq!(files[1].old_path, None);
+        assert_eq!(files[1].new_path.as_deref(), Some("b/new.txt"));
+        assert!(files[1].hunks[0].no_newline_at_end);
+
+        let err = parse_patch("--- a\n+++ b\n@@ -1,3 +1,3 @@\n a\n").unwrap_err();
+        assert!(err.contains("ends inside a hunk"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_applies_with_offset_and_fuzz() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("f.txt"),
+            "header\nheader\nheader\none\ntwo\nthree\nfour\nfive\n",
+        )
+        .unwrap();
+        // Header says line 1, and the first context line no longer matches.
+        let patch =
+            "--- a/f.txt\n+++ b/f.txt\n@@ -1,4 +1,4 @@\n changed\n two\n-three\n+THREE\n four\n";
+
+        let out = apply(dir.path(), json!({"patch": patch, "fuzz": 0})).await;
+        assert!(out.is_error, "{}", out.for_llm);
+        assert!(out.for_llm.contains("1 of 1 hunk(s) rejected"));
+
+        let out = apply(dir.path(), json!({"patch": patch})).await;
+        assert!(!out.is_error, "{}", out.for_llm);
+        assert!(
+            out.for_llm
+                .contains("f.txt: 1 hunk applied (hunk 1 at offset +3 with fuzz 1)"),
+            "{}",
+            out.for_llm
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("f.txt")).unwrap(),
+            "header\nheader\nheader\none\ntwo\nTHREE\nfour\nfive\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_keeps_crlf_line_endings() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("w.txt"), "a\r\nb\r\nc\r\n").unwrap();
+        let patch = "--- w.txt\n+++ w.txt\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n";
+        let out = apply(dir.path(), json!({"patch": patch})).await;
+        assert!(!out.is_error, "{}", out.for_llm);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("w.txt")).unwrap(),
+            "a\r\nB\r\nc\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_creates_and_deletes_files() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("old.txt"), "bye\n").unwrap();
+        let patch = "--- /dev/null\n+++ b/sub/new.txt\n@@ -0,0 +1,2 @@\n+hello\n+world\n\
+                     --- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-bye\n";
+        let out = apply(dir.path(), json!({"patch": patch})).await;
+        assert!(!out.is_error, "{}", out.for_llm);
+        assert!(out.for_llm.contains("sub/new.txt: created"));
+        assert!(out.for_llm.contains("old.txt: deleted"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("sub/new.txt")).unwrap(),
+            "hello\nworld\n"
+        );
+        assert!(!dir.path().join("old.txt").exists());
+
+        // Creating it again fails because it now exists.
+        let patch = "--- /dev/null\n+++ b/sub/new.txt\n@@ -0,0 +1 @@\n+again\n";
+        let out = apply(dir.path(), json!({"patch": patch})).await;
+        assert!(out.is_error);
+        assert!(out.for_llm.contains("already exists"), "{}", out.for_llm);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_writes_nothing() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("f.txt"), "x\n").unwrap();
+        let patch = "--- f.txt\n+++ f.txt\n@@ -1 +1 @@\n-x\n+y\n";
+        let out = apply(dir.path(), json!({"patch": patch, "dry_run": true})).await;
+        assert!(!out.is_error, "{}", out.for_llm);
+        assert!(out
+            .for_llm
+            .starts_with("Dry run: 1 of 1 file(s) would apply; nothing was written"));
+        assert_eq!(fs::read_to_string(dir.path().join("f.txt")).unwrap(), "x\n");
+    }
+
+    #[tokio::test]
+    async fn test_rejected_hunk_reports_file_lines_and_leaves_file() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("f.txt"), "alpha\nbeta\ngamma\n").unwrap();
+        let patch = "--- f.txt\n+++ f.txt\n@@ -1,2 +1,2 @@\n-alpha\n+ALPHA\n beta\n\
+                     @@ -2,2 +2,2 @@\n beta\n-delta\n+DELTA\n";
+        let out = apply(dir.path(), json!({"patch": patch})).await;
+        assert!(out.is_error);
+        assert!(out
+            .for_llm
+            .contains("1 of 2 hunk(s) rejected; file not changed"));
+        assert!(out.for_llm.contains("f.txt hunk 2 @@ -2,2 +2,2 @@"));
+        assert!(out.for_llm.contains("    3 | gamma"), "{}", out.for_llm);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("f.txt")).unwrap(),
+            "alpha\nbeta\ngamma\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refuses_paths_outside_workspace() {
+        let dir = tempdir().unwrap();
+        let patch = "--- /dev/null\n+++ ../escape.txt\n@@ -0,0 +1 @@\n+x\n";
+        let out = apply(dir.path(), json!({"patch": patch})).await;
+        assert!(out.is_error);
+        assert!(!dir.path().parent().unwrap().join("escape.txt").exists());
+    }
+}
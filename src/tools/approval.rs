@@ -21,7 +21,10 @@
 //!     "approval": {
 //!         "enabled": true,
 //!         "policy": "require_for_dangerous",
-//!         "dangerous_tools": ["shell", "write_file", "edit_file", "google"]
+//!         "dangerous_tools": ["shell", "write_file", "edit_file", "apply_patch", "google"],
+//!         "shell_patterns": ["\\brm\\b", "^git push"],
+//!         "ask_in_channel": true,
+//!         "reply_timeout_secs": 120
 //!     }
 //! }
 //! ```
@@ -39,8 +42,10 @@
 //! ```
 
 use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tracing::warn;
 
 // ---------------------------------------------------------------------------
 // Approval policy (runtime enum, not serialized directly)
@@ -97,8 +102,11 @@ pub enum ApprovalPolicyConfig {
 /// - `enabled`: `true`
 /// - `policy`: `RequireForDangerous`
 /// - `require_for`: empty
-/// - `dangerous_tools`: `["shell", "write_file", "edit_file", "google"]`
+/// - `dangerous_tools`: `["shell", "write_file", "edit_file", "apply_patch", "google"]`
 /// - `auto_approve_timeout_secs`: `0` (disabled)
+/// - `shell_patterns`: empty (every gated shell command needs approval)
+/// - `ask_in_channel`: `false`
+/// - `reply_timeout_secs`: `120`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct ApprovalConfig {
@@ -121,6 +129,18 @@ pub struct ApprovalConfig {
     /// If greater than zero, auto-approve after this many seconds without
     /// a response. `0` means no auto-approve (wait indefinitely).
     pub auto_approve_timeout_secs: u64,
+
+    /// Case-insensitive regexes for shell commands. When non-empty, a gated
+    /// `shell` call only needs approval if its command matches one of them.
+    pub shell_patterns: Vec<String>,
+
+    /// Ask for approval in the chat the request came from and wait for the
+    /// reply, instead of refusing gated calls outright.
+    pub ask_in_channel: bool,
+
+    /// How long to wait for a reply in the chat before treating the request
+    /// as denied. The wait counts against `agents.defaults.agent_timeout_secs`.
+    pub reply_timeout_secs: u64,
 }
 
 impl Default for ApprovalConfig {
@@ -131,6 +151,9 @@ impl Default for ApprovalConfig {
             require_for: Vec::new(),
             dangerous_tools: ApprovalGate::default_dangerous_tools(),
             auto_approve_timeout_secs: 0,
+            shell_patterns: Vec::new(),
+            ask_in_channel: false,
+            reply_timeout_secs: 120,
         }
     }
 }
@@ -227,6 +250,8 @@ pub struct ApprovalGate {
     policy: ApprovalPolicy,
     /// Auto-approve timeout in seconds (0 = disabled).
     auto_approve_timeout_secs: u64,
+    /// Shell commands that need approval; `None` means all of them.
+    shell_patterns: Option<Vec<Regex>>,
 }
 
 impl ApprovalGate {
@@ -263,6 +288,7 @@ impl ApprovalGate {
             enabled: config.enabled,
             policy,
             auto_approve_timeout_secs: config.auto_approve_timeout_secs,
+            shell_patterns: compile_shell_patterns(&config.shell_patterns),
         }
     }
 
@@ -289,6 +315,24 @@ impl ApprovalGate {
         }
     }
 
+    /// Check whether this particular invocation requires user approval.
+    ///
+    /// Like [`ApprovalGate::requires_approval`], but a gated `shell` call is
+    /// let through when `shell_patterns` is set and none of them match its
+    /// command.
+    pub fn requires_approval_for(&self, tool_name: &str, args: &Value) -> bool {
+        if !self.requires_approval(tool_name) {
+            return false;
+        }
+        match (&self.shell_patterns, tool_name) {
+            (Some(patterns), "shell") => {
+                let command = args.get("command").and_then(Value::as_str).unwrap_or("");
+                patterns.iter().any(|p| p.is_match(command))
+            }
+            _ => true,
+        }
+    }
+
     /// Format a human-readable approval prompt for the given tool invocation.
     ///
     /// The output is intended for display in a CLI or chat message to ask
@@ -327,6 +371,7 @@ impl ApprovalGate {
             "shell".to_string(),
             "write_file".to_string(),
             "edit_file".to_string(),
+            "apply_patch".to_string(),
             "google".to_string(),
         ]
     }
@@ -342,6 +387,25 @@ impl ApprovalGate {
     }
 }
 
+/// Compile `shell_patterns`. An invalid pattern gates every shell command,
+/// so a typo never silently skips approval.
+fn compile_shell_patterns(patterns: &[String]) -> Option<Vec<Regex>> {
+    if patterns.is_empty() {
+        return None;
+    }
+    let mut compiled = Vec::with_capacity(patterns.len());
+    for pattern in patterns {
+        match Regex::new(&format!("(?i){}", pattern)) {
+            Ok(re) => compiled.push(re),
+            Err(e) => {
+                warn!(pattern = %pattern, error = %e, "Invalid approval shell pattern; gating all shell commands");
+                return None;
+            }
+        }
+    }
+    Some(compiled)
+}
+
 fn matches_tool_pattern(pattern: &str, tool_name: &str) -> bool {
     if pattern == "*" {
         return true;
@@ -518,9 +582,41 @@ mod tests {
         assert!(config.require_for.is_empty());
         assert_eq!(
             config.dangerous_tools,
-            vec!["shell", "write_file", "edit_file", "google"]
+            vec!["shell", "write_file", "edit_file", "apply_patch", "google"]
         );
         assert_eq!(config.auto_approve_timeout_secs, 0);
+        assert!(config.shell_patterns.is_empty());
+        assert!(!config.ask_in_channel);
+        assert_eq!(config.reply_timeout_secs, 120);
+    }
+
+    // ---- Shell command patterns ----------------------------------------
+
+    #[test]
+    fn test_shell_patterns_gate_matching_commands_only() {
+        let gate = ApprovalGate::new(ApprovalConfig {
+            shell_patterns: vec![r"\brm\b".to_string(), "^git push".to_string()],
+            ..Default::default()
+        });
+
+        assert!(gate.requires_approval_for("shell", &json!({"command": "rm -rf build/"})));
+        assert!(gate.requires_approval_for("shell", &json!({"command": "GIT PUSH origin"})));
+        assert!(!gate.requires_approval_for("shell", &json!({"command": "ls -la"})));
+        // Patterns only narrow shell; other gated tools still need approval.
+        assert!(gate.requires_approval_for("write_file", &json!({"path": "a.txt"})));
+        assert!(!gate.requires_approval_for("echo", &json!({})));
+    }
+
+    #[test]
+    fn test_shell_patterns_empty_or_invalid_gate_everything() {
+        let gate = ApprovalGate::new(ApprovalConfig::default());
+        assert!(gate.requires_approval_for("shell", &json!({"command": "ls"})));
+
+        let gate = ApprovalGate::new(ApprovalConfig {
+            shell_patterns: vec!["(unclosed".to_string()],
+            ..Default::default()
+        });
+        assert!(gate.requires_approval_for("shell", &json!({"command": "ls"})));
     }
 
     // ---- Tool name case sensitivity ------------------------------------
@@ -667,6 +763,9 @@ mod tests {
                 "edit_file".to_string(),
             ],
             auto_approve_timeout_secs: 30,
+            shell_patterns: vec!["^rm ".to_string()],
+            ask_in_channel: true,
+            reply_timeout_secs: 60,
         };
 
         let json_str = serde_json::to_string(&config).expect("serialize");
@@ -680,6 +779,9 @@ mod tests {
             deserialized.auto_approve_timeout_secs,
             config.auto_approve_timeout_secs
         );
+        assert_eq!(deserialized.shell_patterns, config.shell_patterns);
+        assert!(deserialized.ask_in_channel);
+        assert_eq!(deserialized.reply_timeout_secs, 60);
     }
 
     #[test]
@@ -708,10 +810,11 @@ mod tests {
     #[test]
     fn test_default_dangerous_tools_list() {
         let defaults = ApprovalGate::default_dangerous_tools();
-        assert_eq!(defaults.len(), 4);
+        assert_eq!(defaults.len(), 5);
         assert!(defaults.contains(&"shell".to_string()));
         assert!(defaults.contains(&"write_file".to_string()));
         assert!(defaults.contains(&"edit_file".to_string()));
+        assert!(defaults.contains(&"apply_patch".to_string()));
         assert!(defaults.contains(&"google".to_string()));
     }
 
@@ -0,0 +1,949 @@
+//! Archive tool — list, extract and create zip and tar.gz archives.
+//!
+//! Everything stays inside the workspace. Extraction checks every entry
+//! before writing anything: entries with absolute paths or `..` components
+//! (zip-slip) reject the whole archive, as do archives over the
+//! `tools.archive` file-count or size limits. Sizes are enforced again on the
+//! bytes actually written, so a lying header cannot get past the limit;
+//! files written before such a failure are removed. Symlinks and other
+//! special entries are skipped.
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde_json::{json, Value};
+
+use crate::config::ArchiveToolConfig;
+use crate::error::{Result, ZeptoError};
+use crate::security::{ensure_directory_chain_secure, Sandbox};
+
+use super::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Entries shown by `list` before the rest are summarized.
+const MAX_LISTED_ENTRIES: usize = 200;
+
+/// Supported archive formats, chosen by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Zip,
+    TarGz,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Result<Self> {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        if name.ends_with(".zip") {
+            Ok(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(Self::TarGz)
+        } else {
+            Err(ZeptoError::Tool(format!(
+                "Unsupported archive '{}': expected .zip, .tar.gz or .tgz",
+                path.display()
+            )))
+        }
+    }
+}
+
+/// One archive entry as seen by `list` and the extraction pre-check.
+#[derive(Debug)]
+struct EntryInfo {
+    name: String,
+    size: u64,
+    kind: EntryKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntryKind {
+    File,
+    Dir,
+    /// Symlinks, hard links, devices: never extracted.
+    Other,
+}
+
+/// Map an archive entry name to a relative path, or `None` if it is
+/// absolute or climbs out with `..`.
+fn safe_entry_path(name: &str) -> Option<PathBuf> {
+    let normalized = name.replace('\\', "/");
+    let mut out = PathBuf::new();
+    for component in Path::new(&normalized).components() {
+        match component {
+            Component::Normal(part) => out.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    (!out.as_os_str().is_empty()).then_some(out)
+}
+
+fn archive_error(e: impl std::fmt::Display) -> ZeptoError {
+    ZeptoError::Tool(format!("Failed to read archive: {}", e))
+}
+
+/// Read the entry table without extracting anything.
+fn read_entries(path: &Path, format: Format) -> Result<Vec<EntryInfo>> {
+    let file = File::open(path)?;
+    let mut entries = Vec::new();
+    match format {
+        Format::Zip => {
+            let mut archive = zip::ZipArchive::new(file).map_err(archive_error)?;
+            for i in 0..archive.len() {
+                let entry = archive.by_index(i).map_err(archive_error)?;
+                let kind = if entry.is_dir() {
+                    EntryKind::Dir
+                } else if entry.is_symlink() {
+                    EntryKind::Other
+                } else {
+                    EntryKind::File
+                };
+                entries.push(EntryInfo {
+                    name: entry.name().to_string(),
+                    size: entry.size(),
+                    kind,
+                });
+            }
+        }
+        Format::TarGz => {
+            let mut archive = tar::Archive::new(GzDecoder::new(file));
+            for entry in archive.entries().map_err(archive_error)? {
+                let entry = entry.map_err(archive_error)?;
+                let entry_type = entry.header().entry_type();
+                let kind = if entry_type.is_file() {
+                    EntryKind::File
+                } else if entry_type.is_dir() {
+                    EntryKind::Dir
+                } else {
+                    EntryKind::Other
+                };
+                entries.push(EntryInfo {
+                    name: String::from_utf8_lossy(&entry.path_bytes()).into_owned(),
+                    size: entry.size(),
+                    kind,
+                });
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Check entries against zip-slip and the configured limits.
+fn check_entries(entries: &[EntryInfo], config: &ArchiveToolConfig) -> Result<()> {
+    if let Some(bad) = entries.iter().find(|e| safe_entry_path(&e.name).is_none()) {
+        return Err(ZeptoError::SecurityViolation(format!(
+            "Archive entry '{}' escapes the destination; refusing to extract",
+            bad.name
+        )));
+    }
+    let files = entries
+        .iter()
+        .filter(|e| e.kind != EntryKind::Other)
+        .count();
+    if files > config.max_files {
+        return Err(ZeptoError::Tool(format!(
+            "Archive has {} entries, over the limit of {} (tools.archive.max_files)",
+            files, config.max_files
+        )));
+    }
+    let total: u64 = entries
+        .iter()
+        .filter(|e| e.kind == EntryKind::File)
+        .map(|e| e.size)
+        .sum();
+    if total > config.max_extract_bytes {
+        return Err(size_error(config));
+    }
+    Ok(())
+}
+
+fn size_error(config: &ArchiveToolConfig) -> ZeptoError {
+    ZeptoError::Tool(format!(
+        "Archive expands past the {} byte limit (tools.archive.max_extract_bytes)",
+        config.max_extract_bytes
+    ))
+}
+
+/// Writes extracted entries under `dest`, tracking what it created so a
+/// failed extraction can be rolled back.
+struct Extractor<'a> {
+    dest: &'a Path,
+    sandbox: &'a Sandbox,
+    config: &'a ArchiveToolConfig,
+    overwrite: bool,
+    written: u64,
+    created: Vec<PathBuf>,
+    skipped: usize,
+}
+
+impl Extractor<'_> {
+    fn target(&self, name: &str) -> Result<PathBuf> {
+        let relative = safe_entry_path(name).ok_or_else(|| {
+            ZeptoError::SecurityViolation(format!(
+                "Archive entry '{}' escapes the destination",
+                name
+            ))
+        })?;
+        let target = self.dest.join(relative);
+        self.sandbox.check_write(&target)?;
+        Ok(target)
+    }
+
+    fn dir(&mut self, name: &str) -> Result<()> {
+        let target = self.target(name)?;
+        ensure_directory_chain_secure(&target, self.sandbox.workspace())
+    }
+
+    fn file(&mut self, name: &str, reader: &mut dyn Read) -> Result<()> {
+        let target = self.target(name)?;
+        if let Some(parent) = target.parent() {
+            ensure_directory_chain_secure(parent, self.sandbox.workspace())?;
+        }
+        self.sandbox.check_write(&target)?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true);
+        if self.overwrite {
+            options.create(true).truncate(true);
+        } else {
+            options.create_new(true);
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.custom_flags(libc::O_NOFOLLOW);
+        }
+        let mut out = options.open(&target).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::AlreadyExists {
+                ZeptoError::Tool(format!(
+                    "'{}' already exists; set overwrite to true to replace it",
+                    target.display()
+                ))
+            } else {
+                ZeptoError::Tool(format!("Failed to create '{}': {}", target.display(), e))
+            }
+        })?;
+        self.created.push(target);
+
+        let remaining = self.config.max_extract_bytes - self.written;
+        let copied = std::io::copy(&mut reader.take(remaining + 1), &mut out)?;
+        if copied > remaining {
+            return Err(size_error(self.config));
+        }
+        self.written += copied;
+        Ok(())
+    }
+
+    fn extract(&mut self, archive_path: &Path, format: Format) -> Result<()> {
+        let file = File::open(archive_path)?;
+        match format {
+            Format::Zip => {
+                let mut archive = zip::ZipArchive::new(file).map_err(archive_error)?;
+                for i in 0..archive.len() {
+                    let mut entry = archive.by_index(i).map_err(archive_error)?;
+                    let name = entry.name().to_string();
+                    if entry.is_dir() {
+                        self.dir(&name)?;
+                    } else if entry.is_symlink() {
+                        self.skipped += 1;
+                    } else {
+                        self.file(&name, &mut entry)?;
+                    }
+                }
+            }
+            Format::TarGz => {
+                let mut archive = tar::Archive::new(GzDecoder::new(file));
+                for entry in archive.entries().map_err(archive_error)? {
+                    let mut entry = entry.map_err(archive_error)?;
+                    let name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+                    let entry_type = entry.header().entry_type();
+                    if entry_type.is_dir() {
+                        self.dir(&name)?;
+                    } else if entry_type.is_file() {
+                        self.file(&name, &mut entry)?;
+                    } else {
+                        self.skipped += 1;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove files created by a failed extraction.
+    fn roll_back(&self) {
+        for path in &self.created {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Collect regular files and directories under `root`, skipping symlinks.
+fn walk(root: &Path, out: &mut Vec<PathBuf>, limit: usize) -> Result<()> {
+    let meta = std::fs::symlink_metadata(root)?;
+    if meta.file_type().is_symlink() {
+        return Ok(());
+    }
+    if out.len() >= limit {
+        return Err(ZeptoError::Tool(format!(
+            "More than {} files to archive (tools.archive.max_files)",
+            limit
+        )));
+    }
+    out.push(root.to_path_buf());
+    if meta.is_dir() {
+        let mut children: Vec<PathBuf> = std::fs::read_dir(root)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .collect();
+        children.sort();
+        for child in children {
+            walk(&child, out, limit)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write `paths` (absolute, inside `workspace_root`) to a new archive at
+/// `output`, naming entries relative to the workspace root. Returns the
+/// number of files added.
+fn create_archive(
+    output: &Path,
+    format: Format,
+    paths: &[PathBuf],
+    workspace_root: &Path,
+) -> Result<usize> {
+    let name_of = |path: &Path| -> String {
+        path.strip_prefix(workspace_root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    };
+    let file = File::create(output)?;
+    let mut files = 0;
+    match format {
+        Format::Zip => {
+            let mut writer = zip::ZipWriter::new(file);
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            for path in paths {
+                let name = name_of(path);
+                if path.is_dir() {
+                    writer
+                        .add_directory(format!("{}/", name), options)
+                        .map_err(archive_error)?;
+                } else {
+                    writer.start_file(name, options).map_err(archive_error)?;
+                    std::io::copy(&mut File::open(path)?, &mut writer)?;
+                    files += 1;
+                }
+            }
+            writer.finish().map_err(archive_error)?.flush()?;
+        }
+        Format::TarGz => {
+            let mut builder =
+                tar::Builder::new(GzEncoder::new(file, flate2::Compression::default()));
+            builder.follow_symlinks(false);
+            for path in paths {
+                let name = name_of(path);
+                if path.is_dir() {
+                    builder.append_dir(&name, path)?;
+                } else {
+                    builder.append_path_with_name(path, &name)?;
+                    files += 1;
+                }
+            }
+            builder.into_inner()?.finish()?.flush()?;
+        }
+    }
+    Ok(files)
+}
+
+/// Tool for listing, extracting and creating archives in the workspace.
+pub struct ArchiveTool {
+    config: ArchiveToolConfig,
+}
+
+impl ArchiveTool {
+    /// Create an archive tool with the given limits.
+    pub fn new(config: ArchiveToolConfig) -> Self {
+        Self { config }
+    }
+
+    fn list(path: &Path) -> Result<String> {
+        let entries = read_entries(path, Format::from_path(path)?)?;
+        let total: u64 = entries.iter().map(|e| e.size).sum();
+        let mut lines = vec![format!(
+            "{} entries, {} bytes uncompressed:",
+            entries.len(),
+            total
+        )];
+        for entry in entries.iter().take(MAX_LISTED_ENTRIES) {
+            lines.push(match entry.kind {
+                EntryKind::File => format!("- {} ({} bytes)", entry.name, entry.size),
+                EntryKind::Dir => format!("- {}", entry.name),
+                EntryKind::Other => {
+                    format!("- {} (link or special file, not extracted)", entry.name)
+                }
+            });
+        }
+        if entries.len() > MAX_LISTED_ENTRIES {
+            lines.push(format!(
+                "... and {} more",
+                entries.len() - MAX_LISTED_ENTRIES
+            ));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    fn extract(
+        &self,
+        path: &Path,
+        dest: &Path,
+        sandbox: &Sandbox,
+        overwrite: bool,
+    ) -> Result<String> {
+        let format = Format::from_path(path)?;
+        check_entries(&read_entries(path, format)?, &self.config)?;
+        ensure_directory_chain_secure(dest, sandbox.workspace())?;
+
+        let mut extractor = Extractor {
+            dest,
+            sandbox,
+            config: &self.config,
+            overwrite,
+            written: 0,
+            created: Vec::new(),
+            skipped: 0,
+        };
+        if let Err(e) = extractor.extract(path, format) {
+            extractor.roll_back();
+            return Err(e);
+        }
+        let mut summary = format!(
+            "Extracted {} files ({} bytes) to {}",
+            extractor.created.len(),
+            extractor.written,
+            dest.display()
+        );
+        if extractor.skipped > 0 {
+            summary.push_str(&format!(
+                "; skipped {} links or special files",
+                extractor.skipped
+            ));
+        }
+        Ok(summary)
+    }
+}
+
+fn archive_sandbox(ctx: &ToolContext) -> Result<Arc<Sandbox>> {
+    ctx.sandbox().ok_or_else(|| {
+        ZeptoError::SecurityViolation(
+            "Workspace not configured; archive requires a workspace".to_string(),
+        )
+    })
+}
+
+/// Resolve the archive itself: written by create, read otherwise.
+fn archive_path(sandbox: &Sandbox, path: &str, operation: &str) -> Result<PathBuf> {
+    if operation == "create" {
+        sandbox.resolve_for_write(path)
+    } else {
+        sandbox.resolve(path)
+    }
+}
+
+fn required_str<'a>(args: &'a Value, key: &str, operation: &str) -> Result<&'a str> {
+    args.get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| ZeptoError::Tool(format!("Missing '{}' for {}", key, operation)))
+}
+
+/// Directory an extract call unpacks into: `dest` if given, otherwise a
+/// directory next to the archive named after it.
+fn extract_dest(args: &Value, path: &Path, sandbox: &Sandbox) -> Result<PathBuf> {
+    if let Some(dest) = args
+        .get("dest")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        return sandbox.resolve_for_write(dest);
+    }
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let stem = [".tar.gz", ".tgz", ".zip"]
+        .iter()
+        .find_map(|ext| {
+            name.len()
+                .checked_sub(ext.len())
+                .filter(|&i| name[i..].eq_ignore_ascii_case(ext))
+                .map(|i| name[..i].to_string())
+        })
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "extracted".to_string());
+    let dest = path.with_file_name(stem);
+    sandbox.check_write(&dest)?;
+    Ok(dest)
+}
+
+#[async_trait]
+impl Tool for ArchiveTool {
+    fn name(&self) -> &str {
+        "archive"
+    }
+
+    fn description(&self) -> &str {
+        "List, extract or create zip and tar.gz archives in the workspace. \
+         list shows the contents without extracting; extract unpacks into a directory \
+         (default: next to the archive, named after it); create bundles workspace paths \
+         into a new archive."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Zip/tar.gz archives"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::FilesystemWrite
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["list", "extract", "create"]
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Archive file (.zip, .tar.gz or .tgz). For create, the archive to write."
+                },
+                "dest": {
+                    "type": "string",
+                    "description": "For extract: directory to extract into"
+                },
+                "paths": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "For create: workspace-relative files and directories to include"
+                },
+                "overwrite": {
+                    "type": "boolean",
+                    "description": "Replace existing files when extracting, or an existing archive when creating (default: false)"
+                }
+            },
+            "required": ["operation", "path"]
+        })
+    }
+
+    async fn dry_run(&self, args: &Value, ctx: &ToolContext) -> Result<DryRun> {
+        let operation = required_str(args, "operation", "archive")?;
+        let sandbox = archive_sandbox(ctx)?;
+        let raw_path = required_str(args, "path", operation)?;
+        let path = archive_path(&sandbox, &ctx.resolve_input_path(raw_path)?, operation)?;
+
+        let effect = match operation {
+            "extract" => {
+                let dest = extract_dest(args, &path, &sandbox)?;
+                sandbox.check(&path)?;
+                let config = self.config.clone();
+                let (files, bytes) = tokio::task::spawn_blocking(move || {
+                    let entries = read_entries(&path, Format::from_path(&path)?)?;
+                    check_entries(&entries, &config)?;
+                    let files: Vec<_> = entries
+                        .iter()
+                        .filter(|e| e.kind == EntryKind::File)
+                        .collect();
+                    Ok::<_, ZeptoError>((files.len(), files.iter().map(|e| e.size).sum::<u64>()))
+                })
+                .await
+                .map_err(|e| ZeptoError::Tool(format!("Task panicked: {e}")))??;
+                format!(
+                    "would extract {} files ({} bytes) from {} into {}",
+                    files,
+                    bytes,
+                    raw_path,
+                    dest.strip_prefix(sandbox.workspace())
+                        .unwrap_or(&dest)
+                        .display()
+                )
+            }
+            "create" => {
+                Format::from_path(&path)?;
+                let count = args
+                    .get("paths")
+                    .and_then(Value::as_array)
+                    .map(|items| items.iter().filter_map(Value::as_str).count())
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| ZeptoError::Tool("Missing 'paths' for create".into()))?;
+                let exists = std::fs::symlink_metadata(&path).is_ok();
+                let overwrite = args
+                    .get("overwrite")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false);
+                if exists && !overwrite {
+                    return Err(ZeptoError::Tool(format!(
+                        "'{}' already exists; set overwrite to true to replace it",
+                        path.display()
+                    )));
+                }
+                let replacing = if exists {
+                    ", replacing the existing archive"
+                } else {
+                    ""
+                };
+                format!(
+                    "would create {} from {} path(s){}",
+                    raw_path, count, replacing
+                )
+            }
+            // list only reads; unknown operations fail in execute.
+            _ => return Ok(DryRun::Execute),
+        };
+        Ok(DryRun::Describe(effect))
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let operation = required_str(&args, "operation", "archive")?.to_string();
+        let sandbox = archive_sandbox(ctx)?;
+        let path = ctx.resolve_input_path(required_str(&args, "path", &operation)?)?;
+        let path = archive_path(&sandbox, &path, &operation)?;
+        let overwrite = args
+            .get("overwrite")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let output = match operation.as_str() {
+            "list" => {
+                sandbox.check(&path)?;
+                tokio::task::spawn_blocking(move || Self::list(&path))
+                    .await
+                    .map_err(|e| ZeptoError::Tool(format!("Task panicked: {e}")))??
+            }
+            "extract" => {
+                let dest = extract_dest(&args, &path, &sandbox)?;
+                sandbox.check(&path)?;
+                let config = self.config.clone();
+                tokio::task::spawn_blocking(move || {
+                    ArchiveTool { config }.extract(&path, &dest, &sandbox, overwrite)
+                })
+                .await
+                .map_err(|e| ZeptoError::Tool(format!("Task panicked: {e}")))??
+            }
+            "create" => {
+                let format = Format::from_path(&path)?;
+                let sources: Vec<PathBuf> = args
+                    .get("paths")
+                    .and_then(Value::as_array)
+                    .map(|items| items.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+                    .filter(|items| !items.is_empty())
+                    .ok_or_else(|| ZeptoError::Tool("Missing 'paths' for create".into()))?
+                    .into_iter()
+                    .map(|p| sandbox.resolve(p))
+                    .collect::<Result<_>>()?;
+                if !overwrite && std::fs::symlink_metadata(&path).is_ok() {
+                    return Err(ZeptoError::Tool(format!(
+                        "'{}' already exists; set overwrite to true to replace it",
+                        path.display()
+                    )));
+                }
+                let max_files = self.config.max_files;
+                tokio::task::spawn_blocking(move || {
+                    let workspace = sandbox.workspace();
+                    let root = Path::new(workspace)
+                        .canonicalize()
+                        .unwrap_or_else(|_| PathBuf::from(workspace));
+                    let mut entries = Vec::new();
+                    for source in &sources {
+                        sandbox.check(source)?;
+                        if !source.exists() {
+                            return Err(ZeptoError::Tool(format!(
+                                "Not found: {}",
+                                source.display()
+                            )));
+                        }
+                        walk(source, &mut entries, max_files)?;
+                    }
+                    // Denied paths inside an included directory are left out.
+                    entries.retain(|entry| {
+                        entry != &path && entry != &root && !sandbox.is_denied(entry)
+                    });
+                    if let Some(parent) = path.parent() {
+                        ensure_directory_chain_secure(parent, workspace)?;
+                    }
+                    sandbox.check_write(&path)?;
+                    let files = create_archive(&path, format, &entries, &root)?;
+                    let size = std::fs::metadata(&path)?.len();
+                    Ok::<_, ZeptoError>(format!(
+                        "Created {} with {} files ({} bytes)",
+                        path.display(),
+                        files,
+                        size
+                    ))
+                })
+                .await
+                .map_err(|e| ZeptoError::Tool(format!("Task panicked: {e}")))??
+            }
+            other => {
+                return Err(ZeptoError::Tool(format!(
+                    "Unknown archive operation '{}'. Valid operations: list, extract, create",
+                    other
+                )))
+            }
+        };
+        Ok(ToolOutput::llm_only(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, ToolContext) {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let ctx = ToolContext::new().with_workspace(root.to_str().unwrap());
+        (dir, ctx)
+    }
+
+    fn root(dir: &TempDir) -> PathBuf {
+        dir.path().canonicalize().unwrap()
+    }
+
+    async fn run(args: Value, ctx: &ToolContext) -> Result<String> {
+        ArchiveTool::new(ArchiveToolConfig::default())
+            .execute(args, ctx)
+            .await
+            .map(|o| o.for_llm)
+    }
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut writer = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, data) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    /// Build a tar.gz with raw entry names, bypassing the tar crate's own
+    /// path checks so hostile names can be written.
+    fn write_raw_tar_gz(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut builder = tar::Builder::new(GzEncoder::new(
+            File::create(path).unwrap(),
+            flate2::Compression::default(),
+        ));
+        for (name, data) in entries {
+            let mut header = tar::Header::new_old();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_cksum();
+            builder.append(&header, *data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn test_safe_entry_path() {
+        assert_eq!(safe_entry_path("a/b.txt"), Some(PathBuf::from("a/b.txt")));
+        assert_eq!(safe_entry_path("./a"), Some(PathBuf::from("a")));
+        assert_eq!(safe_entry_path("../evil"), None);
+        assert_eq!(safe_entry_path("a/../../evil"), None);
+        assert_eq!(safe_entry_path("/etc/passwd"), None);
+        assert_eq!(safe_entry_path("..\\evil"), None);
+        assert_eq!(safe_entry_path(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_create_list_extract_roundtrip() {
+        for archive in ["bundle.zip", "bundle.tar.gz"] {
+            let (dir, ctx) = setup();
+            std::fs::create_dir_all(dir.path().join("report/data")).unwrap();
+            std::fs::write(dir.path().join("report/summary.md"), "# Summary").unwrap();
+            std::fs::write(dir.path().join("report/data/a.csv"), "a,b\n1,2\n").unwrap();
+
+            let out = run(
+                json!({"operation": "create", "path": archive, "paths": ["report"]}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+            assert!(out.contains("with 2 files"), "{}", out);
+
+            let out = run(json!({"operation": "list", "path": archive}), &ctx)
+                .await
+                .unwrap();
+            assert!(out.contains("report/data/a.csv (8 bytes)"), "{}", out);
+            assert!(!root(&dir).join("bundle").exists(), "list must not extract");
+
+            let out = run(json!({"operation": "extract", "path": archive}), &ctx)
+                .await
+                .unwrap();
+            assert!(out.contains("Extracted 2 files"), "{}", out);
+            assert_eq!(
+                std::fs::read_to_string(root(&dir).join("bundle/report/data/a.csv")).unwrap(),
+                "a,b\n1,2\n"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_describes_without_writing() {
+        let (dir, ctx) = setup();
+        write_zip(
+            &root(&dir).join("a.zip"),
+            &[("x.txt", b"hello"), ("y.txt", b"hi")],
+        );
+        let tool = ArchiveTool::new(ArchiveToolConfig::default());
+
+        let effect = tool
+            .dry_run(&json!({"operation": "extract", "path": "a.zip"}), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(
+            effect,
+            DryRun::Describe("would extract 2 files (7 bytes) from a.zip into a".into())
+        );
+        let effect = tool
+            .dry_run(
+                &json!({"operation": "create", "path": "b.tar.gz", "paths": ["a.zip"]}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            effect,
+            DryRun::Describe("would create b.tar.gz from 1 path(s)".into())
+        );
+        let effect = tool
+            .dry_run(&json!({"operation": "list", "path": "a.zip"}), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(effect, DryRun::Execute);
+        assert!(!root(&dir).join("a").exists());
+        assert!(!root(&dir).join("b.tar.gz").exists());
+    }
+
+    #[tokio::test]
+    async fn test_zip_slip_rejected() {
+        let (dir, ctx) = setup();
+        write_zip(
+            &dir.path().join("evil.zip"),
+            &[("ok.txt", b"fine"), ("../escaped.txt", b"pwned")],
+        );
+        let err = run(
+            json!({"operation": "extract", "path": "evil.zip", "dest": "out"}),
+            &ctx,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, ZeptoError::SecurityViolation(_)), "{}", err);
+        assert!(err.to_string().contains("../escaped.txt"), "{}", err);
+        assert!(!dir.path().join("escaped.txt").exists());
+        assert!(
+            !dir.path().join("out/ok.txt").exists(),
+            "nothing is extracted"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tar_slip_rejected() {
+        let (dir, ctx) = setup();
+        write_raw_tar_gz(
+            &dir.path().join("evil.tar.gz"),
+            &[("ok.txt", b"fine"), ("../../escaped.txt", b"pwned")],
+        );
+        let err = run(json!({"operation": "extract", "path": "evil.tar.gz"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ZeptoError::SecurityViolation(_)), "{}", err);
+        assert!(!dir.path().join("evil/ok.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_extract_limits() {
+        let (dir, ctx) = setup();
+        write_zip(
+            &dir.path().join("many.zip"),
+            &[("a", b"1234"), ("b", b"1234"), ("c", b"1234")],
+        );
+        let tool = |config| ArchiveTool::new(config);
+
+        let err = tool(ArchiveToolConfig {
+            max_files: 2,
+            ..Default::default()
+        })
+        .execute(json!({"operation": "extract", "path": "many.zip"}), &ctx)
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("max_files"), "{}", err);
+
+        let err = tool(ArchiveToolConfig {
+            max_extract_bytes: 10,
+            ..Default::default()
+        })
+        .execute(json!({"operation": "extract", "path": "many.zip"}), &ctx)
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("max_extract_bytes"), "{}", err);
+        assert!(!dir.path().join("many").join("a").exists());
+    }
+
+    #[tokio::test]
+    async fn test_extract_does_not_overwrite_by_default() {
+        let (dir, ctx) = setup();
+        write_zip(&dir.path().join("a.zip"), &[("f.txt", b"new")]);
+        std::fs::create_dir(dir.path().join("a")).unwrap();
+        std::fs::write(dir.path().join("a/f.txt"), "old").unwrap();
+
+        let err = run(json!({"operation": "extract", "path": "a.zip"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("already exists"), "{}", err);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("a/f.txt")).unwrap(),
+            "old"
+        );
+
+        run(
+            json!({"operation": "extract", "path": "a.zip", "overwrite": true}),
+            &ctx,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("a/f.txt")).unwrap(),
+            "new"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_paths_outside_workspace_rejected() {
+        let (_dir, ctx) = setup();
+        let err = run(
+            json!({"operation": "create", "path": "out.zip", "paths": ["../.."]}),
+            &ctx,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, ZeptoError::SecurityViolation(_)), "{}", err);
+        let err = run(json!({"operation": "list", "path": "../x.zip"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ZeptoError::SecurityViolation(_)), "{}", err);
+    }
+}
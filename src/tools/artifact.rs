@@ -0,0 +1,303 @@
+//! Session-scoped artifact store for passing large data between tools.
+//!
+//! Tool results flow through the model's context, so handing a large file
+//! from one tool to the next by value is expensive. Tools instead write named
+//! artifacts (see [`ToolContext::put_artifact`](super::ToolContext::put_artifact))
+//! and return a reference such as `artifact://report.csv`; tools that take a
+//! file path accept the reference and resolve it locally. The agent lists the
+//! current artifacts in one line of the system prompt instead of their
+//! contents.
+//!
+//! Artifacts live in `<workspace>/.artifacts/<session>/<name>`, so the usual
+//! workspace path checks apply to them. Names are flat: letters, digits,
+//! `.`, `-` and `_`.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, ZeptoError};
+use crate::security::{ensure_directory_chain_secure, revalidate_path};
+
+/// URI scheme for artifact references.
+pub const ARTIFACT_SCHEME: &str = "artifact://";
+
+/// Directory under the workspace that holds artifacts.
+const ARTIFACT_DIR: &str = ".artifacts";
+
+/// Longest artifact name accepted.
+const MAX_NAME_LEN: usize = 128;
+
+/// Artifacts named in the system prompt inventory; the rest are counted.
+const INVENTORY_MAX_NAMES: usize = 20;
+
+/// One stored artifact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactInfo {
+    pub name: String,
+    pub size: u64,
+}
+
+/// Artifact files for all sessions of one workspace.
+#[derive(Debug, Clone)]
+pub struct ArtifactStore {
+    workspace: PathBuf,
+}
+
+impl ArtifactStore {
+    /// Create a store under `workspace`. Nothing is created until the first
+    /// artifact is written.
+    pub fn new(workspace: impl Into<PathBuf>) -> Self {
+        Self {
+            workspace: workspace.into(),
+        }
+    }
+
+    /// Whether `value` is an `artifact://` reference.
+    pub fn is_reference(value: &str) -> bool {
+        value.starts_with(ARTIFACT_SCHEME)
+    }
+
+    /// The reference for artifact `name`.
+    pub fn reference(name: &str) -> String {
+        format!("{}{}", ARTIFACT_SCHEME, name)
+    }
+
+    /// Workspace-relative directory holding `session`'s artifacts.
+    fn session_dir(session: &str) -> PathBuf {
+        let dir: String = session
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '-' | '_') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        Path::new(ARTIFACT_DIR).join(if dir.is_empty() { "default" } else { &dir })
+    }
+
+    /// Reject names that are empty, too long, hidden, or contain anything
+    /// but letters, digits, `.`, `-` and `_`.
+    fn validate_name(name: &str) -> Result<()> {
+        let valid = !name.is_empty()
+            && name.len() <= MAX_NAME_LEN
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+        if valid {
+            Ok(())
+        } else {
+            Err(ZeptoError::Tool(format!(
+                "Invalid artifact name '{}': use up to {} letters, digits, '.', '-' or '_', \
+                 not starting with '.'",
+                name, MAX_NAME_LEN
+            )))
+        }
+    }
+
+    fn workspace_str(&self) -> String {
+        self.workspace.to_string_lossy().into_owned()
+    }
+
+    /// Path where artifact `name` of `session` is (or would be) stored,
+    /// creating the session directory. For tools that stream their output
+    /// into place; [`put`](Self::put) covers the in-memory case.
+    pub fn path_for_write(&self, session: &str, name: &str) -> Result<PathBuf> {
+        Self::validate_name(name)?;
+        let workspace = self.workspace_str();
+        let dir = self.workspace.join(Self::session_dir(session));
+        ensure_directory_chain_secure(&dir, &workspace)?;
+        let path = dir.join(name);
+        revalidate_path(&path, &workspace)?;
+        Ok(path)
+    }
+
+    /// Store `bytes` as artifact `name`, replacing any previous version, and
+    /// return its `artifact://` reference.
+    pub async fn put(&self, session: &str, name: &str, bytes: &[u8]) -> Result<String> {
+        let path = self.path_for_write(session, name)?;
+        let tmp = path.with_file_name(format!(
+            ".{}.{}.part",
+            name,
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        ));
+        let result = async {
+            tokio::fs::write(&tmp, bytes).await?;
+            revalidate_path(&path, &self.workspace_str())?;
+            tokio::fs::rename(&tmp, &path).await?;
+            Ok::<(), ZeptoError>(())
+        }
+        .await;
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&tmp).await;
+        }
+        result.map(|()| Self::reference(name))
+    }
+
+    /// Resolve an `artifact://name` reference to its workspace-relative path.
+    pub fn resolve(&self, session: &str, reference: &str) -> Result<String> {
+        let name = reference.strip_prefix(ARTIFACT_SCHEME).unwrap_or(reference);
+        Self::validate_name(name)?;
+        let relative = Self::session_dir(session).join(name);
+        if !self.workspace.join(&relative).is_file() {
+            let available = self
+                .list(session)
+                .iter()
+                .map(|a| a.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(ZeptoError::NotFound(format!(
+                "Artifact '{}' not found in this session{}",
+                name,
+                if available.is_empty() {
+                    String::new()
+                } else {
+                    format!(" (available: {})", available)
+                }
+            )));
+        }
+        Ok(relative.to_string_lossy().into_owned())
+    }
+
+    /// Artifacts of `session`, sorted by name. Empty when there are none.
+    pub fn list(&self, session: &str) -> Vec<ArtifactInfo> {
+        let Ok(entries) = std::fs::read_dir(self.workspace.join(Self::session_dir(session))) else {
+            return Vec::new();
+        };
+        let mut artifacts: Vec<ArtifactInfo> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let meta = entry.metadata().ok()?;
+                (meta.is_file() && !name.starts_with('.')).then_some(ArtifactInfo {
+                    name,
+                    size: meta.len(),
+                })
+            })
+            .collect();
+        artifacts.sort_by(|a, b| a.name.cmp(&b.name));
+        artifacts
+    }
+
+    /// Delete all artifacts of `session`. Returns how many were removed.
+    pub async fn clear(&self, session: &str) -> Result<usize> {
+        let count = self.list(session).len();
+        let dir = self.workspace.join(Self::session_dir(session));
+        match tokio::fs::remove_dir_all(&dir).await {
+            Ok(()) => Ok(count),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// One-line inventory of `session`'s artifacts for the system prompt, or
+    /// `None` when there are none.
+    pub fn inventory(&self, session: &str) -> Option<String> {
+        let artifacts = self.list(session);
+        if artifacts.is_empty() {
+            return None;
+        }
+        let mut listed: Vec<String> = artifacts
+            .iter()
+            .take(INVENTORY_MAX_NAMES)
+            .map(|a| format!("{} ({})", a.name, format_size(a.size)))
+            .collect();
+        if artifacts.len() > INVENTORY_MAX_NAMES {
+            listed.push(format!(
+                "and {} more",
+                artifacts.len() - INVENTORY_MAX_NAMES
+            ));
+        }
+        Some(format!(
+            "Artifacts in this session (pass as {}<name> wherever a file path is accepted): {}",
+            ARTIFACT_SCHEME,
+            listed.join(", ")
+        ))
+    }
+}
+
+/// Human-readable byte size (B, KB, MB).
+pub fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_put_resolve_and_list() {
+        let dir = TempDir::new().unwrap();
+        let store = ArtifactStore::new(dir.path());
+        let reference = store
+            .put("telegram:42", "report.csv", b"a,b\n1,2\n")
+            .await
+            .unwrap();
+        assert_eq!(reference, "artifact://report.csv");
+
+        let relative = store.resolve("telegram:42", &reference).unwrap();
+        assert_eq!(relative, ".artifacts/telegram_42/report.csv");
+        assert_eq!(
+            std::fs::read(dir.path().join(&relative)).unwrap(),
+            b"a,b\n1,2\n"
+        );
+        assert_eq!(
+            store.list("telegram:42"),
+            vec![ArtifactInfo {
+                name: "report.csv".into(),
+                size: 8
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sessions_are_isolated() {
+        let dir = TempDir::new().unwrap();
+        let store = ArtifactStore::new(dir.path());
+        store.put("a", "x.txt", b"1").await.unwrap();
+        assert!(store.resolve("b", "artifact://x.txt").is_err());
+        assert!(store.list("b").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_names_rejected() {
+        let dir = TempDir::new().unwrap();
+        let store = ArtifactStore::new(dir.path());
+        for name in ["", "../x", "a/b", ".hidden", "sp ace"] {
+            assert!(store.put("s", name, b"x").await.is_err(), "{}", name);
+        }
+        assert!(store.resolve("s", "artifact://../secret").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_missing_artifact_lists_available() {
+        let dir = TempDir::new().unwrap();
+        let store = ArtifactStore::new(dir.path());
+        store.put("s", "one.txt", b"1").await.unwrap();
+        let err = store.resolve("s", "artifact://two.txt").unwrap_err();
+        assert!(err.to_string().contains("available: one.txt"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_inventory_and_clear() {
+        let dir = TempDir::new().unwrap();
+        let store = ArtifactStore::new(dir.path());
+        assert!(store.inventory("s").is_none());
+        store.put("s", "data.json", &[b'x'; 2048]).await.unwrap();
+        let inventory = store.inventory("s").unwrap();
+        assert!(inventory.contains("data.json (2.0 KB)"), "{}", inventory);
+        assert!(!inventory.contains('\n'));
+
+        assert_eq!(store.clear("s").await.unwrap(), 1);
+        assert!(store.inventory("s").is_none());
+        assert_eq!(store.clear("s").await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,305 @@
+//! Ask-user tool — asks the user a question mid-turn and waits for the answer.
+//!
+//! Unlike `ask_clarification`, which ends the turn, `ask_user` keeps the
+//! turn alive: the question goes to the originating chat over the
+//! [`MessageBus`], and the next message in that session is handed straight
+//! to the waiting call (see [`MessageBus::wait_for_reply`]) and returned as
+//! the tool result. Whatever the user sends counts as the answer, including
+//! a question of their own; the model can follow up with another
+//! `ask_user` call. Waiting is per session, so other chats keep running,
+//! and "stop" still ends the turn.
+//!
+//! No answer within the configured timeout gives a "no answer" result. In
+//! batch mode, and in the CLI where nothing can reply mid-turn, the tool
+//! falls back to ending the turn with the question like
+//! `ask_clarification`.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::bus::{MessageBus, OutboundMessage};
+use crate::config::AskUserToolConfig;
+use crate::error::{Result, ZeptoError};
+
+use super::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Channel whose replies are read only between turns.
+const CLI_CHANNEL: &str = "cli";
+
+/// Tool that asks the user a question and waits for the reply.
+pub struct AskUserTool {
+    bus: Arc<MessageBus>,
+    timeout: Duration,
+}
+
+impl AskUserTool {
+    /// Create the tool; it waits `config.timeout_secs` (at least 1s) for
+    /// each answer.
+    pub fn new(bus: Arc<MessageBus>, config: &AskUserToolConfig) -> Self {
+        Self {
+            bus,
+            timeout: Duration::from_secs(config.timeout_secs.max(1)),
+        }
+    }
+
+    /// Set how long to wait for an answer.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// The question as sent to the chat, with numbered options if any.
+fn format_question(question: &str, options: &[&str]) -> String {
+    let mut text = question.to_string();
+    if !options.is_empty() {
+        text.push('\n');
+        for (i, option) in options.iter().enumerate() {
+            text.push_str(&format!("\n{}. {}", i + 1, option));
+        }
+    }
+    text
+}
+
+#[async_trait]
+impl Tool for AskUserTool {
+    fn name(&self) -> &str {
+        "ask_user"
+    }
+
+    fn description(&self) -> &str {
+        "Ask the user a question and wait for their answer before continuing, e.g. \
+         \"Which environment: staging or prod?\". The answer is returned as the result \
+         and the task carries on in the same turn. Use only when you cannot proceed \
+         without the answer."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Ask user and wait for answer"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Messaging
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "question": {
+                    "type": "string",
+                    "description": "The question to ask the user"
+                },
+                "options": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Suggested answers, shown as a numbered list; the user may still answer freely"
+                }
+            },
+            "required": ["question"]
+        })
+    }
+
+    async fn dry_run(&self, _args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        // Asking changes nothing, and the answer may decide what gets done.
+        Ok(DryRun::Execute)
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let question = args
+            .get("question")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| ZeptoError::Tool("Missing 'question' parameter".to_string()))?;
+        let options: Vec<&str> = args
+            .get("options")
+            .and_then(Value::as_array)
+            .map(|arr| arr.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let text = format_question(question, &options);
+
+        if ctx.is_batch {
+            return Ok(ToolOutput::llm_only(
+                "No user is available to answer in batch mode. Proceed with your best judgment.",
+            ));
+        }
+        let (Some(channel), Some(chat_id)) = (ctx.channel.as_deref(), ctx.chat_id.as_deref())
+        else {
+            return Err(ZeptoError::Tool(
+                "ask_user needs a chat to ask in; none is set for this call".to_string(),
+            ));
+        };
+        if channel == CLI_CHANNEL {
+            return Ok(ToolOutput::split(
+                "Question shown to the user. Their answer will arrive as the next message.",
+                text,
+            )
+            .with_pause());
+        }
+        let session_key = ctx
+            .session_key
+            .clone()
+            .unwrap_or_else(|| format!("{}:{}", channel, chat_id));
+
+        // Register before sending so a quick answer cannot slip past.
+        let reply = self.bus.wait_for_reply(&session_key);
+        if let Err(e) = self
+            .bus
+            .publish_outbound(OutboundMessage::new(channel, chat_id, &text))
+            .await
+        {
+            self.bus.cancel_reply(&session_key);
+            return Err(ZeptoError::Tool(format!(
+                "The question could not be sent: {}",
+                e
+            )));
+        }
+        info!(session = %session_key, "Waiting for the user's answer");
+
+        let waited = ctx
+            .cancellable(async { Ok(tokio::time::timeout(self.timeout, reply).await) })
+            .await;
+        match waited {
+            Ok(Ok(Ok(answer))) => {
+                let mut result = format!("The user answered: {}", answer.content.trim());
+                if !answer.media.is_empty() {
+                    result.push_str(&format!(
+                        "\n({} attachment(s) were sent with the answer.)",
+                        answer.media.len()
+                    ));
+                }
+                Ok(ToolOutput::llm_only(result))
+            }
+            // Another question for this session replaced the waiter.
+            Ok(Ok(Err(_))) => Ok(ToolOutput::llm_only(
+                "The question was superseded by another question before the user answered.",
+            )),
+            Ok(Err(_)) => {
+                self.bus.cancel_reply(&session_key);
+                info!(session = %session_key, "No answer from the user");
+                Ok(ToolOutput::llm_only(format!(
+                    "No answer from the user within {}s. Proceed with your best judgment, \
+                     or tell the user what you need.",
+                    self.timeout.as_secs()
+                )))
+            }
+            Err(e) => {
+                self.bus.cancel_reply(&session_key);
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::InboundMessage;
+
+    fn tool(bus: &Arc<MessageBus>) -> AskUserTool {
+        AskUserTool::new(Arc::clone(bus), &AskUserToolConfig::default())
+    }
+
+    fn ctx(chat_id: &str) -> ToolContext {
+        ToolContext::new().with_channel("telegram", chat_id)
+    }
+
+    #[tokio::test]
+    async fn test_question_is_sent_and_next_message_is_the_answer() {
+        let bus = Arc::new(MessageBus::new());
+        let tool = tool(&bus);
+        let ask = tokio::spawn({
+            let args = json!({"question": "Which environment?", "options": ["staging", "prod"]});
+            async move { tool.execute(args, &ctx("chat1")).await }
+        });
+
+        let question = bus.consume_outbound().await.unwrap();
+        assert_eq!(question.chat_id, "chat1");
+        assert_eq!(
+            question.content,
+            "Which environment?\n\n1. staging\n2. prod"
+        );
+
+        // Other chats are queued as usual while chat1 waits.
+        bus.publish_inbound(InboundMessage::new("telegram", "u2", "chat2", "hi"))
+            .await
+            .unwrap();
+        // An answer that is itself a question still counts.
+        bus.publish_inbound(InboundMessage::new(
+            "telegram",
+            "u1",
+            "chat1",
+            "what's the difference?",
+        ))
+        .await
+        .unwrap();
+
+        let out = ask.await.unwrap().unwrap();
+        assert_eq!(out.for_llm, "The user answered: what's the difference?");
+        assert_eq!(bus.consume_inbound().await.unwrap().chat_id, "chat2");
+    }
+
+    #[tokio::test]
+    async fn test_no_answer_before_timeout() {
+        let bus = Arc::new(MessageBus::new());
+        let tool = tool(&bus).with_timeout(Duration::from_millis(50));
+
+        let out = tool
+            .execute(json!({"question": "Still there?"}), &ctx("chat1"))
+            .await
+            .unwrap();
+        assert!(out.for_llm.starts_with("No answer from the user"));
+
+        // The wait is over, so later messages start a new turn.
+        bus.publish_inbound(InboundMessage::new("telegram", "u1", "chat1", "late"))
+            .await
+            .unwrap();
+        assert_eq!(bus.consume_inbound().await.unwrap().content, "late");
+    }
+
+    #[tokio::test]
+    async fn test_stopping_the_turn_ends_the_wait() {
+        let bus = Arc::new(MessageBus::new());
+        let tool = tool(&bus);
+        let token = bus.begin_turn("telegram:chat1");
+        let ctx = ctx("chat1").with_cancellation(token);
+        let ask = tokio::spawn(async move { tool.execute(json!({"question": "Go?"}), &ctx).await });
+
+        bus.consume_outbound().await.unwrap();
+        bus.publish_inbound(InboundMessage::new("telegram", "u1", "chat1", "stop"))
+            .await
+            .unwrap();
+        let err = ask.await.unwrap().unwrap_err();
+        assert!(matches!(err, ZeptoError::Cancelled(_)));
+    }
+
+    #[tokio::test]
+    async fn test_cli_and_batch_fall_back_without_waiting() {
+        let bus = Arc::new(MessageBus::new());
+        let tool = tool(&bus);
+
+        let out = tool
+            .execute(
+                json!({"question": "Which file?"}),
+                &ToolContext::new().with_channel("cli", "cli"),
+            )
+            .await
+            .unwrap();
+        assert!(out.pause_for_input);
+        assert_eq!(out.for_user.as_deref(), Some("Which file?"));
+
+        let out = tool
+            .execute(
+                json!({"question": "Which file?"}),
+                &ctx("c").with_batch(true),
+            )
+            .await
+            .unwrap();
+        assert!(out.for_llm.contains("batch mode"));
+    }
+}
@@ -0,0 +1,795 @@
+//! Calculator tool — evaluates arithmetic expressions natively.
+//!
+//! Models are unreliable at arithmetic, and running a script through the
+//! shell for it is slow and needs a shell policy. This tool parses the
+//! expression with a small Pratt parser and evaluates it either in `f64`
+//! (default) or in exact 128-bit integer arithmetic. Division by zero,
+//! overflow and domain errors are reported instead of returning `inf`/`NaN`.
+//! The normalized, fully parenthesized expression is echoed back so the
+//! model can confirm how its input was read.
+
+use std::fmt;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::error::{Result, ZeptoError};
+use crate::tools::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Longest expression accepted, in characters.
+const MAX_EXPRESSION_LEN: usize = 1024;
+
+/// Deepest nesting of parentheses, unary operators and function calls.
+const MAX_DEPTH: usize = 64;
+
+/// Tool that evaluates an arithmetic expression.
+///
+/// # Parameters
+/// - `expression`: the expression, e.g. `sqrt(2) * (3 + 4)^2` (required)
+/// - `mode`: `"float"` (default) or `"integer"` for exact integer math
+pub struct CalcTool;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(String),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+}
+
+/// Parsed expression tree.
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    /// Numeric literal, kept as text so integer mode can parse it exactly.
+    Number(String),
+    /// Named constant (`pi`, `e`, `tau`).
+    Constant(String),
+    /// Unary minus.
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn operand(f: &mut fmt::Formatter<'_>, expr: &Expr) -> fmt::Result {
+            match expr {
+                Expr::Binary(..) | Expr::Neg(_) => write!(f, "({})", expr),
+                _ => write!(f, "{}", expr),
+            }
+        }
+        match self {
+            Expr::Number(text) | Expr::Constant(text) => write!(f, "{}", text),
+            Expr::Neg(inner) => {
+                write!(f, "-")?;
+                operand(f, inner)
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                operand(f, lhs)?;
+                write!(f, " {} ", op)?;
+                operand(f, rhs)
+            }
+            Expr::Call(name, args) => {
+                write!(f, "{}(", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+fn calc_error(message: impl Into<String>) -> ZeptoError {
+    ZeptoError::Tool(message.into())
+}
+
+/// Split `input` into tokens. `**` is accepted as an alias for `^`, and
+/// `_` may be used as a digit separator.
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '0'..='9' | '.' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_ascii_digit() || matches!(chars[i], '.' | '_'))
+                {
+                    i += 1;
+                }
+                // Exponent: 1e10, 2.5E-3
+                if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                    let mut j = i + 1;
+                    if j < chars.len() && matches!(chars[j], '+' | '-') {
+                        j += 1;
+                    }
+                    if j < chars.len() && chars[j].is_ascii_digit() {
+                        i = j;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+                let text: String = chars[start..i].iter().filter(|c| **c != '_').collect();
+                if text.matches('.').count() > 1 || text == "." {
+                    return Err(calc_error(format!("Invalid number '{}'", text)));
+                }
+                tokens.push(Token::Number(text));
+            }
+            c if c.is_ascii_alphabetic() => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let name: String = chars[start..i].iter().collect();
+                tokens.push(Token::Ident(name.to_ascii_lowercase()));
+            }
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                tokens.push(Token::Op('^'));
+                i += 2;
+            }
+            '+' | '-' | '*' | '/' | '%' | '^' => {
+                tokens.push(Token::Op(c));
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            other => {
+                return Err(calc_error(format!(
+                    "Unexpected character '{}' at position {}",
+                    other,
+                    i + 1
+                )))
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Binding powers for infix operators: `(left, right)`. `^` is
+/// right-associative and binds tighter than unary minus, so `-2^2 = -4`.
+fn infix_binding_power(op: char) -> (u8, u8) {
+    match op {
+        '+' | '-' => (1, 2),
+        '*' | '/' | '%' => (3, 4),
+        _ => (8, 7),
+    }
+}
+
+/// Binding power of unary minus/plus.
+const PREFIX_BINDING_POWER: u8 = 5;
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<()> {
+        match self.advance() {
+            Some(token) if token == expected => Ok(()),
+            _ => Err(calc_error(format!("Expected {}", what))),
+        }
+    }
+
+    fn parse_expr(&mut self, min_bp: u8, depth: usize) -> Result<Expr> {
+        if depth > MAX_DEPTH {
+            return Err(calc_error(format!(
+                "Expression is nested too deeply (max depth {})",
+                MAX_DEPTH
+            )));
+        }
+        let mut lhs = self.parse_prefix(depth)?;
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            let (left_bp, right_bp) = infix_binding_power(op);
+            if left_bp < min_bp {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.parse_expr(right_bp, depth + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_prefix(&mut self, depth: usize) -> Result<Expr> {
+        match self.advance() {
+            Some(Token::Number(text)) => Ok(Expr::Number(text)),
+            Some(Token::Op('-')) => Ok(Expr::Neg(Box::new(
+                self.parse_expr(PREFIX_BINDING_POWER, depth + 1)?,
+            ))),
+            Some(Token::Op('+')) => self.parse_expr(PREFIX_BINDING_POWER, depth + 1),
+            Some(Token::LParen) => {
+                let inner = self.parse_expr(0, depth + 1)?;
+                self.expect(Token::RParen, "')'")?;
+                Ok(inner)
+            }
+            Some(Token::Ident(name)) => {
+                if self.peek() != Some(&Token::LParen) {
+                    return match name.as_str() {
+                        "pi" | "e" | "tau" => Ok(Expr::Constant(name)),
+                        _ => Err(calc_error(format!(
+                            "Unknown constant '{}'. Known constants: pi, e, tau",
+                            name
+                        ))),
+                    };
+                }
+                self.pos += 1;
+                let mut args = Vec::new();
+                if self.peek() != Some(&Token::RParen) {
+                    loop {
+                        args.push(self.parse_expr(0, depth + 1)?);
+                        if self.peek() == Some(&Token::Comma) {
+                            self.pos += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                self.expect(Token::RParen, &format!("')' to close {}(", name))?;
+                Ok(Expr::Call(name, args))
+            }
+            Some(Token::Op(op)) => Err(calc_error(format!("Unexpected operator '{}'", op))),
+            Some(Token::RParen) => Err(calc_error("Unexpected ')'")),
+            Some(Token::Comma) => Err(calc_error("Unexpected ','")),
+            None => Err(calc_error("Unexpected end of expression")),
+        }
+    }
+}
+
+/// Parse an expression into a tree, enforcing the length and depth limits.
+fn parse(input: &str) -> Result<Expr> {
+    let length = input.chars().count();
+    if length > MAX_EXPRESSION_LEN {
+        return Err(calc_error(format!(
+            "Expression is {} characters; the limit is {}",
+            length, MAX_EXPRESSION_LEN
+        )));
+    }
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+    };
+    if parser.tokens.is_empty() {
+        return Err(calc_error("Expression is empty"));
+    }
+    let expr = parser.parse_expr(0, 0)?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(Token::RParen) => Err(calc_error("Unbalanced ')'")),
+        Some(token) => Err(calc_error(format!(
+            "Unexpected {:?} after a complete expression (implicit multiplication is not supported; use '*')",
+            token
+        ))),
+    }
+}
+
+fn check_arity(name: &str, args: &[Expr], expected: usize) -> Result<()> {
+    if args.len() != expected {
+        return Err(calc_error(format!(
+            "{}() takes {} argument{}, got {}",
+            name,
+            expected,
+            if expected == 1 { "" } else { "s" },
+            args.len()
+        )));
+    }
+    Ok(())
+}
+
+fn domain_error(expr: &Expr, reason: &str) -> ZeptoError {
+    calc_error(format!("Domain error in '{}': {}", expr, reason))
+}
+
+/// Evaluate in `f64`, rejecting non-finite intermediate results.
+fn eval_float(expr: &Expr) -> Result<f64> {
+    let value = match expr {
+        Expr::Number(text) => text
+            .parse::<f64>()
+            .map_err(|_| calc_error(format!("Invalid number '{}'", text)))?,
+        Expr::Constant(name) => match name.as_str() {
+            "pi" => std::f64::consts::PI,
+            "e" => std::f64::consts::E,
+            _ => std::f64::consts::TAU,
+        },
+        Expr::Neg(inner) => -eval_float(inner)?,
+        Expr::Binary(op, lhs, rhs) => {
+            let (a, b) = (eval_float(lhs)?, eval_float(rhs)?);
+            match op {
+                '+' => a + b,
+                '-' => a - b,
+                '*' => a * b,
+                '/' | '%' if b == 0.0 => {
+                    return Err(calc_error(format!("Division by zero in '{}'", expr)))
+                }
+                '/' => a / b,
+                '%' => a % b,
+                _ => {
+                    if a < 0.0 && b.fract() != 0.0 {
+                        return Err(domain_error(
+                            expr,
+                            "negative base with a fractional exponent",
+                        ));
+                    }
+                    a.powf(b)
+                }
+            }
+        }
+        Expr::Call(name, args) => eval_float_call(expr, name, args)?,
+    };
+    if value.is_nan() {
+        return Err(calc_error(format!("'{}' is undefined", expr)));
+    }
+    if value.is_infinite() {
+        return Err(calc_error(format!(
+            "Overflow in '{}': the result exceeds the f64 range",
+            expr
+        )));
+    }
+    Ok(value)
+}
+
+fn eval_float_call(expr: &Expr, name: &str, args: &[Expr]) -> Result<f64> {
+    let unary = |f: fn(f64) -> f64| -> Result<f64> {
+        check_arity(name, args, 1)?;
+        Ok(f(eval_float(&args[0])?))
+    };
+    let binary = |f: fn(f64, f64) -> f64| -> Result<f64> {
+        check_arity(name, args, 2)?;
+        Ok(f(eval_float(&args[0])?, eval_float(&args[1])?))
+    };
+    let positive = |x: f64| -> Result<f64> {
+        if x <= 0.0 {
+            Err(domain_error(expr, "argument must be greater than 0"))
+        } else {
+            Ok(x)
+        }
+    };
+    match name {
+        "sqrt" => {
+            check_arity(name, args, 1)?;
+            let x = eval_float(&args[0])?;
+            if x < 0.0 {
+                return Err(domain_error(expr, "square root of a negative number"));
+            }
+            Ok(x.sqrt())
+        }
+        "cbrt" => unary(f64::cbrt),
+        "abs" => unary(f64::abs),
+        "exp" => unary(f64::exp),
+        "ln" => {
+            check_arity(name, args, 1)?;
+            Ok(positive(eval_float(&args[0])?)?.ln())
+        }
+        "log10" => {
+            check_arity(name, args, 1)?;
+            Ok(positive(eval_float(&args[0])?)?.log10())
+        }
+        "log2" => {
+            check_arity(name, args, 1)?;
+            Ok(positive(eval_float(&args[0])?)?.log2())
+        }
+        "log" => match args.len() {
+            1 => Ok(positive(eval_float(&args[0])?)?.log10()),
+            2 => {
+                let x = positive(eval_float(&args[0])?)?;
+                let base = positive(eval_float(&args[1])?)?;
+                if base == 1.0 {
+                    return Err(domain_error(expr, "logarithm base cannot be 1"));
+                }
+                Ok(if base == 2.0 {
+                    x.log2()
+                } else if base == 10.0 {
+                    x.log10()
+                } else {
+                    x.log(base)
+                })
+            }
+            n => Err(calc_error(format!(
+                "log() takes 1 or 2 arguments (value, base), got {}",
+                n
+            ))),
+        },
+        "sin" => unary(f64::sin),
+        "cos" => unary(f64::cos),
+        "tan" => unary(f64::tan),
+        "asin" | "acos" => {
+            check_arity(name, args, 1)?;
+            let x = eval_float(&args[0])?;
+            if !(-1.0..=1.0).contains(&x) {
+                return Err(domain_error(expr, "argument must be between -1 and 1"));
+            }
+            Ok(if name == "asin" { x.asin() } else { x.acos() })
+        }
+        "atan" => unary(f64::atan),
+        "atan2" => binary(f64::atan2),
+        "sinh" => unary(f64::sinh),
+        "cosh" => unary(f64::cosh),
+        "tanh" => unary(f64::tanh),
+        "radians" => unary(f64::to_radians),
+        "degrees" => unary(f64::to_degrees),
+        "floor" => unary(f64::floor),
+        "ceil" => unary(f64::ceil),
+        "round" => unary(f64::round),
+        "trunc" => unary(f64::trunc),
+        "hypot" => binary(f64::hypot),
+        "pow" => {
+            check_arity(name, args, 2)?;
+            let (a, b) = (eval_float(&args[0])?, eval_float(&args[1])?);
+            if a < 0.0 && b.fract() != 0.0 {
+                return Err(domain_error(
+                    expr,
+                    "negative base with a fractional exponent",
+                ));
+            }
+            Ok(a.powf(b))
+        }
+        "min" | "max" => {
+            if args.is_empty() {
+                return Err(calc_error(format!(
+                    "{}() needs at least one argument",
+                    name
+                )));
+            }
+            let values = args.iter().map(eval_float).collect::<Result<Vec<_>>>()?;
+            let pick = if name == "min" { f64::min } else { f64::max };
+            Ok(values.into_iter().reduce(pick).unwrap_or_default())
+        }
+        _ => Err(unknown_function(name)),
+    }
+}
+
+fn unknown_function(name: &str) -> ZeptoError {
+    calc_error(format!(
+        "Unknown function '{}'. Available: sqrt, cbrt, abs, exp, ln, log, log10, log2, \
+         sin, cos, tan, asin, acos, atan, atan2, sinh, cosh, tanh, radians, degrees, \
+         floor, ceil, round, trunc, hypot, pow, min, max",
+        name
+    ))
+}
+
+/// Evaluate in exact `i128` arithmetic. `/` truncates toward zero.
+fn eval_integer(expr: &Expr) -> Result<i128> {
+    let overflow = || calc_error(format!("Integer overflow in '{}'", expr));
+    match expr {
+        Expr::Number(text) => text.parse::<i128>().map_err(|_| {
+            if text.contains(['.', 'e', 'E']) {
+                calc_error(format!(
+                    "'{}' is not an integer; use float mode for decimals",
+                    text
+                ))
+            } else {
+                overflow()
+            }
+        }),
+        Expr::Constant(name) => Err(calc_error(format!(
+            "Constant '{}' is not available in integer mode",
+            name
+        ))),
+        Expr::Neg(inner) => eval_integer(inner)?.checked_neg().ok_or_else(overflow),
+        Expr::Binary(op, lhs, rhs) => {
+            let (a, b) = (eval_integer(lhs)?, eval_integer(rhs)?);
+            match op {
+                '+' => a.checked_add(b).ok_or_else(overflow),
+                '-' => a.checked_sub(b).ok_or_else(overflow),
+                '*' => a.checked_mul(b).ok_or_else(overflow),
+                '/' | '%' if b == 0 => Err(calc_error(format!("Division by zero in '{}'", expr))),
+                '/' => a.checked_div(b).ok_or_else(overflow),
+                '%' => a.checked_rem(b).ok_or_else(overflow),
+                _ => integer_pow(expr, a, b),
+            }
+        }
+        Expr::Call(name, args) => match name.as_str() {
+            "abs" => {
+                check_arity(name, args, 1)?;
+                eval_integer(&args[0])?.checked_abs().ok_or_else(overflow)
+            }
+            "pow" => {
+                check_arity(name, args, 2)?;
+                integer_pow(expr, eval_integer(&args[0])?, eval_integer(&args[1])?)
+            }
+            "min" | "max" => {
+                if args.is_empty() {
+                    return Err(calc_error(format!(
+                        "{}() needs at least one argument",
+                        name
+                    )));
+                }
+                let values = args.iter().map(eval_integer).collect::<Result<Vec<_>>>()?;
+                let result = if name == "min" {
+                    values.into_iter().min()
+                } else {
+                    values.into_iter().max()
+                };
+                Ok(result.unwrap_or_default())
+            }
+            _ => Err(calc_error(format!(
+                "Function '{}' is not available in integer mode (abs, pow, min, max are)",
+                name
+            ))),
+        },
+    }
+}
+
+fn integer_pow(expr: &Expr, base: i128, exponent: i128) -> Result<i128> {
+    if exponent < 0 {
+        return Err(domain_error(
+            expr,
+            "negative exponents are not supported in integer mode",
+        ));
+    }
+    let exponent = u32::try_from(exponent)
+        .map_err(|_| calc_error(format!("Integer overflow in '{}'", expr)))?;
+    base.checked_pow(exponent)
+        .ok_or_else(|| calc_error(format!("Integer overflow in '{}'", expr)))
+}
+
+/// Render a float result without a trailing `.0` for whole numbers and in
+/// scientific notation when very large or small.
+fn format_float(value: f64) -> String {
+    let magnitude = value.abs();
+    if value == 0.0 {
+        "0".to_string()
+    } else if !(1e-6..1e16).contains(&magnitude) {
+        format!("{:e}", value)
+    } else if value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{}", value)
+    }
+}
+
+/// Parse and evaluate `input`, returning the normalized expression and the
+/// formatted result. Exposed for reuse and testing.
+pub fn evaluate(input: &str, integer_mode: bool) -> Result<(String, String)> {
+    let expr = parse(input)?;
+    let result = if integer_mode {
+        eval_integer(&expr)?.to_string()
+    } else {
+        format_float(eval_float(&expr)?)
+    };
+    Ok((expr.to_string(), result))
+}
+
+#[async_trait]
+impl Tool for CalcTool {
+    fn name(&self) -> &str {
+        "calc"
+    }
+
+    fn description(&self) -> &str {
+        "Evaluate an arithmetic expression exactly instead of doing math in your head or via \
+         the shell. Supports + - * / % ^, parentheses, constants pi/e/tau and functions such \
+         as sqrt, pow, ln, log, sin, cos, tan, min, max (trig in radians). Use mode \"integer\" \
+         for exact big-integer math. Returns the normalized expression and the result."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Evaluate arithmetic expression"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Memory
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": {
+                    "type": "string",
+                    "description": "Expression to evaluate, e.g. \"sqrt(2) * (3 + 4)^2\""
+                },
+                "mode": {
+                    "type": "string",
+                    "enum": ["float", "integer"],
+                    "description": "float (default) or integer for exact 128-bit integer arithmetic (/ truncates)"
+                }
+            },
+            "required": ["expression"]
+        })
+    }
+
+    async fn dry_run(&self, _args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        // Pure computation.
+        Ok(DryRun::Execute)
+    }
+
+    async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
+        let expression = args
+            .get("expression")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ZeptoError::Tool("Missing required field: expression".into()))?;
+        let mode = args.get("mode").and_then(Value::as_str).unwrap_or("float");
+        let integer_mode = match mode {
+            "float" => false,
+            "integer" => true,
+            other => {
+                return Err(ZeptoError::Tool(format!(
+                    "Unknown mode '{}'. Expected one of: float, integer",
+                    other
+                )))
+            }
+        };
+
+        let (normalized, result) = evaluate(expression, integer_mode)?;
+        Ok(ToolOutput::llm_only(
+            json!({
+                "expression": normalized,
+                "result": result,
+                "mode": mode,
+            })
+            .to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn float(input: &str) -> Result<String> {
+        evaluate(input, false).map(|(_, result)| result)
+    }
+
+    fn integer(input: &str) -> Result<String> {
+        evaluate(input, true).map(|(_, result)| result)
+    }
+
+    #[test]
+    fn test_precedence_and_associativity() {
+        assert_eq!(float("2 + 3 * 4").unwrap(), "14");
+        assert_eq!(float("(2 + 3) * 4").unwrap(), "20");
+        assert_eq!(float("2 ^ 3 ^ 2").unwrap(), "512");
+        assert_eq!(float("-2 ^ 2").unwrap(), "-4");
+        assert_eq!(float("2 ** -1").unwrap(), "0.5");
+        assert_eq!(float("10 - 4 - 3").unwrap(), "3");
+        assert_eq!(float("7 % 4").unwrap(), "3");
+    }
+
+    #[test]
+    fn test_normalized_expression() {
+        let (normalized, _) = evaluate("2+3*4", false).unwrap();
+        assert_eq!(normalized, "2 + (3 * 4)");
+        let (normalized, _) = evaluate("-sqrt(16)**2", false).unwrap();
+        assert_eq!(normalized, "-(sqrt(16) ^ 2)");
+    }
+
+    #[test]
+    fn test_functions_and_constants() {
+        assert_eq!(float("sqrt(16) + pow(2, 10)").unwrap(), "1028");
+        assert_eq!(float("ln(e)").unwrap(), "1");
+        assert_eq!(float("log(1000)").unwrap(), "3");
+        assert_eq!(float("log(8, 2)").unwrap(), "3");
+        assert_eq!(float("round(sin(pi / 2))").unwrap(), "1");
+        assert_eq!(float("max(1, 5, 3) - min(4, 2)").unwrap(), "3");
+        assert_eq!(float("1_000 * 1.5e3").unwrap(), "1500000");
+    }
+
+    #[test]
+    fn test_float_errors() {
+        assert!(float("1 / 0")
+            .unwrap_err()
+            .to_string()
+            .contains("Division by zero"));
+        assert!(float("5 % 0")
+            .unwrap_err()
+            .to_string()
+            .contains("Division by zero"));
+        assert!(float("10 ^ 400")
+            .unwrap_err()
+            .to_string()
+            .contains("Overflow"));
+        assert!(float("sqrt(-1)")
+            .unwrap_err()
+            .to_string()
+            .contains("Domain error"));
+        assert!(float("ln(0)")
+            .unwrap_err()
+            .to_string()
+            .contains("Domain error"));
+        assert!(float("foo(1)")
+            .unwrap_err()
+            .to_string()
+            .contains("Unknown function"));
+        assert!(float("sqrt(1, 2)").is_err());
+    }
+
+    #[test]
+    fn test_integer_mode() {
+        assert_eq!(
+            integer("2 ^ 100").unwrap(),
+            "1267650600228229401496703205376"
+        );
+        assert_eq!(integer("7 / 2").unwrap(), "3");
+        assert_eq!(integer("-7 % 3").unwrap(), "-1");
+        assert!(integer("2 ^ 200")
+            .unwrap_err()
+            .to_string()
+            .contains("overflow"));
+        assert!(integer("1 / 0")
+            .unwrap_err()
+            .to_string()
+            .contains("Division by zero"));
+        assert!(integer("1.5 + 1")
+            .unwrap_err()
+            .to_string()
+            .contains("not an integer"));
+        assert!(integer("sqrt(4)").is_err());
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert!(float("").is_err());
+        assert!(float("(1 + 2").is_err());
+        assert!(float("1 + 2)").is_err());
+        assert!(float("2 (3)").unwrap_err().to_string().contains("implicit"));
+        assert!(float("1 + $").is_err());
+        assert!(float("1..2").is_err());
+    }
+
+    #[test]
+    fn test_limits() {
+        let long = "1+".repeat(MAX_EXPRESSION_LEN) + "1";
+        assert!(float(&long).unwrap_err().to_string().contains("limit"));
+        let deep = "(".repeat(MAX_DEPTH + 1) + "1" + &")".repeat(MAX_DEPTH + 1);
+        assert!(float(&deep).unwrap_err().to_string().contains("nested"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_returns_json() {
+        let out = CalcTool
+            .execute(
+                json!({"expression": "6 * 7", "mode": "integer"}),
+                &ToolContext::new(),
+            )
+            .await
+            .unwrap();
+        let value: Value = serde_json::from_str(&out.for_llm).unwrap();
+        assert_eq!(value["result"], "42");
+        assert_eq!(value["expression"], "6 * 7");
+        assert_eq!(value["mode"], "integer");
+    }
+
+    #[tokio::test]
+    async fn test_execute_rejects_unknown_mode() {
+        let err = CalcTool
+            .execute(
+                json!({"expression": "1", "mode": "complex"}),
+                &ToolContext::new(),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Unknown mode"));
+    }
+}
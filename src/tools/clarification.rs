@@ -4,7 +4,7 @@ use async_trait::async_trait;
 use serde_json::{json, Value};
 
 use crate::error::Result;
-use crate::tools::{Tool, ToolCategory, ToolContext, ToolOutput};
+use crate::tools::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
 
 /// Tool that pauses agent execution to ask the user for clarification.
 pub struct AskClarificationTool;
@@ -54,6 +54,11 @@ impl Tool for AskClarificationTool {
         })
     }
 
+    async fn dry_run(&self, _args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        // Asking changes nothing.
+        Ok(DryRun::Execute)
+    }
+
     async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
         // Batch mode fallback — no interactive user
         if ctx.is_batch {
@@ -30,7 +30,7 @@ use tracing::{debug, info, warn};
 
 use crate::error::{Result, ZeptoError};
 
-use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+use super::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
 
 // ---------------------------------------------------------------------------
 // Data model
@@ -198,6 +198,11 @@ impl Tool for ComposedTool {
         })
     }
 
+    async fn dry_run(&self, _args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        // Only returns instructions; the tools they name have their own dry run.
+        Ok(DryRun::Execute)
+    }
+
     async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
         // Extract string values from args
         let string_args: HashMap<String, String> = if let Some(obj) = args.as_object() {
@@ -502,6 +507,11 @@ impl Tool for CreateToolTool {
         })
     }
 
+    async fn dry_run(&self, _args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        // Only returns instructions; the tools they name have their own dry run.
+        Ok(DryRun::Execute)
+    }
+
     async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
         let action = args
             .get("action")
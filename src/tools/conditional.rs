@@ -0,0 +1,189 @@
+//! Conditional tool — evaluates a simple condition and returns a boolean.
+//!
+//! A routing primitive for declarative workflows: the LLM evaluates a
+//! condition through this tool and decides what to do next based on the
+//! result, without any custom Rust code per workflow.
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::error::{Result, ZeptoError};
+use crate::tools::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Maximum regex pattern length accepted from the LLM.
+const MAX_PATTERN_LEN: usize = 1024;
+
+/// Tool that evaluates a single condition and returns `{ "result": bool }`.
+pub struct ConditionalTool;
+
+/// Render a JSON value as the string operand for text comparisons.
+fn as_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Coerce a JSON value (number or numeric string) into an `f64`.
+fn as_number(value: &Value, field: &str) -> Result<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| ZeptoError::Tool(format!("'{}' must be a number, got {}", field, value)))
+}
+
+/// Evaluate a condition. Exposed for reuse and testing.
+pub fn evaluate(condition_type: &str, value: &Value, expected: &Value) -> Result<bool> {
+    match condition_type {
+        "string_equals" => Ok(as_text(value) == as_text(expected)),
+        "contains" => Ok(as_text(value).contains(&as_text(expected))),
+        "numeric_gt" => Ok(as_number(value, "value")? > as_number(expected, "expected")?),
+        "numeric_lt" => Ok(as_number(value, "value")? < as_number(expected, "expected")?),
+        "regex_match" => {
+            let pattern = as_text(expected);
+            if pattern.len() > MAX_PATTERN_LEN {
+                return Err(ZeptoError::Tool(format!(
+                    "Regex pattern too long ({} chars, max {})",
+                    pattern.len(),
+                    MAX_PATTERN_LEN
+                )));
+            }
+            let re = regex::Regex::new(&pattern)
+                .map_err(|e| ZeptoError::Tool(format!("Invalid regex '{}': {}", pattern, e)))?;
+            Ok(re.is_match(&as_text(value)))
+        }
+        other => Err(ZeptoError::Tool(format!(
+            "Unknown condition_type '{}'. Expected one of: string_equals, contains, numeric_gt, numeric_lt, regex_match",
+            other
+        ))),
+    }
+}
+
+#[async_trait]
+impl Tool for ConditionalTool {
+    fn name(&self) -> &str {
+        "conditional"
+    }
+
+    fn description(&self) -> &str {
+        "Evaluate a condition (string_equals, contains, numeric_gt, numeric_lt, regex_match) \
+         on a value and return {\"result\": true|false}. Use it to branch workflow steps."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Evaluate a condition, return boolean"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Memory
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "condition_type": {
+                    "type": "string",
+                    "enum": ["string_equals", "contains", "numeric_gt", "numeric_lt", "regex_match"],
+                    "description": "Comparison to perform"
+                },
+                "value": {
+                    "description": "The value to test (string or number)"
+                },
+                "expected": {
+                    "description": "The value to compare against (for regex_match: the pattern)"
+                }
+            },
+            "required": ["condition_type", "value", "expected"]
+        })
+    }
+
+    async fn dry_run(&self, _args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        // Pure evaluation.
+        Ok(DryRun::Execute)
+    }
+
+    async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
+        let condition_type = args
+            .get("condition_type")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ZeptoError::Tool("Missing required field: condition_type".into()))?;
+        let value = args
+            .get("value")
+            .ok_or_else(|| ZeptoError::Tool("Missing required field: value".into()))?;
+        let expected = args
+            .get("expected")
+            .ok_or_else(|| ZeptoError::Tool("Missing required field: expected".into()))?;
+
+        let result = evaluate(condition_type, value, expected)?;
+        Ok(ToolOutput::llm_only(
+            json!({ "result": result }).to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn run(args: Value) -> Result<ToolOutput> {
+        ConditionalTool.execute(args, &ToolContext::new()).await
+    }
+
+    #[test]
+    fn test_name_and_category() {
+        assert_eq!(ConditionalTool.name(), "conditional");
+        assert_eq!(ConditionalTool.category(), ToolCategory::Memory);
+    }
+
+    #[test]
+    fn test_string_conditions() {
+        assert!(evaluate("string_equals", &json!("ok"), &json!("ok")).unwrap());
+        assert!(!evaluate("string_equals", &json!("ok"), &json!("OK")).unwrap());
+        assert!(evaluate("contains", &json!("build passed"), &json!("passed")).unwrap());
+        assert!(!evaluate("contains", &json!("build failed"), &json!("passed")).unwrap());
+    }
+
+    #[test]
+    fn test_numeric_conditions_accept_numeric_strings() {
+        assert!(evaluate("numeric_gt", &json!(10), &json!("5")).unwrap());
+        assert!(evaluate("numeric_lt", &json!("2.5"), &json!(3)).unwrap());
+        assert!(!evaluate("numeric_gt", &json!(1), &json!(1)).unwrap());
+        assert!(evaluate("numeric_gt", &json!("abc"), &json!(1)).is_err());
+    }
+
+    #[test]
+    fn test_regex_match() {
+        assert!(evaluate("regex_match", &json!("v1.2.3"), &json!(r"^v\d+\.\d+\.\d+$")).unwrap());
+        assert!(!evaluate("regex_match", &json!("latest"), &json!(r"^v\d+")).unwrap());
+        assert!(evaluate("regex_match", &json!("x"), &json!("(")).is_err());
+    }
+
+    #[test]
+    fn test_unknown_condition_type() {
+        assert!(evaluate("between", &json!(1), &json!(2)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_returns_json_result() {
+        let out = run(json!({
+            "condition_type": "string_equals",
+            "value": "yes",
+            "expected": "yes"
+        }))
+        .await
+        .unwrap();
+        assert_eq!(out.for_llm, r#"{"result":true}"#);
+        assert!(out.for_user.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_execute_missing_field() {
+        let err = run(json!({"condition_type": "contains", "value": "x"}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("expected"));
+    }
+}
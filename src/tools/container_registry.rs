@@ -0,0 +1,754 @@
+//! Container registry tool — image metadata from Docker Hub and GHCR.
+//!
+//! Talks to the registries' HTTP API (Distribution v2) directly, so no
+//! Docker daemon is needed. Every request uses a short-lived pull token from
+//! the registry's token endpoint; configured credentials are only needed for
+//! private repositories and higher Docker Hub rate limits.
+//!
+//! Vulnerability counts come from Snyk and only cover images that are already
+//! monitored in the configured Snyk organization (`snyk container monitor`).
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use reqwest::header::{ACCEPT, LINK};
+use reqwest::{Client, Url};
+use serde_json::{json, Value};
+
+use crate::config::ContainerRegistryToolConfig;
+use crate::error::{Result, ZeptoError};
+
+use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+
+const SNYK_API_BASE: &str = "https://api.snyk.io/v1";
+
+/// How long tag lists are served from the in-memory cache.
+const TAG_CACHE_TTL: Duration = Duration::from_secs(60);
+/// Most images whose tag lists are cached at once; the oldest entry makes
+/// room for a new one.
+const TAG_CACHE_CAPACITY: usize = 64;
+/// Upper bound on tag-list pages followed per image.
+const MAX_TAG_PAGES: usize = 10;
+const DEFAULT_TAG_LIMIT: usize = 100;
+const DEFAULT_PLATFORM: &str = "linux/amd64";
+
+const MANIFEST_ACCEPT: &str = "application/vnd.oci.image.index.v1+json, \
+     application/vnd.docker.distribution.manifest.list.v2+json, \
+     application/vnd.oci.image.manifest.v1+json, \
+     application/vnd.docker.distribution.manifest.v2+json";
+
+/// Supported registries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Registry {
+    DockerHub,
+    Ghcr,
+}
+
+impl Registry {
+    fn api_base(self) -> &'static str {
+        match self {
+            Registry::DockerHub => "https://registry-1.docker.io",
+            Registry::Ghcr => "https://ghcr.io",
+        }
+    }
+
+    fn token_url(self, repository: &str) -> String {
+        match self {
+            Registry::DockerHub => format!(
+                "https://auth.docker.io/token?service=registry.docker.io&scope=repository:{}:pull",
+                repository
+            ),
+            Registry::Ghcr => format!(
+                "https://ghcr.io/token?service=ghcr.io&scope=repository:{}:pull",
+                repository
+            ),
+        }
+    }
+}
+
+/// A parsed image reference such as `nginx:1.27` or `ghcr.io/org/app@sha256:...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageRef {
+    pub registry: Registry,
+    /// Repository path as the registry API expects it (`library/nginx`).
+    pub repository: String,
+    /// Tag or digest; `latest` when omitted.
+    pub reference: String,
+}
+
+impl ImageRef {
+    /// Parse an image reference. Only Docker Hub and GHCR are supported.
+    pub fn parse(image: &str) -> Result<Self> {
+        let image = image.trim();
+        if image.is_empty() {
+            return Err(ZeptoError::Tool("Image reference is empty".into()));
+        }
+
+        let (registry, path) = match image.split_once('/') {
+            Some(("ghcr.io", rest)) => (Registry::Ghcr, rest),
+            Some(("docker.io" | "index.docker.io" | "registry-1.docker.io", rest)) => {
+                (Registry::DockerHub, rest)
+            }
+            Some((host, _)) if host.contains('.') || host.contains(':') || host == "localhost" => {
+                return Err(ZeptoError::Tool(format!(
+                    "Unsupported registry '{}': only Docker Hub and ghcr.io are supported",
+                    host
+                )));
+            }
+            _ => (Registry::DockerHub, image),
+        };
+
+        let (name, reference) = if let Some((name, digest)) = path.split_once('@') {
+            (name, digest.to_string())
+        } else {
+            // A ':' after the last '/' separates the tag.
+            let last_slash = path.rfind('/').map_or(0, |i| i + 1);
+            match path[last_slash..].rfind(':') {
+                Some(i) => (
+                    &path[..last_slash + i],
+                    path[last_slash + i + 1..].to_string(),
+                ),
+                None => (path, "latest".to_string()),
+            }
+        };
+        if name.is_empty() || reference.is_empty() {
+            return Err(ZeptoError::Tool(format!(
+                "Invalid image reference '{}'",
+                image
+            )));
+        }
+
+        let repository = if registry == Registry::DockerHub && !name.contains('/') {
+            format!("library/{}", name)
+        } else {
+            name.to_string()
+        };
+        Ok(Self {
+            registry,
+            repository,
+            reference,
+        })
+    }
+
+    /// Name as users write it (`nginx`, `grafana/loki`, `ghcr.io/org/app`).
+    pub fn display_name(&self) -> String {
+        match self.registry {
+            Registry::DockerHub => self
+                .repository
+                .strip_prefix("library/")
+                .unwrap_or(&self.repository)
+                .to_string(),
+            Registry::Ghcr => format!("ghcr.io/{}", self.repository),
+        }
+    }
+}
+
+/// Extract the next-page reference from a registry `Link` header
+/// (`</v2/x/tags/list?last=b&n=100>; rel="next"`).
+fn next_link(header: &str) -> Option<String> {
+    header
+        .split(',')
+        .find(|part| part.contains("rel=\"next\""))
+        .and_then(|part| {
+            let start = part.find('<')? + 1;
+            let end = part.find('>')?;
+            Some(part[start..end].to_string())
+        })
+}
+
+/// Resolve the next page named by a `Link` header against the URL of the
+/// page that sent it. The reference may be relative or absolute; pages on
+/// another origin are not followed, since requests carry the pull token.
+fn next_page(current: &Url, header: &str) -> Option<Url> {
+    let next = current.join(&next_link(header)?).ok()?;
+    (next.origin() == current.origin()).then_some(next)
+}
+
+/// Pick the manifest digest for `platform` (`os/arch[/variant]`) from an
+/// image index. Returns the digest and the list of available platforms.
+fn select_platform(index: &Value, platform: &str) -> (Option<String>, Vec<String>) {
+    let mut available = Vec::new();
+    let mut selected = None;
+    for entry in index
+        .get("manifests")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let p = entry.get("platform");
+        let field = |k: &str| p.and_then(|p| p.get(k)).and_then(Value::as_str);
+        let (Some(os), Some(arch)) = (field("os"), field("architecture")) else {
+            continue;
+        };
+        // Attestation manifests are listed with platform unknown/unknown.
+        if os == "unknown" {
+            continue;
+        }
+        let name = match field("variant") {
+            Some(variant) => format!("{}/{}/{}", os, arch, variant),
+            None => format!("{}/{}", os, arch),
+        };
+        if selected.is_none() && (name == platform || format!("{}/{}", os, arch) == platform) {
+            selected = entry
+                .get("digest")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+        }
+        available.push(name);
+    }
+    (selected, available)
+}
+
+/// Summarize an image manifest: layers and total compressed size.
+fn summarize_manifest(manifest: &Value) -> Value {
+    let layers: Vec<Value> = manifest
+        .get("layers")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|l| {
+            json!({
+                "digest": l.get("digest").cloned().unwrap_or(Value::Null),
+                "size": l.get("size").and_then(Value::as_u64).unwrap_or(0),
+                "media_type": l.get("mediaType").cloned().unwrap_or(Value::Null),
+            })
+        })
+        .collect();
+    let total: u64 = layers.iter().filter_map(|l| l["size"].as_u64()).sum();
+    json!({
+        "media_type": manifest.get("mediaType").cloned().unwrap_or(Value::Null),
+        "config_digest": manifest.pointer("/config/digest").cloned().unwrap_or(Value::Null),
+        "layer_count": layers.len(),
+        "total_size_bytes": total,
+        "total_size_mb": (total as f64 / (1024.0 * 1024.0) * 10.0).round() / 10.0,
+        "layers": layers,
+    })
+}
+
+/// Tool for inspecting container images on Docker Hub and GHCR.
+pub struct ContainerRegistryTool {
+    config: ContainerRegistryToolConfig,
+    client: Client,
+    tag_cache: Mutex<HashMap<String, (Instant, Vec<String>)>>,
+}
+
+impl ContainerRegistryTool {
+    /// Create a new container registry tool.
+    pub fn new(config: ContainerRegistryToolConfig) -> Self {
+        Self {
+            config,
+            client: Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap_or_default(),
+            tag_cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn credentials(&self, registry: Registry) -> Option<(&str, &str)> {
+        let (user, token) = match registry {
+            Registry::DockerHub => (
+                &self.config.dockerhub_username,
+                &self.config.dockerhub_token,
+            ),
+            Registry::Ghcr => (&self.config.ghcr_username, &self.config.ghcr_token),
+        };
+        let token = token.as_deref().filter(|t| !t.trim().is_empty())?;
+        // GHCR accepts any username with a personal access token.
+        let user = user
+            .as_deref()
+            .filter(|u| !u.trim().is_empty())
+            .unwrap_or("token");
+        Some((user, token))
+    }
+
+    async fn pull_token(&self, image: &ImageRef) -> Result<String> {
+        let mut request = self.client.get(image.registry.token_url(&image.repository));
+        if let Some((user, token)) = self.credentials(image.registry) {
+            request = request.basic_auth(user, Some(token));
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(ZeptoError::Tool(format!(
+                "Registry auth for '{}' failed with HTTP {}",
+                image.display_name(),
+                status
+            )));
+        }
+        let body: Value = response.json().await?;
+        body.get("token")
+            .or_else(|| body.get("access_token"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| ZeptoError::Tool("Registry auth response had no token".into()))
+    }
+
+    async fn registry_get(
+        &self,
+        image: &ImageRef,
+        token: &str,
+        url: &str,
+        accept: Option<&str>,
+    ) -> Result<reqwest::Response> {
+        let mut request = self.client.get(url).bearer_auth(token);
+        if let Some(accept) = accept {
+            request = request.header(ACCEPT, accept);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(ZeptoError::NotFound(format!(
+                "image '{}'",
+                image.display_name()
+            )));
+        }
+        if !status.is_success() {
+            return Err(ZeptoError::Tool(format!(
+                "Registry request for '{}' failed with HTTP {}",
+                image.display_name(),
+                status
+            )));
+        }
+        Ok(response)
+    }
+
+    async fn tags(&self, image: &ImageRef) -> Result<Vec<String>> {
+        let cache_key = format!("{:?}/{}", image.registry, image.repository);
+        if let Ok(cache) = self.tag_cache.lock() {
+            if let Some((fetched, tags)) = cache.get(&cache_key) {
+                if fetched.elapsed() < TAG_CACHE_TTL {
+                    return Ok(tags.clone());
+                }
+            }
+        }
+
+        let token = self.pull_token(image).await?;
+        let mut tags = Vec::new();
+        let first = format!(
+            "{}/v2/{}/tags/list?n=1000",
+            image.registry.api_base(),
+            image.repository
+        );
+        let mut url = Some(
+            Url::parse(&first)
+                .map_err(|e| ZeptoError::Tool(format!("Invalid tag list URL: {}", e)))?,
+        );
+        for _ in 0..MAX_TAG_PAGES {
+            let Some(current) = url.take() else { break };
+            let response = self
+                .registry_get(image, &token, current.as_str(), None)
+                .await?;
+            url = response
+                .headers()
+                .get(LINK)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|header| next_page(&current, header));
+            let body: Value = response.json().await?;
+            tags.extend(
+                body.get("tags")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string),
+            );
+        }
+
+        self.cache_tags(cache_key, tags.clone());
+        Ok(tags)
+    }
+
+    /// Cache `tags` under `key`, dropping expired entries and, at
+    /// [`TAG_CACHE_CAPACITY`], the oldest one.
+    fn cache_tags(&self, key: String, tags: Vec<String>) {
+        let Ok(mut cache) = self.tag_cache.lock() else {
+            return;
+        };
+        cache.retain(|_, (fetched, _)| fetched.elapsed() < TAG_CACHE_TTL);
+        if cache.len() >= TAG_CACHE_CAPACITY && !cache.contains_key(&key) {
+            let oldest = cache
+                .iter()
+                .min_by_key(|(_, (fetched, _))| *fetched)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(key, (Instant::now(), tags));
+    }
+
+    async fn get_tags(&self, image: &ImageRef, limit: usize) -> Result<Value> {
+        let tags = self.tags(image).await?;
+        Ok(json!({
+            "image": image.display_name(),
+            "count": tags.len(),
+            "truncated": tags.len() > limit,
+            "tags": tags.iter().take(limit).collect::<Vec<_>>(),
+        }))
+    }
+
+    async fn get_manifest(&self, image: &ImageRef, platform: &str) -> Result<Value> {
+        let token = self.pull_token(image).await?;
+        let url = format!(
+            "{}/v2/{}/manifests/{}",
+            image.registry.api_base(),
+            image.repository,
+            image.reference
+        );
+        let response = self
+            .registry_get(image, &token, &url, Some(MANIFEST_ACCEPT))
+            .await?;
+        let digest = response
+            .headers()
+            .get("docker-content-digest")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let manifest: Value = response.json().await?;
+
+        if manifest.get("manifests").is_none() {
+            let mut summary = summarize_manifest(&manifest);
+            summary["image"] = json!(image.display_name());
+            summary["reference"] = json!(image.reference);
+            summary["digest"] = json!(digest);
+            return Ok(summary);
+        }
+
+        // Multi-platform image: resolve the requested platform.
+        let (selected, available) = select_platform(&manifest, platform);
+        let Some(platform_digest) = selected else {
+            return Err(ZeptoError::Tool(format!(
+                "Platform '{}' not found for '{}:{}'. Available: {}",
+                platform,
+                image.display_name(),
+                image.reference,
+                available.join(", ")
+            )));
+        };
+        let url = format!(
+            "{}/v2/{}/manifests/{}",
+            image.registry.api_base(),
+            image.repository,
+            platform_digest
+        );
+        let platform_manifest: Value = self
+            .registry_get(image, &token, &url, Some(MANIFEST_ACCEPT))
+            .await?
+            .json()
+            .await?;
+        let mut summary = summarize_manifest(&platform_manifest);
+        summary["image"] = json!(image.display_name());
+        summary["reference"] = json!(image.reference);
+        summary["digest"] = json!(digest);
+        summary["platform"] = json!(platform);
+        summary["platform_digest"] = json!(platform_digest);
+        summary["available_platforms"] = json!(available);
+        Ok(summary)
+    }
+
+    async fn check_vulnerabilities(&self, image: &ImageRef) -> Result<Value> {
+        let (Some(token), Some(org)) = (
+            self.config
+                .snyk_token
+                .as_deref()
+                .filter(|t| !t.trim().is_empty()),
+            self.config
+                .snyk_org_id
+                .as_deref()
+                .filter(|o| !o.trim().is_empty()),
+        ) else {
+            return Err(ZeptoError::Tool(
+                "check_vulnerabilities requires tools.container_registry.snyk_token and snyk_org_id"
+                    .into(),
+            ));
+        };
+
+        let name = image.display_name();
+        let response = self
+            .client
+            .post(format!("{}/org/{}/projects", SNYK_API_BASE, org))
+            .header("Authorization", format!("token {}", token))
+            .json(&json!({ "filters": { "name": name } }))
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(ZeptoError::Tool(format!(
+                "Snyk project lookup failed with HTTP {}",
+                status
+            )));
+        }
+        let body: Value = response.json().await?;
+
+        let projects: Vec<Value> = body
+            .get("projects")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|p| {
+                json!({
+                    "name": p.get("name").cloned().unwrap_or(Value::Null),
+                    "type": p.get("type").cloned().unwrap_or(Value::Null),
+                    "issues_by_severity": p.get("issueCountsBySeverity").cloned().unwrap_or(Value::Null),
+                    "last_tested": p.get("lastTestedDate").cloned().unwrap_or(Value::Null),
+                    "url": p.get("browseUrl").cloned().unwrap_or(Value::Null),
+                })
+            })
+            .collect();
+        if projects.is_empty() {
+            return Ok(json!({
+                "image": name,
+                "monitored": false,
+                "message": format!(
+                    "No Snyk project found for '{}'. Run `snyk container monitor {}` to start tracking it.",
+                    name, name
+                ),
+            }));
+        }
+        Ok(json!({ "image": name, "monitored": true, "projects": projects }))
+    }
+}
+
+#[async_trait]
+impl Tool for ContainerRegistryTool {
+    fn name(&self) -> &str {
+        "container_registry"
+    }
+
+    fn description(&self) -> &str {
+        "Look up container image metadata on Docker Hub or ghcr.io. Operations: get_tags \
+         (list tags for an image), get_manifest (layers and compressed size for a tag or \
+         digest), check_vulnerabilities (Snyk issue counts, if Snyk is configured)."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Container image info"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::NetworkRead
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["get_tags", "get_manifest", "check_vulnerabilities"],
+                    "description": "What to look up"
+                },
+                "image": {
+                    "type": "string",
+                    "description": "Image reference, e.g. nginx, grafana/loki:3.0, ghcr.io/org/app:v1"
+                },
+                "platform": {
+                    "type": "string",
+                    "description": "Platform for multi-arch images in get_manifest (default: linux/amd64)"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum tags to return from get_tags (default: 100)"
+                }
+            },
+            "required": ["operation", "image"]
+        })
+    }
+
+    async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
+        let operation = args
+            .get("operation")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ZeptoError::Tool("Missing required field: operation".into()))?;
+        let image = args
+            .get("image")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ZeptoError::Tool("Missing required field: image".into()))?;
+        let image = ImageRef::parse(image)?;
+
+        let result = match operation {
+            "get_tags" => {
+                let limit = args
+                    .get("limit")
+                    .and_then(Value::as_u64)
+                    .map_or(DEFAULT_TAG_LIMIT, |l| l as usize);
+                self.get_tags(&image, limit).await?
+            }
+            "get_manifest" => {
+                let platform = args
+                    .get("platform")
+                    .and_then(Value::as_str)
+                    .unwrap_or(DEFAULT_PLATFORM);
+                self.get_manifest(&image, platform).await?
+            }
+            "check_vulnerabilities" => self.check_vulnerabilities(&image).await?,
+            other => {
+                return Err(ZeptoError::Tool(format!(
+                    "Unknown operation '{}'. Use get_tags, get_manifest, or check_vulnerabilities",
+                    other
+                )))
+            }
+        };
+        Ok(ToolOutput::llm_only(
+            serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_docker_hub_references() {
+        let image = ImageRef::parse("nginx").unwrap();
+        assert_eq!(image.registry, Registry::DockerHub);
+        assert_eq!(image.repository, "library/nginx");
+        assert_eq!(image.reference, "latest");
+        assert_eq!(image.display_name(), "nginx");
+
+        let image = ImageRef::parse("docker.io/grafana/loki:3.0").unwrap();
+        assert_eq!(image.repository, "grafana/loki");
+        assert_eq!(image.reference, "3.0");
+    }
+
+    #[test]
+    fn test_parse_ghcr_and_digest_references() {
+        let image = ImageRef::parse("ghcr.io/org/app@sha256:abc").unwrap();
+        assert_eq!(image.registry, Registry::Ghcr);
+        assert_eq!(image.repository, "org/app");
+        assert_eq!(image.reference, "sha256:abc");
+        assert_eq!(image.display_name(), "ghcr.io/org/app");
+    }
+
+    #[test]
+    fn test_parse_rejects_other_registries() {
+        assert!(ImageRef::parse("quay.io/coreos/etcd").is_err());
+        assert!(ImageRef::parse("localhost:5000/app").is_err());
+        assert!(ImageRef::parse("nginx:").is_err());
+    }
+
+    #[test]
+    fn test_next_link() {
+        let header = r#"</v2/library/nginx/tags/list?last=1.9&n=1000>; rel="next""#;
+        assert_eq!(
+            next_link(header).as_deref(),
+            Some("/v2/library/nginx/tags/list?last=1.9&n=1000")
+        );
+        assert_eq!(next_link(r#"</x>; rel="prev""#), None);
+    }
+
+    #[test]
+    fn test_next_page_resolves_against_current_url() {
+        let current = Url::parse("https://ghcr.io/v2/owner/app/tags/list?n=1000").unwrap();
+        assert_eq!(
+            next_page(
+                &current,
+                r#"</v2/owner/app/tags/list?last=v1&n=1000>; rel="next""#
+            )
+            .unwrap()
+            .as_str(),
+            "https://ghcr.io/v2/owner/app/tags/list?last=v1&n=1000"
+        );
+        assert_eq!(
+            next_page(&current, r#"<list?last=v2>; rel="next""#)
+                .unwrap()
+                .as_str(),
+            "https://ghcr.io/v2/owner/app/tags/list?last=v2"
+        );
+        assert_eq!(
+            next_page(
+                &current,
+                r#"<https://ghcr.io/v2/owner/app/tags/list?last=v3>; rel="next""#
+            )
+            .unwrap()
+            .as_str(),
+            "https://ghcr.io/v2/owner/app/tags/list?last=v3"
+        );
+        // The pull token is never sent to another origin.
+        assert_eq!(
+            next_page(&current, r#"<https://evil.example/list>; rel="next""#),
+            None
+        );
+    }
+
+    #[test]
+    fn test_select_platform_skips_attestations() {
+        let index = json!({
+            "manifests": [
+                { "digest": "sha256:amd", "platform": { "os": "linux", "architecture": "amd64" } },
+                { "digest": "sha256:arm", "platform": { "os": "linux", "architecture": "arm64", "variant": "v8" } },
+                { "digest": "sha256:att", "platform": { "os": "unknown", "architecture": "unknown" } }
+            ]
+        });
+        let (digest, available) = select_platform(&index, "linux/arm64");
+        assert_eq!(digest.as_deref(), Some("sha256:arm"));
+        assert_eq!(available, vec!["linux/amd64", "linux/arm64/v8"]);
+        assert_eq!(select_platform(&index, "windows/amd64").0, None);
+    }
+
+    #[test]
+    fn test_summarize_manifest_sums_layers() {
+        let manifest = json!({
+            "mediaType": "application/vnd.oci.image.manifest.v1+json",
+            "config": { "digest": "sha256:cfg", "size": 100 },
+            "layers": [
+                { "digest": "sha256:l1", "size": 1048576, "mediaType": "application/vnd.oci.image.layer.v1.tar+gzip" },
+                { "digest": "sha256:l2", "size": 524288, "mediaType": "application/vnd.oci.image.layer.v1.tar+gzip" }
+            ]
+        });
+        let summary = summarize_manifest(&manifest);
+        assert_eq!(summary["layer_count"], 2);
+        assert_eq!(summary["total_size_bytes"], 1572864);
+        assert_eq!(summary["total_size_mb"], 1.5);
+        assert_eq!(summary["config_digest"], "sha256:cfg");
+    }
+
+    #[tokio::test]
+    async fn test_tags_served_from_cache() {
+        let tool = ContainerRegistryTool::new(ContainerRegistryToolConfig::default());
+        let image = ImageRef::parse("nginx").unwrap();
+        tool.tag_cache.lock().unwrap().insert(
+            "DockerHub/library/nginx".to_string(),
+            (
+                Instant::now(),
+                vec!["1.27".to_string(), "latest".to_string()],
+            ),
+        );
+        let result = tool.get_tags(&image, 1).await.unwrap();
+        assert_eq!(result["count"], 2);
+        assert_eq!(result["truncated"], true);
+        assert_eq!(result["tags"], json!(["1.27"]));
+    }
+
+    #[test]
+    fn test_tag_cache_evicts_oldest_at_capacity() {
+        let tool = ContainerRegistryTool::new(ContainerRegistryToolConfig::default());
+        tool.tag_cache.lock().unwrap().insert(
+            "oldest".to_string(),
+            (Instant::now() - Duration::from_secs(30), Vec::new()),
+        );
+        for i in 0..TAG_CACHE_CAPACITY {
+            tool.cache_tags(format!("image-{}", i), vec!["latest".to_string()]);
+        }
+        let cache = tool.tag_cache.lock().unwrap();
+        assert_eq!(cache.len(), TAG_CACHE_CAPACITY);
+        assert!(!cache.contains_key("oldest"));
+        assert!(cache.contains_key(&format!("image-{}", TAG_CACHE_CAPACITY - 1)));
+    }
+
+    #[tokio::test]
+    async fn test_check_vulnerabilities_requires_snyk_config() {
+        let tool = ContainerRegistryTool::new(ContainerRegistryToolConfig::default());
+        let err = tool
+            .execute(
+                json!({"operation": "check_vulnerabilities", "image": "nginx"}),
+                &ToolContext::new(),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("snyk_token"));
+    }
+}
@@ -244,9 +244,15 @@ impl CronTool {
                 CronSchedule::Every { every_ms } => format!("every({}ms)", every_ms),
                 CronSchedule::Cron { expr } => format!("cron({})", expr),
             };
+            let next = job
+                .state
+                .next_run_at_ms
+                .and_then(chrono::DateTime::from_timestamp_millis)
+                .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+                .unwrap_or_else(|| "-".to_string());
             lines.push(format!(
-                "- {} [{}] {} -> {}:{}",
-                job.name, job.id, schedule, job.payload.channel, job.payload.chat_id
+                "- {} [{}] {} -> {}:{} (next: {})",
+                job.name, job.id, schedule, job.payload.channel, job.payload.chat_id, next
             ));
         }
         Ok(format!("Scheduled jobs:\n{}", lines.join("\n")))
@@ -441,6 +447,25 @@ mod tests {
         assert_eq!(result.unwrap().for_llm, "No scheduled jobs");
     }
 
+    #[tokio::test]
+    async fn test_execute_list_shows_next_run() {
+        let tool = make_cron_tool();
+        let ctx = ctx_with_channel();
+
+        tool.execute(
+            json!({"action": "add", "message": "ping", "at": "2099-01-01T09:00:00Z"}),
+            &ctx,
+        )
+        .await
+        .unwrap();
+        let output = tool
+            .execute(json!({"action": "list"}), &ctx)
+            .await
+            .unwrap()
+            .for_llm;
+        assert!(output.contains("(next: 2099-01-01T09:00:00Z)"), "{output}");
+    }
+
     #[tokio::test]
     async fn test_execute_remove_missing_job_id() {
         let tool = make_cron_tool();
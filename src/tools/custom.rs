@@ -284,9 +284,20 @@ mod tests {
     fn test_ctx() -> ToolContext {
         ToolContext {
             workspace: Some(std::env::temp_dir().to_string_lossy().to_string()),
+            sandbox: None,
             channel: None,
             chat_id: None,
             is_batch: false,
+            max_output_bytes: None,
+            shell_policy: None,
+            progress: None,
+            processes: None,
+            artifacts: None,
+            session_key: None,
+            command_log: None,
+            dry_run: false,
+            is_admin: false,
+            cancellation: Default::default(),
         }
     }
 
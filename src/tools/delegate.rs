@@ -108,33 +108,7 @@ impl DelegateTool {
     /// Always excludes `delegate` and `spawn` to prevent recursion.
     /// If a whitelist is provided, only tools matching those names are included.
     fn create_sub_agent_tools(&self, whitelist: Option<&[String]>) -> Vec<Box<dyn Tool>> {
-        let mut all_tools: Vec<Box<dyn Tool>> = vec![
-            Box::new(EchoTool),
-            Box::new(ReadFileTool),
-            Box::new(WriteFileTool),
-            Box::new(ListDirTool),
-            Box::new(EditFileTool),
-            Box::new(ShellTool::with_runtime(Arc::new(NativeRuntime::new()))),
-            Box::new(WebFetchTool::new()),
-            Box::new(MessageTool::new(self.bus.clone())),
-        ];
-
-        // Add memory tools if enabled
-        match &self.config.memory.backend {
-            crate::config::MemoryBackend::Disabled => {}
-            _ => {
-                all_tools.push(Box::new(MemorySearchTool::new(self.config.memory.clone())));
-                all_tools.push(Box::new(MemoryGetTool::new(self.config.memory.clone())));
-            }
-        }
-
-        match whitelist {
-            Some(names) => all_tools
-                .into_iter()
-                .filter(|t| names.iter().any(|n| n == t.name()))
-                .collect(),
-            None => all_tools,
-        }
+        sub_agent_tools(&self.config, &self.bus, whitelist)
     }
 
     /// Run a single delegated sub-agent and return its raw result string.
@@ -494,6 +468,42 @@ impl Tool for DelegateTool {
     }
 }
 
+/// The standard tool set for a sub-agent, optionally narrowed to
+/// `whitelist`. Never includes `delegate`, `spawn` or `spawn_agent`.
+pub(crate) fn sub_agent_tools(
+    config: &Config,
+    bus: &Arc<MessageBus>,
+    whitelist: Option<&[String]>,
+) -> Vec<Box<dyn Tool>> {
+    let mut all_tools: Vec<Box<dyn Tool>> = vec![
+        Box::new(EchoTool),
+        Box::new(ReadFileTool),
+        Box::new(WriteFileTool::new()),
+        Box::new(ListDirTool::new()),
+        Box::new(EditFileTool::new()),
+        Box::new(ShellTool::with_runtime(Arc::new(NativeRuntime::new()))),
+        Box::new(WebFetchTool::new()),
+        Box::new(MessageTool::new(bus.clone())),
+    ];
+
+    // Add memory tools if enabled
+    match &config.memory.backend {
+        crate::config::MemoryBackend::Disabled => {}
+        _ => {
+            all_tools.push(Box::new(MemorySearchTool::new(config.memory.clone())));
+            all_tools.push(Box::new(MemoryGetTool::new(config.memory.clone())));
+        }
+    }
+
+    match whitelist {
+        Some(names) => all_tools
+            .into_iter()
+            .filter(|t| names.iter().any(|n| n == t.name()))
+            .collect(),
+        None => all_tools,
+    }
+}
+
 /// Merge aggregated sub-agent results using the specified strategy.
 ///
 /// - `"concatenate"` (default) — joins each result as `[Role]: result` separated
@@ -525,7 +535,7 @@ fn format_results(results: &[(String, String)], strategy: &str) -> String {
 ///
 /// Since `set_provider()` takes `Box<dyn LLMProvider>`, we need this thin wrapper
 /// to share the same provider instance via Arc without cloning the provider itself.
-struct ProviderRef(Arc<dyn LLMProvider>);
+pub(crate) struct ProviderRef(pub(crate) Arc<dyn LLMProvider>);
 
 #[async_trait]
 impl LLMProvider for ProviderRef {
@@ -556,6 +566,10 @@ impl LLMProvider for ProviderRef {
     ) -> crate::error::Result<tokio::sync::mpsc::Receiver<crate::providers::StreamEvent>> {
         self.0.chat_stream(messages, tools, model, options).await
     }
+
+    fn supports_vision(&self) -> bool {
+        self.0.supports_vision()
+    }
 }
 
 #[cfg(test)]
@@ -1,7 +1,8 @@
-//! Unified diff parser and applier.
+//! Unified diff parser, applier and generator.
 //!
 //! Ported from pi-rs (pi-coding-agent). Applies standard unified diffs
-//! with `@@ -old,count +new,count @@` hunk headers.
+//! with `@@ -old,count +new,count @@` hunk headers, and renders the change
+//! between two texts in the same format.
 
 /// Summary of changes made when applying a diff.
 #[derive(Debug, Default, PartialEq)]
@@ -112,6 +113,61 @@ pub fn apply_unified_diff(original: &str, diff: &str) -> Result<(String, DiffSum
     Ok((out.join(""), summary))
 }
 
+/// Lines of unchanged context shown around a change by [`unified_diff`].
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// Render the change from `original` to `modified` as a unified diff.
+///
+/// Lines shared at the start and end of both texts are trimmed, so the
+/// result is a single hunk covering everything between the first and last
+/// changed line, with [`DIFF_CONTEXT_LINES`] of context either side. That is
+/// exact for one edit and still correct, if wider than needed, for several.
+/// Returns an empty string when the texts are equal. The output can be fed
+/// back to [`apply_unified_diff`].
+pub fn unified_diff(label: &str, original: &str, modified: &str) -> String {
+    let old: Vec<&str> = split_lines_keep_terminator(original);
+    let new: Vec<&str> = split_lines_keep_terminator(modified);
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    if prefix == old.len() && prefix == new.len() {
+        return String::new();
+    }
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let start = prefix.saturating_sub(DIFF_CONTEXT_LINES);
+    let old_end = (old.len() - suffix + DIFF_CONTEXT_LINES).min(old.len());
+    let new_end = (new.len() - suffix + DIFF_CONTEXT_LINES).min(new.len());
+    // An empty range is numbered by the line before it.
+    let header_start = |count: usize| if count == 0 { start } else { start + 1 };
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", label, label);
+    out.push_str(&format!(
+        "@@ -{},{} +{},{} @@\n",
+        header_start(old_end - start),
+        old_end - start,
+        header_start(new_end - start),
+        new_end - start
+    ));
+    for line in &old[start..prefix] {
+        out.push_str(&format!(" {}\n", strip_terminator(line)));
+    }
+    for line in &old[prefix..old.len() - suffix] {
+        out.push_str(&format!("-{}\n", strip_terminator(line)));
+    }
+    for line in &new[prefix..new.len() - suffix] {
+        out.push_str(&format!("+{}\n", strip_terminator(line)));
+    }
+    for line in &old[old.len() - suffix..old_end] {
+        out.push_str(&format!(" {}\n", strip_terminator(line)));
+    }
+    out
+}
+
 // --- Internal types ---
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -235,6 +291,34 @@ mod tests {
         hunks.join("\n")
     }
 
+    #[test]
+    fn unified_diff_shows_change_with_context() {
+        let original: String = (1..=10).map(|i| format!("line {}\n", i)).collect();
+        let modified = original.replace("line 5\n", "line five\nline 5.5\n");
+        let diff = unified_diff("f.txt", &original, &modified);
+        assert_eq!(
+            diff,
+            "--- a/f.txt\n+++ b/f.txt\n@@ -2,7 +2,8 @@\n line 2\n line 3\n line 4\n\
+             -line 5\n+line five\n+line 5.5\n line 6\n line 7\n line 8\n"
+        );
+        let (applied, _) = apply_unified_diff(&original, &diff).unwrap();
+        assert_eq!(applied, modified);
+    }
+
+    #[test]
+    fn unified_diff_edges() {
+        assert_eq!(unified_diff("f", "same\n", "same\n"), "");
+        assert_eq!(
+            unified_diff("f", "", "new\n"),
+            "--- a/f\n+++ b/f\n@@ -0,0 +1,1 @@\n+new\n"
+        );
+        // Repeated lines at the boundary are not double counted.
+        assert_eq!(
+            unified_diff("f", "a\na\n", "a\n"),
+            "--- a/f\n+++ b/f\n@@ -1,2 +1,1 @@\n a\n-a\n"
+        );
+    }
+
     #[test]
     fn simple_add_inserts_line() {
         let original = "line one\nline two\nline three\n";
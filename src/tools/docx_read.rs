@@ -10,7 +10,7 @@ use serde_json::{json, Value};
 use std::path::PathBuf;
 
 use crate::error::{Result, ZeptoError};
-use crate::security::{revalidate_path, validate_path_in_workspace};
+use crate::security::Sandbox;
 
 use super::{Tool, ToolContext, ToolOutput};
 
@@ -44,18 +44,22 @@ impl DocxReadTool {
     /// - The file does not have a `.docx` extension.
     /// - The file does not exist.
     pub fn resolve_path(&self, path: &str) -> Result<PathBuf> {
-        let safe = validate_path_in_workspace(path, &self.workspace)?;
-        if safe.as_path().extension().and_then(|e| e.to_str()) != Some("docx") {
+        Self::resolve_in(&Sandbox::new(&self.workspace), path)
+    }
+
+    fn resolve_in(sandbox: &Sandbox, path: &str) -> Result<PathBuf> {
+        let safe = sandbox.resolve(path)?;
+        if safe.extension().and_then(|e| e.to_str()) != Some("docx") {
             return Err(ZeptoError::Tool(
                 "Only .docx files are supported".to_string(),
             ));
         }
         // TOCTOU: re-validate immediately before I/O
-        revalidate_path(safe.as_path(), &self.workspace)?;
-        if !safe.as_path().exists() {
+        sandbox.check(&safe)?;
+        if !safe.exists() {
             return Err(ZeptoError::Tool(format!("File not found: {path}")));
         }
-        Ok(safe.into_path_buf())
+        Ok(safe)
     }
 
     /// Truncate `text` to at most `max_chars` characters.
@@ -99,6 +103,27 @@ impl DocxReadTool {
     /// - `<w:tab/>` inserts a tab character.
     /// - `<w:br/>` inserts a newline.
     pub fn extract_text_from_bytes(bytes: &[u8]) -> Result<String> {
+        Self::extract_from_bytes(bytes, false)
+    }
+
+    /// Extract text from DOCX bytes like
+    /// [`extract_text_from_bytes`](Self::extract_text_from_bytes), with
+    /// `Title` and `HeadingN` paragraphs prefixed by markdown `#` markers so
+    /// the document's sections stay visible.
+    pub fn extract_sections_from_bytes(bytes: &[u8]) -> Result<String> {
+        Self::extract_from_bytes(bytes, true)
+    }
+
+    /// Markdown heading level for a paragraph style id, if it is a heading.
+    fn heading_level(style: &[u8]) -> Option<usize> {
+        if style == b"Title" {
+            return Some(1);
+        }
+        let level = std::str::from_utf8(style.strip_prefix(b"Heading")?).ok()?;
+        level.parse::<usize>().ok().map(|n| n.clamp(1, 6))
+    }
+
+    fn extract_from_bytes(bytes: &[u8], mark_headings: bool) -> Result<String> {
         use std::io::{Cursor, Read};
         use zip::ZipArchive;
 
@@ -124,23 +149,41 @@ impl DocxReadTool {
 
         let mut output = String::new();
         let mut in_t = false;
+        // Where the current paragraph starts, and its heading level if any.
+        let mut paragraph_start = 0;
+        let mut heading = None;
         let mut buf = Vec::new();
 
         loop {
             match reader.read_event_into(&mut buf) {
-                Ok(Event::Start(ref e))
-                    if e.local_name().as_ref() == b"t" => {
-                        in_t = true;
+                Ok(Event::Start(ref e)) => match e.local_name().as_ref() {
+                    b"t" => in_t = true,
+                    b"p" => {
+                        paragraph_start = output.len();
+                        heading = None;
                     }
+                    _ => {}
+                },
                 Ok(Event::Empty(ref e)) => match e.local_name().as_ref() {
                     b"tab" => output.push('\t'),
                     b"br" => output.push('\n'),
+                    b"pStyle" if mark_headings => {
+                        heading = e
+                            .attributes()
+                            .flatten()
+                            .find(|a| a.key.local_name().as_ref() == b"val")
+                            .and_then(|a| Self::heading_level(&a.value));
+                    }
                     _ => {}
                 },
                 Ok(Event::End(ref e)) => {
                     if e.local_name().as_ref() == b"t" {
                         in_t = false;
                     } else if e.local_name().as_ref() == b"p" {
+                        if let Some(level) = heading.take() {
+                            let marker = format!("{} ", "#".repeat(level));
+                            output.insert_str(paragraph_start, &marker);
+                        }
                         output.push('\n');
                     }
                 }
@@ -213,7 +256,7 @@ impl Tool for DocxReadTool {
         })
     }
 
-    async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
         let path_str = args["path"].as_str().unwrap_or("");
         if path_str.is_empty() {
             return Err(ZeptoError::Tool(
@@ -227,7 +270,10 @@ impl Tool for DocxReadTool {
             .unwrap_or(DEFAULT_MAX_CHARS)
             .min(HARD_MAX_CHARS);
 
-        let resolved = self.resolve_path(path_str)?;
+        let resolved = Self::resolve_in(
+            &ctx.sandbox_or(&self.workspace),
+            &ctx.resolve_input_path(path_str)?,
+        )?;
 
         // Size guard before we do any I/O-heavy work.
         let meta = tokio::fs::metadata(&resolved)
@@ -546,6 +592,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_extract_sections_marks_headings() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:body>
+    <w:p><w:pPr><w:pStyle w:val="Title"/></w:pPr><w:r><w:t>Report</w:t></w:r></w:p>
+    <w:p><w:pPr><w:pStyle w:val="Heading2"/></w:pPr><w:r><w:t>Costs</w:t></w:r></w:p>
+    <w:p><w:r><w:t>Up 4%.</w:t></w:r></w:p>
+  </w:body>
+</w:document>"#;
+        let bytes = build_test_docx(xml);
+        let text = DocxReadTool::extract_sections_from_bytes(&bytes).unwrap();
+        assert_eq!(text, "# Report\n## Costs\nUp 4%.\n");
+        let plain = DocxReadTool::extract_text_from_bytes(&bytes).unwrap();
+        assert_eq!(plain, "Report\nCosts\nUp 4%.\n");
+    }
+
     #[test]
     fn test_extract_text_invalid_zip() {
         let not_a_zip = b"not a zip file";
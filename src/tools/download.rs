@@ -0,0 +1,495 @@
+//! Download tool — save a URL into the workspace.
+//!
+//! Requests go through [`HttpFetchTool`]'s checks (scheme, host lists,
+//! private-address blocking with DNS pinning, per-hop redirect checks), so
+//! `tools.http_fetch` governs where downloads may come from. The body is
+//! streamed to a temporary file next to the destination and renamed into
+//! place only when complete; a download that passes `tools.download.max_bytes`
+//! is aborted and its partial file removed.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Method, Url};
+use serde_json::{json, Value};
+use tokio::io::AsyncWriteExt;
+
+use crate::config::{DownloadToolConfig, HttpFetchConfig};
+use crate::error::{Result, ZeptoError};
+use crate::security::{ensure_directory_chain_secure, revalidate_path, Sandbox};
+
+use super::artifact::ArtifactStore;
+use super::http_fetch::HttpFetchTool;
+use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// File name used when the URL path does not end in one.
+const FALLBACK_FILE_NAME: &str = "download";
+
+/// Tool that downloads a URL to a file in the workspace.
+///
+/// # Parameters
+/// - `url`: http(s) URL to download (required)
+/// - `dest_path`: workspace path to save to (optional; named after the URL
+///   when omitted or when it names a directory)
+/// - `overwrite`: replace an existing file (optional, default `false`)
+/// - `artifact`: save as a session artifact and return an `artifact://`
+///   reference; `dest_path` is then the artifact name (optional)
+pub struct DownloadTool {
+    fetch: HttpFetchTool,
+    config: DownloadToolConfig,
+}
+
+impl DownloadTool {
+    /// Create a download tool. Host rules come from `fetch`; the request
+    /// timeout comes from `config`, since large files outlast a page fetch.
+    pub fn new(fetch: HttpFetchConfig, config: DownloadToolConfig) -> Self {
+        let fetch = HttpFetchTool::new(HttpFetchConfig {
+            timeout_secs: config.timeout_secs,
+            ..fetch
+        });
+        Self { fetch, config }
+    }
+
+    /// Resolve the destination through `sandbox`, naming the file after
+    /// the URL when `dest_path` is missing or is a directory.
+    fn resolve_dest(dest_path: Option<&str>, url: &Url, sandbox: &Sandbox) -> Result<PathBuf> {
+        let name = file_name_from_url(url);
+        let relative = match dest_path.map(str::trim).filter(|p| !p.is_empty()) {
+            None => name,
+            Some(path) if path.ends_with('/') => format!("{}{}", path, name),
+            Some(path) => {
+                let safe = sandbox.resolve_for_write(path)?;
+                if safe.is_dir() {
+                    let dest = safe.join(name);
+                    sandbox.check_write(&dest)?;
+                    return Ok(dest);
+                }
+                return Ok(safe);
+            }
+        };
+        sandbox.resolve_for_write(&relative)
+    }
+
+    /// Stream `response` into `dest` via a temporary file, enforcing the
+    /// size limit. Returns the number of bytes written. Stopping the turn
+    /// mid-transfer removes the partial file like any other failure.
+    async fn save(
+        &self,
+        mut response: reqwest::Response,
+        dest: &Path,
+        workspace: &str,
+        overwrite: bool,
+        ctx: &ToolContext,
+    ) -> Result<u64> {
+        let parent = dest
+            .parent()
+            .ok_or_else(|| ZeptoError::Tool("Destination has no parent directory".into()))?;
+        ensure_directory_chain_secure(parent, workspace)?;
+        revalidate_path(dest, workspace)?;
+
+        let tmp = parent.join(format!(
+            ".{}.{}.part",
+            dest.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        ));
+        let result = ctx
+            .cancellable(async {
+                let mut file = tokio::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&tmp)
+                    .await?;
+                let mut written: u64 = 0;
+                while let Some(chunk) = response
+                    .chunk()
+                    .await
+                    .map_err(|e| ZeptoError::Tool(format!("Download interrupted: {}", e)))?
+                {
+                    written += chunk.len() as u64;
+                    if written > self.config.max_bytes {
+                        return Err(self.too_large());
+                    }
+                    file.write_all(&chunk).await?;
+                }
+                file.flush().await?;
+                drop(file);
+
+                revalidate_path(dest, workspace)?;
+                if !overwrite && tokio::fs::symlink_metadata(dest).await.is_ok() {
+                    return Err(exists_error(dest));
+                }
+                tokio::fs::rename(&tmp, dest).await?;
+                Ok(written)
+            })
+            .await;
+
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&tmp).await;
+        }
+        result
+    }
+
+    fn too_large(&self) -> ZeptoError {
+        ZeptoError::Tool(format!(
+            "Download exceeds the {} byte limit (tools.download.max_bytes); aborted",
+            self.config.max_bytes
+        ))
+    }
+}
+
+fn exists_error(dest: &Path) -> ZeptoError {
+    ZeptoError::Tool(format!(
+        "'{}' already exists; set overwrite to true to replace it",
+        dest.display()
+    ))
+}
+
+/// Derive a safe file name from the last segment of the URL path.
+fn file_name_from_url(url: &Url) -> String {
+    let segment = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .unwrap_or("");
+    let name: String = segment
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let name = name.trim_start_matches('.');
+    if name.is_empty() {
+        FALLBACK_FILE_NAME.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+#[async_trait]
+impl Tool for DownloadTool {
+    fn name(&self) -> &str {
+        "download"
+    }
+
+    fn description(&self) -> &str {
+        "Download a file from an http/https URL into the workspace and report its path, size \
+         and content type. Use this instead of curl for files you want to read or analyze."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Download URL to workspace"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::FilesystemWrite
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "http/https URL to download"
+                },
+                "dest_path": {
+                    "type": "string",
+                    "description": "Workspace path to save to. Omit, or give a directory, to name the file after the URL."
+                },
+                "overwrite": {
+                    "type": "boolean",
+                    "description": "Replace the destination if it already exists (default: false)"
+                },
+                "artifact": {
+                    "type": "boolean",
+                    "description": "Save as a session artifact and return an artifact:// reference other tools accept as a path; dest_path becomes the artifact name (default: false)"
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let raw_url = args
+            .get("url")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| ZeptoError::Tool("Missing 'url' parameter".into()))?;
+        let url = Url::parse(raw_url)
+            .map_err(|e| ZeptoError::Tool(format!("Invalid URL '{}': {}", raw_url, e)))?;
+        let overwrite = args
+            .get("overwrite")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let sandbox = ctx.sandbox().ok_or_else(|| {
+            ZeptoError::SecurityViolation(
+                "Workspace not configured; download requires a workspace".to_string(),
+            )
+        })?;
+        let workspace = sandbox.workspace();
+
+        let dest_path = args.get("dest_path").and_then(Value::as_str);
+        let artifact_name = if args.get("artifact").and_then(Value::as_bool) == Some(true) {
+            Some(
+                dest_path
+                    .map(str::trim)
+                    .filter(|p| !p.is_empty())
+                    .map_or_else(|| file_name_from_url(&url), str::to_string),
+            )
+        } else {
+            None
+        };
+        let dest = match &artifact_name {
+            Some(name) => ctx.artifact_path(name)?,
+            None => Self::resolve_dest(dest_path, &url, &sandbox)?,
+        };
+        if !overwrite && tokio::fs::symlink_metadata(&dest).await.is_ok() {
+            return Err(exists_error(&dest));
+        }
+
+        let download = async {
+            let response = self.fetch.send(url, Method::GET, &[], None).await?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(ZeptoError::Tool(format!(
+                    "Download failed: HTTP {}",
+                    status
+                )));
+            }
+            if response
+                .content_length()
+                .is_some_and(|len| len > self.config.max_bytes)
+            {
+                return Err(self.too_large());
+            }
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("unknown")
+                .to_string();
+            let size = self
+                .save(response, &dest, workspace, overwrite, ctx)
+                .await?;
+            Ok((size, content_type))
+        };
+        let (size, content_type) = tokio::time::timeout(
+            Duration::from_secs(self.config.timeout_secs),
+            ctx.cancellable(download),
+        )
+        .await
+        .map_err(|_| {
+            ZeptoError::Tool(format!(
+                "Download timed out after {}s",
+                self.config.timeout_secs
+            ))
+        })??;
+
+        let shown = match &artifact_name {
+            Some(name) => ArtifactStore::reference(name),
+            None => Path::new(workspace)
+                .canonicalize()
+                .ok()
+                .and_then(|ws| dest.strip_prefix(ws).ok().map(Path::to_path_buf))
+                .or_else(|| dest.strip_prefix(workspace).ok().map(Path::to_path_buf))
+                .unwrap_or_else(|| dest.clone())
+                .display()
+                .to_string(),
+        };
+        Ok(ToolOutput::llm_only(format!(
+            "Downloaded {} to {} ({} bytes, {})",
+            raw_url, shown, size, content_type
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    /// Serve one canned response per connection on localhost.
+    async fn serve(body: &'static [u8], content_type: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut buf = vec![0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    content_type,
+                    body.len()
+                );
+                let _ = socket.write_all(head.as_bytes()).await;
+                let _ = socket.write_all(body).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    fn tool(max_bytes: u64) -> DownloadTool {
+        DownloadTool::new(
+            HttpFetchConfig {
+                allow_private_hosts: true,
+                ..Default::default()
+            },
+            DownloadToolConfig {
+                max_bytes,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn ctx(dir: &TempDir) -> ToolContext {
+        ToolContext::new().with_workspace(dir.path().to_str().unwrap())
+    }
+
+    #[test]
+    fn test_file_name_from_url() {
+        let name = |u: &str| file_name_from_url(&Url::parse(u).unwrap());
+        assert_eq!(name("https://example.com/data/report.csv"), "report.csv");
+        assert_eq!(name("https://example.com/a%20b.txt?x=1"), "a_20b.txt");
+        assert_eq!(name("https://example.com/"), "download");
+        assert_eq!(name("https://example.com/..."), "download");
+    }
+
+    #[tokio::test]
+    async fn test_download_auto_names_file() {
+        let base = serve(b"a,b\n1,2\n", "text/csv").await;
+        let dir = TempDir::new().unwrap();
+        let out = tool(1024)
+            .execute(
+                json!({"url": format!("{}/files/data.csv", base)}),
+                &ctx(&dir),
+            )
+            .await
+            .unwrap();
+        assert!(
+            out.for_llm.contains("to data.csv (8 bytes, text/csv)"),
+            "{}",
+            out.for_llm
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("data.csv")).unwrap(),
+            "a,b\n1,2\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_as_artifact() {
+        let base = serve(b"a,b\n", "text/csv").await;
+        let dir = TempDir::new().unwrap();
+        let ctx = ctx(&dir).with_artifacts(
+            std::sync::Arc::new(ArtifactStore::new(dir.path())),
+            "cli:main",
+        );
+        let out = tool(1024)
+            .execute(
+                json!({"url": format!("{}/report.csv", base), "artifact": true}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert!(
+            out.for_llm.contains("to artifact://report.csv"),
+            "{}",
+            out.for_llm
+        );
+        let path = ctx.resolve_input_path("artifact://report.csv").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join(path.as_ref())).unwrap(),
+            "a,b\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_into_directory() {
+        let base = serve(b"x", "text/plain").await;
+        let dir = TempDir::new().unwrap();
+        tool(1024)
+            .execute(
+                json!({"url": format!("{}/x.txt", base), "dest_path": "inbox/"}),
+                &ctx(&dir),
+            )
+            .await
+            .unwrap();
+        assert!(dir.path().join("inbox/x.txt").is_file());
+    }
+
+    #[tokio::test]
+    async fn test_download_size_limit_leaves_no_file() {
+        let base = serve(&[b'x'; 4096], "application/octet-stream").await;
+        let dir = TempDir::new().unwrap();
+        let err = tool(1000)
+            .execute(json!({"url": format!("{}/big.bin", base)}), &ctx(&dir))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("byte limit"), "{}", err);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_download_requires_overwrite_flag() {
+        let base = serve(b"new", "text/plain").await;
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("f.txt"), "old").unwrap();
+        let url = format!("{}/f.txt", base);
+
+        let err = tool(1024)
+            .execute(json!({"url": url}), &ctx(&dir))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("already exists"), "{}", err);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("f.txt")).unwrap(),
+            "old"
+        );
+
+        tool(1024)
+            .execute(json!({"url": url, "overwrite": true}), &ctx(&dir))
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("f.txt")).unwrap(),
+            "new"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_blocks_private_hosts_by_default() {
+        let dir = TempDir::new().unwrap();
+        let t = DownloadTool::new(HttpFetchConfig::default(), DownloadToolConfig::default());
+        let err = t
+            .execute(json!({"url": "http://127.0.0.1:1/x"}), &ctx(&dir))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("local or private"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_download_rejects_path_outside_workspace() {
+        let dir = TempDir::new().unwrap();
+        let err = tool(1024)
+            .execute(
+                json!({"url": "http://127.0.0.1:1/x", "dest_path": "../escape.txt"}),
+                &ctx(&dir),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ZeptoError::SecurityViolation(_)), "{}", err);
+    }
+}
@@ -0,0 +1,582 @@
+//! Text extraction for documents users send: PDF, DOCX, HTML and plain text.
+//!
+//! The format is detected from the file's leading bytes, falling back to the
+//! extension, so a PDF saved as `upload.bin` still reads as a PDF. Output
+//! starts with a one-line summary (format, pages, words) and keeps the
+//! document's structure visible: PDFs get a `--- Page N ---` marker per
+//! page, DOCX and HTML headings become markdown `#` lines.
+//!
+//! PDF support uses lopdf and needs `--features tool-pdf`; the other formats
+//! use unconditional dependencies. A password-protected PDF is reported as
+//! such, and one without a text layer (scanned pages) either goes through
+//! the OCR program set in `tools.extract_text.ocr_command` or is reported
+//! as needing OCR, rather than coming back empty.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::config::ExtractTextToolConfig;
+use crate::error::{Result, ZeptoError};
+
+use super::docx_read::DocxReadTool;
+use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Largest file accepted (50 MB).
+const MAX_FILE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Default output character limit.
+const DEFAULT_MAX_CHARS: usize = 50_000;
+
+/// Maximum allowed `max_chars` value from LLM args.
+const HARD_MAX_CHARS: usize = 200_000;
+
+/// Bytes read to detect the format.
+const SNIFF_BYTES: usize = 8 * 1024;
+
+/// Placeholder in `ocr_args` for the file's path.
+const OCR_PATH_PLACEHOLDER: &str = "{path}";
+
+/// A document format the tool can read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Pdf,
+    Docx,
+    Html,
+    Text,
+}
+
+impl Format {
+    fn label(self) -> &'static str {
+        match self {
+            Format::Pdf => "PDF",
+            Format::Docx => "DOCX",
+            Format::Html => "HTML",
+            Format::Text => "text",
+        }
+    }
+
+    /// Detect the format from the file's first bytes and its extension.
+    fn detect(path: &Path, head: &[u8]) -> Result<Self> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+        if head.starts_with(b"%PDF-") {
+            return Ok(Format::Pdf);
+        }
+        if head.starts_with(b"PK\x03\x04") {
+            // Other zip-based formats (xlsx, pptx, odt) are not supported.
+            return if ext == "docx" {
+                Ok(Format::Docx)
+            } else {
+                Err(ZeptoError::Tool(format!(
+                    "Unsupported format: {} is a zip archive, not a .docx document",
+                    path.display()
+                )))
+            };
+        }
+        let text = match std::str::from_utf8(head) {
+            Ok(text) => text,
+            // The sniffed prefix may end inside a multi-byte character.
+            Err(e) if e.error_len().is_none() => {
+                std::str::from_utf8(&head[..e.valid_up_to()]).unwrap_or_default()
+            }
+            Err(_) => {
+                return Err(ZeptoError::Tool(format!(
+                    "Unsupported format: {} is binary and not a PDF or DOCX",
+                    path.display()
+                )))
+            }
+        };
+        let start: String = text.trim_start().chars().take(15).collect();
+        let start = start.to_ascii_lowercase();
+        if ext == "html"
+            || ext == "htm"
+            || start.starts_with("<!doctype html")
+            || start.starts_with("<html")
+        {
+            Ok(Format::Html)
+        } else {
+            Ok(Format::Text)
+        }
+    }
+}
+
+/// Text pulled out of a document, with what is known about it.
+#[derive(Debug)]
+struct Extracted {
+    format: Format,
+    text: String,
+    /// Page count, for formats that have pages.
+    pages: Option<usize>,
+    /// Remarks for the summary line, e.g. that OCR was used.
+    notes: Vec<String>,
+}
+
+impl Extracted {
+    fn new(format: Format, text: String) -> Self {
+        Self {
+            format,
+            text,
+            pages: None,
+            notes: Vec::new(),
+        }
+    }
+
+    /// One-line summary put above the text.
+    fn summary(&self, name: &str) -> String {
+        let mut parts = vec![self.format.label().to_string()];
+        if let Some(pages) = self.pages {
+            parts.push(format!(
+                "{} page{}",
+                pages,
+                if pages == 1 { "" } else { "s" }
+            ));
+        }
+        // Page and heading markers are not words.
+        let words = self
+            .text
+            .lines()
+            .filter(|line| !line.starts_with("--- Page "))
+            .flat_map(str::split_whitespace)
+            .filter(|word| word.chars().any(char::is_alphanumeric))
+            .count();
+        parts.push(format!("{} words", words));
+        parts.extend(self.notes.iter().cloned());
+        format!("[{}: {}]", name, parts.join(", "))
+    }
+}
+
+/// Cut `text` to `max_chars` characters, saying how much was left out.
+fn truncate(mut text: String, max_chars: usize) -> String {
+    let Some((byte_end, _)) = text.char_indices().nth(max_chars) else {
+        return text;
+    };
+    let total = text.chars().count();
+    text.truncate(byte_end);
+    text.push_str(&format!(
+        "\n\n[TRUNCATED: showing the first {} of {} characters. Call again with a larger \
+         max_chars (up to {}) to see more.]",
+        max_chars, total, HARD_MAX_CHARS
+    ));
+    text
+}
+
+/// Extract the text of each page, marking where pages start.
+#[cfg(feature = "tool-pdf")]
+fn extract_pdf(path: &Path) -> Result<Extracted> {
+    use lopdf::Document;
+
+    let doc = Document::load(path).map_err(|e| {
+        let message = e.to_string();
+        let lower = message.to_ascii_lowercase();
+        if lower.contains("password") || lower.contains("decrypt") || lower.contains("encrypt") {
+            password_protected()
+        } else {
+            ZeptoError::Tool(format!("Failed to load PDF: {message}"))
+        }
+    })?;
+
+    let pages = doc.get_pages();
+    let mut text = String::new();
+    let mut with_text = 0;
+    for (number, _) in pages.iter() {
+        let page_text = doc.extract_text(&[*number]).unwrap_or_default();
+        if !page_text.trim().is_empty() {
+            with_text += 1;
+        }
+        text.push_str(&format!("--- Page {} ---\n", number));
+        text.push_str(page_text.trim_end());
+        text.push_str("\n\n");
+    }
+    if with_text == 0 && doc.is_encrypted() {
+        return Err(password_protected());
+    }
+
+    let mut extracted = Extracted::new(
+        Format::Pdf,
+        if with_text == 0 { String::new() } else { text },
+    );
+    extracted.pages = Some(pages.len());
+    if with_text > 0 && with_text < pages.len() {
+        extracted
+            .notes
+            .push(format!("{} page(s) without text", pages.len() - with_text));
+    }
+    Ok(extracted)
+}
+
+#[cfg(feature = "tool-pdf")]
+fn password_protected() -> ZeptoError {
+    ZeptoError::Tool(
+        "This PDF is password-protected; ask the user for an unprotected copy.".to_string(),
+    )
+}
+
+/// Without lopdf, a PDF yields no text, so it can still go through OCR.
+#[cfg(not(feature = "tool-pdf"))]
+fn extract_pdf(_path: &Path) -> Result<Extracted> {
+    Ok(Extracted::new(Format::Pdf, String::new()))
+}
+
+/// Read `path` as `format`, off the async runtime.
+fn extract(path: &Path, format: Format) -> Result<Extracted> {
+    let read =
+        || std::fs::read(path).map_err(|e| ZeptoError::Tool(format!("Failed to read file: {e}")));
+    match format {
+        Format::Pdf => extract_pdf(path),
+        Format::Docx => Ok(Extracted::new(
+            format,
+            DocxReadTool::extract_sections_from_bytes(&read()?)?,
+        )),
+        Format::Html => {
+            let bytes = read()?;
+            Ok(Extracted::new(
+                format,
+                super::web::html_to_markdown(&String::from_utf8_lossy(&bytes)),
+            ))
+        }
+        Format::Text => {
+            let text = String::from_utf8(read()?).map_err(|_| {
+                ZeptoError::Tool("Unsupported format: the file is not valid UTF-8 text".to_string())
+            })?;
+            Ok(Extracted::new(format, text))
+        }
+    }
+}
+
+/// Extract text from PDF, DOCX, HTML and plain-text files in the workspace.
+pub struct ExtractTextTool {
+    workspace: String,
+    config: ExtractTextToolConfig,
+}
+
+impl ExtractTextTool {
+    /// Create a new `ExtractTextTool` bound to `workspace`.
+    pub fn new(workspace: String, config: ExtractTextToolConfig) -> Self {
+        Self { workspace, config }
+    }
+
+    /// Run the configured OCR program on the PDF at `path`.
+    async fn ocr(&self, command: &str, path: &Path) -> Result<String> {
+        let path_str = path.to_string_lossy();
+        let mut args: Vec<String> = self
+            .config
+            .ocr_args
+            .iter()
+            .map(|arg| arg.replace(OCR_PATH_PLACEHOLDER, &path_str))
+            .collect();
+        if !self
+            .config
+            .ocr_args
+            .iter()
+            .any(|arg| arg.contains(OCR_PATH_PLACEHOLDER))
+        {
+            args.push(path_str.into_owned());
+        }
+
+        let mut cmd = tokio::process::Command::new(command);
+        cmd.args(&args);
+        cmd.stdin(std::process::Stdio::null());
+        cmd.stdout(std::process::Stdio::piped());
+        cmd.stderr(std::process::Stdio::piped());
+        cmd.kill_on_drop(true);
+        let child = cmd
+            .spawn()
+            .map_err(|e| ZeptoError::Tool(format!("Failed to run OCR command '{command}': {e}")))?;
+
+        let timeout = Duration::from_secs(self.config.ocr_timeout_secs.max(1));
+        let output = tokio::time::timeout(timeout, child.wait_with_output())
+            .await
+            .map_err(|_| {
+                ZeptoError::Tool(format!(
+                    "OCR timed out after {}s",
+                    self.config.ocr_timeout_secs
+                ))
+            })?
+            .map_err(|e| ZeptoError::Tool(format!("Failed to run OCR command '{command}': {e}")))?;
+        if !output.status.success() {
+            return Err(ZeptoError::Tool(format!(
+                "OCR command failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Error for a PDF that has no text to extract.
+    fn no_text_layer(pages: Option<usize>) -> ZeptoError {
+        let found = match pages {
+            Some(pages) => format!("none of its {pages} page(s) has a text layer"),
+            None => "it could not be read".to_string(),
+        };
+        let hint = if cfg!(feature = "tool-pdf") {
+            "It is probably scanned images. Set tools.extract_text.ocr_command to run OCR on such files."
+        } else {
+            "PDF extraction requires the 'tool-pdf' build feature (cargo build --features tool-pdf), \
+             or set tools.extract_text.ocr_command to run OCR instead."
+        };
+        ZeptoError::Tool(format!("No text found in the PDF: {found}. {hint}"))
+    }
+}
+
+#[async_trait]
+impl Tool for ExtractTextTool {
+    fn name(&self) -> &str {
+        "extract_text"
+    }
+
+    fn description(&self) -> &str {
+        "Extract the text of a document in the workspace: PDF, DOCX (Word), HTML or plain \
+         text, detected automatically. Output starts with a summary (format, page count, \
+         words) and marks pages and headings."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Extract text from a PDF, DOCX, HTML or text file."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::FilesystemRead
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["path"],
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Relative path to the document within the workspace"
+                },
+                "max_chars": {
+                    "type": "integer",
+                    "description": "Maximum characters to return (default: 50000, max: 200000)",
+                    "default": DEFAULT_MAX_CHARS
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let path_str = args["path"].as_str().unwrap_or("");
+        if path_str.is_empty() {
+            return Err(ZeptoError::Tool(
+                "Missing required argument: path".to_string(),
+            ));
+        }
+        let max_chars = args["max_chars"]
+            .as_u64()
+            .map(|v| v as usize)
+            .unwrap_or(DEFAULT_MAX_CHARS)
+            .min(HARD_MAX_CHARS);
+
+        let sandbox = ctx.sandbox_or(&self.workspace);
+        let resolved: PathBuf = sandbox.resolve(&ctx.resolve_input_path(path_str)?)?;
+        // TOCTOU: re-validate immediately before I/O
+        sandbox.check(&resolved)?;
+        let meta = tokio::fs::metadata(&resolved)
+            .await
+            .map_err(|_| ZeptoError::Tool(format!("File not found: {path_str}")))?;
+        if !meta.is_file() {
+            return Err(ZeptoError::Tool(format!("Not a file: {path_str}")));
+        }
+        if meta.len() > MAX_FILE_BYTES {
+            return Err(ZeptoError::Tool(format!(
+                "File too large: {} bytes (max {}MB)",
+                meta.len(),
+                MAX_FILE_BYTES / 1024 / 1024
+            )));
+        }
+
+        let mut head = Vec::with_capacity(SNIFF_BYTES);
+        {
+            use tokio::io::AsyncReadExt;
+            let file = tokio::fs::File::open(&resolved).await?;
+            file.take(SNIFF_BYTES as u64).read_to_end(&mut head).await?;
+        }
+        let format = Format::detect(&resolved, &head)?;
+
+        let path = resolved.clone();
+        let mut extracted = tokio::task::spawn_blocking(move || extract(&path, format))
+            .await
+            .map_err(|e| ZeptoError::Tool(format!("Task panicked: {e}")))??;
+
+        if format == Format::Pdf && extracted.text.trim().is_empty() {
+            let Some(command) = self.config.ocr_command.as_deref() else {
+                return Err(Self::no_text_layer(extracted.pages));
+            };
+            extracted.text = self.ocr(command, &resolved).await?;
+            extracted.notes.push("text recognized by OCR".to_string());
+        }
+        if extracted.text.trim().is_empty() {
+            return Ok(ToolOutput::llm_only(format!(
+                "{}\nThe document contains no text.",
+                extracted.summary(path_str)
+            )));
+        }
+
+        let summary = extracted.summary(path_str);
+        Ok(ToolOutput::llm_only(format!(
+            "{}\n\n{}",
+            summary,
+            truncate(extracted.text, max_chars)
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn tool(workspace: &TempDir) -> ExtractTextTool {
+        ExtractTextTool::new(
+            workspace.path().to_string_lossy().into_owned(),
+            ExtractTextToolConfig::default(),
+        )
+    }
+
+    async fn run(tool: &ExtractTextTool, args: Value) -> Result<String> {
+        tool.execute(args, &ToolContext::new())
+            .await
+            .map(|out| out.for_llm)
+    }
+
+    #[test]
+    fn test_detect_format() {
+        let detect = |name: &str, head: &[u8]| Format::detect(Path::new(name), head);
+        assert_eq!(detect("upload.bin", b"%PDF-1.7\n").unwrap(), Format::Pdf);
+        assert_eq!(detect("a.docx", b"PK\x03\x04rest").unwrap(), Format::Docx);
+        assert!(detect("a.xlsx", b"PK\x03\x04rest").is_err());
+        assert_eq!(detect("page.htm", b"hello").unwrap(), Format::Html);
+        assert_eq!(
+            detect("page", b"  <!DOCTYPE html><html>").unwrap(),
+            Format::Html
+        );
+        assert_eq!(detect("notes.md", "café".as_bytes()).unwrap(), Format::Text);
+        // A prefix cut inside a multi-byte character is still text.
+        assert_eq!(
+            detect("notes", &"日本".as_bytes()[..4]).unwrap(),
+            Format::Text
+        );
+        assert!(detect("image.png", b"\x89PNG\r\n\x1a\n\x00\xff").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_html_headings_become_sections() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("page.html"),
+            "<html><head><title>Guide</title></head><body>\
+             <h2>Install</h2><p>Run the installer.</p></body></html>",
+        )
+        .unwrap();
+
+        let out = run(&tool(&dir), json!({"path": "page.html"}))
+            .await
+            .unwrap();
+        assert!(out.starts_with("[page.html: HTML, "), "{out}");
+        assert!(out.contains("# Guide"), "{out}");
+        assert!(out.contains("## Install"), "{out}");
+        assert!(out.contains("Run the installer."), "{out}");
+    }
+
+    #[tokio::test]
+    async fn test_docx_is_read_with_headings() {
+        use std::io::Write;
+        let dir = TempDir::new().unwrap();
+        let mut archive =
+            zip::ZipWriter::new(std::fs::File::create(dir.path().join("report.docx")).unwrap());
+        archive
+            .start_file(
+                "word/document.xml",
+                zip::write::SimpleFileOptions::default(),
+            )
+            .unwrap();
+        archive
+            .write_all(
+                br#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Summary</w:t></w:r></w:p>
+<w:p><w:r><w:t>All good.</w:t></w:r></w:p>
+</w:body></w:document>"#,
+            )
+            .unwrap();
+        archive.finish().unwrap();
+
+        let out = run(&tool(&dir), json!({"path": "report.docx"}))
+            .await
+            .unwrap();
+        assert_eq!(
+            out,
+            "[report.docx: DOCX, 3 words]\n\n# Summary\nAll good.\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_long_text_is_truncated_with_notice() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("log.txt"), "é".repeat(30)).unwrap();
+
+        let out = run(&tool(&dir), json!({"path": "log.txt", "max_chars": 10}))
+            .await
+            .unwrap();
+        assert!(
+            out.contains(&format!("{}\n\n[TRUNCATED", "é".repeat(10))),
+            "{out}"
+        );
+        assert!(out.contains("first 10 of 30 characters"), "{out}");
+    }
+
+    #[tokio::test]
+    async fn test_missing_and_escaping_paths_are_rejected() {
+        let dir = TempDir::new().unwrap();
+        let tool = tool(&dir);
+        let err = run(&tool, json!({"path": "nope.pdf"})).await.unwrap_err();
+        assert!(err.to_string().contains("File not found"));
+        assert!(run(&tool, json!({"path": "../../etc/passwd"}))
+            .await
+            .is_err());
+        assert!(run(&tool, json!({})).await.is_err());
+    }
+
+    #[cfg(not(feature = "tool-pdf"))]
+    #[tokio::test]
+    async fn test_pdf_without_text_reports_instead_of_returning_empty() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("scan.pdf"), b"%PDF-1.4\n%%EOF\n").unwrap();
+
+        let err = run(&tool(&dir), json!({"path": "scan.pdf"}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("ocr_command"), "{err}");
+    }
+
+    #[cfg(all(unix, not(feature = "tool-pdf")))]
+    #[tokio::test]
+    async fn test_pdf_without_text_goes_through_ocr_when_configured() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("scan.pdf"), b"%PDF-1.4\n%%EOF\n").unwrap();
+        let tool = ExtractTextTool::new(
+            dir.path().to_string_lossy().into_owned(),
+            ExtractTextToolConfig {
+                ocr_command: Some("sh".to_string()),
+                ocr_args: vec![
+                    "-c".to_string(),
+                    "echo \"recognized from $(basename \"$0\")\"".to_string(),
+                    "{path}".to_string(),
+                ],
+                ..Default::default()
+            },
+        );
+
+        let out = run(&tool, json!({"path": "scan.pdf"})).await.unwrap();
+        assert!(out.contains("text recognized by OCR"), "{out}");
+        assert!(out.ends_with("recognized from scan.pdf\n"), "{out}");
+    }
+}
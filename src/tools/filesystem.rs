@@ -6,45 +6,92 @@
 
 use async_trait::async_trait;
 use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::io::Write as _;
 #[cfg(unix)]
-use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
 use std::path::Path;
-#[cfg(unix)]
-use std::{fs::OpenOptions, io::Write as _, os::unix::fs::MetadataExt};
+use std::sync::Arc;
 
 use unicode_normalization::UnicodeNormalization;
 
 use crate::error::{Result, ZeptoError};
 #[cfg(not(unix))]
 use crate::security::check_hardlink_write;
-use crate::security::{ensure_directory_chain_secure, revalidate_path, validate_path_in_workspace};
-use crate::tools::diff::apply_unified_diff;
+use crate::security::{ensure_directory_chain_secure, revalidate_path, Sandbox};
+use crate::tools::diff::{apply_unified_diff, unified_diff};
 
 use super::output::{truncate_tool_output, DEFAULT_MAX_BYTES, DEFAULT_MAX_LINES};
-use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+use super::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
 
 /// Resolve and validate a path relative to the workspace.
 ///
-/// Requires a workspace to be configured. All paths are validated to stay
-/// within workspace boundaries. This is the correct security posture --
-/// filesystem tools must not operate outside a defined workspace.
+/// `artifact://` references resolve to the session's artifact files. Requires a workspace to be configured. All paths go
+/// through the context's [`Sandbox`], so they stay within workspace
+/// boundaries and respect its denied paths. This is the correct security
+/// posture -- filesystem tools must not operate outside a defined workspace.
 ///
-/// Returns `(resolved_path, workspace)` so callers can re-validate before I/O.
-fn resolve_path(path: &str, ctx: &ToolContext) -> Result<(String, String)> {
-    let workspace = ctx.workspace.as_ref().ok_or_else(|| {
+/// Returns `(resolved_path, sandbox)` so callers can re-check before I/O.
+fn resolve_path(path: &str, ctx: &ToolContext) -> Result<(String, Arc<Sandbox>)> {
+    let (path, sandbox) = input_path(path, ctx)?;
+    let resolved = sandbox.resolve(&path)?;
+    Ok((resolved.to_string_lossy().to_string(), sandbox))
+}
+
+/// Like [`resolve_path`], for a path about to be written; read-only paths
+/// are refused too.
+fn resolve_write_path(path: &str, ctx: &ToolContext) -> Result<(String, Arc<Sandbox>)> {
+    let (path, sandbox) = input_path(path, ctx)?;
+    let resolved = sandbox.resolve_for_write(&path)?;
+    Ok((resolved.to_string_lossy().to_string(), sandbox))
+}
+
+fn input_path<'a>(path: &'a str, ctx: &ToolContext) -> Result<(Cow<'a, str>, Arc<Sandbox>)> {
+    let sandbox = ctx.sandbox().ok_or_else(|| {
         ZeptoError::SecurityViolation(
             "Workspace not configured; filesystem tools require a workspace for safety".to_string(),
         )
     })?;
-    let safe_path = validate_path_in_workspace(path, workspace)?;
-    Ok((
-        safe_path.as_path().to_string_lossy().to_string(),
-        workspace.clone(),
-    ))
+    Ok((ctx.resolve_input_path(path)?, sandbox))
 }
 
-#[cfg(unix)]
-fn write_file_secure_blocking(path: &Path, workspace: &str, content: &[u8]) -> Result<()> {
+/// How `write_file` treats a file that already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WriteMode {
+    /// Replace the existing contents.
+    Overwrite,
+    /// Add to the end of the existing contents.
+    Append,
+    /// Fail if the file exists.
+    CreateNew,
+}
+
+impl WriteMode {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "overwrite" => Some(Self::Overwrite),
+            "append" => Some(Self::Append),
+            "create_new" => Some(Self::CreateNew),
+            _ => None,
+        }
+    }
+
+    fn open_options(self) -> std::fs::OpenOptions {
+        let mut options = std::fs::OpenOptions::new();
+        match self {
+            Self::Overwrite => options.write(true).create(true),
+            Self::Append => options.append(true).create(true),
+            Self::CreateNew => options.write(true).create_new(true),
+        };
+        options
+    }
+}
+
+/// Open `path` for writing in `mode` after checking its parents stay inside
+/// the workspace, creating missing ones.
+///
+/// Returns the file and whether it existed before.
+fn open_for_write(path: &Path, workspace: &str, mode: WriteMode) -> Result<(std::fs::File, bool)> {
     if let Some(parent) = path.parent() {
         if !parent.as_os_str().is_empty() {
             ensure_directory_chain_secure(parent, workspace)?;
@@ -53,19 +100,42 @@ fn write_file_secure_blocking(path: &Path, workspace: &str, content: &[u8]) -> R
     }
 
     revalidate_path(path, workspace)?;
+    #[cfg(not(unix))]
+    check_hardlink_write(path)?;
 
-    let mut options = OpenOptions::new();
-    options
-        .write(true)
-        .create(true)
-        .custom_flags(libc::O_NOFOLLOW);
-    let mut file = options.open(path).map_err(|e| {
-        ZeptoError::Tool(format!(
-            "Failed to securely open file '{}': {}",
-            path.display(),
-            e
-        ))
+    let existed = std::fs::symlink_metadata(path).is_ok();
+    let mut options = mode.open_options();
+    #[cfg(unix)]
+    options.custom_flags(libc::O_NOFOLLOW);
+    let file = options.open(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::AlreadyExists {
+            ZeptoError::Tool(format!(
+                "File '{}' already exists; use mode 'overwrite' or 'append' to change it",
+                path.display()
+            ))
+        } else {
+            ZeptoError::Tool(format!(
+                "Failed to securely open file '{}': {}",
+                path.display(),
+                e
+            ))
+        }
     })?;
+    Ok((file, existed))
+}
+
+/// Write `content` to `path` in `mode`, refusing to let the file grow past
+/// `max_len` bytes.
+///
+/// Returns whether the file existed before.
+fn write_file_secure_blocking(
+    path: &Path,
+    workspace: &str,
+    content: &[u8],
+    mode: WriteMode,
+    max_len: u64,
+) -> Result<bool> {
+    let (mut file, existed) = open_for_write(path, workspace, mode)?;
 
     let metadata = file.metadata().map_err(|e| {
         ZeptoError::Tool(format!(
@@ -74,6 +144,7 @@ fn write_file_secure_blocking(path: &Path, workspace: &str, content: &[u8]) -> R
             e
         ))
     })?;
+    #[cfg(unix)]
     if metadata.is_file() && metadata.nlink() > 1 {
         return Err(ZeptoError::SecurityViolation(format!(
             "Write blocked: '{}' has {} hard links and may alias content outside workspace",
@@ -82,52 +153,77 @@ fn write_file_secure_blocking(path: &Path, workspace: &str, content: &[u8]) -> R
         )));
     }
 
-    file.set_len(0).map_err(|e| {
-        ZeptoError::Tool(format!(
-            "Failed to truncate file '{}': {}",
+    let kept = if mode == WriteMode::Append {
+        metadata.len()
+    } else {
+        0
+    };
+    let new_len = kept.saturating_add(content.len() as u64);
+    if new_len > max_len {
+        return Err(ZeptoError::Tool(format!(
+            "Write refused: '{}' would be {} bytes, over the {} byte limit",
             path.display(),
-            e
-        ))
-    })?;
-    file.write_all(content).map_err(|e| {
-        ZeptoError::Tool(format!("Failed to write file '{}': {}", path.display(), e))
-    })?;
-
-    Ok(())
-}
-
-#[cfg(not(unix))]
-fn write_file_secure_blocking(path: &Path, workspace: &str, content: &[u8]) -> Result<()> {
-    if let Some(parent) = path.parent() {
-        if !parent.as_os_str().is_empty() {
-            ensure_directory_chain_secure(parent, workspace)?;
-            revalidate_path(parent, workspace)?;
-        }
+            new_len,
+            max_len
+        )));
     }
 
-    revalidate_path(path, workspace)?;
-    check_hardlink_write(path)?;
-    std::fs::write(path, content).map_err(|e| {
+    if mode == WriteMode::Overwrite {
+        file.set_len(0).map_err(|e| {
+            ZeptoError::Tool(format!(
+                "Failed to truncate file '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+    }
+    file.write_all(content).map_err(|e| {
         ZeptoError::Tool(format!("Failed to write file '{}': {}", path.display(), e))
     })?;
-    Ok(())
+
+    Ok(existed)
 }
 
-async fn write_file_secure(path: &Path, workspace: &str, content: &[u8]) -> Result<()> {
+async fn write_file_with_mode(
+    path: &Path,
+    workspace: &str,
+    content: &[u8],
+    mode: WriteMode,
+    max_len: u64,
+) -> Result<bool> {
     let path = path.to_path_buf();
     let workspace = workspace.to_string();
     let content = content.to_vec();
-    tokio::task::spawn_blocking(move || write_file_secure_blocking(&path, &workspace, &content))
+    tokio::task::spawn_blocking(move || {
+        write_file_secure_blocking(&path, &workspace, &content, mode, max_len)
+    })
+    .await
+    .map_err(|e| ZeptoError::Tool(format!("Secure write task failed: {}", e)))?
+}
+
+/// Overwrite `path` with `content`, creating it and its parents as needed.
+pub(crate) async fn write_file_secure(path: &Path, workspace: &str, content: &[u8]) -> Result<()> {
+    write_file_with_mode(path, workspace, content, WriteMode::Overwrite, u64::MAX)
         .await
-        .map_err(|e| ZeptoError::Tool(format!("Secure write task failed: {}", e)))?
+        .map(|_| ())
 }
 
+/// Bytes sniffed from the start of a file to decide whether it is binary.
+pub(crate) const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
 /// Tool for reading file contents.
 ///
-/// Reads the entire contents of a file and returns it as a string.
+/// Returns the file with line numbers, optionally limited to a line range.
+/// Output stops at `max_bytes` (or [`DEFAULT_MAX_LINES`] lines) with a notice
+/// saying where to continue, and only as much of the file as is shown is read.
+/// Binary files are summarized instead of dumped, and invalid UTF-8 is
+/// replaced rather than treated as an error.
 ///
 /// # Parameters
 /// - `path`: The path to the file to read (required)
+/// - `start_line`: First line to return, 1-based (optional, default 1)
+/// - `end_line`: Last line to return, inclusive (optional)
+/// - `max_bytes`: Output size limit (optional, default [`DEFAULT_MAX_BYTES`])
 ///
 /// # Example
 /// ```rust
@@ -139,11 +235,144 @@ async fn write_file_secure(path: &Path, workspace: &str, content: &[u8]) -> Resu
 /// let tool = ReadFileTool;
 /// let ctx = ToolContext::new();
 /// // Assuming /tmp/test.txt exists with content "hello"
-/// // let result = tool.execute(json!({"path": "/tmp/test.txt"}), &ctx).await;
+/// // let result = tool.execute(json!({"path": "/tmp/test.txt", "start_line": 1}), &ctx).await;
 /// # });
 /// ```
 pub struct ReadFileTool;
 
+/// Read an optional positive integer argument.
+fn optional_positive(args: &Value, key: &str) -> Result<Option<usize>> {
+    match args.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value
+            .as_u64()
+            .filter(|n| *n >= 1)
+            .map(|n| Some(usize::try_from(n).unwrap_or(usize::MAX)))
+            .ok_or_else(|| ZeptoError::Tool(format!("'{}' must be a positive integer", key))),
+    }
+}
+
+/// Format a byte count for humans, e.g. `2.3 MB`.
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} bytes", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Read one line into `line`, keeping at most `cap` bytes of it.
+///
+/// The rest of an over-long line is consumed and dropped, so a file with a
+/// single huge line cannot blow up memory. Returns the number of bytes
+/// consumed, newline included; zero means end of file.
+fn read_capped_line(
+    reader: &mut impl std::io::BufRead,
+    line: &mut Vec<u8>,
+    cap: usize,
+) -> std::io::Result<usize> {
+    let mut consumed = 0;
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(consumed);
+        }
+        let newline = buf.iter().position(|&b| b == b'\n');
+        let take = newline.map_or(buf.len(), |pos| pos + 1);
+        let keep = take.min(cap.saturating_sub(line.len()));
+        line.extend_from_slice(&buf[..keep]);
+        reader.consume(take);
+        consumed += take;
+        if newline.is_some() {
+            return Ok(consumed);
+        }
+    }
+}
+
+/// Render lines `start..=end` of `reader` with line numbers, stopping once
+/// the output would exceed `max_bytes` or [`DEFAULT_MAX_LINES`] lines.
+fn read_numbered_lines(
+    mut reader: impl std::io::BufRead,
+    start: usize,
+    end: Option<usize>,
+    max_bytes: usize,
+) -> std::io::Result<String> {
+    let mut out = String::new();
+    let mut shown = 0;
+    let mut line_no = 1;
+    let mut line = Vec::new();
+    let mut cut = None;
+
+    while end.is_none_or(|end| line_no <= end) {
+        let wanted = line_no >= start;
+        line.clear();
+        let cap = if wanted {
+            max_bytes.saturating_add(1)
+        } else {
+            0
+        };
+        if read_capped_line(&mut reader, &mut line, cap)? == 0 {
+            break;
+        }
+        if wanted {
+            if shown == DEFAULT_MAX_LINES {
+                cut = Some(format!("{} lines", DEFAULT_MAX_LINES));
+                break;
+            }
+            let content = line.strip_suffix(b"\n").unwrap_or(&line);
+            let content = content.strip_suffix(b"\r").unwrap_or(content);
+            let separator = if out.is_empty() { "" } else { "\n" };
+            let entry = format!(
+                "{}{:>6}\t{}",
+                separator,
+                line_no,
+                String::from_utf8_lossy(content)
+            );
+            if out.len() + entry.len() > max_bytes {
+                if shown == 0 {
+                    // A single line longer than the limit: show its head.
+                    let mut boundary = max_bytes.min(entry.len());
+                    while !entry.is_char_boundary(boundary) {
+                        boundary -= 1;
+                    }
+                    out.push_str(&entry[..boundary]);
+                    shown = 1;
+                    line_no += 1;
+                }
+                cut = Some(format!("{} bytes", max_bytes));
+                break;
+            }
+            out.push_str(&entry);
+            shown += 1;
+        }
+        line_no += 1;
+    }
+
+    if shown == 0 && start > 1 {
+        return Ok(format!(
+            "[start_line {} is past the end of the file ({} lines)]",
+            start,
+            line_no - 1
+        ));
+    }
+    if let Some(reason) = cut {
+        out.push_str(&format!(
+            "\n... [output truncated at {}; showing lines {}-{}, use start_line={} to continue]",
+            reason,
+            start,
+            line_no - 1,
+            line_no
+        ));
+    }
+    Ok(out)
+}
+
 #[async_trait]
 impl Tool for ReadFileTool {
     fn name(&self) -> &str {
@@ -151,7 +380,8 @@ impl Tool for ReadFileTool {
     }
 
     fn description(&self) -> &str {
-        "Read the contents of a file at the specified path"
+        "Read a text file with line numbers, optionally a range of lines. \
+         Large output is truncated with a notice saying where to continue; binary files are summarized."
     }
 
     fn compact_description(&self) -> &str {
@@ -169,6 +399,21 @@ impl Tool for ReadFileTool {
                 "path": {
                     "type": "string",
                     "description": "The path to the file to read"
+                },
+                "start_line": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "First line to return, 1-based (default: 1)"
+                },
+                "end_line": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Last line to return, inclusive (default: end of file)"
+                },
+                "max_bytes": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": format!("Maximum output size in bytes (default: {})", DEFAULT_MAX_BYTES)
                 }
             },
             "required": ["path"]
@@ -180,31 +425,67 @@ impl Tool for ReadFileTool {
             .get("path")
             .and_then(|v| v.as_str())
             .ok_or_else(|| ZeptoError::Tool("Missing 'path' argument".into()))?;
+        let start_line = optional_positive(&args, "start_line")?.unwrap_or(1);
+        let end_line = optional_positive(&args, "end_line")?;
+        let max_bytes = optional_positive(&args, "max_bytes")?.unwrap_or(DEFAULT_MAX_BYTES);
+        if end_line.is_some_and(|end| end < start_line) {
+            return Err(ZeptoError::Tool(
+                "'end_line' must not be less than 'start_line'".into(),
+            ));
+        }
 
-        let (full_path, workspace) = resolve_path(path, ctx)?;
+        let (full_path, sandbox) = resolve_path(path, ctx)?;
 
         // TOCTOU: re-validate immediately before I/O
-        revalidate_path(Path::new(&full_path), &workspace)?;
-
-        let content = tokio::fs::read_to_string(&full_path)
+        sandbox.check(Path::new(&full_path))?;
+
+        let read = {
+            let full_path = full_path.clone();
+            tokio::task::spawn_blocking(move || -> std::io::Result<String> {
+                use std::io::Read as _;
+
+                let file = std::fs::File::open(&full_path)?;
+                let size = file.metadata()?.len();
+                let mut head = Vec::with_capacity(BINARY_SNIFF_BYTES);
+                (&file)
+                    .take(BINARY_SNIFF_BYTES as u64)
+                    .read_to_end(&mut head)?;
+                if head.contains(&0) {
+                    return Ok(format!(
+                        "[Binary file, {}; contents not shown]",
+                        human_size(size)
+                    ));
+                }
+                read_numbered_lines(
+                    std::io::BufReader::new(std::io::Cursor::new(head).chain(file)),
+                    start_line,
+                    end_line,
+                    max_bytes,
+                )
+            })
+        };
+        let content = read
             .await
+            .map_err(|e| ZeptoError::Tool(format!("Read task failed: {}", e)))?
             .map_err(|e| ZeptoError::Tool(format!("Failed to read file '{}': {}", full_path, e)))?;
-        Ok(ToolOutput::llm_only(truncate_tool_output(
-            &content,
-            DEFAULT_MAX_LINES,
-            DEFAULT_MAX_BYTES,
-        )))
+        Ok(ToolOutput::llm_only(content))
     }
 }
 
+/// Default cap on the size of a file written by [`WriteFileTool`]: 10MB.
+pub const DEFAULT_MAX_WRITE_BYTES: u64 = 10 * 1024 * 1024;
+
 /// Tool for writing content to a file.
 ///
-/// Writes the provided content to a file, creating it if it doesn't exist
-/// or overwriting it if it does.
+/// Writes the provided content to a file, creating it and any missing parent
+/// directories if needed. Writes that would leave the file larger than the
+/// configured cap are refused.
 ///
 /// # Parameters
 /// - `path`: The path to the file to write (required)
 /// - `content`: The content to write to the file (required)
+/// - `mode`: `overwrite` (default), `append`, or `create_new`, which fails if
+///   the file already exists
 ///
 /// # Example
 /// ```rust
@@ -213,12 +494,60 @@ impl Tool for ReadFileTool {
 /// use serde_json::json;
 ///
 /// # tokio_test::block_on(async {
-/// let tool = WriteFileTool;
+/// let tool = WriteFileTool::new();
 /// let ctx = ToolContext::new();
 /// // let result = tool.execute(json!({"path": "/tmp/test.txt", "content": "hello"}), &ctx).await;
 /// # });
 /// ```
-pub struct WriteFileTool;
+#[derive(Debug, Clone)]
+pub struct WriteFileTool {
+    max_file_bytes: u64,
+}
+
+impl WriteFileTool {
+    /// Create a write tool with the default size cap.
+    pub fn new() -> Self {
+        Self {
+            max_file_bytes: DEFAULT_MAX_WRITE_BYTES,
+        }
+    }
+
+    /// Set the largest file, in bytes, a write may produce.
+    pub fn with_max_file_bytes(mut self, max_file_bytes: u64) -> Self {
+        self.max_file_bytes = max_file_bytes;
+        self
+    }
+}
+
+impl Default for WriteFileTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The `path`, `content` and `mode` arguments of a `write_file` call.
+fn write_args(args: &Value) -> Result<(&str, &str, WriteMode)> {
+    let path = args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ZeptoError::Tool("Missing 'path' argument".into()))?;
+
+    let content = args
+        .get("content")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ZeptoError::Tool("Missing 'content' argument".into()))?;
+
+    let mode = match args.get("mode").and_then(|v| v.as_str()) {
+        None => WriteMode::Overwrite,
+        Some(name) => WriteMode::from_name(name).ok_or_else(|| {
+            ZeptoError::Tool(format!(
+                "Unknown mode '{}', expected 'overwrite', 'append' or 'create_new'",
+                name
+            ))
+        })?,
+    };
+    Ok((path, content, mode))
+}
 
 #[async_trait]
 impl Tool for WriteFileTool {
@@ -227,7 +556,9 @@ impl Tool for WriteFileTool {
     }
 
     fn description(&self) -> &str {
-        "Write content to a file at the specified path, creating it if necessary"
+        "Write content to a file, creating it and its parent directories if necessary. \
+         Mode 'overwrite' (default) replaces the file, 'append' adds to it, and \
+         'create_new' fails if the file already exists."
     }
 
     fn compact_description(&self) -> &str {
@@ -249,42 +580,107 @@ impl Tool for WriteFileTool {
                 "content": {
                     "type": "string",
                     "description": "The content to write to the file"
+                },
+                "mode": {
+                    "type": "string",
+                    "enum": ["overwrite", "append", "create_new"],
+                    "description": "How to treat an existing file (default: overwrite). Use create_new to avoid clobbering."
                 }
             },
             "required": ["path", "content"]
         })
     }
 
-    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
-        let path = args
-            .get("path")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ZeptoError::Tool("Missing 'path' argument".into()))?;
-
-        let content = args
-            .get("content")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ZeptoError::Tool("Missing 'content' argument".into()))?;
+    async fn dry_run(&self, args: &Value, ctx: &ToolContext) -> Result<DryRun> {
+        let (path, content, mode) = write_args(args)?;
+        let (full_path, _) = resolve_write_path(path, ctx)?;
+        let existed = tokio::fs::symlink_metadata(&full_path).await.is_ok();
+        let kept = match mode {
+            WriteMode::Append if existed => tokio::fs::metadata(&full_path).await?.len(),
+            _ => 0,
+        };
+        let size = kept.saturating_add(content.len() as u64);
+        if size > self.max_file_bytes {
+            return Err(ZeptoError::Tool(format!(
+                "Write refused: '{}' would be {} bytes, over the {} byte limit",
+                path, size, self.max_file_bytes
+            )));
+        }
+        let outcome = match (mode, existed) {
+            (_, false) => "new file",
+            (WriteMode::CreateNew, true) => {
+                return Err(ZeptoError::Tool(format!(
+                    "File '{}' already exists; use mode 'overwrite' or 'append' to change it",
+                    path
+                )))
+            }
+            (WriteMode::Append, true) => "appending to existing file",
+            (WriteMode::Overwrite, true) => "replacing existing file",
+        };
+        Ok(DryRun::Describe(format!(
+            "would write {} to {} ({})",
+            human_size(content.len() as u64),
+            path,
+            outcome
+        )))
+    }
 
-        let (full_path, workspace) = resolve_path(path, ctx)?;
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let (path, content, mode) = write_args(&args)?;
+        let (full_path, sandbox) = resolve_write_path(path, ctx)?;
         let full_path_ref = Path::new(&full_path);
 
-        write_file_secure(full_path_ref, &workspace, content.as_bytes()).await?;
+        let existed = write_file_with_mode(
+            full_path_ref,
+            sandbox.workspace(),
+            content.as_bytes(),
+            mode,
+            self.max_file_bytes,
+        )
+        .await?;
 
+        let outcome = match (mode, existed) {
+            (_, false) => "new file",
+            (WriteMode::Append, true) => "appended to existing file",
+            (_, true) => "replaced existing file",
+        };
         Ok(ToolOutput::llm_only(format!(
-            "Successfully wrote {} bytes to {}",
+            "Successfully wrote {} bytes to {} ({})",
             content.len(),
-            full_path
+            full_path,
+            outcome
         )))
     }
 }
 
+/// Names `list_dir` skips by default: VCS metadata and build/dependency output.
+pub const DEFAULT_LIST_IGNORE: [&str; 3] = [".git", "node_modules", "target"];
+
+/// Default number of directory levels `list_dir` descends for a pattern.
+pub const DEFAULT_LIST_MAX_DEPTH: usize = 8;
+
+/// Default and maximum number of entries `list_dir` returns.
+const DEFAULT_LIST_RESULTS: usize = 200;
+const MAX_LIST_RESULTS: usize = 1_000;
+
+/// Directory entries examined before a listing gives up, so a huge tree
+/// cannot stall the tool.
+const MAX_LIST_SCAN: usize = 20_000;
+
 /// Tool for listing directory contents.
 ///
-/// Lists all files and directories in the specified path.
+/// Lists the entries in the specified path, one per line, with type, size
+/// and modification time (UTC). With a glob `pattern` it walks the tree
+/// instead, up to a bounded depth, returning entries whose path relative to
+/// `path` matches. Entries are in tree order with names sorted, so output
+/// is deterministic. Names on the ignore list (by default
+/// [`DEFAULT_LIST_IGNORE`]) are skipped and not descended into, and
+/// symlinks are listed but never followed.
 ///
 /// # Parameters
 /// - `path`: The path to the directory to list (required)
+/// - `pattern`: Glob matched against relative paths, e.g. `**/*.rs` (optional)
+/// - `max_results`: Maximum entries to return (optional, default 200)
 ///
 /// # Example
 /// ```rust
@@ -293,12 +689,155 @@ impl Tool for WriteFileTool {
 /// use serde_json::json;
 ///
 /// # tokio_test::block_on(async {
-/// let tool = ListDirTool;
+/// let tool = ListDirTool::new();
 /// let ctx = ToolContext::new();
-/// // let result = tool.execute(json!({"path": "/tmp"}), &ctx).await;
+/// // let result = tool.execute(json!({"path": "/tmp", "pattern": "**/*.log"}), &ctx).await;
 /// # });
 /// ```
-pub struct ListDirTool;
+#[derive(Debug, Clone)]
+pub struct ListDirTool {
+    ignore: Vec<String>,
+    max_depth: usize,
+}
+
+impl ListDirTool {
+    /// Create a listing tool with the default ignore list and depth.
+    pub fn new() -> Self {
+        Self {
+            ignore: DEFAULT_LIST_IGNORE.map(String::from).to_vec(),
+            max_depth: DEFAULT_LIST_MAX_DEPTH,
+        }
+    }
+
+    /// Replace the names skipped while listing.
+    pub fn with_ignore(mut self, ignore: Vec<String>) -> Self {
+        self.ignore = ignore;
+        self
+    }
+
+    /// Set how many directory levels a pattern search descends.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth.max(1);
+        self
+    }
+}
+
+impl Default for ListDirTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Result of walking a directory for `list_dir`.
+#[derive(Debug, Default)]
+struct Listing {
+    lines: Vec<String>,
+    /// More entries matched than were returned.
+    more: bool,
+    /// The walk stopped at [`MAX_LIST_SCAN`] entries.
+    scan_capped: bool,
+    /// Ignored names that were present.
+    ignored: std::collections::BTreeSet<String>,
+}
+
+struct ListWalk<'a> {
+    pattern: Option<glob::Pattern>,
+    ignore: &'a [String],
+    /// Denied paths are left out as if they did not exist.
+    sandbox: &'a Sandbox,
+    max_depth: usize,
+    max_results: usize,
+    scanned: usize,
+    listing: Listing,
+    cancellation: tokio_util::sync::CancellationToken,
+}
+
+impl ListWalk<'_> {
+    /// Visit `dir` (at `rel`, relative to the listing root) depth-first.
+    /// Returns `false` once the listing is full or the turn is stopped.
+    fn visit(&mut self, dir: &Path, rel: &str, depth: usize) -> std::io::Result<bool> {
+        let mut entries = std::fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            if self.cancellation.is_cancelled() {
+                return Ok(false);
+            }
+            self.scanned += 1;
+            if self.scanned > MAX_LIST_SCAN {
+                self.listing.scan_capped = true;
+                return Ok(false);
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if self.ignore.contains(&name) {
+                self.listing.ignored.insert(name);
+                continue;
+            }
+            if self.sandbox.is_denied(&entry.path()) {
+                continue;
+            }
+            let rel_path = if rel.is_empty() {
+                name
+            } else {
+                format!("{}/{}", rel, name)
+            };
+            // Does not follow symlinks.
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let is_dir = metadata.is_dir();
+
+            let matched = self.pattern.as_ref().is_none_or(|pattern| {
+                pattern.matches_with(
+                    &rel_path,
+                    glob::MatchOptions {
+                        require_literal_separator: true,
+                        ..Default::default()
+                    },
+                )
+            });
+            if matched {
+                if self.listing.lines.len() == self.max_results {
+                    self.listing.more = true;
+                    return Ok(false);
+                }
+                self.listing
+                    .lines
+                    .push(format_list_entry(&rel_path, &metadata));
+            }
+
+            if is_dir && self.pattern.is_some() && depth < self.max_depth {
+                // Unreadable subdirectories are skipped rather than failing
+                // the whole listing.
+                match self.visit(&entry.path(), &rel_path, depth + 1) {
+                    Ok(true) | Err(_) => {}
+                    Ok(false) => return Ok(false),
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// One `list_dir` line: type, size, modified time (UTC) and path.
+fn format_list_entry(rel_path: &str, metadata: &std::fs::Metadata) -> String {
+    let (kind, size, suffix) = if metadata.is_dir() {
+        ("dir ", "-".to_string(), "/")
+    } else if metadata.is_symlink() {
+        ("link", "-".to_string(), "@")
+    } else {
+        ("file", human_size(metadata.len()), "")
+    };
+    let modified = metadata
+        .modified()
+        .map(|time| {
+            chrono::DateTime::<chrono::Utc>::from(time)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|_| "-".to_string());
+    format!("{} {:>9}  {}  {}{}", kind, size, modified, rel_path, suffix)
+}
 
 #[async_trait]
 impl Tool for ListDirTool {
@@ -307,7 +846,9 @@ impl Tool for ListDirTool {
     }
 
     fn description(&self) -> &str {
-        "List the contents of a directory at the specified path"
+        "List a directory with entry type, size and modified time (UTC). \
+         Pass a glob pattern (e.g. '**/*.rs') to search the tree below it instead. \
+         Skips .git, node_modules and target by default."
     }
 
     fn compact_description(&self) -> &str {
@@ -325,6 +866,15 @@ impl Tool for ListDirTool {
                 "path": {
                     "type": "string",
                     "description": "The path to the directory to list"
+                },
+                "pattern": {
+                    "type": "string",
+                    "description": "Glob matched against paths relative to 'path', searching subdirectories (e.g. '**/*.rs', 'src/*'). '*' does not cross '/'."
+                },
+                "max_results": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": format!("Maximum entries to return (default: {}, max: {})", DEFAULT_LIST_RESULTS, MAX_LIST_RESULTS)
                 }
             },
             "required": ["path"]
@@ -336,54 +886,101 @@ impl Tool for ListDirTool {
             .get("path")
             .and_then(|v| v.as_str())
             .ok_or_else(|| ZeptoError::Tool("Missing 'path' argument".into()))?;
+        let pattern = args
+            .get("pattern")
+            .and_then(|v| v.as_str())
+            .map(|p| {
+                glob::Pattern::new(p)
+                    .map_err(|e| ZeptoError::Tool(format!("Invalid pattern '{}': {}", p, e)))
+            })
+            .transpose()?;
+        let max_results = optional_positive(&args, "max_results")?
+            .unwrap_or(DEFAULT_LIST_RESULTS)
+            .min(MAX_LIST_RESULTS);
 
-        let (full_path, workspace) = resolve_path(path, ctx)?;
+        let (full_path, sandbox) = resolve_path(path, ctx)?;
 
         // TOCTOU: re-validate immediately before I/O
-        revalidate_path(Path::new(&full_path), &workspace)?;
-
-        let mut entries = tokio::fs::read_dir(&full_path).await.map_err(|e| {
-            ZeptoError::Tool(format!("Failed to read directory '{}': {}", full_path, e))
-        })?;
-
-        let mut items = Vec::new();
-
-        while let Some(entry) = entries
-            .next_entry()
+        sandbox.check(Path::new(&full_path))?;
+
+        let walk = {
+            let full_path = full_path.clone();
+            let ignore = self.ignore.clone();
+            let max_depth = self.max_depth;
+            let cancellation = ctx.cancellation.clone();
+            tokio::task::spawn_blocking(move || {
+                let mut walk = ListWalk {
+                    pattern,
+                    ignore: &ignore,
+                    sandbox: &sandbox,
+                    max_depth,
+                    max_results,
+                    scanned: 0,
+                    listing: Listing::default(),
+                    cancellation,
+                };
+                walk.visit(Path::new(&full_path), "", 1)
+                    .map(|_| walk.listing)
+            })
+        };
+        let listing = walk
             .await
-            .map_err(|e| ZeptoError::Tool(format!("Failed to read directory entry: {}", e)))?
-        {
-            let file_name = entry.file_name().to_string_lossy().to_string();
-            let file_type = entry.file_type().await.ok();
-
-            let type_indicator = match file_type {
-                Some(ft) if ft.is_dir() => "/",
-                Some(ft) if ft.is_symlink() => "@",
-                _ => "",
-            };
+            .map_err(|e| ZeptoError::Tool(format!("Listing task failed: {}", e)))?
+            .map_err(|e| {
+                ZeptoError::Tool(format!("Failed to read directory '{}': {}", full_path, e))
+            })?;
+        ctx.check_cancelled()?;
 
-            items.push(format!("{}{}", file_name, type_indicator));
+        let mut output = listing.lines.join("\n");
+        let mut notes = Vec::new();
+        if listing.lines.is_empty() {
+            notes.push("no matching entries".to_string());
         }
-
-        items.sort();
-        let joined = items.join("\n");
-        Ok(ToolOutput::llm_only(truncate_tool_output(
-            &joined,
-            DEFAULT_MAX_LINES,
-            DEFAULT_MAX_BYTES,
-        )))
+        if listing.more {
+            notes.push(format!(
+                "stopped at {} entries; narrow the pattern or raise max_results",
+                max_results
+            ));
+        }
+        if listing.scan_capped {
+            notes.push(format!(
+                "stopped after scanning {} entries; narrow the path or pattern",
+                MAX_LIST_SCAN
+            ));
+        }
+        if !listing.ignored.is_empty() {
+            notes.push(format!(
+                "skipped {}",
+                listing.ignored.into_iter().collect::<Vec<_>>().join(", ")
+            ));
+        }
+        for note in notes {
+            if !output.is_empty() {
+                output.push('\n');
+            }
+            output.push_str(&format!("[{}]", note));
+        }
+        Ok(ToolOutput::llm_only(output))
     }
 }
 
-/// Tool for editing a file by replacing content.
+/// Tool for editing a file by replacing text.
 ///
 /// Searches for a specific string in the file and replaces it with new content.
 /// This is useful for making targeted edits without rewriting the entire file.
+/// The result includes a unified diff of the change so the model can check it.
+/// When the file uses CRLF line endings, `\n` in the search and replacement
+/// text is matched and written as `\r\n`.
 ///
 /// # Parameters
 /// - `path`: The path to the file to edit (required)
-/// - `old_text`: The text to search for and replace (required)
-/// - `new_text`: The text to replace it with (required)
+/// - `old_text` (or `old_string`): The text to search for and replace
+/// - `new_text` (or `new_string`): The text to replace it with
+/// - `occurrence`: Which exact match to replace, 1-based, when the text
+///   appears more than once (optional)
+/// - `expected_replacements`: Replace every exact match, requiring this many
+///   (optional)
+/// - `diff`: A unified diff to apply instead of a replacement (optional)
 ///
 /// # Example
 /// ```rust
@@ -392,7 +989,7 @@ impl Tool for ListDirTool {
 /// use serde_json::json;
 ///
 /// # tokio_test::block_on(async {
-/// let tool = EditFileTool;
+/// let tool = EditFileTool::new();
 /// let ctx = ToolContext::new();
 /// // let result = tool.execute(json!({
 /// //     "path": "/tmp/test.txt",
@@ -401,72 +998,99 @@ impl Tool for ListDirTool {
 /// // }), &ctx).await;
 /// # });
 /// ```
-pub struct EditFileTool;
-
-#[async_trait]
-impl Tool for EditFileTool {
-    fn name(&self) -> &str {
-        "edit_file"
-    }
-
-    fn description(&self) -> &str {
-        "Edit a file using either exact string replacement (old_text/new_text) or a unified diff patch (diff). String replacements must resolve to a single match unless expected_replacements is provided."
-    }
+#[derive(Debug, Clone)]
+pub struct EditFileTool {
+    max_file_bytes: u64,
+}
 
-    fn compact_description(&self) -> &str {
-        "Edit file"
+impl EditFileTool {
+    /// Create an edit tool with the default size cap.
+    pub fn new() -> Self {
+        Self {
+            max_file_bytes: DEFAULT_MAX_WRITE_BYTES,
+        }
     }
 
-    fn category(&self) -> ToolCategory {
-        ToolCategory::FilesystemWrite
+    /// Set the largest file, in bytes, the tool will edit or produce.
+    pub fn with_max_file_bytes(mut self, max_file_bytes: u64) -> Self {
+        self.max_file_bytes = max_file_bytes;
+        self
     }
 
-    fn parameters(&self) -> Value {
-        json!({
-            "type": "object",
-            "properties": {
-                "path": {
-                    "type": "string",
-                    "description": "The path to the file to edit"
-                },
-                "old_text": {
-                    "type": "string",
-                    "description": "The text to search for and replace. Must resolve to a single match unless expected_replacements is provided."
-                },
-                "new_text": {
-                    "type": "string",
-                    "description": "The text to replace it with"
-                },
-                "expected_replacements": {
-                    "type": "integer",
-                    "description": "Optional exact number of matches required before applying the replacement"
-                },
-                "diff": {
-                    "type": "string",
-                    "description": "A unified diff patch to apply. Use standard @@ hunk headers with +/- lines. Mutually exclusive with old_text/new_text."
-                },
-                "expected_replacements": {
-                    "type": "integer",
-                    "description": "Exact number of occurrences to replace. When provided, all exact matches are replaced with count validation. When omitted, the match must be unique (fuzzy matching is used as fallback)."
-                }
-            },
-            "required": ["path"]
-        })
+    /// Read the file to edit, refusing files over the size cap.
+    async fn read_for_edit(&self, full_path: &str) -> Result<String> {
+        if let Ok(metadata) = tokio::fs::metadata(full_path).await {
+            if metadata.len() > self.max_file_bytes {
+                return Err(ZeptoError::Tool(format!(
+                    "File '{}' is {} bytes, over the {} byte limit for edits",
+                    full_path,
+                    metadata.len(),
+                    self.max_file_bytes
+                )));
+            }
+        }
+        tokio::fs::read_to_string(full_path)
+            .await
+            .map_err(|e| ZeptoError::Tool(format!("Failed to read file '{}': {}", full_path, e)))
+    }
+
+    /// Write a string replacement and report it with a diff of the change.
+    /// With `dry_run` nothing is written and only the summary is returned.
+    async fn write_replacement(
+        &self,
+        full_path: &str,
+        workspace: &str,
+        content: &str,
+        new_content: &str,
+        replaced: String,
+        dry_run: bool,
+    ) -> Result<String> {
+        let label = Path::new(full_path)
+            .strip_prefix(workspace)
+            .unwrap_or(Path::new(full_path))
+            .to_string_lossy();
+        if dry_run {
+            return Ok(format!("would replace {} in {}", replaced, label));
+        }
+        write_file_with_mode(
+            Path::new(full_path),
+            workspace,
+            new_content.as_bytes(),
+            WriteMode::Overwrite,
+            self.max_file_bytes,
+        )
+        .await?;
+        let diff = unified_diff(&label, content, new_content);
+        Ok(format!(
+            "Successfully replaced {} in {}\n\n{}",
+            replaced,
+            full_path,
+            truncate_tool_output(&diff, DEFAULT_MAX_LINES, DEFAULT_MAX_BYTES)
+        ))
     }
 
-    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+    /// Apply the edit described by `args` and report it, or with `dry_run`
+    /// only check that it applies and say what it would change.
+    async fn edit(&self, args: &Value, ctx: &ToolContext, dry_run: bool) -> Result<String> {
         let path = args
             .get("path")
             .and_then(|v| v.as_str())
             .ok_or_else(|| ZeptoError::Tool("Missing 'path' argument".into()))?;
 
         let diff_param = args.get("diff").and_then(|v| v.as_str());
-        let old_text = args.get("old_text").and_then(|v| v.as_str());
-        let new_text = args.get("new_text").and_then(|v| v.as_str());
+        let old_text = args
+            .get("old_text")
+            .or_else(|| args.get("old_string"))
+            .and_then(|v| v.as_str());
+        let new_text = args
+            .get("new_text")
+            .or_else(|| args.get("new_string"))
+            .and_then(|v| v.as_str());
         let expected_replacements = args
             .get("expected_replacements")
             .and_then(|v| v.as_u64())
             .map(|n| n as usize);
+        let occurrence = optional_positive(args, "occurrence")?;
 
         if diff_param.is_some() && (old_text.is_some() || new_text.is_some()) {
             return Err(ZeptoError::Tool(
@@ -480,39 +1104,88 @@ impl Tool for EditFileTool {
             ));
         }
 
-        let (full_path, workspace) = resolve_path(path, ctx)?;
+        if occurrence.is_some() && expected_replacements.is_some() {
+            return Err(ZeptoError::Tool(
+                "Provide either 'occurrence' or 'expected_replacements', not both.".into(),
+            ));
+        }
+
+        let (full_path, sandbox) = resolve_write_path(path, ctx)?;
         let full_path_ref = Path::new(&full_path);
 
         if let Some(diff_str) = diff_param {
             // --- Unified diff mode ---
-            revalidate_path(full_path_ref, &workspace)?;
+            sandbox.check_write(full_path_ref)?;
 
-            let content = tokio::fs::read_to_string(&full_path).await.map_err(|e| {
-                ZeptoError::Tool(format!("Failed to read file '{}': {}", full_path, e))
-            })?;
+            let content = self.read_for_edit(&full_path).await?;
 
             let (new_content, summary) = apply_unified_diff(&content, diff_str)
                 .map_err(|e| ZeptoError::Tool(format!("Diff apply failed: {}", e)))?;
 
-            write_file_secure(full_path_ref, &workspace, new_content.as_bytes()).await?;
+            if dry_run {
+                return Ok(format!(
+                    "would apply {} hunk(s): +{} -{} to {}",
+                    summary.hunks_applied, summary.lines_added, summary.lines_removed, path
+                ));
+            }
+
+            write_file_with_mode(
+                full_path_ref,
+                sandbox.workspace(),
+                new_content.as_bytes(),
+                WriteMode::Overwrite,
+                self.max_file_bytes,
+            )
+            .await?;
 
-            Ok(ToolOutput::llm_only(format!(
+            Ok(format!(
                 "Applied {} hunk(s): +{} -{} in {}",
                 summary.hunks_applied, summary.lines_added, summary.lines_removed, full_path
-            )))
+            ))
         } else if let (Some(old_text), Some(new_text)) = (old_text, new_text) {
             // --- String replacement mode ---
-            revalidate_path(full_path_ref, &workspace)?;
+            sandbox.check_write(full_path_ref)?;
 
             if old_text.is_empty() {
                 return Err(ZeptoError::Tool("'old_text' must not be empty".into()));
             }
 
-            let content = tokio::fs::read_to_string(&full_path).await.map_err(|e| {
-                ZeptoError::Tool(format!("Failed to read file '{}': {}", full_path, e))
-            })?;
+            let content = self.read_for_edit(&full_path).await?;
+            let (old_text, new_text) = match_line_endings(&content, old_text, new_text);
+            let (old_text, new_text) = (old_text.as_ref(), new_text.as_ref());
 
-            if let Some(expected) = expected_replacements {
+            if let Some(occurrence) = occurrence {
+                // Pick one of several exact matches
+                let positions = find_all_occurrences(&content, old_text);
+                if positions.is_empty() {
+                    return Err(ZeptoError::Tool(format!(
+                        "Text '{}' not found in file '{}'",
+                        crate::utils::string::preview(old_text, 50),
+                        full_path
+                    )));
+                }
+                let Some(&start) = positions.get(occurrence - 1) else {
+                    return Err(ZeptoError::Tool(format!(
+                        "Occurrence {} requested but the text appears {} time(s) in '{}'",
+                        occurrence,
+                        positions.len(),
+                        full_path
+                    )));
+                };
+                let mut new_content = String::with_capacity(content.len());
+                new_content.push_str(&content[..start]);
+                new_content.push_str(new_text);
+                new_content.push_str(&content[start + old_text.len()..]);
+                self.write_replacement(
+                    &full_path,
+                    sandbox.workspace(),
+                    &content,
+                    &new_content,
+                    format!("1 occurrence (match {} of {})", occurrence, positions.len()),
+                    dry_run,
+                )
+                .await
+            } else if let Some(expected) = expected_replacements {
                 // Guarded multi-match: exact matching with count check
                 let replacements = content.matches(old_text).count();
                 if replacements == 0 {
@@ -532,11 +1205,15 @@ impl Tool for EditFileTool {
                     )));
                 }
                 let new_content = content.replace(old_text, new_text);
-                write_file_secure(full_path_ref, &workspace, new_content.as_bytes()).await?;
-                Ok(ToolOutput::llm_only(format!(
-                    "Successfully replaced {} occurrence(s) in {}",
-                    replacements, full_path
-                )))
+                self.write_replacement(
+                    &full_path,
+                    sandbox.workspace(),
+                    &content,
+                    &new_content,
+                    format!("{} occurrence(s)", replacements),
+                    dry_run,
+                )
+                .await
             } else {
                 // Unique match with tiered fuzzy matching
                 match find_unique_match(&content, old_text) {
@@ -545,16 +1222,19 @@ impl Tool for EditFileTool {
                         new_content.push_str(&content[..m.start]);
                         new_content.push_str(new_text);
                         new_content.push_str(&content[m.end..]);
-                        write_file_secure(full_path_ref, &workspace, new_content.as_bytes())
-                            .await?;
-                        Ok(ToolOutput::llm_only(format!(
-                            "Successfully replaced 1 occurrence ({} match) in {}",
-                            m.tier, full_path
-                        )))
+                        self.write_replacement(
+                            &full_path,
+                            sandbox.workspace(),
+                            &content,
+                            &new_content,
+                            format!("1 occurrence ({} match)", m.tier),
+                            dry_run,
+                        )
+                        .await
                     }
                     Err(EditMatchError::MultipleMatches(n)) => {
                         Err(ZeptoError::Tool(format!(
-                            "Found {} occurrences of text in '{}'. Provide more surrounding context to uniquely identify the location.",
+                            "Found {} occurrences of text in '{}'. Provide more surrounding context to uniquely identify the location, or pass 'occurrence' to pick one.",
                             n, full_path
                         )))
                     }
@@ -576,6 +1256,98 @@ impl Tool for EditFileTool {
     }
 }
 
+impl Default for EditFileTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Use CRLF in the edit strings when the file does.
+///
+/// Models almost always send `\n`; without this an edit to a CRLF file
+/// either fails to match or leaves the file with mixed line endings. The
+/// strings are left alone if they already contain `\r` or if the converted
+/// search text is not in the file (e.g. a file with mixed endings).
+fn match_line_endings<'a>(
+    content: &str,
+    old_text: &'a str,
+    new_text: &'a str,
+) -> (Cow<'a, str>, Cow<'a, str>) {
+    if content.contains("\r\n") && !old_text.contains('\r') && !new_text.contains('\r') {
+        let old_crlf = old_text.replace('\n', "\r\n");
+        if content.contains(&old_crlf) {
+            return (
+                Cow::Owned(old_crlf),
+                Cow::Owned(new_text.replace('\n', "\r\n")),
+            );
+        }
+    }
+    (Cow::Borrowed(old_text), Cow::Borrowed(new_text))
+}
+
+#[async_trait]
+impl Tool for EditFileTool {
+    fn name(&self) -> &str {
+        "edit_file"
+    }
+
+    fn description(&self) -> &str {
+        "Edit a file using either exact string replacement (old_text/new_text) or a unified diff patch (diff). \
+         String replacements must resolve to a single match unless occurrence or expected_replacements is provided. \
+         Returns a unified diff of the change."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Edit file"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::FilesystemWrite
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "The path to the file to edit"
+                },
+                "old_text": {
+                    "type": "string",
+                    "description": "The text to search for and replace (also accepted as old_string). Must resolve to a single match unless occurrence or expected_replacements is provided."
+                },
+                "new_text": {
+                    "type": "string",
+                    "description": "The text to replace it with (also accepted as new_string)"
+                },
+                "occurrence": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Which exact match of old_text to replace, 1-based, when it appears more than once"
+                },
+                "diff": {
+                    "type": "string",
+                    "description": "A unified diff patch to apply. Use standard @@ hunk headers with +/- lines. Mutually exclusive with old_text/new_text."
+                },
+                "expected_replacements": {
+                    "type": "integer",
+                    "description": "Exact number of occurrences to replace. When provided, all exact matches are replaced with count validation. When omitted, the match must be unique (fuzzy matching is used as fallback)."
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn dry_run(&self, args: &Value, ctx: &ToolContext) -> Result<DryRun> {
+        self.edit(args, ctx, true).await.map(DryRun::Describe)
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        self.edit(&args, ctx, false).await.map(ToolOutput::llm_only)
+    }
+}
+
 // --- Fuzzy matching for edit_file ---
 #[derive(Debug)]
 enum MatchTier {
@@ -896,7 +1668,7 @@ mod tests {
             .execute(json!({"path": "zeptoclaw_test_read.txt"}), &ctx)
             .await;
         assert!(result.is_ok());
-        assert_eq!(result.unwrap().for_llm, "test content");
+        assert_eq!(result.unwrap().for_llm, "     1\ttest content");
     }
 
     #[tokio::test]
@@ -951,7 +1723,119 @@ mod tests {
 
         let result = tool.execute(json!({"path": "test.txt"}), &ctx).await;
         assert!(result.is_ok());
-        assert_eq!(result.unwrap().for_llm, "workspace content");
+        assert_eq!(result.unwrap().for_llm, "     1\tworkspace content");
+    }
+
+    #[tokio::test]
+    async fn test_read_file_tool_line_range() {
+        let dir = tempdir().unwrap();
+        let content: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        fs::write(dir.path().join("lines.txt"), content).unwrap();
+        let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
+
+        let result = ReadFileTool
+            .execute(
+                json!({"path": "lines.txt", "start_line": 9, "end_line": 11}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            result.for_llm,
+            "     9\tline 9\n    10\tline 10\n    11\tline 11"
+        );
+
+        let result = ReadFileTool
+            .execute(json!({"path": "lines.txt", "start_line": 30}), &ctx)
+            .await
+            .unwrap();
+        assert!(result
+            .for_llm
+            .contains("past the end of the file (20 lines)"));
+
+        let err = ReadFileTool
+            .execute(
+                json!({"path": "lines.txt", "start_line": 5, "end_line": 2}),
+                &ctx,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("'end_line' must not be less"));
+
+        let err = ReadFileTool
+            .execute(json!({"path": "lines.txt", "start_line": 0}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("positive integer"));
+    }
+
+    #[tokio::test]
+    async fn test_read_file_tool_max_bytes_truncates_with_notice() {
+        let dir = tempdir().unwrap();
+        let content: String = (1..=100).map(|i| format!("line {}\n", i)).collect();
+        fs::write(dir.path().join("lines.txt"), content).unwrap();
+        let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
+
+        let result = ReadFileTool
+            .execute(json!({"path": "lines.txt", "max_bytes": 50}), &ctx)
+            .await
+            .unwrap();
+        assert!(result
+            .for_llm
+            .starts_with("     1\tline 1\n     2\tline 2\n"));
+        assert!(result.for_llm.ends_with(
+            "[output truncated at 50 bytes; showing lines 1-3, use start_line=4 to continue]"
+        ));
+    }
+
+    #[test]
+    fn test_read_numbered_lines_bounds_long_lines() {
+        let huge = format!("{}\nnext\n", "é".repeat(10_000));
+        let out = read_numbered_lines(std::io::Cursor::new(huge), 1, None, 21).unwrap();
+        let (head, notice) = out.split_once('\n').unwrap();
+        assert!(head.len() <= 21);
+        assert!(head.starts_with("     1\té"));
+        assert!(notice.contains("use start_line=2 to continue"));
+    }
+
+    #[test]
+    fn test_read_numbered_lines_line_limit() {
+        let content = "x\n".repeat(DEFAULT_MAX_LINES + 5);
+        let out = read_numbered_lines(std::io::Cursor::new(content), 1, None, usize::MAX).unwrap();
+        assert_eq!(out.lines().count(), DEFAULT_MAX_LINES + 1);
+        assert!(out.ends_with(&format!(
+            "use start_line={} to continue]",
+            DEFAULT_MAX_LINES + 1
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_read_file_tool_binary_and_invalid_utf8() {
+        let dir = tempdir().unwrap();
+        let mut binary = vec![0x89, b'P', b'N', b'G', 0, 0];
+        binary.resize(2_400_000, 7);
+        fs::write(dir.path().join("image.png"), binary).unwrap();
+        fs::write(dir.path().join("latin1.txt"), b"caf\xe9\r\nok\n").unwrap();
+        let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
+
+        let result = ReadFileTool
+            .execute(json!({"path": "image.png"}), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(result.for_llm, "[Binary file, 2.3 MB; contents not shown]");
+
+        let result = ReadFileTool
+            .execute(json!({"path": "latin1.txt"}), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(result.for_llm, "     1\tcaf\u{FFFD}\n     2\tok");
+    }
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(512), "512 bytes");
+        assert_eq!(human_size(1536), "1.5 KB");
+        assert_eq!(human_size(2_400_000), "2.3 MB");
     }
 
     #[tokio::test]
@@ -960,7 +1844,7 @@ mod tests {
         // Use canonical path to avoid macOS /var -> /private/var mismatch
         let canonical = dir.path().canonicalize().unwrap();
 
-        let tool = WriteFileTool;
+        let tool = WriteFileTool::new();
         let ctx = ToolContext::new().with_workspace(canonical.to_str().unwrap());
 
         let result = tool
@@ -985,7 +1869,7 @@ mod tests {
         // Use canonical path to avoid macOS /var -> /private/var mismatch
         let canonical = dir.path().canonicalize().unwrap();
 
-        let tool = WriteFileTool;
+        let tool = WriteFileTool::new();
         let ctx = ToolContext::new().with_workspace(canonical.to_str().unwrap());
 
         let result = tool
@@ -998,9 +1882,123 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_write_file_tool_modes() {
+        let dir = tempdir().unwrap();
+        let canonical = dir.path().canonicalize().unwrap();
+        let tool = WriteFileTool::new();
+        let ctx = ToolContext::new().with_workspace(canonical.to_str().unwrap());
+        let file = canonical.join("notes/log.txt");
+
+        let result = tool
+            .execute(
+                json!({"path": "notes/log.txt", "content": "one\n", "mode": "create_new"}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert!(result.for_llm.starts_with("Successfully wrote 4 bytes"));
+        assert!(result.for_llm.ends_with("(new file)"));
+
+        let err = tool
+            .execute(
+                json!({"path": "notes/log.txt", "content": "clobber", "mode": "create_new"}),
+                &ctx,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "one\n");
+
+        let result = tool
+            .execute(
+                json!({"path": "notes/log.txt", "content": "two\n", "mode": "append"}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert!(result.for_llm.ends_with("(appended to existing file)"));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "one\ntwo\n");
+
+        let result = tool
+            .execute(json!({"path": "notes/log.txt", "content": "fresh"}), &ctx)
+            .await
+            .unwrap();
+        assert!(result.for_llm.ends_with("(replaced existing file)"));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "fresh");
+
+        let result = tool
+            .execute(
+                json!({"path": "notes/new.txt", "content": "x", "mode": "append"}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert!(result.for_llm.ends_with("(new file)"));
+
+        let err = tool
+            .execute(
+                json!({"path": "notes/log.txt", "content": "x", "mode": "truncate"}),
+                &ctx,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Unknown mode 'truncate'"));
+    }
+
+    #[tokio::test]
+    async fn test_write_file_tool_size_cap() {
+        let dir = tempdir().unwrap();
+        let canonical = dir.path().canonicalize().unwrap();
+        let tool = WriteFileTool::new().with_max_file_bytes(8);
+        let ctx = ToolContext::new().with_workspace(canonical.to_str().unwrap());
+
+        let err = tool
+            .execute(json!({"path": "big.txt", "content": "123456789"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("over the 8 byte limit"));
+
+        tool.execute(json!({"path": "big.txt", "content": "12345"}), &ctx)
+            .await
+            .unwrap();
+        // Appending counts what is already in the file.
+        let err = tool
+            .execute(
+                json!({"path": "big.txt", "content": "6789", "mode": "append"}),
+                &ctx,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("would be 9 bytes"));
+        assert_eq!(
+            fs::read_to_string(canonical.join("big.txt")).unwrap(),
+            "12345"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_file_tool_modes_reject_traversal() {
+        let dir = tempdir().unwrap();
+        let workspace = dir.path().join("ws");
+        fs::create_dir(&workspace).unwrap();
+        let ctx = ToolContext::new().with_workspace(workspace.to_str().unwrap());
+
+        for mode in ["overwrite", "append", "create_new"] {
+            let result = WriteFileTool::new()
+                .execute(
+                    json!({"path": "../escape.txt", "content": "x", "mode": mode}),
+                    &ctx,
+                )
+                .await;
+            assert!(result.is_err(), "{mode}");
+        }
+        assert!(!dir.path().join("escape.txt").exists());
+    }
+
     #[tokio::test]
     async fn test_write_file_tool_missing_content() {
-        let tool = WriteFileTool;
+        let tool = WriteFileTool::new();
         let ctx = ToolContext::new().with_workspace("/tmp");
 
         let result = tool.execute(json!({"path": "test.txt"}), &ctx).await;
@@ -1018,7 +2016,7 @@ mod tests {
         fs::write(dir.path().join("file2.txt"), "").unwrap();
         fs::create_dir(dir.path().join("subdir")).unwrap();
 
-        let tool = ListDirTool;
+        let tool = ListDirTool::new();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
 
         let result = tool.execute(json!({"path": "."}), &ctx).await;
@@ -1030,13 +2028,129 @@ mod tests {
         assert!(output.contains("subdir/"));
     }
 
+    #[tokio::test]
+    async fn test_list_dir_tool_entry_details_and_ignore() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("b.txt"), "x".repeat(1536)).unwrap();
+        fs::create_dir(dir.path().join("a")).unwrap();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::create_dir(dir.path().join("node_modules")).unwrap();
+        let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
+
+        let output = ListDirTool::new()
+            .execute(json!({"path": "."}), &ctx)
+            .await
+            .unwrap()
+            .for_llm;
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3, "{output}");
+        assert!(lines[0].starts_with("dir ") && lines[0].ends_with("  a/"));
+        assert!(lines[1].starts_with("file    1.5 KB  "));
+        assert!(lines[1].ends_with("  b.txt"));
+        assert_eq!(lines[2], "[skipped .git, node_modules]");
+
+        let output = ListDirTool::new()
+            .with_ignore(vec![])
+            .execute(json!({"path": "."}), &ctx)
+            .await
+            .unwrap()
+            .for_llm;
+        assert!(output.contains(".git/"));
+        assert!(output.contains("node_modules/"));
+    }
+
+    #[tokio::test]
+    async fn test_list_dir_tool_pattern() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/bin")).unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::write(root.join("build.rs"), "").unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        fs::write(root.join("src/notes.md"), "").unwrap();
+        fs::write(root.join("src/bin/main.rs"), "").unwrap();
+        fs::write(root.join("target/debug/gen.rs"), "").unwrap();
+        let ctx = ToolContext::new().with_workspace(root.to_str().unwrap());
+        let tool = ListDirTool::new();
+
+        let paths = |output: &str| -> Vec<String> {
+            output
+                .lines()
+                .filter(|l| !l.starts_with('['))
+                .map(|l| l.rsplit("  ").next().unwrap().to_string())
+                .collect()
+        };
+
+        let output = tool
+            .execute(json!({"path": ".", "pattern": "**/*.rs"}), &ctx)
+            .await
+            .unwrap()
+            .for_llm;
+        assert_eq!(
+            paths(&output),
+            ["build.rs", "src/bin/main.rs", "src/lib.rs"]
+        );
+
+        // '*' does not cross directories.
+        let output = tool
+            .execute(json!({"path": ".", "pattern": "*.rs"}), &ctx)
+            .await
+            .unwrap()
+            .for_llm;
+        assert_eq!(paths(&output), ["build.rs"]);
+
+        let output = tool
+            .execute(
+                json!({"path": ".", "pattern": "**/*.rs", "max_results": 2}),
+                &ctx,
+            )
+            .await
+            .unwrap()
+            .for_llm;
+        assert_eq!(paths(&output), ["build.rs", "src/bin/main.rs"]);
+        assert!(output.contains("[stopped at 2 entries"));
+
+        // Depth is bounded.
+        let output = ListDirTool::new()
+            .with_max_depth(1)
+            .execute(json!({"path": ".", "pattern": "**/*.rs"}), &ctx)
+            .await
+            .unwrap()
+            .for_llm;
+        assert_eq!(paths(&output), ["build.rs"]);
+
+        let err = tool
+            .execute(json!({"path": ".", "pattern": "[unclosed"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid pattern"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_list_dir_tool_does_not_follow_symlinks() {
+        let outside = tempdir().unwrap();
+        fs::write(outside.path().join("secret.rs"), "").unwrap();
+        let dir = tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+        let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
+
+        let output = ListDirTool::new()
+            .execute(json!({"path": ".", "pattern": "**/*"}), &ctx)
+            .await
+            .unwrap()
+            .for_llm;
+        assert!(output.contains("link@"));
+        assert!(!output.contains("secret.rs"));
+    }
+
     #[tokio::test]
     async fn test_list_dir_tool_not_found() {
         let dir = tempdir().unwrap();
         // Use canonical path to avoid macOS /var -> /private/var mismatch
         let canonical = dir.path().canonicalize().unwrap();
 
-        let tool = ListDirTool;
+        let tool = ListDirTool::new();
         let ctx = ToolContext::new().with_workspace(canonical.to_str().unwrap());
 
         let result = tool.execute(json!({"path": "nonexistent_dir"}), &ctx).await;
@@ -1054,7 +2168,7 @@ mod tests {
         fs::create_dir(&subdir).unwrap();
         fs::write(subdir.join("inner.txt"), "").unwrap();
 
-        let tool = ListDirTool;
+        let tool = ListDirTool::new();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
 
         let result = tool.execute(json!({"path": "mydir"}), &ctx).await;
@@ -1068,7 +2182,7 @@ mod tests {
         let file_path = dir.path().join("edit_test.txt");
         fs::write(&file_path, "Hello World").unwrap();
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::new();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
 
         let result = tool
@@ -1090,13 +2204,124 @@ mod tests {
         assert_eq!(fs::read_to_string(&file_path).unwrap(), "Hello Rust");
     }
 
+    #[tokio::test]
+    async fn test_edit_file_tool_returns_diff() {
+        let dir = tempdir().unwrap();
+        let canonical = dir.path().canonicalize().unwrap();
+        fs::write(canonical.join("main.rs"), "fn main() {\n    old();\n}\n").unwrap();
+        let ctx = ToolContext::new().with_workspace(canonical.to_str().unwrap());
+
+        let result = EditFileTool::new()
+            .execute(
+                json!({"path": "main.rs", "old_string": "old();", "new_string": "new();"}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert!(result.for_llm.ends_with(
+            "--- a/main.rs\n+++ b/main.rs\n@@ -1,3 +1,3 @@\n fn main() {\n-    old();\n+    new();\n }\n"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_edit_file_tool_occurrence() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("dup.txt");
+        fs::write(&file_path, "x = 1\nx = 1\nx = 1\n").unwrap();
+        let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
+        let tool = EditFileTool::new();
+
+        let err = tool
+            .execute(
+                json!({"path": "dup.txt", "old_text": "x = 1", "new_text": "x = 2"}),
+                &ctx,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Found 3 occurrences"));
+
+        let result = tool
+            .execute(
+                json!({"path": "dup.txt", "old_text": "x = 1", "new_text": "x = 2", "occurrence": 2}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert!(result.for_llm.contains("match 2 of 3"));
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "x = 1\nx = 2\nx = 1\n"
+        );
+
+        let err = tool
+            .execute(
+                json!({"path": "dup.txt", "old_text": "x = 1", "new_text": "x = 2", "occurrence": 3}),
+                &ctx,
+            )
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Occurrence 3 requested but the text appears 2 time(s)"));
+    }
+
+    #[tokio::test]
+    async fn test_edit_file_tool_preserves_crlf() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("win.txt");
+        fs::write(&file_path, "one\r\ntwo\r\nthree\r\n").unwrap();
+        let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
+
+        EditFileTool::new()
+            .execute(
+                json!({"path": "win.txt", "old_text": "one\ntwo", "new_text": "one\n1.5\ntwo"}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "one\r\n1.5\r\ntwo\r\nthree\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_edit_file_tool_size_limit() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("big.txt");
+        fs::write(&file_path, "0123456789").unwrap();
+        let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
+
+        let err = EditFileTool::new()
+            .with_max_file_bytes(5)
+            .execute(
+                json!({"path": "big.txt", "old_text": "0", "new_text": "x"}),
+                &ctx,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("over the 5 byte limit"));
+
+        // The result of the edit is capped too.
+        let err = EditFileTool::new()
+            .with_max_file_bytes(12)
+            .execute(
+                json!({"path": "big.txt", "old_text": "0", "new_text": "xyzw"}),
+                &ctx,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("would be 13 bytes"));
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "0123456789");
+    }
+
     #[tokio::test]
     async fn test_edit_file_tool_multiple_occurrences() {
         let dir = tempdir().unwrap();
         let file_path = dir.path().join("edit_multi.txt");
         fs::write(&file_path, "foo bar foo baz foo").unwrap();
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::new();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
 
         // With expected_replacements matching actual count, all occurrences are replaced
@@ -1126,7 +2351,7 @@ mod tests {
         let file_path = dir.path().join("edit_notfound.txt");
         fs::write(&file_path, "Hello World").unwrap();
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::new();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
 
         let result = tool
@@ -1153,7 +2378,7 @@ mod tests {
         let file_path = dir.path().join("edit_empty_old.txt");
         fs::write(&file_path, "Hello World").unwrap();
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::new();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
 
         let result = tool
@@ -1181,7 +2406,7 @@ mod tests {
         let file_path = dir.path().join("edit_expected_count.txt");
         fs::write(&file_path, "foo bar foo").unwrap();
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::new();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
 
         let result = tool
@@ -1210,7 +2435,7 @@ mod tests {
         let file_path = dir.path().join("edit_expected_ok.txt");
         fs::write(&file_path, "foo bar foo").unwrap();
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::new();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
 
         let result = tool
@@ -1231,7 +2456,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_edit_file_tool_missing_args() {
-        let tool = EditFileTool;
+        let tool = EditFileTool::new();
         let ctx = ToolContext::new().with_workspace("/tmp");
 
         // Missing old_text (only new_text provided)
@@ -1291,26 +2516,26 @@ mod tests {
     #[test]
     fn test_tool_names() {
         assert_eq!(ReadFileTool.name(), "read_file");
-        assert_eq!(WriteFileTool.name(), "write_file");
-        assert_eq!(ListDirTool.name(), "list_dir");
-        assert_eq!(EditFileTool.name(), "edit_file");
+        assert_eq!(WriteFileTool::new().name(), "write_file");
+        assert_eq!(ListDirTool::new().name(), "list_dir");
+        assert_eq!(EditFileTool::new().name(), "edit_file");
     }
 
     #[test]
     fn test_tool_descriptions() {
         assert!(!ReadFileTool.description().is_empty());
-        assert!(!WriteFileTool.description().is_empty());
-        assert!(!ListDirTool.description().is_empty());
-        assert!(!EditFileTool.description().is_empty());
+        assert!(!WriteFileTool::new().description().is_empty());
+        assert!(!ListDirTool::new().description().is_empty());
+        assert!(!EditFileTool::new().description().is_empty());
     }
 
     #[test]
     fn test_tool_parameters() {
         for tool in [
             &ReadFileTool as &dyn Tool,
-            &WriteFileTool,
-            &ListDirTool,
-            &EditFileTool,
+            &WriteFileTool::new(),
+            &ListDirTool::new(),
+            &EditFileTool::new(),
         ] {
             let params = tool.parameters();
             assert!(params.is_object());
@@ -1354,7 +2579,7 @@ mod tests {
     #[tokio::test]
     async fn test_write_tool_rejects_traversal_outside_workspace() {
         let dir = tempdir().unwrap();
-        let tool = WriteFileTool;
+        let tool = WriteFileTool::new();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
 
         let result = tool
@@ -1376,7 +2601,7 @@ mod tests {
     #[tokio::test]
     async fn test_list_dir_rejects_absolute_outside_workspace() {
         let dir = tempdir().unwrap();
-        let tool = ListDirTool;
+        let tool = ListDirTool::new();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
 
         let result = tool.execute(json!({"path": "/etc"}), &ctx).await;
@@ -1389,9 +2614,78 @@ mod tests {
         );
     }
 
+    fn sandboxed_ctx(dir: &std::path::Path) -> ToolContext {
+        let sandbox = Sandbox::new(dir.to_str().unwrap())
+            .with_denied_paths(&[".env", "*.pem"])
+            .with_read_only_paths(&["vendor"]);
+        ToolContext::new().with_sandbox(Arc::new(sandbox))
+    }
+
+    #[tokio::test]
+    async fn test_read_file_refuses_denied_path() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(".env"), "TOKEN=secret").unwrap();
+        let ctx = sandboxed_ctx(dir.path());
+
+        let err = ReadFileTool
+            .execute(json!({"path": ".env"}), &ctx)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("denied path"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_list_dir_hides_denied_entries() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("server.pem"), "KEY").unwrap();
+        fs::write(dir.path().join("main.rs"), "").unwrap();
+        let ctx = sandboxed_ctx(dir.path());
+
+        let output = ListDirTool::new()
+            .execute(json!({"path": "."}), &ctx)
+            .await
+            .unwrap()
+            .for_llm;
+        assert!(output.contains("main.rs"));
+        assert!(!output.contains("server.pem"));
+    }
+
+    #[tokio::test]
+    async fn test_write_and_edit_refuse_read_only_path() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("vendor")).unwrap();
+        fs::write(dir.path().join("vendor/lib.rs"), "hello").unwrap();
+        let ctx = sandboxed_ctx(dir.path());
+
+        let write = WriteFileTool::new()
+            .execute(json!({"path": "vendor/new.rs", "content": "x"}), &ctx)
+            .await;
+        assert!(write.unwrap_err().to_string().contains("read-only"));
+        assert!(!dir.path().join("vendor/new.rs").exists());
+
+        let edit = EditFileTool::new()
+            .execute(
+                json!({"path": "vendor/lib.rs", "old_text": "hello", "new_text": "bye"}),
+                &ctx,
+            )
+            .await;
+        assert!(edit.unwrap_err().to_string().contains("read-only"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("vendor/lib.rs")).unwrap(),
+            "hello"
+        );
+
+        // Reading a read-only file is still allowed.
+        let read = ReadFileTool
+            .execute(json!({"path": "vendor/lib.rs"}), &ctx)
+            .await;
+        assert!(read.unwrap().for_llm.contains("hello"));
+    }
+
     #[tokio::test]
     async fn test_edit_tool_rejects_no_workspace() {
-        let tool = EditFileTool;
+        let tool = EditFileTool::new();
         let ctx = ToolContext::new(); // No workspace configured
 
         let result = tool
@@ -1462,7 +2756,7 @@ mod tests {
         let hardlink = canonical.join("hardlink.txt");
         fs::hard_link(&original, &hardlink).unwrap();
 
-        let tool = WriteFileTool;
+        let tool = WriteFileTool::new();
         let ctx = ToolContext::new().with_workspace(workspace);
 
         // Writing to the hardlinked file should be blocked
@@ -1496,7 +2790,7 @@ mod tests {
         let hardlink = canonical.join("edit_link.txt");
         fs::hard_link(&original, &hardlink).unwrap();
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::new();
         let ctx = ToolContext::new().with_workspace(workspace);
 
         let result = tool
@@ -1528,7 +2822,7 @@ mod tests {
         // Create a regular file (nlink = 1)
         fs::write(canonical.join("normal.txt"), "original").unwrap();
 
-        let tool = WriteFileTool;
+        let tool = WriteFileTool::new();
         let ctx = ToolContext::new().with_workspace(workspace);
 
         let result = tool
@@ -1548,7 +2842,7 @@ mod tests {
         let file_path = dir.path().join("diff_test.txt");
         fs::write(&file_path, "line one\nline two\nline three\n").unwrap();
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::new();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
 
         let result = tool
@@ -1576,7 +2870,7 @@ mod tests {
         let file_path = dir.path().join("diff_mismatch.txt");
         fs::write(&file_path, "foo\nbar\nbaz\n").unwrap();
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::new();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
 
         let result = tool
@@ -1595,7 +2889,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_edit_file_diff_and_old_text_mutually_exclusive() {
-        let tool = EditFileTool;
+        let tool = EditFileTool::new();
         let ctx = ToolContext::new().with_workspace("/tmp");
 
         let result = tool
@@ -1620,7 +2914,7 @@ mod tests {
         let file_path = dir.path().join("test.txt");
         fs::write(&file_path, "foo bar foo baz foo").unwrap();
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::new();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
 
         let result = tool
@@ -1645,7 +2939,7 @@ mod tests {
         let file_path = dir.path().join("test.txt");
         fs::write(&file_path, "fn\tmain()\t{}").unwrap();
 
-        let tool = EditFileTool;
+        let tool = EditFileTool::new();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
 
         let result = tool
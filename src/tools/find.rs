@@ -4,7 +4,6 @@ use async_trait::async_trait;
 use serde_json::{json, Value};
 
 use crate::error::{Result, ZeptoError};
-use crate::security::validate_path_in_workspace;
 
 use super::output::{truncate_tool_output, DEFAULT_MAX_BYTES, DEFAULT_MAX_LINES};
 use super::{Tool, ToolCategory, ToolContext, ToolOutput};
@@ -60,18 +59,15 @@ impl Tool for FindTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| ZeptoError::Tool("Missing 'pattern' argument".into()))?;
 
-        let workspace = ctx.workspace.as_ref().ok_or_else(|| {
+        let sandbox = ctx.sandbox().ok_or_else(|| {
             ZeptoError::SecurityViolation(
                 "Workspace not configured; find requires a workspace".to_string(),
             )
         })?;
 
         let root = match args.get("path").and_then(|v| v.as_str()) {
-            Some(p) => {
-                let safe = validate_path_in_workspace(p, workspace)?;
-                safe.as_path().to_string_lossy().to_string()
-            }
-            None => workspace.clone(),
+            Some(p) => sandbox.resolve(p)?.to_string_lossy().to_string(),
+            None => sandbox.workspace().to_string(),
         };
 
         let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(200) as usize;
@@ -81,9 +77,12 @@ impl Tool for FindTool {
         let entries: Vec<String> = glob::glob(&full_pattern)
             .map_err(|e| ZeptoError::Tool(format!("Invalid glob pattern: {}", e)))?
             .filter_map(|r| r.ok())
+            .take_while(|_| !ctx.is_cancelled())
+            .filter(|p| !sandbox.is_denied(p))
             .take(limit)
             .map(|p| p.display().to_string())
             .collect();
+        ctx.check_cancelled()?;
 
         if entries.is_empty() {
             return Ok(ToolOutput::llm_only(
@@ -7,12 +7,16 @@
 //!
 //! - `status`      — working-tree status
 //! - `log`         — commit history (default 10 entries)
-//! - `diff`        — unstaged diff (optionally scoped to a path)
+//! - `diff`        — unstaged diff, or staged with `staged: true` (optionally scoped to a path)
 //! - `blame`       — per-line authorship for a file (requires `path`)
+//! - `branch`      — create a branch from HEAD, or list branches when `branch` is omitted
 //! - `branch_list` — list local branches
-//! - `commit`      — stage all tracked changes and commit (requires `message`)
+//! - `commit`      — commit staged changes (requires `message`)
 //! - `add`         — stage a file or directory (requires `path`)
-//! - `checkout`    — switch branches (requires `branch`)
+//! - `checkout`    — switch branches (requires `branch`; refused on a dirty tree)
+//!
+//! Destructive operations (force checkout, reset, push) are not exposed.
+//! Output is capped at [`MAX_OUTPUT_BYTES`].
 
 use std::path::Path;
 use std::process::Command;
@@ -20,14 +24,19 @@ use std::process::Command;
 use async_trait::async_trait;
 use serde_json::{json, Value};
 
+use crate::config::GitToolConfig;
 use crate::error::{Result, ZeptoError};
 use crate::security::ShellSecurityConfig;
 
-use super::{Tool, ToolContext, ToolOutput};
+use super::output::{truncate_tool_output, DEFAULT_MAX_LINES};
+use super::{DryRun, Tool, ToolContext, ToolOutput};
 
 const DEFAULT_LOG_COUNT: u64 = 10;
 const MAX_LOG_COUNT: u64 = 200;
 
+/// Maximum bytes of git output returned to the model.
+const MAX_OUTPUT_BYTES: usize = 30_000;
+
 /// Tool that exposes common `git` operations by shelling out to the `git` CLI.
 ///
 /// The tool is skipped at registration time when `git` is not found on PATH
@@ -39,14 +48,13 @@ const MAX_LOG_COUNT: u64 = 200;
 /// permitted by the allowlist before executing any command.
 pub struct GitTool {
     shell_config: ShellSecurityConfig,
+    config: GitToolConfig,
 }
 
 impl GitTool {
     /// Create a new GitTool instance with default (blocklist-only) security.
     pub fn new() -> Self {
-        Self {
-            shell_config: ShellSecurityConfig::new(),
-        }
+        Self::with_security(ShellSecurityConfig::new())
     }
 
     /// Create a new GitTool with an explicit shell security config.
@@ -54,7 +62,16 @@ impl GitTool {
     /// When the config uses `ShellAllowlistMode::Strict`, the git binary
     /// must appear in the allowlist or all actions will be rejected.
     pub fn with_security(shell_config: ShellSecurityConfig) -> Self {
-        Self { shell_config }
+        Self {
+            shell_config,
+            config: GitToolConfig::default(),
+        }
+    }
+
+    /// Apply tool configuration (commit identity).
+    pub fn with_config(mut self, config: GitToolConfig) -> Self {
+        self.config = config;
+        self
     }
 
     /// Return `true` if the `git` binary is reachable on PATH.
@@ -87,7 +104,11 @@ impl GitTool {
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
 
         if output.status.success() {
-            Ok(stdout)
+            Ok(truncate_tool_output(
+                &stdout,
+                DEFAULT_MAX_LINES,
+                MAX_OUTPUT_BYTES,
+            ))
         } else {
             let detail = if stderr.trim().is_empty() {
                 stdout.trim().to_string()
@@ -97,6 +118,38 @@ impl GitTool {
             Err(ZeptoError::Tool(format!("git error: {}", detail)))
         }
     }
+
+    /// Fail with a clear message unless `dir` is inside a git work tree.
+    fn ensure_repo(dir: &str) -> Result<()> {
+        Self::run(&["rev-parse", "--is-inside-work-tree"], dir)
+            .map(|_| ())
+            .map_err(|_| ZeptoError::Tool(format!("'{}' is not a git repository", dir)))
+    }
+
+    /// Reject branch names git would parse as options or refuse as refs.
+    fn validate_branch_name(name: &str, dir: &str) -> Result<()> {
+        if name.starts_with('-') {
+            return Err(ZeptoError::Tool(format!(
+                "Invalid branch name '{}': must not start with '-'",
+                name
+            )));
+        }
+        Self::run(&["check-ref-format", "--branch", name], dir)
+            .map(|_| ())
+            .map_err(|_| ZeptoError::Tool(format!("Invalid branch name '{}'", name)))
+    }
+
+    /// `-c` overrides for the configured commit identity.
+    fn identity_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(name) = &self.config.author_name {
+            args.extend(["-c".to_string(), format!("user.name={}", name)]);
+        }
+        if let Some(email) = &self.config.author_email {
+            args.extend(["-c".to_string(), format!("user.email={}", email)]);
+        }
+        args
+    }
 }
 
 impl Default for GitTool {
@@ -107,7 +160,9 @@ impl Default for GitTool {
 
 impl std::fmt::Debug for GitTool {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("GitTool").finish()
+        f.debug_struct("GitTool")
+            .field("config", &self.config)
+            .finish()
     }
 }
 
@@ -118,7 +173,7 @@ impl Tool for GitTool {
     }
 
     fn description(&self) -> &str {
-        "Run git operations (status, log, diff, blame, branch_list, commit, add, checkout) in the workspace."
+        "Run git operations (status, log, diff, blame, branch, branch_list, commit, add, checkout) in the workspace repository. Checkout refuses to run on a dirty working tree."
     }
 
     fn compact_description(&self) -> &str {
@@ -131,7 +186,7 @@ impl Tool for GitTool {
             "properties": {
                 "action": {
                     "type": "string",
-                    "enum": ["status", "log", "diff", "blame", "branch_list", "commit", "add", "checkout"],
+                    "enum": ["status", "log", "diff", "blame", "branch", "branch_list", "commit", "add", "checkout"],
                     "description": "Git operation to perform."
                 },
                 "path": {
@@ -144,7 +199,11 @@ impl Tool for GitTool {
                 },
                 "branch": {
                     "type": "string",
-                    "description": "Branch name. Required for checkout."
+                    "description": "Branch name. Required for checkout; for branch, the new branch to create."
+                },
+                "staged": {
+                    "type": "boolean",
+                    "description": "For diff: show staged changes instead of unstaged ones."
                 },
                 "count": {
                     "type": "integer",
@@ -157,6 +216,45 @@ impl Tool for GitTool {
         })
     }
 
+    async fn dry_run(&self, args: &Value, ctx: &ToolContext) -> Result<DryRun> {
+        let param = |key: &str| {
+            args.get(key)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| ZeptoError::Tool(format!("Missing '{}' parameter", key)))
+        };
+        let effect = match args.get("action").and_then(Value::as_str).map(str::trim) {
+            Some("commit") => {
+                let message = param("message")?;
+                let workspace = ctx
+                    .workspace
+                    .as_deref()
+                    .filter(|s| !s.is_empty())
+                    .ok_or_else(|| {
+                        ZeptoError::Tool(
+                            "Workspace not configured; git tool requires a workspace".to_string(),
+                        )
+                    })?;
+                Self::ensure_repo(workspace)?;
+                let staged = Self::run(&["diff", "--cached", "--name-only"], workspace)?;
+                format!(
+                    "would commit {} staged file(s) with message \"{}\"",
+                    staged.lines().filter(|l| !l.trim().is_empty()).count(),
+                    crate::utils::string::preview(message, 80)
+                )
+            }
+            Some("add") => format!("would stage '{}'", param("path")?),
+            Some("checkout") => format!("would switch to branch '{}'", param("branch")?),
+            Some("branch") if param("branch").is_ok() => {
+                format!("would create branch '{}' from HEAD", param("branch")?)
+            }
+            // Everything else only reads the repository.
+            _ => return Ok(DryRun::Execute),
+        };
+        Ok(DryRun::Describe(effect))
+    }
+
     async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
         // Validate that the git binary is permitted by the shell allowlist.
         // This prevents bypassing shell_allowlist restrictions via the git tool.
@@ -180,6 +278,10 @@ impl Tool for GitTool {
                     "Workspace not configured; git tool requires a workspace".to_string(),
                 )
             })?;
+        Self::ensure_repo(workspace)?;
+        // Paths are checked against the sandbox so git cannot show or stage
+        // files the deny list hides from the file tools.
+        let sandbox = ctx.sandbox_or(workspace);
 
         match action {
             "status" => {
@@ -212,12 +314,15 @@ impl Tool for GitTool {
             }
 
             "diff" => {
-                let path = args.get("path").and_then(Value::as_str);
-                let out = if let Some(p) = path {
-                    Self::run(&["diff", "--", p], workspace)?
-                } else {
-                    Self::run(&["diff"], workspace)?
-                };
+                let mut diff_args = vec!["diff"];
+                if args.get("staged").and_then(Value::as_bool).unwrap_or(false) {
+                    diff_args.push("--cached");
+                }
+                if let Some(p) = args.get("path").and_then(Value::as_str) {
+                    sandbox.resolve(p)?;
+                    diff_args.extend(["--", p]);
+                }
+                let out = Self::run(&diff_args, workspace)?;
                 if out.trim().is_empty() {
                     Ok(ToolOutput::llm_only("No differences found.".to_string()))
                 } else {
@@ -236,10 +341,27 @@ impl Tool for GitTool {
                             "Missing 'path' parameter; blame requires a file path".to_string(),
                         )
                     })?;
+                sandbox.resolve(path)?;
                 Self::run(&["blame", "--", path], workspace).map(ToolOutput::llm_only)
             }
 
-            "branch_list" => {
+            "branch"
+                if args
+                    .get("branch")
+                    .and_then(Value::as_str)
+                    .is_some_and(|b| !b.trim().is_empty()) =>
+            {
+                let branch = args
+                    .get("branch")
+                    .and_then(Value::as_str)
+                    .map(str::trim)
+                    .unwrap_or_default();
+                Self::validate_branch_name(branch, workspace)?;
+                Self::run(&["branch", branch], workspace)?;
+                Ok(ToolOutput::llm_only(format!("Created branch '{}'.", branch)))
+            }
+
+            "branch" | "branch_list" => {
                 let out = Self::run(&["branch", "--list", "--sort=-committerdate"], workspace)?;
                 if out.trim().is_empty() {
                     Ok(ToolOutput::llm_only("No local branches found.".to_string()))
@@ -260,8 +382,11 @@ impl Tool for GitTool {
                                 .to_string(),
                         )
                     })?;
-                // Only commit tracked changes (no -A to avoid accidentally staging untracked files).
-                Self::run(&["commit", "-m", message], workspace).map(ToolOutput::llm_only)
+                // Only commit staged changes (no -A to avoid accidentally staging untracked files).
+                let mut commit_args = self.identity_args();
+                commit_args.extend(["commit".to_string(), "-m".to_string(), message.to_string()]);
+                let commit_args: Vec<&str> = commit_args.iter().map(String::as_str).collect();
+                Self::run(&commit_args, workspace).map(ToolOutput::llm_only)
             }
 
             "add" => {
@@ -276,6 +401,7 @@ impl Tool for GitTool {
                                 .to_string(),
                         )
                     })?;
+                sandbox.resolve(path)?;
                 let out = Self::run(&["add", "--", path], workspace)?;
                 Ok(ToolOutput::llm_only(if out.trim().is_empty() {
                     format!("Staged '{}'.", path)
@@ -296,11 +422,21 @@ impl Tool for GitTool {
                                 .to_string(),
                         )
                     })?;
-                Self::run(&["checkout", branch], workspace).map(ToolOutput::llm_only)
+                Self::validate_branch_name(branch, workspace)?;
+                let dirty = Self::run(&["status", "--porcelain", "--untracked-files=no"], workspace)?;
+                if !dirty.trim().is_empty() {
+                    return Err(ZeptoError::Tool(
+                        "Working tree has uncommitted changes; commit them before checkout"
+                            .to_string(),
+                    ));
+                }
+                // Trailing `--` makes git treat the name as a branch, never a path.
+                Self::run(&["checkout", branch, "--"], workspace)?;
+                Ok(ToolOutput::llm_only(format!("Switched to '{}'.", branch)))
             }
 
             other => Err(ZeptoError::Tool(format!(
-                "Unknown git action '{}'. Supported: status, log, diff, blame, branch_list, commit, add, checkout",
+                "Unknown git action '{}'. Supported: status, log, diff, blame, branch, branch_list, commit, add, checkout",
                 other
             ))),
         }
@@ -527,6 +663,160 @@ mod tests {
         );
     }
 
+    // --- temp repositories ---
+
+    fn tool_with_author() -> GitTool {
+        GitTool::new().with_config(GitToolConfig {
+            author_name: Some("Zepto Bot".to_string()),
+            author_email: Some("bot@example.com".to_string()),
+        })
+    }
+
+    /// A fresh repository with one committed file, `notes.txt`.
+    async fn init_repo() -> (tempfile::TempDir, ToolContext) {
+        let dir = tempfile::tempdir().unwrap();
+        let ws = dir.path().to_str().unwrap();
+        GitTool::run(&["init", "-q"], ws).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "one\n").unwrap();
+        let ctx = ctx_with_workspace(ws);
+        let tool = tool_with_author();
+        tool.execute(json!({"action": "add", "path": "notes.txt"}), &ctx)
+            .await
+            .unwrap();
+        tool.execute(json!({"action": "commit", "message": "initial"}), &ctx)
+            .await
+            .unwrap();
+        (dir, ctx)
+    }
+
+    #[tokio::test]
+    async fn test_non_repo_workspace_is_clear_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = ctx_with_workspace(dir.path().to_str().unwrap());
+        let err = GitTool::new()
+            .execute(json!({"action": "status"}), &ctx)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("is not a git repository"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_commit_uses_configured_author() {
+        let (_dir, ctx) = init_repo().await;
+        let out = GitTool::new()
+            .execute(json!({"action": "log", "count": 1}), &ctx)
+            .await
+            .unwrap();
+        assert!(
+            out.for_llm.contains("Zepto Bot: initial"),
+            "{}",
+            out.for_llm
+        );
+    }
+
+    #[tokio::test]
+    async fn test_branch_create_and_checkout() {
+        let (_dir, ctx) = init_repo().await;
+        let tool = GitTool::new();
+        let out = tool
+            .execute(json!({"action": "branch", "branch": "feature/x"}), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(out.for_llm, "Created branch 'feature/x'.");
+        let out = tool
+            .execute(json!({"action": "checkout", "branch": "feature/x"}), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(out.for_llm, "Switched to 'feature/x'.");
+        let out = tool
+            .execute(json!({"action": "branch"}), &ctx)
+            .await
+            .unwrap();
+        assert!(out.for_llm.contains("* feature/x"), "{}", out.for_llm);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_describes_writes_and_runs_reads() {
+        let (dir, ctx) = init_repo().await;
+        let tool = GitTool::new();
+        std::fs::write(dir.path().join("notes.txt"), "two\n").unwrap();
+        tool.execute(json!({"action": "add", "path": "notes.txt"}), &ctx)
+            .await
+            .unwrap();
+
+        let commit = json!({"action": "commit", "message": "second"});
+        assert_eq!(
+            tool.dry_run(&commit, &ctx).await.unwrap(),
+            DryRun::Describe("would commit 1 staged file(s) with message \"second\"".into())
+        );
+        assert_eq!(
+            tool.dry_run(&json!({"action": "branch", "branch": "x"}), &ctx)
+                .await
+                .unwrap(),
+            DryRun::Describe("would create branch 'x' from HEAD".into())
+        );
+        for action in ["status", "log", "diff", "branch"] {
+            assert_eq!(
+                tool.dry_run(&json!({"action": action}), &ctx)
+                    .await
+                    .unwrap(),
+                DryRun::Execute
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checkout_refuses_dirty_tree() {
+        let (dir, ctx) = init_repo().await;
+        let tool = GitTool::new();
+        tool.execute(json!({"action": "branch", "branch": "other"}), &ctx)
+            .await
+            .unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "changed\n").unwrap();
+        let err = tool
+            .execute(json!({"action": "checkout", "branch": "other"}), &ctx)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("uncommitted changes"), "{}", err);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("notes.txt")).unwrap(),
+            "changed\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_option_like_branch_names_rejected() {
+        let (_dir, ctx) = init_repo().await;
+        let tool = GitTool::new();
+        for action in ["branch", "checkout"] {
+            let err = tool
+                .execute(json!({"action": action, "branch": "--force"}), &ctx)
+                .await
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("must not start with '-'"), "{}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_diff_staged() {
+        let (dir, ctx) = init_repo().await;
+        let tool = GitTool::new();
+        std::fs::write(dir.path().join("notes.txt"), "two\n").unwrap();
+        tool.execute(json!({"action": "add", "path": "notes.txt"}), &ctx)
+            .await
+            .unwrap();
+        let unstaged = tool.execute(json!({"action": "diff"}), &ctx).await.unwrap();
+        assert_eq!(unstaged.for_llm, "No differences found.");
+        let staged = tool
+            .execute(json!({"action": "diff", "staged": true}), &ctx)
+            .await
+            .unwrap();
+        assert!(staged.for_llm.contains("+two"), "{}", staged.for_llm);
+    }
+
     // --- tool metadata ---
 
     #[test]
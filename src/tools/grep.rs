@@ -1,19 +1,239 @@
 //! Grep tool — search file contents by regex pattern.
 
+use std::path::Path;
+
 use async_trait::async_trait;
 use serde_json::{json, Value};
 
 use crate::error::{Result, ZeptoError};
-use crate::security::validate_path_in_workspace;
 
-use super::output::{truncate_tool_output, DEFAULT_MAX_BYTES, DEFAULT_MAX_LINES};
+use super::filesystem::{BINARY_SNIFF_BYTES, DEFAULT_LIST_IGNORE};
+use super::output::DEFAULT_MAX_BYTES;
 use super::{Tool, ToolCategory, ToolContext, ToolOutput};
 
+/// Default and maximum number of matches returned.
+const DEFAULT_MAX_MATCHES: usize = 100;
+const MAX_MATCHES: usize = 1_000;
+
+/// Most context lines allowed either side of a match.
+const MAX_CONTEXT_LINES: usize = 10;
+
+/// Files larger than this are skipped rather than searched.
+const MAX_SEARCH_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Files examined before the search gives up, so a huge tree cannot stall
+/// the tool.
+const MAX_SEARCH_FILES: usize = 20_000;
+
+/// Characters of a matched or context line shown before it is cut.
+const MAX_LINE_CHARS: usize = 300;
+
 /// Tool for searching file contents by pattern.
 ///
-/// Shells out to system `grep -rn` for performance. Supports regex patterns,
-/// glob file filters, case-insensitive search, and result limiting.
-pub struct GrepTool;
+/// Walks the workspace natively (no `grep` binary needed, so it works on
+/// Windows), skipping binary files, files over 10MB and names on the ignore
+/// list (by default the same as `list_dir`). Symlinks are not followed, so
+/// the search stays inside the workspace. Output is `file:line: text`, with
+/// optional context lines as `file-line- text`, and stops at a hard cap on
+/// matches and bytes with a note saying which cap was hit.
+#[derive(Debug, Clone)]
+pub struct GrepTool {
+    ignore: Vec<String>,
+}
+
+impl GrepTool {
+    /// Create a grep tool with the default ignore list.
+    pub fn new() -> Self {
+        Self {
+            ignore: DEFAULT_LIST_IGNORE.map(String::from).to_vec(),
+        }
+    }
+
+    /// Replace the names skipped while searching.
+    pub fn with_ignore(mut self, ignore: Vec<String>) -> Self {
+        self.ignore = ignore;
+        self
+    }
+}
+
+impl Default for GrepTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Why a search stopped early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchCap {
+    Matches,
+    Bytes,
+    Files,
+}
+
+/// State for one search: the compiled query, the output so far and what
+/// was skipped.
+struct GrepSearch {
+    regex: regex::Regex,
+    glob: Option<glob::Pattern>,
+    context: usize,
+    max_matches: usize,
+    max_bytes: usize,
+    out: String,
+    matches: usize,
+    files_searched: usize,
+    skipped_binary: usize,
+    skipped_large: usize,
+    cap: Option<SearchCap>,
+}
+
+impl GrepSearch {
+    /// Whether the file at `rel` (relative to the search root) passes the
+    /// glob filter. Patterns without `/` match the file name, like
+    /// `grep --include`.
+    fn wanted(&self, rel: &str) -> bool {
+        let Some(glob) = &self.glob else {
+            return true;
+        };
+        let target = if glob.as_str().contains('/') {
+            rel
+        } else {
+            rel.rsplit('/').next().unwrap_or(rel)
+        };
+        glob.matches_with(
+            target,
+            glob::MatchOptions {
+                require_literal_separator: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Append one output line, or record the byte cap if it does not fit.
+    fn emit(&mut self, line: String) -> bool {
+        if self.out.len() + line.len() + 1 > self.max_bytes {
+            self.cap = Some(SearchCap::Bytes);
+            return false;
+        }
+        self.out.push_str(&line);
+        self.out.push('\n');
+        true
+    }
+
+    /// Search one file, labelled `display` in the output. Returns `false`
+    /// once a cap is hit and the search should stop.
+    fn search_file(&mut self, path: &Path, display: &str) -> bool {
+        self.files_searched += 1;
+        if self.files_searched > MAX_SEARCH_FILES {
+            self.cap = Some(SearchCap::Files);
+            return false;
+        }
+        match std::fs::metadata(path) {
+            Ok(metadata) if metadata.len() > MAX_SEARCH_FILE_BYTES => {
+                self.skipped_large += 1;
+                return true;
+            }
+            Ok(_) => {}
+            Err(_) => return true,
+        }
+        // Unreadable files are skipped like binary ones would be.
+        let Ok(bytes) = std::fs::read(path) else {
+            return true;
+        };
+        if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+            self.skipped_binary += 1;
+            return true;
+        }
+        let text = String::from_utf8_lossy(&bytes);
+        let lines: Vec<&str> = text.lines().collect();
+
+        let shown = |line: &str| crate::utils::string::preview(line, MAX_LINE_CHARS);
+        // First line index not yet printed, and the last line that is
+        // after-context of a match.
+        let mut next = 0;
+        let mut after_until = None;
+        let mut printed_in_file = false;
+        for (i, line) in lines.iter().enumerate() {
+            if self.regex.is_match(line) {
+                if self.matches == self.max_matches {
+                    self.cap = Some(SearchCap::Matches);
+                    return false;
+                }
+                let from = i.saturating_sub(self.context).max(next);
+                // Separate groups that are not contiguous, like grep does.
+                let gap = !printed_in_file || from > next;
+                if self.context > 0 && !self.out.is_empty() && gap && !self.emit("--".into()) {
+                    return false;
+                }
+                for (j, before) in lines.iter().enumerate().take(i).skip(from) {
+                    if !self.emit(format!("{}-{}- {}", display, j + 1, shown(before))) {
+                        return false;
+                    }
+                }
+                if !self.emit(format!("{}:{}: {}", display, i + 1, shown(line))) {
+                    return false;
+                }
+                self.matches += 1;
+                printed_in_file = true;
+                next = i + 1;
+                after_until = Some(i + self.context);
+            } else if after_until.is_some_and(|until| i <= until) && i >= next {
+                if !self.emit(format!("{}-{}- {}", display, i + 1, shown(line))) {
+                    return false;
+                }
+                next = i + 1;
+            }
+        }
+        true
+    }
+
+    /// Render the result, with notes for caps and skipped files.
+    fn finish(self) -> String {
+        let mut out = self.out;
+        if self.matches == 0 {
+            out.push_str("No matches found\n");
+        }
+        match self.cap {
+            Some(SearchCap::Matches) => out.push_str(&format!(
+                "[stopped at {} matches; narrow the pattern or raise max_matches]\n",
+                self.max_matches
+            )),
+            Some(SearchCap::Bytes) => out.push_str(&format!(
+                "[output capped at {} bytes after {} matches; narrow the search]\n",
+                self.max_bytes, self.matches
+            )),
+            Some(SearchCap::Files) => out.push_str(&format!(
+                "[stopped after searching {} files; narrow the path or glob]\n",
+                MAX_SEARCH_FILES
+            )),
+            None => {}
+        }
+        if self.skipped_binary > 0 || self.skipped_large > 0 {
+            out.push_str(&format!(
+                "[skipped {} binary file(s), {} file(s) over {} MB]\n",
+                self.skipped_binary,
+                self.skipped_large,
+                MAX_SEARCH_FILE_BYTES / (1024 * 1024)
+            ));
+        }
+        out.truncate(out.trim_end().len());
+        out
+    }
+}
+
+/// Read an optional non-negative integer argument, accepting either name.
+fn optional_count(args: &Value, names: &[&str]) -> Result<Option<usize>> {
+    for name in names {
+        if let Some(value) = args.get(*name).filter(|v| !v.is_null()) {
+            return value
+                .as_u64()
+                .map(|n| Some(usize::try_from(n).unwrap_or(usize::MAX)))
+                .ok_or_else(|| {
+                    ZeptoError::Tool(format!("'{}' must be a non-negative integer", name))
+                });
+        }
+    }
+    Ok(None)
+}
 
 #[async_trait]
 impl Tool for GrepTool {
@@ -22,7 +242,8 @@ impl Tool for GrepTool {
     }
 
     fn description(&self) -> &str {
-        "Search for a pattern in files. Supports regex patterns and glob file filters."
+        "Search file contents by regex. Returns 'file:line: text' per match, optionally with \
+         context lines. Skips binary files, .git, node_modules and target."
     }
 
     fn compact_description(&self) -> &str {
@@ -39,7 +260,7 @@ impl Tool for GrepTool {
             "properties": {
                 "pattern": {
                     "type": "string",
-                    "description": "Regex or literal pattern to search for"
+                    "description": "Regex pattern to search for (Rust regex syntax)"
                 },
                 "path": {
                     "type": "string",
@@ -47,15 +268,21 @@ impl Tool for GrepTool {
                 },
                 "glob": {
                     "type": "string",
-                    "description": "Glob pattern to filter files (e.g. '*.rs', '*.py')"
+                    "description": "Glob pattern to filter files (e.g. '*.rs', 'src/**/*.py')"
                 },
-                "ignore_case": {
+                "case_insensitive": {
                     "type": "boolean",
                     "description": "Case-insensitive search (default: false)"
                 },
-                "limit": {
+                "context": {
                     "type": "integer",
-                    "description": "Maximum matches to return (default: 100)"
+                    "minimum": 0,
+                    "description": format!("Lines of context to show around each match (default: 0, max: {})", MAX_CONTEXT_LINES)
+                },
+                "max_matches": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": format!("Maximum matches to return (default: {}, max: {})", DEFAULT_MAX_MATCHES, MAX_MATCHES)
                 }
             },
             "required": ["pattern"]
@@ -68,101 +295,122 @@ impl Tool for GrepTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| ZeptoError::Tool("Missing 'pattern' argument".into()))?;
 
-        let workspace = ctx.workspace.as_ref().ok_or_else(|| {
+        let sandbox = ctx.sandbox().ok_or_else(|| {
             ZeptoError::SecurityViolation(
                 "Workspace not configured; grep requires a workspace".to_string(),
             )
         })?;
+        let workspace = sandbox.workspace();
 
         let search_path = match args.get("path").and_then(|v| v.as_str()) {
-            Some(p) => {
-                let safe = validate_path_in_workspace(p, workspace)?;
-                safe.as_path().to_string_lossy().to_string()
-            }
-            None => workspace.clone(),
+            Some(p) => sandbox.resolve(p)?,
+            None => Path::new(workspace).to_path_buf(),
         };
 
-        let ignore_case = args
-            .get("ignore_case")
+        // `ignore_case` and `limit` are the names this tool used to take.
+        let case_insensitive = args
+            .get("case_insensitive")
+            .or_else(|| args.get("ignore_case"))
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
-        let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(100) as usize;
-        let glob_pattern = args.get("glob").and_then(|v| v.as_str());
-
-        // Validate regex before spawning grep
-        if let Err(e) = regex::Regex::new(pattern) {
-            return Err(ZeptoError::Tool(format!("Invalid regex pattern: {}", e)));
-        }
-
-        let mut cmd_args = vec!["-rn".to_string()];
-        if ignore_case {
-            cmd_args.push("-i".to_string());
-        }
-        if let Some(glob_pat) = glob_pattern {
-            cmd_args.push("--include".to_string());
-            cmd_args.push(glob_pat.to_string());
+        let max_matches = optional_count(&args, &["max_matches", "limit"])?
+            .unwrap_or(DEFAULT_MAX_MATCHES)
+            .clamp(1, MAX_MATCHES);
+        let context = optional_count(&args, &["context"])?
+            .unwrap_or(0)
+            .min(MAX_CONTEXT_LINES);
+        let glob = args
+            .get("glob")
+            .and_then(|v| v.as_str())
+            .map(|g| {
+                glob::Pattern::new(g)
+                    .map_err(|e| ZeptoError::Tool(format!("Invalid glob pattern: {}", e)))
+            })
+            .transpose()?;
+
+        let regex = regex::RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .build()
+            .map_err(|e| ZeptoError::Tool(format!("Invalid regex pattern: {}", e)))?;
+
+        if !search_path.exists() {
+            return Err(ZeptoError::Tool(format!(
+                "Failed to search '{}': path does not exist",
+                search_path.display()
+            )));
         }
-        cmd_args.push("--".to_string());
-        cmd_args.push(pattern.to_string());
-        cmd_args.push(search_path.clone());
-
-        let output = tokio::process::Command::new("grep")
-            .args(&cmd_args)
-            .output()
-            .await
-            .map_err(|e| ZeptoError::Tool(format!("Failed to run grep: {}", e)))?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
 
-        match output.status.code() {
-            Some(0) => {}
-            Some(1) if stderr.trim().is_empty() => {
-                return Ok(ToolOutput::llm_only("No matches found".to_string()));
-            }
-            Some(code) => {
-                let detail = stderr.trim();
-                let suffix = if detail.is_empty() {
-                    String::new()
-                } else {
-                    format!(": {}", detail)
-                };
-                return Err(ZeptoError::Tool(format!(
-                    "grep failed with exit code {}{}",
-                    code, suffix
-                )));
-            }
-            None => {
-                let detail = stderr.trim();
-                let suffix = if detail.is_empty() {
-                    String::new()
-                } else {
-                    format!(": {}", detail)
+        let mut search = GrepSearch {
+            regex,
+            glob,
+            context,
+            max_matches,
+            max_bytes: DEFAULT_MAX_BYTES,
+            out: String::new(),
+            matches: 0,
+            files_searched: 0,
+            skipped_binary: 0,
+            skipped_large: 0,
+            cap: None,
+        };
+        let workspace_root = Path::new(workspace).to_path_buf();
+        let canonical_root = workspace_root
+            .canonicalize()
+            .unwrap_or_else(|_| workspace_root.clone());
+        let ignore = self.ignore.clone();
+        let cancellation = ctx.cancellation.clone();
+
+        let output = tokio::task::spawn_blocking(move || {
+            let walker = walkdir::WalkDir::new(&search_path)
+                .follow_links(false)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_entry(|entry| {
+                    entry.depth() == 0
+                        || (!ignore
+                            .iter()
+                            .any(|name| entry.file_name().to_str() == Some(name.as_str()))
+                            && !sandbox.is_denied(entry.path()))
+                });
+            for entry in walker {
+                if cancellation.is_cancelled() {
+                    break;
+                }
+                // Unreadable directories are skipped, not fatal.
+                let Ok(entry) = entry else {
+                    continue;
                 };
-                return Err(ZeptoError::Tool(format!(
-                    "grep terminated by signal{}",
-                    suffix
-                )));
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                // A file given as `path` is matched by its own name.
+                let rel = match entry.path().strip_prefix(&search_path) {
+                    Ok(rel) if !rel.as_os_str().is_empty() => rel,
+                    _ => Path::new(entry.file_name()),
+                }
+                .to_string_lossy()
+                .replace('\\', "/");
+                if !search.wanted(&rel) {
+                    continue;
+                }
+                let display = entry
+                    .path()
+                    .strip_prefix(&workspace_root)
+                    .or_else(|_| entry.path().strip_prefix(&canonical_root))
+                    .unwrap_or(entry.path())
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                if !search.search_file(entry.path(), &display) {
+                    break;
+                }
             }
-        }
-
-        let lines: Vec<&str> = stdout.lines().take(limit).collect();
-
-        let total = stdout.lines().count();
-        let mut result = lines.join("\n");
-        if total > limit {
-            result.push_str(&format!(
-                "\n... ({} more matches, capped at {})",
-                total - limit,
-                limit
-            ));
-        }
+            search.finish()
+        })
+        .await
+        .map_err(|e| ZeptoError::Tool(format!("Search task failed: {}", e)))?;
+        ctx.check_cancelled()?;
 
-        Ok(ToolOutput::llm_only(truncate_tool_output(
-            &result,
-            DEFAULT_MAX_LINES,
-            DEFAULT_MAX_BYTES,
-        )))
+        Ok(ToolOutput::llm_only(output))
     }
 }
 
@@ -172,30 +420,34 @@ mod tests {
 
     #[test]
     fn test_grep_tool_name() {
-        assert_eq!(GrepTool.name(), "grep");
+        assert_eq!(GrepTool::new().name(), "grep");
     }
 
     #[test]
     fn test_grep_tool_category() {
-        assert!(matches!(GrepTool.category(), ToolCategory::FilesystemRead));
+        assert!(matches!(
+            GrepTool::new().category(),
+            ToolCategory::FilesystemRead
+        ));
     }
 
     #[test]
     fn test_grep_parameters_schema() {
-        let params = GrepTool.parameters();
+        let params = GrepTool::new().parameters();
         assert_eq!(params["type"], "object");
         assert!(params["properties"]["pattern"].is_object());
         assert!(params["properties"]["path"].is_object());
         assert!(params["properties"]["glob"].is_object());
-        assert!(params["properties"]["ignore_case"].is_object());
-        assert!(params["properties"]["limit"].is_object());
+        assert!(params["properties"]["case_insensitive"].is_object());
+        assert!(params["properties"]["context"].is_object());
+        assert!(params["properties"]["max_matches"].is_object());
         assert_eq!(params["required"], json!(["pattern"]));
     }
 
     #[tokio::test]
     async fn test_grep_requires_pattern() {
         let ctx = ToolContext::new().with_workspace("/tmp");
-        let result = GrepTool.execute(json!({}), &ctx).await;
+        let result = GrepTool::new().execute(json!({}), &ctx).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("pattern"));
     }
@@ -203,7 +455,9 @@ mod tests {
     #[tokio::test]
     async fn test_grep_requires_workspace() {
         let ctx = ToolContext::new();
-        let result = GrepTool.execute(json!({"pattern": "test"}), &ctx).await;
+        let result = GrepTool::new()
+            .execute(json!({"pattern": "test"}), &ctx)
+            .await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Workspace"));
     }
@@ -211,7 +465,9 @@ mod tests {
     #[tokio::test]
     async fn test_grep_invalid_regex() {
         let ctx = ToolContext::new().with_workspace("/tmp");
-        let result = GrepTool.execute(json!({"pattern": "[invalid"}), &ctx).await;
+        let result = GrepTool::new()
+            .execute(json!({"pattern": "[invalid"}), &ctx)
+            .await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Invalid regex"));
     }
@@ -221,7 +477,7 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         std::fs::write(dir.path().join("test.txt"), "hello world").unwrap();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
-        let result = GrepTool
+        let result = GrepTool::new()
             .execute(json!({"pattern": "zzz_nonexistent_zzz"}), &ctx)
             .await
             .unwrap();
@@ -237,7 +493,7 @@ mod tests {
         )
         .unwrap();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
-        let result = GrepTool
+        let result = GrepTool::new()
             .execute(json!({"pattern": "hello"}), &ctx)
             .await
             .unwrap();
@@ -245,10 +501,10 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_grep_reports_subprocess_errors() {
+    async fn test_grep_reports_missing_path() {
         let workspace = std::env::current_dir().unwrap().canonicalize().unwrap();
         let ctx = ToolContext::new().with_workspace(workspace.to_str().unwrap());
-        let result = GrepTool
+        let result = GrepTool::new()
             .execute(
                 json!({"pattern": "hello", "path": "Cargo.toml.missing"}),
                 &ctx,
@@ -256,17 +512,94 @@ mod tests {
             .await;
 
         match result {
-            Err(ZeptoError::Tool(err)) => assert!(err.contains("grep failed")),
+            Err(ZeptoError::Tool(err)) => assert!(err.contains("does not exist")),
             other => panic!("expected grep tool error, got {:?}", other),
         }
     }
 
+    #[tokio::test]
+    async fn test_grep_output_format_and_context() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(
+            dir.path().join("src/lib.rs"),
+            "a\nb\nneedle 1\nc\nd\ne\nf\nneedle 2\ng\n",
+        )
+        .unwrap();
+        let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
+
+        let result = GrepTool::new()
+            .execute(json!({"pattern": "needle \\d"}), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(
+            result.for_llm,
+            "src/lib.rs:3: needle 1\nsrc/lib.rs:8: needle 2"
+        );
+
+        let result = GrepTool::new()
+            .execute(json!({"pattern": "needle", "context": 1}), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(
+            result.for_llm,
+            "src/lib.rs-2- b\nsrc/lib.rs:3: needle 1\nsrc/lib.rs-4- c\n--\n\
+             src/lib.rs-7- f\nsrc/lib.rs:8: needle 2\nsrc/lib.rs-9- g"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_grep_skips_binary_and_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("blob.bin"), b"needle\0\x01").unwrap();
+        std::fs::create_dir(dir.path().join("node_modules")).unwrap();
+        std::fs::write(dir.path().join("node_modules/dep.js"), "needle").unwrap();
+        std::fs::write(dir.path().join("main.js"), "needle").unwrap();
+        let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
+
+        let result = GrepTool::new()
+            .execute(json!({"pattern": "needle"}), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(
+            result.for_llm,
+            "main.js:1: needle\n[skipped 1 binary file(s), 0 file(s) over 10 MB]"
+        );
+
+        let result = GrepTool::new()
+            .with_ignore(vec![])
+            .execute(json!({"pattern": "needle", "glob": "*.js"}), &ctx)
+            .await
+            .unwrap();
+        assert!(result.for_llm.contains("node_modules/dep.js:1: needle"));
+    }
+
+    #[tokio::test]
+    async fn test_grep_single_file_path_and_byte_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let long = "needle ".repeat(100);
+        let content: String = (0..1_000).map(|_| format!("{}\n", long)).collect();
+        std::fs::write(dir.path().join("big.txt"), content).unwrap();
+        let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
+
+        let result = GrepTool::new()
+            .execute(
+                json!({"pattern": "needle", "path": "big.txt", "glob": "*.txt", "max_matches": 1000}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert!(result.for_llm.starts_with("big.txt:1: needle"));
+        assert!(result.for_llm.len() <= DEFAULT_MAX_BYTES + 200);
+        assert!(result.for_llm.contains("[output capped at"));
+    }
+
     #[tokio::test]
     async fn test_grep_case_insensitive() {
         let dir = tempfile::tempdir().unwrap();
         std::fs::write(dir.path().join("test.txt"), "Hello World").unwrap();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
-        let result = GrepTool
+        let result = GrepTool::new()
             .execute(json!({"pattern": "hello", "ignore_case": true}), &ctx)
             .await
             .unwrap();
@@ -279,7 +612,7 @@ mod tests {
         std::fs::write(dir.path().join("test.rs"), "fn main() {}").unwrap();
         std::fs::write(dir.path().join("test.txt"), "fn main() {}").unwrap();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
-        let result = GrepTool
+        let result = GrepTool::new()
             .execute(json!({"pattern": "fn main", "glob": "*.rs"}), &ctx)
             .await
             .unwrap();
@@ -293,17 +626,17 @@ mod tests {
         let content: String = (0..20).map(|i| format!("match line {}\n", i)).collect();
         std::fs::write(dir.path().join("test.txt"), &content).unwrap();
         let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
-        let result = GrepTool
+        let result = GrepTool::new()
             .execute(json!({"pattern": "match", "limit": 5}), &ctx)
             .await
             .unwrap();
         let lines: Vec<&str> = result.for_llm.lines().collect();
-        // 5 match lines + 1 "more matches" line
+        // 5 match lines + 1 "stopped at" line
         assert!(
             lines.len() <= 6,
             "Expected at most 6 lines, got {}",
             lines.len()
         );
-        assert!(result.for_llm.contains("more matches"));
+        assert!(result.for_llm.contains("[stopped at 5 matches"));
     }
 }
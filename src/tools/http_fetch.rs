@@ -0,0 +1,583 @@
+//! HTTP fetch tool — retrieve web pages and APIs with safety limits.
+//!
+//! Unlike `web_fetch`, which only GETs pages and returns extracted text,
+//! `http_fetch` supports any method, headers and a body, and reports the
+//! status and selected response headers. Hosts are checked against the
+//! allow/deny lists in `tools.http_fetch`, and private/local addresses are
+//! blocked unless `allow_private_hosts` is set. Redirects are followed by
+//! hand so every hop gets the same checks.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, Method, StatusCode, Url};
+use serde_json::{json, Value};
+
+use crate::config::HttpFetchConfig;
+use crate::error::{Result, ZeptoError};
+use crate::tools::http_request::{host_matches, HttpRequestTool};
+use crate::tools::web::{
+    html_to_markdown, is_blocked_host, resolve_and_check_host, WEB_USER_AGENT,
+};
+
+use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Response headers worth showing the model; the rest are noise.
+const SHOWN_RESPONSE_HEADERS: &[&str] = &[
+    "content-type",
+    "content-length",
+    "last-modified",
+    "etag",
+    "location",
+    "retry-after",
+    "x-ratelimit-remaining",
+];
+
+/// Tool for HTTP requests to web pages and APIs.
+///
+/// # Parameters
+/// - `url`: http(s) URL to fetch (required)
+/// - `method`: HTTP method (optional, default `GET`)
+/// - `headers`: Request headers (optional; `Authorization`, `Host` and
+///   cookies are stripped)
+/// - `body`: Request body (optional)
+/// - `format`: `markdown` (default) converts HTML to readable markdown,
+///   `raw` returns the body as received
+pub struct HttpFetchTool {
+    config: HttpFetchConfig,
+}
+
+impl HttpFetchTool {
+    /// Create a fetch tool with the given limits and host lists.
+    pub fn new(config: HttpFetchConfig) -> Self {
+        Self { config }
+    }
+
+    /// Check a URL (the first request or a redirect target) against the
+    /// scheme, the host lists and, unless allowed, the private ranges.
+    ///
+    /// Returns the address to pin the connection to, if the host was
+    /// resolved here.
+    async fn check_url(&self, url: &Url) -> Result<Option<(String, std::net::SocketAddr)>> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(ZeptoError::SecurityViolation(format!(
+                "Only http and https URLs are allowed, got '{}'",
+                url.scheme()
+            )));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| ZeptoError::SecurityViolation("URL has no host".into()))?
+            .to_ascii_lowercase();
+        if self
+            .config
+            .denied_hosts
+            .iter()
+            .any(|d| host_matches(d, &host))
+        {
+            return Err(ZeptoError::SecurityViolation(format!(
+                "Host '{}' is in tools.http_fetch.denied_hosts",
+                host
+            )));
+        }
+        if !self.config.allowed_hosts.is_empty()
+            && !self
+                .config
+                .allowed_hosts
+                .iter()
+                .any(|d| host_matches(d, &host))
+        {
+            return Err(ZeptoError::SecurityViolation(format!(
+                "Host '{}' is not in tools.http_fetch.allowed_hosts",
+                host
+            )));
+        }
+        if self.config.allow_private_hosts {
+            return Ok(None);
+        }
+        if is_blocked_host(url) {
+            return Err(ZeptoError::SecurityViolation(format!(
+                "Blocked URL host (local or private network): {}",
+                host
+            )));
+        }
+        // Resolve before connecting and pin the result, so a hostname that
+        // points at (or rebinds to) a private address is refused.
+        resolve_and_check_host(url).await
+    }
+
+    /// Send the request, following redirects up to the configured limit.
+    pub(crate) async fn send(
+        &self,
+        mut url: Url,
+        mut method: Method,
+        headers: &[(String, String)],
+        mut body: Option<String>,
+    ) -> Result<reqwest::Response> {
+        let mut redirects = 0;
+        loop {
+            let pinned = self.check_url(&url).await?;
+            let mut builder = Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .timeout(Duration::from_secs(self.config.timeout_secs));
+            if let Some((host, addr)) = pinned {
+                builder = builder.resolve(&host, addr);
+            }
+            let client = builder
+                .build()
+                .map_err(|e| ZeptoError::Tool(format!("HTTP client error: {}", e)))?;
+
+            let mut request = client
+                .request(method.clone(), url.clone())
+                .header(reqwest::header::USER_AGENT, WEB_USER_AGENT);
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            if let Some(body) = &body {
+                request = request.body(body.clone());
+            }
+            let response = request
+                .send()
+                .await
+                .map_err(|e| ZeptoError::Tool(format!("Request failed: {}", e)))?;
+
+            let status = response.status();
+            if !status.is_redirection() {
+                return Ok(response);
+            }
+            let Some(location) = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+            else {
+                return Ok(response);
+            };
+            if redirects == self.config.max_redirects {
+                return Err(ZeptoError::Tool(format!(
+                    "Too many redirects (max {})",
+                    self.config.max_redirects
+                )));
+            }
+            redirects += 1;
+            url = url
+                .join(location)
+                .map_err(|e| ZeptoError::Tool(format!("Invalid redirect target: {}", e)))?;
+            // 307 and 308 repeat the request as is; the others turn it into
+            // a GET without a body, as browsers do.
+            if !matches!(
+                status,
+                StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT
+            ) {
+                method = Method::GET;
+                body = None;
+            }
+        }
+    }
+}
+
+/// Read at most `max_bytes` of the body. Returns the bytes and whether more
+/// were left unread.
+async fn read_body(mut response: reqwest::Response, max_bytes: usize) -> Result<(Vec<u8>, bool)> {
+    let mut buf = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| ZeptoError::Tool(format!("Failed to read response body: {}", e)))?
+    {
+        let take = chunk.len().min(max_bytes - buf.len());
+        buf.extend_from_slice(&chunk[..take]);
+        if take < chunk.len() {
+            return Ok((buf, true));
+        }
+    }
+    Ok((buf, false))
+}
+
+/// Decode a body using the charset from its content type, falling back to
+/// UTF-8. Invalid sequences become replacement characters instead of errors.
+fn decode_body(bytes: &[u8], content_type: &str) -> String {
+    let encoding = content_type
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("charset"))
+        .and_then(|(_, label)| {
+            encoding_rs::Encoding::for_label(label.trim().trim_matches('"').as_bytes())
+        })
+        .unwrap_or(encoding_rs::UTF_8);
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+#[async_trait]
+impl Tool for HttpFetchTool {
+    fn name(&self) -> &str {
+        "http_fetch"
+    }
+
+    fn description(&self) -> &str {
+        "Make an HTTP request to a web page or API and return the status, key response headers \
+         and body. HTML is converted to markdown unless format is 'raw'."
+    }
+
+    fn compact_description(&self) -> &str {
+        "HTTP request"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::NetworkWrite
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "http/https URL to fetch"
+                },
+                "method": {
+                    "type": "string",
+                    "enum": ["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"],
+                    "description": "HTTP method (default: GET)"
+                },
+                "headers": {
+                    "type": "object",
+                    "description": "Request headers (Authorization, Host and Cookie are stripped)",
+                    "additionalProperties": { "type": "string" }
+                },
+                "body": {
+                    "type": "string",
+                    "description": "Request body"
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["markdown", "raw"],
+                    "description": "'markdown' (default) converts HTML responses to readable markdown; 'raw' returns the body as received"
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
+        let raw_url = args
+            .get("url")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| ZeptoError::Tool("Missing 'url' parameter".into()))?;
+        let url = Url::parse(raw_url)
+            .map_err(|e| ZeptoError::Tool(format!("Invalid URL '{}': {}", raw_url, e)))?;
+        let method_name = args
+            .get("method")
+            .and_then(|v| v.as_str())
+            .unwrap_or("GET")
+            .to_ascii_uppercase();
+        let method = Method::from_bytes(method_name.as_bytes())
+            .map_err(|_| ZeptoError::Tool(format!("Unknown HTTP method: {}", method_name)))?;
+        let raw = match args.get("format").and_then(|v| v.as_str()) {
+            None | Some("markdown") => false,
+            Some("raw") => true,
+            Some(other) => {
+                return Err(ZeptoError::Tool(format!(
+                    "Unknown format '{}', expected 'markdown' or 'raw'",
+                    other
+                )))
+            }
+        };
+        let headers: Vec<(String, String)> = args
+            .get("headers")
+            .and_then(|v| v.as_object())
+            .map(|headers| {
+                headers
+                    .iter()
+                    .filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let headers = HttpRequestTool::strip_dangerous_headers(headers);
+        let body = args.get("body").and_then(|v| v.as_str()).map(String::from);
+
+        let exchange = async {
+            let response = self.send(url, method, &headers, body).await?;
+            let status = response.status();
+            let final_url = response.url().clone();
+            let shown_headers: Vec<String> = SHOWN_RESPONSE_HEADERS
+                .iter()
+                .filter_map(|name| {
+                    response
+                        .headers()
+                        .get(*name)
+                        .and_then(|v| v.to_str().ok())
+                        .map(|v| format!("{}: {}", name, v))
+                })
+                .collect();
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("")
+                .to_string();
+            let (bytes, truncated) = read_body(response, self.config.max_response_bytes).await?;
+            Ok::<_, ZeptoError>((
+                status,
+                final_url,
+                shown_headers,
+                content_type,
+                bytes,
+                truncated,
+            ))
+        };
+        // The client timeout covers each hop; this bounds the whole exchange,
+        // redirects and DNS lookups included.
+        let (status, final_url, shown_headers, content_type, bytes, truncated) =
+            tokio::time::timeout(Duration::from_secs(self.config.timeout_secs), exchange)
+                .await
+                .map_err(|_| {
+                    ZeptoError::Tool(format!(
+                        "Request timed out after {}s",
+                        self.config.timeout_secs
+                    ))
+                })??;
+
+        let text = decode_body(&bytes, &content_type);
+        let text = if !raw && content_type.to_ascii_lowercase().contains("text/html") {
+            html_to_markdown(&text)
+        } else {
+            text
+        };
+
+        let mut output = format!("Status: {}\n", status);
+        if final_url.as_str() != raw_url {
+            output.push_str(&format!("URL: {}\n", final_url));
+        }
+        for header in shown_headers {
+            output.push_str(&header);
+            output.push('\n');
+        }
+        output.push('\n');
+        output.push_str(&text);
+        if truncated {
+            output.push_str(&format!(
+                "\n[Body truncated at {} bytes]",
+                self.config.max_response_bytes
+            ));
+        }
+        Ok(ToolOutput::llm_only(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn tool(config: HttpFetchConfig) -> HttpFetchTool {
+        HttpFetchTool::new(config)
+    }
+
+    /// Serve canned responses on localhost, one per connection, keyed by
+    /// request path.
+    async fn serve(routes: Vec<(&'static str, Vec<u8>)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+                let response = routes
+                    .iter()
+                    .find(|(route, _)| *route == path)
+                    .map(|(_, r)| r.clone())
+                    .unwrap_or_else(|| {
+                        b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n".to_vec()
+                    });
+                let _ = socket.write_all(&response).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    fn response(status: &str, headers: &[&str], body: &[u8]) -> Vec<u8> {
+        let mut out = format!("HTTP/1.1 {}\r\ncontent-length: {}\r\n", status, body.len());
+        for header in headers {
+            out.push_str(header);
+            out.push_str("\r\n");
+        }
+        out.push_str("connection: close\r\n\r\n");
+        let mut out = out.into_bytes();
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn local_config() -> HttpFetchConfig {
+        HttpFetchConfig {
+            allow_private_hosts: true,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_fetch_blocks_private_hosts_by_default() {
+        let t = tool(HttpFetchConfig::default());
+        for url in [
+            "http://127.0.0.1/",
+            "http://169.254.169.254/latest/meta-data",
+            "http://10.0.0.1/",
+            "http://localhost:8080/",
+        ] {
+            let err = t.execute(json!({"url": url}), &ToolContext::new()).await;
+            assert!(
+                matches!(err, Err(ZeptoError::SecurityViolation(_))),
+                "{url}: {err:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_fetch_host_lists() {
+        let t = tool(HttpFetchConfig {
+            allowed_hosts: vec!["*.example.com".into()],
+            denied_hosts: vec!["admin.example.com".into()],
+            ..Default::default()
+        });
+        let denied = t
+            .check_url(&Url::parse("https://admin.example.com/").unwrap())
+            .await
+            .unwrap_err();
+        assert!(denied.to_string().contains("denied_hosts"));
+        let not_allowed = t
+            .check_url(&Url::parse("https://example.org/").unwrap())
+            .await
+            .unwrap_err();
+        assert!(not_allowed.to_string().contains("allowed_hosts"));
+        let scheme = t
+            .check_url(&Url::parse("file:///etc/passwd").unwrap())
+            .await
+            .unwrap_err();
+        assert!(scheme.to_string().contains("Only http and https"));
+    }
+
+    #[test]
+    fn test_decode_body_charsets() {
+        assert_eq!(
+            decode_body(b"caf\xe9", "text/plain; charset=ISO-8859-1"),
+            "café"
+        );
+        assert_eq!(
+            decode_body("café".as_bytes(), "text/plain; charset=\"utf-8\""),
+            "café"
+        );
+        assert_eq!(decode_body(b"caf\xe9", "text/plain"), "caf\u{FFFD}");
+    }
+
+    #[tokio::test]
+    async fn test_http_fetch_html_headers_and_truncation() {
+        let page = b"<html><head><title>Hi</title></head><body><p>Hello there</p></body></html>";
+        let base = serve(vec![
+            (
+                "/page",
+                response(
+                    "200 OK",
+                    &["content-type: text/html; charset=utf-8", "etag: \"v1\""],
+                    page,
+                ),
+            ),
+            (
+                "/big",
+                response("200 OK", &["content-type: text/plain"], &[b'x'; 100]),
+            ),
+        ])
+        .await;
+
+        let t = tool(local_config());
+        let out = t
+            .execute(
+                json!({"url": format!("{}/page", base)}),
+                &ToolContext::new(),
+            )
+            .await
+            .unwrap()
+            .for_llm;
+        assert!(out.starts_with("Status: 200 OK\n"));
+        assert!(out.contains("content-type: text/html; charset=utf-8\n"));
+        assert!(out.contains("etag: \"v1\"\n"));
+        assert!(out.contains("# Hi"));
+        assert!(out.contains("Hello there"));
+        assert!(!out.contains("<p>"));
+
+        let out = t
+            .execute(
+                json!({"url": format!("{}/page", base), "format": "raw"}),
+                &ToolContext::new(),
+            )
+            .await
+            .unwrap()
+            .for_llm;
+        assert!(out.contains("<p>Hello there</p>"));
+
+        let t = tool(HttpFetchConfig {
+            max_response_bytes: 10,
+            ..local_config()
+        });
+        let out = t
+            .execute(json!({"url": format!("{}/big", base)}), &ToolContext::new())
+            .await
+            .unwrap()
+            .for_llm;
+        assert!(out.ends_with("\n\nxxxxxxxxxx\n[Body truncated at 10 bytes]"));
+    }
+
+    #[tokio::test]
+    async fn test_http_fetch_redirects_are_bounded() {
+        let base = serve(vec![
+            ("/a", response("302 Found", &["location: /b"], b"")),
+            ("/b", response("301 Moved", &["location: /done"], b"")),
+            (
+                "/done",
+                response("200 OK", &["content-type: text/plain"], b"ok"),
+            ),
+            ("/loop", response("302 Found", &["location: /loop"], b"")),
+        ])
+        .await;
+        let t = tool(local_config());
+
+        let out = t
+            .execute(json!({"url": format!("{}/a", base)}), &ToolContext::new())
+            .await
+            .unwrap()
+            .for_llm;
+        assert!(out.starts_with("Status: 200 OK\n"));
+        assert!(out.contains(&format!("URL: {}/done", base)));
+        assert!(out.ends_with("\n\nok"));
+
+        let err = t
+            .execute(
+                json!({"url": format!("{}/loop", base)}),
+                &ToolContext::new(),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Too many redirects (max 5)"));
+    }
+
+    #[tokio::test]
+    async fn test_http_fetch_rejects_bad_arguments() {
+        let t = tool(local_config());
+        let ctx = ToolContext::new();
+        assert!(t.execute(json!({}), &ctx).await.is_err());
+        let err = t
+            .execute(json!({"url": "http://127.0.0.1/", "format": "pdf"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Unknown format 'pdf'"));
+    }
+}
@@ -83,7 +83,7 @@ impl HttpRequestTool {
 
 /// Check whether `host` matches `pattern`, supporting wildcard subdomains.
 /// `*.myco.com` matches `staging.myco.com` and `myco.com` itself.
-fn host_matches(pattern: &str, host: &str) -> bool {
+pub(crate) fn host_matches(pattern: &str, host: &str) -> bool {
     if let Some(suffix) = pattern.strip_prefix("*.") {
         host == suffix || host.ends_with(&format!(".{suffix}"))
     } else {
@@ -149,7 +149,7 @@ impl Tool for HttpRequestTool {
         })
     }
 
-    async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
         let url_str = args["url"].as_str().unwrap_or("").to_string();
         let method_str = args["method"]
             .as_str()
@@ -207,19 +207,26 @@ impl Tool for HttpRequestTool {
             req = req.body(body.to_string());
         }
 
-        let response = req
-            .send()
-            .await
-            .map_err(|e| ZeptoError::Tool(format!("Request failed: {e}")))?;
+        let response = ctx
+            .cancellable(async {
+                req.send()
+                    .await
+                    .map_err(|e| ZeptoError::Tool(format!("Request failed: {e}")))
+            })
+            .await?;
 
         // Defense in depth: validate final redirect destination too.
         validate_redirect_target(response.url()).await?;
 
         let status = response.status().as_u16();
-        let body_bytes = response
-            .bytes()
-            .await
-            .map_err(|e| ZeptoError::Tool(format!("Failed to read response body: {e}")))?;
+        let body_bytes = ctx
+            .cancellable(async {
+                response
+                    .bytes()
+                    .await
+                    .map_err(|e| ZeptoError::Tool(format!("Failed to read response body: {e}")))
+            })
+            .await?;
 
         let body_str = if body_bytes.len() > self.max_response_bytes {
             let truncated = &body_bytes[..self.max_response_bytes];
@@ -0,0 +1,568 @@
+//! Human-in-the-loop approval tool.
+//!
+//! Sends a question with a fixed set of options to a human approver on
+//! Telegram or Slack, then polls until the approver picks one or the timeout
+//! elapses. The agent blocks on the tool call in the meantime, which makes
+//! it suitable for signing off critical actions.
+//!
+//! - **Telegram**: options are sent as inline keyboard buttons; a button
+//!   press or a reply to the question message counts as the answer.
+//! - **Slack**: the question is posted with numbered options; the first
+//!   thread reply naming an option (by number or text) counts.
+//!
+//! The tool also implements [`CommandApprover`], so the shell tool can route
+//! commands its policy flags for review through the same channel.
+
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use tracing::{debug, warn};
+
+use crate::config::HumanApprovalToolConfig;
+use crate::error::{Result, ZeptoError};
+use crate::security::CommandApprover;
+
+use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+
+const TELEGRAM_API_BASE: &str = "https://api.telegram.org";
+const SLACK_API_BASE: &str = "https://slack.com/api";
+
+/// Returned when nobody answers before the timeout.
+pub const TIMEOUT_RESPONSE: &str = "TIMEOUT";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+const MAX_OPTIONS: usize = 10;
+
+/// Where approval requests are sent.
+#[derive(Debug, Clone)]
+enum ApprovalChannel {
+    Telegram { bot_token: String, chat_id: String },
+    Slack { bot_token: String, channel: String },
+}
+
+/// A posted approval request, used to find its answer.
+enum PostedRequest {
+    Telegram { message_id: i64, next_offset: i64 },
+    Slack { ts: String },
+}
+
+/// Tool that asks a human to choose between options and waits for the answer.
+pub struct HumanApprovalTool {
+    channel: ApprovalChannel,
+    poll_interval: Duration,
+    max_timeout_secs: u64,
+    client: Client,
+}
+
+/// Match a free-text reply against the options, by 1-based number or by
+/// case-insensitive option text.
+pub fn match_option(reply: &str, options: &[String]) -> Option<usize> {
+    let reply = reply.trim();
+    if let Ok(n) = reply.parse::<usize>() {
+        return (1..=options.len()).contains(&n).then(|| n - 1);
+    }
+    options.iter().position(|o| o.eq_ignore_ascii_case(reply))
+}
+
+/// Extract the chosen option from a Telegram `getUpdates` result for the
+/// request message `message_id`. Returns the option index and the callback
+/// query ID to acknowledge, if the answer came from a button.
+fn telegram_choice(
+    update: &Value,
+    message_id: i64,
+    options: &[String],
+) -> Option<(usize, Option<String>)> {
+    if let Some(cb) = update.get("callback_query") {
+        if cb.pointer("/message/message_id").and_then(Value::as_i64) != Some(message_id) {
+            return None;
+        }
+        let index = cb
+            .get("data")
+            .and_then(Value::as_str)
+            .and_then(|d| d.parse::<usize>().ok())
+            .filter(|i| *i < options.len())?;
+        let id = cb.get("id").and_then(Value::as_str).map(str::to_string);
+        return Some((index, id));
+    }
+    let msg = update.get("message")?;
+    if msg
+        .pointer("/reply_to_message/message_id")
+        .and_then(Value::as_i64)
+        != Some(message_id)
+    {
+        return None;
+    }
+    let text = msg.get("text").and_then(Value::as_str)?;
+    match_option(text, options).map(|i| (i, None))
+}
+
+/// Extract the chosen option from a Slack `conversations.replies` response.
+/// The first message is the question itself and is skipped.
+fn slack_choice(replies: &Value, options: &[String]) -> Option<usize> {
+    replies
+        .get("messages")?
+        .as_array()?
+        .iter()
+        .skip(1)
+        .filter(|m| m.get("bot_id").is_none())
+        .filter_map(|m| m.get("text").and_then(Value::as_str))
+        .find_map(|text| match_option(text, options))
+}
+
+fn format_question(question: &str, options: &[String]) -> String {
+    let mut text = format!("Approval needed:\n{}\n", question);
+    for (i, option) in options.iter().enumerate() {
+        text.push_str(&format!("\n{}. {}", i + 1, option));
+    }
+    text
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+impl HumanApprovalTool {
+    /// Build the tool from config. Returns `None` when no approval channel
+    /// is fully configured.
+    ///
+    /// `channel_bot_token` is the token of the Telegram channel, if any. An
+    /// approval bot sharing it is refused: both would poll `getUpdates` and
+    /// each would swallow the other's updates.
+    pub fn from_config(
+        config: &HumanApprovalToolConfig,
+        channel_bot_token: Option<&str>,
+    ) -> Option<Self> {
+        let telegram_bot_token = non_empty(&config.telegram_bot_token).filter(|token| {
+            let shared = channel_bot_token.map(str::trim) == Some(token.as_str());
+            if shared {
+                warn!(
+                    "tools.human_approval.telegram_bot_token is the Telegram channel's token; \
+                     use a dedicated bot for approvals"
+                );
+            }
+            !shared
+        });
+        let channel = match (
+            telegram_bot_token,
+            non_empty(&config.telegram_chat_id),
+            non_empty(&config.slack_bot_token),
+            non_empty(&config.slack_channel),
+        ) {
+            (Some(bot_token), Some(chat_id), _, _) => {
+                ApprovalChannel::Telegram { bot_token, chat_id }
+            }
+            (_, _, Some(bot_token), Some(channel)) => ApprovalChannel::Slack { bot_token, channel },
+            _ => return None,
+        };
+        Some(Self {
+            channel,
+            poll_interval: Duration::from_secs(config.poll_interval_secs.max(1)),
+            max_timeout_secs: config.max_timeout_secs,
+            client: Client::new(),
+        })
+    }
+
+    /// Post `question` and wait up to `timeout_secs` (capped at the
+    /// configured maximum) for an answer. Returns the chosen option's index,
+    /// or `None` on timeout.
+    async fn ask(
+        &self,
+        question: &str,
+        options: &[String],
+        timeout_secs: u64,
+    ) -> Result<Option<usize>> {
+        let mut posted = self.post(question, options).await?;
+        let timeout = Duration::from_secs(timeout_secs.min(self.max_timeout_secs));
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            match self.poll(&mut posted, options).await {
+                Ok(Some(index)) => return Ok(Some(index)),
+                Ok(None) => {}
+                // Transient API errors should not abort a long wait.
+                Err(e) => warn!(error = %e, "Polling for approval response failed"),
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            tokio::time::sleep(self.poll_interval.min(deadline - now)).await;
+        }
+    }
+
+    async fn api_json(&self, request: reqwest::RequestBuilder, what: &str) -> Result<Value> {
+        let response = request
+            .timeout(Duration::from_secs(30))
+            .send()
+            .await
+            .map_err(|e| ZeptoError::Tool(format!("{} request failed: {}", what, e)))?;
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| ZeptoError::Tool(format!("{} returned invalid JSON: {}", what, e)))?;
+        // Both APIs report success with a top-level "ok" flag.
+        if body.get("ok").and_then(Value::as_bool) != Some(true) {
+            let detail = body
+                .get("description")
+                .or_else(|| body.get("error"))
+                .cloned()
+                .unwrap_or(Value::Null);
+            return Err(ZeptoError::Tool(format!("{} failed: {}", what, detail)));
+        }
+        Ok(body)
+    }
+
+    async fn post(&self, question: &str, options: &[String]) -> Result<PostedRequest> {
+        match &self.channel {
+            ApprovalChannel::Telegram { bot_token, chat_id } => {
+                // Skip updates that predate the request.
+                let latest = self
+                    .api_json(
+                        self.client
+                            .get(format!("{}/bot{}/getUpdates", TELEGRAM_API_BASE, bot_token))
+                            .query(&[("offset", "-1"), ("timeout", "0")]),
+                        "Telegram getUpdates",
+                    )
+                    .await?;
+                let next_offset = latest
+                    .pointer("/result/0/update_id")
+                    .and_then(Value::as_i64)
+                    .map_or(0, |id| id + 1);
+
+                let keyboard: Vec<Vec<Value>> = options
+                    .iter()
+                    .enumerate()
+                    .map(|(i, o)| vec![json!({ "text": o, "callback_data": i.to_string() })])
+                    .collect();
+                let sent = self
+                    .api_json(
+                        self.client
+                            .post(format!(
+                                "{}/bot{}/sendMessage",
+                                TELEGRAM_API_BASE, bot_token
+                            ))
+                            .json(&json!({
+                                "chat_id": chat_id,
+                                "text": format_question(question, options),
+                                "reply_markup": { "inline_keyboard": keyboard },
+                            })),
+                        "Telegram sendMessage",
+                    )
+                    .await?;
+                let message_id = sent
+                    .pointer("/result/message_id")
+                    .and_then(Value::as_i64)
+                    .ok_or_else(|| {
+                        ZeptoError::Tool("Telegram sendMessage returned no message_id".into())
+                    })?;
+                Ok(PostedRequest::Telegram {
+                    message_id,
+                    next_offset,
+                })
+            }
+            ApprovalChannel::Slack { bot_token, channel } => {
+                let text = format!(
+                    "{}\n\n_Reply in this thread with the option number or text._",
+                    format_question(question, options)
+                );
+                let sent = self
+                    .api_json(
+                        self.client
+                            .post(format!("{}/chat.postMessage", SLACK_API_BASE))
+                            .bearer_auth(bot_token)
+                            .json(&json!({ "channel": channel, "text": text })),
+                        "Slack chat.postMessage",
+                    )
+                    .await?;
+                let ts = sent
+                    .get("ts")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| {
+                        ZeptoError::Tool("Slack chat.postMessage returned no ts".into())
+                    })?
+                    .to_string();
+                Ok(PostedRequest::Slack { ts })
+            }
+        }
+    }
+
+    /// Check once for an answer. Updates the Telegram offset in place.
+    async fn poll(&self, posted: &mut PostedRequest, options: &[String]) -> Result<Option<usize>> {
+        match (&self.channel, posted) {
+            (
+                ApprovalChannel::Telegram { bot_token, .. },
+                PostedRequest::Telegram {
+                    message_id,
+                    next_offset,
+                },
+            ) => {
+                let body = self
+                    .api_json(
+                        self.client
+                            .get(format!("{}/bot{}/getUpdates", TELEGRAM_API_BASE, bot_token))
+                            .query(&[
+                                ("offset", next_offset.to_string()),
+                                ("timeout", "0".to_string()),
+                                (
+                                    "allowed_updates",
+                                    r#"["message","callback_query"]"#.to_string(),
+                                ),
+                            ]),
+                        "Telegram getUpdates",
+                    )
+                    .await?;
+                let updates = body
+                    .get("result")
+                    .and_then(Value::as_array)
+                    .cloned()
+                    .unwrap_or_default();
+                for update in &updates {
+                    if let Some(id) = update.get("update_id").and_then(Value::as_i64) {
+                        *next_offset = (*next_offset).max(id + 1);
+                    }
+                    if let Some((index, callback_id)) =
+                        telegram_choice(update, *message_id, options)
+                    {
+                        if let Some(callback_id) = callback_id {
+                            let ack = self
+                                .client
+                                .post(format!(
+                                    "{}/bot{}/answerCallbackQuery",
+                                    TELEGRAM_API_BASE, bot_token
+                                ))
+                                .json(&json!({
+                                    "callback_query_id": callback_id,
+                                    "text": format!("Selected: {}", options[index]),
+                                }))
+                                .send()
+                                .await;
+                            if let Err(e) = ack {
+                                debug!(error = %e, "Failed to acknowledge approval button");
+                            }
+                        }
+                        return Ok(Some(index));
+                    }
+                }
+                Ok(None)
+            }
+            (ApprovalChannel::Slack { bot_token, channel }, PostedRequest::Slack { ts }) => {
+                let body = self
+                    .api_json(
+                        self.client
+                            .get(format!("{}/conversations.replies", SLACK_API_BASE))
+                            .bearer_auth(bot_token)
+                            .query(&[("channel", channel.as_str()), ("ts", ts.as_str())]),
+                        "Slack conversations.replies",
+                    )
+                    .await?;
+                Ok(slack_choice(&body, options))
+            }
+            _ => Err(ZeptoError::Tool(
+                "Approval request does not match the configured channel".into(),
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for HumanApprovalTool {
+    fn name(&self) -> &str {
+        "human_approval"
+    }
+
+    fn description(&self) -> &str {
+        "Ask a human approver (via Telegram or Slack) to choose one of several options and \
+         wait for the answer. Returns the chosen option text, or TIMEOUT if nobody answers \
+         in time. Use before critical or irreversible actions."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Ask human for approval"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Messaging
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "question": {
+                    "type": "string",
+                    "description": "What the approver is asked to decide"
+                },
+                "options": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Choices to offer, e.g. [\"Approve\", \"Reject\"]"
+                },
+                "timeout_secs": {
+                    "type": "integer",
+                    "description": "How long to wait for an answer (default: 300)"
+                }
+            },
+            "required": ["question", "options"]
+        })
+    }
+
+    async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
+        let question = args
+            .get("question")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .ok_or_else(|| ZeptoError::Tool("Missing required field: question".into()))?;
+        let options: Vec<String> = args
+            .get("options")
+            .and_then(Value::as_array)
+            .ok_or_else(|| ZeptoError::Tool("Missing required field: options".into()))?
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|o| o.trim().to_string())
+            .filter(|o| !o.is_empty())
+            .collect();
+        if options.is_empty() || options.len() > MAX_OPTIONS {
+            return Err(ZeptoError::Tool(format!(
+                "options must contain between 1 and {} non-empty strings",
+                MAX_OPTIONS
+            )));
+        }
+        let timeout_secs = args
+            .get("timeout_secs")
+            .and_then(Value::as_u64)
+            .unwrap_or(DEFAULT_TIMEOUT_SECS);
+
+        let answer = match self.ask(question, &options, timeout_secs).await? {
+            Some(index) => options[index].clone(),
+            None => TIMEOUT_RESPONSE.to_string(),
+        };
+        Ok(ToolOutput::llm_only(answer))
+    }
+}
+
+#[async_trait]
+impl CommandApprover for HumanApprovalTool {
+    async fn approve(&self, command: &str, reason: &str) -> Result<bool> {
+        let question = format!(
+            "The agent wants to run a shell command ({}):\n\n{}\n\nAllow it?",
+            reason, command
+        );
+        let options = ["Approve".to_string(), "Deny".to_string()];
+        Ok(self.ask(&question, &options, DEFAULT_TIMEOUT_SECS).await? == Some(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> Vec<String> {
+        vec!["Approve".to_string(), "Reject".to_string()]
+    }
+
+    #[test]
+    fn test_match_option() {
+        assert_eq!(match_option("2", &options()), Some(1));
+        assert_eq!(match_option(" approve ", &options()), Some(0));
+        assert_eq!(match_option("3", &options()), None);
+        assert_eq!(match_option("0", &options()), None);
+        assert_eq!(match_option("maybe", &options()), None);
+    }
+
+    #[test]
+    fn test_telegram_choice_from_button() {
+        let update = json!({
+            "update_id": 10,
+            "callback_query": { "id": "cb1", "data": "1", "message": { "message_id": 42 } }
+        });
+        assert_eq!(
+            telegram_choice(&update, 42, &options()),
+            Some((1, Some("cb1".to_string())))
+        );
+        assert_eq!(telegram_choice(&update, 43, &options()), None);
+    }
+
+    #[test]
+    fn test_telegram_choice_from_reply() {
+        let update = json!({
+            "update_id": 11,
+            "message": { "text": "reject", "reply_to_message": { "message_id": 42 } }
+        });
+        assert_eq!(telegram_choice(&update, 42, &options()), Some((1, None)));
+        let unrelated = json!({ "update_id": 12, "message": { "text": "reject" } });
+        assert_eq!(telegram_choice(&unrelated, 42, &options()), None);
+    }
+
+    #[test]
+    fn test_slack_choice_skips_question_and_bots() {
+        let replies = json!({
+            "ok": true,
+            "messages": [
+                { "text": "Approval needed: 1. Approve 2. Reject", "bot_id": "B1" },
+                { "text": "1", "bot_id": "B2" },
+                { "text": "hmm let me check" },
+                { "text": "Reject" }
+            ]
+        });
+        assert_eq!(slack_choice(&replies, &options()), Some(1));
+    }
+
+    #[test]
+    fn test_from_config_requires_complete_channel() {
+        let mut config = HumanApprovalToolConfig::default();
+        assert!(HumanApprovalTool::from_config(&config, None).is_none());
+
+        config.telegram_bot_token = Some("123:abc".into());
+        assert!(HumanApprovalTool::from_config(&config, None).is_none());
+
+        config.slack_bot_token = Some("xoxb-1".into());
+        config.slack_channel = Some("C123".into());
+        let tool = HumanApprovalTool::from_config(&config, None).unwrap();
+        assert!(matches!(tool.channel, ApprovalChannel::Slack { .. }));
+
+        config.telegram_chat_id = Some("-100".into());
+        let tool = HumanApprovalTool::from_config(&config, None).unwrap();
+        assert!(matches!(tool.channel, ApprovalChannel::Telegram { .. }));
+    }
+
+    #[test]
+    fn test_from_config_refuses_the_channel_bot_token() {
+        let mut config = HumanApprovalToolConfig {
+            telegram_bot_token: Some("123:abc".into()),
+            telegram_chat_id: Some("-100".into()),
+            ..Default::default()
+        };
+        assert!(HumanApprovalTool::from_config(&config, Some("123:abc")).is_none());
+        assert!(HumanApprovalTool::from_config(&config, Some("456:def")).is_some());
+
+        config.slack_bot_token = Some("xoxb-1".into());
+        config.slack_channel = Some("C123".into());
+        let tool = HumanApprovalTool::from_config(&config, Some("123:abc")).unwrap();
+        assert!(matches!(tool.channel, ApprovalChannel::Slack { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_execute_validates_options() {
+        let config = HumanApprovalToolConfig {
+            slack_bot_token: Some("xoxb-1".into()),
+            slack_channel: Some("C123".into()),
+            ..Default::default()
+        };
+        let tool = HumanApprovalTool::from_config(&config, None).unwrap();
+        let err = tool
+            .execute(
+                json!({"question": "Deploy?", "options": []}),
+                &ToolContext::new(),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("options"));
+    }
+}
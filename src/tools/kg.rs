@@ -0,0 +1,412 @@
+//! Knowledge graph tool — an in-memory subject-predicate-object triple store.
+//!
+//! Lets the agent record structured facts (`alice works_at acme`) and query
+//! them back with wildcards. The store lives in memory and is shared through
+//! [`TripleStore`]; the `save` operation writes it to
+//! `<workspace>/knowledge_graph.json`, which is loaded again the first time
+//! the tool runs with a workspace.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+
+use crate::error::{Result, ZeptoError};
+
+use super::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// File name of the persisted graph inside the workspace.
+pub const KNOWLEDGE_GRAPH_FILE: &str = "knowledge_graph.json";
+
+/// Maximum triples returned by a single `query` or `describe`.
+const MAX_RESULTS: usize = 100;
+
+/// A (subject, predicate, object) triple.
+pub type Triple = (String, String, String);
+
+/// Shared triple storage.
+pub type TripleStore = Arc<RwLock<Vec<Triple>>>;
+
+/// On-disk form of a triple.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredTriple {
+    subject: String,
+    predicate: String,
+    object: String,
+}
+
+/// Whether `triple` matches a pattern where `None` is a wildcard.
+fn matches(triple: &Triple, s: Option<&str>, p: Option<&str>, o: Option<&str>) -> bool {
+    s.is_none_or(|s| triple.0 == s)
+        && p.is_none_or(|p| triple.1 == p)
+        && o.is_none_or(|o| triple.2 == o)
+}
+
+fn triples_json(triples: &[&Triple]) -> Vec<Value> {
+    triples
+        .iter()
+        .take(MAX_RESULTS)
+        .map(|(s, p, o)| json!({ "subject": s, "predicate": p, "object": o }))
+        .collect()
+}
+
+/// Read a string field; `null` or a missing field is `None`.
+fn optional_field<'a>(args: &'a Value, name: &str) -> Option<&'a str> {
+    args.get(name)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+fn required_field<'a>(args: &'a Value, name: &str) -> Result<&'a str> {
+    optional_field(args, name)
+        .ok_or_else(|| ZeptoError::Tool(format!("Missing required field: {}", name)))
+}
+
+fn required_triple(args: &Value) -> Result<Triple> {
+    Ok((
+        required_field(args, "subject")?.to_string(),
+        required_field(args, "predicate")?.to_string(),
+        required_field(args, "object")?.to_string(),
+    ))
+}
+
+/// Tool for asserting, retracting, and querying knowledge graph triples.
+pub struct KnowledgeGraphTool {
+    store: TripleStore,
+    loaded: AtomicBool,
+}
+
+impl KnowledgeGraphTool {
+    /// Create a tool with an empty store.
+    pub fn new() -> Self {
+        Self::with_store(Arc::new(RwLock::new(Vec::new())))
+    }
+
+    /// Create a tool backed by an existing shared store.
+    pub fn with_store(store: TripleStore) -> Self {
+        Self {
+            store,
+            loaded: AtomicBool::new(false),
+        }
+    }
+
+    /// The shared store backing this tool.
+    pub fn store(&self) -> TripleStore {
+        Arc::clone(&self.store)
+    }
+
+    fn graph_path(ctx: &ToolContext) -> Option<PathBuf> {
+        ctx.workspace
+            .as_deref()
+            .map(|ws| Path::new(ws).join(KNOWLEDGE_GRAPH_FILE))
+    }
+
+    /// Merge the saved graph into the store once, on first use with a workspace.
+    async fn ensure_loaded(&self, ctx: &ToolContext) -> Result<()> {
+        let Some(path) = Self::graph_path(ctx) else {
+            return Ok(());
+        };
+        if self.loaded.swap(true, Ordering::SeqCst) || !path.exists() {
+            return Ok(());
+        }
+        let content = tokio::fs::read_to_string(&path).await?;
+        let saved: Vec<StoredTriple> = serde_json::from_str(&content).map_err(|e| {
+            ZeptoError::Tool(format!(
+                "Invalid knowledge graph file {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let mut store = self.store.write().await;
+        for t in saved {
+            let triple = (t.subject, t.predicate, t.object);
+            if !store.contains(&triple) {
+                store.push(triple);
+            }
+        }
+        Ok(())
+    }
+
+    async fn save(&self, ctx: &ToolContext) -> Result<String> {
+        let path = Self::graph_path(ctx).ok_or_else(|| {
+            ZeptoError::Tool("Saving the knowledge graph requires a workspace context".into())
+        })?;
+        let stored: Vec<StoredTriple> = self
+            .store
+            .read()
+            .await
+            .iter()
+            .map(|(s, p, o)| StoredTriple {
+                subject: s.clone(),
+                predicate: p.clone(),
+                object: o.clone(),
+            })
+            .collect();
+        let content = serde_json::to_string_pretty(&stored)?;
+        tokio::fs::write(&path, content).await?;
+        Ok(format!(
+            "Saved {} triple(s) to {}",
+            stored.len(),
+            KNOWLEDGE_GRAPH_FILE
+        ))
+    }
+}
+
+impl Default for KnowledgeGraphTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for KnowledgeGraphTool {
+    fn name(&self) -> &str {
+        "knowledge_graph"
+    }
+
+    fn description(&self) -> &str {
+        "Store and query structured facts as subject-predicate-object triples. Operations: \
+         assert (add a triple), retract (remove a triple), query (match triples; omit or null \
+         any of subject/predicate/object as a wildcard), describe (all triples about a \
+         subject), save (persist the graph to the workspace)."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Knowledge graph triples"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Memory
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["assert", "retract", "query", "describe", "save"]
+                },
+                "subject": {
+                    "type": ["string", "null"],
+                    "description": "Entity the fact is about, e.g. 'alice'"
+                },
+                "predicate": {
+                    "type": ["string", "null"],
+                    "description": "Relationship, e.g. 'works_at'"
+                },
+                "object": {
+                    "type": ["string", "null"],
+                    "description": "Value or related entity, e.g. 'acme'"
+                }
+            },
+            "required": ["operation"]
+        })
+    }
+
+    async fn dry_run(&self, args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        let field = |name: &str| args.get(name).and_then(Value::as_str).unwrap_or_default();
+        Ok(match field("operation") {
+            "query" | "describe" => DryRun::Execute,
+            "save" => DryRun::Describe("would save the knowledge graph".to_string()),
+            operation => DryRun::Describe(format!(
+                "would {} ({}, {}, {})",
+                operation,
+                field("subject"),
+                field("predicate"),
+                field("object")
+            )),
+        })
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let operation = required_field(&args, "operation")?;
+        self.ensure_loaded(ctx).await?;
+
+        let output = match operation {
+            "assert" => {
+                let triple = required_triple(&args)?;
+                let mut store = self.store.write().await;
+                if store.contains(&triple) {
+                    "Triple already known".to_string()
+                } else {
+                    store.push(triple);
+                    format!("Asserted triple ({} total)", store.len())
+                }
+            }
+            "retract" => {
+                let triple = required_triple(&args)?;
+                let mut store = self.store.write().await;
+                let before = store.len();
+                store.retain(|t| *t != triple);
+                if store.len() < before {
+                    "Retracted triple".to_string()
+                } else {
+                    "Triple not found".to_string()
+                }
+            }
+            "query" => {
+                let store = self.store.read().await;
+                let found: Vec<&Triple> = store
+                    .iter()
+                    .filter(|t| {
+                        matches(
+                            t,
+                            optional_field(&args, "subject"),
+                            optional_field(&args, "predicate"),
+                            optional_field(&args, "object"),
+                        )
+                    })
+                    .collect();
+                json!({
+                    "count": found.len(),
+                    "truncated": found.len() > MAX_RESULTS,
+                    "triples": triples_json(&found),
+                })
+                .to_string()
+            }
+            "describe" => {
+                let subject = required_field(&args, "subject")?;
+                let store = self.store.read().await;
+                let about: Vec<&Triple> = store.iter().filter(|t| t.0 == subject).collect();
+                let referenced_by: Vec<&Triple> = store.iter().filter(|t| t.2 == subject).collect();
+                json!({
+                    "subject": subject,
+                    "triples": triples_json(&about),
+                    "referenced_by": triples_json(&referenced_by),
+                })
+                .to_string()
+            }
+            "save" => self.save(ctx).await?,
+            other => {
+                return Err(ZeptoError::Tool(format!(
+                    "Unknown operation '{}'. Use assert, retract, query, describe, or save",
+                    other
+                )))
+            }
+        };
+        Ok(ToolOutput::llm_only(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn run(tool: &KnowledgeGraphTool, args: Value, ctx: &ToolContext) -> String {
+        tool.execute(args, ctx).await.unwrap().for_llm
+    }
+
+    async fn seed(tool: &KnowledgeGraphTool, ctx: &ToolContext) {
+        for (s, p, o) in [
+            ("alice", "works_at", "acme"),
+            ("bob", "works_at", "acme"),
+            ("alice", "likes", "tea"),
+        ] {
+            run(
+                tool,
+                json!({"operation": "assert", "subject": s, "predicate": p, "object": o}),
+                ctx,
+            )
+            .await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_assert_is_idempotent_and_retract_removes() {
+        let tool = KnowledgeGraphTool::new();
+        let ctx = ToolContext::new();
+        seed(&tool, &ctx).await;
+        let again = run(
+            &tool,
+            json!({"operation": "assert", "subject": "alice", "predicate": "likes", "object": "tea"}),
+            &ctx,
+        )
+        .await;
+        assert_eq!(again, "Triple already known");
+        assert_eq!(tool.store().read().await.len(), 3);
+
+        let out = run(
+            &tool,
+            json!({"operation": "retract", "subject": "alice", "predicate": "likes", "object": "tea"}),
+            &ctx,
+        )
+        .await;
+        assert_eq!(out, "Retracted triple");
+        assert_eq!(tool.store().read().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_query_with_wildcards() {
+        let tool = KnowledgeGraphTool::new();
+        let ctx = ToolContext::new();
+        seed(&tool, &ctx).await;
+
+        let out = run(
+            &tool,
+            json!({"operation": "query", "subject": null, "predicate": "works_at", "object": "acme"}),
+            &ctx,
+        )
+        .await;
+        let parsed: Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(parsed["count"], 2);
+
+        let out = run(&tool, json!({"operation": "query"}), &ctx).await;
+        let parsed: Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(parsed["count"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_describe_lists_outgoing_and_incoming() {
+        let tool = KnowledgeGraphTool::new();
+        let ctx = ToolContext::new();
+        seed(&tool, &ctx).await;
+
+        let out = run(
+            &tool,
+            json!({"operation": "describe", "subject": "acme"}),
+            &ctx,
+        )
+        .await;
+        let parsed: Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(parsed["triples"].as_array().unwrap().len(), 0);
+        assert_eq!(parsed["referenced_by"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_save_and_reload_from_workspace() {
+        let dir = TempDir::new().unwrap();
+        let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
+
+        let tool = KnowledgeGraphTool::new();
+        seed(&tool, &ctx).await;
+        run(&tool, json!({"operation": "save"}), &ctx).await;
+        assert!(dir.path().join(KNOWLEDGE_GRAPH_FILE).exists());
+
+        let fresh = KnowledgeGraphTool::new();
+        let out = run(
+            &fresh,
+            json!({"operation": "query", "subject": "alice"}),
+            &ctx,
+        )
+        .await;
+        let parsed: Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(parsed["count"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_save_requires_workspace() {
+        let tool = KnowledgeGraphTool::new();
+        let err = tool
+            .execute(json!({"operation": "save"}), &ToolContext::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("workspace"));
+    }
+}
@@ -13,7 +13,7 @@ use serde_json::{json, Value};
 use crate::error::{Result, ZeptoError};
 use crate::memory::longterm::LongTermMemory;
 
-use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+use super::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
 
 /// Tool for storing and retrieving long-term memories across sessions.
 pub struct LongTermMemoryTool {
@@ -113,6 +113,20 @@ Tip: call 'categories' before 'set' to reuse an existing category instead of cre
         })
     }
 
+    async fn dry_run(&self, args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        let key = args.get("key").and_then(Value::as_str).unwrap_or_default();
+        Ok(
+            match args
+                .get("action")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+            {
+                "get" | "search" | "list" | "categories" => DryRun::Execute,
+                action => DryRun::Describe(format!("would {} long-term memory '{}'", action, key)),
+            },
+        )
+    }
+
     async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
         let action = args
             .get("action")
@@ -456,6 +470,20 @@ mod tests {
         assert_eq!(params["required"], json!(["action"]));
     }
 
+    #[tokio::test]
+    async fn test_dry_run_describes_writes_and_runs_reads() {
+        let (tool, _dir) = temp_tool();
+        let c = ctx();
+
+        let set = json!({"action": "set", "key": "user:name", "value": "Alice"});
+        assert_eq!(
+            tool.dry_run(&set, &c).await.unwrap(),
+            DryRun::Describe("would set long-term memory 'user:name'".into())
+        );
+        let get = json!({"action": "get", "key": "user:name"});
+        assert_eq!(tool.dry_run(&get, &c).await.unwrap(), DryRun::Execute);
+    }
+
     #[tokio::test]
     async fn test_set_and_get() {
         let (tool, _dir) = temp_tool();
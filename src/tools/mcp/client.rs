@@ -1,18 +1,54 @@
 //! MCP client — transport-agnostic JSON-RPC 2.0 client.
+//!
+//! Clients for stdio servers remember how the server was started. When the
+//! process exits, the next request respawns it and repeats the initialize
+//! handshake; failed attempts back off exponentially, and until one succeeds
+//! every request fails fast with an "unavailable" error.
 
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use tokio::sync::RwLock;
+use tracing::{info, warn};
 
 use super::protocol::*;
 use super::transport::{HttpTransport, McpTransport, StdioTransport};
 
+/// Delay before the second reconnection attempt; doubles per failure.
+const RECONNECT_BASE_DELAY: Duration = Duration::from_secs(1);
+/// Longest delay between reconnection attempts.
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// How a stdio server was started, for respawning it after a crash.
+struct StdioCommand {
+    command: String,
+    args: Vec<String>,
+    env: HashMap<String, String>,
+    timeout_secs: u64,
+}
+
+/// Reconnection state after the server went away.
+#[derive(Default)]
+struct Reconnect {
+    /// Consecutive failed reconnection attempts.
+    failures: u32,
+    /// No attempt before this instant.
+    retry_at: Option<Instant>,
+}
+
 /// MCP client for communicating with MCP servers over any transport.
 pub struct McpClient {
-    /// Transport layer (HTTP, stdio, etc.).
-    transport: Arc<dyn McpTransport>,
+    /// Transport layer (HTTP, stdio, etc.); replaced when a stdio server is
+    /// respawned.
+    transport: std::sync::RwLock<Arc<dyn McpTransport>>,
+    /// Transport type identifier, fixed for the client's lifetime.
+    transport_type: String,
+    /// Set for stdio servers so they can be respawned.
+    respawn: Option<StdioCommand>,
+    /// Serializes reconnection attempts.
+    reconnect: tokio::sync::Mutex<Reconnect>,
     /// Atomic request ID counter.
     next_id: AtomicU64,
     /// Cached tool definitions.
@@ -34,6 +70,7 @@ impl McpClient {
     }
 
     /// Create a new MCP client with stdio transport (spawns child process).
+    /// The process is respawned if it exits.
     pub async fn new_stdio(
         name: &str,
         command: &str,
@@ -42,13 +79,23 @@ impl McpClient {
         timeout_secs: u64,
     ) -> Result<Self, String> {
         let transport = Arc::new(StdioTransport::spawn(command, args, env, timeout_secs).await?);
-        Ok(Self::with_transport(name, transport))
+        let mut client = Self::with_transport(name, transport);
+        client.respawn = Some(StdioCommand {
+            command: command.to_string(),
+            args: args.to_vec(),
+            env: env.clone(),
+            timeout_secs,
+        });
+        Ok(client)
     }
 
     /// Create a new MCP client with a custom transport.
     pub fn with_transport(name: &str, transport: Arc<dyn McpTransport>) -> Self {
         Self {
-            transport,
+            transport_type: transport.transport_type().to_string(),
+            transport: std::sync::RwLock::new(transport),
+            respawn: None,
+            reconnect: tokio::sync::Mutex::new(Reconnect::default()),
             next_id: AtomicU64::new(1),
             tools_cache: Arc::new(RwLock::new(None)),
             server_name: name.to_string(),
@@ -67,16 +114,93 @@ impl McpClient {
 
     /// Get the underlying transport type.
     pub fn transport_type(&self) -> &str {
-        self.transport.transport_type()
+        &self.transport_type
+    }
+
+    /// Whether the server is currently reachable, as far as the transport
+    /// can tell.
+    pub fn is_available(&self) -> bool {
+        self.current_transport().is_alive()
+    }
+
+    fn current_transport(&self) -> Arc<dyn McpTransport> {
+        Arc::clone(&self.transport.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// The transport to use for the next request, respawning a crashed
+    /// stdio server if the backoff allows.
+    async fn live_transport(&self) -> Result<Arc<dyn McpTransport>, String> {
+        let current = self.current_transport();
+        if current.is_alive() {
+            return Ok(current);
+        }
+        let Some(spawn) = &self.respawn else {
+            return Err(format!("MCP server '{}' is unavailable", self.server_name));
+        };
+
+        let mut state = self.reconnect.lock().await;
+        // Another caller may have reconnected while we waited.
+        let current = self.current_transport();
+        if current.is_alive() {
+            return Ok(current);
+        }
+        if state.failures == 0 && state.retry_at.is_none() {
+            warn!(server = %self.server_name, "MCP server process exited, reconnecting");
+        }
+        if let Some(retry_at) = state.retry_at {
+            let now = Instant::now();
+            if now < retry_at {
+                return Err(format!(
+                    "MCP server '{}' is unavailable (process exited; next reconnect in {}s)",
+                    self.server_name,
+                    (retry_at - now).as_secs() + 1
+                ));
+            }
+        }
+
+        let attempt = async {
+            let transport: Arc<dyn McpTransport> = Arc::new(
+                StdioTransport::spawn(&spawn.command, &spawn.args, &spawn.env, spawn.timeout_secs)
+                    .await?,
+            );
+            self.initialize_on(transport.as_ref()).await?;
+            Ok::<_, String>(transport)
+        };
+        match attempt.await {
+            Ok(transport) => {
+                *self.transport.write().unwrap_or_else(|e| e.into_inner()) = Arc::clone(&transport);
+                *state = Reconnect::default();
+                info!(server = %self.server_name, "MCP server reconnected");
+                Ok(transport)
+            }
+            Err(e) => {
+                state.failures += 1;
+                let delay = reconnect_delay(state.failures);
+                state.retry_at = Some(Instant::now() + delay);
+                warn!(
+                    server = %self.server_name,
+                    error = %e,
+                    retry_in_secs = delay.as_secs(),
+                    "MCP server reconnect failed"
+                );
+                Err(format!(
+                    "MCP server '{}' is unavailable (reconnect failed: {})",
+                    self.server_name, e
+                ))
+            }
+        }
     }
 
     /// Send a JSON-RPC request via transport and return the response.
     async fn send_request(&self, request: &McpRequest) -> Result<McpResponse, String> {
-        self.transport.send(request).await
+        self.live_transport().await?.send(request).await
     }
 
-    /// Send the initialize handshake.
-    pub async fn initialize(&self) -> Result<serde_json::Value, String> {
+    /// Run the initialize handshake over `transport`.
+    async fn initialize_on(
+        &self,
+        transport: &dyn McpTransport,
+    ) -> Result<serde_json::Value, String> {
         let params = InitializeParams::default();
         let request = McpRequest::new(
             self.next_request_id(),
@@ -84,7 +208,7 @@ impl McpClient {
             Some(serde_json::to_value(&params).map_err(|e| e.to_string())?),
         );
 
-        let response = self.send_request(&request).await?;
+        let response = transport.send(&request).await?;
         if let Some(error) = response.error {
             return Err(format!("MCP initialize error: {}", error.message));
         }
@@ -92,6 +216,12 @@ impl McpClient {
         Ok(response.result.unwrap_or(serde_json::Value::Null))
     }
 
+    /// Send the initialize handshake.
+    pub async fn initialize(&self) -> Result<serde_json::Value, String> {
+        let transport = self.live_transport().await?;
+        self.initialize_on(transport.as_ref()).await
+    }
+
     /// List available tools (cached after first call).
     pub async fn list_tools(&self) -> Result<Vec<McpTool>, String> {
         {
@@ -154,10 +284,17 @@ impl McpClient {
 
     /// Shut down the transport (kills stdio child process if applicable).
     pub async fn shutdown(&self) -> Result<(), String> {
-        self.transport.shutdown().await
+        self.current_transport().shutdown().await
     }
 }
 
+/// Backoff before the next reconnect after `failures` failed attempts.
+fn reconnect_delay(failures: u32) -> Duration {
+    RECONNECT_BASE_DELAY
+        .saturating_mul(1 << failures.saturating_sub(1).min(16))
+        .min(RECONNECT_MAX_DELAY)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -298,6 +435,80 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_reconnect_delay_backs_off() {
+        assert_eq!(reconnect_delay(1), Duration::from_secs(1));
+        assert_eq!(reconnect_delay(2), Duration::from_secs(2));
+        assert_eq!(reconnect_delay(4), Duration::from_secs(8));
+        assert_eq!(reconnect_delay(10), RECONNECT_MAX_DELAY);
+        assert_eq!(reconnect_delay(u32::MAX), RECONNECT_MAX_DELAY);
+    }
+
+    /// Echoes each Content-Length framed request back; exits on a request
+    /// mentioning "crash".
+    const CRASHY_ECHO: &str = r#"
+while IFS= read -r line; do
+  line="${line%%$'\r'}"
+  if [[ "$line" == Content-Length:* ]]; then
+    cl="${line#Content-Length: }"
+  fi
+  if [[ -z "$line" ]]; then
+    body=$(dd bs=1 count="$cl" 2>/dev/null)
+    if [[ "$body" == *crash* ]]; then exit 1; fi
+    printf "Content-Length: %d\r\n\r\n%s" "${#body}" "$body"
+  fi
+done
+"#;
+
+    #[tokio::test]
+    async fn test_stdio_client_reconnects_after_crash() {
+        let client = McpClient::new_stdio(
+            "crashy",
+            "bash",
+            &["-c".to_string(), CRASHY_ECHO.to_string()],
+            &HashMap::new(),
+            5,
+        )
+        .await
+        .unwrap();
+        client.initialize().await.unwrap();
+
+        assert!(client
+            .call_tool("crash", serde_json::json!({}))
+            .await
+            .is_err());
+        for _ in 0..50 {
+            if !client.is_available() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(!client.is_available());
+
+        // The next request respawns the server and redoes the handshake.
+        client.initialize().await.unwrap();
+        assert!(client.is_available());
+        let _ = client.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_stdio_client_unavailable_backs_off() {
+        let client = McpClient::new_stdio("gone", "true", &[], &HashMap::new(), 5)
+            .await
+            .unwrap();
+        for _ in 0..50 {
+            if !client.is_available() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let err = client.list_tools().await.unwrap_err();
+        assert!(err.contains("reconnect failed"), "{err}");
+        let err = client.list_tools().await.unwrap_err();
+        assert!(err.contains("next reconnect in"), "{err}");
+    }
+
     #[tokio::test]
     async fn test_cache_starts_empty() {
         let client = McpClient::new("test", "http://localhost:8080", 30);
@@ -1,4 +1,7 @@
 //! MCP transport abstractions — HTTP and stdio.
+//!
+//! The HTTP transport accepts both plain JSON replies and replies streamed
+//! as server-sent events, as Streamable HTTP servers may send either.
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -21,6 +24,12 @@ pub trait McpTransport: Send + Sync {
 
     /// Returns the transport type identifier ("http" or "stdio").
     fn transport_type(&self) -> &str;
+
+    /// Whether the server can still be reached. Only transports that own a
+    /// process can tell; the rest report `true` and surface errors per call.
+    fn is_alive(&self) -> bool {
+        true
+    }
 }
 
 /// HTTP transport for MCP — sends JSON-RPC requests via POST.
@@ -54,6 +63,10 @@ impl McpTransport for HttpTransport {
         let resp = self
             .http
             .post(&self.url)
+            .header(
+                reqwest::header::ACCEPT,
+                "application/json, text/event-stream",
+            )
             .json(request)
             .send()
             .await
@@ -65,6 +78,19 @@ impl McpTransport for HttpTransport {
             return Err(format!("HTTP {} from MCP server: {}", status, body));
         }
 
+        let is_sse = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        if is_sse {
+            let body = resp
+                .text()
+                .await
+                .map_err(|e| format!("Failed to read MCP event stream: {}", e))?;
+            return parse_sse_response(&body, request.id);
+        }
+
         resp.json::<McpResponse>()
             .await
             .map_err(|e| format!("Failed to parse MCP response: {}", e))
@@ -79,6 +105,33 @@ impl McpTransport for HttpTransport {
     }
 }
 
+/// Find the response to request `id` in a server-sent event stream. Each
+/// event's `data:` lines form one JSON-RPC message; notifications and
+/// responses to other requests are skipped.
+fn parse_sse_response(body: &str, id: u64) -> Result<McpResponse, String> {
+    let mut data = String::new();
+    // A trailing blank line closes the last event even if the server omitted it.
+    for line in body.lines().chain(std::iter::once("")) {
+        if let Some(value) = line.strip_prefix("data:") {
+            if !data.is_empty() {
+                data.push('\n');
+            }
+            data.push_str(value.strip_prefix(' ').unwrap_or(value));
+        } else if line.is_empty() && !data.is_empty() {
+            if let Ok(response) = serde_json::from_str::<McpResponse>(&data) {
+                if response.id.as_ref().and_then(|v| v.as_u64()) == Some(id) {
+                    return Ok(response);
+                }
+            }
+            data.clear();
+        }
+    }
+    Err(format!(
+        "MCP event stream ended without a response to request {}",
+        id
+    ))
+}
+
 /// Stdio transport for MCP — spawns a child process and communicates via
 /// header-framed JSON-RPC over stdin/stdout (Content-Length framing per
 /// the MCP stdio specification).
@@ -200,6 +253,14 @@ impl McpTransport for StdioTransport {
     fn transport_type(&self) -> &str {
         "stdio"
     }
+
+    fn is_alive(&self) -> bool {
+        match self.child.try_lock() {
+            Ok(mut child) => matches!(child.try_wait(), Ok(None)),
+            // Someone is shutting it down or waiting on it.
+            Err(_) => true,
+        }
+    }
 }
 
 /// Read headers from a MCP stdio stream until an empty line, returning the
@@ -276,6 +337,34 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_parse_sse_response() {
+        let body = "event: message\n\
+                    data: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\"}\n\n\
+                    event: message\n\
+                    data: {\"jsonrpc\":\"2.0\",\"id\":7,\n\
+                    data: \"result\":{\"tools\":[]}}\n";
+        let resp = parse_sse_response(body, 7).unwrap();
+        assert_eq!(resp.result, Some(serde_json::json!({"tools": []})));
+
+        let err = parse_sse_response(body, 8).unwrap_err();
+        assert!(err.contains("without a response to request 8"));
+    }
+
+    #[tokio::test]
+    async fn test_stdio_transport_detects_exit() {
+        let transport = StdioTransport::spawn("true", &[], &HashMap::new(), 5)
+            .await
+            .unwrap();
+        for _ in 0..50 {
+            if !transport.is_alive() {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        panic!("exited server still reported alive");
+    }
+
     #[tokio::test]
     async fn test_read_content_length_valid() {
         let input = b"Content-Length: 42\r\n\r\n";
@@ -8,7 +8,7 @@ use crate::tools::{Tool, ToolCategory, ToolContext, ToolOutput};
 
 /// Wraps a single MCP tool as a ZeptoClaw `Tool` implementation.
 pub struct McpToolWrapper {
-    /// Tool name as exposed to the agent (prefixed with server name).
+    /// Tool name as exposed to the agent (see [`McpToolWrapper::exposed_name`]).
     tool_name: String,
     /// Tool description.
     description: String,
@@ -21,9 +21,8 @@ pub struct McpToolWrapper {
 }
 
 impl McpToolWrapper {
-    /// Create a new wrapper for an MCP tool.
-    ///
-    /// Tool names are prefixed with the server name: `{server}_{tool}`.
+    /// Create a new wrapper for an MCP tool, named
+    /// [`exposed_name`](Self::exposed_name)`(server_name, remote_name)`.
     pub fn new(
         server_name: &str,
         remote_name: &str,
@@ -32,7 +31,7 @@ impl McpToolWrapper {
         client: Arc<McpClient>,
     ) -> Self {
         Self {
-            tool_name: format!("{}_{}", server_name, remote_name),
+            tool_name: Self::exposed_name(server_name, remote_name),
             description: description.to_string(),
             input_schema,
             remote_name: remote_name.to_string(),
@@ -40,6 +39,12 @@ impl McpToolWrapper {
         }
     }
 
+    /// The name the agent sees for `tool` on `server`: `mcp_{server}_{tool}`,
+    /// so server tools never collide with built-ins.
+    pub fn exposed_name(server_name: &str, remote_name: &str) -> String {
+        format!("mcp_{}_{}", server_name, remote_name)
+    }
+
     /// Get the prefixed tool name.
     pub fn tool_name(&self) -> &str {
         &self.tool_name
@@ -127,7 +132,7 @@ mod tests {
             json!({"type": "object"}),
             client,
         );
-        assert_eq!(wrapper.tool_name(), "myserver_read_file");
+        assert_eq!(wrapper.tool_name(), "mcp_myserver_read_file");
     }
 
     #[test]
@@ -170,7 +175,7 @@ mod tests {
     fn test_wrapper_tool_name_special_chars() {
         let client = make_client();
         let wrapper = McpToolWrapper::new("my-server.v2", "read-file", "desc", json!({}), client);
-        assert_eq!(wrapper.tool_name(), "my-server.v2_read-file");
+        assert_eq!(wrapper.tool_name(), "mcp_my-server.v2_read-file");
     }
 
     #[test]
@@ -250,7 +255,7 @@ mod tests {
             parameters: wrapper.parameters(),
         };
 
-        assert_eq!(def.name, "files_read");
+        assert_eq!(def.name, "mcp_files_read");
         assert_eq!(def.description, "Read a file from disk");
         assert_eq!(def.parameters, schema);
     }
@@ -16,7 +16,7 @@ use crate::memory::builtin_searcher::BuiltinSearcher;
 use crate::memory::traits::MemorySearcher;
 use crate::memory::{read_workspace_memory, search_workspace_memory};
 
-use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+use super::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
 
 /// Tool for searching workspace memory files.
 pub struct MemorySearchTool {
@@ -94,6 +94,11 @@ impl Tool for MemorySearchTool {
         })
     }
 
+    async fn dry_run(&self, _args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        // Only reads workspace memory files.
+        Ok(DryRun::Execute)
+    }
+
     async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
         let query = args
             .get("query")
@@ -195,6 +200,11 @@ impl Tool for MemoryGetTool {
         })
     }
 
+    async fn dry_run(&self, _args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        // Only reads workspace memory files.
+        Ok(DryRun::Execute)
+    }
+
     async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
         let path = args
             .get("path")
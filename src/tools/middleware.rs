@@ -0,0 +1,326 @@
+//! Middleware around individual tool executions.
+//!
+//! A [`ToolMiddleware`] wraps `Tool::execute` for every call made through a
+//! [`ToolRegistry`](super::ToolRegistry), after argument validation. The
+//! registry composes them as an onion: the first middleware added is the
+//! outermost, so it sees the call first and the result last. Each one calls
+//! [`ToolNext::run`] to continue inward, or returns early to short-circuit.
+//!
+//! With `[A, B]` installed a call runs `A → B → tool → B → A`.
+//!
+//! These differ from the pipeline's
+//! [`crate::agent::middleware::ToolMiddleware`], which wraps whole tool
+//! calls inside the middleware pipeline; these work with any registry,
+//! including the agent loop's and the kernel's.
+//!
+//! Built-ins:
+//! - [`TracingMiddleware`]: logs name, session, duration, error state, output
+//!   size, and truncated args/results.
+//! - [`ResultSizeLimitMiddleware`]: caps the size of the result sent to the LLM.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::error::Result;
+
+use super::rate_limit::ToolRateLimiter;
+use super::{Tool, ToolContext, ToolOutput};
+
+/// Cross-cutting behaviour around `Tool::execute`.
+#[async_trait]
+pub trait ToolMiddleware: Send + Sync {
+    /// Human-readable name for logging.
+    fn name(&self) -> &str;
+
+    /// Handle one call. `next.run(args, ctx)` continues to the next
+    /// middleware, or to the tool itself.
+    async fn call(
+        &self,
+        tool: &dyn Tool,
+        args: Value,
+        ctx: &ToolContext,
+        next: ToolNext<'_>,
+    ) -> Result<ToolOutput>;
+}
+
+/// Continuation handle passed to each [`ToolMiddleware`].
+pub struct ToolNext<'a> {
+    chain: &'a [Arc<dyn ToolMiddleware>],
+    tool: &'a dyn Tool,
+}
+
+impl<'a> ToolNext<'a> {
+    /// Start of the chain for `tool`.
+    pub fn new(chain: &'a [Arc<dyn ToolMiddleware>], tool: &'a dyn Tool) -> Self {
+        Self { chain, tool }
+    }
+
+    /// Run the remaining middlewares, then the tool.
+    pub async fn run(self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        match self.chain.split_first() {
+            Some((head, tail)) => {
+                let next = ToolNext {
+                    chain: tail,
+                    tool: self.tool,
+                };
+                head.call(self.tool, args, ctx, next).await
+            }
+            None => self.tool.execute(args, ctx).await,
+        }
+    }
+}
+
+/// Logs every call with its duration and truncated arguments and result.
+///
+/// Each event carries the same fields the registry's
+/// [`ToolUsage`](super::ToolUsage) aggregates (`tool`, `session`,
+/// `duration_ms`, `is_error`, `output_bytes`), so usage can be rebuilt from
+/// logs.
+pub struct TracingMiddleware {
+    max_chars: usize,
+}
+
+impl TracingMiddleware {
+    /// Log at most `max_chars` characters of arguments and results.
+    pub fn new(max_chars: usize) -> Self {
+        Self { max_chars }
+    }
+}
+
+impl Default for TracingMiddleware {
+    fn default() -> Self {
+        Self::new(200)
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for TracingMiddleware {
+    fn name(&self) -> &str {
+        "tracing"
+    }
+
+    async fn call(
+        &self,
+        tool: &dyn Tool,
+        args: Value,
+        ctx: &ToolContext,
+        next: ToolNext<'_>,
+    ) -> Result<ToolOutput> {
+        let args_preview = preview(&args.to_string(), self.max_chars);
+        let session = ToolRateLimiter::namespace(ctx);
+        let start = Instant::now();
+        let result = next.run(args, ctx).await;
+        let duration_ms = start.elapsed().as_millis() as u64;
+        match &result {
+            Ok(output) => info!(
+                tool = tool.name(),
+                session = %session,
+                duration_ms,
+                is_error = output.is_error,
+                output_bytes = output.for_llm.len(),
+                args = %args_preview,
+                result = %preview(&output.for_llm, self.max_chars),
+                "Tool call"
+            ),
+            Err(e) => warn!(
+                tool = tool.name(),
+                session = %session,
+                duration_ms,
+                is_error = true,
+                output_bytes = 0,
+                args = %args_preview,
+                error = %e,
+                "Tool call failed"
+            ),
+        }
+        result
+    }
+}
+
+/// Truncates the LLM-facing result of any tool to at most `max_bytes`,
+/// noting how much was cut.
+pub struct ResultSizeLimitMiddleware {
+    max_bytes: usize,
+}
+
+impl ResultSizeLimitMiddleware {
+    /// Keep at most `max_bytes` of each result.
+    pub fn new(max_bytes: usize) -> Self {
+        Self { max_bytes }
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for ResultSizeLimitMiddleware {
+    fn name(&self) -> &str {
+        "result_size_limit"
+    }
+
+    async fn call(
+        &self,
+        _tool: &dyn Tool,
+        args: Value,
+        ctx: &ToolContext,
+        next: ToolNext<'_>,
+    ) -> Result<ToolOutput> {
+        let mut output = next.run(args, ctx).await?;
+        let total = output.for_llm.len();
+        if total > self.max_bytes {
+            let mut end = self.max_bytes;
+            while !output.for_llm.is_char_boundary(end) {
+                end -= 1;
+            }
+            output.for_llm.truncate(end);
+            output.for_llm.push_str(&format!(
+                "\n[Result truncated: showing {} of {} bytes]",
+                end, total
+            ));
+        }
+        Ok(output)
+    }
+}
+
+/// The first `max_chars` characters of `text`, with an ellipsis if cut.
+fn preview(text: &str, max_chars: usize) -> String {
+    let mut chars = text.chars();
+    let head: String = chars.by_ref().take(max_chars).collect();
+    if chars.next().is_some() {
+        format!("{}…", head)
+    } else {
+        head
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::{EchoTool, ToolRegistry};
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    /// Records entry and exit order and tags the result.
+    struct Probe {
+        label: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl ToolMiddleware for Probe {
+        fn name(&self) -> &str {
+            self.label
+        }
+
+        async fn call(
+            &self,
+            _tool: &dyn Tool,
+            args: Value,
+            ctx: &ToolContext,
+            next: ToolNext<'_>,
+        ) -> Result<ToolOutput> {
+            self.log.lock().unwrap().push(format!("{} in", self.label));
+            let mut output = next.run(args, ctx).await?;
+            self.log.lock().unwrap().push(format!("{} out", self.label));
+            output.for_llm = format!("{}({})", self.label, output.for_llm);
+            Ok(output)
+        }
+    }
+
+    struct Deny;
+
+    #[async_trait]
+    impl ToolMiddleware for Deny {
+        fn name(&self) -> &str {
+            "deny"
+        }
+
+        async fn call(
+            &self,
+            tool: &dyn Tool,
+            _args: Value,
+            _ctx: &ToolContext,
+            _next: ToolNext<'_>,
+        ) -> Result<ToolOutput> {
+            Ok(ToolOutput::error(format!("{} skipped", tool.name())))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_first_added_is_outermost() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(EchoTool));
+        for label in ["a", "b"] {
+            registry.add_middleware(Arc::new(Probe {
+                label,
+                log: Arc::clone(&log),
+            }));
+        }
+
+        let output = registry
+            .execute("echo", json!({"message": "hi"}))
+            .await
+            .unwrap();
+
+        assert_eq!(output.for_llm, "a(b(hi))");
+        assert_eq!(*log.lock().unwrap(), ["a in", "b in", "b out", "a out"]);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_can_short_circuit() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(EchoTool));
+        registry.add_middleware(Arc::new(Deny));
+
+        let output = registry
+            .execute("echo", json!({"message": "hi"}))
+            .await
+            .unwrap();
+        assert!(output.is_error);
+        assert_eq!(output.for_llm, "echo skipped");
+    }
+
+    #[tokio::test]
+    async fn test_result_size_limit() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(EchoTool));
+        registry.add_middleware(Arc::new(ResultSizeLimitMiddleware::new(4)));
+
+        let output = registry
+            .execute("echo", json!({"message": "héllo world"}))
+            .await
+            .unwrap();
+        assert_eq!(
+            output.for_llm,
+            "hél\n[Result truncated: showing 4 of 12 bytes]"
+        );
+
+        let output = registry
+            .execute("echo", json!({"message": "ok"}))
+            .await
+            .unwrap();
+        assert_eq!(output.for_llm, "ok");
+    }
+
+    #[tokio::test]
+    async fn test_tracing_middleware_passes_result_through() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(EchoTool));
+        registry.add_middleware(Arc::new(TracingMiddleware::new(3)));
+
+        let output = registry
+            .execute("echo", json!({"message": "hello"}))
+            .await
+            .unwrap();
+        assert_eq!(output.for_llm, "hello");
+    }
+
+    #[test]
+    fn test_preview() {
+        assert_eq!(preview("hello", 10), "hello");
+        assert_eq!(preview("héllo", 2), "hé…");
+    }
+}
@@ -11,6 +11,7 @@
 //! - `ToolContext`: Execution context (channel, chat_id, workspace)
 //! - `ToolOutput`: Dual-audience result (LLM vs user)
 //! - `ToolRegistry`: Central registry for managing and executing tools
+//! - `ToolMiddleware`: Cross-cutting behaviour wrapped around every execution
 //!
 //! # Built-in Tools
 //!
@@ -24,12 +25,15 @@
 //! - `DdgSearchTool`: Free web search via DuckDuckGo HTML scraping (fallback)
 //! - `SearxngSearchTool`: Web search via self-hosted SearXNG instance
 //! - `WebFetchTool`: Fetch URL content and extract text
+//! - `HttpFetchTool`: HTTP requests with host lists, SSRF checks and size limits
 //! - `MessageTool`: Send proactive outbound chat messages
+//! - `SendMessageTool`: Rate-limited notifications to allowlisted chats
 //! - `MemorySearchTool`: Search workspace markdown memory files
 //! - `MemoryGetTool`: Read memory files with line windows
 //! - `WhatsAppTool`: Send WhatsApp Cloud API messages
 //! - `GoogleSheetsTool`: Read and write Google Sheets ranges
 //! - `R8rTool`: Execute r8r workflows for deterministic automation
+//! - `SpawnAgentTool`: Run a task in a fresh sub-agent and return its summary
 //!
 //! # Example
 //!
@@ -56,18 +60,29 @@
 //! # });
 //! ```
 
+#[cfg(feature = "tool-vision")]
+pub mod analyze_image;
 #[cfg(feature = "android")]
 pub mod android;
+pub mod apply_patch;
 pub mod approval;
+pub mod archive;
+pub mod artifact;
+pub mod ask_user;
 pub mod binary_plugin;
 pub mod browser;
+pub mod calc;
 pub mod clarification;
 pub mod composed;
+pub mod conditional;
+pub mod container_registry;
 pub mod cron;
 pub mod custom;
 pub mod delegate;
 pub mod diff;
 pub mod docx_read;
+pub mod download;
+pub mod extract_text;
 pub mod filesystem;
 pub mod find;
 pub mod git;
@@ -76,41 +91,76 @@ pub mod google;
 pub mod grep;
 pub mod gsheets;
 pub mod hardware;
+pub mod http_fetch;
 pub mod http_request;
+pub mod humanloop;
+pub mod kg;
 pub mod longterm_memory;
 pub mod mcp;
 pub mod memory;
 pub mod message;
+pub mod middleware;
 pub mod output;
 pub mod pdf_read;
 pub mod plugin;
+pub mod process;
 pub mod project;
+pub mod provider_schema;
 pub mod r8r;
+pub mod rate_limit;
 mod registry;
 pub mod reminder;
+pub mod run_python;
+pub mod schema;
+pub mod scratchpad;
 #[cfg(feature = "screenshot")]
 pub mod screenshot;
+pub mod script;
+pub mod semver;
+pub mod send_message;
+pub mod session_memory;
 pub mod shell;
 pub mod skills_install;
 pub mod skills_search;
 pub mod spawn;
+pub mod spawn_agent;
+pub mod speak;
+#[cfg(feature = "tool-sqlite")]
+pub mod sqlite_query;
 pub mod stripe;
 #[cfg(feature = "panel")]
 pub mod task;
+pub mod todo;
 pub mod transcribe;
+pub mod transcript;
 mod types;
+pub mod usage;
+#[cfg(feature = "wasm-plugins")]
+pub mod wasm_plugin;
 pub mod web;
 pub mod whatsapp;
 
+pub use self::semver::SemanticVersionTool;
+#[cfg(feature = "tool-vision")]
+pub use analyze_image::AnalyzeImageTool;
 #[cfg(feature = "android")]
 pub use android::AndroidTool;
+pub use apply_patch::ApplyPatchTool;
+pub use archive::ArchiveTool;
+pub use artifact::ArtifactStore;
+pub use ask_user::AskUserTool;
 pub use binary_plugin::BinaryPluginTool;
 pub use browser::BrowserTool;
+pub use calc::CalcTool;
 pub use clarification::AskClarificationTool;
 pub use composed::{ComposedTool, CreateToolTool};
+pub use conditional::ConditionalTool;
+pub use container_registry::ContainerRegistryTool;
 pub use custom::CustomTool;
 pub use delegate::DelegateTool;
 pub use docx_read::DocxReadTool;
+pub use download::DownloadTool;
+pub use extract_text::ExtractTextTool;
 pub use find::FindTool;
 pub use git::GitTool;
 #[cfg(feature = "google")]
@@ -118,24 +168,42 @@ pub use google::GoogleTool;
 pub use grep::GrepTool;
 pub use gsheets::GoogleSheetsTool;
 pub use hardware::HardwareTool;
+pub use http_fetch::HttpFetchTool;
 pub use http_request::HttpRequestTool;
+pub use humanloop::HumanApprovalTool;
+pub use kg::KnowledgeGraphTool;
 pub use longterm_memory::LongTermMemoryTool;
 pub use memory::{MemoryGetTool, MemorySearchTool};
 pub use message::MessageTool;
+pub use middleware::{ResultSizeLimitMiddleware, ToolMiddleware, ToolNext, TracingMiddleware};
 pub use pdf_read::PdfReadTool;
+pub use process::{ProcessRegistry, ProcessTool};
 pub use project::ProjectTool;
+pub use provider_schema::{ProviderTools, ToolApi, ToolNameMap};
 pub use r8r::R8rTool;
 pub use registry::ToolRegistry;
 pub use reminder::ReminderTool;
+pub use run_python::RunPythonTool;
+pub use scratchpad::ScratchpadTool;
 #[cfg(feature = "screenshot")]
 pub use screenshot::WebScreenshotTool;
+pub use send_message::SendMessageTool;
 pub use skills_install::InstallSkillTool;
 pub use skills_search::FindSkillsTool;
+pub use spawn_agent::SpawnAgentTool;
+pub use speak::SpeakTool;
+#[cfg(feature = "tool-sqlite")]
+pub use sqlite_query::SqliteQueryTool;
 pub use stripe::StripeTool;
 #[cfg(feature = "panel")]
 pub use task::TaskTool;
+pub use todo::TodoTool;
 pub use transcribe::TranscribeTool;
-pub use types::{Tool, ToolCategory, ToolContext, ToolOutput};
+pub use transcript::TranscriptFormatterTool;
+pub use types::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput, ToolProgress};
+pub use usage::{ToolUsage, ToolUsageStats};
+#[cfg(feature = "wasm-plugins")]
+pub use wasm_plugin::WasmPluginTool;
 pub use web::{
     is_blocked_host, resolve_and_check_host, DdgSearchTool, SearxngSearchTool, WebFetchTool,
     WebSearchTool,
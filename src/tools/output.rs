@@ -94,6 +94,46 @@ pub fn truncate_tool_output(output: &str, max_lines: usize, max_bytes: usize) ->
     result
 }
 
+/// Truncate output longer than `max_bytes` by keeping its head and tail.
+///
+/// The removed middle is replaced by a `[... N bytes truncated ...]` marker
+/// that also notes the original size. Useful when the end of the output
+/// (exit summaries, the last error) matters as much as the beginning. Cut
+/// points are moved outward or inward to UTF-8 character boundaries, so the
+/// kept text may be a few bytes shorter than `max_bytes`.
+///
+/// # Examples
+///
+/// ```
+/// use zeptoclaw::tools::output::truncate_middle;
+///
+/// assert_eq!(truncate_middle("short", 100), "short");
+///
+/// let truncated = truncate_middle(&"ab".repeat(50), 10);
+/// assert!(truncated.starts_with("ababa\n[... 90 bytes truncated"));
+/// assert!(truncated.ends_with("babab"));
+/// ```
+pub fn truncate_middle(output: &str, max_bytes: usize) -> String {
+    if output.len() <= max_bytes {
+        return output.to_string();
+    }
+    let mut head_end = max_bytes / 2;
+    while !output.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    let mut tail_start = output.len() - (max_bytes - max_bytes / 2);
+    while !output.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+    format!(
+        "{}\n[... {} bytes truncated (original size {} bytes) ...]\n{}",
+        &output[..head_end],
+        tail_start - head_end,
+        output.len(),
+        &output[tail_start..]
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,4 +223,19 @@ mod tests {
             result
         );
     }
+
+    #[test]
+    fn truncate_middle_keeps_head_and_tail_on_char_boundaries() {
+        // 'é' is two bytes, so every odd offset is inside a character.
+        let input = "é".repeat(100);
+        let result = truncate_middle(&input, 11);
+        let (head, rest) = result.split_once('\n').unwrap();
+        let (marker, tail) = rest.split_once('\n').unwrap();
+        assert_eq!(head, "éé");
+        assert_eq!(tail, "ééé");
+        assert_eq!(
+            marker,
+            "[... 190 bytes truncated (original size 200 bytes) ...]"
+        );
+    }
 }
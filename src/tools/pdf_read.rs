@@ -10,7 +10,7 @@ use serde_json::{json, Value};
 use std::path::PathBuf;
 
 use crate::error::{Result, ZeptoError};
-use crate::security::{revalidate_path, validate_path_in_workspace};
+use crate::security::Sandbox;
 
 use super::{Tool, ToolContext, ToolOutput};
 
@@ -43,18 +43,22 @@ impl PdfReadTool {
     /// - The file does not have a `.pdf` extension.
     /// - The file does not exist.
     pub fn resolve_path(&self, path: &str) -> Result<PathBuf> {
-        let safe = validate_path_in_workspace(path, &self.workspace)?;
-        if safe.as_path().extension().and_then(|e| e.to_str()) != Some("pdf") {
+        Self::resolve_in(&Sandbox::new(&self.workspace), path)
+    }
+
+    fn resolve_in(sandbox: &Sandbox, path: &str) -> Result<PathBuf> {
+        let safe = sandbox.resolve(path)?;
+        if safe.extension().and_then(|e| e.to_str()) != Some("pdf") {
             return Err(ZeptoError::Tool(
                 "Only .pdf files are supported".to_string(),
             ));
         }
         // TOCTOU: re-validate immediately before I/O
-        revalidate_path(safe.as_path(), &self.workspace)?;
-        if !safe.as_path().exists() {
+        sandbox.check(&safe)?;
+        if !safe.exists() {
             return Err(ZeptoError::Tool(format!("File not found: {path}")));
         }
-        Ok(safe.into_path_buf())
+        Ok(safe)
     }
 
     /// Truncate `text` to at most `max_chars` characters.
@@ -152,7 +156,7 @@ impl Tool for PdfReadTool {
         })
     }
 
-    async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
         let path_str = args["path"].as_str().unwrap_or("");
         if path_str.is_empty() {
             return Err(ZeptoError::Tool(
@@ -166,7 +170,10 @@ impl Tool for PdfReadTool {
             .unwrap_or(DEFAULT_MAX_CHARS)
             .min(HARD_MAX_CHARS);
 
-        let resolved = self.resolve_path(path_str)?;
+        let resolved = Self::resolve_in(
+            &ctx.sandbox_or(&self.workspace),
+            &ctx.resolve_input_path(path_str)?,
+        )?;
 
         // Size guard before we do any I/O-heavy work.
         let meta = tokio::fs::metadata(&resolved)
@@ -0,0 +1,797 @@
+//! Background process tool.
+//!
+//! Lets the agent start long-running commands (dev servers, file watchers)
+//! and keep talking while they run. Processes live in a [`ProcessRegistry`]
+//! owned by the agent and handed to the tool through
+//! [`ToolContext::processes`]. The registry reaps every child, keeps the tail
+//! of its output, caps how many run at once, and kills them all on shutdown.
+//!
+//! Processes run directly on the host, so the tool is only registered with
+//! the native runtime.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::error::{Result, ZeptoError};
+use crate::runtime::native::ProcessTree;
+use crate::security::{PolicyDecision, ShellPolicy, ShellSecurityConfig};
+use crate::session::{CommandOutcome, CommandRecord};
+
+use super::shell::resolve_cwd;
+use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Default cap on concurrently running background processes.
+pub const DEFAULT_MAX_PROCESSES: usize = 8;
+
+/// Default bytes of output kept per stream for each process.
+pub const DEFAULT_LOG_TAIL_BYTES: usize = 64 * 1024;
+
+/// Default bytes per stream returned by the `logs` action.
+const DEFAULT_LOGS_BYTES: usize = 4 * 1024;
+
+/// Lifecycle state of a background process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcessStatus {
+    /// Still running.
+    Running,
+    /// Exited on its own. `None` means it was terminated by a signal.
+    Exited(Option<i32>),
+    /// Terminated through [`ProcessRegistry::kill`] or shutdown.
+    Killed,
+    /// Waiting for the process failed.
+    Failed(String),
+}
+
+impl std::fmt::Display for ProcessStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessStatus::Running => write!(f, "running"),
+            ProcessStatus::Exited(Some(code)) => write!(f, "exited with code {}", code),
+            ProcessStatus::Exited(None) => write!(f, "terminated by signal"),
+            ProcessStatus::Killed => write!(f, "killed"),
+            ProcessStatus::Failed(e) => write!(f, "failed: {}", e),
+        }
+    }
+}
+
+/// Snapshot of a background process.
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    /// Handle returned by [`ProcessRegistry::spawn`].
+    pub id: String,
+    /// The command line.
+    pub command: String,
+    /// OS process id of the shell running the command.
+    pub pid: Option<u32>,
+    /// Current state.
+    pub status: ProcessStatus,
+    /// Time since the process started (until it finished, if it has).
+    pub elapsed: Duration,
+}
+
+impl std::fmt::Display for ProcessInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)?;
+        if let Some(pid) = self.pid {
+            write!(f, " (pid {})", pid)?;
+        }
+        write!(
+            f,
+            ": {} after {}s — {}",
+            self.status,
+            self.elapsed.as_secs(),
+            self.command
+        )
+    }
+}
+
+/// The most recent bytes of one output stream.
+#[derive(Debug, Default)]
+struct LogTail {
+    bytes: Vec<u8>,
+    dropped: u64,
+}
+
+impl LogTail {
+    fn push(&mut self, chunk: &[u8], cap: usize) {
+        self.bytes.extend_from_slice(chunk);
+        if self.bytes.len() > cap {
+            let excess = self.bytes.len() - cap;
+            self.bytes.drain(..excess);
+            self.dropped += excess as u64;
+        }
+    }
+
+    /// The last `max_bytes` as text, with a marker if anything was cut.
+    fn tail(&self, max_bytes: usize) -> String {
+        let start = self.bytes.len().saturating_sub(max_bytes);
+        let text = String::from_utf8_lossy(&self.bytes[start..]);
+        let omitted = self.dropped + start as u64;
+        if omitted == 0 {
+            text.into_owned()
+        } else {
+            format!("[... {} earlier bytes omitted ...]\n{}", omitted, text)
+        }
+    }
+}
+
+struct ManagedProcess {
+    command: String,
+    pid: Option<u32>,
+    tree: ProcessTree,
+    started_at: Instant,
+    /// State and the time it stopped running.
+    status: Mutex<(ProcessStatus, Option<Instant>)>,
+    stdout: Mutex<LogTail>,
+    stderr: Mutex<LogTail>,
+    kill_tx: Mutex<Option<oneshot::Sender<()>>>,
+    reaper: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ManagedProcess {
+    fn info(&self, id: u64) -> ProcessInfo {
+        let (status, finished_at) = self.status.lock().unwrap().clone();
+        ProcessInfo {
+            id: format_id(id),
+            command: self.command.clone(),
+            pid: self.pid,
+            status,
+            elapsed: finished_at.unwrap_or_else(Instant::now) - self.started_at,
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.status.lock().unwrap().0 == ProcessStatus::Running
+    }
+
+    fn finish(&self, status: ProcessStatus) {
+        *self.status.lock().unwrap() = (status, Some(Instant::now()));
+    }
+
+    /// Ask the reaper to kill the process and wait until it has been reaped.
+    async fn kill(&self) {
+        let kill_tx = self.kill_tx.lock().unwrap().take();
+        if let Some(tx) = kill_tx {
+            let _ = tx.send(());
+        }
+        let reaper = self.reaper.lock().unwrap().take();
+        if let Some(handle) = reaper {
+            let _ = handle.await;
+        }
+    }
+}
+
+fn format_id(id: u64) -> String {
+    format!("proc-{}", id)
+}
+
+fn parse_id(id: &str) -> Result<u64> {
+    id.strip_prefix("proc-")
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(|| ZeptoError::NotFound(format!("No background process '{}'", id)))
+}
+
+/// Called with how a background process ended, how long it ran and the
+/// kept tail of its output.
+pub type ExitHook = Box<dyn FnOnce(&ProcessStatus, Duration, &str) + Send>;
+
+/// Background processes started by one agent.
+///
+/// Each process gets a reaper task that waits for it to exit (or kills it on
+/// request) and reader tasks that keep the tail of stdout and stderr. Up to
+/// `max_processes` may run at once; records of finished processes are kept
+/// for `status` and `logs` until they are crowded out by newer ones.
+/// Dropping the registry kills everything still running.
+pub struct ProcessRegistry {
+    max_processes: usize,
+    log_tail_bytes: usize,
+    next_id: AtomicU64,
+    processes: Mutex<BTreeMap<u64, Arc<ManagedProcess>>>,
+}
+
+impl Default for ProcessRegistry {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_PROCESSES, DEFAULT_LOG_TAIL_BYTES)
+    }
+}
+
+impl std::fmt::Debug for ProcessRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProcessRegistry")
+            .field("max_processes", &self.max_processes)
+            .field("tracked", &self.processes.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl ProcessRegistry {
+    /// Create a registry allowing `max_processes` concurrent processes and
+    /// keeping `log_tail_bytes` of each output stream.
+    pub fn new(max_processes: usize, log_tail_bytes: usize) -> Self {
+        Self {
+            max_processes,
+            log_tail_bytes,
+            next_id: AtomicU64::new(1),
+            processes: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Start `command` with `sh -c` in the background and return its id.
+    ///
+    /// Must be called from within a Tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ZeptoError::Tool` if the cap on running processes is reached
+    /// or the process cannot be started.
+    pub fn spawn(&self, command: &str, cwd: Option<&Path>) -> Result<String> {
+        self.spawn_with_exit_hook(command, cwd, None)
+    }
+
+    /// Like [`spawn`](Self::spawn), calling `on_exit` once the process has
+    /// been reaped.
+    pub fn spawn_with_exit_hook(
+        &self,
+        command: &str,
+        cwd: Option<&Path>,
+        on_exit: Option<ExitHook>,
+    ) -> Result<String> {
+        let mut processes = self.processes.lock().unwrap();
+        let running = processes.values().filter(|p| p.is_running()).count();
+        if running >= self.max_processes {
+            return Err(ZeptoError::Tool(format!(
+                "Too many background processes running (max {}); kill one first",
+                self.max_processes
+            )));
+        }
+
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(cwd) = cwd {
+            cmd.current_dir(cwd);
+        }
+        // Own process group (a job object on Windows, see `ProcessTree`), so
+        // killing reaches everything the command starts.
+        #[cfg(unix)]
+        cmd.process_group(0);
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| ZeptoError::Tool(format!("Failed to start process: {}", e)))?;
+        let (kill_tx, kill_rx) = oneshot::channel();
+        let process = Arc::new(ManagedProcess {
+            command: command.to_string(),
+            pid: child.id(),
+            tree: ProcessTree::of(&child),
+            started_at: Instant::now(),
+            status: Mutex::new((ProcessStatus::Running, None)),
+            stdout: Mutex::new(LogTail::default()),
+            stderr: Mutex::new(LogTail::default()),
+            kill_tx: Mutex::new(Some(kill_tx)),
+            reaper: Mutex::new(None),
+        });
+
+        let cap = self.log_tail_bytes;
+        if let Some(stdout) = child.stdout.take() {
+            let process = Arc::clone(&process);
+            tokio::spawn(async move { capture(stdout, &process.stdout, cap).await });
+        }
+        if let Some(stderr) = child.stderr.take() {
+            let process = Arc::clone(&process);
+            tokio::spawn(async move { capture(stderr, &process.stderr, cap).await });
+        }
+
+        let reaped = Arc::clone(&process);
+        let reaper = tokio::spawn(async move {
+            tokio::select! {
+                status = child.wait() => reaped.finish(match status {
+                    Ok(status) => ProcessStatus::Exited(status.code()),
+                    Err(e) => ProcessStatus::Failed(e.to_string()),
+                }),
+                _ = kill_rx => {
+                    reaped.tree.kill();
+                    // Kills the direct child where process groups are
+                    // unavailable, and reaps it either way.
+                    let _ = child.kill().await;
+                    reaped.finish(ProcessStatus::Killed);
+                }
+            }
+            if let Some(on_exit) = on_exit {
+                let (status, finished_at) = reaped.status.lock().unwrap().clone();
+                let elapsed = finished_at.unwrap_or_else(Instant::now) - reaped.started_at;
+                let output = format!(
+                    "{}{}",
+                    reaped.stdout.lock().unwrap().tail(usize::MAX),
+                    reaped.stderr.lock().unwrap().tail(usize::MAX)
+                );
+                on_exit(&status, elapsed, &output);
+            }
+        });
+        *process.reaper.lock().unwrap() = Some(reaper);
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        processes.insert(id, process);
+        self.prune(&mut processes);
+        Ok(format_id(id))
+    }
+
+    /// Drop the oldest finished records beyond `max_processes`.
+    fn prune(&self, processes: &mut BTreeMap<u64, Arc<ManagedProcess>>) {
+        let finished: Vec<u64> = processes
+            .iter()
+            .filter(|(_, p)| !p.is_running())
+            .map(|(id, _)| *id)
+            .collect();
+        let excess = finished.len().saturating_sub(self.max_processes);
+        for id in finished.into_iter().take(excess) {
+            processes.remove(&id);
+        }
+    }
+
+    fn get(&self, id: &str) -> Result<(u64, Arc<ManagedProcess>)> {
+        let key = parse_id(id)?;
+        self.processes
+            .lock()
+            .unwrap()
+            .get(&key)
+            .map(|p| (key, Arc::clone(p)))
+            .ok_or_else(|| ZeptoError::NotFound(format!("No background process '{}'", id)))
+    }
+
+    /// Current state of a process.
+    pub fn status(&self, id: &str) -> Result<ProcessInfo> {
+        let (key, process) = self.get(id)?;
+        Ok(process.info(key))
+    }
+
+    /// The last `max_bytes` of a process's stdout and stderr.
+    pub fn logs(&self, id: &str, max_bytes: usize) -> Result<(String, String)> {
+        let (_, process) = self.get(id)?;
+        let stdout = process.stdout.lock().unwrap().tail(max_bytes);
+        let stderr = process.stderr.lock().unwrap().tail(max_bytes);
+        Ok((stdout, stderr))
+    }
+
+    /// Kill a process (and everything it started) and wait for it to be
+    /// reaped. Killing a finished process is a no-op.
+    pub async fn kill(&self, id: &str) -> Result<ProcessInfo> {
+        let (key, process) = self.get(id)?;
+        process.kill().await;
+        Ok(process.info(key))
+    }
+
+    /// All tracked processes, oldest first.
+    pub fn list(&self) -> Vec<ProcessInfo> {
+        self.processes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, p)| p.info(*id))
+            .collect()
+    }
+
+    /// Kill every running process and wait for them to be reaped.
+    pub async fn shutdown(&self) {
+        let processes: Vec<_> = self.processes.lock().unwrap().values().cloned().collect();
+        for process in processes {
+            process.kill().await;
+        }
+    }
+}
+
+impl Drop for ProcessRegistry {
+    fn drop(&mut self) {
+        let processes = self.processes.get_mut().unwrap_or_else(|e| e.into_inner());
+        for process in processes.values().filter(|p| p.is_running()) {
+            process.tree.kill();
+            if let Some(tx) = process.kill_tx.lock().unwrap().take() {
+                let _ = tx.send(());
+            }
+        }
+    }
+}
+
+/// Copy a stream into `tail` until it closes.
+async fn capture<R: AsyncRead + Unpin>(mut reader: R, tail: &Mutex<LogTail>, cap: usize) {
+    let mut buf = [0u8; 4096];
+    loop {
+        match reader.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => tail.lock().unwrap().push(&buf[..n], cap),
+        }
+    }
+}
+
+/// Tool for running and managing background processes.
+///
+/// # Parameters
+/// - `action`: One of `spawn`, `status`, `logs`, `kill`, `list` (required)
+/// - `command`: Command to start, for `spawn`
+/// - `cwd`: Directory to start in, relative to the workspace, for `spawn`
+/// - `id`: Process handle, for `status`, `logs`, and `kill`
+/// - `tail_bytes`: Bytes of each stream returned by `logs` (default 4KB)
+///
+/// Commands go through the same blocklist and shell policy as the shell
+/// tool. Commands the policy would send for approval are refused, since a
+/// background start cannot wait on a reviewer.
+pub struct ProcessTool {
+    security_config: ShellSecurityConfig,
+    policy: Option<Arc<ShellPolicy>>,
+}
+
+impl ProcessTool {
+    /// Create a process tool with the default security configuration.
+    pub fn new() -> Self {
+        Self::with_security(ShellSecurityConfig::new())
+    }
+
+    /// Create a process tool with a custom security configuration.
+    pub fn with_security(security_config: ShellSecurityConfig) -> Self {
+        Self {
+            security_config,
+            policy: None,
+        }
+    }
+
+    /// Apply a command policy on top of the security blocklist.
+    pub fn with_policy(mut self, policy: ShellPolicy) -> Self {
+        self.policy = Some(Arc::new(policy));
+        self
+    }
+
+    fn check_command(&self, command: &str, ctx: &ToolContext) -> Result<()> {
+        self.security_config.validate_command(command)?;
+        let Some(policy) = ctx.shell_policy.as_ref().or(self.policy.as_ref()) else {
+            return Ok(());
+        };
+        match policy.evaluate(command, ctx.workspace.as_deref().map(Path::new)) {
+            PolicyDecision::Allow => Ok(()),
+            PolicyDecision::Deny(reason) => Err(ZeptoError::SecurityViolation(format!(
+                "Command blocked by shell policy: {}",
+                reason
+            ))),
+            PolicyDecision::Ask(reason) => Err(ZeptoError::SecurityViolation(format!(
+                "Command requires human approval ({}), which background processes cannot wait for",
+                reason
+            ))),
+        }
+    }
+
+    fn execute_spawn(
+        &self,
+        registry: &ProcessRegistry,
+        args: &Value,
+        ctx: &ToolContext,
+    ) -> Result<String> {
+        let command = args
+            .get("command")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ZeptoError::Tool("Missing 'command' for spawn".into()))?;
+        self.check_command(command, ctx)?;
+
+        let cwd = match args.get("cwd").and_then(|v| v.as_str()) {
+            Some(cwd) => Some(resolve_cwd(cwd, ctx.sandbox().as_deref())?),
+            None => ctx.workspace.as_deref().map(Into::into),
+        };
+        let record = CommandRecord::new(
+            "process",
+            command,
+            &cwd.as_ref()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default(),
+        );
+        // Only what recording needs, so the turn's progress channel and
+        // cancellation are not kept alive with the process.
+        let record_ctx = ToolContext {
+            command_log: ctx.command_log.clone(),
+            session_key: ctx.session_key.clone(),
+            ..ToolContext::default()
+        };
+        let on_exit: ExitHook = Box::new(move |status, elapsed, output| {
+            let (outcome, exit_code) = match status {
+                ProcessStatus::Exited(code) => (CommandOutcome::Exited, *code),
+                ProcessStatus::Killed => (CommandOutcome::Killed, None),
+                ProcessStatus::Running | ProcessStatus::Failed(_) => (CommandOutcome::Failed, None),
+            };
+            record_ctx.record_command(record.finished(outcome, exit_code, elapsed, output));
+        });
+        let id = registry.spawn_with_exit_hook(command, cwd.as_deref(), Some(on_exit))?;
+        Ok(format!(
+            "Started {}. Use action 'status' or 'logs' with this id to check on it, and 'kill' to stop it.",
+            id
+        ))
+    }
+}
+
+impl Default for ProcessTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for ProcessTool {
+    fn name(&self) -> &str {
+        "process"
+    }
+
+    fn description(&self) -> &str {
+        "Run long-lived commands (dev servers, watchers) in the background. Actions: spawn, status, logs, kill, list."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Background process"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Shell
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["spawn", "status", "logs", "kill", "list"],
+                    "description": "Action to perform"
+                },
+                "command": {
+                    "type": "string",
+                    "description": "Shell command to start in the background (spawn)"
+                },
+                "cwd": {
+                    "type": "string",
+                    "description": "Directory to start in, relative to the workspace (spawn)"
+                },
+                "id": {
+                    "type": "string",
+                    "description": "Process id returned by spawn (status, logs, kill)"
+                },
+                "tail_bytes": {
+                    "type": "integer",
+                    "description": "Bytes of stdout and stderr to return (logs, default: 4096)"
+                }
+            },
+            "required": ["action"]
+        })
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let action = args
+            .get("action")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ZeptoError::Tool("Missing 'action' argument".into()))?;
+        let registry = ctx.processes.as_ref().ok_or_else(|| {
+            ZeptoError::Tool("Background processes are not available here".into())
+        })?;
+        let id = || {
+            args.get("id")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ZeptoError::Tool(format!("Missing 'id' for {}", action)))
+        };
+
+        let s = match action {
+            "spawn" => self.execute_spawn(registry, &args, ctx)?,
+            "status" => registry.status(id()?)?.to_string(),
+            "logs" => {
+                let max_bytes = args
+                    .get("tail_bytes")
+                    .and_then(|v| v.as_u64())
+                    .map_or(DEFAULT_LOGS_BYTES, |n| n as usize);
+                let id = id()?;
+                let (stdout, stderr) = registry.logs(id, max_bytes)?;
+                format!(
+                    "{}\n--- stdout ---\n{}\n--- stderr ---\n{}",
+                    registry.status(id)?,
+                    stdout,
+                    stderr
+                )
+            }
+            "kill" => registry.kill(id()?).await?.to_string(),
+            "list" => {
+                let processes = registry.list();
+                if processes.is_empty() {
+                    "No background processes.".to_string()
+                } else {
+                    processes
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+            }
+            other => {
+                return Err(ZeptoError::Tool(format!(
+                    "Unknown process action '{}'",
+                    other
+                )))
+            }
+        };
+        Ok(ToolOutput::llm_only(s))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn ctx(registry: &Arc<ProcessRegistry>) -> ToolContext {
+        ToolContext::new().with_processes(Arc::clone(registry))
+    }
+
+    async fn wait_until_finished(registry: &ProcessRegistry, id: &str) -> ProcessInfo {
+        for _ in 0..100 {
+            let info = registry.status(id).unwrap();
+            if info.status != ProcessStatus::Running {
+                return info;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("process {} did not finish", id);
+    }
+
+    #[tokio::test]
+    async fn test_spawn_reports_exit_code_and_logs() {
+        let registry = ProcessRegistry::default();
+        let id = registry
+            .spawn("echo ready; echo warn >&2; exit 3", None)
+            .unwrap();
+
+        let info = wait_until_finished(&registry, &id).await;
+        assert_eq!(info.status, ProcessStatus::Exited(Some(3)));
+        // Readers may still be draining the pipes right after exit.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let (stdout, stderr) = registry.logs(&id, 1024).unwrap();
+        assert_eq!(stdout, "ready\n");
+        assert_eq!(stderr, "warn\n");
+    }
+
+    /// A command whose background child creates `marker` after a second.
+    fn delayed_touch(marker: &Path) -> String {
+        format!("(sleep 1; touch {}) & wait", marker.display())
+    }
+
+    #[tokio::test]
+    async fn test_kill_terminates_process_and_children() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("marker");
+        let registry = ProcessRegistry::default();
+        let id = registry.spawn(&delayed_touch(&marker), None).unwrap();
+
+        let info = registry.kill(&id).await.unwrap();
+        assert_eq!(info.status, ProcessStatus::Killed);
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!marker.exists(), "background child survived the kill");
+    }
+
+    #[tokio::test]
+    async fn test_running_processes_are_capped() {
+        let registry = ProcessRegistry::new(2, 1024);
+        let first = registry.spawn("sleep 30", None).unwrap();
+        registry.spawn("sleep 30", None).unwrap();
+        let err = registry.spawn("sleep 30", None).unwrap_err();
+        assert!(err.to_string().contains("max 2"));
+
+        registry.kill(&first).await.unwrap();
+        registry.spawn("sleep 30", None).unwrap();
+        registry.shutdown().await;
+        assert!(registry
+            .list()
+            .iter()
+            .all(|p| p.status == ProcessStatus::Killed));
+    }
+
+    #[tokio::test]
+    async fn test_log_tail_keeps_most_recent_output() {
+        let registry = ProcessRegistry::new(1, 16);
+        let id = registry.spawn("seq 1 100", None).unwrap();
+        wait_until_finished(&registry, &id).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let (stdout, _) = registry.logs(&id, 1024).unwrap();
+        assert!(stdout.ends_with("98\n99\n100\n"));
+        assert!(stdout.starts_with("[... 276 earlier bytes omitted ...]"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_actions() {
+        let registry = Arc::new(ProcessRegistry::default());
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        let log = Arc::new(crate::session::CommandLog::memory());
+        let ctx = ctx(&registry)
+            .with_workspace(dir.path().to_str().unwrap())
+            .with_command_log(Arc::clone(&log), "cli:main");
+        let tool = ProcessTool::new();
+
+        let started = tool
+            .execute(
+                json!({"action": "spawn", "command": "pwd; sleep 30", "cwd": "sub"}),
+                &ctx,
+            )
+            .await
+            .unwrap()
+            .for_llm;
+        assert!(started.starts_with("Started proc-1."));
+
+        let listed = tool.execute(json!({"action": "list"}), &ctx).await.unwrap();
+        assert!(listed.for_llm.contains("proc-1"));
+        assert!(listed.for_llm.contains("running"));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let logs = tool
+            .execute(json!({"action": "logs", "id": "proc-1"}), &ctx)
+            .await
+            .unwrap();
+        assert!(logs.for_llm.contains("sub\n"));
+
+        let killed = tool
+            .execute(json!({"action": "kill", "id": "proc-1"}), &ctx)
+            .await
+            .unwrap();
+        assert!(killed.for_llm.contains("killed"));
+        // Killing waits for the reaper, which records the command.
+        let records = log.recent("cli:main", 10).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].tool, "process");
+        assert_eq!(records[0].command, "pwd; sleep 30");
+        assert_eq!(records[0].outcome, CommandOutcome::Killed);
+
+        let err = tool
+            .execute(json!({"action": "status", "id": "proc-9"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ZeptoError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_tool_applies_security_checks() {
+        let registry = Arc::new(ProcessRegistry::default());
+        let tool = ProcessTool::new();
+
+        let err = tool
+            .execute(
+                json!({"action": "spawn", "command": "rm -rf /"}),
+                &ctx(&registry),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ZeptoError::SecurityViolation(_)));
+        assert!(registry.list().is_empty());
+
+        let err = tool
+            .execute(json!({"action": "list"}), &ToolContext::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not available"));
+    }
+
+    #[tokio::test]
+    async fn test_dropping_registry_kills_processes() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("marker");
+        let registry = ProcessRegistry::default();
+        registry.spawn(&delayed_touch(&marker), None).unwrap();
+        drop(registry);
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!marker.exists(), "process outlived its registry");
+    }
+}
@@ -0,0 +1,243 @@
+//! Tool definitions in the shapes provider APIs expect.
+//!
+//! Anthropic wants `{name, description, input_schema}`; OpenAI-compatible
+//! APIs want `{type: "function", function: {name, description, parameters}}`.
+//! Both only accept names of 1–64 characters from `[A-Za-z0-9_-]`, which
+//! MCP and plugin tools do not always have (`github.create issue`). Such
+//! names are renamed to a safe form on the way out, and the returned
+//! [`ToolNameMap`] turns the names in tool calls coming back into the
+//! registered ones.
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::providers::ToolDefinition;
+
+/// Hex digits of the original name's hash kept in a shortened name.
+const NAME_HASH_LEN: usize = 8;
+
+/// A provider API family with its own tool definition shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolApi {
+    /// Anthropic Messages API.
+    Anthropic,
+    /// OpenAI Chat Completions and compatible APIs.
+    OpenAi,
+}
+
+impl ToolApi {
+    /// Longest tool name the API accepts.
+    pub fn max_name_len(self) -> usize {
+        match self {
+            ToolApi::Anthropic | ToolApi::OpenAi => 64,
+        }
+    }
+
+    /// Whether the API accepts `name` as is.
+    pub fn is_valid_name(self, name: &str) -> bool {
+        !name.is_empty()
+            && name.len() <= self.max_name_len()
+            && name
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+    }
+
+    /// `definition` in this API's shape, under `name`.
+    fn tool_json(self, name: &str, definition: &ToolDefinition) -> Value {
+        match self {
+            ToolApi::Anthropic => json!({
+                "name": name,
+                "description": definition.description,
+                "input_schema": definition.parameters,
+            }),
+            ToolApi::OpenAi => json!({
+                "type": "function",
+                "function": {
+                    "name": name,
+                    "description": definition.description,
+                    "parameters": definition.parameters,
+                }
+            }),
+        }
+    }
+}
+
+/// Names sent to a provider, mapped back to the registered tool names.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolNameMap {
+    /// Only renamed tools; everything else went out under its own name.
+    renamed: HashMap<String, String>,
+}
+
+impl ToolNameMap {
+    /// The registered name for a tool call's `name`.
+    pub fn original<'a>(&'a self, sent: &'a str) -> &'a str {
+        self.renamed.get(sent).map(String::as_str).unwrap_or(sent)
+    }
+
+    /// Whether any tool went out under a different name.
+    pub fn is_empty(&self) -> bool {
+        self.renamed.is_empty()
+    }
+
+    /// Number of renamed tools.
+    pub fn len(&self) -> usize {
+        self.renamed.len()
+    }
+}
+
+/// Tool definitions converted for one API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderTools {
+    /// The definitions, sorted by registered name.
+    pub tools: Vec<Value>,
+    /// Reverse map for the names of returned tool calls.
+    pub names: ToolNameMap,
+}
+
+/// `name` with every character the API rejects replaced by `_`, shortened
+/// to fit with a hash of the full name so distinct long names stay distinct.
+fn safe_name(api: ToolApi, name: &str) -> String {
+    let mut safe: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if safe.is_empty() {
+        safe.push_str("tool");
+    }
+    let max = api.max_name_len();
+    if safe.len() > max {
+        let digest = hex::encode(Sha256::digest(name.as_bytes()));
+        safe.truncate(max - NAME_HASH_LEN - 1);
+        safe.push('_');
+        safe.push_str(&digest[..NAME_HASH_LEN]);
+    }
+    safe
+}
+
+/// Convert `definitions` for `api`, renaming invalid names.
+///
+/// Valid names are kept, so a renamed tool never takes the name of one that
+/// was already acceptable; renames that still collide get a `_2`, `_3`, …
+/// suffix.
+pub fn convert(api: ToolApi, mut definitions: Vec<ToolDefinition>) -> ProviderTools {
+    definitions.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut taken: HashSet<String> = definitions
+        .iter()
+        .filter(|d| api.is_valid_name(&d.name))
+        .map(|d| d.name.clone())
+        .collect();
+
+    let mut names = ToolNameMap::default();
+    let tools = definitions
+        .iter()
+        .map(|definition| {
+            if api.is_valid_name(&definition.name) {
+                return api.tool_json(&definition.name, definition);
+            }
+            let base = safe_name(api, &definition.name);
+            let mut sent = base.clone();
+            let mut n = 2;
+            while taken.contains(&sent) {
+                let suffix = format!("_{}", n);
+                let keep = base.len().min(api.max_name_len() - suffix.len());
+                sent = format!("{}{}", &base[..keep], suffix);
+                n += 1;
+            }
+            taken.insert(sent.clone());
+            names.renamed.insert(sent.clone(), definition.name.clone());
+            api.tool_json(&sent, definition)
+        })
+        .collect();
+
+    ProviderTools { tools, names }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn def(name: &str) -> ToolDefinition {
+        ToolDefinition {
+            name: name.to_string(),
+            description: format!("{} tool", name),
+            parameters: json!({"type": "object", "properties": {}}),
+        }
+    }
+
+    fn sent_names(tools: &ProviderTools) -> Vec<&str> {
+        tools
+            .tools
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_names_are_kept() {
+        let out = convert(ToolApi::Anthropic, vec![def("web_fetch"), def("git-log")]);
+        assert_eq!(sent_names(&out), ["git-log", "web_fetch"]);
+        assert!(out.names.is_empty());
+        assert_eq!(out.names.original("web_fetch"), "web_fetch");
+    }
+
+    #[test]
+    fn test_invalid_names_are_renamed_and_mapped_back() {
+        let out = convert(
+            ToolApi::Anthropic,
+            vec![
+                def("github.create issue"),
+                def("github_create_issue"),
+                def(""),
+            ],
+        );
+        assert_eq!(
+            sent_names(&out),
+            ["tool", "github_create_issue_2", "github_create_issue"]
+        );
+        assert_eq!(out.names.len(), 2);
+        assert_eq!(
+            out.names.original("github_create_issue_2"),
+            "github.create issue"
+        );
+        assert_eq!(
+            out.names.original("github_create_issue"),
+            "github_create_issue"
+        );
+        assert_eq!(out.names.original("tool"), "");
+    }
+
+    #[test]
+    fn test_long_names_are_shortened_with_a_hash() {
+        let a = format!("mcp_{}_a", "x".repeat(80));
+        let b = format!("mcp_{}_b", "x".repeat(80));
+        let out = convert(ToolApi::OpenAi, vec![def(&a), def(&b)]);
+        let sent: Vec<&str> = out
+            .tools
+            .iter()
+            .map(|t| t["function"]["name"].as_str().unwrap())
+            .collect();
+        assert!(sent.iter().all(|n| ToolApi::OpenAi.is_valid_name(n)));
+        assert_ne!(sent[0], sent[1]);
+        assert_eq!(out.names.original(sent[0]), a);
+        assert_eq!(out.names.original(sent[1]), b);
+    }
+
+    #[test]
+    fn test_is_valid_name() {
+        assert!(ToolApi::OpenAi.is_valid_name("read_file"));
+        assert!(ToolApi::OpenAi.is_valid_name(&"a".repeat(64)));
+        assert!(!ToolApi::OpenAi.is_valid_name(&"a".repeat(65)));
+        assert!(!ToolApi::Anthropic.is_valid_name("read file"));
+        assert!(!ToolApi::Anthropic.is_valid_name("café"));
+        assert!(!ToolApi::Anthropic.is_valid_name(""));
+    }
+}
@@ -0,0 +1,306 @@
+//! Per-tool rate limits enforced by the [`ToolRegistry`](super::ToolRegistry).
+//!
+//! Limits come from `tools.rate_limits`, e.g. `{"web_search": "10/min"}`.
+//! Each (tool, session) pair gets its own token bucket holding up to the
+//! limit and refilling evenly over the period, so one chat hammering a tool
+//! does not use up another chat's quota.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+
+use crate::error::{Result, ZeptoError};
+
+use super::ToolContext;
+
+/// Slack for float rounding when comparing token counts.
+const TOKEN_EPSILON: f64 = 1e-9;
+
+/// Source of the current time, replaceable in tests.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// The real monotonic clock.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A parsed limit: `calls` per `period`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub calls: u32,
+    pub period: Duration,
+}
+
+impl RateLimit {
+    /// Parse `"<calls>/<unit>"`, where unit is `sec`, `min`, `hour` or `day`
+    /// (`s`, `m`, `h`, `d` and the full words also work).
+    pub fn parse(spec: &str) -> Result<Self> {
+        let invalid = || {
+            ZeptoError::Config(format!(
+                "Invalid rate limit '{}': expected '<calls>/<sec|min|hour|day>', e.g. '10/min'",
+                spec
+            ))
+        };
+        let (calls, unit) = spec.split_once('/').ok_or_else(invalid)?;
+        let calls: u32 = calls.trim().parse().map_err(|_| invalid())?;
+        if calls == 0 {
+            return Err(invalid());
+        }
+        let secs = match unit.trim().to_ascii_lowercase().as_str() {
+            "s" | "sec" | "second" => 1,
+            "m" | "min" | "minute" => 60,
+            "h" | "hour" => 3600,
+            "d" | "day" => 86_400,
+            _ => return Err(invalid()),
+        };
+        Ok(Self {
+            calls,
+            period: Duration::from_secs(secs),
+        })
+    }
+
+    fn unit(&self) -> &'static str {
+        match self.period.as_secs() {
+            1 => "sec",
+            60 => "min",
+            3600 => "hour",
+            _ => "day",
+        }
+    }
+
+    /// Tokens regained over `elapsed` seconds.
+    fn refill(&self, elapsed: f64) -> f64 {
+        elapsed * self.calls as f64 / self.period.as_secs_f64()
+    }
+}
+
+impl std::fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.calls, self.unit())
+    }
+}
+
+/// Remaining quota for one tool in one session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    pub limit: RateLimit,
+    /// Calls that can be made right now.
+    pub remaining: u32,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets for every limited tool, keyed by (tool, session).
+pub struct ToolRateLimiter {
+    limits: HashMap<String, RateLimit>,
+    buckets: Mutex<HashMap<(String, String), Bucket>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl ToolRateLimiter {
+    /// Build a limiter from `tools.rate_limits`. Entries that do not parse
+    /// are skipped with a warning; `zeptoclaw config check` reports them.
+    pub fn from_config(limits: &HashMap<String, String>) -> Self {
+        let limits = limits
+            .iter()
+            .filter_map(|(tool, spec)| match RateLimit::parse(spec) {
+                Ok(limit) => Some((tool.clone(), limit)),
+                Err(e) => {
+                    warn!(tool = %tool, error = %e, "Ignoring tool rate limit");
+                    None
+                }
+            })
+            .collect();
+        Self::new(limits, Arc::new(SystemClock))
+    }
+
+    /// Build a limiter from parsed limits and a clock.
+    pub fn new(limits: HashMap<String, RateLimit>, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            limits,
+            buckets: Mutex::new(HashMap::new()),
+            clock,
+        }
+    }
+
+    /// Whether no tool is limited.
+    pub fn is_empty(&self) -> bool {
+        self.limits.is_empty()
+    }
+
+    /// Session namespace a call is counted against.
+    pub fn namespace(ctx: &ToolContext) -> String {
+        if let Some(key) = &ctx.session_key {
+            return key.clone();
+        }
+        match (&ctx.channel, &ctx.chat_id) {
+            (Some(channel), Some(chat_id)) => format!("{}:{}", channel, chat_id),
+            _ => "default".to_string(),
+        }
+    }
+
+    /// The bucket for (`tool`, `namespace`) refilled up to now.
+    fn refilled<'a>(
+        buckets: &'a mut HashMap<(String, String), Bucket>,
+        tool: &str,
+        namespace: &str,
+        limit: &RateLimit,
+        now: Instant,
+    ) -> &'a mut Bucket {
+        let bucket = buckets
+            .entry((tool.to_string(), namespace.to_string()))
+            .or_insert(Bucket {
+                tokens: limit.calls as f64,
+                updated: now,
+            });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + limit.refill(elapsed)).min(limit.calls as f64);
+        bucket.updated = now;
+        bucket
+    }
+
+    /// Take one call from `tool`'s quota in `namespace`. Fails with a tool
+    /// error naming the limit and when the next call is allowed.
+    pub fn acquire(&self, tool: &str, namespace: &str) -> Result<()> {
+        let Some(limit) = self.limits.get(tool) else {
+            return Ok(());
+        };
+        let now = self.clock.now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = Self::refilled(&mut buckets, tool, namespace, limit, now);
+        if bucket.tokens + TOKEN_EPSILON < 1.0 {
+            let wait = ((1.0 - bucket.tokens) * limit.period.as_secs_f64() / limit.calls as f64)
+                .ceil() as u64;
+            return Err(ZeptoError::Tool(format!(
+                "Rate limit for tool '{}' reached ({}). Next call allowed in {}s.",
+                tool,
+                limit,
+                wait.max(1)
+            )));
+        }
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+
+    /// Remaining quota for `tool` in `namespace`, or `None` if it has no
+    /// limit.
+    pub fn quota(&self, tool: &str, namespace: &str) -> Option<Quota> {
+        let limit = self.limits.get(tool)?;
+        let now = self.clock.now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = Self::refilled(&mut buckets, tool, namespace, limit, now);
+        Some(Quota {
+            limit: *limit,
+            remaining: (bucket.tokens + TOKEN_EPSILON).floor() as u32,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Clock that only moves when told to.
+    struct ManualClock(Mutex<Instant>);
+
+    impl ManualClock {
+        fn advance(&self, by: Duration) {
+            *self.0.lock().unwrap() += by;
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn limiter(tool: &str, spec: &str) -> (ToolRateLimiter, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock(Mutex::new(Instant::now())));
+        let limits = HashMap::from([(tool.to_string(), RateLimit::parse(spec).unwrap())]);
+        (ToolRateLimiter::new(limits, clock.clone()), clock)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            RateLimit::parse("10/min").unwrap(),
+            RateLimit {
+                calls: 10,
+                period: Duration::from_secs(60)
+            }
+        );
+        assert_eq!(
+            RateLimit::parse(" 30 / hour ").unwrap().to_string(),
+            "30/hour"
+        );
+        assert_eq!(RateLimit::parse("5/d").unwrap().to_string(), "5/day");
+        for bad in ["10", "0/min", "x/min", "10/week", ""] {
+            assert!(RateLimit::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_exhausts_and_refills() {
+        let (limiter, clock) = limiter("web_search", "2/min");
+        limiter.acquire("web_search", "s").unwrap();
+        limiter.acquire("web_search", "s").unwrap();
+        let err = limiter.acquire("web_search", "s").unwrap_err();
+        assert!(
+            err.to_string().contains(
+                "Rate limit for tool 'web_search' reached (2/min). Next call allowed in 30s"
+            ),
+            "{}",
+            err
+        );
+
+        clock.advance(Duration::from_secs(29));
+        assert!(limiter.acquire("web_search", "s").is_err());
+        clock.advance(Duration::from_secs(1));
+        limiter.acquire("web_search", "s").unwrap();
+        assert_eq!(limiter.quota("web_search", "s").unwrap().remaining, 0);
+
+        clock.advance(Duration::from_secs(600));
+        assert_eq!(limiter.quota("web_search", "s").unwrap().remaining, 2);
+    }
+
+    #[test]
+    fn test_namespaces_and_tools_are_independent() {
+        let (limiter, _clock) = limiter("shell", "1/hour");
+        limiter.acquire("shell", "telegram:1").unwrap();
+        assert!(limiter.acquire("shell", "telegram:1").is_err());
+        limiter.acquire("shell", "telegram:2").unwrap();
+        limiter.acquire("echo", "telegram:1").unwrap();
+        assert!(limiter.quota("echo", "telegram:1").is_none());
+    }
+
+    #[test]
+    fn test_namespace_from_context() {
+        let ctx = ToolContext::new().with_channel("slack", "C1");
+        assert_eq!(ToolRateLimiter::namespace(&ctx), "slack:C1");
+        assert_eq!(ToolRateLimiter::namespace(&ToolContext::new()), "default");
+    }
+
+    #[test]
+    fn test_from_config_skips_invalid() {
+        let limiter = ToolRateLimiter::from_config(&HashMap::from([
+            ("a".to_string(), "3/min".to_string()),
+            ("b".to_string(), "lots".to_string()),
+        ]));
+        assert_eq!(limiter.quota("a", "s").unwrap().remaining, 3);
+        assert!(limiter.quota("b", "s").is_none());
+    }
+}
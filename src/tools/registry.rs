@@ -4,15 +4,22 @@
 //! Tools can be registered, looked up by name, and executed with context.
 
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
 
 use serde_json::Value;
 use tracing::{error, info};
 
+use crate::config::ArgValidationConfig;
 use crate::error::Result;
 use crate::providers::ToolDefinition;
 
-use super::{Tool, ToolContext, ToolOutput};
+use super::middleware::{ToolMiddleware, ToolNext};
+use super::provider_schema::{self, ProviderTools, ToolApi};
+use super::rate_limit::{Quota, ToolRateLimiter};
+use super::schema::{format_violations, validate_args};
+use super::usage::ToolUsage;
+use super::{DryRun, Tool, ToolContext, ToolOutput};
 
 /// Returns a setup hint for tools that are opt-in (not registered by default).
 fn opt_in_tool_hint(name: &str) -> &'static str {
@@ -47,6 +54,16 @@ fn opt_in_tool_hint(name: &str) -> &'static str {
 /// ```
 pub struct ToolRegistry {
     tools: HashMap<String, Box<dyn Tool>>,
+    /// Schema validation of arguments before `execute`; off unless set with
+    /// [`ToolRegistry::with_arg_validation`].
+    arg_validation: ArgValidationConfig,
+    /// Middlewares wrapped around every execution, outermost first.
+    middlewares: Vec<Arc<dyn ToolMiddleware>>,
+    /// Per-tool call quotas; unlimited unless set with
+    /// [`ToolRegistry::with_rate_limiter`].
+    rate_limiter: Option<ToolRateLimiter>,
+    /// Call counts, errors, durations and output sizes per tool.
+    usage: Arc<ToolUsage>,
 }
 
 impl ToolRegistry {
@@ -62,9 +79,107 @@ impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             tools: HashMap::new(),
+            arg_validation: ArgValidationConfig {
+                enabled: false,
+                coerce: false,
+            },
+            middlewares: Vec::new(),
+            rate_limiter: None,
+            usage: Arc::new(ToolUsage::new()),
         }
     }
 
+    /// Check arguments against each tool's `parameters()` schema before it
+    /// runs. Invalid calls return an error result listing every problem
+    /// instead of reaching the tool.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::config::ArgValidationConfig;
+    /// use zeptoclaw::tools::{ToolRegistry, EchoTool};
+    /// use serde_json::json;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let mut registry = ToolRegistry::new().with_arg_validation(ArgValidationConfig::default());
+    /// registry.register(Box::new(EchoTool));
+    ///
+    /// let result = registry.execute("echo", json!({})).await.unwrap();
+    /// assert!(result.is_error);
+    /// assert!(result.for_llm.contains("message: missing required field"));
+    /// # });
+    /// ```
+    pub fn with_arg_validation(mut self, config: ArgValidationConfig) -> Self {
+        self.arg_validation = config;
+        self
+    }
+
+    /// Enforce per-tool call quotas. A call over its tool's limit returns an
+    /// error result naming the limit and when the next call is allowed,
+    /// without reaching the tool.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::HashMap;
+    /// use zeptoclaw::tools::rate_limit::ToolRateLimiter;
+    /// use zeptoclaw::tools::{ToolRegistry, EchoTool};
+    /// use serde_json::json;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let limits = HashMap::from([("echo".to_string(), "1/min".to_string())]);
+    /// let mut registry =
+    ///     ToolRegistry::new().with_rate_limiter(ToolRateLimiter::from_config(&limits));
+    /// registry.register(Box::new(EchoTool));
+    ///
+    /// assert!(!registry.execute("echo", json!({"message": "a"})).await.unwrap().is_error);
+    /// let result = registry.execute("echo", json!({"message": "b"})).await.unwrap();
+    /// assert!(result.is_error);
+    /// assert!(result.for_llm.contains("1/min"));
+    /// # });
+    /// ```
+    pub fn with_rate_limiter(mut self, limiter: ToolRateLimiter) -> Self {
+        self.rate_limiter = (!limiter.is_empty()).then_some(limiter);
+        self
+    }
+
+    /// Remaining quota for `name` in session `namespace`, or `None` when the
+    /// tool is not rate limited.
+    pub fn quota(&self, name: &str, namespace: &str) -> Option<Quota> {
+        self.rate_limiter.as_ref()?.quota(name, namespace)
+    }
+
+    /// Usage recorded for every call that reached a tool, globally and per
+    /// session.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::tools::{ToolRegistry, ToolContext, EchoTool};
+    /// use serde_json::json;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let mut registry = ToolRegistry::new();
+    /// registry.register(Box::new(EchoTool));
+    ///
+    /// let ctx = ToolContext::new().with_channel("telegram", "123");
+    /// registry.execute_with_context("echo", json!({"message": "hi"}), &ctx).await.unwrap();
+    ///
+    /// assert_eq!(registry.usage().snapshot()["echo"].calls, 1);
+    /// assert_eq!(registry.usage().session("telegram:123")["echo"].calls, 1);
+    /// # });
+    /// ```
+    pub fn usage(&self) -> Arc<ToolUsage> {
+        Arc::clone(&self.usage)
+    }
+
+    /// Wrap every tool execution in `middleware`.
+    ///
+    /// Middlewares run in the order they were added: the first one added is
+    /// the outermost, seeing each call first and its result last. They run
+    /// after argument validation.
+    pub fn add_middleware(&mut self, middleware: Arc<dyn ToolMiddleware>) {
+        info!(middleware = middleware.name(), "Adding tool middleware");
+        self.middlewares.push(middleware);
+    }
+
     /// Register a new tool in the registry.
     ///
     /// If a tool with the same name already exists, it will be replaced.
@@ -180,22 +295,71 @@ impl ToolRegistry {
             }
         };
 
+        let mut args = args;
+        if self.arg_validation.enabled {
+            if let Some(message) = args.get("_parse_error").and_then(Value::as_str) {
+                return Ok(ToolOutput::error(format!(
+                    "Invalid arguments for tool '{}': {}",
+                    name, message
+                )));
+            }
+            let violations =
+                validate_args(&tool.parameters(), &mut args, self.arg_validation.coerce);
+            if !violations.is_empty() {
+                info!(
+                    tool = name,
+                    violations = violations.len(),
+                    "Tool arguments failed schema validation"
+                );
+                return Ok(ToolOutput::error(format_violations(name, &violations)));
+            }
+        }
+
+        if ctx.dry_run {
+            if let DryRun::Describe(effect) = tool.dry_run(&args, ctx).await? {
+                info!(tool = name, "Dry run: tool call described, not executed");
+                return Ok(ToolOutput::llm_only(format!(
+                    "[dry run] {}. Nothing was changed.",
+                    effect
+                )));
+            }
+        }
+
+        if let Some(limiter) = &self.rate_limiter {
+            if let Err(e) = limiter.acquire(name, &ToolRateLimiter::namespace(ctx)) {
+                info!(tool = name, "Tool call rejected by rate limit");
+                return Ok(ToolOutput::error(e.to_string()));
+            }
+        }
+
         let start = Instant::now();
 
-        match tool.execute(args, ctx).await {
+        let next = ToolNext::new(&self.middlewares, tool.as_ref());
+        let result = next.run(args, ctx).await;
+        let duration = start.elapsed();
+        let namespace = ToolRateLimiter::namespace(ctx);
+        match result {
             Ok(output) => {
+                self.usage.record(
+                    name,
+                    &namespace,
+                    duration,
+                    output.is_error,
+                    output.for_llm.len(),
+                );
                 info!(
                     tool = name,
-                    duration_ms = start.elapsed().as_millis() as u64,
+                    duration_ms = duration.as_millis() as u64,
                     "Tool executed successfully"
                 );
                 Ok(output)
             }
             Err(e) => {
+                self.usage.record(name, &namespace, duration, true, 0);
                 error!(
                     tool = name,
                     error = %e,
-                    duration_ms = start.elapsed().as_millis() as u64,
+                    duration_ms = duration.as_millis() as u64,
                     "Tool execution failed"
                 );
                 Err(e)
@@ -233,6 +397,37 @@ impl ToolRegistry {
             .collect()
     }
 
+    /// Tool definitions in the Anthropic Messages API shape
+    /// (`{name, description, input_schema}`), sorted by name.
+    ///
+    /// Names the API rejects are renamed; map the names of returned tool
+    /// calls back with [`ProviderTools::names`].
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::tools::{ToolRegistry, EchoTool};
+    ///
+    /// let mut registry = ToolRegistry::new();
+    /// registry.register(Box::new(EchoTool));
+    ///
+    /// let exported = registry.to_anthropic_tools();
+    /// assert_eq!(exported.tools[0]["name"], "echo");
+    /// assert!(exported.tools[0]["input_schema"].is_object());
+    /// ```
+    pub fn to_anthropic_tools(&self) -> ProviderTools {
+        provider_schema::convert(ToolApi::Anthropic, self.definitions())
+    }
+
+    /// Tool definitions in the OpenAI function-calling shape
+    /// (`{type: "function", function: {name, description, parameters}}`),
+    /// sorted by name.
+    ///
+    /// Names the API rejects are renamed; map the names of returned tool
+    /// calls back with [`ProviderTools::names`].
+    pub fn to_openai_tools(&self) -> ProviderTools {
+        provider_schema::convert(ToolApi::OpenAi, self.definitions())
+    }
+
     /// Get tool definitions, optionally using compact descriptions.
     ///
     /// When `compact` is true, tools that override `compact_description()`
@@ -479,6 +674,137 @@ mod tests {
         assert!(names.contains(&"echo"));
     }
 
+    #[tokio::test]
+    async fn test_registry_arg_validation() {
+        let mut registry = ToolRegistry::new().with_arg_validation(ArgValidationConfig {
+            enabled: true,
+            coerce: false,
+        });
+        registry.register(Box::new(EchoTool));
+
+        let output = registry
+            .execute("echo", json!({"message": 5}))
+            .await
+            .unwrap();
+        assert!(output.is_error);
+        assert!(output
+            .for_llm
+            .starts_with("Invalid arguments for tool 'echo':"));
+        assert!(output.for_llm.contains("message: expected string"));
+
+        let output = registry
+            .execute("echo", json!({"_parse_error": "trailing comma"}))
+            .await
+            .unwrap();
+        assert!(output.is_error);
+        assert!(output.for_llm.contains("trailing comma"));
+
+        let output = registry
+            .execute("echo", json!({"message": "ok"}))
+            .await
+            .unwrap();
+        assert_eq!(output.for_llm, "ok");
+    }
+
+    #[tokio::test]
+    async fn test_registry_arg_validation_coerces() {
+        let mut registry = ToolRegistry::new().with_arg_validation(ArgValidationConfig::default());
+        registry.register(Box::new(EchoTool));
+
+        let output = registry
+            .execute("echo", json!({"message": 5}))
+            .await
+            .unwrap();
+        assert!(!output.is_error);
+        assert_eq!(output.for_llm, "5");
+    }
+
+    #[tokio::test]
+    async fn test_registry_rate_limit_per_session() {
+        let limits = HashMap::from([("echo".to_string(), "1/hour".to_string())]);
+        let mut registry =
+            ToolRegistry::new().with_rate_limiter(ToolRateLimiter::from_config(&limits));
+        registry.register(Box::new(EchoTool));
+        let a = ToolContext::new().with_channel("telegram", "1");
+        let b = ToolContext::new().with_channel("telegram", "2");
+        let args = json!({"message": "hi"});
+
+        assert!(
+            !registry
+                .execute_with_context("echo", args.clone(), &a)
+                .await
+                .unwrap()
+                .is_error
+        );
+        let limited = registry
+            .execute_with_context("echo", args.clone(), &a)
+            .await
+            .unwrap();
+        assert!(limited.is_error);
+        assert!(
+            limited.for_llm.contains("reached (1/hour)"),
+            "{}",
+            limited.for_llm
+        );
+        assert!(
+            !registry
+                .execute_with_context("echo", args, &b)
+                .await
+                .unwrap()
+                .is_error
+        );
+
+        assert_eq!(registry.quota("echo", "telegram:1").unwrap().remaining, 0);
+        assert!(ToolRegistry::new().quota("echo", "telegram:1").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_registry_dry_run_describes_writes_and_runs_reads() {
+        use crate::tools::filesystem::{ReadFileTool, WriteFileTool};
+
+        let dir = tempfile::tempdir().unwrap();
+        let ws = dir.path().canonicalize().unwrap();
+        std::fs::write(ws.join("notes.txt"), "hello").unwrap();
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(ReadFileTool));
+        registry.register(Box::new(WriteFileTool::new()));
+        registry.register(Box::new(EchoTool));
+        let ctx = ToolContext::new()
+            .with_workspace(ws.to_str().unwrap())
+            .with_dry_run(true);
+
+        let out = registry
+            .execute_with_context(
+                "write_file",
+                json!({"path": "out.txt", "content": "x".repeat(1228)}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            out.for_llm,
+            "[dry run] would write 1.2 KB to out.txt (new file). Nothing was changed."
+        );
+        assert!(!ws.join("out.txt").exists());
+
+        let out = registry
+            .execute_with_context("read_file", json!({"path": "notes.txt"}), &ctx)
+            .await
+            .unwrap();
+        assert!(out.for_llm.contains("hello"), "{}", out.for_llm);
+
+        // A state-changing tool without its own description is skipped.
+        let out = registry
+            .execute_with_context("echo", json!({"message": "hi"}), &ctx)
+            .await
+            .unwrap();
+        assert!(
+            out.for_llm.contains("cannot describe its effect"),
+            "{}",
+            out.for_llm
+        );
+    }
+
     #[tokio::test]
     async fn test_tool_not_found() {
         let registry = ToolRegistry::new();
@@ -562,6 +888,66 @@ mod tests {
         assert_eq!(defs[0].description, "Echo message");
     }
 
+    /// A tool registered under a name the provider APIs reject.
+    struct McpSearchTool;
+
+    #[async_trait::async_trait]
+    impl Tool for McpSearchTool {
+        fn name(&self) -> &str {
+            "docs.search pages"
+        }
+
+        fn description(&self) -> &str {
+            "Search the documentation"
+        }
+
+        fn parameters(&self) -> Value {
+            json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" }
+                },
+                "required": ["query"]
+            })
+        }
+
+        async fn execute(&self, _args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
+            Ok(ToolOutput::llm_only("no results"))
+        }
+    }
+
+    fn provider_schema_registry() -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(EchoTool));
+        registry.register(Box::new(McpSearchTool));
+        registry
+    }
+
+    #[test]
+    fn test_to_anthropic_tools_matches_fixture() {
+        let exported = provider_schema_registry().to_anthropic_tools();
+        let expected: Value =
+            serde_json::from_str(include_str!("testdata/anthropic_tools.json")).unwrap();
+        assert_eq!(Value::Array(exported.tools), expected);
+        assert_eq!(
+            exported.names.original("docs_search_pages"),
+            "docs.search pages"
+        );
+        assert_eq!(exported.names.original("echo"), "echo");
+    }
+
+    #[test]
+    fn test_to_openai_tools_matches_fixture() {
+        let exported = provider_schema_registry().to_openai_tools();
+        let expected: Value =
+            serde_json::from_str(include_str!("testdata/openai_tools.json")).unwrap();
+        assert_eq!(Value::Array(exported.tools), expected);
+        assert_eq!(
+            exported.names.original("docs_search_pages"),
+            "docs.search pages"
+        );
+    }
+
     #[test]
     fn test_opt_in_tool_hint_grep() {
         assert!(opt_in_tool_hint("grep").contains("--template coder"));
@@ -18,7 +18,7 @@ use crate::config::Config;
 use crate::cron::{CronPayload, CronSchedule, CronService};
 use crate::error::{Result, ZeptoError};
 
-use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+use super::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
 
 // ---------------------------------------------------------------------------
 // Helpers
@@ -416,6 +416,15 @@ impl Tool for ReminderTool {
         })
     }
 
+    async fn dry_run(&self, args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        let field = |name: &str| args.get(name).and_then(Value::as_str).unwrap_or_default();
+        Ok(match field("action") {
+            "list" | "overdue" => DryRun::Execute,
+            "add" => DryRun::Describe(format!("would add reminder '{}'", field("title"))),
+            action => DryRun::Describe(format!("would {} reminder {}", action, field("id"))),
+        })
+    }
+
     async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
         let action = args
             .get("action")
@@ -0,0 +1,436 @@
+//! Run-python tool — execute a Python script for data analysis.
+//!
+//! The code is written to a temporary file under `<workspace>/.run_python/`
+//! and run with the configured interpreter from the workspace directory.
+//! Each run gets:
+//!
+//! - CPU time and address space limits (`RLIMIT_CPU`, `RLIMIT_AS`) on Unix
+//! - a wall-clock timeout that kills the whole process group
+//! - a scrubbed environment: only a few variables such as `PATH` and `HOME`
+//!   are passed through, so provider API keys never reach the script
+//! - network disabled by convention: `ZEPTOCLAW_NETWORK=disabled` and proxy
+//!   variables pointing at a closed port; `bwrap`/`firejail` sandboxes also
+//!   drop the network namespace
+//!
+//! Output is captured and truncated with the same rules as the shell tool.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tokio::process::Command;
+use tracing::warn;
+
+use crate::config::{PythonSandbox, RunPythonConfig};
+use crate::error::{Result, ZeptoError};
+use crate::runtime::native::ProcessTree;
+use crate::runtime::CommandOutput;
+use crate::security::ensure_directory_chain_secure;
+
+use super::output::truncate_middle;
+use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Workspace directory holding scripts while they run.
+const SCRIPT_DIR: &str = ".run_python";
+
+/// Environment variables passed through to scripts; everything else is
+/// dropped.
+const PASSTHROUGH_ENV: &[&str] = &["PATH", "HOME", "LANG", "LC_ALL", "TZ", "VIRTUAL_ENV"];
+
+/// Proxy a script's HTTP clients at a port nothing listens on.
+const NO_NETWORK_PROXY: &str = "http://127.0.0.1:9";
+
+/// Tool that runs Python code in a resource-limited subprocess.
+///
+/// # Parameters
+/// - `code`: Python source to run (required)
+/// - `timeout`: wall-clock timeout in seconds, capped at the configured
+///   `timeout_secs` (optional)
+pub struct RunPythonTool {
+    config: RunPythonConfig,
+    /// Sandbox actually used, after checking the configured one is on PATH.
+    sandbox: PythonSandbox,
+    max_output_bytes: usize,
+}
+
+impl RunPythonTool {
+    /// Create the tool, capping each of stdout and stderr at
+    /// `max_output_bytes` (normally `tools.shell.max_output_bytes`).
+    pub fn new(config: RunPythonConfig, max_output_bytes: usize) -> Self {
+        let sandbox = resolve_sandbox(config.sandbox);
+        Self {
+            config,
+            sandbox,
+            max_output_bytes,
+        }
+    }
+
+    /// Sandbox scripts run in.
+    pub fn sandbox(&self) -> PythonSandbox {
+        self.sandbox
+    }
+
+    fn build_command(&self, script: &Path, workspace: &Path) -> Command {
+        let interpreter = &self.config.interpreter;
+        let mut cmd = match self.sandbox {
+            PythonSandbox::None => Command::new(interpreter),
+            PythonSandbox::Bwrap => {
+                let mut cmd = Command::new("bwrap");
+                cmd.args(["--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc"])
+                    .args(["--tmpfs", "/tmp", "--bind"])
+                    .arg(workspace)
+                    .arg(workspace)
+                    .args(["--unshare-net", "--unshare-pid", "--die-with-parent"])
+                    .arg("--chdir")
+                    .arg(workspace)
+                    .arg("--")
+                    .arg(interpreter);
+                cmd
+            }
+            PythonSandbox::Firejail => {
+                let mut cmd = Command::new("firejail");
+                cmd.args(["--quiet", "--noprofile", "--net=none", "--"])
+                    .arg(interpreter);
+                cmd
+            }
+        };
+        cmd.arg(script).current_dir(workspace).env_clear();
+        for key in PASSTHROUGH_ENV {
+            if let Ok(value) = std::env::var(key) {
+                cmd.env(key, value);
+            }
+        }
+        for key in [
+            "http_proxy",
+            "https_proxy",
+            "HTTP_PROXY",
+            "HTTPS_PROXY",
+            "ALL_PROXY",
+        ] {
+            cmd.env(key, NO_NETWORK_PROXY);
+        }
+        cmd.env("ZEPTOCLAW_NETWORK", "disabled")
+            .env("PYTHONUNBUFFERED", "1")
+            .env("PYTHONDONTWRITEBYTECODE", "1")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        #[cfg(unix)]
+        {
+            cmd.process_group(0);
+            apply_rlimits(
+                &mut cmd,
+                self.config.cpu_time_secs,
+                self.config.memory_mb.saturating_mul(1024 * 1024),
+            );
+        }
+        cmd
+    }
+
+    /// Write `code` to a fresh script file in the workspace.
+    async fn write_script(&self, code: &str, workspace: &str) -> Result<PathBuf> {
+        let dir = Path::new(workspace).join(SCRIPT_DIR);
+        ensure_directory_chain_secure(&dir, workspace)?;
+        let path = dir.join(format!(
+            "script-{}.py",
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        ));
+        tokio::fs::write(&path, code).await?;
+        Ok(path)
+    }
+}
+
+/// Use `requested` if its binary is on PATH, otherwise no sandbox.
+fn resolve_sandbox(requested: PythonSandbox) -> PythonSandbox {
+    let program = match requested {
+        PythonSandbox::None => return PythonSandbox::None,
+        PythonSandbox::Bwrap => "bwrap",
+        PythonSandbox::Firejail => "firejail",
+    };
+    if on_path(program) {
+        requested
+    } else {
+        warn!(
+            sandbox = program,
+            "run_python sandbox not found on PATH; running scripts without it"
+        );
+        PythonSandbox::None
+    }
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+/// Limit the child's CPU time and address space. A limit of 0 is left unset.
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)] // rlim_t is not u64 on every Unix
+fn apply_rlimits(cmd: &mut Command, cpu_secs: u64, memory_bytes: u64) {
+    let cpu = libc::rlimit {
+        rlim_cur: cpu_secs as libc::rlim_t,
+        rlim_max: cpu_secs as libc::rlim_t,
+    };
+    let memory = libc::rlimit {
+        rlim_cur: memory_bytes as libc::rlim_t,
+        rlim_max: memory_bytes as libc::rlim_t,
+    };
+    // SAFETY: the closure runs in the child between fork and exec and only
+    // calls setrlimit, which is async-signal-safe.
+    unsafe {
+        cmd.pre_exec(move || {
+            if cpu_secs > 0 && libc::setrlimit(libc::RLIMIT_CPU, &cpu) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            if memory_bytes > 0 && libc::setrlimit(libc::RLIMIT_AS, &memory) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Note for a script killed by a signal, e.g. on reaching its CPU limit.
+fn signal_note(status: &std::process::ExitStatus) -> Option<String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        let signal = status.signal()?;
+        Some(if signal == libc::SIGXCPU {
+            format!("[Killed by signal {}: CPU time limit reached]", signal)
+        } else {
+            format!("[Killed by signal {}]", signal)
+        })
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = status;
+        None
+    }
+}
+
+#[async_trait]
+impl Tool for RunPythonTool {
+    fn name(&self) -> &str {
+        "run_python"
+    }
+
+    fn description(&self) -> &str {
+        "Run Python code for calculations and data analysis, from the workspace directory. \
+         Only printed output is returned: print() every result you need. No network access; \
+         CPU time, memory and run time are limited. Write files to the workspace to keep them."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Run Python code"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Shell
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "code": {
+                    "type": "string",
+                    "description": "Python source to run. Print results explicitly."
+                },
+                "timeout": {
+                    "type": "integer",
+                    "description": "Timeout in seconds (default and maximum from config)"
+                }
+            },
+            "required": ["code"]
+        })
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let code = args
+            .get("code")
+            .and_then(Value::as_str)
+            .filter(|c| !c.trim().is_empty())
+            .ok_or_else(|| ZeptoError::Tool("Missing 'code' parameter".into()))?;
+        let timeout_secs = args
+            .get("timeout")
+            .and_then(Value::as_u64)
+            .unwrap_or(self.config.timeout_secs)
+            .clamp(1, self.config.timeout_secs.max(1));
+        let workspace = ctx.workspace.as_deref().ok_or_else(|| {
+            ZeptoError::SecurityViolation(
+                "Workspace not configured; run_python requires a workspace".to_string(),
+            )
+        })?;
+
+        let script = self.write_script(code, workspace).await?;
+        let result = async {
+            let child = self
+                .build_command(&script, Path::new(workspace))
+                .spawn()
+                .map_err(|e| {
+                    ZeptoError::Tool(format!(
+                        "Failed to start '{}': {}",
+                        self.config.interpreter, e
+                    ))
+                })?;
+            let tree = ProcessTree::of(&child);
+            let wait =
+                tokio::time::timeout(Duration::from_secs(timeout_secs), child.wait_with_output());
+            match ctx.cancellable(async { Ok(wait.await) }).await {
+                Ok(Ok(output)) => Ok(output?),
+                Ok(Err(_)) => {
+                    tree.kill();
+                    Err(ZeptoError::Tool(format!(
+                        "Script timed out after {}s",
+                        timeout_secs
+                    )))
+                }
+                // The turn was stopped: take the whole group down, not
+                // just the interpreter that kill_on_drop would reach.
+                Err(e) => {
+                    tree.kill();
+                    Err(e)
+                }
+            }
+        }
+        .await;
+        let _ = tokio::fs::remove_file(&script).await;
+        let output = result?;
+
+        let max_bytes = ctx.max_output_bytes.unwrap_or(self.max_output_bytes);
+        let captured = CommandOutput::new(
+            truncate_middle(&String::from_utf8_lossy(&output.stdout), max_bytes),
+            truncate_middle(&String::from_utf8_lossy(&output.stderr), max_bytes),
+            output.status.code(),
+        );
+        let mut text = captured.format();
+        if let Some(note) = signal_note(&output.status) {
+            text.push('\n');
+            text.push_str(&note);
+        }
+        if text.trim().is_empty() {
+            text = "(no output; use print() to return results)".to_string();
+        }
+        Ok(ToolOutput::user_visible(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn tool() -> Option<RunPythonTool> {
+        on_path("python3").then(|| {
+            RunPythonTool::new(
+                RunPythonConfig {
+                    enabled: true,
+                    timeout_secs: 10,
+                    ..Default::default()
+                },
+                1024,
+            )
+        })
+    }
+
+    fn ctx(dir: &TempDir) -> ToolContext {
+        ToolContext::new().with_workspace(dir.path().to_str().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_runs_code_and_cleans_up() {
+        let Some(tool) = tool() else { return };
+        let dir = TempDir::new().unwrap();
+        let out = tool
+            .execute(json!({"code": "print(sum(range(10)))"}), &ctx(&dir))
+            .await
+            .unwrap();
+        assert_eq!(out.for_llm.trim(), "45");
+        let leftover = std::fs::read_dir(dir.path().join(SCRIPT_DIR))
+            .unwrap()
+            .count();
+        assert_eq!(leftover, 0);
+    }
+
+    #[tokio::test]
+    async fn test_environment_is_scrubbed() {
+        let Some(tool) = tool() else { return };
+        let dir = TempDir::new().unwrap();
+        std::env::set_var("ZEPTOCLAW_RUN_PYTHON_TEST_SECRET", "hunter2");
+        let out = tool
+            .execute(
+                json!({"code": "import os\nprint(os.environ.get('ZEPTOCLAW_RUN_PYTHON_TEST_SECRET'), os.environ['ZEPTOCLAW_NETWORK'])"}),
+                &ctx(&dir),
+            )
+            .await
+            .unwrap();
+        assert_eq!(out.for_llm.trim(), "None disabled");
+    }
+
+    #[tokio::test]
+    async fn test_reports_errors_and_empty_output() {
+        let Some(tool) = tool() else { return };
+        let dir = TempDir::new().unwrap();
+        let out = tool
+            .execute(json!({"code": "raise SystemExit(3)"}), &ctx(&dir))
+            .await
+            .unwrap();
+        assert!(out.for_llm.contains("[Exit code: 3]"), "{}", out.for_llm);
+
+        let out = tool
+            .execute(json!({"code": "x = 1"}), &ctx(&dir))
+            .await
+            .unwrap();
+        assert!(out.for_llm.contains("use print()"));
+    }
+
+    #[tokio::test]
+    async fn test_output_truncated_like_shell() {
+        let Some(tool) = tool() else { return };
+        let dir = TempDir::new().unwrap();
+        let out = tool
+            .execute(json!({"code": "print('x' * 5000)"}), &ctx(&dir))
+            .await
+            .unwrap();
+        assert!(out.for_llm.contains("bytes truncated"), "{}", out.for_llm);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills_script() {
+        let Some(tool) = tool() else { return };
+        let dir = TempDir::new().unwrap();
+        let err = tool
+            .execute(
+                json!({"code": "import time\ntime.sleep(30)", "timeout": 1}),
+                &ctx(&dir),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out after 1s"), "{}", err);
+    }
+
+    #[test]
+    fn test_missing_sandbox_falls_back() {
+        if on_path("bwrap") {
+            return;
+        }
+        assert_eq!(resolve_sandbox(PythonSandbox::Bwrap), PythonSandbox::None);
+        assert_eq!(resolve_sandbox(PythonSandbox::None), PythonSandbox::None);
+    }
+
+    #[tokio::test]
+    async fn test_requires_workspace() {
+        let tool = RunPythonTool::new(RunPythonConfig::default(), 1024);
+        let err = tool
+            .execute(json!({"code": "print(1)"}), &ToolContext::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("requires a workspace"));
+    }
+}
@@ -0,0 +1,528 @@
+//! Tool argument validation against a tool's declared JSON schema.
+//!
+//! Supports the subset of JSON Schema the built-in tools use: `type`
+//! (single or list), `required`, `enum`, nested `properties`, array `items`,
+//! and `additionalProperties` (schema or `false`). Unknown keywords are
+//! ignored rather than rejected, so a richer schema never blocks a call.
+//!
+//! With coercion enabled, near-misses that models commonly emit are fixed in
+//! place instead of reported: numeric strings become numbers, `"true"` and
+//! `"false"` become booleans, and numbers or booleans given for a string
+//! become their text.
+
+use std::fmt;
+
+use serde_json::{Map, Number, Value};
+
+/// One way the arguments fail to match the schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// Where the problem is, e.g. `timeout`, `headers.accept` or `items[2]`.
+    /// Empty for the arguments object itself.
+    pub path: String,
+    /// What is wrong.
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "arguments: {}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Validate `args` against `schema`, coercing near-misses in place when
+/// `coerce` is set. Returns every violation found (empty when valid).
+pub fn validate_args(schema: &Value, args: &mut Value, coerce: bool) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    check(schema, args, "", coerce, &mut violations);
+    violations
+}
+
+/// Render violations as a tool error the model can act on.
+pub fn format_violations(tool: &str, violations: &[SchemaViolation]) -> String {
+    let mut out = format!("Invalid arguments for tool '{}':", tool);
+    for violation in violations {
+        out.push_str("\n- ");
+        out.push_str(&violation.to_string());
+    }
+    out.push_str("\nFix the arguments and call the tool again.");
+    out
+}
+
+fn check(
+    schema: &Value,
+    value: &mut Value,
+    path: &str,
+    coerce: bool,
+    out: &mut Vec<SchemaViolation>,
+) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+
+    let types = declared_types(schema);
+    if !types.is_empty() && !types.iter().any(|t| matches_type(value, t)) {
+        match types
+            .iter()
+            .find_map(|t| coerce.then(|| coerced(value, t)).flatten())
+        {
+            Some(fixed) => *value = fixed,
+            None => {
+                out.push(violation(
+                    path,
+                    format!("expected {}, got {}", types.join(" or "), describe(value)),
+                ));
+                return;
+            }
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let options: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            out.push(violation(
+                path,
+                format!(
+                    "must be one of {}, got {}",
+                    options.join(", "),
+                    describe(value)
+                ),
+            ));
+        }
+    }
+
+    match value {
+        Value::Object(fields) => check_object(schema, fields, path, coerce, out),
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter_mut().enumerate() {
+                    check(item_schema, item, &format!("{}[{}]", path, i), coerce, out);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_object(
+    schema: &Map<String, Value>,
+    fields: &mut Map<String, Value>,
+    path: &str,
+    coerce: bool,
+    out: &mut Vec<SchemaViolation>,
+) {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    for name in &required {
+        if fields.get(*name).is_none_or(Value::is_null) {
+            out.push(violation(
+                &join(path, name),
+                "missing required field".into(),
+            ));
+        }
+    }
+
+    let properties = schema.get("properties").and_then(Value::as_object);
+    let additional = schema.get("additionalProperties");
+    for (name, field) in fields.iter_mut() {
+        let field_path = join(path, name);
+        match properties.and_then(|p| p.get(name)) {
+            // Models often send null for optional fields they mean to omit;
+            // a null required field is already reported as missing.
+            Some(_) if field.is_null() => {}
+            Some(field_schema) => check(field_schema, field, &field_path, coerce, out),
+            None => match additional {
+                Some(Value::Bool(false)) => {
+                    out.push(violation(&field_path, "unknown field".into()));
+                }
+                Some(extra_schema @ Value::Object(_)) => {
+                    check(extra_schema, field, &field_path, coerce, out);
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+fn declared_types(schema: &Map<String, Value>) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        // Unknown type names are not ours to enforce.
+        _ => true,
+    }
+}
+
+/// The value converted to `expected`, if it is an unambiguous near-miss.
+fn coerced(value: &Value, expected: &str) -> Option<Value> {
+    match (expected, value) {
+        ("integer", Value::String(s)) => {
+            let s = s.trim();
+            s.parse::<i64>()
+                .map(Value::from)
+                .ok()
+                .or_else(|| s.parse::<u64>().map(Value::from).ok())
+        }
+        ("number", Value::String(s)) => {
+            let s = s.trim();
+            s.parse::<i64>().map(Value::from).ok().or_else(|| {
+                s.parse::<f64>()
+                    .ok()
+                    .and_then(Number::from_f64)
+                    .map(Value::Number)
+            })
+        }
+        ("boolean", Value::String(s)) => match s.trim().to_ascii_lowercase().as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        ("string", Value::Number(n)) => Some(Value::String(n.to_string())),
+        ("string", Value::Bool(b)) => Some(Value::String(b.to_string())),
+        _ => None,
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Null => "null".into(),
+        Value::Bool(b) => format!("boolean {}", b),
+        Value::Number(n) => format!("number {}", n),
+        Value::String(s) if s.chars().count() > 40 => "a string".into(),
+        Value::String(s) => format!("string {:?}", s),
+        Value::Array(_) => "an array".into(),
+        Value::Object(_) => "an object".into(),
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn violation(path: &str, message: String) -> SchemaViolation {
+    SchemaViolation {
+        path: path.to_string(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::Tool;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "command": { "type": "string" },
+                "timeout": { "type": "integer" },
+                "verbose": { "type": "boolean" },
+                "mode": { "type": "string", "enum": ["fast", "slow"] },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "headers": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" }
+                }
+            },
+            "required": ["command"]
+        })
+    }
+
+    /// Violation paths, sorted so tests do not depend on map ordering.
+    fn paths(violations: &[SchemaViolation]) -> Vec<&str> {
+        let mut paths: Vec<&str> = violations.iter().map(|v| v.path.as_str()).collect();
+        paths.sort_unstable();
+        paths
+    }
+
+    #[test]
+    fn test_valid_args_pass_unchanged() {
+        let mut args = json!({
+            "command": "ls",
+            "timeout": 30,
+            "verbose": true,
+            "mode": "fast",
+            "tags": ["a"],
+            "headers": {"accept": "text/plain"},
+            "undeclared": 1
+        });
+        let before = args.clone();
+        assert!(validate_args(&schema(), &mut args, false).is_empty());
+        assert_eq!(args, before);
+    }
+
+    #[test]
+    fn test_reports_every_violation() {
+        let mut args = json!({
+            "timeout": "soon",
+            "verbose": "yes",
+            "mode": "medium",
+            "tags": ["a", 2, {}],
+            "headers": {"accept": ["x"]}
+        });
+        let violations = validate_args(&schema(), &mut args, true);
+        assert_eq!(
+            paths(&violations),
+            vec![
+                "command",
+                "headers.accept",
+                "mode",
+                "tags[2]",
+                "timeout",
+                "verbose"
+            ]
+        );
+        let text = format_violations("shell", &violations);
+        assert!(text
+            .starts_with("Invalid arguments for tool 'shell':\n- command: missing required field"));
+        assert!(text.contains("- timeout: expected integer, got string \"soon\""));
+        assert!(text.contains("- mode: must be one of \"fast\", \"slow\", got string \"medium\""));
+        assert!(text.ends_with("Fix the arguments and call the tool again."));
+    }
+
+    #[test]
+    fn test_coercion_fixes_near_misses_in_place() {
+        let mut args = json!({
+            "command": 42,
+            "timeout": " 30 ",
+            "verbose": "FALSE",
+            "tags": [true]
+        });
+        assert!(validate_args(&schema(), &mut args, true).is_empty());
+        assert_eq!(
+            args,
+            json!({"command": "42", "timeout": 30, "verbose": false, "tags": ["true"]})
+        );
+
+        let mut args = json!({"command": "ls", "timeout": "30"});
+        let violations = validate_args(&schema(), &mut args, false);
+        assert_eq!(paths(&violations), vec!["timeout"]);
+        assert_eq!(args["timeout"], "30");
+    }
+
+    #[test]
+    fn test_null_optional_fields_are_ignored() {
+        let mut args = json!({"command": "ls", "timeout": null});
+        assert!(validate_args(&schema(), &mut args, false).is_empty());
+        let mut args = json!({"command": null});
+        assert_eq!(
+            paths(&validate_args(&schema(), &mut args, false)),
+            vec!["command"]
+        );
+    }
+
+    #[test]
+    fn test_integer_accepts_whole_floats_and_type_lists() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "n": { "type": "integer" },
+                "id": { "type": ["string", "integer"] }
+            },
+            "additionalProperties": false
+        });
+        let mut args = json!({"n": 5.0, "id": 7});
+        assert!(validate_args(&schema, &mut args, false).is_empty());
+        let mut args = json!({"n": 5.5, "id": [], "extra": 1});
+        assert_eq!(
+            paths(&validate_args(&schema, &mut args, false)),
+            vec!["extra", "id", "n"]
+        );
+        let mut args = json!("not an object");
+        let violations = validate_args(&schema, &mut args, true);
+        assert_eq!(
+            violations[0].to_string(),
+            "arguments: expected object, got string \"not an object\""
+        );
+    }
+
+    // ------------------------------------------------------------------
+    // Property tests over the built-in tools' schemas
+    // ------------------------------------------------------------------
+
+    fn builtin_tools() -> Vec<Box<dyn Tool>> {
+        use crate::tools::filesystem::{EditFileTool, ListDirTool, ReadFileTool, WriteFileTool};
+        vec![
+            Box::new(crate::tools::EchoTool),
+            Box::new(ReadFileTool),
+            Box::new(WriteFileTool::new()),
+            Box::new(ListDirTool::new()),
+            Box::new(EditFileTool::new()),
+            Box::new(crate::tools::shell::ShellTool::new()),
+            Box::new(crate::tools::grep::GrepTool::new()),
+            Box::new(crate::tools::find::FindTool),
+            Box::new(crate::tools::WebFetchTool::new()),
+            Box::new(crate::tools::HttpFetchTool::new(Default::default())),
+            Box::new(crate::tools::process::ProcessTool::new()),
+            Box::new(crate::tools::git::GitTool::new()),
+        ]
+    }
+
+    /// A value that satisfies `schema`, built from its first type or enum
+    /// option. `variant` varies the chosen values between runs.
+    fn sample(schema: &Value, variant: u64, all_fields: bool) -> Value {
+        if let Some(first) = schema
+            .get("enum")
+            .and_then(Value::as_array)
+            .and_then(|options| options.get(variant as usize % options.len().max(1)))
+        {
+            return first.clone();
+        }
+        let ty = schema
+            .get("type")
+            .and_then(|t| t.as_str().or_else(|| t.get(0).and_then(Value::as_str)))
+            .unwrap_or("string");
+        match ty {
+            "integer" => json!(1 + variant % 7),
+            "number" => json!(0.5 + variant as f64),
+            "boolean" => json!(variant.is_multiple_of(2)),
+            "array" => {
+                let item = schema.get("items").cloned().unwrap_or(json!({}));
+                json!([sample(&item, variant, all_fields)])
+            }
+            "object" => {
+                let mut fields = Map::new();
+                let required: Vec<&str> = schema
+                    .get("required")
+                    .and_then(Value::as_array)
+                    .map(|r| r.iter().filter_map(Value::as_str).collect())
+                    .unwrap_or_default();
+                if let Some(props) = schema.get("properties").and_then(Value::as_object) {
+                    for (name, prop) in props {
+                        if all_fields || required.contains(&name.as_str()) {
+                            fields.insert(name.clone(), sample(prop, variant, all_fields));
+                        }
+                    }
+                }
+                Value::Object(fields)
+            }
+            _ => json!(format!("value-{}", variant)),
+        }
+    }
+
+    /// A value of the wrong JSON type for `schema` that coercion cannot fix.
+    fn wrong_value(schema: &Value) -> Value {
+        match schema.get("type").and_then(Value::as_str) {
+            Some("object") | Some("array") => json!("not-a-container"),
+            _ => json!({"unexpected": "object"}),
+        }
+    }
+
+    #[test]
+    fn test_generated_valid_args_pass_for_every_builtin_schema() {
+        for tool in builtin_tools() {
+            let schema = tool.parameters();
+            for variant in 0..8 {
+                for all_fields in [false, true] {
+                    let mut args = sample(&schema, variant, all_fields);
+                    let violations = validate_args(&schema, &mut args, false);
+                    assert!(
+                        violations.is_empty(),
+                        "{} rejected generated args {}: {:?}",
+                        tool.name(),
+                        args,
+                        violations
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_each_broken_field_is_reported_for_every_builtin_schema() {
+        for tool in builtin_tools() {
+            let schema = tool.parameters();
+            let Some(props) = schema.get("properties").and_then(Value::as_object) else {
+                continue;
+            };
+            let required: Vec<String> = schema
+                .get("required")
+                .and_then(Value::as_array)
+                .map(|r| {
+                    r.iter()
+                        .filter_map(|v| v.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default();
+            for (name, prop) in props {
+                if prop.get("type").is_none() && prop.get("enum").is_none() {
+                    continue;
+                }
+                // Wrong type: reported at exactly that field, even with coercion.
+                let mut args = sample(&schema, 1, true);
+                args[name] = wrong_value(prop);
+                let violations = validate_args(&schema, &mut args, true);
+                assert_eq!(
+                    paths(&violations),
+                    vec![name.as_str()],
+                    "{}.{}",
+                    tool.name(),
+                    name
+                );
+
+                // Missing: reported only for required fields.
+                let mut args = sample(&schema, 1, true);
+                args.as_object_mut().unwrap().remove(name);
+                let violations = validate_args(&schema, &mut args, true);
+                let expected: Vec<&str> = if required.contains(name) {
+                    vec![name.as_str()]
+                } else {
+                    vec![]
+                };
+                assert_eq!(paths(&violations), expected, "{}.{}", tool.name(), name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_coerced_scalars_validate_for_every_builtin_schema() {
+        for tool in builtin_tools() {
+            let schema = tool.parameters();
+            for variant in 0..4 {
+                let mut args = sample(&schema, variant, true);
+                let expected = args.clone();
+                // Stringify every top-level number and boolean, as models do.
+                for field in args.as_object_mut().unwrap().values_mut() {
+                    if field.is_number() || field.is_boolean() {
+                        *field = Value::String(field.to_string());
+                    }
+                }
+                assert!(
+                    validate_args(&schema, &mut args, true).is_empty(),
+                    "{}: {}",
+                    tool.name(),
+                    args
+                );
+                assert_eq!(args, expected, "{}", tool.name());
+            }
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! Scratchpad tool — durable key-value memory shared across sessions.
+//!
+//! Backed by [`ScratchpadStore`]. Keys are scoped to the channel the tool is
+//! called from, so all chats on one channel share a scratchpad.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::error::{Result, ZeptoError};
+use crate::memory::scratchpad::ScratchpadStore;
+
+use super::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Namespace used when the tool runs outside a channel conversation.
+const DEFAULT_NAMESPACE: &str = "default";
+
+/// Values longer than this are shortened in `list` output.
+const LIST_VALUE_CHARS: usize = 200;
+
+/// Tool for setting, getting, listing, and deleting scratchpad entries.
+pub struct ScratchpadTool {
+    store: Arc<ScratchpadStore>,
+}
+
+impl ScratchpadTool {
+    /// Create a new scratchpad tool over `store`.
+    pub fn new(store: Arc<ScratchpadStore>) -> Self {
+        Self { store }
+    }
+
+    fn namespace(ctx: &ToolContext) -> &str {
+        ctx.channel
+            .as_deref()
+            .filter(|c| !c.is_empty())
+            .unwrap_or(DEFAULT_NAMESPACE)
+    }
+
+    fn key(args: &Value, operation: &str) -> Result<String> {
+        args.get("key")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .map(str::to_string)
+            .ok_or_else(|| ZeptoError::Tool(format!("Missing 'key' for {}", operation)))
+    }
+}
+
+#[async_trait]
+impl Tool for ScratchpadTool {
+    fn name(&self) -> &str {
+        "scratchpad"
+    }
+
+    fn description(&self) -> &str {
+        "Durable key-value scratchpad shared by all conversations on this channel. Use it for \
+         working data that must outlive this session (progress markers, IDs, lists). \
+         Operations: set (key, value), get (key), list (optional prefix), delete (key). \
+         Use ':'-separated keys like 'project:status' so related entries can be listed by prefix."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Shared key-value scratchpad"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Memory
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["set", "get", "list", "delete"],
+                    "description": "set stores a value, get reads one, list shows keys with a prefix, delete removes a key"
+                },
+                "key": {
+                    "type": "string",
+                    "description": "Entry key. Required for set, get and delete."
+                },
+                "value": {
+                    "type": "string",
+                    "description": "Value to store. Required for set."
+                },
+                "prefix": {
+                    "type": "string",
+                    "description": "For list: only keys starting with this prefix (default: all keys)"
+                }
+            },
+            "required": ["operation"]
+        })
+    }
+
+    async fn dry_run(&self, args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        let key = args.get("key").and_then(Value::as_str).unwrap_or_default();
+        Ok(
+            match args
+                .get("operation")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+            {
+                "get" | "list" => DryRun::Execute,
+                operation => {
+                    DryRun::Describe(format!("would {} scratchpad key '{}'", operation, key))
+                }
+            },
+        )
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let operation = args
+            .get("operation")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ZeptoError::Tool("Missing 'operation' argument".into()))?;
+        let namespace = Self::namespace(ctx);
+
+        let output = match operation {
+            "set" => {
+                let key = Self::key(&args, "set")?;
+                let value = args
+                    .get("value")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| ZeptoError::Tool("Missing 'value' for set".into()))?;
+                if self.store.set(namespace, &key, value).await? {
+                    format!("Updated '{}'", key)
+                } else {
+                    format!("Stored '{}'", key)
+                }
+            }
+            "get" => {
+                let key = Self::key(&args, "get")?;
+                self.store
+                    .get(namespace, &key)
+                    .await
+                    .unwrap_or_else(|| format!("No value for '{}'", key))
+            }
+            "list" => {
+                let prefix = args.get("prefix").and_then(Value::as_str).unwrap_or("");
+                let entries = self.store.list(namespace, prefix).await;
+                if entries.is_empty() {
+                    "No matching keys".to_string()
+                } else {
+                    let mut lines = vec![format!(
+                        "{} keys ({} of {} bytes used):",
+                        entries.len(),
+                        self.store.usage(namespace).await,
+                        self.store.max_bytes()
+                    )];
+                    for (key, value) in entries {
+                        let mut shown: String = value.chars().take(LIST_VALUE_CHARS).collect();
+                        if shown.len() < value.len() {
+                            shown.push('…');
+                        }
+                        lines.push(format!("- {}: {}", key, shown));
+                    }
+                    lines.join("\n")
+                }
+            }
+            "delete" => {
+                let key = Self::key(&args, "delete")?;
+                if self.store.delete(namespace, &key).await? {
+                    format!("Deleted '{}'", key)
+                } else {
+                    format!("No value for '{}'", key)
+                }
+            }
+            other => {
+                return Err(ZeptoError::Tool(format!(
+                    "Unknown scratchpad operation '{}'. Valid operations: set, get, list, delete",
+                    other
+                )))
+            }
+        };
+        Ok(ToolOutput::llm_only(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool() -> ScratchpadTool {
+        ScratchpadTool::new(Arc::new(ScratchpadStore::new_memory(1024)))
+    }
+
+    async fn run(tool: &ScratchpadTool, args: Value, ctx: &ToolContext) -> Result<String> {
+        tool.execute(args, ctx).await.map(|o| o.for_llm)
+    }
+
+    #[tokio::test]
+    async fn test_set_get_list_delete() {
+        let tool = tool();
+        let ctx = ToolContext::new().with_channel("cli", "cli");
+
+        let out = run(
+            &tool,
+            json!({"operation": "set", "key": "a:1", "value": "x"}),
+            &ctx,
+        )
+        .await
+        .unwrap();
+        assert_eq!(out, "Stored 'a:1'");
+        let out = run(&tool, json!({"operation": "get", "key": "a:1"}), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(out, "x");
+        let out = run(&tool, json!({"operation": "list", "prefix": "a:"}), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(out, "1 keys (4 of 1024 bytes used):\n- a:1: x");
+        let out = run(&tool, json!({"operation": "delete", "key": "a:1"}), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(out, "Deleted 'a:1'");
+    }
+
+    #[tokio::test]
+    async fn test_chats_on_a_channel_share_a_namespace() {
+        let tool = tool();
+        let chat_a = ToolContext::new().with_channel("telegram", "1");
+        let chat_b = ToolContext::new().with_channel("telegram", "2");
+        let cli = ToolContext::new().with_channel("cli", "cli");
+
+        run(
+            &tool,
+            json!({"operation": "set", "key": "k", "value": "shared"}),
+            &chat_a,
+        )
+        .await
+        .unwrap();
+        let out = run(&tool, json!({"operation": "get", "key": "k"}), &chat_b)
+            .await
+            .unwrap();
+        assert_eq!(out, "shared");
+        let out = run(&tool, json!({"operation": "get", "key": "k"}), &cli)
+            .await
+            .unwrap();
+        assert_eq!(out, "No value for 'k'");
+    }
+
+    #[tokio::test]
+    async fn test_missing_arguments() {
+        let tool = tool();
+        let ctx = ToolContext::new();
+        let err = run(&tool, json!({"operation": "set", "key": "k"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Missing 'value'"));
+        let err = run(&tool, json!({"operation": "get"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Missing 'key'"));
+        let err = run(&tool, json!({"operation": "clear"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Unknown scratchpad operation"));
+    }
+}
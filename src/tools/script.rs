@@ -0,0 +1,621 @@
+//! Config-defined tools that run a fixed command (`tools.custom`).
+//!
+//! Each entry's `command` is split into arguments once, when the tool is
+//! built; `{param}` placeholders are filled in per call *inside* their
+//! argument, so a value always stays a single argument no matter what
+//! spaces, quotes or shell operators it contains. `env` values may use the
+//! same placeholders. The arguments are then quoted one by one and run
+//! through the container runtime like the `shell` tool, with its timeout and
+//! output cap.
+//!
+//! ```json
+//! {
+//!   "tools": {
+//!     "custom": [{
+//!       "name": "deploy",
+//!       "description": "Deploy a branch to an environment",
+//!       "parameters": {
+//!         "type": "object",
+//!         "properties": {
+//!           "env": { "type": "string", "enum": ["staging", "prod"] },
+//!           "branch": { "type": "string" }
+//!         },
+//!         "required": ["env"]
+//!       },
+//!       "command": "./scripts/deploy.sh --env {env} --branch={branch}",
+//!       "env": { "DEPLOY_ENV": "{env}" },
+//!       "timeout_secs": 600
+//!     }]
+//!   }
+//! }
+//! ```
+//!
+//! An argument whose placeholder has no value (an optional parameter the
+//! model left out) is dropped, so `--branch={branch}` disappears entirely.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tracing::debug;
+
+use crate::config::{ScriptToolDef, ShellKind};
+use crate::error::{Result, ZeptoError};
+use crate::runtime::{ContainerConfig, ContainerRuntime, RuntimeError};
+use crate::security::ShellSecurityConfig;
+
+use super::output::truncate_middle;
+use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Timeout when an entry does not set `timeout_secs`, matching `shell`.
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Characters that would make the shell do something other than run one
+/// program. Rejected outside quotes so a template never silently changes
+/// meaning once its arguments are quoted.
+const SHELL_OPERATORS: &[char] = &['|', ';', '&', '<', '>', '$', '`', '(', ')'];
+
+/// One piece of a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Param(String),
+}
+
+/// A parsed template: a command argument or an `env` value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Template(Vec<Part>);
+
+impl Template {
+    /// Fill in placeholders from `args`. `None` if any placeholder has no
+    /// value.
+    fn render(&self, args: &Value) -> Option<String> {
+        let mut out = String::new();
+        for part in &self.0 {
+            match part {
+                Part::Literal(text) => out.push_str(text),
+                Part::Param(name) => match args.get(name) {
+                    None | Some(Value::Null) => return None,
+                    Some(Value::String(s)) => out.push_str(s),
+                    Some(other) => out.push_str(&other.to_string()),
+                },
+            }
+        }
+        Some(out)
+    }
+
+    fn params(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().filter_map(|part| match part {
+            Part::Param(name) => Some(name.as_str()),
+            Part::Literal(_) => None,
+        })
+    }
+}
+
+/// Appends template text, tracking the literal being built.
+#[derive(Default)]
+struct PartsBuilder {
+    parts: Vec<Part>,
+    literal: String,
+    /// Whether anything (even an empty quoted string) was added.
+    touched: bool,
+}
+
+impl PartsBuilder {
+    fn push_char(&mut self, c: char) {
+        self.literal.push(c);
+        self.touched = true;
+    }
+
+    fn push_param(&mut self, name: String) {
+        if !self.literal.is_empty() {
+            self.parts
+                .push(Part::Literal(std::mem::take(&mut self.literal)));
+        }
+        self.parts.push(Part::Param(name));
+        self.touched = true;
+    }
+
+    fn finish(mut self) -> Option<Template> {
+        if !self.touched {
+            return None;
+        }
+        if !self.literal.is_empty() || self.parts.is_empty() {
+            self.parts.push(Part::Literal(self.literal));
+        }
+        Some(Template(self.parts))
+    }
+}
+
+/// Read a placeholder after its opening `{` (already consumed). `{{` is a
+/// literal brace.
+fn read_brace(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    builder: &mut PartsBuilder,
+) -> std::result::Result<(), String> {
+    if chars.peek() == Some(&'{') {
+        chars.next();
+        builder.push_char('{');
+        return Ok(());
+    }
+    let mut name = String::new();
+    loop {
+        match chars.next() {
+            Some('}') => break,
+            Some(c) if c.is_ascii_alphanumeric() || c == '_' => name.push(c),
+            Some(c) => {
+                return Err(format!(
+                    "invalid character '{}' in placeholder {{{}",
+                    c, name
+                ))
+            }
+            None => return Err(format!("unclosed placeholder {{{}", name)),
+        }
+    }
+    if name.is_empty() {
+        return Err("empty placeholder {}".into());
+    }
+    builder.push_param(name);
+    Ok(())
+}
+
+/// Handle a `}` outside a placeholder: `}}` is a literal brace.
+fn read_closing_brace(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    builder: &mut PartsBuilder,
+) -> std::result::Result<(), String> {
+    if chars.next_if_eq(&'}').is_some() {
+        builder.push_char('}');
+        Ok(())
+    } else {
+        Err("unmatched '}' (write '}}' for a literal brace)".into())
+    }
+}
+
+/// Split a command template into arguments, honouring single quotes, double
+/// quotes and backslash escapes the way a shell would.
+fn parse_command(command: &str) -> std::result::Result<Vec<Template>, String> {
+    let mut words = Vec::new();
+    let mut word = PartsBuilder::default();
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if let Some(done) = std::mem::take(&mut word).finish() {
+                    words.push(done);
+                }
+            }
+            '\'' => {
+                word.touched = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('{') => read_brace(&mut chars, &mut word)?,
+                        Some('}') => read_closing_brace(&mut chars, &mut word)?,
+                        Some(c) => word.push_char(c),
+                        None => return Err("unclosed single quote".into()),
+                    }
+                }
+            }
+            '"' => {
+                word.touched = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) => word.push_char(c),
+                            None => return Err("unclosed double quote".into()),
+                        },
+                        Some('{') => read_brace(&mut chars, &mut word)?,
+                        Some('}') => read_closing_brace(&mut chars, &mut word)?,
+                        Some(c) => word.push_char(c),
+                        None => return Err("unclosed double quote".into()),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) => word.push_char(c),
+                None => return Err("trailing backslash".into()),
+            },
+            '{' => read_brace(&mut chars, &mut word)?,
+            '}' => read_closing_brace(&mut chars, &mut word)?,
+            c if SHELL_OPERATORS.contains(&c) => {
+                return Err(format!(
+                    "shell operator '{}' is not supported; custom tools run a single \
+                     program (quote it to pass it literally, or move pipelines into a script)",
+                    c
+                ))
+            }
+            c => word.push_char(c),
+        }
+    }
+    if let Some(done) = word.finish() {
+        words.push(done);
+    }
+    Ok(words)
+}
+
+/// Parse an `env` value: placeholders only, no quoting.
+fn parse_value(value: &str) -> std::result::Result<Template, String> {
+    let mut builder = PartsBuilder {
+        touched: true,
+        ..Default::default()
+    };
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => read_brace(&mut chars, &mut builder)?,
+            '}' => read_closing_brace(&mut chars, &mut builder)?,
+            c => builder.push_char(c),
+        }
+    }
+    Ok(builder.finish().unwrap_or(Template(Vec::new())))
+}
+
+/// Quote one argument for `sh -c`, leaving plain words readable.
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./=:,+@%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// A `tools.custom` entry compiled into a tool.
+pub struct ScriptTool {
+    name: String,
+    description: String,
+    parameters: Value,
+    argv: Vec<Template>,
+    env: Vec<(String, Template)>,
+    working_dir: Option<String>,
+    timeout_secs: u64,
+    runtime: Arc<dyn ContainerRuntime>,
+    security: ShellSecurityConfig,
+    max_output_bytes: usize,
+}
+
+impl ScriptTool {
+    /// Compile a definition.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ZeptoError::Config` naming the tool when its name, schema or
+    /// templates are invalid, or a placeholder is not a declared parameter.
+    pub fn from_def(
+        def: &ScriptToolDef,
+        runtime: Arc<dyn ContainerRuntime>,
+        security: ShellSecurityConfig,
+        max_output_bytes: usize,
+    ) -> Result<Self> {
+        let invalid =
+            |msg: String| ZeptoError::Config(format!("tools.custom tool '{}': {}", def.name, msg));
+
+        let valid_name = def
+            .name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic())
+            && def
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_name {
+            return Err(invalid(
+                "name must start with a letter and contain only letters, digits and underscores"
+                    .into(),
+            ));
+        }
+
+        let parameters = match &def.parameters {
+            None => json!({"type": "object", "properties": {}}),
+            Some(schema) if schema.get("type").and_then(Value::as_str) == Some("object") => {
+                schema.clone()
+            }
+            Some(_) => {
+                return Err(invalid(
+                    "parameters must be a JSON schema with \"type\": \"object\"".into(),
+                ))
+            }
+        };
+        let declared: HashSet<&str> = parameters
+            .get("properties")
+            .and_then(Value::as_object)
+            .map(|props| props.keys().map(String::as_str).collect())
+            .unwrap_or_default();
+
+        let argv = parse_command(&def.command).map_err(|e| invalid(format!("command: {}", e)))?;
+        match argv.first() {
+            None => return Err(invalid("command must not be empty".into())),
+            Some(program) if program.params().next().is_some() => {
+                return Err(invalid(
+                    "the program (first word of command) cannot be a placeholder".into(),
+                ))
+            }
+            Some(_) => {}
+        }
+
+        let mut env = Vec::with_capacity(def.env.len());
+        for (key, value) in &def.env {
+            let template =
+                parse_value(value).map_err(|e| invalid(format!("env {}: {}", key, e)))?;
+            env.push((key.clone(), template));
+        }
+        env.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let used = argv.iter().chain(env.iter().map(|(_, t)| t));
+        for name in used.flat_map(Template::params) {
+            if !declared.contains(name) {
+                return Err(invalid(format!(
+                    "placeholder {{{}}} is not declared in parameters.properties",
+                    name
+                )));
+            }
+        }
+
+        Ok(Self {
+            name: def.name.clone(),
+            description: def.description.clone(),
+            parameters,
+            argv,
+            env,
+            working_dir: def.working_dir.clone(),
+            timeout_secs: def.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).max(1),
+            runtime,
+            security,
+            max_output_bytes,
+        })
+    }
+
+    /// Check a definition without building a runnable tool, for
+    /// `config check`-style validation.
+    pub fn check_def(def: &ScriptToolDef) -> Result<()> {
+        Self::from_def(
+            def,
+            Arc::new(crate::runtime::NativeRuntime::new()),
+            ShellSecurityConfig::default(),
+            0,
+        )
+        .map(|_| ())
+    }
+
+    /// The command line for one call: arguments whose placeholders have no
+    /// value are dropped, the rest quoted individually.
+    fn command_line(&self, args: &Value) -> String {
+        self.argv
+            .iter()
+            .filter_map(|word| word.render(args))
+            .map(|arg| shell_quote(&arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[async_trait]
+impl Tool for ScriptTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn compact_description(&self) -> &str {
+        self.description()
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Shell
+    }
+
+    fn parameters(&self) -> Value {
+        self.parameters.clone()
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let command = self.command_line(&args);
+        self.security.validate_command(&command)?;
+        debug!(tool = %self.name, command = %command, "Running custom script tool");
+
+        let mut config = ContainerConfig::new()
+            .with_timeout(self.timeout_secs)
+            .with_shell(ShellKind::Sh);
+        config.env = self
+            .env
+            .iter()
+            .filter_map(|(key, value)| value.render(&args).map(|v| (key.clone(), v)))
+            .collect();
+        if let Some(ref workspace) = ctx.workspace {
+            let workspace_path = PathBuf::from(workspace);
+            config = config.with_workdir(workspace_path.clone()).with_mount(
+                workspace_path.clone(),
+                workspace_path,
+                false,
+            );
+        }
+        if let Some(ref dir) = self.working_dir {
+            let dir = PathBuf::from(dir);
+            config.workdir = Some(match &ctx.workspace {
+                Some(workspace) if dir.is_relative() => PathBuf::from(workspace).join(dir),
+                _ => dir,
+            });
+        }
+
+        let mut output = self
+            .runtime
+            .execute(&command, &config)
+            .await
+            .map_err(|e| match e {
+                RuntimeError::Timeout(_) => ZeptoError::Tool(format!(
+                    "Tool '{}' timed out after {}s",
+                    self.name, self.timeout_secs
+                )),
+                other => ZeptoError::Tool(other.to_string()),
+            })?;
+
+        let max_bytes = ctx.max_output_bytes.unwrap_or(self.max_output_bytes);
+        output.stdout = truncate_middle(&output.stdout, max_bytes);
+        output.stderr = truncate_middle(&output.stderr, max_bytes);
+        Ok(ToolOutput::user_visible(output.format()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::NativeRuntime;
+    use std::collections::HashMap;
+
+    fn def(command: &str) -> ScriptToolDef {
+        ScriptToolDef {
+            name: "deploy".into(),
+            description: "Deploy".into(),
+            parameters: Some(json!({
+                "type": "object",
+                "properties": {
+                    "env": {"type": "string"},
+                    "branch": {"type": "string"},
+                    "count": {"type": "integer"}
+                },
+                "required": ["env"]
+            })),
+            command: command.into(),
+            env: HashMap::new(),
+            working_dir: None,
+            timeout_secs: None,
+        }
+    }
+
+    fn build(def: &ScriptToolDef) -> Result<ScriptTool> {
+        ScriptTool::from_def(
+            def,
+            Arc::new(NativeRuntime::new()),
+            ShellSecurityConfig::default(),
+            10_000,
+        )
+    }
+
+    fn words(command: &str) -> Vec<Template> {
+        parse_command(command).unwrap()
+    }
+
+    fn lit(text: &str) -> Part {
+        Part::Literal(text.into())
+    }
+
+    #[test]
+    fn test_parse_command_words_quotes_and_placeholders() {
+        assert_eq!(
+            words(r#"deploy.sh --env {env} "two words" 'it''s' --x={count}s a\ b '' {{lit}}"#),
+            vec![
+                Template(vec![lit("deploy.sh")]),
+                Template(vec![lit("--env")]),
+                Template(vec![Part::Param("env".into())]),
+                Template(vec![lit("two words")]),
+                Template(vec![lit("its")]),
+                Template(vec![lit("--x="), Part::Param("count".into()), lit("s")]),
+                Template(vec![lit("a b")]),
+                Template(vec![lit("")]),
+                Template(vec![lit("{lit}")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_command_errors() {
+        for (command, needle) in [
+            ("make test | tee log", "shell operator '|'"),
+            ("make; rm x", "shell operator ';'"),
+            ("echo {env", "unclosed placeholder"),
+            ("echo {bad-name}", "invalid character '-'"),
+            ("echo {}", "empty placeholder"),
+            ("echo 'open", "unclosed single quote"),
+            ("echo x}", "unmatched '}'"),
+        ] {
+            let err = parse_command(command).unwrap_err();
+            assert!(err.contains(needle), "{command}: {err}");
+        }
+        // Operators are fine inside quotes.
+        assert_eq!(
+            words("echo 'a|b'"),
+            vec![Template(vec![lit("echo")]), Template(vec![lit("a|b")])]
+        );
+    }
+
+    #[test]
+    fn test_from_def_errors_name_the_tool() {
+        let err = build(&def("deploy.sh {target}")).err().unwrap().to_string();
+        assert!(err.contains("tools.custom tool 'deploy'"), "{err}");
+        assert!(
+            err.contains("placeholder {target} is not declared"),
+            "{err}"
+        );
+
+        let err = build(&def("{env} --go")).err().unwrap().to_string();
+        assert!(err.contains("cannot be a placeholder"), "{err}");
+
+        let err = build(&def("   ")).err().unwrap().to_string();
+        assert!(err.contains("command must not be empty"), "{err}");
+
+        let mut bad_env = def("deploy.sh");
+        bad_env.env.insert("TARGET".into(), "{nope}".into());
+        let err = build(&bad_env).err().unwrap().to_string();
+        assert!(err.contains("{nope} is not declared"), "{err}");
+
+        let mut bad_name = def("deploy.sh");
+        bad_name.name = "run tests".into();
+        assert!(build(&bad_name).is_err());
+    }
+
+    #[test]
+    fn test_command_line_keeps_values_as_single_arguments() {
+        let tool = build(&def("deploy.sh --env {env} --branch={branch}")).unwrap();
+
+        assert_eq!(
+            tool.command_line(&json!({"env": "staging; rm -rf /", "branch": "it's $(x)"})),
+            r#"deploy.sh --env 'staging; rm -rf /' '--branch=it'\''s $(x)'"#
+        );
+        // Missing optional values drop their argument.
+        assert_eq!(
+            tool.command_line(&json!({"env": "prod"})),
+            "deploy.sh --env prod"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_passes_argv_and_env() {
+        let mut d =
+            def(r#"sh -c "printf '%s|' \"$@\"; printf '%s' \"$DEPLOY_ENV\"" script {env} {count}"#);
+        d.env.insert("DEPLOY_ENV".into(), "env={env}".into());
+        let tool = build(&d).unwrap();
+
+        let output = tool
+            .execute(
+                json!({"env": "a b; echo pwned", "count": 3}),
+                &ToolContext::new(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(output.for_llm, "a b; echo pwned|3|env=a b; echo pwned");
+    }
+
+    #[tokio::test]
+    async fn test_execute_times_out() {
+        let mut d = def("sleep 5");
+        d.timeout_secs = Some(1);
+        let tool = build(&d).unwrap();
+
+        let err = tool
+            .execute(json!({}), &ToolContext::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out after 1s"), "{err}");
+    }
+}
@@ -0,0 +1,372 @@
+//! Semantic version tool — parse, compare, bump, and match semver strings.
+//!
+//! Gives release-oriented agents exact version arithmetic instead of relying
+//! on the LLM to reason about precedence rules. All operations return
+//! structured JSON.
+
+use std::cmp::Ordering;
+
+use async_trait::async_trait;
+use semver::{BuildMetadata, Prerelease, Version, VersionReq};
+use serde_json::{json, Value};
+
+use crate::error::{Result, ZeptoError};
+use crate::tools::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Maximum number of versions accepted by the `latest` operation.
+const MAX_VERSIONS: usize = 1000;
+
+/// Tool for parsing, comparing, bumping, and range-matching semantic versions.
+pub struct SemanticVersionTool;
+
+/// Parse a version, tolerating surrounding whitespace and a leading `v`.
+fn parse_version(input: &str) -> Result<Version> {
+    let trimmed = input.trim();
+    let trimmed = trimmed
+        .strip_prefix('v')
+        .or_else(|| trimmed.strip_prefix('V'))
+        .unwrap_or(trimmed);
+    Version::parse(trimmed)
+        .map_err(|e| ZeptoError::Tool(format!("Invalid version '{}': {}", input, e)))
+}
+
+fn parse_range(input: &str) -> Result<VersionReq> {
+    VersionReq::parse(input.trim())
+        .map_err(|e| ZeptoError::Tool(format!("Invalid version range '{}': {}", input, e)))
+}
+
+fn required_str<'a>(args: &'a Value, field: &str) -> Result<&'a str> {
+    args.get(field)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ZeptoError::Tool(format!("Missing required field: {}", field)))
+}
+
+/// Bump a version by the given part.
+///
+/// `major`/`minor`/`patch` clear any prerelease and build metadata.
+/// `prerelease` increments a trailing numeric identifier (`1.0.0-rc.1` →
+/// `1.0.0-rc.2`), or starts a new `<patch+1>-<preid>.0` prerelease on a
+/// release version.
+pub fn bump(version: &Version, part: &str, preid: &str) -> Result<Version> {
+    let mut next = version.clone();
+    next.build = BuildMetadata::EMPTY;
+    match part {
+        "major" => {
+            // 2.0.0-rc.1 bumps to 2.0.0, not 3.0.0
+            if version.pre.is_empty() || version.minor != 0 || version.patch != 0 {
+                next.major += 1;
+            }
+            next.minor = 0;
+            next.patch = 0;
+            next.pre = Prerelease::EMPTY;
+        }
+        "minor" => {
+            if version.pre.is_empty() || version.patch != 0 {
+                next.minor += 1;
+            }
+            next.patch = 0;
+            next.pre = Prerelease::EMPTY;
+        }
+        "patch" => {
+            if version.pre.is_empty() {
+                next.patch += 1;
+            }
+            next.pre = Prerelease::EMPTY;
+        }
+        "prerelease" => {
+            let pre = if version.pre.is_empty() {
+                next.patch += 1;
+                format!("{}.0", preid)
+            } else {
+                let current = version.pre.as_str();
+                match current.rsplit_once('.') {
+                    Some((head, n)) if n.parse::<u64>().is_ok() => {
+                        format!("{}.{}", head, n.parse::<u64>().unwrap_or(0) + 1)
+                    }
+                    _ => match current.parse::<u64>() {
+                        Ok(n) => (n + 1).to_string(),
+                        Err(_) => format!("{}.0", current),
+                    },
+                }
+            };
+            next.pre = Prerelease::new(&pre)
+                .map_err(|e| ZeptoError::Tool(format!("Invalid prerelease '{}': {}", pre, e)))?;
+        }
+        other => {
+            return Err(ZeptoError::Tool(format!(
+                "Unknown bump part '{}'. Expected one of: major, minor, patch, prerelease",
+                other
+            )))
+        }
+    }
+    Ok(next)
+}
+
+fn describe(version: &Version) -> Value {
+    json!({
+        "version": version.to_string(),
+        "major": version.major,
+        "minor": version.minor,
+        "patch": version.patch,
+        "prerelease": version.pre.as_str(),
+        "build": version.build.as_str(),
+    })
+}
+
+#[async_trait]
+impl Tool for SemanticVersionTool {
+    fn name(&self) -> &str {
+        "semver"
+    }
+
+    fn description(&self) -> &str {
+        "Work with semantic versions. Operations: parse (validate and normalize), \
+         compare (returns lt|eq|gt), bump (major|minor|patch|prerelease), \
+         satisfies (check a version against a range like '^1.2'), \
+         latest (pick the highest version from a list)."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Parse, compare, bump semver"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Memory
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["parse", "compare", "bump", "satisfies", "latest"],
+                    "description": "Operation to perform"
+                },
+                "version": {
+                    "type": "string",
+                    "description": "Version string (parse, compare, bump, satisfies)"
+                },
+                "other": {
+                    "type": "string",
+                    "description": "Second version for compare"
+                },
+                "part": {
+                    "type": "string",
+                    "enum": ["major", "minor", "patch", "prerelease"],
+                    "description": "Part to bump"
+                },
+                "preid": {
+                    "type": "string",
+                    "description": "Prerelease identifier when starting a new prerelease (default: rc)"
+                },
+                "range": {
+                    "type": "string",
+                    "description": "Version requirement for satisfies, e.g. '>=1.2, <2'"
+                },
+                "versions": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Versions to choose from for latest"
+                }
+            },
+            "required": ["operation"]
+        })
+    }
+
+    async fn dry_run(&self, _args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        // Pure computation.
+        Ok(DryRun::Execute)
+    }
+
+    async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
+        let operation = required_str(&args, "operation")?;
+
+        let result = match operation {
+            "parse" => describe(&parse_version(required_str(&args, "version")?)?),
+            "compare" => {
+                let a = parse_version(required_str(&args, "version")?)?;
+                let b = parse_version(required_str(&args, "other")?)?;
+                let ordering = match a.cmp_precedence(&b) {
+                    Ordering::Less => "lt",
+                    Ordering::Equal => "eq",
+                    Ordering::Greater => "gt",
+                };
+                json!({
+                    "version": a.to_string(),
+                    "other": b.to_string(),
+                    "result": ordering,
+                })
+            }
+            "bump" => {
+                let version = parse_version(required_str(&args, "version")?)?;
+                let part = required_str(&args, "part")?;
+                let preid = args.get("preid").and_then(|v| v.as_str()).unwrap_or("rc");
+                let next = bump(&version, part, preid)?;
+                json!({
+                    "previous": version.to_string(),
+                    "version": next.to_string(),
+                })
+            }
+            "satisfies" => {
+                let version = parse_version(required_str(&args, "version")?)?;
+                let range = parse_range(required_str(&args, "range")?)?;
+                json!({
+                    "version": version.to_string(),
+                    "range": range.to_string(),
+                    "result": range.matches(&version),
+                })
+            }
+            "latest" => {
+                let raw = args
+                    .get("versions")
+                    .and_then(|v| v.as_array())
+                    .ok_or_else(|| ZeptoError::Tool("Missing required field: versions".into()))?;
+                if raw.len() > MAX_VERSIONS {
+                    return Err(ZeptoError::Tool(format!(
+                        "Too many versions ({}, max {})",
+                        raw.len(),
+                        MAX_VERSIONS
+                    )));
+                }
+                let mut latest: Option<Version> = None;
+                for item in raw {
+                    let s = item.as_str().ok_or_else(|| {
+                        ZeptoError::Tool(format!("versions must be strings, got {}", item))
+                    })?;
+                    let v = parse_version(s)?;
+                    if latest
+                        .as_ref()
+                        .is_none_or(|cur| v.cmp_precedence(cur) == Ordering::Greater)
+                    {
+                        latest = Some(v);
+                    }
+                }
+                let latest =
+                    latest.ok_or_else(|| ZeptoError::Tool("versions must not be empty".into()))?;
+                json!({ "version": latest.to_string() })
+            }
+            other => {
+                return Err(ZeptoError::Tool(format!(
+                "Unknown operation '{}'. Expected one of: parse, compare, bump, satisfies, latest",
+                other
+            )))
+            }
+        };
+
+        Ok(ToolOutput::llm_only(result.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn run(args: Value) -> Result<Value> {
+        let out = SemanticVersionTool
+            .execute(args, &ToolContext::new())
+            .await?;
+        Ok(serde_json::from_str(&out.for_llm).unwrap())
+    }
+
+    fn v(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_name_and_category() {
+        assert_eq!(SemanticVersionTool.name(), "semver");
+        assert_eq!(SemanticVersionTool.category(), ToolCategory::Memory);
+    }
+
+    #[test]
+    fn test_bump_release_versions() {
+        assert_eq!(bump(&v("1.2.3"), "major", "rc").unwrap(), v("2.0.0"));
+        assert_eq!(bump(&v("1.2.3"), "minor", "rc").unwrap(), v("1.3.0"));
+        assert_eq!(
+            bump(&v("1.2.3+build.5"), "patch", "rc").unwrap(),
+            v("1.2.4")
+        );
+        assert!(bump(&v("1.2.3"), "micro", "rc").is_err());
+    }
+
+    #[test]
+    fn test_bump_prerelease() {
+        assert_eq!(
+            bump(&v("1.2.3"), "prerelease", "beta").unwrap(),
+            v("1.2.4-beta.0")
+        );
+        assert_eq!(
+            bump(&v("1.2.4-rc.1"), "prerelease", "rc").unwrap(),
+            v("1.2.4-rc.2")
+        );
+        assert_eq!(
+            bump(&v("1.2.4-alpha"), "prerelease", "rc").unwrap(),
+            v("1.2.4-alpha.0")
+        );
+        // Finishing a prerelease releases it rather than skipping ahead
+        assert_eq!(bump(&v("2.0.0-rc.1"), "major", "rc").unwrap(), v("2.0.0"));
+        assert_eq!(bump(&v("1.2.4-rc.1"), "patch", "rc").unwrap(), v("1.2.4"));
+    }
+
+    #[tokio::test]
+    async fn test_parse_normalizes_leading_v() {
+        let out = run(json!({"operation": "parse", "version": " v1.4.0-rc.1 "}))
+            .await
+            .unwrap();
+        assert_eq!(out["version"], "1.4.0-rc.1");
+        assert_eq!(out["minor"], 4);
+        assert_eq!(out["prerelease"], "rc.1");
+
+        assert!(run(json!({"operation": "parse", "version": "1.4"}))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_compare() {
+        let cmp = |a: &str, b: &str| json!({"operation": "compare", "version": a, "other": b});
+        assert_eq!(run(cmp("1.0.0", "1.0.1")).await.unwrap()["result"], "lt");
+        assert_eq!(
+            run(cmp("1.0.0+a", "1.0.0+b")).await.unwrap()["result"],
+            "eq"
+        );
+        assert_eq!(
+            run(cmp("1.0.0", "1.0.0-rc.1")).await.unwrap()["result"],
+            "gt"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_satisfies() {
+        let out = run(json!({"operation": "satisfies", "version": "1.5.2", "range": "^1.2"}))
+            .await
+            .unwrap();
+        assert_eq!(out["result"], true);
+        let out = run(json!({"operation": "satisfies", "version": "2.0.0", "range": "^1.2"}))
+            .await
+            .unwrap();
+        assert_eq!(out["result"], false);
+    }
+
+    #[tokio::test]
+    async fn test_latest() {
+        let out = run(json!({
+            "operation": "latest",
+            "versions": ["1.2.0", "v1.10.0", "1.10.0-rc.3", "1.9.9"]
+        }))
+        .await
+        .unwrap();
+        assert_eq!(out["version"], "1.10.0");
+
+        assert!(run(json!({"operation": "latest", "versions": []}))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_operation() {
+        let err = run(json!({"operation": "sort"})).await.unwrap_err();
+        assert!(err.to_string().contains("Unknown operation"));
+    }
+}
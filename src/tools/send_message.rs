@@ -0,0 +1,270 @@
+//! Send-message tool — proactive notifications to other channels.
+//!
+//! Unlike [`MessageTool`](super::MessageTool), which defaults to replying in
+//! the current conversation, `send_message` always names an explicit
+//! destination. Destinations must be listed in
+//! `tools.send_message.allowed_destinations`, each destination is limited to
+//! `max_per_hour` messages, and the sent text is recorded in the destination
+//! chat's session so a later reply there has context.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::bus::{MessageBus, OutboundMessage};
+use crate::config::SendMessageToolConfig;
+use crate::error::{Result, ZeptoError};
+use crate::session::{Message, SessionManager};
+
+use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Length of the rate-limit window.
+const RATE_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Tool that publishes an outbound message to an allowlisted destination.
+///
+/// # Parameters
+/// - `channel`: destination channel name, e.g. `telegram` (required)
+/// - `chat_id`: destination chat id (required)
+/// - `content`: message text (required)
+pub struct SendMessageTool {
+    bus: Arc<MessageBus>,
+    sessions: Arc<SessionManager>,
+    config: SendMessageToolConfig,
+    /// Send times per destination within the current window.
+    sent: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl SendMessageTool {
+    /// Create a send-message tool publishing to `bus` and recording sent
+    /// messages in `sessions`.
+    pub fn new(
+        bus: Arc<MessageBus>,
+        sessions: Arc<SessionManager>,
+        config: SendMessageToolConfig,
+    ) -> Self {
+        Self {
+            bus,
+            sessions,
+            config,
+            sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether `channel:chat_id` matches an allowlist entry. Entries are
+    /// `channel:chat_id` or `channel:*`.
+    fn is_allowed(&self, channel: &str, chat_id: &str) -> bool {
+        self.config.allowed_destinations.iter().any(|entry| {
+            let Some((c, id)) = entry.trim().split_once(':') else {
+                return false;
+            };
+            c.eq_ignore_ascii_case(channel) && (id == "*" || id == chat_id)
+        })
+    }
+
+    /// Record a send to `destination` if it is under the hourly limit.
+    /// Returns the number of sends in the window including this one.
+    fn try_acquire(&self, destination: &str) -> Option<usize> {
+        let now = Instant::now();
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        let times = sent.entry(destination.to_string()).or_default();
+        while times
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= RATE_WINDOW)
+        {
+            times.pop_front();
+        }
+        if times.len() >= self.config.max_per_hour as usize {
+            return None;
+        }
+        times.push_back(now);
+        Some(times.len())
+    }
+
+    /// Undo the most recent [`try_acquire`](Self::try_acquire) for
+    /// `destination`, used when the bus rejects the message.
+    fn release(&self, destination: &str) {
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(times) = sent.get_mut(destination) {
+            times.pop_back();
+        }
+    }
+}
+
+fn required_str<'a>(args: &'a Value, key: &str) -> Result<&'a str> {
+    args.get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| ZeptoError::Tool(format!("Missing '{}' parameter", key)))
+}
+
+#[async_trait]
+impl Tool for SendMessageTool {
+    fn name(&self) -> &str {
+        "send_message"
+    }
+
+    fn description(&self) -> &str {
+        "Send a proactive message to another chat, e.g. notify an ops channel when a task \
+         finishes. Only configured destinations are allowed and each is rate limited per hour."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Notify another chat"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Messaging
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "channel": {
+                    "type": "string",
+                    "description": "Destination channel, e.g. telegram, slack, discord"
+                },
+                "chat_id": {
+                    "type": "string",
+                    "description": "Destination chat ID on that channel"
+                },
+                "content": {
+                    "type": "string",
+                    "description": "Message text to send"
+                }
+            },
+            "required": ["channel", "chat_id", "content"]
+        })
+    }
+
+    async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
+        let channel = required_str(&args, "channel")?.to_ascii_lowercase();
+        let chat_id = required_str(&args, "chat_id")?;
+        let content = required_str(&args, "content")?;
+        let destination = format!("{}:{}", channel, chat_id);
+
+        if !self.is_allowed(&channel, chat_id) {
+            return Err(ZeptoError::SecurityViolation(format!(
+                "Destination '{}' is not in tools.send_message.allowed_destinations",
+                destination
+            )));
+        }
+        let count = self.try_acquire(&destination).ok_or_else(|| {
+            ZeptoError::Tool(format!(
+                "Rate limit reached for {}: at most {} messages per hour",
+                destination, self.config.max_per_hour
+            ))
+        })?;
+
+        if let Err(e) = self
+            .bus
+            .publish_outbound(OutboundMessage::new(&channel, chat_id, content))
+            .await
+        {
+            self.release(&destination);
+            return Err(e);
+        }
+
+        if self.config.record_in_session {
+            // The message is already out; a failed write only loses history.
+            if let Err(e) = self
+                .sessions
+                .with_session_mut(&destination, |session| {
+                    session.add_message(Message::assistant(content))
+                })
+                .await
+            {
+                warn!(destination = %destination, error = %e, "Failed to record sent message");
+            }
+        }
+
+        Ok(ToolOutput::llm_only(format!(
+            "Message accepted by the bus for {} ({} of {} this hour)",
+            destination, count, self.config.max_per_hour
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(
+        allowed: &[&str],
+        max_per_hour: u32,
+    ) -> (SendMessageTool, Arc<MessageBus>, Arc<SessionManager>) {
+        let bus = Arc::new(MessageBus::new());
+        let sessions = Arc::new(SessionManager::new_memory());
+        let tool = SendMessageTool::new(
+            Arc::clone(&bus),
+            Arc::clone(&sessions),
+            SendMessageToolConfig {
+                enabled: true,
+                allowed_destinations: allowed.iter().map(|s| s.to_string()).collect(),
+                max_per_hour,
+                record_in_session: true,
+            },
+        );
+        (tool, bus, sessions)
+    }
+
+    fn args(channel: &str, chat_id: &str) -> Value {
+        json!({"channel": channel, "chat_id": chat_id, "content": "deploy finished"})
+    }
+
+    #[tokio::test]
+    async fn test_sends_and_records_in_session() {
+        let (tool, bus, sessions) = tool(&["telegram:123"], 10);
+        let out = tool
+            .execute(args("telegram", "123"), &ToolContext::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            out.for_llm,
+            "Message accepted by the bus for telegram:123 (1 of 10 this hour)"
+        );
+
+        let outbound = bus.consume_outbound().await.unwrap();
+        assert_eq!(outbound.channel, "telegram");
+        assert_eq!(outbound.chat_id, "123");
+        assert_eq!(outbound.content, "deploy finished");
+
+        let session = sessions.get("telegram:123").await.unwrap().unwrap();
+        assert_eq!(session.messages.last().unwrap().content, "deploy finished");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unlisted_destination() {
+        let (tool, _bus, _sessions) = tool(&["telegram:123"], 10);
+        let err = tool
+            .execute(args("telegram", "456"), &ToolContext::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ZeptoError::SecurityViolation(_)), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_channel_wildcard() {
+        let (tool, _bus, _sessions) = tool(&["slack:*"], 10);
+        let ctx = ToolContext::new();
+        assert!(tool.execute(args("slack", "C01"), &ctx).await.is_ok());
+        assert!(tool.execute(args("telegram", "C01"), &ctx).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_per_destination() {
+        let (tool, _bus, _sessions) = tool(&["telegram:*"], 2);
+        let ctx = ToolContext::new();
+        assert!(tool.execute(args("telegram", "1"), &ctx).await.is_ok());
+        assert!(tool.execute(args("telegram", "1"), &ctx).await.is_ok());
+        let err = tool.execute(args("telegram", "1"), &ctx).await.unwrap_err();
+        assert!(err.to_string().contains("Rate limit"), "{}", err);
+        assert!(tool.execute(args("telegram", "2"), &ctx).await.is_ok());
+    }
+}
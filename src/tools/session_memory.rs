@@ -0,0 +1,211 @@
+//! Session memory tool — durable notes about the current conversation.
+//!
+//! Notes are stored on the [`Session`](crate::session::Session) rather than
+//! as chat messages, so they survive history trimming and summarization. The
+//! agent loop injects them into the system prompt on every turn.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::error::{Result, ZeptoError};
+use crate::session::SessionManager;
+
+use super::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Source recorded on notes written by this tool.
+pub const AGENT_NOTE_SOURCE: &str = "agent";
+
+/// Tool for adding, removing, and listing notes on the current session.
+pub struct SessionMemoryTool {
+    sessions: Arc<SessionManager>,
+    max_notes: usize,
+}
+
+impl SessionMemoryTool {
+    /// Create a new session memory tool keeping at most `max_notes` notes per
+    /// session.
+    pub fn new(sessions: Arc<SessionManager>, max_notes: usize) -> Self {
+        Self {
+            sessions,
+            max_notes,
+        }
+    }
+
+    /// Session key of the conversation the tool is running in.
+    fn session_key(ctx: &ToolContext) -> Result<String> {
+        match (ctx.channel.as_deref(), ctx.chat_id.as_deref()) {
+            (Some(channel), Some(chat_id)) => Ok(format!("{}:{}", channel, chat_id)),
+            _ => Err(ZeptoError::Tool(
+                "memory tool requires a channel conversation context".into(),
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for SessionMemoryTool {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn description(&self) -> &str {
+        "Keep durable notes about the user or this conversation (e.g. 'prefers metric units', \
+         'works at ACME'). Notes are shown to you on every turn and are never trimmed with \
+         chat history. Operations: add, remove, list."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Conversation notes"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Memory
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["add", "remove", "list"]
+                },
+                "text": {
+                    "type": "string",
+                    "description": "Note text for add, or the exact note to remove"
+                }
+            },
+            "required": ["operation"]
+        })
+    }
+
+    async fn dry_run(&self, args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        let text = args.get("text").and_then(Value::as_str).unwrap_or_default();
+        Ok(
+            match args
+                .get("operation")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+            {
+                "list" => DryRun::Execute,
+                operation => DryRun::Describe(format!("would {} note '{}'", operation, text)),
+            },
+        )
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let operation = args
+            .get("operation")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ZeptoError::Tool("Missing required field: operation".into()))?;
+        let key = Self::session_key(ctx)?;
+        let text = || {
+            args.get("text")
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .ok_or_else(|| ZeptoError::Tool("Missing required field: text".into()))
+        };
+
+        let output = match operation {
+            "add" => {
+                let text = text()?;
+                let max_notes = self.max_notes;
+                let added = self
+                    .sessions
+                    .with_session_mut(&key, |s| s.add_note(text, AGENT_NOTE_SOURCE, max_notes))
+                    .await?;
+                if added {
+                    format!("Noted: {}", text)
+                } else {
+                    "Already noted".to_string()
+                }
+            }
+            "remove" => {
+                let text = text()?;
+                let removed = self
+                    .sessions
+                    .with_session_mut(&key, |s| s.remove_note(text))
+                    .await?;
+                match removed {
+                    Some(note) => format!("Removed note: {}", note.text),
+                    None => format!("No note matching '{}'", text),
+                }
+            }
+            "list" => {
+                let notes = self
+                    .sessions
+                    .with_session(&key, |s| s.notes.clone())
+                    .await?
+                    .unwrap_or_default();
+                if notes.is_empty() {
+                    "No notes yet".to_string()
+                } else {
+                    notes
+                        .iter()
+                        .map(|n| format!("- {}", n.text))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+            }
+            other => {
+                return Err(ZeptoError::Tool(format!(
+                    "Unknown operation '{}'. Use add, remove, or list",
+                    other
+                )))
+            }
+        };
+        Ok(ToolOutput::llm_only(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> ToolContext {
+        ToolContext::new().with_channel("telegram", "42")
+    }
+
+    #[tokio::test]
+    async fn test_add_list_remove() {
+        let sessions = Arc::new(SessionManager::new_memory());
+        let tool = SessionMemoryTool::new(Arc::clone(&sessions), 10);
+
+        let out = tool
+            .execute(json!({"operation": "add", "text": "works at ACME"}), &ctx())
+            .await
+            .unwrap();
+        assert_eq!(out.for_llm, "Noted: works at ACME");
+
+        let session = sessions.get("telegram:42").await.unwrap().unwrap();
+        assert_eq!(session.notes[0].source, AGENT_NOTE_SOURCE);
+
+        let out = tool
+            .execute(json!({"operation": "list"}), &ctx())
+            .await
+            .unwrap();
+        assert_eq!(out.for_llm, "- works at ACME");
+
+        let out = tool
+            .execute(
+                json!({"operation": "remove", "text": "works at acme"}),
+                &ctx(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(out.for_llm, "Removed note: works at ACME");
+    }
+
+    #[tokio::test]
+    async fn test_requires_conversation_context() {
+        let tool = SessionMemoryTool::new(Arc::new(SessionManager::new_memory()), 10);
+        let err = tool
+            .execute(json!({"operation": "list"}), &ToolContext::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("conversation"));
+    }
+}
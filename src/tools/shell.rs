@@ -5,30 +5,77 @@
 
 use async_trait::async_trait;
 use serde_json::{json, Value};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Instant;
 
+use crate::config::ShellKind;
 use crate::error::{Result, ZeptoError};
-use crate::runtime::{ContainerConfig, ContainerRuntime, NativeRuntime};
-use crate::security::ShellSecurityConfig;
+use crate::runtime::{
+    CommandOutput, ContainerConfig, ContainerRuntime, NativeRuntime, RuntimeError,
+};
+use crate::security::{CommandApprover, PolicyDecision, Sandbox, ShellPolicy, ShellSecurityConfig};
+use crate::session::{CommandOutcome, CommandRecord};
 
-use super::output::{truncate_tool_output, DEFAULT_MAX_BYTES, DEFAULT_MAX_LINES};
-use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+use super::output::truncate_middle;
+use super::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Default cap on each of stdout and stderr, in bytes.
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 32 * 1024;
 
 /// Tool for executing shell commands.
 ///
 /// Executes a shell command and returns the combined stdout and stderr output.
-/// Commands are run using `sh -c` for shell interpretation.
+/// Commands are run using `sh -c` by default; see the `shell` parameter.
 ///
 /// # Parameters
 /// - `command`: The shell command to execute (required)
 /// - `timeout`: Timeout in seconds, defaults to 60 (optional)
+/// - `env`: Object of environment variables to set (optional)
+/// - `clear_env`: Start from an empty environment, defaults to false (optional)
+/// - `stdin`: Text written to the command's standard input (optional)
+/// - `cwd`: Directory to run in, relative to and confined to the workspace (optional)
+/// - `shell`: `sh`, `bash`, `zsh`, `cmd`, or `powershell`; defaults to the
+///   configured shell or the platform's (optional)
+/// - `format`: `"text"` (default) or `"json"`, see below (optional)
+///
+/// # Result formats
+/// The text format is stdout, then stderr after a `--- stderr ---` separator,
+/// then `[Exit code: N]` for non-zero exits. The JSON format is a single
+/// object with a stable shape:
+///
+/// ```json
+/// {"stdout": "...", "stderr": "...", "exit_code": 0, "duration_ms": 12, "timed_out": false}
+/// ```
+///
+/// `exit_code` is `null` when the command was killed by a signal or timed
+/// out. A timeout is an error in text mode but a `timed_out: true` result in
+/// JSON mode, with empty streams.
+///
+/// # Streaming
+/// When the [`ToolContext`] carries a progress callback, output is read line
+/// by line and each line is reported as it arrives; the full output is still
+/// returned at the end. A streaming timeout is not an error in either format:
+/// the output captured so far is returned, marked `[Timed out, partial
+/// output]` in text mode.
 ///
 /// # Security
 /// This tool validates commands against a configurable blocklist to prevent
 /// dangerous operations. Use `ShellTool::permissive()` to disable security
 /// checks in trusted environments.
 ///
+/// An optional [`ShellPolicy`] (see [`with_policy`](Self::with_policy),
+/// overridable per call via [`ToolContext::shell_policy`]) adds allowlists,
+/// deny patterns, and workspace write limits on top. Commands the policy
+/// flags for review are sent to the [`CommandApprover`] and refused when none
+/// is configured.
+///
+/// # Output limits
+/// Stdout and stderr are each capped at `max_output_bytes` (see
+/// [`with_max_output_bytes`](Self::with_max_output_bytes), overridable per
+/// call via [`ToolContext::max_output_bytes`]). Longer output keeps its head
+/// and tail around a `[... N bytes truncated ...]` marker.
+///
 /// # Example
 /// ```rust
 /// use zeptoclaw::tools::{Tool, ToolContext};
@@ -46,6 +93,10 @@ use super::{Tool, ToolCategory, ToolContext, ToolOutput};
 pub struct ShellTool {
     security_config: ShellSecurityConfig,
     runtime: Arc<dyn ContainerRuntime>,
+    max_output_bytes: usize,
+    policy: Option<Arc<ShellPolicy>>,
+    approver: Option<Arc<dyn CommandApprover>>,
+    shell: Option<ShellKind>,
 }
 
 impl ShellTool {
@@ -54,6 +105,10 @@ impl ShellTool {
         Self {
             security_config: ShellSecurityConfig::new(),
             runtime: Arc::new(NativeRuntime::new()),
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            policy: None,
+            approver: None,
+            shell: None,
         }
     }
 
@@ -62,6 +117,10 @@ impl ShellTool {
         Self {
             security_config,
             runtime: Arc::new(NativeRuntime::new()),
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            policy: None,
+            approver: None,
+            shell: None,
         }
     }
 
@@ -70,6 +129,10 @@ impl ShellTool {
         Self {
             security_config: ShellSecurityConfig::new(),
             runtime,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            policy: None,
+            approver: None,
+            shell: None,
         }
     }
 
@@ -81,6 +144,10 @@ impl ShellTool {
         Self {
             security_config,
             runtime,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            policy: None,
+            approver: None,
+            shell: None,
         }
     }
 
@@ -92,6 +159,90 @@ impl ShellTool {
         Self {
             security_config: ShellSecurityConfig::permissive(),
             runtime: Arc::new(NativeRuntime::new()),
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            policy: None,
+            approver: None,
+            shell: None,
+        }
+    }
+
+    /// Set the maximum bytes kept from each of stdout and stderr.
+    pub fn with_max_output_bytes(mut self, max_output_bytes: usize) -> Self {
+        self.max_output_bytes = max_output_bytes;
+        self
+    }
+
+    /// Set the shell used when a call does not choose one.
+    pub fn with_shell(mut self, shell: ShellKind) -> Self {
+        self.shell = Some(shell);
+        self
+    }
+
+    /// Shell used when a call does not choose one.
+    fn default_shell(&self) -> ShellKind {
+        self.shell.unwrap_or_else(ShellKind::detect)
+    }
+
+    /// Resolve the optional `shell` argument.
+    fn resolve_shell(&self, args: &Value) -> Result<ShellKind> {
+        let shell = match args.get("shell").and_then(|v| v.as_str()) {
+            None => self.default_shell(),
+            Some(name) => ShellKind::from_name(name).ok_or_else(|| {
+                ZeptoError::Tool(format!(
+                    "Unknown shell '{}', expected one of: {}",
+                    name,
+                    ShellKind::ALL.map(ShellKind::name).join(", ")
+                ))
+            })?,
+        };
+        if shell.is_windows_only() && (!cfg!(windows) || self.runtime.name() != "native") {
+            return Err(ZeptoError::Tool(format!(
+                "Shell '{}' is only available on Windows hosts",
+                shell
+            )));
+        }
+        Ok(shell)
+    }
+
+    /// Apply a command policy on top of the security blocklist.
+    pub fn with_policy(mut self, policy: ShellPolicy) -> Self {
+        self.policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// Set who approves commands the policy flags for review.
+    pub fn with_approver(mut self, approver: Arc<dyn CommandApprover>) -> Self {
+        self.approver = Some(approver);
+        self
+    }
+
+    /// Apply the command policy, asking the approver when required.
+    async fn enforce_policy(&self, command: &str, ctx: &ToolContext) -> Result<()> {
+        let Some(policy) = ctx.shell_policy.as_ref().or(self.policy.as_ref()) else {
+            return Ok(());
+        };
+        match policy.evaluate(command, ctx.workspace.as_deref().map(Path::new)) {
+            PolicyDecision::Allow => Ok(()),
+            PolicyDecision::Deny(reason) => Err(ZeptoError::SecurityViolation(format!(
+                "Command blocked by shell policy: {}",
+                reason
+            ))),
+            PolicyDecision::Ask(reason) => {
+                let Some(ref approver) = self.approver else {
+                    return Err(ZeptoError::SecurityViolation(format!(
+                        "Command requires human approval ({}), but no approval channel is configured",
+                        reason
+                    )));
+                };
+                if approver.approve(command, &reason).await? {
+                    Ok(())
+                } else {
+                    Err(ZeptoError::SecurityViolation(format!(
+                        "Command was not approved by a human reviewer ({})",
+                        reason
+                    )))
+                }
+            }
         }
     }
 
@@ -101,6 +252,100 @@ impl ShellTool {
     }
 }
 
+/// Parse the optional `env` argument into variable pairs.
+///
+/// Values are passed to the runtime as-is and never interpolated into the
+/// command string. Names must be non-empty and consist of ASCII letters,
+/// digits, and underscores, not starting with a digit.
+fn parse_env(args: &Value) -> Result<Vec<(String, String)>> {
+    let Some(env) = args.get("env").filter(|v| !v.is_null()) else {
+        return Ok(Vec::new());
+    };
+    let env = env
+        .as_object()
+        .ok_or_else(|| ZeptoError::Tool("'env' must be an object of strings".into()))?;
+    env.iter()
+        .map(|(key, value)| {
+            let valid = key
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(ZeptoError::Tool(format!(
+                    "Invalid environment variable name '{}'",
+                    key
+                )));
+            }
+            let value = match value {
+                Value::String(s) => s.clone(),
+                Value::Number(n) => n.to_string(),
+                Value::Bool(b) => b.to_string(),
+                _ => {
+                    return Err(ZeptoError::Tool(format!(
+                        "Environment variable '{}' must be a string",
+                        key
+                    )))
+                }
+            };
+            if value.contains('\0') {
+                return Err(ZeptoError::Tool(format!(
+                    "Environment variable '{}' contains a NUL byte",
+                    key
+                )));
+            }
+            Ok((key.clone(), value))
+        })
+        .collect()
+}
+
+/// Resolve the optional `cwd` argument to a directory to run the command in.
+///
+/// Relative paths are taken from the workspace, and the canonical result
+/// (symlinks resolved) must stay inside it. Without a workspace only absolute
+/// paths are accepted, so a relative `cwd` never silently lands in the
+/// daemon's own working directory. A denied directory is refused.
+pub(crate) fn resolve_cwd(cwd: &str, sandbox: Option<&Sandbox>) -> Result<PathBuf> {
+    let Some(sandbox) = sandbox else {
+        if !Path::new(cwd).is_absolute() {
+            return Err(ZeptoError::Tool(format!(
+                "Relative cwd '{}' requires a workspace",
+                cwd
+            )));
+        }
+        return canonical_dir(cwd, Path::new(cwd));
+    };
+
+    let workspace = sandbox.workspace();
+    let safe = sandbox.resolve(cwd)?;
+    let resolved = canonical_dir(cwd, &safe)?;
+    let canonical_workspace = Path::new(workspace)
+        .canonicalize()
+        .map_err(|e| ZeptoError::Tool(format!("Workspace '{}' is unusable: {}", workspace, e)))?;
+    if !resolved.starts_with(&canonical_workspace) {
+        return Err(ZeptoError::SecurityViolation(format!(
+            "cwd '{}' resolves outside the workspace",
+            cwd
+        )));
+    }
+    sandbox.check(&resolved)?;
+    Ok(resolved)
+}
+
+/// Canonicalize `path`, requiring it to be an existing directory.
+fn canonical_dir(cwd: &str, path: &Path) -> Result<PathBuf> {
+    let resolved = path
+        .canonicalize()
+        .map_err(|e| ZeptoError::Tool(format!("cwd '{}' is not accessible: {}", cwd, e)))?;
+    if !resolved.is_dir() {
+        return Err(ZeptoError::Tool(format!(
+            "cwd '{}' is not a directory",
+            cwd
+        )));
+    }
+    Ok(resolved)
+}
+
 impl Default for ShellTool {
     fn default() -> Self {
         Self::new()
@@ -136,12 +381,76 @@ impl Tool for ShellTool {
                 "timeout": {
                     "type": "integer",
                     "description": "Timeout in seconds (default: 60)"
+                },
+                "env": {
+                    "type": "object",
+                    "description": "Environment variables to set for the command, e.g. {\"STAGE\": \"prod\"}. Use this instead of writing FOO=bar in the command.",
+                    "additionalProperties": { "type": "string" }
+                },
+                "clear_env": {
+                    "type": "boolean",
+                    "description": "Start from an empty environment so only 'env' is set (default: false)"
+                },
+                "stdin": {
+                    "type": "string",
+                    "description": "Text piped to the command's standard input, e.g. source code for a formatter. Use this instead of echoing or heredoc-quoting large strings in the command."
+                },
+                "shell": {
+                    "type": "string",
+                    "enum": ShellKind::ALL.map(ShellKind::name),
+                    "description": format!(
+                        "Shell that interprets the command (default: {}). 'cmd' is Windows-only; 'powershell' runs pwsh outside Windows.",
+                        self.default_shell()
+                    )
+                },
+                "cwd": {
+                    "type": "string",
+                    "description": "Directory to run the command in, relative to the workspace (default: the workspace root). Use this instead of 'cd dir && ...'."
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["text", "json"],
+                    "description": "Result format (default: text). 'text' returns stdout, then stderr after '--- stderr ---', then '[Exit code: N]' if non-zero. 'json' returns {\"stdout\": string, \"stderr\": string, \"exit_code\": integer or null, \"duration_ms\": integer, \"timed_out\": boolean}."
                 }
             },
             "required": ["command"]
         })
     }
 
+    async fn dry_run(&self, args: &Value, ctx: &ToolContext) -> Result<DryRun> {
+        let command = args
+            .get("command")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ZeptoError::Tool("Missing 'command' argument".into()))?;
+        self.security_config.validate_command(command)?;
+        let policy = ctx.shell_policy.as_ref().or(self.policy.as_ref());
+        let approval =
+            match policy.map(|p| p.evaluate(command, ctx.workspace.as_deref().map(Path::new))) {
+                Some(PolicyDecision::Deny(reason)) => {
+                    return Err(ZeptoError::SecurityViolation(format!(
+                        "Command blocked by shell policy: {}",
+                        reason
+                    )))
+                }
+                Some(PolicyDecision::Ask(reason)) => format!(", after human approval ({})", reason),
+                _ => String::new(),
+            };
+        let shell = self.resolve_shell(args)?;
+        let dir = match args.get("cwd").and_then(|v| v.as_str()) {
+            Some(cwd) => resolve_cwd(cwd, ctx.sandbox().as_deref())?
+                .display()
+                .to_string(),
+            None => "the workspace".to_string(),
+        };
+        Ok(DryRun::Describe(format!(
+            "would run `{}` with {} in {}{}",
+            command,
+            shell.name(),
+            dir,
+            approval
+        )))
+    }
+
     async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
         let command = args
             .get("command")
@@ -150,11 +459,38 @@ impl Tool for ShellTool {
 
         // Security check
         self.security_config.validate_command(command)?;
+        self.enforce_policy(command, ctx).await?;
 
         let timeout_secs = args.get("timeout").and_then(|v| v.as_u64()).unwrap_or(60);
+        let shell = self.resolve_shell(&args)?;
+        let env = parse_env(&args)?;
+        let clear_env = args
+            .get("clear_env")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let stdin = match args.get("stdin").filter(|v| !v.is_null()) {
+            None => None,
+            Some(Value::String(s)) => Some(s.clone()),
+            Some(_) => return Err(ZeptoError::Tool("'stdin' must be a string".into())),
+        };
+        let json_format = match args.get("format").and_then(|v| v.as_str()) {
+            None | Some("text") => false,
+            Some("json") => true,
+            Some(other) => {
+                return Err(ZeptoError::Tool(format!(
+                    "Unknown format '{}', expected 'text' or 'json'",
+                    other
+                )))
+            }
+        };
 
         // Build container configuration
-        let mut container_config = ContainerConfig::new().with_timeout(timeout_secs);
+        let mut container_config = ContainerConfig::new()
+            .with_timeout(timeout_secs)
+            .with_clear_env(clear_env)
+            .with_shell(shell);
+        container_config.env = env;
+        container_config.stdin = stdin;
 
         // Set working directory and mount if workspace is specified
         if let Some(ref workspace) = ctx.workspace {
@@ -163,17 +499,86 @@ impl Tool for ShellTool {
                 .with_workdir(workspace_path.clone())
                 .with_mount(workspace_path.clone(), workspace_path, false);
         }
+        if let Some(cwd) = args.get("cwd").and_then(|v| v.as_str()) {
+            container_config.workdir = Some(resolve_cwd(cwd, ctx.sandbox().as_deref())?);
+        }
 
-        // Execute command via runtime
-        let output = self
-            .runtime
-            .execute(command, &container_config)
-            .await
-            .map_err(|e| ZeptoError::Tool(e.to_string()))?;
-
-        let formatted =
-            truncate_tool_output(&output.format(), DEFAULT_MAX_LINES, DEFAULT_MAX_BYTES);
-        Ok(ToolOutput::user_visible(formatted))
+        // Execute command via runtime, streaming lines out as they arrive
+        // when the caller wants progress. Stopping the turn drops the
+        // runtime future, which kills the child.
+        let record = CommandRecord::new(
+            "shell",
+            command,
+            &container_config
+                .workdir
+                .as_ref()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default(),
+        );
+        let started = Instant::now();
+        let result = ctx
+            .cancellable(async {
+                Ok(match ctx.progress.clone() {
+                    Some(progress) => {
+                        self.runtime
+                            .execute_streaming(command, &container_config, &move |_, line: &str| {
+                                progress.report(line)
+                            })
+                            .await
+                    }
+                    None => self.runtime.execute(command, &container_config).await,
+                })
+            })
+            .await;
+        let elapsed = started.elapsed();
+        let duration_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+        let mut output = match result {
+            Ok(Ok(output)) => output,
+            Ok(Err(RuntimeError::Timeout(_))) if json_format => {
+                CommandOutput::timed_out(String::new(), String::new())
+            }
+            Ok(Err(e)) => {
+                let outcome = match e {
+                    RuntimeError::Timeout(_) => CommandOutcome::TimedOut,
+                    _ => CommandOutcome::Failed,
+                };
+                ctx.record_command(record.finished(outcome, None, elapsed, ""));
+                return Err(ZeptoError::Tool(e.to_string()));
+            }
+            Err(e) => {
+                ctx.record_command(record.finished(CommandOutcome::Cancelled, None, elapsed, ""));
+                return Err(e);
+            }
+        };
+        let outcome = if output.timed_out {
+            CommandOutcome::TimedOut
+        } else {
+            CommandOutcome::Exited
+        };
+        ctx.record_command(record.finished(
+            outcome,
+            output.exit_code,
+            elapsed,
+            &format!("{}{}", output.stdout, output.stderr),
+        ));
+
+        // Cap each stream separately so a noisy stdout cannot push the
+        // stderr explaining a failure out of the result.
+        let max_bytes = ctx.max_output_bytes.unwrap_or(self.max_output_bytes);
+        output.stdout = truncate_middle(&output.stdout, max_bytes);
+        output.stderr = truncate_middle(&output.stderr, max_bytes);
+
+        if json_format {
+            let result = json!({
+                "stdout": output.stdout,
+                "stderr": output.stderr,
+                "exit_code": output.exit_code,
+                "duration_ms": duration_ms,
+                "timed_out": output.timed_out,
+            });
+            return Ok(ToolOutput::user_visible(result.to_string()));
+        }
+        Ok(ToolOutput::user_visible(output.format()))
     }
 }
 
@@ -192,6 +597,33 @@ mod tests {
         assert_eq!(result.unwrap().for_llm.trim(), "hello");
     }
 
+    #[tokio::test]
+    async fn test_shell_dry_run_describes_command() {
+        let dir = tempdir().unwrap();
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
+
+        let effect = tool
+            .dry_run(&json!({"command": "touch made.txt"}), &ctx)
+            .await
+            .unwrap();
+        let DryRun::Describe(effect) = effect else {
+            panic!("shell must not run in dry-run mode");
+        };
+        assert!(
+            effect.starts_with("would run `touch made.txt` with "),
+            "{}",
+            effect
+        );
+        assert!(effect.ends_with(" in the workspace"), "{}", effect);
+        assert!(!dir.path().join("made.txt").exists());
+
+        assert!(tool
+            .dry_run(&json!({"command": "rm -rf /"}), &ctx)
+            .await
+            .is_err());
+    }
+
     #[tokio::test]
     async fn test_shell_multiple_commands() {
         let tool = ShellTool::new();
@@ -317,6 +749,40 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("timed out"));
     }
 
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_shell_timeout_kills_background_children() {
+        let dir = tempdir().unwrap();
+        let pid_file = dir.path().join("child.pid");
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new();
+
+        let command = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+        let result = tool
+            .execute(json!({"command": command, "timeout": 1}), &ctx)
+            .await;
+        assert!(result.unwrap_err().to_string().contains("timed out"));
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let stat_path = format!("/proc/{}/stat", pid.trim());
+        // A killed child can linger briefly as a zombie until it is reaped.
+        let mut alive = true;
+        for _ in 0..50 {
+            alive = std::fs::read_to_string(&stat_path)
+                .map(|stat| !stat.contains(") Z "))
+                .unwrap_or(false);
+            if !alive {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(
+            !alive,
+            "background child {} survived the timeout",
+            pid.trim()
+        );
+    }
+
     #[tokio::test]
     async fn test_shell_custom_timeout_success() {
         let tool = ShellTool::new();
@@ -344,6 +810,556 @@ mod tests {
         assert!(result.unwrap().for_llm.contains("hello"));
     }
 
+    #[tokio::test]
+    async fn test_shell_env_parameter_is_not_interpolated() {
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new();
+
+        let result = tool
+            .execute(
+                json!({
+                    "command": "printf '%s' \"$STAGE\"",
+                    "env": {"STAGE": "prod; echo injected"}
+                }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.for_llm, "prod; echo injected");
+    }
+
+    #[tokio::test]
+    async fn test_shell_clear_env() {
+        std::env::set_var("ZEPTOCLAW_SHELL_TEST_INHERITED", "yes");
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new();
+
+        let result = tool
+            .execute(
+                json!({
+                    "command": "echo \"[$ZEPTOCLAW_SHELL_TEST_INHERITED][$ONLY]\"",
+                    "env": {"ONLY": "set"},
+                    "clear_env": true
+                }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.for_llm.trim(), "[][set]");
+    }
+
+    #[tokio::test]
+    async fn test_shell_env_rejects_invalid_names() {
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new();
+
+        let err = tool
+            .execute(json!({"command": "true", "env": {"BAD-NAME": "x"}}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("BAD-NAME"));
+        let err = tool
+            .execute(json!({"command": "true", "env": ["A=1"]}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("object"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_stdin_round_trips_through_cat() {
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new();
+        let payload: String = (0..400)
+            .map(|i| format!("line {i}: 'quotes' \"and\" $dollars `ticks`\n"))
+            .collect();
+        assert!(payload.len() > 16 * 1024);
+
+        let result = tool
+            .execute(json!({"command": "cat", "stdin": payload}), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(result.for_llm, payload);
+    }
+
+    #[tokio::test]
+    async fn test_shell_stdin_ignored_by_command_is_not_an_error() {
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new();
+
+        let result = tool
+            .execute(
+                json!({"command": "echo done", "stdin": "z".repeat(256 * 1024)}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.for_llm.trim(), "done");
+
+        let err = tool
+            .execute(json!({"command": "cat", "stdin": 42}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("'stdin' must be a string"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_cwd_runs_in_workspace_subdirectory() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("sub/inner")).unwrap();
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
+
+        let result = tool
+            .execute(json!({"command": "pwd", "cwd": "sub/inner"}), &ctx)
+            .await
+            .unwrap();
+        let expected = dir.path().join("sub/inner").canonicalize().unwrap();
+        assert_eq!(result.for_llm.trim(), expected.to_str().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_shell_cwd_cannot_escape_workspace() {
+        let dir = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("file.txt"), "").unwrap();
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new().with_workspace(dir.path().to_str().unwrap());
+
+        for cwd in [
+            "..".to_string(),
+            "sub/../..".to_string(),
+            outside.path().to_str().unwrap().to_string(),
+        ] {
+            let err = tool
+                .execute(json!({"command": "pwd", "cwd": cwd}), &ctx)
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(err, ZeptoError::SecurityViolation(_)),
+                "{cwd}: {err}"
+            );
+        }
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+            let err = tool
+                .execute(json!({"command": "pwd", "cwd": "link"}), &ctx)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, ZeptoError::SecurityViolation(_)), "{err}");
+        }
+
+        let err = tool
+            .execute(json!({"command": "pwd", "cwd": "file.txt"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not a directory"));
+        let err = tool
+            .execute(json!({"command": "pwd", "cwd": "missing"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not accessible"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_relative_cwd_requires_workspace() {
+        let tool = ShellTool::new();
+        let err = tool
+            .execute(json!({"command": "pwd", "cwd": "sub"}), &ToolContext::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("requires a workspace"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_streams_progress_and_returns_full_output() {
+        let tool = ShellTool::new();
+        let lines = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&lines);
+        let ctx = ToolContext::new().with_progress(crate::tools::ToolProgress::new(move |chunk| {
+            sink.lock().unwrap().push(chunk.to_string())
+        }));
+
+        let result = tool
+            .execute(json!({"command": "echo one; echo two; echo err >&2"}), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(result.for_llm, "one\ntwo\n\n--- stderr ---\nerr\n");
+        let mut lines = lines.lock().unwrap().clone();
+        lines.sort();
+        assert_eq!(lines, vec!["err\n", "one\n", "two\n"]);
+    }
+
+    #[tokio::test]
+    async fn test_shell_streaming_timeout_returns_partial_output() {
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new().with_progress(crate::tools::ToolProgress::new(|_| {}));
+
+        let result = tool
+            .execute(
+                json!({"command": "echo started; sleep 10", "timeout": 1}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.for_llm, "started\n\n[Timed out, partial output]");
+
+        let result = tool
+            .execute(
+                json!({"command": "echo started; sleep 10", "timeout": 1, "format": "json"}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        let value: Value = serde_json::from_str(&result.for_llm).unwrap();
+        assert_eq!(value["stdout"], "started\n");
+        assert_eq!(value["timed_out"], true);
+    }
+
+    /// Whether `program` can be found on `PATH`.
+    fn on_path(program: &str) -> bool {
+        std::env::var_os("PATH")
+            .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+    }
+
+    #[tokio::test]
+    async fn test_shell_rejects_unknown_shell() {
+        let tool = ShellTool::new();
+        let err = tool
+            .execute(
+                json!({"command": "true", "shell": "fish"}),
+                &ToolContext::new(),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Unknown shell 'fish'"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shell_selects_bash_and_zsh() {
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new();
+        for (shell, probe) in [
+            ("bash", "echo ${BASH_VERSION:+yes}; exit 3"),
+            ("zsh", "echo ${ZSH_VERSION:+yes}; exit 3"),
+        ] {
+            if !on_path(shell) {
+                continue;
+            }
+            let result = tool
+                .execute(json!({"command": probe, "shell": shell}), &ctx)
+                .await
+                .unwrap();
+            assert_eq!(result.for_llm, "yes\n\n[Exit code: 3]", "{shell}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shell_configured_default_and_cmd_rejected() {
+        let ctx = ToolContext::new();
+        if on_path("bash") {
+            let tool = ShellTool::new().with_shell(ShellKind::Bash);
+            let result = tool
+                .execute(json!({"command": "echo ${BASH_VERSION:+yes}"}), &ctx)
+                .await
+                .unwrap();
+            assert_eq!(result.for_llm.trim(), "yes");
+            // A per-call choice overrides the configured default.
+            let result = tool
+                .execute(
+                    json!({"command": "echo ${BASH_VERSION:-plain}", "shell": "sh"}),
+                    &ctx,
+                )
+                .await
+                .unwrap();
+            assert_ne!(result.for_llm.trim(), "yes");
+        }
+
+        let err = ShellTool::new()
+            .execute(json!({"command": "echo hi", "shell": "cmd"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("only available on Windows"));
+    }
+
+    #[cfg(windows)]
+    #[tokio::test]
+    async fn test_shell_windows_cmd() {
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new();
+        let result = tool
+            .execute(
+                json!({"command": "echo hello& echo oops 1>&2& exit /b 3", "shell": "cmd"}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert!(result.for_llm.starts_with("hello"));
+        assert!(result.for_llm.contains("--- stderr ---\noops"));
+        assert!(result.for_llm.ends_with("[Exit code: 3]"));
+    }
+
+    #[cfg(windows)]
+    #[tokio::test]
+    async fn test_shell_windows_powershell() {
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new();
+        let result = tool
+            .execute(
+                json!({"command": "Write-Output hello; exit 4", "shell": "powershell"}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert!(result.for_llm.starts_with("hello"));
+        assert!(result.for_llm.ends_with("[Exit code: 4]"));
+
+        let err = tool
+            .execute(
+                json!({"command": "Start-Sleep 10", "shell": "powershell", "timeout": 1}),
+                &ctx,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+    }
+
+    #[test]
+    fn test_shell_kind_names_round_trip() {
+        for shell in ShellKind::ALL {
+            assert_eq!(ShellKind::from_name(shell.name()), Some(shell));
+        }
+        assert_eq!(
+            ShellKind::from_name("PowerShell"),
+            Some(ShellKind::PowerShell)
+        );
+        assert_eq!(ShellKind::from_name("fish"), None);
+        #[cfg(unix)]
+        assert_eq!(ShellKind::detect(), ShellKind::Sh);
+    }
+
+    #[tokio::test]
+    async fn test_shell_json_format() {
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new();
+
+        let result = tool
+            .execute(
+                json!({"command": "echo out; echo err >&2; exit 3", "format": "json"}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        let value: Value = serde_json::from_str(&result.for_llm).unwrap();
+        assert_eq!(value["stdout"], "out\n");
+        assert_eq!(value["stderr"], "err\n");
+        assert_eq!(value["exit_code"], 3);
+        assert!(value["duration_ms"].is_u64());
+        assert_eq!(value["timed_out"], false);
+    }
+
+    #[tokio::test]
+    async fn test_shell_json_format_reports_timeout() {
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new();
+
+        let result = tool
+            .execute(
+                json!({"command": "sleep 10", "timeout": 1, "format": "json"}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        let value: Value = serde_json::from_str(&result.for_llm).unwrap();
+        assert_eq!(value["timed_out"], true);
+        assert!(value["exit_code"].is_null());
+        assert!(value["duration_ms"].as_u64().unwrap() >= 1000);
+
+        // Text mode keeps reporting a timeout as an error.
+        let err = tool
+            .execute(json!({"command": "sleep 10", "timeout": 1}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_stops_when_turn_is_cancelled() {
+        let tool = ShellTool::new();
+        let token = tokio_util::sync::CancellationToken::new();
+        let ctx = ToolContext::new().with_cancellation(token.clone());
+        let cancel = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            token.cancel();
+        });
+
+        let started = Instant::now();
+        let err = tool
+            .execute(json!({"command": "sleep 10"}), &ctx)
+            .await
+            .unwrap_err();
+        cancel.await.unwrap();
+        assert!(matches!(err, ZeptoError::Cancelled(_)));
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_shell_records_commands_in_command_log() {
+        let dir = tempdir().unwrap();
+        let log = Arc::new(crate::session::CommandLog::memory());
+        let ctx = ToolContext::new()
+            .with_workspace(dir.path().to_str().unwrap())
+            .with_command_log(Arc::clone(&log), "cli:main");
+        let tool = ShellTool::new();
+
+        tool.execute(json!({"command": "echo hi; exit 2"}), &ctx)
+            .await
+            .unwrap();
+        let token = tokio_util::sync::CancellationToken::new();
+        token.cancel();
+        let _ = tool
+            .execute(
+                json!({"command": "sleep 10"}),
+                &ctx.clone().with_cancellation(token),
+            )
+            .await;
+
+        let records = log.recent("cli:main", 10).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].command, "echo hi; exit 2");
+        assert_eq!(records[0].outcome, CommandOutcome::Exited);
+        assert_eq!(records[0].exit_code, Some(2));
+        assert_eq!(records[0].output_bytes, 3);
+        assert_eq!(records[0].cwd, dir.path().display().to_string());
+        assert_eq!(records[1].outcome, CommandOutcome::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_shell_rejects_unknown_format() {
+        let tool = ShellTool::new();
+        let err = tool
+            .execute(
+                json!({"command": "true", "format": "xml"}),
+                &ToolContext::new(),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Unknown format 'xml'"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_truncates_each_stream_keeping_head_and_tail() {
+        let tool = ShellTool::new().with_max_output_bytes(100);
+        let ctx = ToolContext::new();
+
+        let result = tool
+            .execute(
+                json!({"command": "echo START; head -c 5000 /dev/zero | tr '\\0' x; echo; echo END; echo oops >&2"}),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        let out = result.for_llm;
+        assert!(out.starts_with("START"));
+        assert!(out.contains("bytes truncated (original size 5011 bytes)"));
+        assert!(out.contains("END\n\n--- stderr ---\noops"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_context_overrides_output_limit() {
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new().with_max_output_bytes(10);
+
+        let result = tool
+            .execute(json!({"command": "seq 1 1000"}), &ctx)
+            .await
+            .unwrap();
+        assert!(result.for_llm.starts_with("1\n2\n3"));
+        assert!(result.for_llm.contains("bytes truncated"));
+        assert!(result.for_llm.ends_with("...]\n1000\n"));
+    }
+
+    struct FixedApprover(bool);
+
+    #[async_trait]
+    impl CommandApprover for FixedApprover {
+        async fn approve(&self, _command: &str, _reason: &str) -> Result<bool> {
+            Ok(self.0)
+        }
+    }
+
+    fn policy(mode: crate::config::ShellPolicyMode) -> ShellPolicy {
+        ShellPolicy::from_config(&crate::config::ShellPolicyConfig {
+            mode,
+            allow: vec!["echo".into()],
+            deny: vec![r"\bsudo\b".into()],
+            restrict_writes_to_workspace: false,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_shell_policy_denies_with_readable_error() {
+        use crate::config::ShellPolicyMode;
+        let tool = ShellTool::new().with_policy(policy(ShellPolicyMode::Deny));
+        let ctx = ToolContext::new();
+
+        assert!(tool
+            .execute(json!({"command": "echo ok"}), &ctx)
+            .await
+            .is_ok());
+        let err = tool
+            .execute(json!({"command": "printf ok"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not on the shell allowlist"));
+        let err = tool
+            .execute(json!({"command": "echo x; sudo ls"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("denied pattern"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_policy_ask_routes_to_approver() {
+        use crate::config::ShellPolicyMode;
+        let ctx = ToolContext::new();
+        let args = json!({"command": "printf approved"});
+
+        let no_channel = ShellTool::new().with_policy(policy(ShellPolicyMode::Ask));
+        let err = no_channel.execute(args.clone(), &ctx).await.unwrap_err();
+        assert!(err.to_string().contains("no approval channel"));
+
+        let approving = ShellTool::new()
+            .with_policy(policy(ShellPolicyMode::Ask))
+            .with_approver(Arc::new(FixedApprover(true)));
+        let out = approving.execute(args.clone(), &ctx).await.unwrap();
+        assert_eq!(out.for_llm, "approved");
+
+        let rejecting = ShellTool::new()
+            .with_policy(policy(ShellPolicyMode::Ask))
+            .with_approver(Arc::new(FixedApprover(false)));
+        let err = rejecting.execute(args, &ctx).await.unwrap_err();
+        assert!(err.to_string().contains("not approved"));
+    }
+
+    #[tokio::test]
+    async fn test_shell_context_policy_overrides_tool_policy() {
+        use crate::config::ShellPolicyMode;
+        let tool = ShellTool::new();
+        let ctx = ToolContext::new().with_shell_policy(Arc::new(policy(ShellPolicyMode::Deny)));
+
+        let err = tool
+            .execute(json!({"command": "printf ok"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("shell policy"));
+    }
+
     #[tokio::test]
     async fn test_shell_piped_commands() {
         let tool = ShellTool::new();
@@ -386,6 +1402,18 @@ mod tests {
         assert_eq!(params["type"], "object");
         assert!(params["properties"]["command"].is_object());
         assert!(params["properties"]["timeout"].is_object());
+        assert_eq!(params["properties"]["env"]["type"], "object");
+        assert_eq!(params["properties"]["clear_env"]["type"], "boolean");
+        assert_eq!(params["properties"]["stdin"]["type"], "string");
+        assert_eq!(params["properties"]["cwd"]["type"], "string");
+        assert_eq!(
+            params["properties"]["shell"]["enum"],
+            json!(["sh", "bash", "zsh", "cmd", "powershell"])
+        );
+        assert_eq!(
+            params["properties"]["format"]["enum"],
+            json!(["text", "json"])
+        );
         assert_eq!(params["required"][0], "command");
     }
 
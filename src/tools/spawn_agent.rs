@@ -0,0 +1,396 @@
+//! Foreground sub-agent tool.
+//!
+//! `spawn_agent` hands a self-contained task to a fresh `AgentLoop` that
+//! shares the caller's provider but starts from an empty context, runs it to
+//! completion, and returns only the sub-agent's final answer plus a compact
+//! log of the tools it called. The sub-agent's session is saved under a
+//! generated `spawn_agent:<id>` key so its full transcript can be inspected
+//! later with `zeptoclaw history show <key>`.
+//!
+//! Nesting is capped by `swarm.max_depth`: a sub-agent only receives its own
+//! `spawn_agent` tool while it is shallower than that.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::agent::{AgentLoop, ContextBuilder};
+use crate::bus::{InboundMessage, MessageBus};
+use crate::config::Config;
+use crate::error::{Result, ZeptoError};
+use crate::providers::LLMProvider;
+use crate::session::{Message, Role, SessionManager};
+
+use super::delegate::{sub_agent_tools, ProviderRef};
+use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Most tool calls listed in the action log.
+const ACTION_LOG_MAX_ENTRIES: usize = 20;
+/// Longest argument preview per action log entry, in chars.
+const ACTION_LOG_ARGS_MAX_CHARS: usize = 80;
+
+const SUB_AGENT_PROMPT: &str = "You are a sub-agent working on a task delegated by another agent. \
+     Work independently with the tools available; nobody can answer questions mid-task. \
+     When done, reply with a concise summary of what you did and found. \
+     That final reply is all the requesting agent will see.";
+
+/// Tool that runs a task in a fresh sub-agent and returns its summary.
+pub struct SpawnAgentTool {
+    config: Config,
+    provider: Arc<dyn LLMProvider>,
+    bus: Arc<MessageBus>,
+    /// Nesting level of the agent that owns this tool (0 = main agent).
+    depth: u32,
+    /// Where sub-agent sessions are saved; the default sessions directory
+    /// when `None`.
+    sessions_dir: Option<PathBuf>,
+}
+
+impl SpawnAgentTool {
+    /// Create the tool for the main agent.
+    pub fn new(config: Config, provider: Arc<dyn LLMProvider>, bus: Arc<MessageBus>) -> Self {
+        Self {
+            config,
+            provider,
+            bus,
+            depth: 0,
+            sessions_dir: None,
+        }
+    }
+
+    /// Save sub-agent sessions under `dir` instead of the default sessions
+    /// directory.
+    pub fn with_sessions_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.sessions_dir = Some(dir.into());
+        self
+    }
+
+    fn session_manager(&self) -> SessionManager {
+        let manager = match &self.sessions_dir {
+            Some(dir) => SessionManager::with_path(dir.clone()),
+            None => SessionManager::new(),
+        };
+        manager.unwrap_or_else(|e| {
+            warn!(error = %e, "Sub-agent session store unavailable, transcript will not be kept");
+            SessionManager::new_memory()
+        })
+    }
+
+    /// The tool handed to a sub-agent, one level deeper, if nesting allows.
+    fn nested(&self) -> Option<Self> {
+        (self.depth + 1 < self.config.swarm.max_depth).then(|| Self {
+            config: self.config.clone(),
+            provider: Arc::clone(&self.provider),
+            bus: Arc::clone(&self.bus),
+            depth: self.depth + 1,
+            sessions_dir: self.sessions_dir.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl Tool for SpawnAgentTool {
+    fn name(&self) -> &str {
+        "spawn_agent"
+    }
+
+    fn description(&self) -> &str {
+        "Hand a large, self-contained task to a sub-agent with a fresh context and wait for it. \
+         Returns the sub-agent's final summary and a log of the tools it used. \
+         Describe the task fully: the sub-agent cannot see this conversation."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Run sub-agent"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Shell
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "task": {
+                    "type": "string",
+                    "description": "Complete description of the task, including any context the sub-agent needs"
+                },
+                "tools": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Optional tool allowlist for the sub-agent (default: the standard sub-agent tools)"
+                },
+                "max_turns": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Optional cap on the sub-agent's tool-calling rounds (default and maximum: this agent's own limit)"
+                }
+            },
+            "required": ["task"]
+        })
+    }
+
+    async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
+        if self.depth >= self.config.swarm.max_depth {
+            return Err(ZeptoError::Tool(format!(
+                "Cannot spawn a sub-agent at depth {} (swarm.max_depth is {})",
+                self.depth + 1,
+                self.config.swarm.max_depth
+            )));
+        }
+
+        let task = args
+            .get("task")
+            .and_then(Value::as_str)
+            .filter(|t| !t.trim().is_empty())
+            .ok_or_else(|| ZeptoError::Tool("Missing required 'task' argument".into()))?;
+        let allowlist: Option<Vec<String>> =
+            args.get("tools").and_then(Value::as_array).map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            });
+        let parent_limit = self.config.agents.defaults.max_tool_iterations;
+        let max_turns = args
+            .get("max_turns")
+            .and_then(Value::as_u64)
+            .map_or(parent_limit, |n| (n.max(1) as u32).min(parent_limit));
+
+        let mut config = self.config.clone();
+        config.agents.defaults.max_tool_iterations = max_turns;
+
+        let sub_agent = AgentLoop::with_context_builder(
+            config,
+            self.session_manager(),
+            Arc::new(MessageBus::new()),
+            ContextBuilder::new().with_system_prompt(SUB_AGENT_PROMPT),
+        );
+        sub_agent
+            .set_provider(Box::new(ProviderRef(Arc::clone(&self.provider))))
+            .await;
+        for tool in sub_agent_tools(&self.config, &self.bus, allowlist.as_deref()) {
+            sub_agent.register_tool(tool).await;
+        }
+        if let Some(nested) = self.nested() {
+            let allowed = allowlist
+                .as_ref()
+                .is_none_or(|names| names.iter().any(|n| n == "spawn_agent"));
+            if allowed {
+                sub_agent.register_tool(Box::new(nested)).await;
+            }
+        }
+
+        let id: String = uuid::Uuid::new_v4().to_string().chars().take(8).collect();
+        let inbound = InboundMessage::new("spawn_agent", "spawn_agent", &id, task);
+        let session_key = inbound.session_key.clone();
+        info!(
+            session = %session_key,
+            depth = self.depth + 1,
+            max_turns,
+            "Spawning sub-agent"
+        );
+
+        let answer = sub_agent.process_message(&inbound).await.map_err(|e| {
+            warn!(session = %session_key, error = %e, "Sub-agent failed");
+            ZeptoError::Tool(format!(
+                "Sub-agent failed: {} (transcript: {})",
+                e, session_key
+            ))
+        })?;
+
+        let messages = sub_agent
+            .session_manager()
+            .get(&session_key)
+            .await
+            .ok()
+            .flatten()
+            .map(|session| session.messages)
+            .unwrap_or_default();
+        info!(session = %session_key, "Sub-agent completed");
+
+        Ok(ToolOutput::llm_only(format!(
+            "{}\n\n{}\n\nTranscript: {}",
+            answer.trim(),
+            action_log(&messages),
+            session_key
+        )))
+    }
+}
+
+/// One line per tool call the sub-agent made, e.g.
+/// `- read_file {"path":"src/lib.rs"}`, flagging calls whose result was an
+/// error.
+fn action_log(messages: &[Message]) -> String {
+    let failed: std::collections::HashSet<&str> = messages
+        .iter()
+        .filter(|m| m.role == Role::Tool && m.content.starts_with("Error"))
+        .filter_map(|m| m.tool_call_id.as_deref())
+        .collect();
+    let calls: Vec<_> = messages
+        .iter()
+        .filter(|m| m.role == Role::Assistant)
+        .flat_map(|m| m.tool_calls.iter().flatten())
+        .collect();
+    if calls.is_empty() {
+        return "Actions: none".to_string();
+    }
+
+    let mut log = format!("Actions ({}):", calls.len());
+    for call in calls.iter().take(ACTION_LOG_MAX_ENTRIES) {
+        let args = crate::utils::string::preview(&call.arguments, ACTION_LOG_ARGS_MAX_CHARS);
+        let status = if failed.contains(call.id.as_str()) {
+            " [error]"
+        } else {
+            ""
+        };
+        log.push_str(&format!("\n- {} {}{}", call.name, args, status));
+    }
+    if calls.len() > ACTION_LOG_MAX_ENTRIES {
+        log.push_str(&format!(
+            "\n- … and {} more",
+            calls.len() - ACTION_LOG_MAX_ENTRIES
+        ));
+    }
+    log
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{ChatOptions, LLMResponse, LLMToolCall, ToolDefinition};
+    use crate::session::ToolCall;
+    use std::sync::Mutex;
+
+    /// Calls `echo` once, then answers with the tools it was offered.
+    struct EchoThenAnswer {
+        calls: Mutex<u32>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for EchoThenAnswer {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        fn default_model(&self) -> &str {
+            "test-model"
+        }
+
+        async fn chat(
+            &self,
+            _messages: Vec<Message>,
+            tools: Vec<ToolDefinition>,
+            _model: Option<&str>,
+            _options: ChatOptions,
+        ) -> Result<crate::providers::LLMResponse> {
+            let mut calls = self.calls.lock().unwrap();
+            *calls += 1;
+            if *calls == 1 {
+                return Ok(LLMResponse::with_tools(
+                    "",
+                    vec![LLMToolCall::new("c1", "echo", r#"{"message":"hi"}"#)],
+                ));
+            }
+            let mut names: Vec<_> = tools.iter().map(|t| t.name.clone()).collect();
+            names.sort();
+            Ok(LLMResponse::text(&format!("done with {}", names.join(","))))
+        }
+    }
+
+    fn tool(max_depth: u32, dir: &std::path::Path) -> SpawnAgentTool {
+        let mut config = Config::default();
+        config.swarm.max_depth = max_depth;
+        SpawnAgentTool::new(
+            config,
+            Arc::new(EchoThenAnswer {
+                calls: Mutex::new(0),
+            }),
+            Arc::new(MessageBus::new()),
+        )
+        .with_sessions_dir(dir)
+    }
+
+    #[tokio::test]
+    async fn test_spawn_agent_returns_summary_log_and_transcript_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = tool(1, dir.path())
+            .execute(
+                json!({"task": "say hi", "tools": ["echo", "read_file"]}),
+                &ToolContext::new(),
+            )
+            .await
+            .unwrap();
+
+        // Restricted tool set, and no nested spawn_agent at max_depth 1.
+        assert!(output.for_llm.starts_with("done with echo,read_file\n"));
+        assert!(output
+            .for_llm
+            .contains("Actions (1):\n- echo {\"message\":\"hi\"}"));
+
+        let key = output.for_llm.rsplit("Transcript: ").next().unwrap();
+        assert!(key.starts_with("spawn_agent:"));
+        let session = SessionManager::with_path(dir.path().to_path_buf())
+            .unwrap()
+            .get(key)
+            .await
+            .unwrap()
+            .expect("sub-agent transcript saved");
+        assert_eq!(session.messages[0].content, "say hi");
+    }
+
+    #[tokio::test]
+    async fn test_spawn_agent_nests_below_max_depth() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = tool(2, dir.path())
+            .execute(
+                json!({"task": "t", "tools": ["echo", "spawn_agent"]}),
+                &ToolContext::new(),
+            )
+            .await
+            .unwrap();
+        assert!(output.for_llm.starts_with("done with echo,spawn_agent\n"));
+        assert!(tool(2, dir.path()).nested().unwrap().nested().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_spawn_agent_depth_and_args_checked() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = tool(0, dir.path())
+            .execute(json!({"task": "t"}), &ToolContext::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("swarm.max_depth"));
+
+        let err = tool(1, dir.path())
+            .execute(json!({"task": "  "}), &ToolContext::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("task"));
+    }
+
+    #[test]
+    fn test_action_log() {
+        let call = Message::assistant_with_tools(
+            "",
+            vec![
+                ToolCall::new("a", "read_file", r#"{"path":"x"}"#),
+                ToolCall::new("b", "shell", r#"{"command":"false"}"#),
+            ],
+        );
+        let messages = vec![
+            Message::user("task"),
+            call,
+            Message::tool_result("a", "contents"),
+            Message::tool_result("b", "Error: exit 1"),
+        ];
+        assert_eq!(
+            action_log(&messages),
+            "Actions (2):\n- read_file {\"path\":\"x\"}\n- shell {\"command\":\"false\"} [error]"
+        );
+        assert_eq!(action_log(&[Message::user("t")]), "Actions: none");
+    }
+}
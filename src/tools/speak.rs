@@ -0,0 +1,325 @@
+//! Speak tool — turn text into an audio file in the workspace.
+//!
+//! Uses an OpenAI-compatible `/audio/speech` endpoint. The default `opus`
+//! format produces an OGG file that chat platforms accept as a voice
+//! message; `mp3` is available for everything else.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::config::SpeakConfig;
+use crate::error::{Result, ZeptoError};
+use crate::security::{ensure_directory_chain_secure, Sandbox};
+
+use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Request timeout for the speech endpoint.
+const SPEAK_TIMEOUT_SECS: u64 = 60;
+
+/// Tool that synthesizes speech from text.
+///
+/// # Parameters
+/// - `text`: what to say (required)
+/// - `dest_path`: workspace path to save to (optional; defaults to
+///   `voice/speech-<timestamp>.<ext>`)
+/// - `voice`: voice name overriding `tools.speak.voice` (optional)
+pub struct SpeakTool {
+    config: SpeakConfig,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+impl SpeakTool {
+    /// Create a speak tool. Fails when no API key is configured or the
+    /// output format is not supported.
+    pub fn new(config: SpeakConfig) -> Result<Self> {
+        let api_key = config
+            .api_key
+            .clone()
+            .filter(|k| !k.trim().is_empty())
+            .ok_or_else(|| {
+                ZeptoError::Config(
+                    "tools.speak.api_key is required (or set ZEPTOCLAW_TOOLS_SPEAK_API_KEY)".into(),
+                )
+            })?;
+        extension_for(&config.format)?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(SPEAK_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| ZeptoError::Tool(format!("Failed to build HTTP client: {}", e)))?;
+        Ok(Self {
+            config,
+            api_key,
+            client,
+        })
+    }
+
+    /// Resolve the destination through `sandbox`.
+    fn resolve_dest(&self, dest_path: Option<&str>, sandbox: &Sandbox) -> Result<PathBuf> {
+        let relative = match dest_path.map(str::trim).filter(|p| !p.is_empty()) {
+            Some(path) => path.to_string(),
+            None => {
+                let millis = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis())
+                    .unwrap_or_default();
+                format!(
+                    "voice/speech-{}.{}",
+                    millis,
+                    extension_for(&self.config.format)?
+                )
+            }
+        };
+        sandbox.resolve_for_write(&relative)
+    }
+
+    async fn synthesize(&self, text: &str, voice: &str) -> Result<Vec<u8>> {
+        let url = format!(
+            "{}/audio/speech",
+            self.config.api_base.trim_end_matches('/')
+        );
+        let response = self
+            .client
+            .post(&url)
+            .bearer_auth(&self.api_key)
+            .json(&json!({
+                "model": self.config.model,
+                "input": text,
+                "voice": voice,
+                "response_format": self.config.format,
+            }))
+            .send()
+            .await
+            .map_err(|e| ZeptoError::Tool(format!("Speech request failed: {}", e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ZeptoError::Tool(format!(
+                "Speech API error {}: {}",
+                status,
+                body.chars().take(300).collect::<String>()
+            )));
+        }
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| ZeptoError::Tool(format!("Failed to read speech audio: {}", e)))?;
+        Ok(bytes.to_vec())
+    }
+}
+
+/// File extension for a configured output format.
+fn extension_for(format: &str) -> Result<&'static str> {
+    match format {
+        "opus" => Ok("ogg"),
+        "mp3" => Ok("mp3"),
+        other => Err(ZeptoError::Config(format!(
+            "Unsupported tools.speak.format '{}'. Expected one of: opus, mp3",
+            other
+        ))),
+    }
+}
+
+#[async_trait]
+impl Tool for SpeakTool {
+    fn name(&self) -> &str {
+        "speak"
+    }
+
+    fn description(&self) -> &str {
+        "Convert text to speech and save it as an audio file in the workspace (OGG/Opus by \
+         default, suitable for voice messages). Returns the saved path."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Text to speech audio file"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::NetworkWrite
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "text": {
+                    "type": "string",
+                    "description": "Text to speak"
+                },
+                "dest_path": {
+                    "type": "string",
+                    "description": "Workspace path to save the audio to (default: voice/speech-<timestamp>.ogg)"
+                },
+                "voice": {
+                    "type": "string",
+                    "description": "Voice name, e.g. alloy, echo, nova (default from config)"
+                }
+            },
+            "required": ["text"]
+        })
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let text = args
+            .get("text")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| ZeptoError::Tool("Missing 'text' parameter".into()))?;
+        let chars = text.chars().count();
+        if chars > self.config.max_chars {
+            return Err(ZeptoError::Tool(format!(
+                "Text is {} characters; the limit is {} (tools.speak.max_chars). \
+                 Split it into shorter parts.",
+                chars, self.config.max_chars
+            )));
+        }
+        let voice = args
+            .get("voice")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .unwrap_or(&self.config.voice);
+        let sandbox = ctx.sandbox().ok_or_else(|| {
+            ZeptoError::SecurityViolation(
+                "Workspace not configured; speak requires a workspace".to_string(),
+            )
+        })?;
+        let workspace = sandbox.workspace();
+
+        let dest = self.resolve_dest(args.get("dest_path").and_then(Value::as_str), &sandbox)?;
+        let audio = self.synthesize(text, voice).await?;
+
+        let parent = dest
+            .parent()
+            .ok_or_else(|| ZeptoError::Tool("Destination has no parent directory".into()))?;
+        ensure_directory_chain_secure(parent, workspace)?;
+        sandbox.check_write(&dest)?;
+        tokio::fs::write(&dest, &audio).await?;
+
+        let shown = Path::new(workspace)
+            .canonicalize()
+            .ok()
+            .and_then(|ws| dest.strip_prefix(ws).ok().map(Path::to_path_buf))
+            .or_else(|| dest.strip_prefix(workspace).ok().map(Path::to_path_buf))
+            .unwrap_or_else(|| dest.clone());
+        Ok(ToolOutput::llm_only(format!(
+            "Saved speech to {} ({} bytes, voice {})",
+            shown.display(),
+            audio.len(),
+            voice
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve one canned audio response per connection on localhost.
+    async fn serve(body: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut buf = vec![0u8; 8192];
+                let _ = socket.read(&mut buf).await;
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: audio/ogg\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = socket.write_all(head.as_bytes()).await;
+                let _ = socket.write_all(body).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    fn config(api_base: &str) -> SpeakConfig {
+        SpeakConfig {
+            enabled: true,
+            api_key: Some("test-key".into()),
+            api_base: api_base.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn ctx(dir: &TempDir) -> ToolContext {
+        ToolContext::new().with_workspace(dir.path().to_str().unwrap())
+    }
+
+    #[test]
+    fn test_new_requires_api_key() {
+        let err = SpeakTool::new(SpeakConfig::default()).err().unwrap();
+        assert!(err.to_string().contains("tools.speak.api_key"));
+    }
+
+    #[test]
+    fn test_new_rejects_unknown_format() {
+        let err = SpeakTool::new(SpeakConfig {
+            format: "flac".into(),
+            ..config("http://localhost")
+        })
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("opus, mp3"));
+    }
+
+    #[tokio::test]
+    async fn test_speak_saves_audio() {
+        let base = serve(b"OggS-fake-audio").await;
+        let dir = TempDir::new().unwrap();
+        let out = SpeakTool::new(config(&base))
+            .unwrap()
+            .execute(
+                json!({"text": "hello", "dest_path": "out/hello.ogg"}),
+                &ctx(&dir),
+            )
+            .await
+            .unwrap();
+        assert!(out.for_llm.contains("out/hello.ogg"), "{}", out.for_llm);
+        assert_eq!(
+            std::fs::read(dir.path().join("out/hello.ogg")).unwrap(),
+            b"OggS-fake-audio"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_speak_rejects_long_text() {
+        let dir = TempDir::new().unwrap();
+        let tool = SpeakTool::new(SpeakConfig {
+            max_chars: 5,
+            ..config("http://127.0.0.1:9")
+        })
+        .unwrap();
+        let err = tool
+            .execute(json!({"text": "too long"}), &ctx(&dir))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("tools.speak.max_chars"));
+    }
+
+    #[tokio::test]
+    async fn test_speak_rejects_path_outside_workspace() {
+        let dir = TempDir::new().unwrap();
+        let tool = SpeakTool::new(config("http://127.0.0.1:9")).unwrap();
+        let result = tool
+            .execute(
+                json!({"text": "hi", "dest_path": "../escape.ogg"}),
+                &ctx(&dir),
+            )
+            .await;
+        assert!(result.is_err());
+    }
+}
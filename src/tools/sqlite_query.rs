@@ -0,0 +1,422 @@
+//! SQLite query tool (`tool-sqlite` feature).
+//!
+//! Runs one SQL statement against a database file inside the workspace and
+//! returns the result as a markdown table. Databases are opened read-only
+//! and only SELECT/PRAGMA statements are accepted unless
+//! `tools.sqlite.allow_write` is set. Every query runs under a row cap and a
+//! wall-clock limit enforced by SQLite's progress handler, so a runaway join
+//! is interrupted instead of blocking the agent.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, ErrorCode, OpenFlags};
+use serde_json::{json, Value};
+
+use crate::config::SqliteToolConfig;
+use crate::error::{Result, ZeptoError};
+use crate::security::Sandbox;
+
+use super::{Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// SQLite VM instructions between deadline checks.
+const PROGRESS_INTERVAL_OPS: i32 = 1_000;
+
+/// Leading keywords accepted when writes are not allowed.
+const READ_KEYWORDS: [&str; 3] = ["select", "with", "pragma"];
+
+/// Query a SQLite database in the workspace.
+pub struct SqliteQueryTool {
+    workspace: String,
+    config: SqliteToolConfig,
+}
+
+impl SqliteQueryTool {
+    /// Create a new `SqliteQueryTool` bound to `workspace`.
+    pub fn new(workspace: String, config: SqliteToolConfig) -> Self {
+        Self { workspace, config }
+    }
+
+    /// Resolve `path` to an existing database file inside the workspace.
+    /// A database a query `writes` to must not be read-only.
+    fn resolve_path(&self, path: &str, sandbox: &Sandbox, writes: bool) -> Result<PathBuf> {
+        let safe = if writes {
+            sandbox.resolve_for_write(path)?
+        } else {
+            sandbox.resolve(path)?
+        };
+        // TOCTOU: re-validate immediately before I/O
+        sandbox.check(&safe)?;
+        if !safe.is_file() {
+            return Err(ZeptoError::Tool(format!("Database not found: {path}")));
+        }
+        Ok(safe)
+    }
+}
+
+/// First keyword of `query`, lowercased, skipping leading whitespace,
+/// comments and parentheses.
+fn leading_keyword(query: &str) -> String {
+    let mut rest = query;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '(');
+        if let Some(line) = rest.strip_prefix("--") {
+            rest = line.split_once('\n').map_or("", |(_, tail)| tail);
+        } else if let Some(block) = rest.strip_prefix("/*") {
+            rest = block.split_once("*/").map_or("", |(_, tail)| tail);
+        } else {
+            break;
+        }
+    }
+    rest.chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Render a cell for a markdown table: pipes escaped, newlines flattened and
+/// at most `max_chars` characters kept.
+fn format_cell(value: ValueRef<'_>, max_chars: usize) -> String {
+    let text = match value {
+        ValueRef::Null => return "NULL".to_string(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(f) => f.to_string(),
+        ValueRef::Text(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+        ValueRef::Blob(bytes) => return format!("<blob {} bytes>", bytes.len()),
+    };
+    let mut cell: String = text
+        .chars()
+        .take(max_chars)
+        .map(|c| if c == '\n' || c == '\r' { ' ' } else { c })
+        .collect();
+    if text.chars().nth(max_chars).is_some() {
+        cell.push('…');
+    }
+    cell.replace('|', "\\|")
+}
+
+/// Run `query` against the database at `path` and format the result.
+fn run_query(
+    path: &Path,
+    query: &str,
+    max_rows: usize,
+    config: &SqliteToolConfig,
+) -> Result<String> {
+    let flags = if config.allow_write {
+        OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX
+    } else {
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX
+    };
+    let conn = Connection::open_with_flags(path, flags)
+        .map_err(|e| ZeptoError::Tool(format!("Failed to open database: {e}")))?;
+
+    let timeout = Duration::from_secs(config.timeout_secs.max(1));
+    let deadline = Instant::now() + timeout;
+    conn.busy_timeout(timeout)
+        .map_err(|e| ZeptoError::Tool(format!("Failed to configure database: {e}")))?;
+    conn.progress_handler(
+        PROGRESS_INTERVAL_OPS,
+        Some(move || Instant::now() >= deadline),
+    );
+
+    let query_error = |e: rusqlite::Error| match e.sqlite_error_code() {
+        Some(ErrorCode::OperationInterrupted) => {
+            ZeptoError::Tool(format!("Query timed out after {}s", timeout.as_secs()))
+        }
+        Some(ErrorCode::ReadOnly) => {
+            ZeptoError::Tool("Database is opened read-only; writes are disabled".to_string())
+        }
+        _ => ZeptoError::Tool(format!("Query failed: {e}")),
+    };
+
+    let mut stmt = conn.prepare(query).map_err(query_error)?;
+    if !config.allow_write && !stmt.readonly() {
+        return Err(ZeptoError::Tool(
+            "Only read-only statements are allowed (set tools.sqlite.allow_write to enable writes)"
+                .to_string(),
+        ));
+    }
+
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+    if columns.is_empty() {
+        let changed = stmt.execute([]).map_err(query_error)?;
+        return Ok(format!("OK ({changed} rows affected)"));
+    }
+
+    let mut lines = vec![
+        format!("| {} |", columns.join(" | ")),
+        format!("|{}", "---|".repeat(columns.len())),
+    ];
+    let mut rows = stmt.query([]).map_err(query_error)?;
+    let mut count = 0;
+    let mut truncated = false;
+    while let Some(row) = rows.next().map_err(query_error)? {
+        if count == max_rows {
+            truncated = true;
+            break;
+        }
+        let cells: Vec<String> = (0..columns.len())
+            .map(|i| {
+                row.get_ref(i)
+                    .map(|v| format_cell(v, config.max_cell_chars))
+                    .unwrap_or_default()
+            })
+            .collect();
+        lines.push(format!("| {} |", cells.join(" | ")));
+        count += 1;
+    }
+
+    if count == 0 {
+        return Ok(format!("No rows (columns: {})", columns.join(", ")));
+    }
+    if truncated {
+        lines.push(format!("\n[showing first {count} rows; more rows omitted]"));
+    } else {
+        lines.push(format!("\n({count} rows)"));
+    }
+    Ok(lines.join("\n"))
+}
+
+#[async_trait]
+impl Tool for SqliteQueryTool {
+    fn name(&self) -> &str {
+        "sqlite_query"
+    }
+
+    fn description(&self) -> &str {
+        "Run a SQL query against a SQLite database file in the workspace and return the rows \
+         as a markdown table. Only SELECT and PRAGMA statements are allowed unless writes are \
+         enabled in config. One statement per call."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Query a workspace SQLite database"
+    }
+
+    fn category(&self) -> ToolCategory {
+        if self.config.allow_write {
+            ToolCategory::FilesystemWrite
+        } else {
+            ToolCategory::FilesystemRead
+        }
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["path", "query"],
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Relative path to the database file within the workspace"
+                },
+                "query": {
+                    "type": "string",
+                    "description": "A single SQL statement"
+                },
+                "max_rows": {
+                    "type": "integer",
+                    "description": format!("Maximum rows to return (default and max: {})", self.config.max_rows)
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let path = args
+            .get("path")
+            .and_then(Value::as_str)
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| ZeptoError::Tool("Missing required argument: path".to_string()))?;
+        let query = args
+            .get("query")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .ok_or_else(|| ZeptoError::Tool("Missing required argument: query".to_string()))?
+            .to_string();
+        let max_rows = args
+            .get("max_rows")
+            .and_then(Value::as_u64)
+            .map_or(self.config.max_rows, |n| {
+                (n as usize).min(self.config.max_rows)
+            });
+
+        let writes = !READ_KEYWORDS.contains(&leading_keyword(&query).as_str());
+        if !self.config.allow_write && writes {
+            return Err(ZeptoError::Tool(
+                "Only SELECT and PRAGMA statements are allowed (set tools.sqlite.allow_write to enable writes)"
+                    .to_string(),
+            ));
+        }
+
+        let resolved = self.resolve_path(
+            &ctx.resolve_input_path(path)?,
+            &ctx.sandbox_or(&self.workspace),
+            writes,
+        )?;
+        let config = self.config.clone();
+        let output =
+            tokio::task::spawn_blocking(move || run_query(&resolved, &query, max_rows, &config))
+                .await
+                .map_err(|e| ZeptoError::Tool(format!("Task panicked: {e}")))??;
+        Ok(ToolOutput::llm_only(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup() -> TempDir {
+        let tmp = TempDir::new().unwrap();
+        let conn = Connection::open(tmp.path().join("shop.db")).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT, note TEXT);
+             INSERT INTO items (name, note) VALUES ('apple', 'red|green'), ('pear', NULL);
+             CREATE TABLE n (x INTEGER);
+             WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 1000)
+             INSERT INTO n SELECT x FROM c;",
+        )
+        .unwrap();
+        tmp
+    }
+
+    fn tool(tmp: &TempDir, config: SqliteToolConfig) -> SqliteQueryTool {
+        SqliteQueryTool::new(tmp.path().to_string_lossy().into_owned(), config)
+    }
+
+    async fn query(tool: &SqliteQueryTool, sql: &str) -> Result<String> {
+        tool.execute(
+            json!({"path": "shop.db", "query": sql}),
+            &ToolContext::new(),
+        )
+        .await
+        .map(|o| o.for_llm)
+    }
+
+    #[test]
+    fn test_leading_keyword() {
+        assert_eq!(leading_keyword("  SELECT 1"), "select");
+        assert_eq!(leading_keyword("-- note\n/* x */ (select 1)"), "select");
+        assert_eq!(leading_keyword("Pragma table_info(items)"), "pragma");
+        assert_eq!(leading_keyword("DELETE FROM items"), "delete");
+    }
+
+    #[test]
+    fn test_format_cell_truncates_and_escapes() {
+        assert_eq!(format_cell(ValueRef::Null, 5), "NULL");
+        assert_eq!(format_cell(ValueRef::Text(b"a|b\nc"), 10), "a\\|b c");
+        assert_eq!(
+            format_cell(ValueRef::Text("héllo wörld".as_bytes()), 5),
+            "héllo…"
+        );
+        assert_eq!(format_cell(ValueRef::Blob(&[1, 2, 3]), 5), "<blob 3 bytes>");
+    }
+
+    #[tokio::test]
+    async fn test_select_returns_markdown_table() {
+        let tmp = setup();
+        let t = tool(&tmp, SqliteToolConfig::default());
+        let out = query(&t, "SELECT name, note FROM items ORDER BY id")
+            .await
+            .unwrap();
+        assert_eq!(
+            out,
+            "| name | note |\n|---|---|\n| apple | red\\|green |\n| pear | NULL |\n\n(2 rows)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_row_cap() {
+        let tmp = setup();
+        let t = tool(&tmp, SqliteToolConfig::default());
+        let out = t
+            .execute(
+                json!({"path": "shop.db", "query": "SELECT x FROM n", "max_rows": 3}),
+                &ToolContext::new(),
+            )
+            .await
+            .unwrap()
+            .for_llm;
+        assert!(out.contains("| 3 |"));
+        assert!(!out.contains("| 4 |"));
+        assert!(out.contains("showing first 3 rows"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_writes_by_default() {
+        let tmp = setup();
+        let t = tool(&tmp, SqliteToolConfig::default());
+        let err = query(&t, "DELETE FROM items").await.unwrap_err();
+        assert!(err.to_string().contains("Only SELECT and PRAGMA"), "{err}");
+        // A write hidden behind a CTE is caught by the statement check.
+        let err = query(&t, "WITH x AS (SELECT 1) DELETE FROM items")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("read-only"), "{err}");
+        assert!(query(&t, "SELECT count(*) FROM items")
+            .await
+            .unwrap()
+            .contains("| 2 |"));
+    }
+
+    #[tokio::test]
+    async fn test_allow_write() {
+        let tmp = setup();
+        let t = tool(
+            &tmp,
+            SqliteToolConfig {
+                allow_write: true,
+                ..Default::default()
+            },
+        );
+        let out = query(&t, "DELETE FROM items WHERE name = 'pear'")
+            .await
+            .unwrap();
+        assert_eq!(out, "OK (1 rows affected)");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_multiple_statements() {
+        let tmp = setup();
+        let t = tool(&tmp, SqliteToolConfig::default());
+        assert!(query(&t, "SELECT 1; DROP TABLE items").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_interrupts_query() {
+        let tmp = setup();
+        let t = tool(
+            &tmp,
+            SqliteToolConfig {
+                timeout_secs: 1,
+                ..Default::default()
+            },
+        );
+        let err = query(
+            &t,
+            "SELECT count(*) FROM n a, n b, n c, n d WHERE a.x + b.x + c.x + d.x = -1",
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("timed out after 1s"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_path_outside_workspace() {
+        let tmp = setup();
+        let t = tool(&tmp, SqliteToolConfig::default());
+        let err = t
+            .execute(
+                json!({"path": "../other.db", "query": "SELECT 1"}),
+                &ToolContext::new(),
+            )
+            .await
+            .unwrap_err();
+        assert!(!err.to_string().is_empty());
+    }
+}
@@ -29,7 +29,7 @@ use serde_json::{json, Value};
 
 use crate::api::tasks::TaskStore;
 use crate::error::Result;
-use crate::tools::{Tool, ToolCategory, ToolContext, ToolOutput};
+use crate::tools::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
 
 /// Agent tool for managing kanban board tasks.
 ///
@@ -104,6 +104,15 @@ impl Tool for TaskTool {
         })
     }
 
+    async fn dry_run(&self, args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        let field = |name: &str| args.get(name).and_then(Value::as_str).unwrap_or_default();
+        Ok(match field("action") {
+            "list" => DryRun::Execute,
+            "create" => DryRun::Describe(format!("would create task '{}'", field("title"))),
+            action => DryRun::Describe(format!("would {} task {}", action, field("id"))),
+        })
+    }
+
     async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
         let action = args.get("action").and_then(|v| v.as_str()).unwrap_or("");
 
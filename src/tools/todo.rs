@@ -0,0 +1,279 @@
+//! Todo tool — an explicit plan the agent keeps for multi-step tasks.
+//!
+//! The plan is stored on the [`Session`](crate::session::Session), persists
+//! with it, and is rendered into the system prompt on every turn so the model
+//! stays oriented across tool calls. Users see it with `/plan`.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::error::{Result, ZeptoError};
+use crate::session::{PlanItem, SessionManager};
+
+use super::{DryRun, Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Most items a plan may hold.
+const MAX_PLAN_ITEMS: usize = 50;
+
+/// Tool for setting, extending, checking off, and clearing the session plan.
+pub struct TodoTool {
+    sessions: Arc<SessionManager>,
+}
+
+impl TodoTool {
+    /// Create a todo tool storing plans in `sessions`.
+    pub fn new(sessions: Arc<SessionManager>) -> Self {
+        Self { sessions }
+    }
+
+    /// Session key of the conversation the tool is running in.
+    fn session_key(ctx: &ToolContext) -> Result<String> {
+        match (ctx.channel.as_deref(), ctx.chat_id.as_deref()) {
+            (Some(channel), Some(chat_id)) => Ok(format!("{}:{}", channel, chat_id)),
+            _ => Err(ZeptoError::Tool(
+                "todo tool requires a channel conversation context".into(),
+            )),
+        }
+    }
+}
+
+/// Render a plan as a numbered checklist for chat and tool output.
+pub fn format_plan(plan: &[PlanItem]) -> String {
+    if plan.is_empty() {
+        return "No plan yet".to_string();
+    }
+    plan.iter()
+        .enumerate()
+        .map(|(i, item)| {
+            format!(
+                "{}. [{}] {}",
+                i + 1,
+                if item.done { "x" } else { " " },
+                item.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn too_many_items() -> ZeptoError {
+    ZeptoError::Tool(format!(
+        "A plan holds at most {} items; group smaller steps together",
+        MAX_PLAN_ITEMS
+    ))
+}
+
+#[async_trait]
+impl Tool for TodoTool {
+    fn name(&self) -> &str {
+        "todo"
+    }
+
+    fn description(&self) -> &str {
+        "Keep an explicit plan for multi-step tasks. set_plan replaces the plan with a list of \
+         steps, add appends a step, check marks step item_index (1-based) done, clear removes \
+         the plan. The current plan is shown to you on every turn."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Task plan checklist"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Memory
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["set_plan", "add", "check", "clear"]
+                },
+                "items": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Steps for set_plan, in order"
+                },
+                "item": {
+                    "type": "string",
+                    "description": "Step to append for add"
+                },
+                "item_index": {
+                    "type": "integer",
+                    "description": "1-based number of the step to check"
+                }
+            },
+            "required": ["operation"]
+        })
+    }
+
+    async fn dry_run(&self, args: &Value, _ctx: &ToolContext) -> Result<DryRun> {
+        let operation = args
+            .get("operation")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        Ok(DryRun::Describe(format!(
+            "would {} the plan",
+            operation.replace('_', " ")
+        )))
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let operation = args
+            .get("operation")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ZeptoError::Tool("Missing required field: operation".into()))?;
+        let key = Self::session_key(ctx)?;
+
+        let plan = match operation {
+            "set_plan" => {
+                let items: Vec<&str> = args
+                    .get("items")
+                    .and_then(Value::as_array)
+                    .ok_or_else(|| ZeptoError::Tool("Missing required field: items".into()))?
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect();
+                if items.len() > MAX_PLAN_ITEMS {
+                    return Err(too_many_items());
+                }
+                self.sessions
+                    .with_session_mut(&key, |s| {
+                        s.set_plan(&items);
+                        s.plan.clone()
+                    })
+                    .await?
+            }
+            "add" => {
+                let item = args
+                    .get("item")
+                    .and_then(Value::as_str)
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .ok_or_else(|| ZeptoError::Tool("Missing required field: item".into()))?;
+                self.sessions
+                    .with_session_mut(&key, |s| {
+                        if s.plan.len() >= MAX_PLAN_ITEMS {
+                            return Err(too_many_items());
+                        }
+                        s.plan.push(PlanItem {
+                            text: item.to_string(),
+                            done: false,
+                        });
+                        s.updated_at = chrono::Utc::now();
+                        Ok(s.plan.clone())
+                    })
+                    .await??
+            }
+            "check" => {
+                let index = args
+                    .get("item_index")
+                    .and_then(Value::as_u64)
+                    .ok_or_else(|| ZeptoError::Tool("Missing required field: item_index".into()))?
+                    as usize;
+                self.sessions
+                    .with_session_mut(&key, |s| {
+                        if s.check_plan_item(index).is_none() {
+                            return Err(ZeptoError::Tool(format!(
+                                "No step {}; the plan has {} step(s)",
+                                index,
+                                s.plan.len()
+                            )));
+                        }
+                        Ok(s.plan.clone())
+                    })
+                    .await??
+            }
+            "clear" => {
+                self.sessions
+                    .with_session_mut(&key, |s| s.set_plan::<&str>(&[]))
+                    .await?;
+                return Ok(ToolOutput::llm_only("Plan cleared"));
+            }
+            other => {
+                return Err(ZeptoError::Tool(format!(
+                    "Unknown operation '{}'. Use set_plan, add, check, or clear",
+                    other
+                )))
+            }
+        };
+        Ok(ToolOutput::llm_only(format_plan(&plan)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> ToolContext {
+        ToolContext::new().with_channel("telegram", "42")
+    }
+
+    #[tokio::test]
+    async fn test_set_add_check_clear() {
+        let sessions = Arc::new(SessionManager::new_memory());
+        let tool = TodoTool::new(Arc::clone(&sessions));
+
+        let out = tool
+            .execute(
+                json!({"operation": "set_plan", "items": ["fetch data", "write report"]}),
+                &ctx(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(out.for_llm, "1. [ ] fetch data\n2. [ ] write report");
+
+        tool.execute(json!({"operation": "add", "item": "send it"}), &ctx())
+            .await
+            .unwrap();
+        let out = tool
+            .execute(json!({"operation": "check", "item_index": 1}), &ctx())
+            .await
+            .unwrap();
+        assert_eq!(
+            out.for_llm,
+            "1. [x] fetch data\n2. [ ] write report\n3. [ ] send it"
+        );
+
+        let session = sessions.get("telegram:42").await.unwrap().unwrap();
+        assert_eq!(session.plan.len(), 3);
+        assert!(session.plan[0].done);
+
+        let out = tool
+            .execute(json!({"operation": "clear"}), &ctx())
+            .await
+            .unwrap();
+        assert_eq!(out.for_llm, "Plan cleared");
+        let session = sessions.get("telegram:42").await.unwrap().unwrap();
+        assert!(session.plan.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_out_of_range() {
+        let tool = TodoTool::new(Arc::new(SessionManager::new_memory()));
+        tool.execute(json!({"operation": "set_plan", "items": ["one"]}), &ctx())
+            .await
+            .unwrap();
+        for index in [0, 2] {
+            let err = tool
+                .execute(json!({"operation": "check", "item_index": index}), &ctx())
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("the plan has 1 step"), "{}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requires_conversation_context() {
+        let tool = TodoTool::new(Arc::new(SessionManager::new_memory()));
+        let err = tool
+            .execute(json!({"operation": "clear"}), &ToolContext::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("conversation context"));
+    }
+}
@@ -1,17 +1,23 @@
-//! Voice transcription tool using Groq Whisper API.
+//! Voice transcription tool.
+//!
+//! Sends audio to the backend configured in `tools.transcribe` (Groq or
+//! OpenAI Whisper APIs, or a local whisper.cpp binary) via
+//! [`SpeechToText`].
 
 use async_trait::async_trait;
 use serde_json::Value;
 use std::path::Path;
 
+use crate::config::TranscribeConfig;
 use crate::error::{Result, ZeptoError};
-use crate::security::{revalidate_path, validate_path_in_workspace};
 use crate::tools::{Tool, ToolContext, ToolOutput};
+use crate::transcription::{SpeechBackend, SpeechToText};
 
 /// Maximum file size accepted for transcription (25 MiB).
 const MAX_FILE_BYTES: u64 = 25 * 1024 * 1024;
 
-/// Supported audio extensions for the Groq Whisper API.
+/// Supported audio extensions (the Whisper API formats; whisper.cpp input is
+/// converted to WAV with ffmpeg).
 const SUPPORTED_EXTENSIONS: &[&str] = &["mp3", "mp4", "mpeg", "mpga", "m4a", "wav", "webm", "ogg"];
 
 /// Map a file extension to the correct MIME type for the Groq multipart upload.
@@ -27,29 +33,39 @@ fn mime_for_extension(ext: &str) -> &'static str {
 }
 
 pub struct TranscribeTool {
-    api_key: String,
-    model: String,
-    client: reqwest::Client,
+    stt: SpeechToText,
 }
 
 impl TranscribeTool {
-    /// Create a new `TranscribeTool`.
-    ///
-    /// Returns `Err` if the underlying HTTP client cannot be built.
+    /// Create a `TranscribeTool` using the Groq Whisper API.
     pub fn new(api_key: impl Into<String>, model: impl Into<String>) -> Result<Self> {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(60))
-            .build()
-            .map_err(|e| ZeptoError::Tool(format!("Failed to build HTTP client: {}", e)))?;
+        Self::from_config(&TranscribeConfig {
+            groq_api_key: Some(api_key.into()),
+            model: model.into(),
+            ..Default::default()
+        })
+    }
 
+    /// Create a `TranscribeTool` for the backend configured in `tools.transcribe`.
+    ///
+    /// Returns `Err` naming the missing setting if the backend is not usable.
+    pub fn from_config(config: &TranscribeConfig) -> Result<Self> {
         Ok(Self {
-            api_key: api_key.into(),
-            model: model.into(),
-            client,
+            stt: SpeechToText::from_config(config)?,
         })
     }
 
-    async fn transcribe_file(&self, path: &str) -> Result<String> {
+    /// Short backend label for logs.
+    pub fn backend_label(&self) -> String {
+        match self.stt.backend() {
+            SpeechBackend::Api {
+                api_base, model, ..
+            } => format!("{} at {}", model, api_base),
+            SpeechBackend::WhisperCpp { binary, .. } => format!("whisper.cpp ({})", binary),
+        }
+    }
+
+    async fn transcribe_file(&self, path: &str, language: Option<&str>) -> Result<String> {
         // --- File size guard ---
         let metadata = tokio::fs::metadata(path)
             .await
@@ -69,9 +85,11 @@ impl TranscribeTool {
 
         if !SUPPORTED_EXTENSIONS.contains(&ext.as_str()) {
             return Err(ZeptoError::Tool(format!(
-                "Unsupported audio format '{}'. Supported: {}",
+                "Unsupported audio format '{}'. Supported: {}. \
+                 Convert it first, e.g. `ffmpeg -i {} output.mp3`",
                 ext,
-                SUPPORTED_EXTENSIONS.join(", ")
+                SUPPORTED_EXTENSIONS.join(", "),
+                path
             )));
         }
 
@@ -87,39 +105,9 @@ impl TranscribeTool {
             .unwrap_or("audio.ogg")
             .to_string();
 
-        let part = reqwest::multipart::Part::bytes(file_bytes)
-            .file_name(filename)
-            .mime_str(mime)
-            .map_err(|e| ZeptoError::Tool(e.to_string()))?;
-
-        let form = reqwest::multipart::Form::new()
-            .part("file", part)
-            .text("model", self.model.clone());
-
-        let resp = self
-            .client
-            .post("https://api.groq.com/openai/v1/audio/transcriptions")
-            .bearer_auth(&self.api_key)
-            .multipart(form)
-            .send()
+        self.stt
+            .transcribe(file_bytes, &filename, mime, language)
             .await
-            .map_err(|e| ZeptoError::Tool(e.to_string()))?;
-
-        if !resp.status().is_success() {
-            let status = resp.status().as_u16();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(ZeptoError::Tool(format!(
-                "Groq transcription failed ({}): {}",
-                status, body
-            )));
-        }
-
-        let json: Value = resp
-            .json()
-            .await
-            .map_err(|e| ZeptoError::Tool(e.to_string()))?;
-
-        Ok(json["text"].as_str().unwrap_or("").to_string())
     }
 }
 
@@ -130,13 +118,13 @@ impl Tool for TranscribeTool {
     }
 
     fn description(&self) -> &str {
-        "Transcribe a voice or audio file to text using Groq Whisper. \
-         Provide the local file path to the audio file. \
+        "Transcribe a voice or audio file to text using Whisper. \
+         Provide the local file path to the audio file and optionally a language hint. \
          Supported formats: mp3, mp4, mpeg, mpga, m4a, wav, webm, ogg."
     }
 
     fn compact_description(&self) -> &str {
-        "Transcribe an audio file to text via Groq Whisper."
+        "Transcribe an audio file to text via Whisper."
     }
 
     fn parameters(&self) -> Value {
@@ -146,6 +134,10 @@ impl Tool for TranscribeTool {
                 "file_path": {
                     "type": "string",
                     "description": "Absolute or workspace-relative path to the audio file"
+                },
+                "language": {
+                    "type": "string",
+                    "description": "ISO-639-1 language code such as 'en' (default: configured hint or auto-detect)"
                 }
             },
             "required": ["file_path"]
@@ -157,15 +149,18 @@ impl Tool for TranscribeTool {
             Some(p) => p,
             None => return Ok(ToolOutput::error("file_path is required")),
         };
+        let file_path = ctx.resolve_input_path(file_path)?;
+        let file_path = file_path.as_ref();
 
         let input_path = Path::new(file_path);
 
         // --- Path validation ---
+        let sandbox = ctx.sandbox();
         let resolved: String = if input_path.is_absolute() {
             // Absolute path: when a workspace is set, it must reside within it.
-            if let Some(ws) = &ctx.workspace {
-                match validate_path_in_workspace(file_path, ws) {
-                    Ok(safe) => safe.as_path().to_string_lossy().to_string(),
+            if let Some(sandbox) = &sandbox {
+                match sandbox.resolve(file_path) {
+                    Ok(safe) => safe.to_string_lossy().to_string(),
                     Err(_) => {
                         return Ok(ToolOutput::error("Path is outside the workspace boundary"))
                     }
@@ -185,10 +180,10 @@ impl Tool for TranscribeTool {
                 ));
             }
 
-            if let Some(ws) = &ctx.workspace {
-                // Workspace present: validate through the security module.
-                match validate_path_in_workspace(file_path, ws) {
-                    Ok(safe) => safe.as_path().to_string_lossy().to_string(),
+            if let Some(sandbox) = &sandbox {
+                // Workspace present: resolve through the sandbox.
+                match sandbox.resolve(file_path) {
+                    Ok(safe) => safe.to_string_lossy().to_string(),
                     Err(_) => {
                         return Ok(ToolOutput::error("Path is outside the workspace boundary"))
                     }
@@ -200,8 +195,8 @@ impl Tool for TranscribeTool {
         };
 
         // TOCTOU: re-validate immediately before I/O
-        if let Some(ws) = &ctx.workspace {
-            if let Err(e) = revalidate_path(Path::new(&resolved), ws) {
+        if let Some(sandbox) = &sandbox {
+            if let Err(e) = sandbox.check(Path::new(&resolved)) {
                 return Ok(ToolOutput::error(format!(
                     "Path re-validation failed: {}",
                     e
@@ -209,7 +204,8 @@ impl Tool for TranscribeTool {
             }
         }
 
-        match self.transcribe_file(&resolved).await {
+        let language = args["language"].as_str().filter(|l| !l.is_empty());
+        match self.transcribe_file(&resolved, language).await {
             Ok(text) if text.is_empty() => Ok(ToolOutput::llm_only(
                 "Transcription returned empty (no speech detected)",
             )),
@@ -236,7 +232,7 @@ mod tests {
     #[test]
     fn test_transcribe_tool_description() {
         let tool = make_tool();
-        assert!(tool.description().contains("Groq Whisper"));
+        assert!(tool.description().contains("Whisper"));
         assert!(tool.description().contains("ogg"));
     }
 
@@ -305,6 +301,22 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_from_config_requires_backend_settings() {
+        let config = TranscribeConfig {
+            backend: "whisper_cpp".to_string(),
+            whisper_cpp_model: Some("/models/ggml-base.bin".to_string()),
+            ..Default::default()
+        };
+        let tool = TranscribeTool::from_config(&config).unwrap();
+        assert_eq!(tool.backend_label(), "whisper.cpp (whisper-cli)");
+
+        let err = TranscribeTool::from_config(&TranscribeConfig::default())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("groq_api_key"), "{err}");
+    }
+
     #[test]
     fn test_mime_for_extension_mp3() {
         assert_eq!(mime_for_extension("mp3"), "audio/mpeg");
@@ -0,0 +1,450 @@
+//! Transcript formatter tool — render a stored session as a transcript.
+//!
+//! Formats: `plain_text`, `srt`, `vtt`, `html`, and `pdf`. The text and
+//! subtitle formats include only user and assistant messages with text;
+//! system prompts and raw tool traffic are omitted. `html` is the session's
+//! HTML export ([`crate::session::html`]) with system messages hidden and
+//! tool arguments redacted. PDF output renders that page through the
+//! `wkhtmltopdf` binary, which must be installed separately.
+//!
+//! Session messages do not carry individual timestamps, so each message's
+//! time is interpolated evenly between the session's `created_at` and
+//! `updated_at` (with at least one second per cue for subtitle formats).
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use tokio::io::AsyncWriteExt;
+
+use crate::config::Config;
+use crate::error::{Result, ZeptoError};
+use crate::session::{html, HtmlExportOptions, Role, Session, SessionManager};
+use crate::tools::{Tool, ToolCategory, ToolContext, ToolOutput};
+
+/// Maximum time allowed for a `wkhtmltopdf` run.
+const PDF_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// HTML export settings for `html` and `pdf` transcripts.
+const HTML_OPTIONS: HtmlExportOptions = HtmlExportOptions {
+    hide_system_messages: true,
+    redact_tool_arguments: true,
+};
+
+/// A transcript line: speaker, text, and interpolated offset from session start.
+struct Entry<'a> {
+    speaker: &'static str,
+    text: &'a str,
+    at: DateTime<Utc>,
+    offset_ms: i64,
+    end_ms: i64,
+}
+
+/// Collect user/assistant messages with interpolated timing.
+fn entries(session: &Session) -> Vec<Entry<'_>> {
+    let messages: Vec<_> = session
+        .messages
+        .iter()
+        .filter(|m| matches!(m.role, Role::User | Role::Assistant) && !m.content.trim().is_empty())
+        .collect();
+    let n = messages.len().max(1) as i64;
+    let span_ms = (session.updated_at - session.created_at)
+        .num_milliseconds()
+        .max(0);
+    // Subtitle cues shorter than a second are unreadable.
+    let step_ms = (span_ms / n).max(1_000);
+
+    messages
+        .into_iter()
+        .enumerate()
+        .map(|(i, m)| {
+            let offset_ms = step_ms * i as i64;
+            Entry {
+                speaker: if m.role == Role::User {
+                    "User"
+                } else {
+                    "Assistant"
+                },
+                text: m.content.trim(),
+                at: session.created_at + chrono::Duration::milliseconds(offset_ms),
+                offset_ms,
+                end_ms: offset_ms + step_ms,
+            }
+        })
+        .collect()
+}
+
+/// Format a millisecond offset as `HH:MM:SS<sep>mmm`.
+fn cue_time(ms: i64, sep: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        (ms / 60_000) % 60,
+        (ms / 1_000) % 60,
+        sep,
+        ms % 1_000
+    )
+}
+
+/// Render a session as plain text, one `Speaker: text` block per message.
+pub fn to_plain_text(session: &Session, include_timestamps: bool) -> String {
+    entries(session)
+        .iter()
+        .map(|e| {
+            if include_timestamps {
+                format!("[{}] {}: {}", e.at.to_rfc3339(), e.speaker, e.text)
+            } else {
+                format!("{}: {}", e.speaker, e.text)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Render a session as SubRip subtitles, numbered by message index.
+pub fn to_srt(session: &Session) -> String {
+    let mut out = String::new();
+    for (i, e) in entries(session).iter().enumerate() {
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}: {}\n\n",
+            i + 1,
+            cue_time(e.offset_ms, ','),
+            cue_time(e.end_ms, ','),
+            e.speaker,
+            e.text
+        ));
+    }
+    out
+}
+
+/// Render a session as WebVTT subtitles, numbered by message index.
+pub fn to_vtt(session: &Session) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for (i, e) in entries(session).iter().enumerate() {
+        // "-->" is not allowed in cue text.
+        let text = e.text.replace("-->", "->");
+        out.push_str(&format!(
+            "{}\n{} --> {}\n<v {}>{}\n\n",
+            i + 1,
+            cue_time(e.offset_ms, '.'),
+            cue_time(e.end_ms, '.'),
+            e.speaker,
+            text
+        ));
+    }
+    out
+}
+
+/// Tool that renders a stored session as a transcript.
+pub struct TranscriptFormatterTool {
+    sessions: Arc<SessionManager>,
+    wkhtmltopdf: String,
+}
+
+impl TranscriptFormatterTool {
+    /// Create a transcript tool reading from `sessions`.
+    pub fn new(sessions: Arc<SessionManager>) -> Self {
+        Self {
+            sessions,
+            wkhtmltopdf: "wkhtmltopdf".to_string(),
+        }
+    }
+
+    /// Override the `wkhtmltopdf` executable used for PDF output.
+    pub fn with_wkhtmltopdf(mut self, executable: impl Into<String>) -> Self {
+        self.wkhtmltopdf = executable.into();
+        self
+    }
+
+    /// Directory PDF transcripts are written to.
+    fn output_dir(ctx: &ToolContext) -> PathBuf {
+        ctx.workspace
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(Config::dir)
+            .join("transcripts")
+    }
+
+    /// Render `html` to a PDF file via `wkhtmltopdf`, returning its path.
+    async fn render_pdf(&self, html: &str, key: &str, ctx: &ToolContext) -> Result<PathBuf> {
+        let dir = Self::output_dir(ctx);
+        tokio::fs::create_dir_all(&dir).await?;
+        let file_name: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let path = dir.join(format!("{}.pdf", file_name));
+
+        let mut child = tokio::process::Command::new(&self.wkhtmltopdf)
+            .arg("--quiet")
+            .arg("-")
+            .arg(&path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    ZeptoError::Tool(format!(
+                        "'{}' not found. Install wkhtmltopdf to export PDF transcripts.",
+                        self.wkhtmltopdf
+                    ))
+                } else {
+                    ZeptoError::Tool(format!("Failed to run wkhtmltopdf: {}", e))
+                }
+            })?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(html.as_bytes()).await?;
+        }
+
+        let output = tokio::time::timeout(PDF_TIMEOUT, child.wait_with_output())
+            .await
+            .map_err(|_| {
+                ZeptoError::Tool(format!(
+                    "wkhtmltopdf timed out after {}s",
+                    PDF_TIMEOUT.as_secs()
+                ))
+            })?
+            .map_err(|e| ZeptoError::Tool(format!("Failed to run wkhtmltopdf: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(ZeptoError::Tool(format!(
+                "wkhtmltopdf failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(path)
+    }
+}
+
+#[async_trait]
+impl Tool for TranscriptFormatterTool {
+    fn name(&self) -> &str {
+        "transcript"
+    }
+
+    fn description(&self) -> &str {
+        "Render a stored conversation session as a transcript. Formats: plain_text, srt, vtt, \
+         html (styled chat layout with collapsible tool calls), or pdf (written to the \
+         transcripts folder; requires wkhtmltopdf). System messages are left out."
+    }
+
+    fn compact_description(&self) -> &str {
+        "Export session transcript"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::FilesystemWrite
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "session_key": {
+                    "type": "string",
+                    "description": "Session to export (e.g. 'telegram:12345'). Default: the current conversation; only admins may export others"
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["plain_text", "srt", "vtt", "html", "pdf"],
+                    "description": "Output format"
+                },
+                "include_timestamps": {
+                    "type": "boolean",
+                    "description": "Prefix messages with timestamps (plain_text only). Default: false"
+                }
+            },
+            "required": ["format"]
+        })
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
+        let key = args
+            .get("session_key")
+            .and_then(|v| v.as_str())
+            .or(ctx.session_key.as_deref())
+            .ok_or_else(|| ZeptoError::Tool("Missing required field: session_key".into()))?;
+        if !ctx.is_admin && ctx.session_key.as_deref() != Some(key) {
+            return Err(ZeptoError::Tool(
+                "Only admins can export other conversations.".into(),
+            ));
+        }
+        let format = args
+            .get("format")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ZeptoError::Tool("Missing required field: format".into()))?;
+        let include_timestamps = args
+            .get("include_timestamps")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let session = self
+            .sessions
+            .get(key)
+            .await?
+            .ok_or_else(|| ZeptoError::Tool(format!("Session '{}' not found", key)))?;
+
+        let rendered = match format {
+            "plain_text" => to_plain_text(&session, include_timestamps),
+            "srt" => to_srt(&session),
+            "vtt" => to_vtt(&session),
+            "html" => html::render(&session, &HTML_OPTIONS),
+            "pdf" => {
+                let html = html::render(&session, &HTML_OPTIONS);
+                let path = self.render_pdf(&html, key, ctx).await?;
+                return Ok(ToolOutput::llm_only(format!(
+                    "PDF transcript written to {}",
+                    path.display()
+                )));
+            }
+            other => {
+                return Err(ZeptoError::Tool(format!(
+                    "Unknown format '{}'. Expected one of: plain_text, srt, vtt, html, pdf",
+                    other
+                )))
+            }
+        };
+
+        Ok(ToolOutput::llm_only(rendered))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{Message, ToolCall};
+
+    fn sample_session() -> Session {
+        let mut session = Session::new("telegram:1");
+        session.add_message(Message::system("You are helpful"));
+        session.add_message(Message::user("Is 2 < 3?"));
+        session.add_message(Message::assistant_with_tools(
+            "",
+            vec![ToolCall::new("c1", "conditional", "{}")],
+        ));
+        session.add_message(Message::tool_result("c1", r#"{"result":true}"#));
+        session.add_message(Message::assistant("Yes --> it is."));
+        session.updated_at = session.created_at + chrono::Duration::seconds(10);
+        session
+    }
+
+    #[test]
+    fn test_plain_text_skips_system_and_tool_messages() {
+        let text = to_plain_text(&sample_session(), false);
+        assert_eq!(text, "User: Is 2 < 3?\n\nAssistant: Yes --> it is.");
+        assert!(to_plain_text(&sample_session(), true).starts_with('['));
+    }
+
+    #[test]
+    fn test_srt_cues() {
+        let srt = to_srt(&sample_session());
+        assert!(srt.starts_with("1\n00:00:00,000 --> 00:00:05,000\nUser: Is 2 < 3?\n\n"));
+        assert!(srt.contains("2\n00:00:05,000 --> 00:00:10,000\nAssistant: Yes --> it is.\n"));
+    }
+
+    #[test]
+    fn test_vtt_header_and_escaping() {
+        let vtt = to_vtt(&sample_session());
+        assert!(vtt.starts_with("WEBVTT\n\n1\n00:00:00.000 --> 00:00:05.000\n<v User>"));
+        assert!(vtt.contains("Yes -> it is."));
+    }
+
+    #[tokio::test]
+    async fn test_html_uses_the_session_export() {
+        let sessions = Arc::new(SessionManager::new_memory());
+        sessions.save(&sample_session()).await.unwrap();
+        let tool = TranscriptFormatterTool::new(Arc::clone(&sessions));
+        let out = tool
+            .execute(
+                json!({"session_key": "telegram:1", "format": "html"}),
+                &ToolContext::new().with_admin(true),
+            )
+            .await
+            .unwrap();
+
+        let expected = sessions
+            .export_html("telegram:1", &HTML_OPTIONS)
+            .await
+            .unwrap();
+        assert_eq!(out.for_llm, expected);
+        assert!(out.for_llm.contains("Is 2 &lt; 3?"));
+        assert!(!out.for_llm.contains("You are helpful"));
+    }
+
+    #[test]
+    fn test_cue_time_formatting() {
+        assert_eq!(cue_time(3_723_004, ','), "01:02:03,004");
+    }
+
+    #[tokio::test]
+    async fn test_execute_missing_session() {
+        let tool = TranscriptFormatterTool::new(Arc::new(SessionManager::new_memory()));
+        let err = tool
+            .execute(
+                json!({"session_key": "nope", "format": "srt"}),
+                &ToolContext::new().with_admin(true),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not found"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_renders_stored_session() {
+        let sessions = Arc::new(SessionManager::new_memory());
+        sessions.save(&sample_session()).await.unwrap();
+        let tool = TranscriptFormatterTool::new(Arc::clone(&sessions));
+
+        // The current conversation, by default.
+        let ctx = ToolContext {
+            session_key: Some("telegram:1".to_string()),
+            ..Default::default()
+        };
+        let out = tool
+            .execute(json!({"format": "plain_text"}), &ctx)
+            .await
+            .unwrap();
+        assert!(out.for_llm.starts_with("User: Is 2 < 3?"));
+
+        // Other conversations are for admins only.
+        let ctx = ToolContext {
+            session_key: Some("telegram:2".to_string()),
+            ..Default::default()
+        };
+        let args = json!({"session_key": "telegram:1", "format": "plain_text"});
+        let err = tool.execute(args.clone(), &ctx).await.unwrap_err();
+        assert!(err.to_string().contains("Only admins"));
+        let out = tool.execute(args, &ctx.with_admin(true)).await.unwrap();
+        assert!(out.for_llm.starts_with("User: Is 2 < 3?"));
+    }
+
+    #[tokio::test]
+    async fn test_pdf_reports_missing_binary() {
+        let tool = TranscriptFormatterTool::new(Arc::new(SessionManager::new_memory()))
+            .with_wkhtmltopdf("definitely-not-a-real-wkhtmltopdf");
+        tool.sessions.save(&sample_session()).await.unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let ctx = ToolContext::new()
+            .with_workspace(dir.path().to_str().unwrap())
+            .with_admin(true);
+
+        let err = tool
+            .execute(json!({"session_key": "telegram:1", "format": "pdf"}), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not found"));
+    }
+}
@@ -4,11 +4,22 @@
 //! that all tools must implement, and the `ToolContext` struct that provides
 //! execution context to tools.
 
+use std::borrow::Cow;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+use crate::error::{Result, ZeptoError};
+use crate::security::{Sandbox, ShellPolicy};
+use crate::session::{CommandLog, CommandRecord};
 
-use crate::error::Result;
+use super::artifact::ArtifactStore;
+use super::process::ProcessRegistry;
 
 /// Category for agent mode enforcement.
 ///
@@ -53,6 +64,25 @@ impl ToolCategory {
             ToolCategory::Destructive,
         ]
     }
+
+    /// Whether tools in this category only read state, so dry-run mode lets
+    /// them run.
+    pub fn is_read_only(self) -> bool {
+        matches!(
+            self,
+            ToolCategory::FilesystemRead | ToolCategory::NetworkRead
+        )
+    }
+}
+
+/// What a tool does with a call made while [`ToolContext::dry_run`] is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DryRun {
+    /// Run the call for real; it changes nothing.
+    Execute,
+    /// Skip the call. The text says what it would have done, e.g.
+    /// "would write 1.2 KB to src/main.rs".
+    Describe(String),
 }
 
 impl std::fmt::Display for ToolCategory {
@@ -243,6 +273,66 @@ pub trait Tool: Send + Sync {
     fn category(&self) -> ToolCategory {
         ToolCategory::Shell
     }
+
+    /// Whether this tool may run at the same time as other tool calls from
+    /// the same assistant message.
+    ///
+    /// Tools that are not parallel-safe still run alongside safe ones, but
+    /// one after another relative to each other. Defaults to `true` except
+    /// for shell and filesystem-write tools, whose side effects depend on
+    /// ordering.
+    fn parallel_safe(&self) -> bool {
+        !matches!(
+            self.category(),
+            ToolCategory::Shell | ToolCategory::FilesystemWrite
+        )
+    }
+
+    /// Decide what a call does in dry-run mode, where state-changing tools
+    /// must not act.
+    ///
+    /// Read-only categories run normally. For every other category the
+    /// default skips the call with a generic note, so a tool that changes
+    /// state and does not override this is visibly described as having no
+    /// dry-run support. Tools that change state should override it to say
+    /// what the call would do, and return [`DryRun::Execute`] for actions
+    /// that only read.
+    async fn dry_run(&self, args: &Value, ctx: &ToolContext) -> Result<DryRun> {
+        let _ = (args, ctx);
+        if self.category().is_read_only() {
+            return Ok(DryRun::Execute);
+        }
+        Ok(DryRun::Describe(format!(
+            "would call {} (this tool cannot describe its effect)",
+            self.name()
+        )))
+    }
+}
+
+/// Callback receiving incremental output from a long-running tool.
+///
+/// Tools call [`ToolContext::report_progress`] with chunks of output as they
+/// are produced; the agent loop relays them to the user while the tool keeps
+/// running. The tool's final result is unaffected.
+#[derive(Clone)]
+pub struct ToolProgress(Arc<dyn Fn(&str) + Send + Sync>);
+
+impl ToolProgress {
+    /// Wrap a callback.
+    pub fn new(callback: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    /// Pass a chunk of output to the callback.
+    pub fn report(&self, chunk: &str) {
+        (self.0)(chunk)
+    }
+}
+
+impl fmt::Debug for ToolProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ToolProgress")
+    }
 }
 
 /// Context provided to tools during execution.
@@ -257,8 +347,34 @@ pub struct ToolContext {
     pub chat_id: Option<String>,
     /// The workspace directory for file operations
     pub workspace: Option<String>,
+    /// Denied and read-only path rules for the workspace; see
+    /// [`ToolContext::sandbox`].
+    pub sandbox: Option<Arc<Sandbox>>,
     /// Whether the tool is running in batch mode (no interactive user).
     pub is_batch: bool,
+    /// Per-call cap on command output size, overriding the tool's own limit.
+    pub max_output_bytes: Option<usize>,
+    /// Shell command policy overriding the shell tool's configured policy.
+    pub shell_policy: Option<Arc<ShellPolicy>>,
+    /// Receiver for incremental output; tools stream to it when set.
+    pub progress: Option<ToolProgress>,
+    /// Background processes owned by the agent, for the `process` tool.
+    pub processes: Option<Arc<ProcessRegistry>>,
+    /// Artifact store shared by the agent's tools.
+    pub artifacts: Option<Arc<ArtifactStore>>,
+    /// Session the call belongs to; scopes artifacts and command records.
+    pub session_key: Option<String>,
+    /// Audit log the shell and process tools record commands in.
+    pub command_log: Option<Arc<CommandLog>>,
+    /// Describe state-changing calls instead of running them; see
+    /// [`Tool::dry_run`].
+    pub dry_run: bool,
+    /// Whether the caller is an admin under `agents.authorization` and may
+    /// act on sessions other than [`session_key`](Self::session_key).
+    pub is_admin: bool,
+    /// Cancelled when the turn the call belongs to is stopped; long-running
+    /// tools should stop early and fail with [`ZeptoError::Cancelled`].
+    pub cancellation: CancellationToken,
 }
 
 impl ToolContext {
@@ -314,6 +430,45 @@ impl ToolContext {
         self
     }
 
+    /// Confine file tools with `sandbox`; its workspace becomes the
+    /// context's workspace.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use zeptoclaw::security::Sandbox;
+    /// use zeptoclaw::tools::ToolContext;
+    ///
+    /// let sandbox = Sandbox::new("/home/user/project").with_denied_paths(&[".env"]);
+    /// let ctx = ToolContext::new().with_sandbox(Arc::new(sandbox));
+    /// assert_eq!(ctx.workspace.as_deref(), Some("/home/user/project"));
+    /// assert!(ctx.sandbox().unwrap().resolve(".env").is_err());
+    /// ```
+    pub fn with_sandbox(mut self, sandbox: Arc<Sandbox>) -> Self {
+        self.workspace = Some(sandbox.workspace().to_string());
+        self.sandbox = Some(sandbox);
+        self
+    }
+
+    /// The sandbox file tools resolve paths through: the one set with
+    /// [`with_sandbox`](Self::with_sandbox), else a plain one for the
+    /// workspace. `None` without a workspace.
+    pub fn sandbox(&self) -> Option<Arc<Sandbox>> {
+        self.sandbox.clone().or_else(|| {
+            self.workspace
+                .as_deref()
+                .map(|workspace| Arc::new(Sandbox::new(workspace)))
+        })
+    }
+
+    /// The sandbox set with [`with_sandbox`](Self::with_sandbox), else a
+    /// plain one for `workspace`; for tools bound to a workspace when built.
+    pub fn sandbox_or(&self, workspace: &str) -> Arc<Sandbox> {
+        self.sandbox
+            .clone()
+            .unwrap_or_else(|| Arc::new(Sandbox::new(workspace)))
+    }
+
     /// Set whether the tool is running in batch mode.
     ///
     /// In batch mode, there is no interactive user, so tools that need
@@ -322,6 +477,175 @@ impl ToolContext {
         self.is_batch = is_batch;
         self
     }
+
+    /// Describe state-changing tool calls instead of running them.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::tools::ToolContext;
+    ///
+    /// let ctx = ToolContext::new().with_dry_run(true);
+    /// assert!(ctx.dry_run);
+    /// ```
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Mark the caller as an admin, who may act on other sessions.
+    ///
+    /// # Example
+    /// ```
+    /// use zeptoclaw::tools::ToolContext;
+    ///
+    /// let ctx = ToolContext::new().with_admin(true);
+    /// assert!(ctx.is_admin);
+    /// ```
+    pub fn with_admin(mut self, is_admin: bool) -> Self {
+        self.is_admin = is_admin;
+        self
+    }
+
+    /// Stop long-running tools when `token` is cancelled.
+    ///
+    /// # Example
+    /// ```
+    /// use tokio_util::sync::CancellationToken;
+    /// use zeptoclaw::tools::ToolContext;
+    ///
+    /// let token = CancellationToken::new();
+    /// let ctx = ToolContext::new().with_cancellation(token.clone());
+    /// token.cancel();
+    /// assert!(ctx.is_cancelled());
+    /// ```
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Whether the turn this call belongs to has been stopped.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Fail with [`ZeptoError::Cancelled`] if the turn has been stopped.
+    /// Tools call this between steps of a long loop.
+    pub fn check_cancelled(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(cancelled_error());
+        }
+        Ok(())
+    }
+
+    /// Run `fut` unless the turn is stopped first, in which case `fut` is
+    /// dropped (aborting an HTTP request or killing a `kill_on_drop` child)
+    /// and [`ZeptoError::Cancelled`] is returned.
+    pub async fn cancellable<T, F>(&self, fut: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        tokio::select! {
+            biased;
+            _ = self.cancellation.cancelled() => Err(cancelled_error()),
+            result = fut => result,
+        }
+    }
+
+    /// Cap the size of command output returned by tools such as `shell`,
+    /// overriding their configured limit.
+    pub fn with_max_output_bytes(mut self, max_output_bytes: usize) -> Self {
+        self.max_output_bytes = Some(max_output_bytes);
+        self
+    }
+
+    /// Apply a shell command policy, overriding the shell tool's own.
+    pub fn with_shell_policy(mut self, policy: Arc<ShellPolicy>) -> Self {
+        self.shell_policy = Some(policy);
+        self
+    }
+
+    /// Stream incremental output from long-running tools to `progress`.
+    pub fn with_progress(mut self, progress: ToolProgress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Share the agent's background process registry.
+    pub fn with_processes(mut self, processes: Arc<ProcessRegistry>) -> Self {
+        self.processes = Some(processes);
+        self
+    }
+
+    /// Share the agent's artifact store, scoped to `session_key`.
+    pub fn with_artifacts(mut self, artifacts: Arc<ArtifactStore>, session_key: &str) -> Self {
+        self.artifacts = Some(artifacts);
+        self.session_key = Some(session_key.to_string());
+        self
+    }
+
+    /// Record the commands run through this context in `log`, under
+    /// `session_key`.
+    pub fn with_command_log(mut self, log: Arc<CommandLog>, session_key: &str) -> Self {
+        self.command_log = Some(log);
+        self.session_key = Some(session_key.to_string());
+        self
+    }
+
+    /// Append `record` to the command log, if there is one. A failed write
+    /// is logged rather than failing the command that already ran.
+    pub fn record_command(&self, mut record: CommandRecord) {
+        let Some(ref log) = self.command_log else {
+            return;
+        };
+        record.session_key = self.session_key.clone().unwrap_or_default();
+        if let Err(e) = log.append(&record) {
+            tracing::warn!(error = %e, command = %record.command, "Failed to record command");
+        }
+    }
+
+    fn artifact_scope(&self) -> Result<(&ArtifactStore, &str)> {
+        match (&self.artifacts, &self.session_key) {
+            (Some(store), Some(session)) => Ok((store, session)),
+            _ => Err(ZeptoError::Tool(
+                "Artifacts are not available in this context".to_string(),
+            )),
+        }
+    }
+
+    /// Store `bytes` as artifact `name` for this session and return its
+    /// `artifact://` reference.
+    pub async fn put_artifact(&self, name: &str, bytes: &[u8]) -> Result<String> {
+        let (store, session) = self.artifact_scope()?;
+        store.put(session, name, bytes).await
+    }
+
+    /// Path to write artifact `name` to, for tools that stream output.
+    pub fn artifact_path(&self, name: &str) -> Result<std::path::PathBuf> {
+        let (store, session) = self.artifact_scope()?;
+        store.path_for_write(session, name)
+    }
+
+    /// Resolve a path-like tool input: `artifact://name` becomes the
+    /// artifact's workspace-relative path; anything else is returned as is.
+    pub fn resolve_input_path<'a>(&self, path: &'a str) -> Result<Cow<'a, str>> {
+        if !ArtifactStore::is_reference(path) {
+            return Ok(Cow::Borrowed(path));
+        }
+        let (store, session) = self.artifact_scope()?;
+        store.resolve(session, path).map(Cow::Owned)
+    }
+
+    /// Report a chunk of incremental output, if anyone is listening.
+    pub fn report_progress(&self, chunk: &str) {
+        if let Some(ref progress) = self.progress {
+            progress.report(chunk);
+        }
+    }
+}
+
+/// The error a tool returns when its turn is stopped.
+fn cancelled_error() -> ZeptoError {
+    ZeptoError::Cancelled("the turn was stopped".to_string())
 }
 
 #[cfg(test)]
@@ -350,6 +674,58 @@ mod tests {
         assert!(!ctx.is_batch);
     }
 
+    #[test]
+    fn test_tool_context_report_progress() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let ctx = ToolContext::new().with_progress(ToolProgress::new(move |chunk| {
+            sink.lock().unwrap().push(chunk.to_string())
+        }));
+        ctx.clone().report_progress("one");
+        ctx.report_progress("two");
+        ToolContext::new().report_progress("dropped");
+        assert_eq!(*seen.lock().unwrap(), vec!["one", "two"]);
+    }
+
+    #[tokio::test]
+    async fn test_tool_context_cancellable() {
+        let ctx = ToolContext::new();
+        assert_eq!(ctx.cancellable(async { Ok(1) }).await.unwrap(), 1);
+        assert!(ctx.check_cancelled().is_ok());
+
+        let token = CancellationToken::new();
+        let ctx = ctx.with_cancellation(token.clone());
+        let slow = ctx.cancellable(async {
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+            Ok(())
+        });
+        token.cancel();
+        assert!(matches!(slow.await, Err(ZeptoError::Cancelled(_))));
+        assert!(matches!(
+            ctx.check_cancelled(),
+            Err(ZeptoError::Cancelled(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tool_context_artifacts() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = Arc::new(ArtifactStore::new(dir.path()));
+        let ctx = ToolContext::new().with_artifacts(Arc::clone(&store), "cli:main");
+        let reference = ctx.put_artifact("notes.txt", b"hi").await.unwrap();
+        assert_eq!(
+            ctx.resolve_input_path(&reference).unwrap(),
+            ".artifacts/cli_main/notes.txt"
+        );
+        assert_eq!(
+            ctx.resolve_input_path("src/main.rs").unwrap(),
+            "src/main.rs"
+        );
+        assert!(ToolContext::new()
+            .resolve_input_path("artifact://notes.txt")
+            .is_err());
+    }
+
     #[test]
     fn test_tool_context_with_batch() {
         let ctx = ToolContext::new().with_batch(true);
@@ -0,0 +1,253 @@
+//! Tool usage accounting kept by the [`ToolRegistry`](super::ToolRegistry).
+//!
+//! Every call that reaches a tool is recorded with its duration, whether it
+//! failed, and the size of its LLM-facing output, both across all sessions
+//! and per session. Durations are kept as a bounded window of recent samples
+//! so p50/p95 reflect current behaviour without unbounded memory.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Recent durations kept per tool for percentiles.
+const MAX_DURATION_SAMPLES: usize = 512;
+
+/// Sessions tracked at once; the least recently active is dropped first.
+const MAX_TRACKED_SESSIONS: usize = 1024;
+
+/// Usage of one tool, as reported by [`ToolUsage`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ToolUsageStats {
+    /// Calls that reached the tool.
+    pub calls: u64,
+    /// Calls that returned an error or an error result.
+    pub errors: u64,
+    /// Median duration of recent calls, in milliseconds.
+    pub p50_ms: u64,
+    /// 95th percentile duration of recent calls, in milliseconds.
+    pub p95_ms: u64,
+    /// Total bytes of output returned to the LLM.
+    pub output_bytes: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    calls: u64,
+    errors: u64,
+    output_bytes: u64,
+    durations: VecDeque<Duration>,
+}
+
+impl Counters {
+    fn record(&mut self, duration: Duration, is_error: bool, output_bytes: usize) {
+        self.calls += 1;
+        if is_error {
+            self.errors += 1;
+        }
+        self.output_bytes += output_bytes as u64;
+        if self.durations.len() == MAX_DURATION_SAMPLES {
+            self.durations.pop_front();
+        }
+        self.durations.push_back(duration);
+    }
+
+    fn stats(&self) -> ToolUsageStats {
+        let mut sorted: Vec<Duration> = self.durations.iter().copied().collect();
+        sorted.sort_unstable();
+        ToolUsageStats {
+            calls: self.calls,
+            errors: self.errors,
+            p50_ms: percentile_ms(&sorted, 0.50),
+            p95_ms: percentile_ms(&sorted, 0.95),
+            output_bytes: self.output_bytes,
+        }
+    }
+}
+
+/// Nearest-rank percentile of `sorted`, in milliseconds.
+fn percentile_ms(sorted: &[Duration], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1].as_millis() as u64
+}
+
+#[derive(Debug)]
+struct SessionUsage {
+    tools: HashMap<String, Counters>,
+    last_seen: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    global: HashMap<String, Counters>,
+    sessions: HashMap<String, SessionUsage>,
+}
+
+/// Per-tool call counts, errors, durations and output sizes, globally and
+/// per session.
+#[derive(Debug, Default)]
+pub struct ToolUsage {
+    inner: Mutex<Inner>,
+}
+
+impl ToolUsage {
+    /// Create an empty collector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one call of `tool` in session `namespace`.
+    pub fn record(
+        &self,
+        tool: &str,
+        namespace: &str,
+        duration: Duration,
+        is_error: bool,
+        output_bytes: usize,
+    ) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner
+            .global
+            .entry(tool.to_string())
+            .or_default()
+            .record(duration, is_error, output_bytes);
+
+        if !inner.sessions.contains_key(namespace) && inner.sessions.len() >= MAX_TRACKED_SESSIONS {
+            let oldest = inner
+                .sessions
+                .iter()
+                .min_by_key(|(_, usage)| usage.last_seen)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                inner.sessions.remove(&oldest);
+            }
+        }
+        let session = inner
+            .sessions
+            .entry(namespace.to_string())
+            .or_insert_with(|| SessionUsage {
+                tools: HashMap::new(),
+                last_seen: Instant::now(),
+            });
+        session.last_seen = Instant::now();
+        session
+            .tools
+            .entry(tool.to_string())
+            .or_default()
+            .record(duration, is_error, output_bytes);
+    }
+
+    /// Usage of every tool across all sessions, by tool name.
+    pub fn snapshot(&self) -> BTreeMap<String, ToolUsageStats> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner
+            .global
+            .iter()
+            .map(|(name, counters)| (name.clone(), counters.stats()))
+            .collect()
+    }
+
+    /// Usage of every tool in session `namespace`, by tool name. Empty if
+    /// the session has not called any tool.
+    pub fn session(&self, namespace: &str) -> BTreeMap<String, ToolUsageStats> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner
+            .sessions
+            .get(namespace)
+            .map(|usage| {
+                usage
+                    .tools
+                    .iter()
+                    .map(|(name, counters)| (name.clone(), counters.stats()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Render usage as one line per tool, busiest first.
+pub fn format_usage(stats: &BTreeMap<String, ToolUsageStats>) -> String {
+    let mut entries: Vec<_> = stats.iter().collect();
+    entries.sort_by_key(|(_, s)| std::cmp::Reverse(s.calls));
+    entries
+        .iter()
+        .map(|(name, s)| {
+            format!(
+                "- {}: {} call{}, {} error{}, p50 {}ms, p95 {}ms, {} output",
+                name,
+                s.calls,
+                if s.calls == 1 { "" } else { "s" },
+                s.errors,
+                if s.errors == 1 { "" } else { "s" },
+                s.p50_ms,
+                s.p95_ms,
+                super::artifact::format_size(s.output_bytes)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn test_records_globally_and_per_session() {
+        let usage = ToolUsage::new();
+        for n in 1..=20 {
+            usage.record("shell", "telegram:1", ms(n * 10), n == 20, 100);
+        }
+        usage.record("shell", "telegram:2", ms(5), false, 7);
+        usage.record("echo", "telegram:2", ms(1), false, 3);
+
+        let global = usage.snapshot();
+        assert_eq!(
+            global["shell"],
+            ToolUsageStats {
+                calls: 21,
+                errors: 1,
+                p50_ms: 100,
+                p95_ms: 190,
+                output_bytes: 2007,
+            }
+        );
+
+        let session = usage.session("telegram:1");
+        assert_eq!(session.len(), 1);
+        assert_eq!(session["shell"].calls, 20);
+        assert_eq!(session["shell"].p50_ms, 100);
+        assert_eq!(session["shell"].p95_ms, 190);
+        assert_eq!(usage.session("telegram:2")["echo"].output_bytes, 3);
+        assert!(usage.session("slack:9").is_empty());
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let sorted: Vec<Duration> = [10, 20, 30, 40].into_iter().map(ms).collect();
+        assert_eq!(percentile_ms(&sorted, 0.50), 20);
+        assert_eq!(percentile_ms(&sorted, 0.95), 40);
+        assert_eq!(percentile_ms(&[], 0.95), 0);
+    }
+
+    #[test]
+    fn test_format_usage_busiest_first() {
+        let usage = ToolUsage::new();
+        usage.record("echo", "s", ms(2), false, 10);
+        usage.record("shell", "s", ms(30), true, 2048);
+        usage.record("shell", "s", ms(50), false, 0);
+        assert_eq!(
+            format_usage(&usage.session("s")),
+            "- shell: 2 calls, 1 error, p50 30ms, p95 50ms, 2.0 KB output\n\
+             - echo: 1 call, 0 errors, p50 2ms, p95 2ms, 10 B output"
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! WebAssembly plugin tools (`wasm-plugins` feature).
+//!
+//! Every `*.wasm` file in a plugin directory becomes one tool. Modules run
+//! under wasmtime with no host imports, so a plugin can compute but cannot
+//! touch the filesystem, network or environment. Each call gets a fresh
+//! instance with its own fuel, wall-clock and memory limits
+//! ([`WasmPluginLimits`]); a trap fails that call only.
+//!
+//! # ABI
+//!
+//! Strings cross the boundary as UTF-8 in the module's linear memory. A
+//! function that returns a string returns an `i64` packing the pointer in
+//! the high 32 bits and the length in the low 32 bits.
+//!
+//! | Export | Signature | Purpose |
+//! |---|---|---|
+//! | `memory` | memory | Linear memory shared with the host |
+//! | `alloc` | `(len: i32) -> i32` | Reserve `len` bytes for the host to write arguments into |
+//! | `name` | `() -> i64` | Tool name |
+//! | `description` | `() -> i64` | Tool description |
+//! | `parameters` | `() -> i64` | JSON Schema for the arguments |
+//! | `execute` | `(ptr: i32, len: i32) -> i64` | Run with JSON arguments; returns a JSON result |
+//!
+//! `execute` returns `{"output": "..."}` on success or `{"error": "..."}` on
+//! failure; any other JSON value is passed to the model as-is. See
+//! `examples/wasm_plugins/echo.wat` for a complete plugin.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tracing::{info, warn};
+use wasmtime::{Config, Engine, Instance, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+
+use crate::error::{Result, ZeptoError};
+use crate::plugins::WasmPluginLimits;
+
+use super::{Tool, ToolContext, ToolOutput};
+
+/// How often the shared engine's epoch advances; the granularity of
+/// `timeout_secs`.
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Engine shared by all plugins, with a background thread driving epochs.
+fn engine() -> Result<&'static Engine> {
+    static ENGINE: OnceLock<std::result::Result<Engine, String>> = OnceLock::new();
+    ENGINE
+        .get_or_init(|| {
+            let mut config = Config::new();
+            config.consume_fuel(true).epoch_interruption(true);
+            let engine = Engine::new(&config).map_err(|e| e.to_string())?;
+            let ticker = engine.clone();
+            std::thread::Builder::new()
+                .name("wasm-epoch".into())
+                .spawn(move || loop {
+                    std::thread::sleep(EPOCH_TICK);
+                    ticker.increment_epoch();
+                })
+                .map_err(|e| e.to_string())?;
+            Ok(engine)
+        })
+        .as_ref()
+        .map_err(|e| ZeptoError::Config(format!("Failed to start WASM engine: {}", e)))
+}
+
+/// Split a packed `(ptr << 32) | len` return value.
+fn unpack(packed: i64) -> (usize, usize) {
+    let packed = packed as u64;
+    ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize)
+}
+
+/// Interpret the JSON returned by `execute`.
+fn parse_result(raw: &str) -> std::result::Result<String, String> {
+    let value: Value = serde_json::from_str(raw).map_err(|e| format!("invalid JSON: {}", e))?;
+    if let Some(error) = value.get("error") {
+        return Err(error
+            .as_str()
+            .map_or_else(|| error.to_string(), String::from));
+    }
+    match value.get("output") {
+        Some(Value::String(output)) => Ok(output.clone()),
+        Some(output) => Ok(output.to_string()),
+        None => Ok(raw.to_string()),
+    }
+}
+
+/// Per-call store data.
+struct CallState {
+    limits: StoreLimits,
+}
+
+/// A tool backed by a WebAssembly module.
+pub struct WasmPluginTool {
+    name: String,
+    description: String,
+    parameters: Value,
+    path: PathBuf,
+    module: Module,
+    limits: WasmPluginLimits,
+}
+
+impl WasmPluginTool {
+    /// Compile the module at `path` and read its name, description and
+    /// parameters.
+    pub fn load(path: &Path, limits: &WasmPluginLimits) -> Result<Self> {
+        let invalid =
+            |msg: String| ZeptoError::Config(format!("WASM plugin {}: {}", path.display(), msg));
+
+        let module = Module::from_file(engine()?, path).map_err(|e| invalid(e.to_string()))?;
+        let mut tool = Self {
+            name: String::new(),
+            description: String::new(),
+            parameters: Value::Null,
+            path: path.to_path_buf(),
+            module,
+            limits: limits.clone(),
+        };
+
+        tool.name = tool.call_string("name").map_err(invalid)?;
+        let valid_name = tool.name.len() <= 64
+            && tool.name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && tool
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_name {
+            return Err(invalid(format!(
+                "invalid tool name '{}': must be 1-64 alphanumeric characters and underscores, starting with a letter",
+                tool.name
+            )));
+        }
+        tool.description = tool.call_string("description").map_err(invalid)?;
+        let parameters = tool.call_string("parameters").map_err(invalid)?;
+        tool.parameters = serde_json::from_str(&parameters)
+            .map_err(|e| invalid(format!("parameters is not valid JSON: {}", e)))?;
+        if tool.parameters.get("type").and_then(Value::as_str) != Some("object") {
+            return Err(invalid(
+                "parameters must be a JSON schema with \"type\": \"object\"".into(),
+            ));
+        }
+        Ok(tool)
+    }
+
+    fn call_string(&self, export: &str) -> std::result::Result<String, String> {
+        call_export(&self.module, &self.limits, export, None)
+    }
+}
+
+/// Call a string-returning export in a fresh instance, writing `input` into
+/// the module's memory first when given.
+fn call_export(
+    module: &Module,
+    limits: &WasmPluginLimits,
+    export: &str,
+    input: Option<&[u8]>,
+) -> std::result::Result<String, String> {
+    let max_memory =
+        usize::try_from(limits.max_memory_mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX);
+    let mut store = Store::new(
+        module.engine(),
+        CallState {
+            limits: StoreLimitsBuilder::new()
+                .memory_size(max_memory)
+                .trap_on_grow_failure(true)
+                .build(),
+        },
+    );
+    store.limiter(|state| &mut state.limits);
+    store.set_fuel(limits.fuel).map_err(|e| e.to_string())?;
+    let ticks = (limits.timeout_secs.saturating_mul(1000) / EPOCH_TICK.as_millis() as u64).max(1);
+    store.set_epoch_deadline(ticks);
+
+    let run = |store: &mut Store<CallState>| -> wasmtime::Result<String> {
+        let instance = Instance::new(&mut *store, module, &[])?;
+        let memory = instance
+            .get_memory(&mut *store, "memory")
+            .ok_or_else(|| wasmtime::Error::msg("missing exported memory"))?;
+        let packed = match input {
+            None => instance
+                .get_typed_func::<(), i64>(&mut *store, export)?
+                .call(&mut *store, ())?,
+            Some(bytes) => {
+                let len = i32::try_from(bytes.len())?;
+                let alloc = instance.get_typed_func::<i32, i32>(&mut *store, "alloc")?;
+                let ptr = alloc.call(&mut *store, len)?;
+                memory.write(&mut *store, ptr as u32 as usize, bytes)?;
+                instance
+                    .get_typed_func::<(i32, i32), i64>(&mut *store, export)?
+                    .call(&mut *store, (ptr, len))?
+            }
+        };
+        let (ptr, len) = unpack(packed);
+        let bytes = memory
+            .data(&*store)
+            .get(ptr..ptr.saturating_add(len))
+            .ok_or_else(|| {
+                wasmtime::Error::msg(format!("{} returned out-of-bounds string", export))
+            })?;
+        Ok(String::from_utf8(bytes.to_vec())?)
+    };
+
+    run(&mut store).map_err(|e| match e.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => format!("{} ran out of fuel (limit {})", export, limits.fuel),
+        Some(Trap::Interrupt) => format!("{} timed out after {}s", export, limits.timeout_secs),
+        _ => format!("{} failed: {:#}", export, e),
+    })
+}
+
+impl std::fmt::Debug for WasmPluginTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmPluginTool")
+            .field("name", &self.name)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Tool for WasmPluginTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn compact_description(&self) -> &str {
+        self.description()
+    }
+
+    fn parameters(&self) -> Value {
+        self.parameters.clone()
+    }
+
+    /// Every call runs in its own instance.
+    fn parallel_safe(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
+        let name = self.name.clone();
+        let module = self.module.clone();
+        let limits = self.limits.clone();
+        let input = args.to_string();
+        let raw = tokio::task::spawn_blocking(move || {
+            call_export(&module, &limits, "execute", Some(input.as_bytes()))
+        })
+        .await
+        .map_err(|e| ZeptoError::Tool(format!("WASM plugin '{}' panicked: {}", name, e)))?
+        .map_err(|e| {
+            warn!(tool = %name, error = %e, "WASM plugin call failed");
+            ZeptoError::Tool(format!("WASM plugin '{}': {}", name, e))
+        })?;
+
+        parse_result(&raw)
+            .map(ToolOutput::llm_only)
+            .map_err(|e| ZeptoError::Tool(format!("WASM plugin '{}' error: {}", name, e)))
+    }
+}
+
+/// Load every `*.wasm` file directly inside `dirs`. Files that fail to load
+/// are logged and skipped.
+pub fn discover_wasm_plugins(dirs: &[PathBuf], limits: &WasmPluginLimits) -> Vec<WasmPluginTool> {
+    let mut tools = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "wasm"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            match WasmPluginTool::load(&path, limits) {
+                Ok(tool) => {
+                    info!(tool = %tool.name, path = %path.display(), "Discovered WASM plugin");
+                    tools.push(tool);
+                }
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Failed to load WASM plugin, skipping")
+                }
+            }
+        }
+    }
+    tools
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unpack() {
+        assert_eq!(unpack((1024_i64 << 32) | 9), (1024, 9));
+        assert_eq!(unpack(0), (0, 0));
+        assert_eq!(
+            unpack(((0xffff_fff0_u64 << 32) | 0xffff_ffff) as i64),
+            (0xffff_fff0, 0xffff_ffff)
+        );
+    }
+
+    #[test]
+    fn test_parse_result() {
+        assert_eq!(parse_result(r#"{"output":"done"}"#), Ok("done".into()));
+        assert_eq!(
+            parse_result(r#"{"output":{"n":1}}"#),
+            Ok(r#"{"n":1}"#.into())
+        );
+        assert_eq!(
+            parse_result(r#"{"message":"hi"}"#),
+            Ok(r#"{"message":"hi"}"#.into())
+        );
+        assert_eq!(
+            parse_result(r#"{"error":"bad input"}"#),
+            Err("bad input".into())
+        );
+        assert!(parse_result("not json")
+            .unwrap_err()
+            .contains("invalid JSON"));
+    }
+}
@@ -23,7 +23,7 @@ use super::{Tool, ToolCategory, ToolContext, ToolOutput};
 
 const BRAVE_API_URL: &str = "https://api.search.brave.com/res/v1/web/search";
 const DDG_HTML_URL: &str = "https://html.duckduckgo.com/html/";
-const WEB_USER_AGENT: &str = "zeptoclaw/0.1 (+https://github.com/zeptoclaw/zeptoclaw)";
+pub(crate) const WEB_USER_AGENT: &str = "zeptoclaw/0.1 (+https://github.com/zeptoclaw/zeptoclaw)";
 const MAX_WEB_SEARCH_COUNT: usize = 10;
 const DEFAULT_MAX_FETCH_CHARS: usize = 50_000;
 const MAX_FETCH_CHARS: usize = 200_000;
@@ -694,7 +694,7 @@ impl Tool for WebFetchTool {
         })
     }
 
-    async fn execute(&self, args: Value, _ctx: &ToolContext) -> Result<ToolOutput> {
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolOutput> {
         let url = args
             .get("url")
             .and_then(|v| v.as_str())
@@ -747,12 +747,16 @@ impl Tool for WebFetchTool {
             self.client.clone()
         };
 
-        let response = client
-            .get(parsed.clone())
-            .header("User-Agent", WEB_USER_AGENT)
-            .send()
-            .await
-            .map_err(|e| ZeptoError::Tool(format!("Web fetch failed: {}", e)))?;
+        let response = ctx
+            .cancellable(async {
+                client
+                    .get(parsed.clone())
+                    .header("User-Agent", WEB_USER_AGENT)
+                    .send()
+                    .await
+                    .map_err(|e| ZeptoError::Tool(format!("Web fetch failed: {}", e)))
+            })
+            .await?;
 
         // Defense in depth: validate the final destination URL as well.
         validate_redirect_target(response.url()).await?;
@@ -773,7 +777,9 @@ impl Tool for WebFetchTool {
 
         // Read body in chunks with a size limit to prevent unbounded memory
         // allocation from malicious or oversized responses.
-        let body = read_body_limited(response, MAX_FETCH_BYTES).await?;
+        let body = ctx
+            .cancellable(read_body_limited(response, MAX_FETCH_BYTES))
+            .await?;
 
         let include_links = args
             .get("include_links")
@@ -960,6 +966,25 @@ fn find_content_root(document: &Html) -> Option<ElementRef<'_>> {
         .or_else(|| document.select(&SEL_BODY).next())
 }
 
+/// Convert an HTML page to readable markdown: its title as a heading, then
+/// the main content. Used by `http_fetch`.
+pub(crate) fn html_to_markdown(html: &str) -> String {
+    let document = Html::parse_document(html);
+    let content = find_content_root(&document)
+        .map(|root| normalize_whitespace_md(&dom_to_markdown(root)))
+        .unwrap_or_default();
+    let title = document
+        .select(&SEL_TITLE)
+        .next()
+        .map(|el| normalize_whitespace(&el.text().collect::<String>()))
+        .unwrap_or_default();
+    if title.is_empty() {
+        content
+    } else {
+        format!("# {}\n\n{}", title, content)
+    }
+}
+
 /// Convert an HTML element subtree to markdown.
 fn dom_to_markdown(element: ElementRef<'_>) -> String {
     let mut output = String::new();
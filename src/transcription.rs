@@ -1,14 +1,26 @@
-//! Provider-agnostic audio transcription service.
+//! Audio transcription services.
 //!
-//! Tries each configured OpenAI-compatible provider in order until one succeeds.
-//! Falls back to `[Voice Message]` if all fail or none are configured.
+//! [`SpeechToText`] uses the single backend chosen in `tools.transcribe`
+//! (Groq, OpenAI or a local whisper.cpp binary). It backs the `transcribe`
+//! tool.
+//!
+//! [`TranscriberService`] handles voice messages arriving on channels. It
+//! tries the `tools.transcribe` backend first when that tool is enabled, then
+//! each configured OpenAI-compatible provider in order until one succeeds,
+//! falling back to `[Voice Message]` if all fail or none are configured.
+
+use std::time::Duration;
 
 use reqwest::multipart;
 use tracing::{debug, warn};
 
-use crate::config::Config;
+use crate::config::{Config, TranscribeConfig};
+use crate::error::{Result, ZeptoError};
 use crate::providers::{provider_config_by_name, PROVIDER_REGISTRY};
 
+/// Placeholder content for a voice message that has not been transcribed.
+pub const VOICE_MESSAGE_PLACEHOLDER: &str = "[Voice Message]";
+
 /// A single transcription endpoint candidate.
 #[derive(Debug, Clone)]
 pub struct TranscriptionCandidate {
@@ -23,6 +35,7 @@ pub struct TranscriptionCandidate {
 /// Anthropic (which has no audio API). Falls back to `[Voice Message]` on total failure.
 #[derive(Debug, Clone)]
 pub struct TranscriberService {
+    stt: Option<SpeechToText>,
     candidates: Vec<TranscriptionCandidate>,
     model: String,
     client: reqwest::Client,
@@ -30,7 +43,8 @@ pub struct TranscriberService {
 
 impl TranscriberService {
     /// Build from config. Skips providers with `backend == "anthropic"`.
-    /// Returns `None` if transcription is disabled or no eligible providers are configured.
+    /// Returns `None` if transcription is disabled or neither the
+    /// `tools.transcribe` backend nor any eligible provider is configured.
     pub fn from_config(config: &Config) -> Option<Self> {
         if !config.transcription.enabled {
             return None;
@@ -55,11 +69,22 @@ impl TranscriberService {
             })
             .collect();
 
-        if candidates.is_empty() {
+        let stt = if config.tools.transcribe.enabled {
+            SpeechToText::from_config(&config.tools.transcribe)
+                .map_err(
+                    |e| warn!(error = %e, "tools.transcribe backend unusable for voice messages"),
+                )
+                .ok()
+        } else {
+            None
+        };
+
+        if stt.is_none() && candidates.is_empty() {
             return None;
         }
 
         Some(Self {
+            stt,
             candidates,
             model: config.transcription.model.clone(),
             client: reqwest::Client::new(),
@@ -68,6 +93,21 @@ impl TranscriberService {
 
     /// Transcribe raw audio bytes. Returns transcript or `"[Voice Message]"` on total failure.
     pub async fn transcribe(&self, audio: Vec<u8>, content_type: &str) -> String {
+        if let Some(stt) = &self.stt {
+            let filename = format!("voice.{}", extension_for_mime(content_type));
+            match stt
+                .transcribe(audio.clone(), &filename, content_type, None)
+                .await
+            {
+                Ok(text) => {
+                    debug!("Transcription succeeded with tools.transcribe backend");
+                    return text;
+                }
+                Err(e) => {
+                    warn!(error = %e, "tools.transcribe backend failed, trying providers");
+                }
+            }
+        }
         for candidate in &self.candidates {
             match self
                 .try_transcribe(candidate, audio.clone(), content_type)
@@ -86,7 +126,7 @@ impl TranscriberService {
                 }
             }
         }
-        "[Voice Message]".to_string()
+        VOICE_MESSAGE_PLACEHOLDER.to_string()
     }
 
     async fn try_transcribe(
@@ -94,7 +134,7 @@ impl TranscriberService {
         candidate: &TranscriptionCandidate,
         audio: Vec<u8>,
         content_type: &str,
-    ) -> Result<String, String> {
+    ) -> std::result::Result<String, String> {
         let file_part = multipart::Part::bytes(audio)
             .file_name("voice.ogg")
             .mime_str(content_type)
@@ -132,6 +172,390 @@ impl TranscriberService {
     }
 }
 
+// ============================================================================
+// SpeechToText
+// ============================================================================
+
+const GROQ_API_BASE: &str = "https://api.groq.com/openai/v1";
+const OPENAI_API_BASE: &str = "https://api.openai.com/v1";
+
+/// Speech-to-text backend selected by `tools.transcribe.backend`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpeechBackend {
+    /// OpenAI-compatible `audio/transcriptions` endpoint (OpenAI, Groq).
+    Api {
+        api_base: String,
+        api_key: String,
+        model: String,
+    },
+    /// Local whisper.cpp CLI, fed 16 kHz WAV (converted with ffmpeg if needed).
+    WhisperCpp { binary: String, model_path: String },
+}
+
+/// Transcribes audio with the backend configured in `tools.transcribe`.
+#[derive(Debug, Clone)]
+pub struct SpeechToText {
+    backend: SpeechBackend,
+    language: Option<String>,
+    max_duration_secs: u64,
+    client: reqwest::Client,
+}
+
+impl SpeechToText {
+    /// Build from `tools.transcribe`, failing with a message that names the
+    /// missing setting when the backend is not usable.
+    pub fn from_config(config: &TranscribeConfig) -> Result<Self> {
+        let api_key = |key: Option<&String>, setting: &str| {
+            key.filter(|k| !k.is_empty()).cloned().ok_or_else(|| {
+                ZeptoError::Config(format!(
+                    "tools.transcribe.backend '{}' needs {}",
+                    config.backend, setting
+                ))
+            })
+        };
+        let backend = match config.backend.as_str() {
+            "groq" => SpeechBackend::Api {
+                api_base: config
+                    .api_base
+                    .clone()
+                    .unwrap_or_else(|| GROQ_API_BASE.to_string()),
+                api_key: api_key(
+                    config.groq_api_key.as_ref().or(config.api_key.as_ref()),
+                    "tools.transcribe.groq_api_key",
+                )?,
+                model: config.model.clone(),
+            },
+            "openai" => SpeechBackend::Api {
+                api_base: config
+                    .api_base
+                    .clone()
+                    .unwrap_or_else(|| OPENAI_API_BASE.to_string()),
+                api_key: api_key(config.api_key.as_ref(), "tools.transcribe.api_key")?,
+                // The default model name is Groq's; OpenAI only serves whisper-1.
+                model: if config.model == TranscribeConfig::default().model {
+                    "whisper-1".to_string()
+                } else {
+                    config.model.clone()
+                },
+            },
+            "whisper_cpp" => SpeechBackend::WhisperCpp {
+                binary: config.whisper_cpp_binary.clone(),
+                model_path: config.whisper_cpp_model.clone().ok_or_else(|| {
+                    ZeptoError::Config(
+                        "tools.transcribe.backend 'whisper_cpp' needs \
+                         tools.transcribe.whisper_cpp_model (path to a ggml model file)"
+                            .to_string(),
+                    )
+                })?,
+            },
+            other => {
+                return Err(ZeptoError::Config(format!(
+                "Unknown tools.transcribe.backend '{}'. Expected one of: groq, openai, whisper_cpp",
+                other
+            )))
+            }
+        };
+        Ok(Self::new(backend)
+            .with_language(config.language.clone())
+            .with_max_duration_secs(config.max_duration_secs))
+    }
+
+    /// Create a service for `backend` with no language hint and a 10 minute cap.
+    pub fn new(backend: SpeechBackend) -> Self {
+        Self {
+            backend,
+            language: None,
+            max_duration_secs: 600,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(120))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Set the default language hint (ISO-639-1, e.g. "en").
+    pub fn with_language(mut self, language: Option<String>) -> Self {
+        self.language = language.filter(|l| !l.is_empty());
+        self
+    }
+
+    /// Reject audio longer than `secs` (when the length can be determined).
+    pub fn with_max_duration_secs(mut self, secs: u64) -> Self {
+        self.max_duration_secs = secs;
+        self
+    }
+
+    /// The configured backend.
+    pub fn backend(&self) -> &SpeechBackend {
+        &self.backend
+    }
+
+    /// Transcribe `audio`. `filename` supplies the format via its extension;
+    /// `language` overrides the configured hint.
+    pub async fn transcribe(
+        &self,
+        audio: Vec<u8>,
+        filename: &str,
+        mime: &str,
+        language: Option<&str>,
+    ) -> Result<String> {
+        let ext = std::path::Path::new(filename)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        if let Some(secs) = audio_duration_secs(&audio, &ext) {
+            if secs > self.max_duration_secs as f64 {
+                return Err(ZeptoError::Tool(format!(
+                    "Audio is {:.0}s long, over the {}s limit (tools.transcribe.max_duration_secs)",
+                    secs, self.max_duration_secs
+                )));
+            }
+        }
+        let language = language.or(self.language.as_deref());
+
+        let text = match &self.backend {
+            SpeechBackend::Api {
+                api_base,
+                api_key,
+                model,
+            } => {
+                self.transcribe_api(api_base, api_key, model, audio, filename, mime, language)
+                    .await?
+            }
+            SpeechBackend::WhisperCpp { binary, model_path } => {
+                self.transcribe_whisper_cpp(binary, model_path, audio, &ext, language)
+                    .await?
+            }
+        };
+        Ok(text.trim().to_string())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn transcribe_api(
+        &self,
+        api_base: &str,
+        api_key: &str,
+        model: &str,
+        audio: Vec<u8>,
+        filename: &str,
+        mime: &str,
+        language: Option<&str>,
+    ) -> Result<String> {
+        let part = multipart::Part::bytes(audio)
+            .file_name(filename.to_string())
+            .mime_str(mime)
+            .map_err(|e| ZeptoError::Tool(e.to_string()))?;
+        let mut form = multipart::Form::new()
+            .part("file", part)
+            .text("model", model.to_string());
+        if let Some(language) = language {
+            form = form.text("language", language.to_string());
+        }
+
+        let url = format!("{}/audio/transcriptions", api_base.trim_end_matches('/'));
+        let resp = self
+            .client
+            .post(&url)
+            .bearer_auth(api_key)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| ZeptoError::Tool(format!("Transcription request failed: {}", e)))?;
+
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(ZeptoError::Tool(format!(
+                "Transcription API returned HTTP {}: {}",
+                status.as_u16(),
+                crate::utils::string::preview(body.trim(), 300)
+            )));
+        }
+
+        // Some providers return JSON {"text": "..."}, others return plain text.
+        let trimmed = body.trim();
+        if let Ok(json) = serde_json::from_str::<serde_json::Value>(trimmed) {
+            if let Some(text) = json.get("text").and_then(|v| v.as_str()) {
+                return Ok(text.to_string());
+            }
+        }
+        Ok(trimmed.to_string())
+    }
+
+    async fn transcribe_whisper_cpp(
+        &self,
+        binary: &str,
+        model_path: &str,
+        audio: Vec<u8>,
+        ext: &str,
+        language: Option<&str>,
+    ) -> Result<String> {
+        use tokio::process::Command;
+
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join(format!(
+            "input.{}",
+            if ext.is_empty() { "bin" } else { ext }
+        ));
+        tokio::fs::write(&input, &audio).await?;
+
+        // whisper.cpp reads 16 kHz mono WAV; convert anything else with ffmpeg.
+        let wav = if ext == "wav" {
+            input
+        } else {
+            let wav = dir.path().join("input.wav");
+            let output = Command::new("ffmpeg")
+                .args(["-nostdin", "-loglevel", "error", "-y", "-i"])
+                .arg(&input)
+                .args(["-ar", "16000", "-ac", "1", "-c:a", "pcm_s16le"])
+                .arg(&wav)
+                .kill_on_drop(true)
+                .output()
+                .await
+                .map_err(|e| {
+                    if e.kind() == std::io::ErrorKind::NotFound {
+                        ZeptoError::Tool(format!(
+                            "The whisper_cpp backend needs WAV audio and ffmpeg was not found to \
+                             convert '{}' audio. Install ffmpeg, send 16 kHz mono WAV, or use \
+                             the groq/openai backend.",
+                            ext
+                        ))
+                    } else {
+                        ZeptoError::Tool(format!("Failed to run ffmpeg: {}", e))
+                    }
+                })?;
+            if !output.status.success() {
+                return Err(ZeptoError::Tool(format!(
+                    "ffmpeg could not convert '{}' audio: {}",
+                    ext,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+            wav
+        };
+
+        let mut command = Command::new(binary);
+        command
+            .arg("-m")
+            .arg(model_path)
+            .arg("-f")
+            .arg(&wav)
+            .args(["-nt", "-np"])
+            // Cap processing at the duration limit even when the length is unknown.
+            .args(["-d", &(self.max_duration_secs * 1000).to_string()])
+            .kill_on_drop(true);
+        if let Some(language) = language {
+            command.args(["-l", language]);
+        }
+        let timeout = Duration::from_secs(self.max_duration_secs.max(60) * 2);
+        let output = tokio::time::timeout(timeout, command.output())
+            .await
+            .map_err(|_| {
+                ZeptoError::Tool(format!(
+                    "whisper.cpp timed out after {}s",
+                    timeout.as_secs()
+                ))
+            })?
+            .map_err(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    ZeptoError::Tool(format!(
+                        "whisper.cpp binary '{}' not found. Install whisper.cpp or set \
+                         tools.transcribe.whisper_cpp_binary",
+                        binary
+                    ))
+                } else {
+                    ZeptoError::Tool(format!("Failed to run whisper.cpp: {}", e))
+                }
+            })?;
+        if !output.status.success() {
+            return Err(ZeptoError::Tool(format!(
+                "whisper.cpp failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "))
+    }
+}
+
+/// File extension for an audio MIME type, so backends can tell the format
+/// of channel voice messages. Unknown types are treated as Ogg, the format
+/// Telegram and WhatsApp use for voice notes.
+fn extension_for_mime(mime: &str) -> &'static str {
+    match mime.split(';').next().unwrap_or("").trim() {
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/mp4" | "audio/m4a" | "audio/x-m4a" => "m4a",
+        "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
+        "audio/webm" => "webm",
+        _ => "ogg",
+    }
+}
+
+/// Length of WAV or Ogg (Opus/Vorbis) audio in seconds, read from headers.
+/// `None` for other formats or malformed files.
+pub fn audio_duration_secs(bytes: &[u8], ext: &str) -> Option<f64> {
+    match ext {
+        "wav" => wav_duration_secs(bytes),
+        "ogg" | "oga" | "opus" => ogg_duration_secs(bytes),
+        _ => None,
+    }
+}
+
+fn u32_le(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn wav_duration_secs(bytes: &[u8]) -> Option<f64> {
+    if bytes.get(0..4)? != b"RIFF" || bytes.get(8..12)? != b"WAVE" {
+        return None;
+    }
+    let mut pos = 12;
+    let mut byte_rate = None;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let size = u32_le(bytes, pos + 4)? as usize;
+        match id {
+            b"fmt " => byte_rate = u32_le(bytes, pos + 16),
+            b"data" => {
+                let rate = byte_rate.filter(|r| *r > 0)?;
+                return Some(size as f64 / rate as f64);
+            }
+            _ => {}
+        }
+        pos += 8 + size + (size & 1);
+    }
+    None
+}
+
+fn ogg_duration_secs(bytes: &[u8]) -> Option<f64> {
+    if bytes.get(0..4)? != b"OggS" {
+        return None;
+    }
+    // First packet follows the page header and its segment table.
+    let packet = 27 + *bytes.get(26)? as usize;
+    let (rate, pre_skip) = if bytes.get(packet..packet + 8)? == b"OpusHead" {
+        let pre_skip = u16::from_le_bytes(bytes.get(packet + 10..packet + 12)?.try_into().ok()?);
+        (48_000.0, pre_skip as f64)
+    } else if bytes.get(packet..packet + 7)? == b"\x01vorbis" {
+        (u32_le(bytes, packet + 12)? as f64, 0.0)
+    } else {
+        return None;
+    };
+    if rate <= 0.0 {
+        return None;
+    }
+    // The granule position of the last page is the total sample count.
+    let last = bytes.windows(4).rposition(|w| w == b"OggS")?;
+    let granule = i64::from_le_bytes(bytes.get(last + 6..last + 14)?.try_into().ok()?);
+    (granule >= 0).then(|| ((granule as f64 - pre_skip) / rate).max(0.0))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -206,6 +630,7 @@ mod tests {
     #[tokio::test]
     async fn test_transcribe_empty_candidates_returns_fallback() {
         let svc = TranscriberService {
+            stt: None,
             candidates: vec![],
             model: "whisper-1".to_string(),
             client: reqwest::Client::new(),
@@ -213,4 +638,137 @@ mod tests {
         let result = svc.transcribe(vec![1, 2, 3], "audio/ogg").await;
         assert_eq!(result, "[Voice Message]");
     }
+
+    #[test]
+    fn test_from_config_uses_transcribe_tool_backend() {
+        let mut config = Config::default();
+        config.tools.transcribe.enabled = true;
+        config.tools.transcribe.groq_api_key = Some("gsk_test".to_string());
+        let svc = TranscriberService::from_config(&config).unwrap();
+        assert!(svc.candidates.is_empty());
+        assert!(svc.stt.is_some());
+    }
+
+    #[test]
+    fn test_extension_for_mime() {
+        assert_eq!(extension_for_mime("audio/ogg; codecs=opus"), "ogg");
+        assert_eq!(extension_for_mime("audio/mpeg"), "mp3");
+        assert_eq!(extension_for_mime("audio/x-wav"), "wav");
+        assert_eq!(extension_for_mime("application/octet-stream"), "ogg");
+    }
+
+    fn wav(seconds: u32) -> Vec<u8> {
+        let byte_rate = 16_000 * 2;
+        let data_len = seconds * byte_rate;
+        let mut out = Vec::new();
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&(36 + data_len).to_le_bytes());
+        out.extend_from_slice(b"WAVEfmt ");
+        out.extend_from_slice(&16u32.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        out.extend_from_slice(&1u16.to_le_bytes()); // mono
+        out.extend_from_slice(&16_000u32.to_le_bytes());
+        out.extend_from_slice(&byte_rate.to_le_bytes());
+        out.extend_from_slice(&2u16.to_le_bytes());
+        out.extend_from_slice(&16u16.to_le_bytes());
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&data_len.to_le_bytes());
+        out.resize(out.len() + data_len as usize, 0);
+        out
+    }
+
+    fn ogg_page(granule: i64, packet: &[u8]) -> Vec<u8> {
+        let mut page = b"OggS".to_vec();
+        page.push(0); // version
+        page.push(0); // header type
+        page.extend_from_slice(&granule.to_le_bytes());
+        page.extend_from_slice(&[0; 12]); // serial, sequence, checksum
+        page.push(1);
+        page.push(packet.len() as u8);
+        page.extend_from_slice(packet);
+        page
+    }
+
+    #[test]
+    fn test_wav_duration() {
+        let secs = audio_duration_secs(&wav(3), "wav").unwrap();
+        assert!((secs - 3.0).abs() < 1e-9, "{secs}");
+        assert_eq!(audio_duration_secs(b"not audio", "wav"), None);
+        assert_eq!(audio_duration_secs(&wav(3), "mp3"), None);
+    }
+
+    #[test]
+    fn test_opus_duration() {
+        let mut head = b"OpusHead".to_vec();
+        head.push(1); // version
+        head.push(1); // channels
+        head.extend_from_slice(&312u16.to_le_bytes()); // pre-skip
+        head.extend_from_slice(&48_000u32.to_le_bytes());
+        let mut bytes = ogg_page(0, &head);
+        bytes.extend(ogg_page(5 * 48_000 + 312, b"audio"));
+        let secs = audio_duration_secs(&bytes, "ogg").unwrap();
+        assert!((secs - 5.0).abs() < 1e-9, "{secs}");
+    }
+
+    #[test]
+    fn test_speech_to_text_backends_from_config() {
+        let mut config = TranscribeConfig {
+            groq_api_key: Some("gsk".into()),
+            ..Default::default()
+        };
+        let stt = SpeechToText::from_config(&config).unwrap();
+        assert!(
+            matches!(stt.backend(), SpeechBackend::Api { api_base, model, .. }
+                if api_base == GROQ_API_BASE && model == "whisper-large-v3-turbo")
+        );
+
+        config.backend = "openai".into();
+        let err = SpeechToText::from_config(&config).unwrap_err();
+        assert!(
+            err.to_string().contains("tools.transcribe.api_key"),
+            "{err}"
+        );
+        config.api_key = Some("sk".into());
+        let stt = SpeechToText::from_config(&config).unwrap();
+        assert!(
+            matches!(stt.backend(), SpeechBackend::Api { api_base, model, .. }
+                if api_base == OPENAI_API_BASE && model == "whisper-1")
+        );
+
+        config.backend = "whisper_cpp".into();
+        let err = SpeechToText::from_config(&config).unwrap_err();
+        assert!(err.to_string().contains("whisper_cpp_model"), "{err}");
+
+        config.backend = "vosk".into();
+        let err = SpeechToText::from_config(&config).unwrap_err();
+        assert!(err.to_string().contains("Expected one of"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_speech_to_text_rejects_long_audio() {
+        let stt = SpeechToText::new(SpeechBackend::Api {
+            api_base: "http://127.0.0.1:9".into(),
+            api_key: "k".into(),
+            model: "m".into(),
+        })
+        .with_max_duration_secs(2);
+        let err = stt
+            .transcribe(wav(3), "clip.wav", "audio/wav", None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("max_duration_secs"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_whisper_cpp_missing_binary_is_actionable() {
+        let stt = SpeechToText::new(SpeechBackend::WhisperCpp {
+            binary: "zeptoclaw-no-such-whisper".into(),
+            model_path: "model.bin".into(),
+        });
+        let err = stt
+            .transcribe(wav(1), "clip.wav", "audio/wav", None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("whisper_cpp_binary"), "{err}");
+    }
 }
@@ -96,6 +96,13 @@ pub fn default_pricing() -> HashMap<String, ModelPricing> {
     m
 }
 
+/// Price of a prompt-cache write, relative to the model's input price
+/// (Anthropic list prices, 5-minute cache).
+pub const CACHE_WRITE_PRICE_RATIO: f64 = 1.25;
+
+/// Price of a prompt-cache read, relative to the model's input price.
+pub const CACHE_READ_PRICE_RATIO: f64 = 0.1;
+
 /// Estimate the cost of a single LLM call in USD.
 ///
 /// Looks up pricing in `custom_pricing` first, then falls back to
@@ -118,6 +125,35 @@ pub fn estimate_cost(
     })
 }
 
+/// Estimate the cost of a single LLM call in USD when `cache_read_tokens`
+/// and `cache_write_tokens` of its `prompt_tokens` were read from and written
+/// to the provider's prompt cache.
+///
+/// Cached tokens are priced with [`CACHE_READ_PRICE_RATIO`] and
+/// [`CACHE_WRITE_PRICE_RATIO`]; the difference to [`estimate_cost`] for the
+/// same call is what caching saved.
+pub fn estimate_cost_with_cache(
+    model: &str,
+    prompt_tokens: u32,
+    completion_tokens: u32,
+    cache_read_tokens: u32,
+    cache_write_tokens: u32,
+    custom_pricing: &HashMap<String, ModelPricing>,
+) -> Option<f64> {
+    let defaults = default_pricing();
+    let pricing = custom_pricing.get(model).or_else(|| defaults.get(model));
+
+    pricing.map(|p| {
+        let uncached = prompt_tokens.saturating_sub(cache_read_tokens + cache_write_tokens);
+        let input_tokens = uncached as f64
+            + cache_read_tokens as f64 * CACHE_READ_PRICE_RATIO
+            + cache_write_tokens as f64 * CACHE_WRITE_PRICE_RATIO;
+        let input_cost = (input_tokens / 1_000_000.0) * p.input_cost_per_million;
+        let output_cost = (completion_tokens as f64 / 1_000_000.0) * p.output_cost_per_million;
+        input_cost + output_cost
+    })
+}
+
 /// Internal mutable state guarded by the `CostTracker` mutex.
 #[derive(Debug, Default)]
 struct CostState {
@@ -236,6 +272,17 @@ impl Default for CostTracker {
     }
 }
 
+/// What the agent does once the monthly budget is spent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetAction {
+    /// Log a warning and keep answering (default).
+    #[default]
+    Warn,
+    /// Refuse new turns until the next month.
+    Refuse,
+}
+
 /// Configuration for cost tracking, suitable for embedding in the main
 /// ZeptoClaw config file.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -245,6 +292,13 @@ pub struct CostConfig {
     pub enabled: bool,
     /// Custom per-model pricing overrides.
     pub custom_pricing: HashMap<String, ModelPricing>,
+    /// Append the turn's cost and the conversation's total to each reply.
+    pub show_cost: bool,
+    /// Spend limit in USD per calendar month across all conversations.
+    /// `None` means no limit.
+    pub monthly_budget_usd: Option<f64>,
+    /// What to do once `monthly_budget_usd` is spent.
+    pub budget_action: BudgetAction,
 }
 
 // We need Copy-like semantics for the lookup in estimate_cost where we clone
@@ -304,6 +358,22 @@ mod tests {
         assert!((cost - 0.015).abs() < 1e-10);
     }
 
+    #[test]
+    fn test_estimate_cost_with_cache() {
+        let custom = HashMap::new();
+        let model = "claude-sonnet-4-5-20250929";
+        // Without cache hits the price is the same as estimate_cost.
+        let plain = estimate_cost_with_cache(model, 1000, 500, 0, 0, &custom).unwrap();
+        assert!((plain - 0.0105).abs() < 1e-10);
+        // 1000 written: 1000 * 1.25 / 1M * 3.0 = 0.00375, + 0.0075 output
+        let write = estimate_cost_with_cache(model, 1000, 500, 0, 1000, &custom).unwrap();
+        assert!((write - 0.01125).abs() < 1e-10);
+        // 800 read, 200 uncached: (80 + 200) / 1M * 3.0 = 0.00084, + 0.0075
+        let read = estimate_cost_with_cache(model, 1000, 500, 800, 0, &custom).unwrap();
+        assert!((read - 0.00834).abs() < 1e-10);
+        assert!(estimate_cost_with_cache("unknown-model-xyz", 1, 1, 1, 0, &custom).is_none());
+    }
+
     #[test]
     fn test_estimate_cost_unknown_model_returns_none() {
         let custom = HashMap::new();
@@ -457,6 +527,7 @@ mod tests {
         let config = CostConfig {
             enabled: true,
             custom_pricing: custom,
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -489,6 +560,17 @@ mod tests {
         let parsed: CostConfig = serde_json::from_str(json).unwrap();
         assert!(!parsed.enabled);
         assert!(parsed.custom_pricing.is_empty());
+        assert!(!parsed.show_cost);
+        assert!(parsed.monthly_budget_usd.is_none());
+        assert_eq!(parsed.budget_action, BudgetAction::Warn);
+    }
+
+    #[test]
+    fn test_budget_action_serde() {
+        let json = r#"{"monthly_budget_usd": 20.0, "budget_action": "refuse"}"#;
+        let parsed: CostConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.monthly_budget_usd, Some(20.0));
+        assert_eq!(parsed.budget_action, BudgetAction::Refuse);
     }
 
     #[test]
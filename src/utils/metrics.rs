@@ -21,6 +21,9 @@ pub struct ToolMetrics {
     pub min_duration: Option<Duration>,
     /// Longest call duration observed.
     pub max_duration: Option<Duration>,
+    /// Calls answered without running because they repeated an identical
+    /// earlier call of the same turn. Not included in `call_count`.
+    pub repeat_count: u64,
 }
 
 impl ToolMetrics {
@@ -53,6 +56,7 @@ pub struct MetricsCollector {
     session_start: Instant,
     total_tokens_in: Mutex<u64>,
     total_tokens_out: Mutex<u64>,
+    cache_hits: Mutex<u64>,
 }
 
 impl MetricsCollector {
@@ -63,6 +67,7 @@ impl MetricsCollector {
             session_start: Instant::now(),
             total_tokens_in: Mutex::new(0),
             total_tokens_out: Mutex::new(0),
+            cache_hits: Mutex::new(0),
         }
     }
 
@@ -91,12 +96,29 @@ impl MetricsCollector {
         });
     }
 
+    /// Records a call that was not run because it repeated an identical
+    /// earlier call.
+    pub fn record_repeated_call(&self, tool_name: &str) {
+        let mut tools = self.tools.lock().unwrap();
+        tools.entry(tool_name.to_string()).or_default().repeat_count += 1;
+    }
+
     /// Adds to the running token totals.
     pub fn record_tokens(&self, input_tokens: u64, output_tokens: u64) {
         *self.total_tokens_in.lock().unwrap() += input_tokens;
         *self.total_tokens_out.lock().unwrap() += output_tokens;
     }
 
+    /// Records a reply served from the response cache instead of the provider.
+    pub fn record_cache_hit(&self) {
+        *self.cache_hits.lock().unwrap() += 1;
+    }
+
+    /// Returns the number of replies served from the response cache.
+    pub fn cache_hits(&self) -> u64 {
+        *self.cache_hits.lock().unwrap()
+    }
+
     /// Returns a clone of the metrics for a specific tool, or `None` if the
     /// tool has never been called.
     pub fn tool_metrics(&self, tool_name: &str) -> Option<ToolMetrics> {
@@ -293,6 +315,18 @@ mod tests {
         assert_eq!(avg, Duration::from_millis(200));
     }
 
+    #[test]
+    fn test_record_repeated_call() {
+        let collector = MetricsCollector::new();
+        collector.record_repeated_call("shell");
+        collector.record_repeated_call("shell");
+
+        let metrics = collector.tool_metrics("shell").unwrap();
+        assert_eq!(metrics.repeat_count, 2);
+        assert_eq!(metrics.call_count, 0);
+        assert_eq!(collector.total_tool_calls(), 0);
+    }
+
     #[test]
     fn test_record_tokens() {
         let collector = MetricsCollector::new();
@@ -303,6 +337,18 @@ mod tests {
         assert_eq!(collector.total_tokens(), (1500, 800));
     }
 
+    #[test]
+    fn test_record_cache_hit() {
+        let collector = MetricsCollector::new();
+        assert_eq!(collector.cache_hits(), 0);
+
+        collector.record_cache_hit();
+        collector.record_cache_hit();
+
+        assert_eq!(collector.cache_hits(), 2);
+        assert_eq!(collector.total_tokens(), (0, 0));
+    }
+
     #[test]
     fn test_total_tool_calls() {
         let collector = MetricsCollector::new();
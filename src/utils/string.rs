@@ -1,4 +1,4 @@
-//! UTF-8 safe string preview helpers.
+//! UTF-8 safe string preview helpers, and HTML escaping.
 //!
 //! Provides small helpers to take the first N Unicode scalar values (chars)
 //! from a string without slicing by byte index which can panic on multibyte
@@ -19,6 +19,15 @@ pub fn preview(s: &str, n: usize) -> String {
     prefix
 }
 
+/// Escape `&`, `<`, `>` and `"` so `s` can go in HTML text or a quoted
+/// attribute.
+pub fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -46,4 +55,12 @@ mod tests {
             assert_eq!(p, manual);
         }
     }
+
+    #[test]
+    fn escape_html_special_characters() {
+        assert_eq!(
+            escape_html(r#"<a href="x">Tom & Jerry</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&lt;/a&gt;"
+        );
+    }
 }
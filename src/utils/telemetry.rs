@@ -61,6 +61,7 @@ pub fn render(collector: &MetricsCollector, format: &TelemetryFormat) -> String
 /// Metric families emitted:
 /// - `zeptoclaw_tool_calls_total` (counter)
 /// - `zeptoclaw_tool_errors_total` (counter)
+/// - `zeptoclaw_tool_repeats_total` (counter)
 /// - `zeptoclaw_tool_duration_seconds_sum` (counter)
 /// - `zeptoclaw_tool_duration_seconds_min` (gauge)
 /// - `zeptoclaw_tool_duration_seconds_max` (gauge)
@@ -93,6 +94,18 @@ pub fn render_prometheus(collector: &MetricsCollector) -> String {
         ));
     }
 
+    // --- tool repeats total ---
+    out.push_str(
+        "# HELP zeptoclaw_tool_repeats_total Repeated identical tool calls answered without running.\n",
+    );
+    out.push_str("# TYPE zeptoclaw_tool_repeats_total counter\n");
+    for (name, m) in &tools {
+        out.push_str(&format!(
+            "zeptoclaw_tool_repeats_total{{tool=\"{}\"}} {}\n",
+            name, m.repeat_count,
+        ));
+    }
+
     // --- tool duration sum ---
     out.push_str(
         "# HELP zeptoclaw_tool_duration_seconds_sum Cumulative tool call duration in seconds.\n",
@@ -163,6 +176,7 @@ pub fn render_prometheus(collector: &MetricsCollector) -> String {
 ///     "shell": {
 ///       "call_count": 5,
 ///       "error_count": 1,
+///       "repeat_count": 0,
 ///       "total_duration_seconds": 1.234,
 ///       "min_duration_seconds": 0.1,
 ///       "max_duration_seconds": 0.5
@@ -184,6 +198,7 @@ pub fn render_json(collector: &MetricsCollector) -> String {
             serde_json::json!({
                 "call_count": m.call_count,
                 "error_count": m.error_count,
+                "repeat_count": m.repeat_count,
                 "total_duration_seconds": m.total_duration.as_secs_f64(),
                 "min_duration_seconds": m.min_duration.map(|d| d.as_secs_f64()),
                 "max_duration_seconds": m.max_duration.map(|d| d.as_secs_f64()),
@@ -329,6 +344,7 @@ mod tests {
         let expected_families = [
             "zeptoclaw_tool_calls_total",
             "zeptoclaw_tool_errors_total",
+            "zeptoclaw_tool_repeats_total",
             "zeptoclaw_tool_duration_seconds_sum",
             "zeptoclaw_tool_duration_seconds_min",
             "zeptoclaw_tool_duration_seconds_max",
@@ -375,6 +391,7 @@ mod tests {
         let collector = MetricsCollector::new();
         collector.record_tool_call("shell", Duration::from_millis(100), true);
         collector.record_tool_call("shell", Duration::from_millis(300), false);
+        collector.record_repeated_call("shell");
         collector.record_tokens(500, 200);
 
         let output = render_json(&collector);
@@ -383,6 +400,7 @@ mod tests {
         let shell = &parsed["tools"]["shell"];
         assert_eq!(shell["call_count"], 2);
         assert_eq!(shell["error_count"], 1);
+        assert_eq!(shell["repeat_count"], 1);
         assert!(shell["total_duration_seconds"].as_f64().unwrap() > 0.0);
         assert!(shell["min_duration_seconds"].as_f64().unwrap() > 0.0);
         assert!(shell["max_duration_seconds"].as_f64().unwrap() > 0.0);
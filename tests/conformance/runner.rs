@@ -45,12 +45,12 @@ pub struct Expected {
 
 fn tool_by_name(name: &str) -> Box<dyn Tool> {
     match name {
-        "edit_file" => Box::new(EditFileTool),
+        "edit_file" => Box::new(EditFileTool::new()),
         "read_file" => Box::new(ReadFileTool),
         "shell" => Box::new(ShellTool::default()),
-        "grep" => Box::new(GrepTool),
+        "grep" => Box::new(GrepTool::new()),
         "find" => Box::new(FindTool),
-        "list_dir" => Box::new(ListDirTool),
+        "list_dir" => Box::new(ListDirTool::new()),
         _ => panic!("Unknown fixture tool: {}", name),
     }
 }
@@ -111,6 +111,7 @@ impl LLMProvider for MockToolCallingProvider {
                     r#"{"message": "e2e-tool-test"}"#,
                 )],
                 usage: None,
+                thinking: Vec::new(),
             })
         } else {
             // Subsequent call: return final text
@@ -156,7 +157,11 @@ impl LLMProvider for MockBudgetExhaustingProvider {
                 prompt_tokens: self.tokens_per_call,
                 completion_tokens: self.tokens_per_call,
                 total_tokens: self.tokens_per_call * 2,
+                cache_read_tokens: 0,
+                cache_write_tokens: 0,
+                thinking_tokens: 0,
             }),
+            thinking: Vec::new(),
         })
     }
 }
@@ -215,6 +220,7 @@ impl LLMProvider for MockBatchToolProvider {
                 content: String::new(),
                 tool_calls,
                 usage: None,
+                thinking: Vec::new(),
             })
         } else {
             // Synthesis / no-tools call: return text
@@ -317,7 +323,11 @@ impl LLMProvider for MockTokenCountingProvider {
                 prompt_tokens: 500,
                 completion_tokens: 200,
                 total_tokens: 700,
+                cache_read_tokens: 0,
+                cache_write_tokens: 0,
+                thinking_tokens: 0,
             }),
+            thinking: Vec::new(),
         })
     }
 }
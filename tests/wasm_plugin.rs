@@ -0,0 +1,174 @@
+//! Integration tests for WASM plugin tools.
+//!
+//! Run with `cargo test --features wasm-plugins --test wasm_plugin`.
+
+#![cfg(feature = "wasm-plugins")]
+
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+use tempfile::tempdir;
+use zeptoclaw::plugins::WasmPluginLimits;
+use zeptoclaw::tools::wasm_plugin::{discover_wasm_plugins, WasmPluginTool};
+use zeptoclaw::tools::{Tool, ToolContext, ToolRegistry};
+
+fn example_plugin() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/wasm_plugins/echo.wat")
+}
+
+/// A minimal plugin named `probe` whose `execute` body is `body`. `data` is
+/// placed at offset 512 for bodies that return a fixed string.
+fn probe_plugin(dir: &Path, body: &str, data: &str) -> PathBuf {
+    let params = r#"{"type":"object","properties":{}}"#;
+    let wat = format!(
+        r#"(module
+  (memory (export "memory") 1)
+  (data (i32.const 0) "probe")
+  (data (i32.const 16) "Test probe")
+  (data (i32.const 64) "{params}")
+  (data (i32.const 512) "{data}")
+  (func (export "name") (result i64) i64.const 5)
+  (func (export "description") (result i64) i64.const {desc})
+  (func (export "parameters") (result i64) i64.const {params_packed})
+  (func (export "alloc") (param i32) (result i32) i32.const 1024)
+  (func (export "execute") (param i32 i32) (result i64) {body})
+)"#,
+        params = params.replace('"', "\\\""),
+        data = data.replace('"', "\\\""),
+        desc = (16_i64 << 32) | 10,
+        params_packed = (64_i64 << 32) | params.len() as i64,
+        body = body,
+    );
+    // wasmtime also accepts the text format, so no wat2wasm step is needed.
+    let path = dir.join("probe.wasm");
+    std::fs::write(&path, wat).unwrap();
+    path
+}
+
+fn packed(offset: i64, len: usize) -> String {
+    format!("i64.const {}", (offset << 32) | len as i64)
+}
+
+#[tokio::test]
+async fn test_example_plugin_loads_and_executes() {
+    let tool = WasmPluginTool::load(&example_plugin(), &WasmPluginLimits::default()).unwrap();
+    assert_eq!(tool.name(), "wasm_echo");
+    assert_eq!(tool.description(), "Echo the arguments back as JSON");
+    assert_eq!(tool.parameters()["required"], json!(["message"]));
+
+    let mut registry = ToolRegistry::new();
+    registry.register(Box::new(tool));
+    let output = registry
+        .execute_with_context(
+            "wasm_echo",
+            json!({"message": "hello from wasm"}),
+            &ToolContext::new(),
+        )
+        .await
+        .unwrap();
+    assert!(!output.is_error);
+    assert_eq!(output.for_llm, r#"{"message":"hello from wasm"}"#);
+}
+
+#[test]
+fn test_discovery_picks_up_wasm_files() {
+    let dir = tempdir().unwrap();
+    std::fs::copy(example_plugin(), dir.path().join("echo.wasm")).unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "not a plugin").unwrap();
+    std::fs::write(dir.path().join("broken.wasm"), "(module").unwrap();
+
+    let tools = discover_wasm_plugins(&[dir.path().to_path_buf()], &WasmPluginLimits::default());
+    let names: Vec<&str> = tools.iter().map(|t| t.name()).collect();
+    assert_eq!(names, ["wasm_echo"]);
+}
+
+#[tokio::test]
+async fn test_plugin_error_result_is_tool_error() {
+    let dir = tempdir().unwrap();
+    let error = r#"{"error":"bad input"}"#;
+    let path = probe_plugin(dir.path(), &packed(512, error.len()), error);
+    let tool = WasmPluginTool::load(&path, &WasmPluginLimits::default()).unwrap();
+
+    let err = tool
+        .execute(json!({}), &ToolContext::new())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("bad input"), "{err}");
+}
+
+#[tokio::test]
+async fn test_trap_is_tool_error() {
+    let dir = tempdir().unwrap();
+    let path = probe_plugin(dir.path(), "unreachable", "");
+    let tool = WasmPluginTool::load(&path, &WasmPluginLimits::default()).unwrap();
+
+    let err = tool
+        .execute(json!({}), &ToolContext::new())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("execute failed"), "{err}");
+
+    // The tool stays usable after a trap.
+    let err = tool
+        .execute(json!({}), &ToolContext::new())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("execute failed"), "{err}");
+}
+
+#[tokio::test]
+async fn test_fuel_limit() {
+    let dir = tempdir().unwrap();
+    let path = probe_plugin(dir.path(), "(loop br 0) i64.const 0", "");
+    let limits = WasmPluginLimits {
+        fuel: 100_000,
+        ..Default::default()
+    };
+    let tool = WasmPluginTool::load(&path, &limits).unwrap();
+
+    let err = tool
+        .execute(json!({}), &ToolContext::new())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("ran out of fuel"), "{err}");
+}
+
+#[tokio::test]
+async fn test_time_limit() {
+    let dir = tempdir().unwrap();
+    let path = probe_plugin(dir.path(), "(loop br 0) i64.const 0", "");
+    let limits = WasmPluginLimits {
+        fuel: u64::MAX,
+        timeout_secs: 1,
+        ..Default::default()
+    };
+    let tool = WasmPluginTool::load(&path, &limits).unwrap();
+
+    let err = tool
+        .execute(json!({}), &ToolContext::new())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("timed out after 1s"), "{err}");
+}
+
+#[tokio::test]
+async fn test_memory_limit() {
+    let dir = tempdir().unwrap();
+    // Grow by 2 MiB past a 1 MiB cap.
+    let path = probe_plugin(
+        dir.path(),
+        "(drop (memory.grow (i32.const 32))) i64.const 0",
+        "",
+    );
+    let limits = WasmPluginLimits {
+        max_memory_mb: 1,
+        ..Default::default()
+    };
+    let tool = WasmPluginTool::load(&path, &limits).unwrap();
+
+    let err = tool
+        .execute(json!({}), &ToolContext::new())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("execute failed"), "{err}");
+}